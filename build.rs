@@ -0,0 +1,33 @@
+//! Build script: resolved dependency versions reported by `lsl-toolbox doctor`
+//!
+//! The manifest only holds version requirements; the version actually compiled in
+//! is the one in Cargo.lock, which is written before build scripts run.
+
+use std::path::Path;
+
+fn main() {
+    let manifest_dir = std::env::var("CARGO_MANIFEST_DIR").unwrap_or_default();
+    let lock_path = Path::new(&manifest_dir).join("Cargo.lock");
+    println!("cargo:rerun-if-changed=build.rs");
+    println!("cargo:rerun-if-changed={}", lock_path.display());
+
+    if let Some(version) = std::fs::read_to_string(&lock_path)
+        .ok()
+        .and_then(|lock| locked_version(&lock, "zarrs"))
+    {
+        println!("cargo:rustc-env=LSL_TOOLBOX_ZARRS_VERSION={}", version);
+    }
+}
+
+/// Version of package `name` in a Cargo.lock (`name = ...` is followed by `version = ...`)
+fn locked_version(lock: &str, name: &str) -> Option<String> {
+    let name_line = format!("name = \"{}\"", name);
+    let mut lines = lock.lines();
+    while let Some(line) = lines.next() {
+        if line.trim() == name_line {
+            let version = lines.next()?.trim().strip_prefix("version = \"")?.strip_suffix('"')?;
+            return Some(version.to_string());
+        }
+    }
+    None
+}
@@ -0,0 +1,78 @@
+//! Experimenter annotations in the store's `meta` group
+//!
+//! `MARK <label>` notes a moment of a session ("subject sneezed", "electrode
+//! adjusted") without a separate marker stream. Each annotation is appended to the
+//! `annotations` attribute of the `meta` group with the LSL clock time at which the
+//! recorder received it, so it lines up with the stream timestamps:
+//!
+//! ```json
+//! {"annotations": [{"label": "electrode adjusted", "time": 12345.678}]}
+//! ```
+//!
+//! Annotations belong to the session, not to a stream: `lsl-multi-recorder` writes
+//! them once per store rather than forwarding `MARK` to its recorders.
+
+use anyhow::Result;
+use fs2::FileExt;
+use serde::{Deserialize, Serialize};
+use std::fs::OpenOptions;
+use std::path::Path;
+use std::sync::Arc;
+use zarrs::filesystem::FilesystemStore;
+
+use crate::zarr::layout::create_group_if_not_exists;
+use crate::zarr::{read_group_attributes, META_GROUP};
+
+/// Name of the `meta` group attribute holding the annotations
+pub const ANNOTATIONS_ATTRIBUTE: &str = "annotations";
+
+/// A labelled moment of a session
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Annotation {
+    pub label: String,
+    /// LSL clock time at which the annotation was made
+    pub time: f64,
+}
+
+/// Annotations stored in the `meta` group, in the order they were made
+pub fn read_annotations(store_path: &Path) -> Vec<Annotation> {
+    let Ok(store) = FilesystemStore::new(store_path) else {
+        return Vec::new();
+    };
+    read_group_attributes(&Arc::new(store), &format!("/{}", META_GROUP))
+        .ok()
+        .and_then(|attrs| attrs.get(ANNOTATIONS_ATTRIBUTE).cloned())
+        .and_then(|value| serde_json::from_value(value).ok())
+        .unwrap_or_default()
+}
+
+/// Append an annotation to the `meta` group of a store
+///
+/// Holds the store's metadata lock, so recorders updating the same store can call
+/// this concurrently.
+pub fn append_annotation(store_path: &Path, annotation: &Annotation) -> Result<()> {
+    let lock_file = OpenOptions::new()
+        .create(true)
+        .write(true)
+        .truncate(false)
+        .open(store_path.join(".zarr_metadata.lock"))?;
+    lock_file.lock_exclusive()?;
+    let result = (|| -> Result<()> {
+        let store = Arc::new(FilesystemStore::new(store_path)?);
+        let meta_path = format!("/{}", META_GROUP);
+        create_group_if_not_exists(&store, &meta_path)?;
+        let mut group = zarrs::group::Group::open(store, &meta_path)?;
+
+        let attributes = group.attributes_mut();
+        let mut annotations: Vec<Annotation> = attributes
+            .get(ANNOTATIONS_ATTRIBUTE)
+            .and_then(|value| serde_json::from_value(value.clone()).ok())
+            .unwrap_or_default();
+        annotations.push(annotation.clone());
+        attributes.insert(ANNOTATIONS_ATTRIBUTE.to_string(), serde_json::to_value(&annotations)?);
+        group.store_metadata()?;
+        Ok(())
+    })();
+    lock_file.unlock()?;
+    result
+}
@@ -0,0 +1,737 @@
+//! In-process recorder core on a tokio runtime (`lsl-multi-recorder --async-core`)
+//!
+//! lsl-multi-recorder normally starts one `lsl-recorder` process per stream, and each
+//! recorder blocks a thread in pull timeouts, flush checks and its control threads.
+//! That is the right isolation for a handful of streams; with dozens of low-rate
+//! streams it mostly costs processes and threads that sleep. The async core records
+//! all streams inside the multi-recorder on one runtime with a few worker threads
+//! (`--async-workers`):
+//!
+//! - every stream is a task that takes whatever its inlet has buffered without
+//!   blocking, on a timer matched to the nominal rate ([`poll_interval`])
+//! - a `--flush-interval` timer per stream schedules the flushes, so the buffered tail
+//!   of a stream that goes quiet is written as well
+//! - a stall timer reports `STATUS STREAM_LOST` once a regular stream has been silent
+//!   for the lost timeout, and `STATUS STREAM_RECOVERED` with its next samples
+//! - commands from stdin and `--control-port` reach the tasks through a `watch`
+//!   channel ([`SessionControl`]); plain TCP control clients are served as tasks
+//!   ([`serve_control_async`])
+//!
+//! Resolving a stream, opening its store and finalizing it block, so they run on the
+//! runtime's blocking pool, as do WebSocket control clients. Each stream's
+//! [`ZarrWriter`] keeps its writer thread, which sleeps until a flush arrives.
+//!
+//! The async core covers the recording itself: data, timestamps, pauses, connection
+//! statistics, mirrors, object storage spools, permissions and bad-channel detection.
+//! The checks that live in the recorder loop (`--rule`, `--quality`, `--plausible-range`,
+//! `--sample-flags`, captures, rotation, schedules, monitors and status reports) need
+//! `lsl-recorder` processes and are refused with `--async-core`. The rate guard and
+//! `--reconnect-after` are not applied; the inlet still recovers by itself when the
+//! outlet comes back. Status lines carry the stream name: `[EMG] STATUS FIRST_SAMPLE (regular)`.
+
+use anyhow::{Context, Result};
+use lsl::{ExPullable, Pullable};
+use std::io::Write;
+use std::net::SocketAddr;
+use std::path::PathBuf;
+use std::sync::{
+    atomic::{AtomicBool, Ordering},
+    Arc,
+};
+use std::time::{Duration, Instant};
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::watch;
+use tokio::task::JoinSet;
+use tokio::time::MissedTickBehavior;
+
+use crate::chunked::for_each_kept_run;
+use crate::cli::Args;
+use crate::commands::{
+    apply_annotation, apply_metadata_update, handle_request, serve_client, Command, ACCEPT_RETRY_DELAY,
+    FIRST_REQUEST_TIMEOUT,
+};
+use crate::connection::ConnectionTracker;
+use crate::lsl::{
+    apply_store_permissions, create_sample_buffer, finish_writer, initialize_zarr_writer,
+    resolve_lsl_stream_with_retry, set_postprocessing, RecordingSummary, SampleBuffer, ZarrConfig, INLET_MAX_BUFFER,
+};
+use crate::pauses::PauseTracker;
+use crate::zarr::remote::resolve_output;
+use crate::zarr::writer::ZarrWriter;
+
+/// Default `--async-workers`
+pub const DEFAULT_ASYNC_WORKERS: usize = 2;
+
+/// Most marker samples taken per poll, so a flooding marker stream cannot hold a worker
+const MAX_STRING_SAMPLES_PER_POLL: usize = 1000;
+
+/// Shortest flush timer period (tokio intervals cannot be zero)
+const MIN_FLUSH_PERIOD: Duration = Duration::from_millis(10);
+
+/// How often the Ctrl+C/SIGTERM flag is checked
+const SHUTDOWN_POLL: Duration = Duration::from_millis(100);
+
+/// Time between two non-blocking pulls of a stream of `nominal_srate` Hz
+///
+/// About 2.5 sample periods, like the pull timeout of `lsl-recorder`, between 5 ms
+/// and 100 ms; irregular streams are polled every 50 ms.
+pub fn poll_interval(nominal_srate: f64) -> Duration {
+    if nominal_srate > 0.0 {
+        Duration::from_secs_f64((2.5 / nominal_srate).clamp(0.005, 0.1))
+    } else {
+        Duration::from_millis(50)
+    }
+}
+
+/// What every stream task of a session follows
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct SessionState {
+    pub recording: bool,
+    /// Set between `PAUSE` and `RESUME`; `recording` is cleared meanwhile
+    pub paused: bool,
+    pub quit: bool,
+}
+
+/// Commands of an async session, published to the stream tasks through a `watch` channel
+///
+/// The in-process counterpart of [`crate::commands::RecorderControls`].
+#[derive(Clone)]
+pub struct SessionControl {
+    state: Arc<watch::Sender<SessionState>>,
+    /// Streams a `STOP_AFTER` timer waits for: regular ones once they delivered, others once connected
+    ready: Arc<watch::Sender<usize>>,
+    streams: usize,
+    /// Stores whose session manifest `METADATA` updates and `MARK` annotates (store and mirror)
+    stores: Vec<PathBuf>,
+    /// LSL clock that `MARK` annotations are timestamped with
+    clock: fn() -> f64,
+    runtime: tokio::runtime::Handle,
+}
+
+impl SessionControl {
+    /// Control of `streams` streams recorded into `stores`; must be created on the session's runtime
+    pub fn new(streams: usize, stores: Vec<PathBuf>, clock: fn() -> f64) -> Self {
+        Self {
+            state: Arc::new(watch::channel(SessionState::default()).0),
+            ready: Arc::new(watch::channel(0).0),
+            streams,
+            stores,
+            clock,
+            runtime: tokio::runtime::Handle::current(),
+        }
+    }
+
+    pub fn state(&self) -> SessionState {
+        *self.state.borrow()
+    }
+
+    /// Follow the state from a stream task
+    pub fn subscribe(&self) -> watch::Receiver<SessionState> {
+        self.state.subscribe()
+    }
+
+    /// Count one more stream as ready for `STOP_AFTER` timers
+    pub fn stream_ready(&self) {
+        self.ready.send_modify(|ready| *ready += 1);
+    }
+
+    /// Run a command, reporting it on stdout as `STATUS ...`
+    pub fn execute(&self, command: &Command) -> Result<()> {
+        match command {
+            Command::Start => {
+                self.state.send_modify(|state| {
+                    state.recording = true;
+                    state.paused = false;
+                });
+                println!("STATUS STARTED");
+            }
+            Command::Stop => {
+                self.state.send_modify(|state| {
+                    state.recording = false;
+                    state.paused = false;
+                });
+                println!("STATUS STOPPED");
+            }
+            Command::Pause => {
+                if !self.state().recording {
+                    anyhow::bail!("PAUSE needs a running recording");
+                }
+                self.state.send_modify(|state| {
+                    state.recording = false;
+                    state.paused = true;
+                });
+                println!("STATUS PAUSED");
+            }
+            Command::Resume => {
+                if !self.state().paused {
+                    anyhow::bail!("RESUME needs a paused recording");
+                }
+                self.state.send_modify(|state| {
+                    state.recording = true;
+                    state.paused = false;
+                });
+                println!("STATUS RESUMED");
+            }
+            Command::StopAfter(secs) => self.stop_after(*secs),
+            Command::Quit => {
+                println!("STATUS QUIT");
+                self.state.send_modify(|state| state.quit = true);
+            }
+            Command::Metadata(update) => {
+                apply_metadata_update(&self.stores, update)?;
+                println!("STATUS METADATA_UPDATED");
+            }
+            Command::Mark(label) => {
+                let annotation = apply_annotation(&self.stores, label, (self.clock)())?;
+                println!("STATUS MARKED {:.6} {}", annotation.time, annotation.label);
+            }
+        }
+        std::io::stdout().flush().ok();
+        Ok(())
+    }
+
+    /// Stop `secs` after every stream is ready, on a timer task
+    fn stop_after(&self, secs: u64) {
+        println!("STATUS WILL STOP AFTER {}s (timer starts once every regular stream has delivered)", secs);
+        let mut ready = self.ready.subscribe();
+        let streams = self.streams;
+        let state = self.state.clone();
+        self.runtime.spawn(async move {
+            if ready.wait_for(|ready| *ready >= streams).await.is_err() {
+                return;
+            }
+            println!("STATUS TIMER_STARTED ({}s countdown begins now)", secs);
+            std::io::stdout().flush().ok();
+            tokio::time::sleep(Duration::from_secs(secs)).await;
+            state.send_modify(|state| {
+                state.recording = false;
+                state.paused = false;
+            });
+            println!("STATUS STOPPED_BY_TIMER ({}s)", secs);
+            std::io::stdout().flush().ok();
+        });
+    }
+}
+
+/// Counts its stream as ready once, at the latest when the stream's task ends
+///
+/// A stream that failed must not hold back the `STOP_AFTER` timer of the others.
+struct StreamReady {
+    control: SessionControl,
+    counted: bool,
+}
+
+impl StreamReady {
+    fn mark(&mut self) {
+        if !self.counted {
+            self.counted = true;
+            self.control.stream_ready();
+        }
+    }
+}
+
+impl Drop for StreamReady {
+    fn drop(&mut self) {
+        self.mark();
+    }
+}
+
+/// Accept control clients on `port` (all interfaces) like [`crate::commands::serve_control`], as tasks
+///
+/// Plain TCP clients are served on the runtime; WebSocket clients (an HTTP `GET`
+/// first) are handed to the blocking pool. Port 0 picks a free port. Returns the
+/// bound address.
+pub async fn serve_control_async<F>(port: u16, handler: F) -> Result<SocketAddr>
+where
+    F: Fn(&Command) -> Result<()> + Send + Sync + 'static,
+{
+    let listener = TcpListener::bind(("0.0.0.0", port))
+        .await
+        .with_context(|| format!("Failed to bind the control server on port {}", port))?;
+    let address = listener.local_addr()?;
+    let handler = Arc::new(handler);
+
+    tokio::spawn(async move {
+        loop {
+            let (stream, peer) = match listener.accept().await {
+                Ok(accepted) => accepted,
+                Err(e) => {
+                    eprintln!("Control server failed to accept a client: {}", e);
+                    tokio::time::sleep(ACCEPT_RETRY_DELAY).await;
+                    continue;
+                }
+            };
+            let handler = handler.clone();
+            tokio::spawn(async move {
+                if let Err(e) = serve_client_async(stream, handler).await {
+                    eprintln!("Control client {} disconnected: {:#}", peer, e);
+                }
+            });
+        }
+    });
+    Ok(address)
+}
+
+/// Serve one control client; WebSocket clients go to the blocking [`serve_client`]
+async fn serve_client_async<F>(stream: TcpStream, handler: Arc<F>) -> Result<()>
+where
+    F: Fn(&Command) -> Result<()> + Send + Sync + 'static,
+{
+    stream.set_nodelay(true).ok();
+    if is_http_request(&stream).await? {
+        let stream = stream.into_std()?;
+        stream.set_nonblocking(false)?;
+        return tokio::task::spawn_blocking(move || serve_client(stream, &*handler)).await?;
+    }
+
+    let (reader, mut writer) = stream.into_split();
+    let mut lines = BufReader::new(reader).lines();
+    while let Some(line) = lines.next_line().await? {
+        if let Some(reply) = handle_request(&line, &*handler) {
+            writer.write_all(format!("{}\n", reply).as_bytes()).await?;
+        }
+    }
+    Ok(())
+}
+
+/// Whether the client's first bytes are an HTTP `GET` (WebSocket upgrade)
+async fn is_http_request(stream: &TcpStream) -> Result<bool> {
+    const GET: &[u8] = b"GET ";
+    let mut prefix = [0u8; 4];
+    // Wait until the prefix is complete or can no longer become "GET "
+    let peek = async {
+        loop {
+            let n = stream.peek(&mut prefix).await?;
+            if n == 0 || n >= GET.len() || prefix[..n] != GET[..n] {
+                return Ok::<bool, std::io::Error>(n >= GET.len() && prefix == *GET);
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+    };
+    tokio::time::timeout(FIRST_REQUEST_TIMEOUT, peek)
+        .await
+        .context("no request within the first seconds")?
+        .map_err(Into::into)
+}
+
+/// Read commands from stdin until `QUIT` or end of input
+async fn read_commands(control: SessionControl) {
+    let mut lines = BufReader::new(tokio::io::stdin()).lines();
+    while let Ok(Some(line)) = lines.next_line().await {
+        if line.trim().is_empty() {
+            continue;
+        }
+        match Command::parse(&line).and_then(|command| control.execute(&command).map(|_| command)) {
+            Ok(Command::Quit) => break,
+            Ok(_) => {}
+            Err(e) => {
+                println!("ERROR {:#}", e);
+                std::io::stdout().flush().ok();
+            }
+        }
+    }
+}
+
+/// Settings of an async session that are not per stream
+pub struct AsyncSessionOptions {
+    /// Tokio worker threads shared by all streams
+    pub workers: usize,
+    pub control_port: Option<u16>,
+    /// Stop this many seconds after every regular stream has delivered
+    pub duration: Option<u64>,
+    /// Stores (primary and mirror) that `METADATA` and `MARK` write to
+    pub stores: Vec<PathBuf>,
+    /// Set by Ctrl+C/SIGTERM; ends the session like `QUIT`
+    pub shutdown: Arc<AtomicBool>,
+}
+
+/// Record `streams` in this process until `QUIT`
+///
+/// Each entry holds the options the stream's `lsl-recorder` would have been started
+/// with. A failing stream does not stop the others; the session fails once all are done.
+pub fn run_session(streams: Vec<Args>, options: AsyncSessionOptions) -> Result<()> {
+    let runtime = tokio::runtime::Builder::new_multi_thread()
+        .worker_threads(options.workers.max(1))
+        .thread_name("async-core")
+        .enable_all()
+        .build()
+        .context("Failed to start the async recorder runtime")?;
+    let result = runtime.block_on(session(streams, options));
+    // The stdin reader may be blocked in a read that never returns
+    runtime.shutdown_timeout(Duration::from_millis(100));
+    result
+}
+
+async fn session(streams: Vec<Args>, options: AsyncSessionOptions) -> Result<()> {
+    let AsyncSessionOptions {
+        control_port,
+        duration,
+        stores,
+        shutdown,
+        ..
+    } = options;
+    let control = SessionControl::new(streams.len(), stores, lsl::local_clock);
+    if let Some(port) = control_port {
+        let server_control = control.clone();
+        let address = serve_control_async(port, move |command| server_control.execute(command)).await?;
+        println!("Control server: {}", address);
+    }
+    tokio::spawn(read_commands(control.clone()));
+
+    // Ctrl+C / SIGTERM end the session like QUIT, so every stream is finalized
+    let shutdown_control = control.clone();
+    tokio::spawn(async move {
+        let mut poll = tokio::time::interval(SHUTDOWN_POLL);
+        loop {
+            poll.tick().await;
+            if shutdown.load(Ordering::SeqCst) {
+                println!("Shutdown signal received, stopping all streams...");
+                shutdown_control.execute(&Command::Quit).ok();
+                break;
+            }
+        }
+    });
+    if let Some(duration) = duration {
+        control.execute(&Command::StopAfter(duration))?;
+    }
+
+    let total = streams.len();
+    let mut tasks = JoinSet::new();
+    for args in streams {
+        let name = args.zarr_config().1;
+        let control = control.clone();
+        tasks.spawn(async move { (name, record_stream(args, control).await) });
+    }
+    let mut failed = Vec::new();
+    while let Some(joined) = tasks.join_next().await {
+        match joined {
+            Ok((_, Ok(()))) => {}
+            Ok((name, Err(e))) => {
+                eprintln!("[{}] Recording error: {:#}", name, e);
+                failed.push(name);
+            }
+            Err(e) => {
+                eprintln!("Stream task failed: {}", e);
+                failed.push("(panicked)".to_string());
+            }
+        }
+    }
+    if !failed.is_empty() {
+        anyhow::bail!("{} of {} streams failed: {}", failed.len(), total, failed.join(", "));
+    }
+    Ok(())
+}
+
+/// Print a status line of one stream
+fn report(stream: &str, message: &str) {
+    println!("[{}] STATUS {}", stream, message);
+    std::io::stdout().flush().ok();
+}
+
+/// A connected stream with its store opened
+struct OpenStream {
+    inlet: lsl::StreamInlet,
+    info: lsl::StreamInfo,
+    writer: ZarrWriter,
+    config: ZarrConfig,
+    connection: ConnectionTracker,
+}
+
+/// Resolve the stream, connect and open its store as `lsl-recorder` does
+///
+/// Blocks. `None` if the session ended while the stream was being resolved.
+fn open_stream(args: &Args, state: &watch::Receiver<SessionState>) -> Result<Option<OpenStream>> {
+    let (store_path, stream_name, subject, session_id, notes) = args.zarr_config();
+    let (store_path, remote_url) = resolve_output(&store_path, args.spool_dir.as_deref());
+    let mut config = ZarrConfig {
+        store_path,
+        stream_name,
+        subject,
+        session_id,
+        notes,
+        storage: args.storage_options(),
+        mirror_path: args.mirror_path(),
+        remote_url,
+        permissions: args.store_permissions()?,
+    };
+
+    let resolution = args.resolution_config();
+    let mut connection = ConnectionTracker::new();
+    let streams = resolve_lsl_stream_with_retry(
+        &resolution.strategy,
+        resolution.timeout,
+        args.quiet,
+        resolution.max_retry_attempts,
+        resolution.retry_base_delay_ms,
+        &mut connection,
+    )?;
+    if state.borrow().quit {
+        return Ok(None);
+    }
+
+    let inlet = lsl::StreamInlet::new(&streams[0], INLET_MAX_BUFFER, 0, true)
+        .map_err(|e| anyhow::anyhow!("LSL error: {}", e))?;
+    let mut info = inlet
+        .info(lsl::FOREVER)
+        .map_err(|e| anyhow::anyhow!("LSL error: {}", e))?;
+    let supported = matches!(
+        info.channel_format(),
+        lsl::ChannelFormat::Float32
+            | lsl::ChannelFormat::Double64
+            | lsl::ChannelFormat::Int32
+            | lsl::ChannelFormat::Int16
+            | lsl::ChannelFormat::Int8
+            | lsl::ChannelFormat::String
+    );
+    if !supported {
+        anyhow::bail!("Unsupported channel format: {:?}", info.channel_format());
+    }
+    config.stream_name = args.name_by.group_name(&config.stream_name, &info.uid())?;
+    set_postprocessing(&inlet)?;
+
+    let writer = initialize_zarr_writer(&config, &mut info, &inlet, &args.recording_config(), args, true)?
+        .context("no writer for the stream")?;
+    if !args.quiet {
+        println!(
+            "[{}] Connected: {} channels at {} Hz",
+            config.stream_name,
+            info.channel_count(),
+            info.nominal_srate()
+        );
+    }
+    Ok(Some(OpenStream {
+        inlet,
+        info,
+        writer,
+        config,
+        connection,
+    }))
+}
+
+/// Take every sample the inlet has buffered into the writer, without blocking
+///
+/// Numeric chunks are pulled into `chunk` and `timestamps`, which are reused between
+/// polls, and handed to the writer as blocks. Returns the timestamps of the stored
+/// samples; samples taken during the last pause are dropped.
+fn pull_available(
+    inlet: &lsl::StreamInlet,
+    chunk: &mut SampleBuffer,
+    timestamps: &mut Vec<f64>,
+    writer: &mut ZarrWriter,
+    pauses: &PauseTracker,
+) -> std::result::Result<Vec<f64>, lsl::Error> {
+    let mut stored = Vec::new();
+    macro_rules! drain {
+        ($data:expr, $method:ident) => {{
+            $data.clear();
+            timestamps.clear();
+            inlet.pull_chunk_buf($data, timestamps)?;
+            let channels = $data.len().checked_div(timestamps.len()).unwrap_or(0);
+            // Runs of samples between skipped ones go to the writer as one block each
+            for_each_kept_run(timestamps, |ts| !pauses.covers(ts), |run| {
+                stored.extend_from_slice(&timestamps[run.clone()]);
+                writer.$method(&$data[run.start * channels..run.end * channels], &timestamps[run]);
+            });
+        }};
+    }
+    match chunk {
+        SampleBuffer::Float32(data) => drain!(data, add_sample_block_f32),
+        SampleBuffer::Float64(data) => drain!(data, add_sample_block_f64),
+        SampleBuffer::Int32(data) => drain!(data, add_sample_block_i32),
+        SampleBuffer::Int16(data) => drain!(data, add_sample_block_i16),
+        SampleBuffer::Int8(data) => drain!(data, add_sample_block_i8),
+        SampleBuffer::String(_) => {
+            // Marker samples are pulled one by one, as in lsl-recorder
+            for _ in 0..MAX_STRING_SAMPLES_PER_POLL {
+                let (sample, ts) = <lsl::StreamInlet as Pullable<String>>::pull_sample(inlet, 0.0)?;
+                if ts == 0.0 {
+                    break;
+                }
+                if !pauses.covers(ts) {
+                    writer.add_sample_slice_string(&sample, ts);
+                    stored.push(ts);
+                }
+            }
+        }
+    }
+    Ok(stored)
+}
+
+/// Hand the buffered samples to the writer thread if they are due
+///
+/// The flush waits for room in the write queue when the disk is behind, so it runs
+/// on the blocking pool rather than stall the worker's other tasks. The writer is
+/// handed back along with the outcome.
+async fn flush_if_due(mut writer: ZarrWriter) -> (ZarrWriter, Result<()>) {
+    if !writer.needs_flush() {
+        return (writer, Ok(()));
+    }
+    match tokio::task::spawn_blocking(move || {
+        let flushed = writer.flush();
+        (writer, flushed)
+    })
+    .await
+    {
+        Ok(done) => done,
+        // The writer went down with the flush
+        Err(e) => std::panic::resume_unwind(e.into_panic()),
+    }
+}
+
+/// Record one stream until `QUIT`: set up on the blocking pool, then pull, flush and watch on timers
+async fn record_stream(args: Args, control: SessionControl) -> Result<()> {
+    let mut ready = StreamReady {
+        control: control.clone(),
+        counted: false,
+    };
+    let mut state = control.subscribe();
+    let recording_config = args.recording_config();
+    let quiet = args.quiet;
+
+    let setup_state = state.clone();
+    let Some(OpenStream {
+        inlet,
+        info,
+        mut writer,
+        config,
+        mut connection,
+    }) = tokio::task::spawn_blocking(move || open_stream(&args, &setup_state)).await??
+    else {
+        return Ok(());
+    };
+    let name = config.stream_name.clone();
+    let mut chunk = create_sample_buffer(&info)?;
+    let mut chunk_timestamps = Vec::new();
+    let regular = info.nominal_srate() > 0.0;
+    if !regular {
+        ready.mark();
+    }
+
+    let mut poll = tokio::time::interval(poll_interval(info.nominal_srate()));
+    poll.set_missed_tick_behavior(MissedTickBehavior::Delay);
+    let mut flush = tokio::time::interval(recording_config.flush_interval.max(MIN_FLUSH_PERIOD));
+    flush.set_missed_tick_behavior(MissedTickBehavior::Delay);
+    let lost_timeout = recording_config.lost_timeout;
+
+    let mut samples: u64 = 0;
+    let mut first_timestamp: Option<f64> = None;
+    let mut last_timestamp: Option<f64> = None;
+    // Last samples while recording, for the stall timer
+    let mut last_sample_at: Option<Instant> = None;
+    let mut lost = false;
+    let mut pauses = PauseTracker::default();
+    // A failed flush ends the recording, after the writer is finished like on QUIT
+    let mut flush_error = None;
+
+    loop {
+        let current = *state.borrow_and_update();
+        if current.quit {
+            break;
+        }
+        if let Some(pause) = pauses.update(current.paused, lsl::local_clock())
+            && let Err(e) = writer.record_pause(pause)
+        {
+            eprintln!("Warning: {:#}", e);
+        }
+        if !current.recording {
+            // Silence while stopped is expected
+            last_sample_at = None;
+        }
+        let stall_at = last_sample_at
+            .filter(|_| regular && current.recording && !lost && !lost_timeout.is_zero())
+            .map(|at| tokio::time::Instant::from_std(at + lost_timeout));
+
+        tokio::select! {
+            changed = state.changed() => {
+                if changed.is_err() {
+                    break;
+                }
+            }
+            _ = poll.tick(), if current.recording => {
+                match pull_available(&inlet, &mut chunk, &mut chunk_timestamps, &mut writer, &pauses) {
+                    Ok(timestamps) => {
+                        let (Some(&first), Some(&last)) = (timestamps.first(), timestamps.last()) else {
+                            continue;
+                        };
+                        let now = Instant::now();
+                        if lost {
+                            lost = false;
+                            connection.stream_recovered_at(now, Some(first));
+                            report(&name, "STREAM_RECOVERED");
+                        }
+                        last_sample_at = Some(now);
+                        if samples == 0 {
+                            report(&name, if regular { "FIRST_SAMPLE (regular)" } else { "FIRST_SAMPLE (irregular)" });
+                            ready.mark();
+                            if let Err(e) = writer.record_start_time() {
+                                eprintln!("Warning: {:#}", e);
+                            }
+                        }
+                        samples += timestamps.len() as u64;
+                        first_timestamp.get_or_insert(first);
+                        last_timestamp = Some(last);
+                        let (flushed_writer, flushed) = flush_if_due(writer).await;
+                        writer = flushed_writer;
+                        if let Err(e) = flushed {
+                            flush_error = Some(e);
+                            break;
+                        }
+                    }
+                    // A failing inlet counts as a lost stream rather than ending the recording
+                    Err(e) => {
+                        if !lost {
+                            lost = true;
+                            connection.stream_lost_after(last_sample_at.unwrap_or_else(Instant::now), last_timestamp);
+                            report(&name, &format!("STREAM_LOST (inlet error: {})", e));
+                        }
+                    }
+                }
+            }
+            _ = flush.tick() => {
+                let (flushed_writer, flushed) = flush_if_due(writer).await;
+                writer = flushed_writer;
+                if let Err(e) = flushed {
+                    flush_error = Some(e);
+                    break;
+                }
+            }
+            _ = tokio::time::sleep_until(stall_at.unwrap_or_else(tokio::time::Instant::now)), if stall_at.is_some() => {
+                lost = true;
+                connection.stream_lost_after(last_sample_at.unwrap_or_else(Instant::now), last_timestamp);
+                report(&name, &format!("STREAM_LOST (no samples for {:.1}s)", lost_timeout.as_secs_f64()));
+            }
+        }
+    }
+
+    if let Some(pause) = pauses.finish(lsl::local_clock())
+        && let Err(e) = writer.record_pause(pause)
+    {
+        eprintln!("Warning: {:#}", e);
+    }
+    let connection = connection.finish(Instant::now());
+    // The final flush and metadata wait for the disk
+    let finished = tokio::task::spawn_blocking(move || {
+        let summary = RecordingSummary {
+            samples_recorded: samples,
+            first_timestamp,
+            last_timestamp,
+            connection: &connection,
+        };
+        finish_writer(&mut writer, &config, &summary)?;
+        apply_store_permissions(&config)
+    })
+    .await?;
+    if let Some(e) = flush_error {
+        if let Err(finish) = finished {
+            eprintln!("Warning: {:#}", finish);
+        }
+        return Err(e);
+    }
+    finished?;
+
+    if !quiet {
+        println!("[{}] Recording stopped. Total samples: {}", name, samples);
+    }
+    Ok(())
+}
@@ -0,0 +1,145 @@
+//! Back-pressure monitoring of LSL outlets
+//!
+//! `lsl-replay` and `lsl-dummy-stream` push samples on a schedule: the recorded
+//! offsets divided by `--speed`, or the nominal rate. liblsl never blocks a push;
+//! when the outlet cannot keep up, or a consumer stops pulling and the outlet
+//! buffer fills, samples are either rejected with an error or silently dropped
+//! from the buffer. From the outlet's side that shows as failed pushes, samples
+//! pushed late, and a delivered rate below the nominal one, which is what a
+//! [`PushMonitor`] tracks.
+//!
+//! A replay that falls behind real time invalidates timing experiments built on
+//! it, so the tools warn while it happens (at most every [`WARNING_INTERVAL`]
+//! seconds) and print the statistics when they stop.
+
+use anyhow::Result;
+use std::fmt::Display;
+
+/// Default lag behind schedule, in seconds, above which a push counts as late
+pub const DEFAULT_MAX_LAG: f64 = 0.1;
+
+/// Seconds between two warnings about the same outlet
+pub const WARNING_INTERVAL: f64 = 5.0;
+
+/// Failed pushes in a row after which the outlet is given up
+pub const MAX_CONSECUTIVE_FAILURES: u64 = 100;
+
+/// Delivery statistics of an outlet
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct PushStats {
+    /// Samples the outlet accepted
+    pub pushed: u64,
+    /// Samples whose push failed (dropped pushes)
+    pub failed: u64,
+    /// Samples pushed more than the lag threshold after they were due
+    pub late: u64,
+    /// Largest lag behind schedule in seconds
+    pub max_lag: f64,
+    /// Lag of the latest push in seconds
+    pub lag: f64,
+    /// Seconds since the first push was due
+    pub elapsed: f64,
+    /// Rate the schedule asks for in Hz (0 for irregular streams)
+    pub nominal_rate: f64,
+}
+
+impl PushStats {
+    /// Samples per second the outlet accepted
+    pub fn delivered_rate(&self) -> f64 {
+        if self.elapsed > 0.0 {
+            self.pushed as f64 / self.elapsed
+        } else {
+            0.0
+        }
+    }
+
+    /// Whether any push failed or came late
+    pub fn degraded(&self) -> bool {
+        self.failed > 0 || self.late > 0
+    }
+
+    /// One-line summary for the end of a run
+    pub fn summary(&self) -> String {
+        let rate = if self.nominal_rate > 0.0 {
+            format!("{:.1} Hz delivered of {:.1} Hz", self.delivered_rate(), self.nominal_rate)
+        } else {
+            format!("{:.1} Hz delivered", self.delivered_rate())
+        };
+        format!(
+            "{} pushed, {} failed, {} late (max lag {:.3} s), {}",
+            self.pushed, self.failed, self.late, self.max_lag, rate
+        )
+    }
+}
+
+/// Tracks the pushes of one outlet against its schedule
+#[derive(Debug, Clone)]
+pub struct PushMonitor {
+    stats: PushStats,
+    max_lag: f64,
+    consecutive_failures: u64,
+    last_warning: Option<f64>,
+}
+
+impl PushMonitor {
+    /// Monitor for an outlet scheduled at `nominal_rate` Hz; pushes more than
+    /// `max_lag` seconds behind schedule count as late
+    pub fn new(nominal_rate: f64, max_lag: f64) -> Self {
+        Self {
+            stats: PushStats {
+                nominal_rate,
+                ..PushStats::default()
+            },
+            max_lag,
+            consecutive_failures: 0,
+            last_warning: None,
+        }
+    }
+
+    /// Record the push of `samples` samples, `lag` seconds after they were due
+    /// and `elapsed` seconds into the run
+    ///
+    /// Returns a warning to show when the outlet fell behind or a push failed, at
+    /// most once every [`WARNING_INTERVAL`]. An error after
+    /// [`MAX_CONSECUTIVE_FAILURES`] failed pushes in a row.
+    pub fn record<E: Display>(
+        &mut self,
+        result: std::result::Result<(), E>,
+        samples: u64,
+        lag: f64,
+        elapsed: f64,
+    ) -> Result<Option<String>> {
+        self.stats.elapsed = elapsed;
+        let problem = match result {
+            Ok(()) => {
+                self.consecutive_failures = 0;
+                self.stats.pushed += samples;
+                self.stats.lag = lag;
+                self.stats.max_lag = self.stats.max_lag.max(lag);
+                if lag <= self.max_lag {
+                    return Ok(None);
+                }
+                self.stats.late += samples;
+                format!("falling behind real time, {:.3} s late", lag)
+            }
+            Err(e) => {
+                self.consecutive_failures += 1;
+                self.stats.failed += samples;
+                if self.consecutive_failures >= MAX_CONSECUTIVE_FAILURES {
+                    anyhow::bail!("{} pushes in a row failed, last: {}", self.consecutive_failures, e);
+                }
+                format!("push failed: {}", e)
+            }
+        };
+
+        if self.last_warning.is_some_and(|last| elapsed - last < WARNING_INTERVAL) {
+            return Ok(None);
+        }
+        self.last_warning = Some(elapsed);
+        Ok(Some(format!("Outlet {} ({})", problem, self.stats.summary())))
+    }
+
+    pub fn stats(&self) -> &PushStats {
+        &self.stats
+    }
+}
@@ -0,0 +1,301 @@
+//! Bad-channel masks
+//!
+//! A stream's bad channels (dead electrodes, bridged or saturated channels) are stored
+//! as its `bad_channels` group attribute:
+//!
+//! ```json
+//! {
+//!   "mask": [false, true, false],
+//!   "channels": [{"index": 1, "label": "Fp2", "reason": "flat", "source": "detected"}],
+//!   "updated_at": "2025-01-12T10:00:00+00:00"
+//! }
+//! ```
+//!
+//! `mask` has one entry per channel so analysis code can apply it directly; `channels`
+//! keeps the reason for each bad channel and where the mark came from (`manual` for
+//! `lsl-badchannels --mark`, `detected` for `lsl-badchannels --detect`, `online` for
+//! `lsl-recorder --detect-bad-channels`). `lsl-export` leaves bad channels out of EDF+
+//! and table exports and flags them in XDF channel descriptions.
+
+use anyhow::Result;
+use serde_json::json;
+use std::path::Path;
+use std::sync::Arc;
+use zarrs::filesystem::FilesystemStore;
+
+use crate::export::{ExportStream, EXPORT_BLOCK_SAMPLES};
+
+/// Name of the stream group attribute holding the mask
+pub const BAD_CHANNELS_ATTRIBUTE: &str = "bad_channels";
+
+/// One channel marked as bad
+#[derive(Debug, Clone, PartialEq)]
+pub struct BadChannel {
+    /// 0-based channel index
+    pub index: usize,
+    pub label: String,
+    pub reason: String,
+    /// Where the mark came from: `manual`, `detected` or `online`
+    pub source: String,
+}
+
+impl BadChannel {
+    fn to_json(&self) -> serde_json::Value {
+        json!({
+            "index": self.index,
+            "label": self.label,
+            "reason": self.reason,
+            "source": self.source,
+        })
+    }
+}
+
+/// Bad channels of one stream
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct BadChannelMask {
+    pub channel_count: usize,
+    /// Bad channels, sorted by index
+    pub channels: Vec<BadChannel>,
+}
+
+impl BadChannelMask {
+    /// Empty mask for a stream with `channel_count` channels
+    pub fn new(channel_count: usize) -> Self {
+        Self {
+            channel_count,
+            channels: Vec::new(),
+        }
+    }
+
+    /// Mask stored in a stream's group attributes (empty if there is none)
+    pub fn from_attributes(attributes: &serde_json::Value, channel_count: usize) -> Self {
+        let mut mask = Self::new(channel_count);
+        let entries = attributes
+            .get(BAD_CHANNELS_ATTRIBUTE)
+            .and_then(|b| b.get("channels"))
+            .and_then(|c| c.as_array());
+        for entry in entries.into_iter().flatten() {
+            let Some(index) = entry.get("index").and_then(|v| v.as_u64()) else {
+                continue;
+            };
+            let text = |key: &str| entry.get(key).and_then(|v| v.as_str()).unwrap_or_default().to_string();
+            mask.mark(BadChannel {
+                index: index as usize,
+                label: text("label"),
+                reason: text("reason"),
+                source: text("source"),
+            });
+        }
+        mask
+    }
+
+    /// Whether channel `index` is marked bad
+    pub fn is_bad(&self, index: usize) -> bool {
+        self.channels.iter().any(|c| c.index == index)
+    }
+
+    /// One flag per channel, true for bad channels
+    pub fn mask(&self) -> Vec<bool> {
+        (0..self.channel_count).map(|i| self.is_bad(i)).collect()
+    }
+
+    /// Indices of the channels not marked bad
+    pub fn good_channels(&self) -> Vec<usize> {
+        (0..self.channel_count).filter(|&i| !self.is_bad(i)).collect()
+    }
+
+    /// Mark a channel bad, replacing an earlier mark of the same channel
+    pub fn mark(&mut self, channel: BadChannel) {
+        self.clear(channel.index);
+        let position = self.channels.partition_point(|c| c.index < channel.index);
+        self.channels.insert(position, channel);
+    }
+
+    /// Remove the mark of channel `index`; returns whether it was marked
+    pub fn clear(&mut self, index: usize) -> bool {
+        let before = self.channels.len();
+        self.channels.retain(|c| c.index != index);
+        self.channels.len() != before
+    }
+
+    /// Remove all marks from `source`
+    pub fn clear_source(&mut self, source: &str) {
+        self.channels.retain(|c| c.source != source);
+    }
+
+    /// JSON form stored in the `bad_channels` attribute
+    pub fn to_json(&self) -> serde_json::Value {
+        json!({
+            "mask": self.mask(),
+            "channels": self.channels.iter().map(BadChannel::to_json).collect::<Vec<_>>(),
+            "updated_at": chrono::Utc::now().to_rfc3339(),
+        })
+    }
+}
+
+/// Thresholds for automatic bad-channel detection
+#[derive(Debug, Clone)]
+pub struct DetectionOptions {
+    /// Channels whose peak-to-peak range is at or below this value are flat
+    pub flat_range: f64,
+    /// Channels whose standard deviation exceeds this multiple of the median channel's are noisy
+    pub noise_ratio: f64,
+    /// Channels with a larger fraction of NaN/infinite values are invalid
+    pub max_non_finite: f64,
+}
+
+impl Default for DetectionOptions {
+    fn default() -> Self {
+        Self {
+            flat_range: 0.0,
+            noise_ratio: 10.0,
+            max_non_finite: 0.5,
+        }
+    }
+}
+
+/// Running statistics of one channel
+#[derive(Debug, Clone, Default)]
+struct ChannelStats {
+    count: u64,
+    non_finite: u64,
+    mean: f64,
+    // Sum of squared deviations from the mean (Welford)
+    m2: f64,
+    min: f64,
+    max: f64,
+}
+
+impl ChannelStats {
+    fn push(&mut self, value: f64) {
+        if !value.is_finite() {
+            self.non_finite += 1;
+            return;
+        }
+        if self.count == 0 {
+            self.min = value;
+            self.max = value;
+        } else {
+            self.min = self.min.min(value);
+            self.max = self.max.max(value);
+        }
+        self.count += 1;
+        let delta = value - self.mean;
+        self.mean += delta / self.count as f64;
+        self.m2 += delta * (value - self.mean);
+    }
+
+    fn std(&self) -> f64 {
+        if self.count == 0 { 0.0 } else { (self.m2 / self.count as f64).sqrt() }
+    }
+}
+
+/// Per-channel statistics accumulated over a stream's samples
+#[derive(Debug, Clone)]
+pub struct ChannelStatistics {
+    channels: Vec<ChannelStats>,
+}
+
+impl ChannelStatistics {
+    pub fn new(channel_count: usize) -> Self {
+        Self {
+            channels: vec![ChannelStats::default(); channel_count],
+        }
+    }
+
+    /// Add samples in sample-major order (`[s0c0, s0c1, ..., s1c0, ...]`)
+    pub fn push_interleaved(&mut self, values: &[f64]) {
+        let channels = self.channels.len();
+        for (i, &value) in values.iter().enumerate() {
+            self.channels[i % channels].push(value);
+        }
+    }
+
+    /// Add samples in channel-major order (`[c0s0, c0s1, ..., c1s0, ...]`)
+    pub fn push_channel_major(&mut self, values: &[f64], samples: usize) {
+        for (stats, channel) in self.channels.iter_mut().zip(values.chunks(samples.max(1))) {
+            for &value in channel {
+                stats.push(value);
+            }
+        }
+    }
+
+    /// Channels that look bad, with the reason
+    pub fn detect(&self, options: &DetectionOptions) -> Vec<(usize, String)> {
+        let mut stds: Vec<f64> = self
+            .channels
+            .iter()
+            .filter(|c| c.count > 0)
+            .map(ChannelStats::std)
+            .collect();
+        stds.sort_by(f64::total_cmp);
+        let median_std = stds.get(stds.len() / 2).copied().unwrap_or(0.0);
+
+        let mut bad = Vec::new();
+        for (index, stats) in self.channels.iter().enumerate() {
+            let total = stats.count + stats.non_finite;
+            if total == 0 {
+                continue;
+            }
+            let non_finite = stats.non_finite as f64 / total as f64;
+            if non_finite > options.max_non_finite {
+                bad.push((index, format!("{:.0}% non-finite values", non_finite * 100.0)));
+            } else if stats.max - stats.min <= options.flat_range {
+                bad.push((index, "flat".to_string()));
+            } else if median_std > 0.0 && stats.std() > options.noise_ratio * median_std {
+                bad.push((
+                    index,
+                    format!("noisy (std {:.1}x channel median)", stats.std() / median_std),
+                ));
+            }
+        }
+        bad
+    }
+}
+
+/// Statistics of all samples of a numeric stream, read in blocks
+pub fn stream_statistics(stream: &ExportStream) -> Result<ChannelStatistics> {
+    let mut statistics = ChannelStatistics::new(stream.channel_count);
+    let mut start = 0;
+    while start < stream.sample_count {
+        let len = EXPORT_BLOCK_SAMPLES.min(stream.sample_count - start);
+        let (_, values) = stream.read_block(start, len)?;
+        let values = values
+            .to_f64()
+            .ok_or_else(|| anyhow::anyhow!("Stream '{}' is not numeric", stream.name))?;
+        statistics.push_interleaved(&values);
+        start += len;
+    }
+    Ok(statistics)
+}
+
+/// Store `mask` as the `bad_channels` attribute of `stream`
+pub fn store_bad_channels(store_path: &Path, stream: &str, mask: &BadChannelMask) -> Result<()> {
+    if !store_path.join("zarr.json").exists() {
+        anyhow::bail!("Not a Zarr store: {}", store_path.display());
+    }
+    let store = Arc::new(FilesystemStore::new(store_path)?);
+    let mut group = zarrs::group::Group::open(store, &format!("/{}", stream))?;
+    group
+        .attributes_mut()
+        .insert(BAD_CHANNELS_ATTRIBUTE.to_string(), mask.to_json());
+    group.store_metadata()?;
+    Ok(())
+}
+
+/// Channel index from a label or 0-based index
+pub fn resolve_channel(stream: &ExportStream, name: &str) -> Result<usize> {
+    let labels = stream.channel_labels();
+    labels
+        .iter()
+        .position(|label| label == name)
+        .or_else(|| name.parse::<usize>().ok().filter(|&i| i < stream.channel_count))
+        .ok_or_else(|| {
+            anyhow::anyhow!(
+                "Channel '{}' not found in stream '{}' (channels: {})",
+                name,
+                stream.name,
+                labels.join(", ")
+            )
+        })
+}
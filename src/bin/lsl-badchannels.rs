@@ -0,0 +1,191 @@
+//! LSL Bad Channels - Record which channels of a recording should not be analyzed
+//!
+//! This tool maintains the `bad_channels` attribute of stream groups: a boolean mask
+//! with one entry per channel plus the reason each channel was marked. lsl-export
+//! leaves bad channels out of EDF+/BDF+ and CSV/Parquet output (unless
+//! `--include-bad-channels`) and flags them in XDF channel descriptions.
+//!
+//! # Features
+//!
+//! - Manual marks by channel label or index, with a reason
+//! - Automatic detection of flat, noisy and mostly non-finite channels
+//! - Lists the current masks of all streams when run without changes
+//!
+//! # Usage
+//!
+//! ```bash
+//! # Show the bad channels of all streams
+//! lsl-badchannels experiment.zarr
+//!
+//! # Mark a dead electrode
+//! lsl-badchannels experiment.zarr --stream EEG --mark Fp2 --reason "dead electrode"
+//!
+//! # Detect flat and noisy channels in every numeric stream
+//! lsl-badchannels experiment.zarr --detect
+//! ```
+//!
+//! Detection replaces earlier detected marks of a stream; manual marks are kept until
+//! cleared with `--clear` or `--clear-all`.
+
+use anyhow::Result;
+use clap::Parser;
+use std::path::PathBuf;
+
+use lsl_recording_toolbox::badchannels::{
+    resolve_channel, store_bad_channels, stream_statistics, BadChannel, DetectionOptions,
+};
+use lsl_recording_toolbox::export::{open_streams, ExportOptions};
+use lsl_recording_toolbox::{config, usage};
+
+#[derive(Parser)]
+#[command(name = "lsl-badchannels")]
+#[command(about = "Record and detect bad channels in Zarr recordings")]
+#[command(version)]
+struct Args {
+    /// Path to the Zarr store
+    file_path: PathBuf,
+
+    /// Stream to work on (can be repeated; all streams if omitted)
+    #[arg(long = "stream")]
+    streams: Vec<String>,
+
+    /// Mark a channel bad, by label or 0-based index (can be repeated)
+    #[arg(long = "mark")]
+    marks: Vec<String>,
+
+    /// Reason stored with --mark
+    #[arg(long, default_value = "marked manually")]
+    reason: String,
+
+    /// Remove the mark of a channel, by label or 0-based index (can be repeated)
+    #[arg(long = "clear")]
+    clears: Vec<String>,
+
+    /// Remove all marks before applying --mark and --detect
+    #[arg(long)]
+    clear_all: bool,
+
+    /// Detect flat, noisy and mostly non-finite channels
+    #[arg(long)]
+    detect: bool,
+
+    /// Detection: peak-to-peak range at or below which a channel is flat
+    #[arg(long, default_value = "0.0")]
+    flat_range: f64,
+
+    /// Detection: standard deviation above this multiple of the median channel's is noisy
+    #[arg(long, default_value = "10.0")]
+    noise_ratio: f64,
+
+    /// Show what would change without writing to the store
+    #[arg(long)]
+    dry_run: bool,
+
+    /// Print the masks as JSON
+    #[arg(long)]
+    json: bool,
+}
+
+fn main() -> Result<()> {
+    usage::track("lsl-badchannels", run)
+}
+
+fn run() -> Result<()> {
+    let args: Args = config::parse_args("lsl-badchannels")?;
+
+    if !args.json {
+        lsl_recording_toolbox::display_license_notice("lsl-badchannels");
+    }
+
+    if (!args.marks.is_empty() || !args.clears.is_empty()) && args.streams.len() != 1 {
+        anyhow::bail!("--mark and --clear need exactly one --stream");
+    }
+
+    let options = ExportOptions {
+        streams: args.streams.clone(),
+        ..Default::default()
+    };
+    let detection = DetectionOptions {
+        flat_range: args.flat_range,
+        noise_ratio: args.noise_ratio,
+        ..Default::default()
+    };
+    let changes = args.clear_all || args.detect || !args.marks.is_empty() || !args.clears.is_empty();
+
+    let mut report = serde_json::Map::new();
+    for stream in open_streams(&args.file_path, &options)? {
+        let mut mask = stream.bad_channels.clone();
+
+        if args.clear_all {
+            mask.channels.clear();
+        }
+        for name in &args.clears {
+            let index = resolve_channel(&stream, name)?;
+            if !mask.clear(index) && !args.json {
+                println!("{}: channel '{}' was not marked", stream.name, name);
+            }
+        }
+
+        let labels = stream.channel_labels();
+        for name in &args.marks {
+            let index = resolve_channel(&stream, name)?;
+            mask.mark(BadChannel {
+                index,
+                label: labels[index].clone(),
+                reason: args.reason.clone(),
+                source: "manual".to_string(),
+            });
+        }
+
+        if args.detect {
+            if stream.is_marker() {
+                if !args.json {
+                    println!("{}: marker stream, skipping detection", stream.name);
+                }
+            } else {
+                mask.clear_source("detected");
+                for (index, reason) in stream_statistics(&stream)?.detect(&detection) {
+                    // A manual mark already explains the channel
+                    if mask.is_bad(index) {
+                        continue;
+                    }
+                    mask.mark(BadChannel {
+                        index,
+                        label: labels[index].clone(),
+                        reason,
+                        source: "detected".to_string(),
+                    });
+                }
+            }
+        }
+
+        if changes && !args.dry_run && mask != stream.bad_channels {
+            store_bad_channels(&args.file_path, &stream.name, &mask)?;
+        }
+
+        if args.json {
+            report.insert(stream.name.clone(), mask.to_json());
+            continue;
+        }
+        if mask.channels.is_empty() {
+            println!("{}:\tno bad channels ({} channels)", stream.name, stream.channel_count);
+        } else {
+            println!("{}:\t{} of {} channels bad", stream.name, mask.channels.len(), stream.channel_count);
+            for channel in &mask.channels {
+                println!(
+                    "  [{}] {}:\t{} ({})",
+                    channel.index, channel.label, channel.reason, channel.source
+                );
+            }
+        }
+    }
+
+    if args.json {
+        println!("{}", serde_json::to_string_pretty(&report)?);
+    } else if changes && args.dry_run {
+        println!();
+        println!("Dry run: store not modified");
+    }
+
+    Ok(())
+}
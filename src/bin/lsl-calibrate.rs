@@ -0,0 +1,247 @@
+//! LSL Calibrate - Measure end-to-end latency and timestamp bias with test pulses
+//!
+//! This tool drives a calibration source that emits rectangular pulses together with a
+//! marker stream holding the true pulse onsets, receives both streams the way
+//! lsl-recorder does, and compares the two. The result can be stored in a Zarr store
+//! so the measured timestamp bias can be corrected later.
+//!
+//! # Features
+//!
+//! - Starts `lsl-dummy-stream --calibration-interval` automatically, or calibrates any
+//!   source that publishes the `<source-id>_calibration` marker stream
+//! - Timestamp bias: recorded onset timestamp minus true onset
+//! - Latency: arrival of the onset sample at the inlet minus true onset
+//! - Stores the result as the `calibration` attribute of a stream (or root) group
+//!
+//! # Usage
+//!
+//! ```bash
+//! # Calibrate with a spawned dummy stream
+//! lsl-calibrate --pulses 20
+//!
+//! # Calibrate a device adapter and attach the result to a recording
+//! lsl-calibrate --source-id "EMG_1234" --store experiment.zarr --stream EMG
+//! ```
+//!
+//! # Notes
+//!
+//! Received timestamps use the same post-processing as lsl-recorder (clock sync,
+//! dejitter, monotonize), so the bias matches what ends up in recordings.
+
+use anyhow::{Context, Result};
+use clap::Parser;
+use lsl::Pullable;
+use std::path::PathBuf;
+use std::process::{Child, Command, Stdio};
+use std::time::{Duration, Instant};
+
+use lsl_recording_toolbox::calibration::{
+    match_onsets, store_calibration, CalibrationResult, EdgeDetector, OffsetStats, MARKER_SOURCE_SUFFIX,
+};
+use lsl_recording_toolbox::{config, usage};
+
+#[derive(Parser)]
+#[command(name = "lsl-calibrate")]
+#[command(about = "Measure end-to-end latency and timestamp bias with test pulses")]
+#[command(version)]
+struct Args {
+    /// Source ID of a calibration-capable stream (spawns lsl-dummy-stream if omitted)
+    #[arg(long)]
+    source_id: Option<String>,
+
+    /// Number of pulses to measure
+    #[arg(long, default_value = "10")]
+    pulses: usize,
+
+    /// Seconds between pulses (passed to the spawned dummy stream)
+    #[arg(long, default_value = "1.0")]
+    interval: f64,
+
+    /// Sample rate of the spawned dummy stream in Hz
+    #[arg(long, default_value = "1000")]
+    sample_rate: f64,
+
+    /// Channel carrying the pulses
+    #[arg(long, default_value = "0")]
+    channel: usize,
+
+    /// Signal level separating baseline from pulse
+    #[arg(long, default_value = "0.5")]
+    threshold: f64,
+
+    /// Seconds to wait for the streams to appear
+    #[arg(long, default_value = "5.0")]
+    resolve_timeout: f64,
+
+    /// Path to lsl-dummy-stream (defaults to the one next to this executable)
+    #[arg(long)]
+    dummy_path: Option<PathBuf>,
+
+    /// Zarr store to write the result to
+    #[arg(long)]
+    store: Option<PathBuf>,
+
+    /// Stream group in --store to attach the result to (root group if omitted)
+    #[arg(long, requires = "store")]
+    stream: Option<String>,
+
+    /// Print the result as JSON
+    #[arg(long)]
+    json: bool,
+}
+
+/// lsl-dummy-stream next to the current executable, else from PATH
+fn default_dummy_path() -> PathBuf {
+    let name = if cfg!(windows) { "lsl-dummy-stream.exe" } else { "lsl-dummy-stream" };
+    std::env::current_exe()
+        .ok()
+        .and_then(|exe| exe.parent().map(|dir| dir.join(name)))
+        .filter(|path| path.exists())
+        .unwrap_or_else(|| PathBuf::from(name))
+}
+
+/// Kills the spawned dummy stream when calibration ends (including on error)
+struct DummyGuard(Child);
+
+impl Drop for DummyGuard {
+    fn drop(&mut self) {
+        self.0.kill().ok();
+        self.0.wait().ok();
+    }
+}
+
+/// Resolve a stream by source ID and open an inlet with the recorder's post-processing
+fn open_inlet(source_id: &str, timeout: f64) -> Result<lsl::StreamInlet> {
+    let info = lsl::resolve_byprop("source_id", source_id, 1, timeout)
+        .map_err(|e| anyhow::anyhow!("LSL error: {}", e))?
+        .into_iter()
+        .next()
+        .ok_or_else(|| anyhow::anyhow!("Stream with source ID '{}' not found", source_id))?;
+
+    let inlet = lsl::StreamInlet::new(&info, 360, 0, true)
+        .map_err(|e| anyhow::anyhow!("LSL error: {}", e))?;
+    inlet
+        .set_postprocessing(&[
+            lsl::ProcessingOption::ClockSync,
+            lsl::ProcessingOption::Dejitter,
+            lsl::ProcessingOption::Monotonize,
+        ])
+        .map_err(|e| anyhow::anyhow!("LSL error: {}", e))?;
+    inlet
+        .open_stream(timeout)
+        .map_err(|e| anyhow::anyhow!("LSL error: {}", e))?;
+    Ok(inlet)
+}
+
+fn main() -> Result<()> {
+    usage::track("lsl-calibrate", run)
+}
+
+fn run() -> Result<()> {
+    let args: Args = config::parse_args("lsl-calibrate")?;
+
+    if !args.json {
+        lsl_recording_toolbox::display_license_notice("lsl-calibrate");
+    }
+
+    let (source_id, _dummy) = match args.source_id {
+        Some(ref id) => (id.clone(), None),
+        None => {
+            let id = format!("lsl_calibrate_{}", std::process::id());
+            let dummy_path = args.dummy_path.clone().unwrap_or_else(default_dummy_path);
+            let child = Command::new(&dummy_path)
+                .args(["--name", "Calibration", "--type", "Calibration", "--source-id", &id])
+                .args(["--channels", "1", "--chunk-size", "1"])
+                .args(["--sample-rate", &args.sample_rate.to_string()])
+                .args(["--calibration-interval", &args.interval.to_string()])
+                .stdout(Stdio::null())
+                .stderr(Stdio::null())
+                .spawn()
+                .with_context(|| format!("Failed to start {}", dummy_path.display()))?;
+            (id, Some(DummyGuard(child)))
+        }
+    };
+
+    let data_inlet = open_inlet(&source_id, args.resolve_timeout)?;
+    let marker_inlet = open_inlet(&format!("{}{}", source_id, MARKER_SOURCE_SUFFIX), args.resolve_timeout)
+        .context("Calibration marker stream not found (is the source calibration-capable?)")?;
+
+    if !args.json {
+        println!("Source:\t\t{}", source_id);
+        println!("Measuring {} pulses (~{:.0} s)...", args.pulses, args.pulses as f64 * args.interval);
+    }
+
+    let mut detector = EdgeDetector::new(args.threshold);
+    let mut true_onsets = Vec::new();
+    let mut recorded_onsets = Vec::new();
+    let mut arrivals = Vec::new();
+    let mut sample = Vec::<f64>::new();
+
+    // Allow for the first partial interval and transport delay on top of the pulse train
+    let deadline = Instant::now() + Duration::from_secs_f64((args.pulses as f64 + 2.0) * args.interval + 1.0);
+    while Instant::now() < deadline && true_onsets.len() < args.pulses {
+        let ts = data_inlet
+            .pull_sample_buf(&mut sample, 0.1)
+            .map_err(|e| anyhow::anyhow!("LSL error: {}", e))?;
+        if ts != 0.0
+            && let Some(&value) = sample.get(args.channel)
+            && detector.is_onset(value)
+        {
+            recorded_onsets.push(ts);
+            arrivals.push(lsl::local_clock());
+        }
+
+        // Drain markers without blocking the data stream
+        while let Ok((_, marker_ts)) = <lsl::StreamInlet as Pullable<String>>::pull_sample(&marker_inlet, 0.0) {
+            if marker_ts == 0.0 {
+                break;
+            }
+            true_onsets.push(marker_ts);
+        }
+    }
+
+    let max_offset = args.interval / 2.0;
+    let result = CalibrationResult {
+        source_id,
+        pulses_sent: true_onsets.len(),
+        pulses_detected: recorded_onsets.len(),
+        timestamp_bias: OffsetStats::from_offsets(&match_onsets(&true_onsets, &recorded_onsets, max_offset)),
+        latency: OffsetStats::from_offsets(&match_onsets(&true_onsets, &arrivals, max_offset)),
+    };
+
+    if args.json {
+        println!("{}", serde_json::to_string_pretty(&result.to_json())?);
+    } else {
+        println!();
+        println!("Pulses:\t\t{} sent, {} detected", result.pulses_sent, result.pulses_detected);
+        for (label, stats) in [("Timestamp bias", &result.timestamp_bias), ("Latency", &result.latency)] {
+            match stats {
+                Some(s) => println!(
+                    "{}:\t{:+.3} ms (std {:.3} ms, range {:+.3} to {:+.3} ms)",
+                    label,
+                    s.mean * 1000.0,
+                    s.std * 1000.0,
+                    s.min * 1000.0,
+                    s.max * 1000.0
+                ),
+                None => println!("{}:\tno matched pulses", label),
+            }
+        }
+        if let Some(correction) = result.timestamp_correction() {
+            println!("Correction:\tadd {:+.3} ms to recorded timestamps", correction * 1000.0);
+        }
+    }
+
+    if result.timestamp_bias.is_none() {
+        anyhow::bail!("No pulses could be matched; check --channel and --threshold");
+    }
+
+    if let Some(ref store) = args.store {
+        store_calibration(store, args.stream.as_deref(), &result)?;
+        if !args.json {
+            println!("Stored calibration in {}", store.display());
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,100 @@
+//! LSL Demo - Example data for trying the toolbox without hardware
+//!
+//! `lsl-demo generate` writes a small, fully populated example store: simulated EMG,
+//! EEG and ECG streams, a marker stream, annotations, quality summaries, bad-channel
+//! masks and aligned timestamps from lsl-sync. The data depends only on the seed and
+//! duration, so tutorials and tests can rely on the exact contents.
+//!
+//! # Usage
+//!
+//! ```bash
+//! # Write demo.zarr and explore it
+//! lsl-demo generate
+//! lsl-inspect demo.zarr --verbose
+//! lsl-validate demo.zarr
+//!
+//! # A longer session with different noise, replacing the previous one
+//! lsl-demo generate session.zarr --duration 120 --seed 7 --force
+//! ```
+
+use anyhow::Result;
+use clap::{Parser, Subcommand};
+use std::path::PathBuf;
+
+use lsl_recording_toolbox::demo::{generate_demo_store, DemoOptions};
+use lsl_recording_toolbox::{config, usage};
+
+#[derive(Parser)]
+#[command(name = "lsl-demo")]
+#[command(about = "Generate example data for trying the toolbox without hardware")]
+#[command(version)]
+struct Args {
+    #[command(subcommand)]
+    command: DemoCommand,
+}
+
+#[derive(Subcommand)]
+enum DemoCommand {
+    /// Write a deterministic example store with several streams, markers, annotations and QC results
+    Generate(GenerateArgs),
+}
+
+#[derive(clap::Args)]
+struct GenerateArgs {
+    /// Zarr store to write
+    #[arg(default_value = "demo.zarr")]
+    output: PathBuf,
+
+    /// Length of the session in seconds
+    #[arg(long, default_value_t = 30.0)]
+    duration: f64,
+
+    /// Seed of the simulated signals (the same seed gives the same data)
+    #[arg(long, default_value_t = 42)]
+    seed: u64,
+
+    /// Replace an existing store at the output path
+    #[arg(long)]
+    force: bool,
+}
+
+fn main() -> Result<()> {
+    usage::track("lsl-demo", run)
+}
+
+fn run() -> Result<()> {
+    let args: Args = config::parse_args("lsl-demo")?;
+
+    lsl_recording_toolbox::display_license_notice("lsl-demo");
+
+    match args.command {
+        DemoCommand::Generate(args) => {
+            let options = DemoOptions {
+                duration: args.duration,
+                seed: args.seed,
+                overwrite: args.force,
+                ..DemoOptions::default()
+            };
+            let streams = generate_demo_store(&args.output, &options)?;
+
+            println!("Generated {}:", args.output.display());
+            for stream in &streams {
+                if stream.nominal_srate > 0.0 {
+                    println!(
+                        "\t/{}/:\t{} x {} at {} Hz ({} samples)",
+                        stream.name, stream.channels, stream.stream_type, stream.nominal_srate, stream.samples
+                    );
+                } else {
+                    println!("\t/{}/:\t{} events", stream.name, stream.samples);
+                }
+            }
+            println!();
+            println!("Try:");
+            println!("\tlsl-inspect {} --verbose", args.output.display());
+            println!("\tlsl-validate {}", args.output.display());
+            println!("\tlsl-export {} --format csv", args.output.display());
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,116 @@
+//! LSL Discover - List LSL streams visible on the network
+//!
+//! This tool resolves all LSL streams currently visible on the local network and
+//! prints their identifying properties, so source IDs no longer have to be guessed
+//! before starting lsl-recorder.
+//!
+//! # Features
+//!
+//! - Resolves every visible stream (name, type, source_id, channels, rate, host)
+//! - Table output for humans, JSON output for scripts
+//! - Watch mode that refreshes the list periodically
+//!
+//! # Usage
+//!
+//! ```bash
+//! # List streams once
+//! lsl-discover
+//!
+//! # Machine-readable output
+//! lsl-discover --json
+//!
+//! # Refresh every 2 seconds until Ctrl+C
+//! lsl-discover --watch --interval 2
+//! ```
+//!
+//! # Output Format
+//!
+//! ```text
+//! NAME      TYPE  SOURCE_ID  CHANNELS  RATE     FORMAT   HOST
+//! TestEMG   EMG   EMG_1234   8         2000 Hz  Float32  lab-pc
+//! ```
+//!
+//! With `--json`, a JSON array of stream objects is printed (one array per
+//! refresh in watch mode).
+
+use anyhow::Result;
+use clap::Parser;
+use std::thread;
+use std::time::Duration;
+
+use lsl_recording_toolbox::discovery::{discover_streams, format_table};
+use lsl_recording_toolbox::{config, usage};
+
+#[derive(Parser)]
+#[command(name = "lsl-discover")]
+#[command(about = "List LSL streams visible on the network")]
+#[command(version)]
+struct Args {
+    /// Time to wait for stream responses in seconds
+    #[arg(short, long, default_value = "2.0")]
+    timeout: f64,
+
+    /// Print results as JSON instead of a table
+    #[arg(long)]
+    json: bool,
+
+    /// Keep refreshing the stream list until interrupted
+    #[arg(short, long)]
+    watch: bool,
+
+    /// Seconds between refreshes in watch mode
+    #[arg(long, default_value = "5.0")]
+    interval: f64,
+}
+
+fn main() -> Result<()> {
+    usage::track("lsl-discover", run)
+}
+
+fn run() -> Result<()> {
+    let args: Args = config::parse_args("lsl-discover")?;
+
+    // Keep stdout clean for JSON consumers
+    if !args.json {
+        lsl_recording_toolbox::display_license_notice("lsl-discover");
+    }
+
+    loop {
+        let streams = discover_streams(args.timeout)?;
+
+        if args.json {
+            if args.watch {
+                // One compact array per line so watchers can parse line by line
+                println!("{}", serde_json::to_string(&streams)?);
+            } else {
+                println!("{}", serde_json::to_string_pretty(&streams)?);
+            }
+        } else {
+            if args.watch {
+                // Clear screen and move cursor home before redrawing
+                print!("\x1B[2J\x1B[H");
+                println!(
+                    "LSL streams at {} (refresh every {}s, Ctrl+C to exit)",
+                    chrono::Local::now().format("%H:%M:%S"),
+                    args.interval
+                );
+                println!();
+            }
+
+            if streams.is_empty() {
+                println!("No streams found (waited {}s)", args.timeout);
+            } else {
+                print!("{}", format_table(&streams));
+                println!();
+                println!("{} stream(s) found", streams.len());
+            }
+        }
+
+        if !args.watch {
+            break;
+        }
+        thread::sleep(Duration::from_secs_f64(args.interval.max(0.0)));
+    }
+
+    Ok(())
+}
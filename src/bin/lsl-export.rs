@@ -0,0 +1,418 @@
+//! LSL Export - Convert Zarr recordings into other file formats
+//!
+//! This tool converts a Zarr store written by lsl-recorder into formats read by the
+//! wider ecosystem, so recordings can be opened without custom Python code.
+//!
+//! # Formats
+//!
+//! - `xdf` - Extensible Data Format (MNE, EEGLAB, SigViewer, pyxdf). Stream headers
+//!   are rebuilt from the stored `stream_info`; samples keep their original timestamps.
+//! - `edf`, `bdf` - EDF+/BDF+ for clinical review software. Numeric streams are
+//!   resampled onto a common grid, marker streams become annotations, and long
+//!   recordings are split into several files.
+//! - `csv`, `parquet`, `ndjson` - One flat table per stream (time, aligned_time,
+//!   channels), with channel selection and time-range slicing.
+//!
+//! With `--stdout`, one stream's table is streamed to stdout as CSV or NDJSON
+//! instead, so huge stores can be piped into other programs without temporary files.
+//!
+//! `lsl-export wide` instead joins regular streams onto one resampled time base and
+//! writes a single CSV or Parquet table with a `time` column and a `<stream>.<channel>`
+//! column for every channel, ready for pandas or R.
+//!
+//! `lsl-export audio` renders one channel as a normalized WAV file, for checking a
+//! recording by ear: mains hum, movement artifacts and dropouts are easy to hear.
+//!
+//! A damaged chunk does not end the export: its samples are written as fill values
+//! (timestamps as `NaN`) and the affected sample ranges are reported on stderr.
+//! `--strict` fails on the first unreadable chunk instead.
+//!
+//! # Usage
+//!
+//! ```bash
+//! # Export all streams to experiment.xdf
+//! lsl-export experiment.zarr --format xdf
+//!
+//! # Export selected streams with lsl-sync timestamps
+//! lsl-export experiment.zarr --format xdf --stream EMG --stream Markers \
+//!   --aligned-time -o aligned.xdf
+//!
+//! # EDF+ with irregular streams resampled at 250 Hz, one file per hour
+//! lsl-export experiment.zarr --format edf --resample-rate 250 --split-duration 3600
+//!
+//! # Two EMG channels, seconds 60-120, as Parquet tables in experiment_parquet/
+//! lsl-export experiment.zarr --format parquet --stream EMG --channel 0 --channel 3 \
+//!   --start 60 --end 120
+//!
+//! # EMG samples, one JSON object per line, into another program
+//! lsl-export experiment.zarr --stdout --format ndjson --stream EMG | python detect.py
+//!
+//! # EMG and EEG at 250 Hz in one table, experiment_wide.csv
+//! lsl-export wide experiment.zarr --stream EMG --stream EEG --rate 250
+//!
+//! # Channel 3 of EMG as experiment_EMG_3.wav; EEG channel Cz at 10x speed
+//! lsl-export audio experiment.zarr --channel EMG:3
+//! lsl-export audio experiment.zarr --channel EEG:Cz --speed 10 --normalize rms
+//! ```
+
+use anyhow::Result;
+use clap::{Parser, Subcommand};
+use std::path::PathBuf;
+
+use lsl_recording_toolbox::export::audio::{
+    default_audio_output_path, export_audio, AudioOptions, Normalization, DEFAULT_AUDIO_RATE,
+};
+use lsl_recording_toolbox::export::edf::{export_edf, EdfOptions};
+use lsl_recording_toolbox::export::table::{export_tables, is_broken_pipe, write_table, TableOptions};
+use lsl_recording_toolbox::export::wide::{default_wide_output_path, export_wide, WideOptions};
+use lsl_recording_toolbox::export::xdf::export_xdf;
+use lsl_recording_toolbox::export::{default_output_path, ExportFormat, ExportOptions};
+use lsl_recording_toolbox::{config, usage};
+
+#[derive(Parser)]
+#[command(name = "lsl-export")]
+#[command(about = "Convert Zarr recordings into other file formats")]
+#[command(version)]
+#[command(args_conflicts_with_subcommands = true, subcommand_negates_reqs = true)]
+struct Args {
+    #[command(subcommand)]
+    command: Option<ExportCommand>,
+
+    /// Path to the Zarr store to export
+    #[arg(required = true)]
+    file_path: Option<PathBuf>,
+
+    /// Output format
+    #[arg(long, value_enum, default_value = "xdf")]
+    format: ExportFormat,
+
+    /// Output file, or directory for csv/parquet/ndjson (defaults to the store path with the format's extension)
+    #[arg(short, long)]
+    output: Option<PathBuf>,
+
+    /// Write one stream's samples to stdout as csv or ndjson instead of files (for pipes)
+    #[arg(long, conflicts_with = "output")]
+    stdout: bool,
+
+    /// Stream to export (can be repeated; all streams if omitted)
+    #[arg(long = "stream")]
+    streams: Vec<String>,
+
+    /// Use aligned_time from lsl-sync instead of the original timestamps
+    #[arg(long)]
+    aligned_time: bool,
+
+    /// Keep channels marked bad (see lsl-badchannels) in EDF/BDF and table output
+    #[arg(long)]
+    include_bad_channels: bool,
+
+    /// Fail on unreadable chunks instead of substituting fill values and reporting the affected samples
+    #[arg(long)]
+    strict: bool,
+
+    /// EDF/BDF: rate in Hz at which irregular numeric streams are resampled
+    #[arg(long, default_value = "100")]
+    resample_rate: f64,
+
+    /// EDF/BDF: maximum seconds per file (longer exports are split into numbered files)
+    #[arg(long)]
+    split_duration: Option<f64>,
+
+    /// CSV/Parquet/NDJSON: channel to export, by label or 0-based index (can be repeated)
+    #[arg(long = "channel")]
+    channels: Vec<String>,
+
+    /// CSV/Parquet/NDJSON: start of the exported range in seconds from the start of the recording
+    #[arg(long)]
+    start: Option<f64>,
+
+    /// CSV/Parquet/NDJSON: end of the exported range in seconds from the start of the recording
+    #[arg(long)]
+    end: Option<f64>,
+}
+
+#[derive(Subcommand)]
+enum ExportCommand {
+    /// Join regular streams on a common resampled time base into one wide CSV/Parquet table
+    Wide(WideArgs),
+    /// Render one channel to a normalized WAV file for listening
+    Audio(AudioArgs),
+}
+
+#[derive(clap::Args)]
+struct AudioArgs {
+    /// Path to the Zarr store to export
+    file_path: PathBuf,
+
+    /// Channel to render as <stream>:<channel>, the channel by label or 0-based index (e.g. EMG:3)
+    #[arg(long)]
+    channel: String,
+
+    /// Output file (defaults to <store>_<stream>_<channel>.wav next to the store)
+    #[arg(short, long)]
+    output: Option<PathBuf>,
+
+    /// WAV sample rate in Hz
+    #[arg(long, default_value_t = DEFAULT_AUDIO_RATE)]
+    rate: u32,
+
+    /// Seconds of recording per second of audio (e.g. 10 to hear EEG rhythms)
+    #[arg(long, default_value_t = 1.0)]
+    speed: f64,
+
+    /// Scale the largest deviation (peak) or the RMS (rms, -20 dBFS) to full scale
+    #[arg(long, value_enum, default_value = "peak")]
+    normalize: Normalization,
+
+    /// Use aligned_time from lsl-sync instead of the original timestamps
+    #[arg(long)]
+    aligned_time: bool,
+
+    /// Fail on unreadable chunks instead of substituting fill values and reporting the affected samples
+    #[arg(long)]
+    strict: bool,
+
+    /// Start of the exported range in seconds from the start of the recording
+    #[arg(long)]
+    start: Option<f64>,
+
+    /// End of the exported range in seconds from the start of the recording
+    #[arg(long)]
+    end: Option<f64>,
+}
+
+#[derive(clap::Args)]
+struct WideArgs {
+    /// Path to the Zarr store to export
+    file_path: PathBuf,
+
+    /// Output format (csv or parquet)
+    #[arg(long, value_enum, default_value = "csv")]
+    format: ExportFormat,
+
+    /// Output file (defaults to <store>_wide.<format> next to the store)
+    #[arg(short, long)]
+    output: Option<PathBuf>,
+
+    /// Regular stream to include (can be repeated; all regular streams if omitted)
+    #[arg(long = "stream")]
+    streams: Vec<String>,
+
+    /// Rate of the common time base in Hz (defaults to the highest nominal rate)
+    #[arg(long)]
+    rate: Option<f64>,
+
+    /// Use aligned_time from lsl-sync instead of the original timestamps
+    #[arg(long)]
+    aligned_time: bool,
+
+    /// Keep channels marked bad (see lsl-badchannels)
+    #[arg(long)]
+    include_bad_channels: bool,
+
+    /// Fail on unreadable chunks instead of substituting fill values and reporting the affected samples
+    #[arg(long)]
+    strict: bool,
+
+    /// Start of the exported range in seconds from the start of the recording
+    #[arg(long)]
+    start: Option<f64>,
+
+    /// End of the exported range in seconds from the start of the recording
+    #[arg(long)]
+    end: Option<f64>,
+}
+
+fn main() -> Result<()> {
+    usage::track("lsl-export", run)
+}
+
+fn run() -> Result<()> {
+    let args: Args = config::parse_args("lsl-export")?;
+
+    // Keep stdout clean for the program reading the samples
+    if !args.stdout {
+        lsl_recording_toolbox::display_license_notice("lsl-export");
+    }
+
+    match args.command {
+        Some(ExportCommand::Wide(wide)) => return run_wide(wide),
+        Some(ExportCommand::Audio(audio)) => return run_audio(audio),
+        None => {}
+    }
+    if args.stdout {
+        return run_stdout(&args);
+    }
+    let file_path = args.file_path.clone().expect("clap requires the store path");
+
+    let output = args
+        .output
+        .clone()
+        .unwrap_or_else(|| default_output_path(&file_path, args.format));
+    let options = ExportOptions {
+        streams: args.streams.clone(),
+        aligned_time: args.aligned_time,
+        include_bad_channels: args.include_bad_channels,
+        tolerate_damage: !args.strict,
+    };
+
+    println!("Store:\t{}", file_path.display());
+    println!("Output:\t{} ({})", output.display(), args.format.extension());
+    println!();
+
+    match args.format {
+        ExportFormat::Xdf => {
+            let summaries = export_xdf(&file_path, &output, &options)?;
+            for summary in &summaries {
+                println!("Stream {}:\t{} ({} samples)", summary.stream_id, summary.name, summary.samples);
+            }
+            println!();
+            println!("Exported {} stream(s)", summaries.len());
+        }
+        ExportFormat::Edf | ExportFormat::Bdf => {
+            let edf = EdfOptions {
+                bdf: args.format == ExportFormat::Bdf,
+                resample_rate: args.resample_rate,
+                split_duration: args.split_duration,
+            };
+            let files = export_edf(&file_path, &output, &options, &edf)?;
+            for file in &files {
+                println!(
+                    "{}:\t{} signals, {} s, {} annotations",
+                    file.path.display(),
+                    file.signals,
+                    file.records,
+                    file.annotations
+                );
+            }
+            println!();
+            println!("Exported {} file(s)", files.len());
+        }
+        ExportFormat::Csv | ExportFormat::Parquet | ExportFormat::Ndjson => {
+            let table = TableOptions {
+                channels: args.channels.clone(),
+                start: args.start,
+                end: args.end,
+            };
+            let tables = export_tables(&file_path, &output, args.format, &options, &table)?;
+            for table in &tables {
+                println!(
+                    "{}:\t{} rows, {} columns -> {}",
+                    table.stream,
+                    table.rows,
+                    table.columns,
+                    table.path.display()
+                );
+            }
+            println!();
+            println!("Exported {} stream(s)", tables.len());
+        }
+    }
+
+    Ok(())
+}
+
+fn run_stdout(args: &Args) -> Result<()> {
+    let file_path = args.file_path.as_deref().expect("clap requires the store path");
+    let options = ExportOptions {
+        streams: args.streams.clone(),
+        aligned_time: args.aligned_time,
+        include_bad_channels: args.include_bad_channels,
+        tolerate_damage: !args.strict,
+    };
+    let table = TableOptions {
+        channels: args.channels.clone(),
+        start: args.start,
+        end: args.end,
+    };
+
+    let stdout = std::io::BufWriter::new(std::io::stdout().lock());
+    match write_table(file_path, stdout, args.format, &options, &table) {
+        // The reader stopped early (e.g. `| head`), which is not an error
+        Err(e) if is_broken_pipe(&e) => Ok(()),
+        result => result.map(|_| ()),
+    }
+}
+
+fn run_wide(args: WideArgs) -> Result<()> {
+    let output = args
+        .output
+        .clone()
+        .unwrap_or_else(|| default_wide_output_path(&args.file_path, args.format));
+    let options = ExportOptions {
+        streams: args.streams.clone(),
+        aligned_time: args.aligned_time,
+        include_bad_channels: args.include_bad_channels,
+        tolerate_damage: !args.strict,
+    };
+    let wide = WideOptions {
+        rate: args.rate,
+        start: args.start,
+        end: args.end,
+    };
+
+    println!("Store:\t{}", args.file_path.display());
+    println!("Output:\t{} (wide {})", output.display(), args.format.extension());
+    println!();
+
+    let summary = export_wide(&args.file_path, &output, args.format, &options, &wide)?;
+    for (stream, reason) in &summary.skipped {
+        println!("Skipped {}:\t{}", stream, reason);
+    }
+    println!("Streams:\t{}", summary.streams.join(", "));
+    println!("Time base:\t{} Hz", summary.rate);
+    println!();
+    println!(
+        "Exported {} rows, {} columns -> {}",
+        summary.rows,
+        summary.columns,
+        summary.path.display()
+    );
+
+    Ok(())
+}
+
+fn run_audio(args: AudioArgs) -> Result<()> {
+    let output = args
+        .output
+        .clone()
+        .unwrap_or_else(|| default_audio_output_path(&args.file_path, &args.channel));
+    let options = ExportOptions {
+        aligned_time: args.aligned_time,
+        tolerate_damage: !args.strict,
+        ..ExportOptions::default()
+    };
+    let audio = AudioOptions {
+        channel: args.channel.clone(),
+        rate: args.rate,
+        speed: args.speed,
+        normalization: args.normalize,
+        start: args.start,
+        end: args.end,
+    };
+
+    println!("Store:\t{}", args.file_path.display());
+    println!("Output:\t{} (wav)", output.display());
+    println!();
+
+    let summary = export_audio(&args.file_path, &output, &options, &audio)?;
+    println!("Channel:\t{}:{}", summary.stream, summary.channel);
+    println!(
+        "Audio:\t\t{:.1} s at {} Hz ({:.1} s recorded, {}x)",
+        summary.duration, args.rate, summary.recorded_duration, args.speed
+    );
+    println!("Gain:\t\t{:.4}", summary.gain);
+    if summary.gain == 0.0 {
+        println!("Warning: the channel is flat, the file is silent");
+    }
+    if summary.clipped > 0 {
+        println!("Clipped:\t{} samples", summary.clipped);
+    }
+    if summary.silent > 0 {
+        println!("Silent:\t\t{} samples in gaps", summary.silent);
+    }
+    println!();
+    println!("Exported {} samples -> {}", summary.frames, summary.path.display());
+
+    Ok(())
+}
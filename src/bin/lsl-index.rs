@@ -0,0 +1,102 @@
+//! LSL Index - Maintain a SQLite index of recording sessions
+//!
+//! This tool walks a data root, summarizes every `.zarr` store from its metadata and
+//! keeps the results in a SQLite database. Runs are incremental: only stores whose
+//! metadata changed since the last run are re-read, and entries for deleted stores
+//! are removed.
+//!
+//! # Features
+//!
+//! - One `sessions` row per store (subject, session, notes, duration, validation status)
+//! - One `streams` row per stream (type, source ID, channels, rate, samples, timestamps)
+//! - Incremental updates based on metadata modification times
+//! - Usable from `lsl-query --index` or any SQLite client
+//!
+//! # Usage
+//!
+//! ```bash
+//! # Create or update the index in the data root (./lsl-index.sqlite)
+//! lsl-index /data
+//!
+//! # Custom database location, rebuild from scratch
+//! lsl-index /data --db /data/catalog.sqlite --rebuild
+//!
+//! # Query it directly
+//! sqlite3 /data/lsl-index.sqlite "SELECT path, duration FROM sessions WHERE subject = 'P001'"
+//! ```
+//!
+//! # Validation Status
+//!
+//! - `ok` - every stream has samples and final timestamps
+//! - `empty` - no streams, or a stream without samples
+//! - `incomplete` - a stream was not finalized (missing `last_timestamp`)
+
+use anyhow::Result;
+use clap::Parser;
+use std::path::PathBuf;
+
+use lsl_recording_toolbox::index::{open_index, update_index, DEFAULT_INDEX_FILE};
+use lsl_recording_toolbox::{config, usage};
+
+#[derive(Parser)]
+#[command(name = "lsl-index")]
+#[command(about = "Maintain a SQLite index of Zarr recording sessions")]
+#[command(version)]
+struct Args {
+    /// Data root to scan for .zarr stores
+    #[arg(default_value = ".")]
+    root: PathBuf,
+
+    /// Database file (defaults to <root>/lsl-index.sqlite)
+    #[arg(long)]
+    db: Option<PathBuf>,
+
+    /// Delete the existing database and index everything again
+    #[arg(long)]
+    rebuild: bool,
+
+    /// Only print the final summary
+    #[arg(short, long)]
+    quiet: bool,
+}
+
+fn main() -> Result<()> {
+    usage::track("lsl-index", run)
+}
+
+fn run() -> Result<()> {
+    let args: Args = config::parse_args("lsl-index")?;
+
+    if !args.quiet {
+        lsl_recording_toolbox::display_license_notice("lsl-index");
+    }
+
+    let db_path = args.db.clone().unwrap_or_else(|| args.root.join(DEFAULT_INDEX_FILE));
+    if args.rebuild && db_path.exists() {
+        std::fs::remove_file(&db_path)?;
+    }
+
+    let mut conn = open_index(&db_path)?;
+    let stats = update_index(&mut conn, &args.root, args.quiet)?;
+
+    let total: i64 = conn.query_row("SELECT COUNT(*) FROM sessions", [], |row| row.get(0))?;
+    let mut stmt = conn.prepare(
+        "SELECT validation_status, COUNT(*) FROM sessions GROUP BY validation_status ORDER BY validation_status",
+    )?;
+    let by_status: Vec<(String, i64)> = stmt
+        .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?
+        .collect::<std::result::Result<_, _>>()?;
+
+    println!();
+    println!("Index:\t\t{}", db_path.display());
+    println!(
+        "Changes:\t{} added, {} updated, {} unchanged, {} removed, {} failed",
+        stats.added, stats.updated, stats.unchanged, stats.removed, stats.failed
+    );
+    println!("Sessions:\t{}", total);
+    for (status, count) in by_status {
+        println!("\t{}:\t{}", status, count);
+    }
+
+    Ok(())
+}
@@ -0,0 +1,237 @@
+//! LSL Loopback - Verify the replay → record path end to end
+//!
+//! This tool replays a recorded stream with lsl-replay, records it again with
+//! lsl-recorder, and compares the recorded copy with the original: the recorded
+//! samples are located in the original by cross-correlation, then checked for
+//! identical values (fidelity) and for the timing error the loop adds (offset,
+//! drift and jitter of the recorded against the original timestamps).
+//!
+//! # Usage
+//!
+//! ```bash
+//! # Replay the EMG stream for 20 s and verify the recorded copy
+//! lsl-loopback session.zarr --stream EMG
+//!
+//! # Longer run with a stricter jitter limit, as JSON
+//! lsl-loopback session.zarr --stream EMG --duration 60 --max-jitter-ms 1 --json
+//! ```
+//!
+//! # Outcome
+//!
+//! Exit code `0` when the recorded samples were found in the original, every
+//! compared sample is identical and the largest timing residual stays within
+//! `--max-jitter-ms`; `1` otherwise. Only numeric streams can be verified.
+//! The recorded store (`loopback_<date>_<time>.zarr` by default) is kept.
+
+use anyhow::{Context, Result};
+use clap::Parser;
+use std::path::{Path, PathBuf};
+use std::process::{Child, Command, Stdio};
+use std::sync::Arc;
+use zarrs::filesystem::FilesystemStore;
+
+use lsl_recording_toolbox::loopback::{compare, read_signal};
+use lsl_recording_toolbox::{config, usage};
+use lsl_recording_toolbox::zarr::{expand_output_template, read_group_attributes};
+
+#[derive(Parser)]
+#[command(name = "lsl-loopback")]
+#[command(about = "Replay a stream, record it again and verify the copy against the original")]
+#[command(version)]
+struct Args {
+    /// Zarr store holding the stream to replay
+    file_path: PathBuf,
+
+    /// Stream to replay
+    #[arg(short, long)]
+    stream: String,
+
+    /// Seconds to record
+    #[arg(long, default_value = "20")]
+    duration: u64,
+
+    /// Recording base path (without .zarr extension); supports {date}, {time}
+    #[arg(short, long, default_value = "loopback_{date}_{time}")]
+    output: PathBuf,
+
+    /// Longest delay (s) between the start of the replay and the start of the recording
+    #[arg(long, default_value = "10.0")]
+    max_lag: f64,
+
+    /// Largest timing residual (ms) still considered a pass
+    #[arg(long, default_value = "5.0")]
+    max_jitter_ms: f64,
+
+    /// Timeout for stream resolution in seconds
+    #[arg(long, default_value = "10.0")]
+    resolve_timeout: f64,
+
+    /// Directory holding lsl-replay and lsl-recorder (defaults to the directory of this executable)
+    #[arg(long)]
+    bin_dir: Option<PathBuf>,
+
+    /// Print the result as JSON
+    #[arg(long)]
+    json: bool,
+
+    /// Show the output of lsl-replay and lsl-recorder
+    #[arg(short, long)]
+    verbose: bool,
+}
+
+/// Path of a toolbox executable in `bin_dir`, else next to this executable, else from PATH
+fn tool_path(bin_dir: Option<&Path>, name: &str) -> PathBuf {
+    let name = if cfg!(windows) { format!("{}.exe", name) } else { name.to_string() };
+    let dir = bin_dir
+        .map(Path::to_path_buf)
+        .or_else(|| std::env::current_exe().ok().and_then(|exe| exe.parent().map(Path::to_path_buf)));
+    dir.map(|dir| dir.join(&name))
+        .filter(|path| path.exists())
+        .unwrap_or_else(|| PathBuf::from(name))
+}
+
+/// Stops the replay when verification ends (including on error)
+struct ReplayGuard(Child);
+
+impl Drop for ReplayGuard {
+    fn drop(&mut self) {
+        self.0.kill().ok();
+        self.0.wait().ok();
+    }
+}
+
+fn main() -> Result<()> {
+    usage::track("lsl-loopback", run)
+}
+
+fn run() -> Result<()> {
+    let args: Args = config::parse_args("lsl-loopback")?;
+
+    if !args.json {
+        lsl_recording_toolbox::display_license_notice("lsl-loopback");
+    }
+
+    let output = expand_output_template(&args.output, None, None);
+    let store_path = PathBuf::from(format!("{}.zarr", output.display()));
+    if store_path.exists() {
+        anyhow::bail!("{} already exists; choose another --output", store_path.display());
+    }
+
+    // A unique outlet name keeps the recorder off the live device and other replays
+    let loop_name = format!("{}_loopback_{}", args.stream, std::process::id());
+    let output_of = |verbose: bool| if verbose { Stdio::inherit() } else { Stdio::null() };
+
+    if !args.json {
+        println!("1/3 Recording {} s of {} replayed as {}...", args.duration, args.stream, loop_name);
+    }
+    let mut recorder = Command::new(tool_path(args.bin_dir.as_deref(), "lsl-recorder"))
+        .args(["--by-name", &loop_name])
+        .args(["--stream-name", &args.stream])
+        .arg("--output")
+        .arg(&output)
+        .args(["--duration", &args.duration.to_string()])
+        .args(["--resolve-timeout", &args.resolve_timeout.to_string()])
+        .arg("--quiet")
+        .stdout(output_of(args.verbose))
+        .stderr(output_of(args.verbose))
+        .spawn()
+        .context("Failed to start lsl-recorder")?;
+    let replay = ReplayGuard(
+        Command::new(tool_path(args.bin_dir.as_deref(), "lsl-replay"))
+            .arg(&args.file_path)
+            .args(["--stream", &args.stream])
+            .args(["--output-name", &loop_name])
+            .args(["--loop", "false"])
+            .stdout(output_of(args.verbose))
+            .stderr(output_of(args.verbose))
+            .spawn()
+            .context("Failed to start lsl-replay")?,
+    );
+
+    let status = recorder.wait().context("Failed to wait for lsl-recorder")?;
+    drop(replay);
+    if !status.success() {
+        anyhow::bail!("lsl-recorder failed ({})", status);
+    }
+
+    if !args.json {
+        println!("2/3 Reading both copies...");
+    }
+    let recorded = read_signal(&store_path, &args.stream, None)
+        .with_context(|| format!("Nothing was recorded to {}", store_path.display()))?;
+    let original_store = Arc::new(FilesystemStore::new(&args.file_path)?);
+    let nominal_srate = read_group_attributes(&original_store, &args.stream)?
+        .get("stream_info")
+        .and_then(|info| info.get("nominal_srate"))
+        .and_then(|rate| rate.as_f64())
+        .unwrap_or(0.0);
+    // Irregular streams: allow one sample per millisecond of lag
+    let max_lag = (args.max_lag * if nominal_srate > 0.0 { nominal_srate } else { 1000.0 }).ceil() as usize;
+    let original = read_signal(&args.file_path, &args.stream, Some((recorded.len() + max_lag) as u64))?;
+
+    if !args.json {
+        println!("3/3 Cross-correlating {} recorded samples...", recorded.len());
+        println!();
+    }
+    let report = compare(&original, &recorded, max_lag);
+    let passed = report.as_ref().is_some_and(|report| {
+        report.identical == report.compared && report.max_jitter * 1000.0 <= args.max_jitter_ms
+    });
+
+    if args.json {
+        let result = match &report {
+            Some(report) => serde_json::json!({
+                "store": store_path.display().to_string(),
+                "stream": args.stream,
+                "passed": passed,
+                "found": true,
+                "channel": report.channel,
+                "lag_samples": report.lag,
+                "correlation": report.correlation,
+                "compared_samples": report.compared,
+                "identical_samples": report.identical,
+                "fidelity_percent": report.fidelity_percent(),
+                "max_abs_error": report.max_abs_error,
+                "offset_ms": report.timing.map(|fit| fit.intercept * 1000.0),
+                "drift_ppm": report.drift_ppm(),
+                "jitter_rms_ms": report.jitter_rms * 1000.0,
+                "max_jitter_ms": report.max_jitter * 1000.0,
+                "max_jitter_limit_ms": args.max_jitter_ms,
+            }),
+            None => serde_json::json!({
+                "store": store_path.display().to_string(),
+                "stream": args.stream,
+                "passed": false,
+                "found": false,
+            }),
+        };
+        println!("{}", serde_json::to_string_pretty(&result)?);
+    } else if let Some(report) = &report {
+        println!("LOCATION");
+        println!("\tFirst recorded sample:\toriginal sample {} (channel {}, r = {:.6})", report.lag, report.channel, report.correlation);
+        println!();
+        println!("FIDELITY");
+        println!("\tCompared samples:\t{}", report.compared);
+        println!("\tIdentical samples:\t{} ({:.3}%)", report.identical, report.fidelity_percent());
+        if report.identical < report.compared {
+            println!("\tLargest difference:\t{}", report.max_abs_error);
+        }
+        println!();
+        println!("TIMING (recorded - original)");
+        if let Some(fit) = report.timing {
+            println!("\tOffset:\t\t\t{:.3} ms", fit.intercept * 1000.0);
+        }
+        println!("\tDrift:\t\t\t{:+.2} ppm", report.drift_ppm());
+        println!("\tJitter (RMS):\t\t{:.3} ms", report.jitter_rms * 1000.0);
+        println!("\tJitter (max):\t\t{:.3} ms (limit {} ms)", report.max_jitter * 1000.0, args.max_jitter_ms);
+        println!();
+        println!("RESULT: {}", if passed { "PASS" } else { "FAIL" });
+    } else {
+        println!("RESULT: FAIL (recorded samples not found in the first {} s of the original)", args.max_lag);
+    }
+
+    if !passed {
+        usage::exit(1);
+    }
+    Ok(())
+}
@@ -0,0 +1,152 @@
+//! LSL Markers - Normalize marker labels of recordings
+//!
+//! This tool maps the marker labels of a store's marker streams to canonical labels
+//! from a TOML normalization table, so sessions recorded with different versions of
+//! the stimulus software (`stim_on`, `StimOn`, `1`) can be epoched with one config.
+//!
+//! # Features
+//!
+//! - Writes normalized labels to a `labels` array next to each stream's `events`
+//! - Keeps the recorded `events` untouched
+//! - Reports rewritten events and labels the table does not know
+//! - Lists the labels of all marker streams when run without a table
+//!
+//! # Usage
+//!
+//! ```bash
+//! # Show the marker labels and their counts
+//! lsl-markers experiment.zarr
+//!
+//! # Normalize every marker stream
+//! lsl-markers experiment.zarr --table markers.toml
+//!
+//! # Check a table against a recording without writing
+//! lsl-markers experiment.zarr --table markers.toml --stream Markers --dry-run
+//! ```
+//!
+//! # Output Format
+//!
+//! Normalization writes `/<stream>/labels` (one string per event) and the stream's
+//! `marker_normalization` attribute: the table, `rewritten` and `events` counts,
+//! the `unmatched` labels and `normalized_event_counts`. Running again with a new
+//! table replaces both.
+
+use anyhow::Result;
+use clap::Parser;
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+
+use lsl_recording_toolbox::export::{open_streams, ExportOptions};
+use lsl_recording_toolbox::markers::{normalize_events, normalize_stream, MarkerTable, NormalizationReport};
+use lsl_recording_toolbox::{config, usage};
+
+#[derive(Parser)]
+#[command(name = "lsl-markers")]
+#[command(about = "Normalize marker labels of Zarr recordings")]
+#[command(version)]
+struct Args {
+    /// Path to the Zarr store
+    file_path: PathBuf,
+
+    /// Normalization table (TOML) mapping label aliases to canonical labels
+    #[arg(long)]
+    table: Option<PathBuf>,
+
+    /// Marker stream to work on (can be repeated; all marker streams if omitted)
+    #[arg(long = "stream")]
+    streams: Vec<String>,
+
+    /// Show what would change without writing to the store
+    #[arg(long)]
+    dry_run: bool,
+
+    /// Print the reports as JSON
+    #[arg(long)]
+    json: bool,
+}
+
+fn main() -> Result<()> {
+    usage::track("lsl-markers", run)
+}
+
+fn run() -> Result<()> {
+    let args: Args = config::parse_args("lsl-markers")?;
+
+    if !args.json {
+        lsl_recording_toolbox::display_license_notice("lsl-markers");
+    }
+
+    let table = match args.table {
+        Some(ref path) => Some(MarkerTable::load(path)?),
+        None => None,
+    };
+    // Without a table every label is reported as it is
+    let listing = MarkerTable::new(BTreeMap::new(), false)?;
+
+    let options = ExportOptions {
+        streams: args.streams.clone(),
+        ..Default::default()
+    };
+    let streams = open_streams(&args.file_path, &options)?;
+    let mut report = serde_json::Map::new();
+    let mut found = false;
+    for stream in streams {
+        if !stream.is_marker() {
+            if !args.streams.is_empty() {
+                anyhow::bail!("Stream '{}' is not a marker stream", stream.name);
+            }
+            continue;
+        }
+        found = true;
+
+        let result = match table {
+            Some(ref table) if !args.dry_run => {
+                let source = args.table.as_ref().map(|p| p.display().to_string());
+                normalize_stream(&args.file_path, &stream.name, table, source.as_deref())?
+            }
+            Some(ref table) => normalize_events(&stream, table)?.1,
+            None => normalize_events(&stream, &listing)?.1,
+        };
+
+        if args.json {
+            report.insert(stream.name.clone(), result.to_json());
+            continue;
+        }
+        if table.is_some() {
+            print_normalization(&result);
+        } else {
+            println!("{}:\t{} events", result.stream, result.events);
+            for (label, count) in &result.counts {
+                println!("  {}:\t{}", label, count);
+            }
+        }
+    }
+
+    if !found {
+        anyhow::bail!("No marker streams in {}", args.file_path.display());
+    }
+    if args.json {
+        println!("{}", serde_json::to_string_pretty(&report)?);
+    } else if table.is_some() && args.dry_run {
+        println!();
+        println!("Dry run: store not modified");
+    }
+
+    Ok(())
+}
+
+fn print_normalization(result: &NormalizationReport) {
+    println!(
+        "{}:\t{} events, {} rewritten, {} unmatched",
+        result.stream,
+        result.events,
+        result.rewritten,
+        result.unmatched.values().sum::<u64>()
+    );
+    for (label, count) in &result.counts {
+        println!("  {}:\t{}", label, count);
+    }
+    for (label, count) in &result.unmatched {
+        println!("  not in table: {:?} ({} events)", label, count);
+    }
+}
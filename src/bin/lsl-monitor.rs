@@ -0,0 +1,413 @@
+//! LSL Monitor - Live terminal view of an incoming LSL stream
+//!
+//! This tool connects to one stream and plots selected channels as scrolling
+//! traces in the terminal, together with the measured sample rate and transport
+//! latency. Nothing is recorded, so it can be used to eyeball signals (electrode
+//! contact, saturation, dropouts) before committing to a recording.
+//!
+//! # Features
+//!
+//! - Resolves the stream by source ID or by name
+//! - One scrolling trace per selected channel (min/max per screen column, so spikes stay visible)
+//! - Effective sample rate next to the nominal rate
+//! - Latency of each sample (arrival time minus its clock-corrected timestamp)
+//! - Pause and zoom the time window without disconnecting
+//!
+//! # Usage
+//!
+//! ```bash
+//! # Watch the first four channels of a stream
+//! lsl-monitor --source-id "EMG_1234"
+//!
+//! # Watch two channels (by label or 0-based index) over a 10 s window
+//! lsl-monitor --name "EEG" --channels Cz,Pz --window 10
+//! ```
+//!
+//! Keys: `Space` pauses the traces, `+`/`-` widen or narrow the window,
+//! `q`/`Esc` quits.
+//!
+//! # Output Format
+//!
+//! ```text
+//! ┌ LSL Monitor ──────────────────────────────────────────────┐
+//! │TestEMG (EMG)  source_id EMG_1234  8 ch  2000 Hz  Float32  │
+//! └───────────────────────────────────────────────────────────┘
+//! ┌ ch0 ──────────────────────────────────────────────────────┐
+//! │ 1.02 ⡀⢀⠤⠒⠉⠑⠢⡀    ⢀⠔⠉⠉⠢⡀     ⡠⠊⠉⠒⢄    ⢀⠤⠊⠉⠢⡀               │
+//! ...
+//! Rate 1999.8 Hz (nominal 2000 Hz)  Latency 1.4 ms (mean 1.2, max 3.1)  Samples 48211
+//! ```
+
+use anyhow::{Context, Result};
+use clap::Parser;
+use crossterm::{
+    cursor::{Hide, Show},
+    event::KeyCode,
+    execute,
+    terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
+};
+use ratatui::{
+    backend::CrosstermBackend,
+    layout::{Constraint, Direction, Layout, Rect},
+    style::{Color, Modifier, Style},
+    symbols,
+    text::{Line, Span},
+    widgets::{Axis, Block, Borders, Chart, Dataset, GraphType, Paragraph},
+    Frame, Terminal,
+};
+use std::io;
+use std::panic;
+use std::time::Instant;
+
+use lsl_recording_toolbox::live_view::{
+    LatencyWindow, SampleWindow, DEFAULT_PLOT_CHANNELS, DEFAULT_WINDOW_SECS,
+};
+use lsl_recording_toolbox::lsl::stream_channel_labels;
+use lsl_recording_toolbox::monitor::select_monitor_channels;
+use lsl_recording_toolbox::tui::events::{is_ctrl_c, is_esc, is_space, Event, EventHandler};
+use lsl_recording_toolbox::{config, usage};
+
+/// Screen refresh interval in milliseconds
+const REFRESH_MS: u64 = 50;
+
+/// Seconds of arrivals the latency statistics cover
+const LATENCY_SPAN: f64 = 2.0;
+
+/// Seconds without samples before the stream is shown as stalled
+const STALL_SECS: f64 = 1.0;
+
+/// Narrowest and widest time window in seconds (`-`/`+`)
+const WINDOW_LIMITS: (f64, f64) = (0.5, 120.0);
+
+#[derive(Parser)]
+#[command(name = "lsl-monitor")]
+#[command(about = "Live terminal view of an LSL stream's signals, rate and latency")]
+#[command(version)]
+struct Args {
+    /// Source ID of the stream to watch
+    #[arg(long, required_unless_present = "name", conflicts_with = "name")]
+    source_id: Option<String>,
+
+    /// Name of the stream to watch (first match)
+    #[arg(long)]
+    name: Option<String>,
+
+    /// Channels to plot, by label or 0-based index (comma-separated; default: the first four)
+    #[arg(short, long, value_delimiter = ',')]
+    channels: Vec<String>,
+
+    /// Seconds of signal shown
+    #[arg(short, long, default_value_t = DEFAULT_WINDOW_SECS)]
+    window: f64,
+
+    /// Seconds to wait for the stream to appear
+    #[arg(long, default_value = "5.0")]
+    resolve_timeout: f64,
+}
+
+/// Static description of the watched stream for the header
+struct StreamHeader {
+    text: String,
+    nominal_srate: f64,
+    labels: Vec<String>,
+}
+
+/// Everything the screen shows
+struct View {
+    header: StreamHeader,
+    live: SampleWindow,
+    /// Copy of the traces taken when paused
+    frozen: Option<SampleWindow>,
+    latency: LatencyWindow,
+    last_arrival: Instant,
+}
+
+fn main() -> Result<()> {
+    usage::track("lsl-monitor", run)
+}
+
+fn run() -> Result<()> {
+    let args: Args = config::parse_args("lsl-monitor")?;
+
+    let (property, value) = match (&args.source_id, &args.name) {
+        (Some(id), _) => ("source_id", id.as_str()),
+        (None, Some(name)) => ("name", name.as_str()),
+        (None, None) => unreachable!("clap requires --source-id or --name"),
+    };
+    println!("Resolving stream {}={}...", property, value);
+    let mut info = lsl::resolve_byprop(property, value, 1, args.resolve_timeout)
+        .map_err(|e| anyhow::anyhow!("LSL error: {}", e))?
+        .into_iter()
+        .next()
+        .ok_or_else(|| {
+            anyhow::anyhow!("No stream with {} '{}' found (waited {}s)", property, value, args.resolve_timeout)
+        })?;
+    if matches!(info.channel_format(), lsl::ChannelFormat::String) {
+        anyhow::bail!("lsl-monitor needs a numeric stream ('{}' carries strings)", info.stream_name());
+    }
+
+    let labels = stream_channel_labels(&mut info);
+    let channels = if args.channels.is_empty() {
+        (0..labels.len().min(DEFAULT_PLOT_CHANNELS)).collect()
+    } else {
+        select_monitor_channels(&labels, &args.channels)?
+    };
+
+    let inlet = lsl::StreamInlet::new(&info, 360, 0, true).map_err(|e| anyhow::anyhow!("LSL error: {}", e))?;
+    // Timestamps in the local clock, so arrival minus timestamp is the transport latency
+    inlet
+        .set_postprocessing(&[lsl::ProcessingOption::ClockSync])
+        .map_err(|e| anyhow::anyhow!("LSL error: {}", e))?;
+
+    let header = StreamHeader {
+        text: format!(
+            "{} ({})  source_id {}  {} ch  {}  {:?}",
+            info.stream_name(),
+            info.stream_type(),
+            info.source_id(),
+            info.channel_count(),
+            format_rate(info.nominal_srate()),
+            info.channel_format()
+        ),
+        nominal_srate: info.nominal_srate(),
+        labels: channels.iter().map(|&c| labels[c].clone()).collect(),
+    };
+    let mut view = View {
+        header,
+        live: SampleWindow::new(channels.len(), args.window.clamp(WINDOW_LIMITS.0, WINDOW_LIMITS.1)),
+        frozen: None,
+        latency: LatencyWindow::new(LATENCY_SPAN),
+        last_arrival: Instant::now(),
+    };
+
+    // Setup panic hook to restore terminal on panic
+    let original_hook = panic::take_hook();
+    panic::set_hook(Box::new(move |panic_info| {
+        cleanup_terminal();
+        original_hook(panic_info);
+    }));
+
+    enable_raw_mode()?;
+    let mut stdout = io::stdout();
+    execute!(stdout, EnterAlternateScreen, Hide)?;
+    let mut terminal = Terminal::new(CrosstermBackend::new(stdout))?;
+
+    let result = monitor_loop(&mut terminal, &inlet, &channels, &mut view);
+
+    cleanup_terminal();
+    result
+}
+
+fn cleanup_terminal() {
+    let _ = disable_raw_mode();
+    let _ = execute!(io::stdout(), LeaveAlternateScreen, Show);
+}
+
+fn monitor_loop(
+    terminal: &mut Terminal<CrosstermBackend<io::Stdout>>,
+    inlet: &lsl::StreamInlet,
+    channels: &[usize],
+    view: &mut View,
+) -> Result<()> {
+    let events = EventHandler::new(REFRESH_MS);
+    let mut sample = Vec::<f64>::new();
+    let mut values = vec![0.0; channels.len()];
+
+    loop {
+        // Drain everything that arrived since the last frame
+        loop {
+            let timestamp = inlet
+                .pull_sample_buf(&mut sample, 0.0)
+                .map_err(|e| anyhow::anyhow!("LSL error: {}", e))?;
+            if timestamp == 0.0 {
+                break;
+            }
+            view.latency.push(lsl::local_clock(), timestamp);
+            view.last_arrival = Instant::now();
+            for (value, &channel) in values.iter_mut().zip(channels) {
+                *value = sample.get(channel).copied().unwrap_or(f64::NAN);
+            }
+            view.live.push(timestamp, &values);
+        }
+
+        terminal.draw(|frame| render(frame, view)).context("Failed to draw")?;
+
+        if let Event::Key(key) = events.next()? {
+            if is_esc(&key) || is_ctrl_c(&key) || key.code == KeyCode::Char('q') {
+                return Ok(());
+            }
+            if is_space(&key) {
+                view.frozen = match view.frozen {
+                    Some(_) => None,
+                    None => Some(view.live.clone()),
+                };
+            }
+            let zoom = match key.code {
+                KeyCode::Char('+') => Some(2.0),
+                KeyCode::Char('-') => Some(0.5),
+                _ => None,
+            };
+            if let Some(factor) = zoom {
+                let span = (view.live.span() * factor).clamp(WINDOW_LIMITS.0, WINDOW_LIMITS.1);
+                view.live.set_span(span);
+                if let Some(ref mut frozen) = view.frozen {
+                    frozen.set_span(span);
+                }
+            }
+        }
+    }
+}
+
+fn render(frame: &mut Frame, view: &View) {
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Length(3), // Stream header
+            Constraint::Min(3),    // Traces
+            Constraint::Length(1), // Statistics
+            Constraint::Length(1), // Help text
+        ])
+        .split(frame.area());
+
+    let header = Paragraph::new(view.header.text.as_str())
+        .style(Style::default().fg(Color::White))
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title(" LSL Monitor ")
+                .title_style(Style::default().fg(Color::Cyan).add_modifier(Modifier::BOLD))
+                .border_style(Style::default().fg(Color::Cyan)),
+        );
+    frame.render_widget(header, chunks[0]);
+
+    let window = view.frozen.as_ref().unwrap_or(&view.live);
+    let count = view.header.labels.len().max(1) as u32;
+    let rows = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints((0..count).map(|_| Constraint::Ratio(1, count)))
+        .split(chunks[1]);
+    for (channel, (&area, label)) in rows.iter().zip(&view.header.labels).enumerate() {
+        let last = channel + 1 == view.header.labels.len();
+        render_trace(frame, area, window, channel, label, last);
+    }
+
+    frame.render_widget(Paragraph::new(stats_line(view)), chunks[2]);
+
+    let mut help = vec![Span::raw(" ")];
+    help.extend(help_item("Space", if view.frozen.is_some() { "Resume " } else { "Pause " }));
+    help.extend(help_item("+/-", "Window "));
+    help.extend(help_item("q", "Quit"));
+    frame.render_widget(Paragraph::new(Line::from(help)), chunks[3]);
+}
+
+/// Plot one channel; only the bottom trace carries time labels
+fn render_trace(frame: &mut Frame, area: Rect, window: &SampleWindow, channel: usize, label: &str, last: bool) {
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .title(format!(" {} ", label))
+        .border_style(Style::default().fg(Color::DarkGray));
+
+    let Some((min, max)) = window.value_range(channel) else {
+        let waiting = Paragraph::new("waiting for samples...")
+            .style(Style::default().fg(Color::DarkGray))
+            .block(block);
+        frame.render_widget(waiting, area);
+        return;
+    };
+    // Keep flat signals in the middle instead of on the border
+    let pad = if max > min { (max - min) * 0.05 } else { min.abs().max(1.0) * 0.1 };
+    let (low, high) = (min - pad, max + pad);
+
+    // Braille markers have two dots per cell horizontally
+    let points = window.plot_points(channel, area.width as usize * 2);
+    let dataset = Dataset::default()
+        .marker(symbols::Marker::Braille)
+        .graph_type(GraphType::Line)
+        .style(Style::default().fg(Color::Cyan))
+        .data(&points);
+
+    let span = window.span();
+    let time_labels = if last {
+        vec![Span::raw(format!("-{:.1} s", span)), Span::raw("now")]
+    } else {
+        Vec::new()
+    };
+    let chart = Chart::new(vec![dataset])
+        .block(block)
+        .x_axis(Axis::default().bounds([-span, 0.0]).labels(time_labels).style(Style::default().fg(Color::DarkGray)))
+        .y_axis(
+            Axis::default()
+                .bounds([low, high])
+                .labels(vec![Span::raw(format_value(min)), Span::raw(format_value(max))])
+                .style(Style::default().fg(Color::DarkGray)),
+        );
+    frame.render_widget(chart, area);
+}
+
+/// Rate, latency and sample count of the live stream (also while paused)
+fn stats_line(view: &View) -> Line<'static> {
+    let label = Style::default().fg(Color::DarkGray);
+    let value = Style::default().fg(Color::White);
+    let mut spans = vec![Span::styled(" Rate ", label)];
+    let rate = view.live.effective_rate().map_or("-".to_string(), format_rate);
+    spans.push(Span::styled(rate, value));
+    if view.header.nominal_srate > 0.0 {
+        spans.push(Span::styled(format!(" (nominal {})", format_rate(view.header.nominal_srate)), label));
+    }
+    spans.push(Span::styled("  Latency ", label));
+    match view.latency.stats() {
+        Some(stats) => {
+            spans.push(Span::styled(format!("{:.1} ms", stats.last * 1000.0), value));
+            spans.push(Span::styled(
+                format!(" (mean {:.1}, max {:.1})", stats.mean * 1000.0, stats.max * 1000.0),
+                label,
+            ));
+        }
+        None => spans.push(Span::styled("-", value)),
+    }
+    spans.push(Span::styled("  Samples ", label));
+    spans.push(Span::styled(view.live.total().to_string(), value));
+
+    let silent = view.last_arrival.elapsed().as_secs_f64();
+    if silent > STALL_SECS {
+        spans.push(Span::styled(
+            format!("  NO DATA for {:.1} s", silent),
+            Style::default().fg(Color::Red).add_modifier(Modifier::BOLD),
+        ));
+    }
+    if view.frozen.is_some() {
+        spans.push(Span::styled("  PAUSED", Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD)));
+    }
+    Line::from(spans)
+}
+
+/// Help item in the TUI's "[Key] Action" style
+fn help_item(key: &str, action: &str) -> Vec<Span<'static>> {
+    vec![
+        Span::styled("[", Style::default().fg(Color::DarkGray)),
+        Span::styled(key.to_string(), Style::default().fg(Color::Cyan)),
+        Span::styled(format!("] {} ", action), Style::default().fg(Color::DarkGray)),
+    ]
+}
+
+fn format_rate(rate: f64) -> String {
+    if rate <= 0.0 {
+        "irregular".to_string()
+    } else if rate.fract() == 0.0 {
+        format!("{:.0} Hz", rate)
+    } else {
+        format!("{:.1} Hz", rate)
+    }
+}
+
+/// Axis label with a precision that suits the value's magnitude
+fn format_value(value: f64) -> String {
+    let magnitude = value.abs();
+    if magnitude != 0.0 && !(1e-3..1e6).contains(&magnitude) {
+        format!("{:.2e}", value)
+    } else if magnitude >= 100.0 {
+        format!("{:.0}", value)
+    } else {
+        format!("{:.3}", value)
+    }
+}
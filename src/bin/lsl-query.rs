@@ -0,0 +1,148 @@
+//! LSL Query - Search a directory of Zarr recordings by metadata
+//!
+//! This tool scans a data root for `.zarr` stores, summarizes each session from its
+//! metadata (no sample data is read) and lists the sessions matching an expression.
+//!
+//! # Features
+//!
+//! - Recursive discovery of `.zarr` stores below a root directory
+//! - Expressions with `==`, `!=`, `<`, `<=`, `>`, `>=`, `&&`, `||`, `!` and parentheses
+//! - Wildcards (`*`, `?`) in string equality
+//! - `has_stream("EEG")` and `has_type("Markers")` functions
+//! - Selectable output fields, table or JSON output
+//!
+//! # Usage
+//!
+//! ```bash
+//! # List every session below the current directory
+//! lsl-query
+//!
+//! # Sessions of subjects P00x longer than 10 minutes that contain EEG
+//! lsl-query 'subject == "P00*" && duration > 600 && has_stream("EEG")' --root /data
+//!
+//! # Choose output fields
+//! lsl-query 'streams >= 3' --fields name,subject,duration,stream_names
+//!
+//! # JSON for scripting
+//! lsl-query 'has_type("EMG")' --json
+//!
+//! # Use a SQLite index built by lsl-index instead of scanning
+//! lsl-query 'subject == "P001"' --index /data/lsl-index.sqlite
+//! ```
+//!
+//! # Fields
+//!
+//! `path`, `name`, `subject`, `session_id`, `notes`, `recorded_at`,
+//! `recorder_version`, `duration` (seconds), `streams` (count), `stream_names`,
+//! `samples` (total across streams)
+
+use anyhow::Result;
+use clap::Parser;
+use std::path::PathBuf;
+
+use lsl_recording_toolbox::index::{load_sessions, open_index};
+use lsl_recording_toolbox::query::{field_value, Query, FIELDS};
+use lsl_recording_toolbox::session::{find_stores, summarize_store};
+use lsl_recording_toolbox::{config, usage};
+
+#[derive(Parser)]
+#[command(name = "lsl-query")]
+#[command(about = "Search Zarr recordings by metadata expressions")]
+#[command(version)]
+struct Args {
+    /// Filter expression (matches all sessions if omitted)
+    #[arg(default_value = "")]
+    expression: String,
+
+    /// Directory to search for .zarr stores (or a single store)
+    #[arg(short, long, default_value = ".")]
+    root: PathBuf,
+
+    /// Comma-separated fields to display
+    #[arg(short, long, value_delimiter = ',', default_value = "path,subject,session_id,duration,stream_names")]
+    fields: Vec<String>,
+
+    /// Print matching sessions as JSON
+    #[arg(long)]
+    json: bool,
+
+    /// Read sessions from an lsl-index database instead of scanning --root
+    #[arg(long)]
+    index: Option<PathBuf>,
+}
+
+fn main() -> Result<()> {
+    usage::track("lsl-query", run)
+}
+
+fn run() -> Result<()> {
+    let args: Args = config::parse_args("lsl-query")?;
+
+    for field in &args.fields {
+        if !FIELDS.contains(&field.as_str()) {
+            anyhow::bail!("Unknown field '{}' (available: {})", field, FIELDS.join(", "));
+        }
+    }
+
+    let query = Query::parse(&args.expression)?;
+
+    let sessions = if let Some(ref db_path) = args.index {
+        if !db_path.exists() {
+            anyhow::bail!("Index database not found: {}", db_path.display());
+        }
+        load_sessions(&open_index(db_path)?)?
+    } else {
+        let mut sessions = Vec::new();
+        for store_path in find_stores(&args.root)? {
+            match summarize_store(&store_path) {
+                Ok(session) => sessions.push(session),
+                Err(e) => eprintln!("Warning: Skipping {}: {}", store_path.display(), e),
+            }
+        }
+        sessions
+    };
+    let total = sessions.len();
+
+    let matches: Vec<_> = sessions.into_iter().filter(|s| query.matches(s)).collect();
+
+    if args.json {
+        println!("{}", serde_json::to_string_pretty(&matches)?);
+        return Ok(());
+    }
+
+    let rows: Vec<Vec<String>> = matches
+        .iter()
+        .map(|session| {
+            args.fields
+                .iter()
+                .map(|field| field_value(session, field).to_string())
+                .collect()
+        })
+        .collect();
+
+    let mut widths: Vec<usize> = args.fields.iter().map(|f| f.len()).collect();
+    for row in &rows {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(cell.chars().count());
+        }
+    }
+
+    let print_row = |cells: &[String]| {
+        let line: Vec<String> = cells
+            .iter()
+            .zip(&widths)
+            .map(|(cell, width)| format!("{:<width$}", cell, width = width))
+            .collect();
+        println!("{}", line.join("  ").trim_end());
+    };
+
+    let header: Vec<String> = args.fields.iter().map(|f| f.to_uppercase()).collect();
+    print_row(&header);
+    for row in &rows {
+        print_row(row);
+    }
+    println!();
+    println!("{} of {} session(s) matched", matches.len(), total);
+
+    Ok(())
+}
@@ -0,0 +1,109 @@
+//! LSL Repair - Recover Zarr recordings interrupted by a crash or power loss
+//!
+//! This tool scans a Zarr store written by lsl-recorder for streams that were not
+//! closed cleanly, truncates them to the last valid sample and restores the
+//! finalization metadata, so the store can be read by the other tools again.
+//!
+//! # Features
+//!
+//! - Recovers chunks written to disk whose array metadata was never updated
+//! - Drops trailing fill-value samples (timestamps of `0.0`)
+//! - Truncates data and time arrays to a common length
+//! - Restores `first_timestamp`/`last_timestamp` and marks streams `recovered: true`
+//! - Applies recovery markers (`<store>.<stream>.recovery.json`) left by a recorder
+//!   whose final flush or finalization failed: connection history, event counts and
+//!   end time are restored, then the marker is deleted
+//! - Dry-run mode to preview changes
+//!
+//! # Usage
+//!
+//! ```bash
+//! # Preview what would change
+//! lsl-repair experiment.zarr --dry-run
+//!
+//! # Repair in place
+//! lsl-repair experiment.zarr
+//!
+//! # With a marker the recorder had to write to another directory
+//! lsl-repair experiment.zarr --marker /tmp/lsl-recovery/experiment.zarr.EEG.recovery.json
+//! ```
+//!
+//! # Notes
+//!
+//! Streams with an `aligned_time` array are flagged when its length no longer
+//! matches; re-run lsl-sync after repairing.
+
+use anyhow::Result;
+use clap::Parser;
+use std::path::PathBuf;
+
+use lsl_recording_toolbox::zarr::repair::repair_store;
+use lsl_recording_toolbox::{config, usage};
+
+#[derive(Parser)]
+#[command(name = "lsl-repair")]
+#[command(about = "Recover Zarr recordings interrupted by a crash or power loss")]
+#[command(version)]
+struct Args {
+    /// Path to the Zarr store to repair
+    file_path: PathBuf,
+
+    /// Only report what would be changed
+    #[arg(long)]
+    dry_run: bool,
+
+    /// Recovery marker kept outside the usual places (next to the store, current
+    /// directory, temp directory); can be repeated
+    #[arg(long, value_name = "PATH")]
+    marker: Vec<PathBuf>,
+}
+
+fn main() -> Result<()> {
+    usage::track("lsl-repair", run)
+}
+
+fn run() -> Result<()> {
+    let args: Args = config::parse_args("lsl-repair")?;
+
+    lsl_recording_toolbox::display_license_notice("lsl-repair");
+
+    println!("Store:\t{}", args.file_path.display());
+    if args.dry_run {
+        println!("Mode:\tdry run (no changes written)");
+    }
+    println!();
+
+    let reports = repair_store(&args.file_path, &args.marker, args.dry_run)?;
+    let mut repaired = 0;
+
+    for report in &reports {
+        if report.needs_repair() {
+            repaired += 1;
+            println!(
+                "{}:\t{} -> {} samples{}",
+                report.stream,
+                report.samples_before,
+                report.samples_after,
+                if report.was_unfinalized { " (was not finalized)" } else { "" }
+            );
+        } else {
+            println!("{}:\tOK ({} samples)", report.stream, report.samples_after);
+        }
+        if let Some(ref marker) = report.marker {
+            println!(
+                "\tRecovery marker: {} ({} samples recorded)",
+                marker.display(),
+                report.samples_recorded.unwrap_or(0)
+            );
+        }
+        if report.stale_alignment {
+            println!("\tWarning: aligned_time length differs, re-run lsl-sync");
+        }
+    }
+
+    println!();
+    let verb = if args.dry_run { "would be repaired" } else { "repaired" };
+    println!("{} of {} stream(s) {}", repaired, reports.len(), verb);
+
+    Ok(())
+}
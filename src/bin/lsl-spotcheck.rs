@@ -0,0 +1,321 @@
+//! LSL Spotcheck - Pre-session go/no-go check of the recording setup
+//!
+//! This tool records a short test session from all configured streams with
+//! lsl-multi-recorder, validates it with lsl-validate, renders quicklook plots of
+//! every stream and prints a go/no-go verdict. Run it right before the subject
+//! arrives to catch setup problems (a missing stream, a device at the wrong rate,
+//! dropouts, a dead electrode) while they can still be fixed.
+//!
+//! # Features
+//!
+//! - Records `--duration` seconds (default 30) with the normal recording pipeline
+//! - Validates sample rates, gaps, synchronization and clock drift
+//! - Writes one SVG quicklook per numeric stream (`<output>_quicklook/<stream>.svg`)
+//! - Exit code `0` for GO, `1` for NO-GO
+//!
+//! # Usage
+//!
+//! ```bash
+//! # Check the streams of the session
+//! lsl-spotcheck --source-ids "EMG_1234" "EEG_5678" --stream-names "EMG" "EEG"
+//!
+//! # Check every stream on the network for 10 s
+//! lsl-spotcheck --record-all --duration 10
+//!
+//! # Stricter limits
+//! lsl-spotcheck --record-all --max-rate-error-percent 1 --max-lost-percent 0.1 --threshold-ms 50
+//! ```
+//!
+//! # Verdict
+//!
+//! NO-GO when a configured stream is missing or recorded no samples, a regular
+//! stream's measured rate deviates from its nominal rate by more than
+//! `--max-rate-error-percent`, more than `--max-lost-percent` of a stream is lost
+//! to gaps, or the streams are not synchronized within `--threshold-ms`. Clock
+//! drift over `--drift-budget-ppm` is reported as a warning.
+//!
+//! The test store (`spotcheck_<date>_<time>.zarr` by default) is kept for a closer
+//! look with lsl-inspect.
+
+use anyhow::{Context, Result};
+use clap::Parser;
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::sync::mpsc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use lsl_recording_toolbox::drift::DEFAULT_DRIFT_BUDGET_PPM;
+use lsl_recording_toolbox::gaps::DEFAULT_GAP_FACTOR;
+use lsl_recording_toolbox::spotcheck::{evaluate, write_quicklooks, SpotcheckCriteria};
+use lsl_recording_toolbox::{config, usage};
+use lsl_recording_toolbox::zarr::expand_output_template;
+
+#[derive(Parser)]
+#[command(name = "lsl-spotcheck")]
+#[command(about = "Record a short test session and print a go/no-go verdict for the setup")]
+#[command(version)]
+struct Args {
+    /// LSL stream source IDs to check (space-separated)
+    #[arg(long, required_unless_present = "record_all", num_args = 1..)]
+    source_ids: Vec<String>,
+
+    /// Check every stream currently visible on the network
+    #[arg(long, conflicts_with_all = ["source_ids", "stream_names"])]
+    record_all: bool,
+
+    /// Custom stream names (must match source-ids count if provided)
+    #[arg(long, num_args = 1..)]
+    stream_names: Option<Vec<String>>,
+
+    /// Seconds to record
+    #[arg(long, default_value = "30")]
+    duration: u64,
+
+    /// Test store base path (without .zarr extension); supports {date}, {time}
+    #[arg(short, long, default_value = "spotcheck_{date}_{time}")]
+    output: PathBuf,
+
+    /// Directory for the quicklook plots (defaults to <output>_quicklook)
+    #[arg(long)]
+    plot_dir: Option<PathBuf>,
+
+    /// Channels plotted per stream
+    #[arg(long, default_value = "8")]
+    plot_channels: usize,
+
+    /// Largest start/end/drift difference (ms) still considered synchronized
+    #[arg(long, default_value = "200.0")]
+    threshold_ms: f64,
+
+    /// Report intervals longer than this many nominal sample periods as gaps
+    #[arg(long, default_value_t = DEFAULT_GAP_FACTOR)]
+    gap_factor: f64,
+
+    /// Warn when the clock drift between two streams exceeds this many ppm
+    #[arg(long, default_value_t = DEFAULT_DRIFT_BUDGET_PPM)]
+    drift_budget_ppm: f64,
+
+    /// Largest deviation of a stream's measured from its nominal rate (percent)
+    #[arg(long, default_value = "5.0")]
+    max_rate_error_percent: f64,
+
+    /// Largest share of a stream's duration that may be lost to gaps (percent)
+    #[arg(long, default_value = "1.0")]
+    max_lost_percent: f64,
+
+    /// Timeout for stream resolution in seconds
+    #[arg(long, default_value = "5.0")]
+    resolve_timeout: f64,
+
+    /// Directory holding lsl-multi-recorder, lsl-recorder and lsl-validate
+    /// (defaults to the directory of this executable)
+    #[arg(long)]
+    bin_dir: Option<PathBuf>,
+
+    /// Show the output of the recorders
+    #[arg(short, long)]
+    verbose: bool,
+}
+
+/// Path of a toolbox executable in `bin_dir`, else next to this executable, else from PATH
+fn tool_path(bin_dir: Option<&Path>, name: &str) -> PathBuf {
+    let name = if cfg!(windows) { format!("{}.exe", name) } else { name.to_string() };
+    let dir = bin_dir
+        .map(Path::to_path_buf)
+        .or_else(|| std::env::current_exe().ok().and_then(|exe| exe.parent().map(Path::to_path_buf)));
+    dir.map(|dir| dir.join(&name))
+        .filter(|path| path.exists())
+        .unwrap_or_else(|| PathBuf::from(name))
+}
+
+/// Record the test session with lsl-multi-recorder
+fn record(args: &Args, output: &Path) -> Result<()> {
+    let mut cmd_args = vec![
+        "--output".to_string(),
+        output.display().to_string(),
+        "--duration".to_string(),
+        args.duration.to_string(),
+        "--resolve-timeout".to_string(),
+        args.resolve_timeout.to_string(),
+        "--recorder-path".to_string(),
+        tool_path(args.bin_dir.as_deref(), "lsl-recorder").display().to_string(),
+        "--quiet".to_string(),
+    ];
+    if args.record_all {
+        cmd_args.push("--record-all".to_string());
+    } else {
+        cmd_args.push("--source-ids".to_string());
+        cmd_args.extend(args.source_ids.iter().cloned());
+        if let Some(ref names) = args.stream_names {
+            cmd_args.push("--stream-names".to_string());
+            cmd_args.extend(names.iter().cloned());
+        }
+    }
+
+    let mut child = Command::new(tool_path(args.bin_dir.as_deref(), "lsl-multi-recorder"))
+        .args(&cmd_args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::inherit())
+        .spawn()
+        .context("Failed to start lsl-multi-recorder")?;
+    let mut stdin = child.stdin.take().context("Failed to get stdin of lsl-multi-recorder")?;
+    let stdout = child.stdout.take().context("Failed to get stdout of lsl-multi-recorder")?;
+
+    let (line_sender, lines) = mpsc::channel();
+    thread::spawn(move || {
+        for line in BufReader::new(stdout).lines().map_while(Result::ok) {
+            if line_sender.send(line).is_err() {
+                break;
+            }
+        }
+    });
+
+    writeln!(stdin, "START")?;
+    stdin.flush()?;
+
+    // The recording timer starts once every regular stream delivers samples
+    let deadline = Instant::now() + Duration::from_secs_f64(args.resolve_timeout + args.duration as f64 + 30.0);
+    let mut stopped_at: Option<Instant> = None;
+    loop {
+        match lines.recv_timeout(Duration::from_millis(100)) {
+            Ok(line) => {
+                if args.verbose {
+                    println!("\t{}", line);
+                }
+                if line.contains("STATUS STOPPED_BY_TIMER") {
+                    stopped_at.get_or_insert_with(Instant::now);
+                }
+            }
+            Err(mpsc::RecvTimeoutError::Timeout) => {}
+            // lsl-multi-recorder exited on its own (e.g. a stream could not be resolved)
+            Err(mpsc::RecvTimeoutError::Disconnected) => break,
+        }
+
+        // Give the other recorders a moment to stop and flush as well
+        if stopped_at.is_some_and(|at| at.elapsed() >= Duration::from_secs(2)) {
+            writeln!(stdin, "QUIT").ok();
+            stdin.flush().ok();
+            break;
+        }
+        if Instant::now() >= deadline {
+            eprintln!("Warning: recording did not stop on its own, stopping it");
+            writeln!(stdin, "STOP").and_then(|_| writeln!(stdin, "QUIT")).ok();
+            stdin.flush().ok();
+            break;
+        }
+    }
+
+    drop(stdin);
+    let status = child.wait().context("Failed to wait for lsl-multi-recorder")?;
+    if !status.success() {
+        anyhow::bail!("lsl-multi-recorder failed ({})", status);
+    }
+    Ok(())
+}
+
+/// Run lsl-validate on the test store and parse its JSON report
+fn validate(args: &Args, store_path: &Path) -> Result<serde_json::Value> {
+    let output = Command::new(tool_path(args.bin_dir.as_deref(), "lsl-validate"))
+        .arg(store_path)
+        .args(["--format", "json"])
+        .args(["--threshold-ms", &args.threshold_ms.to_string()])
+        .args(["--gap-factor", &args.gap_factor.to_string()])
+        .args(["--drift-budget-ppm", &args.drift_budget_ppm.to_string()])
+        .output()
+        .context("Failed to run lsl-validate")?;
+    // Exit codes 1 and 2 still come with a report
+    serde_json::from_slice(&output.stdout).with_context(|| {
+        format!(
+            "lsl-validate did not produce a report ({}): {}",
+            output.status,
+            String::from_utf8_lossy(&output.stderr).trim()
+        )
+    })
+}
+
+fn main() -> Result<()> {
+    usage::track("lsl-spotcheck", run)
+}
+
+fn run() -> Result<()> {
+    let args: Args = config::parse_args("lsl-spotcheck")?;
+
+    lsl_recording_toolbox::display_license_notice("lsl-spotcheck");
+
+    if let Some(ref names) = args.stream_names
+        && names.len() != args.source_ids.len()
+    {
+        anyhow::bail!(
+            "Number of stream names ({}) must match number of source IDs ({})",
+            names.len(),
+            args.source_ids.len()
+        );
+    }
+
+    let output = expand_output_template(&args.output, None, None);
+    let store_path = PathBuf::from(format!("{}.zarr", output.display()));
+    if store_path.exists() {
+        anyhow::bail!("{} already exists; choose another --output", store_path.display());
+    }
+    let plot_dir = args
+        .plot_dir
+        .clone()
+        .unwrap_or_else(|| PathBuf::from(format!("{}_quicklook", output.display())));
+
+    println!("1/3 Recording {} s to {}...", args.duration, store_path.display());
+    record(&args, &output)?;
+    if !store_path.exists() {
+        anyhow::bail!("Nothing was recorded to {}", store_path.display());
+    }
+
+    println!("2/3 Validating...");
+    let report = validate(&args, &store_path)?;
+
+    println!("3/3 Rendering quicklook plots...");
+    let plots = write_quicklooks(&store_path, &plot_dir, args.plot_channels)?;
+    for plot in &plots {
+        println!("\t{}", plot.display());
+    }
+    println!();
+
+    println!("STREAMS");
+    for stream in report["streams"].as_array().into_iter().flatten() {
+        println!(
+            "\t{}:\t{} samples, {:.1} Hz (nominal {} Hz), {} gap(s)",
+            stream["name"].as_str().unwrap_or("<unnamed>"),
+            stream["samples"],
+            stream["actual_rate"].as_f64().unwrap_or(0.0),
+            stream["nominal_rate"],
+            stream["gaps"]["count"]
+        );
+    }
+    println!();
+
+    let expected: Vec<String> = if args.record_all {
+        Vec::new()
+    } else {
+        args.stream_names.clone().unwrap_or_else(|| args.source_ids.clone())
+    };
+    let criteria = SpotcheckCriteria {
+        max_rate_error_percent: args.max_rate_error_percent,
+        max_lost_percent: args.max_lost_percent,
+    };
+    let verdict = evaluate(&report, &expected, &criteria);
+
+    for problem in &verdict.problems {
+        println!("\tPROBLEM: {}", problem);
+    }
+    for warning in &verdict.warnings {
+        println!("\tWARNING: {}", warning);
+    }
+    if verdict.go() {
+        println!("VERDICT: GO");
+        Ok(())
+    } else {
+        println!("VERDICT: NO-GO ({} problem(s))", verdict.problems.len());
+        usage::exit(1);
+    }
+}
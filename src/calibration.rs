@@ -0,0 +1,153 @@
+//! End-to-end latency calibration with a known test pattern
+//!
+//! A calibration source emits rectangular pulses on its data channels and, for every
+//! pulse, a `pulse` marker on a companion marker stream (source ID suffixed with
+//! [`MARKER_SOURCE_SUFFIX`]). The marker carries the true onset time as its timestamp.
+//! `lsl-dummy-stream --calibration-interval` implements this protocol; device adapters
+//! can calibrate the same way by publishing such a marker stream.
+//!
+//! Comparing the true onsets with what the recorder sees gives:
+//!
+//! - **timestamp bias**: timestamp assigned to the pulse onset sample minus the true onset
+//! - **latency**: time the onset sample became available to the inlet minus the true onset
+//!
+//! The result is stored as the `calibration` attribute of a stream group (or the root
+//! group), where `timestamp_correction` is the offset to add to recorded timestamps.
+
+use anyhow::Result;
+use serde_json::json;
+use std::path::Path;
+use std::sync::Arc;
+use zarrs::filesystem::FilesystemStore;
+
+/// Source ID suffix of the marker stream accompanying a calibration source
+pub const MARKER_SOURCE_SUFFIX: &str = "_calibration";
+
+/// Width of each calibration pulse in seconds (at least one sample)
+pub const PULSE_WIDTH_SECS: f64 = 0.01;
+
+/// Summary statistics of a set of time offsets (seconds)
+#[derive(Debug, Clone, PartialEq)]
+pub struct OffsetStats {
+    pub mean: f64,
+    pub std: f64,
+    pub min: f64,
+    pub max: f64,
+}
+
+impl OffsetStats {
+    /// Statistics of `offsets`, or `None` if there are none
+    pub fn from_offsets(offsets: &[f64]) -> Option<Self> {
+        if offsets.is_empty() {
+            return None;
+        }
+        let n = offsets.len() as f64;
+        let mean = offsets.iter().sum::<f64>() / n;
+        let variance = offsets.iter().map(|x| (x - mean).powi(2)).sum::<f64>() / n;
+        Some(Self {
+            mean,
+            std: variance.sqrt(),
+            min: offsets.iter().copied().fold(f64::INFINITY, f64::min),
+            max: offsets.iter().copied().fold(f64::NEG_INFINITY, f64::max),
+        })
+    }
+
+    fn to_json(&self) -> serde_json::Value {
+        json!({ "mean": self.mean, "std": self.std, "min": self.min, "max": self.max })
+    }
+}
+
+/// Outcome of a calibration run
+#[derive(Debug, Clone)]
+pub struct CalibrationResult {
+    pub source_id: String,
+    pub pulses_sent: usize,
+    pub pulses_detected: usize,
+    pub timestamp_bias: Option<OffsetStats>,
+    pub latency: Option<OffsetStats>,
+}
+
+impl CalibrationResult {
+    /// Offset to add to recorded timestamps to remove the measured bias
+    pub fn timestamp_correction(&self) -> Option<f64> {
+        self.timestamp_bias.as_ref().map(|bias| -bias.mean)
+    }
+
+    /// JSON form stored in the `calibration` attribute
+    pub fn to_json(&self) -> serde_json::Value {
+        json!({
+            "method": "pulse",
+            "source_id": self.source_id,
+            "pulses_sent": self.pulses_sent,
+            "pulses_detected": self.pulses_detected,
+            "timestamp_bias": self.timestamp_bias.as_ref().map(OffsetStats::to_json),
+            "latency": self.latency.as_ref().map(OffsetStats::to_json),
+            "timestamp_correction": self.timestamp_correction(),
+            "calibrated_at": chrono::Utc::now().to_rfc3339(),
+            "calibrator_version": env!("CARGO_PKG_VERSION"),
+        })
+    }
+}
+
+/// Detects rising edges through `threshold` in a stream of samples
+#[derive(Debug, Clone)]
+pub struct EdgeDetector {
+    threshold: f64,
+    above: bool,
+}
+
+impl EdgeDetector {
+    pub fn new(threshold: f64) -> Self {
+        // Start "above" so a stream that begins mid-pulse does not count as an onset
+        Self { threshold, above: true }
+    }
+
+    /// Feed one sample; returns true if it is the first sample of a pulse
+    pub fn is_onset(&mut self, value: f64) -> bool {
+        let above = value >= self.threshold;
+        let onset = above && !self.above;
+        self.above = above;
+        onset
+    }
+}
+
+/// Offset (`observed - reference`) of the nearest observed time for each reference time
+///
+/// Reference times without an observation within `max_offset` seconds are skipped,
+/// as are observations already matched to an earlier reference.
+pub fn match_onsets(reference: &[f64], observed: &[f64], max_offset: f64) -> Vec<f64> {
+    let mut used = vec![false; observed.len()];
+    let mut offsets = Vec::new();
+
+    for &time in reference {
+        let nearest = observed
+            .iter()
+            .enumerate()
+            .filter(|(i, obs)| !used[*i] && (*obs - time).abs() <= max_offset)
+            .min_by(|(_, a), (_, b)| (*a - time).abs().total_cmp(&(*b - time).abs()));
+        if let Some((i, obs)) = nearest {
+            used[i] = true;
+            offsets.push(obs - time);
+        }
+    }
+    offsets
+}
+
+/// Store a calibration result as the `calibration` attribute of `stream` (or the root group)
+pub fn store_calibration(store_path: &Path, stream: Option<&str>, result: &CalibrationResult) -> Result<()> {
+    if !store_path.join("zarr.json").exists() {
+        anyhow::bail!("Not a Zarr store: {}", store_path.display());
+    }
+    let store = Arc::new(FilesystemStore::new(store_path)?);
+    let group_path = match stream {
+        Some(name) => format!("/{}", name),
+        None => "/".to_string(),
+    };
+
+    let mut group = zarrs::group::Group::open(store, &group_path)?;
+    group
+        .attributes_mut()
+        .insert("calibration".to_string(), result.to_json());
+    group.store_metadata()?;
+    Ok(())
+}
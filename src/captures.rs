@@ -0,0 +1,256 @@
+//! Marker-triggered captures stored in the store's `annex` group
+//!
+//! Setups without video sync hardware can still line up a webcam frame or a
+//! screenshot with the recording: when the recorder of a marker stream receives one
+//! of the `--capture-on` markers, it notes the marker's LSL timestamp together with
+//! the LSL clock and the host wall clock at that moment, and optionally runs
+//! `--capture-command` to grab the frame. Each capture is appended to the
+//! `captures` attribute of the `annex` group:
+//!
+//! ```json
+//! {"captures": [{"index": 1, "marker": "stimulus", "stream": "Markers",
+//!                "marker_time": 12345.678, "lsl_time": 12345.679, "wall_clock": 1760600000.5,
+//!                "duration_s": 0.21, "exit_code": 0, "files": ["capture_0001.jpg"]}]}
+//! ```
+//!
+//! The command runs through the shell in the background, so a slow camera never
+//! holds up the recording. `{file}` in the command is replaced by the capture's path
+//! without extension (`<store>/annex/capture_0001`), which is also passed as
+//! `LSL_CAPTURE_FILE` along with `LSL_CAPTURE_MARKER`, `LSL_CAPTURE_LSL_TIME` and
+//! `LSL_CAPTURE_WALL_CLOCK`. Whatever the command writes next to that path is
+//! listed in `files`, relative to the `annex` directory.
+
+use anyhow::Result;
+use fs2::FileExt;
+use serde::{Deserialize, Serialize};
+use std::fs::OpenOptions;
+use std::path::Path;
+use std::process::Command;
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+use std::time::Instant;
+use zarrs::filesystem::FilesystemStore;
+
+use crate::zarr::layout::create_group_if_not_exists;
+use crate::zarr::read_group_attributes;
+pub use crate::zarr::ANNEX_GROUP;
+
+/// Name of the `annex` group attribute holding the captures
+pub const CAPTURES_ATTRIBUTE: &str = "captures";
+
+/// One marker-triggered capture with its time anchors
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Capture {
+    /// Number of the capture in the store, from 1
+    pub index: u32,
+    pub marker: String,
+    /// Marker stream that delivered the marker
+    pub stream: String,
+    /// LSL timestamp of the marker sample
+    pub marker_time: f64,
+    /// LSL clock when the recorder received the marker
+    pub lsl_time: f64,
+    /// Host wall clock (Unix seconds) at the same moment
+    pub wall_clock: f64,
+    /// Runtime of `--capture-command` (s)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub duration_s: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub exit_code: Option<i32>,
+    /// Why the command could not be run
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    /// Files the command wrote, relative to the `annex` directory
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub files: Vec<String>,
+}
+
+impl Capture {
+    /// File name stem the command's output starts with
+    pub fn file_stem(&self) -> String {
+        format!("capture_{:04}", self.index)
+    }
+}
+
+/// Captures stored in the `annex` group, in the order they were taken
+pub fn read_captures(store_path: &Path) -> Vec<Capture> {
+    let Ok(store) = FilesystemStore::new(store_path) else {
+        return Vec::new();
+    };
+    read_group_attributes(&Arc::new(store), &format!("/{}", ANNEX_GROUP))
+        .ok()
+        .and_then(|attrs| attrs.get(CAPTURES_ATTRIBUTE).cloned())
+        .and_then(|value| serde_json::from_value(value).ok())
+        .unwrap_or_default()
+}
+
+/// Append a capture to the `annex` group of a store
+///
+/// Holds the store's metadata lock, like [`crate::annotations::append_annotation`].
+pub fn append_capture(store_path: &Path, capture: &Capture) -> Result<()> {
+    let lock_file = OpenOptions::new()
+        .create(true)
+        .write(true)
+        .truncate(false)
+        .open(store_path.join(".zarr_metadata.lock"))?;
+    lock_file.lock_exclusive()?;
+    let result = (|| -> Result<()> {
+        let store = Arc::new(FilesystemStore::new(store_path)?);
+        let annex_path = format!("/{}", ANNEX_GROUP);
+        create_group_if_not_exists(&store, &annex_path)?;
+        let mut group = zarrs::group::Group::open(store, &annex_path)?;
+
+        let attributes = group.attributes_mut();
+        let mut captures: Vec<Capture> = attributes
+            .get(CAPTURES_ATTRIBUTE)
+            .and_then(|value| serde_json::from_value(value.clone()).ok())
+            .unwrap_or_default();
+        captures.push(capture.clone());
+        attributes.insert(CAPTURES_ATTRIBUTE.to_string(), serde_json::to_value(&captures)?);
+        group.store_metadata()?;
+        Ok(())
+    })();
+    lock_file.unlock()?;
+    result
+}
+
+/// Watches a marker stream for `--capture-on` markers and takes the captures
+pub struct CaptureTrigger {
+    markers: Vec<String>,
+    command: Option<String>,
+    stream: String,
+    next_index: u32,
+    running: Vec<JoinHandle<()>>,
+}
+
+impl CaptureTrigger {
+    /// Trigger for the markers of `stream`, numbering on from the captures in `store_path`
+    pub fn new(markers: Vec<String>, command: Option<String>, stream: String, store_path: &Path) -> Self {
+        let next_index = read_captures(store_path).iter().map(|c| c.index).max().unwrap_or(0) + 1;
+        Self {
+            markers,
+            command,
+            stream,
+            next_index,
+            running: Vec::new(),
+        }
+    }
+
+    /// The capture marker among a marker sample's values, if any
+    pub fn matching<'a>(&self, sample: &'a [String]) -> Option<&'a str> {
+        sample
+            .iter()
+            .find(|value| self.markers.iter().any(|marker| marker == value.trim()))
+            .map(|value| value.trim())
+    }
+
+    /// Take a capture into `store_path` if the sample holds a capture marker
+    ///
+    /// `lsl_time` and `wall_clock` are read by the caller when the sample arrives.
+    /// Without a command the capture is stored at once; with one it is stored with
+    /// the command's outcome once the command has finished.
+    pub fn sample(
+        &mut self,
+        store_path: &Path,
+        sample: &[String],
+        marker_time: f64,
+        lsl_time: f64,
+        wall_clock: f64,
+    ) -> Option<Capture> {
+        let marker = self.matching(sample)?.to_string();
+        let capture = Capture {
+            index: self.next_index,
+            marker,
+            stream: self.stream.clone(),
+            marker_time,
+            lsl_time,
+            wall_clock,
+            ..Capture::default()
+        };
+        self.next_index += 1;
+        self.running.retain(|handle| !handle.is_finished());
+
+        match self.command.clone() {
+            None => {
+                if let Err(e) = append_capture(store_path, &capture) {
+                    eprintln!("Warning: storing capture {} failed: {:#}", capture.index, e);
+                }
+            }
+            Some(command) => {
+                let store_path = store_path.to_path_buf();
+                let pending = capture.clone();
+                self.running.push(thread::spawn(move || {
+                    let capture = run_capture_command(&command, &store_path, pending);
+                    if let Some(ref e) = capture.error {
+                        eprintln!("Warning: capture {} command failed: {}", capture.index, e);
+                    }
+                    if let Err(e) = append_capture(&store_path, &capture) {
+                        eprintln!("Warning: storing capture {} failed: {:#}", capture.index, e);
+                    }
+                }));
+            }
+        }
+        Some(capture)
+    }
+
+    /// Wait for running capture commands, so their captures are stored
+    pub fn finish(&mut self) {
+        for handle in self.running.drain(..) {
+            handle.join().ok();
+        }
+    }
+}
+
+/// Run the capture command and note its outcome and output files
+fn run_capture_command(command: &str, store_path: &Path, mut capture: Capture) -> Capture {
+    let annex_dir = store_path.join(ANNEX_GROUP);
+    let stem = capture.file_stem();
+    let file = annex_dir.join(&stem);
+    if let Err(e) = std::fs::create_dir_all(&annex_dir) {
+        capture.error = Some(format!("creating {}: {}", annex_dir.display(), e));
+        return capture;
+    }
+
+    let command = command.replace("{file}", &file.display().to_string());
+    let mut shell = if cfg!(windows) {
+        let mut shell = Command::new("cmd");
+        shell.arg("/C");
+        shell
+    } else {
+        let mut shell = Command::new("sh");
+        shell.arg("-c");
+        shell
+    };
+    shell
+        .arg(&command)
+        .env("LSL_CAPTURE_FILE", &file)
+        .env("LSL_CAPTURE_MARKER", &capture.marker)
+        .env("LSL_CAPTURE_LSL_TIME", capture.lsl_time.to_string())
+        .env("LSL_CAPTURE_WALL_CLOCK", capture.wall_clock.to_string());
+
+    let started = Instant::now();
+    match shell.status() {
+        Ok(status) => {
+            capture.exit_code = status.code();
+            if !status.success() {
+                capture.error = Some(format!("'{}' exited with {}", command, status));
+            }
+        }
+        Err(e) => capture.error = Some(format!("running '{}': {}", command, e)),
+    }
+    capture.duration_s = Some(started.elapsed().as_secs_f64());
+
+    if let Ok(entries) = std::fs::read_dir(&annex_dir) {
+        capture.files = entries
+            .filter_map(|entry| entry.ok())
+            .map(|entry| entry.file_name().to_string_lossy().to_string())
+            .filter(|name| {
+                // capture_1000 is not output of capture_100
+                name.strip_prefix(&stem)
+                    .is_some_and(|rest| !rest.starts_with(|c: char| c.is_ascii_digit()))
+            })
+            .collect();
+        capture.files.sort();
+    }
+    capture
+}
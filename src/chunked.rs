@@ -0,0 +1,158 @@
+//! Chunked acquisition for high-rate streams
+//!
+//! Pulling one sample per call costs an inlet round trip per sample; at 4 kHz with
+//! 256 channels that keeps a core busy and lets the inlet buffer fill up when the
+//! recorder falls behind. Streams whose nominal rate reaches `--chunk-pull-rate` are
+//! therefore pulled in chunks: the recorder blocks for one sample as before, then
+//! takes everything else the inlet has buffered in a single call and queues it.
+//! The following loop iterations are served from the queue without touching the
+//! inlet, so pauses, rotation, flags and the live outputs see the same samples in
+//! the same order as with sample-wise pulling. Unless rotation or sample flags need
+//! a per-sample decision from the writer, the queued samples reach the writer as
+//! blocks, along with the sample that started the chunk.
+
+use std::ops::Range;
+
+/// Default `--chunk-pull-rate` (Hz)
+pub const DEFAULT_CHUNK_PULL_RATE: f64 = 1000.0;
+
+/// Whether a stream of `nominal_srate` Hz is pulled in chunks (`threshold` 0 disables it)
+pub fn pulls_in_chunks(nominal_srate: f64, threshold: f64) -> bool {
+    threshold > 0.0 && nominal_srate >= threshold
+}
+
+/// Pulled samples waiting to be recorded, oldest first
+///
+/// A chunk is pulled into one flat buffer (sample after sample, as liblsl lays it
+/// out) that is reused for every chunk, so queueing allocates nothing once the
+/// buffers have grown to the largest chunk.
+#[derive(Debug, Clone)]
+pub struct ChunkQueue<T> {
+    data: Vec<T>,
+    timestamps: Vec<f64>,
+    channels: usize,
+    next: usize,
+    // Samples before this index were already handed to the writer
+    written: usize,
+    chunks: u64,
+    chunk_samples: u64,
+    largest_chunk: usize,
+}
+
+impl<T> Default for ChunkQueue<T> {
+    fn default() -> Self {
+        Self {
+            data: Vec::new(),
+            timestamps: Vec::new(),
+            channels: 0,
+            next: 0,
+            written: 0,
+            chunks: 0,
+            chunk_samples: 0,
+            largest_chunk: 0,
+        }
+    }
+}
+
+impl<T: Copy> ChunkQueue<T> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Replace the queue with the chunk `pull` writes into the (cleared) data and timestamp buffers
+    ///
+    /// Call only once the queue is empty; samples without a timestamp are skipped when handed out.
+    pub fn refill<E>(&mut self, pull: impl FnOnce(&mut Vec<T>, &mut Vec<f64>) -> Result<(), E>) -> Result<(), E> {
+        self.data.clear();
+        self.timestamps.clear();
+        self.next = 0;
+        self.written = 0;
+        let pulled = pull(&mut self.data, &mut self.timestamps);
+        let samples = self.timestamps.len();
+        // A failed or malformed pull leaves nothing queued
+        if pulled.is_err() || samples == 0 || !self.data.len().is_multiple_of(samples) {
+            self.timestamps.clear();
+            return pulled;
+        }
+        self.channels = self.data.len() / samples;
+
+        let stamped = self.timestamps.iter().filter(|ts| **ts != 0.0).count();
+        if stamped > 0 {
+            self.chunks += 1;
+            self.chunk_samples += stamped as u64;
+            self.largest_chunk = self.largest_chunk.max(stamped);
+        }
+        Ok(())
+    }
+
+    /// Copy the oldest queued sample into `buf` and return its timestamp
+    pub fn pop_into(&mut self, buf: &mut Vec<T>) -> Option<f64> {
+        while self.next < self.timestamps.len() {
+            let index = self.next;
+            self.next += 1;
+            let ts = self.timestamps[index];
+            if ts != 0.0 {
+                buf.clear();
+                buf.extend_from_slice(&self.data[index * self.channels..(index + 1) * self.channels]);
+                return Some(ts);
+            }
+        }
+        None
+    }
+
+    /// Whether the sample handed out last already went to the writer with [`take_unwritten`](Self::take_unwritten)
+    pub fn last_written(&self) -> bool {
+        self.next > 0 && self.next - 1 < self.written
+    }
+
+    /// Queued samples not handed to the writer yet, as flat data and timestamps
+    ///
+    /// They count as written from then on; samples without a timestamp are included.
+    pub fn take_unwritten(&mut self) -> (&[T], &[f64]) {
+        let start = self.next.max(self.written);
+        self.written = self.timestamps.len();
+        let end = self.timestamps.len();
+        (&self.data[start * self.channels..end * self.channels], &self.timestamps[start..])
+    }
+
+    /// Values per queued sample
+    pub fn channels(&self) -> usize {
+        self.channels
+    }
+
+    pub fn len(&self) -> usize {
+        self.timestamps[self.next..].iter().filter(|ts| **ts != 0.0).count()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Chunks pulled so far and the mean and largest number of samples in them
+    pub fn chunk_stats(&self) -> (u64, f64, usize) {
+        let mean = if self.chunks == 0 {
+            0.0
+        } else {
+            self.chunk_samples as f64 / self.chunks as f64
+        };
+        (self.chunks, mean, self.largest_chunk)
+    }
+}
+
+/// Hand the index ranges of consecutive samples in `timestamps` that have a
+/// timestamp and pass `keep` to `write`, so that a chunk goes to the writer in as
+/// few blocks as the skipped samples allow
+pub fn for_each_kept_run(timestamps: &[f64], keep: impl Fn(f64) -> bool, mut write: impl FnMut(Range<usize>)) {
+    let mut run_start = 0;
+    for (i, &ts) in timestamps.iter().enumerate() {
+        if ts == 0.0 || !keep(ts) {
+            if run_start < i {
+                write(run_start..i);
+            }
+            run_start = i + 1;
+        }
+    }
+    if run_start < timestamps.len() {
+        write(run_start..timestamps.len());
+    }
+}
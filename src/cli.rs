@@ -2,6 +2,23 @@ use clap::Parser;
 use serde_json::json;
 use std::path::PathBuf;
 
+use crate::chunked::DEFAULT_CHUNK_PULL_RATE;
+use crate::clock_jumps::DEFAULT_CLOCK_JUMP_THRESHOLD;
+use crate::lsl::{RecordingConfig, ResolutionStrategy, StreamResolutionConfig};
+use crate::monitor::{MonitorOptions, MonitorReduction};
+use crate::permissions::StorePermissions;
+use crate::plausibility::{parse_plausible_range, PlausibleRange};
+use crate::quality::QualityOptions;
+use crate::rate_guard::parse_rate_factor;
+use crate::redact::{redact_config, storage_environment};
+use crate::rotation::{parse_duration, parse_size, RotationOptions};
+use crate::rules::{parse_rule, Rule};
+use crate::schedule::BlockSchedule;
+use crate::subject::DEFAULT_SUBJECT_PATTERN;
+use crate::tap::TapFormat;
+use crate::zarr::remote::is_remote;
+use crate::zarr::{expand_output_template, Compressor, GroupNaming, StorageOptions};
+
 #[derive(Parser, Clone)]
 #[command(name = "lsl-recorder")]
 #[command(about = "Record LSL streams to disk with dedicated control interface")]
@@ -9,20 +26,55 @@ pub struct Args {
     #[arg(long, help = "LSL stream source ID to record", default_value = "1234")]
     pub source_id: String,
 
+    #[arg(
+        long,
+        help = "Resolve the stream by its name (takes precedence over --source-id)",
+        conflicts_with_all = ["by_type", "predicate"]
+    )]
+    pub by_name: Option<String>,
+
+    #[arg(
+        long,
+        help = "Resolve the first stream of this type (takes precedence over --source-id)",
+        conflicts_with = "predicate"
+    )]
+    pub by_type: Option<String>,
+
+    #[arg(
+        long,
+        help = "Resolve the stream with an LSL XPath predicate (takes precedence over --source-id)",
+        requires = "stream_name"
+    )]
+    pub predicate: Option<String>,
+
     #[arg(
         long,
         short = 'o',
-        help = "Zarr experiment base path (without .zarr extension)",
+        help = "Zarr experiment base path (without .zarr extension); supports {date}, {time}, {subject}, {session_id}",
         default_value = "experiment"
     )]
     pub output: PathBuf,
 
+    #[arg(
+        long,
+        help = "Allow writing into an existing store recorded for a different subject/session"
+    )]
+    pub append_session: bool,
+
     #[arg(
         long,
         help = "Stream name for Zarr group (defaults to source-id if not specified)"
     )]
     pub stream_name: Option<String>,
 
+    #[arg(
+        long,
+        value_enum,
+        default_value = "name",
+        help = "Name the Zarr group after the stream name (name) or its LSL UID (uid), which stays unique when identical devices share a name and source ID"
+    )]
+    pub name_by: GroupNaming,
+
     #[arg(
         long,
         help = "Optional suffix for Zarr store (defaults to stream name if not specified)"
@@ -45,6 +97,36 @@ pub struct Args {
     #[arg(long, short = 'd', help = "Maximum recording duration in seconds")]
     pub duration: Option<u64>,
 
+    #[arg(
+        long,
+        help = "Check the setup instead of recording: resolve the stream, pull samples for --preflight-seconds, compare the rate to the nominal one, check the output and its free space, and estimate the data rate; exits with 1 on NO-GO"
+    )]
+    pub preflight: bool,
+
+    #[arg(long, default_value = "5.0", value_name = "SECONDS", help = "How long --preflight pulls samples")]
+    pub preflight_seconds: f64,
+
+    #[arg(
+        long,
+        value_name = "TIME",
+        help = "Connect now but start recording at this local time (HH:MM[:SS], next occurrence, or YYYY-MM-DD HH:MM[:SS])"
+    )]
+    pub start_at: Option<String>,
+
+    #[arg(
+        long,
+        value_parser = clap::value_parser!(u32).range(1..),
+        requires = "block_duration",
+        help = "Record this many blocks of --block-duration seconds, separated by --inter-block pauses, then quit"
+    )]
+    pub blocks: Option<u32>,
+
+    #[arg(long, value_name = "SECONDS", requires = "blocks", help = "Length of each --blocks block in seconds")]
+    pub block_duration: Option<f64>,
+
+    #[arg(long, value_name = "SECONDS", default_value = "0", help = "Pause between --blocks blocks in seconds")]
+    pub inter_block: f64,
+
     #[arg(long, default_value = "1000", help = "Stream buffer size")]
     pub buffer_size: usize,
 
@@ -61,6 +143,20 @@ pub struct Args {
     #[arg(long, help = "Subject identifier for metadata")]
     pub subject: Option<String>,
 
+    #[arg(
+        long,
+        conflicts_with = "subject",
+        help = "Assign the next free subject ID (see --subject-pattern) among the stores under the output path"
+    )]
+    pub auto_subject: bool,
+
+    #[arg(
+        long,
+        default_value = DEFAULT_SUBJECT_PATTERN,
+        help = "Subject ID pattern for --auto-subject, one '#' per digit (e.g. P### gives P001, P002, ...)"
+    )]
+    pub subject_pattern: String,
+
     #[arg(long, help = "Session identifier for metadata")]
     pub session_id: Option<String>,
 
@@ -107,8 +203,289 @@ pub struct Args {
     )]
     pub lsl_pull_timeout: Option<f64>,
 
-    #[arg(long, help = "Enable memory usage monitoring and periodic reporting")]
+    #[arg(
+        long,
+        default_value = "100",
+        value_parser = clap::value_parser!(u64).range(1..),
+        help = "Samples per chunk for new arrays (larger values mean fewer files for high-rate streams)"
+    )]
+    pub chunk_samples: u64,
+
+    #[arg(long, value_enum, default_value = "lz4", help = "Compression codec for new arrays")]
+    pub compressor: Compressor,
+
+    #[arg(
+        long,
+        default_value = "5",
+        value_parser = clap::value_parser!(u8).range(0..=9),
+        help = "Blosc compression level (0-9)"
+    )]
+    pub compression_level: u8,
+
+    #[arg(
+        long,
+        value_parser = clap::value_parser!(u64).range(1..),
+        help = "Store samples in Zarr shards of this many samples (multiple of --chunk-samples); reduces file count for high-rate streams"
+    )]
+    pub shard_samples: Option<u64>,
+
+    #[arg(long, help = "Report process memory (RSS, virtual, buffer) as STATUS MEMORY lines and warn on growth")]
     pub memory_monitor: bool,
+
+    #[arg(
+        long,
+        default_value = "10.0",
+        help = "Seconds between --memory-monitor reports (minimum 1)"
+    )]
+    pub memory_interval: f64,
+
+    #[arg(
+        long,
+        default_value = "10.0",
+        value_name = "MB_PER_MIN",
+        help = "Report STATUS MEMORY_GROWTH when RSS grows faster than this over the last reports (0 disables)"
+    )]
+    pub memory_growth_limit: f64,
+
+    #[arg(
+        long,
+        default_value = "0",
+        value_name = "SECONDS",
+        help = "Re-fetch the stream info this often and store changed versions in stream_info_history (0 disables)"
+    )]
+    pub info_refresh: f64,
+
+    #[arg(
+        long,
+        value_name = "PORT",
+        help = "Serve a JSON status endpoint (GET /status) on this port for remote monitoring"
+    )]
+    pub status_port: Option<u16>,
+
+    #[arg(
+        long,
+        default_value = "0",
+        help = "Print STATUS PROGRESS lines every this many seconds (0 disables; used by lsl-multi-recorder and the TUI)"
+    )]
+    pub status_interval: f64,
+
+    #[arg(
+        long,
+        value_name = "PORT",
+        help = "Accept START/STOP/STOP_AFTER/PAUSE/RESUME/QUIT/METADATA/MARK commands over TCP or WebSocket on this port"
+    )]
+    pub control_port: Option<u16>,
+
+    #[arg(
+        long,
+        value_name = "PATH",
+        help = "Also write every flush to a second store (same placeholders as --output), e.g. on another disk"
+    )]
+    pub mirror: Option<PathBuf>,
+
+    #[arg(
+        long,
+        value_name = "DIR",
+        help = "Local directory for the files of an s3:// or gs:// --output until they are uploaded (default: system temp dir)"
+    )]
+    pub spool_dir: Option<PathBuf>,
+
+    #[arg(
+        long,
+        value_name = "MODE",
+        help = "Octal mode for the files of the store, e.g. 660 (directories also get execute where read is granted)"
+    )]
+    pub chmod: Option<String>,
+
+    #[arg(
+        long,
+        value_name = "GROUP",
+        help = "Group owning the store's files and directories (name or GID); new files inherit it"
+    )]
+    pub chgrp: Option<String>,
+
+    #[arg(
+        long,
+        value_name = "URL",
+        help = "Serve a decimated live copy of the samples to WebSocket clients, e.g. ws://0.0.0.0:9001"
+    )]
+    pub tap: Option<String>,
+
+    #[arg(long, value_enum, default_value = "json", help = "Frame encoding for --tap clients")]
+    pub tap_format: TapFormat,
+
+    #[arg(
+        long,
+        default_value = "50.0",
+        help = "Approximate sample rate (Hz) forwarded to --tap clients; irregular streams are not decimated"
+    )]
+    pub tap_rate: f64,
+
+    #[arg(
+        long,
+        help = "Republish a decimated copy of the stream as a new LSL outlet (<stream>_monitor) for remote monitoring"
+    )]
+    pub monitor: bool,
+
+    #[arg(long, default_value = "50.0", help = "Approximate sample rate (Hz) of the --monitor stream")]
+    pub monitor_rate: f64,
+
+    #[arg(
+        long,
+        value_delimiter = ',',
+        help = "Channels republished by --monitor, by label or 0-based index (comma-separated; default: all)"
+    )]
+    pub monitor_channels: Vec<String>,
+
+    #[arg(
+        long,
+        value_enum,
+        default_value = "mean",
+        help = "How --monitor reduces each block of samples: mean or pick (first sample)"
+    )]
+    pub monitor_reduction: MonitorReduction,
+
+    #[arg(
+        long,
+        default_value = "4.0",
+        value_parser = parse_rate_factor,
+        help = "Abort when the measured sample rate exceeds this multiple of the nominal rate (0 disables the guard, else at least 1)"
+    )]
+    pub max_rate_factor: f64,
+
+    #[arg(
+        long,
+        help = "Only warn (instead of aborting) when the sample rate exceeds --max-rate-factor"
+    )]
+    pub allow_rate_overrun: bool,
+
+    #[arg(
+        long,
+        default_value = "2.0",
+        help = "Report STATUS STREAM_LOST when a regular stream delivers no samples for this many seconds (0 disables)"
+    )]
+    pub lost_timeout: f64,
+
+    #[arg(
+        long,
+        default_value = "5.0",
+        value_name = "SECONDS",
+        help = "Resolve the stream anew and reconnect when it stays lost (no samples or inlet errors) for this many seconds (0 disables)"
+    )]
+    pub reconnect_after: f64,
+
+    #[arg(
+        long,
+        default_value_t = DEFAULT_CHUNK_PULL_RATE,
+        value_name = "HZ",
+        help = "Pull numeric streams with a nominal rate of at least this many Hz in chunks instead of sample by sample (0 disables)"
+    )]
+    pub chunk_pull_rate: f64,
+
+    #[arg(
+        long,
+        default_value_t = DEFAULT_CLOCK_JUMP_THRESHOLD,
+        value_name = "SECONDS",
+        help = "Report STATUS CLOCK_JUMP and store a clock_jumps entry when the wall clock or the stream's timestamps jump by more than this (suspend/resume, NTP steps; 0 disables)"
+    )]
+    pub clock_jump_threshold: f64,
+
+    #[arg(
+        long,
+        value_name = "DURATION",
+        value_parser = parse_duration,
+        help = "Continue in a new store (experiment_part002.zarr, ...) every DURATION of recording, e.g. 30min or 2h"
+    )]
+    pub rotate_every: Option<std::time::Duration>,
+
+    #[arg(
+        long,
+        value_name = "SIZE",
+        value_parser = parse_size,
+        help = "Continue in a new store once the current one has grown past SIZE, e.g. 10GB"
+    )]
+    pub rotate_size: Option<u64>,
+
+    #[arg(
+        long,
+        help = "Detect flat, noisy and mostly non-finite channels and store them as bad_channels when recording ends"
+    )]
+    pub detect_bad_channels: bool,
+
+    #[arg(
+        long,
+        help = "Write a per-sample flags array marking samples received after a reconnect, near an inlet buffer overflow, after a clock jump, or flagged by the device (--flag-channel)"
+    )]
+    pub sample_flags: bool,
+
+    #[arg(
+        long,
+        value_name = "INDEX",
+        requires = "sample_flags",
+        help = "Channel (0-based) whose non-zero values mark samples as flagged by the device, e.g. a status or battery channel"
+    )]
+    pub flag_channel: Option<usize>,
+
+    #[arg(
+        long = "capture-on",
+        value_name = "MARKER",
+        help = "Marker of a marker stream that stores its LSL and wall-clock time in the annex group and runs --capture-command; repeatable"
+    )]
+    pub capture_on: Vec<String>,
+
+    #[arg(
+        long,
+        value_name = "COMMAND",
+        requires = "capture_on",
+        help = "Shell command run in the background for every --capture-on marker, e.g. grabbing a webcam frame; {file} is replaced by the capture's path without extension"
+    )]
+    pub capture_command: Option<String>,
+
+    #[arg(
+        long,
+        help = "Watch per-channel RMS, flatlines and clipping while recording; warn as STATUS QUALITY_WARNING and store a quality summary"
+    )]
+    pub quality: bool,
+
+    #[arg(long, default_value = "1.0", value_name = "SECONDS", help = "Length of the --quality RMS and clipping window")]
+    pub quality_window: f64,
+
+    #[arg(
+        long,
+        default_value = "2.0",
+        help = "Report a --quality flatline when a channel has not changed for this many seconds"
+    )]
+    pub flatline_seconds: f64,
+
+    #[arg(
+        long,
+        help = "Absolute value at which --quality counts samples as clipped (default: range of integer formats; float streams use repeated window extremes)"
+    )]
+    pub clip_level: Option<f64>,
+
+    #[arg(
+        long,
+        default_value = "0.01",
+        help = "Report --quality clipping when at least this fraction of a window's samples is clipped"
+    )]
+    pub clip_fraction: f64,
+
+    #[arg(
+        long = "rule",
+        value_name = "RULE",
+        value_parser = parse_rule,
+        help = "Signal rule such as 'rms < 5 for 30s then pause': warn (default) or pause while the RMS of every channel stays below/above the threshold; repeatable"
+    )]
+    pub rules: Vec<Rule>,
+
+    #[arg(
+        long,
+        value_name = "RANGE",
+        value_parser = parse_plausible_range,
+        allow_hyphen_values = true,
+        help = "Physically plausible values, e.g. '-10..10 mV' or '±500': count samples outside the range, report the first one per channel as STATUS IMPLAUSIBLE and store the counts as the plausibility attribute"
+    )]
+    pub plausible_range: Option<PlausibleRange>,
 }
 
 impl Args {
@@ -127,25 +504,153 @@ impl Args {
     ) {
         // Single Zarr file for all streams - concurrent writes are supported
         // via stream-specific subgroups: /{stream_name}/
-        let zarr_store_path = PathBuf::from(format!("{}.zarr", self.output.display()));
+        let output = expand_output_template(&self.output, self.subject.as_deref(), self.session_id.as_deref());
+        let zarr_store_path = PathBuf::from(format!("{}.zarr", output.display()));
 
         (
             zarr_store_path,
             self.stream_name
                 .clone()
-                .unwrap_or_else(|| self.source_id.clone()),
+                .unwrap_or_else(|| self.resolution_strategy().value().to_string()),
             self.subject.clone(),
             self.session_id.clone(),
             self.notes.clone(),
         )
     }
 
-    /// Serialize recorder configuration to JSON string
+    /// Block schedule from --blocks/--block-duration/--inter-block, if --blocks is set
+    pub fn block_schedule(&self) -> anyhow::Result<Option<BlockSchedule>> {
+        let Some(blocks) = self.blocks else {
+            return Ok(None);
+        };
+        let block_duration = self.block_duration.unwrap_or_default();
+        if !block_duration.is_finite() || block_duration <= 0.0 {
+            anyhow::bail!("--block-duration must be positive");
+        }
+        if !self.inter_block.is_finite() || self.inter_block < 0.0 {
+            anyhow::bail!("--inter-block cannot be negative");
+        }
+        Ok(Some(BlockSchedule {
+            blocks,
+            block_duration: std::time::Duration::from_secs_f64(block_duration),
+            inter_block: std::time::Duration::from_secs_f64(self.inter_block),
+        }))
+    }
+
+    /// Store permissions from --chmod/--chgrp
+    pub fn store_permissions(&self) -> anyhow::Result<StorePermissions> {
+        StorePermissions::new(self.chmod.as_deref(), self.chgrp.as_deref())
+    }
+
+    /// Mirror store path from --mirror, expanded like the output path
+    pub fn mirror_path(&self) -> Option<PathBuf> {
+        self.mirror.as_ref().map(|mirror| {
+            let expanded = expand_output_template(mirror, self.subject.as_deref(), self.session_id.as_deref());
+            PathBuf::from(format!("{}.zarr", expanded.display()))
+        })
+    }
+
+    /// Monitor outlet settings from --monitor-rate/--monitor-channels/--monitor-reduction, if --monitor is set
+    pub fn monitor_options(&self) -> Option<MonitorOptions> {
+        self.monitor.then(|| MonitorOptions {
+            rate: self.monitor_rate,
+            channels: self.monitor_channels.clone(),
+            reduction: self.monitor_reduction,
+            name: None,
+        })
+    }
+
+    /// When to continue in a new part, from --rotate-every/--rotate-size
+    pub fn rotation_options(&self) -> RotationOptions {
+        RotationOptions {
+            every: self.rotate_every,
+            size: self.rotate_size,
+        }
+    }
+
+    /// Quality check settings from --quality-window/--flatline-seconds/--clip-level/--clip-fraction, if --quality is set
+    pub fn quality_options(&self) -> Option<QualityOptions> {
+        self.quality.then(|| QualityOptions {
+            window: self.quality_window,
+            flatline_seconds: self.flatline_seconds,
+            clip_fraction: self.clip_fraction,
+            clip_level: self.clip_level,
+        })
+    }
+
+    /// Chunk layout and compression selected by --chunk-samples/--shard-samples/--compressor/--compression-level
+    pub fn storage_options(&self) -> StorageOptions {
+        StorageOptions {
+            chunk_samples: self.chunk_samples,
+            compressor: self.compressor,
+            compression_level: self.compression_level,
+            shard_samples: self.shard_samples,
+        }
+    }
+
+    /// Stream resolution strategy selected by --by-name/--by-type/--predicate (source ID otherwise)
+    pub fn resolution_strategy(&self) -> ResolutionStrategy {
+        if let Some(ref name) = self.by_name {
+            ResolutionStrategy::Name(name.clone())
+        } else if let Some(ref stream_type) = self.by_type {
+            ResolutionStrategy::Type(stream_type.clone())
+        } else if let Some(ref predicate) = self.predicate {
+            ResolutionStrategy::Predicate(predicate.clone())
+        } else {
+            ResolutionStrategy::SourceId(self.source_id.clone())
+        }
+    }
+
+    /// Buffering, flushing and stream loss handling from the recording options
+    pub fn recording_config(&self) -> RecordingConfig {
+        RecordingConfig {
+            flush_interval: std::time::Duration::from_secs_f64(self.flush_interval),
+            flush_buffer_size: self.flush_buffer_size,
+            immediate_flush: self.immediate_flush,
+            max_rate_factor: self.max_rate_factor,
+            allow_rate_overrun: self.allow_rate_overrun,
+            lost_timeout: std::time::Duration::from_secs_f64(self.lost_timeout.max(0.0)),
+            reconnect_after: std::time::Duration::from_secs_f64(self.reconnect_after.max(0.0)),
+            chunk_pull_rate: self.chunk_pull_rate.max(0.0),
+            detect_bad_channels: self.detect_bad_channels,
+            sample_flags: self.sample_flags,
+        }
+    }
+
+    /// Stream lookup and retries from --resolve-timeout and the --lsl-* options
+    pub fn resolution_config(&self) -> StreamResolutionConfig {
+        StreamResolutionConfig {
+            strategy: self.resolution_strategy(),
+            timeout: self.resolve_timeout,
+            max_retry_attempts: self.lsl_max_retry_attempts,
+            retry_base_delay_ms: self.lsl_retry_base_delay_ms,
+            manual_pull_timeout: self.lsl_pull_timeout,
+        }
+    }
+
+    /// Serialize the effective recorder configuration to a JSON string
+    ///
+    /// Every argument is included with the value in effect (defaults included), so a
+    /// stored recording shows exactly how it was made. Secrets in URLs and in the
+    /// object store environment are redacted (see [`crate::redact`]).
     pub fn to_recorder_config_json(
         &self,
         recording_start_time: Option<String>,
     ) -> anyhow::Result<String> {
-        let config_json = json!({
+        let mut config_json = json!({
+            "source_id": self.source_id,
+            "by_name": self.by_name,
+            "by_type": self.by_type,
+            "predicate": self.predicate,
+            "output": self.output,
+            "store_path": self.zarr_config().0,
+            // Defaults came from this lsl-toolbox.toml (see crate::config)
+            "project_config": crate::config::config_path(),
+            "stream_name": self.stream_name,
+            "name_by": self.name_by.to_string(),
+            // The group of a stream named by UID keeps its human-readable name here
+            "display_name": self.zarr_config().1,
+            "suffix": self.suffix,
             "flush_interval": self.flush_interval,
             "flush_buffer_size": self.flush_buffer_size,
             "immediate_flush": self.immediate_flush,
@@ -153,18 +658,75 @@ impl Args {
             "lsl_retry_base_delay_ms": self.lsl_retry_base_delay_ms,
             "lsl_pull_timeout": self.lsl_pull_timeout,
             "resolve_timeout": self.resolve_timeout,
+            "resolution_strategy": self.resolution_strategy().to_string(),
             "subject": self.subject,
+            "auto_subject": self.auto_subject,
+            "subject_pattern": self.subject_pattern,
             "session_id": self.session_id,
             "notes": self.notes,
             "interactive": self.interactive,
             "quiet": self.quiet,
             "auto_start": self.auto_start,
             "duration": self.duration,
+            "start_at": self.start_at,
+            "blocks": self.blocks,
+            "block_duration": self.block_duration,
+            "inter_block": self.inter_block,
             "buffer_size": self.buffer_size,
+            "append_session": self.append_session,
+            "chunk_samples": self.chunk_samples,
+            "compressor": self.compressor.to_string(),
+            "compression_level": self.compression_level,
+            "shard_samples": self.shard_samples,
+            "mirror": self.mirror,
+            "spool_dir": self.spool_dir,
+            "chmod": self.chmod,
+            "chgrp": self.chgrp,
+            "tap": self.tap,
+            "tap_format": self.tap_format.to_string(),
+            "tap_rate": self.tap_rate,
+            "monitor": self.monitor,
+            "monitor_rate": self.monitor_rate,
+            "monitor_channels": self.monitor_channels,
+            "monitor_reduction": self.monitor_reduction.to_string(),
+            "max_rate_factor": self.max_rate_factor,
+            "allow_rate_overrun": self.allow_rate_overrun,
+            "lost_timeout": self.lost_timeout,
+            "reconnect_after": self.reconnect_after,
+            "chunk_pull_rate": self.chunk_pull_rate,
+            "clock_jump_threshold": self.clock_jump_threshold,
+            "rotate_every_s": self.rotate_every.map(|d| d.as_secs_f64()),
+            "rotate_size_bytes": self.rotate_size,
+            "detect_bad_channels": self.detect_bad_channels,
+            "sample_flags": self.sample_flags,
+            "flag_channel": self.flag_channel,
+            "capture_on": self.capture_on,
+            "capture_command": self.capture_command,
+            "quality": self.quality_options().map(|q| json!({
+                "window": q.window,
+                "flatline_seconds": q.flatline_seconds,
+                "clip_level": q.clip_level,
+                "clip_fraction": q.clip_fraction,
+            })),
+            "rules": self.rules.iter().map(|rule| rule.to_string()).collect::<Vec<_>>(),
+            "plausible_range": self.plausible_range.as_ref().map(|range| range.to_string()),
+            "memory_monitor": self.memory_monitor,
+            "memory_interval": self.memory_interval,
+            "memory_growth_limit": self.memory_growth_limit,
+            "info_refresh": self.info_refresh,
+            "status_port": self.status_port,
+            "status_interval": self.status_interval,
+            "control_port": self.control_port,
             "recorded_at": recording_start_time,
             "recorder_version": env!("CARGO_PKG_VERSION")
         });
 
+        // Credentials of s3:// and gs:// outputs come from the environment
+        if is_remote(&self.output) {
+            config_json["storage_environment"] = serde_json::Value::Object(storage_environment());
+        }
+        redact_config(&mut config_json);
+
         Ok(serde_json::to_string_pretty(&config_json)?)
     }
 }
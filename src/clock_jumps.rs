@@ -0,0 +1,220 @@
+//! Clock jumps during a recording (suspend/resume, NTP steps)
+//!
+//! Samples are stamped in LSL clock time, a monotonic clock that stops while the
+//! host is suspended, whereas the wall clock keeps counting and can be stepped by
+//! NTP. The recorder watches for two kinds of discontinuity:
+//!
+//! - **Wall clock**: the offset between the wall clock and the LSL clock changed by
+//!   more than the threshold. The host was suspended or its clock was stepped, so
+//!   wall-clock anchors (`start_time`, `end_time`, annotations) taken on either side
+//!   no longer line up with the LSL clock.
+//! - **Timestamps**: a sample arrived earlier, relative to its timestamp, than any
+//!   sample before it by more than the threshold. Samples cannot arrive before they
+//!   are stamped, so the stream's clock (or its clock-sync offset) jumped forward.
+//!   Buffered samples only ever arrive late and never trigger this.
+//!
+//! Every jump is reported as `STATUS CLOCK_JUMP` and appended to the stream group's
+//! `clock_jumps` attribute. `before` and `after` bracket the jump in LSL clock time,
+//! `jump` is its size in seconds:
+//!
+//! ```json
+//! "clock_jumps": [{"kind": "wall_clock", "lsl_clock": 81542.61, "before": 81542.10,
+//!                  "after": 81542.61, "jump": 1835.2}]
+//! ```
+//!
+//! `lsl-validate` reports gaps that coincide with a jump as clock jumps instead of
+//! dropouts (see [`split_gaps`]).
+
+use crate::gaps::{Gap, GapReport};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Stream group attribute holding the detected clock jumps
+pub const CLOCK_JUMPS_ATTRIBUTE: &str = "clock_jumps";
+
+/// Default size in seconds above which a discontinuity is reported
+pub const DEFAULT_CLOCK_JUMP_THRESHOLD: f64 = 1.0;
+
+/// Seconds of LSL clock between two wall-clock comparisons
+const WALL_CLOCK_CHECK_INTERVAL: f64 = 0.5;
+
+/// Seconds around a jump within which a gap counts as caused by it; LSL clock
+/// synchronization takes a few seconds to pick up a new offset
+const GAP_MATCH_SLACK: f64 = 5.0;
+
+/// Which clock jumped
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ClockJumpKind {
+    /// Wall clock against LSL clock: suspend/resume or a clock step on the host
+    WallClock,
+    /// Sample timestamps against their arrival: the stream's clock jumped
+    Timestamps,
+}
+
+impl ClockJumpKind {
+    pub fn name(&self) -> &'static str {
+        match self {
+            ClockJumpKind::WallClock => "wall clock",
+            ClockJumpKind::Timestamps => "timestamps",
+        }
+    }
+}
+
+/// One detected discontinuity
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct ClockJump {
+    pub kind: ClockJumpKind,
+    /// LSL clock time at which the jump was noticed
+    pub lsl_clock: f64,
+    /// Last LSL clock time (or sample timestamp) before the jump
+    pub before: f64,
+    /// First LSL clock time (or sample timestamp) after the jump
+    pub after: f64,
+    /// Size of the jump in seconds (negative: backwards)
+    pub jump: f64,
+}
+
+impl ClockJump {
+    /// Whether a gap in the stream's timestamps coincides with this jump
+    pub fn explains(&self, gap: &Gap) -> bool {
+        match self.kind {
+            ClockJumpKind::Timestamps => gap.start <= self.after && gap.end >= self.before,
+            ClockJumpKind::WallClock => {
+                gap.start <= self.after + GAP_MATCH_SLACK && gap.end >= self.before - GAP_MATCH_SLACK
+            }
+        }
+    }
+}
+
+impl std::fmt::Display for ClockJump {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} jumped {:+.2}s at LSL {:.3}", self.kind.name(), self.jump, self.lsl_clock)?;
+        if self.kind == ClockJumpKind::WallClock {
+            write!(f, " (suspend or clock step)")?;
+        }
+        Ok(())
+    }
+}
+
+/// Watches the wall clock and the sample timestamps of one recorder for jumps
+#[derive(Debug, Clone)]
+pub struct ClockJumpDetector {
+    threshold: f64,
+    // (LSL clock, wall clock) at the last comparison
+    wall: Option<(f64, f64)>,
+    // Smallest arrival minus timestamp seen so far
+    latency: Option<f64>,
+    last_timestamp: Option<f64>,
+}
+
+impl ClockJumpDetector {
+    /// Report discontinuities larger than `threshold` seconds (0 disables detection)
+    pub fn new(threshold: f64) -> Self {
+        Self {
+            threshold,
+            wall: None,
+            latency: None,
+            last_timestamp: None,
+        }
+    }
+
+    pub fn enabled(&self) -> bool {
+        self.threshold > 0.0
+    }
+
+    /// Compare the wall clock (Unix seconds) with the LSL clock; call regularly
+    pub fn check_wall_clock(&mut self, lsl_clock: f64, wall_clock: f64) -> Option<ClockJump> {
+        if !self.enabled() {
+            return None;
+        }
+        let Some((last_lsl, last_wall)) = self.wall else {
+            self.wall = Some((lsl_clock, wall_clock));
+            return None;
+        };
+        if lsl_clock - last_lsl < WALL_CLOCK_CHECK_INTERVAL {
+            return None;
+        }
+        self.wall = Some((lsl_clock, wall_clock));
+        let jump = (wall_clock - last_wall) - (lsl_clock - last_lsl);
+        (jump.abs() > self.threshold).then_some(ClockJump {
+            kind: ClockJumpKind::WallClock,
+            lsl_clock,
+            before: last_lsl,
+            after: lsl_clock,
+            jump,
+        })
+    }
+
+    /// Compare a sample's timestamp with the LSL clock time it was pulled at
+    pub fn check_sample(&mut self, timestamp: f64, arrival: f64) -> Option<ClockJump> {
+        if !self.enabled() {
+            return None;
+        }
+        let latency = arrival - timestamp;
+        let previous = self.last_timestamp.replace(timestamp);
+        let jump = match self.latency {
+            Some(lowest) if lowest - latency > self.threshold => Some(ClockJump {
+                kind: ClockJumpKind::Timestamps,
+                lsl_clock: arrival,
+                before: previous.unwrap_or(timestamp),
+                after: timestamp,
+                jump: lowest - latency,
+            }),
+            _ => None,
+        };
+        // A jump moves the baseline for the samples after it
+        if jump.is_some() || self.latency.is_none_or(|lowest| latency < lowest) {
+            self.latency = Some(latency);
+        }
+        jump
+    }
+}
+
+/// Current wall-clock time in Unix seconds
+pub fn wall_clock_now() -> f64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0.0, |d| d.as_secs_f64())
+}
+
+/// Append a jump to the `clock_jumps` attribute of a stream group's attributes
+pub fn append_clock_jump(attributes: &mut Map<String, Value>, jump: &ClockJump) -> serde_json::Result<()> {
+    let entry = serde_json::to_value(jump)?;
+    match attributes.get_mut(CLOCK_JUMPS_ATTRIBUTE) {
+        Some(Value::Array(jumps)) => jumps.push(entry),
+        _ => {
+            attributes.insert(CLOCK_JUMPS_ATTRIBUTE.to_string(), Value::Array(vec![entry]));
+        }
+    }
+    Ok(())
+}
+
+/// Clock jumps stored in a stream group's attributes (empty if there were none)
+pub fn read_clock_jumps(attributes: &Value) -> Vec<ClockJump> {
+    attributes
+        .get(CLOCK_JUMPS_ATTRIBUTE)
+        .and_then(|jumps| serde_json::from_value(jumps.clone()).ok())
+        .unwrap_or_default()
+}
+
+/// Split a gap report into the gaps no clock jump accounts for and those caused by one
+///
+/// The returned report's totals only cover the remaining gaps.
+pub fn split_gaps(report: &GapReport, jumps: &[ClockJump]) -> (GapReport, Vec<Gap>) {
+    let mut remaining = GapReport::default();
+    let mut explained = Vec::new();
+    for gap in &report.gaps {
+        if jumps.iter().any(|jump| jump.explains(gap)) {
+            explained.push(*gap);
+            continue;
+        }
+        remaining.lost_time += gap.lost;
+        if remaining.largest.is_none_or(|largest| gap.interval() > largest.interval()) {
+            remaining.largest = Some(*gap);
+        }
+        remaining.gaps.push(*gap);
+    }
+    (remaining, explained)
+}
@@ -1,80 +1,363 @@
-use anyhow::Result;
-use std::io::{self, BufRead, Write};
+//! Recorder commands from stdin and the remote control server
+//!
+//! Both recorders take the same commands: `START`, `STOP`, `STOP_AFTER <seconds>`,
+//! `PAUSE` and `RESUME` to interrupt a run without ending it (see [`crate::pauses`]),
+//! `QUIT`, `METADATA {"notes": ...}` to change the subject, session ID or notes
+//! of the session manifest while recording, and `MARK <label>` to annotate the
+//! current moment (see [`crate::annotations`]). They arrive on stdin as text lines
+//! ([`Command::parse`]) and, with `--control-port`, over the network
+//! ([`serve_control`]), so a stimulus PC can start and stop recordings
+//! programmatically.
+//!
+//! # Control protocol
+//!
+//! The control port accepts plain TCP and WebSocket clients (detected from the
+//! first request line). Every request is one line (TCP) or one text message
+//! (WebSocket): either a text command as on stdin, or a JSON object
+//!
+//! ```json
+//! {"command": "start"}
+//! {"command": "stop_after", "seconds": 30, "id": 7}
+//! {"command": "metadata", "notes": "block 2: eyes closed"}
+//! {"command": "mark", "label": "electrode adjusted"}
+//! ```
+//!
+//! Each request is answered with `{"ok": true, "command": "start"}` or
+//! `{"ok": false, "error": "..."}`, echoing the request's `id` if it had one.
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::io::{self, BufRead, BufReader, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::path::PathBuf;
 use std::sync::{
     atomic::{AtomicBool, Ordering},
     Arc,
 };
 use std::thread;
 use std::time::Duration;
+use tungstenite::Message;
+
+use crate::annotations::{append_annotation, Annotation};
+use crate::manifest::write_manifest;
+use crate::rotation::current_part;
+
+/// Time a new control client has to send the start of its first request
+pub(crate) const FIRST_REQUEST_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Pause after a failed accept, so persistent errors (e.g. out of file descriptors) don't spin
+pub(crate) const ACCEPT_RETRY_DELAY: Duration = Duration::from_millis(100);
+
+/// Session details changed by a `METADATA` command; absent fields are kept
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct MetadataUpdate {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub subject: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub session_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub notes: Option<String>,
+}
+
+/// A recorder command
+#[derive(Debug, Clone, PartialEq)]
+pub enum Command {
+    Start,
+    Stop,
+    /// Stop after this many seconds (of data, for regular streams)
+    StopAfter(u64),
+    /// Stop storing samples until `RESUME`, recording the pause in the stream metadata
+    Pause,
+    Resume,
+    Quit,
+    Metadata(MetadataUpdate),
+    /// Annotate the current moment with a label (see [`crate::annotations`])
+    Mark(String),
+}
+
+impl Command {
+    /// Parse a text command (`START`, `STOP`, `STOP_AFTER <seconds>`, `PAUSE`, `RESUME`, `QUIT`, `METADATA <json>`, `MARK <label>`)
+    pub fn parse(line: &str) -> Result<Self> {
+        let line = line.trim();
+        let (word, rest) = line.split_once(char::is_whitespace).unwrap_or((line, ""));
+        let rest = rest.trim();
+        match word.to_ascii_uppercase().as_str() {
+            "START" if rest.is_empty() => Ok(Command::Start),
+            "STOP" if rest.is_empty() => Ok(Command::Stop),
+            "PAUSE" if rest.is_empty() => Ok(Command::Pause),
+            "RESUME" if rest.is_empty() => Ok(Command::Resume),
+            "QUIT" if rest.is_empty() => Ok(Command::Quit),
+            "STOP_AFTER" => rest
+                .parse()
+                .map(Command::StopAfter)
+                .map_err(|_| anyhow::anyhow!("bad STOP_AFTER arg")),
+            "METADATA" => serde_json::from_str(rest)
+                .map(Command::Metadata)
+                .map_err(|e| anyhow::anyhow!("bad METADATA arg ({}), expected e.g. {{\"notes\": \"...\"}}", e)),
+            "MARK" if rest.is_empty() => anyhow::bail!("MARK needs a label"),
+            "MARK" => Ok(Command::Mark(rest.to_string())),
+            _ => anyhow::bail!("unknown command: {}", line),
+        }
+    }
+
+    /// Parse a JSON request such as `{"command": "stop_after", "seconds": 30}`
+    pub fn from_json(request: &Value) -> Result<Self> {
+        let name = request
+            .get("command")
+            .and_then(|c| c.as_str())
+            .context("request has no \"command\" field")?;
+        match name.to_ascii_lowercase().as_str() {
+            "start" => Ok(Command::Start),
+            "stop" => Ok(Command::Stop),
+            "pause" => Ok(Command::Pause),
+            "resume" => Ok(Command::Resume),
+            "quit" => Ok(Command::Quit),
+            "stop_after" => request
+                .get("seconds")
+                .and_then(|s| s.as_u64())
+                .map(Command::StopAfter)
+                .context("stop_after needs a whole number of \"seconds\""),
+            "metadata" => {
+                let mut fields = request.as_object().cloned().unwrap_or_default();
+                fields.remove("command");
+                fields.remove("id");
+                serde_json::from_value(Value::Object(fields))
+                    .map(Command::Metadata)
+                    .context("metadata accepts \"subject\", \"session_id\" and \"notes\"")
+            }
+            "mark" => request
+                .get("label")
+                .and_then(|l| l.as_str())
+                .map(str::trim)
+                .filter(|l| !l.is_empty())
+                .map(|l| Command::Mark(l.to_string()))
+                .context("mark needs a \"label\""),
+            _ => anyhow::bail!("unknown command: {}", name),
+        }
+    }
+
+    /// Text form, as sent to recorders over stdin
+    pub fn to_line(&self) -> String {
+        match self {
+            Command::Start => "START".to_string(),
+            Command::Stop => "STOP".to_string(),
+            Command::StopAfter(secs) => format!("STOP_AFTER {}", secs),
+            Command::Pause => "PAUSE".to_string(),
+            Command::Resume => "RESUME".to_string(),
+            Command::Quit => "QUIT".to_string(),
+            Command::Metadata(update) => format!("METADATA {}", json!(update)),
+            Command::Mark(label) => format!("MARK {}", label),
+        }
+    }
+
+    /// Name used in JSON requests and replies
+    pub fn name(&self) -> &'static str {
+        match self {
+            Command::Start => "start",
+            Command::Stop => "stop",
+            Command::StopAfter(_) => "stop_after",
+            Command::Pause => "pause",
+            Command::Resume => "resume",
+            Command::Quit => "quit",
+            Command::Metadata(_) => "metadata",
+            Command::Mark(_) => "mark",
+        }
+    }
+}
+
+/// Write a metadata update into the session manifest of each store
+///
+/// The stores must exist already, i.e. the stream has been connected. Of a rotated
+/// recording, the part being written is updated (later parts take it over).
+pub fn apply_metadata_update(stores: &[PathBuf], update: &MetadataUpdate) -> Result<()> {
+    if *update == MetadataUpdate::default() {
+        anyhow::bail!("METADATA needs at least one of subject, session_id or notes");
+    }
+    for store in stores {
+        let store = &current_part(store);
+        if !store.join("zarr.json").is_file() {
+            anyhow::bail!(
+                "store {} does not exist yet; send METADATA once the stream is connected",
+                store.display()
+            );
+        }
+        write_manifest(
+            store,
+            update.subject.as_deref(),
+            update.session_id.as_deref(),
+            update.notes.as_deref(),
+        )
+        .with_context(|| format!("Failed to update the session manifest of {}", store.display()))?;
+    }
+    Ok(())
+}
+
+/// Append an annotation made at `lsl_clock` to the `meta` group of each store
+///
+/// As for [`apply_metadata_update`], the stores must exist already, and a rotated
+/// recording is annotated in the part being written.
+pub fn apply_annotation(stores: &[PathBuf], label: &str, lsl_clock: f64) -> Result<Annotation> {
+    let annotation = Annotation {
+        label: label.to_string(),
+        time: lsl_clock,
+    };
+    for store in stores {
+        let store = &current_part(store);
+        if !store.join("zarr.json").is_file() {
+            anyhow::bail!(
+                "store {} does not exist yet; send MARK once the stream is connected",
+                store.display()
+            );
+        }
+        append_annotation(store, &annotation)
+            .with_context(|| format!("Failed to store the annotation in {}", store.display()))?;
+    }
+    Ok(annotation)
+}
 
-pub fn handle_commands(
-    recording: Arc<AtomicBool>,
-    quit: Arc<AtomicBool>,
-    first_sample_pulled: Arc<AtomicBool>,
-    is_irregular_stream: Arc<AtomicBool>,
-) -> Result<()> {
+/// Install a SIGINT/SIGTERM (Ctrl+C on Windows) handler that requests a clean shutdown
+///
+/// The first signal sets `shutdown` so the recording loop can flush buffered samples
+/// and finalize metadata before exiting. A second signal exits immediately.
+pub fn install_shutdown_handler(shutdown: Arc<AtomicBool>) -> Result<()> {
+    ctrlc::set_handler(move || {
+        if shutdown.swap(true, Ordering::SeqCst) {
+            eprintln!("Second interrupt received, exiting without finalizing");
+            std::process::exit(130);
+        }
+        println!("STATUS SHUTDOWN_REQUESTED");
+        io::stdout().flush().ok();
+    })?;
+    Ok(())
+}
+
+/// Flags of a running lsl-recorder that commands act on
+#[derive(Clone)]
+pub struct RecorderControls {
+    pub recording: Arc<AtomicBool>,
+    pub quit: Arc<AtomicBool>,
+    /// Set between `PAUSE` and `RESUME`; `recording` is cleared meanwhile
+    pub paused: Arc<AtomicBool>,
+    pub first_sample_pulled: Arc<AtomicBool>,
+    pub is_irregular_stream: Arc<AtomicBool>,
+    /// Stores whose session manifest `METADATA` updates and `MARK` annotates (store and mirror)
+    pub stores: Vec<PathBuf>,
+    /// LSL clock that `MARK` annotations are timestamped with
+    pub clock: fn() -> f64,
+}
+
+impl RecorderControls {
+    /// Run a command, reporting it on stdout as `STATUS ...`
+    pub fn execute(&self, command: &Command) -> Result<()> {
+        match command {
+            Command::Start => {
+                self.paused.store(false, Ordering::SeqCst);
+                self.recording.store(true, Ordering::SeqCst);
+                println!("STATUS STARTED");
+            }
+            Command::Stop => {
+                self.recording.store(false, Ordering::SeqCst);
+                self.paused.store(false, Ordering::SeqCst);
+                println!("STATUS STOPPED");
+            }
+            Command::Pause => {
+                if !self.recording.load(Ordering::SeqCst) {
+                    anyhow::bail!("PAUSE needs a running recording");
+                }
+                self.recording.store(false, Ordering::SeqCst);
+                self.paused.store(true, Ordering::SeqCst);
+                println!("STATUS PAUSED");
+            }
+            Command::Resume => {
+                if !self.paused.load(Ordering::SeqCst) {
+                    anyhow::bail!("RESUME needs a paused recording");
+                }
+                self.paused.store(false, Ordering::SeqCst);
+                self.recording.store(true, Ordering::SeqCst);
+                println!("STATUS RESUMED");
+            }
+            Command::StopAfter(secs) => self.stop_after(*secs),
+            Command::Quit => {
+                println!("STATUS QUIT");
+                self.quit.store(true, Ordering::SeqCst);
+            }
+            Command::Metadata(update) => {
+                apply_metadata_update(&self.stores, update)?;
+                println!("STATUS METADATA_UPDATED");
+            }
+            Command::Mark(label) => {
+                let annotation = apply_annotation(&self.stores, label, (self.clock)())?;
+                println!("STATUS MARKED {:.6} {}", annotation.time, annotation.label);
+            }
+        }
+        io::stdout().flush().ok();
+        Ok(())
+    }
+
+    fn stop_after(&self, secs: u64) {
+        let recording_clone = self.recording.clone();
+        let paused_clone = self.paused.clone();
+        let first_sample_clone = self.first_sample_pulled.clone();
+
+        // Check if this is an irregular stream (set by recording thread after stream resolution)
+        if self.is_irregular_stream.load(Ordering::SeqCst) {
+            // For irregular streams (events): start timer immediately
+            // Don't wait for first sample as events may be sparse or never arrive
+            println!("STATUS WILL STOP AFTER {}s (irregular stream: timer starts immediately)", secs);
+            io::stdout().flush().ok();
+            thread::spawn(move || {
+                println!("STATUS TIMER_STARTED ({}s countdown begins now - irregular stream)", secs);
+                io::stdout().flush().ok();
+                thread::sleep(Duration::from_secs(secs));
+                recording_clone.store(false, Ordering::SeqCst);
+                paused_clone.store(false, Ordering::SeqCst);
+                println!("STATUS STOPPED_BY_TIMER ({}s)", secs);
+                io::stdout().flush().ok();
+            });
+        } else {
+            // For regular streams: wait for first sample before starting timer
+            // This ensures accurate recording duration excluding initialization time
+            println!("STATUS WILL STOP AFTER {}s (regular stream: timer starts after first sample)", secs);
+            io::stdout().flush().ok();
+            thread::spawn(move || {
+                // Wait for first sample to be pulled
+                while !first_sample_clone.load(Ordering::SeqCst) {
+                    thread::sleep(Duration::from_millis(10));
+                }
+                println!("STATUS TIMER_STARTED ({}s countdown begins now)", secs);
+                io::stdout().flush().ok();
+                thread::sleep(Duration::from_secs(secs));
+                recording_clone.store(false, Ordering::SeqCst);
+                paused_clone.store(false, Ordering::SeqCst);
+                println!("STATUS STOPPED_BY_TIMER ({}s)", secs);
+                io::stdout().flush().ok();
+            });
+        }
+    }
+}
+
+/// Read commands from stdin until `QUIT` or end of input
+pub fn handle_commands(controls: RecorderControls) -> Result<()> {
     let stdin = io::stdin();
     for line_res in stdin.lock().lines() {
         match line_res {
             Ok(line) => {
-                let cmd = line.trim();
-                if cmd.eq_ignore_ascii_case("START") {
-                    recording.store(true, Ordering::SeqCst);
-                    println!("STATUS STARTED");
-                    io::stdout().flush().ok();
-                } else if cmd.eq_ignore_ascii_case("STOP") {
-                    recording.store(false, Ordering::SeqCst);
-                    println!("STATUS STOPPED");
-                    io::stdout().flush().ok();
-                } else if let Some(arg) = cmd.strip_prefix("STOP_AFTER ") {
-                    if let Ok(secs) = arg.trim().parse::<u64>() {
-                        let recording_clone = recording.clone();
-                        let first_sample_clone = first_sample_pulled.clone();
-
-                        // Check if this is an irregular stream (set by recording thread after stream resolution)
-                        if is_irregular_stream.load(Ordering::SeqCst) {
-                            // For irregular streams (events): start timer immediately
-                            // Don't wait for first sample as events may be sparse or never arrive
-                            println!("STATUS WILL STOP AFTER {}s (irregular stream: timer starts immediately)", secs);
-                            io::stdout().flush().ok();
-                            thread::spawn(move || {
-                                println!("STATUS TIMER_STARTED ({}s countdown begins now - irregular stream)", secs);
-                                io::stdout().flush().ok();
-                                thread::sleep(Duration::from_secs(secs));
-                                recording_clone.store(false, Ordering::SeqCst);
-                                println!("STATUS STOPPED_BY_TIMER ({}s)", secs);
-                                io::stdout().flush().ok();
-                            });
-                        } else {
-                            // For regular streams: wait for first sample before starting timer
-                            // This ensures accurate recording duration excluding initialization time
-                            println!("STATUS WILL STOP AFTER {}s (regular stream: timer starts after first sample)", secs);
-                            io::stdout().flush().ok();
-                            thread::spawn(move || {
-                                // Wait for first sample to be pulled
-                                while !first_sample_clone.load(Ordering::SeqCst) {
-                                    thread::sleep(Duration::from_millis(10));
-                                }
-                                println!("STATUS TIMER_STARTED ({}s countdown begins now)", secs);
-                                io::stdout().flush().ok();
-                                thread::sleep(Duration::from_secs(secs));
-                                recording_clone.store(false, Ordering::SeqCst);
-                                println!("STATUS STOPPED_BY_TIMER ({}s)", secs);
-                                io::stdout().flush().ok();
-                            });
-                        }
-                    } else {
-                        println!("ERROR bad STOP_AFTER arg");
+                if line.trim().is_empty() {
+                    continue;
+                }
+                let result = Command::parse(&line).and_then(|command| {
+                    controls.execute(&command)?;
+                    Ok(command)
+                });
+                match result {
+                    Ok(Command::Quit) => break,
+                    Ok(_) => {}
+                    Err(e) => {
+                        println!("ERROR {:#}", e);
                         io::stdout().flush().ok();
                     }
-                } else if cmd.eq_ignore_ascii_case("QUIT") {
-                    println!("STATUS QUIT");
-                    io::stdout().flush().ok();
-                    quit.store(true, Ordering::SeqCst);
-                    break;
-                } else if !cmd.is_empty() {
-                    println!("ERROR unknown command: {}", cmd);
-                    io::stdout().flush().ok();
                 }
             }
             Err(e) => {
@@ -85,3 +368,111 @@ pub fn handle_commands(
     }
     Ok(())
 }
+
+/// Run one control request (JSON object or text command) and build its JSON reply
+///
+/// Returns `None` for blank requests.
+pub fn handle_request(request: &str, handler: &dyn Fn(&Command) -> Result<()>) -> Option<String> {
+    let request = request.trim();
+    if request.is_empty() {
+        return None;
+    }
+    let (parsed, id) = if request.starts_with('{') {
+        match serde_json::from_str::<Value>(request) {
+            Ok(value) => (Command::from_json(&value), value.get("id").cloned()),
+            Err(e) => (Err(anyhow::anyhow!("invalid JSON: {}", e)), None),
+        }
+    } else {
+        (Command::parse(request), None)
+    };
+
+    let mut reply = match parsed.and_then(|command| handler(&command).map(|_| command)) {
+        Ok(command) => json!({ "ok": true, "command": command.name() }),
+        Err(e) => json!({ "ok": false, "error": format!("{:#}", e) }),
+    };
+    if let Some(id) = id {
+        reply["id"] = id;
+    }
+    Some(reply.to_string())
+}
+
+/// Accept control clients on `port` (all interfaces) and run their commands with `handler`
+///
+/// Every client gets its own thread; port 0 picks a free port. Returns the bound address.
+pub fn serve_control<F>(port: u16, handler: F) -> Result<SocketAddr>
+where
+    F: Fn(&Command) -> Result<()> + Send + Sync + 'static,
+{
+    let listener = TcpListener::bind(("0.0.0.0", port))
+        .with_context(|| format!("Failed to bind the control server on port {}", port))?;
+    let address = listener.local_addr()?;
+    let handler = Arc::new(handler);
+
+    thread::Builder::new().name("control-server".to_string()).spawn(move || {
+        for stream in listener.incoming() {
+            let stream = match stream {
+                Ok(stream) => stream,
+                Err(e) => {
+                    eprintln!("Control server failed to accept a client: {}", e);
+                    thread::sleep(ACCEPT_RETRY_DELAY);
+                    continue;
+                }
+            };
+            let handler = handler.clone();
+            thread::spawn(move || {
+                let peer = stream.peer_addr().map(|a| a.to_string()).unwrap_or_default();
+                if let Err(e) = serve_client(stream, &*handler) {
+                    eprintln!("Control client {} disconnected: {:#}", peer, e);
+                }
+            });
+        }
+    })?;
+    Ok(address)
+}
+
+/// Serve one control client, as WebSocket if it opens with an HTTP upgrade request
+pub(crate) fn serve_client(stream: TcpStream, handler: &dyn Fn(&Command) -> Result<()>) -> Result<()> {
+    stream.set_nodelay(true).ok();
+    if is_http_request(&stream)? {
+        let mut socket =
+            tungstenite::accept(stream).map_err(|e| anyhow::anyhow!("WebSocket handshake failed: {}", e))?;
+        loop {
+            match socket.read() {
+                Ok(Message::Text(text)) => {
+                    if let Some(reply) = handle_request(text.as_str(), handler) {
+                        socket.send(Message::text(reply))?;
+                    }
+                }
+                Ok(Message::Close(_)) | Err(tungstenite::Error::ConnectionClosed) => return Ok(()),
+                Ok(_) => {}
+                Err(e) => return Err(e.into()),
+            }
+        }
+    }
+
+    let mut writer = stream.try_clone()?;
+    for line in BufReader::new(stream).lines() {
+        if let Some(reply) = handle_request(&line?, handler) {
+            writeln!(writer, "{}", reply)?;
+            writer.flush()?;
+        }
+    }
+    Ok(())
+}
+
+/// Whether the client's first bytes are an HTTP `GET` (WebSocket upgrade)
+fn is_http_request(stream: &TcpStream) -> Result<bool> {
+    const GET: &[u8] = b"GET ";
+    stream.set_read_timeout(Some(FIRST_REQUEST_TIMEOUT))?;
+    let mut prefix = [0u8; 4];
+    // Wait until the prefix is complete or can no longer become "GET "
+    let result = loop {
+        let n = stream.peek(&mut prefix)?;
+        if n == 0 || n >= GET.len() || prefix[..n] != GET[..n] {
+            break n >= GET.len() && prefix == *GET;
+        }
+        thread::sleep(Duration::from_millis(10));
+    };
+    stream.set_read_timeout(None)?;
+    Ok(result)
+}
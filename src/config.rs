@@ -0,0 +1,332 @@
+//! Project configuration file (`lsl-toolbox.toml`)
+//!
+//! A lab describes its rig once in an `lsl-toolbox.toml` checked into the project
+//! directory instead of repeating long command lines. Every tool looks for the file
+//! in the current directory and its parents (or takes the path in
+//! `LSL_TOOLBOX_CONFIG`) and uses its values as defaults; options given on the
+//! command line always win.
+//!
+//! ```toml
+//! output = "data/{subject}/session_{date}"
+//! subject_pattern = "sub-##"
+//! flush_interval = 2.0
+//! flush_buffer_size = 100
+//! compressor = "zstd"
+//! compression_level = 7
+//!
+//! [[streams]]
+//! source_id = "EMG_1234"
+//! name = "EMG"
+//! plausible_range = "±10 mV"
+//!
+//! [[streams]]
+//! source_id = "EEG_5678"
+//! name = "EEG"
+//!
+//! # Any option of one tool, by its long name
+//! [tools.lsl-validate]
+//! threshold-ms = 50
+//! ```
+//!
+//! The settings at the top apply to the recorders ([`RECORDING_TOOLS`]), and the
+//! stream list becomes `--source-ids`/`--stream-names` of lsl-multi-recorder and
+//! lsl-spotcheck ([`STREAM_LIST_TOOLS`]); a stream list given on the command line
+//! replaces it, names included. The streams' plausibility ranges become
+//! `--plausible-range` of lsl-multi-recorder (see [`crate::plausibility`]). Options
+//! under `[tools.<binary>]` must exist for that tool. The TUI fills its forms with
+//! the same defaults.
+
+use anyhow::{Context, Result};
+use clap::parser::ValueSource;
+use clap::{Arg, ArgMatches, Command, Parser};
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::ffi::OsString;
+use std::path::{Path, PathBuf};
+
+use crate::plausibility::parse_plausible_range;
+
+/// Name of the project configuration file
+pub const CONFIG_FILE: &str = "lsl-toolbox.toml";
+
+/// Environment variable naming the configuration file to use instead of searching for one
+pub const CONFIG_ENV: &str = "LSL_TOOLBOX_CONFIG";
+
+/// Tools the shared settings (output, subject pattern, flushing, compression) apply to
+pub const RECORDING_TOOLS: &[&str] = &["lsl-recorder", "lsl-multi-recorder"];
+
+/// Tools that take the stream list as `--source-ids` and `--stream-names`
+pub const STREAM_LIST_TOOLS: &[&str] = &["lsl-multi-recorder", "lsl-spotcheck"];
+
+/// Settings of an `lsl-toolbox.toml`
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ProjectConfig {
+    /// File the settings were read from
+    #[serde(skip)]
+    pub path: Option<PathBuf>,
+    /// Store base path (`--output`); supports the usual placeholders
+    pub output: Option<String>,
+    /// Subject naming scheme for `--auto-subject` (`--subject-pattern`)
+    pub subject_pattern: Option<String>,
+    pub flush_interval: Option<f64>,
+    pub flush_buffer_size: Option<u64>,
+    pub chunk_samples: Option<u64>,
+    pub compressor: Option<String>,
+    pub compression_level: Option<u8>,
+    /// Streams of the rig, recorded by lsl-multi-recorder and checked by lsl-spotcheck
+    #[serde(default)]
+    pub streams: Vec<StreamEntry>,
+    /// Options of single tools by binary name, then by long option name
+    #[serde(default)]
+    pub tools: BTreeMap<String, BTreeMap<String, OptionValue>>,
+}
+
+/// One stream of the rig
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct StreamEntry {
+    pub source_id: String,
+    /// Stream group name (defaults to the source ID)
+    pub name: Option<String>,
+    /// Physically plausible values, e.g. `"±10 mV"` (see [`crate::plausibility`])
+    pub plausible_range: Option<String>,
+}
+
+/// Value of a tool option: a flag, a number, text, or a list for options taking several values
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(untagged)]
+pub enum OptionValue {
+    Flag(bool),
+    Integer(i64),
+    Float(f64),
+    Text(String),
+    List(Vec<OptionValue>),
+}
+
+impl OptionValue {
+    /// Command-line values of the option
+    pub fn values(&self) -> Vec<String> {
+        match self {
+            OptionValue::Flag(flag) => vec![flag.to_string()],
+            OptionValue::Integer(value) => vec![value.to_string()],
+            OptionValue::Float(value) => vec![value.to_string()],
+            OptionValue::Text(text) => vec![text.clone()],
+            OptionValue::List(items) => items.iter().flat_map(OptionValue::values).collect(),
+        }
+    }
+}
+
+impl ProjectConfig {
+    /// Parse the contents of an `lsl-toolbox.toml`
+    pub fn from_toml(text: &str) -> Result<Self> {
+        let config: ProjectConfig = toml::from_str(text)?;
+        let named = config.streams.iter().filter(|stream| stream.name.is_some()).count();
+        if named != 0 && named != config.streams.len() {
+            anyhow::bail!("Name every stream in [[streams]] or none of them");
+        }
+        for stream in &config.streams {
+            if let Some(ref range) = stream.plausible_range {
+                parse_plausible_range(range).with_context(|| format!("[[streams]] entry '{}'", stream.source_id))?;
+            }
+        }
+        Ok(config)
+    }
+
+    /// Read the configuration file at `path`
+    pub fn load(path: &Path) -> Result<Self> {
+        let text = std::fs::read_to_string(path).with_context(|| format!("Failed to read {}", path.display()))?;
+        let mut config = Self::from_toml(&text).with_context(|| format!("Invalid {}", path.display()))?;
+        config.path = Some(path.to_path_buf());
+        Ok(config)
+    }
+
+    /// The configuration in effect for the current directory; empty if there is none
+    pub fn discover() -> Result<Self> {
+        match config_path() {
+            Some(path) => Self::load(&path),
+            None => Ok(Self::default()),
+        }
+    }
+
+    /// Default values of `tool`'s options by argument ID (long name with `_`)
+    ///
+    /// Holds every shared setting for a recorder, whether or not it has the option;
+    /// settings under `[tools.<tool>]` take precedence.
+    pub fn defaults(&self, tool: &str) -> BTreeMap<String, Vec<String>> {
+        let mut defaults = BTreeMap::new();
+        let shared = [
+            ("output", self.output.clone()),
+            ("subject_pattern", self.subject_pattern.clone()),
+            ("flush_interval", self.flush_interval.map(|value| value.to_string())),
+            ("flush_buffer_size", self.flush_buffer_size.map(|value| value.to_string())),
+            ("chunk_samples", self.chunk_samples.map(|value| value.to_string())),
+            ("compressor", self.compressor.clone()),
+            ("compression_level", self.compression_level.map(|value| value.to_string())),
+        ];
+        for (id, value) in shared {
+            if let Some(value) = value
+                && RECORDING_TOOLS.contains(&tool)
+            {
+                defaults.insert(id.to_string(), vec![value]);
+            }
+        }
+        if !self.streams.is_empty() && STREAM_LIST_TOOLS.contains(&tool) {
+            let source_ids = self.streams.iter().map(|stream| stream.source_id.clone()).collect();
+            defaults.insert("source_ids".to_string(), source_ids);
+            let names: Vec<String> = self.streams.iter().filter_map(|stream| stream.name.clone()).collect();
+            if !names.is_empty() {
+                defaults.insert("stream_names".to_string(), names);
+            }
+            let ranges: Vec<String> = self
+                .streams
+                .iter()
+                .filter_map(|stream| {
+                    let range = stream.plausible_range.as_ref()?;
+                    Some(format!("{}: {}", stream.name.as_ref().unwrap_or(&stream.source_id), range))
+                })
+                .collect();
+            if !ranges.is_empty() {
+                defaults.insert("plausible_range".to_string(), ranges);
+            }
+        }
+        for (name, value) in self.tools.get(tool).into_iter().flatten() {
+            defaults.insert(argument_id(name), value.values());
+        }
+        defaults
+    }
+
+    /// Defaults of `tool` as TUI form values; several values are joined with commas
+    pub fn form_values(&self, tool: &str) -> BTreeMap<String, String> {
+        self.defaults(tool)
+            .into_iter()
+            .map(|(id, values)| (id, values.join(",")))
+            .collect()
+    }
+
+    /// Set the defaults of `tool` on its command
+    ///
+    /// Shared settings the command has no option for are skipped; an unknown option
+    /// under `[tools.<tool>]` is an error. An option with a default is no longer
+    /// required, so a configured stream list stands in for `--source-ids`.
+    pub fn apply(&self, tool: &str, mut command: Command) -> Result<Command> {
+        for name in self.tools.get(tool).into_iter().flat_map(BTreeMap::keys) {
+            if !has_argument(&command, &argument_id(name)) {
+                anyhow::bail!(
+                    "'{}' in [tools.{}] of {} is not an option of {}",
+                    name,
+                    tool,
+                    self.source(),
+                    tool
+                );
+            }
+        }
+        for (id, values) in self.defaults(tool) {
+            if !has_argument(&command, &id) {
+                continue;
+            }
+            let values: Vec<&'static str> = values.into_iter().map(leak).collect();
+            command = command.mut_arg(&id, |arg: Arg| {
+                arg.default_values(values)
+                    .required(false)
+                    .required_unless_present(None::<&'static str>)
+            });
+        }
+        Ok(command)
+    }
+
+    /// Where the settings come from, for messages
+    fn source(&self) -> String {
+        self.path
+            .as_ref()
+            .map_or_else(|| CONFIG_FILE.to_string(), |path| path.display().to_string())
+    }
+}
+
+/// The configuration file in effect: `LSL_TOOLBOX_CONFIG` if set and non-empty,
+/// else the nearest `lsl-toolbox.toml` from the current directory up
+pub fn config_path() -> Option<PathBuf> {
+    if let Some(path) = std::env::var_os(CONFIG_ENV).filter(|path| !path.is_empty()) {
+        return Some(PathBuf::from(path));
+    }
+    find_config(&std::env::current_dir().ok()?)
+}
+
+/// Nearest `lsl-toolbox.toml` in `start` or one of its parents
+pub fn find_config(start: &Path) -> Option<PathBuf> {
+    start
+        .ancestors()
+        .map(|dir| dir.join(CONFIG_FILE))
+        .find(|path| path.is_file())
+}
+
+/// Parse the command line of `tool` with the project configuration as defaults
+pub fn parse_args<T: Parser>(tool: &str) -> Result<T> {
+    parse_args_from(tool, std::env::args_os())
+}
+
+/// Like [`parse_args`], for an explicit argument list (program name first)
+pub fn parse_args_from<T, I, A>(tool: &str, args: I) -> Result<T>
+where
+    T: Parser,
+    I: IntoIterator<Item = A>,
+    A: Into<OsString> + Clone,
+{
+    let command = ProjectConfig::discover()?.apply(tool, T::command())?;
+    Ok(parse_matches(command, args))
+}
+
+/// Parse the command line of a multicall binary; each of the `subcommands` gets the
+/// defaults of the tool it runs, given as (subcommand, tool binary)
+pub fn parse_subcommand_args<T: Parser>(subcommands: &[(&str, &str)]) -> Result<T> {
+    let config = ProjectConfig::discover()?;
+    let mut command = T::command();
+    for &(name, tool) in subcommands {
+        let Some(subcommand) = command.find_subcommand(name) else { continue };
+        let subcommand = config.apply(tool, subcommand.clone())?;
+        command = command.mut_subcommand(name, |_| subcommand);
+    }
+    Ok(parse_matches(command, std::env::args_os()))
+}
+
+/// Parse `args` with `command`, exiting with clap's message on invalid arguments
+fn parse_matches<T, I, A>(command: Command, args: I) -> T
+where
+    T: Parser,
+    I: IntoIterator<Item = A>,
+    A: Into<OsString> + Clone,
+{
+    let mut matches = command.get_matches_from(args);
+    drop_configured_names(&mut matches);
+    T::from_arg_matches(&matches).unwrap_or_else(|e| e.format(&mut T::command()).exit())
+}
+
+/// A stream list given on the command line replaces the configured one, names included
+fn drop_configured_names(matches: &mut ArgMatches) {
+    if value_source(matches, "source_ids") == Some(ValueSource::CommandLine)
+        && value_source(matches, "stream_names") == Some(ValueSource::DefaultValue)
+    {
+        let _ = matches.try_remove_many::<String>("stream_names");
+    }
+    if let Some((_, subcommand)) = matches.subcommand_mut() {
+        drop_configured_names(subcommand);
+    }
+}
+
+fn value_source(matches: &ArgMatches, id: &str) -> Option<ValueSource> {
+    matches.ids().any(|known| known == id).then(|| matches.value_source(id)).flatten()
+}
+
+/// Argument ID of a long option name (`threshold-ms` -> `threshold_ms`)
+fn argument_id(name: &str) -> String {
+    name.trim_start_matches("--").replace('-', "_")
+}
+
+fn has_argument(command: &Command, id: &str) -> bool {
+    command.get_arguments().any(|arg| arg.get_id() == id)
+}
+
+/// clap takes borrowed defaults; the few configured values live for the whole run
+fn leak(value: String) -> &'static str {
+    Box::leak(value.into_boxed_str())
+}
@@ -0,0 +1,152 @@
+//! Per-stream connection statistics
+//!
+//! The recorder counts how often it had to query the network before the stream
+//! was found, the delays it waited between those queries, and every outage of a
+//! regular stream (silence of at least `--lost-timeout`, reported as
+//! `STATUS STREAM_LOST`) that ended with the inlet reconnecting. Each outage is
+//! also kept as an interval of LSL timestamps, and a stream that stays lost for
+//! `--reconnect-after` is re-resolved, counted as a reconnect attempt. When
+//! recording ends the totals are written to the stream's `connection` attribute,
+//! so analysis anomalies can later be matched with network trouble during
+//! acquisition; `lsl-inspect` and the `lsl-query` session summaries show them.
+
+use serde::{Deserialize, Serialize};
+use std::time::{Duration, Instant};
+
+/// Stream group attribute holding the [`ConnectionStats`]
+pub const CONNECTION_ATTRIBUTE: &str = "connection";
+
+/// Resolution and reconnection history of one recorded stream
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ConnectionStats {
+    /// Resolve queries sent until the stream was found (1 = first try)
+    pub resolution_attempts: u32,
+    /// Delay before each repeated resolve query (ms)
+    pub retry_delays_ms: Vec<u64>,
+    /// Outages after which the stream delivered samples again
+    pub reconnections: u32,
+    /// Outages, including one still ongoing when recording ended
+    pub outages: u32,
+    /// Time without samples over all outages, from the last sample before each (s)
+    pub total_downtime_s: f64,
+    /// Longest single outage (s)
+    pub longest_outage_s: f64,
+    /// Every outage, in the order they happened
+    #[serde(default)]
+    pub outage_intervals: Vec<OutageInterval>,
+    /// Times the lost stream was resolved anew (`--reconnect-after`)
+    #[serde(default)]
+    pub reconnect_attempts: u32,
+}
+
+/// Samples missing from the recording during one outage
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct OutageInterval {
+    /// LSL timestamp of the last sample before the outage
+    pub start: Option<f64>,
+    /// LSL timestamp of the first sample after it; `None` if recording ended first
+    pub end: Option<f64>,
+}
+
+impl ConnectionStats {
+    /// Read the statistics from stream group attributes
+    pub fn from_attributes(attributes: &serde_json::Value) -> Option<Self> {
+        attributes
+            .get(CONNECTION_ATTRIBUTE)
+            .and_then(|value| serde_json::from_value(value.clone()).ok())
+    }
+
+    /// Total delay spent waiting between resolve queries (ms)
+    pub fn total_retry_delay_ms(&self) -> u64 {
+        self.retry_delays_ms.iter().sum()
+    }
+
+    fn add_outage(&mut self, duration: Duration, interval: OutageInterval) {
+        let seconds = duration.as_secs_f64();
+        self.outages += 1;
+        self.outage_intervals.push(interval);
+        self.total_downtime_s += seconds;
+        self.longest_outage_s = self.longest_outage_s.max(seconds);
+    }
+}
+
+/// Collects [`ConnectionStats`] while recording
+#[derive(Debug, Clone, Default)]
+pub struct ConnectionTracker {
+    stats: ConnectionStats,
+    // Last sample before the ongoing outage, and its timestamp
+    lost_since: Option<Instant>,
+    lost_after: Option<f64>,
+}
+
+impl ConnectionTracker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// A resolve query is sent, after waiting `delay` (zero for the first query)
+    pub fn resolution_attempt(&mut self, delay: Duration) {
+        if self.stats.resolution_attempts > 0 {
+            self.stats.retry_delays_ms.push(delay.as_millis() as u64);
+        }
+        self.stats.resolution_attempts += 1;
+    }
+
+    /// The stream was reported lost; `last_sample` is when it last delivered a sample
+    pub fn stream_lost(&mut self, last_sample: Instant) {
+        self.stream_lost_after(last_sample, None);
+    }
+
+    /// Like [`stream_lost`](Self::stream_lost), with the LSL timestamp of that sample
+    pub fn stream_lost_after(&mut self, last_sample: Instant, last_timestamp: Option<f64>) {
+        if self.lost_since.is_none() {
+            self.lost_since = Some(last_sample);
+            self.lost_after = last_timestamp;
+        }
+    }
+
+    /// The lost stream delivered a sample again at `now`
+    pub fn stream_recovered(&mut self, now: Instant) {
+        self.stream_recovered_at(now, None);
+    }
+
+    /// Like [`stream_recovered`](Self::stream_recovered), with the LSL timestamp of the sample
+    pub fn stream_recovered_at(&mut self, now: Instant, timestamp: Option<f64>) {
+        if let Some(since) = self.lost_since.take() {
+            let interval = OutageInterval {
+                start: self.lost_after.take(),
+                end: timestamp,
+            };
+            self.stats.reconnections += 1;
+            self.stats.add_outage(now.saturating_duration_since(since), interval);
+        }
+    }
+
+    /// The lost stream is resolved anew
+    pub fn reconnect_attempt(&mut self) {
+        self.stats.reconnect_attempts += 1;
+    }
+
+    /// Whether an outage is ongoing
+    pub fn is_lost(&self) -> bool {
+        self.lost_since.is_some()
+    }
+
+    /// How long the ongoing outage has lasted at `now`
+    pub fn lost_for(&self, now: Instant) -> Option<Duration> {
+        self.lost_since.map(|since| now.saturating_duration_since(since))
+    }
+
+    /// Statistics at `now`, counting an ongoing outage up to then
+    pub fn finish(&self, now: Instant) -> ConnectionStats {
+        let mut stats = self.stats.clone();
+        if let Some(since) = self.lost_since {
+            let interval = OutageInterval {
+                start: self.lost_after,
+                end: None,
+            };
+            stats.add_outage(now.saturating_duration_since(since), interval);
+        }
+        stats
+    }
+}
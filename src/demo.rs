@@ -0,0 +1,470 @@
+//! Example store for tutorials and tests (`lsl-demo generate`)
+//!
+//! Trying lsl-inspect, lsl-sync or lsl-validate otherwise needs an amplifier, or at
+//! least two running dummy streams and a recorder. [`generate_demo_store`] writes a
+//! small session straight into the store layout instead, without LSL:
+//!
+//! - `EMG`: 4 channels at 1000 Hz, simulated bursts of contraction
+//! - `EEG`: 8 channels at 250 Hz, simulated background activity with an alpha
+//!   rhythm; `Oz` is a dead electrode (flat)
+//! - `ECG`: 1 channel at 500 Hz, simulated PQRST beats
+//! - `Markers`: irregular `trial_start`/`stimulus`/`response` events every 3 s
+//!
+//! The streams start a few milliseconds apart, as they would in a real recording,
+//! and their timestamps carry a little jitter. Besides the samples the store holds
+//! what the recorder and the post-processing tools add: the session manifest and
+//! devices table, experimenter annotations, each regular stream's `quality` summary
+//! and `bad_channels` mask, and `aligned_time` written by lsl-sync (`--trim-both`).
+//!
+//! Samples, timestamps and events depend only on [`DemoOptions`]: the same options
+//! give the same data, so documentation and tests can refer to exact values. Only
+//! the manifest records when and where the store was generated.
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, SecondsFormat, Utc};
+use clap::Parser;
+use ndarray::{Array1, Array2, Ix1, Ix2};
+use serde_json::json;
+use std::collections::BTreeMap;
+use std::ffi::OsStr;
+use std::path::Path;
+use std::sync::Arc;
+use zarrs::filesystem::FilesystemStore;
+
+use crate::annotations::{append_annotation, Annotation};
+use crate::badchannels::{store_bad_channels, BadChannel, BadChannelMask, ChannelStatistics, DetectionOptions};
+use crate::devices::update_devices_table;
+use crate::manifest::update_manifest_streams;
+use crate::quality::{QualityMonitor, QualityOptions, QUALITY_ATTRIBUTE};
+use crate::signals::{SignalGenerator, SignalOptions, Simulation, Waveform};
+use crate::zarr::layout::{create_group_if_not_exists, create_sample_array, create_time_array};
+use crate::zarr::time_index::{open_or_create_index_array, TimeIndex};
+use crate::zarr::{open_or_create_zarr_store, StorageOptions};
+
+/// LSL clock time at which the demo session starts
+pub const DEMO_START: f64 = 1000.0;
+
+/// Wall-clock time (UTC) of [`DEMO_START`]
+pub const DEMO_RECORDED_AT: &str = "2026-01-15T10:00:00Z";
+
+/// Standard deviation of the timestamp jitter in seconds
+const TIMESTAMP_JITTER: f64 = 20e-6;
+
+/// Seconds between the starts of two trials in the `Markers` stream
+const TRIAL_INTERVAL: f64 = 3.0;
+
+/// What to generate
+#[derive(Debug, Clone)]
+pub struct DemoOptions {
+    /// Length of the session in seconds
+    pub duration: f64,
+    /// Seed of the simulated signals, jitter and reaction times
+    pub seed: u64,
+    pub subject: String,
+    pub session_id: String,
+    /// Replace an existing store at the output path
+    pub overwrite: bool,
+}
+
+impl Default for DemoOptions {
+    fn default() -> Self {
+        Self {
+            duration: 30.0,
+            seed: 42,
+            subject: "demo01".to_string(),
+            session_id: "demo_session".to_string(),
+            overwrite: false,
+        }
+    }
+}
+
+/// One stream written to the demo store
+#[derive(Debug, Clone)]
+pub struct DemoStream {
+    pub name: String,
+    pub stream_type: String,
+    pub channels: usize,
+    /// Nominal rate in Hz (0 for the marker stream)
+    pub nominal_srate: f64,
+    pub samples: u64,
+}
+
+/// A simulated regular stream
+struct RegularSpec {
+    name: &'static str,
+    simulation: Simulation,
+    labels: &'static [&'static str],
+    sample_rate: f64,
+    /// Peak amplitude in microvolts of a full-scale generator value
+    scale: f64,
+    /// Start relative to [`DEMO_START`] in seconds
+    offset: f64,
+    /// Channel left at 0 (a dead electrode)
+    flat_channel: Option<usize>,
+    model: &'static str,
+    serial_number: &'static str,
+}
+
+const REGULAR_STREAMS: [RegularSpec; 3] = [
+    RegularSpec {
+        name: "EMG",
+        simulation: Simulation::Emg,
+        labels: &["EMG1", "EMG2", "EMG3", "EMG4"],
+        sample_rate: 1000.0,
+        scale: 500.0,
+        offset: 0.0,
+        flat_channel: None,
+        model: "Demo EMG amplifier",
+        serial_number: "DEMO-0001",
+    },
+    RegularSpec {
+        name: "EEG",
+        simulation: Simulation::Eeg,
+        labels: &["Fz", "Cz", "Pz", "C3", "C4", "O1", "O2", "Oz"],
+        sample_rate: 250.0,
+        scale: 50.0,
+        offset: 0.137,
+        flat_channel: Some(7),
+        model: "Demo EEG amplifier",
+        serial_number: "DEMO-0002",
+    },
+    RegularSpec {
+        name: "ECG",
+        simulation: Simulation::Ecg,
+        labels: &["ECG"],
+        sample_rate: 500.0,
+        scale: 1000.0,
+        offset: 0.052,
+        flat_channel: None,
+        model: "Demo EEG amplifier",
+        serial_number: "DEMO-0002",
+    },
+];
+
+/// Wall-clock anchor of an LSL clock time, in the layout of [`crate::zarr::time_anchor`]
+fn demo_time_anchor(lsl_clock: f64) -> Result<serde_json::Value> {
+    let start: DateTime<Utc> = DEMO_RECORDED_AT.parse()?;
+    let time = start + chrono::Duration::microseconds(((lsl_clock - DEMO_START) * 1e6).round() as i64);
+    Ok(json!({
+        "utc": time.to_rfc3339_opts(SecondsFormat::Micros, true),
+        "local": time.to_rfc3339_opts(SecondsFormat::Micros, false),
+        "lsl_clock": lsl_clock,
+    }))
+}
+
+/// Approximately normal value (sum of uniforms) with the given standard deviation
+fn gaussian(rng: &mut fastrand::Rng, std: f64) -> f64 {
+    let sum: f64 = (0..12).map(|_| rng.f64()).sum();
+    (sum - 6.0) * std
+}
+
+/// `stream_info` attribute in the recorder's layout
+fn stream_info(
+    name: &str,
+    stream_type: &str,
+    channel_format: &str,
+    nominal_srate: f64,
+    channels: serde_json::Value,
+    description: serde_json::Value,
+) -> serde_json::Value {
+    let channel_count = channels.as_array().map_or(1, Vec::len);
+    json!({
+        "name": name,
+        "type": stream_type,
+        "source_id": format!("demo_{}", name.to_lowercase()),
+        "hostname": "demo",
+        "channel_count": channel_count,
+        "nominal_srate": nominal_srate,
+        "channel_format": channel_format,
+        "created_at": DEMO_START,
+        "uid": format!("demo-{}", name.to_lowercase()),
+        "session_id": "default",
+        "version": 1.1,
+        "description": description,
+        "channels": channels,
+    })
+}
+
+/// Writes the streams of the demo store
+struct DemoWriter<'a> {
+    store: Arc<FilesystemStore>,
+    store_path: &'a Path,
+    options: &'a DemoOptions,
+    storage: StorageOptions,
+}
+
+impl DemoWriter<'_> {
+    /// Create a stream's group and arrays and write all samples at once
+    fn write_stream<T: zarrs::array::Element>(
+        &self,
+        name: &str,
+        channel_format: &str,
+        channels: usize,
+        values: Vec<T>,
+        timestamps: &[f64],
+    ) -> Result<()> {
+        let stream_path = format!("/{}", name);
+        create_group_if_not_exists(&self.store, &stream_path)?;
+
+        let events = channel_format == "String";
+        let data_path = format!("{}/{}", stream_path, if events { "events" } else { "data" });
+        let mut data = create_sample_array(&self.store, &data_path, channel_format, channels, events, &self.storage)?;
+        let mut time = create_time_array(&self.store, &format!("{}/time", stream_path), &self.storage)?;
+        let mut index_array = open_or_create_index_array(&self.store, name, self.storage.chunk_samples)?;
+
+        let samples = timestamps.len();
+        if events {
+            data.set_shape(vec![samples as u64])?;
+            data.store_array_subset_ndarray::<T, Ix1>(&[0], Array1::from_vec(values))?;
+        } else {
+            // Sample-major -> [channels, samples]
+            let block = Array2::from_shape_vec((samples, channels), values)?
+                .reversed_axes()
+                .as_standard_layout()
+                .into_owned();
+            data.set_shape(vec![channels as u64, samples as u64])?;
+            data.store_array_subset_ndarray::<T, Ix2>(&[0, 0], block)?;
+        }
+        time.set_shape(vec![samples as u64])?;
+        time.store_array_subset_ndarray::<f64, Ix1>(&[0], Array1::from_vec(timestamps.to_vec()))?;
+        TimeIndex::build(timestamps, self.storage.chunk_samples).store_rows(&mut index_array, 0)?;
+
+        data.store_metadata()?;
+        time.store_metadata()?;
+        index_array.store_metadata()?;
+        Ok(())
+    }
+
+    /// Group attributes shared by every stream, plus `extra`
+    fn store_attributes(
+        &self,
+        name: &str,
+        stream_info: serde_json::Value,
+        timestamps: &[f64],
+        extra: serde_json::Map<String, serde_json::Value>,
+    ) -> Result<()> {
+        let first = timestamps.first().copied().unwrap_or(DEMO_START);
+        let last = timestamps.last().copied().unwrap_or(DEMO_START);
+        let mut attributes = serde_json::Map::new();
+        attributes.insert("stream_info".to_string(), stream_info);
+        attributes.insert(
+            "recorder_config".to_string(),
+            json!({
+                "subject": self.options.subject,
+                "session_id": self.options.session_id,
+                "notes": "Example session generated by lsl-demo",
+                "chunk_samples": self.storage.chunk_samples,
+                "compressor": self.storage.compressor.to_string(),
+                "compression_level": self.storage.compression_level,
+                "shard_samples": self.storage.shard_samples,
+                "generated_by": "lsl-demo",
+                "seed": self.options.seed,
+                "recorder_version": env!("CARGO_PKG_VERSION"),
+            }),
+        );
+        attributes.insert("start_time".to_string(), demo_time_anchor(first)?);
+        attributes.insert("end_time".to_string(), demo_time_anchor(last)?);
+        attributes.insert("first_timestamp".to_string(), json!(first));
+        attributes.insert("last_timestamp".to_string(), json!(last));
+        attributes.extend(extra);
+
+        let mut group = zarrs::group::Group::open(self.store.clone(), &format!("/{}", name))?;
+        group.attributes_mut().extend(attributes);
+        group.store_metadata()?;
+        Ok(())
+    }
+
+    /// Simulate a regular stream with its quality summary and detected bad channels
+    fn regular_stream(&self, spec: &RegularSpec, index: u64) -> Result<DemoStream> {
+        let channels = spec.labels.len();
+        let mut generator = SignalGenerator::new(SignalOptions {
+            waveform: Waveform::Sine,
+            simulate: Some(spec.simulation),
+            frequencies: vec![10.0; channels],
+            chirp_range: (1.0, 50.0),
+            chirp_duration: 10.0,
+            trigger_frequency: None,
+            sample_rate: spec.sample_rate,
+            seed: self.options.seed.wrapping_add(index * 1000),
+        })?;
+        let mut jitter = fastrand::Rng::with_seed(self.options.seed.wrapping_add(index * 1000 + 500));
+
+        let labels: Vec<String> = spec.labels.iter().map(|label| label.to_string()).collect();
+        let mut quality = QualityMonitor::new(labels.clone(), spec.sample_rate, None, &QualityOptions::default());
+        let mut statistics = ChannelStatistics::new(channels);
+
+        let samples = (self.options.duration * spec.sample_rate).round() as usize;
+        let start = DEMO_START + spec.offset;
+        let mut values = Vec::with_capacity(samples * channels);
+        let mut timestamps = Vec::with_capacity(samples);
+        for i in 0..samples {
+            let mut sample: Vec<f32> = generator
+                .next_sample()
+                .into_iter()
+                .map(|value| (value * spec.scale) as f32)
+                .collect();
+            if let Some(flat) = spec.flat_channel {
+                sample[flat] = 0.0;
+            }
+            quality.push(&sample);
+            statistics.push_interleaved(&sample.iter().map(|v| *v as f64).collect::<Vec<_>>());
+            values.extend_from_slice(&sample);
+            timestamps.push(start + i as f64 / spec.sample_rate + gaussian(&mut jitter, TIMESTAMP_JITTER));
+        }
+        self.write_stream(spec.name, "Float32", channels, values, &timestamps)?;
+
+        let stream_type = spec.simulation.stream_type();
+        let channel_info: Vec<serde_json::Value> = labels
+            .iter()
+            .map(|label| json!({"label": label, "unit": "microvolts", "type": stream_type}))
+            .collect();
+        let description = json!({
+            "manufacturer": "LSL Recording Toolbox",
+            "model": spec.model,
+            "serial_number": spec.serial_number,
+        });
+        let mut extra = serde_json::Map::new();
+        extra.insert(QUALITY_ATTRIBUTE.to_string(), quality.summary());
+        self.store_attributes(
+            spec.name,
+            stream_info(spec.name, stream_type, "Float32", spec.sample_rate, json!(channel_info), description),
+            &timestamps,
+            extra,
+        )?;
+
+        let mut mask = BadChannelMask::new(channels);
+        for (index, reason) in statistics.detect(&DetectionOptions::default()) {
+            mask.mark(BadChannel {
+                index,
+                label: labels[index].clone(),
+                reason,
+                source: "detected".to_string(),
+            });
+        }
+        store_bad_channels(self.store_path, spec.name, &mask)?;
+
+        Ok(DemoStream {
+            name: spec.name.to_string(),
+            stream_type: stream_type.to_string(),
+            channels,
+            nominal_srate: spec.sample_rate,
+            samples: samples as u64,
+        })
+    }
+
+    /// Trial events: start, stimulus 0.5 s later and a response after a random reaction time
+    fn marker_stream(&self) -> Result<DemoStream> {
+        let mut rng = fastrand::Rng::with_seed(self.options.seed.wrapping_add(9000));
+        let mut events = Vec::new();
+        let mut timestamps = Vec::new();
+        let mut trial_start = DEMO_START + 1.0;
+        while trial_start + TRIAL_INTERVAL <= DEMO_START + self.options.duration {
+            let stimulus = trial_start + 0.5;
+            let response = stimulus + 0.35 + 0.3 * rng.f64();
+            for (event, time) in [("trial_start", trial_start), ("stimulus", stimulus), ("response", response)] {
+                events.push(event.to_string());
+                timestamps.push(time);
+            }
+            trial_start += TRIAL_INTERVAL;
+        }
+
+        let mut event_counts: BTreeMap<String, u64> = BTreeMap::new();
+        for event in &events {
+            *event_counts.entry(event.clone()).or_insert(0) += 1;
+        }
+        let samples = events.len() as u64;
+        self.write_stream("Markers", "String", 1, events, &timestamps)?;
+
+        let mut extra = serde_json::Map::new();
+        extra.insert("event_count".to_string(), json!(samples));
+        extra.insert("event_counts".to_string(), json!(event_counts));
+        self.store_attributes(
+            "Markers",
+            stream_info(
+                "Markers",
+                "Markers",
+                "String",
+                0.0,
+                json!([{"label": "Marker", "type": "Markers"}]),
+                json!({}),
+            ),
+            &timestamps,
+            extra,
+        )?;
+
+        Ok(DemoStream {
+            name: "Markers".to_string(),
+            stream_type: "Markers".to_string(),
+            channels: 1,
+            nominal_srate: 0.0,
+            samples,
+        })
+    }
+}
+
+/// Write the demo session to a new store at `store_path`
+///
+/// Fails if the path exists, unless `options.overwrite` is set and it holds a Zarr
+/// store. Prints the output of the lsl-sync run that adds `aligned_time`.
+pub fn generate_demo_store(store_path: &Path, options: &DemoOptions) -> Result<Vec<DemoStream>> {
+    if !options.duration.is_finite() || options.duration < 2.0 * TRIAL_INTERVAL {
+        anyhow::bail!("The demo session must last at least {} s", 2.0 * TRIAL_INTERVAL);
+    }
+    if store_path.exists() {
+        if !options.overwrite {
+            anyhow::bail!("{} already exists (pass --force to replace it)", store_path.display());
+        }
+        if !store_path.join("zarr.json").exists() {
+            anyhow::bail!("{} exists and is not a Zarr store; not replacing it", store_path.display());
+        }
+        std::fs::remove_dir_all(store_path)
+            .with_context(|| format!("Failed to remove {}", store_path.display()))?;
+    }
+
+    let store = open_or_create_zarr_store(
+        store_path,
+        Some(&options.subject),
+        Some(&options.session_id),
+        Some("Example session generated by lsl-demo"),
+    )?;
+    let writer = DemoWriter {
+        store,
+        store_path,
+        options,
+        storage: StorageOptions::default(),
+    };
+
+    let mut streams = Vec::new();
+    for (index, spec) in REGULAR_STREAMS.iter().enumerate() {
+        let stream = writer
+            .regular_stream(spec, index as u64)
+            .with_context(|| format!("Failed to write stream '{}'", spec.name))?;
+        streams.push(stream);
+    }
+    streams.push(writer.marker_stream().context("Failed to write stream 'Markers'")?);
+    update_devices_table(store_path)?;
+    update_manifest_streams(store_path)?;
+
+    for (label, time) in [
+        ("baseline start", DEMO_START + 0.5),
+        ("Oz electrode loose", DEMO_START + options.duration / 2.0),
+        ("end of task", DEMO_START + options.duration - 1.0),
+    ] {
+        append_annotation(
+            store_path,
+            &Annotation {
+                label: label.to_string(),
+                time,
+            },
+        )?;
+    }
+
+    let sync_args = crate::tools::sync::Args::try_parse_from([
+        OsStr::new("lsl-sync"),
+        store_path.as_os_str(),
+        OsStr::new("--trim-both"),
+    ])?;
+    crate::tools::sync::run(sync_args).context("Failed to synchronize the demo streams")?;
+
+    Ok(streams)
+}
@@ -0,0 +1,159 @@
+//! Store-level table of acquisition devices
+//!
+//! Amplifiers and other acquisition devices describe themselves in the stream header,
+//! usually as `<desc><acquisition><manufacturer>`, `<model>` and `<serial_number>`.
+//! The recorder and lsl-import collect these into the `devices` attribute of the
+//! store's `meta` group, one row per physical device:
+//!
+//! ```json
+//! [{"manufacturer": "BrainProducts", "model": "actiCHamp", "serial_number": "17010768",
+//!   "streams": ["EEG", "EEG_aux"]}]
+//! ```
+//!
+//! Streams from the same device (same manufacturer, model and serial number) share a
+//! row. The table is rebuilt from the streams' `stream_info` each time, so it also
+//! works for stores recorded before it existed (`lsl-inspect` falls back to that).
+
+use anyhow::Result;
+use fs2::FileExt;
+use serde_json::json;
+use std::fs::OpenOptions;
+use std::path::Path;
+use std::sync::Arc;
+use zarrs::filesystem::FilesystemStore;
+
+use crate::export::list_streams;
+use crate::zarr::layout::create_group_if_not_exists;
+use crate::zarr::{read_group_attributes, META_GROUP};
+
+/// Name of the `meta` group attribute holding the table
+pub const DEVICES_ATTRIBUTE: &str = "devices";
+
+/// One acquisition device and the streams recorded from it
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Device {
+    pub manufacturer: Option<String>,
+    pub model: Option<String>,
+    pub serial_number: Option<String>,
+    pub streams: Vec<String>,
+}
+
+impl Device {
+    /// Device described in a stream's `stream_info` attribute, if any
+    ///
+    /// Reads the flattened header description, where the `<acquisition>` fields end
+    /// up as `manufacturer`, `model` and `serial_number` (or `serial`).
+    pub fn from_stream_info(stream_info: &serde_json::Value) -> Option<Self> {
+        let description = stream_info.get("description")?;
+        let field = |keys: &[&str]| {
+            keys.iter()
+                .filter_map(|key| description.get(*key).and_then(|v| v.as_str()))
+                .map(str::trim)
+                .find(|value| !value.is_empty())
+                .map(String::from)
+        };
+        let device = Self {
+            manufacturer: field(&["manufacturer"]),
+            model: field(&["model"]),
+            serial_number: field(&["serial_number", "serial"]),
+            streams: Vec::new(),
+        };
+        (device.manufacturer.is_some() || device.model.is_some() || device.serial_number.is_some()).then_some(device)
+    }
+
+    fn same_device(&self, other: &Device) -> bool {
+        self.manufacturer == other.manufacturer
+            && self.model == other.model
+            && self.serial_number == other.serial_number
+    }
+
+    pub fn to_json(&self) -> serde_json::Value {
+        json!({
+            "manufacturer": self.manufacturer,
+            "model": self.model,
+            "serial_number": self.serial_number,
+            "streams": self.streams,
+        })
+    }
+
+    pub fn from_json(value: &serde_json::Value) -> Self {
+        let text = |key: &str| value.get(key).and_then(|v| v.as_str()).map(String::from);
+        Self {
+            manufacturer: text("manufacturer"),
+            model: text("model"),
+            serial_number: text("serial_number"),
+            streams: value
+                .get("streams")
+                .and_then(|v| v.as_array())
+                .map(|a| a.iter().filter_map(|s| s.as_str().map(String::from)).collect())
+                .unwrap_or_default(),
+        }
+    }
+}
+
+/// Deduplicated device table from `(stream name, stream_info)` pairs, in stream order
+pub fn devices_from_streams(streams: &[(String, serde_json::Value)]) -> Vec<Device> {
+    let mut devices: Vec<Device> = Vec::new();
+    for (name, stream_info) in streams {
+        let Some(device) = Device::from_stream_info(stream_info) else {
+            continue;
+        };
+        match devices.iter_mut().find(|d| d.same_device(&device)) {
+            Some(existing) => existing.streams.push(name.clone()),
+            None => devices.push(Device {
+                streams: vec![name.clone()],
+                ..device
+            }),
+        }
+    }
+    devices
+}
+
+/// Device table built from the `stream_info` of every stream in the store
+pub fn collect_devices(store_path: &Path) -> Result<Vec<Device>> {
+    let store = Arc::new(FilesystemStore::new(store_path)?);
+    let streams: Vec<_> = list_streams(store_path)?
+        .into_iter()
+        .filter_map(|name| {
+            let attrs = read_group_attributes(&store, &format!("/{}", name)).ok()?;
+            Some((name, attrs.get("stream_info")?.clone()))
+        })
+        .collect();
+    Ok(devices_from_streams(&streams))
+}
+
+/// Device table stored in the `meta` group, if the store has one
+pub fn read_devices(store_path: &Path) -> Option<Vec<Device>> {
+    let store = Arc::new(FilesystemStore::new(store_path).ok()?);
+    let attrs = read_group_attributes(&store, &format!("/{}", META_GROUP)).ok()?;
+    let rows = attrs.get(DEVICES_ATTRIBUTE)?.as_array()?;
+    Some(rows.iter().map(Device::from_json).collect())
+}
+
+/// Rebuild the device table of a store and write it to the `meta` group
+///
+/// Holds the store's metadata lock, so recorders adding streams to the same store
+/// can call this concurrently.
+pub fn update_devices_table(store_path: &Path) -> Result<Vec<Device>> {
+    let lock_file = OpenOptions::new()
+        .create(true)
+        .write(true)
+        .truncate(false)
+        .open(store_path.join(".zarr_metadata.lock"))?;
+    lock_file.lock_exclusive()?;
+    let result = (|| -> Result<Vec<Device>> {
+        let devices = collect_devices(store_path)?;
+        let store = Arc::new(FilesystemStore::new(store_path)?);
+        let meta_path = format!("/{}", META_GROUP);
+        create_group_if_not_exists(&store, &meta_path)?;
+        let mut group = zarrs::group::Group::open(store, &meta_path)?;
+        group.attributes_mut().insert(
+            DEVICES_ATTRIBUTE.to_string(),
+            devices.iter().map(Device::to_json).collect(),
+        );
+        group.store_metadata()?;
+        Ok(devices)
+    })();
+    lock_file.unlock()?;
+    result
+}
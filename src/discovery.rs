@@ -0,0 +1,117 @@
+//! Network discovery of LSL streams
+//!
+//! Resolves every stream currently visible on the network and reduces each
+//! `StreamInfo` to a plain, serializable summary that can be printed as a table
+//! or emitted as JSON.
+
+use anyhow::Result;
+use serde::Serialize;
+
+/// Summary of a stream visible on the network
+#[derive(Debug, Clone, Serialize)]
+pub struct DiscoveredStream {
+    pub name: String,
+    pub stream_type: String,
+    pub source_id: String,
+    pub channel_count: i32,
+    pub nominal_srate: f64,
+    pub channel_format: String,
+    pub hostname: String,
+    pub uid: String,
+}
+
+impl DiscoveredStream {
+    /// Build a summary from a resolved stream info
+    pub fn from_info(info: &lsl::StreamInfo) -> Self {
+        Self {
+            name: info.stream_name(),
+            stream_type: info.stream_type(),
+            source_id: info.source_id(),
+            channel_count: info.channel_count(),
+            nominal_srate: info.nominal_srate(),
+            channel_format: format!("{:?}", info.channel_format()),
+            hostname: info.hostname(),
+            uid: info.uid(),
+        }
+    }
+
+    /// Human-readable sample rate ("irregular" for event streams)
+    pub fn rate_label(&self) -> String {
+        if self.nominal_srate > 0.0 {
+            format!("{} Hz", self.nominal_srate)
+        } else {
+            "irregular".to_string()
+        }
+    }
+}
+
+/// Resolve all streams visible on the network, sorted by name then source_id
+pub fn discover_streams(timeout: f64) -> Result<Vec<DiscoveredStream>> {
+    let infos = lsl::resolve_streams(timeout).map_err(|e| anyhow::anyhow!("LSL error: {}", e))?;
+
+    let mut streams: Vec<DiscoveredStream> = infos.iter().map(DiscoveredStream::from_info).collect();
+    sort_streams(&mut streams);
+    Ok(streams)
+}
+
+/// Sort streams by name, source_id and uid, keeping one entry per outlet
+///
+/// The same outlet can answer a resolve query more than once; sorting on the uid
+/// last puts its answers next to each other even when another outlet (e.g. a
+/// restarted app) shares its name and source_id.
+pub fn sort_streams(streams: &mut Vec<DiscoveredStream>) {
+    streams.sort_by(|a, b| {
+        a.name
+            .cmp(&b.name)
+            .then_with(|| a.source_id.cmp(&b.source_id))
+            .then_with(|| a.uid.cmp(&b.uid))
+    });
+    streams.dedup_by(|a, b| a.uid == b.uid);
+}
+
+/// Render streams as an aligned text table
+pub fn format_table(streams: &[DiscoveredStream]) -> String {
+    let headers = ["NAME", "TYPE", "SOURCE_ID", "CHANNELS", "RATE", "FORMAT", "HOST"];
+    let rows: Vec<[String; 7]> = streams
+        .iter()
+        .map(|s| {
+            [
+                s.name.clone(),
+                s.stream_type.clone(),
+                s.source_id.clone(),
+                s.channel_count.to_string(),
+                s.rate_label(),
+                s.channel_format.clone(),
+                s.hostname.clone(),
+            ]
+        })
+        .collect();
+
+    let mut widths: Vec<usize> = headers.iter().map(|h| h.len()).collect();
+    for row in &rows {
+        for (width, cell) in widths.iter_mut().zip(row.iter()) {
+            *width = (*width).max(cell.chars().count());
+        }
+    }
+
+    let format_row = |cells: &[String]| -> String {
+        cells
+            .iter()
+            .zip(widths.iter())
+            .map(|(cell, width)| format!("{:<width$}", cell, width = width))
+            .collect::<Vec<_>>()
+            .join("  ")
+            .trim_end()
+            .to_string()
+    };
+
+    let mut out = String::new();
+    let header_cells: Vec<String> = headers.iter().map(|h| h.to_string()).collect();
+    out.push_str(&format_row(&header_cells));
+    out.push('\n');
+    for row in &rows {
+        out.push_str(&format_row(row));
+        out.push('\n');
+    }
+    out
+}
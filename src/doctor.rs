@@ -0,0 +1,328 @@
+//! Environment diagnostics for `lsl-toolbox doctor`
+//!
+//! Collects the information usually needed to debug "no streams found" or slow
+//! write reports: library versions, available codecs, LSL network configuration,
+//! multicast/firewall sanity checks and a write-speed test of the target directory.
+
+use anyhow::Result;
+use std::io::Write;
+use std::net::{Ipv4Addr, SocketAddrV4, UdpSocket};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use zarrs::array::codec::{BloscCodec, BloscCompressionLevel, BloscCompressor, BloscShuffleMode};
+use zarrs::array::{ArrayBuilder, DataType, FillValue};
+use zarrs::array_subset::ArraySubset;
+use zarrs::filesystem::FilesystemStore;
+
+/// Default IPv4 multicast group used by liblsl for site-local resolution
+const LSL_MULTICAST_GROUP: Ipv4Addr = Ipv4Addr::new(239, 255, 172, 215);
+
+/// Options controlling which checks `run_doctor` performs
+#[derive(Debug, Clone)]
+pub struct DoctorOptions {
+    /// Directory used for the codec round-trip and write-speed test
+    pub target_dir: PathBuf,
+    /// Size of the write-speed test file in MiB (0 skips the test)
+    pub write_test_mb: usize,
+    /// Run multicast and stream resolution checks
+    pub network_checks: bool,
+    /// Timeout for LSL resolution checks in seconds
+    pub resolve_timeout: f64,
+}
+
+impl Default for DoctorOptions {
+    fn default() -> Self {
+        Self {
+            target_dir: PathBuf::from("."),
+            write_test_mb: 64,
+            network_checks: true,
+            resolve_timeout: 2.0,
+        }
+    }
+}
+
+/// Print the full diagnostics report
+pub fn run_doctor(options: &DoctorOptions) -> Result<()> {
+    println!("╔════════════════════════════════════════════════════════════════╗");
+    println!("║              LSL Toolbox Doctor                                ║");
+    println!("╚════════════════════════════════════════════════════════════════╝");
+    println!();
+
+    print_versions();
+    print_codecs(&options.target_dir);
+    print_lsl_config();
+    if options.network_checks {
+        print_network_checks(options.resolve_timeout);
+    }
+    print_storage(&options.target_dir, options.write_test_mb);
+
+    Ok(())
+}
+
+fn print_versions() {
+    println!("VERSIONS");
+    println!("\tlsl-recording-toolbox:\t{}", env!("CARGO_PKG_VERSION"));
+    #[cfg(feature = "lsl")]
+    {
+        let lib_version = lsl::library_version();
+        println!(
+            "\tliblsl:\t\t\t{}.{} ({})",
+            lib_version / 100,
+            lib_version % 100,
+            lsl::library_info()
+        );
+        let protocol = lsl::protocol_version();
+        println!("\tLSL protocol:\t\t{}.{}", protocol / 100, protocol % 100);
+    }
+    #[cfg(not(feature = "lsl"))]
+    println!("\tliblsl:\t\t\tnot linked (built without the `lsl` feature)");
+    // Resolved from Cargo.lock by build.rs; missing when the build had no lock file
+    println!(
+        "\tzarrs:\t\t\t{}",
+        option_env!("LSL_TOOLBOX_ZARRS_VERSION").unwrap_or("unknown")
+    );
+    println!("\tOS:\t\t\t{} ({})", std::env::consts::OS, std::env::consts::ARCH);
+    println!();
+}
+
+fn print_codecs(target_dir: &Path) {
+    println!("CODECS (round-trip through a temporary store)");
+
+    let compressors = [
+        ("blosc/lz4", BloscCompressor::LZ4),
+        ("blosc/lz4hc", BloscCompressor::LZ4HC),
+        ("blosc/blosclz", BloscCompressor::BloscLZ),
+        ("blosc/zlib", BloscCompressor::Zlib),
+        ("blosc/zstd", BloscCompressor::Zstd),
+    ];
+
+    let store_path = target_dir.join(".lsl_doctor_codecs.zarr");
+    for (label, compressor) in compressors {
+        match codec_round_trip(&store_path, label, compressor) {
+            Ok(()) => println!("\t{:<16}\tOK", label),
+            Err(e) => println!("\t{:<16}\tUNAVAILABLE ({})", label, e),
+        }
+    }
+    let _ = std::fs::remove_dir_all(&store_path);
+    println!();
+}
+
+/// Write and read back a small array compressed with the given Blosc compressor
+fn codec_round_trip(store_path: &Path, label: &str, compressor: BloscCompressor) -> Result<()> {
+    std::fs::create_dir_all(store_path)?;
+    let store = Arc::new(FilesystemStore::new(store_path)?);
+
+    let level = BloscCompressionLevel::try_from(5u8)
+        .map_err(|e| anyhow::anyhow!("Invalid compression level: {}", e))?;
+    let codec = Arc::new(BloscCodec::new(
+        compressor,
+        level,
+        None,
+        BloscShuffleMode::BitShuffle,
+        Some(4),
+    )?);
+
+    let path = format!("/{}", label.replace('/', "_"));
+    let array = ArrayBuilder::new(vec![64], vec![64], DataType::Float32, FillValue::from(0.0f32))
+        .bytes_to_bytes_codecs(vec![codec])
+        .build(store.clone(), &path)?;
+    array.store_metadata()?;
+
+    let values = ndarray::Array1::from_iter((0..64).map(|i| (i as f32 * 0.1).sin()));
+    array.store_array_subset_ndarray(&[0], values.clone())?;
+
+    let subset = ArraySubset::new_with_shape(vec![64]);
+    let read: ndarray::ArrayD<f32> = array.retrieve_array_subset_ndarray(&subset)?;
+    if read.iter().zip(values.iter()).any(|(a, b)| a != b) {
+        anyhow::bail!("data mismatch after round-trip");
+    }
+    Ok(())
+}
+
+fn print_lsl_config() {
+    println!("LSL NETWORK CONFIGURATION");
+
+    let mut candidates: Vec<PathBuf> = Vec::new();
+    if let Ok(path) = std::env::var("LSLAPICFG") {
+        candidates.push(PathBuf::from(path));
+    }
+    candidates.push(PathBuf::from("lsl_api.cfg"));
+    if let Some(home) = std::env::var_os("HOME").or_else(|| std::env::var_os("USERPROFILE")) {
+        candidates.push(PathBuf::from(home).join("lsl_api").join("lsl_api.cfg"));
+    }
+    candidates.push(PathBuf::from("/etc/lsl_api/lsl_api.cfg"));
+
+    let Some(config_path) = candidates.iter().find(|p| p.is_file()) else {
+        println!("\tNo lsl_api.cfg found (liblsl defaults in use)");
+        println!("\tSearched:");
+        for path in &candidates {
+            println!("\t  {}", path.display());
+        }
+        println!();
+        return;
+    };
+
+    println!("\tConfig file:\t{}", config_path.display());
+    match std::fs::read_to_string(config_path) {
+        Ok(contents) => {
+            let mut section = String::new();
+            for line in contents.lines() {
+                let line = line.trim();
+                if line.is_empty() || line.starts_with(';') || line.starts_with('#') {
+                    continue;
+                }
+                if line.starts_with('[') {
+                    section = line.trim_matches(|c| c == '[' || c == ']').to_string();
+                } else {
+                    println!("\t{}.{}", section, line);
+                }
+            }
+        }
+        Err(e) => println!("\tCould not read config: {}", e),
+    }
+    println!();
+}
+
+fn print_network_checks(resolve_timeout: f64) {
+    println!("NETWORK CHECKS");
+
+    match multicast_loopback_check() {
+        Ok(latency) => println!(
+            "\tMulticast loopback ({}):\tOK ({:.2} ms)",
+            LSL_MULTICAST_GROUP,
+            latency.as_secs_f64() * 1000.0
+        ),
+        Err(e) => println!(
+            "\tMulticast loopback ({}):\tFAILED ({}) - check firewall/multicast routing",
+            LSL_MULTICAST_GROUP, e
+        ),
+    }
+
+    #[cfg(feature = "lsl")]
+    print_resolution_checks(resolve_timeout);
+    #[cfg(not(feature = "lsl"))]
+    {
+        let _ = resolve_timeout;
+        println!("\tLSL resolution checks:\t\tskipped (built without the `lsl` feature)");
+    }
+    println!();
+}
+
+/// Resolve a temporary local outlet and count the streams visible on the network
+#[cfg(feature = "lsl")]
+fn print_resolution_checks(resolve_timeout: f64) {
+    match self_resolution_check(resolve_timeout) {
+        Ok(elapsed) => println!(
+            "\tResolve own outlet:\t\tOK ({:.0} ms)",
+            elapsed.as_secs_f64() * 1000.0
+        ),
+        Err(e) => println!(
+            "\tResolve own outlet:\t\tFAILED ({}) - local firewall is likely blocking LSL",
+            e
+        ),
+    }
+
+    let start = Instant::now();
+    match lsl::resolve_streams(resolve_timeout) {
+        Ok(streams) => println!(
+            "\tVisible streams:\t\t{} (resolved in {:.1}s)",
+            streams.len(),
+            start.elapsed().as_secs_f64()
+        ),
+        Err(e) => println!("\tVisible streams:\t\tFAILED ({})", e),
+    }
+}
+
+/// Send a datagram to the LSL multicast group and wait for it to loop back
+fn multicast_loopback_check() -> Result<Duration> {
+    let socket = UdpSocket::bind(SocketAddrV4::new(Ipv4Addr::UNSPECIFIED, 0))?;
+    socket.join_multicast_v4(&LSL_MULTICAST_GROUP, &Ipv4Addr::UNSPECIFIED)?;
+    socket.set_multicast_loop_v4(true)?;
+    socket.set_read_timeout(Some(Duration::from_secs(1)))?;
+
+    let port = socket.local_addr()?.port();
+    let payload = format!("lsl-toolbox-doctor-{}", fastrand::u64(..));
+    let start = Instant::now();
+    socket.send_to(payload.as_bytes(), SocketAddrV4::new(LSL_MULTICAST_GROUP, port))?;
+
+    let mut buf = [0u8; 128];
+    loop {
+        let (len, _) = socket
+            .recv_from(&mut buf)
+            .map_err(|e| anyhow::anyhow!("no loopback datagram received: {}", e))?;
+        if &buf[..len] == payload.as_bytes() {
+            return Ok(start.elapsed());
+        }
+    }
+}
+
+/// Create a temporary outlet and check that it can be resolved from this machine
+#[cfg(feature = "lsl")]
+fn self_resolution_check(timeout: f64) -> Result<Duration> {
+    let source_id = format!("lsl-toolbox-doctor-{}", fastrand::u64(..));
+    let info = lsl::StreamInfo::new(
+        "DoctorProbe",
+        "Diagnostics",
+        1,
+        0.0,
+        lsl::ChannelFormat::Float32,
+        &source_id,
+    )
+    .map_err(|e| anyhow::anyhow!("LSL error: {}", e))?;
+    let _outlet = lsl::StreamOutlet::new(&info, 0, 1).map_err(|e| anyhow::anyhow!("LSL error: {}", e))?;
+
+    let start = Instant::now();
+    let streams = lsl::resolve_byprop("source_id", &source_id, 1, timeout)
+        .map_err(|e| anyhow::anyhow!("LSL error: {}", e))?;
+    if streams.is_empty() {
+        anyhow::bail!("not found within {}s", timeout);
+    }
+    Ok(start.elapsed())
+}
+
+fn print_storage(target_dir: &Path, write_test_mb: usize) {
+    println!("STORAGE ({})", target_dir.display());
+
+    match fs2::available_space(target_dir) {
+        Ok(bytes) => println!(
+            "\tFree space:\t{:.1} GiB",
+            bytes as f64 / (1024.0 * 1024.0 * 1024.0)
+        ),
+        Err(e) => println!("\tFree space:\tunknown ({})", e),
+    }
+
+    if write_test_mb == 0 {
+        println!("\tWrite test:\tskipped");
+    } else {
+        match write_speed_test(target_dir, write_test_mb) {
+            Ok(mb_per_sec) => println!(
+                "\tWrite speed:\t{:.1} MiB/s ({} MiB, fsync included)",
+                mb_per_sec, write_test_mb
+            ),
+            Err(e) => println!("\tWrite speed:\tFAILED ({})", e),
+        }
+    }
+    println!();
+}
+
+/// Write `size_mb` MiB to a temporary file and return the throughput in MiB/s
+fn write_speed_test(target_dir: &Path, size_mb: usize) -> Result<f64> {
+    let path = target_dir.join(".lsl_doctor_write_test.tmp");
+    let block: Vec<u8> = (0..1024 * 1024).map(|_| fastrand::u8(..)).collect();
+
+    let start = Instant::now();
+    let result = (|| -> Result<()> {
+        let mut file = std::fs::File::create(&path)?;
+        for _ in 0..size_mb {
+            file.write_all(&block)?;
+        }
+        file.sync_all()?;
+        Ok(())
+    })();
+    let elapsed = start.elapsed().as_secs_f64();
+    let _ = std::fs::remove_file(&path);
+    result?;
+
+    Ok(size_mb as f64 / elapsed.max(f64::EPSILON))
+}
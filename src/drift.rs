@@ -0,0 +1,279 @@
+//! Clock-drift regression between pairs of streams
+//!
+//! Two devices sampling at their nominal rates produce samples at the same elapsed
+//! time if their clocks agree. Pairing each sample of one stream with the other
+//! stream's timestamp at the same nominal elapsed time (interpolated between its
+//! neighbouring samples), the difference should stay constant; a linear trend in
+//! it is clock drift. The slope of a least-squares fit of that difference over the
+//! first stream's elapsed time is the relative drift (reported in ppm), the
+//! intercept the offset at its first sample, and R² how well a constant drift
+//! explains the data.
+//!
+//! The pairing assumes no samples were dropped; dropouts (see [`crate::gaps`])
+//! show up as steps in the difference and lower R². Irregular streams have no
+//! sample clock and are not fitted.
+//!
+//! [`DriftModel`] turns such fits into a correction (`lsl-sync --mode drift-correct`):
+//! the recording is split into equal segments, each fitted on its own, and the
+//! difference is interpolated linearly between the segment boundaries (each
+//! boundary taking the mean of the two adjacent fits), so a single segment is the
+//! plain linear fit and more segments follow drift that changes over the session.
+//! Correcting a stream subtracts the drift accumulated since its first sample and
+//! keeps the offset between the streams' starts.
+
+use serde::{Deserialize, Serialize};
+
+/// Default drift above which `lsl-validate` warns (ppm)
+pub const DEFAULT_DRIFT_BUDGET_PPM: f64 = 50.0;
+
+/// Most sample pairs used for one fit; longer recordings are subsampled evenly
+pub const MAX_FIT_POINTS: usize = 10_000;
+
+/// Least-squares line `y = intercept + slope * x`
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct LinearFit {
+    pub slope: f64,
+    pub intercept: f64,
+    /// Coefficient of determination (1 when `y` is constant)
+    pub r_squared: f64,
+    /// Number of points fitted
+    pub points: usize,
+}
+
+impl LinearFit {
+    /// Value of the line at `x`
+    pub fn at(&self, x: f64) -> f64 {
+        self.intercept + self.slope * x
+    }
+}
+
+/// Fit a line through the points `(x[i], y[i])`; `None` with fewer than two distinct `x`
+pub fn linear_fit(x: &[f64], y: &[f64]) -> Option<LinearFit> {
+    let n = x.len().min(y.len());
+    if n < 2 {
+        return None;
+    }
+    let mean_x = x[..n].iter().sum::<f64>() / n as f64;
+    let mean_y = y[..n].iter().sum::<f64>() / n as f64;
+
+    let (mut sxx, mut sxy, mut syy) = (0.0, 0.0, 0.0);
+    for (&xi, &yi) in x[..n].iter().zip(&y[..n]) {
+        let dx = xi - mean_x;
+        let dy = yi - mean_y;
+        sxx += dx * dx;
+        sxy += dx * dy;
+        syy += dy * dy;
+    }
+    if sxx == 0.0 {
+        return None;
+    }
+
+    let slope = sxy / sxx;
+    let r_squared = if syy == 0.0 { 1.0 } else { (sxy * sxy) / (sxx * syy) };
+    Some(LinearFit {
+        slope,
+        intercept: mean_y - slope * mean_x,
+        r_squared,
+        points: n,
+    })
+}
+
+/// Drift of one stream's timestamps relative to a reference stream
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct DriftFit {
+    /// Fit of `other - reference` timestamp differences (s) over reference elapsed time (s)
+    pub fit: LinearFit,
+    /// Reference elapsed time covered by the fit (s)
+    pub span: f64,
+}
+
+impl DriftFit {
+    /// Relative drift in parts per million
+    ///
+    /// Positive when the other stream's samples fall progressively later than the
+    /// reference's, i.e. its sample clock runs slow relative to the reference.
+    pub fn ppm(&self) -> f64 {
+        self.fit.slope * 1e6
+    }
+
+    /// Offset between the streams at the reference's first sample (s)
+    pub fn offset(&self) -> f64 {
+        self.fit.intercept
+    }
+
+    /// Change of the offset over the fitted span (s)
+    pub fn total_drift(&self) -> f64 {
+        self.fit.slope * self.span
+    }
+}
+
+/// Reference elapsed times and `other - reference` timestamp differences of paired samples
+///
+/// Sample `i` of the reference is paired with the other stream's timestamp at
+/// fractional sample `i * other_srate / reference_srate`, subsampled to at most
+/// [`MAX_FIT_POINTS`] pairs. `None` for irregular or empty streams.
+fn drift_pairs(reference: &[f64], reference_srate: f64, other: &[f64], other_srate: f64) -> Option<(Vec<f64>, Vec<f64>)> {
+    if reference_srate <= 0.0 || other_srate <= 0.0 || reference.is_empty() || other.is_empty() {
+        return None;
+    }
+    let ratio = other_srate / reference_srate;
+    // Reference samples that have a partner in the other stream
+    let paired = (((other.len() - 1) as f64 / ratio).floor() as usize + 1).min(reference.len());
+    let step = paired.div_ceil(MAX_FIT_POINTS).max(1);
+
+    let origin = reference[0];
+    let mut elapsed = Vec::with_capacity(paired / step + 1);
+    let mut difference = Vec::with_capacity(paired / step + 1);
+    for i in (0..paired).step_by(step) {
+        let position = i as f64 * ratio;
+        let j = (position.floor() as usize).min(other.len() - 1);
+        let timestamp = match other.get(j + 1) {
+            Some(&next) => other[j] + (position - j as f64) * (next - other[j]),
+            None => other[j],
+        };
+        elapsed.push(reference[i] - origin);
+        difference.push(timestamp - reference[i]);
+    }
+    Some((elapsed, difference))
+}
+
+/// Regress the timestamp difference of `other` against `reference` over the whole recording
+///
+/// Sample `i` of the reference is paired with the other stream's timestamp at
+/// fractional sample `i * other_srate / reference_srate`. Returns `None` for
+/// irregular streams or when fewer than two samples overlap.
+pub fn fit_drift(reference: &[f64], reference_srate: f64, other: &[f64], other_srate: f64) -> Option<DriftFit> {
+    let (elapsed, difference) = drift_pairs(reference, reference_srate, other, other_srate)?;
+    let span = elapsed.last().copied().unwrap_or(0.0);
+    linear_fit(&elapsed, &difference).map(|fit| DriftFit { fit, span })
+}
+
+/// Linear fit of one segment of a [`DriftModel`]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DriftSegment {
+    /// Reference elapsed time where the segment starts (s)
+    pub start: f64,
+    /// Reference elapsed time where the segment ends (s)
+    pub end: f64,
+    pub fit: LinearFit,
+}
+
+/// Piecewise-linear drift of one stream relative to a reference
+#[derive(Debug, Clone, PartialEq)]
+pub struct DriftModel {
+    /// Fit over the whole recording
+    pub overall: DriftFit,
+    /// Per-segment fits, in time order
+    pub segments: Vec<DriftSegment>,
+    /// `(reference elapsed time, timestamp difference)` at the segment boundaries
+    pub knots: Vec<(f64, f64)>,
+    /// First timestamp of the corrected stream
+    pub first_timestamp: f64,
+}
+
+impl DriftModel {
+    /// Modelled `other - reference` timestamp difference at reference elapsed time `x`
+    ///
+    /// Outside the fitted span the first and last pieces are extended.
+    pub fn difference(&self, x: f64) -> f64 {
+        let knots = &self.knots;
+        if knots.len() < 2 {
+            return knots.first().map(|k| k.1).unwrap_or(0.0);
+        }
+        // Index of the piece containing x (clamped to the first and last piece)
+        let piece = knots.partition_point(|k| k.0 <= x).clamp(1, knots.len() - 1) - 1;
+        let (x0, y0) = knots[piece];
+        let (x1, y1) = knots[piece + 1];
+        if x1 == x0 {
+            return y0;
+        }
+        y0 + (x - x0) * (y1 - y0) / (x1 - x0)
+    }
+
+    /// Drift accumulated since the start at reference elapsed time `x` (s)
+    pub fn drift_at(&self, x: f64) -> f64 {
+        self.difference(x) - self.difference(0.0)
+    }
+
+    /// Map a timestamp of the corrected stream onto the reference clock
+    ///
+    /// The reference elapsed time of a sample is its own elapsed time minus the
+    /// drift up to then; one refinement step makes the error negligible.
+    pub fn correct(&self, timestamp: f64) -> f64 {
+        let elapsed = timestamp - self.first_timestamp;
+        let x = elapsed - self.drift_at(elapsed);
+        timestamp - self.drift_at(x)
+    }
+}
+
+/// Fit a drift model of `other` against `reference` with `segments` equal pieces
+///
+/// Segments with too few paired samples for a fit of their own are merged into
+/// a neighbour. Returns `None` where [`fit_drift`] would.
+pub fn fit_drift_model(
+    reference: &[f64],
+    reference_srate: f64,
+    other: &[f64],
+    other_srate: f64,
+    segments: usize,
+) -> Option<DriftModel> {
+    let (elapsed, difference) = drift_pairs(reference, reference_srate, other, other_srate)?;
+    let span = elapsed.last().copied().unwrap_or(0.0);
+    let overall = DriftFit {
+        fit: linear_fit(&elapsed, &difference)?,
+        span,
+    };
+
+    let count = segments.max(1);
+    let mut fitted: Vec<DriftSegment> = Vec::with_capacity(count);
+    let mut first = 0;
+    for k in 0..count {
+        let end = span * (k + 1) as f64 / count as f64;
+        let last = if k + 1 == count {
+            elapsed.len()
+        } else {
+            elapsed.partition_point(|&x| x < end)
+        };
+        if let Some(fit) = linear_fit(&elapsed[first..last], &difference[first..last]) {
+            fitted.push(DriftSegment {
+                start: elapsed[first],
+                end: elapsed[last - 1],
+                fit,
+            });
+            first = last;
+        }
+    }
+    // Leftover pairs too few for a fit of their own extend the last segment
+    if first < elapsed.len()
+        && let Some(segment) = fitted.last_mut()
+    {
+        let start = elapsed.partition_point(|&x| x < segment.start);
+        if let Some(fit) = linear_fit(&elapsed[start..], &difference[start..]) {
+            segment.end = span;
+            segment.fit = fit;
+        }
+    }
+    if fitted.is_empty() {
+        fitted.push(DriftSegment {
+            start: 0.0,
+            end: span,
+            fit: overall.fit,
+        });
+    }
+
+    let mut knots = Vec::with_capacity(fitted.len() + 1);
+    knots.push((0.0, fitted[0].fit.at(0.0)));
+    for pair in fitted.windows(2) {
+        let x = pair[1].start;
+        knots.push((x, (pair[0].fit.at(x) + pair[1].fit.at(x)) / 2.0));
+    }
+    let last = fitted[fitted.len() - 1];
+    knots.push((span, last.fit.at(span)));
+
+    Some(DriftModel {
+        overall,
+        segments: fitted,
+        knots,
+        first_timestamp: other[0],
+    })
+}
@@ -0,0 +1,143 @@
+//! Online estimate of dropped samples from the nominal sample rate
+//!
+//! A regular stream should deliver `nominal_srate` samples per second of its own
+//! timestamps. The recorder counts the samples it receives and compares them with
+//! the number the recorded time span calls for, so a device or network that silently
+//! loses a few percent of its samples is noticed while recording, not only in offline
+//! analysis. Unlike the gap count of the status endpoint, this also catches losses
+//! that leave no gap in the timestamps, e.g. when the inlet's dejittering spreads the
+//! remaining samples evenly.
+//!
+//! Only time spent recording counts: STOP and PAUSE end a span, and the next sample
+//! starts a new one. Every [`DROP_REPORT_INTERVAL`] seconds a loss above
+//! [`DROP_TOLERANCE`] of the expected samples is reported; the tolerance absorbs the
+//! few samples per thousand that a device clock running slightly slow accounts for.
+//! When recording ends the final estimate is stored as the stream's
+//! `dropped_samples` attribute.
+
+use serde::{Deserialize, Serialize};
+
+/// Stream group attribute holding the final [`DropEstimate`]
+pub const DROPPED_SAMPLES_ATTRIBUTE: &str = "dropped_samples";
+
+/// Seconds of recorded timestamps between reports
+pub const DROP_REPORT_INTERVAL: f64 = 10.0;
+
+/// Fraction of the expected samples that may be missing without a report
+pub const DROP_TOLERANCE: f64 = 0.001;
+
+/// Received against expected samples of one stream
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct DropEstimate {
+    pub nominal_srate: f64,
+    /// Recorded time span, stops and pauses excluded (s)
+    pub duration_s: f64,
+    /// Samples the nominal rate calls for over that time
+    pub expected: u64,
+    pub received: u64,
+    /// Expected samples that were not received
+    pub dropped: u64,
+}
+
+impl DropEstimate {
+    /// Read the estimate from stream group attributes
+    pub fn from_attributes(attributes: &serde_json::Value) -> Option<Self> {
+        attributes
+            .get(DROPPED_SAMPLES_ATTRIBUTE)
+            .and_then(|value| serde_json::from_value(value.clone()).ok())
+    }
+
+    /// Dropped samples as a percentage of the expected ones
+    pub fn percent(&self) -> f64 {
+        if self.expected == 0 {
+            0.0
+        } else {
+            self.dropped as f64 / self.expected as f64 * 100.0
+        }
+    }
+
+    /// Whether more samples are missing than [`DROP_TOLERANCE`] allows
+    pub fn is_significant(&self) -> bool {
+        self.dropped as f64 > self.expected as f64 * DROP_TOLERANCE
+    }
+}
+
+/// Counts received samples against the nominal rate while recording
+#[derive(Debug, Clone)]
+pub struct DropEstimator {
+    nominal_srate: f64,
+    // Finished spans: their duration and expected samples
+    duration: f64,
+    expected: u64,
+    received: u64,
+    // First and latest timestamp of the current span
+    span: Option<(f64, f64)>,
+    last_report: Option<f64>,
+    reported: u64,
+}
+
+impl DropEstimator {
+    /// Estimator for a stream of `nominal_srate` Hz; `None` for irregular streams
+    pub fn new(nominal_srate: f64) -> Option<Self> {
+        (nominal_srate > 0.0).then_some(Self {
+            nominal_srate,
+            duration: 0.0,
+            expected: 0,
+            received: 0,
+            span: None,
+            last_report: None,
+            reported: 0,
+        })
+    }
+
+    /// Count a recorded sample; returns an estimate when a loss is due to be reported
+    pub fn sample(&mut self, timestamp: f64) -> Option<DropEstimate> {
+        self.received += 1;
+        // The inlet monotonizes timestamps, so the latest sample ends the span
+        let first = self.span.map_or(timestamp, |(first, _)| first);
+        self.span = Some((first, timestamp));
+
+        let last_report = *self.last_report.get_or_insert(timestamp);
+        if timestamp - last_report < DROP_REPORT_INTERVAL {
+            return None;
+        }
+        self.last_report = Some(timestamp);
+        let estimate = self.estimate();
+        if estimate.dropped > self.reported && estimate.is_significant() {
+            self.reported = estimate.dropped;
+            Some(estimate)
+        } else {
+            None
+        }
+    }
+
+    /// Recording stopped or paused: the current span ends at its latest sample
+    pub fn interrupt(&mut self) {
+        if let Some((first, latest)) = self.span.take() {
+            self.duration += latest - first;
+            self.expected += span_samples(latest - first, self.nominal_srate);
+        }
+        self.last_report = None;
+    }
+
+    /// Estimate over everything recorded so far
+    pub fn estimate(&self) -> DropEstimate {
+        let (mut duration, mut expected) = (self.duration, self.expected);
+        if let Some((first, latest)) = self.span {
+            duration += latest - first;
+            expected += span_samples(latest - first, self.nominal_srate);
+        }
+        DropEstimate {
+            nominal_srate: self.nominal_srate,
+            duration_s: duration,
+            expected,
+            received: self.received,
+            dropped: expected.saturating_sub(self.received),
+        }
+    }
+}
+
+/// Samples from the first to the last one of a span, both included
+fn span_samples(duration: f64, nominal_srate: f64) -> u64 {
+    (duration * nominal_srate).round() as u64 + 1
+}
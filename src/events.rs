@@ -0,0 +1,86 @@
+//! Marker events for `lsl-dummy-stream --format string`
+//!
+//! A string stream of named events exercises the parts of the toolbox that numeric
+//! test streams never reach: the 1-D `events` array, `event_counts`, marker export
+//! and the alignment of events to other streams. The events cycle through
+//! `--events` and arrive either every `--event-interval` seconds or, with
+//! `--poisson`, after exponentially distributed waits with that mean (a Poisson
+//! process, like the responses of a participant).
+//!
+//! Event times are offsets from the start of the stream; the seed makes Poisson
+//! schedules reproducible.
+
+use anyhow::Result;
+
+/// Events of the dummy marker stream unless `--events` is given
+pub const DEFAULT_EVENTS: [&str; 3] = ["trial_start", "stimulus", "response"];
+
+/// Names and timing of the generated events
+#[derive(Debug, Clone)]
+pub struct EventOptions {
+    /// Event names, emitted in turn
+    pub names: Vec<String>,
+    /// Seconds between events (mean wait with `poisson`)
+    pub interval: f64,
+    /// Exponentially distributed waits instead of a fixed interval
+    pub poisson: bool,
+}
+
+impl Default for EventOptions {
+    fn default() -> Self {
+        Self {
+            names: DEFAULT_EVENTS.iter().map(|name| name.to_string()).collect(),
+            interval: 1.0,
+            poisson: false,
+        }
+    }
+}
+
+/// One generated event
+#[derive(Debug, Clone, PartialEq)]
+pub struct Event {
+    /// Seconds after the start of the stream
+    pub time: f64,
+    pub name: String,
+}
+
+/// Endless sequence of named events
+#[derive(Debug, Clone)]
+pub struct EventGenerator {
+    options: EventOptions,
+    rng: fastrand::Rng,
+    time: f64,
+    count: u64,
+}
+
+impl EventGenerator {
+    pub fn new(options: EventOptions, seed: u64) -> Result<Self> {
+        if options.names.is_empty() || options.names.iter().any(|name| name.is_empty()) {
+            anyhow::bail!("Event names cannot be empty");
+        }
+        if !options.interval.is_finite() || options.interval <= 0.0 {
+            anyhow::bail!("Event interval must be a positive number of seconds");
+        }
+        Ok(Self {
+            options,
+            rng: fastrand::Rng::with_seed(seed),
+            time: 0.0,
+            count: 0,
+        })
+    }
+}
+
+impl Iterator for EventGenerator {
+    type Item = Event;
+
+    fn next(&mut self) -> Option<Event> {
+        self.time = if self.options.poisson {
+            self.time - (1.0 - self.rng.f64()).ln() * self.options.interval
+        } else {
+            (self.count + 1) as f64 * self.options.interval
+        };
+        let name = self.options.names[(self.count % self.options.names.len() as u64) as usize].clone();
+        self.count += 1;
+        Some(Event { time: self.time, name })
+    }
+}
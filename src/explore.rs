@@ -0,0 +1,248 @@
+//! Store tree, node metadata and data previews for the TUI Store Explorer
+//!
+//! The explorer walks a store one level at a time, so opening a group only reads
+//! the `zarr.json` files of its children, and previews read a small slice from the
+//! start of an array: the first [`PREVIEW_SAMPLES`] samples of at most
+//! [`PREVIEW_CHANNELS`] channels, however long the recording is.
+
+use anyhow::{Context, Result};
+use serde_json::Value;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use zarrs::array::{Array, DataType};
+use zarrs::array_subset::ArraySubset;
+use zarrs::filesystem::FilesystemStore;
+
+/// Samples shown in an array preview
+pub const PREVIEW_SAMPLES: u64 = 10;
+
+/// Channels shown in a preview of a 2-D `[channels, samples]` array
+pub const PREVIEW_CHANNELS: u64 = 8;
+
+/// Characters of a string value shown in a preview
+const PREVIEW_TEXT_LEN: usize = 32;
+
+/// Kind of a node in a Zarr v3 hierarchy
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NodeKind {
+    Group,
+    Array,
+}
+
+/// A group or array below a store root
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StoreNode {
+    /// Path from the store root, e.g. `/EEG/data` (`/` for the root group)
+    pub path: String,
+    /// Last path component
+    pub name: String,
+    pub kind: NodeKind,
+}
+
+/// Metadata of one node from its `zarr.json`
+#[derive(Debug, Clone, PartialEq)]
+pub struct NodeDetails {
+    pub kind: NodeKind,
+    /// User attributes (`{}` without any)
+    pub attributes: Value,
+    /// Array shape
+    pub shape: Option<Vec<u64>>,
+    /// Array data type, e.g. `float32`
+    pub data_type: Option<String>,
+    /// Shape of the chunks (of the shards for sharded arrays)
+    pub chunk_shape: Option<Vec<u64>>,
+    /// Names of the array's codecs, outermost first
+    pub codecs: Vec<String>,
+}
+
+impl NodeDetails {
+    /// Lines describing the node, followed by its attributes as indented JSON
+    pub fn lines(&self) -> Vec<String> {
+        let mut lines = Vec::new();
+        match self.kind {
+            NodeKind::Group => lines.push("Group".to_string()),
+            NodeKind::Array => {
+                lines.push("Array".to_string());
+                if let Some(ref shape) = self.shape {
+                    lines.push(format!("Shape: {:?}", shape));
+                }
+                if let Some(ref data_type) = self.data_type {
+                    lines.push(format!("Data type: {}", data_type));
+                }
+                if let Some(ref chunk_shape) = self.chunk_shape {
+                    lines.push(format!("Chunks: {:?}", chunk_shape));
+                }
+                if !self.codecs.is_empty() {
+                    lines.push(format!("Codecs: {}", self.codecs.join(", ")));
+                }
+            }
+        }
+        let empty = self.attributes.as_object().is_none_or(|attributes| attributes.is_empty());
+        if empty {
+            lines.push("Attributes: none".to_string());
+        } else {
+            lines.push("Attributes:".to_string());
+            let pretty = serde_json::to_string_pretty(&self.attributes).unwrap_or_default();
+            lines.extend(pretty.lines().map(|line| format!("  {}", line)));
+        }
+        lines
+    }
+}
+
+fn node_dir(store_path: &Path, node_path: &str) -> PathBuf {
+    let relative = node_path.trim_matches('/');
+    if relative.is_empty() {
+        store_path.to_path_buf()
+    } else {
+        store_path.join(relative)
+    }
+}
+
+fn read_zarr_json(dir: &Path) -> Result<Value> {
+    let file = dir.join("zarr.json");
+    let bytes = std::fs::read(&file).with_context(|| format!("Cannot read {}", file.display()))?;
+    serde_json::from_slice(&bytes).with_context(|| format!("Invalid metadata in {}", file.display()))
+}
+
+fn node_kind(metadata: &Value) -> Option<NodeKind> {
+    match metadata.get("node_type")?.as_str()? {
+        "group" => Some(NodeKind::Group),
+        "array" => Some(NodeKind::Array),
+        _ => None,
+    }
+}
+
+/// Groups and arrays directly below a group, sorted by name
+///
+/// Directories without a Zarr v3 `zarr.json` (chunk directories, archives) are skipped.
+pub fn list_children(store_path: &Path, group_path: &str) -> Result<Vec<StoreNode>> {
+    let dir = node_dir(store_path, group_path);
+    let entries = std::fs::read_dir(&dir).with_context(|| format!("Cannot read {}", dir.display()))?;
+    let parent = group_path.trim_end_matches('/');
+    let mut children: Vec<StoreNode> = entries
+        .flatten()
+        .filter(|entry| entry.file_type().is_ok_and(|t| t.is_dir()))
+        .filter_map(|entry| {
+            let name = entry.file_name().to_string_lossy().to_string();
+            let kind = node_kind(&read_zarr_json(&entry.path()).ok()?)?;
+            Some(StoreNode {
+                path: format!("{}/{}", parent, name),
+                name,
+                kind,
+            })
+        })
+        .collect();
+    children.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(children)
+}
+
+/// Metadata of the group or array at `node_path`
+pub fn node_details(store_path: &Path, node_path: &str) -> Result<NodeDetails> {
+    let metadata = read_zarr_json(&node_dir(store_path, node_path))?;
+    let kind = node_kind(&metadata).with_context(|| format!("{} is not a Zarr v3 group or array", node_path))?;
+    let u64_list = |value: &Value| -> Option<Vec<u64>> {
+        value.as_array()?.iter().map(|v| v.as_u64()).collect()
+    };
+    let codec_name = |codec: &Value| codec.get("name").and_then(|n| n.as_str()).map(String::from);
+    let data_type = metadata.get("data_type").and_then(|t| match t {
+        Value::String(name) => Some(name.clone()),
+        other => other.get("name").and_then(|n| n.as_str()).map(String::from),
+    });
+    Ok(NodeDetails {
+        kind,
+        attributes: metadata.get("attributes").cloned().unwrap_or_else(|| Value::Object(Default::default())),
+        shape: metadata.get("shape").and_then(u64_list),
+        data_type,
+        chunk_shape: metadata.pointer("/chunk_grid/configuration/chunk_shape").and_then(u64_list),
+        codecs: metadata
+            .get("codecs")
+            .and_then(|c| c.as_array())
+            .map(|codecs| codecs.iter().filter_map(codec_name).collect())
+            .unwrap_or_default(),
+    })
+}
+
+/// Table of the first `samples` samples of an array, one line per sample
+///
+/// 2-D arrays are read as `[channels, samples]` and show at most `channels` channels;
+/// the first line is a header naming the columns.
+pub fn preview_array(store_path: &Path, array_path: &str, samples: u64, channels: u64) -> Result<Vec<String>> {
+    let store = Arc::new(FilesystemStore::new(store_path)?);
+    let array = Array::<FilesystemStore>::open(store, &format!("/{}", array_path.trim_start_matches('/')))?;
+    let shape = array.shape().to_vec();
+    let (rows, cols) = match shape.as_slice() {
+        [length] => (samples.min(*length), 1),
+        [channel_count, length] => (samples.min(*length), channels.min(*channel_count)),
+        _ => anyhow::bail!("Preview only covers 1-D and 2-D arrays, not shape {:?}", shape),
+    };
+    let total = shape.last().copied().unwrap_or(0);
+    if rows == 0 || cols == 0 {
+        return Ok(vec!["(no samples)".to_string()]);
+    }
+    let subset = if shape.len() == 1 {
+        ArraySubset::new_with_start_shape(vec![0], vec![rows])?
+    } else {
+        ArraySubset::new_with_start_shape(vec![0, 0], vec![cols, rows])?
+    };
+
+    macro_rules! cells {
+        ($ty:ty, $format:expr) => {{
+            let block = array.retrieve_array_subset_ndarray::<$ty>(&subset)?;
+            // Sample-major, so each row of the table is one sample
+            let block = if shape.len() == 1 { block } else { block.reversed_axes() };
+            block.iter().map($format).collect::<Vec<String>>()
+        }};
+    }
+
+    let cells = match array.data_type() {
+        DataType::Float32 => cells!(f32, |v: &f32| format!("{:.6}", v)),
+        DataType::Float64 => cells!(f64, |v: &f64| format!("{:.6}", v)),
+        DataType::Int8 => cells!(i8, |v: &i8| v.to_string()),
+        DataType::Int16 => cells!(i16, |v: &i16| v.to_string()),
+        DataType::Int32 => cells!(i32, |v: &i32| v.to_string()),
+        DataType::Int64 => cells!(i64, |v: &i64| v.to_string()),
+        DataType::UInt8 => cells!(u8, |v: &u8| v.to_string()),
+        DataType::UInt16 => cells!(u16, |v: &u16| v.to_string()),
+        DataType::UInt32 => cells!(u32, |v: &u32| v.to_string()),
+        DataType::UInt64 => cells!(u64, |v: &u64| v.to_string()),
+        DataType::Bool => cells!(bool, |v: &bool| v.to_string()),
+        DataType::String => cells!(String, |v: &String| format!("{:?}", preview_text(v))),
+        other => anyhow::bail!("No preview for data type {:?}", other),
+    };
+
+    let width = cells.iter().map(|cell| cell.len()).max().unwrap_or(0).max(6);
+    let mut lines = Vec::with_capacity(rows as usize + 2);
+    let mut header = format!("{:>8}", "sample");
+    if shape.len() == 1 {
+        header.push_str(&format!("  {:>width$}", "value"));
+    } else {
+        for channel in 0..cols {
+            header.push_str(&format!("  {:>width$}", format!("ch{}", channel)));
+        }
+    }
+    lines.push(header);
+    for (row, values) in cells.chunks(cols as usize).enumerate() {
+        let mut line = format!("{:>8}", row);
+        for value in values {
+            line.push_str(&format!("  {:>width$}", value));
+        }
+        lines.push(line);
+    }
+    if total > rows || (shape.len() == 2 && shape[0] > cols) {
+        let mut note = format!("... first {} of {} samples", rows, total);
+        if shape.len() == 2 {
+            note.push_str(&format!(", {} of {} channels", cols, shape[0]));
+        }
+        lines.push(note);
+    }
+    Ok(lines)
+}
+
+/// Start of a string value, shortened for a table cell
+fn preview_text(text: &str) -> String {
+    if text.chars().count() > PREVIEW_TEXT_LEN {
+        format!("{}…", text.chars().take(PREVIEW_TEXT_LEN - 1).collect::<String>())
+    } else {
+        text.to_string()
+    }
+}
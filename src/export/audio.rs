@@ -0,0 +1,367 @@
+//! Channel sonification (`lsl-export audio`)
+//!
+//! Listening to a channel is a cheap QC check that EMG labs use a lot: mains hum is
+//! a steady tone, movement artifacts thump, electrode pops click, and dropouts are
+//! silence. One channel (`--channel EMG:3`, by label or 0-based index) is written
+//! as a mono 16-bit PCM WAV file that any audio player opens.
+//!
+//! The channel is resampled onto the audio rate as by `lsl-sync --resample-to`
+//! (see [`crate::resample`]): linearly, low-pass filtered when the audio rate is
+//! below the stream's rate, and left silent inside gaps. `speed` compresses time,
+//! so slow signals become audible: at 10x, an hour of EEG plays in six minutes and
+//! a 10 Hz alpha rhythm sounds at 100 Hz.
+//!
+//! The mean of the exported range is removed, then the channel is scaled so that
+//! either its largest deviation reaches full scale ([`Normalization::Peak`]), or
+//! its RMS sits at [`RMS_TARGET`] of full scale ([`Normalization::Rms`]), which
+//! keeps quiet recordings audible when a single artifact dominates the peak; louder
+//! samples are clipped. `start`/`end` are seconds from the start of the recording,
+//! as for [per-stream tables](super::table).
+
+use anyhow::{Context, Result};
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+use super::{open_streams, ExportOptions, ExportStream, EXPORT_BLOCK_SAMPLES};
+use crate::gaps::DEFAULT_GAP_FACTOR;
+use crate::resample::{anti_alias_kernel, resample_block};
+
+/// Default WAV sample rate in Hz
+pub const DEFAULT_AUDIO_RATE: u32 = 44_100;
+
+/// Full-scale fraction of the largest deviation with [`Normalization::Peak`]
+pub const PEAK_TARGET: f64 = 0.99;
+
+/// Full-scale fraction of the RMS with [`Normalization::Rms`] (-20 dBFS)
+pub const RMS_TARGET: f64 = 0.1;
+
+/// Largest audio data a WAV header can describe (32-bit chunk sizes)
+const WAV_MAX_DATA_BYTES: u64 = u32::MAX as u64 - 36;
+
+/// How a channel is scaled to full scale
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, clap::ValueEnum)]
+pub enum Normalization {
+    /// Largest deviation from the mean at full scale
+    #[default]
+    Peak,
+    /// RMS at -20 dBFS; louder samples are clipped
+    Rms,
+}
+
+/// Options specific to audio output
+#[derive(Debug, Clone)]
+pub struct AudioOptions {
+    /// Channel to render as `<stream>:<channel>`, the channel by label or 0-based index
+    pub channel: String,
+    /// WAV sample rate in Hz
+    pub rate: u32,
+    /// Seconds of recording per second of audio
+    pub speed: f64,
+    pub normalization: Normalization,
+    /// Start of the exported range in seconds from the start of the recording
+    pub start: Option<f64>,
+    /// End of the exported range in seconds from the start of the recording
+    pub end: Option<f64>,
+}
+
+impl Default for AudioOptions {
+    fn default() -> Self {
+        Self {
+            channel: String::new(),
+            rate: DEFAULT_AUDIO_RATE,
+            speed: 1.0,
+            normalization: Normalization::Peak,
+            start: None,
+            end: None,
+        }
+    }
+}
+
+/// The file written by [`export_audio`]
+#[derive(Debug, Clone)]
+pub struct AudioSummary {
+    pub path: PathBuf,
+    pub stream: String,
+    /// Label of the rendered channel
+    pub channel: String,
+    /// Audio samples written
+    pub frames: u64,
+    /// Seconds of recording rendered
+    pub recorded_duration: f64,
+    /// Seconds of audio
+    pub duration: f64,
+    /// Factor applied to the channel (after removing its mean) to reach full scale
+    pub gain: f64,
+    /// Audio samples clipped at full scale
+    pub clipped: u64,
+    /// Audio samples left silent because the stream has no data there (gaps)
+    pub silent: u64,
+}
+
+/// Split a `<stream>:<channel>` selection at its last colon
+pub fn parse_channel_spec(spec: &str) -> Result<(&str, &str)> {
+    spec.rsplit_once(':')
+        .filter(|(stream, channel)| !stream.is_empty() && !channel.is_empty())
+        .with_context(|| format!("Invalid channel '{}', expected <stream>:<channel> (e.g. EMG:3)", spec))
+}
+
+/// Default output path: `experiment.zarr` with `EMG:3` -> `experiment_EMG_3.wav`
+pub fn default_audio_output_path(store_path: &Path, spec: &str) -> PathBuf {
+    let trimmed = store_path.components().as_path();
+    let stem = trimmed.file_stem().map(|s| s.to_string_lossy().to_string()).unwrap_or_default();
+    let suffix: String = spec
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() || c == '-' { c } else { '_' })
+        .collect();
+    trimmed.with_file_name(format!("{}_{}.wav", stem, suffix))
+}
+
+/// Mean, largest deviation from the mean, and standard deviation of a channel
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct ChannelLevels {
+    pub mean: f64,
+    pub peak: f64,
+    pub rms: f64,
+}
+
+impl ChannelLevels {
+    /// Levels of `values`, ignoring non-finite ones
+    pub fn from_values(values: &[f64]) -> Self {
+        let mut accumulator = LevelAccumulator::default();
+        accumulator.add(values);
+        accumulator.levels()
+    }
+
+    /// Gain that brings the channel to full scale, 0 for a flat channel
+    pub fn gain(&self, normalization: Normalization) -> f64 {
+        let (level, target) = match normalization {
+            Normalization::Peak => (self.peak, PEAK_TARGET),
+            Normalization::Rms => (self.rms, RMS_TARGET),
+        };
+        if level > 0.0 && level.is_finite() {
+            target / level
+        } else {
+            0.0
+        }
+    }
+}
+
+/// Running sums for [`ChannelLevels`] over blocks of samples
+#[derive(Debug, Clone, Copy)]
+struct LevelAccumulator {
+    count: u64,
+    sum: f64,
+    sum_squares: f64,
+    min: f64,
+    max: f64,
+}
+
+impl Default for LevelAccumulator {
+    fn default() -> Self {
+        Self {
+            count: 0,
+            sum: 0.0,
+            sum_squares: 0.0,
+            min: f64::INFINITY,
+            max: f64::NEG_INFINITY,
+        }
+    }
+}
+
+impl LevelAccumulator {
+    fn add(&mut self, values: &[f64]) {
+        for &value in values.iter().filter(|v| v.is_finite()) {
+            self.count += 1;
+            self.sum += value;
+            self.sum_squares += value * value;
+            self.min = self.min.min(value);
+            self.max = self.max.max(value);
+        }
+    }
+
+    fn levels(&self) -> ChannelLevels {
+        if self.count == 0 {
+            return ChannelLevels::default();
+        }
+        let mean = self.sum / self.count as f64;
+        let variance = (self.sum_squares / self.count as f64 - mean * mean).max(0.0);
+        ChannelLevels {
+            mean,
+            peak: (self.max - mean).max(mean - self.min),
+            rms: variance.sqrt(),
+        }
+    }
+}
+
+/// 44-byte header of a mono 16-bit PCM WAV file with `frames` samples
+pub fn wav_header(rate: u32, frames: u32) -> [u8; 44] {
+    let data_bytes = frames * 2;
+    let mut header = [0u8; 44];
+    header[0..4].copy_from_slice(b"RIFF");
+    header[4..8].copy_from_slice(&(36 + data_bytes).to_le_bytes());
+    header[8..12].copy_from_slice(b"WAVE");
+    header[12..16].copy_from_slice(b"fmt ");
+    header[16..20].copy_from_slice(&16u32.to_le_bytes());
+    header[20..22].copy_from_slice(&1u16.to_le_bytes()); // PCM
+    header[22..24].copy_from_slice(&1u16.to_le_bytes()); // mono
+    header[24..28].copy_from_slice(&rate.to_le_bytes());
+    header[28..32].copy_from_slice(&(rate * 2).to_le_bytes()); // bytes per second
+    header[32..34].copy_from_slice(&2u16.to_le_bytes()); // bytes per frame
+    header[34..36].copy_from_slice(&16u16.to_le_bytes()); // bits per sample
+    header[36..40].copy_from_slice(b"data");
+    header[40..44].copy_from_slice(&data_bytes.to_le_bytes());
+    header
+}
+
+/// 16-bit PCM value of a normalized sample; NaN (no data) is silence
+///
+/// Returns the value and whether it was clipped.
+pub fn pcm_sample(value: f64) -> (i16, bool) {
+    if value.is_nan() {
+        return (0, false);
+    }
+    let clipped = !(-1.0..=1.0).contains(&value);
+    ((value.clamp(-1.0, 1.0) * i16::MAX as f64).round() as i16, clipped)
+}
+
+/// Render the channel selected by `audio.channel` into a WAV file at `output`
+pub fn export_audio(
+    store_path: &Path,
+    output: &Path,
+    options: &ExportOptions,
+    audio: &AudioOptions,
+) -> Result<AudioSummary> {
+    if audio.rate == 0 {
+        anyhow::bail!("--rate must be a positive rate");
+    }
+    if audio.speed.is_nan() || audio.speed <= 0.0 {
+        anyhow::bail!("--speed must be positive");
+    }
+    let (stream_name, channel_name) = parse_channel_spec(&audio.channel)?;
+
+    // All streams, for the start of the recording that --start/--end count from
+    let all = ExportOptions {
+        streams: Vec::new(),
+        ..options.clone()
+    };
+    let streams = open_streams(store_path, &all)?;
+    let mut recording_start = f64::INFINITY;
+    for stream in &streams {
+        if let Some((first, _)) = stream.timestamp_range()? {
+            recording_start = recording_start.min(first);
+        }
+    }
+    let stream = streams.iter().find(|s| s.name == stream_name).with_context(|| {
+        let names: Vec<&str> = streams.iter().map(|s| s.name.as_str()).collect();
+        format!("Stream '{}' not found (available: {})", stream_name, names.join(", "))
+    })?;
+    if stream.is_marker() {
+        anyhow::bail!("Stream '{}' is a marker stream and has no signal to listen to", stream.name);
+    }
+    let channel = stream.channel_index(channel_name)?;
+    let (first, last) = stream
+        .timestamp_range()?
+        .with_context(|| format!("Stream '{}' has no samples", stream.name))?;
+
+    let window_start = audio.start.map_or(first, |t| first.max(recording_start + t));
+    let window_end = audio.end.map_or(last, |t| last.min(recording_start + t));
+    if window_end <= window_start {
+        anyhow::bail!("Stream '{}' has no samples in the exported range", stream.name);
+    }
+
+    let start_index = stream.sample_index(window_start)?;
+    let end_index = (stream.sample_index(window_end)? + 1).min(stream.sample_count).max(start_index);
+    let levels = channel_levels(stream, channel, start_index, end_index)?;
+    let gain = levels.gain(audio.normalization);
+
+    // Audio samples at `rate` Hz, each `speed / rate` seconds of recording apart
+    let frame_step = audio.speed / audio.rate as f64;
+    let frames = ((window_end - window_start) / frame_step + 1e-9).floor() as u64 + 1;
+    if frames * 2 > WAV_MAX_DATA_BYTES {
+        anyhow::bail!(
+            "{} audio samples exceed the size of a WAV file; shorten the range with --start/--end or raise --speed",
+            frames
+        );
+    }
+
+    let source_rate = stream.nominal_srate();
+    let target_rate = audio.rate as f64 / audio.speed;
+    let kernel = anti_alias_kernel(source_rate, target_rate);
+    let max_interval = (source_rate > 0.0).then(|| DEFAULT_GAP_FACTOR / source_rate);
+    // About one export block of source samples per block of frames
+    let ratio = (source_rate / target_rate).max(1.0);
+    let block_frames = ((EXPORT_BLOCK_SAMPLES as f64 / ratio) as u64).max(1);
+    let margin = 1 + kernel.as_ref().map_or(0, |k| k.len() / 2) as u64;
+
+    if let Some(parent) = output.parent().filter(|p| !p.as_os_str().is_empty()) {
+        std::fs::create_dir_all(parent)?;
+    }
+    let file = File::create(output).with_context(|| format!("Failed to create {}", output.display()))?;
+    let mut writer = BufWriter::new(file);
+    writer.write_all(&wav_header(audio.rate, frames as u32))?;
+
+    let (mut clipped, mut silent) = (0, 0);
+    let mut frame = 0;
+    while frame < frames {
+        let len = block_frames.min(frames - frame);
+        let points: Vec<f64> = (frame..frame + len)
+            .map(|k| window_start + k as f64 * frame_step)
+            .collect();
+        let first_sample = stream.sample_index(points[0])?.saturating_sub(margin);
+        let end_sample = (stream.sample_index(points[points.len() - 1])? + 1 + margin).min(stream.sample_count);
+
+        let values = if first_sample < end_sample {
+            let (timestamps, block) = stream.read_block(first_sample, end_sample - first_sample)?;
+            let values = block.to_f64().context("numeric samples expected")?;
+            resample_block(
+                &timestamps,
+                &values,
+                stream.channel_count,
+                kernel.as_deref(),
+                max_interval,
+                &points,
+            )
+        } else {
+            vec![f64::NAN; points.len() * stream.channel_count]
+        };
+
+        for sample in values.chunks(stream.channel_count.max(1)) {
+            let value = sample[channel];
+            let (pcm, clip) = pcm_sample((value - levels.mean) * gain);
+            clipped += clip as u64;
+            silent += value.is_nan() as u64;
+            writer.write_all(&pcm.to_le_bytes())?;
+        }
+        frame += len;
+    }
+    writer.flush()?;
+
+    Ok(AudioSummary {
+        path: output.to_path_buf(),
+        stream: stream.name.clone(),
+        channel: stream.channel_labels()[channel].clone(),
+        frames,
+        recorded_duration: window_end - window_start,
+        duration: frames as f64 / audio.rate as f64,
+        gain,
+        clipped,
+        silent,
+    })
+}
+
+/// Levels of one channel over samples `start..end`
+fn channel_levels(stream: &ExportStream, channel: usize, start: u64, end: u64) -> Result<ChannelLevels> {
+    let mut accumulator = LevelAccumulator::default();
+    let count = stream.channel_count.max(1);
+    let mut block_start = start;
+    while block_start < end {
+        let len = EXPORT_BLOCK_SAMPLES.min(end - block_start);
+        let (_, block) = stream.read_block(block_start, len)?;
+        let values = block.to_f64().context("numeric samples expected")?;
+        let channel_values: Vec<f64> = values.chunks(count).map(|sample| sample[channel]).collect();
+        accumulator.add(&channel_values);
+        block_start += len;
+    }
+    Ok(accumulator.levels())
+}
@@ -0,0 +1,614 @@
+//! EDF+ and BDF+ writer
+//!
+//! EDF stores each signal with a fixed number of samples per data record, so every
+//! numeric stream is placed on a uniform grid that starts at the earliest timestamp
+//! of the export:
+//!
+//! - Regular streams are resampled at their nominal rate (rounded to whole Hz) by
+//!   linear interpolation over the recorded timestamps, which also absorbs jitter
+//!   and bridges gaps
+//! - Irregular numeric streams are resampled at [`EdfOptions::resample_rate`]
+//! - Marker streams become EDF+ annotations
+//!
+//! Channel labels follow the EDF+ `<type> <label>` convention, using the `type` and
+//! `label` fields of the stored channel description, and the channel unit becomes
+//! the physical dimension. Physical ranges are the observed min/max of each channel.
+//!
+//! Data records last one second. Exports larger than [`EDF_MAX_FILE_BYTES`] or longer
+//! than [`EdfOptions::split_duration`] are split into numbered files, each with its
+//! own start time.
+
+use anyhow::Result;
+use chrono::{DateTime, Datelike, NaiveDateTime, Timelike, Utc};
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+use super::{open_streams, ExportOptions, ExportStream, SampleBlock, EXPORT_BLOCK_SAMPLES};
+use crate::units::unit_symbol;
+
+/// Largest file written before splitting (many EDF readers use 32-bit file offsets)
+pub const EDF_MAX_FILE_BYTES: u64 = i32::MAX as u64;
+
+/// Largest data record count that fits the 8-character header field
+const EDF_MAX_RECORDS: u64 = 99_999_999;
+
+/// Duration of one data record in seconds
+const RECORD_DURATION: f64 = 1.0;
+
+/// Options specific to EDF+/BDF+ output
+#[derive(Debug, Clone)]
+pub struct EdfOptions {
+    /// Write BDF+ (24-bit samples) instead of EDF+ (16-bit samples)
+    pub bdf: bool,
+    /// Rate in Hz at which irregular numeric streams are resampled
+    pub resample_rate: f64,
+    /// Maximum duration per file in seconds
+    pub split_duration: Option<f64>,
+}
+
+impl Default for EdfOptions {
+    fn default() -> Self {
+        Self {
+            bdf: false,
+            resample_rate: 100.0,
+            split_duration: None,
+        }
+    }
+}
+
+/// One file written by an EDF export
+#[derive(Debug, Clone)]
+pub struct EdfFileSummary {
+    pub path: PathBuf,
+    /// Number of one-second data records
+    pub records: u64,
+    /// Number of data signals (excluding the annotation signal)
+    pub signals: usize,
+    pub annotations: usize,
+}
+
+/// Sample width and digital range of EDF (16-bit) or BDF (24-bit)
+#[derive(Debug, Clone, Copy)]
+struct SampleFormat {
+    bytes: usize,
+    digital_min: i32,
+    digital_max: i32,
+}
+
+impl SampleFormat {
+    fn new(bdf: bool) -> Self {
+        if bdf {
+            Self { bytes: 3, digital_min: -8_388_608, digital_max: 8_388_607 }
+        } else {
+            Self { bytes: 2, digital_min: -32_768, digital_max: 32_767 }
+        }
+    }
+
+    fn push(&self, out: &mut Vec<u8>, value: i32) {
+        out.extend_from_slice(&value.to_le_bytes()[..self.bytes]);
+    }
+}
+
+/// Header fields and scaling of one EDF signal
+#[derive(Debug, Clone)]
+struct Signal {
+    label: String,
+    transducer: String,
+    dimension: String,
+    /// Physical bounds with their header text
+    physical_min: (String, f64),
+    physical_max: (String, f64),
+    samples_per_record: usize,
+}
+
+impl Signal {
+    /// Digital value of a physical value, clamped to the digital range
+    fn digital(&self, value: f64, format: SampleFormat) -> i32 {
+        if !value.is_finite() {
+            return format.digital_min;
+        }
+        let (min, max) = (self.physical_min.1, self.physical_max.1);
+        let digital_span = (format.digital_max - format.digital_min) as f64;
+        let scaled = (value - min) / (max - min) * digital_span + format.digital_min as f64;
+        scaled.round().clamp(format.digital_min as f64, format.digital_max as f64) as i32
+    }
+}
+
+/// Linear interpolation of a stream's samples at increasing times
+struct Resampler<'a> {
+    stream: &'a ExportStream,
+    next_index: u64,
+    block_times: Vec<f64>,
+    block_values: Vec<f64>,
+    block_pos: usize,
+    left_time: f64,
+    left: Vec<f64>,
+    right_time: f64,
+    right: Vec<f64>,
+}
+
+impl<'a> Resampler<'a> {
+    fn new(stream: &'a ExportStream) -> Result<Self> {
+        let mut resampler = Self {
+            stream,
+            next_index: 0,
+            block_times: Vec::new(),
+            block_values: Vec::new(),
+            block_pos: 0,
+            left_time: f64::NEG_INFINITY,
+            left: vec![0.0; stream.channel_count],
+            right_time: f64::NEG_INFINITY,
+            right: vec![0.0; stream.channel_count],
+        };
+        resampler.advance()?;
+        resampler.left.clone_from(&resampler.right);
+        resampler.left_time = resampler.right_time;
+        Ok(resampler)
+    }
+
+    /// Move the interpolation window forward by one sample; false at the end of the stream
+    fn advance(&mut self) -> Result<bool> {
+        if self.block_pos == self.block_times.len() {
+            if self.next_index >= self.stream.sample_count {
+                return Ok(false);
+            }
+            let len = EXPORT_BLOCK_SAMPLES.min(self.stream.sample_count - self.next_index);
+            let (times, values) = self.stream.read_block(self.next_index, len)?;
+            self.block_values = values
+                .to_f64()
+                .ok_or_else(|| anyhow::anyhow!("Stream '{}' is not numeric", self.stream.name))?;
+            self.block_times = times;
+            self.block_pos = 0;
+            self.next_index += len;
+        }
+
+        let channels = self.right.len();
+        let i = self.block_pos;
+        std::mem::swap(&mut self.left, &mut self.right);
+        self.left_time = self.right_time;
+        self.right.copy_from_slice(&self.block_values[i * channels..(i + 1) * channels]);
+        self.right_time = self.block_times[i];
+        self.block_pos += 1;
+        Ok(true)
+    }
+
+    /// Values of all channels at `time`; held constant before the first and after the last sample
+    fn sample_at(&mut self, time: f64, out: &mut [f64]) -> Result<()> {
+        while self.right_time < time && self.advance()? {}
+
+        if time >= self.right_time {
+            out.copy_from_slice(&self.right);
+        } else if time <= self.left_time {
+            out.copy_from_slice(&self.left);
+        } else {
+            let weight = (time - self.left_time) / (self.right_time - self.left_time);
+            for (value, (left, right)) in out.iter_mut().zip(self.left.iter().zip(&self.right)) {
+                *value = left + (right - left) * weight;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// A numeric stream placed on the record grid
+struct GridStream<'a> {
+    resampler: Resampler<'a>,
+    samples_per_record: usize,
+    /// Index of the stream's first channel in the signal list
+    first_signal: usize,
+    /// Exported channels of the stream (bad channels left out)
+    channels: Vec<usize>,
+}
+
+/// Fixed-width ASCII header field, space padded; other characters become `_`
+fn ascii_field(text: &str, width: usize) -> Vec<u8> {
+    let mut field: Vec<u8> = text
+        .chars()
+        .map(|c| if c.is_ascii() && !c.is_ascii_control() { c as u8 } else { b'_' })
+        .take(width)
+        .collect();
+    field.resize(width, b' ');
+    field
+}
+
+/// Physical bound rounded outwards so that its text fits the 8-character field
+fn physical_bound(value: f64, round_up: bool) -> Result<(String, f64)> {
+    for decimals in (0..=6).rev() {
+        let scale = 10f64.powi(decimals);
+        let rounded = if round_up {
+            (value * scale).ceil() / scale
+        } else {
+            (value * scale).floor() / scale
+        };
+        let text = format!("{:.*}", decimals as usize, rounded);
+        if text.len() <= 8 {
+            return Ok((text, rounded));
+        }
+    }
+    anyhow::bail!("Value {} does not fit an 8-character EDF header field", value)
+}
+
+/// Observed min/max of each channel (ignoring non-finite values)
+fn channel_ranges(stream: &ExportStream) -> Result<Vec<(f64, f64)>> {
+    let channels = stream.channel_count;
+    let mut ranges = vec![(f64::INFINITY, f64::NEG_INFINITY); channels];
+
+    let mut start = 0;
+    while start < stream.sample_count {
+        let len = EXPORT_BLOCK_SAMPLES.min(stream.sample_count - start);
+        let (_, values) = stream.read_block(start, len)?;
+        let values = values
+            .to_f64()
+            .ok_or_else(|| anyhow::anyhow!("Stream '{}' is not numeric", stream.name))?;
+        for (i, value) in values.iter().enumerate() {
+            if value.is_finite() {
+                let range = &mut ranges[i % channels];
+                range.0 = range.0.min(*value);
+                range.1 = range.1.max(*value);
+            }
+        }
+        start += len;
+    }
+
+    Ok(ranges
+        .into_iter()
+        .map(|(min, max)| match (min, max) {
+            (min, max) if min > max => (-1.0, 1.0),
+            (min, max) if min == max => (min - 1.0, max + 1.0),
+            range => range,
+        })
+        .collect())
+}
+
+/// Label, transducer and physical dimension of a channel from its stored description
+fn channel_header(stream: &ExportStream, channel: usize) -> (String, String, String) {
+    let description = stream
+        .stream_info
+        .get("channels")
+        .and_then(|c| c.as_array())
+        .and_then(|c| c.get(channel));
+    let text = |key: &str| {
+        description
+            .and_then(|c| c.get(key))
+            .and_then(|v| v.as_str())
+            .filter(|s| !s.is_empty())
+    };
+
+    let name = stream.channel_labels().swap_remove(channel);
+    let label = match text("type") {
+        Some(kind) => format!("{} {}", kind, name),
+        None => name,
+    };
+    let dimension = text("unit").map(|u| unit_symbol(u).replace('µ', "u")).unwrap_or_default();
+    (label, format!("LSL stream {}", stream.name), dimension)
+}
+
+/// Marker text with the TAL delimiter characters removed
+fn annotation_text(text: &str) -> String {
+    text.chars()
+        .map(|c| if matches!(c, '\x00' | '\x14' | '\x15') { ' ' } else { c })
+        .collect()
+}
+
+/// All samples of the marker streams as (timestamp, text), sorted by time
+fn read_markers(streams: &[&ExportStream]) -> Result<Vec<(f64, String)>> {
+    let mut markers = Vec::new();
+    for stream in streams {
+        let mut start = 0;
+        while start < stream.sample_count {
+            let len = EXPORT_BLOCK_SAMPLES.min(stream.sample_count - start);
+            let (times, values) = stream.read_block(start, len)?;
+            let SampleBlock::String(values) = values else {
+                anyhow::bail!("Stream '{}' is not a marker stream", stream.name);
+            };
+            for (time, sample) in times.iter().zip(values.chunks(stream.channel_count)) {
+                let text = annotation_text(&sample.join(", "));
+                // Name the source when markers of several streams are merged
+                let text = if streams.len() > 1 {
+                    format!("{}: {}", stream.name, text)
+                } else {
+                    text
+                };
+                markers.push((*time, text));
+            }
+            start += len;
+        }
+    }
+    markers.sort_by(|a, b| a.0.total_cmp(&b.0));
+    Ok(markers)
+}
+
+/// Annotation bytes of one data record: the time-keeping TAL, then one TAL per marker
+fn annotation_record(record_onset: f64, markers: &[(f64, String)]) -> Vec<u8> {
+    let mut bytes = format!("{:+.6}\x14\x14\x00", record_onset).into_bytes();
+    for (onset, text) in markers {
+        bytes.extend_from_slice(format!("{:+.6}\x14{}\x14\x00", onset, text).as_bytes());
+    }
+    bytes
+}
+
+/// Write the fixed header and the signal headers
+#[allow(clippy::too_many_arguments)]
+fn write_header<W: Write>(
+    out: &mut W,
+    format: SampleFormat,
+    patient: &str,
+    recording: &str,
+    start: NaiveDateTime,
+    records: u64,
+    signals: &[Signal],
+    annotation_samples: usize,
+) -> Result<()> {
+    let bdf = format.bytes == 3;
+    let signal_count = signals.len() + 1;
+    let mut header = Vec::with_capacity(256 * (signal_count + 1));
+
+    if bdf {
+        header.push(0xFF);
+        header.extend_from_slice(b"BIOSEMI");
+    } else {
+        header.extend_from_slice(&ascii_field("0", 8));
+    }
+    header.extend_from_slice(&ascii_field(patient, 80));
+    header.extend_from_slice(&ascii_field(recording, 80));
+    header.extend_from_slice(&ascii_field(&start.format("%d.%m.%y").to_string(), 8));
+    header.extend_from_slice(&ascii_field(&start.format("%H.%M.%S").to_string(), 8));
+    header.extend_from_slice(&ascii_field(&(256 * (signal_count + 1)).to_string(), 8));
+    header.extend_from_slice(&ascii_field(if bdf { "BDF+C" } else { "EDF+C" }, 44));
+    header.extend_from_slice(&ascii_field(&records.to_string(), 8));
+    header.extend_from_slice(&ascii_field(&RECORD_DURATION.to_string(), 8));
+    header.extend_from_slice(&ascii_field(&signal_count.to_string(), 4));
+
+    let annotation_label = if bdf { "BDF Annotations" } else { "EDF Annotations" };
+    let column = |header: &mut Vec<u8>, width: usize, value: &dyn Fn(&Signal) -> String, annotation: &str| {
+        for signal in signals {
+            header.extend_from_slice(&ascii_field(&value(signal), width));
+        }
+        header.extend_from_slice(&ascii_field(annotation, width));
+    };
+
+    column(&mut header, 16, &|s| s.label.clone(), annotation_label);
+    column(&mut header, 80, &|s| s.transducer.clone(), "");
+    column(&mut header, 8, &|s| s.dimension.clone(), "");
+    column(&mut header, 8, &|s| s.physical_min.0.clone(), "-1");
+    column(&mut header, 8, &|s| s.physical_max.0.clone(), "1");
+    column(&mut header, 8, &|_| format.digital_min.to_string(), &format.digital_min.to_string());
+    column(&mut header, 8, &|_| format.digital_max.to_string(), &format.digital_max.to_string());
+    column(&mut header, 80, &|_| String::new(), "");
+    column(&mut header, 8, &|s| s.samples_per_record.to_string(), &annotation_samples.to_string());
+    column(&mut header, 32, &|_| String::new(), "");
+
+    out.write_all(&header)?;
+    Ok(())
+}
+
+/// EDF+ date as `dd-MMM-yyyy` (`16-OCT-2026`)
+fn edf_plus_date(start: NaiveDateTime) -> String {
+    start.format("%d-%b-%Y").to_string().to_uppercase()
+}
+
+/// Path of part `index` (1-based) when an export is split: `experiment_002.edf`
+fn part_path(output: &Path, index: usize) -> PathBuf {
+    let stem = output.file_stem().map(|s| s.to_string_lossy().to_string()).unwrap_or_default();
+    let name = match output.extension() {
+        Some(ext) => format!("{}_{:03}.{}", stem, index, ext.to_string_lossy()),
+        None => format!("{}_{:03}", stem, index),
+    };
+    output.with_file_name(name)
+}
+
+/// Convert the store at `store_path` into EDF+ (or BDF+) file(s) at `output`
+pub fn export_edf(
+    store_path: &Path,
+    output: &Path,
+    options: &ExportOptions,
+    edf: &EdfOptions,
+) -> Result<Vec<EdfFileSummary>> {
+    if edf.resample_rate < 1.0 {
+        anyhow::bail!("Resample rate must be at least 1 Hz");
+    }
+    let format = SampleFormat::new(edf.bdf);
+    let streams = open_streams(store_path, options)?;
+
+    let mut numeric = Vec::new();
+    let mut marker_streams = Vec::new();
+    for stream in &streams {
+        if stream.sample_count == 0 {
+            eprintln!("Warning: stream '{}' is empty, skipping", stream.name);
+        } else if stream.is_marker() {
+            marker_streams.push(stream);
+        } else {
+            numeric.push(stream);
+        }
+    }
+    if numeric.is_empty() && marker_streams.is_empty() {
+        anyhow::bail!("No samples to export in {}", store_path.display());
+    }
+
+    // Record grid spans all numeric samples (markers only if there is no numeric stream)
+    let span_streams = if numeric.is_empty() { &marker_streams } else { &numeric };
+    let mut start = f64::INFINITY;
+    let mut end = f64::NEG_INFINITY;
+    for stream in span_streams {
+        if let Some((first, last)) = stream.timestamp_range()? {
+            start = start.min(first);
+            end = end.max(last);
+        }
+    }
+    let total_records = ((end - start) / RECORD_DURATION).floor() as u64 + 1;
+
+    // Signals and grid of the numeric streams
+    let mut signals = Vec::new();
+    let mut grid = Vec::new();
+    for stream in &numeric {
+        let nominal = stream.nominal_srate();
+        let rate = if nominal > 0.0 { nominal.round().max(1.0) } else { edf.resample_rate };
+        if nominal <= 0.0 {
+            eprintln!("Stream '{}' is irregular, resampling at {} Hz", stream.name, rate);
+        } else if rate != nominal {
+            eprintln!("Stream '{}': resampling {} Hz to {} Hz", stream.name, nominal, rate);
+        }
+        let samples_per_record = (rate * RECORD_DURATION) as usize;
+        let channels = stream.export_channels(options.include_bad_channels);
+        if channels.len() < stream.channel_count {
+            eprintln!(
+                "Stream '{}': leaving out {} bad channel(s)",
+                stream.name,
+                stream.channel_count - channels.len()
+            );
+        }
+        if channels.is_empty() {
+            continue;
+        }
+
+        let ranges = channel_ranges(stream)?;
+        grid.push(GridStream {
+            resampler: Resampler::new(stream)?,
+            samples_per_record,
+            first_signal: signals.len(),
+            channels: channels.clone(),
+        });
+        for channel in channels {
+            let (min, max) = ranges[channel];
+            let (label, transducer, dimension) = channel_header(stream, channel);
+            signals.push(Signal {
+                label,
+                transducer,
+                dimension,
+                physical_min: physical_bound(min, false)?,
+                physical_max: physical_bound(max, true)?,
+                samples_per_record,
+            });
+        }
+    }
+
+    // Markers grouped by data record
+    let markers = read_markers(&marker_streams)?;
+    let record_of = |time: f64| (((time - start) / RECORD_DURATION).floor().max(0.0) as u64).min(total_records - 1);
+    let mut record_markers: Vec<(u64, usize, usize)> = Vec::new();
+    for (i, (time, _)) in markers.iter().enumerate() {
+        let record = record_of(*time);
+        match record_markers.last_mut() {
+            Some((r, _, end)) if *r == record => *end = i + 1,
+            _ => record_markers.push((record, i, i + 1)),
+        }
+    }
+
+    // Room for the largest record's annotations, with slack for the longer onsets of later parts
+    let largest_annotations = record_markers
+        .iter()
+        .map(|(record, from, to)| {
+            let onsets: Vec<(f64, String)> = markers[*from..*to].iter().map(|(t, text)| (t - start + 1.0, text.clone())).collect();
+            annotation_record(*record as f64 + 1.0, &onsets).len()
+        })
+        .max()
+        .unwrap_or(0)
+        .max(annotation_record(total_records as f64 + 1.0, &[]).len());
+    let annotation_samples = (largest_annotations + 16).div_ceil(format.bytes);
+
+    let record_bytes = (signals.iter().map(|s| s.samples_per_record).sum::<usize>() + annotation_samples) * format.bytes;
+    let header_bytes = 256 * (signals.len() + 2);
+    let mut records_per_file = ((EDF_MAX_FILE_BYTES - header_bytes as u64) / record_bytes as u64).clamp(1, EDF_MAX_RECORDS);
+    if let Some(duration) = edf.split_duration {
+        records_per_file = records_per_file.min(((duration / RECORD_DURATION).floor() as u64).max(1));
+    }
+    let parts = total_records.div_ceil(records_per_file) as usize;
+
+    // Header identification from the recorder configuration
+    let config_text = |key: &str| {
+        streams.iter().find_map(|s| {
+            s.attributes
+                .get("recorder_config")
+                .and_then(|c| c.get(key))
+                .and_then(|v| v.as_str())
+                .filter(|v| !v.is_empty())
+                .map(|v| v.replace(' ', "_"))
+        })
+    };
+    let patient = format!("{} X X X", config_text("subject").unwrap_or_else(|| "X".to_string()));
+    let session = config_text("session_id").unwrap_or_else(|| "X".to_string());
+    let wall_clock = |time: f64| -> Option<DateTime<Utc>> { streams.iter().find_map(|s| s.wall_clock(time)) };
+    if wall_clock(start).is_none() {
+        eprintln!("Warning: no wall-clock anchor in the store, start time set to 01.01.85 00.00.00");
+    }
+
+    let mut summaries = Vec::with_capacity(parts);
+    let mut values = Vec::new();
+    let mut marker_groups = record_markers.iter().peekable();
+
+    for part in 0..parts {
+        let first_record = part as u64 * records_per_file;
+        let records = records_per_file.min(total_records - first_record);
+        let part_start = start + first_record as f64 * RECORD_DURATION;
+
+        // Header time has whole seconds; the remainder goes into the time-keeping TALs
+        let (start_time, subsecond) = match wall_clock(part_start) {
+            Some(time) => {
+                let local = time.with_timezone(&chrono::Local).naive_local();
+                let whole = local.with_nanosecond(0).unwrap_or(local);
+                (whole, (local - whole).num_microseconds().unwrap_or(0) as f64 / 1e6)
+            }
+            None => (
+                NaiveDateTime::parse_from_str("1985-01-01 00:00:00", "%Y-%m-%d %H:%M:%S")?,
+                0.0,
+            ),
+        };
+        let recording = format!(
+            "Startdate {} {} X lsl-recording-toolbox",
+            if start_time.year() >= 1985 { edf_plus_date(start_time) } else { "X".to_string() },
+            session
+        );
+
+        let path = if parts == 1 { output.to_path_buf() } else { part_path(output, part + 1) };
+        let mut out = BufWriter::new(File::create(&path)?);
+        write_header(&mut out, format, &patient, &recording, start_time, records, &signals, annotation_samples)?;
+
+        let mut annotations = 0;
+        let mut record = Vec::with_capacity(record_bytes);
+        for index in first_record..first_record + records {
+            record.clear();
+            let record_start = start + index as f64 * RECORD_DURATION;
+
+            for stream in grid.iter_mut() {
+                let channels = stream.resampler.stream.channel_count;
+                let samples = stream.samples_per_record;
+                values.resize(samples * channels, 0.0);
+                for i in 0..samples {
+                    let time = record_start + i as f64 * RECORD_DURATION / samples as f64;
+                    stream.resampler.sample_at(time, &mut values[i * channels..(i + 1) * channels])?;
+                }
+                for (signal_offset, &channel) in stream.channels.iter().enumerate() {
+                    let signal = &signals[stream.first_signal + signal_offset];
+                    for i in 0..samples {
+                        format.push(&mut record, signal.digital(values[i * channels + channel], format));
+                    }
+                }
+            }
+
+            let onsets: Vec<(f64, String)> = match marker_groups.next_if(|(r, _, _)| *r == index) {
+                Some((_, from, to)) => markers[*from..*to]
+                    .iter()
+                    .map(|(time, text)| (time - part_start + subsecond, text.clone()))
+                    .collect(),
+                None => Vec::new(),
+            };
+            annotations += onsets.len();
+            let mut tal = annotation_record((index - first_record) as f64 * RECORD_DURATION + subsecond, &onsets);
+            tal.resize(annotation_samples * format.bytes, 0);
+            record.extend_from_slice(&tal);
+
+            out.write_all(&record)?;
+        }
+        out.flush()?;
+
+        summaries.push(EdfFileSummary {
+            path,
+            records,
+            signals: signals.len(),
+            annotations,
+        });
+    }
+
+    Ok(summaries)
+}
@@ -0,0 +1,510 @@
+//! Conversion of recorded Zarr stores into other file formats
+//!
+//! [`ExportStream`] reads a stream's samples and timestamps in blocks, so stores larger
+//! than memory can be converted. Each format lives in its own submodule:
+//!
+//! - [`xdf`] - Extensible Data Format, as written by LabRecorder
+//! - [`edf`] - EDF+ and BDF+, for clinical EEG review software
+//! - [`table`] - One CSV, Parquet or NDJSON table per stream, or one stream to any writer
+//! - [`wide`] - One CSV or Parquet table of several streams on a common time base
+//! - [`audio`] - One channel as a WAV file for listening
+//!
+//! A stream opened with [`ExportStream::tolerate_damage`] reads around chunks that
+//! cannot be decoded: their samples come back as the array's fill value (`0`, or an
+//! empty string for markers) and their timestamps as `NaN`, and every affected sample
+//! range is reported on stderr and kept in [`ExportStream::damaged_ranges`]. Missing
+//! time chunks are treated the same way. A missing data chunk cannot be told apart
+//! from one the store skipped because it held only fill values, so it is read as such.
+
+pub mod audio;
+pub mod edf;
+pub mod table;
+pub mod wide;
+pub mod xdf;
+
+use anyhow::{Context, Result};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use zarrs::array::Array;
+use zarrs::array_subset::ArraySubset;
+use zarrs::filesystem::FilesystemStore;
+
+use crate::badchannels::BadChannelMask;
+use crate::zarr::time_index::TimeIndex;
+use crate::zarr::{parse_time_anchor, read_group_attributes, sample_array_path, stream_sample_count};
+
+/// Samples read from the store per block (and written per XDF samples chunk)
+pub const EXPORT_BLOCK_SAMPLES: u64 = 10_000;
+
+/// Output formats supported by `lsl-export`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum ExportFormat {
+    /// Extensible Data Format (MNE, EEGLAB, SigViewer, pyxdf)
+    Xdf,
+    /// EDF+ with 16-bit samples (clinical EEG viewers)
+    Edf,
+    /// BDF+ with 24-bit samples (BioSemi variant of EDF+)
+    Bdf,
+    /// One CSV table per stream
+    Csv,
+    /// One Parquet table per stream
+    Parquet,
+    /// One newline-delimited JSON file per stream, an object per sample
+    Ndjson,
+}
+
+impl ExportFormat {
+    /// File extension of the format (without dot)
+    pub fn extension(&self) -> &'static str {
+        match self {
+            ExportFormat::Xdf => "xdf",
+            ExportFormat::Edf => "edf",
+            ExportFormat::Bdf => "bdf",
+            ExportFormat::Csv => "csv",
+            ExportFormat::Parquet => "parquet",
+            ExportFormat::Ndjson => "ndjson",
+        }
+    }
+
+    /// Whether the format writes one file per stream into an output directory
+    pub fn per_stream(&self) -> bool {
+        matches!(self, ExportFormat::Csv | ExportFormat::Parquet | ExportFormat::Ndjson)
+    }
+}
+
+/// Options shared by all export formats
+#[derive(Debug, Clone, Default)]
+pub struct ExportOptions {
+    /// Streams to export (all streams if empty)
+    pub streams: Vec<String>,
+    /// Use `aligned_time` (from lsl-sync) instead of the original timestamps where available
+    pub aligned_time: bool,
+    /// Keep channels marked in the `bad_channels` attribute in EDF+ and table exports
+    pub include_bad_channels: bool,
+    /// Substitute fill values for unreadable chunks instead of failing (see [`ExportStream::tolerate_damage`])
+    pub tolerate_damage: bool,
+}
+
+/// Samples of one array that could not be read and were substituted
+#[derive(Debug, Clone, PartialEq)]
+pub struct DamagedRange {
+    /// Array within the stream group: `data`, `time` or `aligned_time`
+    pub array: String,
+    /// First affected sample
+    pub start: u64,
+    /// End of the affected samples (exclusive)
+    pub end: u64,
+    pub reason: String,
+}
+
+/// Interleaved (sample-major) values of a block of samples
+#[derive(Debug, Clone, PartialEq)]
+pub enum SampleBlock {
+    Float32(Vec<f32>),
+    Float64(Vec<f64>),
+    Int32(Vec<i32>),
+    Int16(Vec<i16>),
+    Int8(Vec<i8>),
+    String(Vec<String>),
+}
+
+impl SampleBlock {
+    /// Numeric values as f64 (`None` for string samples)
+    pub fn to_f64(&self) -> Option<Vec<f64>> {
+        match self {
+            SampleBlock::Float32(v) => Some(v.iter().map(|&x| x as f64).collect()),
+            SampleBlock::Float64(v) => Some(v.clone()),
+            SampleBlock::Int32(v) => Some(v.iter().map(|&x| x as f64).collect()),
+            SampleBlock::Int16(v) => Some(v.iter().map(|&x| x as f64).collect()),
+            SampleBlock::Int8(v) => Some(v.iter().map(|&x| x as f64).collect()),
+            SampleBlock::String(_) => None,
+        }
+    }
+}
+
+/// A recorded stream opened for export
+pub struct ExportStream {
+    /// Stream group name in the store
+    pub name: String,
+    /// `stream_info` group attribute (empty object if missing)
+    pub stream_info: serde_json::Value,
+    /// All stream group attributes
+    pub attributes: serde_json::Value,
+    /// Channel format as stored by the recorder (`Float32`, `Double64`, `String`, ...)
+    pub channel_format: String,
+    pub channel_count: usize,
+    pub sample_count: u64,
+    /// Channels marked bad in the `bad_channels` attribute
+    pub bad_channels: BadChannelMask,
+    data: Array<FilesystemStore>,
+    time: Array<FilesystemStore>,
+    aligned: Option<Array<FilesystemStore>>,
+    // Chunk index of the timestamps in use (none for aligned_time)
+    index: Option<TimeIndex>,
+    tolerate_damage: bool,
+    damaged: Mutex<Vec<DamagedRange>>,
+}
+
+impl ExportStream {
+    /// Open a stream's sample and time arrays
+    pub fn open(store: &Arc<FilesystemStore>, name: &str, aligned_time: bool) -> Result<Self> {
+        let attributes = read_group_attributes(store, name)?;
+        let stream_info = attributes.get("stream_info").cloned().unwrap_or_else(|| serde_json::json!({}));
+
+        let data = Array::<FilesystemStore>::open(store.clone(), &sample_array_path(store, name))
+            .with_context(|| format!("Failed to open sample array of stream '{}'", name))?;
+
+        let aligned_path = format!("/{}/aligned_time", name);
+        let aligned = Array::<FilesystemStore>::open(store.clone(), &aligned_path).ok();
+        let use_aligned = aligned_time && aligned.is_some();
+        let time_path = if use_aligned {
+            aligned_path
+        } else {
+            if aligned_time {
+                eprintln!("Warning: stream '{}' has no aligned_time, using original timestamps", name);
+            }
+            format!("/{}/time", name)
+        };
+        let time = Array::<FilesystemStore>::open(store.clone(), &time_path)
+            .with_context(|| format!("Failed to open time array of stream '{}'", name))?;
+        let index = if use_aligned { None } else { TimeIndex::load(store, name)? };
+
+        // Samples are the last axis: [channels, samples] for data, [samples] for events
+        let channel_count = match data.shape() {
+            [_] => 1,
+            shape => shape[0] as usize,
+        };
+        let data_samples = data.shape().last().copied().unwrap_or(0);
+        let sample_count = stream_sample_count(&attributes, time.shape()[0].min(data_samples));
+
+        let channel_format = stream_info
+            .get("channel_format")
+            .and_then(|v| v.as_str())
+            .unwrap_or("Float32")
+            .to_string();
+        let bad_channels = BadChannelMask::from_attributes(&attributes, channel_count);
+
+        Ok(Self {
+            name: name.to_string(),
+            stream_info,
+            attributes,
+            channel_format,
+            channel_count,
+            sample_count,
+            bad_channels,
+            data,
+            time,
+            aligned,
+            index,
+            tolerate_damage: false,
+            damaged: Mutex::new(Vec::new()),
+        })
+    }
+
+    /// Read around unreadable chunks instead of failing, substituting fill values
+    pub fn tolerate_damage(mut self, tolerate: bool) -> Self {
+        self.tolerate_damage = tolerate;
+        self
+    }
+
+    /// Sample ranges substituted so far because their chunks could not be read
+    pub fn damaged_ranges(&self) -> Vec<DamagedRange> {
+        self.damaged.lock().map(|damaged| damaged.clone()).unwrap_or_default()
+    }
+
+    /// Nominal sample rate from the stream info (0 for irregular streams)
+    pub fn nominal_srate(&self) -> f64 {
+        self.stream_info.get("nominal_srate").and_then(|v| v.as_f64()).unwrap_or(0.0)
+    }
+
+    /// Channel labels from the stored channel description (see [`channel_label`])
+    pub fn channel_labels(&self) -> Vec<String> {
+        (0..self.channel_count).map(|i| channel_label(&self.stream_info, i)).collect()
+    }
+
+    /// Index of a channel given by label or 0-based index
+    pub fn channel_index(&self, name: &str) -> Result<usize> {
+        let labels = self.channel_labels();
+        labels
+            .iter()
+            .position(|label| label == name)
+            .or_else(|| name.parse::<usize>().ok().filter(|&i| i < self.channel_count))
+            .ok_or_else(|| {
+                anyhow::anyhow!(
+                    "Channel '{}' not found in stream '{}' (channels: {})",
+                    name,
+                    self.name,
+                    labels.join(", ")
+                )
+            })
+    }
+
+    /// Channels to export: all, or only those not marked bad
+    pub fn export_channels(&self, include_bad: bool) -> Vec<usize> {
+        if include_bad {
+            (0..self.channel_count).collect()
+        } else {
+            self.bad_channels.good_channels()
+        }
+    }
+
+    /// Whether the store has lsl-sync's `aligned_time` for this stream
+    pub fn has_aligned_time(&self) -> bool {
+        self.aligned.is_some()
+    }
+
+    /// Read `len` aligned timestamps starting at `start`, if the stream has them
+    pub fn read_aligned_times(&self, start: u64, len: u64) -> Result<Option<Vec<f64>>> {
+        let Some(ref aligned) = self.aligned else {
+            return Ok(None);
+        };
+        self.read_times("aligned_time", aligned, start, len).map(Some)
+    }
+
+    /// The stream's chunk timestamp index, if it has one for the timestamps in use
+    pub fn time_index(&self) -> Option<&TimeIndex> {
+        self.index.as_ref()
+    }
+
+    /// Index of the first sample with a timestamp at or after `time`
+    ///
+    /// The time index narrows the search to one chunk; streams recorded without one
+    /// are binary-searched over the whole time array.
+    pub fn sample_index(&self, time: f64) -> Result<u64> {
+        let range = match self.index {
+            Some(ref index) => index.locate(time, self.sample_count),
+            None => 0..self.sample_count,
+        };
+        let (mut low, mut high) = (range.start, range.end);
+        if let Some(ref index) = self.index {
+            // Whole chunk fits in one read
+            if high > low && high - low <= index.chunk_samples {
+                let (timestamps, _) = self.read_block(low, high - low)?;
+                return Ok(low + timestamps.partition_point(|&t| t < time) as u64);
+            }
+        }
+        while low < high {
+            let mid = low + (high - low) / 2;
+            let (timestamps, _) = self.read_block(mid, 1)?;
+            if timestamps[0] < time {
+                low = mid + 1;
+            } else {
+                high = mid;
+            }
+        }
+        Ok(low)
+    }
+
+    /// Whether the stream holds string (marker) samples
+    pub fn is_marker(&self) -> bool {
+        self.channel_format == "String"
+    }
+
+    /// First and last timestamp, or `None` if the stream is empty
+    pub fn timestamp_range(&self) -> Result<Option<(f64, f64)>> {
+        if self.sample_count == 0 {
+            return Ok(None);
+        }
+        let (first, _) = self.read_block(0, 1)?;
+        let (last, _) = self.read_block(self.sample_count - 1, 1)?;
+        Ok(Some((first[0], last[0])))
+    }
+
+    /// Wall-clock time of an LSL timestamp, from the stream's `start_time` anchor
+    pub fn wall_clock(&self, timestamp: f64) -> Option<chrono::DateTime<chrono::Utc>> {
+        let anchor = self.attributes.get("start_time")?;
+        let anchor_clock = anchor.get("lsl_clock")?.as_f64()?;
+        let anchor_time = parse_time_anchor(anchor)?;
+        let offset = chrono::Duration::microseconds(((timestamp - anchor_clock) * 1e6).round() as i64);
+        Some(anchor_time + offset)
+    }
+
+    /// Read `len` samples starting at `start`: timestamps and interleaved values
+    pub fn read_block(&self, start: u64, len: u64) -> Result<(Vec<f64>, SampleBlock)> {
+        let timestamps = self.read_times("time", &self.time, start, len)?;
+
+        macro_rules! read_interleaved {
+            ($ty:ty, $variant:ident, $fill:expr) => {{
+                let read = |start: u64, len: u64| -> Result<Vec<$ty>> {
+                    let subset = match self.data.shape().len() {
+                        1 => ArraySubset::new_with_start_shape(vec![start], vec![len])?,
+                        _ => ArraySubset::new_with_start_shape(vec![0, start], vec![self.channel_count as u64, len])?,
+                    };
+                    let block = self.data.retrieve_array_subset_ndarray::<$ty>(&subset)?;
+                    // [channels, samples] -> sample-major order
+                    Ok(block.reversed_axes().iter().cloned().collect())
+                };
+                let fill = |samples: u64| vec![$fill; samples as usize * self.channel_count];
+                SampleBlock::$variant(self.read_pieces("data", &self.data, start, len, read, fill)?)
+            }};
+        }
+
+        let values = match self.channel_format.as_str() {
+            "Float32" => read_interleaved!(f32, Float32, 0.0),
+            "Double64" | "Float64" => read_interleaved!(f64, Float64, 0.0),
+            "Int32" => read_interleaved!(i32, Int32, 0),
+            "Int16" => read_interleaved!(i16, Int16, 0),
+            "Int8" => read_interleaved!(i8, Int8, 0),
+            "String" => read_interleaved!(String, String, String::new()),
+            other => anyhow::bail!("Unsupported channel format '{}' in stream '{}'", other, self.name),
+        };
+
+        Ok((timestamps, values))
+    }
+
+    /// Read timestamps of a time array; damaged and missing chunks read as `NaN`
+    fn read_times(&self, name: &str, array: &Array<FilesystemStore>, start: u64, len: u64) -> Result<Vec<f64>> {
+        let read = |start: u64, len: u64| -> Result<Vec<f64>> {
+            let subset = ArraySubset::new_with_start_shape(vec![start], vec![len])?;
+            Ok(array.retrieve_array_subset_ndarray::<f64>(&subset)?.into_iter().collect())
+        };
+        let fill = |samples: u64| vec![f64::NAN; samples as usize];
+        let mut timestamps = self.read_pieces(name, array, start, len, read, fill)?;
+
+        // A missing chunk reads as the fill value 0, which no recorded timestamp has
+        if self.tolerate_damage && timestamps.contains(&0.0) {
+            let chunk = sample_chunk_len(array).unwrap_or(len.max(1));
+            for (offset, timestamp) in timestamps.iter_mut().enumerate() {
+                if *timestamp == 0.0 {
+                    *timestamp = f64::NAN;
+                    self.report_damage(name, (start + offset as u64) / chunk * chunk, chunk, "chunk missing");
+                }
+            }
+        }
+        Ok(timestamps)
+    }
+
+    /// Read `len` samples of `array` from `start` with `read`
+    ///
+    /// When tolerating damage and the read fails, the range is read again chunk by
+    /// chunk, and chunks that still fail are replaced by `fill` and reported.
+    fn read_pieces<T>(
+        &self,
+        name: &str,
+        array: &Array<FilesystemStore>,
+        start: u64,
+        len: u64,
+        read: impl Fn(u64, u64) -> Result<Vec<T>>,
+        fill: impl Fn(u64) -> Vec<T>,
+    ) -> Result<Vec<T>> {
+        let result = read(start, len);
+        if result.is_ok() || !self.tolerate_damage {
+            return result;
+        }
+
+        let chunk = sample_chunk_len(array).unwrap_or(len.max(1));
+        let end = start + len;
+        let mut values = Vec::new();
+        let mut piece = start;
+        while piece < end {
+            let chunk_start = piece / chunk * chunk;
+            let piece_end = (chunk_start + chunk).min(end);
+            match read(piece, piece_end - piece) {
+                Ok(piece_values) => values.extend(piece_values),
+                Err(e) => {
+                    self.report_damage(name, chunk_start, chunk, &format!("{:#}", e));
+                    values.extend(fill(piece_end - piece));
+                }
+            }
+            piece = piece_end;
+        }
+        Ok(values)
+    }
+
+    /// Record a substituted chunk once, with a warning on stderr
+    fn report_damage(&self, array: &str, chunk_start: u64, chunk_len: u64, reason: &str) {
+        let Ok(mut damaged) = self.damaged.lock() else {
+            return;
+        };
+        if damaged.iter().any(|range| range.array == array && range.start == chunk_start) {
+            return;
+        }
+        let range = DamagedRange {
+            array: array.to_string(),
+            start: chunk_start,
+            end: (chunk_start + chunk_len).min(self.sample_count).max(chunk_start),
+            reason: reason.to_string(),
+        };
+        eprintln!(
+            "Warning: stream '{}': {} samples {}-{} unreadable ({}), substituted",
+            self.name,
+            array,
+            range.start,
+            range.end,
+            range.reason
+        );
+        damaged.push(range);
+    }
+}
+
+/// Chunk length of an array along its sample axis (the last one)
+fn sample_chunk_len(array: &Array<FilesystemStore>) -> Option<u64> {
+    let origin = vec![0; array.shape().len()];
+    let shape = array.chunk_grid().chunk_shape(&origin).ok()??;
+    shape.last().map(|length| length.get())
+}
+
+/// Label of a channel in a `stream_info` attribute: `label`, then `name`, else `ch<index>`
+pub fn channel_label(stream_info: &serde_json::Value, index: usize) -> String {
+    let channel = stream_info
+        .get("channels")
+        .and_then(|c| c.as_array())
+        .and_then(|c| c.get(index));
+    ["label", "name"]
+        .iter()
+        .find_map(|key| channel.and_then(|c| c.get(*key)).and_then(|v| v.as_str()))
+        .filter(|s| !s.is_empty())
+        .map(String::from)
+        .unwrap_or_else(|| format!("ch{}", index))
+}
+
+/// Names of all streams in a store, sorted
+pub fn list_streams(store_path: &Path) -> Result<Vec<String>> {
+    let mut names: Vec<String> = std::fs::read_dir(store_path)?
+        .filter_map(|e| e.ok())
+        .filter(|e| e.path().join("time").join("zarr.json").exists())
+        .map(|e| e.file_name().to_string_lossy().to_string())
+        .collect();
+    names.sort();
+    Ok(names)
+}
+
+/// Open the streams selected by `options` (all streams if none are named)
+pub fn open_streams(store_path: &Path, options: &ExportOptions) -> Result<Vec<ExportStream>> {
+    if !store_path.join("zarr.json").exists() {
+        anyhow::bail!("Not a Zarr store: {}", store_path.display());
+    }
+    let store = Arc::new(FilesystemStore::new(store_path)?);
+    let available = list_streams(store_path)?;
+
+    let names = if options.streams.is_empty() {
+        available
+    } else {
+        for name in &options.streams {
+            if !available.contains(name) {
+                anyhow::bail!("Stream '{}' not found (available: {})", name, available.join(", "));
+            }
+        }
+        options.streams.clone()
+    };
+
+    names
+        .iter()
+        .map(|name| {
+            ExportStream::open(&store, name, options.aligned_time)
+                .map(|stream| stream.tolerate_damage(options.tolerate_damage))
+        })
+        .collect()
+}
+
+/// Default output path: the store path with the format's extension (`experiment.zarr` -> `experiment.xdf`),
+/// or a directory for per-stream formats (`experiment.zarr` -> `experiment_csv`)
+pub fn default_output_path(store_path: &Path, format: ExportFormat) -> PathBuf {
+    let trimmed = store_path.components().as_path();
+    if format.per_stream() {
+        let stem = trimmed.file_stem().map(|s| s.to_string_lossy().to_string()).unwrap_or_default();
+        trimmed.with_file_name(format!("{}_{}", stem, format.extension()))
+    } else {
+        trimmed.with_extension(format.extension())
+    }
+}
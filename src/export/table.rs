@@ -0,0 +1,391 @@
+//! Per-stream tables (CSV, Parquet, NDJSON)
+//!
+//! Each stream becomes one table in the output directory, named after the stream
+//! (`EMG.csv`, `EMG.parquet`, `EMG.ndjson`). Rows are samples; columns are `time`,
+//! `aligned_time` (when lsl-sync has been run) and one column per channel, named by
+//! channel label. NDJSON holds one object per sample with the columns as keys.
+//!
+//! [`write_table`] streams a single stream as CSV or NDJSON to any writer instead,
+//! e.g. stdout for `lsl-export --stdout`; samples are read and written block by block.
+//!
+//! Time ranges are given in seconds from the start of the recording, i.e. the
+//! earliest first timestamp among the exported streams, so slices of different
+//! streams cover the same period.
+
+use anyhow::Result;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use super::{open_streams, ExportFormat, ExportOptions, ExportStream, SampleBlock, EXPORT_BLOCK_SAMPLES};
+
+/// Samples per Parquet row group
+#[cfg(feature = "parquet")]
+const PARQUET_ROW_GROUP_SAMPLES: u64 = 100_000;
+
+/// Options specific to the table formats
+#[derive(Debug, Clone, Default)]
+pub struct TableOptions {
+    /// Channels to export, by label or 0-based index (all channels if empty)
+    pub channels: Vec<String>,
+    /// Start of the exported range in seconds from the start of the recording
+    pub start: Option<f64>,
+    /// End of the exported range in seconds from the start of the recording
+    pub end: Option<f64>,
+}
+
+/// One table written by a table export
+#[derive(Debug, Clone)]
+pub struct TableFileSummary {
+    pub stream: String,
+    pub path: PathBuf,
+    pub rows: u64,
+    pub columns: usize,
+}
+
+/// A stream restricted to the selected channels and sample range
+struct TableSlice<'a> {
+    stream: &'a ExportStream,
+    channels: Vec<usize>,
+    labels: Vec<String>,
+    start: u64,
+    end: u64,
+}
+
+impl TableSlice<'_> {
+    fn column_names(&self) -> Vec<String> {
+        let mut names = vec!["time".to_string()];
+        if self.stream.has_aligned_time() {
+            names.push("aligned_time".to_string());
+        }
+        names.extend(self.channels.iter().map(|&c| self.labels[c].clone()));
+        names
+    }
+
+    /// Visit the slice in blocks of up to `block_samples` samples: timestamps, aligned timestamps, values
+    fn for_each_block(
+        &self,
+        block_samples: u64,
+        mut f: impl FnMut(&[f64], Option<&[f64]>, &SampleBlock) -> Result<()>,
+    ) -> Result<()> {
+        let mut start = self.start;
+        while start < self.end {
+            let len = block_samples.min(self.end - start);
+            let (times, values) = self.stream.read_block(start, len)?;
+            let aligned = self.stream.read_aligned_times(start, len)?;
+            f(&times, aligned.as_deref(), &values)?;
+            start += len;
+        }
+        Ok(())
+    }
+}
+
+/// Channel indices selected by label or 0-based index
+///
+/// Without a selection all channels are exported, except bad ones unless `include_bad`
+/// is set; channels named explicitly are exported even when marked bad.
+fn select_channels(stream: &ExportStream, wanted: &[String], include_bad: bool) -> Result<Vec<usize>> {
+    if wanted.is_empty() {
+        return Ok(stream.export_channels(include_bad));
+    }
+    wanted.iter().map(|name| stream.channel_index(name)).collect()
+}
+
+/// Text of one value for CSV output
+fn cell(values: &SampleBlock, index: usize) -> String {
+    match values {
+        SampleBlock::Float32(v) => v[index].to_string(),
+        SampleBlock::Float64(v) => v[index].to_string(),
+        SampleBlock::Int32(v) => v[index].to_string(),
+        SampleBlock::Int16(v) => v[index].to_string(),
+        SampleBlock::Int8(v) => v[index].to_string(),
+        SampleBlock::String(v) => v[index].clone(),
+    }
+}
+
+/// Value of one cell for NDJSON output; non-finite numbers become `null`
+fn json_cell(values: &SampleBlock, index: usize) -> serde_json::Value {
+    match values {
+        SampleBlock::Float32(v) => serde_json::json!(v[index]),
+        SampleBlock::Float64(v) => serde_json::json!(v[index]),
+        SampleBlock::Int32(v) => serde_json::json!(v[index]),
+        SampleBlock::Int16(v) => serde_json::json!(v[index]),
+        SampleBlock::Int8(v) => serde_json::json!(v[index]),
+        SampleBlock::String(v) => serde_json::json!(v[index]),
+    }
+}
+
+fn write_csv(slice: &TableSlice, output: impl Write) -> Result<()> {
+    let mut writer = csv::Writer::from_writer(output);
+    writer.write_record(slice.column_names())?;
+
+    let channel_count = slice.stream.channel_count;
+    let mut record = Vec::new();
+    slice.for_each_block(EXPORT_BLOCK_SAMPLES, |times, aligned, values| {
+        for (i, time) in times.iter().enumerate() {
+            record.clear();
+            record.push(time.to_string());
+            if let Some(aligned) = aligned {
+                record.push(aligned[i].to_string());
+            }
+            for &channel in &slice.channels {
+                record.push(cell(values, i * channel_count + channel));
+            }
+            writer.write_record(&record)?;
+        }
+        Ok(())
+    })?;
+
+    writer.flush()?;
+    Ok(())
+}
+
+fn write_ndjson(slice: &TableSlice, mut output: impl Write) -> Result<()> {
+    let names = slice.column_names();
+    let time_columns = names.len() - slice.channels.len();
+    let channel_count = slice.stream.channel_count;
+    slice.for_each_block(EXPORT_BLOCK_SAMPLES, |times, aligned, values| {
+        for (i, time) in times.iter().enumerate() {
+            let mut row = serde_json::Map::with_capacity(names.len());
+            row.insert(names[0].clone(), serde_json::json!(time));
+            if let Some(aligned) = aligned {
+                row.insert(names[1].clone(), serde_json::json!(aligned[i]));
+            }
+            for (name, &channel) in names[time_columns..].iter().zip(&slice.channels) {
+                row.insert(name.clone(), json_cell(values, i * channel_count + channel));
+            }
+            serde_json::to_writer(&mut output, &row)?;
+            output.write_all(b"\n")?;
+        }
+        Ok(())
+    })?;
+
+    output.flush()?;
+    Ok(())
+}
+
+#[cfg(feature = "parquet")]
+fn write_parquet(slice: &TableSlice, path: &Path) -> Result<()> {
+    use parquet::basic::{Compression, LogicalType, Repetition, Type as PhysicalType};
+    use parquet::data_type::{ByteArray, ByteArrayType, DoubleType, FloatType, Int32Type};
+    use parquet::file::properties::WriterProperties;
+    use parquet::file::writer::SerializedFileWriter;
+    use parquet::schema::types::Type;
+    use std::sync::Arc;
+
+    let (physical, logical) = match slice.stream.channel_format.as_str() {
+        "Float32" => (PhysicalType::FLOAT, None),
+        "Double64" | "Float64" => (PhysicalType::DOUBLE, None),
+        "Int32" => (PhysicalType::INT32, None),
+        "Int16" => (PhysicalType::INT32, Some(LogicalType::Integer { bit_width: 16, is_signed: true })),
+        "Int8" => (PhysicalType::INT32, Some(LogicalType::Integer { bit_width: 8, is_signed: true })),
+        "String" => (PhysicalType::BYTE_ARRAY, Some(LogicalType::String)),
+        other => anyhow::bail!("Unsupported channel format '{}' in stream '{}'", other, slice.stream.name),
+    };
+
+    let time_columns = slice.column_names().len() - slice.channels.len();
+    let fields = slice
+        .column_names()
+        .iter()
+        .enumerate()
+        .map(|(i, name)| {
+            let (physical, logical) = if i < time_columns {
+                (PhysicalType::DOUBLE, None)
+            } else {
+                (physical, logical.clone())
+            };
+            Type::primitive_type_builder(name, physical)
+                .with_repetition(Repetition::REQUIRED)
+                .with_logical_type(logical)
+                .build()
+                .map(Arc::new)
+        })
+        .collect::<parquet::errors::Result<Vec<_>>>()?;
+    let schema = Arc::new(Type::group_type_builder("stream").with_fields(fields).build()?);
+    let properties = Arc::new(WriterProperties::builder().set_compression(Compression::SNAPPY).build());
+    let mut writer = SerializedFileWriter::new(std::fs::File::create(path)?, schema, properties)?;
+
+    let channel_count = slice.stream.channel_count;
+    slice.for_each_block(PARQUET_ROW_GROUP_SAMPLES, |times, aligned, values| {
+        let mut row_group = writer.next_row_group()?;
+
+        let time_columns = std::iter::once(times).chain(aligned);
+        for column in time_columns {
+            if let Some(mut writer) = row_group.next_column()? {
+                writer.typed::<DoubleType>().write_batch(column, None, None)?;
+                writer.close()?;
+            }
+        }
+
+        for &channel in &slice.channels {
+            let Some(mut writer) = row_group.next_column()? else {
+                break;
+            };
+            let indices = (0..times.len()).map(|i| i * channel_count + channel);
+            match values {
+                SampleBlock::Float32(v) => {
+                    let column: Vec<f32> = indices.map(|i| v[i]).collect();
+                    writer.typed::<FloatType>().write_batch(&column, None, None)?;
+                }
+                SampleBlock::Float64(v) => {
+                    let column: Vec<f64> = indices.map(|i| v[i]).collect();
+                    writer.typed::<DoubleType>().write_batch(&column, None, None)?;
+                }
+                SampleBlock::Int32(v) => {
+                    let column: Vec<i32> = indices.map(|i| v[i]).collect();
+                    writer.typed::<Int32Type>().write_batch(&column, None, None)?;
+                }
+                SampleBlock::Int16(v) => {
+                    let column: Vec<i32> = indices.map(|i| v[i] as i32).collect();
+                    writer.typed::<Int32Type>().write_batch(&column, None, None)?;
+                }
+                SampleBlock::Int8(v) => {
+                    let column: Vec<i32> = indices.map(|i| v[i] as i32).collect();
+                    writer.typed::<Int32Type>().write_batch(&column, None, None)?;
+                }
+                SampleBlock::String(v) => {
+                    let column: Vec<ByteArray> = indices.map(|i| ByteArray::from(v[i].as_str())).collect();
+                    writer.typed::<ByteArrayType>().write_batch(&column, None, None)?;
+                }
+            }
+            writer.close()?;
+        }
+
+        row_group.close()?;
+        Ok(())
+    })?;
+
+    writer.close()?;
+    Ok(())
+}
+
+#[cfg(not(feature = "parquet"))]
+fn write_parquet(_slice: &TableSlice, _path: &Path) -> Result<()> {
+    anyhow::bail!("Parquet output requires the `parquet` feature")
+}
+
+/// Open the streams of a table export
+///
+/// Both time columns are written, so the original timestamps drive range selection.
+fn open_table_streams(store_path: &Path, options: &ExportOptions) -> Result<Vec<ExportStream>> {
+    let options = ExportOptions {
+        aligned_time: false,
+        ..options.clone()
+    };
+    let streams = open_streams(store_path, &options)?;
+    if streams.is_empty() {
+        anyhow::bail!("No streams to export in {}", store_path.display());
+    }
+    Ok(streams)
+}
+
+/// Earliest first timestamp among `streams`, the origin of `--start`/`--end`
+fn recording_start(streams: &[ExportStream]) -> Result<f64> {
+    let mut recording_start = f64::INFINITY;
+    for stream in streams {
+        if let Some((first, _)) = stream.timestamp_range()? {
+            recording_start = recording_start.min(first);
+        }
+    }
+    Ok(recording_start)
+}
+
+/// The selected channels and time range of one stream
+fn table_slice<'a>(
+    stream: &'a ExportStream,
+    table: &TableOptions,
+    include_bad: bool,
+    recording_start: f64,
+) -> Result<TableSlice<'a>> {
+    let channels = select_channels(stream, &table.channels, include_bad)?;
+    let start = match table.start {
+        Some(t) => stream.sample_index(recording_start + t)?,
+        None => 0,
+    };
+    let end = match table.end {
+        Some(t) => stream.sample_index(recording_start + t)?,
+        None => stream.sample_count,
+    };
+    Ok(TableSlice {
+        stream,
+        channels,
+        labels: stream.channel_labels(),
+        start,
+        end: end.max(start),
+    })
+}
+
+/// Write one table per stream of the store at `store_path` into the directory `output`
+pub fn export_tables(
+    store_path: &Path,
+    output: &Path,
+    format: ExportFormat,
+    options: &ExportOptions,
+    table: &TableOptions,
+) -> Result<Vec<TableFileSummary>> {
+    let streams = open_table_streams(store_path, options)?;
+    let recording_start = recording_start(&streams)?;
+
+    std::fs::create_dir_all(output)?;
+    let mut summaries = Vec::with_capacity(streams.len());
+    for stream in &streams {
+        let slice = table_slice(stream, table, options.include_bad_channels, recording_start)?;
+
+        let path = output.join(format!("{}.{}", stream.name, format.extension()));
+        match format {
+            ExportFormat::Csv => write_csv(&slice, std::fs::File::create(&path)?)?,
+            ExportFormat::Parquet => write_parquet(&slice, &path)?,
+            ExportFormat::Ndjson => write_ndjson(&slice, std::io::BufWriter::new(std::fs::File::create(&path)?))?,
+            other => anyhow::bail!("{:?} is not a table format", other),
+        }
+
+        summaries.push(TableFileSummary {
+            stream: stream.name.clone(),
+            path,
+            rows: slice.end - slice.start,
+            columns: slice.column_names().len(),
+        });
+    }
+
+    Ok(summaries)
+}
+
+/// Write the table of a single stream to `output` as CSV or NDJSON, in sample order
+///
+/// `options` must select exactly one stream, unless the store holds only one.
+/// Returns the number of rows written.
+pub fn write_table(
+    store_path: &Path,
+    output: impl Write,
+    format: ExportFormat,
+    options: &ExportOptions,
+    table: &TableOptions,
+) -> Result<u64> {
+    if !matches!(format, ExportFormat::Csv | ExportFormat::Ndjson) {
+        anyhow::bail!("Streamed tables are written as csv or ndjson, not {}", format.extension());
+    }
+    let streams = open_table_streams(store_path, options)?;
+    let [stream] = streams.as_slice() else {
+        let names: Vec<&str> = streams.iter().map(|stream| stream.name.as_str()).collect();
+        anyhow::bail!("Choose one stream with --stream (available: {})", names.join(", "));
+    };
+    let slice = table_slice(stream, table, options.include_bad_channels, recording_start(&streams)?)?;
+    match format {
+        ExportFormat::Csv => write_csv(&slice, output)?,
+        _ => write_ndjson(&slice, output)?,
+    }
+    Ok(slice.end - slice.start)
+}
+
+/// Whether `error` is a write to a closed pipe, e.g. `lsl-export --stdout | head`
+pub fn is_broken_pipe(error: &anyhow::Error) -> bool {
+    error.chain().any(|cause| {
+        let io = match cause.downcast_ref::<csv::Error>() {
+            Some(error) => match error.kind() {
+                csv::ErrorKind::Io(io) => Some(io),
+                _ => None,
+            },
+            None => cause.downcast_ref::<std::io::Error>(),
+        };
+        io.is_some_and(|io| io.kind() == std::io::ErrorKind::BrokenPipe)
+    })
+}
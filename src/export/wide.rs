@@ -0,0 +1,319 @@
+//! One wide table of several streams on a common time base (`lsl-export wide`)
+//!
+//! Regular streams are interpolated onto one uniform time base and written as a
+//! single CSV or Parquet table: a `time` column (LSL clock, or lsl-sync's aligned time
+//! with `aligned_time`), then one column per channel of every stream, named
+//! `<stream>.<channel label>`. This is the layout statistics packages
+//! load directly, without joining per-stream tables on timestamps in pandas or R.
+//!
+//! The time base runs at `rate` Hz (default: the highest nominal rate of the selected
+//! streams) over the period in which all of them have data, starting at the latest
+//! first timestamp. `start`/`end` narrow it, in seconds from the start of the
+//! recording as for [per-stream tables](super::table). Streams are resampled as by
+//! `lsl-sync --resample-to` (see [`crate::resample`]): linearly, low-pass filtered
+//! when the rate is below their nominal rate, and missing (empty CSV cells, Parquet
+//! nulls) inside gaps. Marker and irregular streams have no values between samples
+//! and are left out.
+
+use anyhow::{Context, Result};
+use std::path::{Path, PathBuf};
+
+use super::{open_streams, ExportFormat, ExportOptions, ExportStream, EXPORT_BLOCK_SAMPLES};
+use crate::gaps::DEFAULT_GAP_FACTOR;
+use crate::resample::{anti_alias_kernel, resample_block};
+
+/// Options specific to wide tables
+#[derive(Debug, Clone, Default)]
+pub struct WideOptions {
+    /// Rate of the common time base in Hz (highest nominal rate of the streams if not set)
+    pub rate: Option<f64>,
+    /// Start of the exported range in seconds from the start of the recording
+    pub start: Option<f64>,
+    /// End of the exported range in seconds from the start of the recording
+    pub end: Option<f64>,
+}
+
+/// The table written by [`export_wide`]
+#[derive(Debug, Clone)]
+pub struct WideTableSummary {
+    pub path: PathBuf,
+    pub rows: u64,
+    pub columns: usize,
+    /// Rate of the time base in Hz
+    pub rate: f64,
+    /// Streams in the table, in column order
+    pub streams: Vec<String>,
+    /// Streams left out, with the reason
+    pub skipped: Vec<(String, String)>,
+}
+
+/// A stream's selected channels and resampling filter
+struct WideColumns<'a> {
+    stream: &'a ExportStream,
+    channels: Vec<usize>,
+    kernel: Option<Vec<f64>>,
+    max_interval: f64,
+}
+
+impl WideColumns<'_> {
+    /// Values of the selected channels at `points`, interleaved, NaN where the stream has no data
+    fn resample(&self, points: &[f64]) -> Result<Vec<f64>> {
+        let (Some(&first_point), Some(&last_point)) = (points.first(), points.last()) else {
+            return Ok(Vec::new());
+        };
+        // Interpolation needs one sample beyond each end, filtering half a kernel more
+        let margin = 1 + self.kernel.as_ref().map_or(0, |k| k.len() / 2) as u64;
+        let first = self.stream.sample_index(first_point)?.saturating_sub(margin);
+        let end = (self.stream.sample_index(last_point)? + 1 + margin).min(self.stream.sample_count);
+
+        let count = self.stream.channel_count;
+        let values = if first < end {
+            let (timestamps, block) = self.stream.read_block(first, end - first)?;
+            let values = block.to_f64().context("numeric samples expected")?;
+            resample_block(&timestamps, &values, count, self.kernel.as_deref(), Some(self.max_interval), points)
+        } else {
+            vec![f64::NAN; points.len() * count]
+        };
+        Ok(values
+            .chunks(count.max(1))
+            .flat_map(|sample| self.channels.iter().map(|&c| sample[c]))
+            .collect())
+    }
+}
+
+/// Default output path: `experiment.zarr` -> `experiment_wide.csv` (or `.parquet`)
+pub fn default_wide_output_path(store_path: &Path, format: ExportFormat) -> PathBuf {
+    let trimmed = store_path.components().as_path();
+    let stem = trimmed.file_stem().map(|s| s.to_string_lossy().to_string()).unwrap_or_default();
+    trimmed.with_file_name(format!("{}_wide.{}", stem, format.extension()))
+}
+
+/// Number of points of a `rate` Hz time base from `start` to `end` (both included)
+pub fn grid_points(start: f64, end: f64, rate: f64) -> u64 {
+    if end < start || rate <= 0.0 {
+        return 0;
+    }
+    ((end - start) * rate + 1e-9).floor() as u64 + 1
+}
+
+/// Write the regular streams selected by `options` into one wide table at `output`
+pub fn export_wide(
+    store_path: &Path,
+    output: &Path,
+    format: ExportFormat,
+    options: &ExportOptions,
+    wide: &WideOptions,
+) -> Result<WideTableSummary> {
+    if !matches!(format, ExportFormat::Csv | ExportFormat::Parquet) {
+        anyhow::bail!("Wide tables are written as csv or parquet, not {}", format.extension());
+    }
+    if wide.rate.is_some_and(|rate| rate.is_nan() || rate <= 0.0) {
+        anyhow::bail!("--rate must be a positive rate");
+    }
+
+    let streams = open_streams(store_path, options)?;
+    let mut included = Vec::new();
+    let mut skipped = Vec::new();
+    let mut recording_start = f64::INFINITY;
+    let (mut window_start, mut window_end) = (f64::NEG_INFINITY, f64::INFINITY);
+    for stream in &streams {
+        let reason = if stream.is_marker() {
+            Some("marker stream")
+        } else if stream.nominal_srate() <= 0.0 {
+            Some("irregular stream")
+        } else {
+            None
+        };
+        let range = stream.timestamp_range()?;
+        if let Some((first, _)) = range {
+            recording_start = recording_start.min(first);
+        }
+        let Some((first, last)) = range.filter(|_| reason.is_none()) else {
+            let reason = reason.unwrap_or("no samples");
+            // Streams named explicitly must make it into the table
+            if !options.streams.is_empty() {
+                anyhow::bail!("Stream '{}' cannot be exported to a wide table ({})", stream.name, reason);
+            }
+            skipped.push((stream.name.clone(), reason.to_string()));
+            continue;
+        };
+        window_start = window_start.max(first);
+        window_end = window_end.min(last);
+        included.push(stream);
+    }
+    if included.is_empty() {
+        anyhow::bail!("No regular streams to export in {}", store_path.display());
+    }
+
+    if let Some(start) = wide.start {
+        window_start = window_start.max(recording_start + start);
+    }
+    if let Some(end) = wide.end {
+        window_end = window_end.min(recording_start + end);
+    }
+    if window_end < window_start {
+        anyhow::bail!("The selected streams have no data in common in the exported range");
+    }
+
+    let rate = wide
+        .rate
+        .unwrap_or_else(|| included.iter().map(|s| s.nominal_srate()).fold(0.0, f64::max));
+    let columns: Vec<WideColumns> = included
+        .iter()
+        .map(|&stream| WideColumns {
+            stream,
+            channels: stream.export_channels(options.include_bad_channels),
+            kernel: anti_alias_kernel(stream.nominal_srate(), rate),
+            max_interval: DEFAULT_GAP_FACTOR / stream.nominal_srate(),
+        })
+        .collect();
+    let mut names = vec!["time".to_string()];
+    for column in &columns {
+        let labels = column.stream.channel_labels();
+        names.extend(column.channels.iter().map(|&c| format!("{}.{}", column.stream.name, labels[c])));
+    }
+
+    // About one export block of source samples per block of rows
+    let ratio = included.iter().map(|s| s.nominal_srate() / rate).fold(1.0, f64::max);
+    let rows = grid_points(window_start, window_end, rate);
+    let table = WideTable {
+        columns: &columns,
+        names: &names,
+        start: window_start,
+        rate,
+        rows,
+        block_rows: ((EXPORT_BLOCK_SAMPLES as f64 / ratio) as u64).max(1),
+    };
+    if let Some(parent) = output.parent().filter(|p| !p.as_os_str().is_empty()) {
+        std::fs::create_dir_all(parent)?;
+    }
+    match format {
+        ExportFormat::Csv => write_csv(&table, output)?,
+        _ => write_parquet(&table, output)?,
+    }
+
+    Ok(WideTableSummary {
+        path: output.to_path_buf(),
+        rows,
+        columns: names.len(),
+        rate,
+        streams: included.iter().map(|s| s.name.clone()).collect(),
+        skipped,
+    })
+}
+
+/// The time base and the streams resampled onto it
+struct WideTable<'a> {
+    columns: &'a [WideColumns<'a>],
+    names: &'a [String],
+    start: f64,
+    rate: f64,
+    rows: u64,
+    block_rows: u64,
+}
+
+impl WideTable<'_> {
+    /// Visit the table in blocks of rows: time points and one value vector per stream
+    fn for_each_block(&self, mut f: impl FnMut(&[f64], &[Vec<f64>]) -> Result<()>) -> Result<()> {
+        let mut row = 0;
+        while row < self.rows {
+            let len = self.block_rows.min(self.rows - row);
+            let points: Vec<f64> = (row..row + len).map(|k| self.start + k as f64 / self.rate).collect();
+            let values = self
+                .columns
+                .iter()
+                .map(|column| column.resample(&points))
+                .collect::<Result<Vec<_>>>()?;
+            f(&points, &values)?;
+            row += len;
+        }
+        Ok(())
+    }
+}
+
+fn write_csv(table: &WideTable, path: &Path) -> Result<()> {
+    let mut writer = csv::Writer::from_path(path)?;
+    writer.write_record(table.names)?;
+
+    let mut record = Vec::with_capacity(table.names.len());
+    table.for_each_block(|points, values| {
+        for (i, time) in points.iter().enumerate() {
+            record.clear();
+            record.push(time.to_string());
+            for (column, values) in table.columns.iter().zip(values) {
+                let width = column.channels.len();
+                // Missing values stay empty, which pandas and R read as NaN/NA
+                record.extend(
+                    values[i * width..(i + 1) * width]
+                        .iter()
+                        .map(|v| if v.is_nan() { String::new() } else { v.to_string() }),
+                );
+            }
+            writer.write_record(&record)?;
+        }
+        Ok(())
+    })?;
+
+    writer.flush()?;
+    Ok(())
+}
+
+#[cfg(feature = "parquet")]
+fn write_parquet(table: &WideTable, path: &Path) -> Result<()> {
+    use parquet::basic::{Compression, Repetition, Type as PhysicalType};
+    use parquet::data_type::DoubleType;
+    use parquet::file::properties::WriterProperties;
+    use parquet::file::writer::SerializedFileWriter;
+    use parquet::schema::types::Type;
+    use std::sync::Arc;
+
+    // Channel columns are nullable: a stream has no value outside its samples and inside gaps
+    let fields = table
+        .names
+        .iter()
+        .enumerate()
+        .map(|(i, name)| {
+            let repetition = if i == 0 { Repetition::REQUIRED } else { Repetition::OPTIONAL };
+            Type::primitive_type_builder(name, PhysicalType::DOUBLE)
+                .with_repetition(repetition)
+                .build()
+                .map(Arc::new)
+        })
+        .collect::<parquet::errors::Result<Vec<_>>>()?;
+    let schema = Arc::new(Type::group_type_builder("wide").with_fields(fields).build()?);
+    let properties = Arc::new(WriterProperties::builder().set_compression(Compression::SNAPPY).build());
+    let mut writer = SerializedFileWriter::new(std::fs::File::create(path)?, schema, properties)?;
+
+    table.for_each_block(|points, values| {
+        let mut row_group = writer.next_row_group()?;
+        if let Some(mut column_writer) = row_group.next_column()? {
+            column_writer.typed::<DoubleType>().write_batch(points, None, None)?;
+            column_writer.close()?;
+        }
+
+        for (column, values) in table.columns.iter().zip(values) {
+            let width = column.channels.len();
+            for channel in 0..width {
+                let Some(mut column_writer) = row_group.next_column()? else {
+                    break;
+                };
+                let column_values: Vec<f64> = values.iter().skip(channel).step_by(width).copied().collect();
+                let levels: Vec<i16> = column_values.iter().map(|v| i16::from(!v.is_nan())).collect();
+                let present: Vec<f64> = column_values.into_iter().filter(|v| !v.is_nan()).collect();
+                column_writer.typed::<DoubleType>().write_batch(&present, Some(&levels), None)?;
+                column_writer.close()?;
+            }
+        }
+
+        row_group.close()?;
+        Ok(())
+    })?;
+
+    writer.close()?;
+    Ok(())
+}
+
+#[cfg(not(feature = "parquet"))]
+fn write_parquet(_table: &WideTable, _path: &Path) -> Result<()> {
+    anyhow::bail!("Parquet output requires the `parquet` feature")
+}
@@ -0,0 +1,247 @@
+//! XDF (Extensible Data Format) writer
+//!
+//! Layout follows the XDF 1.0 specification: the `XDF:` magic, a FileHeader chunk,
+//! one StreamHeader per stream, Samples chunks of up to [`EXPORT_BLOCK_SAMPLES`]
+//! samples each, and a StreamFooter per stream. Every sample carries its recorded
+//! timestamp.
+//!
+//! Timestamps in the store are already clock-synchronized by the recorder (LSL
+//! `ClockSync` post-processing), so no ClockOffset chunks are written; readers that
+//! apply clock offsets (pyxdf, MNE) leave the timestamps unchanged.
+
+use anyhow::Result;
+use quick_xml::escape::escape;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+
+use super::{open_streams, ExportOptions, ExportStream, SampleBlock, EXPORT_BLOCK_SAMPLES};
+
+const TAG_FILE_HEADER: u16 = 1;
+const TAG_STREAM_HEADER: u16 = 2;
+const TAG_SAMPLES: u16 = 3;
+const TAG_STREAM_FOOTER: u16 = 6;
+
+/// Per-stream outcome of an XDF export
+#[derive(Debug, Clone)]
+pub struct XdfStreamSummary {
+    pub name: String,
+    pub stream_id: u32,
+    pub samples: u64,
+}
+
+/// Write a variable-length integer: 1 byte with the width (1, 4 or 8), then the value
+fn write_varlen<W: Write>(out: &mut W, value: u64) -> Result<()> {
+    if value <= u8::MAX as u64 {
+        out.write_all(&[1, value as u8])?;
+    } else if value <= u32::MAX as u64 {
+        out.write_all(&[4])?;
+        out.write_all(&(value as u32).to_le_bytes())?;
+    } else {
+        out.write_all(&[8])?;
+        out.write_all(&value.to_le_bytes())?;
+    }
+    Ok(())
+}
+
+/// Write one chunk: varlen length (tag + content), 2-byte tag, content
+fn write_chunk<W: Write>(out: &mut W, tag: u16, content: &[u8]) -> Result<()> {
+    write_varlen(out, content.len() as u64 + 2)?;
+    out.write_all(&tag.to_le_bytes())?;
+    out.write_all(content)?;
+    Ok(())
+}
+
+/// XDF channel format name for the recorder's channel format
+fn xdf_channel_format(channel_format: &str) -> &'static str {
+    match channel_format {
+        "Double64" | "Float64" => "double64",
+        "Int32" => "int32",
+        "Int16" => "int16",
+        "Int8" => "int8",
+        "Int64" => "int64",
+        "String" => "string",
+        _ => "float32",
+    }
+}
+
+/// `<tag>value</tag>` with the value escaped
+fn xml_element(tag: &str, value: &str) -> String {
+    format!("<{}>{}</{}>", tag, escape(value), tag)
+}
+
+/// String form of a JSON scalar for XML element text
+fn json_text(value: &serde_json::Value) -> String {
+    match value {
+        serde_json::Value::String(s) => s.clone(),
+        serde_json::Value::Null => String::new(),
+        other => other.to_string(),
+    }
+}
+
+/// Rebuild the `<desc>` element from the stored description and channel metadata
+///
+/// Bad channels keep their data and get `<bad>1</bad>` and `<bad_reason>` in their
+/// channel description.
+fn desc_xml(stream: &ExportStream) -> String {
+    let stream_info = &stream.stream_info;
+    let channels = stream_info.get("channels").and_then(|c| c.as_array());
+    let mut xml = String::from("<desc>");
+
+    let bad = &stream.bad_channels.channels;
+    if channels.is_some() || !bad.is_empty() {
+        xml.push_str("<channels>");
+        let labels = stream.channel_labels();
+        for (index, label) in labels.iter().enumerate() {
+            xml.push_str("<channel>");
+            match channels.and_then(|c| c.get(index)) {
+                Some(channel) => {
+                    for (key, value) in channel.as_object().into_iter().flatten() {
+                        xml.push_str(&xml_element(key, &json_text(value)));
+                    }
+                }
+                None => xml.push_str(&xml_element("label", label)),
+            }
+            if let Some(mark) = bad.iter().find(|c| c.index == index) {
+                xml.push_str(&xml_element("bad", "1"));
+                xml.push_str(&xml_element("bad_reason", &mark.reason));
+            }
+            xml.push_str("</channel>");
+        }
+        xml.push_str("</channels>");
+    }
+
+    // The flattened description repeats per-channel fields; keep only the others
+    let channel_keys: Vec<&str> = channels
+        .into_iter()
+        .flatten()
+        .filter_map(|c| c.as_object())
+        .flat_map(|c| c.keys().map(String::as_str))
+        .collect();
+    if let Some(description) = stream_info.get("description").and_then(|d| d.as_object()) {
+        for (key, value) in description {
+            if !channel_keys.contains(&key.as_str()) && !value.is_object() && !value.is_array() {
+                xml.push_str(&xml_element(key, &json_text(value)));
+            }
+        }
+    }
+
+    xml.push_str("</desc>");
+    xml
+}
+
+/// StreamHeader XML reconstructed from the stored `stream_info`
+fn stream_header_xml(stream: &ExportStream) -> String {
+    let info = &stream.stream_info;
+    let field = |key: &str| info.get(key).map(json_text).unwrap_or_default();
+    let name = info.get("name").map(json_text).unwrap_or_else(|| stream.name.clone());
+
+    let mut xml = String::from("<?xml version=\"1.0\"?><info>");
+    xml.push_str(&xml_element("name", &name));
+    xml.push_str(&xml_element("type", &field("type")));
+    xml.push_str(&xml_element("channel_count", &stream.channel_count.to_string()));
+    xml.push_str(&xml_element("nominal_srate", &stream.nominal_srate().to_string()));
+    xml.push_str(&xml_element("channel_format", xdf_channel_format(&stream.channel_format)));
+    for key in ["source_id", "version", "created_at", "uid", "session_id", "hostname"] {
+        xml.push_str(&xml_element(key, &field(key)));
+    }
+    xml.push_str(&desc_xml(stream));
+    xml.push_str("</info>");
+    xml
+}
+
+/// Samples chunk content: stream ID, sample count, then timestamp and values per sample
+fn samples_chunk(stream_id: u32, channels: usize, timestamps: &[f64], values: &SampleBlock) -> Result<Vec<u8>> {
+    let mut content = Vec::new();
+    content.extend_from_slice(&stream_id.to_le_bytes());
+    write_varlen(&mut content, timestamps.len() as u64)?;
+
+    macro_rules! numeric_samples {
+        ($values:expr) => {
+            for (timestamp, sample) in timestamps.iter().zip($values.chunks(channels)) {
+                content.push(8);
+                content.extend_from_slice(&timestamp.to_le_bytes());
+                for value in sample {
+                    content.extend_from_slice(&value.to_le_bytes());
+                }
+            }
+        };
+    }
+
+    match values {
+        SampleBlock::Float32(v) => numeric_samples!(v),
+        SampleBlock::Float64(v) => numeric_samples!(v),
+        SampleBlock::Int32(v) => numeric_samples!(v),
+        SampleBlock::Int16(v) => numeric_samples!(v),
+        SampleBlock::Int8(v) => numeric_samples!(v),
+        SampleBlock::String(v) => {
+            for (timestamp, sample) in timestamps.iter().zip(v.chunks(channels)) {
+                content.push(8);
+                content.extend_from_slice(&timestamp.to_le_bytes());
+                for value in sample {
+                    write_varlen(&mut content, value.len() as u64)?;
+                    content.extend_from_slice(value.as_bytes());
+                }
+            }
+        }
+    }
+    Ok(content)
+}
+
+/// Write `streams` as an XDF file to `out`
+pub fn write_xdf<W: Write>(out: &mut W, streams: &[ExportStream]) -> Result<Vec<XdfStreamSummary>> {
+    out.write_all(b"XDF:")?;
+    write_chunk(out, TAG_FILE_HEADER, b"<?xml version=\"1.0\"?><info><version>1.0</version></info>")?;
+
+    for (index, stream) in streams.iter().enumerate() {
+        let mut content = (index as u32 + 1).to_le_bytes().to_vec();
+        content.extend_from_slice(stream_header_xml(stream).as_bytes());
+        write_chunk(out, TAG_STREAM_HEADER, &content)?;
+    }
+
+    let mut summaries = Vec::with_capacity(streams.len());
+    for (index, stream) in streams.iter().enumerate() {
+        let stream_id = index as u32 + 1;
+        let mut first_timestamp = None;
+        let mut last_timestamp = None;
+
+        let mut start = 0;
+        while start < stream.sample_count {
+            let len = EXPORT_BLOCK_SAMPLES.min(stream.sample_count - start);
+            let (timestamps, values) = stream.read_block(start, len)?;
+            first_timestamp = first_timestamp.or(timestamps.first().copied());
+            last_timestamp = timestamps.last().copied().or(last_timestamp);
+            write_chunk(out, TAG_SAMPLES, &samples_chunk(stream_id, stream.channel_count, &timestamps, &values)?)?;
+            start += len;
+        }
+
+        let footer = format!(
+            "<?xml version=\"1.0\"?><info><first_timestamp>{}</first_timestamp><last_timestamp>{}</last_timestamp><sample_count>{}</sample_count><clock_offsets></clock_offsets></info>",
+            first_timestamp.unwrap_or(0.0),
+            last_timestamp.unwrap_or(0.0),
+            stream.sample_count
+        );
+        let mut content = stream_id.to_le_bytes().to_vec();
+        content.extend_from_slice(footer.as_bytes());
+        write_chunk(out, TAG_STREAM_FOOTER, &content)?;
+
+        summaries.push(XdfStreamSummary {
+            name: stream.name.clone(),
+            stream_id,
+            samples: stream.sample_count,
+        });
+    }
+
+    out.flush()?;
+    Ok(summaries)
+}
+
+/// Convert the store at `store_path` into an XDF file at `output`
+pub fn export_xdf(store_path: &Path, output: &Path, options: &ExportOptions) -> Result<Vec<XdfStreamSummary>> {
+    let streams = open_streams(store_path, options)?;
+    if streams.is_empty() {
+        anyhow::bail!("No streams to export in {}", store_path.display());
+    }
+    let mut out = BufWriter::new(File::create(output)?);
+    write_xdf(&mut out, &streams)
+}
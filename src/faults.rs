@@ -0,0 +1,134 @@
+//! Fault injection for `lsl-dummy-stream`
+//!
+//! A clean dummy stream never exercises the parts of the toolbox that deal with bad
+//! streams. With fault injection the dummy stream timestamps each sample itself and
+//! distorts the timing on purpose:
+//!
+//! - **Jitter** (`--jitter-ms`): Gaussian noise with this standard deviation on every
+//!   timestamp
+//! - **Dropouts** (`--dropout-prob`): each chunk is lost with this probability, leaving
+//!   a gap of one chunk
+//! - **Clock drift** (`--drift-ppm`): the stream's clock runs fast (positive) or slow
+//!   (negative) by this many parts per million against the LSL clock
+//! - **Burst gaps** (`--burst-gap`): every `--burst-interval` seconds the stream stalls
+//!   for this many seconds and the samples of the stall are lost
+//!
+//! The faults are known exactly, so lsl-validate's gap, jitter and drift figures and
+//! lsl-sync's drift correction can be checked against them.
+
+use anyhow::Result;
+use std::f64::consts::PI;
+
+/// Faults to inject; the default injects none
+#[derive(Debug, Clone, Default)]
+pub struct FaultOptions {
+    /// Standard deviation of the timestamp jitter in milliseconds
+    pub jitter_ms: f64,
+    /// Probability that a chunk is lost
+    pub dropout_prob: f64,
+    /// Clock drift in parts per million (positive: the stream's clock runs fast)
+    pub drift_ppm: f64,
+    /// Seconds of every stall (no stalls if `None`)
+    pub burst_gap: Option<f64>,
+    /// Seconds from the start of one stall to the next
+    pub burst_interval: f64,
+}
+
+impl FaultOptions {
+    /// Whether any fault is injected
+    pub fn any(&self) -> bool {
+        self.jitter_ms > 0.0 || self.dropout_prob > 0.0 || self.drift_ppm != 0.0 || self.burst_gap.is_some()
+    }
+}
+
+/// Samples lost to each kind of fault
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct FaultCounts {
+    pub dropped_chunks: u64,
+    pub dropped_samples: u64,
+    pub gap_samples: u64,
+}
+
+/// Timestamps (or losses) of the samples of a faulty stream
+#[derive(Debug, Clone)]
+pub struct FaultInjector {
+    options: FaultOptions,
+    sample_rate: f64,
+    rng: fastrand::Rng,
+    counts: FaultCounts,
+}
+
+impl FaultInjector {
+    pub fn new(options: FaultOptions, sample_rate: f64, seed: u64) -> Result<Self> {
+        if !(options.jitter_ms >= 0.0 && options.jitter_ms.is_finite()) {
+            anyhow::bail!("Jitter must be a non-negative number of milliseconds");
+        }
+        if !(0.0..=1.0).contains(&options.dropout_prob) {
+            anyhow::bail!("Dropout probability must be between 0 and 1");
+        }
+        if !options.drift_ppm.is_finite() || options.drift_ppm.abs() >= 1e6 {
+            anyhow::bail!("Clock drift must be less than 1000000 ppm");
+        }
+        if let Some(gap) = options.burst_gap
+            && !(gap > 0.0 && gap < options.burst_interval)
+        {
+            anyhow::bail!(
+                "Burst gap must be positive and shorter than the burst interval ({} s)",
+                options.burst_interval
+            );
+        }
+        Ok(Self {
+            options,
+            sample_rate,
+            rng: fastrand::Rng::with_seed(seed),
+            counts: FaultCounts::default(),
+        })
+    }
+
+    /// Timestamps of the `count` samples from sample `first` on, `None` for lost samples
+    ///
+    /// `clock_start` is the LSL clock time of sample 0.
+    pub fn stamp_chunk(&mut self, first: u64, count: usize, clock_start: f64) -> Vec<Option<f64>> {
+        if self.options.dropout_prob > 0.0 && self.rng.f64() < self.options.dropout_prob {
+            self.counts.dropped_chunks += 1;
+            self.counts.dropped_samples += count as u64;
+            return vec![None; count];
+        }
+
+        let clock_rate = 1.0 + self.options.drift_ppm * 1e-6;
+        (first..first + count as u64)
+            .map(|index| {
+                let t = index as f64 / self.sample_rate;
+                if self.in_burst_gap(t) {
+                    self.counts.gap_samples += 1;
+                    return None;
+                }
+                let jitter = if self.options.jitter_ms > 0.0 {
+                    self.gaussian() * self.options.jitter_ms / 1000.0
+                } else {
+                    0.0
+                };
+                Some(clock_start + t * clock_rate + jitter)
+            })
+            .collect()
+    }
+
+    /// Samples lost so far
+    pub fn counts(&self) -> FaultCounts {
+        self.counts
+    }
+
+    /// Stalls start at every multiple of the burst interval after the first
+    fn in_burst_gap(&self, t: f64) -> bool {
+        let interval = self.options.burst_interval;
+        self.options
+            .burst_gap
+            .is_some_and(|gap| t >= interval && t % interval < gap)
+    }
+
+    fn gaussian(&mut self) -> f64 {
+        let u1 = 1.0 - self.rng.f64();
+        let u2 = self.rng.f64();
+        (-2.0 * u1.ln()).sqrt() * (2.0 * PI * u2).cos()
+    }
+}
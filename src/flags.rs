@@ -0,0 +1,215 @@
+//! Per-sample validity flags (`lsl-recorder --sample-flags`)
+//!
+//! Not every recorded sample is equally trustworthy: samples arriving right after
+//! the inlet reconnected to a restarted outlet, samples pulled while the inlet
+//! buffer was close to overflowing (older samples may have been dropped), samples
+//! around a clock jump, and samples the device itself marks as invalid in a status
+//! channel. With `--sample-flags` the recorder writes a `flags` array next to the
+//! `time` array, one `uint8` per sample, holding an OR of these bits:
+//!
+//! | Bit | Name | Set on |
+//! |-----|------|--------|
+//! | 1 | `reconnected` | samples pulled within [`RECONNECT_SETTLE`] seconds after the stream came back |
+//! | 2 | `buffer_overflow` | samples pulled more than [`BACKLOG_FRACTION`] of the inlet buffer behind |
+//! | 4 | `clock_jump` | the first sample after a clock jump (see [`crate::clock_jumps`]) |
+//! | 8 | `device` | samples whose `--flag-channel` value is not zero |
+//!
+//! A value of 0 means nothing was noticed. Samples recorded without `--sample-flags`
+//! (an older recording appended to, or a flags array shorter than the stream) read
+//! as 0. The legend is stored in the array's `bits` attribute and the number of
+//! flagged samples per bit in the stream's `sample_flags` attribute:
+//!
+//! ```json
+//! "sample_flags": {"flagged": 412, "counts": {"reconnected": 400, "clock_jump": 1, "device": 11}}
+//! ```
+//!
+//! Analyses mask suspect samples with [`valid_mask`].
+
+use anyhow::Result;
+use serde_json::{json, Map, Value};
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use zarrs::array::Array;
+use zarrs::array_subset::ArraySubset;
+use zarrs::filesystem::FilesystemStore;
+
+use crate::zarr::array_exists;
+
+/// Pulled shortly after the stream was lost and came back
+pub const RECONNECTED: u8 = 1;
+/// Pulled while the inlet buffer was nearly full; samples before it may have been dropped
+pub const BUFFER_OVERFLOW: u8 = 2;
+/// First sample after a clock jump
+pub const CLOCK_JUMP: u8 = 4;
+/// Marked invalid by the device (`--flag-channel`)
+pub const DEVICE: u8 = 8;
+
+/// Every flag bit with its name, in bit order
+pub const FLAG_BITS: [(u8, &str); 4] = [
+    (RECONNECTED, "reconnected"),
+    (BUFFER_OVERFLOW, "buffer_overflow"),
+    (CLOCK_JUMP, "clock_jump"),
+    (DEVICE, "device"),
+];
+
+/// Name of the flags array in a stream group
+pub const FLAGS_ARRAY: &str = "flags";
+
+/// Stream group attribute holding the flagged sample counts
+pub const SAMPLE_FLAGS_ATTRIBUTE: &str = "sample_flags";
+
+/// Seconds after a reconnection during which samples are flagged `reconnected`
+pub const RECONNECT_SETTLE: f64 = 1.0;
+
+/// Fraction of the inlet buffer a sample may lag behind before it is flagged `buffer_overflow`
+pub const BACKLOG_FRACTION: f64 = 0.9;
+
+/// Names of the bits set in `flags`
+pub fn flag_names(flags: u8) -> Vec<&'static str> {
+    FLAG_BITS
+        .iter()
+        .filter(|(bit, _)| flags & bit != 0)
+        .map(|(_, name)| *name)
+        .collect()
+}
+
+/// Legend stored as the flags array's `bits` attribute
+pub fn bits_attribute() -> Value {
+    Value::Object(FLAG_BITS.iter().map(|(bit, name)| (bit.to_string(), json!(name))).collect())
+}
+
+/// Decides the flags of each sample while recording
+#[derive(Debug, Clone)]
+pub struct FlagTracker {
+    // Channel whose non-zero values mark samples as invalid
+    device_channel: Option<usize>,
+    // Lag behind the newest sample (seconds) from which the inlet buffer counts as nearly full
+    backlog_limit: Option<f64>,
+    // LSL clock time until which samples count as just reconnected
+    settle_until: Option<f64>,
+    // Bits for the next sample
+    pending: u8,
+    counts: BTreeMap<&'static str, u64>,
+    flagged: u64,
+}
+
+impl FlagTracker {
+    /// Tracker for a stream with an inlet buffer of `buffer_seconds` (`None`: irregular
+    /// streams, whose buffer is counted in samples)
+    pub fn new(device_channel: Option<usize>, buffer_seconds: Option<f64>) -> Self {
+        Self {
+            device_channel,
+            backlog_limit: buffer_seconds.map(|seconds| seconds * BACKLOG_FRACTION),
+            settle_until: None,
+            pending: 0,
+            counts: BTreeMap::new(),
+            flagged: 0,
+        }
+    }
+
+    /// Channel whose values mark samples as invalid
+    pub fn device_channel(&self) -> Option<usize> {
+        self.device_channel
+    }
+
+    /// The stream came back at LSL clock time `now`
+    pub fn stream_recovered(&mut self, now: f64) {
+        self.settle_until = Some(now + RECONNECT_SETTLE);
+    }
+
+    /// A clock jump was noticed; flags the next sample
+    pub fn clock_jump(&mut self) {
+        self.pending |= CLOCK_JUMP;
+    }
+
+    /// Flags of a sample stamped `timestamp` and pulled at LSL clock time `now`
+    ///
+    /// `device_value` is the sample's value in the flag channel.
+    pub fn sample(&mut self, timestamp: f64, now: f64, device_value: Option<f64>) -> u8 {
+        let mut flags = std::mem::take(&mut self.pending);
+        if self.settle_until.is_some_and(|until| now <= until) {
+            flags |= RECONNECTED;
+        }
+        if self.backlog_limit.is_some_and(|limit| now - timestamp > limit) {
+            flags |= BUFFER_OVERFLOW;
+        }
+        if device_value.is_some_and(|value| value != 0.0) {
+            flags |= DEVICE;
+        }
+        if flags != 0 {
+            self.flagged += 1;
+            for name in flag_names(flags) {
+                *self.counts.entry(name).or_default() += 1;
+            }
+        }
+        flags
+    }
+
+    /// Samples flagged so far
+    pub fn flagged(&self) -> u64 {
+        self.flagged
+    }
+
+    /// Flagged samples per flag name
+    pub fn counts(&self) -> &BTreeMap<&'static str, u64> {
+        &self.counts
+    }
+}
+
+/// Add the counts of a recording to the `sample_flags` attribute of a stream group
+///
+/// Counts of earlier recordings appended to the same stream are kept.
+pub fn add_flag_counts(attributes: &mut Map<String, Value>, flagged: u64, counts: &BTreeMap<&str, u64>) {
+    let existing = attributes.get(SAMPLE_FLAGS_ATTRIBUTE);
+    let total = existing.and_then(|f| f["flagged"].as_u64()).unwrap_or(0) + flagged;
+    let mut merged: BTreeMap<String, u64> = existing
+        .and_then(|f| serde_json::from_value(f["counts"].clone()).ok())
+        .unwrap_or_default();
+    for (name, count) in counts {
+        *merged.entry(name.to_string()).or_default() += count;
+    }
+    attributes.insert(SAMPLE_FLAGS_ATTRIBUTE.to_string(), json!({"flagged": total, "counts": merged}));
+}
+
+/// Flagged sample counts of a stream: total and per flag name, if it was recorded with flags
+pub fn read_flag_counts(attributes: &Value) -> Option<(u64, BTreeMap<String, u64>)> {
+    let flags = attributes.get(SAMPLE_FLAGS_ATTRIBUTE)?;
+    let counts = serde_json::from_value(flags["counts"].clone()).unwrap_or_default();
+    Some((flags["flagged"].as_u64().unwrap_or(0), counts))
+}
+
+/// Path of a stream's flags array
+pub fn flags_array_path(stream_name: &str) -> String {
+    format!("/{}/{}", stream_name.trim_start_matches('/'), FLAGS_ARRAY)
+}
+
+/// Flags of the samples `[start, start + len)` of a stream, or `None` without a flags array
+///
+/// Samples the array does not cover read as 0.
+pub fn read_sample_flags(
+    store: &Arc<FilesystemStore>,
+    stream_name: &str,
+    start: u64,
+    len: u64,
+) -> Result<Option<Vec<u8>>> {
+    let path = flags_array_path(stream_name);
+    if !array_exists(store, &path)? {
+        return Ok(None);
+    }
+    let array = Array::<FilesystemStore>::open(store.clone(), &path)?;
+    let stored = array.shape().first().copied().unwrap_or(0);
+    let end = (start + len).min(stored);
+    let mut flags = if start < end {
+        let subset = ArraySubset::new_with_start_shape(vec![start], vec![end - start])?;
+        array.retrieve_array_subset_ndarray::<u8>(&subset)?.into_iter().collect()
+    } else {
+        Vec::new()
+    };
+    flags.resize(len as usize, 0);
+    Ok(Some(flags))
+}
+
+/// Whether each sample is valid: none of the bits in `reject` set
+pub fn valid_mask(flags: &[u8], reject: u8) -> Vec<bool> {
+    flags.iter().map(|flags| flags & reject == 0).collect()
+}
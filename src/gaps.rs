@@ -0,0 +1,76 @@
+//! Gap and dropout detection in timestamp series
+//!
+//! A gap is an inter-sample interval longer than `factor` times the nominal sample
+//! period, the signature of dropped packets or a stalled device. The time lost to a
+//! gap is the interval minus one nominal period (the samples that should have been
+//! there). Irregular streams (nominal rate 0) have no expected period and never gap.
+
+use serde::{Deserialize, Serialize};
+
+/// Default multiple of the nominal period above which an interval is a gap
+pub const DEFAULT_GAP_FACTOR: f64 = 2.0;
+
+/// One interval between consecutive samples that exceeds the gap threshold
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Gap {
+    /// Index of the sample after the gap
+    pub index: usize,
+    /// Timestamp of the last sample before the gap
+    pub start: f64,
+    /// Timestamp of the first sample after the gap
+    pub end: f64,
+    /// Time missing from the stream (interval minus one nominal period)
+    pub lost: f64,
+}
+
+impl Gap {
+    /// Full interval between the samples around the gap
+    pub fn interval(&self) -> f64 {
+        self.end - self.start
+    }
+}
+
+/// Gaps of one stream and their totals
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct GapReport {
+    pub gaps: Vec<Gap>,
+    /// Sum of the time lost to all gaps
+    pub lost_time: f64,
+    /// Gap with the longest interval
+    pub largest: Option<Gap>,
+}
+
+impl GapReport {
+    pub fn count(&self) -> usize {
+        self.gaps.len()
+    }
+}
+
+/// Find intervals longer than `factor` nominal periods in `timestamps`
+pub fn find_gaps(timestamps: &[f64], nominal_srate: f64, factor: f64) -> GapReport {
+    let mut report = GapReport::default();
+    if nominal_srate <= 0.0 {
+        return report;
+    }
+    let period = 1.0 / nominal_srate;
+    let threshold = factor * period;
+
+    for (i, pair) in timestamps.windows(2).enumerate() {
+        let interval = pair[1] - pair[0];
+        if interval <= threshold {
+            continue;
+        }
+        let gap = Gap {
+            index: i + 1,
+            start: pair[0],
+            end: pair[1],
+            lost: interval - period,
+        };
+        report.lost_time += gap.lost;
+        if report.largest.is_none_or(|largest| gap.interval() > largest.interval()) {
+            report.largest = Some(gap);
+        }
+        report.gaps.push(gap);
+    }
+    report
+}
@@ -0,0 +1,53 @@
+//! Conversion of recordings from other tools into the toolbox's Zarr layout
+//!
+//! Imported streams get the same groups, arrays and attributes as streams written by
+//! lsl-recorder, so lsl-sync, lsl-validate, lsl-inspect and lsl-export work on them
+//! unchanged. Each source format lives in its own submodule:
+//!
+//! - [`xdf`] - XDF files, as written by LabRecorder
+
+pub mod xdf;
+
+use crate::zarr::{StorageOptions, META_GROUP};
+
+/// Options shared by all import formats
+#[derive(Debug, Clone, Default)]
+pub struct ImportOptions {
+    /// Streams to import, by name (all streams if empty)
+    pub streams: Vec<String>,
+    /// Chunk layout and compression of the new arrays
+    pub storage: StorageOptions,
+    /// Metadata stored in each stream's `recorder_config`
+    pub subject: Option<String>,
+    pub session_id: Option<String>,
+    pub notes: Option<String>,
+}
+
+/// One stream written by an import
+#[derive(Debug, Clone)]
+pub struct ImportStreamSummary {
+    /// Stream group name in the store
+    pub name: String,
+    /// Channel format as stored (`Float32`, `Double64`, `String`, ...)
+    pub channel_format: String,
+    pub channels: usize,
+    pub samples: u64,
+}
+
+/// Zarr group name for a stream name: filesystem-safe, unique among `used` and not [`META_GROUP`]
+pub fn group_name(stream_name: &str, used: &[String]) -> String {
+    let mut base: String = stream_name
+        .chars()
+        .map(|c| if c.is_alphanumeric() || c == '-' || c == '_' { c } else { '_' })
+        .collect();
+    if base.is_empty() {
+        base = "stream".to_string();
+    }
+    let mut name = base.clone();
+    let mut counter = 2;
+    while used.contains(&name) || name == META_GROUP {
+        name = format!("{}_{}", base, counter);
+        counter += 1;
+    }
+    name
+}
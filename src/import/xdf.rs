@@ -0,0 +1,626 @@
+//! XDF (Extensible Data Format) reader
+//!
+//! The file is read twice. The first pass collects the stream headers and clock
+//! offset measurements and skips over sample data; the second decodes the Samples
+//! chunks, which LabRecorder interleaves across streams, and appends them to each
+//! stream's arrays in blocks of whole chunks.
+//!
+//! LabRecorder stores timestamps in the clock of the sending machine together with
+//! ClockOffset measurements. Like pyxdf, the import adds the measured offset
+//! (interpolated linearly between measurements) so all streams share the recording
+//! machine's clock, as they do in stores written by lsl-recorder. Timestamps are
+//! not dejittered. Samples stored without a timestamp get the previous one plus
+//! the nominal sampling interval.
+
+use anyhow::{Context, Result};
+use ndarray::{Array1, Array2, Ix1, Ix2};
+use quick_xml::events::Event;
+use quick_xml::Reader;
+use serde_json::json;
+use std::collections::{BTreeMap, HashMap};
+use std::fs::File;
+use std::io::{BufReader, Cursor, Read};
+use std::path::Path;
+use std::sync::Arc;
+use zarrs::array::Array;
+use zarrs::filesystem::FilesystemStore;
+
+use super::{group_name, ImportOptions, ImportStreamSummary};
+use crate::devices::update_devices_table;
+use crate::export::SampleBlock;
+use crate::manifest::update_manifest_streams;
+use crate::zarr::layout::{
+    create_group_if_not_exists, create_sample_array, create_time_array, parse_channels_to_json, parse_desc_to_json,
+};
+use crate::zarr::open_or_create_zarr_store;
+use crate::zarr::paths::check_stream_paths;
+use crate::zarr::time_index::{open_or_create_index_array, TimeIndex};
+
+const TAG_FILE_HEADER: u16 = 1;
+const TAG_STREAM_HEADER: u16 = 2;
+const TAG_SAMPLES: u16 = 3;
+const TAG_CLOCK_OFFSET: u16 = 4;
+
+/// Samples buffered per stream before a write (rounded up to whole chunks)
+const IMPORT_BLOCK_SAMPLES: u64 = 10_000;
+
+/// Options specific to XDF import
+#[derive(Debug, Clone)]
+pub struct XdfOptions {
+    /// Apply the recorded clock offsets to the timestamps
+    pub clock_sync: bool,
+}
+
+impl Default for XdfOptions {
+    fn default() -> Self {
+        Self { clock_sync: true }
+    }
+}
+
+/// Read a variable-length integer: 1 byte with the width (1, 4 or 8), then the value
+fn read_varlen<R: Read>(input: &mut R) -> Result<u64> {
+    let mut width = [0u8; 1];
+    input.read_exact(&mut width)?;
+    match width[0] {
+        1 => {
+            let mut value = [0u8; 1];
+            input.read_exact(&mut value)?;
+            Ok(value[0] as u64)
+        }
+        4 => {
+            let mut value = [0u8; 4];
+            input.read_exact(&mut value)?;
+            Ok(u32::from_le_bytes(value) as u64)
+        }
+        8 => {
+            let mut value = [0u8; 8];
+            input.read_exact(&mut value)?;
+            Ok(u64::from_le_bytes(value))
+        }
+        other => anyhow::bail!("Invalid XDF length width {}", other),
+    }
+}
+
+/// Tag and content length of the next chunk, or `None` at the end of the file
+fn read_chunk_header<R: Read>(input: &mut R) -> Result<Option<(u16, u64)>> {
+    let mut width = [0u8; 1];
+    if input.read(&mut width)? == 0 {
+        return Ok(None);
+    }
+    let length = match width[0] {
+        1 | 4 | 8 => {
+            let mut value = [0u8; 8];
+            input.read_exact(&mut value[..width[0] as usize])?;
+            u64::from_le_bytes(value)
+        }
+        other => anyhow::bail!("Invalid XDF chunk length width {}", other),
+    };
+    let mut tag = [0u8; 2];
+    input.read_exact(&mut tag)?;
+    let content_length = length
+        .checked_sub(2)
+        .context("XDF chunk shorter than its tag")?;
+    Ok(Some((u16::from_le_bytes(tag), content_length)))
+}
+
+/// Open an XDF file positioned after the `XDF:` magic
+fn open_xdf(path: &Path) -> Result<BufReader<File>> {
+    let mut input = BufReader::new(File::open(path).with_context(|| format!("Failed to open {}", path.display()))?);
+    let mut magic = [0u8; 4];
+    input.read_exact(&mut magic)?;
+    if &magic != b"XDF:" {
+        anyhow::bail!("Not an XDF file: {}", path.display());
+    }
+    Ok(input)
+}
+
+/// Text of the top-level elements of an `<info>` header (`name`, `type`, `nominal_srate`, ...)
+fn header_fields(xml: &str) -> HashMap<String, String> {
+    let mut reader = Reader::from_str(xml);
+    reader.config_mut().trim_text(true);
+
+    let mut fields = HashMap::new();
+    let mut path: Vec<String> = Vec::new();
+    let mut text = String::new();
+    loop {
+        match reader.read_event() {
+            Ok(Event::Start(e)) => {
+                path.push(String::from_utf8_lossy(e.name().as_ref()).to_string());
+                text.clear();
+            }
+            Ok(Event::Text(e)) => {
+                if let Ok(t) = e.unescape() {
+                    text.push_str(&t);
+                }
+            }
+            Ok(Event::End(_)) => {
+                if path.len() == 2 {
+                    fields.insert(path[1].clone(), std::mem::take(&mut text));
+                }
+                path.pop();
+            }
+            Ok(Event::Eof) | Err(_) => break,
+            _ => {}
+        }
+    }
+    fields
+}
+
+/// Channel format name as stored by the recorder for an XDF channel format
+fn stored_channel_format(xdf_format: &str) -> Option<&'static str> {
+    match xdf_format {
+        "float32" => Some("Float32"),
+        "double64" => Some("Double64"),
+        "int32" => Some("Int32"),
+        "int16" => Some("Int16"),
+        "int8" => Some("Int8"),
+        "string" => Some("String"),
+        _ => None,
+    }
+}
+
+/// Clock offset at `time`, interpolated linearly between `(collection_time, offset)` measurements
+fn clock_offset(offsets: &[(f64, f64)], time: f64) -> f64 {
+    match offsets {
+        [] => 0.0,
+        [(_, offset)] => *offset,
+        _ => {
+            let i = offsets.partition_point(|(t, _)| *t < time).clamp(1, offsets.len() - 1);
+            let (t0, o0) = offsets[i - 1];
+            let (t1, o1) = offsets[i];
+            if time <= t0 {
+                o0
+            } else if time >= t1 || t1 <= t0 {
+                o1
+            } else {
+                o0 + (o1 - o0) * (time - t0) / (t1 - t0)
+            }
+        }
+    }
+}
+
+/// A stream as described by its StreamHeader chunk
+struct XdfStream {
+    header_xml: String,
+    fields: HashMap<String, String>,
+    /// Channel format as stored by the recorder (`None` if it cannot be imported)
+    channel_format: Option<&'static str>,
+    channels: usize,
+    nominal_srate: f64,
+    /// `(collection_time, offset)` pairs, sorted by collection time
+    clock_offsets: Vec<(f64, f64)>,
+}
+
+impl XdfStream {
+    fn name(&self) -> &str {
+        self.fields.get("name").map(String::as_str).unwrap_or_default()
+    }
+
+    /// `stream_info` attribute in the recorder's layout
+    fn stream_info(&self) -> serde_json::Value {
+        let field = |key: &str| self.fields.get(key).cloned().unwrap_or_default();
+        let number = |key: &str| self.fields.get(key).and_then(|v| v.parse::<f64>().ok()).unwrap_or(0.0);
+        let mut info = json!({
+            "name": field("name"),
+            "type": field("type"),
+            "source_id": field("source_id"),
+            "hostname": field("hostname"),
+            "channel_count": self.channels,
+            "nominal_srate": self.nominal_srate,
+            "channel_format": self.channel_format.unwrap_or_default(),
+            "created_at": number("created_at"),
+            "uid": field("uid"),
+            "session_id": field("session_id"),
+            "version": number("version"),
+            "description": parse_desc_to_json(&self.header_xml),
+        });
+        let channels = parse_channels_to_json(&self.header_xml);
+        if !channels.is_empty() {
+            info["channels"] = serde_json::Value::Array(channels);
+        }
+        info
+    }
+}
+
+/// First pass: stream headers (by stream ID) and the file header's `datetime`
+fn read_headers(path: &Path) -> Result<(BTreeMap<u32, XdfStream>, Option<String>)> {
+    let mut input = open_xdf(path)?;
+    let mut streams = BTreeMap::new();
+    let mut datetime = None;
+
+    while let Some((tag, length)) = read_chunk_header(&mut input)? {
+        match tag {
+            TAG_FILE_HEADER => {
+                let mut content = vec![0u8; length as usize];
+                input.read_exact(&mut content)?;
+                datetime = header_fields(&String::from_utf8_lossy(&content)).remove("datetime");
+            }
+            TAG_STREAM_HEADER => {
+                let mut content = vec![0u8; length as usize];
+                input.read_exact(&mut content)?;
+                let (id, xml) = content.split_at(4.min(content.len()));
+                let id = u32::from_le_bytes(id.try_into().context("Truncated XDF stream header")?);
+                let header_xml = String::from_utf8_lossy(xml).to_string();
+                let fields = header_fields(&header_xml);
+                let channel_format = fields.get("channel_format").and_then(|f| stored_channel_format(f));
+                let channels = fields.get("channel_count").and_then(|v| v.parse().ok()).unwrap_or(0);
+                let nominal_srate = fields.get("nominal_srate").and_then(|v| v.parse().ok()).unwrap_or(0.0);
+                streams.insert(
+                    id,
+                    XdfStream {
+                        header_xml,
+                        fields,
+                        channel_format,
+                        channels,
+                        nominal_srate,
+                        clock_offsets: Vec::new(),
+                    },
+                );
+            }
+            TAG_CLOCK_OFFSET if length >= 20 => {
+                let mut content = [0u8; 20];
+                input.read_exact(&mut content)?;
+                input.seek_relative(length as i64 - 20)?;
+                let id = u32::from_le_bytes(content[0..4].try_into()?);
+                let collection_time = f64::from_le_bytes(content[4..12].try_into()?);
+                let offset = f64::from_le_bytes(content[12..20].try_into()?);
+                if let Some(stream) = streams.get_mut(&id) {
+                    stream.clock_offsets.push((collection_time, offset));
+                }
+            }
+            _ => input.seek_relative(length as i64)?,
+        }
+    }
+
+    for stream in streams.values_mut() {
+        stream.clock_offsets.sort_by(|a, b| a.0.total_cmp(&b.0));
+    }
+    Ok((streams, datetime))
+}
+
+/// A stream being written to the store
+struct StreamWriter<'a> {
+    source: &'a XdfStream,
+    name: String,
+    clock_sync: bool,
+    data: Array<FilesystemStore>,
+    time: Array<FilesystemStore>,
+    index_array: Array<FilesystemStore>,
+    time_index: TimeIndex,
+    /// Samples per write: a whole number of chunks (or shards)
+    block_samples: usize,
+    timestamps: Vec<f64>,
+    values: SampleBlock,
+    written: u64,
+    last_raw_timestamp: Option<f64>,
+    first_timestamp: Option<f64>,
+    last_timestamp: Option<f64>,
+    event_counts: BTreeMap<String, u64>,
+}
+
+impl<'a> StreamWriter<'a> {
+    fn create(
+        store: &Arc<FilesystemStore>,
+        source: &'a XdfStream,
+        name: String,
+        options: &ImportOptions,
+        xdf: &XdfOptions,
+    ) -> Result<Self> {
+        let channel_format = source.channel_format.context("unsupported channel format")?;
+        let stream_path = format!("/{}", name);
+        create_group_if_not_exists(store, &stream_path)?;
+
+        let events = channel_format == "String" && source.channels == 1;
+        let data_path = format!("{}/{}", stream_path, if events { "events" } else { "data" });
+        let data = create_sample_array(store, &data_path, channel_format, source.channels, events, &options.storage)?;
+        let time = create_time_array(store, &format!("{}/time", stream_path), &options.storage)?;
+
+        let outer_chunk_samples = options.storage.shard_samples.unwrap_or(options.storage.chunk_samples);
+        let index_array = open_or_create_index_array(store, &name, outer_chunk_samples)?;
+        let block_samples = IMPORT_BLOCK_SAMPLES.div_ceil(outer_chunk_samples) * outer_chunk_samples;
+
+        let values = match channel_format {
+            "Float32" => SampleBlock::Float32(Vec::new()),
+            "Double64" => SampleBlock::Float64(Vec::new()),
+            "Int32" => SampleBlock::Int32(Vec::new()),
+            "Int16" => SampleBlock::Int16(Vec::new()),
+            "Int8" => SampleBlock::Int8(Vec::new()),
+            _ => SampleBlock::String(Vec::new()),
+        };
+
+        Ok(Self {
+            source,
+            name,
+            clock_sync: xdf.clock_sync,
+            data,
+            time,
+            index_array,
+            time_index: TimeIndex::new(outer_chunk_samples),
+            block_samples: block_samples as usize,
+            timestamps: Vec::new(),
+            values,
+            written: 0,
+            last_raw_timestamp: None,
+            first_timestamp: None,
+            last_timestamp: None,
+            event_counts: BTreeMap::new(),
+        })
+    }
+
+    fn is_events(&self) -> bool {
+        self.data.shape().len() == 1
+    }
+
+    /// Decode the samples of one Samples chunk (after the stream ID) into the buffers
+    fn push_samples(&mut self, content: &mut Cursor<&[u8]>) -> Result<()> {
+        let count = read_varlen(content)?;
+        let channels = self.source.channels;
+        let interval = if self.source.nominal_srate > 0.0 {
+            1.0 / self.source.nominal_srate
+        } else {
+            0.0
+        };
+
+        for _ in 0..count {
+            let mut timestamp_bytes = [0u8; 1];
+            content.read_exact(&mut timestamp_bytes)?;
+            let raw = match timestamp_bytes[0] {
+                8 => {
+                    let mut value = [0u8; 8];
+                    content.read_exact(&mut value)?;
+                    f64::from_le_bytes(value)
+                }
+                0 => self.last_raw_timestamp.map(|t| t + interval).unwrap_or(0.0),
+                other => anyhow::bail!("Invalid XDF timestamp width {}", other),
+            };
+            self.last_raw_timestamp = Some(raw);
+
+            let timestamp = if self.clock_sync {
+                raw + clock_offset(&self.source.clock_offsets, raw)
+            } else {
+                raw
+            };
+            self.first_timestamp.get_or_insert(timestamp);
+            self.last_timestamp = Some(timestamp);
+            self.timestamps.push(timestamp);
+
+            macro_rules! read_numeric {
+                ($values:expr, $ty:ty) => {
+                    for _ in 0..channels {
+                        let mut value = [0u8; std::mem::size_of::<$ty>()];
+                        content.read_exact(&mut value)?;
+                        $values.push(<$ty>::from_le_bytes(value));
+                    }
+                };
+            }
+
+            match &mut self.values {
+                SampleBlock::Float32(v) => read_numeric!(v, f32),
+                SampleBlock::Float64(v) => read_numeric!(v, f64),
+                SampleBlock::Int32(v) => read_numeric!(v, i32),
+                SampleBlock::Int16(v) => read_numeric!(v, i16),
+                SampleBlock::Int8(v) => read_numeric!(v, i8),
+                SampleBlock::String(v) => {
+                    for _ in 0..channels {
+                        let length = read_varlen(content)?;
+                        let mut bytes = vec![0u8; length as usize];
+                        content.read_exact(&mut bytes)?;
+                        v.push(String::from_utf8_lossy(&bytes).to_string());
+                    }
+                }
+            }
+        }
+
+        while self.timestamps.len() >= self.block_samples {
+            self.write(self.block_samples)?;
+        }
+        Ok(())
+    }
+
+    /// Append the first `count` buffered samples to the arrays
+    fn write(&mut self, count: usize) -> Result<()> {
+        if count == 0 {
+            return Ok(());
+        }
+        let channels = self.source.channels;
+        let start = self.written;
+        let new_length = start + count as u64;
+
+        if self.is_events() {
+            self.data.set_shape(vec![new_length])?;
+        } else {
+            self.data.set_shape(vec![channels as u64, new_length])?;
+        }
+        self.time.set_shape(vec![new_length])?;
+
+        macro_rules! store_block {
+            ($values:expr, $ty:ty) => {{
+                let block: Vec<$ty> = $values.drain(..count * channels).collect();
+                // Sample-major -> [channels, samples]
+                let block = Array2::from_shape_vec((count, channels), block)?
+                    .reversed_axes()
+                    .as_standard_layout()
+                    .into_owned();
+                self.data.store_array_subset_ndarray::<$ty, Ix2>(&[0, start], block)?;
+            }};
+        }
+
+        let events = self.is_events();
+        match &mut self.values {
+            SampleBlock::String(v) if events => {
+                let block: Vec<String> = v.drain(..count).collect();
+                for event in &block {
+                    *self.event_counts.entry(event.clone()).or_insert(0) += 1;
+                }
+                self.data.store_array_subset_ndarray::<String, Ix1>(&[start], Array1::from_vec(block))?;
+            }
+            SampleBlock::Float32(v) => store_block!(v, f32),
+            SampleBlock::Float64(v) => store_block!(v, f64),
+            SampleBlock::Int32(v) => store_block!(v, i32),
+            SampleBlock::Int16(v) => store_block!(v, i16),
+            SampleBlock::Int8(v) => store_block!(v, i8),
+            SampleBlock::String(v) => store_block!(v, String),
+        }
+
+        let timestamps: Vec<f64> = self.timestamps.drain(..count).collect();
+        let first_index_row = self.time_index.append(timestamps.iter().copied());
+        self.time.store_array_subset_ndarray::<f64, Ix1>(&[start], Array1::from_vec(timestamps))?;
+        self.time_index.store_rows(&mut self.index_array, first_index_row)?;
+
+        self.written = new_length;
+        self.data.store_metadata()?;
+        self.time.store_metadata()?;
+        self.index_array.store_metadata()?;
+        Ok(())
+    }
+
+    /// Write the remaining samples and the stream group attributes
+    fn finish(
+        mut self,
+        store: &Arc<FilesystemStore>,
+        source_path: &Path,
+        options: &ImportOptions,
+        xdf: &XdfOptions,
+        datetime: Option<&str>,
+    ) -> Result<ImportStreamSummary> {
+        self.write(self.timestamps.len())?;
+
+        let storage = &options.storage;
+        let mut attributes = serde_json::Map::new();
+        attributes.insert("stream_info".to_string(), self.source.stream_info());
+        attributes.insert(
+            "recorder_config".to_string(),
+            json!({
+                "subject": options.subject,
+                "session_id": options.session_id,
+                "notes": options.notes,
+                "chunk_samples": storage.chunk_samples,
+                "compressor": storage.compressor.to_string(),
+                "compression_level": storage.compression_level,
+                "shard_samples": storage.shard_samples,
+                "imported_from": source_path.display().to_string(),
+                "import_format": "xdf",
+                "clock_sync": xdf.clock_sync,
+                "recorder_version": env!("CARGO_PKG_VERSION"),
+            }),
+        );
+        let first_offset = self.source.clock_offsets.first().map(|(_, offset)| *offset).unwrap_or(0.0);
+        attributes.insert("lsl_clock_offset".to_string(), json!(first_offset));
+        if let Some(datetime) = datetime {
+            attributes.insert("recorded_at".to_string(), json!(datetime));
+        }
+        if let Some(first) = self.first_timestamp {
+            attributes.insert("first_timestamp".to_string(), json!(first));
+        }
+        if let Some(last) = self.last_timestamp {
+            attributes.insert("last_timestamp".to_string(), json!(last));
+        }
+        if self.is_events() {
+            attributes.insert("event_count".to_string(), json!(self.written));
+            attributes.insert("event_counts".to_string(), json!(self.event_counts));
+        }
+
+        let mut group = zarrs::group::Group::open(store.clone(), &format!("/{}", self.name))?;
+        group.attributes_mut().extend(attributes);
+        group.store_metadata()?;
+
+        Ok(ImportStreamSummary {
+            name: self.name,
+            channel_format: self.source.channel_format.unwrap_or_default().to_string(),
+            channels: self.source.channels,
+            samples: self.written,
+        })
+    }
+}
+
+/// Import the XDF file at `xdf_path` into the Zarr store at `store_path`
+///
+/// Streams with a channel format the recorder does not support (`int64`) are
+/// skipped with a warning. Fails if a stream group of the same name already exists.
+pub fn import_xdf(
+    xdf_path: &Path,
+    store_path: &Path,
+    options: &ImportOptions,
+    xdf: &XdfOptions,
+) -> Result<Vec<ImportStreamSummary>> {
+    options.storage.validate()?;
+    let (streams, datetime) = read_headers(xdf_path)?;
+    if streams.is_empty() {
+        anyhow::bail!("No streams in {}", xdf_path.display());
+    }
+
+    let available: Vec<&str> = streams.values().map(|s| s.name()).collect();
+    for name in &options.streams {
+        if !available.contains(&name.as_str()) {
+            anyhow::bail!("Stream '{}' not found (available: {})", name, available.join(", "));
+        }
+    }
+
+    // Choose group names before touching the store, so collisions fail early
+    let mut used = Vec::new();
+    let mut selected = Vec::new();
+    for (&id, stream) in &streams {
+        if !options.streams.is_empty() && !options.streams.iter().any(|n| n == stream.name()) {
+            continue;
+        }
+        if stream.channel_format.is_none() {
+            eprintln!(
+                "Warning: skipping stream '{}': unsupported channel format '{}'",
+                stream.name(),
+                stream.fields.get("channel_format").map(String::as_str).unwrap_or_default()
+            );
+            continue;
+        }
+        let name = group_name(stream.name(), &used);
+        if store_path.join(&name).exists() {
+            anyhow::bail!("Store {} already contains a stream '{}'", store_path.display(), name);
+        }
+        if let Some(warning) = check_stream_paths(store_path, &name)? {
+            eprintln!("Warning: {}", warning);
+        }
+        used.push(name.clone());
+        selected.push((id, name));
+    }
+
+    let store = open_or_create_zarr_store(
+        store_path,
+        options.subject.as_deref(),
+        options.session_id.as_deref(),
+        options.notes.as_deref(),
+    )?;
+    let mut writers = HashMap::new();
+    for (id, name) in selected {
+        let writer = StreamWriter::create(&store, &streams[&id], name, options, xdf)
+            .with_context(|| format!("Failed to create stream '{}'", streams[&id].name()))?;
+        writers.insert(id, writer);
+    }
+
+    // Second pass: samples
+    let mut input = open_xdf(xdf_path)?;
+    while let Some((tag, length)) = read_chunk_header(&mut input)? {
+        if tag != TAG_SAMPLES || length < 4 {
+            input.seek_relative(length as i64)?;
+            continue;
+        }
+        let mut id = [0u8; 4];
+        input.read_exact(&mut id)?;
+        let Some(writer) = writers.get_mut(&u32::from_le_bytes(id)) else {
+            input.seek_relative(length as i64 - 4)?;
+            continue;
+        };
+        let mut content = vec![0u8; length as usize - 4];
+        input.read_exact(&mut content)?;
+        writer
+            .push_samples(&mut Cursor::new(content.as_slice()))
+            .with_context(|| format!("Invalid samples for stream '{}'", writer.name))?;
+    }
+
+    let mut summaries: Vec<_> = writers
+        .into_values()
+        .map(|writer| writer.finish(&store, xdf_path, options, xdf, datetime.as_deref()))
+        .collect::<Result<_>>()?;
+    summaries.sort_by(|a, b| a.name.cmp(&b.name));
+    update_devices_table(store_path)?;
+    update_manifest_streams(store_path)?;
+    Ok(summaries)
+}
@@ -0,0 +1,269 @@
+//! SQLite index of recording sessions
+//!
+//! `lsl-index` keeps one row per store (plus one row per stream) so that inventory
+//! tools can look up thousands of recordings without opening each store. Stores are
+//! re-summarized only when their metadata files changed since the last run.
+
+use anyhow::Result;
+use rusqlite::{params, Connection, OptionalExtension};
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+
+use crate::session::{find_stores, summarize_store, SessionSummary, StreamSummary};
+
+/// Default database file name created in the data root
+pub const DEFAULT_INDEX_FILE: &str = "lsl-index.sqlite";
+
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS sessions (
+    id                INTEGER PRIMARY KEY,
+    path              TEXT NOT NULL UNIQUE,
+    name              TEXT NOT NULL,
+    subject           TEXT,
+    session_id        TEXT,
+    notes             TEXT,
+    recorded_at       TEXT,
+    recorder_version  TEXT,
+    duration          REAL,
+    total_samples     INTEGER NOT NULL,
+    stream_count      INTEGER NOT NULL,
+    validation_status TEXT NOT NULL,
+    fingerprint       INTEGER NOT NULL,
+    indexed_at        TEXT NOT NULL
+);
+CREATE TABLE IF NOT EXISTS streams (
+    session_ref     INTEGER NOT NULL REFERENCES sessions(id) ON DELETE CASCADE,
+    name            TEXT NOT NULL,
+    stream_type     TEXT,
+    source_id       TEXT,
+    channel_count   INTEGER,
+    nominal_srate   REAL,
+    channel_format  TEXT,
+    samples         INTEGER NOT NULL,
+    first_timestamp REAL,
+    last_timestamp  REAL,
+    duration        REAL
+);
+CREATE INDEX IF NOT EXISTS idx_sessions_subject ON sessions(subject);
+CREATE INDEX IF NOT EXISTS idx_streams_name ON streams(name);
+";
+
+/// Counts reported after an index update
+#[derive(Debug, Default, Clone)]
+pub struct IndexStats {
+    pub added: usize,
+    pub updated: usize,
+    pub unchanged: usize,
+    pub removed: usize,
+    pub failed: usize,
+}
+
+/// Open (or create) an index database and ensure the schema exists
+pub fn open_index(db_path: &Path) -> Result<Connection> {
+    let conn = Connection::open(db_path)?;
+    conn.execute_batch("PRAGMA foreign_keys = ON;")?;
+    conn.execute_batch(SCHEMA)?;
+    Ok(conn)
+}
+
+/// Quick health classification of a session from its metadata
+///
+/// - `ok`: every stream has samples and final timestamps
+/// - `empty`: the store has no streams or a stream has no samples
+/// - `incomplete`: a stream is missing `last_timestamp` (recorder did not finalize)
+pub fn validation_status(session: &SessionSummary) -> &'static str {
+    if session.streams.is_empty() || session.streams.iter().any(|s| s.samples == 0) {
+        "empty"
+    } else if session.streams.iter().any(|s| s.last_timestamp.is_none()) {
+        "incomplete"
+    } else {
+        "ok"
+    }
+}
+
+/// Latest modification time (milliseconds since epoch) of a store's metadata files
+fn store_fingerprint(store_path: &Path) -> u64 {
+    let mtime = |path: &Path| {
+        std::fs::metadata(path)
+            .and_then(|m| m.modified())
+            .ok()
+            .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+            .map(|d| d.as_millis() as u64)
+            .unwrap_or(0)
+    };
+
+    let mut latest = mtime(&store_path.join("zarr.json"));
+    if let Ok(entries) = std::fs::read_dir(store_path) {
+        for entry in entries.filter_map(|e| e.ok()) {
+            let stream_dir = entry.path();
+            latest = latest
+                .max(mtime(&stream_dir.join("zarr.json")))
+                .max(mtime(&stream_dir.join("time").join("zarr.json")));
+        }
+    }
+    latest
+}
+
+/// Walk `root`, (re-)index changed stores and drop entries for stores that vanished
+pub fn update_index(conn: &mut Connection, root: &Path, quiet: bool) -> Result<IndexStats> {
+    let mut stats = IndexStats::default();
+    let stores = find_stores(root)?;
+    let tx = conn.transaction()?;
+
+    for store_path in &stores {
+        let path_str = store_path.display().to_string();
+        let fingerprint = store_fingerprint(store_path);
+
+        let existing: Option<(i64, i64)> = tx
+            .query_row(
+                "SELECT id, fingerprint FROM sessions WHERE path = ?1",
+                params![path_str],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .optional()?;
+
+        if let Some((_, old_fingerprint)) = existing
+            && old_fingerprint as u64 == fingerprint
+        {
+            stats.unchanged += 1;
+            continue;
+        }
+
+        let session = match summarize_store(store_path) {
+            Ok(session) => session,
+            Err(e) => {
+                eprintln!("Warning: Skipping {}: {}", path_str, e);
+                stats.failed += 1;
+                continue;
+            }
+        };
+
+        if let Some((id, _)) = existing {
+            tx.execute("DELETE FROM sessions WHERE id = ?1", params![id])?;
+            stats.updated += 1;
+        } else {
+            stats.added += 1;
+        }
+
+        tx.execute(
+            "INSERT INTO sessions (path, name, subject, session_id, notes, recorded_at, recorder_version,
+                                   duration, total_samples, stream_count, validation_status, fingerprint, indexed_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13)",
+            params![
+                path_str,
+                session.name(),
+                session.subject,
+                session.session_id,
+                session.notes,
+                session.recorded_at,
+                session.recorder_version,
+                session.duration(),
+                session.total_samples() as i64,
+                session.streams.len() as i64,
+                validation_status(&session),
+                fingerprint as i64,
+                chrono::Utc::now().to_rfc3339(),
+            ],
+        )?;
+        let session_ref = tx.last_insert_rowid();
+
+        for stream in &session.streams {
+            tx.execute(
+                "INSERT INTO streams (session_ref, name, stream_type, source_id, channel_count, nominal_srate,
+                                      channel_format, samples, first_timestamp, last_timestamp, duration)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)",
+                params![
+                    session_ref,
+                    stream.name,
+                    stream.stream_type,
+                    stream.source_id,
+                    stream.channel_count.map(|c| c as i64),
+                    stream.nominal_srate,
+                    stream.channel_format,
+                    stream.samples as i64,
+                    stream.first_timestamp,
+                    stream.last_timestamp,
+                    stream.duration(),
+                ],
+            )?;
+        }
+
+        if !quiet {
+            println!("Indexed:\t{} ({})", path_str, validation_status(&session));
+        }
+    }
+
+    // Remove sessions below this root whose stores no longer exist
+    let root_prefix = root.display().to_string();
+    let indexed: Vec<(i64, String)> = {
+        let mut stmt = tx.prepare("SELECT id, path FROM sessions")?;
+        stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?
+            .collect::<std::result::Result<_, _>>()?
+    };
+    for (id, path) in indexed {
+        if path.starts_with(&root_prefix) && !Path::new(&path).exists() {
+            tx.execute("DELETE FROM sessions WHERE id = ?1", params![id])?;
+            stats.removed += 1;
+            if !quiet {
+                println!("Removed:\t{}", path);
+            }
+        }
+    }
+
+    tx.commit()?;
+    Ok(stats)
+}
+
+/// Load all indexed sessions (with their streams) from the database
+pub fn load_sessions(conn: &Connection) -> Result<Vec<SessionSummary>> {
+    let mut session_stmt = conn.prepare(
+        "SELECT id, path, subject, session_id, notes, recorded_at, recorder_version
+         FROM sessions ORDER BY path",
+    )?;
+    let mut stream_stmt = conn.prepare(
+        "SELECT name, stream_type, source_id, channel_count, nominal_srate, channel_format,
+                samples, first_timestamp, last_timestamp
+         FROM streams WHERE session_ref = ?1 ORDER BY name",
+    )?;
+
+    let rows: Vec<(i64, SessionSummary)> = session_stmt
+        .query_map([], |row| {
+            Ok((
+                row.get(0)?,
+                SessionSummary {
+                    path: PathBuf::from(row.get::<_, String>(1)?),
+                    subject: row.get(2)?,
+                    session_id: row.get(3)?,
+                    notes: row.get(4)?,
+                    recorded_at: row.get(5)?,
+                    recorder_version: row.get(6)?,
+                    streams: Vec::new(),
+                },
+            ))
+        })?
+        .collect::<std::result::Result<_, _>>()?;
+
+    let mut sessions = Vec::with_capacity(rows.len());
+    for (id, mut session) in rows {
+        session.streams = stream_stmt
+            .query_map(params![id], |row| {
+                Ok(StreamSummary {
+                    name: row.get(0)?,
+                    stream_type: row.get(1)?,
+                    source_id: row.get(2)?,
+                    channel_count: row.get::<_, Option<i64>>(3)?.map(|c| c as u64),
+                    nominal_srate: row.get(4)?,
+                    channel_format: row.get(5)?,
+                    samples: row.get::<_, i64>(6)? as u64,
+                    first_timestamp: row.get(7)?,
+                    last_timestamp: row.get(8)?,
+                    // Not indexed; read the store for connection statistics
+                    connection: None,
+                })
+            })?
+            .collect::<std::result::Result<_, _>>()?;
+        sessions.push(session);
+    }
+
+    Ok(sessions)
+}
@@ -0,0 +1,131 @@
+//! Stream metadata snapshots taken while recording (`--info-refresh`)
+//!
+//! Some devices update their `<desc>` while streaming, e.g. electrode impedances or
+//! a changed amplifier gain. The `stream_info` attribute only holds what the outlet
+//! advertised at connect time, so with `--info-refresh <seconds>` the recorder
+//! re-fetches the stream info periodically and appends every version that differs
+//! from the previous one to the stream group's `stream_info_history` attribute:
+//!
+//! ```json
+//! "stream_info_history": [
+//!   {"lsl_clock": 81520.25, "utc": "2025-01-31T14:35:10.123456Z",
+//!    "changed": ["description.impedances.C3"],
+//!    "stream_info": {"type": "EEG", "channel_count": 32, "description": {...}, ...}}
+//! ]
+//! ```
+//!
+//! Each entry holds the complete stream info, so the metadata valid at any sample is
+//! the last entry fetched before its timestamp (or `stream_info` before the first).
+
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+/// Stream group attribute holding the [`InfoSnapshot`]s of a recording
+pub const STREAM_INFO_HISTORY_ATTRIBUTE: &str = "stream_info_history";
+
+/// Changed fields listed per snapshot before the list is cut short
+const MAX_CHANGED_PATHS: usize = 20;
+
+/// Stream info fetched while recording that differed from the previous version
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct InfoSnapshot {
+    /// LSL clock when the info was fetched, comparable to sample timestamps
+    pub lsl_clock: f64,
+    /// Wall-clock time of the fetch (UTC, RFC3339)
+    pub utc: String,
+    /// Dotted paths of the fields that changed, e.g. `description.impedances.C3`
+    #[serde(default)]
+    pub changed: Vec<String>,
+    /// The complete stream info, in the layout of the `stream_info` attribute
+    pub stream_info: Value,
+}
+
+/// Last known stream info, turning re-fetched versions into snapshots when they differ
+#[derive(Debug, Clone)]
+pub struct InfoHistory {
+    last: Value,
+}
+
+impl InfoHistory {
+    /// Start from the stream info stored at connect time
+    pub fn new(initial: Value) -> Self {
+        Self { last: initial }
+    }
+
+    /// Compare a re-fetched stream info with the last one; `Some` if anything changed
+    pub fn update(&mut self, lsl_clock: f64, stream_info: Value) -> Option<InfoSnapshot> {
+        if stream_info == self.last {
+            return None;
+        }
+        let changed = changed_paths(&self.last, &stream_info);
+        self.last = stream_info.clone();
+        Some(InfoSnapshot {
+            lsl_clock,
+            utc: chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Micros, true),
+            changed,
+            stream_info,
+        })
+    }
+}
+
+/// Dotted paths of the leaves that differ between two JSON values
+///
+/// Array elements are addressed by index; the list stops after twenty paths,
+/// ending with `...`.
+pub fn changed_paths(old: &Value, new: &Value) -> Vec<String> {
+    let mut paths = Vec::new();
+    collect_changes(old, new, String::new(), &mut paths);
+    if paths.len() > MAX_CHANGED_PATHS {
+        paths.truncate(MAX_CHANGED_PATHS);
+        paths.push("...".to_string());
+    }
+    paths
+}
+
+fn collect_changes(old: &Value, new: &Value, path: String, paths: &mut Vec<String>) {
+    if old == new || paths.len() > MAX_CHANGED_PATHS {
+        return;
+    }
+    let child = |key: &str| if path.is_empty() { key.to_string() } else { format!("{}.{}", path, key) };
+    match (old, new) {
+        (Value::Object(old), Value::Object(new)) => {
+            let mut keys: Vec<&String> = old.keys().chain(new.keys().filter(|k| !old.contains_key(*k))).collect();
+            keys.sort();
+            for key in keys {
+                let missing = Value::Null;
+                collect_changes(
+                    old.get(key).unwrap_or(&missing),
+                    new.get(key).unwrap_or(&missing),
+                    child(key),
+                    paths,
+                );
+            }
+        }
+        (Value::Array(old), Value::Array(new)) if old.len() == new.len() => {
+            for (index, (old, new)) in old.iter().zip(new).enumerate() {
+                collect_changes(old, new, child(&index.to_string()), paths);
+            }
+        }
+        _ => paths.push(if path.is_empty() { "stream_info".to_string() } else { path }),
+    }
+}
+
+/// Append a snapshot to the `stream_info_history` of a stream group's attributes
+pub fn append_snapshot(attributes: &mut Map<String, Value>, snapshot: &InfoSnapshot) -> serde_json::Result<()> {
+    let entry = serde_json::to_value(snapshot)?;
+    match attributes.get_mut(STREAM_INFO_HISTORY_ATTRIBUTE) {
+        Some(Value::Array(history)) => history.push(entry),
+        _ => {
+            attributes.insert(STREAM_INFO_HISTORY_ATTRIBUTE.to_string(), Value::Array(vec![entry]));
+        }
+    }
+    Ok(())
+}
+
+/// Snapshots stored in a stream group's attributes (empty without `--info-refresh` changes)
+pub fn read_history(attributes: &Value) -> Vec<InfoSnapshot> {
+    attributes
+        .get(STREAM_INFO_HISTORY_ATTRIBUTE)
+        .and_then(|history| serde_json::from_value(history.clone()).ok())
+        .unwrap_or_default()
+}
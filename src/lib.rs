@@ -13,7 +13,7 @@
 //!
 //! - **Multi-stream recording** with millisecond-level synchronization
 //! - **Zarr format** for efficient hierarchical storage and analysis
-//! - **Interactive control** via stdin commands (START/STOP/QUIT)
+//! - **Interactive control** via stdin commands (START/STOP/PAUSE/RESUME/MARK/QUIT) or over the network (`--control-port`)
 //! - **Post-processing synchronization** to align timestamps across streams
 //! - **Validation tools** for timing accuracy and drift analysis
 //! - **Inspection utilities** for metadata and structure visualization
@@ -21,7 +21,7 @@
 //!
 //! # Command-Line Tools
 //!
-//! The toolkit includes six main binaries:
+//! The toolkit includes eighteen main binaries:
 //!
 //! - [`lsl-recorder`](../lsl_recorder/index.html) - Single-stream recorder with interactive control
 //! - [`lsl-multi-recorder`](../lsl_multi_recorder/index.html) - Multi-stream unified controller
@@ -29,6 +29,19 @@
 //! - [`lsl-inspect`](../lsl_inspect/index.html) - Zarr file inspection and visualization
 //! - [`lsl-validate`](../lsl_validate/index.html) - Synchronization quality analyzer
 //! - [`lsl-dummy-stream`](../lsl_dummy_stream/index.html) - Test stream generator
+//! - [`lsl-discover`](../lsl_discover/index.html) - Network stream discovery
+//! - [`lsl-monitor`](../lsl_monitor/index.html) - Live terminal viewer for incoming streams
+//! - [`lsl-query`](../lsl_query/index.html) - Metadata search across recordings
+//! - [`lsl-index`](../lsl_index/index.html) - SQLite session index builder
+//! - [`lsl-repair`](../lsl_repair/index.html) - Crash recovery for interrupted recordings
+//! - [`lsl-calibrate`](../lsl_calibrate/index.html) - End-to-end latency calibration
+//! - [`lsl-loopback`](../lsl_loopback/index.html) - Replay → record loop verification
+//! - [`lsl-spotcheck`](../lsl_spotcheck/index.html) - Pre-session go/no-go check of the setup
+//! - [`lsl-export`](../lsl_export/index.html) - Export to XDF, EDF+/BDF+, CSV, Parquet and WAV
+//! - [`lsl-import`](../lsl_import/index.html) - Import XDF files into the Zarr layout
+//! - [`lsl-badchannels`](../lsl_badchannels/index.html) - Bad-channel masks for analysis
+//! - [`lsl-markers`](../lsl_markers/index.html) - Marker label normalization across sessions
+//! - [`lsl-demo`](../lsl_demo/index.html) - Example store for trying the tools without hardware
 //!
 //! # Quick Start
 //!
@@ -115,6 +128,8 @@
 //! │   ├── time
 //! │   ├── aligned_time
 //! │   └── zarr.json
+//! ├── meta/
+//! │   └── zarr.json      (store-level metadata: session manifest, device table)
 //! └── zarr.json          (root metadata)
 //! ```
 //!
@@ -122,11 +137,78 @@
 //!
 //! While primarily a CLI toolkit, the library modules can be used programmatically:
 //!
-//! - [`zarr`] - Zarr file writing and metadata management
+//! - [`zarr`] - Zarr file writing and metadata management (`zarr::repair` for crash recovery, `zarr::recovery` for failed finalizations, `zarr::clean` to undo `lsl-sync`, `zarr::cache` for results reused across tool runs, `zarr::attributes` for typed `stream_info`, `recorder_config` and alignment attributes)
 //! - [`lsl`] - LSL stream recording and configuration
+//! - [`chunked`] - Chunked acquisition of high-rate streams (`--chunk-pull-rate`)
+//! - [`async_core`] - In-process tokio recorder core for dozens of streams (`lsl-multi-recorder --async-core`)
+//! - [`observer`] - Sample batch, flush and state callbacks for applications embedding the recorder (`RecordingParams::observer`)
+//! - [`discovery`] - Network stream discovery
+//! - [`tap`] - Live WebSocket tap for dashboards (`lsl-recorder --tap`)
+//! - [`monitor`] - Decimated LSL monitor outlet (`lsl-recorder --monitor`)
+//! - [`doctor`] - Environment diagnostics (`lsl-toolbox doctor`)
+//! - [`session`] - Metadata-only session summaries of Zarr stores
+//! - [`study`] - Stream consistency across the sessions of a study (`lsl-validate --study-dir`)
+//! - [`connection`] - Per-stream resolution retries, reconnections and downtime
+//! - [`query`] - Expression language for filtering sessions
+//! - [`index`] - SQLite session index
+//! - [`units`] - Channel unit normalization and scaling plausibility checks
+//! - [`calibration`] - Pulse-based latency and timestamp bias analysis (`lsl-calibrate`)
+//! - [`gaps`] - Gap and dropout detection in timestamp series (`lsl-validate`)
+//! - [`dropped`] - Online dropped-sample estimate from the nominal rate (`dropped_samples`)
+//! - [`drift`] - Clock-drift regression between stream pairs (`lsl-validate`)
+//! - [`replay`] - Timestamping and pacing of batch replays (`lsl-replay --batch`)
+//! - [`loopback`] - Cross-correlation check of re-recorded replays (`lsl-loopback`)
+//! - [`resample`] - Anti-aliased resampling onto a common time grid (`lsl-sync --resample-to`)
+//! - [`spotcheck`] - Go/no-go verdict and quicklook plots (`lsl-spotcheck`)
+//! - [`badchannels`] - Per-stream bad-channel masks and detection (`lsl-badchannels`)
+//! - [`markers`] - Marker label normalization tables (`lsl-markers`)
+//! - [`devices`] - Store-level table of acquisition devices (`meta` group)
+//! - [`manifest`] - Session manifest: subject, session, host, versions and streams (`meta` group)
+//! - [`subject`] - Next free subject ID from existing stores (`--auto-subject`)
+//! - [`permissions`] - Store modes, group ownership and writability checks (`--chmod`, `--chgrp`)
+//! - [`memory`] - Process RSS sampling and memory growth warnings (`--memory-monitor`)
+//! - [`status`] - Live JSON status endpoint for remote monitoring (`--status-port`)
+//! - [`info_history`] - Stream info snapshots when device metadata changes while recording (`--info-refresh`)
+//! - [`schedule`] - Scheduled starts and block-structured recordings (`--start-at`, `--blocks`)
+//! - [`pauses`] - Paused intervals of a recording (`PAUSE`/`RESUME`, `lsl-sync --exclude-pauses`)
+//! - [`annotations`] - Experimenter annotations of a session (`MARK`, `meta` group)
+//! - [`captures`] - Marker-triggered captures with LSL and wall-clock anchors (`--capture-on`, `annex` group)
+//! - [`quality`] - Live signal quality checks: rolling RMS, flatline and clipping (`--quality`)
+//! - [`rules`] - Signal rules that warn or pause, e.g. when electrodes come off (`--rule`)
+//! - [`plausibility`] - Physical plausibility ranges counted while recording (`--plausible-range`)
+//! - [`rate_guard`] - Abort on streams far above their nominal rate (`--max-rate-factor`)
+//! - [`preflight`] - Go/no-go check of a stream and its output before a session (`lsl-recorder --preflight`)
+//! - [`clock_jumps`] - Clock jumps from suspend/resume and NTP steps while recording (`clock_jumps`, `lsl-validate`)
+//! - [`flags`] - Per-sample validity flags (`lsl-recorder --sample-flags`)
+//! - [`time_axis`] - Time-axis summary and sample density sparkline (`lsl-inspect --timeline`)
+//! - [`explore`] - Store tree, node metadata and data previews (TUI Store Explorer)
+//! - [`live_view`] - Rolling sample window and latency statistics (`lsl-monitor`)
+//! - [`tui_settings`] - Persistent TUI preferences such as native file dialogs (`lsl-toolbox`)
+//! - [`tui_presets`] - Named presets of TUI tool forms (`lsl-toolbox`)
+//! - [`rotation`] - Splitting long recordings into session parts (`--rotate-every`, `--rotate-size`)
+//! - [`session_lock`] - Refusing a second session that records into the same store (`--join-session`)
+//! - [`signals`] - Waveforms and simulated EMG/EEG/ECG test signals (`lsl-dummy-stream --waveform`, `--simulate`)
+//! - [`faults`] - Timestamp jitter, dropouts, clock drift and stalls for test streams (`lsl-dummy-stream --jitter-ms`, ...)
+//! - [`events`] - Named marker events at fixed or Poisson intervals (`lsl-dummy-stream --format string`)
+//! - [`backpressure`] - Failed, late and dropped pushes of replay and test outlets (`--max-lag`)
+//! - [`export`] - Conversion of stores to other formats (`lsl-export`: XDF, EDF+/BDF+, CSV, Parquet, WAV)
+//! - [`import`] - Conversion of other formats into stores (`lsl-import`: XDF)
+//! - [`demo`] - Deterministic example store with simulated streams and QC results (`lsl-demo generate`)
+//! - [`redact`] - Secrets removed from stored recorder configurations (`recorder_config`, `lsl-inspect --config`)
+//! - [`usage`] - Opt-in local log of tool runs (`lsl-toolbox stats`)
 //! - [`sync`] - Timestamp synchronization algorithms
 //! - [`cli`] - Command-line argument definitions
-//! - [`commands`] - Interactive command handling
+//! - [`config`] - Project-wide option defaults from `lsl-toolbox.toml` (all tools)
+//! - [`commands`] - Interactive command handling and the remote control server (`--control-port`)
+//! - [`tools`] - Entry points of the recorder, sync, inspect, validate, replay and dummy tools (`lsl-toolbox <tool>`)
+//!
+//! `lsl`, `discovery`, `tap`, `monitor`, `cli`, the live tools in `tools` (`record`, `multi`,
+//! `replay`, `dummy`) and the recording side of `zarr` require the default `lsl` cargo
+//! feature (links liblsl). Building with `--no-default-features` gives the
+//! offline tools only: `lsl-inspect`, `lsl-sync`, `lsl-validate`, `lsl-query`,
+//! `lsl-index`, `lsl-repair`, `lsl-export`, `lsl-import`, `lsl-badchannels`,
+//! `lsl-markers`, `lsl-demo` and `lsl-toolbox`. `async_core` needs the `async-core`
+//! feature, which pulls in a multi-threaded tokio runtime.
 //!
 //! # License
 //!
@@ -135,9 +217,78 @@
 
 pub mod zarr;
 pub mod sync;
+#[cfg(feature = "lsl")]
 pub mod cli;
+pub mod config;
 pub mod commands;
+#[cfg(feature = "lsl")]
 pub mod lsl;
+pub mod chunked;
+#[cfg(feature = "async-core")]
+pub mod async_core;
+#[cfg(feature = "lsl")]
+pub mod discovery;
+#[cfg(feature = "lsl")]
+pub mod tap;
+#[cfg(feature = "lsl")]
+pub mod monitor;
+pub mod observer;
+pub mod doctor;
+pub mod session;
+pub mod study;
+pub mod connection;
+pub mod query;
+pub mod index;
+pub mod units;
+pub mod calibration;
+pub mod badchannels;
+pub mod markers;
+pub mod gaps;
+pub mod dropped;
+pub mod drift;
+pub mod spotcheck;
+pub mod replay;
+pub mod loopback;
+pub mod resample;
+pub mod devices;
+pub mod manifest;
+pub mod permissions;
+pub mod subject;
+pub mod memory;
+pub mod status;
+pub mod info_history;
+pub mod schedule;
+pub mod pauses;
+pub mod annotations;
+pub mod captures;
+pub mod quality;
+pub mod rules;
+pub mod plausibility;
+pub mod rate_guard;
+pub mod preflight;
+pub mod clock_jumps;
+pub mod flags;
+pub mod time_axis;
+pub mod explore;
+pub mod live_view;
+pub mod tui_settings;
+pub mod tui_presets;
+/// Terminal key handling shared by the `lsl-toolbox` launcher and `lsl-monitor`
+pub mod tui {
+    pub mod events;
+}
+pub mod rotation;
+pub mod session_lock;
+pub mod signals;
+pub mod faults;
+pub mod events;
+pub mod backpressure;
+pub mod export;
+pub mod import;
+pub mod demo;
+pub mod redact;
+pub mod usage;
+pub mod tools;
 
 use chrono::Datelike;
 
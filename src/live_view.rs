@@ -0,0 +1,176 @@
+//! Rolling sample window and stream statistics for the live terminal viewer (`lsl-monitor`)
+//!
+//! The viewer keeps only the last few seconds of the selected channels in memory,
+//! so it can run next to a long session without growing. Plots are reduced to
+//! one min/max pair per screen column, which keeps spikes visible however many
+//! samples fall into a column.
+
+use std::collections::VecDeque;
+
+/// Seconds of signal shown by default
+pub const DEFAULT_WINDOW_SECS: f64 = 5.0;
+
+/// Channels plotted when none are selected
+pub const DEFAULT_PLOT_CHANNELS: usize = 4;
+
+/// Last `span` seconds of samples of the plotted channels
+#[derive(Debug, Clone)]
+pub struct SampleWindow {
+    span: f64,
+    times: VecDeque<f64>,
+    values: Vec<VecDeque<f64>>,
+    total: u64,
+}
+
+impl SampleWindow {
+    pub fn new(channels: usize, span: f64) -> Self {
+        Self {
+            span: span.max(f64::EPSILON),
+            times: VecDeque::new(),
+            values: vec![VecDeque::new(); channels],
+            total: 0,
+        }
+    }
+
+    /// Seconds of signal kept
+    pub fn span(&self) -> f64 {
+        self.span
+    }
+
+    /// Change the span; samples older than the new span are dropped at the next push
+    pub fn set_span(&mut self, span: f64) {
+        self.span = span.max(f64::EPSILON);
+        self.trim();
+    }
+
+    /// Add one sample (values of the plotted channels, in plot order)
+    pub fn push(&mut self, timestamp: f64, values: &[f64]) {
+        self.times.push_back(timestamp);
+        for (channel, &value) in self.values.iter_mut().zip(values) {
+            channel.push_back(value);
+        }
+        self.total += 1;
+        self.trim();
+    }
+
+    fn trim(&mut self) {
+        let Some(&latest) = self.times.back() else { return };
+        while self.times.front().is_some_and(|&t| t < latest - self.span) {
+            self.times.pop_front();
+            for channel in &mut self.values {
+                channel.pop_front();
+            }
+        }
+    }
+
+    /// Samples currently in the window
+    pub fn len(&self) -> usize {
+        self.times.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.times.is_empty()
+    }
+
+    /// Samples pushed since the viewer started
+    pub fn total(&self) -> u64 {
+        self.total
+    }
+
+    /// Timestamp of the newest sample
+    pub fn latest(&self) -> Option<f64> {
+        self.times.back().copied()
+    }
+
+    /// Sample rate measured from the timestamps in the window
+    pub fn effective_rate(&self) -> Option<f64> {
+        let (first, last) = (*self.times.front()?, *self.times.back()?);
+        (last > first).then(|| (self.times.len() - 1) as f64 / (last - first))
+    }
+
+    /// Smallest and largest value of a channel in the window
+    pub fn value_range(&self, channel: usize) -> Option<(f64, f64)> {
+        self.values.get(channel)?.iter().filter(|v| v.is_finite()).fold(None, |range, &v| match range {
+            None => Some((v, v)),
+            Some((min, max)) => Some((min.min(v), max.max(v))),
+        })
+    }
+
+    /// Plot points of a channel, reduced to a min/max pair per column
+    ///
+    /// X values are seconds relative to the newest sample (`-span..=0`). Columns
+    /// without samples are skipped, so gaps show up as straight segments.
+    pub fn plot_points(&self, channel: usize, columns: usize) -> Vec<(f64, f64)> {
+        let (Some(values), Some(latest)) = (self.values.get(channel), self.latest()) else {
+            return Vec::new();
+        };
+        let columns = columns.max(1);
+        let column_width = self.span / columns as f64;
+        let mut buckets: Vec<Option<(f64, f64)>> = vec![None; columns];
+        for (&time, &value) in self.times.iter().zip(values) {
+            if !value.is_finite() {
+                continue;
+            }
+            let column = (((time - latest + self.span) / column_width) as usize).min(columns - 1);
+            let bucket = &mut buckets[column];
+            *bucket = Some(match *bucket {
+                None => (value, value),
+                Some((min, max)) => (min.min(value), max.max(value)),
+            });
+        }
+
+        let mut points = Vec::with_capacity(columns * 2);
+        for (column, bucket) in buckets.into_iter().enumerate() {
+            if let Some((min, max)) = bucket {
+                let x = -self.span + (column as f64 + 0.5) * column_width;
+                points.push((x, min));
+                if max != min {
+                    points.push((x, max));
+                }
+            }
+        }
+        points
+    }
+}
+
+/// Transport latency (arrival time minus sample timestamp) over the last `span` seconds
+#[derive(Debug, Clone)]
+pub struct LatencyWindow {
+    span: f64,
+    latencies: VecDeque<(f64, f64)>,
+}
+
+/// Summary of a [`LatencyWindow`], in seconds
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LatencyStats {
+    pub last: f64,
+    pub mean: f64,
+    pub max: f64,
+}
+
+impl LatencyWindow {
+    pub fn new(span: f64) -> Self {
+        Self {
+            span,
+            latencies: VecDeque::new(),
+        }
+    }
+
+    /// Record a sample that arrived at local time `arrival`
+    pub fn push(&mut self, arrival: f64, timestamp: f64) {
+        self.latencies.push_back((arrival, arrival - timestamp));
+        while self.latencies.front().is_some_and(|&(t, _)| t < arrival - self.span) {
+            self.latencies.pop_front();
+        }
+    }
+
+    pub fn stats(&self) -> Option<LatencyStats> {
+        let &(_, last) = self.latencies.back()?;
+        let sum: f64 = self.latencies.iter().map(|&(_, l)| l).sum();
+        Some(LatencyStats {
+            last,
+            mean: sum / self.latencies.len() as f64,
+            max: self.latencies.iter().map(|&(_, l)| l).fold(f64::NEG_INFINITY, f64::max),
+        })
+    }
+}
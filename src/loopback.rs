@@ -0,0 +1,233 @@
+//! Replay → record loop verification
+//!
+//! `lsl-loopback` replays a recorded stream, records it again with the normal
+//! recording pipeline and compares the two copies here. The recording starts at
+//! whichever sample was pushed when the inlet connected, so the recorded samples
+//! are first located in the original by normalized cross-correlation of one
+//! channel (the one varying most at the start of the recording). From that lag
+//! on, the copies are compared sample by sample:
+//!
+//! - **Fidelity**: samples whose values are identical on every channel, and the
+//!   largest difference otherwise. A lossless loop reproduces every sample.
+//! - **Timing**: the difference between recorded and original timestamps of the
+//!   same sample. Its constant part is the clock offset between the sessions;
+//!   a linear trend is drift of the replay pacing, and the residuals around the
+//!   trend are the jitter the loop adds.
+//!
+//! Strictly periodic signals correlate equally well at every period; the
+//! smallest such lag is taken, so verify with non-periodic data where possible.
+
+use anyhow::Result;
+use std::path::Path;
+use std::sync::Arc;
+use zarrs::filesystem::FilesystemStore;
+
+use crate::drift::{linear_fit, LinearFit};
+use crate::export::{ExportStream, EXPORT_BLOCK_SAMPLES};
+
+/// Recorded samples correlated against the original to find the lag
+pub const CORRELATION_WINDOW: usize = 1024;
+
+/// Correlations this close to the best one count as equally good
+const CORRELATION_TOLERANCE: f64 = 1e-9;
+
+/// Timestamps and interleaved (sample-major) values of a numeric stream
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Signal {
+    pub timestamps: Vec<f64>,
+    pub values: Vec<f64>,
+    pub channel_count: usize,
+}
+
+impl Signal {
+    /// Number of samples
+    pub fn len(&self) -> usize {
+        self.timestamps.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.timestamps.is_empty()
+    }
+
+    /// Values of one channel
+    pub fn channel(&self, channel: usize) -> Vec<f64> {
+        self.values.iter().skip(channel).step_by(self.channel_count.max(1)).copied().collect()
+    }
+
+    /// Values of one sample
+    pub fn sample(&self, index: usize) -> &[f64] {
+        &self.values[index * self.channel_count..(index + 1) * self.channel_count]
+    }
+}
+
+/// Read up to `max_samples` samples of a numeric stream
+pub fn read_signal(store_path: &Path, stream_name: &str, max_samples: Option<u64>) -> Result<Signal> {
+    let store = Arc::new(FilesystemStore::new(store_path)?);
+    let stream = ExportStream::open(&store, stream_name, false)?;
+    if stream.is_marker() {
+        anyhow::bail!("Stream '{}' holds string samples; only numeric streams can be compared", stream_name);
+    }
+
+    let total = max_samples.map_or(stream.sample_count, |max| max.min(stream.sample_count));
+    let mut signal = Signal {
+        timestamps: Vec::with_capacity(total as usize),
+        values: Vec::with_capacity(total as usize * stream.channel_count),
+        channel_count: stream.channel_count,
+    };
+    let mut read = 0;
+    while read < total {
+        let len = EXPORT_BLOCK_SAMPLES.min(total - read);
+        let (timestamps, values) = stream.read_block(read, len)?;
+        signal.timestamps.extend(timestamps);
+        signal.values.extend(values.to_f64().unwrap_or_default());
+        read += len;
+    }
+    Ok(signal)
+}
+
+/// Pearson correlation of two equally long slices; `None` if either is constant
+fn correlation(a: &[f64], b: &[f64]) -> Option<f64> {
+    let n = a.len().min(b.len());
+    if n < 2 {
+        return None;
+    }
+    let mean_a = a[..n].iter().sum::<f64>() / n as f64;
+    let mean_b = b[..n].iter().sum::<f64>() / n as f64;
+    let (mut sab, mut saa, mut sbb) = (0.0, 0.0, 0.0);
+    for (&x, &y) in a[..n].iter().zip(&b[..n]) {
+        sab += (x - mean_a) * (y - mean_b);
+        saa += (x - mean_a) * (x - mean_a);
+        sbb += (y - mean_b) * (y - mean_b);
+    }
+    if saa == 0.0 || sbb == 0.0 {
+        return None;
+    }
+    Some(sab / (saa * sbb).sqrt())
+}
+
+/// Offset of `recorded` within `original` with the best normalized cross-correlation
+///
+/// Correlates the first [`CORRELATION_WINDOW`] recorded samples against the
+/// original at lags `0..=max_lag`. Returns the lag and its correlation, or `None`
+/// if the recorded window is constant or never fits into the original.
+pub fn find_lag(original: &[f64], recorded: &[f64], max_lag: usize) -> Option<(usize, f64)> {
+    let window = &recorded[..CORRELATION_WINDOW.min(recorded.len())];
+    if window.len() < 2 || !window.iter().all(|v| v.is_finite()) {
+        return None;
+    }
+    let mut best: Option<(usize, f64)> = None;
+    for lag in 0..=max_lag {
+        let Some(candidate) = original.get(lag..lag + window.len()) else {
+            break;
+        };
+        if let Some(r) = correlation(candidate, window)
+            && best.is_none_or(|(_, best_r)| r > best_r + CORRELATION_TOLERANCE)
+        {
+            best = Some((lag, r));
+        }
+    }
+    best
+}
+
+/// Outcome of comparing a re-recorded stream with its original
+#[derive(Debug, Clone, PartialEq)]
+pub struct LoopbackReport {
+    /// Channel used for the cross-correlation
+    pub channel: usize,
+    /// Index of the original sample matching the first recorded sample
+    pub lag: usize,
+    /// Normalized cross-correlation at the lag
+    pub correlation: f64,
+    /// Samples compared (overlap of both copies from the lag on)
+    pub compared: usize,
+    /// Compared samples identical on every channel
+    pub identical: usize,
+    /// Largest absolute value difference on any channel
+    pub max_abs_error: f64,
+    /// Fit of recorded minus original timestamp over original elapsed time
+    pub timing: Option<LinearFit>,
+    /// Root mean square of the timing residuals around the fit (s)
+    pub jitter_rms: f64,
+    /// Largest absolute timing residual (s)
+    pub max_jitter: f64,
+}
+
+impl LoopbackReport {
+    /// Share of compared samples reproduced exactly (percent)
+    pub fn fidelity_percent(&self) -> f64 {
+        if self.compared == 0 {
+            return 0.0;
+        }
+        self.identical as f64 / self.compared as f64 * 100.0
+    }
+
+    /// Drift of the recorded against the original timestamps (ppm)
+    pub fn drift_ppm(&self) -> f64 {
+        self.timing.map_or(0.0, |fit| fit.slope * 1e6)
+    }
+}
+
+/// Locate `recorded` in `original` and compare values and timing
+///
+/// `None` if the streams have different channel counts or no lag up to
+/// `max_lag` samples correlates.
+pub fn compare(original: &Signal, recorded: &Signal, max_lag: usize) -> Option<LoopbackReport> {
+    if original.channel_count != recorded.channel_count || original.channel_count == 0 || recorded.is_empty() {
+        return None;
+    }
+
+    // Correlate on the channel varying most at the start of the recording
+    let window = CORRELATION_WINDOW.min(recorded.len());
+    let channel = (0..recorded.channel_count)
+        .map(|c| {
+            let values: Vec<f64> = recorded.channel(c).into_iter().take(window).collect();
+            let mean = values.iter().sum::<f64>() / values.len() as f64;
+            let variance = values.iter().map(|v| (v - mean) * (v - mean)).sum::<f64>();
+            (c, if variance.is_finite() { variance } else { 0.0 })
+        })
+        .max_by(|a, b| a.1.total_cmp(&b.1))
+        .map(|(c, _)| c)?;
+    let (lag, correlation) = find_lag(&original.channel(channel), &recorded.channel(channel), max_lag)?;
+
+    let compared = recorded.len().min(original.len() - lag);
+    let mut identical = 0;
+    let mut max_abs_error: f64 = 0.0;
+    let mut elapsed = Vec::with_capacity(compared);
+    let mut difference = Vec::with_capacity(compared);
+    for i in 0..compared {
+        let mut same = true;
+        for (&a, &b) in original.sample(lag + i).iter().zip(recorded.sample(i)) {
+            if a != b && !(a.is_nan() && b.is_nan()) {
+                same = false;
+                max_abs_error = max_abs_error.max((a - b).abs());
+            }
+        }
+        if same {
+            identical += 1;
+        }
+        elapsed.push(original.timestamps[lag + i] - original.timestamps[lag]);
+        difference.push(recorded.timestamps[i] - original.timestamps[lag + i]);
+    }
+
+    let timing = linear_fit(&elapsed, &difference);
+    let (mut sum_squares, mut max_jitter): (f64, f64) = (0.0, 0.0);
+    if let Some(fit) = timing {
+        for (&x, &d) in elapsed.iter().zip(&difference) {
+            let residual = d - fit.at(x);
+            sum_squares += residual * residual;
+            max_jitter = max_jitter.max(residual.abs());
+        }
+    }
+
+    Some(LoopbackReport {
+        channel,
+        lag,
+        correlation,
+        compared,
+        identical,
+        max_abs_error,
+        timing,
+        jitter_rms: if compared > 0 { (sum_squares / compared as f64).sqrt() } else { 0.0 },
+        max_jitter,
+    })
+}
@@ -1,25 +1,67 @@
-use anyhow::Result;
-use lsl::Pullable;
+use anyhow::{Context, Result};
+use lsl::{ExPullable, Pullable};
 use std::io::Write;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::sync::{
     atomic::{AtomicBool, Ordering},
-    Arc,
+    mpsc, Arc,
 };
 use std::thread;
 use std::time::{Duration, Instant};
 
 use crate::cli::Args;
-use crate::zarr::writer::{ZarrWriter, ZarrWriterConfig};
-use crate::zarr::{open_or_create_zarr_store, setup_stream_arrays};
+use crate::captures::CaptureTrigger;
+use crate::chunked::{for_each_kept_run, pulls_in_chunks, ChunkQueue, DEFAULT_CHUNK_PULL_RATE};
+use crate::clock_jumps::{wall_clock_now, ClockJump, ClockJumpDetector};
+use crate::connection::{ConnectionStats, ConnectionTracker};
+use crate::dropped::{DropEstimate, DropEstimator};
+use crate::flags::FlagTracker;
+use crate::info_history::InfoHistory;
+use crate::memory::MemoryMonitor;
+use crate::monitor::MonitorOutlet;
+use crate::observer::{ObserverDispatch, RecordingObserver};
+use crate::pauses::PauseTracker;
+use crate::plausibility::{PlausibilityCheck, PlausibilityEvent, PlausibleRange};
+use crate::preflight::PreflightReport;
+use crate::quality::{QualityEvent, QualityMonitor, QualityOptions};
+use crate::rate_guard::RateGuard;
+use crate::rotation::{directory_size, link_next, mark_part, part_name, part_path, Continuation, PartTracker};
+use crate::rules::{Rule, RuleAction, RuleEngine, RuleEvent};
+use crate::schedule::{Block, BlockEvent, BlockTracker};
+use crate::status::{serve_status, RecordingState, StatusTracker};
+use crate::tap::{LiveTap, TapStreamInfo};
+use crate::zarr::remote::RemoteUploader;
+use crate::zarr::writer::{MirrorConfig, ZarrWriter, ZarrWriterConfig};
+use crate::devices::update_devices_table;
+use crate::export::channel_label;
+use crate::manifest::{read_manifest, update_manifest_streams};
+use crate::permissions::{apply_permissions, apply_root_permissions, StorePermissions};
+use crate::zarr::layout::parse_channels_to_json;
+use crate::zarr::recovery::{write_recovery_marker, RecoveryMarker};
+use crate::zarr::{
+    open_or_create_zarr_store, serialize_stream_info, setup_flags_array, setup_stream_arrays, time_anchor,
+    StorageOptions,
+};
+
+/// Timeout for resolving the stream and fetching its info in `--info-refresh`
+const INFO_REFRESH_TIMEOUT: f64 = 2.0;
+
+/// Pull timeout of `--preflight` (seconds), short so the check ends on time
+const PREFLIGHT_PULL_TIMEOUT: f64 = 0.1;
+
+/// Inlet buffer: seconds of data for regular streams, hundreds of samples for irregular ones
+pub(crate) const INLET_MAX_BUFFER: i32 = 300;
 
 /// Resolve LSL stream with retry logic and random delays to avoid race conditions
+///
+/// Every query and retry delay is counted in `tracker`.
 pub fn resolve_lsl_stream_with_retry(
-    source_id: &str,
+    strategy: &ResolutionStrategy,
     timeout: f64,
     quiet: bool,
     max_attempts: u32,
     base_delay_ms: u64,
+    tracker: &mut ConnectionTracker,
 ) -> Result<Vec<lsl::StreamInfo>> {
     use std::time::Duration;
 
@@ -36,9 +78,12 @@ pub fn resolve_lsl_stream_with_retry(
                 println!("Retrying stream resolution in {:?}...", delay);
             }
             std::thread::sleep(delay);
+            tracker.resolution_attempt(delay);
+        } else {
+            tracker.resolution_attempt(Duration::ZERO);
         }
 
-        match lsl::resolve_byprop("source_id", source_id, 1, timeout) {
+        match strategy.resolve(timeout) {
             Ok(streams) => {
                 if !streams.is_empty() {
                     if !quiet && attempt > 0 {
@@ -70,37 +115,88 @@ pub fn resolve_lsl_stream_with_retry(
     }
 
     Err(anyhow::anyhow!(
-        "No stream found with source_id={} after {} attempts",
-        source_id,
+        "No stream found with {} after {} attempts",
+        strategy,
         max_attempts
     ))
 }
 
-pub fn record_lsl_stream(params: RecordingParams) -> Result<()> {
+pub fn record_lsl_stream(mut params: RecordingParams) -> Result<()> {
+    // Figures for --status-port and the STATUS PROGRESS lines read by lsl-multi-recorder
+    let stream_label = params
+        .zarr_config
+        .as_ref()
+        .map_or(params.source_id, |config| config.stream_name.as_str());
+    let mut status = StatusTracker::new(stream_label, params.recorder_args.status_interval);
+    if let Some(port) = params.recorder_args.status_port {
+        let shared = status.shared();
+        let address = serve_status(port, "lsl-recorder", move || {
+            shared.lock().map(|status| vec![status.clone()]).unwrap_or_default()
+        })?;
+        if !params.quiet {
+            println!("Status endpoint:\thttp://{}/status", address);
+        }
+    }
+
     // Resolve stream with retry logic for robustness
+    let mut connection = ConnectionTracker::new();
     let res = resolve_lsl_stream_with_retry(
-        params.source_id,
+        &params.resolution_config.strategy,
         params.resolution_config.timeout,
         params.quiet,
         params.resolution_config.max_retry_attempts,
         params.resolution_config.retry_base_delay_ms,
+        &mut connection,
     )?;
 
-    let inl = lsl::StreamInlet::new(&res[0], 300, 0, true)
+    // Shutdown requested while resolving: nothing has been written yet
+    if params.quit.load(Ordering::SeqCst) {
+        return Ok(());
+    }
+
+    let mut inl = lsl::StreamInlet::new(&res[0], INLET_MAX_BUFFER, 0, true)
         .map_err(|e| anyhow::anyhow!("LSL error: {}", e))?;
     let mut info = inl
         .info(lsl::FOREVER)
         .map_err(|e| anyhow::anyhow!("LSL error: {}", e))?;
 
+    // --name-by uid: the group is keyed by the outlet's UID, known only once connected
+    if let Some(ref mut config) = params.zarr_config {
+        config.stream_name = params.recorder_args.name_by.group_name(&config.stream_name, &info.uid())?;
+    }
+
     // Detect if this is an irregular stream (nominal_srate == 0)
     let is_irregular = info.nominal_srate() == 0.0;
     params.is_irregular_stream.store(is_irregular, Ordering::SeqCst);
+    status.set_nominal_srate(info.nominal_srate());
+    // Progress callbacks of an embedding application
+    let mut observer = params.observer.take().map(|observer| {
+        let text = matches!(info.channel_format(), lsl::ChannelFormat::String);
+        ObserverDispatch::new(observer, &status.status().stream, info.channel_count() as usize, text)
+    });
 
     if !params.quiet {
         println!("Connected to stream with {} channels", info.channel_count());
         println!("Sample rate: {}", info.nominal_srate());
     }
 
+    if !params.recorder_args.capture_on.is_empty() && !matches!(info.channel_format(), lsl::ChannelFormat::String) {
+        anyhow::bail!("--capture-on needs a marker (string) stream");
+    }
+
+    if let Some(channel) = params.recorder_args.flag_channel {
+        if matches!(info.channel_format(), lsl::ChannelFormat::String) {
+            anyhow::bail!("--flag-channel needs a numeric stream");
+        }
+        if channel >= info.channel_count() as usize {
+            anyhow::bail!(
+                "--flag-channel {} is out of range (stream has {} channels)",
+                channel,
+                info.channel_count()
+            );
+        }
+    }
+
     // Calculate optimal pull timeout based on stream frequency
     let pull_timeout = calculate_pull_timeout(
         &info,
@@ -108,18 +204,12 @@ pub fn record_lsl_stream(params: RecordingParams) -> Result<()> {
         params.quiet,
     );
 
-    inl.set_postprocessing(&[
-        lsl::ProcessingOption::ClockSync,
-        lsl::ProcessingOption::Dejitter,
-        lsl::ProcessingOption::Monotonize,
-        // lsl::ProcessingOption::Threadsafe,
-    ])
-    .map_err(|e| anyhow::anyhow!("LSL error: {}", e))?;
+    set_postprocessing(&inl)?;
 
     // Initialize Zarr writer if config is provided
-    let mut zarr_writer = if let Some(zarr_config) = params.zarr_config {
+    let mut zarr_writer = if let Some(ref zarr_config) = params.zarr_config {
         initialize_zarr_writer(
-            &zarr_config,
+            zarr_config,
             &mut info,
             &inl,
             &params.recording_config,
@@ -130,74 +220,462 @@ pub fn record_lsl_stream(params: RecordingParams) -> Result<()> {
         None
     };
 
+    // --rotate-every/--rotate-size: the part being written and when to move on
+    let rotation = params.recorder_args.rotation_options();
+    let mut parts = PartTracker::new(rotation);
+    let mut zarr_config = params.zarr_config.clone();
+    // Samples recorded before the current part
+    let mut part_start_count: u64 = 0;
+    if rotation.is_enabled()
+        && let Some(ref config) = zarr_config
+    {
+        for store in std::iter::once(&config.store_path).chain(config.mirror_path.as_ref()) {
+            if let Err(e) = mark_part(store, store, 1, None, &rotation) {
+                eprintln!("Warning: {:#}", e);
+            }
+        }
+    }
+
+    // Optional live tap; runs on its own thread and never blocks this loop
+    let mut live_tap = match params.recorder_args.tap {
+        Some(ref url) => {
+            let tap = LiveTap::start(
+                url,
+                params.recorder_args.tap_format,
+                params.recorder_args.tap_rate,
+                TapStreamInfo {
+                    stream_name: info.stream_name(),
+                    channel_count: info.channel_count() as usize,
+                    nominal_srate: info.nominal_srate(),
+                },
+            )?;
+            if !params.quiet {
+                println!("Live tap listening on {}", url);
+            }
+            Some(tap)
+        }
+        None => None,
+    };
+
+    // Optional decimated LSL outlet for monitoring stations
+    let mut monitor = match params.recorder_args.monitor_options() {
+        Some(options) => {
+            let monitor = MonitorOutlet::start(&mut info, &options)?;
+            if !params.quiet {
+                println!("Monitor outlet:\t{} ({:.1} Hz)", monitor.name(), monitor.rate());
+            }
+            Some(monitor)
+        }
+        None => None,
+    };
+
+    // Optional live signal quality checks (RMS, flatline, clipping)
+    let mut quality = match params.recorder_args.quality_options() {
+        Some(options) => start_quality_monitor(&mut info, &options, params.quiet),
+        None => None,
+    };
+    // Optional --rule checks, e.g. pausing when the electrodes come off
+    let mut rules = start_rule_engine(&info, &params.recorder_args.rules, params.quiet);
+    // Whether the current pause was taken by a rule, which also ends it
+    let mut rule_paused = false;
+    // Optional --plausible-range check, e.g. catching a wrong amplifier gain early
+    let mut plausibility = params
+        .recorder_args
+        .plausible_range
+        .as_ref()
+        .and_then(|range| start_plausibility_check(&mut info, range, params.quiet));
+    if plausibility.is_some() {
+        status.set_implausible_samples(0);
+    }
+    // Optional --capture-on markers that note time anchors and run --capture-command
+    let mut captures = match params.zarr_config {
+        Some(ref config) if !params.recorder_args.capture_on.is_empty() => Some(CaptureTrigger::new(
+            params.recorder_args.capture_on.clone(),
+            params.recorder_args.capture_command.clone(),
+            info.stream_name(),
+            &config.store_path,
+        )),
+        _ => None,
+    };
+
+    // Devices may update their <desc> (e.g. impedances) while streaming
+    let mut info_refresh = if params.recorder_args.info_refresh > 0.0 {
+        let history = InfoHistory::new(serialize_stream_info(&mut info)?);
+        let source_id = info.source_id();
+        let predicate = if source_id.is_empty() {
+            format!("uid='{}'", info.uid())
+        } else {
+            format!("source_id='{}'", source_id)
+        };
+        let interval = Duration::from_secs_f64(params.recorder_args.info_refresh);
+        Some((InfoRefresher::start(predicate, interval), history))
+    } else {
+        None
+    };
+
     // Create appropriate sample buffer based on channel format
     let mut sample_buffer = create_sample_buffer(&info)?;
 
+    // --chunk-pull-rate: high-rate numeric streams are pulled in chunks
+    let mut chunk_queue = pulls_in_chunks(info.nominal_srate(), params.recording_config.chunk_pull_rate)
+        .then(|| create_sample_queue(&sample_buffer))
+        .flatten();
+    if chunk_queue.is_some() && !params.quiet {
+        println!(
+            "Pulling in chunks ({:.1} Hz >= --chunk-pull-rate {:.1} Hz)",
+            info.nominal_srate(),
+            params.recording_config.chunk_pull_rate
+        );
+    }
+
+    // --sample-flags: what makes a sample suspect, decided as it is pulled
+    let buffer_seconds = (!is_irregular).then_some(INLET_MAX_BUFFER as f64);
+    let mut sample_flags = params
+        .recording_config
+        .sample_flags
+        .then(|| FlagTracker::new(params.recorder_args.flag_channel, buffer_seconds));
+    // Value of the pulled sample in the --flag-channel
+    let mut device_value: Option<f64> = None;
+    // Chunks reach the writer as blocks unless rotation or sample flags decide per sample
+    let block_writes = sample_flags.is_none() && !rotation.is_enabled();
+
     let mut sample_count: u64 = 0;
-    let mut memory_monitor = MemoryMonitor::new(params.recorder_args.memory_monitor);
+    let mut memory_monitor = params.recorder_args.memory_monitor.then(|| {
+        MemoryMonitor::new(
+            Duration::from_secs_f64(params.recorder_args.memory_interval.max(1.0)),
+            params.recorder_args.memory_growth_limit,
+        )
+    });
+    // First timestamp of the part being written (of the whole recording without rotation)
     let mut first_timestamp: Option<f64> = None;
     let mut last_timestamp: Option<f64> = None;
+    let mut rate_guard = RateGuard::new(
+        info.nominal_srate(),
+        params.recording_config.max_rate_factor,
+        params.recording_config.allow_rate_overrun,
+    );
+    // Received samples against the nominal rate, for losses that leave no timestamp gap
+    let mut drops = DropEstimator::new(info.nominal_srate());
+    let mut guard_error: Option<anyhow::Error> = None;
+    // Last sample while recording, for STREAM_LOST/STREAM_RECOVERED reports to lsl-multi-recorder
+    let mut last_sample_at: Option<Instant> = None;
+    let mut stream_lost = false;
+    let lost_timeout = params.recording_config.lost_timeout;
+    // --reconnect-after: when the lost stream was last resolved anew
+    let reconnect_after = params.recording_config.reconnect_after;
+    let mut last_reconnect: Option<Instant> = None;
+    let mut blocks = BlockTracker::default();
+    let mut pauses = PauseTracker::default();
+    // Suspend/resume and clock steps, noticed as the wall clock or the timestamps jump
+    let mut clock_jumps = ClockJumpDetector::new(params.recorder_args.clock_jump_threshold);
 
     loop {
         if params.quit.load(Ordering::SeqCst) {
             break;
         }
 
+        if let Some(pause) = pauses.update(params.paused.load(Ordering::SeqCst), lsl::local_clock()) {
+            store_pause(zarr_writer.as_ref(), pause);
+        }
+        if let Some(jump) = clock_jumps.check_wall_clock(lsl::local_clock(), wall_clock_now()) {
+            report_clock_jump(zarr_writer.as_ref(), &jump);
+            if let Some(ref mut flags) = sample_flags {
+                flags.clock_jump();
+            }
+        }
+
+        status.set_state(if stream_lost {
+            RecordingState::Lost
+        } else if pauses.is_paused() {
+            RecordingState::Paused
+        } else if params.recording.load(Ordering::SeqCst) {
+            RecordingState::Recording
+        } else if sample_count > 0 {
+            RecordingState::Stopped
+        } else {
+            RecordingState::Ready
+        });
+        status.tick();
+        if let Some(ref mut observer) = observer {
+            observer.state(status.status());
+            observer.tick();
+        }
+
+        if let Some((ref refresher, ref mut history)) = info_refresh
+            && let Some((lsl_clock, stream_info)) = refresher.try_recv()
+            && let Some(snapshot) = history.update(lsl_clock, stream_info)
+        {
+            println!("STATUS STREAM_INFO_CHANGED ({})", snapshot.changed.join(", "));
+            std::io::stdout().flush().ok();
+            if let Some(ref writer) = zarr_writer
+                && let Err(e) = writer.record_stream_info_snapshot(&snapshot)
+            {
+                eprintln!("Warning: {:#}", e);
+            }
+        }
+
+        if let Some(ref events) = params.block_events {
+            for block in events.try_iter().filter_map(|event| blocks.apply(event)) {
+                store_block(zarr_writer.as_ref(), &block);
+            }
+        }
+
         if params.recording.load(Ordering::SeqCst) {
+            // A RESUME ends a pause taken by a rule
+            rule_paused = false;
+            if rotation.is_enabled() {
+                parts.start(lsl::local_clock());
+            }
+
+            // A sample past the end of the current part starts the next one
+            macro_rules! rotate_if_due {
+                ($ts:expr) => {
+                    if let Some(ref mut config) = zarr_config
+                        && let Some(ref mut writer) = zarr_writer
+                        && let Some(part) = parts.due($ts, || directory_size(&config.store_path))
+                    {
+                        let summary = RecordingSummary {
+                            samples_recorded: sample_count - part_start_count,
+                            first_timestamp,
+                            last_timestamp,
+                            connection: &connection.finish(Instant::now()),
+                        };
+                        let previous = config.store_path.clone();
+                        *writer = rotate_part(writer, config, part, &summary, &mut info, &inl, &params)?;
+                        let continuation = Continuation {
+                            store: previous.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default(),
+                            last_timestamp,
+                            samples_before: sample_count,
+                        };
+                        if let Err(e) = writer.record_continuation(&continuation) {
+                            eprintln!("Warning: {:#}", e);
+                        }
+                        parts.advance(part);
+                        part_start_count = sample_count;
+                        first_timestamp = None;
+                    }
+                };
+            }
+
             macro_rules! pull_and_record {
-                ($buf:expr, $method:ident) => {{
+                ($buf:expr, $method:ident, $block:ident, $queue:ident) => {{
                     // Clear buffer and reuse capacity
                     $buf.clear();
-                    let ts = inl
-                        .pull_sample_buf($buf, pull_timeout)
-                        .map_err(|e| anyhow::anyhow!("LSL error: {}", e))?;
+                    let pulled = match chunk_queue {
+                        Some(SampleQueue::$queue(ref mut queue)) => pull_queued(&inl, queue, $buf, pull_timeout),
+                        _ => inl.pull_sample_buf($buf, pull_timeout),
+                    };
+                    // A failing inlet counts as a lost stream rather than ending the recording
+                    let ts = match pulled {
+                        Ok(ts) => ts,
+                        Err(e) => {
+                            inlet_error = Some(e.to_string());
+                            0.0
+                        }
+                    };
+                    // The inlet kept buffering while paused; those samples are dropped
+                    let ts = if pauses.covers(ts) { 0.0 } else { ts };
                     if ts != 0.0 {
+                        rotate_if_due!(ts);
                         if let Some(ref mut writer) = zarr_writer {
-                            // Pass data by slice reference to avoid full clone
-                            writer.$method(&$buf, ts);
+                            match chunk_queue {
+                                // A chunk goes to the writer in blocks along with its first recorded
+                                // sample; the queued samples then only pass through the rest of the loop
+                                Some(SampleQueue::$queue(ref mut queue)) if block_writes => {
+                                    if !queue.last_written() {
+                                        writer.$method(&$buf, ts);
+                                        let channels = queue.channels();
+                                        let (data, timestamps) = queue.take_unwritten();
+                                        for_each_kept_run(timestamps, |ts| !pauses.covers(ts), |run| {
+                                            let values = &data[run.start * channels..run.end * channels];
+                                            writer.$block(values, &timestamps[run]);
+                                        });
+                                    }
+                                }
+                                // Pass data by slice reference to avoid full clone
+                                _ => writer.$method(&$buf, ts),
+                            }
+                        }
+                        device_value = sample_flags
+                            .as_ref()
+                            .and_then(|flags| flags.device_channel())
+                            .and_then(|channel| $buf.get(channel))
+                            .map(|value| *value as f64);
+                        if let Some(ref mut tap) = live_tap {
+                            tap.offer(ts, &$buf);
+                        }
+                        if let Some(ref mut monitor) = monitor {
+                            monitor.offer(ts, &$buf);
+                        }
+                        if let Some(ref mut observer) = observer {
+                            observer.offer(ts, &$buf);
+                        }
+                        if let Some(ref mut quality) = quality {
+                            report_quality_events(quality.push(&$buf));
+                        }
+                        if let Some(ref mut rules) = rules {
+                            rule_events.extend(rules.push(ts, &$buf));
+                        }
+                        if let Some(ref mut plausibility) = plausibility {
+                            report_plausibility_events(plausibility.push(ts, &$buf));
+                            status.set_implausible_samples(plausibility.implausible_samples());
                         }
                     }
                     ts
                 }};
             }
 
+            let mut rule_events = Vec::new();
+            let mut inlet_error: Option<String> = None;
             let ts = match &mut sample_buffer {
-                SampleBuffer::Float32(buf) => pull_and_record!(buf, add_sample_slice_f32),
-                SampleBuffer::Float64(buf) => pull_and_record!(buf, add_sample_slice_f64),
-                SampleBuffer::Int32(buf) => pull_and_record!(buf, add_sample_slice_i32),
-                SampleBuffer::Int16(buf) => pull_and_record!(buf, add_sample_slice_i16),
-                SampleBuffer::Int8(buf) => pull_and_record!(buf, add_sample_slice_i8),
+                SampleBuffer::Float32(buf) => {
+                    pull_and_record!(buf, add_sample_slice_f32, add_sample_block_f32, Float32)
+                }
+                SampleBuffer::Float64(buf) => {
+                    pull_and_record!(buf, add_sample_slice_f64, add_sample_block_f64, Float64)
+                }
+                SampleBuffer::Int32(buf) => {
+                    pull_and_record!(buf, add_sample_slice_i32, add_sample_block_i32, Int32)
+                }
+                SampleBuffer::Int16(buf) => {
+                    pull_and_record!(buf, add_sample_slice_i16, add_sample_block_i16, Int16)
+                }
+                SampleBuffer::Int8(buf) => {
+                    pull_and_record!(buf, add_sample_slice_i8, add_sample_block_i8, Int8)
+                }
                 SampleBuffer::String(buf) => {
                     // String streams require special handling - use pull_sample() instead of pull_sample_buf()
                     // pull_sample_buf() doesn't work correctly with Vec<String>
                     match <lsl::StreamInlet as Pullable<String>>::pull_sample(&inl, pull_timeout) {
+                        Ok((_, ts)) if pauses.covers(ts) => 0.0,
                         Ok((sample_data, ts)) => {
                             if ts != 0.0 {
+                                rotate_if_due!(ts);
                                 *buf = sample_data; // Update the buffer with the pulled data
                                 if let Some(ref mut writer) = zarr_writer {
                                     writer.add_sample_slice_string(buf, ts);
                                 }
+                                if let Some(ref mut tap) = live_tap {
+                                    tap.offer_marker(ts, buf);
+                                }
+                                if let Some(ref mut observer) = observer {
+                                    observer.offer_marker(ts, buf);
+                                }
+                                if let Some(ref mut captures) = captures
+                                    && let Some(ref config) = zarr_config
+                                    && let Some(capture) = captures.sample(
+                                        &config.store_path,
+                                        buf,
+                                        ts,
+                                        lsl::local_clock(),
+                                        wall_clock_now(),
+                                    )
+                                {
+                                    println!("STATUS CAPTURE ({} #{})", capture.marker, capture.index);
+                                    std::io::stdout().flush().ok();
+                                }
                             }
                             ts
                         }
                         Err(e) => {
-                            // Log error but don't fail - string streams may have no data
-                            if !params.quiet {
-                                eprintln!("Warning: Failed to pull string sample: {}", e);
-                            }
+                            inlet_error = Some(e.to_string());
                             0.0
                         }
                     }
                 }
             };
+            handle_rule_events(rule_events, &params, &mut rule_paused);
+
+            if ts == 0.0 {
+                // Regular streams that go quiet have lost their outlet; the inlet keeps
+                // trying to reconnect, and the parent decides whether to wait for it
+                let silent = !is_irregular
+                    && !lost_timeout.is_zero()
+                    && last_sample_at.is_some_and(|t| t.elapsed() >= lost_timeout);
+                if !stream_lost && (silent || inlet_error.is_some()) {
+                    stream_lost = true;
+                    connection.stream_lost_after(last_sample_at.unwrap_or_else(Instant::now), last_timestamp);
+                    match inlet_error {
+                        Some(ref e) => println!("STATUS STREAM_LOST (inlet error: {})", e),
+                        None => println!("STATUS STREAM_LOST (no samples for {:.1}s)", lost_timeout.as_secs_f64()),
+                    }
+                    std::io::stdout().flush().ok();
+                }
+
+                // The inlet did not find its way back: look for the source again
+                let now = Instant::now();
+                if !reconnect_after.is_zero()
+                    && connection.lost_for(now).is_some_and(|lost| lost >= reconnect_after)
+                    && last_reconnect.is_none_or(|at| now.duration_since(at) >= reconnect_after)
+                {
+                    let lost = connection.lost_for(now).unwrap_or_default();
+                    last_reconnect = Some(now);
+                    connection.reconnect_attempt();
+                    println!("STATUS RECONNECTING (lost for {:.1}s)", lost.as_secs_f64());
+                    std::io::stdout().flush().ok();
+                    match reconnect_inlet(&info, &params) {
+                        Ok(Some(inlet)) => {
+                            inl = inlet;
+                            println!("STATUS RECONNECTED");
+                            std::io::stdout().flush().ok();
+                        }
+                        Ok(None) => {
+                            if !params.quiet {
+                                println!("Stream not found yet, retrying in {:.1}s", reconnect_after.as_secs_f64());
+                            }
+                        }
+                        Err(e) => eprintln!("Warning: reconnecting failed: {:#}", e),
+                    }
+                }
+                if inlet_error.is_some() {
+                    // A broken inlet fails at once instead of waiting for the pull timeout
+                    thread::sleep(Duration::from_millis(50));
+                }
+            } else {
+                let now = Instant::now();
+                last_sample_at = Some(now);
+                if stream_lost {
+                    stream_lost = false;
+                    last_reconnect = None;
+                    connection.stream_recovered_at(now, Some(ts));
+                    if let Some(ref mut flags) = sample_flags {
+                        flags.stream_recovered(lsl::local_clock());
+                    }
+                    println!("STATUS STREAM_RECOVERED");
+                    std::io::stdout().flush().ok();
+                }
+            }
 
             if ts != 0.0 {
                 sample_count += 1;
+                status.sample(ts);
                 last_timestamp = Some(ts);  // Track last timestamp
+                // The wall-clock start is when recording begins, not when the store was set up
+                if first_timestamp.is_none()
+                    && let Some(ref writer) = zarr_writer
+                    && let Err(e) = writer.record_start_time()
+                {
+                    eprintln!("Warning: {:#}", e);
+                }
+                first_timestamp.get_or_insert(ts);
+                if let Some(jump) = clock_jumps.check_sample(ts, lsl::local_clock()) {
+                    report_clock_jump(zarr_writer.as_ref(), &jump);
+                    if let Some(ref mut flags) = sample_flags {
+                        flags.clock_jump();
+                    }
+                }
+                if let Some(estimate) = drops.as_mut().and_then(|drops| drops.sample(ts)) {
+                    report_dropped_samples(&estimate);
+                }
+                if let Some(ref mut flags) = sample_flags {
+                    let bits = flags.sample(ts, lsl::local_clock(), device_value.take());
+                    if let Some(ref mut writer) = zarr_writer {
+                        writer.flag_last_sample(bits);
+                    }
+                }
 
                 // Signal first sample pulled for STOP_AFTER timer
                 if sample_count == 1 {
-                    first_timestamp = Some(ts);  // Track first timestamp
                     params.first_sample_pulled.store(true, Ordering::SeqCst);
 
                     // Report to parent (lsl-multi-recorder) that first sample is pulled
@@ -216,37 +694,683 @@ pub fn record_lsl_stream(params: RecordingParams) -> Result<()> {
                 if let Some(ref mut writer) = zarr_writer
                     && writer.needs_flush() {
                         writer.flush()?;
+                        status.flushed();
+                        if let Some(ref mut observer) = observer {
+                            observer.flushed(status.status());
+                        }
                     }
+                if let Some(ref writer) = zarr_writer {
+                    status.set_buffer_fill(writer.buffer_sample_count() as f64 / writer.buffer_capacity() as f64);
+                    let stats = writer.writer_stats();
+                    let latency = (stats.writes > 0).then_some((stats.mean_write_ms, stats.max_write_ms));
+                    status.set_write_stats(stats.queue_depth, latency);
+                }
 
                 // Memory monitoring report
-                memory_monitor.maybe_report(sample_count, &zarr_writer, params.quiet);
+                if let Some(ref mut monitor) = memory_monitor {
+                    let buffer_bytes = zarr_writer.as_ref().map_or(0, |writer| writer.buffer_bytes());
+                    monitor.maybe_report(sample_count, buffer_bytes);
+                } else if !params.quiet && sample_count.is_multiple_of(100) {
+                    println!("Recorded {} samples", sample_count);
+                }
+
+                // Stop recording if the stream delivers far more data than it advertises
+                if let Err(e) = rate_guard.check(ts, params.quiet) {
+                    guard_error = Some(e);
+                    break;
+                }
+            }
+        } else if rule_paused && params.paused.load(Ordering::SeqCst) {
+            if let Some(ref mut drops) = drops {
+                drops.interrupt();
+            }
+            // Keep watching the signal so the rule can end the pause it took
+            if let Some(ref mut rules) = rules {
+                match pull_for_rules(&inl, &mut sample_buffer, rules, pull_timeout) {
+                    Ok(events) => handle_rule_events(events, &params, &mut rule_paused),
+                    // A failing inlet counts as a lost stream rather than ending the recording
+                    Err(e) => {
+                        if !stream_lost {
+                            stream_lost = true;
+                            connection.stream_lost_after(last_sample_at.unwrap_or_else(Instant::now), last_timestamp);
+                            println!("STATUS STREAM_LOST (inlet error: {})", e);
+                            std::io::stdout().flush().ok();
+                        }
+                        thread::sleep(Duration::from_millis(50));
+                    }
+                }
             }
         } else {
+            // Silence while stopped is expected
+            rule_paused = false;
+            last_sample_at = None;
+            if let Some(ref mut drops) = drops {
+                drops.interrupt();
+            }
             thread::sleep(Duration::from_millis(50));
         }
     }
 
-    // Final flush for any remaining samples
-    if let Some(ref mut writer) = zarr_writer {
-        writer.flush()?;
+    let connection = connection.finish(Instant::now());
 
-        // Update final recording metadata with first and last timestamps
-        // Note: requested duration is already in recorder_config.duration
-        writer.finalize_recording_metadata(first_timestamp, last_timestamp)?;
+    // Blocks that ended while the loop was exiting, and one cut short by QUIT
+    if let Some(ref events) = params.block_events {
+        for block in events.try_iter().filter_map(|event| blocks.apply(event)) {
+            store_block(zarr_writer.as_ref(), &block);
+        }
+    }
+    if let Some(block) = blocks.interrupt(lsl::local_clock()) {
+        store_block(zarr_writer.as_ref(), &block);
+    }
+    if let Some(pause) = pauses.finish(lsl::local_clock()) {
+        store_pause(zarr_writer.as_ref(), pause);
+    }
+    if let Some(ref quality) = quality
+        && let Some(ref writer) = zarr_writer
+        && let Err(e) = writer.record_quality(&quality.summary())
+    {
+        eprintln!("Warning: {:#}", e);
+    }
+    if let Some(ref plausibility) = plausibility
+        && let Some(ref writer) = zarr_writer
+        && let Err(e) = writer.record_plausibility(&plausibility.summary())
+    {
+        eprintln!("Warning: {:#}", e);
+    }
+    if let Some(ref mut rules) = rules {
+        rules.finish(last_timestamp.unwrap_or_else(lsl::local_clock));
+        if let Some(ref writer) = zarr_writer
+            && let Err(e) = writer.record_rules(&rules.summary())
+        {
+            eprintln!("Warning: {:#}", e);
+        }
+    }
+    if let Some(ref flags) = sample_flags
+        && let Some(ref writer) = zarr_writer
+        && let Err(e) = writer.record_flag_counts(flags.flagged(), flags.counts())
+    {
+        eprintln!("Warning: {:#}", e);
+    }
+    // Running capture commands store their captures before the store is finalized
+    if let Some(ref mut captures) = captures {
+        captures.finish();
+    }
+    let drop_estimate = drops.as_ref().map(|drops| drops.estimate()).filter(|estimate| estimate.received > 0);
+    if let Some(ref estimate) = drop_estimate
+        && let Some(ref writer) = zarr_writer
+        && let Err(e) = writer.record_dropped_samples(estimate)
+    {
+        eprintln!("Warning: {:#}", e);
+    }
+
+    // Final flush for any remaining samples, then the final recording metadata
+    // Note: requested duration is already in recorder_config.duration
+    if let Some(ref mut writer) = zarr_writer
+        && let Some(ref config) = zarr_config
+    {
+        let summary = RecordingSummary {
+            samples_recorded: sample_count - part_start_count,
+            first_timestamp,
+            last_timestamp,
+            connection: &connection,
+        };
+        finish_writer(writer, config, &summary)?;
+    }
+
+    // Chunks written while recording were created with the process umask
+    if let Some(ref config) = zarr_config {
+        apply_store_permissions(config)?;
+    }
+    status.finish();
+    if let Some(ref mut observer) = observer {
+        observer.finish(status.status());
+    }
+
+    if let Some(e) = guard_error {
+        return Err(e);
     }
 
     if !params.quiet {
         println!("Recording stopped. Total samples: {}", sample_count);
+        if let Some(ref flags) = sample_flags {
+            let counts: Vec<String> = flags.counts().iter().map(|(name, n)| format!("{} {}", name, n)).collect();
+            if counts.is_empty() {
+                println!("Flagged samples:\t0");
+            } else {
+                println!("Flagged samples:\t{} ({})", flags.flagged(), counts.join(", "));
+            }
+        }
+        if let Some(ref estimate) = drop_estimate {
+            println!(
+                "Dropped:\t~{} of {} expected samples ({:.2}%, from the nominal rate)",
+                estimate.dropped,
+                estimate.expected,
+                estimate.percent()
+            );
+        }
+        if let Some(ref plausibility) = plausibility {
+            println!("Implausible:\t{} samples", plausibility.implausible_samples());
+        }
+        if let Some(ref queue) = chunk_queue {
+            let (chunks, mean, largest) = queue.chunk_stats();
+            println!("Chunked pulls:\t{} (mean {:.1}, largest {} samples)", chunks, mean, largest);
+        }
+        if let Some(stats) = zarr_writer.as_ref().map(|writer| writer.writer_stats()).filter(|s| s.writes > 0) {
+            println!(
+                "Writes:\t{} (mean {:.1} ms, longest {:.1} ms, queue peak {}/{}, {:.1}s waited for the disk)",
+                stats.writes,
+                stats.mean_write_ms,
+                stats.max_write_ms,
+                stats.max_queue_depth,
+                stats.queue_capacity,
+                stats.blocked_s
+            );
+        }
+        print_connection_summary(&connection);
+        if let Some(tap) = live_tap.as_ref().filter(|t| t.dropped_frames() > 0) {
+            println!("Live tap dropped {} frames (clients too slow)", tap.dropped_frames());
+        }
     }
     Ok(())
 }
 
+/// What the recorder knows about a recording when it ends
+pub(crate) struct RecordingSummary<'a> {
+    pub samples_recorded: u64,
+    pub first_timestamp: Option<f64>,
+    pub last_timestamp: Option<f64>,
+    pub connection: &'a ConnectionStats,
+}
+
+/// Flush the remaining samples and finalize the stream metadata
+///
+/// If either step fails, the summary is saved as a recovery marker outside the store
+/// so `lsl-repair` can restore it later.
+pub(crate) fn finish_writer(writer: &mut ZarrWriter, config: &ZarrConfig, summary: &RecordingSummary) -> Result<()> {
+    let (failed_step, result) = match writer.flush_all() {
+        Ok(()) => (
+            "finalize",
+            writer.finalize_recording_metadata(
+                summary.first_timestamp,
+                summary.last_timestamp,
+                Some(summary.connection),
+            ),
+        ),
+        Err(e) => ("flush", Err(e)),
+    };
+    let Err(error) = result else {
+        return Ok(());
+    };
+
+    // Samples still buffered or queued for the writer thread when the stores failed
+    let samples_written = writer.samples_written() as u64;
+    let unflushed_samples = summary.samples_recorded.saturating_sub(samples_written);
+    let marker = RecoveryMarker {
+        store: config.store_path.clone(),
+        stream: config.stream_name.clone(),
+        failed_step: failed_step.to_string(),
+        error: format!("{:#}", error),
+        samples_recorded: summary.samples_recorded,
+        samples_written,
+        unflushed_samples: if failed_step == "flush" { unflushed_samples } else { 0 },
+        first_timestamp: summary.first_timestamp,
+        last_timestamp: summary.last_timestamp,
+        end_time: Some(time_anchor(lsl::local_clock())),
+        event_counts: writer.event_counts().clone(),
+        connection: Some(summary.connection.clone()),
+        written_at: chrono::Utc::now().to_rfc3339(),
+        recorder_version: env!("CARGO_PKG_VERSION").to_string(),
+    };
+    match write_recovery_marker(&marker) {
+        Ok(path) => {
+            println!("STATUS RECOVERY_MARKER ({})", path.display());
+            std::io::stdout().flush().ok();
+            eprintln!(
+                "Recording summary saved to {}; run lsl-repair on {} once the store is writable",
+                path.display(),
+                config.store_path.display()
+            );
+        }
+        Err(e) => eprintln!("Warning: {:#}", e),
+    }
+    Err(error.context(format!("Failed to {} the recording", failed_step)))
+}
+
+/// Finish the current part of a rotated recording and open part `part`
+///
+/// `config` is switched to the new part's paths. Finishing the old part can fail
+/// like the end of a recording (see [`finish_writer`]) without stopping the recording.
+fn rotate_part(
+    writer: &mut ZarrWriter,
+    config: &mut ZarrConfig,
+    part: u32,
+    summary: &RecordingSummary,
+    info: &mut lsl::StreamInfo,
+    inl: &lsl::StreamInlet,
+    params: &RecordingParams,
+) -> Result<ZarrWriter> {
+    let base = params.zarr_config.as_ref().context("rotation needs a store")?;
+    if let Err(e) = finish_writer(writer, config, summary) {
+        eprintln!("Warning: {:#}", e);
+    }
+    if let Err(e) = apply_store_permissions(config) {
+        eprintln!("Warning: {:#}", e);
+    }
+
+    let previous = config.clone();
+    config.store_path = part_path(&base.store_path, part);
+    config.mirror_path = base.mirror_path.as_deref().map(|mirror| part_path(mirror, part));
+    config.remote_url = base.remote_url.as_deref().map(|url| part_name(url, part));
+    // METADATA updates made during the previous part carry over
+    if let Some(manifest) = read_manifest(&previous.store_path) {
+        config.subject = manifest.subject;
+        config.session_id = manifest.session_id;
+        config.notes = manifest.notes;
+    }
+    let next = initialize_zarr_writer(config, info, inl, &params.recording_config, params.recorder_args, true)?
+        .context("no writer for the next part")?;
+
+    let rotation = params.recorder_args.rotation_options();
+    let links = [
+        (Some(&base.store_path), Some(&previous.store_path), Some(&config.store_path)),
+        (base.mirror_path.as_ref(), previous.mirror_path.as_ref(), config.mirror_path.as_ref()),
+    ];
+    for (first, old, new) in links {
+        if let (Some(first), Some(old), Some(new)) = (first, old, new)
+            && let Err(e) = mark_part(new, first, part, Some(old), &rotation).and_then(|_| link_next(old, new))
+        {
+            eprintln!("Warning: {:#}", e);
+        }
+    }
+
+    println!("STATUS ROTATED (part {}: {})", part, config.store_path.display());
+    std::io::stdout().flush().ok();
+    Ok(next)
+}
+
+/// Store a finished `--blocks` block; a failure only costs the annotation
+fn store_block(writer: Option<&ZarrWriter>, block: &Block) {
+    if let Some(writer) = writer
+        && let Err(e) = writer.record_block(block)
+    {
+        eprintln!("Warning: {:#}", e);
+    }
+}
+
+/// Store the interval between PAUSE and RESUME; a failure only costs the annotation
+fn store_pause(writer: Option<&ZarrWriter>, pause: (f64, f64)) {
+    if let Some(writer) = writer
+        && let Err(e) = writer.record_pause(pause)
+    {
+        eprintln!("Warning: {:#}", e);
+    }
+}
+
+/// Report a clock jump to the parent process and store it; a failure only costs the annotation
+fn report_clock_jump(writer: Option<&ZarrWriter>, jump: &ClockJump) {
+    println!("STATUS CLOCK_JUMP ({})", jump);
+    std::io::stdout().flush().ok();
+    if let Some(writer) = writer
+        && let Err(e) = writer.record_clock_jump(jump)
+    {
+        eprintln!("Warning: {:#}", e);
+    }
+}
+
+/// Resolve the stream and pull samples from it for `seconds` (`--preflight`)
+///
+/// Fills in the stream side of the report; nothing is written.
+pub fn preflight_stream(config: &StreamResolutionConfig, seconds: f64, quiet: bool) -> Result<PreflightReport> {
+    let mut connection = ConnectionTracker::new();
+    let res = resolve_lsl_stream_with_retry(
+        &config.strategy,
+        config.timeout,
+        quiet,
+        config.max_retry_attempts,
+        config.retry_base_delay_ms,
+        &mut connection,
+    )?;
+    let inl = lsl::StreamInlet::new(&res[0], INLET_MAX_BUFFER, 0, true)
+        .map_err(|e| anyhow::anyhow!("LSL error: {}", e))?;
+    let info = inl
+        .info(lsl::FOREVER)
+        .map_err(|e| anyhow::anyhow!("LSL error: {}", e))?;
+    let mut buffer = create_sample_buffer(&info)?;
+    let mut report = PreflightReport {
+        stream: info.stream_name(),
+        channels: info.channel_count() as usize,
+        channel_format: format!("{:?}", info.channel_format()),
+        nominal_rate: info.nominal_srate(),
+        seconds,
+        ..Default::default()
+    };
+
+    macro_rules! pull {
+        ($buf:expr) => {{
+            $buf.clear();
+            let ts = inl
+                .pull_sample_buf($buf, PREFLIGHT_PULL_TIMEOUT)
+                .map_err(|e| anyhow::anyhow!("LSL error: {}", e))?;
+            (ts, std::mem::size_of_val(&$buf[..]))
+        }};
+    }
+    let mut value_bytes = 0;
+    let deadline = Instant::now() + Duration::from_secs_f64(seconds);
+    while Instant::now() < deadline {
+        let (ts, bytes) = match &mut buffer {
+            SampleBuffer::Float32(buf) => pull!(buf),
+            SampleBuffer::Float64(buf) => pull!(buf),
+            SampleBuffer::Int32(buf) => pull!(buf),
+            SampleBuffer::Int16(buf) => pull!(buf),
+            SampleBuffer::Int8(buf) => pull!(buf),
+            SampleBuffer::String(_) => {
+                let (sample, ts) = <lsl::StreamInlet as Pullable<String>>::pull_sample(&inl, PREFLIGHT_PULL_TIMEOUT)
+                    .map_err(|e| anyhow::anyhow!("LSL error: {}", e))?;
+                (ts, sample.iter().map(String::len).sum())
+            }
+        };
+        if ts != 0.0 {
+            report.samples += 1;
+            value_bytes += bytes;
+            report.first_timestamp.get_or_insert(ts);
+            report.last_timestamp = Some(ts);
+        }
+    }
+    // The values plus the float64 timestamp
+    report.sample_bytes = value_bytes as f64 / report.samples.max(1) as f64 + 8.0;
+    Ok(report)
+}
+
+/// Channel labels from the stream description (`ch<i>` for unlabeled channels)
+pub fn stream_channel_labels(info: &mut lsl::StreamInfo) -> Vec<String> {
+    let stream_info = serde_json::json!({
+        "channels": parse_channels_to_json(&info.to_xml().unwrap_or_default()),
+    });
+    (0..info.channel_count() as usize)
+        .map(|i| channel_label(&stream_info, i))
+        .collect()
+}
+
+/// Quality checks for a regular numeric stream; other streams are recorded without them
+fn start_quality_monitor(info: &mut lsl::StreamInfo, options: &QualityOptions, quiet: bool) -> Option<QualityMonitor> {
+    let format_clip_level = match info.channel_format() {
+        lsl::ChannelFormat::Int8 => Some(i8::MAX as f64),
+        lsl::ChannelFormat::Int16 => Some(i16::MAX as f64),
+        lsl::ChannelFormat::Int32 => Some(i32::MAX as f64),
+        lsl::ChannelFormat::Float32 | lsl::ChannelFormat::Double64 => None,
+        _ => {
+            eprintln!("Warning: --quality needs a numeric stream; recording without quality checks");
+            return None;
+        }
+    };
+    if info.nominal_srate() <= 0.0 {
+        eprintln!("Warning: --quality needs a regular stream; recording without quality checks");
+        return None;
+    }
+
+    let labels = stream_channel_labels(info);
+    if !quiet {
+        println!(
+            "Quality checks:\t{:.1}s windows, flatline after {:.1}s",
+            options.window, options.flatline_seconds
+        );
+    }
+    Some(QualityMonitor::new(labels, info.nominal_srate(), format_clip_level, options))
+}
+
+/// Report channel problems that started or ended to the parent process
+fn report_quality_events(events: Vec<QualityEvent>) {
+    if events.is_empty() {
+        return;
+    }
+    for event in events {
+        let status = if event.active { "QUALITY_WARNING" } else { "QUALITY_OK" };
+        println!("STATUS {} ({})", status, event);
+    }
+    std::io::stdout().flush().ok();
+}
+
+/// Plausibility check for a numeric stream; other streams are recorded without it
+fn start_plausibility_check(
+    info: &mut lsl::StreamInfo,
+    range: &PlausibleRange,
+    quiet: bool,
+) -> Option<PlausibilityCheck> {
+    let numeric = matches!(
+        info.channel_format(),
+        lsl::ChannelFormat::Int8
+            | lsl::ChannelFormat::Int16
+            | lsl::ChannelFormat::Int32
+            | lsl::ChannelFormat::Float32
+            | lsl::ChannelFormat::Double64
+    );
+    if !numeric {
+        eprintln!("Warning: --plausible-range needs a numeric stream; recording without the check");
+        return None;
+    }
+    let channels = parse_channels_to_json(&info.to_xml().unwrap_or_default());
+    let units: Vec<Option<String>> = (0..info.channel_count() as usize)
+        .map(|i| {
+            channels
+                .get(i)
+                .and_then(|channel| channel.get("unit"))
+                .and_then(|unit| unit.as_str())
+                .map(String::from)
+        })
+        .collect();
+    if !quiet {
+        println!("Plausible range:\t{}", range);
+    }
+    let labels = stream_channel_labels(info);
+    Some(PlausibilityCheck::new(range.clone(), labels, &units))
+}
+
+/// Report channels that left the plausible range for the first time
+fn report_plausibility_events(events: Vec<PlausibilityEvent>) {
+    if events.is_empty() {
+        return;
+    }
+    for event in events {
+        println!("STATUS IMPLAUSIBLE ({})", event);
+    }
+    std::io::stdout().flush().ok();
+}
+
+/// Rule checks for a regular numeric stream; other streams are recorded without them
+fn start_rule_engine(info: &lsl::StreamInfo, rules: &[Rule], quiet: bool) -> Option<RuleEngine> {
+    if rules.is_empty() {
+        return None;
+    }
+    let numeric = matches!(
+        info.channel_format(),
+        lsl::ChannelFormat::Int8
+            | lsl::ChannelFormat::Int16
+            | lsl::ChannelFormat::Int32
+            | lsl::ChannelFormat::Float32
+            | lsl::ChannelFormat::Double64
+    );
+    if !numeric || info.nominal_srate() <= 0.0 {
+        eprintln!("Warning: --rule needs a regular numeric stream; recording without rules");
+        return None;
+    }
+    if !quiet {
+        for rule in rules {
+            println!("Rule:\t\t{}", rule);
+        }
+    }
+    Some(RuleEngine::new(rules.to_vec(), info.channel_count() as usize, info.nominal_srate()))
+}
+
+/// Report rules that triggered or cleared; a `then pause` rule pauses the recording
+/// and resumes it when it clears, unless RESUME or STOP came first
+fn handle_rule_events(events: Vec<RuleEvent>, params: &RecordingParams, rule_paused: &mut bool) {
+    if events.is_empty() {
+        return;
+    }
+    for event in events {
+        let status = if event.triggered { "RULE_TRIGGERED" } else { "RULE_CLEARED" };
+        println!("STATUS {} ({})", status, event);
+        if event.rule.action != RuleAction::Pause {
+            continue;
+        }
+        if event.triggered && params.recording.load(Ordering::SeqCst) {
+            params.recording.store(false, Ordering::SeqCst);
+            params.paused.store(true, Ordering::SeqCst);
+            *rule_paused = true;
+            println!("STATUS PAUSED (rule: {})", event.rule);
+        } else if !event.triggered && *rule_paused && params.paused.load(Ordering::SeqCst) {
+            params.paused.store(false, Ordering::SeqCst);
+            params.recording.store(true, Ordering::SeqCst);
+            *rule_paused = false;
+            println!("STATUS RESUMED (rule cleared: {})", event.rule);
+        }
+    }
+    std::io::stdout().flush().ok();
+}
+
+/// Pull one sample while a rule holds the recording paused; it only goes to the rules
+fn pull_for_rules(
+    inl: &lsl::StreamInlet,
+    buffer: &mut SampleBuffer,
+    rules: &mut RuleEngine,
+    timeout: f64,
+) -> std::result::Result<Vec<RuleEvent>, lsl::Error> {
+    macro_rules! pull {
+        ($buf:expr) => {{
+            $buf.clear();
+            let ts = inl.pull_sample_buf($buf, timeout)?;
+            if ts == 0.0 { Vec::new() } else { rules.push(ts, &$buf) }
+        }};
+    }
+    Ok(match buffer {
+        SampleBuffer::Float32(buf) => pull!(buf),
+        SampleBuffer::Float64(buf) => pull!(buf),
+        SampleBuffer::Int32(buf) => pull!(buf),
+        SampleBuffer::Int16(buf) => pull!(buf),
+        SampleBuffer::Int8(buf) => pull!(buf),
+        SampleBuffer::String(_) => Vec::new(),
+    })
+}
+
+/// Apply the inlet post-processing every recording uses
+pub(crate) fn set_postprocessing(inlet: &lsl::StreamInlet) -> Result<()> {
+    inlet
+        .set_postprocessing(&[
+            lsl::ProcessingOption::ClockSync,
+            lsl::ProcessingOption::Dejitter,
+            lsl::ProcessingOption::Monotonize,
+            // lsl::ProcessingOption::Threadsafe,
+        ])
+        .map_err(|e| anyhow::anyhow!("LSL error: {}", e))
+}
+
+/// Resolve a lost stream anew and open a fresh inlet to it (`--reconnect-after`)
+///
+/// Only a stream with the recorded stream's source ID (if its outlet set one), channel
+/// count and format is taken; `None` while there is none on the network.
+fn reconnect_inlet(info: &lsl::StreamInfo, params: &RecordingParams) -> Result<Option<lsl::StreamInlet>> {
+    let source_id = info.source_id();
+    let format = format!("{:?}", info.channel_format());
+    let timeout = params.resolution_config.timeout;
+    // The strategy returns the first match only, which may be another stream of the same name or type
+    let streams = if source_id.is_empty() {
+        params.resolution_config.strategy.resolve(timeout)
+    } else {
+        lsl::resolve_byprop("source_id", &source_id, 1, timeout)
+    }
+    .map_err(|e| anyhow::anyhow!("LSL error: {}", e))?;
+    let Some(stream) = streams.iter().find(|stream| {
+        (source_id.is_empty() || stream.source_id() == source_id)
+            && stream.channel_count() == info.channel_count()
+            && format!("{:?}", stream.channel_format()) == format
+    }) else {
+        return Ok(None);
+    };
+    let inlet = lsl::StreamInlet::new(stream, INLET_MAX_BUFFER, 0, true)
+        .map_err(|e| anyhow::anyhow!("LSL error: {}", e))?;
+    set_postprocessing(&inlet)?;
+    Ok(Some(inlet))
+}
+
+/// Apply --chmod/--chgrp to the store and its mirror
+///
+/// The mirror may have failed during recording, so problems there are only reported.
+pub(crate) fn apply_store_permissions(config: &ZarrConfig) -> Result<()> {
+    apply_permissions(&config.store_path, &config.permissions)?;
+    if let Some(ref mirror_path) = config.mirror_path
+        && let Err(e) = apply_permissions(mirror_path, &config.permissions)
+    {
+        eprintln!("Warning: could not set permissions on the mirror: {:#}", e);
+    }
+    Ok(())
+}
+
+/// Apply --chmod/--chgrp to a store as the writer is set up
+///
+/// Entries created by other recorders writing to the same store cannot be changed
+/// by this one, so problems are only reported; only a store root this recorder
+/// created must take the permissions.
+fn apply_setup_permissions(path: &Path, permissions: &StorePermissions, created: bool) -> Result<()> {
+    if created {
+        apply_root_permissions(path, permissions)?;
+    }
+    if let Err(e) = apply_permissions(path, permissions) {
+        eprintln!("Warning: could not set permissions on {}: {:#}", path.display(), e);
+    }
+    Ok(())
+}
+
+/// Report resolution retries and outages, if there were any
+fn print_connection_summary(stats: &ConnectionStats) {
+    if stats.resolution_attempts > 1 {
+        println!(
+            "Resolution:\t{} attempts ({} ms waited between them)",
+            stats.resolution_attempts,
+            stats.total_retry_delay_ms()
+        );
+    }
+    if stats.outages > 0 {
+        println!(
+            "Outages:\t{} ({} reconnected), {:.3} s downtime, longest {:.3} s",
+            stats.outages, stats.reconnections, stats.total_downtime_s, stats.longest_outage_s
+        );
+    }
+    if stats.reconnect_attempts > 0 {
+        println!("Re-resolved:\t{} time(s) after --reconnect-after", stats.reconnect_attempts);
+    }
+}
+
+/// Report a loss found by the dropped-sample estimate
+fn report_dropped_samples(estimate: &DropEstimate) {
+    println!(
+        "STATUS DROPPED_SAMPLES (~{} of {} expected, {:.2}%)",
+        estimate.dropped,
+        estimate.expected,
+        estimate.percent()
+    );
+    std::io::stdout().flush().ok();
+}
+
 /// Configuration for recording behavior (buffering and flushing)
 #[derive(Debug, Clone)]
 pub struct RecordingConfig {
     pub flush_interval: Duration,
     pub flush_buffer_size: usize,
     pub immediate_flush: bool,
+    /// Maximum allowed ratio of measured to nominal sample rate (0 disables the guard)
+    pub max_rate_factor: f64,
+    /// Warn instead of aborting when the rate guard trips
+    pub allow_rate_overrun: bool,
+    /// Silence after which a regular stream is reported lost (zero disables the report)
+    pub lost_timeout: Duration,
+    /// Outage after which the stream is resolved anew and the inlet replaced (zero disables it)
+    pub reconnect_after: Duration,
+    /// Nominal rate (Hz) from which numeric streams are pulled in chunks (0 disables it)
+    pub chunk_pull_rate: f64,
+    /// Store flat, noisy and mostly non-finite channels as `bad_channels` when recording ends
+    pub detect_bad_channels: bool,
+    /// Write the per-sample `flags` array (see [`crate::flags`])
+    pub sample_flags: bool,
 }
 
 impl Default for RecordingConfig {
@@ -255,6 +1379,13 @@ impl Default for RecordingConfig {
             flush_interval: Duration::from_secs(1),
             flush_buffer_size: 50,
             immediate_flush: false,
+            max_rate_factor: 4.0,
+            allow_rate_overrun: false,
+            lost_timeout: Duration::from_secs(2),
+            reconnect_after: Duration::from_secs(5),
+            chunk_pull_rate: DEFAULT_CHUNK_PULL_RATE,
+            detect_bad_channels: false,
+            sample_flags: false,
         }
     }
 }
@@ -267,11 +1398,65 @@ pub struct ZarrConfig {
     pub subject: Option<String>,
     pub session_id: Option<String>,
     pub notes: Option<String>,
+    pub storage: StorageOptions,
+    /// Secondary store written in parallel with the primary one
+    pub mirror_path: Option<PathBuf>,
+    /// Object storage URL the store is uploaded to; `store_path` is then the local spool
+    pub remote_url: Option<String>,
+    /// Mode and group applied to the store (and mirror) when opened and when recording ends
+    pub permissions: StorePermissions,
+}
+
+/// How the stream to record is looked up on the network
+#[derive(Debug, Clone, PartialEq)]
+pub enum ResolutionStrategy {
+    /// Match the `source_id` property (stable across device restarts)
+    SourceId(String),
+    /// Match the stream `name` property
+    Name(String),
+    /// Match the stream `type` property (first match wins)
+    Type(String),
+    /// Arbitrary LSL XPath predicate, e.g. `name='EEG' and hostname='lab-pc'`
+    Predicate(String),
+}
+
+impl ResolutionStrategy {
+    /// Run a single resolve query for this strategy
+    fn resolve(&self, timeout: f64) -> std::result::Result<Vec<lsl::StreamInfo>, lsl::Error> {
+        match self {
+            ResolutionStrategy::SourceId(id) => lsl::resolve_byprop("source_id", id, 1, timeout),
+            ResolutionStrategy::Name(name) => lsl::resolve_byprop("name", name, 1, timeout),
+            ResolutionStrategy::Type(stream_type) => lsl::resolve_byprop("type", stream_type, 1, timeout),
+            ResolutionStrategy::Predicate(pred) => lsl::resolve_bypred(pred, 1, timeout),
+        }
+    }
+
+    /// The value being matched (source ID, name, type or predicate)
+    pub fn value(&self) -> &str {
+        match self {
+            ResolutionStrategy::SourceId(v)
+            | ResolutionStrategy::Name(v)
+            | ResolutionStrategy::Type(v)
+            | ResolutionStrategy::Predicate(v) => v,
+        }
+    }
+}
+
+impl std::fmt::Display for ResolutionStrategy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ResolutionStrategy::SourceId(id) => write!(f, "source_id={}", id),
+            ResolutionStrategy::Name(name) => write!(f, "name={}", name),
+            ResolutionStrategy::Type(stream_type) => write!(f, "type={}", stream_type),
+            ResolutionStrategy::Predicate(pred) => write!(f, "predicate \"{}\"", pred),
+        }
+    }
 }
 
 /// Stream resolution and retry configuration
 #[derive(Debug, Clone)]
 pub struct StreamResolutionConfig {
+    pub strategy: ResolutionStrategy,
     pub timeout: f64,
     pub max_retry_attempts: u32,
     pub retry_base_delay_ms: u64,
@@ -281,6 +1466,7 @@ pub struct StreamResolutionConfig {
 impl Default for StreamResolutionConfig {
     fn default() -> Self {
         Self {
+            strategy: ResolutionStrategy::SourceId("1234".to_string()),
             timeout: 5.0,
             max_retry_attempts: 3,
             retry_base_delay_ms: 100,
@@ -294,6 +1480,8 @@ pub struct RecordingParams<'a> {
     pub source_id: &'a str,
     pub recording: Arc<AtomicBool>,
     pub quit: Arc<AtomicBool>,
+    /// Set between PAUSE and RESUME (see [`crate::pauses`])
+    pub paused: Arc<AtomicBool>,
     pub first_sample_pulled: Arc<AtomicBool>,
     pub is_irregular_stream: Arc<AtomicBool>,
     pub quiet: bool,
@@ -301,6 +1489,10 @@ pub struct RecordingParams<'a> {
     pub recording_config: RecordingConfig,
     pub resolution_config: StreamResolutionConfig,
     pub recorder_args: &'a Args,
+    /// Block boundaries from the `--blocks` scheduler
+    pub block_events: Option<mpsc::Receiver<BlockEvent>>,
+    /// Progress callbacks for an embedding application (see [`crate::observer`])
+    pub observer: Option<Box<dyn RecordingObserver>>,
 }
 
 /// Sample buffer for different LSL channel formats
@@ -313,6 +1505,65 @@ pub enum SampleBuffer {
     String(Vec<String>),
 }
 
+/// Samples pulled ahead in chunks, typed like the stream's [`SampleBuffer`]
+enum SampleQueue {
+    Float32(ChunkQueue<f32>),
+    Float64(ChunkQueue<f64>),
+    Int32(ChunkQueue<i32>),
+    Int16(ChunkQueue<i16>),
+    Int8(ChunkQueue<i8>),
+}
+
+impl SampleQueue {
+    fn chunk_stats(&self) -> (u64, f64, usize) {
+        match self {
+            SampleQueue::Float32(queue) => queue.chunk_stats(),
+            SampleQueue::Float64(queue) => queue.chunk_stats(),
+            SampleQueue::Int32(queue) => queue.chunk_stats(),
+            SampleQueue::Int16(queue) => queue.chunk_stats(),
+            SampleQueue::Int8(queue) => queue.chunk_stats(),
+        }
+    }
+}
+
+/// Chunk queue for the stream's sample buffer; `None` for string streams, which are
+/// low-rate markers and pulled sample by sample
+fn create_sample_queue(buffer: &SampleBuffer) -> Option<SampleQueue> {
+    match buffer {
+        SampleBuffer::Float32(_) => Some(SampleQueue::Float32(ChunkQueue::new())),
+        SampleBuffer::Float64(_) => Some(SampleQueue::Float64(ChunkQueue::new())),
+        SampleBuffer::Int32(_) => Some(SampleQueue::Int32(ChunkQueue::new())),
+        SampleBuffer::Int16(_) => Some(SampleQueue::Int16(ChunkQueue::new())),
+        SampleBuffer::Int8(_) => Some(SampleQueue::Int8(ChunkQueue::new())),
+        SampleBuffer::String(_) => None,
+    }
+}
+
+/// Pull the next sample of a chunk-pulled stream into `buf` (`--chunk-pull-rate`)
+///
+/// Queued samples are handed out first. Once the queue is empty the call blocks for
+/// one sample like a sample-wise pull, then takes whatever else the inlet has
+/// buffered in one chunk, into the queue's reused buffers. Returns 0.0 when the
+/// pull timed out.
+fn pull_queued<T: Copy>(
+    inlet: &lsl::StreamInlet,
+    queue: &mut ChunkQueue<T>,
+    buf: &mut Vec<T>,
+    timeout: f64,
+) -> std::result::Result<f64, lsl::Error>
+where
+    lsl::StreamInlet: Pullable<T> + ExPullable<T>,
+{
+    if let Some(ts) = queue.pop_into(buf) {
+        return Ok(ts);
+    }
+    let ts = inlet.pull_sample_buf(buf, timeout)?;
+    if ts != 0.0 {
+        queue.refill(|data, timestamps| inlet.pull_chunk_buf(data, timestamps).map(|_| ()))?;
+    }
+    Ok(ts)
+}
+
 /// Calculate optimal pull timeout based on stream sample rate
 fn calculate_pull_timeout(
     info: &lsl::StreamInfo,
@@ -348,7 +1599,7 @@ fn calculate_pull_timeout(
 }
 
 /// Create sample buffer appropriate for the stream's channel format
-fn create_sample_buffer(info: &lsl::StreamInfo) -> Result<SampleBuffer> {
+pub(crate) fn create_sample_buffer(info: &lsl::StreamInfo) -> Result<SampleBuffer> {
     let channel_count = info.channel_count() as usize;
     let channel_format = info.channel_format();
 
@@ -370,52 +1621,73 @@ fn create_sample_buffer(info: &lsl::StreamInfo) -> Result<SampleBuffer> {
     Ok(buffer)
 }
 
-/// Helper for monitoring memory usage during recording
-struct MemoryMonitor {
-    last_report: Option<Instant>,
+/// Re-fetches the full stream info on its own thread (`--info-refresh`)
+///
+/// An inlet keeps the info it fetched first, so each refresh resolves the stream and
+/// reads the info through a short-lived second inlet. The recording inlet is never
+/// blocked by a slow or unreachable outlet.
+struct InfoRefresher {
+    receiver: mpsc::Receiver<(f64, serde_json::Value)>,
+    stop: Arc<AtomicBool>,
 }
 
-impl MemoryMonitor {
-    fn new(enabled: bool) -> Self {
-        Self {
-            last_report: if enabled { Some(Instant::now()) } else { None },
-        }
+impl InfoRefresher {
+    /// Fetch the info of the stream matching `predicate` every `interval`
+    fn start(predicate: String, interval: Duration) -> Self {
+        let (sender, receiver) = mpsc::channel();
+        let stop = Arc::new(AtomicBool::new(false));
+        let stop_flag = stop.clone();
+        thread::spawn(move || {
+            let mut next = Instant::now() + interval;
+            while !stop_flag.load(Ordering::SeqCst) {
+                if Instant::now() < next {
+                    thread::sleep(Duration::from_millis(100));
+                    continue;
+                }
+                next = Instant::now() + interval;
+                match fetch_stream_info(&predicate) {
+                    Ok(Some(info)) => {
+                        if sender.send((lsl::local_clock(), info)).is_err() {
+                            break;
+                        }
+                    }
+                    // Outlet not visible right now; STREAM_LOST reports the outage
+                    Ok(None) => {}
+                    Err(e) => eprintln!("Warning: stream info refresh failed: {:#}", e),
+                }
+            }
+        });
+        Self { receiver, stop }
     }
 
-    fn maybe_report(
-        &mut self,
-        sample_count: u64,
-        zarr_writer: &Option<ZarrWriter>,
-        quiet: bool,
-    ) {
-        if let Some(ref mut last_report) = self.last_report {
-            if last_report.elapsed() >= Duration::from_secs(10) {
-                let buffer_samples = if let Some(writer) = zarr_writer {
-                    writer.buffer_sample_count()
-                } else {
-                    0
-                };
+    /// The latest fetched info and the LSL clock at the fetch, if a new one arrived
+    fn try_recv(&self) -> Option<(f64, serde_json::Value)> {
+        self.receiver.try_recv().ok()
+    }
+}
 
-                println!(
-                    "Memory status:\t{} samples recorded, {} buffered samples, buffer usage: {:.1}%",
-                    sample_count,
-                    buffer_samples,
-                    if let Some(writer) = zarr_writer {
-                        (buffer_samples as f64 / writer.buffer_capacity() as f64) * 100.0
-                    } else {
-                        0.0
-                    }
-                );
-                *last_report = Instant::now();
-            }
-        } else if !quiet && sample_count.is_multiple_of(100) {
-            println!("Recorded {} samples", sample_count);
-        }
+impl Drop for InfoRefresher {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::SeqCst);
     }
 }
 
+/// Resolve the stream matching `predicate` and serialize its current full info
+fn fetch_stream_info(predicate: &str) -> Result<Option<serde_json::Value>> {
+    let found = lsl::resolve_bypred(predicate, 1, INFO_REFRESH_TIMEOUT)
+        .map_err(|e| anyhow::anyhow!("LSL error: {}", e))?;
+    let Some(stream) = found.first() else {
+        return Ok(None);
+    };
+    let inlet = lsl::StreamInlet::new(stream, 1, 0, false).map_err(|e| anyhow::anyhow!("LSL error: {}", e))?;
+    let mut info = inlet
+        .info(INFO_REFRESH_TIMEOUT)
+        .map_err(|e| anyhow::anyhow!("LSL error: {}", e))?;
+    serialize_stream_info(&mut info).map(Some)
+}
+
 /// Initialize Zarr writer with all necessary configuration
-fn initialize_zarr_writer(
+pub(crate) fn initialize_zarr_writer(
     config: &ZarrConfig,
     info: &mut lsl::StreamInfo,
     inl: &lsl::StreamInlet,
@@ -428,6 +1700,8 @@ fn initialize_zarr_writer(
         println!("Stream group: {}", config.stream_name);
     }
 
+    // Entries of a store shared with other recorders belong to them
+    let created = !config.store_path.exists();
     let store = open_or_create_zarr_store(
         &config.store_path,
         config.subject.as_deref(),
@@ -449,11 +1723,72 @@ fn initialize_zarr_writer(
         &store,
         &config.stream_name,
         info,
-        channel_format,
         &recorder_config_json,
         time_correction,
         None, // first_timestamp will be updated after first sample
+        &config.storage,
     )?;
+    let flags_array = recording_config
+        .sample_flags
+        .then(|| setup_flags_array(&store, &config.stream_name, &config.storage))
+        .transpose()?;
+
+    // Device information is only informational; a failure must not stop recording
+    if let Err(e) = update_devices_table(&config.store_path) {
+        eprintln!("Warning: could not update the device table: {:#}", e);
+    }
+    if let Err(e) = update_manifest_streams(&config.store_path) {
+        eprintln!("Warning: could not update the session manifest: {:#}", e);
+    }
+    apply_setup_permissions(&config.store_path, &config.permissions, created)?;
+
+    // The mirror gets the same arrays and metadata as the primary store
+    let mirror = match config.mirror_path {
+        Some(ref mirror_path) => {
+            let mirror_created = !mirror_path.exists();
+            let mirror_store = open_or_create_zarr_store(
+                mirror_path,
+                config.subject.as_deref(),
+                config.session_id.as_deref(),
+                config.notes.as_deref(),
+            )?;
+            let (data_array, time_array) = setup_stream_arrays(
+                &mirror_store,
+                &config.stream_name,
+                info,
+                &recorder_config_json,
+                time_correction,
+                None,
+                &config.storage,
+            )?;
+            let flags_array = recording_config
+                .sample_flags
+                .then(|| setup_flags_array(&mirror_store, &config.stream_name, &config.storage))
+                .transpose()?;
+            update_devices_table(mirror_path).ok();
+            update_manifest_streams(mirror_path).ok();
+            apply_setup_permissions(mirror_path, &config.permissions, mirror_created)?;
+            Some(MirrorConfig {
+                data_array,
+                time_array,
+                flags_array,
+                store_path: mirror_path.clone(),
+                store: mirror_store,
+            })
+        }
+        None => None,
+    };
+
+    let remote = config
+        .remote_url
+        .as_deref()
+        .map(|url| RemoteUploader::start(url, &config.store_path, Some(&config.stream_name)))
+        .transpose()?;
+    if let Some(ref remote) = remote
+        && !quiet
+    {
+        println!("Uploading to {} (spool: {})", remote.url(), config.store_path.display());
+    }
 
     let buffer_size = if recording_config.immediate_flush {
         1
@@ -483,10 +1818,20 @@ fn initialize_zarr_writer(
         data_array,
         time_array,
         buffer_size,
+        // String arrays are never sharded (see setup_stream_arrays)
+        shard_samples: config
+            .storage
+            .shard_samples
+            .filter(|_| !matches!(channel_format, lsl::ChannelFormat::String))
+            .map(|s| s as usize),
         channel_format,
         flush_interval: recording_config.flush_interval,
         store_path: config.store_path.clone(),
         store,
         stream_name: config.stream_name.clone(),
+        mirror,
+        detect_bad_channels: recording_config.detect_bad_channels,
+        remote,
+        flags_array,
     })?))
 }
@@ -1,13 +1,38 @@
-//! LSL Toolbox - TUI launcher for the LSL Recording Toolbox
+//! LSL Toolbox - TUI launcher and multicall binary for the LSL Recording Toolbox
 //!
 //! This is the main entry point when running `cargo run`. It provides a terminal
 //! user interface to select and run the various LSL tools in the toolbox.
 //! Supports multiple concurrent tools running in separate tabs.
+//!
+//! The main tools are built into this executable as subcommands, so one file is
+//! enough to deploy a recording machine. Each subcommand takes the same arguments
+//! as the corresponding standalone executable.
+//!
+//! # Subcommands
+//!
+//! ```bash
+//! # Launch the TUI (no subcommand, or `tui`)
+//! lsl-toolbox
+//!
+//! # Run a tool: record | multi | sync | inspect | validate | replay | dummy
+//! lsl-toolbox record --source-id "EMG_1234" --output experiment
+//! lsl-toolbox multi --record-all --output experiment
+//! lsl-toolbox inspect experiment.zarr
+//!
+//! # Print versions, codecs, LSL network config and a write-speed test
+//! lsl-toolbox doctor --dir /data/recordings
+//!
+//! # Enable the local usage log, then summarize it
+//! lsl-toolbox stats --enable
+//! lsl-toolbox stats
+//! ```
 
 use std::io;
 use std::panic;
+use std::path::PathBuf;
 
 use anyhow::Result;
+use clap::{Parser, Subcommand};
 use crossterm::{
     cursor::{Hide, Show},
     execute,
@@ -15,16 +40,20 @@ use crossterm::{
 };
 use ratatui::{backend::CrosstermBackend, Terminal};
 
+use lsl_recording_toolbox::{config, tools, usage};
+
 mod tui;
 
 use crossterm::event::KeyCode;
 use tui::{
-    app::TOOLS,
+    app::{tool_index, EXPLORER_TOOL_INDEX, TOOLS},
     events::{
-        is_backspace, is_ctrl_c, is_ctrl_enter, is_ctrl_r, is_delete, is_down, is_end, is_enter,
+        is_backspace, is_ctrl_c, is_ctrl_enter, is_ctrl_p, is_ctrl_r, is_ctrl_s, is_delete, is_down, is_end, is_enter,
         is_esc, is_home, is_left, is_page_down, is_page_up, is_right, is_shift_tab, is_space,
         is_tab, is_up, Event, EventHandler,
     },
+    explorer::ExplorerState,
+    native_dialog,
     process::{ProcessEvent, ProcessManager},
     tab::TabMode,
     tool_config,
@@ -32,10 +61,123 @@ use tui::{
     App,
 };
 
+#[derive(Parser)]
+#[command(name = "lsl-toolbox")]
+#[command(about = "LSL Recording Toolbox launcher (TUI when run without a subcommand)")]
+#[command(version)]
+struct Cli {
+    #[command(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Launch the terminal UI (default)
+    Tui,
+
+    /// Record a single LSL stream (lsl-recorder)
+    #[cfg(feature = "lsl")]
+    Record(Box<lsl_recording_toolbox::cli::Args>),
+
+    /// Record multiple LSL streams into one store (lsl-multi-recorder)
+    #[cfg(feature = "lsl")]
+    Multi(Box<tools::multi::Args>),
+
+    /// Synchronize timestamps across streams (lsl-sync)
+    Sync(Box<tools::sync::Args>),
+
+    /// Inspect a recording (lsl-inspect)
+    Inspect(Box<tools::inspect::Args>),
+
+    /// Check the synchronization quality of a recording (lsl-validate)
+    Validate(Box<tools::validate::Args>),
+
+    /// Replay recorded streams as LSL outlets (lsl-replay)
+    #[cfg(feature = "lsl")]
+    Replay(Box<tools::replay::Args>),
+
+    /// Generate test LSL streams (lsl-dummy-stream)
+    #[cfg(feature = "lsl")]
+    Dummy(Box<tools::dummy::Args>),
+
+    /// Print a version/compatibility report for troubleshooting
+    Doctor {
+        /// Directory to test (write speed, codecs, free space)
+        #[arg(long, default_value = ".")]
+        dir: PathBuf,
+
+        /// Size of the write-speed test in MiB (0 to skip)
+        #[arg(long, default_value = "64")]
+        write_test_mb: usize,
+
+        /// Skip multicast and stream resolution checks
+        #[arg(long)]
+        no_network: bool,
+
+        /// Timeout for LSL resolution checks in seconds
+        #[arg(long, default_value = "2.0")]
+        resolve_timeout: f64,
+    },
+
+    /// Summarize the local usage log, or turn it on and off (nothing leaves the machine)
+    Stats {
+        /// Start logging tool runs
+        #[arg(long, conflicts_with = "disable")]
+        enable: bool,
+
+        /// Stop logging tool runs (the log is kept)
+        #[arg(long)]
+        disable: bool,
+
+        /// Delete the log
+        #[arg(long)]
+        clear: bool,
+
+        /// Print the summary as JSON
+        #[arg(long)]
+        json: bool,
+    },
+}
+
 fn main() -> Result<()> {
+    // Tool subcommands take the project configuration's defaults of the tool they run
+    let subcommands: Vec<(&str, &str)> = TOOLS
+        .iter()
+        .filter_map(|tool| tool.subcommand.map(|subcommand| (subcommand, tool.binary)))
+        .collect();
+    let cli: Cli = config::parse_subcommand_args(&subcommands)?;
+
+    // Tool subcommands print their own license notice
+    match cli.command {
+        #[cfg(feature = "lsl")]
+        Some(Command::Record(args)) => return usage::track("lsl-recorder", || tools::record::run(*args)),
+        #[cfg(feature = "lsl")]
+        Some(Command::Multi(args)) => return usage::track("lsl-multi-recorder", || tools::multi::run(*args)),
+        Some(Command::Sync(args)) => return usage::track("lsl-sync", || tools::sync::run(*args)),
+        Some(Command::Inspect(args)) => return usage::track("lsl-inspect", || tools::inspect::run(*args)),
+        Some(Command::Validate(args)) => return usage::track("lsl-validate", || tools::validate::run(*args)),
+        #[cfg(feature = "lsl")]
+        Some(Command::Replay(args)) => return usage::track("lsl-replay", || tools::replay::run(*args)),
+        #[cfg(feature = "lsl")]
+        Some(Command::Dummy(args)) => return usage::track("lsl-dummy-stream", || tools::dummy::run(*args)),
+        Some(Command::Stats { enable, disable, clear, json }) => {
+            return usage::run_stats(&usage::StatsOptions { enable, disable, clear, json });
+        }
+        _ => {}
+    }
+
     // Display license notice before entering TUI
     lsl_recording_toolbox::display_license_notice("lsl-toolbox");
 
+    if let Some(Command::Doctor { dir, write_test_mb, no_network, resolve_timeout }) = cli.command {
+        return lsl_recording_toolbox::doctor::run_doctor(&lsl_recording_toolbox::doctor::DoctorOptions {
+            target_dir: dir,
+            write_test_mb,
+            network_checks: !no_network,
+            resolve_timeout,
+        });
+    }
+
     // Setup panic hook to restore terminal on panic
     let original_hook = panic::take_hook();
     panic::set_hook(Box::new(move |panic_info| {
@@ -161,6 +303,33 @@ fn run_app(terminal: &mut Terminal<CrosstermBackend<io::Stdout>>) -> Result<()>
                     continue;
                 }
 
+                // Handle preset picker / save dialog (high priority)
+                if let Some(ref dialog) = app.preset_dialog {
+                    if dialog.name_input.is_some() {
+                        if is_enter(&key) {
+                            app.save_preset();
+                        } else if is_esc(&key) {
+                            app.close_preset_dialog();
+                        } else if is_backspace(&key) {
+                            app.preset_name_backspace();
+                        } else if let KeyCode::Char(c) = key.code {
+                            app.preset_name_insert(c);
+                        }
+                    } else if is_enter(&key) {
+                        app.load_selected_preset();
+                    } else if is_esc(&key) {
+                        app.close_preset_dialog();
+                    } else if is_up(&key) {
+                        app.preset_select(-1);
+                    } else if is_down(&key) {
+                        app.preset_select(1);
+                    } else if is_delete(&key) {
+                        app.delete_selected_preset();
+                    }
+                    needs_full_redraw = true;
+                    continue;
+                }
+
                 // Handle rename dialog (high priority)
                 if app.is_renaming() {
                     if is_enter(&key) {
@@ -217,11 +386,18 @@ fn run_app(terminal: &mut Terminal<CrosstermBackend<io::Stdout>>) -> Result<()>
                         // Create new tab for selected tool
                         app.create_tab_from_menu();
                         needs_full_redraw = true;
+                    } else if native_dialog::AVAILABLE && key.code == KeyCode::Char('d') {
+                        app.toggle_file_dialog_mode();
+                    } else if matches!(key.code, KeyCode::Char('w') | KeyCode::Char('W')) {
+                        // Experiment wizard: record, then sync and validate the store
+                        app.start_wizard();
+                        needs_full_redraw = true;
                     }
                 } else {
                     // Tab mode - we have an active tab
                     // Track if we need a redraw after this event
                     let mut mode_changed = false;
+                    let native_dialogs = app.uses_native_dialogs();
 
                     // Get active tab for mode-specific handling
                     if let Some(tab) = app.active_tab_mut() {
@@ -231,6 +407,13 @@ fn run_app(terminal: &mut Terminal<CrosstermBackend<io::Stdout>>) -> Result<()>
                                     // Ctrl+Enter runs the tool from anywhere in the form
                                     if let Some(ref mut form) = tab.form_state {
                                         match form.validate() {
+                                            Ok(()) if tab.tool_index == EXPLORER_TOOL_INDEX => {
+                                                // The explorer runs in the tab itself, no process
+                                                let explorer =
+                                                    ExplorerState::new(form.get_value("file_path").unwrap_or_default());
+                                                tab.start_exploring(explorer);
+                                                mode_changed = true;
+                                            }
                                             Ok(()) => {
                                                 let tool = &TOOLS[tab.tool_index];
                                                 let args = tool_config::form_to_args(form);
@@ -260,6 +443,12 @@ fn run_app(terminal: &mut Terminal<CrosstermBackend<io::Stdout>>) -> Result<()>
                                             }
                                         }
                                     }
+                                } else if is_ctrl_s(&key) {
+                                    app.open_preset_dialog(true);
+                                    mode_changed = true;
+                                } else if is_ctrl_p(&key) {
+                                    app.open_preset_dialog(false);
+                                    mode_changed = true;
                                 } else if is_esc(&key) {
                                     // Close tab or return to menu if only tab
                                     if app.tabs.len() == 1 {
@@ -317,8 +506,18 @@ fn run_app(terminal: &mut Terminal<CrosstermBackend<io::Stdout>>) -> Result<()>
                                         if field.is_path_field() {
                                             let current_value = field.value.clone();
                                             let select_dir = field.selects_directory();
-                                            let field_idx = form.active_field_idx;
-                                            app.open_file_browser(&current_value, select_dir, field_idx);
+                                            if native_dialogs {
+                                                // Blocks until the dialog closes; cancelling keeps the value
+                                                if let Some(path) = native_dialog::pick_path(&current_value, select_dir)
+                                                    && let Some(field) = form.active_field_mut()
+                                                {
+                                                    field.value = path.to_string_lossy().to_string();
+                                                    field.cursor_pos = field.value.len();
+                                                }
+                                            } else {
+                                                let field_idx = form.active_field_idx;
+                                                app.open_file_browser(&current_value, select_dir, field_idx);
+                                            }
                                             needs_full_redraw = true;
                                         } else if !field.accepts_text_input() {
                                             form.toggle_or_cycle();
@@ -331,6 +530,41 @@ fn run_app(terminal: &mut Terminal<CrosstermBackend<io::Stdout>>) -> Result<()>
                                     }
                                 }
                             }
+                            TabMode::Explore => {
+                                let Some(ref mut explorer) = tab.explorer else { continue };
+                                if is_esc(&key) {
+                                    app.request_close_active_tab();
+                                    mode_changed = true;
+                                } else if is_up(&key) {
+                                    explorer.select_previous();
+                                } else if is_down(&key) {
+                                    explorer.select_next();
+                                } else if is_right(&key) || is_enter(&key) {
+                                    explorer.expand_selected();
+                                } else if is_left(&key) {
+                                    explorer.collapse_selected();
+                                } else if is_page_up(&key) {
+                                    explorer.scroll_details(-10);
+                                } else if is_page_down(&key) {
+                                    explorer.scroll_details(10);
+                                } else if let KeyCode::Char(c) = key.code {
+                                    // Launch sync or validate on the explored store in a new tab
+                                    let binary = match c.to_ascii_lowercase() {
+                                        's' => Some("lsl-sync"),
+                                        'v' => Some("lsl-validate"),
+                                        'r' => {
+                                            explorer.refresh();
+                                            None
+                                        }
+                                        _ => None,
+                                    };
+                                    let store_path = explorer.store_path.to_string_lossy().to_string();
+                                    if let Some(index) = binary.and_then(tool_index) {
+                                        app.create_tab_for_store(index, &store_path);
+                                        mode_changed = true;
+                                    }
+                                }
+                            }
                             TabMode::Running | TabMode::Completed => {
                                 // Handle close/exit for both modes
                                 let should_close = match tab.mode {
@@ -339,9 +573,16 @@ fn run_app(terminal: &mut Terminal<CrosstermBackend<io::Stdout>>) -> Result<()>
                                     _ => false,
                                 };
 
+                                let wizard_next = tab.mode == TabMode::Completed
+                                    && matches!(key.code, KeyCode::Char('n') | KeyCode::Char('N'))
+                                    && tab.wizard.as_ref().is_some_and(|wizard| wizard.next_step().is_some());
+
                                 if should_close {
                                     app.request_close_active_tab();
                                     mode_changed = true;
+                                } else if wizard_next {
+                                    app.continue_wizard();
+                                    mode_changed = true;
                                 } else if is_up(&key) {
                                     tab.scroll_up(1);
                                 } else if is_down(&key) {
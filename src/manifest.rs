@@ -0,0 +1,235 @@
+//! Session manifest in the store's `meta` group
+//!
+//! The `manifest` attribute of the `meta` group describes a session as a whole:
+//! subject, session ID and notes, the toolbox version, host name, OS and liblsl
+//! version of the machine that created the store, and every recorded stream with
+//! its recorder configuration:
+//!
+//! ```json
+//! {"subject": "P001", "session_id": "session_001", "notes": "pilot",
+//!  "created_at": "2025-01-31T14:30:05.123456Z", "toolbox_version": "1.10.0",
+//!  "host": {"hostname": "lab-pc", "os": "linux", "arch": "x86_64"}, "lsl_version": "1.16",
+//!  "streams": [{"name": "EMG", "source_id": "EMG_001", "type": "EMG", "channel_count": 8,
+//!               "nominal_srate": 2000.0, "recorder_version": "1.10.0", "config": {...}}]}
+//! ```
+//!
+//! The session part is written when a recorder or `lsl-import` opens the store
+//! ([`write_manifest`]); later writers only fill in what they know, so the creator's
+//! host and versions are kept. The stream list is rebuilt from the stream groups
+//! whenever a stream is added ([`update_manifest_streams`]), like the device table.
+
+use anyhow::Result;
+use fs2::FileExt;
+use serde::{Deserialize, Serialize};
+use std::fs::OpenOptions;
+use std::path::Path;
+use std::sync::Arc;
+use zarrs::filesystem::FilesystemStore;
+
+use crate::export::list_streams;
+use crate::zarr::layout::create_group_if_not_exists;
+use crate::zarr::{read_group_attributes, META_GROUP};
+
+/// Name of the `meta` group attribute holding the [`SessionManifest`]
+pub const MANIFEST_ATTRIBUTE: &str = "manifest";
+
+/// Machine a store was created on
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct HostInfo {
+    pub hostname: Option<String>,
+    pub os: String,
+    pub arch: String,
+}
+
+impl HostInfo {
+    /// The machine this process runs on
+    pub fn current() -> Self {
+        Self {
+            hostname: hostname(),
+            os: std::env::consts::OS.to_string(),
+            arch: std::env::consts::ARCH.to_string(),
+        }
+    }
+}
+
+/// One recorded stream and the configuration it was recorded with
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ManifestStream {
+    pub name: String,
+    pub source_id: Option<String>,
+    #[serde(rename = "type")]
+    pub stream_type: Option<String>,
+    pub channel_count: Option<u64>,
+    pub nominal_srate: Option<f64>,
+    pub recorder_version: Option<String>,
+    /// The stream's `recorder_config` attribute
+    #[serde(default)]
+    pub config: serde_json::Value,
+}
+
+impl ManifestStream {
+    /// Manifest entry of a stream group from its attributes
+    pub fn from_attributes(name: &str, attributes: &serde_json::Value) -> Self {
+        let text = |pointer: &str| attributes.pointer(pointer).and_then(|v| v.as_str()).map(String::from);
+        let config = attributes.get("recorder_config").cloned().unwrap_or_default();
+        Self {
+            name: name.to_string(),
+            source_id: text("/stream_info/source_id"),
+            stream_type: text("/stream_info/type"),
+            channel_count: attributes.pointer("/stream_info/channel_count").and_then(|v| v.as_u64()),
+            nominal_srate: attributes.pointer("/stream_info/nominal_srate").and_then(|v| v.as_f64()),
+            recorder_version: text("/recorder_config/recorder_version"),
+            config,
+        }
+    }
+}
+
+/// Experiment-level description of a store
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct SessionManifest {
+    pub subject: Option<String>,
+    pub session_id: Option<String>,
+    pub notes: Option<String>,
+    /// When the manifest was first written (RFC3339, UTC)
+    pub created_at: Option<String>,
+    pub toolbox_version: Option<String>,
+    pub host: Option<HostInfo>,
+    /// liblsl version (`major.minor`); `None` for stores written without liblsl
+    pub lsl_version: Option<String>,
+    #[serde(default)]
+    pub streams: Vec<ManifestStream>,
+}
+
+impl SessionManifest {
+    /// Manifest of a session recorded on this machine, without streams
+    pub fn new(subject: Option<&str>, session_id: Option<&str>, notes: Option<&str>) -> Self {
+        Self {
+            subject: subject.map(String::from),
+            session_id: session_id.map(String::from),
+            notes: notes.map(String::from),
+            created_at: Some(chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Micros, true)),
+            toolbox_version: Some(env!("CARGO_PKG_VERSION").to_string()),
+            host: Some(HostInfo::current()),
+            lsl_version: lsl_version(),
+            streams: Vec::new(),
+        }
+    }
+
+    /// Read the manifest from `meta` group attributes
+    pub fn from_attributes(attributes: &serde_json::Value) -> Option<Self> {
+        attributes
+            .get(MANIFEST_ATTRIBUTE)
+            .and_then(|value| serde_json::from_value(value.clone()).ok())
+    }
+
+    /// Fill in the fields `other` has and this manifest lacks; subject, session and notes are replaced
+    fn merge(&mut self, other: SessionManifest) {
+        self.subject = other.subject.or(self.subject.take());
+        self.session_id = other.session_id.or(self.session_id.take());
+        self.notes = other.notes.or(self.notes.take());
+        self.created_at = self.created_at.take().or(other.created_at);
+        self.toolbox_version = self.toolbox_version.take().or(other.toolbox_version);
+        self.host = self.host.take().or(other.host);
+        self.lsl_version = self.lsl_version.take().or(other.lsl_version);
+    }
+}
+
+/// Version of the linked liblsl (`major.minor`)
+#[cfg(feature = "lsl")]
+pub fn lsl_version() -> Option<String> {
+    let version = lsl::library_version();
+    Some(format!("{}.{}", version / 100, version % 100))
+}
+
+/// Version of the linked liblsl; `None` when built without the `lsl` feature
+#[cfg(not(feature = "lsl"))]
+pub fn lsl_version() -> Option<String> {
+    None
+}
+
+/// Host name from the environment (Windows), /etc/hostname or the `hostname` command
+pub fn hostname() -> Option<String> {
+    std::env::var("COMPUTERNAME")
+        .ok()
+        .or_else(|| std::fs::read_to_string("/etc/hostname").ok())
+        .or_else(|| {
+            let output = std::process::Command::new("hostname").output().ok()?;
+            output.status.success().then(|| String::from_utf8_lossy(&output.stdout).to_string())
+        })
+        .map(|name| name.trim().to_string())
+        .filter(|name| !name.is_empty())
+}
+
+/// Manifest entries of every stream in the store
+pub fn collect_manifest_streams(store_path: &Path) -> Result<Vec<ManifestStream>> {
+    let store = Arc::new(FilesystemStore::new(store_path)?);
+    Ok(list_streams(store_path)?
+        .into_iter()
+        .filter_map(|name| {
+            let attrs = read_group_attributes(&store, &format!("/{}", name)).ok()?;
+            Some(ManifestStream::from_attributes(&name, &attrs))
+        })
+        .collect())
+}
+
+/// Manifest stored in the `meta` group, if the store has one
+pub fn read_manifest(store_path: &Path) -> Option<SessionManifest> {
+    let store = Arc::new(FilesystemStore::new(store_path).ok()?);
+    let attrs = read_group_attributes(&store, &format!("/{}", META_GROUP)).ok()?;
+    SessionManifest::from_attributes(&attrs)
+}
+
+/// Write the session part of the manifest, keeping what an earlier writer recorded
+///
+/// Subject, session ID and notes replace the stored values when given; creation
+/// time, host and versions stay those of the first writer. The stream list is
+/// rebuilt from the store.
+pub fn write_manifest(
+    store_path: &Path,
+    subject: Option<&str>,
+    session_id: Option<&str>,
+    notes: Option<&str>,
+) -> Result<SessionManifest> {
+    modify_manifest(store_path, |manifest| {
+        manifest.merge(SessionManifest::new(subject, session_id, notes))
+    })
+}
+
+/// Rebuild the stream list of the manifest from the store's stream groups
+pub fn update_manifest_streams(store_path: &Path) -> Result<SessionManifest> {
+    modify_manifest(store_path, |_| {})
+}
+
+/// Read, change and store the manifest under the store's metadata lock
+///
+/// Recorders adding streams to the same store may call this concurrently.
+fn modify_manifest(store_path: &Path, change: impl FnOnce(&mut SessionManifest)) -> Result<SessionManifest> {
+    let lock_file = OpenOptions::new()
+        .create(true)
+        .write(true)
+        .truncate(false)
+        .open(store_path.join(".zarr_metadata.lock"))?;
+    lock_file.lock_exclusive()?;
+    let result = (|| -> Result<SessionManifest> {
+        let store = Arc::new(FilesystemStore::new(store_path)?);
+        let meta_path = format!("/{}", META_GROUP);
+        create_group_if_not_exists(&store, &meta_path)?;
+        let mut group = zarrs::group::Group::open(store, &meta_path)?;
+
+        let mut manifest: SessionManifest = group
+            .attributes()
+            .get(MANIFEST_ATTRIBUTE)
+            .and_then(|value| serde_json::from_value(value.clone()).ok())
+            .unwrap_or_default();
+        change(&mut manifest);
+        manifest.streams = collect_manifest_streams(store_path)?;
+
+        group
+            .attributes_mut()
+            .insert(MANIFEST_ATTRIBUTE.to_string(), serde_json::to_value(&manifest)?);
+        group.store_metadata()?;
+        Ok(manifest)
+    })();
+    lock_file.unlock()?;
+    result
+}
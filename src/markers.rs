@@ -0,0 +1,244 @@
+//! Marker label normalization (`lsl-markers`)
+//!
+//! Stimulus software tends to change its marker labels between versions, so the same
+//! event arrives as `stim_on` in one session, `StimOn` in the next and `1` in a third.
+//! A normalization table maps these aliases to one canonical label:
+//!
+//! ```toml
+//! # Match aliases regardless of case (default false)
+//! ignore_case = true
+//!
+//! [labels]
+//! stim_on = ["StimOn", "stimulus_onset", 1]
+//! response = ["resp", "button_press"]
+//! ```
+//!
+//! Every canonical label also matches itself. Normalizing a marker stream writes a
+//! `labels` array next to its `events` array, one canonical label per event; events
+//! the table does not know keep their original text. The `events` array is never
+//! modified, so the recorded labels stay available. The stream's
+//! `marker_normalization` attribute records the table, the number of rewritten events,
+//! the unmatched labels and the event counts per normalized label.
+
+use anyhow::{Context, Result};
+use ndarray::{Array1, Ix1};
+use serde::Deserialize;
+use serde_json::json;
+use std::collections::{BTreeMap, HashMap};
+use std::path::Path;
+use std::sync::Arc;
+use zarrs::filesystem::FilesystemStore;
+
+use crate::export::{ExportStream, SampleBlock, EXPORT_BLOCK_SAMPLES};
+use crate::zarr::layout::create_sample_array;
+use crate::zarr::materialize::storage_from_attributes;
+
+/// Array of normalized labels, next to a marker stream's `events`
+pub const LABELS_ARRAY: &str = "labels";
+
+/// Stream group attribute describing the last normalization
+pub const NORMALIZATION_ATTRIBUTE: &str = "marker_normalization";
+
+/// Normalization table as written in TOML
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct TableFile {
+    #[serde(default)]
+    ignore_case: bool,
+    labels: BTreeMap<String, Vec<toml::Value>>,
+}
+
+/// Mapping of marker label aliases to canonical labels
+#[derive(Debug, Clone, PartialEq)]
+pub struct MarkerTable {
+    pub ignore_case: bool,
+    /// Aliases of each canonical label, as written in the table
+    pub labels: BTreeMap<String, Vec<String>>,
+    /// Match key of every alias (and canonical label) -> canonical label
+    lookup: HashMap<String, String>,
+}
+
+impl MarkerTable {
+    /// Table from TOML text
+    ///
+    /// Aliases may be strings or integers (numeric trigger codes). An alias listed for
+    /// two canonical labels is an error.
+    pub fn from_toml(text: &str) -> Result<Self> {
+        let file: TableFile = toml::from_str(text).context("Invalid marker table")?;
+        let mut labels = BTreeMap::new();
+        for (canonical, values) in file.labels {
+            let aliases = values
+                .into_iter()
+                .map(|value| match value {
+                    toml::Value::String(text) => Ok(text),
+                    toml::Value::Integer(code) => Ok(code.to_string()),
+                    other => anyhow::bail!("Alias {} of '{}' must be a string or an integer", other, canonical),
+                })
+                .collect::<Result<Vec<_>>>()?;
+            labels.insert(canonical, aliases);
+        }
+        Self::new(labels, file.ignore_case)
+    }
+
+    /// Table from a TOML file
+    pub fn load(path: &Path) -> Result<Self> {
+        let text = std::fs::read_to_string(path).with_context(|| format!("Failed to read {}", path.display()))?;
+        Self::from_toml(&text).with_context(|| format!("In {}", path.display()))
+    }
+
+    /// Table from canonical labels and their aliases
+    pub fn new(labels: BTreeMap<String, Vec<String>>, ignore_case: bool) -> Result<Self> {
+        let mut lookup: HashMap<String, String> = HashMap::new();
+        for (canonical, aliases) in &labels {
+            for alias in std::iter::once(canonical).chain(aliases) {
+                let key = match_key(alias, ignore_case);
+                match lookup.get(&key) {
+                    Some(existing) if existing != canonical => anyhow::bail!(
+                        "Alias '{}' maps to both '{}' and '{}'",
+                        alias,
+                        existing,
+                        canonical
+                    ),
+                    _ => {
+                        lookup.insert(key, canonical.clone());
+                    }
+                }
+            }
+        }
+        Ok(Self {
+            ignore_case,
+            labels,
+            lookup,
+        })
+    }
+
+    /// Canonical label of a marker, or `None` if the table does not know it
+    pub fn normalize(&self, label: &str) -> Option<&str> {
+        self.lookup.get(&match_key(label, self.ignore_case)).map(String::as_str)
+    }
+
+    /// JSON form stored in the `marker_normalization` attribute
+    pub fn to_json(&self) -> serde_json::Value {
+        json!({
+            "ignore_case": self.ignore_case,
+            "labels": self.labels,
+        })
+    }
+}
+
+/// Key used to look up a label: the label itself, or lowercased with `ignore_case`
+fn match_key(label: &str, ignore_case: bool) -> String {
+    if ignore_case { label.to_lowercase() } else { label.to_string() }
+}
+
+/// Outcome of normalizing one marker stream
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct NormalizationReport {
+    pub stream: String,
+    /// Number of events in the stream
+    pub events: u64,
+    /// Events whose label was replaced by a different canonical label
+    pub rewritten: u64,
+    /// Labels the table does not know, with their event counts
+    pub unmatched: BTreeMap<String, u64>,
+    /// Event counts per normalized label
+    pub counts: BTreeMap<String, u64>,
+}
+
+impl NormalizationReport {
+    /// Count one event with its original and normalized label
+    pub fn push(&mut self, original: &str, normalized: Option<&str>) {
+        self.events += 1;
+        match normalized {
+            Some(label) => {
+                if label != original {
+                    self.rewritten += 1;
+                }
+                *self.counts.entry(label.to_string()).or_insert(0) += 1;
+            }
+            None => {
+                *self.unmatched.entry(original.to_string()).or_insert(0) += 1;
+                *self.counts.entry(original.to_string()).or_insert(0) += 1;
+            }
+        }
+    }
+
+    /// JSON summary (the `marker_normalization` attribute without the table)
+    pub fn to_json(&self) -> serde_json::Value {
+        json!({
+            "events": self.events,
+            "rewritten": self.rewritten,
+            "unmatched": self.unmatched,
+            "normalized_event_counts": self.counts,
+        })
+    }
+}
+
+/// Normalized labels of all events of a marker stream
+pub fn normalize_events(stream: &ExportStream, table: &MarkerTable) -> Result<(Vec<String>, NormalizationReport)> {
+    if !stream.is_marker() {
+        anyhow::bail!("Stream '{}' is not a marker stream", stream.name);
+    }
+    let mut report = NormalizationReport {
+        stream: stream.name.clone(),
+        ..Default::default()
+    };
+    let mut labels = Vec::with_capacity(stream.sample_count as usize);
+    let mut start = 0;
+    while start < stream.sample_count {
+        let len = EXPORT_BLOCK_SAMPLES.min(stream.sample_count - start);
+        let (_, block) = stream.read_block(start, len)?;
+        let SampleBlock::String(events) = block else {
+            anyhow::bail!("Stream '{}' has no string events", stream.name);
+        };
+        for event in events {
+            let normalized = table.normalize(&event);
+            report.push(&event, normalized);
+            labels.push(normalized.map(str::to_string).unwrap_or(event));
+        }
+        start += len;
+    }
+    Ok((labels, report))
+}
+
+/// Normalize a marker stream of a store and write its `labels` array
+///
+/// An earlier `labels` array is replaced. With `table_source` (usually the table's
+/// path) the attribute records where the table came from.
+pub fn normalize_stream(
+    store_path: &Path,
+    stream_name: &str,
+    table: &MarkerTable,
+    table_source: Option<&str>,
+) -> Result<NormalizationReport> {
+    if !store_path.join("zarr.json").exists() {
+        anyhow::bail!("Not a Zarr store: {}", store_path.display());
+    }
+    let store = Arc::new(FilesystemStore::new(store_path)?);
+    let stream = ExportStream::open(&store, stream_name, false)?;
+    let (labels, report) = normalize_events(&stream, table)?;
+
+    let labels_dir = store_path.join(stream_name).join(LABELS_ARRAY);
+    if labels_dir.exists() {
+        std::fs::remove_dir_all(&labels_dir)?;
+    }
+    let storage = storage_from_attributes(&stream.attributes);
+    let path = format!("/{}/{}", stream_name, LABELS_ARRAY);
+    let mut array = create_sample_array(&store, &path, "String", 1, true, &storage)?;
+    array.set_shape(vec![labels.len() as u64])?;
+    if !labels.is_empty() {
+        array.store_array_subset_ndarray::<String, Ix1>(&[0], Array1::from_vec(labels))?;
+    }
+    array.store_metadata()?;
+
+    let mut attribute = report.to_json();
+    attribute["table"] = table.to_json();
+    attribute["table_source"] = json!(table_source);
+    attribute["labels_array"] = json!(LABELS_ARRAY);
+    attribute["normalized_at"] = json!(chrono::Utc::now().to_rfc3339());
+
+    let mut group = zarrs::group::Group::open(store, &format!("/{}", stream_name))?;
+    group.attributes_mut().insert(NORMALIZATION_ATTRIBUTE.to_string(), attribute);
+    group.store_metadata()?;
+    Ok(report)
+}
@@ -0,0 +1,240 @@
+//! Process memory monitoring while recording (`--memory-monitor`)
+//!
+//! Every `--memory-interval` seconds the recorder samples its resident (RSS) and
+//! virtual memory, the bytes held in its sample buffer and the sample rate since
+//! the previous report, and prints them on the status protocol:
+//!
+//! ```text
+//! STATUS MEMORY rss=52.1MB virtual=1204.3MB buffer=0.41MB rate=2000.0Hz growth=+0.02MB/min
+//! ```
+//!
+//! `growth` is the least-squares slope of RSS over the last [`GROWTH_WINDOW`]
+//! reports. A recorder that keeps up with its stream levels off once its buffers
+//! and codecs are allocated, so a slope above `--memory-growth-limit` over a full
+//! window means memory is leaking or the buffer is not drained fast enough. The
+//! recorder then prints `STATUS MEMORY_GROWTH (+12.0MB/min)` once, and
+//! `STATUS MEMORY_STABLE (...)` when the slope drops back below the limit.
+//!
+//! `lsl-multi-recorder` forwards these lines with the stream label, and the TUI
+//! parses them ([`MemoryStatus::parse`]) into the memory panel of a running tab.
+
+use std::collections::VecDeque;
+use std::io::Write;
+use std::time::{Duration, Instant};
+use sysinfo::{Pid, ProcessRefreshKind, ProcessesToUpdate, System};
+
+/// Reports used for the RSS slope; growth is only judged once the window is full
+pub const GROWTH_WINDOW: usize = 6;
+
+const BYTES_PER_MB: f64 = 1024.0 * 1024.0;
+
+/// One `STATUS MEMORY` report
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct MemoryStatus {
+    /// Resident set size (MB); `None` where the platform does not report it
+    pub rss_mb: Option<f64>,
+    /// Virtual memory size (MB)
+    pub virtual_mb: Option<f64>,
+    /// Samples waiting in the write buffer (MB)
+    pub buffer_mb: f64,
+    /// Samples per second since the previous report
+    pub rate_hz: f64,
+    /// RSS slope over the last reports (MB/min)
+    pub growth_mb_per_min: Option<f64>,
+}
+
+impl MemoryStatus {
+    /// The `STATUS MEMORY ...` line for this report
+    pub fn status_line(&self) -> String {
+        let mut line = String::from("STATUS MEMORY");
+        if let Some(rss) = self.rss_mb {
+            line.push_str(&format!(" rss={:.1}MB", rss));
+        }
+        if let Some(virtual_mb) = self.virtual_mb {
+            line.push_str(&format!(" virtual={:.1}MB", virtual_mb));
+        }
+        line.push_str(&format!(" buffer={:.2}MB rate={:.1}Hz", self.buffer_mb, self.rate_hz));
+        if let Some(growth) = self.growth_mb_per_min {
+            line.push_str(&format!(" growth={:+.2}MB/min", growth));
+        }
+        line
+    }
+
+    /// Parse a `STATUS MEMORY` report anywhere in `line` (e.g. behind lsl-multi-recorder's prefix)
+    pub fn parse(line: &str) -> Option<Self> {
+        let (_, fields) = line.split_once("STATUS MEMORY ")?;
+        let mut status = Self::default();
+        for field in fields.split_whitespace() {
+            let Some((key, value)) = field.split_once('=') else {
+                continue;
+            };
+            let number = |unit: &str| value.strip_suffix(unit).and_then(|v| v.parse::<f64>().ok());
+            match key {
+                "rss" => status.rss_mb = number("MB"),
+                "virtual" => status.virtual_mb = number("MB"),
+                "buffer" => status.buffer_mb = number("MB")?,
+                "rate" => status.rate_hz = number("Hz")?,
+                "growth" => status.growth_mb_per_min = number("MB/min"),
+                _ => {}
+            }
+        }
+        Some(status)
+    }
+}
+
+/// Change of the growth state, reported once per change
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MemoryEvent {
+    /// RSS slope rose above the limit (MB/min)
+    Growing(f64),
+    /// RSS slope fell back below the limit (MB/min)
+    Stable(f64),
+}
+
+impl MemoryEvent {
+    /// The `STATUS MEMORY_GROWTH`/`STATUS MEMORY_STABLE` line for this event
+    pub fn status_line(&self) -> String {
+        match self {
+            MemoryEvent::Growing(slope) => format!("STATUS MEMORY_GROWTH ({:+.1}MB/min)", slope),
+            MemoryEvent::Stable(slope) => format!("STATUS MEMORY_STABLE ({:+.1}MB/min)", slope),
+        }
+    }
+}
+
+/// Least-squares slope of `(seconds, megabytes)` points in MB/min
+///
+/// `None` for fewer than two points or points at a single time.
+pub fn growth_rate(points: &[(f64, f64)]) -> Option<f64> {
+    if points.len() < 2 {
+        return None;
+    }
+    let n = points.len() as f64;
+    let mean_t = points.iter().map(|(t, _)| t).sum::<f64>() / n;
+    let mean_m = points.iter().map(|(_, m)| m).sum::<f64>() / n;
+    let covariance: f64 = points.iter().map(|(t, m)| (t - mean_t) * (m - mean_m)).sum();
+    let variance: f64 = points.iter().map(|(t, _)| (t - mean_t).powi(2)).sum();
+    (variance > 0.0).then(|| covariance / variance * 60.0)
+}
+
+/// RSS slope over the last [`GROWTH_WINDOW`] reports, compared against a limit
+#[derive(Debug, Clone)]
+pub struct GrowthDetector {
+    limit_mb_per_min: f64,
+    history: VecDeque<(f64, f64)>,
+    growing: bool,
+}
+
+impl GrowthDetector {
+    /// Detector warning above `limit_mb_per_min` (0 only reports the slope)
+    pub fn new(limit_mb_per_min: f64) -> Self {
+        Self {
+            limit_mb_per_min,
+            history: VecDeque::with_capacity(GROWTH_WINDOW + 1),
+            growing: false,
+        }
+    }
+
+    /// Add an RSS sample at `seconds` since recording began; returns the slope and any change of state
+    pub fn update(&mut self, seconds: f64, rss_mb: f64) -> (Option<f64>, Option<MemoryEvent>) {
+        self.history.push_back((seconds, rss_mb));
+        if self.history.len() > GROWTH_WINDOW {
+            self.history.pop_front();
+        }
+        let slope = growth_rate(self.history.make_contiguous());
+        let Some(slope) = slope else {
+            return (None, None);
+        };
+        // The allocations of the first flushes are not judged on their own
+        if self.limit_mb_per_min <= 0.0 || self.history.len() < GROWTH_WINDOW {
+            return (Some(slope), None);
+        }
+        let growing = slope > self.limit_mb_per_min;
+        if growing == self.growing {
+            return (Some(slope), None);
+        }
+        self.growing = growing;
+        let event = if growing { MemoryEvent::Growing(slope) } else { MemoryEvent::Stable(slope) };
+        (Some(slope), Some(event))
+    }
+
+    /// Whether the slope is currently above the limit
+    pub fn is_growing(&self) -> bool {
+        self.growing
+    }
+}
+
+/// Periodic process memory reports for one recorder
+pub struct MemoryMonitor {
+    interval: Duration,
+    started: Instant,
+    last_report: Instant,
+    last_sample_count: u64,
+    detector: GrowthDetector,
+    system: System,
+    pid: Option<Pid>,
+}
+
+impl MemoryMonitor {
+    /// Report every `interval`, warning when RSS grows faster than `growth_limit` MB/min
+    pub fn new(interval: Duration, growth_limit: f64) -> Self {
+        let now = Instant::now();
+        Self {
+            interval,
+            started: now,
+            last_report: now,
+            last_sample_count: 0,
+            detector: GrowthDetector::new(growth_limit),
+            system: System::new(),
+            pid: sysinfo::get_current_pid().ok(),
+        }
+    }
+
+    /// RSS and virtual memory of this process in bytes
+    fn process_memory(&mut self) -> Option<(u64, u64)> {
+        let pid = self.pid?;
+        self.system.refresh_processes_specifics(
+            ProcessesToUpdate::Some(&[pid]),
+            true,
+            ProcessRefreshKind::nothing().with_memory(),
+        );
+        self.system
+            .process(pid)
+            .map(|process| (process.memory(), process.virtual_memory()))
+    }
+
+    /// Print a report if the interval has passed; `buffer_bytes` is held by the write buffer
+    pub fn maybe_report(&mut self, sample_count: u64, buffer_bytes: usize) -> Option<MemoryStatus> {
+        let elapsed = self.last_report.elapsed();
+        if elapsed < self.interval {
+            return None;
+        }
+        let memory = self.process_memory();
+        let mut status = MemoryStatus {
+            rss_mb: memory.map(|(rss, _)| rss as f64 / BYTES_PER_MB),
+            virtual_mb: memory.map(|(_, virtual_bytes)| virtual_bytes as f64 / BYTES_PER_MB),
+            buffer_mb: buffer_bytes as f64 / BYTES_PER_MB,
+            rate_hz: sample_count.saturating_sub(self.last_sample_count) as f64 / elapsed.as_secs_f64(),
+            growth_mb_per_min: None,
+        };
+        let mut event = None;
+        if let Some(rss) = status.rss_mb {
+            (status.growth_mb_per_min, event) = self.detector.update(self.started.elapsed().as_secs_f64(), rss);
+        }
+
+        println!("{}", status.status_line());
+        if let Some(event) = event {
+            println!("{}", event.status_line());
+            if let MemoryEvent::Growing(slope) = event {
+                eprintln!(
+                    "Warning: memory grew by {:.1} MB/min over the last {} reports (buffer {:.2} MB at {:.1} Hz)",
+                    slope, GROWTH_WINDOW, status.buffer_mb, status.rate_hz
+                );
+            }
+        }
+        std::io::stdout().flush().ok();
+
+        self.last_report = Instant::now();
+        self.last_sample_count = sample_count;
+        Some(status)
+    }
+}
@@ -0,0 +1,213 @@
+//! Decimated LSL monitor outlet
+//!
+//! `lsl-recorder --monitor` republishes a low-rate copy of selected channels as a new
+//! LSL stream, so monitoring stations elsewhere on the network can watch signal quality
+//! without pulling the full-rate stream. The recording path is unchanged: every
+//! incoming sample still goes to the writer, and only every `decimation`-th sample
+//! (or the mean of each block of `decimation` samples) is pushed to the outlet.
+//!
+//! The monitor stream is named `<stream>_monitor` (source ID `<source_id>_monitor`),
+//! carries Float32 samples at `nominal_srate / decimation`, and its description lists
+//! the selected channels plus a `<monitor>` element with the source stream and the
+//! decimation settings.
+
+use anyhow::Result;
+use lsl::ExPushable;
+
+use crate::lsl::stream_channel_labels;
+use crate::tap::tap_decimation;
+
+/// How each block of `decimation` samples becomes one monitor sample
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum MonitorReduction {
+    /// Mean of the block (simple anti-aliasing)
+    Mean,
+    /// First sample of the block
+    Pick,
+}
+
+impl std::fmt::Display for MonitorReduction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            MonitorReduction::Mean => write!(f, "mean"),
+            MonitorReduction::Pick => write!(f, "pick"),
+        }
+    }
+}
+
+/// Settings of the monitor outlet
+#[derive(Debug, Clone)]
+pub struct MonitorOptions {
+    /// Approximate rate of the monitor stream in Hz
+    pub rate: f64,
+    /// Channels to republish, by label or 0-based index (all channels if empty)
+    pub channels: Vec<String>,
+    pub reduction: MonitorReduction,
+    /// Name of the monitor stream (`<stream>_monitor` if `None`)
+    pub name: Option<String>,
+}
+
+impl Default for MonitorOptions {
+    fn default() -> Self {
+        Self {
+            rate: 50.0,
+            channels: Vec::new(),
+            reduction: MonitorReduction::Mean,
+            name: None,
+        }
+    }
+}
+
+/// Reduces a sample stream to every `decimation`-th sample of selected channels
+#[derive(Debug, Clone)]
+pub struct Decimator {
+    decimation: u64,
+    channels: Vec<usize>,
+    reduction: MonitorReduction,
+    sums: Vec<f64>,
+    time_sum: f64,
+    count: u64,
+}
+
+impl Decimator {
+    pub fn new(decimation: u64, channels: Vec<usize>, reduction: MonitorReduction) -> Self {
+        Self {
+            decimation: decimation.max(1),
+            sums: vec![0.0; channels.len()],
+            channels,
+            reduction,
+            time_sum: 0.0,
+            count: 0,
+        }
+    }
+
+    /// Feed one sample; returns the monitor sample when a block is complete
+    ///
+    /// `Pick` keeps the first sample of each block, `Mean` averages values and
+    /// timestamps over the block.
+    pub fn push<T: Copy + Into<f64>>(&mut self, timestamp: f64, values: &[T]) -> Option<(f64, Vec<f32>)> {
+        let first = self.count == 0;
+        self.count += 1;
+        let complete = self.count == self.decimation;
+        if complete {
+            self.count = 0;
+        }
+
+        match self.reduction {
+            MonitorReduction::Pick => first.then(|| {
+                let sample = self.channels.iter().map(|&c| values[c].into() as f32).collect();
+                (timestamp, sample)
+            }),
+            MonitorReduction::Mean => {
+                for (sum, &channel) in self.sums.iter_mut().zip(&self.channels) {
+                    *sum += values[channel].into();
+                }
+                self.time_sum += timestamp;
+                if !complete {
+                    return None;
+                }
+                let n = self.decimation as f64;
+                let sample = self.sums.iter().map(|&sum| (sum / n) as f32).collect();
+                let time = self.time_sum / n;
+                self.sums.iter_mut().for_each(|sum| *sum = 0.0);
+                self.time_sum = 0.0;
+                Some((time, sample))
+            }
+        }
+    }
+}
+
+/// Channel indices selected by label or 0-based index (all channels if none are named)
+pub fn select_monitor_channels(labels: &[String], wanted: &[String]) -> Result<Vec<usize>> {
+    if wanted.is_empty() {
+        return Ok((0..labels.len()).collect());
+    }
+    wanted
+        .iter()
+        .map(|name| {
+            labels
+                .iter()
+                .position(|label| label == name)
+                .or_else(|| name.parse::<usize>().ok().filter(|&i| i < labels.len()))
+                .ok_or_else(|| {
+                    anyhow::anyhow!("Monitor channel '{}' not found (channels: {})", name, labels.join(", "))
+                })
+        })
+        .collect()
+}
+
+/// LSL outlet publishing the decimated copy of a recorded stream
+pub struct MonitorOutlet {
+    outlet: lsl::StreamOutlet,
+    decimator: Decimator,
+    name: String,
+    rate: f64,
+}
+
+impl MonitorOutlet {
+    /// Create the monitor outlet for the stream described by `info`
+    pub fn start(info: &mut lsl::StreamInfo, options: &MonitorOptions) -> Result<Self> {
+        if matches!(info.channel_format(), lsl::ChannelFormat::String) {
+            anyhow::bail!("--monitor needs a numeric stream");
+        }
+
+        let labels = stream_channel_labels(info);
+        let channels = select_monitor_channels(&labels, &options.channels)?;
+        let decimation = tap_decimation(info.nominal_srate(), options.rate);
+        let rate = if info.nominal_srate() > 0.0 { info.nominal_srate() / decimation as f64 } else { 0.0 };
+
+        let name = options
+            .name
+            .clone()
+            .unwrap_or_else(|| format!("{}_monitor", info.stream_name()));
+        let mut monitor_info = lsl::StreamInfo::new(
+            &name,
+            &info.stream_type(),
+            channels.len() as u32,
+            rate,
+            lsl::ChannelFormat::Float32,
+            &format!("{}_monitor", info.source_id()),
+        )
+        .map_err(|e| anyhow::anyhow!("LSL error: {}", e))?;
+
+        let mut desc = monitor_info.desc();
+        let mut channels_element = desc.append_child("channels");
+        for &channel in &channels {
+            let mut element = channels_element.append_child("channel");
+            element.append_child_value("label", &labels[channel]);
+            element.append_child_value("source_index", &channel.to_string());
+        }
+        let mut monitor_element = desc.append_child("monitor");
+        monitor_element.append_child_value("source_stream", &info.stream_name());
+        monitor_element.append_child_value("source_id", &info.source_id());
+        monitor_element.append_child_value("decimation", &decimation.to_string());
+        monitor_element.append_child_value("reduction", &options.reduction.to_string());
+
+        let outlet = lsl::StreamOutlet::new(&monitor_info, 0, 10).map_err(|e| anyhow::anyhow!("LSL error: {}", e))?;
+
+        Ok(Self {
+            outlet,
+            decimator: Decimator::new(decimation, channels, options.reduction),
+            name,
+            rate,
+        })
+    }
+
+    /// Name of the monitor stream
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Nominal rate of the monitor stream (0 for irregular sources)
+    pub fn rate(&self) -> f64 {
+        self.rate
+    }
+
+    /// Offer a recorded sample; pushes a monitor sample when one is due
+    pub fn offer<T: Copy + Into<f64>>(&mut self, timestamp: f64, values: &[T]) {
+        if let Some((time, sample)) = self.decimator.push(timestamp, values) {
+            // Monitoring is best effort; a failed push must not disturb recording
+            self.outlet.push_sample_ex(&sample, time, true).ok();
+        }
+    }
+}
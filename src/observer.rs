@@ -0,0 +1,224 @@
+//! Progress callbacks for applications embedding the recorder
+//!
+//! A custom GUI that records through [`record_lsl_stream`](crate::lsl::record_lsl_stream)
+//! can follow the recording through [`RecordingParams::observer`](crate::lsl::RecordingParams)
+//! instead of parsing the `STATUS` lines on stdout. The observer runs on the recording
+//! thread, so it should hand the figures on (e.g. over a channel) rather than draw:
+//!
+//! - [`on_sample_batch`](RecordingObserver::on_sample_batch) - the samples recorded
+//!   since the previous batch, about every [`BATCH_INTERVAL`], for plots
+//! - [`on_flush`](RecordingObserver::on_flush) - the write buffer reached the store
+//! - [`on_state_change`](RecordingObserver::on_state_change) - e.g. `Ready` to
+//!   `Recording`, `Paused`, `Lost` or `Finished`
+//!
+//! Flushes and state changes come with the stream's [`StreamStatus`], the figures the
+//! `--status-port` endpoint serves. [`RecordingCallbacks`] implements the trait with
+//! closures:
+//!
+//! ```no_run
+//! use lsl_recording_toolbox::observer::RecordingCallbacks;
+//!
+//! let callbacks = RecordingCallbacks::new()
+//!     .on_sample_batch(|batch| println!("{} new samples", batch.len()))
+//!     .on_state_change(|previous, status| println!("{:?} -> {:?}", previous, status.state));
+//! ```
+
+use std::time::{Duration, Instant};
+
+use crate::status::{RecordingState, StreamStatus};
+
+/// How often recorded samples are handed to the observer
+pub const BATCH_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Receives the progress of one recording; every method does nothing by default
+pub trait RecordingObserver: Send {
+    /// Samples recorded since the previous batch
+    fn on_sample_batch(&mut self, _batch: &SampleBatch) {}
+
+    /// The write buffer was flushed to the store
+    fn on_flush(&mut self, _status: &StreamStatus) {}
+
+    /// The recording state changed from `previous` to `status.state`
+    fn on_state_change(&mut self, _previous: RecordingState, _status: &StreamStatus) {}
+}
+
+/// Values of a batch, channels interleaved (sample after sample)
+#[derive(Debug, Clone, PartialEq)]
+pub enum BatchValues {
+    /// Numeric streams, converted to `f64`
+    Numeric(Vec<f64>),
+    /// String (marker) streams
+    Text(Vec<String>),
+}
+
+/// Samples recorded from one stream
+#[derive(Debug, Clone, PartialEq)]
+pub struct SampleBatch {
+    pub stream: String,
+    pub channels: usize,
+    /// LSL timestamp of each sample
+    pub timestamps: Vec<f64>,
+    pub values: BatchValues,
+}
+
+impl SampleBatch {
+    /// Number of samples
+    pub fn len(&self) -> usize {
+        self.timestamps.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.timestamps.is_empty()
+    }
+
+    /// Values of sample `index`
+    pub fn numeric_sample(&self, index: usize) -> Option<&[f64]> {
+        match self.values {
+            BatchValues::Numeric(ref values) => values.get(index * self.channels..(index + 1) * self.channels),
+            BatchValues::Text(_) => None,
+        }
+    }
+
+    fn clear(&mut self) {
+        self.timestamps.clear();
+        match self.values {
+            BatchValues::Numeric(ref mut values) => values.clear(),
+            BatchValues::Text(ref mut values) => values.clear(),
+        }
+    }
+}
+
+/// Hands the recording loop's progress to an observer: batches samples, and reports
+/// flushes and the state changes it notices
+pub struct ObserverDispatch {
+    observer: Box<dyn RecordingObserver>,
+    batch: SampleBatch,
+    last_batch: Instant,
+    state: RecordingState,
+}
+
+impl ObserverDispatch {
+    /// Dispatch for `stream` with `channels` channels; `text` for string streams
+    pub fn new(observer: Box<dyn RecordingObserver>, stream: &str, channels: usize, text: bool) -> Self {
+        Self {
+            observer,
+            batch: SampleBatch {
+                stream: stream.to_string(),
+                channels,
+                timestamps: Vec::new(),
+                values: if text {
+                    BatchValues::Text(Vec::new())
+                } else {
+                    BatchValues::Numeric(Vec::new())
+                },
+            },
+            last_batch: Instant::now(),
+            state: RecordingState::Resolving,
+        }
+    }
+
+    /// Add a recorded numeric sample
+    pub fn offer<T: Copy + Into<f64>>(&mut self, timestamp: f64, values: &[T]) {
+        if let BatchValues::Numeric(ref mut batch) = self.batch.values {
+            self.batch.timestamps.push(timestamp);
+            batch.extend(values.iter().map(|&value| value.into()));
+        }
+    }
+
+    /// Add a recorded string sample
+    pub fn offer_marker(&mut self, timestamp: f64, values: &[String]) {
+        if let BatchValues::Text(ref mut batch) = self.batch.values {
+            self.batch.timestamps.push(timestamp);
+            batch.extend(values.iter().cloned());
+        }
+    }
+
+    /// Hand over the batch if it is due; cheap to call per sample
+    pub fn tick(&mut self) {
+        if self.last_batch.elapsed() >= BATCH_INTERVAL {
+            self.send_batch();
+        }
+    }
+
+    /// Report the state of `status` if it changed; pending samples go first
+    pub fn state(&mut self, status: &StreamStatus) {
+        if status.state == self.state {
+            return;
+        }
+        self.send_batch();
+        let previous = std::mem::replace(&mut self.state, status.state);
+        self.observer.on_state_change(previous, status);
+    }
+
+    /// Report a flush of the write buffer
+    pub fn flushed(&mut self, status: &StreamStatus) {
+        self.observer.on_flush(status);
+    }
+
+    /// Hand over the last samples and the final state
+    pub fn finish(&mut self, status: &StreamStatus) {
+        self.send_batch();
+        self.state(status);
+    }
+
+    fn send_batch(&mut self) {
+        self.last_batch = Instant::now();
+        if !self.batch.is_empty() {
+            self.observer.on_sample_batch(&self.batch);
+            self.batch.clear();
+        }
+    }
+}
+
+type BatchCallback = Box<dyn FnMut(&SampleBatch) + Send>;
+type FlushCallback = Box<dyn FnMut(&StreamStatus) + Send>;
+type StateCallback = Box<dyn FnMut(RecordingState, &StreamStatus) + Send>;
+
+/// Observer made of closures, for the callbacks an application needs
+#[derive(Default)]
+pub struct RecordingCallbacks {
+    sample_batch: Option<BatchCallback>,
+    flush: Option<FlushCallback>,
+    state_change: Option<StateCallback>,
+}
+
+impl RecordingCallbacks {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn on_sample_batch(mut self, callback: impl FnMut(&SampleBatch) + Send + 'static) -> Self {
+        self.sample_batch = Some(Box::new(callback));
+        self
+    }
+
+    pub fn on_flush(mut self, callback: impl FnMut(&StreamStatus) + Send + 'static) -> Self {
+        self.flush = Some(Box::new(callback));
+        self
+    }
+
+    pub fn on_state_change(mut self, callback: impl FnMut(RecordingState, &StreamStatus) + Send + 'static) -> Self {
+        self.state_change = Some(Box::new(callback));
+        self
+    }
+}
+
+impl RecordingObserver for RecordingCallbacks {
+    fn on_sample_batch(&mut self, batch: &SampleBatch) {
+        if let Some(ref mut callback) = self.sample_batch {
+            callback(batch);
+        }
+    }
+
+    fn on_flush(&mut self, status: &StreamStatus) {
+        if let Some(ref mut callback) = self.flush {
+            callback(status);
+        }
+    }
+
+    fn on_state_change(&mut self, previous: RecordingState, status: &StreamStatus) {
+        if let Some(ref mut callback) = self.state_change {
+            callback(previous, status);
+        }
+    }
+}
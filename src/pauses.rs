@@ -0,0 +1,131 @@
+//! Paused intervals of a recording (`PAUSE`/`RESUME`)
+//!
+//! `STOP` followed by `START` leaves it open whether the samples on either side
+//! belong to the same run. `PAUSE` and `RESUME` interrupt a run without ending it:
+//! nothing is stored while paused, and every pause is added to the stream group's
+//! `pauses` attribute as `[pause_start, pause_end]` in LSL clock time, comparable to
+//! the `time` array:
+//!
+//! ```json
+//! "pauses": [[81542.103, 81561.851], [81602.004, 81610.392]]
+//! ```
+//!
+//! A pause still open when the recording ends is closed at that moment.
+//! `lsl-sync --exclude-pauses` leaves the samples inside the pauses of any
+//! synchronized stream out of the trimmed range (see [`exclude_pauses`]).
+
+use serde_json::{Map, Value};
+use std::ops::Range;
+
+/// Stream group attribute holding the `[pause_start, pause_end]` intervals
+pub const PAUSES_ATTRIBUTE: &str = "pauses";
+
+/// Stream group attribute written by `lsl-sync --exclude-pauses`: sample index
+/// ranges `[start, end)` inside the trim range that fall into a pause
+pub const EXCLUDED_RANGES_ATTRIBUTE: &str = "excluded_ranges";
+
+/// Follows the recorder's paused flag and turns it into pause intervals
+#[derive(Debug, Default)]
+pub struct PauseTracker {
+    open: Option<f64>,
+    last: Option<(f64, f64)>,
+}
+
+impl PauseTracker {
+    /// Observe the paused flag at `lsl_clock`; returns the pause it ended, if any
+    pub fn update(&mut self, paused: bool, lsl_clock: f64) -> Option<(f64, f64)> {
+        match (paused, self.open) {
+            (true, None) => {
+                self.open = Some(lsl_clock);
+                None
+            }
+            (false, Some(start)) => {
+                self.open = None;
+                self.last = Some((start, lsl_clock));
+                self.last
+            }
+            _ => None,
+        }
+    }
+
+    /// Close a pause that is still open when the recording ends
+    pub fn finish(&mut self, lsl_clock: f64) -> Option<(f64, f64)> {
+        self.update(false, lsl_clock)
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.open.is_some()
+    }
+
+    /// Whether a sample was taken during the last pause
+    ///
+    /// The inlet keeps buffering while paused, so the first samples pulled after
+    /// `RESUME` can still belong to the pause.
+    pub fn covers(&self, timestamp: f64) -> bool {
+        self.last.is_some_and(|(start, end)| timestamp >= start && timestamp <= end)
+    }
+}
+
+/// Append a pause to the `pauses` attribute of a stream group's attributes
+pub fn append_pause(attributes: &mut Map<String, Value>, (start, end): (f64, f64)) {
+    let entry = Value::from(vec![start, end]);
+    match attributes.get_mut(PAUSES_ATTRIBUTE) {
+        Some(Value::Array(pauses)) => pauses.push(entry),
+        _ => {
+            attributes.insert(PAUSES_ATTRIBUTE.to_string(), Value::Array(vec![entry]));
+        }
+    }
+}
+
+/// Pauses stored in a stream group's attributes (empty if it was never paused)
+pub fn read_pauses(attributes: &Value) -> Vec<(f64, f64)> {
+    attributes
+        .get(PAUSES_ATTRIBUTE)
+        .and_then(|pauses| serde_json::from_value(pauses.clone()).ok())
+        .unwrap_or_default()
+}
+
+/// Sort pauses and merge the overlapping ones (e.g. the same pause seen by several recorders)
+pub fn merge_pauses(mut pauses: Vec<(f64, f64)>) -> Vec<(f64, f64)> {
+    pauses.retain(|(start, end)| start <= end);
+    pauses.sort_by(|a, b| a.0.total_cmp(&b.0));
+    let mut merged: Vec<(f64, f64)> = Vec::with_capacity(pauses.len());
+    for (start, end) in pauses {
+        match merged.last_mut() {
+            Some(last) if start <= last.1 => last.1 = last.1.max(end),
+            _ => merged.push((start, end)),
+        }
+    }
+    merged
+}
+
+/// Remove the samples inside `pauses` from the sample range `range` of `timestamps`
+///
+/// Pauses at the edges of the range shrink it; the returned ranges are the paused
+/// samples left inside it. `timestamps` must be sorted and `pauses` merged.
+pub fn exclude_pauses(
+    timestamps: &[f64],
+    pauses: &[(f64, f64)],
+    range: Range<usize>,
+) -> (Range<usize>, Vec<Range<usize>>) {
+    let mut range = range.start.min(timestamps.len())..range.end.min(timestamps.len());
+    let mut excluded = Vec::new();
+    for &(start, end) in pauses {
+        let window = &timestamps[range.clone()];
+        let first = range.start + window.partition_point(|&t| t < start);
+        let last = range.start + window.partition_point(|&t| t <= end);
+        if first < last {
+            excluded.push(first..last);
+        }
+    }
+
+    if let Some(leading) = excluded.first().filter(|r| r.start == range.start).cloned() {
+        range.start = leading.end;
+        excluded.remove(0);
+    }
+    if let Some(trailing) = excluded.last().filter(|r| r.end == range.end).cloned() {
+        range.end = trailing.start.max(range.start);
+        excluded.pop();
+    }
+    (range, excluded)
+}
@@ -0,0 +1,191 @@
+//! Permissions and group ownership of created stores (`--chmod`, `--chgrp`)
+//!
+//! On shared Linux workstations a store gets the recording account's umask and
+//! primary group, so the analysis account often cannot read it. With
+//! `--chmod 660 --chgrp lab` the recorders give every file of the store mode
+//! `660` and group `lab` ([`apply_permissions`]). Directories get the matching
+//! execute bits (`660` becomes `770`) and, when a group is set, the setgid bit,
+//! so files created later inherit the group. The recorders apply both when the
+//! store is opened and again when recording ends, covering the chunks written in
+//! between.
+//!
+//! [`check_writable`] runs before any stream is resolved: it creates and removes a
+//! probe file in the store (or the closest existing parent directory), so a
+//! directory the account cannot write to fails right away instead of at the first
+//! flush.
+//!
+//! Modes and groups are Unix concepts; `--chmod`/`--chgrp` are rejected on other
+//! platforms.
+
+use anyhow::{Context, Result};
+use std::path::Path;
+
+/// Directory bit making new entries inherit the directory's group
+const SETGID: u32 = 0o2000;
+
+/// Mode and group applied to every file and directory of a store
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct StorePermissions {
+    /// File mode; directories additionally get execute where read is granted
+    pub mode: Option<u32>,
+    /// Group name or numeric GID
+    pub group: Option<String>,
+}
+
+impl StorePermissions {
+    /// Permissions from `--chmod` (octal) and `--chgrp` values
+    pub fn new(chmod: Option<&str>, chgrp: Option<&str>) -> Result<Self> {
+        let permissions = Self {
+            mode: chmod.map(parse_mode).transpose()?,
+            group: chgrp.map(String::from),
+        };
+        if !cfg!(unix) && !permissions.is_empty() {
+            anyhow::bail!("--chmod and --chgrp are only supported on Unix systems");
+        }
+        if let Some(ref group) = permissions.group {
+            resolve_group(group)?;
+        }
+        Ok(permissions)
+    }
+
+    /// Whether nothing is changed
+    pub fn is_empty(&self) -> bool {
+        self.mode.is_none() && self.group.is_none()
+    }
+}
+
+/// Parse an octal file mode such as `660` or `0640`
+pub fn parse_mode(text: &str) -> Result<u32> {
+    let mode = u32::from_str_radix(text.trim(), 8)
+        .ok()
+        .filter(|mode| *mode <= 0o7777)
+        .with_context(|| format!("Invalid mode '{}': expected octal digits such as 660 or 0640", text))?;
+    if mode & 0o600 != 0o600 {
+        anyhow::bail!("Mode {:o} would keep the recorder from writing its own files; include 6 for the owner", mode);
+    }
+    Ok(mode)
+}
+
+/// Directory mode for a file mode: execute (search) wherever read is granted
+pub fn directory_mode(file_mode: u32) -> u32 {
+    file_mode | ((file_mode & 0o444) >> 2)
+}
+
+/// Group ID of a group name (from /etc/group) or numeric GID
+pub fn resolve_group(group: &str) -> Result<u32> {
+    if let Ok(gid) = group.parse() {
+        return Ok(gid);
+    }
+    let groups = std::fs::read_to_string("/etc/group").unwrap_or_default();
+    groups
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.split(':');
+            let name = fields.next()?;
+            let gid = fields.nth(1)?.parse().ok()?;
+            (name == group).then_some(gid)
+        })
+        .next()
+        .with_context(|| format!("Unknown group '{}' (not in /etc/group; pass the numeric GID instead)", group))
+}
+
+/// Apply `permissions` to `path` and everything below it; returns the number of entries changed
+///
+/// Missing paths are skipped, so this can run before a mirror or spool exists.
+pub fn apply_permissions(path: &Path, permissions: &StorePermissions) -> Result<usize> {
+    if permissions.is_empty() || !path.exists() {
+        return Ok(0);
+    }
+    let gid = permissions.group.as_deref().map(resolve_group).transpose()?;
+    let mut changed = 0;
+    apply_recursive(path, permissions.mode, gid, true, &mut changed)?;
+    Ok(changed)
+}
+
+/// Apply `permissions` to `path` alone, not to the entries below it
+pub fn apply_root_permissions(path: &Path, permissions: &StorePermissions) -> Result<()> {
+    if permissions.is_empty() || !path.exists() {
+        return Ok(());
+    }
+    let gid = permissions.group.as_deref().map(resolve_group).transpose()?;
+    apply_recursive(path, permissions.mode, gid, false, &mut 0)
+}
+
+#[cfg(unix)]
+fn apply_recursive(
+    path: &Path,
+    file_mode: Option<u32>,
+    gid: Option<u32>,
+    recurse: bool,
+    changed: &mut usize,
+) -> Result<()> {
+    use std::os::unix::fs::PermissionsExt;
+
+    // Entries removed meanwhile by a concurrent writer are skipped
+    let metadata = match std::fs::symlink_metadata(path) {
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(()),
+        metadata => metadata?,
+    };
+    // Links are left alone rather than followed out of the store
+    if metadata.file_type().is_symlink() {
+        return Ok(());
+    }
+    if let Some(gid) = gid {
+        std::os::unix::fs::chown(path, None, Some(gid))
+            .with_context(|| format!("Failed to change the group of {} to {}", path.display(), gid))?;
+    }
+    let mode = if metadata.is_dir() {
+        let setgid = if gid.is_some() { SETGID } else { 0 };
+        match file_mode {
+            Some(file_mode) => Some(directory_mode(file_mode) | setgid),
+            None if gid.is_some() => Some((metadata.permissions().mode() & 0o7777) | SETGID),
+            None => None,
+        }
+    } else {
+        file_mode
+    };
+    if let Some(mode) = mode {
+        std::fs::set_permissions(path, std::fs::Permissions::from_mode(mode))
+            .with_context(|| format!("Failed to set mode {:o} on {}", mode, path.display()))?;
+    }
+    *changed += 1;
+
+    if recurse && metadata.is_dir() {
+        for entry in std::fs::read_dir(path)? {
+            apply_recursive(&entry?.path(), file_mode, gid, true, changed)?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(not(unix))]
+fn apply_recursive(
+    _path: &Path,
+    _mode: Option<u32>,
+    _gid: Option<u32>,
+    _recurse: bool,
+    _changed: &mut usize,
+) -> Result<()> {
+    anyhow::bail!("--chmod and --chgrp are only supported on Unix systems")
+}
+
+/// Fail early if a store cannot be written at `store_path`
+///
+/// Creates and removes a probe file in the store, or in the closest existing
+/// parent directory if the store does not exist yet.
+pub fn check_writable(store_path: &Path) -> Result<()> {
+    let dir = store_path
+        .ancestors()
+        .find(|path| path.is_dir())
+        .unwrap_or_else(|| Path::new("."));
+    let probe = dir.join(format!(".lsl-write-test-{}", std::process::id()));
+    std::fs::write(&probe, b"")
+        .and_then(|_| std::fs::remove_file(&probe))
+        .with_context(|| {
+            format!(
+                "Cannot write to {} (needed for {}); check the directory's owner and permissions",
+                dir.display(),
+                store_path.display()
+            )
+        })
+}
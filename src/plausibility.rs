@@ -0,0 +1,295 @@
+//! Physical plausibility ranges checked while recording (`lsl-recorder --plausible-range`)
+//!
+//! A wrong gain setting on an amplifier records hours of saturated or scaled-up data
+//! that nobody notices until the analysis. With a plausibility range per stream,
+//! e.g. `±10 mV` for surface EMG, every sample outside the range is counted as it
+//! arrives and the first one on each channel is reported as
+//! `STATUS IMPLAUSIBLE (...)`, so the problem shows up during the first minute.
+//!
+//! A range is written `MIN..MAX` or `±LIMIT` (`+-LIMIT`), optionally followed by a
+//! voltage unit: `-10..10 mV`. With a unit, the bounds are converted to the unit each
+//! channel declares (`µV`, `mV`, `V`, ...); without one, or for channels without a
+//! voltage unit, they apply to the raw values. Non-finite values are not counted.
+//!
+//! Ranges are set per stream in the `[[streams]]` list of `lsl-toolbox.toml`
+//! (`plausible_range = "±10 mV"`), which lsl-multi-recorder passes to the recorder of
+//! that stream; lsl-multi-recorder also takes `--plausible-range` with a `STREAM:`
+//! prefix like `--rule`. When recording ends, the counts are stored as the stream's
+//! `plausibility` attribute:
+//!
+//! ```json
+//! {"range": "-10..10 mV", "samples": 120000, "implausible_samples": 35, "first_time": 81234.5,
+//!  "channels": [{"index": 0, "label": "EMG1", "min": -10000.0, "max": 10000.0,
+//!                "outside": 35, "observed_min": -812.4, "observed_max": 15022.0}]}
+//! ```
+
+use anyhow::{Context, Result};
+use serde_json::json;
+
+use crate::units::{unit_symbol, volt_scale};
+
+/// Name of the stream group attribute holding the plausibility counts
+pub const PLAUSIBILITY_ATTRIBUTE: &str = "plausibility";
+
+/// Bounds of physically plausible values, optionally in a voltage unit
+#[derive(Debug, Clone, PartialEq)]
+pub struct PlausibleRange {
+    pub min: f64,
+    pub max: f64,
+    /// Unit of the bounds; channels declaring another voltage unit get converted bounds
+    pub unit: Option<String>,
+}
+
+impl std::fmt::Display for PlausibleRange {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}..{}", self.min, self.max)?;
+        if let Some(ref unit) = self.unit {
+            write!(f, " {}", unit)?;
+        }
+        Ok(())
+    }
+}
+
+impl PlausibleRange {
+    /// Bounds for a channel declaring `channel_unit`
+    ///
+    /// Converted when both the range and the channel have a voltage unit, else unchanged.
+    pub fn bounds_in(&self, channel_unit: Option<&str>) -> (f64, f64) {
+        let from = self.unit.as_deref().and_then(volt_scale);
+        let to = channel_unit.and_then(volt_scale);
+        match (from, to) {
+            // Voltage units differ by powers of 1000; a rounded ratio keeps `±10 mV` at `±10000 µV`
+            (Some(from), Some(to)) if from >= to => {
+                let factor = (from / to).round();
+                (self.min * factor, self.max * factor)
+            }
+            (Some(from), Some(to)) => {
+                let divisor = (to / from).round();
+                (self.min / divisor, self.max / divisor)
+            }
+            _ => (self.min, self.max),
+        }
+    }
+}
+
+/// Parse a range such as `-10..10 mV`, `±10 mV` or `+-500`
+pub fn parse_plausible_range(text: &str) -> Result<PlausibleRange> {
+    let text = text.trim();
+    let (bounds, unit) = match text.split_once(char::is_whitespace) {
+        Some((bounds, unit)) => (bounds, Some(unit.trim())),
+        None => (text, None),
+    };
+    let number = |value: &str| {
+        value
+            .trim()
+            .parse::<f64>()
+            .ok()
+            .filter(|v| v.is_finite())
+            .with_context(|| format!("invalid bound '{}' in plausible range '{}'", value.trim(), text))
+    };
+    let (min, max) = match bounds.strip_prefix('±').or_else(|| bounds.strip_prefix("+-")) {
+        Some(limit) => {
+            let limit = number(limit)?.abs();
+            (-limit, limit)
+        }
+        None => {
+            let (min, max) = bounds
+                .split_once("..")
+                .with_context(|| format!("plausible range '{}' is not MIN..MAX or ±LIMIT", text))?;
+            (number(min)?, number(max)?)
+        }
+    };
+    if min >= max {
+        anyhow::bail!("plausible range '{}' is empty (minimum must be below maximum)", text);
+    }
+    if let Some(unit) = unit
+        && volt_scale(unit).is_none()
+    {
+        anyhow::bail!("unit '{}' of plausible range '{}' is not a voltage unit (V, mV, µV, nV)", unit, text);
+    }
+    Ok(PlausibleRange {
+        min,
+        max,
+        unit: unit.map(unit_symbol),
+    })
+}
+
+/// A plausible range limited to one stream of lsl-multi-recorder
+#[derive(Debug, Clone, PartialEq)]
+pub struct StreamRange {
+    /// Stream name or source ID the range is limited to
+    pub stream: Option<String>,
+    pub range: PlausibleRange,
+}
+
+impl StreamRange {
+    /// Whether the range applies to the stream recorded as group `stream_name`
+    pub fn applies_to(&self, stream_name: &str, source_id: &str) -> bool {
+        self.stream
+            .as_deref()
+            .is_none_or(|stream| stream == stream_name || stream == source_id)
+    }
+}
+
+impl std::fmt::Display for StreamRange {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.stream {
+            Some(ref stream) => write!(f, "{}: {}", stream, self.range),
+            None => write!(f, "{}", self.range),
+        }
+    }
+}
+
+/// Parse a range with an optional `STREAM:` prefix
+pub fn parse_stream_range(text: &str) -> Result<StreamRange> {
+    match text.split_once(':') {
+        Some((stream, range)) if !stream.trim().is_empty() => Ok(StreamRange {
+            stream: Some(stream.trim().to_string()),
+            range: parse_plausible_range(range)?,
+        }),
+        Some(_) => anyhow::bail!("missing stream name before ':' in plausible range '{}'", text.trim()),
+        None => Ok(StreamRange {
+            stream: None,
+            range: parse_plausible_range(text)?,
+        }),
+    }
+}
+
+/// First value of a channel outside its plausible range
+#[derive(Debug, Clone, PartialEq)]
+pub struct PlausibilityEvent {
+    pub channel: usize,
+    pub label: String,
+    pub value: f64,
+    pub min: f64,
+    pub max: f64,
+    /// Timestamp of the sample
+    pub time: f64,
+}
+
+impl std::fmt::Display for PlausibilityEvent {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}: {} outside {}..{} at {:.3}",
+            self.label, self.value, self.min, self.max, self.time
+        )
+    }
+}
+
+#[derive(Debug, Clone)]
+struct ChannelCheck {
+    label: String,
+    min: f64,
+    max: f64,
+    outside: u64,
+    observed_min: f64,
+    observed_max: f64,
+}
+
+/// Counts the samples of a stream that fall outside its plausible range
+#[derive(Debug, Clone)]
+pub struct PlausibilityCheck {
+    range: PlausibleRange,
+    channels: Vec<ChannelCheck>,
+    samples: u64,
+    implausible_samples: u64,
+    first_time: Option<f64>,
+}
+
+impl PlausibilityCheck {
+    /// Check for channels with these labels and declared units
+    pub fn new(range: PlausibleRange, labels: Vec<String>, units: &[Option<String>]) -> Self {
+        let channels = labels
+            .into_iter()
+            .enumerate()
+            .map(|(index, label)| {
+                let (min, max) = range.bounds_in(units.get(index).and_then(|unit| unit.as_deref()));
+                ChannelCheck {
+                    label,
+                    min,
+                    max,
+                    outside: 0,
+                    observed_min: f64::INFINITY,
+                    observed_max: f64::NEG_INFINITY,
+                }
+            })
+            .collect();
+        Self {
+            range,
+            channels,
+            samples: 0,
+            implausible_samples: 0,
+            first_time: None,
+        }
+    }
+
+    /// Check one sample; returns the channels that left their range for the first time
+    pub fn push<T: Copy + Into<f64>>(&mut self, time: f64, values: &[T]) -> Vec<PlausibilityEvent> {
+        let mut events = Vec::new();
+        let mut implausible = false;
+        self.samples += 1;
+        for (index, &value) in values.iter().enumerate().take(self.channels.len()) {
+            let value: f64 = value.into();
+            if !value.is_finite() {
+                continue;
+            }
+            let channel = &mut self.channels[index];
+            channel.observed_min = channel.observed_min.min(value);
+            channel.observed_max = channel.observed_max.max(value);
+            if (channel.min..=channel.max).contains(&value) {
+                continue;
+            }
+            implausible = true;
+            channel.outside += 1;
+            if channel.outside == 1 {
+                events.push(PlausibilityEvent {
+                    channel: index,
+                    label: channel.label.clone(),
+                    value,
+                    min: channel.min,
+                    max: channel.max,
+                    time,
+                });
+            }
+        }
+        if implausible {
+            self.implausible_samples += 1;
+            self.first_time.get_or_insert(time);
+        }
+        events
+    }
+
+    /// Samples with at least one channel outside its range
+    pub fn implausible_samples(&self) -> u64 {
+        self.implausible_samples
+    }
+
+    /// Counts to store as the `plausibility` attribute
+    pub fn summary(&self) -> serde_json::Value {
+        let channels: Vec<serde_json::Value> = self
+            .channels
+            .iter()
+            .enumerate()
+            .map(|(index, channel)| {
+                let observed = channel.observed_min <= channel.observed_max;
+                json!({
+                    "index": index,
+                    "label": channel.label,
+                    "min": channel.min,
+                    "max": channel.max,
+                    "outside": channel.outside,
+                    "observed_min": observed.then_some(channel.observed_min),
+                    "observed_max": observed.then_some(channel.observed_max),
+                })
+            })
+            .collect();
+        json!({
+            "range": self.range.to_string(),
+            "samples": self.samples,
+            "implausible_samples": self.implausible_samples,
+            "first_time": self.first_time,
+            "channels": channels,
+        })
+    }
+}
@@ -0,0 +1,142 @@
+//! Preflight check of a recording setup (`lsl-recorder --preflight`)
+//!
+//! Before the subject arrives, `lsl-recorder --preflight` resolves the stream it would
+//! record, pulls samples for a few seconds and checks the output path without
+//! creating a store. The report is judged here:
+//!
+//! - the stream must deliver samples, and a regular stream must run within
+//!   `max_rate_error_percent` of its nominal rate
+//! - the store must be writable and free of another subject's or session's data
+//! - the free space must hold the planned recording (`--duration`); without one,
+//!   room for less than `min_free_hours` is only a warning
+//!
+//! The data rate counts the stored values and timestamp of each sample before
+//! compression, so it is an upper bound for most signals.
+
+use std::path::PathBuf;
+
+use crate::spotcheck::Verdict;
+
+const GIB: f64 = 1024.0 * 1024.0 * 1024.0;
+
+/// Limits a preflight check has to meet for a GO
+#[derive(Debug, Clone)]
+pub struct PreflightCriteria {
+    /// Largest deviation of the measured from the nominal sample rate (percent)
+    pub max_rate_error_percent: f64,
+    /// Hours of recording the free space should hold when no duration is planned
+    pub min_free_hours: f64,
+}
+
+impl Default for PreflightCriteria {
+    fn default() -> Self {
+        Self {
+            max_rate_error_percent: 5.0,
+            min_free_hours: 2.0,
+        }
+    }
+}
+
+/// What a preflight check found out about the stream and the output
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct PreflightReport {
+    pub stream: String,
+    /// Why the stream could not be checked, e.g. it did not resolve
+    pub stream_error: Option<String>,
+    pub channels: usize,
+    pub channel_format: String,
+    pub nominal_rate: f64,
+    /// Samples pulled during the check
+    pub samples: u64,
+    /// Seconds the check pulled samples for
+    pub seconds: f64,
+    pub first_timestamp: Option<f64>,
+    pub last_timestamp: Option<f64>,
+    /// Average bytes stored per sample: the values and the timestamp
+    pub sample_bytes: f64,
+    /// Store the recording would write
+    pub output: PathBuf,
+    /// Reasons the store cannot be written (permissions, another session's data)
+    pub output_errors: Vec<String>,
+    /// Free space at the output, if it could be determined
+    pub free_bytes: Option<u64>,
+    /// Planned recording length in seconds (`--duration`)
+    pub planned_seconds: Option<f64>,
+}
+
+impl PreflightReport {
+    /// Sample rate from the timestamps of the pulled samples
+    pub fn measured_rate(&self) -> Option<f64> {
+        let span = self.last_timestamp? - self.first_timestamp?;
+        (self.samples > 1 && span > 0.0).then(|| (self.samples - 1) as f64 / span)
+    }
+
+    /// Bytes per hour of recording before compression; irregular streams use the measured rate
+    pub fn bytes_per_hour(&self) -> Option<f64> {
+        let rate = if self.nominal_rate > 0.0 {
+            self.nominal_rate
+        } else {
+            self.measured_rate()?
+        };
+        Some(rate * self.sample_bytes * 3600.0)
+    }
+
+    /// Hours of recording the free space holds
+    pub fn hours_of_space(&self) -> Option<f64> {
+        let per_hour = self.bytes_per_hour().filter(|bytes| *bytes > 0.0)?;
+        Some(self.free_bytes? as f64 / per_hour)
+    }
+
+    /// Judge the report
+    pub fn evaluate(&self, criteria: &PreflightCriteria) -> Verdict {
+        let mut verdict = Verdict::default();
+        if let Some(ref error) = self.stream_error {
+            verdict.problems.push(error.clone());
+        } else if self.samples == 0 {
+            verdict.problems.push(format!(
+                "Stream '{}' delivered no samples within {:.1}s",
+                self.stream, self.seconds
+            ));
+        } else if self.nominal_rate > 0.0
+            && let Some(rate) = self.measured_rate()
+        {
+            let error = (rate / self.nominal_rate - 1.0) * 100.0;
+            if error.abs() > criteria.max_rate_error_percent {
+                verdict.problems.push(format!(
+                    "Stream '{}' runs at {:.1} Hz, {:+.1}% off its nominal {} Hz",
+                    self.stream, rate, error, self.nominal_rate
+                ));
+            }
+        }
+
+        verdict.problems.extend(self.output_errors.iter().cloned());
+
+        match (self.free_bytes, self.bytes_per_hour(), self.planned_seconds) {
+            (None, _, _) => verdict
+                .warnings
+                .push(format!("Free space at {} is unknown", self.output.display())),
+            (Some(free), Some(per_hour), Some(planned)) if per_hour * planned / 3600.0 > free as f64 => {
+                verdict.problems.push(format!(
+                    "{:.1} GiB free at {}, the planned {:.0}s need about {:.1} GiB",
+                    free as f64 / GIB,
+                    self.output.display(),
+                    planned,
+                    per_hour * planned / 3600.0 / GIB
+                ));
+            }
+            (Some(_), Some(_), None) => {
+                if let Some(hours) = self.hours_of_space()
+                    && hours < criteria.min_free_hours
+                {
+                    verdict.warnings.push(format!(
+                        "The free space at {} holds only about {:.1} hours of recording",
+                        self.output.display(),
+                        hours
+                    ));
+                }
+            }
+            _ => {}
+        }
+        verdict
+    }
+}
@@ -0,0 +1,288 @@
+//! Live signal quality checks while recording (`lsl-recorder --quality`)
+//!
+//! A dead electrode found after a two-hour session costs the session, so the recorder
+//! can watch every channel as samples arrive:
+//!
+//! - **RMS** over rolling windows of `window` seconds, taken about the window mean so a
+//!   DC offset does not hide a dead channel
+//! - **Flatline**: the value has not changed for `flatline_seconds`
+//! - **Clipping**: at least `clip_fraction` of a window's samples sit at the ADC limit,
+//!   i.e. at or beyond `clip_level` (the range of integer formats by default), or for
+//!   float streams without a level, in runs of identical values at the window's
+//!   maximum or minimum
+//!
+//! Each problem is reported once when it starts and once when it ends (see
+//! [`QualityEvent`]). When recording ends, the per-channel figures are stored as the
+//! stream's `quality` attribute:
+//!
+//! ```json
+//! {"window_s": 1.0, "windows": 7200, "flatline_seconds": 2.0, "clip_fraction": 0.01,
+//!  "clip_level": 32767.0,
+//!  "channels": [{"index": 0, "label": "C3", "rms_mean": 12.1, "rms_min": 8.4,
+//!                "rms_max": 40.2, "flatline_s": 0.0, "clipped_fraction": 0.0, "warnings": 0}]}
+//! ```
+
+use serde_json::json;
+
+/// Name of the stream group attribute holding the quality summary
+pub const QUALITY_ATTRIBUTE: &str = "quality";
+
+/// Thresholds of the quality checks
+#[derive(Debug, Clone)]
+pub struct QualityOptions {
+    /// Length of the rolling RMS and clipping window in seconds
+    pub window: f64,
+    /// Seconds without a change after which a channel is reported flat
+    pub flatline_seconds: f64,
+    /// Fraction of a window's samples at the ADC limit reported as clipping
+    pub clip_fraction: f64,
+    /// Absolute value at which samples count as clipped (format range of integer streams if `None`)
+    pub clip_level: Option<f64>,
+}
+
+impl Default for QualityOptions {
+    fn default() -> Self {
+        Self {
+            window: 1.0,
+            flatline_seconds: 2.0,
+            clip_fraction: 0.01,
+            clip_level: None,
+        }
+    }
+}
+
+/// A problem found on a channel
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum QualityIssue {
+    Flatline,
+    Clipping,
+}
+
+impl QualityIssue {
+    pub fn name(&self) -> &'static str {
+        match self {
+            QualityIssue::Flatline => "flatline",
+            QualityIssue::Clipping => "clipping",
+        }
+    }
+}
+
+/// A channel problem that started (`active`) or ended
+#[derive(Debug, Clone, PartialEq)]
+pub struct QualityEvent {
+    pub channel: usize,
+    pub label: String,
+    pub issue: QualityIssue,
+    pub active: bool,
+    /// Seconds without change (flatline) or fraction of clipped samples (clipping)
+    pub amount: f64,
+}
+
+impl std::fmt::Display for QualityEvent {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match (self.issue, self.active) {
+            (QualityIssue::Flatline, true) => write!(f, "{}: flatline for {:.1}s", self.label, self.amount),
+            (QualityIssue::Clipping, true) => {
+                write!(f, "{}: clipping {:.1}% of samples", self.label, self.amount * 100.0)
+            }
+            (issue, false) => write!(f, "{}: {} ended", self.label, issue.name()),
+        }
+    }
+}
+
+/// Running figures of one channel
+#[derive(Debug, Clone, Default)]
+struct ChannelQuality {
+    last: Option<f64>,
+    // Consecutive samples equal to their predecessor
+    unchanged: u64,
+    flat: bool,
+    flat_samples: u64,
+    clipping: bool,
+    clipped_samples: u64,
+    rms_sum: f64,
+    rms_min: f64,
+    rms_max: f64,
+    warnings: u64,
+}
+
+/// Rolling quality checks over the samples of one stream
+#[derive(Debug, Clone)]
+pub struct QualityMonitor {
+    options: QualityOptions,
+    rate: f64,
+    labels: Vec<String>,
+    clip_level: Option<f64>,
+    window_samples: usize,
+    flat_samples: u64,
+    channels: Vec<ChannelQuality>,
+    // Samples of the current window, interleaved
+    window: Vec<f64>,
+    windows: u64,
+    samples: u64,
+}
+
+impl QualityMonitor {
+    /// Checks for a regular stream with these channel labels at `nominal_srate` Hz
+    ///
+    /// `format_clip_level` is the ADC limit implied by the channel format (integer
+    /// streams); `options.clip_level` takes precedence.
+    pub fn new(labels: Vec<String>, nominal_srate: f64, format_clip_level: Option<f64>, options: &QualityOptions) -> Self {
+        let rate = nominal_srate.max(f64::MIN_POSITIVE);
+        Self {
+            clip_level: options.clip_level.or(format_clip_level),
+            window_samples: ((options.window * rate).round() as usize).max(2),
+            flat_samples: ((options.flatline_seconds * rate).round() as u64).max(1),
+            channels: vec![ChannelQuality::default(); labels.len()],
+            window: Vec::new(),
+            windows: 0,
+            samples: 0,
+            options: options.clone(),
+            rate,
+            labels,
+        }
+    }
+
+    /// Check one sample; returns the problems that started or ended with it
+    pub fn push<T: Copy + Into<f64>>(&mut self, values: &[T]) -> Vec<QualityEvent> {
+        let mut events = Vec::new();
+        self.samples += 1;
+        for (index, &value) in values.iter().enumerate().take(self.channels.len()) {
+            let value: f64 = value.into();
+            let channel = &mut self.channels[index];
+            if channel.last == Some(value) {
+                channel.unchanged += 1;
+            } else {
+                channel.unchanged = 0;
+                if channel.flat {
+                    channel.flat = false;
+                    events.push(self.event(index, QualityIssue::Flatline, false, 0.0));
+                }
+            }
+            let channel = &mut self.channels[index];
+            channel.last = Some(value);
+            if channel.flat {
+                channel.flat_samples += 1;
+            } else if channel.unchanged >= self.flat_samples {
+                channel.flat = true;
+                channel.flat_samples += channel.unchanged;
+                channel.warnings += 1;
+                let seconds = channel.unchanged as f64 / self.rate;
+                events.push(self.event(index, QualityIssue::Flatline, true, seconds));
+            }
+            self.window.push(value);
+        }
+
+        if self.window.len() >= self.window_samples * self.channels.len() {
+            self.finish_window(&mut events);
+        }
+        events
+    }
+
+    /// Close the current window: RMS and clipping of every channel
+    fn finish_window(&mut self, events: &mut Vec<QualityEvent>) {
+        let count = self.channels.len();
+        let samples = self.window.len() / count.max(1);
+        self.windows += 1;
+        for index in 0..count {
+            let values: Vec<f64> = self.window.iter().skip(index).step_by(count).copied().collect();
+            let rms = window_rms(&values);
+            let clipped = self.clipped_samples(&values);
+            let fraction = clipped as f64 / samples as f64;
+
+            let first = self.windows == 1;
+            let channel = &mut self.channels[index];
+            channel.rms_sum += rms;
+            channel.rms_min = if first { rms } else { channel.rms_min.min(rms) };
+            channel.rms_max = if first { rms } else { channel.rms_max.max(rms) };
+            channel.clipped_samples += clipped;
+
+            let clipping = fraction >= self.options.clip_fraction && clipped > 0;
+            if clipping != channel.clipping {
+                channel.clipping = clipping;
+                if clipping {
+                    channel.warnings += 1;
+                }
+                events.push(self.event(index, QualityIssue::Clipping, clipping, fraction));
+            }
+        }
+        self.window.clear();
+    }
+
+    /// Samples of one channel's window at the ADC limit
+    fn clipped_samples(&self, values: &[f64]) -> u64 {
+        if let Some(level) = self.clip_level {
+            return values.iter().filter(|v| v.abs() >= level).count() as u64;
+        }
+        // Without a known limit: runs of identical samples at the window's extremes
+        let max = values.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+        let min = values.iter().copied().fold(f64::INFINITY, f64::min);
+        if !(max > min) {
+            return 0;
+        }
+        values
+            .iter()
+            .enumerate()
+            .filter(|&(i, &v)| {
+                let repeated = (i > 0 && values[i - 1] == v) || values.get(i + 1) == Some(&v);
+                repeated && (v == max || v == min)
+            })
+            .count() as u64
+    }
+
+    fn event(&self, channel: usize, issue: QualityIssue, active: bool, amount: f64) -> QualityEvent {
+        QualityEvent {
+            channel,
+            label: self.labels[channel].clone(),
+            issue,
+            active,
+            amount,
+        }
+    }
+
+    /// Windows completed so far
+    pub fn window_count(&self) -> u64 {
+        self.windows
+    }
+
+    /// Per-channel summary for the `quality` attribute
+    pub fn summary(&self) -> serde_json::Value {
+        let windows = self.windows.max(1) as f64;
+        let samples = self.samples.max(1) as f64;
+        let channels: Vec<serde_json::Value> = self
+            .channels
+            .iter()
+            .enumerate()
+            .map(|(index, channel)| {
+                let measured = self.windows > 0;
+                json!({
+                    "index": index,
+                    "label": self.labels[index],
+                    "rms_mean": measured.then(|| channel.rms_sum / windows),
+                    "rms_min": measured.then_some(channel.rms_min),
+                    "rms_max": measured.then_some(channel.rms_max),
+                    "flatline_s": channel.flat_samples as f64 / self.rate,
+                    "clipped_fraction": channel.clipped_samples as f64 / samples,
+                    "warnings": channel.warnings,
+                })
+            })
+            .collect();
+        json!({
+            "window_s": self.options.window,
+            "windows": self.windows,
+            "flatline_seconds": self.options.flatline_seconds,
+            "clip_fraction": self.options.clip_fraction,
+            "clip_level": self.clip_level,
+            "channels": channels,
+        })
+    }
+}
+
+/// RMS about the mean
+pub(crate) fn window_rms(values: &[f64]) -> f64 {
+    if values.is_empty() {
+        return 0.0;
+    }
+    let mean = values.iter().sum::<f64>() / values.len() as f64;
+    (values.iter().map(|v| (v - mean).powi(2)).sum::<f64>() / values.len() as f64).sqrt()
+}
@@ -0,0 +1,433 @@
+//! Small expression language for filtering sessions
+//!
+//! Expressions combine field comparisons with `&&`, `||`, `!` and parentheses:
+//!
+//! ```text
+//! subject == "P00*" && duration > 600 && has_stream("EEG")
+//! !(notes == "*pilot*") || streams >= 3
+//! ```
+//!
+//! String equality (`==`, `!=`) supports `*` and `?` wildcards. Missing fields never
+//! compare equal to anything, so `subject != "P001"` also matches sessions without a
+//! subject.
+
+use anyhow::{bail, Result};
+
+use crate::session::SessionSummary;
+
+/// Fields that can be used in expressions and selected for output
+pub const FIELDS: &[&str] = &[
+    "path",
+    "name",
+    "subject",
+    "session_id",
+    "notes",
+    "recorded_at",
+    "recorder_version",
+    "duration",
+    "streams",
+    "stream_names",
+    "samples",
+];
+
+/// Functions that can be called in expressions
+const FUNCTIONS: &[&str] = &["has_stream", "has_type"];
+
+/// Value of a field or literal during evaluation
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    Str(String),
+    Num(f64),
+    Bool(bool),
+    Null,
+}
+
+impl Value {
+    fn truthy(&self) -> bool {
+        match self {
+            Value::Bool(b) => *b,
+            Value::Num(n) => *n != 0.0,
+            Value::Str(s) => !s.is_empty(),
+            Value::Null => false,
+        }
+    }
+}
+
+impl std::fmt::Display for Value {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Value::Str(s) => write!(f, "{}", s),
+            Value::Num(n) if n.fract() == 0.0 && n.abs() < 1e15 => write!(f, "{}", *n as i64),
+            Value::Num(n) => write!(f, "{:.3}", n),
+            Value::Bool(b) => write!(f, "{}", b),
+            Value::Null => write!(f, "-"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum CompareOp {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+#[derive(Debug, Clone)]
+enum Expr {
+    Literal(Value),
+    Field(String),
+    Call(String, Vec<Expr>),
+    Not(Box<Expr>),
+    And(Box<Expr>, Box<Expr>),
+    Or(Box<Expr>, Box<Expr>),
+    Compare(Box<Expr>, CompareOp, Box<Expr>),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Ident(String),
+    Str(String),
+    Num(f64),
+    Op(CompareOp),
+    And,
+    Or,
+    Not,
+    LParen,
+    RParen,
+    Comma,
+}
+
+/// A parsed query that can be evaluated against session summaries
+#[derive(Debug, Clone)]
+pub struct Query {
+    expr: Option<Expr>,
+}
+
+impl Query {
+    /// Parse an expression; an empty string matches every session
+    pub fn parse(input: &str) -> Result<Self> {
+        let tokens = tokenize(input)?;
+        if tokens.is_empty() {
+            return Ok(Self { expr: None });
+        }
+
+        let mut parser = Parser { tokens, pos: 0 };
+        let expr = parser.parse_or()?;
+        if parser.pos < parser.tokens.len() {
+            bail!("Unexpected token {:?} in query", parser.tokens[parser.pos]);
+        }
+        Ok(Self { expr: Some(expr) })
+    }
+
+    /// Check whether a session matches this query
+    pub fn matches(&self, session: &SessionSummary) -> bool {
+        self.expr
+            .as_ref()
+            .is_none_or(|expr| evaluate(expr, session).truthy())
+    }
+}
+
+/// Look up a field of a session (see [`FIELDS`])
+pub fn field_value(session: &SessionSummary, field: &str) -> Value {
+    let opt_str = |v: &Option<String>| v.clone().map(Value::Str).unwrap_or(Value::Null);
+    match field {
+        "path" => Value::Str(session.path.display().to_string()),
+        "name" => Value::Str(session.name()),
+        "subject" => opt_str(&session.subject),
+        "session_id" => opt_str(&session.session_id),
+        "notes" => opt_str(&session.notes),
+        "recorded_at" => opt_str(&session.recorded_at),
+        "recorder_version" => opt_str(&session.recorder_version),
+        "duration" => session.duration().map(Value::Num).unwrap_or(Value::Null),
+        "streams" => Value::Num(session.streams.len() as f64),
+        "stream_names" => Value::Str(
+            session
+                .streams
+                .iter()
+                .map(|s| s.name.as_str())
+                .collect::<Vec<_>>()
+                .join(","),
+        ),
+        "samples" => Value::Num(session.total_samples() as f64),
+        _ => Value::Null,
+    }
+}
+
+/// Match `text` against a pattern with `*` (any run) and `?` (any single char)
+pub fn glob_match(pattern: &str, text: &str) -> bool {
+    let p: Vec<char> = pattern.chars().collect();
+    let t: Vec<char> = text.chars().collect();
+    let (mut pi, mut ti) = (0, 0);
+    let mut backtrack: Option<(usize, usize)> = None;
+
+    while ti < t.len() {
+        if pi < p.len() && (p[pi] == '?' || p[pi] == t[ti]) {
+            pi += 1;
+            ti += 1;
+        } else if pi < p.len() && p[pi] == '*' {
+            backtrack = Some((pi, ti));
+            pi += 1;
+        } else if let Some((star_pi, star_ti)) = backtrack {
+            pi = star_pi + 1;
+            ti = star_ti + 1;
+            backtrack = Some((star_pi, star_ti + 1));
+        } else {
+            return false;
+        }
+    }
+    p[pi..].iter().all(|&c| c == '*')
+}
+
+fn evaluate(expr: &Expr, session: &SessionSummary) -> Value {
+    match expr {
+        Expr::Literal(v) => v.clone(),
+        Expr::Field(name) => field_value(session, name),
+        Expr::Not(inner) => Value::Bool(!evaluate(inner, session).truthy()),
+        Expr::And(a, b) => Value::Bool(evaluate(a, session).truthy() && evaluate(b, session).truthy()),
+        Expr::Or(a, b) => Value::Bool(evaluate(a, session).truthy() || evaluate(b, session).truthy()),
+        Expr::Compare(a, op, b) => {
+            Value::Bool(compare(&evaluate(a, session), *op, &evaluate(b, session)))
+        }
+        Expr::Call(name, args) => {
+            let pattern = match args.first().map(|a| evaluate(a, session)) {
+                Some(Value::Str(s)) => s,
+                Some(other) => other.to_string(),
+                None => return Value::Bool(false),
+            };
+            let found = match name.as_str() {
+                "has_stream" => session.streams.iter().any(|s| glob_match(&pattern, &s.name)),
+                "has_type" => session
+                    .streams
+                    .iter()
+                    .any(|s| s.stream_type.as_deref().is_some_and(|t| glob_match(&pattern, t))),
+                _ => false,
+            };
+            Value::Bool(found)
+        }
+    }
+}
+
+fn compare(left: &Value, op: CompareOp, right: &Value) -> bool {
+    let equal = match (left, right) {
+        (Value::Null, _) | (_, Value::Null) => None,
+        (Value::Str(l), Value::Str(r)) => Some(glob_match(r, l)),
+        (Value::Num(l), Value::Num(r)) => Some(l == r),
+        (Value::Bool(l), Value::Bool(r)) => Some(l == r),
+        (Value::Num(l), Value::Str(r)) | (Value::Str(r), Value::Num(l)) => {
+            Some(r.parse::<f64>().is_ok_and(|r| r == *l))
+        }
+        _ => Some(false),
+    };
+
+    match op {
+        CompareOp::Eq => equal == Some(true),
+        CompareOp::Ne => equal != Some(true),
+        _ => {
+            let ordering = match (left, right) {
+                (Value::Num(l), Value::Num(r)) => l.partial_cmp(r),
+                (Value::Str(l), Value::Str(r)) => Some(l.cmp(r)),
+                _ => None,
+            };
+            match ordering {
+                Some(ord) => match op {
+                    CompareOp::Lt => ord.is_lt(),
+                    CompareOp::Le => ord.is_le(),
+                    CompareOp::Gt => ord.is_gt(),
+                    CompareOp::Ge => ord.is_ge(),
+                    CompareOp::Eq | CompareOp::Ne => unreachable!(),
+                },
+                None => false,
+            }
+        }
+    }
+}
+
+fn tokenize(input: &str) -> Result<Vec<Token>> {
+    let chars: Vec<char> = input.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+
+    while i < chars.len() {
+        let c = chars[i];
+        let next = chars.get(i + 1).copied();
+        match c {
+            _ if c.is_whitespace() => i += 1,
+            '(' => {
+                tokens.push(Token::LParen);
+                i += 1;
+            }
+            ')' => {
+                tokens.push(Token::RParen);
+                i += 1;
+            }
+            ',' => {
+                tokens.push(Token::Comma);
+                i += 1;
+            }
+            '&' if next == Some('&') => {
+                tokens.push(Token::And);
+                i += 2;
+            }
+            '|' if next == Some('|') => {
+                tokens.push(Token::Or);
+                i += 2;
+            }
+            '=' if next == Some('=') => {
+                tokens.push(Token::Op(CompareOp::Eq));
+                i += 2;
+            }
+            '!' if next == Some('=') => {
+                tokens.push(Token::Op(CompareOp::Ne));
+                i += 2;
+            }
+            '!' => {
+                tokens.push(Token::Not);
+                i += 1;
+            }
+            '<' | '>' => {
+                let with_eq = next == Some('=');
+                tokens.push(Token::Op(match (c, with_eq) {
+                    ('<', false) => CompareOp::Lt,
+                    ('<', true) => CompareOp::Le,
+                    ('>', false) => CompareOp::Gt,
+                    _ => CompareOp::Ge,
+                }));
+                i += if with_eq { 2 } else { 1 };
+            }
+            '"' | '\'' => {
+                let quote = c;
+                let start = i + 1;
+                let Some(len) = chars[start..].iter().position(|&ch| ch == quote) else {
+                    bail!("Unterminated string starting at position {}", i);
+                };
+                tokens.push(Token::Str(chars[start..start + len].iter().collect()));
+                i = start + len + 1;
+            }
+            _ if c.is_ascii_digit() || (c == '-' && next.is_some_and(|n| n.is_ascii_digit())) => {
+                let start = i;
+                i += 1;
+                while i < chars.len() && (chars[i].is_ascii_digit() || chars[i] == '.') {
+                    i += 1;
+                }
+                let text: String = chars[start..i].iter().collect();
+                let value = text
+                    .parse::<f64>()
+                    .map_err(|_| anyhow::anyhow!("Invalid number '{}' in query", text))?;
+                tokens.push(Token::Num(value));
+            }
+            _ if c.is_alphabetic() || c == '_' => {
+                let start = i;
+                while i < chars.len() && (chars[i].is_alphanumeric() || chars[i] == '_') {
+                    i += 1;
+                }
+                tokens.push(Token::Ident(chars[start..i].iter().collect()));
+            }
+            _ => bail!("Unexpected character '{}' at position {}", c, i),
+        }
+    }
+
+    Ok(tokens)
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        token
+    }
+
+    fn expect(&mut self, expected: Token) -> Result<()> {
+        match self.next() {
+            Some(ref token) if *token == expected => Ok(()),
+            Some(token) => bail!("Expected {:?} but found {:?} in query", expected, token),
+            None => bail!("Expected {:?} but query ended", expected),
+        }
+    }
+
+    fn parse_or(&mut self) -> Result<Expr> {
+        let mut left = self.parse_and()?;
+        while self.peek() == Some(&Token::Or) {
+            self.pos += 1;
+            left = Expr::Or(Box::new(left), Box::new(self.parse_and()?));
+        }
+        Ok(left)
+    }
+
+    fn parse_and(&mut self) -> Result<Expr> {
+        let mut left = self.parse_unary()?;
+        while self.peek() == Some(&Token::And) {
+            self.pos += 1;
+            left = Expr::And(Box::new(left), Box::new(self.parse_unary()?));
+        }
+        Ok(left)
+    }
+
+    fn parse_unary(&mut self) -> Result<Expr> {
+        if self.peek() == Some(&Token::Not) {
+            self.pos += 1;
+            return Ok(Expr::Not(Box::new(self.parse_unary()?)));
+        }
+        if self.peek() == Some(&Token::LParen) {
+            self.pos += 1;
+            let inner = self.parse_or()?;
+            self.expect(Token::RParen)?;
+            return Ok(inner);
+        }
+        self.parse_comparison()
+    }
+
+    fn parse_comparison(&mut self) -> Result<Expr> {
+        let left = self.parse_operand()?;
+        if let Some(Token::Op(op)) = self.peek().cloned() {
+            self.pos += 1;
+            let right = self.parse_operand()?;
+            return Ok(Expr::Compare(Box::new(left), op, Box::new(right)));
+        }
+        Ok(left)
+    }
+
+    fn parse_operand(&mut self) -> Result<Expr> {
+        match self.next() {
+            Some(Token::Str(s)) => Ok(Expr::Literal(Value::Str(s))),
+            Some(Token::Num(n)) => Ok(Expr::Literal(Value::Num(n))),
+            Some(Token::Ident(name)) if name == "true" => Ok(Expr::Literal(Value::Bool(true))),
+            Some(Token::Ident(name)) if name == "false" => Ok(Expr::Literal(Value::Bool(false))),
+            Some(Token::Ident(name)) if self.peek() == Some(&Token::LParen) => {
+                if !FUNCTIONS.contains(&name.as_str()) {
+                    bail!("Unknown function '{}' (available: {})", name, FUNCTIONS.join(", "));
+                }
+                self.pos += 1;
+                let mut args = Vec::new();
+                if self.peek() != Some(&Token::RParen) {
+                    args.push(self.parse_operand()?);
+                    while self.peek() == Some(&Token::Comma) {
+                        self.pos += 1;
+                        args.push(self.parse_operand()?);
+                    }
+                }
+                self.expect(Token::RParen)?;
+                Ok(Expr::Call(name, args))
+            }
+            Some(Token::Ident(name)) => {
+                if !FIELDS.contains(&name.as_str()) {
+                    bail!("Unknown field '{}' (available: {})", name, FIELDS.join(", "));
+                }
+                Ok(Expr::Field(name))
+            }
+            Some(token) => bail!("Unexpected token {:?} in query", token),
+            None => bail!("Query ended unexpectedly"),
+        }
+    }
+}
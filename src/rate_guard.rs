@@ -0,0 +1,107 @@
+//! Guard against streams whose actual rate far exceeds their nominal rate (`--max-rate-factor`)
+//!
+//! A misconfigured device or a duplicated outlet can deliver many times the samples
+//! its stream info announces, which silently corrupts every rate-based analysis.
+//! The recorder measures the rate from LSL timestamps over windows of
+//! [`RateGuard::WINDOW_SECS`] seconds and aborts (or, with `--allow-rate-overrun`,
+//! warns) once a window exceeds `nominal rate × factor`. Measuring on timestamps
+//! rather than arrival times means draining a backlog after STOP/START does not
+//! count as an overrun.
+
+use anyhow::Result;
+use std::io::Write;
+
+/// Parse `--max-rate-factor`: `0` disables the guard, anything else must be at least 1
+pub fn parse_rate_factor(text: &str) -> Result<f64> {
+    let factor: f64 = text
+        .trim()
+        .parse()
+        .map_err(|_| anyhow::anyhow!("invalid rate factor '{}'", text.trim()))?;
+    if factor == 0.0 || (factor >= 1.0 && factor.is_finite()) {
+        Ok(factor)
+    } else {
+        anyhow::bail!("rate factor must be 0 (off) or at least 1, got {}", factor)
+    }
+}
+
+/// Measures a stream's rate in windows and compares it with the nominal rate
+#[derive(Debug, Clone)]
+pub struct RateGuard {
+    nominal_srate: f64,
+    max_factor: f64,
+    warn_only: bool,
+    window_start: Option<f64>,
+    window_samples: u64,
+    measured: Option<f64>,
+}
+
+impl RateGuard {
+    /// Length of a measurement window in LSL seconds
+    pub const WINDOW_SECS: f64 = 2.0;
+
+    /// Guard for a stream of `nominal_srate` Hz; a factor of 0 or an irregular stream disables it
+    pub fn new(nominal_srate: f64, max_factor: f64, warn_only: bool) -> Self {
+        Self {
+            nominal_srate,
+            max_factor,
+            warn_only,
+            window_start: None,
+            window_samples: 0,
+            measured: None,
+        }
+    }
+
+    /// Rate of the last complete window (Hz)
+    pub fn measured_rate(&self) -> Option<f64> {
+        self.measured
+    }
+
+    /// Count a sample with timestamp `ts`; fails when a window exceeds the limit and overruns abort
+    pub fn check(&mut self, ts: f64, quiet: bool) -> Result<()> {
+        // Irregular streams have no nominal rate to compare against
+        if self.nominal_srate <= 0.0 || self.max_factor <= 0.0 {
+            return Ok(());
+        }
+
+        let Some(start) = self.window_start else {
+            self.window_start = Some(ts);
+            self.window_samples = 0;
+            return Ok(());
+        };
+
+        self.window_samples += 1;
+        let span = ts - start;
+        if span < Self::WINDOW_SECS {
+            return Ok(());
+        }
+
+        let measured = self.window_samples as f64 / span;
+        self.measured = Some(measured);
+        self.window_start = Some(ts);
+        self.window_samples = 0;
+
+        let limit = self.nominal_srate * self.max_factor;
+        if measured <= limit {
+            return Ok(());
+        }
+
+        if self.warn_only {
+            if !quiet {
+                eprintln!(
+                    "Warning: measured rate {:.1}Hz exceeds {:.1}x nominal rate ({:.1}Hz)",
+                    measured, self.max_factor, self.nominal_srate
+                );
+            }
+            Ok(())
+        } else {
+            println!("STATUS RATE_EXCEEDED ({:.1}Hz)", measured);
+            std::io::stdout().flush().ok();
+            Err(anyhow::anyhow!(
+                "Measured rate {:.1}Hz exceeds {:.1}x nominal rate ({:.1}Hz); use --allow-rate-overrun to record anyway",
+                measured,
+                self.max_factor,
+                self.nominal_srate
+            ))
+        }
+    }
+}
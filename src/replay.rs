@@ -0,0 +1,253 @@
+//! Timing and reading of batch, live and multi-stream replays
+//!
+//! `lsl-replay --batch` feeds a recording through once, for regression tests of
+//! online-analysis algorithms. Samples keep their recorded spacing: each one is
+//! stamped with the replay clock origin plus its offset from the first recorded
+//! sample, independent of the playback speed, so the consumer sees the
+//! recording's own time axis. The offset between pushed and recorded timestamps
+//! is published in the stream description.
+//!
+//! Pacing is separate from stamping. With real-time pacing a sample is due at
+//! its recorded offset divided by the speed; without it (`--no-realtime`) every
+//! sample is due immediately and the outlet buffer, sized to hold the whole
+//! recording, absorbs whatever the consumer has not pulled yet.
+//!
+//! Samples are read from the store in blocks of [`DEFAULT_BLOCK_SAMPLES`] (or
+//! `--block-samples`) by a background thread that stays up to
+//! [`DEFAULT_PREFETCH_BLOCKS`] blocks ahead, so pushing never waits for the disk
+//! and the store is not touched once per sample.
+//!
+//! Pushing one sample at a time means one sleep and one outlet call per sample,
+//! which at kHz rates costs a core and lets scheduler wake-up latency into the
+//! timestamps of live replays. With `--chunk-ms` the samples due within a few
+//! milliseconds are grouped by [`chunk_ranges`] and pushed as one chunk, once its
+//! last sample is due, each sample carrying its own timestamp: the recorded offset
+//! in batch mode, [`ReplayClock::due_stamp`] in live replays.
+//!
+//! A multi-stream replay (`--all`, or several `--stream`s) puts every stream on
+//! one [`SessionSpan`]: all streams share the first timestamp of the session as
+//! their reference, so the offsets between streams stay as recorded. Looping
+//! passes follow each other back to back, each shifted by the session's length plus
+//! [`SessionSpan::gap`].
+//!
+//! A [`ReplayWindow`] (`--start-time`/`--end-time` or `--start-sample`/`--end-sample`)
+//! narrows the replay to a portion of the recording, e.g. the seconds around an
+//! artifact; the replay then starts, loops and is stamped as if the recording began
+//! at the first sample of the window.
+
+use anyhow::Result;
+use std::ops::Range;
+use std::time::Duration;
+
+/// Samples read from the store per block unless `--block-samples` is given
+pub const DEFAULT_BLOCK_SAMPLES: usize = 4096;
+
+/// Blocks read ahead of the outlet unless `--prefetch` is given
+pub const DEFAULT_PREFETCH_BLOCKS: usize = 4;
+
+/// `(start, len)` of the blocks a stream of `num_samples` samples is read in
+pub fn block_ranges(num_samples: usize, block_samples: usize) -> impl Iterator<Item = (usize, usize)> {
+    let block_samples = block_samples.max(1);
+    (0..num_samples)
+        .step_by(block_samples)
+        .map(move |start| (start, block_samples.min(num_samples - start)))
+}
+
+/// Consecutive chunks of samples, each spanning less than `span` seconds of recording
+///
+/// Every chunk holds at least one sample, so a gap longer than `span` ends a chunk
+/// instead of stretching it.
+pub fn chunk_ranges(timestamps: &[f64], span: f64) -> Vec<Range<usize>> {
+    let mut ranges = Vec::new();
+    let mut start = 0;
+    while start < timestamps.len() {
+        let first = timestamps[start];
+        let end = start + 1 + timestamps[start + 1..].iter().take_while(|&&t| t - first < span).count();
+        ranges.push(start..end);
+        start = end;
+    }
+    ranges
+}
+
+/// Portion of a recording to replay
+///
+/// Times are seconds on the time axis the caller measures them on (see
+/// [`ReplayWindow::sample_range`]); sample bounds are indices into the stream.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct ReplayWindow {
+    pub start_time: Option<f64>,
+    pub end_time: Option<f64>,
+    /// First sample to replay
+    pub start_sample: Option<usize>,
+    /// End (exclusive) of the samples to replay
+    pub end_sample: Option<usize>,
+}
+
+impl ReplayWindow {
+    /// Whether the window covers the whole recording
+    pub fn is_full(&self) -> bool {
+        self.start_time.is_none() && self.end_time.is_none() && self.start_sample.is_none() && self.end_sample.is_none()
+    }
+
+    /// Whether the window is given by sample indices
+    pub fn has_samples(&self) -> bool {
+        self.start_sample.is_some() || self.end_sample.is_some()
+    }
+
+    /// An error for bounds that cannot select any sample
+    pub fn validate(&self) -> Result<()> {
+        for time in [self.start_time, self.end_time].into_iter().flatten() {
+            if !time.is_finite() {
+                anyhow::bail!("--start-time and --end-time must be finite");
+            }
+        }
+        if let (Some(start), Some(end)) = (self.start_time, self.end_time)
+            && start >= end
+        {
+            anyhow::bail!("--start-time ({}) must be before --end-time ({})", start, end);
+        }
+        if let (Some(start), Some(end)) = (self.start_sample, self.end_sample)
+            && start >= end
+        {
+            anyhow::bail!("--start-sample ({}) must be below --end-sample ({})", start, end);
+        }
+        Ok(())
+    }
+
+    /// Samples `start..end` of a stream of `num_samples` samples inside the window
+    ///
+    /// `first_at(t)` is the first sample whose time on the axis of `start_time` and
+    /// `end_time` is at or after `t`, as
+    /// [`ExportStream::sample_index`](crate::export::ExportStream::sample_index) finds
+    /// it. Both time bounds are inclusive. The range is empty if no sample is inside.
+    pub fn sample_range(
+        &self,
+        num_samples: usize,
+        mut first_at: impl FnMut(f64) -> Result<usize>,
+    ) -> Result<Range<usize>> {
+        let mut start = self.start_sample.unwrap_or(0).min(num_samples);
+        let mut end = self.end_sample.unwrap_or(num_samples).min(num_samples);
+        if let Some(start_time) = self.start_time {
+            start = start.max(first_at(start_time)?);
+        }
+        if let Some(end_time) = self.end_time {
+            // The first sample after the end
+            end = end.min(first_at(end_time.next_up())?);
+        }
+        Ok(start..end.max(start))
+    }
+}
+
+/// Maps recorded timestamps to pushed timestamps and push deadlines
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ReplayClock {
+    /// First recorded timestamp of the stream
+    pub first_timestamp: f64,
+    /// Replay clock (LSL local clock) at the start of the replay
+    pub origin: f64,
+    /// Playback speed multiplier
+    pub speed: f64,
+    /// Whether samples are held back until their scaled recorded offset
+    pub realtime: bool,
+}
+
+impl ReplayClock {
+    /// Timestamp pushed for a sample recorded at `recorded`
+    pub fn stamp(&self, recorded: f64) -> f64 {
+        self.origin + (recorded - self.first_timestamp)
+    }
+
+    /// LSL clock time at which a sample recorded at `recorded` is due
+    ///
+    /// The timestamp of a live replay, which follows the playback speed.
+    pub fn due_stamp(&self, recorded: f64) -> f64 {
+        self.origin + (recorded - self.first_timestamp) / self.speed
+    }
+
+    /// Difference between recorded and pushed timestamps
+    pub fn timestamp_offset(&self) -> f64 {
+        self.first_timestamp - self.origin
+    }
+
+    /// Time after the replay start at which a sample recorded at `recorded` is due
+    ///
+    /// `None` without real-time pacing.
+    pub fn due(&self, recorded: f64) -> Option<Duration> {
+        if !self.realtime {
+            return None;
+        }
+        let offset = (recorded - self.first_timestamp) / self.speed;
+        Some(Duration::from_secs_f64(if offset.is_finite() { offset.max(0.0) } else { 0.0 }))
+    }
+
+    /// Seconds a sample recorded at `recorded` is late when pushed `elapsed` after the replay start
+    ///
+    /// 0 without real-time pacing, where nothing is ever late.
+    pub fn lag(&self, recorded: f64, elapsed: Duration) -> f64 {
+        self.due(recorded)
+            .map_or(0.0, |due| (elapsed.as_secs_f64() - due.as_secs_f64()).max(0.0))
+    }
+}
+
+/// Recorded extent of the streams of a multi-stream replay
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SessionSpan {
+    /// Earliest first timestamp of any stream
+    pub start: f64,
+    /// Latest last timestamp of any stream
+    pub end: f64,
+    /// Seconds between the end of one loop pass and the start of the next
+    pub gap: f64,
+}
+
+impl SessionSpan {
+    /// Span of streams given as (first, last) timestamps, without a loop gap
+    ///
+    /// `None` without streams.
+    pub fn new(extents: impl IntoIterator<Item = (f64, f64)>) -> Option<Self> {
+        extents.into_iter().fold(None, |span, (first, last)| {
+            Some(match span {
+                Some(span) => SessionSpan {
+                    start: span.start.min(first),
+                    end: span.end.max(last),
+                    gap: 0.0,
+                },
+                None => SessionSpan {
+                    start: first,
+                    end: last,
+                    gap: 0.0,
+                },
+            })
+        })
+    }
+
+    /// Recorded length of the session in seconds
+    pub fn duration(&self) -> f64 {
+        self.end - self.start
+    }
+
+    /// Replay clock shared by all streams during loop pass `pass` (0 for the first)
+    pub fn clock(&self, pass: u64, origin: f64, speed: f64, realtime: bool) -> ReplayClock {
+        ReplayClock {
+            first_timestamp: self.start - pass as f64 * (self.duration() + self.gap),
+            origin,
+            speed,
+            realtime,
+        }
+    }
+}
+
+/// Outlet buffer (`max_buffered`) holding a whole recording
+///
+/// liblsl counts the buffer in seconds for regular streams and in hundreds of
+/// samples for irregular ones. Regular streams get 10% headroom for devices
+/// running above their nominal rate.
+pub fn batch_buffer(num_samples: usize, duration: f64, nominal_srate: f64) -> i32 {
+    let buffer = if nominal_srate > 0.0 {
+        let seconds = duration.max(0.0).ceil() as usize;
+        seconds + seconds / 10 + 1
+    } else {
+        num_samples.div_ceil(100) + 1
+    };
+    buffer.min(i32::MAX as usize) as i32
+}
@@ -0,0 +1,278 @@
+//! Resampling of regular streams onto a common time grid
+//!
+//! `lsl-sync --resample-to <Hz>` (or `--resample-to-stream <name>`) puts every
+//! regular stream on one grid of aligned timestamps, so analysis code gets all
+//! modalities sample by sample without its own interpolation. The grid is either
+//! uniform at the given rate from the start of the common window, or the aligned
+//! timestamps of the named stream within it (that stream is then copied as is).
+//!
+//! Values are interpolated linearly between the two source samples around each
+//! grid point. When the grid is coarser than a stream's nominal rate, the stream is
+//! low-pass filtered first with a Blackman-windowed sinc (cutoff at 90% of the
+//! grid's Nyquist frequency) to avoid aliasing; the filter is only evaluated at
+//! the samples the interpolation needs. Grid points outside a stream's samples or
+//! inside a gap (an interval over [`crate::gaps::DEFAULT_GAP_FACTOR`] nominal
+//! periods) are NaN.
+
+use anyhow::{Context, Result};
+use ndarray::{Array1, Array2, Ix1, Ix2};
+use serde_json::json;
+use std::f64::consts::PI;
+use std::path::Path;
+use std::sync::Arc;
+use zarrs::filesystem::FilesystemStore;
+
+use crate::export::{ExportStream, EXPORT_BLOCK_SAMPLES};
+use crate::gaps::DEFAULT_GAP_FACTOR;
+use crate::zarr::layout::{create_sample_array, create_time_array};
+use crate::zarr::materialize::storage_from_attributes;
+
+/// Resampled sample array of a stream
+pub const RESAMPLED_DATA: &str = "resampled_data";
+
+/// Grid timestamps of the resampled sample array
+pub const RESAMPLED_TIME: &str = "resampled_time";
+
+/// Stream group attribute describing the resampling
+pub const RESAMPLING_ATTRIBUTE: &str = "resampling";
+
+/// Anti-aliasing cutoff as a fraction of the target Nyquist frequency
+pub const CUTOFF_FRACTION: f64 = 0.9;
+
+/// Filter half-length in target sample periods
+const HALF_LENGTH_PERIODS: f64 = 4.0;
+
+/// Longest anti-aliasing filter (taps)
+pub const MAX_TAPS: usize = 4001;
+
+/// Low-pass FIR kernel for resampling from `source_rate` to `target_rate` (Hz)
+///
+/// `None` when the target rate is not below the source rate (nothing to filter).
+/// The kernel has an odd number of taps and unit gain at DC.
+pub fn anti_alias_kernel(source_rate: f64, target_rate: f64) -> Option<Vec<f64>> {
+    if source_rate <= 0.0 || target_rate <= 0.0 || target_rate >= source_rate {
+        return None;
+    }
+    let ratio = source_rate / target_rate;
+    let half = ((HALF_LENGTH_PERIODS * ratio).ceil() as usize).min(MAX_TAPS / 2);
+    let taps = 2 * half + 1;
+    // Cutoff in cycles per source sample
+    let cutoff = CUTOFF_FRACTION * 0.5 / ratio;
+
+    let mut kernel: Vec<f64> = (0..taps)
+        .map(|i| {
+            let n = i as f64 - half as f64;
+            let sinc = if n == 0.0 { 2.0 * cutoff } else { (2.0 * PI * cutoff * n).sin() / (PI * n) };
+            let phase = 2.0 * PI * i as f64 / (taps - 1) as f64;
+            let window = 0.42 - 0.5 * phase.cos() + 0.08 * (2.0 * phase).cos();
+            sinc * window
+        })
+        .collect();
+    let gain: f64 = kernel.iter().sum();
+    for tap in kernel.iter_mut() {
+        *tap /= gain;
+    }
+    Some(kernel)
+}
+
+/// Uniform grid of `rate` Hz covering `0..=duration` seconds
+pub fn uniform_grid(duration: f64, rate: f64) -> Vec<f64> {
+    if rate <= 0.0 || duration < 0.0 {
+        return Vec::new();
+    }
+    let count = (duration * rate + 1e-9).floor() as usize + 1;
+    (0..count).map(|k| k as f64 / rate).collect()
+}
+
+/// Value of channel `channel` at sample `index`, low-pass filtered if a kernel is given
+///
+/// Samples beyond the ends of the block repeat the edge sample.
+fn filtered(values: &[f64], channel_count: usize, channel: usize, index: usize, kernel: Option<&[f64]>) -> f64 {
+    let samples = values.len() / channel_count;
+    let Some(kernel) = kernel else {
+        return values[index * channel_count + channel];
+    };
+    let half = kernel.len() / 2;
+    kernel
+        .iter()
+        .enumerate()
+        .map(|(k, &tap)| {
+            let source = (index + k).saturating_sub(half).min(samples - 1);
+            tap * values[source * channel_count + channel]
+        })
+        .sum()
+}
+
+/// Resample a block of interleaved samples onto `grid`
+///
+/// `timestamps` and `values` hold consecutive samples of one stream (on the
+/// grid's time axis); the block should extend half a kernel beyond the grid
+/// points on either side, unless it ends with the stream. Intervals longer than
+/// `max_interval` seconds are not interpolated across. Returns interleaved
+/// values, one sample per grid point, NaN where the stream has no data.
+pub fn resample_block(
+    timestamps: &[f64],
+    values: &[f64],
+    channel_count: usize,
+    kernel: Option<&[f64]>,
+    max_interval: Option<f64>,
+    grid: &[f64],
+) -> Vec<f64> {
+    let mut out = vec![f64::NAN; grid.len() * channel_count];
+    let samples = timestamps.len().min(values.len() / channel_count.max(1));
+    if samples == 0 || channel_count == 0 {
+        return out;
+    }
+    let timestamps = &timestamps[..samples];
+    let values = &values[..samples * channel_count];
+    let first = timestamps[0];
+    let last = timestamps[samples - 1];
+
+    for (point, &time) in grid.iter().enumerate() {
+        if !(first..=last).contains(&time) {
+            continue;
+        }
+        // Last sample at or before the grid point
+        let j = timestamps.partition_point(|&t| t <= time).saturating_sub(1);
+        let target = &mut out[point * channel_count..(point + 1) * channel_count];
+        if j + 1 >= samples || timestamps[j] == time {
+            for (channel, value) in target.iter_mut().enumerate() {
+                *value = filtered(values, channel_count, channel, j, kernel);
+            }
+            continue;
+        }
+        let interval = timestamps[j + 1] - timestamps[j];
+        if max_interval.is_some_and(|max| interval > max) || interval <= 0.0 {
+            continue;
+        }
+        let weight = (time - timestamps[j]) / interval;
+        for (channel, value) in target.iter_mut().enumerate() {
+            let before = filtered(values, channel_count, channel, j, kernel);
+            let after = filtered(values, channel_count, channel, j + 1, kernel);
+            *value = before + weight * (after - before);
+        }
+    }
+    out
+}
+
+/// Where the grid of a resampling came from
+#[derive(Debug, Clone, PartialEq)]
+pub enum GridSource {
+    /// Uniform grid at a rate (Hz)
+    Rate(f64),
+    /// Aligned timestamps of a stream (name and nominal rate)
+    Stream(String, f64),
+}
+
+impl GridSource {
+    /// Sampling rate of the grid (Hz)
+    pub fn rate(&self) -> f64 {
+        match self {
+            GridSource::Rate(rate) | GridSource::Stream(_, rate) => *rate,
+        }
+    }
+}
+
+/// Resample a regular stream onto `grid` and write `resampled_data`/`resampled_time`
+///
+/// `aligned` are the stream's timestamps on the grid's time axis (one per
+/// sample). The arrays get the stream's chunk layout and compression; the
+/// `resampling` attribute records the grid and filter. Returns the number of
+/// grid points covered by the stream.
+pub fn resample_stream(
+    store_path: &Path,
+    stream_name: &str,
+    aligned: &[f64],
+    grid: &[f64],
+    source: &GridSource,
+) -> Result<usize> {
+    let store = Arc::new(FilesystemStore::new(store_path)?);
+    let stream = ExportStream::open(&store, stream_name, false)?;
+    if stream.is_marker() {
+        anyhow::bail!("Stream '{}' holds string samples and cannot be resampled", stream_name);
+    }
+    let nominal_srate = stream.nominal_srate();
+    if nominal_srate <= 0.0 {
+        anyhow::bail!("Stream '{}' is irregular and cannot be resampled", stream_name);
+    }
+    let samples = (stream.sample_count as usize).min(aligned.len());
+    let aligned = &aligned[..samples];
+    let channels = stream.channel_count;
+
+    let kernel = anti_alias_kernel(nominal_srate, source.rate());
+    let half = kernel.as_ref().map_or(0, |k| k.len() / 2);
+    let max_interval = Some(DEFAULT_GAP_FACTOR / nominal_srate);
+
+    let storage = storage_from_attributes(&stream.attributes);
+    storage.validate()?;
+    for name in [RESAMPLED_DATA, RESAMPLED_TIME] {
+        let path = store_path.join(stream_name).join(name);
+        if path.exists() {
+            std::fs::remove_dir_all(&path)?;
+        }
+    }
+    let mut data = create_sample_array(
+        &store,
+        &format!("/{}/{}", stream_name, RESAMPLED_DATA),
+        "Double64",
+        channels,
+        false,
+        &storage,
+    )?;
+    let mut time = create_time_array(&store, &format!("/{}/{}", stream_name, RESAMPLED_TIME), &storage)?;
+    data.set_shape(vec![channels as u64, grid.len() as u64])?;
+    time.set_shape(vec![grid.len() as u64])?;
+
+    // Blocks of grid points aligned to the outer chunks, reading about one export block of source samples
+    let outer_chunk_samples = storage.shard_samples.unwrap_or(storage.chunk_samples) as usize;
+    let ratio = (nominal_srate / source.rate()).max(1.0);
+    let block_points = ((EXPORT_BLOCK_SAMPLES as f64 / ratio) as usize / outer_chunk_samples).max(1) * outer_chunk_samples;
+
+    let mut covered = 0;
+    for block_start in (0..grid.len()).step_by(block_points) {
+        let points = &grid[block_start..(block_start + block_points).min(grid.len())];
+        let first = aligned.partition_point(|&t| t < points[0]).saturating_sub(1 + half);
+        let end = (aligned.partition_point(|&t| t <= points[points.len() - 1]) + 1 + half).min(samples);
+
+        let values = if first < end {
+            let (_, block) = stream.read_block(first as u64, (end - first) as u64)?;
+            let values = block.to_f64().context("numeric samples expected")?;
+            resample_block(&aligned[first..end], &values, channels, kernel.as_deref(), max_interval, points)
+        } else {
+            vec![f64::NAN; points.len() * channels]
+        };
+        covered += values.chunks(channels.max(1)).filter(|sample| sample.iter().any(|v| !v.is_nan())).count();
+
+        // Sample-major -> [channels, samples]
+        let block = Array2::from_shape_vec((points.len(), channels), values)?
+            .reversed_axes()
+            .as_standard_layout()
+            .into_owned();
+        data.store_array_subset_ndarray::<f64, Ix2>(&[0, block_start as u64], block)?;
+        time.store_array_subset_ndarray::<f64, Ix1>(&[block_start as u64], Array1::from_vec(points.to_vec()))?;
+    }
+    data.store_metadata()?;
+    time.store_metadata()?;
+
+    let grid_description = match source {
+        GridSource::Rate(rate) => json!({ "rate": rate }),
+        GridSource::Stream(name, rate) => json!({ "stream": name, "rate": rate }),
+    };
+    let mut group = zarrs::group::Group::open(store.clone(), &format!("/{}", stream_name))?;
+    group.attributes_mut().insert(
+        RESAMPLING_ATTRIBUTE.to_string(),
+        json!({
+            "grid": grid_description,
+            "grid_points": grid.len(),
+            "covered_points": covered,
+            "method": "linear",
+            "anti_alias": kernel.as_ref().map(|k| json!({
+                "filter": "blackman-windowed sinc",
+                "taps": k.len(),
+                "cutoff_hz": CUTOFF_FRACTION * source.rate() / 2.0,
+            })),
+        }),
+    );
+    group.store_metadata()?;
+    Ok(covered)
+}
@@ -0,0 +1,375 @@
+//! Splitting long recordings into several stores (`--rotate-every`, `--rotate-size`)
+//!
+//! An overnight recording should not live in one gigantic store. With rotation the
+//! recorder closes the current store, finalized like at the end of a recording, and
+//! continues in the next part: `experiment.zarr`, then `experiment_part002.zarr`,
+//! `experiment_part003.zarr`, ...
+//!
+//! - `--rotate-every 30min` starts a new part every 30 minutes of LSL clock time
+//!   after `START`. Part `n` holds the samples stamped within
+//!   `[start + (n-1)·every, start + n·every)`, so the recorders of one
+//!   `lsl-multi-recorder` session split their streams at the same moments.
+//! - `--rotate-size 10GB` starts a new part once the store has grown past the size
+//!   (checked every few seconds).
+//!
+//! Every part records its place in the session as the `rotation` attribute of its
+//! `meta` group, and every stream its predecessor as the `continues_from` attribute:
+//!
+//! ```json
+//! "rotation": {"session": "experiment.zarr", "part": 2, "previous": "experiment.zarr",
+//!              "next": "experiment_part003.zarr", "every_s": 1800.0, "size_bytes": null}
+//! "continues_from": {"store": "experiment.zarr", "last_timestamp": 83342.12, "samples_before": 1800000}
+//! ```
+
+use anyhow::{Context, Result};
+use fs2::FileExt;
+use serde::{Deserialize, Serialize};
+use std::fs::OpenOptions;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use zarrs::filesystem::FilesystemStore;
+
+use crate::zarr::layout::create_group_if_not_exists;
+use crate::zarr::{read_group_attributes, META_GROUP};
+
+/// `meta` group attribute describing a store's place in a rotated session
+pub const ROTATION_ATTRIBUTE: &str = "rotation";
+
+/// Stream group attribute linking a stream to its samples in the previous part
+pub const CONTINUATION_ATTRIBUTE: &str = "continues_from";
+
+/// Time between two measurements of the store size for `--rotate-size`
+const SIZE_CHECK_INTERVAL: Duration = Duration::from_secs(5);
+
+/// When to start a new part
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct RotationOptions {
+    /// LSL clock time per part
+    pub every: Option<Duration>,
+    /// Store size in bytes after which a new part is started
+    pub size: Option<u64>,
+}
+
+impl RotationOptions {
+    pub fn is_enabled(&self) -> bool {
+        self.every.is_some() || self.size.is_some()
+    }
+}
+
+/// Place of a store in a rotated session (the `rotation` attribute)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PartInfo {
+    /// File name of the first part, which names the session
+    pub session: String,
+    pub part: u32,
+    /// File name of the part before this one
+    pub previous: Option<String>,
+    /// File name of the part after this one, once it has been started
+    pub next: Option<String>,
+    pub every_s: Option<f64>,
+    pub size_bytes: Option<u64>,
+}
+
+/// Where a stream's samples before this part are (the `continues_from` attribute)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Continuation {
+    /// File name of the previous part
+    pub store: String,
+    /// Timestamp of the stream's last sample in the previous part
+    pub last_timestamp: Option<f64>,
+    /// Samples of the stream in all earlier parts
+    pub samples_before: u64,
+}
+
+/// Parse a duration such as `30min`, `2h`, `1h30m`, `90s` or `45` (seconds)
+pub fn parse_duration(text: &str) -> Result<Duration> {
+    let text = text.trim();
+    let mut seconds = 0.0;
+    let mut rest = text;
+    while !rest.is_empty() {
+        let number_end = rest
+            .find(|c: char| !(c.is_ascii_digit() || c == '.'))
+            .unwrap_or(rest.len());
+        let (number, tail) = rest.split_at(number_end);
+        let unit_end = tail.find(|c: char| c.is_ascii_digit() || c == '.').unwrap_or(tail.len());
+        let (unit, tail) = tail.split_at(unit_end);
+        let value: f64 = number
+            .parse()
+            .with_context(|| format!("invalid duration '{}', expected e.g. 30min, 2h or 90s", text))?;
+        let factor = match unit.trim().to_ascii_lowercase().as_str() {
+            "" | "s" | "sec" | "secs" => 1.0,
+            "m" | "min" | "mins" => 60.0,
+            "h" | "hr" | "hrs" | "hour" | "hours" => 3600.0,
+            other => anyhow::bail!("invalid duration unit '{}' in '{}' (use s, min or h)", other, text),
+        };
+        seconds += value * factor;
+        rest = tail;
+    }
+    if seconds <= 0.0 {
+        anyhow::bail!("duration '{}' must be positive", text);
+    }
+    Ok(Duration::from_secs_f64(seconds))
+}
+
+/// Parse a size such as `10GB`, `500MB`, `1.5GiB` or `1048576` (bytes)
+pub fn parse_size(text: &str) -> Result<u64> {
+    let text = text.trim();
+    let number_end = text
+        .find(|c: char| !(c.is_ascii_digit() || c == '.'))
+        .unwrap_or(text.len());
+    let (number, unit) = text.split_at(number_end);
+    let value: f64 = number
+        .parse()
+        .with_context(|| format!("invalid size '{}', expected e.g. 10GB or 500MB", text))?;
+    let factor: f64 = match unit.trim().to_ascii_uppercase().as_str() {
+        "" | "B" => 1.0,
+        "K" | "KB" => 1e3,
+        "M" | "MB" => 1e6,
+        "G" | "GB" => 1e9,
+        "T" | "TB" => 1e12,
+        "KIB" => 1024.0,
+        "MIB" => 1024.0 * 1024.0,
+        "GIB" => 1024.0 * 1024.0 * 1024.0,
+        "TIB" => 1024.0 * 1024.0 * 1024.0 * 1024.0,
+        other => anyhow::bail!("invalid size unit '{}' in '{}' (use B, KB, MB, GB or TB)", other, text),
+    };
+    let bytes = (value * factor).round();
+    if bytes < 1.0 {
+        anyhow::bail!("size '{}' must be positive", text);
+    }
+    Ok(bytes as u64)
+}
+
+/// Name of part `part` of the session whose first part is named `base`
+///
+/// Works on store paths and object storage URLs alike; part 1 is `base` itself.
+pub fn part_name(base: &str, part: u32) -> String {
+    if part <= 1 {
+        return base.to_string();
+    }
+    let trimmed = base.trim_end_matches('/');
+    let stem = trimmed.strip_suffix(".zarr").unwrap_or(trimmed);
+    format!("{}_part{:03}.zarr", stem, part)
+}
+
+/// Path of part `part` of the session whose first part is `base`
+pub fn part_path(base: &Path, part: u32) -> PathBuf {
+    if part <= 1 {
+        return base.to_path_buf();
+    }
+    let name = base.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+    base.with_file_name(part_name(&name, part))
+}
+
+/// Part number encoded in a store's file name (1 for a name without `_partNNN`)
+fn part_number(name: &str, base_stem: &str) -> Option<u32> {
+    let stem = name.strip_suffix(".zarr")?;
+    if stem == base_stem {
+        return Some(1);
+    }
+    stem.strip_prefix(base_stem)?.strip_prefix("_part")?.parse().ok()
+}
+
+/// First part of the session `store` belongs to
+pub fn session_base(store: &Path) -> PathBuf {
+    if let Some(info) = read_part_info(store) {
+        return store.with_file_name(info.session);
+    }
+    let name = store.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+    match name.strip_suffix(".zarr").and_then(|stem| stem.rsplit_once("_part")) {
+        Some((stem, number)) if number.len() >= 3 && number.chars().all(|c| c.is_ascii_digit()) => {
+            store.with_file_name(format!("{}.zarr", stem))
+        }
+        _ => store.to_path_buf(),
+    }
+}
+
+/// All parts of the session `store` belongs to that exist, in part order
+///
+/// A store that was never rotated is its own only part.
+pub fn session_parts(store: &Path) -> Vec<PathBuf> {
+    let base = session_base(store);
+    let name = base.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+    let stem = name.strip_suffix(".zarr").unwrap_or(&name).to_string();
+    let dir = match base.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent.to_path_buf(),
+        _ => PathBuf::from("."),
+    };
+    let mut parts: Vec<(u32, PathBuf)> = std::fs::read_dir(&dir)
+        .into_iter()
+        .flatten()
+        .filter_map(|entry| entry.ok())
+        .filter_map(|entry| {
+            let name = entry.file_name().to_string_lossy().to_string();
+            let part = part_number(&name, &stem)?;
+            Some((part, base.with_file_name(name)))
+        })
+        .filter(|(_, path)| path.is_dir())
+        .collect();
+    parts.sort_by_key(|(part, _)| *part);
+    if parts.is_empty() {
+        return vec![store.to_path_buf()];
+    }
+    parts.into_iter().map(|(_, path)| path).collect()
+}
+
+/// The part of the session `store` belongs to that is currently being written (the last one)
+pub fn current_part(store: &Path) -> PathBuf {
+    session_parts(store).pop().unwrap_or_else(|| store.to_path_buf())
+}
+
+/// Refuse to rotate into parts left by an earlier recording to the same output
+pub fn check_no_earlier_parts(base: &Path) -> Result<()> {
+    if let Some(part) = session_parts(base).into_iter().find(|part| part != base) {
+        anyhow::bail!(
+            "{} exists from an earlier recording; choose another output or pass --append-session",
+            part.display()
+        );
+    }
+    Ok(())
+}
+
+/// The `rotation` attribute of a store's `meta` group, if the store is part of a rotated session
+pub fn read_part_info(store_path: &Path) -> Option<PartInfo> {
+    if !store_path.is_dir() {
+        return None;
+    }
+    let store = Arc::new(FilesystemStore::new(store_path).ok()?);
+    read_group_attributes(&store, &format!("/{}", META_GROUP))
+        .ok()?
+        .get(ROTATION_ATTRIBUTE)
+        .and_then(|value| serde_json::from_value(value.clone()).ok())
+}
+
+/// Record that `store` is part `part` of the session starting at `base`, following `previous`
+///
+/// Recorders sharing a store all call this; the first one writes the attribute and a
+/// `next` link set meanwhile is kept.
+pub fn mark_part(store: &Path, base: &Path, part: u32, previous: Option<&Path>, options: &RotationOptions) -> Result<()> {
+    let file_name = |path: &Path| path.file_name().map(|n| n.to_string_lossy().to_string());
+    update_part_info(store, |info| {
+        let next = info.take().and_then(|existing| existing.next);
+        *info = Some(PartInfo {
+            session: file_name(base).unwrap_or_default(),
+            part,
+            previous: previous.and_then(file_name),
+            next,
+            every_s: options.every.map(|d| d.as_secs_f64()),
+            size_bytes: options.size,
+        });
+    })
+}
+
+/// Link a finished part to the part that follows it
+pub fn link_next(store: &Path, next: &Path) -> Result<()> {
+    let next = next.file_name().map(|n| n.to_string_lossy().to_string());
+    update_part_info(store, |info| {
+        if let Some(info) = info {
+            info.next = next;
+        }
+    })
+}
+
+/// Change the `rotation` attribute of a store's `meta` group under the metadata lock
+fn update_part_info(store_path: &Path, update: impl FnOnce(&mut Option<PartInfo>)) -> Result<()> {
+    let lock_file = OpenOptions::new()
+        .create(true)
+        .write(true)
+        .truncate(false)
+        .open(store_path.join(".zarr_metadata.lock"))?;
+    lock_file.lock_exclusive()?;
+    let result = (|| -> Result<()> {
+        let store = Arc::new(FilesystemStore::new(store_path)?);
+        let meta_path = format!("/{}", META_GROUP);
+        create_group_if_not_exists(&store, &meta_path)?;
+        let mut group = zarrs::group::Group::open(store, &meta_path)?;
+
+        let attributes = group.attributes_mut();
+        let mut info: Option<PartInfo> = attributes
+            .get(ROTATION_ATTRIBUTE)
+            .and_then(|value| serde_json::from_value(value.clone()).ok());
+        update(&mut info);
+        if let Some(info) = info {
+            attributes.insert(ROTATION_ATTRIBUTE.to_string(), serde_json::to_value(&info)?);
+        }
+        group.store_metadata()?;
+        Ok(())
+    })();
+    lock_file.unlock()?;
+    result.with_context(|| format!("Failed to store the part links of {}", store_path.display()))
+}
+
+/// Total size of the files below `path` in bytes
+pub fn directory_size(path: &Path) -> u64 {
+    let mut total = 0;
+    let mut pending = vec![path.to_path_buf()];
+    while let Some(dir) = pending.pop() {
+        for entry in std::fs::read_dir(&dir).into_iter().flatten().filter_map(|e| e.ok()) {
+            match entry.metadata() {
+                Ok(metadata) if metadata.is_dir() => pending.push(entry.path()),
+                Ok(metadata) => total += metadata.len(),
+                Err(_) => {}
+            }
+        }
+    }
+    total
+}
+
+/// Decides, sample by sample, when a recorder moves on to the next part
+#[derive(Debug, Clone)]
+pub struct PartTracker {
+    options: RotationOptions,
+    part: u32,
+    // LSL clock time at which recording started; parts by duration count from here
+    origin: Option<f64>,
+    last_size_check: Option<Instant>,
+}
+
+impl PartTracker {
+    pub fn new(options: RotationOptions) -> Self {
+        Self {
+            options,
+            part: 1,
+            origin: None,
+            last_size_check: None,
+        }
+    }
+
+    /// Part currently being written
+    pub fn part(&self) -> u32 {
+        self.part
+    }
+
+    /// Recording started at `lsl_clock`; only the first start counts
+    pub fn start(&mut self, lsl_clock: f64) {
+        self.origin.get_or_insert(lsl_clock);
+    }
+
+    /// The part a sample stamped `timestamp` must go to, if that is a new one
+    ///
+    /// `store_size` is only called when the size is due to be checked.
+    pub fn due(&mut self, timestamp: f64, store_size: impl FnOnce() -> u64) -> Option<u32> {
+        if let (Some(every), Some(origin)) = (self.options.every, self.origin) {
+            let part = ((timestamp - origin) / every.as_secs_f64()).floor().max(0.0) as u32 + 1;
+            if part > self.part {
+                return Some(part);
+            }
+        }
+        if let Some(limit) = self.options.size
+            && self.last_size_check.is_none_or(|t| t.elapsed() >= SIZE_CHECK_INTERVAL)
+        {
+            self.last_size_check = Some(Instant::now());
+            if store_size() >= limit {
+                return Some(self.part + 1);
+            }
+        }
+        None
+    }
+
+    /// Writing continues in `part`
+    pub fn advance(&mut self, part: u32) {
+        self.part = part;
+        self.last_size_check = None;
+    }
+}
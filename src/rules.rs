@@ -0,0 +1,298 @@
+//! Rules acting on the live signal (`lsl-recorder --rule`)
+//!
+//! Long passive monitoring sessions run without anyone watching the traces. A rule
+//! watches the stream while it is recorded and reacts when a condition has held for
+//! long enough, e.g. when every electrode has come off the subject:
+//!
+//! ```text
+//! rms < 5 for 30s then pause
+//! ```
+//!
+//! - **Condition**: `rms < THRESHOLD` or `rms > THRESHOLD`, met when the RMS (about
+//!   the mean, over one-second windows) of *every* channel is below or above the
+//!   threshold
+//! - **Duration**: how long the condition must hold, e.g. `30s` or `2min`
+//! - **Action**: `warn` (the default) reports `STATUS RULE_TRIGGERED`; `pause` also
+//!   pauses the recording as `PAUSE` would and resumes it once the condition no
+//!   longer holds (a `RESUME` or `STOP` in between takes precedence)
+//!
+//! The end of a trigger is reported as `STATUS RULE_CLEARED`. When recording ends,
+//! each rule and the `[start, end]` of its triggers (stream timestamps, from the first
+//! window the condition held in) are stored as the stream's `rules` attribute; pauses
+//! taken by a rule are in `pauses` as well. lsl-multi-recorder applies a rule with a
+//! `STREAM:` prefix (`EEG: rms < 5 for 30s then pause`) to that stream only.
+
+use anyhow::{Context, Result};
+use serde_json::json;
+use std::time::Duration;
+
+use crate::quality::window_rms;
+use crate::rotation::parse_duration;
+
+/// Name of the stream group attribute holding the rules and their triggers
+pub const RULES_ATTRIBUTE: &str = "rules";
+
+/// Length of the RMS windows rules are evaluated on, in seconds
+pub const RULE_WINDOW: f64 = 1.0;
+
+/// How a channel's RMS is compared to the threshold
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Comparison {
+    Below,
+    Above,
+}
+
+/// What a rule does when it triggers
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RuleAction {
+    /// Report `STATUS RULE_TRIGGERED`
+    Warn,
+    /// Report and pause the recording until the condition no longer holds
+    Pause,
+}
+
+/// A condition on the signal and the action taken when it holds for `duration`
+#[derive(Debug, Clone, PartialEq)]
+pub struct Rule {
+    pub comparison: Comparison,
+    pub threshold: f64,
+    pub duration: Duration,
+    pub action: RuleAction,
+}
+
+impl std::fmt::Display for Rule {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let comparison = match self.comparison {
+            Comparison::Below => "<",
+            Comparison::Above => ">",
+        };
+        let action = match self.action {
+            RuleAction::Warn => "warn",
+            RuleAction::Pause => "pause",
+        };
+        write!(
+            f,
+            "rms {} {} for {}s then {}",
+            comparison,
+            self.threshold,
+            self.duration.as_secs_f64(),
+            action
+        )
+    }
+}
+
+impl Rule {
+    /// Whether an RMS value meets the condition
+    fn holds(&self, rms: f64) -> bool {
+        match self.comparison {
+            Comparison::Below => rms < self.threshold,
+            Comparison::Above => rms > self.threshold,
+        }
+    }
+}
+
+/// Parse a rule such as `rms < 5 for 30s then pause` (`then warn` if no action is given)
+pub fn parse_rule(text: &str) -> Result<Rule> {
+    let usage = || format!("invalid rule '{}', expected e.g. 'rms < 5 for 30s then pause'", text.trim());
+    let spaced = text.replace('<', " < ").replace('>', " > ");
+    let tokens: Vec<String> = spaced.split_whitespace().map(str::to_ascii_lowercase).collect();
+    let tokens: Vec<&str> = tokens.iter().map(String::as_str).collect();
+    let (condition, action) = match tokens.as_slice() {
+        [condition @ .., "then", action] => (condition, *action),
+        condition => (condition, "warn"),
+    };
+    let [metric, comparison, threshold, "for", duration] = condition else {
+        anyhow::bail!(usage());
+    };
+    if *metric != "rms" {
+        anyhow::bail!("unknown metric '{}' in rule '{}' (use rms)", metric, text.trim());
+    }
+    let comparison = match *comparison {
+        "<" => Comparison::Below,
+        ">" => Comparison::Above,
+        _ => anyhow::bail!(usage()),
+    };
+    let threshold: f64 = threshold.parse().with_context(usage)?;
+    if !threshold.is_finite() || threshold < 0.0 {
+        anyhow::bail!("the threshold of rule '{}' must be a non-negative number", text.trim());
+    }
+    let action = match action {
+        "warn" => RuleAction::Warn,
+        "pause" => RuleAction::Pause,
+        other => anyhow::bail!("unknown action '{}' in rule '{}' (use warn or pause)", other, text.trim()),
+    };
+    Ok(Rule {
+        comparison,
+        threshold,
+        duration: parse_duration(duration)?,
+        action,
+    })
+}
+
+/// A rule of lsl-multi-recorder, for one stream (`EEG: rms < 5 for 30s`) or all of them
+#[derive(Debug, Clone, PartialEq)]
+pub struct StreamRule {
+    /// Stream name or source ID the rule is limited to
+    pub stream: Option<String>,
+    pub rule: Rule,
+}
+
+impl StreamRule {
+    /// Whether the rule applies to the stream recorded as group `stream_name`
+    pub fn applies_to(&self, stream_name: &str, source_id: &str) -> bool {
+        self.stream
+            .as_deref()
+            .is_none_or(|stream| stream == stream_name || stream == source_id)
+    }
+}
+
+/// Parse a rule with an optional `STREAM:` prefix
+pub fn parse_stream_rule(text: &str) -> Result<StreamRule> {
+    match text.split_once(':') {
+        Some((stream, rule)) if !stream.trim().is_empty() => Ok(StreamRule {
+            stream: Some(stream.trim().to_string()),
+            rule: parse_rule(rule)?,
+        }),
+        Some(_) => anyhow::bail!("missing stream name before ':' in rule '{}'", text.trim()),
+        None => Ok(StreamRule {
+            stream: None,
+            rule: parse_rule(text)?,
+        }),
+    }
+}
+
+/// A rule that triggered (`triggered`) or cleared
+#[derive(Debug, Clone, PartialEq)]
+pub struct RuleEvent {
+    pub rule: Rule,
+    pub triggered: bool,
+    /// Timestamp of the first window the condition held in
+    pub since: f64,
+}
+
+impl std::fmt::Display for RuleEvent {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}; condition met since {:.3}", self.rule, self.since)
+    }
+}
+
+/// State of one rule
+#[derive(Debug, Clone)]
+struct RuleState {
+    rule: Rule,
+    since: Option<f64>,
+    active: bool,
+    triggers: Vec<(f64, Option<f64>)>,
+}
+
+/// Evaluates rules on the samples of one regular numeric stream
+#[derive(Debug, Clone)]
+pub struct RuleEngine {
+    rules: Vec<RuleState>,
+    channels: usize,
+    window_samples: usize,
+    // Samples of the current window, interleaved, and the timestamp of its first sample
+    window: Vec<f64>,
+    window_start: Option<f64>,
+}
+
+impl RuleEngine {
+    /// Engine for a stream of `channels` channels at `nominal_srate` Hz
+    pub fn new(rules: Vec<Rule>, channels: usize, nominal_srate: f64) -> Self {
+        Self {
+            rules: rules
+                .into_iter()
+                .map(|rule| RuleState {
+                    rule,
+                    since: None,
+                    active: false,
+                    triggers: Vec::new(),
+                })
+                .collect(),
+            channels,
+            window_samples: ((RULE_WINDOW * nominal_srate).round() as usize).max(2),
+            window: Vec::new(),
+            window_start: None,
+        }
+    }
+
+    /// Add one sample; returns the rules that triggered or cleared with it
+    pub fn push<T: Copy + Into<f64>>(&mut self, timestamp: f64, values: &[T]) -> Vec<RuleEvent> {
+        if self.rules.is_empty() || self.channels == 0 {
+            return Vec::new();
+        }
+        let start = *self.window_start.get_or_insert(timestamp);
+        self.window
+            .extend(values.iter().take(self.channels).map(|&value| value.into()));
+        if self.window.len() < self.window_samples * self.channels {
+            return Vec::new();
+        }
+
+        let rms: Vec<f64> = (0..self.channels)
+            .map(|channel| {
+                let values: Vec<f64> = self.window.iter().skip(channel).step_by(self.channels).copied().collect();
+                window_rms(&values)
+            })
+            .collect();
+        self.window.clear();
+        self.window_start = None;
+
+        let mut events = Vec::new();
+        for state in &mut self.rules {
+            if rms.iter().all(|&value| state.rule.holds(value)) {
+                let since = *state.since.get_or_insert(start);
+                if !state.active && timestamp - since >= state.rule.duration.as_secs_f64() {
+                    state.active = true;
+                    state.triggers.push((since, None));
+                    events.push(RuleEvent {
+                        rule: state.rule.clone(),
+                        triggered: true,
+                        since,
+                    });
+                }
+            } else if let Some(since) = state.since.take()
+                && state.active
+            {
+                state.active = false;
+                if let Some(trigger) = state.triggers.last_mut() {
+                    trigger.1 = Some(start);
+                }
+                events.push(RuleEvent {
+                    rule: state.rule.clone(),
+                    triggered: false,
+                    since,
+                });
+            }
+        }
+        events
+    }
+
+    /// Close triggers still open when recording ends at `timestamp`
+    pub fn finish(&mut self, timestamp: f64) {
+        for state in &mut self.rules {
+            if let Some(trigger) = state.triggers.last_mut()
+                && trigger.1.is_none()
+            {
+                trigger.1 = Some(timestamp);
+            }
+            state.active = false;
+            state.since = None;
+        }
+    }
+
+    /// Rules with their triggers for the `rules` attribute
+    pub fn summary(&self) -> serde_json::Value {
+        let rules: Vec<serde_json::Value> = self
+            .rules
+            .iter()
+            .map(|state| {
+                json!({
+                    "rule": state.rule.to_string(),
+                    "window_s": RULE_WINDOW,
+                    "triggers": state.triggers.iter().map(|&(start, end)| json!([start, end])).collect::<Vec<_>>(),
+                })
+            })
+            .collect();
+        json!(rules)
+    }
+}
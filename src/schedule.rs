@@ -0,0 +1,181 @@
+//! Scheduled and block-structured recordings (`--start-at`, `--blocks`)
+//!
+//! `--start-at 14:30:00` connects to the stream right away but keeps the recorder
+//! stopped until that local time. `--blocks 10 --block-duration 60 --inter-block 30`
+//! records ten 60 s blocks separated by 30 s pauses and quits after the last one, so
+//! protocols with a fixed block structure need nobody typing START/STOP. Blocks are
+//! timed from the start of the first block, so pauses do not accumulate drift.
+//!
+//! Block boundaries are stored in the stream group's `blocks` attribute, as LSL clock
+//! times comparable to the `time` array:
+//!
+//! ```json
+//! "blocks": [
+//!   {"index": 1, "start": 81520.25, "end": 81580.25, "start_utc": "2025-01-31T14:30:00.000123Z"},
+//!   {"index": 2, "start": 81610.25, "end": 81631.02, "start_utc": "2025-01-31T14:31:30.000098Z", "interrupted": true}
+//! ]
+//! ```
+//!
+//! A block cut short by `QUIT` or Ctrl+C is marked `interrupted`.
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, NaiveDateTime, NaiveTime, TimeZone};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Stream group attribute holding the recorded [`Block`]s
+pub const BLOCKS_ATTRIBUTE: &str = "blocks";
+
+/// Accepted `--start-at` date and time formats (local time)
+const DATE_TIME_FORMATS: [&str; 4] = ["%Y-%m-%d %H:%M:%S", "%Y-%m-%dT%H:%M:%S", "%Y-%m-%d %H:%M", "%Y-%m-%dT%H:%M"];
+
+/// Accepted `--start-at` time-of-day formats
+const TIME_FORMATS: [&str; 2] = ["%H:%M:%S", "%H:%M"];
+
+/// Repeated record/pause cycles from `--blocks`/`--block-duration`/`--inter-block`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BlockSchedule {
+    pub blocks: u32,
+    pub block_duration: Duration,
+    pub inter_block: Duration,
+}
+
+impl BlockSchedule {
+    /// Offset of the start of block `index` (1-based) from the start of the first block
+    pub fn block_start(&self, index: u32) -> Duration {
+        (self.block_duration + self.inter_block) * index.saturating_sub(1)
+    }
+
+    /// Time from the start of the first block to the end of the last
+    pub fn total_duration(&self) -> Duration {
+        self.block_start(self.blocks) + self.block_duration
+    }
+}
+
+/// A recorded block, as stored in the `blocks` attribute
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Block {
+    /// 1-based block number
+    pub index: u32,
+    /// LSL clock when the block started
+    pub start: f64,
+    /// LSL clock when the block ended
+    pub end: f64,
+    /// Wall-clock start of the block (UTC, RFC3339)
+    pub start_utc: String,
+    /// The recording ended before the block did
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub interrupted: bool,
+}
+
+/// Block boundaries reported by the scheduler to the recording loop
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BlockEvent {
+    Started { index: u32, lsl_clock: f64 },
+    Ended { lsl_clock: f64 },
+}
+
+/// Turns [`BlockEvent`]s into completed [`Block`]s
+#[derive(Debug, Default)]
+pub struct BlockTracker {
+    open: Option<(u32, f64, String)>,
+}
+
+impl BlockTracker {
+    /// Apply a scheduler event; returns the block it completed, if any
+    pub fn apply(&mut self, event: BlockEvent) -> Option<Block> {
+        match event {
+            BlockEvent::Started { index, lsl_clock } => {
+                let start_utc = chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Micros, true);
+                self.open = Some((index, lsl_clock, start_utc));
+                None
+            }
+            BlockEvent::Ended { lsl_clock } => self.close(lsl_clock, false),
+        }
+    }
+
+    /// Close a block that is still running when the recording ends
+    pub fn interrupt(&mut self, lsl_clock: f64) -> Option<Block> {
+        self.close(lsl_clock, true)
+    }
+
+    fn close(&mut self, end: f64, interrupted: bool) -> Option<Block> {
+        self.open.take().map(|(index, start, start_utc)| Block {
+            index,
+            start,
+            end,
+            start_utc,
+            interrupted,
+        })
+    }
+}
+
+/// Parse `--start-at` relative to `now`
+///
+/// Accepts a time of day (`HH:MM` or `HH:MM:SS`), taken as its next occurrence, or a
+/// date and time (`YYYY-MM-DD HH:MM[:SS]`, `T` also accepted), which must be in the future.
+pub fn parse_start_at<Tz: TimeZone>(value: &str, now: &DateTime<Tz>) -> Result<DateTime<Tz>> {
+    let value = value.trim();
+    let local = |naive: NaiveDateTime| {
+        naive
+            .and_local_timezone(now.timezone())
+            .earliest()
+            .with_context(|| format!("{} does not exist in the local time zone", naive))
+    };
+
+    if let Some(time) = TIME_FORMATS.iter().find_map(|f| NaiveTime::parse_from_str(value, f).ok()) {
+        let today = local(now.date_naive().and_time(time))?;
+        if today > *now {
+            return Ok(today);
+        }
+        let tomorrow = now.date_naive().succ_opt().context("--start-at is out of range")?;
+        return local(tomorrow.and_time(time));
+    }
+
+    if let Some(naive) = DATE_TIME_FORMATS.iter().find_map(|f| NaiveDateTime::parse_from_str(value, f).ok()) {
+        let start = local(naive)?;
+        if start <= *now {
+            anyhow::bail!("--start-at {} is in the past", value);
+        }
+        return Ok(start);
+    }
+
+    anyhow::bail!("invalid --start-at '{}', expected HH:MM[:SS] or YYYY-MM-DD HH:MM[:SS]", value)
+}
+
+/// Sleep until `deadline`; returns false as soon as `quit` is set
+pub fn wait_until(deadline: Instant, quit: &AtomicBool) -> bool {
+    loop {
+        if quit.load(Ordering::SeqCst) {
+            return false;
+        }
+        let remaining = deadline.saturating_duration_since(Instant::now());
+        if remaining.is_zero() {
+            return true;
+        }
+        thread::sleep(remaining.min(Duration::from_millis(100)));
+    }
+}
+
+/// Append a block to the `blocks` attribute of a stream group's attributes
+pub fn append_block(attributes: &mut Map<String, Value>, block: &Block) -> serde_json::Result<()> {
+    let entry = serde_json::to_value(block)?;
+    match attributes.get_mut(BLOCKS_ATTRIBUTE) {
+        Some(Value::Array(blocks)) => blocks.push(entry),
+        _ => {
+            attributes.insert(BLOCKS_ATTRIBUTE.to_string(), Value::Array(vec![entry]));
+        }
+    }
+    Ok(())
+}
+
+/// Blocks stored in a stream group's attributes (empty for unscheduled recordings)
+pub fn read_blocks(attributes: &Value) -> Vec<Block> {
+    attributes
+        .get(BLOCKS_ATTRIBUTE)
+        .and_then(|blocks| serde_json::from_value(blocks.clone()).ok())
+        .unwrap_or_default()
+}
@@ -0,0 +1,189 @@
+//! Session summaries for inventory tools
+//!
+//! Reads only group attributes and array shapes (no sample data), so summarizing a
+//! store stays cheap even for long recordings. Used by `lsl-query` to list sessions
+//! across a directory of stores.
+
+use anyhow::Result;
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use zarrs::array::Array;
+use zarrs::filesystem::FilesystemStore;
+
+use crate::connection::ConnectionStats;
+use crate::zarr::{is_store_group, parse_time_anchor, read_group_attributes, stream_sample_count};
+
+/// Metadata of a single stream inside a store
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct StreamSummary {
+    pub name: String,
+    pub stream_type: Option<String>,
+    pub source_id: Option<String>,
+    pub channel_count: Option<u64>,
+    pub nominal_srate: Option<f64>,
+    pub channel_format: Option<String>,
+    pub samples: u64,
+    pub first_timestamp: Option<f64>,
+    pub last_timestamp: Option<f64>,
+    /// Resolution retries, reconnections and downtime while recording
+    pub connection: Option<ConnectionStats>,
+}
+
+impl StreamSummary {
+    /// Recorded duration in seconds (from first/last timestamp attributes)
+    pub fn duration(&self) -> Option<f64> {
+        match (self.first_timestamp, self.last_timestamp) {
+            (Some(first), Some(last)) => Some(last - first),
+            _ => None,
+        }
+    }
+}
+
+/// Metadata of a whole store (one recording session)
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct SessionSummary {
+    pub path: PathBuf,
+    pub subject: Option<String>,
+    pub session_id: Option<String>,
+    pub notes: Option<String>,
+    pub recorded_at: Option<String>,
+    pub recorder_version: Option<String>,
+    pub streams: Vec<StreamSummary>,
+}
+
+impl SessionSummary {
+    /// Span from the earliest first timestamp to the latest last timestamp
+    pub fn duration(&self) -> Option<f64> {
+        let first = self
+            .streams
+            .iter()
+            .filter_map(|s| s.first_timestamp)
+            .reduce(f64::min)?;
+        let last = self
+            .streams
+            .iter()
+            .filter_map(|s| s.last_timestamp)
+            .reduce(f64::max)?;
+        Some(last - first)
+    }
+
+    /// Total number of samples across all streams
+    pub fn total_samples(&self) -> u64 {
+        self.streams.iter().map(|s| s.samples).sum()
+    }
+
+    /// Store file name without the .zarr extension
+    pub fn name(&self) -> String {
+        self.path
+            .file_stem()
+            .map(|s| s.to_string_lossy().to_string())
+            .unwrap_or_default()
+    }
+}
+
+/// Summarize a store from its group attributes and array shapes
+pub fn summarize_store(path: &Path) -> Result<SessionSummary> {
+    let store = Arc::new(FilesystemStore::new(path)?);
+    let mut summary = SessionSummary {
+        path: path.to_path_buf(),
+        ..Default::default()
+    };
+
+    let mut stream_names: Vec<String> = std::fs::read_dir(path)?
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.file_type().map(|t| t.is_dir()).unwrap_or(false))
+        .map(|entry| entry.file_name().to_string_lossy().to_string())
+        .filter(|name| !is_store_group(name) && path.join(name).join("zarr.json").is_file())
+        .collect();
+    stream_names.sort();
+
+    for name in stream_names {
+        let stream_path = format!("/{}", name);
+        let attrs = read_group_attributes(&store, &stream_path).unwrap_or_default();
+        let info = attrs.get("stream_info");
+        let config = attrs.get("recorder_config");
+
+        let info_str = |key: &str| info.and_then(|i| i.get(key)).and_then(|v| v.as_str()).map(String::from);
+        let config_str = |key: &str| config.and_then(|c| c.get(key)).and_then(|v| v.as_str()).map(String::from);
+
+        // Session-level fields come from the first stream that has them
+        if summary.subject.is_none() {
+            summary.subject = config_str("subject");
+        }
+        if summary.session_id.is_none() {
+            summary.session_id = config_str("session_id");
+        }
+        if summary.notes.is_none() {
+            summary.notes = config_str("notes");
+        }
+        if summary.recorder_version.is_none() {
+            summary.recorder_version = config_str("recorder_version");
+        }
+        // The `start_time` anchor is canonical; older stores only have the RFC3339 strings
+        let recorded_at = attrs
+            .get("start_time")
+            .and_then(parse_time_anchor)
+            .map(|time| time.to_rfc3339())
+            .or_else(|| config_str("recorded_at"))
+            .or_else(|| attrs.get("recorded_at").and_then(|v| v.as_str()).map(String::from));
+        if let Some(recorded_at) = recorded_at
+            && summary.recorded_at.as_ref().is_none_or(|existing| recorded_at < *existing)
+        {
+            summary.recorded_at = Some(recorded_at);
+        }
+
+        let samples = Array::<FilesystemStore>::open(store.clone(), &format!("{}/time", stream_path))
+            .map(|a| stream_sample_count(&attrs, a.shape().first().copied().unwrap_or(0)))
+            .unwrap_or(0);
+
+        summary.streams.push(StreamSummary {
+            name,
+            stream_type: info_str("type"),
+            source_id: info_str("source_id"),
+            channel_count: info.and_then(|i| i.get("channel_count")).and_then(|v| v.as_u64()),
+            nominal_srate: info.and_then(|i| i.get("nominal_srate")).and_then(|v| v.as_f64()),
+            channel_format: info_str("channel_format"),
+            samples,
+            first_timestamp: attrs.get("first_timestamp").and_then(|v| v.as_f64()),
+            last_timestamp: attrs.get("last_timestamp").and_then(|v| v.as_f64()),
+            connection: ConnectionStats::from_attributes(&attrs),
+        });
+    }
+
+    Ok(summary)
+}
+
+/// Find all `.zarr` stores below a root directory (stores are not descended into)
+pub fn find_stores(root: &Path) -> Result<Vec<PathBuf>> {
+    let mut stores = Vec::new();
+    if root.extension().is_some_and(|ext| ext == "zarr") {
+        stores.push(root.to_path_buf());
+        return Ok(stores);
+    }
+
+    let mut pending = vec![root.to_path_buf()];
+    while let Some(dir) = pending.pop() {
+        let entries = match std::fs::read_dir(&dir) {
+            Ok(entries) => entries,
+            Err(e) => {
+                eprintln!("Warning: Cannot read {}: {}", dir.display(), e);
+                continue;
+            }
+        };
+        for entry in entries.filter_map(|e| e.ok()) {
+            let path = entry.path();
+            if !entry.file_type().map(|t| t.is_dir()).unwrap_or(false) {
+                continue;
+            }
+            if path.extension().is_some_and(|ext| ext == "zarr") {
+                stores.push(path);
+            } else {
+                pending.push(path);
+            }
+        }
+    }
+
+    stores.sort();
+    Ok(stores)
+}
@@ -0,0 +1,207 @@
+//! Protection against two recording sessions writing into the same store
+//!
+//! The init and metadata locks only keep single writes apart: two independent
+//! `lsl-multi-recorder` runs started with the same `--output` would still interleave
+//! their streams in one store. Each run therefore registers itself in the store's
+//! `.zarr_session.lock` for as long as it records:
+//!
+//! ```json
+//! {"holders": [{"session_id": "session_001", "pid": 4242, "host": "lab-pc",
+//!               "started": "2026-03-02T09:15:04+01:00"}]}
+//! ```
+//!
+//! A run that finds a live holder is refused. Holders on this host whose process
+//! has exited were left behind by a crashed run and are dropped; holders on other
+//! hosts cannot be checked and always count as live. `--join-session` registers the
+//! run next to the existing holders instead, for sessions recorded by
+//! multi-recorders on several hosts into one shared store. Joining requires the
+//! same `--session-id` as the running session.
+
+use anyhow::Result;
+use chrono::Local;
+use fs2::FileExt;
+use serde::{Deserialize, Serialize};
+use std::fs::{File, OpenOptions};
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use sysinfo::{Pid, ProcessesToUpdate, System};
+
+use crate::manifest::hostname;
+
+/// File in the store listing the sessions recording into it
+pub const SESSION_LOCK_FILE: &str = ".zarr_session.lock";
+
+/// One recording session registered in a store
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SessionHolder {
+    pub session_id: Option<String>,
+    pub pid: u32,
+    pub host: Option<String>,
+    /// Local time (RFC3339) at which the session took the store
+    pub started: String,
+}
+
+impl SessionHolder {
+    /// This process, recording the given session
+    pub fn current(session_id: Option<&str>) -> Self {
+        Self {
+            session_id: session_id.map(str::to_string),
+            pid: std::process::id(),
+            host: hostname(),
+            started: Local::now().to_rfc3339(),
+        }
+    }
+
+    /// Whether the holder may still be recording; only processes on this host can be checked
+    pub fn is_alive(&self) -> bool {
+        if self.host != hostname() {
+            return true;
+        }
+        let pid = Pid::from_u32(self.pid);
+        let mut system = System::new();
+        system.refresh_processes(ProcessesToUpdate::Some(&[pid]), true);
+        system.process(pid).is_some()
+    }
+}
+
+impl std::fmt::Display for SessionHolder {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "session {} (PID {} on {}, since {})",
+            self.session_id.as_deref().unwrap_or("<none>"),
+            self.pid,
+            self.host.as_deref().unwrap_or("unknown host"),
+            self.started
+        )
+    }
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct SessionLockFile {
+    holders: Vec<SessionHolder>,
+}
+
+/// Sessions registered in a store's lock file (empty if there is none)
+pub fn read_session_holders(store_path: &Path) -> Vec<SessionHolder> {
+    std::fs::read_to_string(store_path.join(SESSION_LOCK_FILE))
+        .ok()
+        .and_then(|content| serde_json::from_str::<SessionLockFile>(&content).ok())
+        .map(|lock| lock.holders)
+        .unwrap_or_default()
+}
+
+/// Registration of this process in a store's session lock, removed again on drop
+#[derive(Debug)]
+pub struct SessionLock {
+    store_path: PathBuf,
+    holder: SessionHolder,
+}
+
+impl SessionLock {
+    /// Register this process as recording `session_id` into the store
+    ///
+    /// Fails if another live session holds the store, unless `join` is set and the
+    /// session IDs match. Creates the store directory if needed.
+    pub fn acquire(store_path: &Path, session_id: Option<&str>, join: bool) -> Result<Self> {
+        std::fs::create_dir_all(store_path)?;
+        let holder = SessionHolder::current(session_id);
+        modify_lock_file(store_path, |holders| {
+            holders.retain(SessionHolder::is_alive);
+            if let Some(running) = holders.first() {
+                if !join {
+                    anyhow::bail!(
+                        "Store {} is already being recorded by {}. \
+                         Choose a different --output, or pass --join-session if both recorders belong to \
+                         the same multi-host session (delete {} if that session no longer runs)",
+                        store_path.display(),
+                        running,
+                        store_path.join(SESSION_LOCK_FILE).display()
+                    );
+                }
+                if running.session_id != holder.session_id {
+                    anyhow::bail!(
+                        "Cannot join {} in {}: this recording has session {}; \
+                         pass the same --session-id on every host",
+                        running,
+                        store_path.display(),
+                        holder.session_id.as_deref().unwrap_or("<none>")
+                    );
+                }
+            }
+            holders.push(holder.clone());
+            Ok(())
+        })?;
+        Ok(Self {
+            store_path: store_path.to_path_buf(),
+            holder,
+        })
+    }
+
+    pub fn store_path(&self) -> &Path {
+        &self.store_path
+    }
+
+    /// Sessions recording into the store besides this one
+    pub fn others(&self) -> Vec<SessionHolder> {
+        let mut holders = read_session_holders(&self.store_path);
+        if let Some(own) = holders.iter().position(|h| *h == self.holder) {
+            holders.remove(own);
+        }
+        holders
+    }
+}
+
+impl Drop for SessionLock {
+    fn drop(&mut self) {
+        let released = modify_lock_file(&self.store_path, |holders| {
+            if let Some(own) = holders.iter().position(|h| *h == self.holder) {
+                holders.remove(own);
+            }
+            Ok(())
+        });
+        match released {
+            // A store this run created but never wrote to is removed again
+            Ok(0) if !self.store_path.join("zarr.json").exists() => {
+                std::fs::remove_file(self.store_path.join(SESSION_LOCK_FILE)).ok();
+                std::fs::remove_dir(&self.store_path).ok();
+            }
+            Ok(_) => {}
+            Err(e) => eprintln!(
+                "Warning: could not release the session lock of {}: {:#}",
+                self.store_path.display(),
+                e
+            ),
+        }
+    }
+}
+
+/// Read, change and store the holder list under an exclusive lock on the file
+///
+/// Returns the number of holders left.
+fn modify_lock_file(store_path: &Path, change: impl FnOnce(&mut Vec<SessionHolder>) -> Result<()>) -> Result<usize> {
+    let mut file = OpenOptions::new()
+        .create(true)
+        .read(true)
+        .write(true)
+        .truncate(false)
+        .open(store_path.join(SESSION_LOCK_FILE))?;
+    file.lock_exclusive()?;
+    let result = (|| -> Result<usize> {
+        let mut content = String::new();
+        file.read_to_string(&mut content)?;
+        let mut lock: SessionLockFile = serde_json::from_str(&content).unwrap_or_default();
+        change(&mut lock.holders)?;
+        rewrite(&mut file, &serde_json::to_string_pretty(&lock)?)?;
+        Ok(lock.holders.len())
+    })();
+    file.unlock()?;
+    result
+}
+
+fn rewrite(file: &mut File, content: &str) -> std::io::Result<()> {
+    file.set_len(0)?;
+    file.seek(SeekFrom::Start(0))?;
+    file.write_all(content.as_bytes())?;
+    file.sync_all()
+}
@@ -0,0 +1,346 @@
+//! Test signal generators for `lsl-dummy-stream`
+//!
+//! A counter ramp or a plain sine tells little about how filters, quality checks or
+//! validation code behave on real data, so the dummy stream can produce:
+//!
+//! - **Waveforms** (`--waveform`): amplitude-modulated sines (the default), pure
+//!   sines, linear chirps repeatedly sweeping a frequency range, and white or pink
+//!   (1/f) noise.
+//! - **Simulations** (`--simulate`): band-limited physiological signals. `emg` is
+//!   20-450 Hz band-passed Gaussian noise in bursts of contraction, `eeg` is pink
+//!   noise band-passed to 0.5-40 Hz with a waxing and waning alpha rhythm, `ecg` is
+//!   a PQRST beat at about 72 bpm with heart rate variability and baseline wander.
+//! - **Trigger channel** (`--trigger-frequency`): the last channel carries a 0/1
+//!   square wave, like the trigger input of an amplifier.
+//!
+//! Values lie within [-1, 1] and are scaled to the stream's data type by the caller.
+//! Noise comes from a seeded generator, so the same seed reproduces the same signal.
+
+use anyhow::Result;
+use std::f64::consts::PI;
+
+/// Signal shape of `--waveform`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum Waveform {
+    /// Sines with a slowly varying amplitude, one frequency per channel (default)
+    ModulatedSine,
+    /// Constant-amplitude sines, one frequency per channel
+    Sine,
+    /// Linear sweeps across the frequency range, restarting every chirp duration
+    Chirp,
+    /// Uniform white noise
+    WhiteNoise,
+    /// Pink (1/f) noise
+    PinkNoise,
+}
+
+/// Physiological signal of `--simulate`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum Simulation {
+    /// Band-passed noise (20-450 Hz) in bursts of contraction
+    Emg,
+    /// Pink background activity (0.5-40 Hz) with an alpha rhythm
+    Eeg,
+    /// PQRST beats at about 72 bpm
+    Ecg,
+}
+
+impl Simulation {
+    /// LSL stream type matching the simulated signal
+    pub fn stream_type(&self) -> &'static str {
+        match self {
+            Simulation::Emg => "EMG",
+            Simulation::Eeg => "EEG",
+            Simulation::Ecg => "ECG",
+        }
+    }
+}
+
+/// What to generate
+#[derive(Debug, Clone)]
+pub struct SignalOptions {
+    pub waveform: Waveform,
+    /// Replaces the waveform with a simulated signal
+    pub simulate: Option<Simulation>,
+    /// Sine frequency of each channel in Hz; also sets the number of channels
+    pub frequencies: Vec<f64>,
+    /// Start and end frequency of a chirp in Hz
+    pub chirp_range: (f64, f64),
+    /// Seconds per chirp sweep
+    pub chirp_duration: f64,
+    /// Frequency of a square wave on the last channel
+    pub trigger_frequency: Option<f64>,
+    pub sample_rate: f64,
+    pub seed: u64,
+}
+
+/// Second-order IIR section (Butterworth low- or high-pass)
+#[derive(Debug, Clone)]
+struct Biquad {
+    b: [f64; 3],
+    a: [f64; 2],
+    z: [f64; 2],
+}
+
+impl Biquad {
+    fn lowpass(sample_rate: f64, cutoff: f64) -> Self {
+        let (cos, alpha) = Self::prewarp(sample_rate, cutoff);
+        Self::normalized([(1.0 - cos) / 2.0, 1.0 - cos, (1.0 - cos) / 2.0], cos, alpha)
+    }
+
+    fn highpass(sample_rate: f64, cutoff: f64) -> Self {
+        let (cos, alpha) = Self::prewarp(sample_rate, cutoff);
+        Self::normalized([(1.0 + cos) / 2.0, -(1.0 + cos), (1.0 + cos) / 2.0], cos, alpha)
+    }
+
+    fn prewarp(sample_rate: f64, cutoff: f64) -> (f64, f64) {
+        let w0 = 2.0 * PI * cutoff / sample_rate;
+        (w0.cos(), w0.sin() / (2.0 * std::f64::consts::FRAC_1_SQRT_2))
+    }
+
+    fn normalized(b: [f64; 3], cos: f64, alpha: f64) -> Self {
+        let a0 = 1.0 + alpha;
+        Self {
+            b: [b[0] / a0, b[1] / a0, b[2] / a0],
+            a: [-2.0 * cos / a0, (1.0 - alpha) / a0],
+            z: [0.0; 2],
+        }
+    }
+
+    fn process(&mut self, x: f64) -> f64 {
+        let y = self.b[0] * x + self.z[0];
+        self.z[0] = self.b[1] * x - self.a[0] * y + self.z[1];
+        self.z[1] = self.b[2] * x - self.a[1] * y;
+        y
+    }
+}
+
+/// Pink noise from white noise (Paul Kellett's refined filter)
+#[derive(Debug, Clone, Default)]
+struct PinkFilter {
+    b: [f64; 7],
+}
+
+impl PinkFilter {
+    fn process(&mut self, white: f64) -> f64 {
+        let b = &mut self.b;
+        b[0] = 0.99886 * b[0] + white * 0.0555179;
+        b[1] = 0.99332 * b[1] + white * 0.0750759;
+        b[2] = 0.96900 * b[2] + white * 0.1538520;
+        b[3] = 0.86650 * b[3] + white * 0.3104856;
+        b[4] = 0.55000 * b[4] + white * 0.5329522;
+        b[5] = -0.7616 * b[5] - white * 0.0168980;
+        let pink = b[0] + b[1] + b[2] + b[3] + b[4] + b[5] + b[6] + white * 0.5362;
+        b[6] = white * 0.115926;
+        pink * 0.11
+    }
+}
+
+/// State of one generated channel
+#[derive(Debug, Clone)]
+struct Channel {
+    frequency: f64,
+    /// Amplitude and phase of simulated signals, which differ between channels
+    gain: f64,
+    phase: f64,
+    rng: fastrand::Rng,
+    pink: PinkFilter,
+    filters: Vec<Biquad>,
+}
+
+impl Channel {
+    fn gaussian(&mut self) -> f64 {
+        let u1 = 1.0 - self.rng.f64();
+        let u2 = self.rng.f64();
+        (-2.0 * u1.ln()).sqrt() * (2.0 * PI * u2).cos()
+    }
+
+    fn filter(&mut self, x: f64) -> f64 {
+        self.filters.iter_mut().fold(x, |x, filter| filter.process(x))
+    }
+}
+
+/// R-peak times of the simulated heart, shared by all ECG channels
+#[derive(Debug, Clone)]
+struct Heartbeat {
+    previous: f64,
+    next: f64,
+    rng: fastrand::Rng,
+}
+
+impl Heartbeat {
+    /// Seconds between beats at rest (72 bpm)
+    const INTERVAL: f64 = 60.0 / 72.0;
+    /// P, Q, R, S and T waves as (offset from the R peak in s, amplitude, width in s)
+    const WAVES: [(f64, f64, f64); 5] = [
+        (-0.2, 0.12, 0.025),
+        (-0.03, -0.12, 0.01),
+        (0.0, 1.0, 0.012),
+        (0.035, -0.25, 0.01),
+        (0.25, 0.3, 0.04),
+    ];
+
+    fn new(seed: u64) -> Self {
+        Self {
+            previous: f64::NEG_INFINITY,
+            next: 0.3,
+            rng: fastrand::Rng::with_seed(seed),
+        }
+    }
+
+    /// Beat waveform at time `t`, from the surrounding R peaks
+    fn value(&mut self, t: f64) -> f64 {
+        // The T wave of a beat is over 0.4 s after its R peak
+        while t >= self.next + 0.4 {
+            self.previous = self.next;
+            let variability = 1.0 + 0.04 * (self.rng.f64() * 2.0 - 1.0);
+            self.next += Self::INTERVAL * variability;
+        }
+        [self.previous, self.next]
+            .iter()
+            .flat_map(|peak| {
+                Self::WAVES
+                    .iter()
+                    .map(move |(offset, amplitude, width)| {
+                        amplitude * (-((t - peak - offset) / width).powi(2) / 2.0).exp()
+                    })
+            })
+            .sum()
+    }
+}
+
+/// Sample-by-sample generator of a multichannel test signal
+#[derive(Debug, Clone)]
+pub struct SignalGenerator {
+    options: SignalOptions,
+    channels: Vec<Channel>,
+    heartbeat: Heartbeat,
+    /// Brings band-passed white noise back to unit variance
+    noise_gain: f64,
+    index: u64,
+}
+
+impl SignalGenerator {
+    pub fn new(options: SignalOptions) -> Result<Self> {
+        let sample_rate = options.sample_rate;
+        if !sample_rate.is_finite() || sample_rate <= 0.0 {
+            anyhow::bail!("Test signals need a positive sample rate");
+        }
+        let nyquist = sample_rate / 2.0;
+        if let Some(frequency) = options.trigger_frequency
+            && !(frequency > 0.0 && frequency < nyquist)
+        {
+            anyhow::bail!("Trigger frequency must be between 0 and {} Hz (half the sample rate)", nyquist);
+        }
+        if options.waveform == Waveform::Chirp && options.chirp_duration <= 0.0 {
+            anyhow::bail!("Chirp duration must be positive");
+        }
+        // Pass bands are narrowed for low sample rates, both edges so that they stay ordered
+        let band = |low: f64, high: f64| Some((low.min(0.2 * sample_rate), high.min(0.45 * sample_rate)));
+        let band = match options.simulate {
+            Some(Simulation::Emg) => band(20.0, 450.0),
+            Some(Simulation::Eeg) => band(0.5, 40.0),
+            _ => None,
+        };
+
+        let channels = options
+            .frequencies
+            .iter()
+            .enumerate()
+            .map(|(index, &frequency)| {
+                let mut rng = fastrand::Rng::with_seed(options.seed.wrapping_add(index as u64 + 1));
+                Channel {
+                    frequency,
+                    gain: 0.6 + 0.4 * rng.f64(),
+                    phase: 2.0 * PI * rng.f64(),
+                    rng,
+                    pink: PinkFilter::default(),
+                    filters: band
+                        .map(|(low, high)| {
+                            vec![Biquad::highpass(sample_rate, low), Biquad::lowpass(sample_rate, high)]
+                        })
+                        .unwrap_or_default(),
+                }
+            })
+            .collect();
+
+        Ok(Self {
+            heartbeat: Heartbeat::new(options.seed),
+            noise_gain: band.map_or(1.0, |(low, high)| (sample_rate / (2.0 * (high - low))).sqrt()),
+            options,
+            channels,
+            index: 0,
+        })
+    }
+
+    /// Values of the next sample, one per channel, within [-1, 1]
+    pub fn next_sample(&mut self) -> Vec<f64> {
+        let t = self.index as f64 / self.options.sample_rate;
+        self.index += 1;
+
+        let shared = match self.options.simulate {
+            // Contractions of 2 s every 4 s over a low resting activity
+            Some(Simulation::Emg) => 0.1 + 0.9 * (2.0 * PI * t / 4.0).sin().max(0.0).powi(2),
+            Some(Simulation::Ecg) => self.heartbeat.value(t),
+            _ => 0.0,
+        };
+        let options = &self.options;
+        let noise_gain = self.noise_gain;
+        let mut sample: Vec<f64> = self
+            .channels
+            .iter_mut()
+            .enumerate()
+            .map(|(index, channel)| {
+                let value = match options.simulate {
+                    Some(Simulation::Emg) => {
+                        let noise = channel.gaussian();
+                        0.2 * channel.gain * shared * noise_gain * channel.filter(noise)
+                    }
+                    Some(Simulation::Eeg) => {
+                        let white = channel.rng.f64() * 2.0 - 1.0;
+                        let pink = channel.pink.process(white);
+                        let background = 1.2 * channel.filter(pink);
+                        let waxing = 0.6 + 0.4 * (2.0 * PI * 0.15 * t + channel.phase).sin();
+                        let alpha = 0.3 * channel.gain * waxing * (2.0 * PI * 10.0 * t + channel.phase).sin();
+                        background + alpha
+                    }
+                    Some(Simulation::Ecg) => {
+                        // Leads see the heart's axis at different angles
+                        let lead = (index as f64 * 0.7).cos();
+                        let wander = 0.05 * (2.0 * PI * 0.25 * t + channel.phase).sin();
+                        0.8 * lead * shared + wander + 0.01 * channel.gaussian()
+                    }
+                    None => waveform_value(options, channel, t),
+                };
+                value.clamp(-1.0, 1.0)
+            })
+            .collect();
+
+        if let (Some(frequency), Some(last)) = (options.trigger_frequency, sample.last_mut()) {
+            *last = if (t * frequency).fract() < 0.5 { 1.0 } else { 0.0 };
+        }
+        sample
+    }
+}
+
+fn waveform_value(options: &SignalOptions, channel: &mut Channel, t: f64) -> f64 {
+    match options.waveform {
+        Waveform::ModulatedSine => {
+            // Varying amplitude: 0.5 + 0.3 * sin(2π * 0.1 * freq * t)
+            let amplitude = 0.5 + 0.3 * (2.0 * PI * 0.1 * channel.frequency * t).sin();
+            amplitude * (2.0 * PI * channel.frequency * t).sin()
+        }
+        Waveform::Sine => (2.0 * PI * channel.frequency * t).sin(),
+        Waveform::Chirp => {
+            let (start, end) = options.chirp_range;
+            let sweep_time = t % options.chirp_duration;
+            let rate = (end - start) / options.chirp_duration;
+            (2.0 * PI * (start * sweep_time + rate * sweep_time * sweep_time / 2.0)).sin()
+        }
+        Waveform::WhiteNoise => channel.rng.f64() * 2.0 - 1.0,
+        Waveform::PinkNoise => {
+            let white = channel.rng.f64() * 2.0 - 1.0;
+            channel.pink.process(white)
+        }
+    }
+}
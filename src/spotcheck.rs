@@ -0,0 +1,265 @@
+//! Go/no-go verdict and quicklook plots for pre-session spot checks
+//!
+//! `lsl-spotcheck` records a short test session, validates it with
+//! `lsl-validate --format json` and judges that report here: every expected
+//! stream must have delivered samples, regular streams must run close to their
+//! nominal rate without losing more than a small fraction of the recording to
+//! gaps, and the streams must be synchronized. Clock drift over budget is only a
+//! warning, since a few seconds of data cannot estimate it reliably.
+//!
+//! Quicklook plots are SVG files with one trace per channel, reduced to the
+//! minimum and maximum of each pixel column, so flat, clipped or noisy channels
+//! stand out at a glance. Marker streams are not plotted.
+
+use anyhow::Result;
+use serde_json::Value;
+use std::fmt::Write;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use zarrs::filesystem::FilesystemStore;
+
+use crate::export::{list_streams, ExportStream, EXPORT_BLOCK_SAMPLES};
+
+/// Width of the plotted traces in a quicklook (pixels)
+const PLOT_WIDTH: usize = 1000;
+/// Left margin holding the channel labels (pixels)
+const LABEL_WIDTH: usize = 120;
+/// Height of one channel trace (pixels)
+const LANE_HEIGHT: usize = 60;
+const TITLE_HEIGHT: usize = 30;
+const AXIS_HEIGHT: usize = 20;
+
+/// Limits a spot check recording has to meet for a GO
+#[derive(Debug, Clone)]
+pub struct SpotcheckCriteria {
+    /// Largest deviation of the measured from the nominal sample rate (percent)
+    pub max_rate_error_percent: f64,
+    /// Largest share of a stream's duration lost to gaps (percent)
+    pub max_lost_percent: f64,
+}
+
+impl Default for SpotcheckCriteria {
+    fn default() -> Self {
+        Self {
+            max_rate_error_percent: 5.0,
+            max_lost_percent: 1.0,
+        }
+    }
+}
+
+/// Outcome of a spot check
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Verdict {
+    /// Reasons for a NO-GO
+    pub problems: Vec<String>,
+    /// Findings worth a look that do not block the session
+    pub warnings: Vec<String>,
+}
+
+impl Verdict {
+    /// Whether the setup is ready for the session
+    pub fn go(&self) -> bool {
+        self.problems.is_empty()
+    }
+}
+
+/// Judge an `lsl-validate --format json` report
+///
+/// `expected_streams` are stream names that must be present (none when the
+/// streams were discovered on the network).
+pub fn evaluate(report: &Value, expected_streams: &[String], criteria: &SpotcheckCriteria) -> Verdict {
+    let mut verdict = Verdict::default();
+    let streams = report.get("streams").and_then(|v| v.as_array()).cloned().unwrap_or_default();
+    if streams.is_empty() {
+        verdict.problems.push("No stream was recorded".to_string());
+        return verdict;
+    }
+
+    for name in expected_streams {
+        if !streams.iter().any(|s| s["name"] == name.as_str()) {
+            verdict.problems.push(format!("Stream '{}' was not recorded", name));
+        }
+    }
+
+    for stream in &streams {
+        let name = stream["name"].as_str().unwrap_or("<unnamed>");
+        if stream["samples"].as_u64().unwrap_or(0) == 0 {
+            verdict.problems.push(format!("Stream '{}' recorded no samples", name));
+            continue;
+        }
+        let nominal_rate = stream["nominal_rate"].as_f64().unwrap_or(0.0);
+        if nominal_rate <= 0.0 {
+            continue;
+        }
+
+        let accuracy = stream["rate_accuracy_percent"].as_f64().unwrap_or(0.0);
+        if (accuracy - 100.0).abs() > criteria.max_rate_error_percent {
+            verdict.problems.push(format!(
+                "Stream '{}' runs at {:.1}% of its nominal {} Hz",
+                name, accuracy, nominal_rate
+            ));
+        }
+
+        let duration = stream["duration"].as_f64().unwrap_or(0.0);
+        let lost = stream["gaps"]["lost_time_ms"].as_f64().unwrap_or(0.0) / 1000.0;
+        if duration > 0.0 && lost / duration * 100.0 > criteria.max_lost_percent {
+            verdict.problems.push(format!(
+                "Stream '{}' lost {:.1}% of the recording to {} gap(s)",
+                name,
+                lost / duration * 100.0,
+                stream["gaps"]["count"].as_u64().unwrap_or(0)
+            ));
+        }
+    }
+
+    let messages = |key: &str| -> Vec<String> {
+        report
+            .get(key)
+            .and_then(|v| v.as_array())
+            .map(|items| items.iter().filter_map(|v| v.as_str()).map(String::from).collect())
+            .unwrap_or_default()
+    };
+    verdict.problems.extend(messages("failures"));
+    verdict.warnings.extend(messages("drift_warnings"));
+    verdict
+}
+
+/// Escape text for SVG
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+}
+
+/// Render channels as stacked traces over `duration` seconds
+///
+/// Each channel is scaled to its own finite range; channels without finite
+/// values or with a constant value are labeled as such.
+pub fn quicklook_svg(title: &str, duration: f64, channels: &[Vec<f64>], labels: &[String]) -> String {
+    let width = LABEL_WIDTH + PLOT_WIDTH + 10;
+    let height = TITLE_HEIGHT + channels.len() * LANE_HEIGHT + AXIS_HEIGHT;
+    let mut svg = String::new();
+    let _ = writeln!(
+        svg,
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="{}" height="{}" font-family="sans-serif" font-size="11">"#,
+        width, height
+    );
+    let _ = writeln!(svg, r#"<rect width="100%" height="100%" fill="white"/>"#);
+    let _ = writeln!(svg, r#"<text x="4" y="18" font-size="14">{}</text>"#, escape(title));
+
+    for (lane, values) in channels.iter().enumerate() {
+        let top = (TITLE_HEIGHT + lane * LANE_HEIGHT) as f64;
+        let middle = top + LANE_HEIGHT as f64 / 2.0;
+        let label = labels.get(lane).map(String::as_str).unwrap_or("");
+        let _ = writeln!(svg, r#"<text x="4" y="{:.1}">{}</text>"#, middle + 4.0, escape(label));
+        let _ = writeln!(
+            svg,
+            r##"<line x1="{}" y1="{:.1}" x2="{}" y2="{:.1}" stroke="#ddd"/>"##,
+            LABEL_WIDTH,
+            top + LANE_HEIGHT as f64,
+            LABEL_WIDTH + PLOT_WIDTH,
+            top + LANE_HEIGHT as f64
+        );
+
+        let finite = values.iter().copied().filter(|v| v.is_finite());
+        let (min, max) = finite.fold((f64::INFINITY, f64::NEG_INFINITY), |(lo, hi), v| (lo.min(v), hi.max(v)));
+        if min > max {
+            let _ = writeln!(svg, r#"<text x="{}" y="{:.1}" fill="red">no finite values</text>"#, LABEL_WIDTH + 4, middle + 4.0);
+            continue;
+        }
+        if min == max {
+            let _ = writeln!(
+                svg,
+                r##"<line x1="{}" y1="{:.1}" x2="{}" y2="{:.1}" stroke="#c00"/><text x="{}" y="{:.1}" fill="red">flat ({})</text>"##,
+                LABEL_WIDTH,
+                middle,
+                LABEL_WIDTH + PLOT_WIDTH,
+                middle,
+                LABEL_WIDTH + 4,
+                middle - 4.0,
+                min
+            );
+            continue;
+        }
+
+        let scale = (LANE_HEIGHT - 10) as f64 / (max - min);
+        let y = |v: f64| top + LANE_HEIGHT as f64 - 5.0 - (v - min) * scale;
+        let mut points = String::new();
+        let n = values.len();
+        let columns = PLOT_WIDTH.min(n);
+        for column in 0..columns {
+            let start = column * n / columns;
+            let end = ((column + 1) * n / columns).max(start + 1);
+            let (lo, hi) = values[start..end]
+                .iter()
+                .filter(|v| v.is_finite())
+                .fold((f64::INFINITY, f64::NEG_INFINITY), |(lo, hi), &v| (lo.min(v), hi.max(v)));
+            if lo > hi {
+                continue;
+            }
+            let x = LABEL_WIDTH as f64 + column as f64 * PLOT_WIDTH as f64 / columns as f64;
+            let _ = write!(points, "{:.1},{:.1} {:.1},{:.1} ", x, y(hi), x, y(lo));
+        }
+        let _ = writeln!(
+            svg,
+            r##"<polyline points="{}" fill="none" stroke="#1f77b4" stroke-width="1"/>"##,
+            points.trim_end()
+        );
+    }
+
+    let axis = (TITLE_HEIGHT + channels.len() * LANE_HEIGHT + 14) as f64;
+    let _ = writeln!(svg, r#"<text x="{}" y="{:.1}">0 s</text>"#, LABEL_WIDTH, axis);
+    let _ = writeln!(
+        svg,
+        r#"<text x="{}" y="{:.1}" text-anchor="end">{:.1} s</text>"#,
+        LABEL_WIDTH + PLOT_WIDTH,
+        axis,
+        duration
+    );
+    svg.push_str("</svg>\n");
+    svg
+}
+
+/// Write a quicklook SVG of every numeric stream in a store to `out_dir`
+///
+/// Plots at most `max_channels` channels per stream, skipping channels marked
+/// bad. Returns the written files.
+pub fn write_quicklooks(store_path: &Path, out_dir: &Path, max_channels: usize) -> Result<Vec<PathBuf>> {
+    let store = Arc::new(FilesystemStore::new(store_path)?);
+    std::fs::create_dir_all(out_dir)?;
+
+    let mut written = Vec::new();
+    for name in list_streams(store_path)? {
+        let stream = ExportStream::open(&store, &name, false)?;
+        if stream.is_marker() || stream.sample_count == 0 {
+            continue;
+        }
+        let selected: Vec<usize> = stream.export_channels(false).into_iter().take(max_channels).collect();
+        let all_labels = stream.channel_labels();
+        let labels: Vec<String> = selected.iter().map(|&c| all_labels[c].clone()).collect();
+
+        let mut channels = vec![Vec::with_capacity(stream.sample_count as usize); selected.len()];
+        let mut read = 0;
+        while read < stream.sample_count {
+            let len = EXPORT_BLOCK_SAMPLES.min(stream.sample_count - read);
+            let (_, values) = stream.read_block(read, len)?;
+            let Some(values) = values.to_f64() else {
+                break;
+            };
+            for sample in values.chunks(stream.channel_count) {
+                for (trace, &channel) in channels.iter_mut().zip(&selected) {
+                    trace.push(sample[channel]);
+                }
+            }
+            read += len;
+        }
+
+        let duration = match stream.timestamp_range()? {
+            Some((first, last)) => last - first,
+            None => 0.0,
+        };
+        let title = format!("{}: {} samples, {:.1} s", name, stream.sample_count, duration);
+        let path = out_dir.join(format!("{}.svg", name));
+        std::fs::write(&path, quicklook_svg(&title, duration, &channels, &labels))?;
+        written.push(path);
+    }
+    Ok(written)
+}
@@ -0,0 +1,442 @@
+//! Live recording status over HTTP (`--status-port`)
+//!
+//! `lsl-recorder --status-port 8080` answers `GET /status` (or `/`) with a JSON
+//! snapshot of the recording, so dashboards and the experiment control PC can
+//! watch acquisition health without access to the recorder's terminal:
+//!
+//! ```json
+//! {"tool": "lsl-recorder", "state": "recording", "uptime_s": 312.4, "streams": [
+//!   {"stream": "EMG", "state": "recording", "samples_recorded": 598000, "rate_hz": 1999.6,
+//!    "nominal_srate": 2000.0, "buffer_fill": 0.02, "last_timestamp": 81234.5678,
+//!    "dropped_samples_estimate": 0, "elapsed_s": 299.0, "since_flush_s": 0.4,
+//!    "write_queue": 0, "write_latency_ms": 3.2, "write_latency_max_ms": 41.7,
+//!    "implausible_samples": 0}]}
+//! ```
+//!
+//! The recording loop keeps its figures in a [`StatusTracker`] and publishes them a
+//! few times per second; the server thread only reads that snapshot, so slow or
+//! stalled clients never delay sample pulls. Dropped samples are estimated from
+//! timestamp gaps of regular streams: a gap of `n` sample periods counts `n - 1`
+//! missing samples (pauses between STOP and START are not counted). The write figures
+//! come from the Zarr writer thread: flushes queued for it and the time it takes per
+//! flush, so a disk that falls behind shows up before the queue fills.
+//! `implausible_samples` counts samples outside `--plausible-range` (`null` without one).
+//!
+//! `lsl-multi-recorder --status-port` serves all its streams in one document. Its
+//! recorders print their figures as `STATUS PROGRESS` lines
+//! ([`StreamStatus::progress_line`], enabled by `--status-interval`), which the
+//! multi-recorder parses instead of logging; with its own `--status-interval` it
+//! forwards them, labeled, for the dashboard of a TUI recorder tab.
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::io::{BufRead, BufReader, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// How often the recording loop refreshes the shared snapshot
+const PUBLISH_INTERVAL: Duration = Duration::from_millis(250);
+
+/// Window over which the current sample rate is measured
+const RATE_WINDOW: Duration = Duration::from_secs(1);
+
+/// Upper bound for reading a request or writing a response
+const CLIENT_IO_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// Recording state of one stream
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RecordingState {
+    /// Looking for the stream on the network
+    #[default]
+    Resolving,
+    /// Connected, waiting for START
+    Ready,
+    Recording,
+    /// Paused by STOP after recording
+    Stopped,
+    /// Between PAUSE and RESUME, within the same run
+    Paused,
+    /// A regular stream stopped delivering samples (`STATUS STREAM_LOST`)
+    Lost,
+    /// Recording ended and the store was finalized
+    Finished,
+}
+
+impl RecordingState {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            RecordingState::Resolving => "resolving",
+            RecordingState::Ready => "ready",
+            RecordingState::Recording => "recording",
+            RecordingState::Stopped => "stopped",
+            RecordingState::Paused => "paused",
+            RecordingState::Lost => "lost",
+            RecordingState::Finished => "finished",
+        }
+    }
+
+    fn parse(text: &str) -> Option<Self> {
+        [
+            RecordingState::Resolving,
+            RecordingState::Ready,
+            RecordingState::Recording,
+            RecordingState::Stopped,
+            RecordingState::Paused,
+            RecordingState::Lost,
+            RecordingState::Finished,
+        ]
+        .into_iter()
+        .find(|state| state.as_str() == text)
+    }
+}
+
+/// Acquisition figures of one stream
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct StreamStatus {
+    pub stream: String,
+    pub state: RecordingState,
+    pub samples_recorded: u64,
+    /// Samples per second over the last second of recording
+    pub rate_hz: f64,
+    pub nominal_srate: f64,
+    /// Fraction of the write buffer's capacity in use (0-1)
+    pub buffer_fill: f64,
+    /// LSL timestamp of the latest sample
+    pub last_timestamp: Option<f64>,
+    /// Samples missing from timestamp gaps (regular streams only)
+    pub dropped_samples_estimate: u64,
+    /// Seconds spent recording (stops and pauses excluded)
+    #[serde(default)]
+    pub elapsed_s: f64,
+    /// Seconds since the write buffer was last flushed to the store
+    #[serde(default)]
+    pub since_flush_s: Option<f64>,
+    /// Flushes waiting for or being written by the writer thread
+    #[serde(default)]
+    pub write_queue: usize,
+    /// Mean time the writer thread takes per flush (ms), once it has written one
+    #[serde(default)]
+    pub write_latency_ms: Option<f64>,
+    /// Longest write so far (ms)
+    #[serde(default)]
+    pub write_latency_max_ms: Option<f64>,
+    /// Samples with a value outside `--plausible-range`, if a range is set
+    #[serde(default)]
+    pub implausible_samples: Option<u64>,
+}
+
+impl StreamStatus {
+    /// Status of a stream that is still being resolved
+    pub fn new(stream: &str) -> Self {
+        Self {
+            stream: stream.to_string(),
+            ..Self::default()
+        }
+    }
+
+    /// The `STATUS PROGRESS ...` line for lsl-multi-recorder
+    pub fn progress_line(&self) -> String {
+        let mut line = format!(
+            "STATUS PROGRESS state={} samples={} rate={:.1}Hz nominal={}Hz buffer={:.3} dropped={}",
+            self.state.as_str(),
+            self.samples_recorded,
+            self.rate_hz,
+            self.nominal_srate,
+            self.buffer_fill,
+            self.dropped_samples_estimate
+        );
+        line.push_str(&format!(" elapsed={:.1}s", self.elapsed_s));
+        if let Some(timestamp) = self.last_timestamp {
+            line.push_str(&format!(" last_ts={:.6}", timestamp));
+        }
+        if let Some(since_flush) = self.since_flush_s {
+            line.push_str(&format!(" flushed={:.1}s", since_flush));
+        }
+        if let (Some(latency), Some(max)) = (self.write_latency_ms, self.write_latency_max_ms) {
+            line.push_str(&format!(" queue={} write={:.1}ms write_max={:.1}ms", self.write_queue, latency, max));
+        }
+        if let Some(implausible) = self.implausible_samples {
+            line.push_str(&format!(" implausible={}", implausible));
+        }
+        line
+    }
+
+    /// Parse a `STATUS PROGRESS` line anywhere in `line` as the status of `stream`
+    pub fn parse_progress(stream: &str, line: &str) -> Option<Self> {
+        let (_, fields) = line.split_once("STATUS PROGRESS ")?;
+        let mut status = Self::new(stream);
+        for field in fields.split_whitespace() {
+            let Some((key, value)) = field.split_once('=') else {
+                continue;
+            };
+            let number = |unit: &str| value.strip_suffix(unit).and_then(|v| v.parse::<f64>().ok());
+            match key {
+                "state" => status.state = RecordingState::parse(value)?,
+                "samples" => status.samples_recorded = value.parse().ok()?,
+                "rate" => status.rate_hz = number("Hz")?,
+                "nominal" => status.nominal_srate = number("Hz")?,
+                "buffer" => status.buffer_fill = value.parse().ok()?,
+                "dropped" => status.dropped_samples_estimate = value.parse().ok()?,
+                "last_ts" => status.last_timestamp = Some(value.parse().ok()?),
+                "elapsed" => status.elapsed_s = number("s")?,
+                "flushed" => status.since_flush_s = Some(number("s")?),
+                "queue" => status.write_queue = value.parse().ok()?,
+                "write" => status.write_latency_ms = Some(number("ms")?),
+                "write_max" => status.write_latency_max_ms = Some(number("ms")?),
+                "implausible" => status.implausible_samples = Some(value.parse().ok()?),
+                _ => {}
+            }
+        }
+        Some(status)
+    }
+}
+
+/// Overall state of a session: recording while any stream records, else the most pressing state
+pub fn session_state(streams: &[StreamStatus]) -> RecordingState {
+    let any = |state: RecordingState| streams.iter().any(|s| s.state == state);
+    if streams.is_empty() {
+        RecordingState::Resolving
+    } else if any(RecordingState::Recording) {
+        RecordingState::Recording
+    } else if any(RecordingState::Lost) {
+        RecordingState::Lost
+    } else if streams.iter().all(|s| s.state == RecordingState::Finished) {
+        RecordingState::Finished
+    } else if any(RecordingState::Paused) {
+        RecordingState::Paused
+    } else if any(RecordingState::Stopped) {
+        RecordingState::Stopped
+    } else if any(RecordingState::Ready) {
+        RecordingState::Ready
+    } else {
+        RecordingState::Resolving
+    }
+}
+
+/// Keeps the status of one stream up to date from the recording loop
+pub struct StatusTracker {
+    status: StreamStatus,
+    shared: Arc<Mutex<StreamStatus>>,
+    // Previous timestamp within the current recording segment (reset on STOP)
+    previous_timestamp: Option<f64>,
+    window_start: Instant,
+    window_samples: u64,
+    last_publish: Instant,
+    progress_interval: Option<Duration>,
+    last_progress: Instant,
+    // Recording time of finished segments, and the start of the current one
+    recorded: Duration,
+    recording_since: Option<Instant>,
+    last_flush: Option<Instant>,
+}
+
+impl StatusTracker {
+    /// Tracker for `stream`; prints `STATUS PROGRESS` every `progress_interval` seconds (0 disables)
+    pub fn new(stream: &str, progress_interval: f64) -> Self {
+        let status = StreamStatus::new(stream);
+        let now = Instant::now();
+        Self {
+            shared: Arc::new(Mutex::new(status.clone())),
+            status,
+            previous_timestamp: None,
+            window_start: now,
+            window_samples: 0,
+            last_publish: now,
+            progress_interval: (progress_interval > 0.0).then(|| Duration::from_secs_f64(progress_interval)),
+            last_progress: now,
+            recorded: Duration::ZERO,
+            recording_since: None,
+            last_flush: None,
+        }
+    }
+
+    /// Snapshot read by the status server
+    pub fn shared(&self) -> Arc<Mutex<StreamStatus>> {
+        self.shared.clone()
+    }
+
+    /// Current figures (not yet published ones included)
+    pub fn status(&self) -> &StreamStatus {
+        &self.status
+    }
+
+    pub fn set_nominal_srate(&mut self, nominal_srate: f64) {
+        self.status.nominal_srate = nominal_srate;
+    }
+
+    /// Change the state; published right away
+    pub fn set_state(&mut self, state: RecordingState) {
+        if self.status.state == state {
+            return;
+        }
+        if state != RecordingState::Recording {
+            self.previous_timestamp = None;
+            self.status.rate_hz = 0.0;
+            if let Some(since) = self.recording_since.take() {
+                self.recorded += since.elapsed();
+            }
+        } else {
+            self.recording_since = Some(Instant::now());
+        }
+        self.status.state = state;
+        self.update_clocks();
+        self.window_start = Instant::now();
+        self.window_samples = 0;
+        self.publish();
+    }
+
+    /// Count a recorded sample and estimate samples missing before it
+    pub fn sample(&mut self, timestamp: f64) {
+        self.status.samples_recorded += 1;
+        self.window_samples += 1;
+        if let Some(previous) = self.previous_timestamp
+            && self.status.nominal_srate > 0.0
+        {
+            let periods = (timestamp - previous) * self.status.nominal_srate;
+            if periods >= 1.5 {
+                self.status.dropped_samples_estimate += periods.round() as u64 - 1;
+            }
+        }
+        self.previous_timestamp = Some(timestamp);
+        self.status.last_timestamp = Some(timestamp);
+    }
+
+    /// Fraction of the write buffer in use
+    pub fn set_buffer_fill(&mut self, fill: f64) {
+        self.status.buffer_fill = fill;
+    }
+
+    /// Queue depth of the writer thread, with its mean and longest write (ms) once it has written
+    pub fn set_write_stats(&mut self, queue: usize, latency_ms: Option<(f64, f64)>) {
+        self.status.write_queue = queue;
+        self.status.write_latency_ms = latency_ms.map(|(mean, _)| mean);
+        self.status.write_latency_max_ms = latency_ms.map(|(_, max)| max);
+    }
+
+    /// Samples outside the plausible range so far
+    pub fn set_implausible_samples(&mut self, samples: u64) {
+        self.status.implausible_samples = Some(samples);
+    }
+
+    /// Note that the write buffer was just flushed
+    pub fn flushed(&mut self) {
+        self.last_flush = Some(Instant::now());
+    }
+
+    /// Update the rate and publish the snapshot and progress line when due; cheap to call per sample
+    pub fn tick(&mut self) {
+        let elapsed = self.window_start.elapsed();
+        if elapsed >= RATE_WINDOW {
+            self.status.rate_hz = self.window_samples as f64 / elapsed.as_secs_f64();
+            self.window_start = Instant::now();
+            self.window_samples = 0;
+        }
+        let publish = self.last_publish.elapsed() >= PUBLISH_INTERVAL;
+        let progress = self
+            .progress_interval
+            .is_some_and(|interval| self.last_progress.elapsed() >= interval);
+        if publish || progress {
+            self.update_clocks();
+        }
+        if publish {
+            self.publish();
+        }
+        if progress {
+            self.print_progress();
+        }
+    }
+
+    fn update_clocks(&mut self) {
+        let current = self.recording_since.map_or(Duration::ZERO, |since| since.elapsed());
+        self.status.elapsed_s = (self.recorded + current).as_secs_f64();
+        self.status.since_flush_s = self.last_flush.map(|flush| flush.elapsed().as_secs_f64());
+    }
+
+    /// Mark the recording finished and publish the final figures
+    pub fn finish(&mut self) {
+        self.set_state(RecordingState::Finished);
+        if self.progress_interval.is_some() {
+            self.print_progress();
+        }
+    }
+
+    fn publish(&mut self) {
+        if let Ok(mut shared) = self.shared.lock() {
+            *shared = self.status.clone();
+        }
+        self.last_publish = Instant::now();
+    }
+
+    fn print_progress(&mut self) {
+        println!("{}", self.status.progress_line());
+        std::io::stdout().flush().ok();
+        self.last_progress = Instant::now();
+    }
+}
+
+/// Serve `GET /status` on `port` (all interfaces) from a background thread; returns the bound address
+///
+/// `snapshot` is called once per request; port 0 picks a free port.
+pub fn serve_status<F>(port: u16, tool: &str, snapshot: F) -> Result<SocketAddr>
+where
+    F: Fn() -> Vec<StreamStatus> + Send + 'static,
+{
+    let listener = TcpListener::bind(("0.0.0.0", port))
+        .with_context(|| format!("Failed to bind the status endpoint on port {}", port))?;
+    let address = listener.local_addr()?;
+    let tool = tool.to_string();
+    let started = Instant::now();
+
+    thread::Builder::new().name("status-http".to_string()).spawn(move || {
+        for stream in listener.incoming().flatten() {
+            let streams = snapshot();
+            let body = json!({
+                "tool": tool,
+                "state": session_state(&streams),
+                "uptime_s": started.elapsed().as_secs_f64(),
+                "streams": streams,
+            });
+            // A misbehaving client only loses its own response
+            respond(stream, &body.to_string()).ok();
+        }
+    })?;
+    Ok(address)
+}
+
+/// Answer one HTTP request: the status document for `GET /` and `GET /status`
+fn respond(mut stream: TcpStream, body: &str) -> Result<()> {
+    stream.set_read_timeout(Some(CLIENT_IO_TIMEOUT))?;
+    stream.set_write_timeout(Some(CLIENT_IO_TIMEOUT))?;
+
+    let mut reader = BufReader::new(stream.try_clone()?);
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line)?;
+    // Consume the headers so the client sees a clean close
+    let mut header = String::new();
+    while reader.read_line(&mut header)? > 2 {
+        header.clear();
+    }
+
+    let mut parts = request_line.split_whitespace();
+    let (method, path) = (parts.next().unwrap_or(""), parts.next().unwrap_or(""));
+    let path = path.split('?').next().unwrap_or(path);
+    let (status, body) = match (method, path) {
+        ("GET", "/" | "/status") => ("200 OK", body),
+        ("GET", _) => ("404 Not Found", r#"{"error": "not found, use /status"}"#),
+        _ => ("405 Method Not Allowed", r#"{"error": "only GET is supported"}"#),
+    };
+    write!(
+        stream,
+        "HTTP/1.1 {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\n\
+         Access-Control-Allow-Origin: *\r\nCache-Control: no-store\r\nConnection: close\r\n\r\n{}",
+        status,
+        body.len(),
+        body
+    )?;
+    stream.flush()?;
+    Ok(())
+}
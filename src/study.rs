@@ -0,0 +1,164 @@
+//! Consistency of identically named streams across the sessions of a study
+//!
+//! `lsl-validate --study-dir` compares the channel count, nominal rate and channel
+//! format of every stream name over all stores below a directory. For each stream and
+//! property, the value most sessions share is taken as expected (ties go to the
+//! session that sorts first), and every session that differs is an outlier: the
+//! amplifier montage changed in week 3, a device was set to another rate, or a
+//! recorder stored a different sample type. Only stream metadata is read (see
+//! [`crate::session`]), so a study of hundreds of sessions is checked in seconds.
+
+use anyhow::Result;
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+
+use crate::session::{find_stores, summarize_store, SessionSummary, StreamSummary};
+
+/// A stream property compared across sessions
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, clap::ValueEnum)]
+#[serde(rename_all = "snake_case")]
+pub enum StudyCheck {
+    /// Channel count
+    Channels,
+    /// Nominal sampling rate
+    Rate,
+    /// Channel format (Float32, Int16, String, ...)
+    Format,
+}
+
+/// All properties, the default of `--study-check`
+pub const ALL_STUDY_CHECKS: [StudyCheck; 3] = [StudyCheck::Channels, StudyCheck::Rate, StudyCheck::Format];
+
+impl StudyCheck {
+    pub fn name(&self) -> &'static str {
+        match self {
+            StudyCheck::Channels => "channels",
+            StudyCheck::Rate => "rate",
+            StudyCheck::Format => "format",
+        }
+    }
+
+    /// The stream's value of this property, if its metadata has it
+    pub fn value(&self, stream: &StreamSummary) -> Option<String> {
+        match self {
+            StudyCheck::Channels => stream.channel_count.map(|c| c.to_string()),
+            StudyCheck::Rate => stream.nominal_srate.map(|r| r.to_string()),
+            StudyCheck::Format => stream.channel_format.clone(),
+        }
+    }
+}
+
+/// A session whose stream differs from the rest of the study
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct StudyOutlier {
+    pub stream: String,
+    pub check: StudyCheck,
+    pub session: PathBuf,
+    pub value: String,
+    /// Value shared by most sessions
+    pub expected: String,
+    /// Sessions with the expected value
+    pub agreeing: usize,
+    /// Sessions in which the stream has a value for this property
+    pub sessions: usize,
+}
+
+/// Expected values of one stream name
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct StudyStream {
+    pub name: String,
+    /// Sessions that recorded the stream
+    pub sessions: usize,
+    /// Expected value of each checked property
+    pub expected: Vec<(StudyCheck, String)>,
+}
+
+/// Result of a study check
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct StudyReport {
+    pub sessions: Vec<PathBuf>,
+    pub streams: Vec<StudyStream>,
+    pub outliers: Vec<StudyOutlier>,
+}
+
+impl StudyReport {
+    pub fn is_consistent(&self) -> bool {
+        self.outliers.is_empty()
+    }
+}
+
+/// Compare the streams of `sessions` on the properties in `checks`
+pub fn check_sessions(sessions: &[SessionSummary], checks: &[StudyCheck]) -> StudyReport {
+    let mut names: Vec<&str> = sessions
+        .iter()
+        .flat_map(|session| session.streams.iter().map(|s| s.name.as_str()))
+        .collect();
+    names.sort();
+    names.dedup();
+
+    let mut report = StudyReport {
+        sessions: sessions.iter().map(|s| s.path.clone()).collect(),
+        ..Default::default()
+    };
+    for name in names {
+        let recorded: Vec<(&Path, &StreamSummary)> = sessions
+            .iter()
+            .filter_map(|session| {
+                let stream = session.streams.iter().find(|s| s.name == name)?;
+                Some((session.path.as_path(), stream))
+            })
+            .collect();
+
+        let mut expected = Vec::new();
+        for &check in checks {
+            let values: Vec<(&Path, String)> = recorded
+                .iter()
+                .filter_map(|(path, stream)| Some((*path, check.value(stream)?)))
+                .collect();
+            let Some((value, agreeing)) = most_common(values.iter().map(|(_, v)| v.as_str())) else {
+                continue;
+            };
+            report.outliers.extend(values.iter().filter(|(_, v)| v != value).map(|(path, v)| StudyOutlier {
+                stream: name.to_string(),
+                check,
+                session: path.to_path_buf(),
+                value: v.clone(),
+                expected: value.to_string(),
+                agreeing,
+                sessions: values.len(),
+            }));
+            expected.push((check, value.to_string()));
+        }
+        report.streams.push(StudyStream {
+            name: name.to_string(),
+            sessions: recorded.len(),
+            expected,
+        });
+    }
+    report
+}
+
+/// Check all stores below `study_dir`
+pub fn check_study(study_dir: &Path, checks: &[StudyCheck]) -> Result<StudyReport> {
+    let mut sessions = Vec::new();
+    for path in find_stores(study_dir)? {
+        match summarize_store(&path) {
+            Ok(summary) => sessions.push(summary),
+            Err(e) => eprintln!("Warning: Cannot read {}: {}", path.display(), e),
+        }
+    }
+    Ok(check_sessions(&sessions, checks))
+}
+
+/// Most frequent value and its count; the first one seen wins a tie
+fn most_common<'a>(values: impl Iterator<Item = &'a str>) -> Option<(&'a str, usize)> {
+    let mut counts: Vec<(&str, usize)> = Vec::new();
+    for value in values {
+        match counts.iter_mut().find(|(v, _)| *v == value) {
+            Some((_, count)) => *count += 1,
+            None => counts.push((value, 1)),
+        }
+    }
+    // max_by_key returns the last maximum, so search from the back
+    counts.into_iter().rev().max_by_key(|&(_, count)| count)
+}
@@ -0,0 +1,170 @@
+//! Automatic subject IDs (`--auto-subject`)
+//!
+//! Typing subject IDs by hand invites duplicates (two participants recorded as
+//! `P007`) that only surface during analysis. With `--auto-subject` the recorders
+//! assign the next free ID of a pattern such as `P###` (`P001`, `P002`, ...):
+//! [`next_subject`] looks at every store under the output root, collects the
+//! subjects recorded there (session manifest, stream `recorder_config`, and
+//! directory names) and returns the pattern with the highest number seen plus one.
+//! Gaps left by deleted recordings are not reused.
+//!
+//! The output root is the part of `--output` before the first placeholder
+//! (`data` for `data/{subject}/session_{date}`), else the directory the store is
+//! written to.
+
+use anyhow::Result;
+use std::path::{Component, Path, PathBuf};
+use std::sync::Arc;
+use zarrs::filesystem::FilesystemStore;
+
+use crate::manifest::read_manifest;
+use crate::zarr::remote::is_remote;
+use crate::zarr::{is_store_group, read_group_attributes};
+
+/// Pattern used when `--subject-pattern` is not given
+pub const DEFAULT_SUBJECT_PATTERN: &str = "P###";
+
+/// Directory levels below the output root searched for stores
+const SEARCH_DEPTH: usize = 4;
+
+/// Subject ID pattern: fixed prefix and suffix around a zero-padded number (`#` per digit)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SubjectPattern {
+    prefix: String,
+    digits: usize,
+    suffix: String,
+}
+
+impl SubjectPattern {
+    /// Parse a pattern like `P###` or `sub-##_pilot`; needs exactly one run of `#`
+    pub fn parse(pattern: &str) -> Result<Self> {
+        let Some(start) = pattern.find('#') else {
+            anyhow::bail!("Subject pattern '{}' has no '#' digit placeholder", pattern);
+        };
+        let digits = pattern[start..].chars().take_while(|&c| c == '#').count();
+        let (prefix, suffix) = (&pattern[..start], &pattern[start + digits..]);
+        if suffix.contains('#') {
+            anyhow::bail!("Subject pattern '{}' must have a single run of '#'", pattern);
+        }
+        if pattern.contains(['/', '\\', '{', '}']) {
+            anyhow::bail!("Subject pattern '{}' must not contain path separators or placeholders", pattern);
+        }
+        Ok(Self {
+            prefix: prefix.to_string(),
+            digits,
+            suffix: suffix.to_string(),
+        })
+    }
+
+    /// Subject ID with `number`, zero-padded to the pattern's digit count
+    pub fn format(&self, number: u32) -> String {
+        format!("{}{:0width$}{}", self.prefix, number, self.suffix, width = self.digits)
+    }
+
+    /// Number of a subject ID following the pattern (any digit count), `None` otherwise
+    pub fn number(&self, subject: &str) -> Option<u32> {
+        let digits = subject.strip_prefix(&self.prefix)?.strip_suffix(&self.suffix)?;
+        if digits.is_empty() || !digits.chars().all(|c| c.is_ascii_digit()) {
+            return None;
+        }
+        digits.parse().ok()
+    }
+}
+
+/// Directory searched for existing subjects: `output` up to its first placeholder, else its parent
+pub fn output_root(output: &Path) -> PathBuf {
+    let components: Vec<Component> = output.components().collect();
+    let fixed = match components
+        .iter()
+        .position(|c| c.as_os_str().to_string_lossy().contains('{'))
+    {
+        Some(first_placeholder) => &components[..first_placeholder],
+        None => &components[..components.len().saturating_sub(1)],
+    };
+    let root: PathBuf = fixed.iter().collect();
+    if root.as_os_str().is_empty() { PathBuf::from(".") } else { root }
+}
+
+/// Subject IDs and directory names found in the stores under `root`
+///
+/// Stores (directories ending in `.zarr`) are read for the manifest subject and
+/// the `recorder_config` subject of every stream; the names of all directories
+/// on the way (store names without `.zarr`) are included as well.
+pub fn existing_subjects(root: &Path) -> Result<Vec<String>> {
+    let mut subjects = Vec::new();
+    if root.is_dir() {
+        collect_subjects(root, SEARCH_DEPTH, &mut subjects)?;
+    }
+    subjects.sort();
+    subjects.dedup();
+    Ok(subjects)
+}
+
+fn collect_subjects(dir: &Path, depth: usize, subjects: &mut Vec<String>) -> Result<()> {
+    for entry in std::fs::read_dir(dir)? {
+        let entry = entry?;
+        if !entry.file_type()?.is_dir() {
+            continue;
+        }
+        let path = entry.path();
+        let name = entry.file_name().to_string_lossy().to_string();
+        if name.starts_with('.') {
+            continue;
+        }
+        match name.strip_suffix(".zarr") {
+            Some(stem) => {
+                subjects.push(stem.to_string());
+                subjects.extend(store_subjects(&path));
+            }
+            None => {
+                subjects.push(name);
+                if depth > 1 {
+                    collect_subjects(&path, depth - 1, subjects)?;
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Subjects recorded in one store's metadata
+fn store_subjects(store_path: &Path) -> Vec<String> {
+    let mut subjects: Vec<String> = read_manifest(store_path).and_then(|m| m.subject).into_iter().collect();
+    let Ok(store) = FilesystemStore::new(store_path).map(Arc::new) else {
+        return subjects;
+    };
+    let Ok(entries) = std::fs::read_dir(store_path) else {
+        return subjects;
+    };
+    for entry in entries.flatten() {
+        let name = entry.file_name().to_string_lossy().to_string();
+        if is_store_group(&name) || !entry.path().join("zarr.json").is_file() {
+            continue;
+        }
+        if let Ok(attrs) = read_group_attributes(&store, &name)
+            && let Some(subject) = attrs.pointer("/recorder_config/subject").and_then(|v| v.as_str())
+        {
+            subjects.push(subject.to_string());
+        }
+    }
+    subjects
+}
+
+/// Next free subject ID of `pattern` among the stores under `root`
+pub fn next_subject(root: &Path, pattern: &SubjectPattern) -> Result<String> {
+    let highest = existing_subjects(root)?
+        .iter()
+        .filter_map(|subject| pattern.number(subject))
+        .max()
+        .unwrap_or(0);
+    Ok(pattern.format(highest.saturating_add(1)))
+}
+
+/// Subject ID for `--auto-subject` with output path `output` (before placeholder expansion)
+pub fn auto_subject(output: &Path, pattern: &str) -> Result<String> {
+    if is_remote(output) {
+        anyhow::bail!("--auto-subject needs a local --output to look for existing subjects");
+    }
+    let pattern = SubjectPattern::parse(pattern)?;
+    next_subject(&output_root(output), &pattern)
+}
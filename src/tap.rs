@@ -0,0 +1,214 @@
+//! Live WebSocket tap for external visualizers
+//!
+//! `lsl-recorder --tap ws://0.0.0.0:9001` serves a decimated copy of the incoming
+//! samples to any number of WebSocket clients, e.g. a browser dashboard. The
+//! recording loop only hands samples to a bounded queue; a background thread does
+//! all networking, and samples are dropped (never waited on) when it falls behind,
+//! so the Zarr write path is unaffected by slow or stalled clients.
+//!
+//! # Frames
+//!
+//! On connect every client receives a JSON header:
+//! `{"type": "header", "stream": "EMG", "channels": 8, "nominal_srate": 2000.0, "decimation": 40}`
+//!
+//! Samples follow in the selected [`TapFormat`]:
+//!
+//! - `json`: `{"t": <lsl timestamp>, "x": [<channel values>]}` text frames
+//! - `binary`: binary frames with the timestamp as little-endian `f64`, followed by
+//!   one little-endian `f32` per channel
+//!
+//! Marker (string) samples are always sent as `{"t": ..., "marker": [...]}` text frames
+//! and are never decimated.
+
+use anyhow::{Context, Result};
+use serde_json::json;
+use std::net::{TcpListener, TcpStream};
+use std::sync::mpsc::{sync_channel, Receiver, RecvTimeoutError, SyncSender, TrySendError};
+use std::thread;
+use std::time::Duration;
+use tungstenite::{Message, WebSocket};
+
+/// Frames buffered between the recording loop and the network thread
+const TAP_QUEUE_FRAMES: usize = 1024;
+
+/// How often the network thread checks for new clients while idle
+const ACCEPT_POLL: Duration = Duration::from_millis(100);
+
+/// Upper bound for a client handshake or a single frame write
+const CLIENT_IO_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// Encoding of sample frames sent to tap clients
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum TapFormat {
+    /// JSON text frames (easy to consume in a browser)
+    Json,
+    /// Compact binary frames (f64 timestamp + f32 per channel, little-endian)
+    Binary,
+}
+
+impl std::fmt::Display for TapFormat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TapFormat::Json => write!(f, "json"),
+            TapFormat::Binary => write!(f, "binary"),
+        }
+    }
+}
+
+/// Stream properties announced to clients in the header frame
+#[derive(Debug, Clone)]
+pub struct TapStreamInfo {
+    pub stream_name: String,
+    pub channel_count: usize,
+    pub nominal_srate: f64,
+}
+
+/// Handle used by the recording loop to publish samples to the tap
+pub struct LiveTap {
+    sender: SyncSender<Message>,
+    format: TapFormat,
+    decimation: u64,
+    counter: u64,
+    dropped: u64,
+}
+
+/// Parse `ws://host:port` (a bare `host:port` is accepted too) into a bind address
+pub fn parse_tap_address(url: &str) -> Result<String> {
+    let address = url.strip_prefix("ws://").unwrap_or(url);
+    if url.starts_with("wss://") {
+        anyhow::bail!("TLS (wss://) is not supported for the live tap, use ws://");
+    }
+    let address = address.split('/').next().unwrap_or(address);
+    if !address.contains(':') {
+        anyhow::bail!("Tap address '{}' must include a port, e.g. ws://0.0.0.0:9001", url);
+    }
+    Ok(address.to_string())
+}
+
+/// Samples to skip between forwarded samples to reach roughly `tap_rate` Hz
+///
+/// Irregular streams (nominal rate 0) are forwarded in full.
+pub fn tap_decimation(nominal_srate: f64, tap_rate: f64) -> u64 {
+    if nominal_srate <= 0.0 || tap_rate <= 0.0 {
+        return 1;
+    }
+    (nominal_srate / tap_rate).round().max(1.0) as u64
+}
+
+impl LiveTap {
+    /// Bind the WebSocket server and start its network thread
+    pub fn start(url: &str, format: TapFormat, tap_rate: f64, info: TapStreamInfo) -> Result<Self> {
+        let address = parse_tap_address(url)?;
+        let listener = TcpListener::bind(&address)
+            .with_context(|| format!("Failed to bind live tap on {}", address))?;
+        listener.set_nonblocking(true)?;
+
+        let decimation = tap_decimation(info.nominal_srate, tap_rate);
+        let header = json!({
+            "type": "header",
+            "stream": info.stream_name,
+            "channels": info.channel_count,
+            "nominal_srate": info.nominal_srate,
+            "decimation": decimation,
+            "format": match format {
+                TapFormat::Json => "json",
+                TapFormat::Binary => "binary",
+            },
+        })
+        .to_string();
+
+        let (sender, receiver) = sync_channel(TAP_QUEUE_FRAMES);
+        thread::Builder::new()
+            .name("live-tap".to_string())
+            .spawn(move || serve(listener, receiver, header))?;
+
+        Ok(Self {
+            sender,
+            format,
+            decimation,
+            counter: 0,
+            dropped: 0,
+        })
+    }
+
+    /// Offer a numeric sample; only every `decimation`-th sample is forwarded
+    pub fn offer<T: Copy + Into<f64>>(&mut self, timestamp: f64, values: &[T]) {
+        let due = self.counter.is_multiple_of(self.decimation);
+        self.counter += 1;
+        if !due {
+            return;
+        }
+
+        let frame = match self.format {
+            TapFormat::Json => {
+                let values: Vec<f64> = values.iter().map(|&v| v.into()).collect();
+                Message::text(json!({ "t": timestamp, "x": values }).to_string())
+            }
+            TapFormat::Binary => {
+                let mut bytes = Vec::with_capacity(8 + 4 * values.len());
+                bytes.extend_from_slice(&timestamp.to_le_bytes());
+                for &value in values {
+                    bytes.extend_from_slice(&(value.into() as f32).to_le_bytes());
+                }
+                Message::binary(bytes)
+            }
+        };
+        self.send(frame);
+    }
+
+    /// Offer a marker sample; markers are never decimated
+    pub fn offer_marker(&mut self, timestamp: f64, values: &[String]) {
+        self.send(Message::text(json!({ "t": timestamp, "marker": values }).to_string()));
+    }
+
+    /// Frames dropped because the network thread could not keep up
+    pub fn dropped_frames(&self) -> u64 {
+        self.dropped
+    }
+
+    fn send(&mut self, frame: Message) {
+        match self.sender.try_send(frame) {
+            Ok(()) => {}
+            Err(TrySendError::Full(_)) => self.dropped += 1,
+            // Network thread is gone (e.g. panicked); keep recording regardless
+            Err(TrySendError::Disconnected(_)) => {}
+        }
+    }
+}
+
+/// Complete the WebSocket handshake with a new client and send it the header
+fn accept_client(stream: TcpStream, header: &str) -> Option<WebSocket<TcpStream>> {
+    stream.set_nonblocking(false).ok()?;
+    stream.set_read_timeout(Some(CLIENT_IO_TIMEOUT)).ok()?;
+    stream.set_write_timeout(Some(CLIENT_IO_TIMEOUT)).ok()?;
+    stream.set_nodelay(true).ok()?;
+
+    let mut client = tungstenite::accept(stream).ok()?;
+    client.send(Message::text(header.to_string())).ok()?;
+    Some(client)
+}
+
+/// Network thread: accept clients and broadcast frames until the tap is dropped
+fn serve(listener: TcpListener, receiver: Receiver<Message>, header: String) {
+    let mut clients: Vec<WebSocket<TcpStream>> = Vec::new();
+
+    loop {
+        while let Ok((stream, _)) = listener.accept() {
+            if let Some(client) = accept_client(stream, &header) {
+                clients.push(client);
+            }
+        }
+
+        match receiver.recv_timeout(ACCEPT_POLL) {
+            // Clients that fail to keep up or have disconnected are dropped
+            Ok(frame) => clients.retain_mut(|client| client.send(frame.clone()).is_ok()),
+            Err(RecvTimeoutError::Timeout) => {}
+            Err(RecvTimeoutError::Disconnected) => break,
+        }
+    }
+
+    for mut client in clients {
+        client.close(None).ok();
+        client.flush().ok();
+    }
+}
@@ -0,0 +1,193 @@
+//! Time-axis summary of a stream (`lsl-inspect --timeline`)
+//!
+//! A quick health check of a stream's time array without a full `lsl-validate` run:
+//! effective sample rate, whether timestamps only ever increase, the gaps found the
+//! same way as [`crate::gaps::find_gaps`], and a sparkline of how many samples fell
+//! into each of a fixed number of bins across the recording. A flat line of full
+//! blocks is a healthy regular stream; dips are dropouts, `·` bins hold no samples
+//! at all.
+//!
+//! ```text
+//! ├─ Time axis: 499.87 Hz effective, monotonic, 2 gaps (1.204 s lost)
+//! │    │████████████████▃·████████████████████████████████▆██████████████│
+//! │    │                ^^                                ^              │
+//! ```
+//!
+//! The time array is scanned in blocks with [`TimeAxisScan`], so arbitrarily long
+//! recordings are summarized in constant memory.
+
+use serde::{Deserialize, Serialize};
+
+use crate::gaps::DEFAULT_GAP_FACTOR;
+
+/// Bins of the sparkline when not given otherwise
+pub const DEFAULT_TIMELINE_BINS: usize = 60;
+
+/// Sparkline levels from nearly empty to full
+const LEVELS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+
+/// Summary of one stream's time array
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TimeAxisSummary {
+    pub samples: u64,
+    pub first: f64,
+    pub last: f64,
+    /// Nominal rate the gaps and bin fill were judged against (0 for irregular streams)
+    pub nominal_srate: f64,
+    /// Samples per second over the recording, `None` below two samples or zero duration
+    pub effective_rate: Option<f64>,
+    /// Intervals of zero or negative length (repeated or backwards timestamps)
+    pub non_increasing: u64,
+    /// Intervals of negative length
+    pub backwards: u64,
+    /// Largest backwards step in seconds (0 without any)
+    pub largest_backwards: f64,
+    /// Intervals longer than [`DEFAULT_GAP_FACTOR`] nominal periods
+    pub gaps: u64,
+    /// Time missing from the stream (each gap's interval minus one nominal period)
+    pub lost_time: f64,
+    /// Longest gap interval in seconds (0 without any)
+    pub largest_gap: f64,
+    /// Samples per bin between the first and last timestamp
+    pub bins: Vec<u64>,
+    /// Whether a gap starts in each bin
+    pub gap_bins: Vec<bool>,
+}
+
+impl TimeAxisSummary {
+    pub fn duration(&self) -> f64 {
+        self.last - self.first
+    }
+
+    /// Whether every timestamp is larger than the one before it
+    pub fn monotonic(&self) -> bool {
+        self.non_increasing == 0
+    }
+
+    /// Sample density per bin as a line of block characters
+    ///
+    /// Bins are scaled to the samples a bin should hold at the nominal rate, or to the
+    /// fullest bin for irregular streams; empty bins are shown as `·`.
+    pub fn sparkline(&self) -> String {
+        let full = if self.nominal_srate > 0.0 && !self.bins.is_empty() {
+            self.nominal_srate * self.duration() / self.bins.len() as f64
+        } else {
+            self.bins.iter().copied().max().unwrap_or(0) as f64
+        };
+        self.bins
+            .iter()
+            .map(|&count| {
+                if count == 0 {
+                    '·'
+                } else if full <= 0.0 {
+                    LEVELS[LEVELS.len() - 1]
+                } else {
+                    let level = (count as f64 / full * LEVELS.len() as f64).ceil() as usize;
+                    LEVELS[level.clamp(1, LEVELS.len()) - 1]
+                }
+            })
+            .collect()
+    }
+
+    /// `^` under every bin of the [`sparkline`](Self::sparkline) in which a gap starts,
+    /// `None` without gaps
+    pub fn gap_markers(&self) -> Option<String> {
+        self.gap_bins
+            .iter()
+            .any(|&gap| gap)
+            .then(|| self.gap_bins.iter().map(|&gap| if gap { '^' } else { ' ' }).collect())
+    }
+}
+
+/// Block-wise scan of a time array into a [`TimeAxisSummary`]
+///
+/// The first and last timestamp must be known up front to place samples into bins;
+/// [`push`](Self::push) then takes the timestamps in order, in blocks of any size.
+#[derive(Debug, Clone)]
+pub struct TimeAxisScan {
+    summary: TimeAxisSummary,
+    period: Option<f64>,
+    previous: Option<f64>,
+}
+
+impl TimeAxisScan {
+    /// Scan of a stream spanning `first..=last`, divided into `bins` bins
+    pub fn new(first: f64, last: f64, nominal_srate: f64, bins: usize) -> Self {
+        let bins = if last > first { bins.max(1) } else { 1 };
+        Self {
+            summary: TimeAxisSummary {
+                samples: 0,
+                first,
+                last,
+                nominal_srate: nominal_srate.max(0.0),
+                effective_rate: None,
+                non_increasing: 0,
+                backwards: 0,
+                largest_backwards: 0.0,
+                gaps: 0,
+                lost_time: 0.0,
+                largest_gap: 0.0,
+                bins: vec![0; bins],
+                gap_bins: vec![false; bins],
+            },
+            period: (nominal_srate > 0.0).then(|| 1.0 / nominal_srate),
+            previous: None,
+        }
+    }
+
+    fn bin(&self, timestamp: f64) -> usize {
+        let summary = &self.summary;
+        let count = summary.bins.len();
+        let span = summary.duration();
+        if span <= 0.0 {
+            return 0;
+        }
+        let position = ((timestamp - summary.first) / span * count as f64).floor();
+        (position.max(0.0) as usize).min(count - 1)
+    }
+
+    /// Add the next timestamps
+    pub fn push(&mut self, timestamps: &[f64]) {
+        for &timestamp in timestamps {
+            if let Some(previous) = self.previous {
+                let interval = timestamp - previous;
+                if interval <= 0.0 {
+                    self.summary.non_increasing += 1;
+                }
+                if interval < 0.0 {
+                    self.summary.backwards += 1;
+                    self.summary.largest_backwards = self.summary.largest_backwards.max(-interval);
+                }
+                if let Some(period) = self.period
+                    && interval > DEFAULT_GAP_FACTOR * period
+                {
+                    self.summary.gaps += 1;
+                    self.summary.lost_time += interval - period;
+                    self.summary.largest_gap = self.summary.largest_gap.max(interval);
+                    let bin = self.bin(previous);
+                    self.summary.gap_bins[bin] = true;
+                }
+            }
+            let bin = self.bin(timestamp);
+            self.summary.bins[bin] += 1;
+            self.summary.samples += 1;
+            self.previous = Some(timestamp);
+        }
+    }
+
+    pub fn finish(mut self) -> TimeAxisSummary {
+        let duration = self.summary.duration();
+        if self.summary.samples >= 2 && duration > 0.0 {
+            self.summary.effective_rate = Some((self.summary.samples - 1) as f64 / duration);
+        }
+        self.summary
+    }
+}
+
+/// Summary of a whole time series held in memory
+pub fn summarize_time_axis(timestamps: &[f64], nominal_srate: f64, bins: usize) -> Option<TimeAxisSummary> {
+    let (&first, &last) = (timestamps.first()?, timestamps.last()?);
+    let mut scan = TimeAxisScan::new(first, last, nominal_srate, bins);
+    scan.push(timestamps);
+    Some(scan.finish())
+}
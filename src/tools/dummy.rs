@@ -0,0 +1,655 @@
+//! LSL Dummy Stream - Generate test LSL streams with sine wave or noise data
+//!
+//! This tool generates configurable LSL streams with sine wave or random noise data
+//! for testing and development of recording pipelines.
+//!
+//! # Features
+//!
+//! - Generate sine wave test streams (default)
+//! - Generate random noise streams (optional)
+//! - Pure sines, chirps, white and pink noise (`--waveform`)
+//! - Simulated EMG, EEG and ECG with realistic bands (`--simulate`)
+//! - Square-wave trigger on the last channel (`--trigger-frequency`)
+//! - Fault injection: timestamp jitter, lost chunks, clock drift and stalls
+//!   (`--jitter-ms`, `--dropout-prob`, `--drift-ppm`, `--burst-gap`)
+//! - String marker streams of named events at fixed or Poisson-distributed intervals
+//!   (`--format string`, `--events`, `--event-interval`, `--poisson`)
+//! - Configurable channel count and sample rate
+//! - Customizable stream name, type, and source ID
+//! - Adjustable chunk size for streaming
+//! - Frequency range configuration per channel (for sine wave mode)
+//! - Multiple data types supported (float32, float64, int32, etc.)
+//! - Verbose output mode
+//!
+//! # Usage
+//!
+//! ```bash
+//! # Generate default test stream (100 channels, 10kHz EMG)
+//! lsl-dummy-stream
+//!
+//! # Custom EMG stream
+//! lsl-dummy-stream --name "TestEMG" \
+//!   --source-id "EMG_1234" \
+//!   --channels 8 \
+//!   --sample-rate 2000
+//!
+//! # Generate EEG stream
+//! lsl-dummy-stream --name "TestEEG" \
+//!   --type "EEG" \
+//!   --source-id "EEG_5678" \
+//!   --channels 64 \
+//!   --sample-rate 1000
+//!
+//! # Custom frequency range
+//! lsl-dummy-stream --name "TestSignal" \
+//!   --source-id "SIG_9999" \
+//!   --channels 4 \
+//!   --freq-range "5,20"
+//!
+//! # Verbose output
+//! lsl-dummy-stream --verbose
+//!
+//! # Generate random noise stream
+//! lsl-dummy-stream --noise --name "NoiseTest"
+//!
+//! # 50 Hz sine on every channel, e.g. to test a notch filter
+//! lsl-dummy-stream --waveform sine --frequency 50 --channels 4 --sample-rate 1000
+//!
+//! # 1-200 Hz sweep every 5 s
+//! lsl-dummy-stream --waveform chirp --freq-range "1,200" --chirp-duration 5 --sample-rate 1000
+//!
+//! # 32-channel EEG with a 1 Hz trigger on the last channel
+//! lsl-dummy-stream --simulate eeg --channels 33 --sample-rate 500 --trigger-frequency 1
+//!
+//! # Pathological stream: 0.5 ms jitter, 1% lost chunks, 50 ppm drift, 2 s stall every 30 s
+//! lsl-dummy-stream --jitter-ms 0.5 --dropout-prob 0.01 --drift-ppm 50 \
+//!   --burst-gap 2 --burst-interval 30
+//!
+//! # Marker stream: a random trial_start/stimulus/response sequence, 2 s apart on average
+//! lsl-dummy-stream --name "TestMarkers" --type Markers --format string --poisson --event-interval 2
+//!
+//! # Calibration pulses every second (used by lsl-calibrate)
+//! lsl-dummy-stream --name "Calib" --source-id "CAL_1" --channels 1 \
+//!   --sample-rate 1000 --calibration-interval 1.0
+//! ```
+//!
+//! # Signal Generation
+//!
+//! By default, generates sine waves with:
+//! - Each channel has a different frequency
+//! - Frequencies linearly spaced across specified range
+//! - Continuous phase-coherent output
+//! - Realistic timing and chunk delivery
+//!
+//! With `--noise` flag, generates random noise:
+//! - Uniform random values in range [-1, 1] (scaled for data type)
+//! - Independent samples per channel
+//!
+//! `--waveform` and `--simulate` choose other signals (see the `signals` module);
+//! `--seed` makes their noise reproducible.
+//!
+//! With any fault option, samples are pushed with their own timestamps instead of
+//! the push time, so jitter, drift and lost samples show up in the recording (see
+//! the `faults` module). `--seed` makes the faults reproducible as well.
+//!
+//! With `--format string` (alias of `--data-type`), the stream is an irregular
+//! single-channel marker stream of named events instead (see the `events` module),
+//! each timestamped with its scheduled time.
+//!
+//! With `--calibration-interval`, generates a calibration pattern instead:
+//! - 10 ms pulses (value 1, scaled for data type) on all channels at the given interval
+//! - A companion marker stream (`<source-id>_calibration`) with one `pulse` marker per
+//!   pulse, timestamped with the pulse's true onset time
+//!
+//! Chunks are pushed on a fixed schedule. A chunk pushed more than `--max-lag`
+//! seconds after its slot, or a failed push, prints a warning with the delivered
+//! rate (see the `backpressure` module); `--verbose` status lines include it.
+
+use anyhow::Result;
+use clap::Parser;
+use lsl::{ExPushable, Pushable, StreamInfo, StreamOutlet};
+use crate::backpressure::{PushMonitor, DEFAULT_MAX_LAG};
+use crate::calibration::{MARKER_SOURCE_SUFFIX, PULSE_WIDTH_SECS};
+use crate::events::{EventGenerator, EventOptions};
+use crate::faults::{FaultInjector, FaultOptions};
+use crate::signals::{SignalGenerator, SignalOptions, Simulation, Waveform};
+use std::thread;
+use std::time::{Duration, Instant};
+
+#[derive(Parser)]
+#[command(name = "lsl-dummy-stream")]
+#[command(about = "Generate dummy LSL streams with sine wave data for testing")]
+pub struct Args {
+    #[arg(long = "name", help = "Stream name", default_value = "TestStream")]
+    name: String,
+
+    #[arg(long = "type", help = "Stream type (default: EMG, or the --simulate signal)")]
+    stream_type: Option<String>,
+
+    #[arg(long = "source-id", help = "Source ID", default_value = "TEST_1234")]
+    source_id: String,
+
+    #[arg(long = "channels", help = "Number of channels", default_value = "100")]
+    channels: u32,
+
+    #[arg(
+        long = "sample-rate",
+        help = "Sampling rate in Hz",
+        default_value = "10000"
+    )]
+    sample_rate: f64,
+
+    #[arg(long = "chunk-size", help = "Samples per chunk", default_value = "18")]
+    chunk_size: u32,
+
+    #[arg(
+        long = "freq-range",
+        help = "Frequency range for channels as 'min,max'",
+        default_value = "1,10"
+    )]
+    freq_range: String,
+
+    #[arg(
+        long = "data-type",
+        visible_alias = "format",
+        help = "Data type for samples: float32, int16, or string for a marker stream of named events",
+        default_value = "float32"
+    )]
+    data_type: String,
+
+    #[arg(short = 'v', long = "verbose", help = "Verbose output")]
+    verbose: bool,
+
+    #[arg(
+        long = "noise",
+        help = "Generate random noise instead of sine waves",
+        default_value = "false"
+    )]
+    noise: bool,
+
+    #[arg(
+        long,
+        value_enum,
+        default_value = "modulated-sine",
+        conflicts_with = "noise",
+        help = "Signal shape of every channel"
+    )]
+    waveform: Waveform,
+
+    #[arg(long, value_name = "HZ", help = "Sine frequency of every channel (instead of spreading --freq-range)")]
+    frequency: Option<f64>,
+
+    #[arg(long, default_value = "10.0", value_name = "SECONDS", help = "Length of one --waveform chirp sweep")]
+    chirp_duration: f64,
+
+    #[arg(
+        long,
+        value_enum,
+        conflicts_with_all = ["waveform", "noise"],
+        help = "Generate a realistic band-limited EMG, EEG or ECG signal"
+    )]
+    simulate: Option<Simulation>,
+
+    #[arg(long, value_name = "HZ", help = "Replace the last channel with a 0/1 square-wave trigger of this frequency")]
+    trigger_frequency: Option<f64>,
+
+    #[arg(long, help = "Seed for the noise of --waveform and --simulate signals and the faults (default: random)")]
+    seed: Option<u64>,
+
+    #[arg(long, default_value_t = 0.0, value_name = "MS", help = "Standard deviation of Gaussian jitter added to every timestamp")]
+    jitter_ms: f64,
+
+    #[arg(long, default_value_t = 0.0, value_name = "PROBABILITY", help = "Probability that a chunk is lost")]
+    dropout_prob: f64,
+
+    #[arg(
+        long,
+        default_value_t = 0.0,
+        value_name = "PPM",
+        allow_hyphen_values = true,
+        help = "Clock drift of the timestamps in ppm (positive: the stream's clock runs fast)"
+    )]
+    drift_ppm: f64,
+
+    #[arg(long, value_name = "SECONDS", help = "Stall for this long every --burst-interval, losing the samples")]
+    burst_gap: Option<f64>,
+
+    #[arg(long, default_value_t = 10.0, value_name = "SECONDS", requires = "burst_gap", help = "Seconds between the starts of two --burst-gap stalls")]
+    burst_interval: f64,
+
+    #[arg(
+        long,
+        value_delimiter = ',',
+        help = "Event names of a string stream, emitted in turn (comma-separated; default: trial_start,stimulus,response)"
+    )]
+    events: Vec<String>,
+
+    #[arg(long, default_value_t = 1.0, value_name = "SECONDS", help = "Seconds between the events of a string stream (mean with --poisson)")]
+    event_interval: f64,
+
+    #[arg(long, help = "Space string stream events at exponentially distributed intervals (Poisson process)")]
+    poisson: bool,
+
+    #[arg(
+        long = "calibration-interval",
+        conflicts_with_all = ["noise", "waveform", "simulate", "trigger_frequency"],
+        help = "Emit calibration pulses every N seconds plus a marker stream with their true onsets"
+    )]
+    calibration_interval: Option<f64>,
+
+    #[arg(
+        long,
+        default_value_t = DEFAULT_MAX_LAG,
+        value_name = "SECONDS",
+        help = "Warn when chunks are pushed more than this many seconds behind schedule"
+    )]
+    max_lag: f64,
+}
+
+impl Args {
+    /// Faults selected by --jitter-ms/--dropout-prob/--drift-ppm/--burst-gap/--burst-interval
+    fn fault_options(&self) -> FaultOptions {
+        FaultOptions {
+            jitter_ms: self.jitter_ms,
+            dropout_prob: self.dropout_prob,
+            drift_ppm: self.drift_ppm,
+            burst_gap: self.burst_gap,
+            burst_interval: self.burst_interval,
+        }
+    }
+}
+
+fn parse_freq_range(freq_range: &str) -> Result<(f64, f64)> {
+    let parts: Vec<&str> = freq_range.split(',').collect();
+    if parts.len() != 2 {
+        return Err(anyhow::anyhow!(
+            "Frequency range must be in format 'min,max'"
+        ));
+    }
+
+    let min_freq: f64 = parts[0]
+        .trim()
+        .parse()
+        .map_err(|_| anyhow::anyhow!("Invalid minimum frequency"))?;
+    let max_freq: f64 = parts[1]
+        .trim()
+        .parse()
+        .map_err(|_| anyhow::anyhow!("Invalid maximum frequency"))?;
+
+    if min_freq >= max_freq {
+        return Err(anyhow::anyhow!(
+            "Minimum frequency must be less than maximum frequency"
+        ));
+    }
+
+    Ok((min_freq, max_freq))
+}
+
+/// Run `lsl-dummy-stream` with parsed arguments
+pub fn run(args: Args) -> Result<()> {
+
+    crate::display_license_notice("lsl-dummy-stream");
+
+    if args.data_type.eq_ignore_ascii_case("string") {
+        return run_marker_stream(&args);
+    }
+
+    // Parse frequency range
+    let (min_freq, max_freq) = parse_freq_range(&args.freq_range)?;
+
+    // Parse data type
+    let channel_format = match args.data_type.to_lowercase().as_str() {
+        "float32" | "f32" => lsl::ChannelFormat::Float32,
+        "int16" | "i16" => lsl::ChannelFormat::Int16,
+        _ => {
+            return Err(anyhow::anyhow!(
+                "Invalid data type. Supported: float32, int16, string"
+            ))
+        }
+    };
+
+    let stream_type = args
+        .stream_type
+        .clone()
+        .unwrap_or_else(|| args.simulate.map_or("EMG", |s| s.stream_type()).to_string());
+    let waveform = if args.noise { Waveform::WhiteNoise } else { args.waveform };
+
+    // Create stream info
+    let info = StreamInfo::new(
+        &args.name,
+        &stream_type,
+        args.channels,
+        args.sample_rate,
+        channel_format,
+        &args.source_id,
+    )?;
+
+    // Create outlet
+    let outlet = StreamOutlet::new(&info, 0, 360)?;
+
+    // Calibration mode: pulse period/width in samples and the marker outlet for true onsets
+    let calibration = match args.calibration_interval {
+        Some(interval) => {
+            if interval <= PULSE_WIDTH_SECS {
+                anyhow::bail!("Calibration interval must be longer than the {} s pulse", PULSE_WIDTH_SECS);
+            }
+            let marker_info = StreamInfo::new(
+                &format!("{}{}", args.name, MARKER_SOURCE_SUFFIX),
+                "Markers",
+                1,
+                lsl::IRREGULAR_RATE,
+                lsl::ChannelFormat::String,
+                &format!("{}{}", args.source_id, MARKER_SOURCE_SUFFIX),
+            )?;
+            let interval_samples = ((interval * args.sample_rate).round() as u64).max(2);
+            let pulse_samples = ((PULSE_WIDTH_SECS * args.sample_rate).round() as u64).clamp(1, interval_samples - 1);
+            Some((interval_samples, pulse_samples, StreamOutlet::new(&marker_info, 0, 360)?))
+        }
+        None => None,
+    };
+
+    println!("LSL Dummy Stream Generator");
+    println!("==========================");
+    println!("Stream name:\t{}", args.name);
+    println!("Stream type:\t{}", stream_type);
+    println!("Source ID:\t{}", args.source_id);
+    println!("Channels:\t{}", args.channels);
+    println!("Sample rate:\t{} Hz", args.sample_rate);
+    println!("Chunk size:\t{} samples", args.chunk_size);
+    if let Some(interval) = args.calibration_interval {
+        println!("Signal type:\tCalibration pulses every {} s", interval);
+        println!("Markers:\t{}{}", args.source_id, MARKER_SOURCE_SUFFIX);
+    } else if let Some(simulation) = args.simulate {
+        println!("Signal type:\tSimulated {}", simulation.stream_type());
+    } else {
+        match waveform {
+            Waveform::WhiteNoise => println!("Signal type:\tRandom noise"),
+            Waveform::PinkNoise => println!("Signal type:\tPink noise"),
+            Waveform::Chirp => println!(
+                "Signal type:\tChirp {:.1} - {:.1} Hz every {} s",
+                min_freq, max_freq, args.chirp_duration
+            ),
+            Waveform::Sine | Waveform::ModulatedSine => match args.frequency {
+                Some(frequency) => println!("Frequency:\t{:.1} Hz", frequency),
+                None => println!("Freq. range:\t{:.1} - {:.1} Hz", min_freq, max_freq),
+            },
+        }
+    }
+    if let Some(frequency) = args.trigger_frequency {
+        println!("Trigger:\t{} Hz square wave on channel {}", frequency, args.channels);
+    }
+    if args.jitter_ms > 0.0 {
+        println!("Jitter:\t\t{} ms (standard deviation)", args.jitter_ms);
+    }
+    if args.dropout_prob > 0.0 {
+        println!("Dropouts:\t{:.2}% of chunks", args.dropout_prob * 100.0);
+    }
+    if args.drift_ppm != 0.0 {
+        println!("Clock drift:\t{:+} ppm", args.drift_ppm);
+    }
+    if let Some(gap) = args.burst_gap {
+        println!("Burst gaps:\t{} s every {} s", gap, args.burst_interval);
+    }
+    println!("Data type:\t{:?}", channel_format);
+    println!();
+    let sine = args.simulate.is_none() && matches!(waveform, Waveform::Sine | Waveform::ModulatedSine);
+    if args.noise {
+        println!("Starting continuous noise generation...");
+    } else if sine || args.calibration_interval.is_some() {
+        println!("Starting continuous sine wave generation...");
+    } else {
+        println!("Starting continuous signal generation...");
+    }
+    println!("Press Ctrl+C to stop");
+    println!();
+
+    // Calculate frequencies for each channel (linearly spaced)
+    let frequencies: Vec<f64> = if let Some(frequency) = args.frequency {
+        vec![frequency; args.channels as usize]
+    } else if args.channels == 1 {
+        vec![(min_freq + max_freq) / 2.0]
+    } else {
+        (0..args.channels)
+            .map(|i| min_freq + (max_freq - min_freq) * (i as f64) / ((args.channels - 1) as f64))
+            .collect()
+    };
+
+    let seed = args.seed.unwrap_or_else(|| fastrand::u64(..));
+    let fault_options = args.fault_options();
+    let mut faults = if fault_options.any() {
+        Some(FaultInjector::new(fault_options, args.sample_rate, seed)?)
+    } else {
+        None
+    };
+
+    let mut generator = SignalGenerator::new(SignalOptions {
+        waveform,
+        simulate: args.simulate,
+        frequencies: frequencies.clone(),
+        chirp_range: (min_freq, max_freq),
+        chirp_duration: args.chirp_duration,
+        trigger_frequency: args.trigger_frequency,
+        sample_rate: args.sample_rate,
+        seed,
+    })?;
+
+    if args.verbose && sine && args.calibration_interval.is_none() {
+        println!("Channel frequencies:");
+        for (i, freq) in frequencies.iter().enumerate() {
+            println!("\tChannel {}: {:.2} Hz", i + 1, freq);
+        }
+        println!();
+    }
+
+    // Generate and stream data
+    let mut sample_count = 0u64;
+    let chunk_duration = Duration::from_secs_f64(args.chunk_size as f64 / args.sample_rate);
+    let start_time = Instant::now();
+    // LSL clock reading matching sample time 0, for calibration marker timestamps
+    let clock_start = lsl::local_clock();
+    let mut next_chunk_time = start_time;
+
+   macro_rules! generate_and_push_chunk {
+        ($ty:ty, $scale:expr, $convert:expr, $outlet:expr, $args:expr,
+        $sample_count:expr, $generator:expr) => {{
+            let mut chunk: Vec<Vec<$ty>> = Vec::with_capacity($args.chunk_size as usize);
+
+            for sample_idx in 0..$args.chunk_size {
+                let mut sample: Vec<$ty> = Vec::with_capacity($args.channels as usize);
+                if let Some((interval_samples, pulse_samples, _)) = &calibration {
+                    // Rectangular pulse at the start of every interval
+                    let index = $sample_count * $args.chunk_size as u64 + sample_idx as u64;
+                    let level = if index % interval_samples < *pulse_samples { 1.0 } else { 0.0 };
+                    sample.resize($args.channels as usize, $convert(level * $scale));
+                } else {
+                    // Values in [-1, 1] from the chosen waveform or simulation
+                    for value_f64 in $generator.next_sample() {
+                        sample.push($convert(value_f64 * $scale));
+                    }
+                }
+                chunk.push(sample);
+            }
+
+            // Push chunk to LSL, with the injected timestamps and losses if there are faults;
+            // evaluates to the push result and the number of samples pushed
+            if let Some(faults) = faults.as_mut() {
+                let first = $sample_count * $args.chunk_size as u64;
+                let stamps = faults.stamp_chunk(first, $args.chunk_size as usize, clock_start);
+                let kept: Vec<(Vec<$ty>, f64)> = chunk
+                    .into_iter()
+                    .zip(stamps)
+                    .filter_map(|(sample, stamp)| stamp.map(|stamp| (sample, stamp)))
+                    .collect();
+                let result = kept
+                    .iter()
+                    .enumerate()
+                    .try_for_each(|(i, (sample, stamp))| $outlet.push_sample_ex(sample, *stamp, i + 1 == kept.len()));
+                (result, kept.len() as u64)
+            } else {
+                ($outlet.push_chunk(&chunk), $args.chunk_size as u64)
+            }
+        }};
+    }
+
+
+    let mut monitor = PushMonitor::new(args.sample_rate, args.max_lag);
+
+    loop {
+        let (result, samples) = match channel_format {
+            lsl::ChannelFormat::Float32 => {
+                generate_and_push_chunk!(
+                    f32,          // type
+                    1.0,          // scale
+                    |v| v as f32, // conversion
+                    outlet,
+                    args,
+                    sample_count,
+                    generator
+                )
+            }
+            lsl::ChannelFormat::Int16 => {
+                generate_and_push_chunk!(
+                    i16,
+                    32767.0,
+                    |v| v as i16,
+                    outlet,
+                    args,
+                    sample_count,
+                    generator
+                )
+            }
+            _ => unreachable!("Only Float32 and Int16 are supported"),
+        };
+
+        // Lag of this chunk behind its slot in the schedule
+        let lag = Instant::now().saturating_duration_since(next_chunk_time).as_secs_f64();
+        if let Some(warning) = monitor.record(result, samples, lag, start_time.elapsed().as_secs_f64())? {
+            eprintln!("Warning: {}", warning);
+        }
+
+        // Announce pulses that started in this chunk with their true onset time
+        if let Some((interval_samples, _, marker_outlet)) = &calibration {
+            let first = sample_count * args.chunk_size as u64;
+            for index in first..first + args.chunk_size as u64 {
+                if index.is_multiple_of(*interval_samples) {
+                    let onset = clock_start + index as f64 / args.sample_rate;
+                    marker_outlet.push_sample_ex(&vec!["pulse".to_string()], onset, true)?;
+                }
+            }
+        }
+
+        if args.verbose && sample_count.is_multiple_of(100) {
+            let elapsed = start_time.elapsed().as_secs_f64();
+            let samples_sent = (sample_count + 1) * args.chunk_size as u64;
+            let expected_samples = (elapsed * args.sample_rate) as u64;
+            let drift = samples_sent as i64 - expected_samples as i64;
+            println!(
+                "Status: {} samples sent in {:.1}s (avg rate: {:.1} Hz, drift: {} samples)",
+                samples_sent,
+                elapsed,
+                samples_sent as f64 / elapsed,
+                drift
+            );
+            println!("Outlet: {}", monitor.stats().summary());
+            if let Some(ref faults) = faults {
+                let counts = faults.counts();
+                println!(
+                    "Faults: {} chunks ({} samples) dropped, {} samples lost to burst gaps",
+                    counts.dropped_chunks, counts.dropped_samples, counts.gap_samples
+                );
+            }
+        }
+
+        sample_count += 1;
+
+        // Calculate when the next chunk should be sent
+        next_chunk_time += chunk_duration;
+
+        // Sleep until close to the target time
+        let now = Instant::now();
+        if next_chunk_time > now {
+            let sleep_duration = next_chunk_time - now;
+
+            // If we need to sleep more than 1ms, use thread::sleep for most of it
+            if sleep_duration > Duration::from_millis(1) {
+                thread::sleep(sleep_duration - Duration::from_millis(1));
+            }
+
+            // Spin-wait for the remaining time for better accuracy
+            while Instant::now() < next_chunk_time {
+                std::hint::spin_loop();
+            }
+        }
+        // If we're already late, don't sleep at all (catch up)
+    }
+
+}
+
+/// String mode: named events on an irregular single-channel marker stream
+fn run_marker_stream(args: &Args) -> Result<()> {
+    if args.calibration_interval.is_some() || args.fault_options().any() {
+        anyhow::bail!("Calibration pulses and fault injection need a numeric stream (--data-type float32 or int16)");
+    }
+
+    let stream_type = args.stream_type.clone().unwrap_or_else(|| "Markers".to_string());
+    let names = if args.events.is_empty() {
+        EventOptions::default().names
+    } else {
+        args.events.clone()
+    };
+    let options = EventOptions {
+        names,
+        interval: args.event_interval,
+        poisson: args.poisson,
+    };
+    let seed = args.seed.unwrap_or_else(|| fastrand::u64(..));
+    let events = EventGenerator::new(options.clone(), seed)?;
+
+    let info = StreamInfo::new(
+        &args.name,
+        &stream_type,
+        1,
+        lsl::IRREGULAR_RATE,
+        lsl::ChannelFormat::String,
+        &args.source_id,
+    )?;
+    let outlet = StreamOutlet::new(&info, 0, 360)?;
+
+    println!("LSL Dummy Stream Generator");
+    println!("==========================");
+    println!("Stream name:\t{}", args.name);
+    println!("Stream type:\t{}", stream_type);
+    println!("Source ID:\t{}", args.source_id);
+    println!("Channels:\t1 (string markers)");
+    println!("Events:\t\t{}", options.names.join(", "));
+    if options.poisson {
+        println!("Timing:\t\tPoisson, {} s mean interval", options.interval);
+    } else {
+        println!("Timing:\t\tevery {} s", options.interval);
+    }
+    println!();
+    println!("Starting continuous event generation...");
+    println!("Press Ctrl+C to stop");
+    println!();
+
+    let start_time = Instant::now();
+    // LSL clock reading matching event time 0
+    let clock_start = lsl::local_clock();
+
+    let mut monitor = PushMonitor::new(1.0 / options.interval, args.max_lag);
+
+    for (index, event) in events.enumerate() {
+        let due = start_time + Duration::from_secs_f64(event.time);
+        thread::sleep(due.saturating_duration_since(Instant::now()));
+        let lag = Instant::now().saturating_duration_since(due).as_secs_f64();
+        let result = outlet.push_sample_ex(&vec![event.name.clone()], clock_start + event.time, true);
+        if let Some(warning) = monitor.record(result, 1, lag, start_time.elapsed().as_secs_f64())? {
+            eprintln!("Warning: {}", warning);
+        }
+        if args.verbose {
+            println!("Event {}: {} at {:.3} s", index + 1, event.name, event.time);
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,1220 @@
+//! LSL Inspect - Zarr file inspection and metadata viewer
+//!
+//! This tool inspects Zarr files created by lsl-recorder and displays file structure,
+//! metadata, stream information, and recording duration.
+//!
+//! # Features
+//!
+//! - Display Zarr file structure and hierarchy
+//! - Show global metadata (subject, session, notes)
+//! - List all streams within a Zarr file
+//! - Display stream information (channels, sample rate, format, duration)
+//! - Filter by specific stream name(s)
+//! - Verbose mode for additional details
+//! - Clean hierarchical output with Unicode box drawing
+//!
+//! # Usage
+//!
+//! ```bash
+//! # Inspect default file (experiment.zarr)
+//! lsl-inspect
+//!
+//! # Inspect specific file
+//! lsl-inspect recording.zarr
+//!
+//! # Verbose mode with additional metadata
+//! lsl-inspect experiment.zarr --verbose
+//!
+//! # Filter to specific stream(s)
+//! lsl-inspect experiment.zarr --stream EMG
+//! lsl-inspect experiment.zarr --stream EMG --stream EEG
+//!
+//! # Show start/end times in UTC instead of local time
+//! lsl-inspect experiment.zarr --utc
+//!
+//! # Per-channel value ranges in physical units with scaling checks
+//! lsl-inspect experiment.zarr --units
+//!
+//! # Effective rate, non-increasing timestamps and a gap map of each time array
+//! lsl-inspect experiment.zarr --timeline
+//!
+//! # Every setting a stream was recorded with, defaults included
+//! lsl-inspect experiment.zarr --config
+//!
+//! # Machine-readable output for scripts and CI checks
+//! lsl-inspect experiment.zarr --json | jq '.streams[].duration'
+//!
+//! # Quick check of a store served over HTTP (needs the `http` feature)
+//! lsl-inspect https://data.lab.org/sessions/P001.zarr
+//! ```
+//!
+//! Stores given as `http://` or `https://` URLs are read through a local cache
+//! (`--cache-dir`) holding their metadata and timestamps; sample chunks are only
+//! downloaded for `--units` (see [`crate::zarr::http`]).
+//!
+//! The `--units` channel ranges need a scan of every sample and the `--timeline`
+//! summary (see [`crate::time_axis`]) one of every timestamp, so both are kept in the
+//! derived-value cache (see [`crate::zarr::cache`]) and reused until the stream's
+//! arrays change; `--no-cache` rescans.
+//!
+//! # Output Format
+//!
+//! Displays:
+//! - Session manifest (subject, session ID, notes, host, OS, toolbox and liblsl
+//!   versions, recorded streams; recorder configs with `--verbose`)
+//! - Acquisition devices (manufacturer, model, serial number) and their streams
+//! - Annotations made with `MARK` (label and LSL clock time)
+//! - Captures taken on `--capture-on` markers (marker time, files written or error)
+//! - Parts of a rotated session (`--rotate-every`, `--rotate-size`) with their
+//!   duration, streams and samples
+//! - Stream list with names and key information
+//! - For each stream:
+//!   - Channel count and format
+//!   - Sample rate (nominal and actual)
+//!   - Recording duration
+//!   - Sample count
+//!   - Timestamp range
+//!   - (`--timeline`) Effective rate, count of non-increasing timestamps, gaps and a
+//!     sparkline of sample density over the recording with the gaps marked below it
+//!   - Start/end wall-clock time (local timezone, or UTC with `--utc`)
+//!   - (`--units`) Per-channel min/max in declared units; flat channels and voltage
+//!     ranges implausible for biopotentials (e.g. volts declared as microvolts) are flagged
+//!   - Clock jumps noticed while recording (suspend/resume, NTP steps)
+//!   - Signal quality warnings from `lsl-recorder --quality` (per-channel RMS,
+//!     flatline time and clipped fraction with `--verbose`)
+//!   - Samples outside `lsl-recorder --plausible-range` (per channel with `--verbose`)
+//!   - (Verbose) Full stream info and recorder config
+//!   - (`--config`) Every setting of the recorder configuration, with secrets
+//!     (URL passwords and tokens, object store keys) redacted
+//!
+//! With `--json`, the same information is printed as one JSON document instead:
+//! `store`, `metadata` (root attributes), `session_status`, `manifest`, `devices`, `annotations`, `captures`, `session_parts`, and a `streams` array with
+//! shape, sample counts, timestamp range, duration, nominal and effective rate, start/end
+//! time, the synchronization attributes (`sync`), the full group `attributes`, and
+//! (`--units`) `channel_ranges`, (`--timeline`) `time_axis`, and (`--config`) the redacted recorder configuration
+//! as `config`. Secrets are redacted in the `recorder_config` attributes of the JSON
+//! output as well, also for stores recorded before the recorder redacted them.
+
+use anyhow::Result;
+use clap::Parser;
+use serde_json::json;
+use crate::annotations::read_annotations;
+use crate::captures::read_captures;
+use crate::connection::ConnectionStats;
+use crate::devices::{collect_devices, read_devices, Device};
+use crate::dropped::DropEstimate;
+use crate::info_history::read_history;
+use crate::manifest::{read_manifest, SessionManifest};
+use crate::clock_jumps::read_clock_jumps;
+use crate::flags::read_flag_counts;
+use crate::pauses::read_pauses;
+use crate::plausibility::PLAUSIBILITY_ATTRIBUTE;
+use crate::quality::QUALITY_ATTRIBUTE;
+use crate::redact::redact_config;
+use crate::rotation::{read_part_info, session_parts, Continuation, PartInfo, CONTINUATION_ATTRIBUTE};
+use crate::schedule::read_blocks;
+use crate::session::{summarize_store, SessionSummary};
+use crate::time_axis::{TimeAxisScan, TimeAxisSummary, DEFAULT_TIMELINE_BINS};
+use crate::units::{range_warning, unit_symbol};
+use crate::zarr::cache::StoreCache;
+use crate::zarr::http::{fetch_http_store, is_http, HttpFetchOptions};
+use crate::zarr::{
+    is_store_group, parse_time_anchor, read_group_attributes, sample_array_path, stream_sample_count, RecorderConfig,
+    StreamInfoAttribute,
+};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use zarrs::array::Array;
+use zarrs::array_subset::ArraySubset;
+use zarrs::filesystem::FilesystemStore;
+
+#[derive(Parser)]
+#[command(name = "lsl-inspect")]
+#[command(about = "Inspect Zarr recordings created by lsl-recorder")]
+#[command(version)]
+pub struct Args {
+    /// Path to Zarr file to inspect
+    #[arg(default_value = "experiment.zarr")]
+    file_path: String,
+
+    /// Show detailed stream information
+    #[arg(short, long)]
+    verbose: bool,
+
+    /// Filter to specific stream name(s)
+    #[arg(short, long)]
+    stream: Option<Vec<String>>,
+
+    /// Show start/end times in UTC instead of local time
+    #[arg(long)]
+    utc: bool,
+
+    /// Report per-channel value ranges in physical units and flag implausible scaling
+    #[arg(long)]
+    units: bool,
+
+    /// Print the inspection result as a single JSON document
+    #[arg(long)]
+    json: bool,
+
+    /// Cache directory for stores read over HTTP (default: <tmp>/lsl-http-cache)
+    #[arg(long)]
+    cache_dir: Option<PathBuf>,
+
+    /// Rescan the arrays for --units and --timeline instead of reusing the results of an earlier run
+    #[arg(long)]
+    no_cache: bool,
+
+    /// Print the full recorder configuration each stream was recorded with (secrets redacted)
+    #[arg(long)]
+    config: bool,
+
+    /// Summarize each time array: effective rate, non-increasing timestamps, gaps and a
+    /// sparkline of sample density over the recording
+    #[arg(long)]
+    timeline: bool,
+}
+
+/// Stream group attributes describing timestamp synchronization (recorder and lsl-sync)
+const SYNC_ATTRIBUTES: [&str; 8] = [
+    "lsl_clock_offset",
+    "first_timestamp",
+    "alignment_offset",
+    "trim_start_index",
+    "trim_end_index",
+    "original_sample_count",
+    "trimmed_sample_count",
+    "drift_correction",
+];
+
+/// Samples read per block when scanning data arrays
+const RANGE_BLOCK_SAMPLES: u64 = 50_000;
+
+/// Min/max per channel of a numeric [channels × samples] data array
+fn channel_ranges(array: &Array<FilesystemStore>, channel_format: &str) -> Result<Option<Vec<(f64, f64)>>> {
+    let shape = array.shape();
+    if shape.len() < 2 {
+        return Ok(None);
+    }
+    let (channels, samples) = (shape[0], shape[1]);
+    let mut ranges = vec![(f64::INFINITY, f64::NEG_INFINITY); channels as usize];
+
+    macro_rules! scan {
+        ($ty:ty) => {{
+            let mut start = 0;
+            while start < samples {
+                let len = RANGE_BLOCK_SAMPLES.min(samples - start);
+                let subset = ArraySubset::new_with_start_shape(vec![0, start], vec![channels, len])?;
+                let block = array.retrieve_array_subset_ndarray::<$ty>(&subset)?;
+                for (ch, row) in block.outer_iter().enumerate() {
+                    for &value in row.iter() {
+                        let value = value as f64;
+                        ranges[ch].0 = ranges[ch].0.min(value);
+                        ranges[ch].1 = ranges[ch].1.max(value);
+                    }
+                }
+                start += len;
+            }
+        }};
+    }
+
+    match channel_format {
+        "Float32" => scan!(f32),
+        "Double64" | "Float64" => scan!(f64),
+        "Int32" => scan!(i32),
+        "Int16" => scan!(i16),
+        "Int8" => scan!(i8),
+        _ => return Ok(None),
+    }
+
+    Ok(Some(ranges))
+}
+
+/// Time-axis summary of a stream's first `samples` timestamps, read in blocks
+fn scan_time_axis(
+    time_array: &Array<FilesystemStore>,
+    samples: u64,
+    nominal_srate: f64,
+) -> Result<Option<TimeAxisSummary>> {
+    let Some((first, last)) = timestamp_range(time_array, samples)? else {
+        return Ok(None);
+    };
+    let mut scan = TimeAxisScan::new(first, last, nominal_srate, DEFAULT_TIMELINE_BINS);
+    let mut start = 0;
+    while start < samples {
+        let len = RANGE_BLOCK_SAMPLES.min(samples - start);
+        let subset = ArraySubset::new_with_start_shape(vec![start], vec![len])?;
+        let block: Vec<f64> = time_array.retrieve_array_subset_ndarray::<f64>(&subset)?.into_iter().collect();
+        scan.push(&block);
+        start += len;
+    }
+    Ok(Some(scan.finish()))
+}
+
+/// Time-axis summary of a stream, from the cache if its time array was scanned before
+fn cached_time_axis(
+    cache: &mut StoreCache,
+    stream_name: &str,
+    time_array: &Array<FilesystemStore>,
+    samples: u64,
+    nominal_srate: f64,
+) -> Result<Option<TimeAxisSummary>> {
+    if let Some(summary) = cache
+        .stream(stream_name)
+        .and_then(|c| c.time_axis(nominal_srate, DEFAULT_TIMELINE_BINS))
+    {
+        return Ok(Some(summary.clone()));
+    }
+    let summary = scan_time_axis(time_array, samples, nominal_srate)?;
+    if let Some(ref summary) = summary
+        && let Some(entry) = cache.stream_mut(stream_name)
+    {
+        entry.time_axis = Some(summary.clone());
+    }
+    Ok(summary)
+}
+
+/// Print the time-axis summary below a stream's time range
+fn print_time_axis(summary: &TimeAxisSummary, indent: &str) {
+    let rate = summary
+        .effective_rate
+        .map(|rate| format!("{:.2} Hz effective", rate))
+        .unwrap_or_else(|| "no effective rate".to_string());
+    let order = if summary.monotonic() {
+        "monotonic".to_string()
+    } else if summary.backwards > 0 {
+        format!(
+            "{} non-increasing ({} backwards, up to {:.6} s)",
+            summary.non_increasing, summary.backwards, summary.largest_backwards
+        )
+    } else {
+        format!("{} non-increasing", summary.non_increasing)
+    };
+    let gaps = if summary.nominal_srate > 0.0 {
+        format!(", {} gaps ({:.3} s lost)", summary.gaps, summary.lost_time)
+    } else {
+        String::new()
+    };
+    println!("{}├─ Time axis: {}, {}{}", indent, rate, order, gaps);
+    println!("{}│    │{}│", indent, summary.sparkline());
+    if let Some(markers) = summary.gap_markers() {
+        println!("{}│    │{}│", indent, markers);
+    }
+}
+
+/// Channel ranges of a stream, from the cache if its data array was scanned before
+fn cached_channel_ranges(
+    cache: &mut StoreCache,
+    stream_name: &str,
+    array: &Array<FilesystemStore>,
+    channel_format: &str,
+) -> Result<Option<Vec<(f64, f64)>>> {
+    if let Some(ranges) = cache.stream(stream_name).and_then(|c| c.channel_ranges.clone()) {
+        // Channels without samples keep the empty range channel_ranges starts from
+        let empty = (f64::INFINITY, f64::NEG_INFINITY);
+        return Ok(Some(ranges.into_iter().map(|range| range.unwrap_or(empty)).collect()));
+    }
+    let ranges = channel_ranges(array, channel_format)?;
+    if let Some(ref ranges) = ranges
+        && let Some(entry) = cache.stream_mut(stream_name)
+    {
+        entry.channel_ranges = Some(ranges.iter().map(|&(min, max)| (min <= max).then_some((min, max))).collect());
+    }
+    Ok(ranges)
+}
+
+/// Value range of one channel in its declared unit
+struct ChannelRange {
+    label: String,
+    unit: String,
+    /// `None` if the channel has no samples
+    range: Option<(f64, f64)>,
+    warning: Option<String>,
+}
+
+/// Per-channel ranges with units and scaling warnings (`None` for non-numeric data)
+fn unit_report(
+    array: &Array<FilesystemStore>,
+    stream_info: &StreamInfoAttribute,
+    cache: &mut StoreCache,
+    stream_name: &str,
+) -> Result<Option<Vec<ChannelRange>>> {
+    let channel_format = stream_info.channel_format.as_deref().unwrap_or("");
+    let Some(ranges) = cached_channel_ranges(cache, stream_name, array, channel_format)? else {
+        return Ok(None);
+    };
+
+    // Per-channel metadata (newer recordings), else a single stream-wide unit
+    let channel_meta = (!stream_info.channels.is_empty()).then_some(&stream_info.channels);
+    let stream_unit = stream_info.description.get("unit").and_then(|v| v.as_str());
+
+    let report = ranges
+        .iter()
+        .enumerate()
+        .map(|(ch, &(min, max))| {
+            let meta = channel_meta.and_then(|c| c.get(ch));
+            let label = meta
+                .and_then(|m| m.get("label"))
+                .and_then(|v| v.as_str())
+                .map(|s| s.to_string())
+                .unwrap_or_else(|| format!("ch{}", ch));
+            let unit = meta
+                .and_then(|m| m.get("unit"))
+                .and_then(|v| v.as_str())
+                .or(stream_unit)
+                .unwrap_or("")
+                .to_string();
+            let range = (min <= max).then_some((min, max));
+            let warning = match range {
+                Some((min, max)) if min == max => Some("flat channel (constant value)".to_string()),
+                Some((min, max)) => range_warning(&unit, min, max),
+                None => None,
+            };
+            ChannelRange { label, unit, range, warning }
+        })
+        .collect();
+    Ok(Some(report))
+}
+
+/// Print per-channel ranges with units and flag flat or implausibly scaled channels
+fn print_unit_report(
+    array: &Array<FilesystemStore>,
+    stream_info: &StreamInfoAttribute,
+    cache: &mut StoreCache,
+    stream_name: &str,
+    indent: &str,
+) -> Result<()> {
+    let Some(report) = unit_report(array, stream_info, cache, stream_name)? else {
+        let channel_format = stream_info.channel_format.as_deref().unwrap_or("");
+        println!("{}├─ Units: not applicable to {} data", indent, channel_format);
+        return Ok(());
+    };
+
+    let stream_unit = stream_info.description.get("unit").and_then(|v| v.as_str());
+    if stream_info.channels.is_empty() && stream_unit.is_none() {
+        println!("{}├─ Units: no unit metadata (raw ranges shown)", indent);
+    }
+
+    println!("{}├─ Channel ranges:", indent);
+    let mut flagged = 0;
+    for channel in &report {
+        let Some((min, max)) = channel.range else {
+            println!("{}│  {:<12} no samples", indent, channel.label);
+            continue;
+        };
+        println!(
+            "{}│  {:<12} [{:.6}, {:.6}] {}{}",
+            indent,
+            channel.label,
+            min,
+            max,
+            unit_symbol(&channel.unit),
+            channel.warning.as_ref().map(|w| format!("\tWARNING: {}", w)).unwrap_or_default()
+        );
+        if channel.warning.is_some() {
+            flagged += 1;
+        }
+    }
+    if flagged > 0 {
+        println!("{}├─ Scaling check: {} channel(s) flagged", indent, flagged);
+    }
+
+    Ok(())
+}
+
+/// Format a wall-clock time as RFC3339 in UTC or the local timezone
+fn format_time(time: chrono::DateTime<chrono::Utc>, utc: bool) -> String {
+    if utc {
+        time.to_rfc3339_opts(chrono::SecondsFormat::Millis, true)
+    } else {
+        time.with_timezone(&chrono::Local)
+            .to_rfc3339_opts(chrono::SecondsFormat::Millis, false)
+    }
+}
+
+/// First and last of a stream's first `samples` timestamps
+fn timestamp_range(time_array: &Array<FilesystemStore>, samples: u64) -> Result<Option<(f64, f64)>> {
+    if samples == 0 {
+        return Ok(None);
+    }
+    let read = |index: u64| -> Result<f64> {
+        let subset = ArraySubset::new_with_start_shape(vec![index], vec![1])?;
+        Ok(time_array.retrieve_array_subset_ndarray::<f64>(&subset)?[[0]])
+    };
+    Ok(Some((read(0)?, read(samples - 1)?)))
+}
+
+/// Wall-clock start of a stream (falls back to recorded_at for older stores)
+fn stream_start_time(attrs: &serde_json::Value) -> Option<chrono::DateTime<chrono::Utc>> {
+    attrs.get("start_time").and_then(parse_time_anchor).or_else(|| {
+        attrs
+            .get("recorded_at")
+            .and_then(|v| v.as_str())
+            .and_then(|s| chrono::DateTime::parse_from_rfc3339(s).ok())
+            .map(|t| t.with_timezone(&chrono::Utc))
+    })
+}
+
+/// JSON description of one stream group
+fn inspect_stream_json(
+    store: &Arc<FilesystemStore>,
+    stream_name: &str,
+    args: &Args,
+    cache: &mut StoreCache,
+) -> Result<serde_json::Value> {
+    let stream_path = format!("/{}", stream_name);
+    let attrs = read_group_attributes(store, &stream_path).unwrap_or_else(|_| json!({}));
+    let stream_info = StreamInfoAttribute::from_attributes(&attrs).unwrap_or_default();
+    let recorder_config = RecorderConfig::from_attributes(&attrs).unwrap_or_default();
+    let mut stream = json!({
+        "name": stream_name,
+        "source_id": stream_info.source_id,
+        "type": stream_info.stream_type,
+        "hostname": stream_info.hostname,
+        "channel_format": stream_info.channel_format,
+        "nominal_srate": stream_info.nominal_srate,
+        "recorder_version": recorder_config.recorder_version,
+    });
+
+    let data_array_path = sample_array_path(store, stream_name);
+    let data_array = Array::<FilesystemStore>::open(store.clone(), &data_array_path);
+    if let Ok(ref data_array) = data_array {
+        let shape = data_array.shape();
+        stream["shape"] = json!(shape);
+        stream["channels"] = json!(if shape.len() >= 2 { shape[0] } else { 1 });
+        stream["marker"] = json!(shape.len() < 2);
+    }
+
+    if let Ok(time_array) = Array::<FilesystemStore>::open(store.clone(), &format!("{}/time", stream_path)) {
+        let samples = stream_sample_count(&attrs, time_array.shape()[0]);
+        stream["samples"] = json!(samples);
+        if let Some((first, last)) = timestamp_range(&time_array, samples)? {
+            let duration = last - first;
+            stream["first_timestamp"] = json!(first);
+            stream["last_timestamp"] = json!(last);
+            stream["duration"] = json!(duration);
+            if duration > 0.0 {
+                stream["effective_srate"] = json!((samples - 1) as f64 / duration);
+            }
+        }
+        if args.timeline
+            && let Some(summary) =
+                cached_time_axis(cache, stream_name, &time_array, samples, stream_info.nominal_srate.unwrap_or(0.0))?
+        {
+            let mut time_axis = serde_json::to_value(&summary)?;
+            time_axis["monotonic"] = json!(summary.monotonic());
+            time_axis["sparkline"] = json!(summary.sparkline());
+            stream["time_axis"] = time_axis;
+        }
+    }
+
+    if let Some(started) = stream_start_time(&attrs) {
+        stream["started"] = json!(format_time(started, args.utc));
+    }
+    if let Some(ended) = attrs.get("end_time").and_then(parse_time_anchor) {
+        stream["ended"] = json!(format_time(ended, args.utc));
+    }
+    for key in ["event_count", "event_counts"] {
+        if let Some(value) = attrs.get(key) {
+            stream[key] = value.clone();
+        }
+    }
+
+    let sync: serde_json::Map<_, _> = SYNC_ATTRIBUTES
+        .iter()
+        .filter_map(|&key| attrs.get(key).map(|v| (key.to_string(), v.clone())))
+        .collect();
+    stream["sync"] = json!(sync);
+
+    if args.units
+        && let Ok(ref data_array) = data_array
+        && let Some(report) = unit_report(data_array, &stream_info, cache, stream_name)?
+    {
+        stream["channel_ranges"] = report
+            .iter()
+            .map(|channel| {
+                json!({
+                    "label": channel.label,
+                    "unit": channel.unit,
+                    "min": channel.range.map(|r| r.0),
+                    "max": channel.range.map(|r| r.1),
+                    "warning": channel.warning,
+                })
+            })
+            .collect();
+    }
+
+    let mut attrs = attrs;
+    if let Some(config) = attrs.get_mut("recorder_config") {
+        redact_config(config);
+        if args.config {
+            stream["config"] = config.clone();
+        }
+    }
+    stream["attributes"] = attrs;
+    Ok(stream)
+}
+
+/// Device table of a store (built from the streams for stores recorded without one)
+fn store_devices(store_path: &std::path::Path) -> Vec<Device> {
+    read_devices(store_path)
+        .or_else(|| collect_devices(store_path).ok())
+        .unwrap_or_default()
+}
+
+/// Print the session manifest of the `meta` group
+fn print_manifest(manifest: &SessionManifest, utc: bool, verbose: bool) {
+    let unknown = |value: &Option<String>| value.clone().unwrap_or_else(|| "-".to_string());
+    println!("SESSION");
+    println!("  Subject: {}", unknown(&manifest.subject));
+    println!("  Session: {}", unknown(&manifest.session_id));
+    if let Some(ref notes) = manifest.notes {
+        println!("  Notes: {}", notes);
+    }
+    if let Some(created) = manifest
+        .created_at
+        .as_deref()
+        .and_then(|s| chrono::DateTime::parse_from_rfc3339(s).ok())
+    {
+        println!("  Created: {}", format_time(created.with_timezone(&chrono::Utc), utc));
+    }
+    if let Some(ref host) = manifest.host {
+        println!(
+            "  Host: {} ({} {})",
+            host.hostname.as_deref().unwrap_or("unknown"),
+            host.os,
+            host.arch
+        );
+    }
+    println!(
+        "  Versions: toolbox {}, liblsl {}",
+        unknown(&manifest.toolbox_version),
+        unknown(&manifest.lsl_version)
+    );
+    for stream in &manifest.streams {
+        println!(
+            "  Stream {}: {} ({}), {} channel(s) @ {} Hz, recorder {}",
+            stream.name,
+            unknown(&stream.source_id),
+            unknown(&stream.stream_type),
+            stream.channel_count.map(|c| c.to_string()).unwrap_or_else(|| "?".to_string()),
+            stream.nominal_srate.map(|r| r.to_string()).unwrap_or_else(|| "?".to_string()),
+            unknown(&stream.recorder_version)
+        );
+        let mut config = stream.config.clone();
+        redact_config(&mut config);
+        if verbose && let Some(config) = config.as_object() {
+            for (key, value) in config.iter().filter(|(_, v)| !v.is_null()) {
+                println!("    {}: {}", key, value);
+            }
+        }
+    }
+    println!();
+}
+
+/// Print every setting of a stream's recorder configuration, secrets redacted
+fn print_recorder_config(config: &serde_json::Value, indent: &str) {
+    let mut config = config.clone();
+    redact_config(&mut config);
+    println!("{}   Configuration:", indent);
+    for (key, value) in config.as_object().into_iter().flatten() {
+        let value = match value {
+            serde_json::Value::Null => "(not set)".to_string(),
+            serde_json::Value::String(text) => text.clone(),
+            other => other.to_string(),
+        };
+        println!("{}     {}: {}", indent, key, value);
+    }
+}
+
+/// JSON description of the whole store
+fn inspect_json(store: &Arc<FilesystemStore>, args: &Args, cache: &mut StoreCache) -> Result<serde_json::Value> {
+    let metadata = read_group_attributes(store, "/").unwrap_or_else(|_| json!({}));
+
+    let mut names: Vec<String> = std::fs::read_dir(&args.file_path)?
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.file_type().map(|t| t.is_dir()).unwrap_or(false))
+        .map(|entry| entry.file_name().to_string_lossy().to_string())
+        .filter(|name| !is_store_group(name))
+        .collect();
+    names.sort();
+
+    let mut streams = Vec::new();
+    for name in names {
+        if let Some(ref filter_streams) = args.stream
+            && !filter_streams.contains(&name)
+        {
+            continue;
+        }
+        streams.push(inspect_stream_json(store, &name, args, cache)?);
+    }
+    let total_samples: u64 = streams.iter().filter_map(|s| s["samples"].as_u64()).sum();
+
+    Ok(json!({
+        "store": args.file_path,
+        "session_status": metadata.get("session_status"),
+        "metadata": metadata,
+        "manifest": read_manifest(&PathBuf::from(&args.file_path)).map(|mut manifest| {
+            for stream in &mut manifest.streams {
+                redact_config(&mut stream.config);
+            }
+            manifest
+        }),
+        "devices": store_devices(&PathBuf::from(&args.file_path)).iter().map(Device::to_json).collect::<Vec<_>>(),
+        "annotations": read_annotations(&PathBuf::from(&args.file_path)),
+        "captures": read_captures(&PathBuf::from(&args.file_path)),
+        "session_parts": session_parts_json(Path::new(&args.file_path)),
+        "stream_count": streams.len(),
+        "total_samples": total_samples,
+        "streams": streams,
+    }))
+}
+
+/// Store summary of every part of a rotated session
+type PartSummaries = Vec<(PathBuf, Option<SessionSummary>)>;
+
+/// Parts of a rotated session with their duration, streams and samples (`None` if not rotated)
+fn session_part_summaries(store_path: &Path) -> Option<(Option<PartInfo>, PartSummaries)> {
+    let info = read_part_info(store_path);
+    let parts = session_parts(store_path);
+    if info.is_none() && parts.len() < 2 {
+        return None;
+    }
+    let summaries = parts
+        .into_iter()
+        .map(|part| {
+            let summary = summarize_store(&part).ok();
+            (part, summary)
+        })
+        .collect();
+    Some((info, summaries))
+}
+
+/// List the parts of a rotated session (`--rotate-every`, `--rotate-size`)
+fn print_session_parts(store_path: &Path) {
+    let Some((info, parts)) = session_part_summaries(store_path) else {
+        return;
+    };
+    match info {
+        Some(info) => println!("SESSION PARTS ({} found, this is part {} of {})", parts.len(), info.part, info.session),
+        None => println!("SESSION PARTS ({} found)", parts.len()),
+    }
+    for (path, summary) in &parts {
+        let name = path.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+        let this = if path.file_name() == store_path.file_name() { "  <- inspected" } else { "" };
+        match summary {
+            Some(summary) => println!(
+                "  {}\t{:.1} s, {} streams, {} samples{}",
+                name,
+                summary.duration().unwrap_or(0.0),
+                summary.streams.len(),
+                summary.total_samples(),
+                this
+            ),
+            None => println!("  {}\tunreadable{}", name, this),
+        }
+    }
+    println!();
+}
+
+/// The `session_parts` entry of the `--json` document
+fn session_parts_json(store_path: &Path) -> serde_json::Value {
+    let Some((info, parts)) = session_part_summaries(store_path) else {
+        return serde_json::Value::Null;
+    };
+    let parts: Vec<serde_json::Value> = parts
+        .iter()
+        .map(|(path, summary)| {
+            json!({
+                "store": path,
+                "duration": summary.as_ref().and_then(|s| s.duration()),
+                "streams": summary.as_ref().map(|s| s.streams.len()),
+                "total_samples": summary.as_ref().map(|s| s.total_samples()),
+            })
+        })
+        .collect();
+    json!({"rotation": info, "parts": parts})
+}
+
+/// Derived-value cache of the inspected store (only `--units` ranges and `--timeline`
+/// summaries are cached)
+fn open_cache(args: &Args) -> StoreCache {
+    let store_path = Path::new(&args.file_path);
+    if args.no_cache || !(args.units || args.timeline) {
+        StoreCache::disabled(store_path)
+    } else {
+        StoreCache::open(store_path)
+    }
+}
+
+/// Run `lsl-inspect` with parsed arguments
+pub fn run(mut args: Args) -> Result<()> {
+
+    // Remote stores are inspected through their local cache
+    let source = args.file_path.clone();
+    if is_http(&source) {
+        let options = HttpFetchOptions {
+            cache_dir: args.cache_dir.clone(),
+            streams: args.stream.clone().unwrap_or_default(),
+            sample_data: args.units,
+        };
+        args.file_path = fetch_http_store(&source, &options)?.display().to_string();
+    }
+
+    if args.json {
+        if !PathBuf::from(&args.file_path).is_dir() {
+            anyhow::bail!("Not a Zarr store: {}", args.file_path);
+        }
+        let store = Arc::new(FilesystemStore::new(&args.file_path)?);
+        let mut cache = open_cache(&args);
+        let mut report = inspect_json(&store, &args, &mut cache)?;
+        cache.save_or_warn();
+        report["store"] = json!(source);
+        println!("{}", serde_json::to_string_pretty(&report)?);
+        return Ok(());
+    }
+
+    crate::display_license_notice("lsl-inspect");
+
+    println!("╔════════════════════════════════════════════════════════════════╗");
+    println!("║              LSL Zarr File Inspector                           ║");
+    println!("╚════════════════════════════════════════════════════════════════╝");
+    println!();
+    println!("Store: {}", source);
+    if source != args.file_path {
+        println!("Cached at: {}", args.file_path);
+    }
+    println!();
+
+    let store = Arc::new(FilesystemStore::new(&args.file_path)?);
+    let mut cache = open_cache(&args);
+
+    // Sessions ended by lsl-multi-recorder --stop-all-on-failure
+    if let Ok(root_attrs) = read_group_attributes(&store, "/")
+        && let Some(status) = root_attrs.get("session_status")
+        && status.get("complete").and_then(|v| v.as_bool()) == Some(false)
+    {
+        println!(
+            "SESSION INCOMPLETE: stream '{}' {} (stopped at {})",
+            status.get("failed_stream").and_then(|v| v.as_str()).unwrap_or("?"),
+            status.get("reason").and_then(|v| v.as_str()).unwrap_or("failed"),
+            status.get("stopped_at").and_then(|v| v.as_str()).unwrap_or("?")
+        );
+        println!();
+    }
+
+    // Inspect streams (now at zarr root)
+    let streams_path = PathBuf::from(&args.file_path);
+    let mut stream_count = 0;
+    let mut total_samples = 0;
+
+    if streams_path.exists() && streams_path.is_dir() {
+        // Count streams first (exclude zarr.json file)
+        for entry in std::fs::read_dir(&streams_path)? {
+            let entry = entry?;
+            if entry.file_type()?.is_dir() && !is_store_group(&entry.file_name().to_string_lossy()) {
+                stream_count += 1;
+            }
+        }
+
+        if let Some(manifest) = read_manifest(&streams_path) {
+            print_manifest(&manifest, args.utc, args.verbose);
+        }
+
+        let devices = store_devices(&streams_path);
+        if !devices.is_empty() {
+            println!("DEVICES ({} found)", devices.len());
+            for device in &devices {
+                println!(
+                    "  {} {} (serial {})\t{}",
+                    device.manufacturer.as_deref().unwrap_or("unknown"),
+                    device.model.as_deref().unwrap_or("unknown model"),
+                    device.serial_number.as_deref().unwrap_or("unknown"),
+                    device.streams.join(", ")
+                );
+            }
+            println!();
+        }
+
+        let annotations = read_annotations(&streams_path);
+        if !annotations.is_empty() {
+            println!("ANNOTATIONS ({} found)", annotations.len());
+            for annotation in &annotations {
+                println!("  {:.3}\t{}", annotation.time, annotation.label);
+            }
+            println!();
+        }
+
+        let captures = read_captures(&streams_path);
+        if !captures.is_empty() {
+            println!("CAPTURES ({} found)", captures.len());
+            for capture in &captures {
+                let outcome = match (&capture.error, capture.files.is_empty()) {
+                    (Some(error), _) => format!("failed: {}", error),
+                    (None, false) => capture.files.join(", "),
+                    (None, true) => "time anchors only".to_string(),
+                };
+                println!(
+                    "  {:.3}\t{} #{} ({})\t{}",
+                    capture.marker_time, capture.marker, capture.index, capture.stream, outcome
+                );
+            }
+            println!();
+        }
+
+        print_session_parts(&streams_path);
+
+        println!("STREAMS ({} found)", stream_count);
+        println!();
+
+        let mut stream_idx = 0;
+        let streams_dir = std::fs::read_dir(&streams_path)?;
+        let mut entries: Vec<_> = streams_dir.collect();
+        entries.sort_by_key(|e| e.as_ref().map(|e| e.file_name()).unwrap_or_default());
+
+        for entry in entries {
+            let entry = entry?;
+            if entry.file_type()?.is_dir() && !is_store_group(&entry.file_name().to_string_lossy()) {
+                let stream_name = entry.file_name().to_string_lossy().to_string();
+
+                // Filter by stream name if specified
+                if let Some(ref filter_streams) = args.stream
+                    && !filter_streams.contains(&stream_name)
+                {
+                    continue;
+                }
+
+                stream_idx += 1;
+                let is_last = stream_idx == stream_count;
+                let prefix = if is_last { "  └─" } else { "  ├─" };
+                let indent = if is_last { "     " } else { "  │  " };
+
+                println!("{} {}", prefix, stream_name);
+
+                let stream_path = format!("/{}", stream_name);
+
+                // Show data array info
+                let data_array_path = sample_array_path(&store, &stream_name);
+                match Array::<FilesystemStore>::open(store.clone(), &data_array_path) {
+                    Ok(data_array) => {
+                        let shape = data_array.shape();
+                        if shape.len() >= 2 {
+                            let num_channels = shape[0] as usize;
+                            println!("{}├─ Channels: {}", indent, num_channels);
+                        } else {
+                            println!("{}├─ Channels: 1 (marker events)", indent);
+                        }
+                    }
+                    Err(e) if args.verbose => {
+                        println!("{}├─ WARNING: Could not open data array at '{}': {}", indent, data_array_path, e);
+                    }
+                    _ => {}
+                }
+
+                // Show time array info and calculate duration
+                let time_array_path = format!("{}/time", stream_path);
+                match Array::<FilesystemStore>::open(store.clone(), &time_array_path) {
+                    Ok(time_array) => {
+                    let attrs = read_group_attributes(&store, &stream_path).unwrap_or_default();
+                    let samples = stream_sample_count(&attrs, time_array.shape()[0]);
+
+                    // Read time data to calculate duration
+                    if samples > 0 {
+                        let num_samples = samples as usize;
+                        total_samples += num_samples;
+                        println!("{}├─ Samples: {}", indent, num_samples);
+
+                        if num_samples >= 2
+                            && let Some((first_time, last_time)) = timestamp_range(&time_array, samples)?
+                        {
+                            let duration = last_time - first_time;
+                            println!("{}├─ Duration: {:.3} s", indent, duration);
+                            println!("{}├─ Time Range: {:.6} → {:.6}", indent, first_time, last_time);
+                            if args.timeline {
+                                let nominal_srate = StreamInfoAttribute::from_attributes(&attrs)
+                                    .and_then(|info| info.nominal_srate)
+                                    .unwrap_or(0.0);
+                                if let Some(summary) =
+                                    cached_time_axis(&mut cache, &stream_name, &time_array, samples, nominal_srate)?
+                                {
+                                    print_time_axis(&summary, indent);
+                                }
+                            }
+                        } else if num_samples == 1 {
+                            println!("{}├─ Duration: single sample", indent);
+                        } else {
+                            println!("{}├─ Duration: no samples", indent);
+                        }
+                    }
+                    }
+                    Err(e) if args.verbose => {
+                        println!("{}├─ WARNING: Could not open time array at '{}': {}", indent, time_array_path, e);
+                    }
+                    _ => {}
+                }
+
+                // Show wall-clock start/end (falls back to recorded_at for older stores)
+                if let Ok(attrs) = read_group_attributes(&store, &stream_path) {
+                    if let Some(started) = stream_start_time(&attrs) {
+                        println!("{}├─ Started: {}", indent, format_time(started, args.utc));
+                    }
+                    if let Some(ended) = attrs.get("end_time").and_then(parse_time_anchor) {
+                        println!("{}├─ Ended: {}", indent, format_time(ended, args.utc));
+                    }
+                    if let Some(event_count) = attrs.get("event_count").and_then(|v| v.as_u64()) {
+                        let event_counts = attrs.get("event_counts").and_then(|v| v.as_object());
+                        println!(
+                            "{}├─ Events: {} ({} distinct)",
+                            indent,
+                            event_count,
+                            event_counts.map(|c| c.len()).unwrap_or(0)
+                        );
+                        if args.verbose {
+                            for (event, count) in event_counts.into_iter().flatten() {
+                                println!("{}│    {}: {}", indent, event, count);
+                            }
+                        }
+                    }
+                    if let Some(drops) = DropEstimate::from_attributes(&attrs) {
+                        println!(
+                            "{}├─ Dropped samples: ~{} of {} expected ({:.2}%, {:.1} s at {} Hz)",
+                            indent,
+                            drops.dropped,
+                            drops.expected,
+                            drops.percent(),
+                            drops.duration_s,
+                            drops.nominal_srate
+                        );
+                    }
+                    if let Some(connection) = ConnectionStats::from_attributes(&attrs) {
+                        println!(
+                            "{}├─ Connection: {} resolution attempt(s), {} outage(s), {} reconnection(s), {:.3} s downtime",
+                            indent,
+                            connection.resolution_attempts,
+                            connection.outages,
+                            connection.reconnections,
+                            connection.total_downtime_s
+                        );
+                        if args.verbose && !connection.retry_delays_ms.is_empty() {
+                            println!("{}│    Retry delays: {:?} ms", indent, connection.retry_delays_ms);
+                        }
+                        if connection.reconnect_attempts > 0 {
+                            println!("{}│    Re-resolved {} time(s)", indent, connection.reconnect_attempts);
+                        }
+                        if args.verbose {
+                            for outage in &connection.outage_intervals {
+                                println!(
+                                    "{}│    Outage: {} to {}",
+                                    indent,
+                                    outage.start.map_or("first sample".to_string(), |t| format!("{:.3}", t)),
+                                    outage.end.map_or("recording end".to_string(), |t| format!("{:.3}", t))
+                                );
+                            }
+                        }
+                    }
+                    let info_history = read_history(&attrs);
+                    if !info_history.is_empty() {
+                        println!("{}├─ Stream info changed {} time(s) while recording", indent, info_history.len());
+                        if args.verbose {
+                            for snapshot in &info_history {
+                                println!(
+                                    "{}│    {} (LSL {:.3}): {}",
+                                    indent,
+                                    snapshot.utc,
+                                    snapshot.lsl_clock,
+                                    snapshot.changed.join(", ")
+                                );
+                            }
+                        }
+                    }
+                    let blocks = read_blocks(&attrs);
+                    if !blocks.is_empty() {
+                        let interrupted = blocks.iter().filter(|b| b.interrupted).count();
+                        println!(
+                            "{}├─ Blocks: {} recorded{}",
+                            indent,
+                            blocks.len(),
+                            if interrupted > 0 { format!(" ({} interrupted)", interrupted) } else { String::new() }
+                        );
+                        if args.verbose {
+                            for block in &blocks {
+                                println!(
+                                    "{}│    Block {}: LSL {:.3} - {:.3} ({:.1} s, from {}){}",
+                                    indent,
+                                    block.index,
+                                    block.start,
+                                    block.end,
+                                    block.end - block.start,
+                                    block.start_utc,
+                                    if block.interrupted { ", interrupted" } else { "" }
+                                );
+                            }
+                        }
+                    }
+                    let pauses = read_pauses(&attrs);
+                    if !pauses.is_empty() {
+                        let paused: f64 = pauses.iter().map(|(start, end)| end - start).sum();
+                        println!("{}├─ Pauses: {} ({:.1} s paused)", indent, pauses.len(), paused);
+                        if args.verbose {
+                            for (start, end) in &pauses {
+                                println!("{}│    LSL {:.3} - {:.3} ({:.1} s)", indent, start, end, end - start);
+                            }
+                        }
+                    }
+                    if let Some(continuation) = attrs
+                        .get(CONTINUATION_ATTRIBUTE)
+                        .and_then(|c| serde_json::from_value::<Continuation>(c.clone()).ok())
+                    {
+                        println!(
+                            "{}├─ Continues from: {} ({} samples before this part)",
+                            indent, continuation.store, continuation.samples_before
+                        );
+                    }
+                    let clock_jumps = read_clock_jumps(&attrs);
+                    if !clock_jumps.is_empty() {
+                        println!("{}├─ Clock jumps: {}", indent, clock_jumps.len());
+                        for jump in &clock_jumps {
+                            println!("{}│    {}", indent, jump);
+                        }
+                    }
+                    if let Some((flagged, counts)) = read_flag_counts(&attrs) {
+                        let counts: Vec<String> = counts.iter().map(|(name, n)| format!("{} {}", name, n)).collect();
+                        if counts.is_empty() {
+                            println!("{}├─ Flagged samples: 0", indent);
+                        } else {
+                            println!("{}├─ Flagged samples: {} ({})", indent, flagged, counts.join(", "));
+                        }
+                    }
+                    if let Some(channels) = attrs
+                        .get(QUALITY_ATTRIBUTE)
+                        .and_then(|q| q.get("channels"))
+                        .and_then(|c| c.as_array())
+                    {
+                        let warned: Vec<&str> = channels
+                            .iter()
+                            .filter(|c| c["warnings"].as_u64().unwrap_or(0) > 0)
+                            .filter_map(|c| c["label"].as_str())
+                            .collect();
+                        if warned.is_empty() {
+                            println!("{}├─ Quality: no warnings on {} channels", indent, channels.len());
+                        } else {
+                            println!("{}├─ Quality: warnings on {}", indent, warned.join(", "));
+                        }
+                        if args.verbose {
+                            for channel in channels {
+                                println!(
+                                    "{}│    {}: RMS {:.3} ({:.3} - {:.3}), flat {:.1} s, clipped {:.2}%",
+                                    indent,
+                                    channel["label"].as_str().unwrap_or("?"),
+                                    channel["rms_mean"].as_f64().unwrap_or(f64::NAN),
+                                    channel["rms_min"].as_f64().unwrap_or(f64::NAN),
+                                    channel["rms_max"].as_f64().unwrap_or(f64::NAN),
+                                    channel["flatline_s"].as_f64().unwrap_or(0.0),
+                                    channel["clipped_fraction"].as_f64().unwrap_or(0.0) * 100.0
+                                );
+                            }
+                        }
+                    }
+                    if let Some(plausibility) = attrs.get(PLAUSIBILITY_ATTRIBUTE) {
+                        println!(
+                            "{}├─ Implausible samples: {} of {} (range {})",
+                            indent,
+                            plausibility["implausible_samples"].as_u64().unwrap_or(0),
+                            plausibility["samples"].as_u64().unwrap_or(0),
+                            plausibility["range"].as_str().unwrap_or("?")
+                        );
+                        let channels = plausibility["channels"].as_array().into_iter().flatten();
+                        for channel in channels.filter(|c| args.verbose && c["outside"].as_u64().unwrap_or(0) > 0) {
+                            println!(
+                                "{}│    {}: {} outside {}..{} (observed {} to {})",
+                                indent,
+                                channel["label"].as_str().unwrap_or("?"),
+                                channel["outside"].as_u64().unwrap_or(0),
+                                channel["min"],
+                                channel["max"],
+                                channel["observed_min"],
+                                channel["observed_max"]
+                            );
+                        }
+                    }
+                    if args.verbose {
+                        for key in ["start_time", "end_time"] {
+                            if let Some(lsl_clock) = attrs.get(key).and_then(|a| a.get("lsl_clock")).and_then(|v| v.as_f64()) {
+                                println!("{}├─ {} LSL clock: {:.6}", indent, key, lsl_clock);
+                            }
+                        }
+                    }
+                }
+
+                // Per-channel value ranges in physical units (reads the full data array)
+                if args.units
+                    && let Ok(attrs) = read_group_attributes(&store, &stream_path)
+                {
+                    let stream_info = StreamInfoAttribute::from_attributes(&attrs).unwrap_or_default();
+                    match Array::<FilesystemStore>::open(store.clone(), &data_array_path) {
+                        Ok(data_array) => print_unit_report(&data_array, &stream_info, &mut cache, &stream_name, indent)?,
+                        Err(e) => println!("{}├─ Units: could not open data array: {}", indent, e),
+                    }
+                }
+
+                // Show attributes from /<stream_name>/zarr.json (stream group attributes)
+                if let Ok(attrs) = read_group_attributes(&store, &stream_path) {
+                    if let Some(stream_info) = StreamInfoAttribute::from_attributes(&attrs) {
+                        // Show key stream info fields
+                        if let Some(ref source_id) = stream_info.source_id {
+                            println!("{}├─ Source ID: {}", indent, source_id);
+                        }
+                        if let Some(nominal_srate) = stream_info.nominal_srate {
+                            println!("{}├─ Nominal rate: {} Hz", indent, nominal_srate);
+                        }
+                        if let Some(ref channel_format) = stream_info.channel_format {
+                            println!("{}├─ Format: {}", indent, channel_format);
+                        }
+
+                        // Show additional fields in verbose mode
+                        if args.verbose {
+                            if let Some(ref hostname) = stream_info.hostname {
+                                println!("{}├─ Hostname: {}", indent, hostname);
+                            }
+                            if let Some(ref stream_type) = stream_info.stream_type {
+                                println!("{}├─ Type: {}", indent, stream_type);
+                            }
+                        }
+                    }
+
+                    if let Some(recorder_config) = RecorderConfig::from_attributes(&attrs) {
+                        // Show recorder version
+                        if let Some(ref recorder_version) = recorder_config.recorder_version {
+                            println!("{}└─ Recorder: v{}", indent, recorder_version);
+                        }
+
+                        // Show additional fields in verbose mode
+                        if args.verbose
+                            && let Some(ref recorded_at) = recorder_config.recorded_at
+                        {
+                            println!("{}   Recorded at: {}", indent, recorded_at);
+                        }
+
+                        if args.config
+                            && let Some(config) = attrs.get("recorder_config")
+                        {
+                            print_recorder_config(config, indent);
+                        }
+                    }
+                }
+                println!();
+            }
+        }
+
+        cache.save_or_warn();
+
+        // Show summary
+        println!("Summary: {} stream{}, {} total samples",
+                 stream_count,
+                 if stream_count == 1 { "" } else { "s" },
+                 total_samples);
+        println!();
+    }
+
+    Ok(())
+}
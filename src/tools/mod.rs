@@ -0,0 +1,45 @@
+//! Entry points of the tools bundled into the `lsl-toolbox` multicall binary
+//!
+//! Each submodule holds one tool's arguments (`Args`) and its `run` function. The
+//! individual executables (`lsl-recorder`, `lsl-sync`, ...) only parse their
+//! arguments and call `run`; `lsl-toolbox` exposes the same tools as subcommands:
+//!
+//! | Subcommand            | Executable           | Module       |
+//! |-----------------------|----------------------|--------------|
+//! | `lsl-toolbox record`  | `lsl-recorder`       | [`record`]   |
+//! | `lsl-toolbox multi`   | `lsl-multi-recorder` | [`multi`]    |
+//! | `lsl-toolbox sync`    | `lsl-sync`           | [`sync`]     |
+//! | `lsl-toolbox inspect` | `lsl-inspect`        | [`inspect`]  |
+//! | `lsl-toolbox validate`| `lsl-validate`       | [`validate`] |
+//! | `lsl-toolbox replay`  | `lsl-replay`         | [`replay`]   |
+//! | `lsl-toolbox dummy`   | `lsl-dummy-stream`   | [`dummy`]    |
+//!
+//! Tools that start other tools (the multi-recorder's per-stream recorders, the
+//! TUI's tabs) run them from their own executable with [`self_command`], so a
+//! deployment needs only one file.
+
+#[cfg(feature = "lsl")]
+pub mod dummy;
+pub mod inspect;
+#[cfg(feature = "lsl")]
+pub mod multi;
+#[cfg(feature = "lsl")]
+pub mod record;
+#[cfg(feature = "lsl")]
+pub mod replay;
+pub mod sync;
+pub mod validate;
+
+use anyhow::{Context, Result};
+use std::process::Command;
+
+/// Subcommand under which `lsl-toolbox` and `lsl-multi-recorder` run a single-stream recorder
+pub const RECORD_SUBCOMMAND: &str = "record";
+
+/// Command running `subcommand` of the current executable, e.g. `lsl-toolbox record`
+pub fn self_command(subcommand: &str) -> Result<Command> {
+    let exe = std::env::current_exe().context("Failed to locate the running executable")?;
+    let mut command = Command::new(exe);
+    command.arg(subcommand);
+    Ok(command)
+}
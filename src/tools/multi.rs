@@ -0,0 +1,1709 @@
+//! LSL Multi-Recorder - Unified controller for recording multiple LSL streams
+//!
+//! This tool provides synchronized control over multiple LSL stream recordings,
+//! broadcasting commands to all recorders and managing their lifecycle.
+//!
+//! # Features
+//!
+//! - Record multiple LSL streams simultaneously
+//! - Synchronized START/STOP/QUIT commands across all recorders; PAUSE/RESUME
+//!   interrupt the run and record the pauses in every stream's metadata
+//! - Single shared Zarr file for all streams
+//! - Millisecond-level synchronization of start/stop events
+//! - Shared metadata (subject, session, notes) across recordings
+//! - Record-all mode that discovers every stream on the network
+//! - Stream groups keyed by LSL UID (`--name-by uid`) for identical devices that
+//!   broadcast the same name and source ID
+//! - File locking prevents race conditions during concurrent writes
+//! - A session lock refuses a second multi-recorder writing into the same store;
+//!   `--join-session` lets multi-recorders on several hosts share one session's store
+//! - Professional tab-delimited output formatting
+//! - Labeled output from each child recorder
+//! - Process lifecycle management and clean shutdown
+//! - Each stream is recorded by a child process of this same executable
+//!   (`<exe> record ...`), so no separate `lsl-recorder` has to be installed
+//! - Ctrl+C/SIGTERM broadcast QUIT so every stream is flushed and finalized
+//! - Optional stop-all policy when a required stream dies (`--stop-all-on-failure`)
+//! - Per-stream memory reports and growth warnings (`--memory-monitor`)
+//! - Periodic stream info snapshots for devices that update their metadata (`--info-refresh`)
+//! - JSON status of all streams for remote monitoring (`--status-port`)
+//! - Scheduled start (`--start-at`) and block-structured recordings (`--blocks`); the
+//!   multi-recorder exits once every recorder has finished its schedule
+//! - With the `async-core` cargo feature, `--async-core` records every stream in this
+//!   process on a tokio runtime instead of one child process per stream, for sessions
+//!   with dozens of streams (see [`crate::async_core`])
+//! - Cross-platform support (Windows/Linux/Mac)
+//!
+//! # Usage
+//!
+//! ```bash
+//! # Record two streams interactively
+//! lsl-multi-recorder \
+//!   --source-ids "EMG_1234" "EEG_5678" \
+//!   --stream-names "EMG" "EEG" \
+//!   --output experiment \
+//!   --subject P001
+//!
+//! # With full metadata
+//! lsl-multi-recorder \
+//!   --source-ids "EMG_1234" "EEG_5678" "Markers_9999" \
+//!   --stream-names "EMG" "EEG" "Events" \
+//!   --output experiment \
+//!   --subject P001 \
+//!   --session-id session_001 \
+//!   --notes "Multi-modal recording session"
+//!
+//! # Record every stream currently visible on the network
+//! lsl-multi-recorder --record-all --output experiment --subject P001
+//!
+//! # One store per session, named by subject and start time
+//! lsl-multi-recorder --record-all --output "{subject}_{date}_{time}" --subject P001
+//!
+//! # Next free subject ID (P001, P002, ...) among the stores in data/
+//! lsl-multi-recorder --record-all --output "data/{subject}/session" --auto-subject
+//!
+//! # Redundant copy of every stream on a second disk
+//! lsl-multi-recorder --record-all --output experiment --mirror /mnt/backup/experiment
+//!
+//! # End the whole session if EMG or EEG is gone for more than 10 s
+//! lsl-multi-recorder --source-ids "EMG_1234" "EEG_5678" "Markers_9999" \
+//!   --output experiment --stop-all-on-failure --required-streams EMG_1234 EEG_5678
+//!
+//! # Acquisition health for the experiment control PC at http://<host>:8080/status
+//! lsl-multi-recorder --record-all --output experiment --status-port 8080
+//!
+//! # Let the stimulus PC start and stop the session over the network
+//! lsl-multi-recorder --record-all --output experiment --control-port 7000
+//!
+//! # Five 2-minute blocks with 1-minute pauses on every stream, from 09:00
+//! lsl-multi-recorder --record-all --output experiment \
+//!   --start-at 09:00 --blocks 5 --block-duration 120 --inter-block 60
+//!
+//! # Two identical headsets: one group per outlet UID
+//! lsl-multi-recorder --record-all --output experiment --name-by uid
+//!
+//! # Forty marker and sensor streams in one process (built with --features async-core)
+//! lsl-multi-recorder --record-all --output experiment --async-core --async-workers 4
+//!
+//! # Custom flush settings
+//! lsl-multi-recorder \
+//!   --source-ids "id1" "id2" \
+//!   --output experiment \
+//!   --flush-interval 2.0
+//! ```
+//!
+//! # Interactive Commands
+//!
+//! After starting, use these commands:
+//! - `START` - Begin recording all streams
+//! - `STOP` - Stop recording all streams
+//! - `STOP_AFTER <seconds>` - Stop all streams after duration
+//! - `PAUSE` / `RESUME` - Pause and resume all streams within the same run
+//! - `QUIT` - Terminate all recorders
+//! - `METADATA {"notes": "..."}` - Update subject, session ID or notes in the session manifest
+//! - `MARK <label>` - Annotate the current moment in the store's `annotations` (written
+//!   once for the session, not forwarded to the recorders)
+//!
+//! With `--control-port`, the same commands are accepted over TCP or WebSocket, as
+//! text or as JSON (`{"command": "stop_after", "seconds": 60}`); see the `commands`
+//! module for the protocol.
+//!
+//! Once the output path is resolved (placeholders expanded, subject assigned), a local
+//! store is announced as `STATUS STORE <path>`.
+//!
+//! # Failure Policy
+//!
+//! Each recorder reports `STATUS STREAM_LOST` when its regular stream stops
+//! delivering samples and `STATUS STREAM_RECOVERED` once the inlet has reconnected.
+//! With `--stop-all-on-failure`, a required stream that stays lost for longer than
+//! `--failure-grace` seconds, or whose recorder exits, stops every recorder cleanly.
+//! The store's root group is then marked with `session_status.complete = false` and
+//! the multi-recorder exits with an error.
+//!
+//! # Output Format
+//!
+//! All streams write to a single shared Zarr file:
+//! ```text
+//! experiment.zarr/
+//! ├── EMG/
+//! │   ├── data
+//! │   ├── time
+//! │   └── zarr.json (stream metadata)
+//! ├── EEG/
+//! │   ├── data
+//! │   ├── time
+//! │   └── zarr.json (stream metadata)
+//! ├── Events/
+//! │   ├── events
+//! │   ├── time
+//! │   └── zarr.json (stream metadata)
+//! └── zarr.json (root metadata)
+//! ```
+
+use anyhow::{Context, Result};
+use chrono::Local;
+use clap::Parser;
+use std::io::{BufRead, BufReader, Write};
+use std::path::PathBuf;
+use std::process::{Child, Command, Stdio};
+use std::sync::{
+    atomic::{AtomicBool, Ordering},
+    mpsc, Arc, Mutex,
+};
+use std::thread;
+use std::time::Instant;
+
+#[cfg(feature = "async-core")]
+use crate::async_core::{run_session, AsyncSessionOptions, DEFAULT_ASYNC_WORKERS};
+use crate::chunked::DEFAULT_CHUNK_PULL_RATE;
+use crate::commands::{
+    apply_annotation, apply_metadata_update, install_shutdown_handler, serve_control, Command as RecorderCommand,
+};
+use crate::discovery::{discover_streams, DiscoveredStream};
+use crate::permissions::{apply_permissions, check_writable, StorePermissions};
+use crate::plausibility::{parse_stream_range, StreamRange};
+use crate::rate_guard::parse_rate_factor;
+use crate::rotation::{
+    check_no_earlier_parts, current_part, parse_duration, parse_size, part_name, read_part_info, session_parts,
+};
+use crate::rules::{parse_stream_rule, StreamRule};
+use crate::schedule::parse_start_at;
+use crate::session_lock::SessionLock;
+use crate::status::{serve_status, StreamStatus};
+use crate::subject::{auto_subject, DEFAULT_SUBJECT_PATTERN};
+use crate::tools::{self_command, RECORD_SUBCOMMAND};
+use crate::zarr::{
+    check_session_collision, expand_output_template, mark_session_incomplete, Compressor, GroupNaming, StorageOptions,
+};
+use crate::zarr::paths::check_stream_paths;
+use crate::zarr::remote::{is_remote, resolve_output, RemoteUploader};
+
+#[derive(Debug, Clone)]
+enum RecorderEvent {
+    FirstSample { stream_name: String, is_regular: bool },
+    Started,
+    Stopped,
+    ScheduleComplete { stream_name: String },
+    StreamLost { stream_name: String },
+    StreamRecovered { stream_name: String },
+}
+
+#[derive(Parser)]
+#[command(name = "lsl-multi-recorder")]
+#[command(about = "Record multiple LSL streams simultaneously with unified control")]
+pub struct Args {
+    #[arg(
+        long,
+        required_unless_present = "record_all",
+        num_args = 1..,
+        help = "LSL stream source IDs to record (space-separated)"
+    )]
+    source_ids: Vec<String>,
+
+    #[arg(
+        long,
+        conflicts_with_all = ["source_ids", "stream_names"],
+        help = "Record every stream currently visible on the network (names derived from LSL metadata)"
+    )]
+    record_all: bool,
+
+    #[arg(
+        long,
+        short = 'o',
+        help = "Zarr experiment base path (without .zarr extension); supports {date}, {time}, {subject}, {session_id}",
+        default_value = "experiment"
+    )]
+    output: PathBuf,
+
+    #[arg(
+        long,
+        help = "Allow writing into an existing store recorded for a different subject/session"
+    )]
+    append_session: bool,
+
+    #[arg(
+        long,
+        help = "Record into a store another multi-recorder (e.g. on another host) is recording the same --session-id into"
+    )]
+    join_session: bool,
+
+    #[arg(long, help = "Subject identifier for metadata")]
+    subject: Option<String>,
+
+    #[arg(
+        long,
+        conflicts_with = "subject",
+        help = "Assign the next free subject ID (see --subject-pattern) among the stores under the output path"
+    )]
+    auto_subject: bool,
+
+    #[arg(
+        long,
+        default_value = DEFAULT_SUBJECT_PATTERN,
+        help = "Subject ID pattern for --auto-subject, one '#' per digit (e.g. P### gives P001, P002, ...)"
+    )]
+    subject_pattern: String,
+
+    #[arg(long, help = "Session identifier for metadata")]
+    session_id: Option<String>,
+
+    #[arg(long, help = "Notes for metadata")]
+    notes: Option<String>,
+
+    #[arg(
+        long,
+        default_value = "5.0",
+        help = "Timeout for stream resolution in seconds"
+    )]
+    resolve_timeout: f64,
+
+    #[arg(
+        long,
+        default_value = "1.0",
+        help = "Flush data to disk interval in seconds"
+    )]
+    flush_interval: f64,
+
+    #[arg(
+        long,
+        default_value = "50",
+        help = "Buffer size before forcing flush (number of samples)"
+    )]
+    flush_buffer_size: usize,
+
+    #[arg(
+        long,
+        help = "Flush immediately after every sample (maximum safety, lower performance)"
+    )]
+    immediate_flush: bool,
+
+    #[arg(long, short = 'q', help = "Minimal output mode for child recorders")]
+    quiet: bool,
+
+    #[arg(
+        long,
+        num_args = 0..,
+        help = "Custom stream names (must match source-ids count if provided)"
+    )]
+    stream_names: Option<Vec<String>>,
+
+    #[arg(
+        long,
+        value_enum,
+        default_value = "name",
+        help = "Name each Zarr group after its stream name (name) or its LSL UID (uid); names are kept as display_name"
+    )]
+    name_by: GroupNaming,
+
+    #[arg(
+        long,
+        help = "Path to an lsl-recorder executable (default: the recorder built into this executable)"
+    )]
+    recorder_path: Option<PathBuf>,
+
+    #[arg(
+        long,
+        help = "Auto-stop recording after specified duration in seconds (timer starts when all regular streams ready)"
+    )]
+    duration: Option<u64>,
+
+    #[arg(
+        long,
+        value_name = "TIME",
+        help = "Start recording all streams at this local time (HH:MM[:SS], next occurrence, or YYYY-MM-DD HH:MM[:SS])"
+    )]
+    start_at: Option<String>,
+
+    #[arg(
+        long,
+        value_parser = clap::value_parser!(u32).range(1..),
+        requires = "block_duration",
+        help = "Record this many blocks of --block-duration seconds on every stream, then quit"
+    )]
+    blocks: Option<u32>,
+
+    #[arg(long, value_name = "SECONDS", requires = "blocks", help = "Length of each --blocks block in seconds")]
+    block_duration: Option<f64>,
+
+    #[arg(long, value_name = "SECONDS", default_value = "0", help = "Pause between --blocks blocks in seconds")]
+    inter_block: f64,
+
+    #[arg(
+        long,
+        default_value = "100",
+        value_parser = clap::value_parser!(u64).range(1..),
+        help = "Samples per chunk for new arrays"
+    )]
+    chunk_samples: u64,
+
+    #[arg(long, value_enum, default_value = "lz4", help = "Compression codec for new arrays")]
+    compressor: Compressor,
+
+    #[arg(
+        long,
+        default_value = "5",
+        value_parser = clap::value_parser!(u8).range(0..=9),
+        help = "Blosc compression level (0-9)"
+    )]
+    compression_level: u8,
+
+    #[arg(
+        long,
+        value_parser = clap::value_parser!(u64).range(1..),
+        help = "Store samples in shards of this many samples (multiple of --chunk-samples)"
+    )]
+    shard_samples: Option<u64>,
+
+    #[arg(
+        long,
+        value_name = "PATH",
+        help = "Also write every stream to a second store, e.g. on another disk"
+    )]
+    mirror: Option<PathBuf>,
+
+    #[arg(
+        long,
+        value_name = "DIR",
+        help = "Local directory for the files of an s3:// or gs:// --output until they are uploaded"
+    )]
+    spool_dir: Option<PathBuf>,
+
+    #[arg(
+        long,
+        value_name = "MODE",
+        help = "Octal mode for the files of the store, e.g. 660 (passed to every recorder)"
+    )]
+    chmod: Option<String>,
+
+    #[arg(
+        long,
+        value_name = "GROUP",
+        help = "Group owning the store's files and directories (name or GID)"
+    )]
+    chgrp: Option<String>,
+
+    #[arg(
+        long,
+        default_value = "4.0",
+        value_parser = parse_rate_factor,
+        help = "Abort a recorder when its measured rate exceeds this multiple of the nominal rate (0 disables, else at least 1)"
+    )]
+    max_rate_factor: f64,
+
+    #[arg(
+        long,
+        help = "Only warn (instead of aborting) when a stream exceeds --max-rate-factor"
+    )]
+    allow_rate_overrun: bool,
+
+    #[arg(
+        long,
+        help = "Store detected flat, noisy and mostly non-finite channels as bad_channels when recording ends"
+    )]
+    detect_bad_channels: bool,
+
+    #[arg(
+        long,
+        help = "Have every recorder write a per-sample flags array (see lsl-recorder --sample-flags)"
+    )]
+    sample_flags: bool,
+
+    #[arg(
+        long,
+        help = "Have every recorder watch RMS, flatlines and clipping and report STATUS QUALITY_WARNING (see lsl-recorder --quality)"
+    )]
+    quality: bool,
+
+    #[arg(long, default_value = "2.0", help = "Seconds without change reported as a --quality flatline")]
+    flatline_seconds: f64,
+
+    #[arg(long, help = "Absolute value at which --quality counts samples as clipped (default: integer format range)")]
+    clip_level: Option<f64>,
+
+    #[arg(
+        long = "rule",
+        value_name = "RULE",
+        value_parser = parse_stream_rule,
+        help = "Signal rule for every recorder, or one stream with a STREAM: prefix, e.g. 'EEG: rms < 5 for 30s then pause' (see lsl-recorder --rule); repeatable"
+    )]
+    rules: Vec<StreamRule>,
+
+    #[arg(
+        long,
+        value_name = "RANGE",
+        value_parser = parse_stream_range,
+        allow_hyphen_values = true,
+        help = "Plausible value range for every recorder, or one stream with a STREAM: prefix, e.g. 'EMG: ±10 mV' (see lsl-recorder --plausible-range; defaults to plausible_range of [[streams]] in lsl-toolbox.toml); repeatable"
+    )]
+    plausible_range: Vec<StreamRange>,
+
+    #[arg(
+        long,
+        value_name = "STREAM",
+        requires = "capture_on",
+        help = "Marker stream (name or source ID) whose recorder takes the --capture-on captures"
+    )]
+    capture_stream: Option<String>,
+
+    #[arg(
+        long = "capture-on",
+        value_name = "MARKER",
+        requires = "capture_stream",
+        help = "Marker of --capture-stream that stores its time anchors in the annex group (see lsl-recorder --capture-on); repeatable"
+    )]
+    capture_on: Vec<String>,
+
+    #[arg(
+        long,
+        value_name = "COMMAND",
+        requires = "capture_on",
+        help = "Shell command run for every --capture-on marker (see lsl-recorder --capture-command)"
+    )]
+    capture_command: Option<String>,
+
+    #[arg(
+        long,
+        value_name = "DURATION",
+        value_parser = parse_duration,
+        help = "Continue the session in a new store (experiment_part002.zarr, ...) every DURATION, e.g. 30min or 2h"
+    )]
+    rotate_every: Option<std::time::Duration>,
+
+    #[arg(
+        long,
+        value_name = "SIZE",
+        value_parser = parse_size,
+        help = "Continue the session in a new store once the current one has grown past SIZE, e.g. 10GB"
+    )]
+    rotate_size: Option<u64>,
+
+    #[arg(
+        long,
+        help = "Have every recorder report its memory use as STATUS MEMORY lines (see lsl-recorder --memory-monitor)"
+    )]
+    memory_monitor: bool,
+
+    #[arg(long, default_value = "10.0", help = "Seconds between --memory-monitor reports")]
+    memory_interval: f64,
+
+    #[arg(
+        long,
+        default_value = "10.0",
+        value_name = "MB_PER_MIN",
+        help = "RSS growth per recorder that is reported as STATUS MEMORY_GROWTH (0 disables)"
+    )]
+    memory_growth_limit: f64,
+
+    #[arg(
+        long,
+        default_value = "0",
+        value_name = "SECONDS",
+        help = "Have every recorder re-fetch its stream info this often and store changes (see lsl-recorder --info-refresh)"
+    )]
+    info_refresh: f64,
+
+    #[arg(
+        long,
+        value_name = "PORT",
+        help = "Serve the status of all streams as JSON (GET /status) on this port"
+    )]
+    status_port: Option<u16>,
+
+    #[arg(
+        long,
+        default_value = "0",
+        help = "Forward the recorders' STATUS PROGRESS lines every this many seconds (0 disables; used by the TUI)"
+    )]
+    status_interval: f64,
+
+    #[arg(
+        long,
+        value_name = "PORT",
+        help = "Accept START/STOP/STOP_AFTER/PAUSE/RESUME/QUIT/METADATA/MARK commands over TCP or WebSocket on this port"
+    )]
+    control_port: Option<u16>,
+
+    #[arg(
+        long,
+        help = "Republish a decimated copy of every stream as <stream>_monitor (see lsl-recorder --monitor)"
+    )]
+    monitor: bool,
+
+    #[arg(long, default_value = "50.0", help = "Approximate sample rate (Hz) of the --monitor streams")]
+    monitor_rate: f64,
+
+    #[arg(
+        long,
+        help = "Stop all streams and mark the session incomplete when a required stream fails"
+    )]
+    stop_all_on_failure: bool,
+
+    #[arg(
+        long,
+        default_value = "10.0",
+        help = "Seconds a lost stream may take to reconnect before --stop-all-on-failure stops the session"
+    )]
+    failure_grace: f64,
+
+    #[arg(
+        long,
+        default_value = "5.0",
+        value_name = "SECONDS",
+        help = "Have every recorder resolve its stream anew when it stays lost this long (see lsl-recorder --reconnect-after)"
+    )]
+    reconnect_after: f64,
+
+    #[arg(
+        long,
+        default_value_t = DEFAULT_CHUNK_PULL_RATE,
+        value_name = "HZ",
+        help = "Have every recorder pull streams of at least this many Hz in chunks (see lsl-recorder --chunk-pull-rate)"
+    )]
+    chunk_pull_rate: f64,
+
+    #[arg(
+        long,
+        num_args = 1..,
+        help = "Streams (source ID or stream name) covered by --stop-all-on-failure (all streams if omitted)"
+    )]
+    required_streams: Vec<String>,
+
+    #[cfg(feature = "async-core")]
+    #[arg(
+        long,
+        help = "Record all streams in this process on a tokio runtime instead of one lsl-recorder process per stream"
+    )]
+    async_core: bool,
+
+    #[cfg(feature = "async-core")]
+    #[arg(
+        long,
+        default_value_t = DEFAULT_ASYNC_WORKERS,
+        value_name = "THREADS",
+        requires = "async_core",
+        help = "Worker threads of the --async-core runtime, shared by all streams"
+    )]
+    async_workers: usize,
+}
+
+/// Stream to record and how its recorder should resolve it
+struct RecorderTarget {
+    /// Identifier used in log messages
+    label: String,
+    stream_name: String,
+    /// Resolution arguments passed to lsl-recorder (e.g. `--source-id <id>`)
+    resolve_args: Vec<String>,
+}
+
+/// Build recorder targets from explicit --source-ids/--stream-names
+fn targets_from_args(args: &Args) -> Vec<RecorderTarget> {
+    args.source_ids
+        .iter()
+        .enumerate()
+        .map(|(idx, source_id)| RecorderTarget {
+            label: source_id.clone(),
+            stream_name: args
+                .stream_names
+                .as_ref()
+                .map(|names| names[idx].clone())
+                .unwrap_or_else(|| source_id.clone()),
+            resolve_args: vec!["--source-id".to_string(), source_id.clone()],
+        })
+        .collect()
+}
+
+/// Build recorder targets for every stream visible on the network
+fn targets_from_network(streams: &[DiscoveredStream]) -> Vec<RecorderTarget> {
+    let mut used_names: Vec<String> = Vec::new();
+    let mut targets = Vec::new();
+
+    for stream in streams {
+        // Zarr group names must be filesystem-safe
+        let sanitize = |s: &str| -> String {
+            s.chars()
+                .map(|c| if c.is_alphanumeric() || c == '-' || c == '_' { c } else { '_' })
+                .collect()
+        };
+
+        let mut base = sanitize(&stream.name);
+        if base.is_empty() {
+            base = sanitize(&stream.stream_type);
+        }
+        if base.is_empty() {
+            base = "stream".to_string();
+        }
+
+        // Resolve collisions: prefer the source ID as suffix, then a counter
+        let mut stream_name = base.clone();
+        if used_names.contains(&stream_name) && !stream.source_id.is_empty() {
+            stream_name = format!("{}_{}", base, sanitize(&stream.source_id));
+        }
+        let mut counter = 2;
+        while used_names.contains(&stream_name) {
+            stream_name = format!("{}_{}", base, counter);
+            counter += 1;
+        }
+        used_names.push(stream_name.clone());
+
+        // Source IDs are only usable if they identify exactly one stream
+        let source_id_unique = !stream.source_id.is_empty()
+            && streams.iter().filter(|s| s.source_id == stream.source_id).count() == 1;
+        let (label, resolve_args) = if source_id_unique {
+            (
+                stream.source_id.clone(),
+                vec!["--source-id".to_string(), stream.source_id.clone()],
+            )
+        } else {
+            (
+                format!("uid:{}", stream.uid),
+                vec!["--predicate".to_string(), format!("uid='{}'", stream.uid)],
+            )
+        };
+
+        targets.push(RecorderTarget {
+            label,
+            stream_name,
+            resolve_args,
+        });
+    }
+
+    targets
+}
+
+struct RecorderProcess {
+    source_id: String,
+    stream_name: String,
+    child: Child,
+    stdin: std::process::ChildStdin,
+    is_regular: Option<bool>, // None = unknown, Some(true) = regular, Some(false) = irregular
+    first_sample_received: bool,
+    // Whether --stop-all-on-failure applies to this stream
+    required: bool,
+    // Recorder reported the end of its --blocks schedule
+    schedule_complete: bool,
+    // Since when the recorder has reported its stream lost
+    lost_since: Option<Instant>,
+}
+
+/// A required stream that ended the session under --stop-all-on-failure
+struct SessionFailure {
+    stream_name: String,
+    reason: String,
+}
+
+/// First required recorder that has exited or stayed lost for longer than `grace`
+fn find_failure(recorders: &mut [RecorderProcess], grace: std::time::Duration) -> Option<SessionFailure> {
+    for recorder in recorders.iter_mut().filter(|r| r.required) {
+        if let Ok(Some(status)) = recorder.child.try_wait() {
+            return Some(SessionFailure {
+                stream_name: recorder.stream_name.clone(),
+                reason: format!("recorder exited ({})", status),
+            });
+        }
+        if let Some(since) = recorder.lost_since
+            && since.elapsed() >= grace
+        {
+            return Some(SessionFailure {
+                stream_name: recorder.stream_name.clone(),
+                reason: format!("stream lost and not reconnected within {:.1}s", grace.as_secs_f64()),
+            });
+        }
+    }
+    None
+}
+
+fn log_with_time(message: &str, start_time: Instant) {
+    let elapsed = start_time.elapsed();
+    let total_millis = elapsed.as_millis();
+    let seconds = (total_millis / 1000) % 60;
+    let minutes = (total_millis / 60000) % 60;
+    let millis = total_millis % 1000;
+    println!("[+{:02}:{:02}.{:03}] {}", minutes, seconds, millis, message);
+}
+
+fn spawn_output_reader<R: BufRead + Send + 'static>(
+    reader: R,
+    label: String,
+    stream_name: String,
+    start_time: Instant,
+    event_sender: mpsc::Sender<RecorderEvent>,
+    stream_status: Arc<Mutex<Vec<StreamStatus>>>,
+    forward_progress: bool,
+) -> thread::JoinHandle<()> {
+    thread::spawn(move || {
+        for line in reader.lines() {
+            match line {
+                Ok(line) => {
+                    // Progress lines feed the status endpoint; logging them would flood the output
+                    // unless they were asked for (--status-interval)
+                    if let Some(progress) = StreamStatus::parse_progress(&stream_name, &line) {
+                        if let Ok(mut streams) = stream_status.lock()
+                            && let Some(entry) = streams.iter_mut().find(|s| s.stream == stream_name)
+                        {
+                            *entry = progress;
+                        }
+                        if forward_progress {
+                            log_with_time(&format!("[{}] {}", label, line), start_time);
+                        }
+                        continue;
+                    }
+
+                    log_with_time(&format!("[{}] {}", label, line), start_time);
+
+                    // Parse FIRST_SAMPLE messages
+                    if line.contains("STATUS FIRST_SAMPLE") {
+                        let is_regular = line.contains("(regular)");
+                        let _ = event_sender.send(RecorderEvent::FirstSample {
+                            stream_name: stream_name.clone(),
+                            is_regular,
+                        });
+                    }
+
+                    // Parse STOPPED_BY_TIMER messages (and the pauses of a --blocks schedule)
+                    if line.contains("STATUS STOPPED_BY_TIMER") || line.contains("STATUS BLOCK_ENDED") {
+                        let _ = event_sender.send(RecorderEvent::Stopped);
+                    }
+                    if line.contains("STATUS BLOCK_STARTED") {
+                        let _ = event_sender.send(RecorderEvent::Started);
+                    }
+                    if line.contains("STATUS SCHEDULE_COMPLETE") {
+                        let _ = event_sender.send(RecorderEvent::ScheduleComplete {
+                            stream_name: stream_name.clone(),
+                        });
+                    }
+
+                    if line.contains("STATUS STREAM_LOST") {
+                        let _ = event_sender.send(RecorderEvent::StreamLost {
+                            stream_name: stream_name.clone(),
+                        });
+                    }
+                    if line.contains("STATUS STREAM_RECOVERED") {
+                        let _ = event_sender.send(RecorderEvent::StreamRecovered {
+                            stream_name: stream_name.clone(),
+                        });
+                    }
+                }
+                Err(_) => break,
+            }
+        }
+    })
+}
+
+/// Command-line arguments of the recorder of `target`
+fn recorder_arguments(target: &RecorderTarget, args: &Args) -> Vec<String> {
+    let source_id = target.label.as_str();
+    let stream_name = target.stream_name.as_str();
+    let mut cmd_args = vec!["--interactive".to_string()];
+    cmd_args.extend(target.resolve_args.iter().cloned());
+    cmd_args.extend([
+        "--stream-name".to_string(),
+        stream_name.to_string(),
+        "-o".to_string(),
+        args.output.display().to_string(),
+        "--resolve-timeout".to_string(),
+        args.resolve_timeout.to_string(),
+        "--flush-interval".to_string(),
+        args.flush_interval.to_string(),
+        "--flush-buffer-size".to_string(),
+        args.flush_buffer_size.to_string(),
+        "--max-rate-factor".to_string(),
+        args.max_rate_factor.to_string(),
+        "--chunk-samples".to_string(),
+        args.chunk_samples.to_string(),
+        "--compressor".to_string(),
+        args.compressor.to_string(),
+        "--compression-level".to_string(),
+        args.compression_level.to_string(),
+        "--name-by".to_string(),
+        args.name_by.to_string(),
+    ]);
+
+    if let Some(shard_samples) = args.shard_samples {
+        cmd_args.push("--shard-samples".to_string());
+        cmd_args.push(shard_samples.to_string());
+    }
+
+    if let Some(ref spool_dir) = args.spool_dir {
+        cmd_args.push("--spool-dir".to_string());
+        cmd_args.push(spool_dir.display().to_string());
+    }
+
+    if let Some(ref mirror) = args.mirror {
+        cmd_args.push("--mirror".to_string());
+        cmd_args.push(mirror.display().to_string());
+    }
+
+    if let Some(ref chmod) = args.chmod {
+        cmd_args.push("--chmod".to_string());
+        cmd_args.push(chmod.clone());
+    }
+
+    if let Some(ref chgrp) = args.chgrp {
+        cmd_args.push("--chgrp".to_string());
+        cmd_args.push(chgrp.clone());
+    }
+
+    if args.allow_rate_overrun {
+        cmd_args.push("--allow-rate-overrun".to_string());
+    }
+
+    if args.detect_bad_channels {
+        cmd_args.push("--detect-bad-channels".to_string());
+    }
+
+    if args.sample_flags {
+        cmd_args.push("--sample-flags".to_string());
+    }
+
+    if args.quality {
+        cmd_args.push("--quality".to_string());
+        cmd_args.push("--flatline-seconds".to_string());
+        cmd_args.push(args.flatline_seconds.to_string());
+        if let Some(level) = args.clip_level {
+            cmd_args.push("--clip-level".to_string());
+            cmd_args.push(level.to_string());
+        }
+    }
+
+    for rule in args.rules.iter().filter(|rule| rule.applies_to(stream_name, source_id)) {
+        cmd_args.push("--rule".to_string());
+        cmd_args.push(rule.rule.to_string());
+    }
+
+    // A range for this stream takes precedence over one for every stream
+    let ranges = || args.plausible_range.iter().filter(|range| range.applies_to(stream_name, source_id));
+    if let Some(range) = ranges().find(|range| range.stream.is_some()).or_else(|| ranges().next()) {
+        cmd_args.push("--plausible-range".to_string());
+        cmd_args.push(range.range.to_string());
+    }
+
+    if args.capture_stream.as_deref().is_some_and(|stream| stream == stream_name || stream == source_id) {
+        for marker in &args.capture_on {
+            cmd_args.push("--capture-on".to_string());
+            cmd_args.push(marker.clone());
+        }
+        if let Some(ref command) = args.capture_command {
+            cmd_args.push("--capture-command".to_string());
+            cmd_args.push(command.clone());
+        }
+    }
+
+    // Every recorder rotates its stream; parts by duration start at the same moments
+    if let Some(every) = args.rotate_every {
+        cmd_args.push("--rotate-every".to_string());
+        cmd_args.push(format!("{}s", every.as_secs_f64()));
+    }
+    if let Some(size) = args.rotate_size {
+        cmd_args.push("--rotate-size".to_string());
+        cmd_args.push(size.to_string());
+    }
+
+    // Recorders report their figures once per second for the status endpoint
+    if args.status_interval > 0.0 {
+        cmd_args.push("--status-interval".to_string());
+        cmd_args.push(args.status_interval.to_string());
+    } else if args.status_port.is_some() {
+        cmd_args.push("--status-interval".to_string());
+        cmd_args.push("1".to_string());
+    }
+
+    if args.memory_monitor {
+        cmd_args.push("--memory-monitor".to_string());
+        cmd_args.push("--memory-interval".to_string());
+        cmd_args.push(args.memory_interval.to_string());
+        cmd_args.push("--memory-growth-limit".to_string());
+        cmd_args.push(args.memory_growth_limit.to_string());
+    }
+
+    cmd_args.push("--reconnect-after".to_string());
+    cmd_args.push(args.reconnect_after.to_string());
+    cmd_args.push("--chunk-pull-rate".to_string());
+    cmd_args.push(args.chunk_pull_rate.to_string());
+
+    if args.info_refresh > 0.0 {
+        cmd_args.push("--info-refresh".to_string());
+        cmd_args.push(args.info_refresh.to_string());
+    }
+
+    if args.monitor {
+        cmd_args.push("--monitor".to_string());
+        cmd_args.push("--monitor-rate".to_string());
+        cmd_args.push(args.monitor_rate.to_string());
+    }
+
+    if args.append_session {
+        cmd_args.push("--append-session".to_string());
+    }
+
+    if args.immediate_flush {
+        cmd_args.push("--immediate-flush".to_string());
+    }
+
+    if args.quiet {
+        cmd_args.push("--quiet".to_string());
+    }
+
+    if let Some(ref subject) = args.subject {
+        cmd_args.push("--subject".to_string());
+        cmd_args.push(subject.clone());
+    }
+
+    if let Some(ref session_id) = args.session_id {
+        cmd_args.push("--session-id".to_string());
+        cmd_args.push(session_id.clone());
+    }
+
+    if let Some(ref notes) = args.notes {
+        cmd_args.push("--notes".to_string());
+        cmd_args.push(notes.clone());
+    }
+
+    if let Some(duration) = args.duration {
+        cmd_args.push("--duration".to_string());
+        cmd_args.push(duration.to_string());
+    }
+
+    if let Some(ref start_at) = args.start_at {
+        cmd_args.push("--start-at".to_string());
+        cmd_args.push(start_at.clone());
+    }
+
+    if let (Some(blocks), Some(block_duration)) = (args.blocks, args.block_duration) {
+        cmd_args.push("--blocks".to_string());
+        cmd_args.push(blocks.to_string());
+        cmd_args.push("--block-duration".to_string());
+        cmd_args.push(block_duration.to_string());
+        cmd_args.push("--inter-block".to_string());
+        cmd_args.push(args.inter_block.to_string());
+    }
+    cmd_args
+}
+
+fn spawn_recorder(
+    target: &RecorderTarget,
+    args: &Args,
+    mut recorder_command: Command,
+) -> Result<RecorderProcess> {
+    let source_id = target.label.as_str();
+    let stream_name = target.stream_name.as_str();
+    let cmd_args = recorder_arguments(target, args);
+
+    let mut child = recorder_command
+        .args(&cmd_args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .context(format!("Failed to spawn recorder for {}", source_id))?;
+
+    let stdin = child
+        .stdin
+        .take()
+        .context("Failed to get stdin for recorder")?;
+
+    Ok(RecorderProcess {
+        source_id: source_id.to_string(),
+        stream_name: stream_name.to_string(),
+        child,
+        stdin,
+        is_regular: None, // Will be determined from FIRST_SAMPLE message
+        first_sample_received: false,
+        required: args.required_streams.is_empty()
+            || args
+                .required_streams
+                .iter()
+                .any(|s| s == source_id || s == stream_name),
+        schedule_complete: false,
+        lost_since: None,
+    })
+}
+
+/// Command starting one recorder: `--recorder-path` if given, else `<this executable> record`
+fn recorder_command(args: &Args) -> Result<Command> {
+    match args.recorder_path {
+        Some(ref path) => Ok(Command::new(path)),
+        None => self_command(RECORD_SUBCOMMAND),
+    }
+}
+
+/// Options set in `args` that need one lsl-recorder process per stream
+#[cfg(feature = "async-core")]
+fn async_core_conflicts(args: &Args) -> Vec<&'static str> {
+    [
+        ("--rule", !args.rules.is_empty()),
+        ("--plausible-range", !args.plausible_range.is_empty()),
+        ("--capture-on", !args.capture_on.is_empty()),
+        ("--quality", args.quality),
+        ("--sample-flags", args.sample_flags),
+        ("--monitor", args.monitor),
+        ("--rotate-every", args.rotate_every.is_some()),
+        ("--rotate-size", args.rotate_size.is_some()),
+        ("--start-at", args.start_at.is_some()),
+        ("--blocks", args.blocks.is_some()),
+        ("--memory-monitor", args.memory_monitor),
+        ("--info-refresh", args.info_refresh > 0.0),
+        ("--status-port", args.status_port.is_some()),
+        ("--status-interval", args.status_interval > 0.0),
+        ("--stop-all-on-failure", args.stop_all_on_failure),
+        ("--recorder-path", args.recorder_path.is_some()),
+    ]
+    .into_iter()
+    .filter(|(_, set)| *set)
+    .map(|(option, _)| option)
+    .collect()
+}
+
+/// `--async-core`: record every target in this process (see [`crate::async_core`])
+///
+/// Each stream gets the options its lsl-recorder would have been started with.
+#[cfg(feature = "async-core")]
+fn run_async_core(
+    args: &Args,
+    targets: &[RecorderTarget],
+    stores: Vec<PathBuf>,
+    shutdown: Arc<AtomicBool>,
+    start_time: Instant,
+) -> Result<()> {
+    let streams = targets
+        .iter()
+        .map(|target| {
+            let recorder_args = std::iter::once(RECORD_SUBCOMMAND.to_string()).chain(recorder_arguments(target, args));
+            crate::config::parse_args_from::<crate::cli::Args, _, _>("lsl-recorder", recorder_args)
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    log_with_time(
+        &format!(
+            "Recording {} streams in-process ({} worker threads)",
+            streams.len(),
+            args.async_workers.max(1)
+        ),
+        start_time,
+    );
+    log_with_time("Commands: START, STOP, STOP_AFTER <seconds>, PAUSE, RESUME, QUIT, METADATA, MARK", start_time);
+    run_session(
+        streams,
+        AsyncSessionOptions {
+            workers: args.async_workers,
+            control_port: args.control_port,
+            duration: args.duration,
+            stores,
+            shutdown,
+        },
+    )?;
+    log_with_time("All streams finalized", start_time);
+    Ok(())
+}
+
+fn broadcast_command(recorders: &mut [RecorderProcess], command: &str) -> Result<()> {
+    for recorder in recorders.iter_mut() {
+        writeln!(recorder.stdin, "{}", command)
+            .context(format!("Failed to send {} to {}", command, recorder.source_id))?;
+        recorder.stdin.flush().ok();
+    }
+    Ok(())
+}
+
+/// Run `lsl-multi-recorder` with parsed arguments
+pub fn run(mut args: Args) -> Result<()> {
+    let start_time = Instant::now();
+
+    // Assign the subject once for all child recorders, which get it as --subject
+    if args.auto_subject {
+        args.subject = Some(auto_subject(&args.output, &args.subject_pattern)?);
+    }
+
+    // Expand placeholders once so every child recorder writes to the same store
+    args.output = expand_output_template(&args.output, args.subject.as_deref(), args.session_id.as_deref());
+    args.mirror = args
+        .mirror
+        .map(|mirror| expand_output_template(&mirror, args.subject.as_deref(), args.session_id.as_deref()));
+
+    if !args.quiet {
+        crate::display_license_notice("lsl-multi-recorder");
+        if args.auto_subject
+            && let Some(ref subject) = args.subject
+        {
+            println!("Assigned subject: {}", subject);
+        }
+    }
+
+    // Every recorder gets the same absolute start time, even across midnight
+    if let Some(ref start_at) = args.start_at {
+        let at = parse_start_at(start_at, &Local::now())?;
+        args.start_at = Some(at.format("%Y-%m-%d %H:%M:%S").to_string());
+    }
+    if args.block_duration.is_some_and(|d| !d.is_finite() || d <= 0.0) {
+        anyhow::bail!("--block-duration must be positive");
+    }
+    if !args.inter_block.is_finite() || args.inter_block < 0.0 {
+        anyhow::bail!("--inter-block cannot be negative");
+    }
+
+    // Validate stream names if provided
+    if let Some(ref names) = args.stream_names
+        && names.len() != args.source_ids.len()
+    {
+        anyhow::bail!(
+            "Number of stream names ({}) must match number of source IDs ({})",
+            names.len(),
+            args.source_ids.len()
+        );
+    }
+
+    #[cfg(feature = "async-core")]
+    if args.async_core {
+        let unsupported = async_core_conflicts(&args);
+        if !unsupported.is_empty() {
+            anyhow::bail!("--async-core does not support {}", unsupported.join(", "));
+        }
+    }
+
+    // Fail here rather than in every child recorder
+    StorageOptions {
+        chunk_samples: args.chunk_samples,
+        compressor: args.compressor,
+        compression_level: args.compression_level,
+        shard_samples: args.shard_samples,
+    }
+    .validate()?;
+
+    if args.mirror.as_deref().is_some_and(is_remote) {
+        anyhow::bail!("--mirror must be a local path");
+    }
+    // Local store, or the spool of a remote one (shared with the recorders)
+    let (store_path, remote_url) = resolve_output(
+        &PathBuf::from(format!("{}.zarr", args.output.display())),
+        args.spool_dir.as_deref(),
+    );
+
+    if !args.append_session {
+        check_session_collision(&store_path, args.subject.as_deref(), args.session_id.as_deref())?;
+        if let Some(ref mirror) = args.mirror {
+            let mirror_path = PathBuf::from(format!("{}.zarr", mirror.display()));
+            check_session_collision(&mirror_path, args.subject.as_deref(), args.session_id.as_deref())?;
+        }
+        // A joined session may already have moved on to later parts
+        if (args.rotate_every.is_some() || args.rotate_size.is_some()) && !args.join_session {
+            check_no_earlier_parts(&store_path)?;
+            if let Some(ref mirror) = args.mirror {
+                check_no_earlier_parts(&PathBuf::from(format!("{}.zarr", mirror.display())))?;
+            }
+        }
+    }
+
+    // Fail here rather than in every child recorder once the streams are resolved
+    let permissions = StorePermissions::new(args.chmod.as_deref(), args.chgrp.as_deref())?;
+    let mirror_path = args.mirror.as_ref().map(|m| PathBuf::from(format!("{}.zarr", m.display())));
+    for path in std::iter::once(&store_path).chain(mirror_path.as_ref()) {
+        check_writable(path)?;
+    }
+    // Tells the TUI wizard which store to sync and validate afterwards
+    if remote_url.is_none() {
+        println!("STATUS STORE {}", store_path.display());
+    }
+
+    // The init lock only keeps single writes apart; a second session in the store is refused
+    let session_locks = std::iter::once(&store_path)
+        .chain(mirror_path.as_ref())
+        .map(|path| SessionLock::acquire(path, args.session_id.as_deref(), args.join_session))
+        .collect::<Result<Vec<_>>>()?;
+    for lock in &session_locks {
+        for other in lock.others() {
+            log_with_time(&format!("Joined {} in {}", other, lock.store_path().display()), start_time);
+        }
+    }
+
+    let targets = if args.record_all {
+        log_with_time(
+            &format!("Discovering streams on the network ({}s)...", args.resolve_timeout),
+            start_time,
+        );
+        let streams = discover_streams(args.resolve_timeout)?;
+        if streams.is_empty() {
+            anyhow::bail!("--record-all: no LSL streams found on the network");
+        }
+        targets_from_network(&streams)
+    } else {
+        targets_from_args(&args)
+    };
+
+    // A rule for a misspelled stream would silently never apply
+    for rule in &args.rules {
+        if let Some(ref stream) = rule.stream
+            && !targets.iter().any(|target| rule.applies_to(&target.stream_name, &target.label))
+        {
+            anyhow::bail!("--rule '{}: {}' names no recorded stream", stream, rule.rule);
+        }
+    }
+    for range in &args.plausible_range {
+        if range.stream.is_some() && !targets.iter().any(|target| range.applies_to(&target.stream_name, &target.label))
+        {
+            anyhow::bail!("--plausible-range '{}' names no recorded stream", range);
+        }
+    }
+    if let Some(ref stream) = args.capture_stream
+        && !targets.iter().any(|target| *stream == target.stream_name || *stream == target.label)
+    {
+        anyhow::bail!("--capture-stream '{}' names no recorded stream", stream);
+    }
+
+    log_with_time(
+        &format!(
+            "LSL Multi-Recorder - Managing {} streams",
+            targets.len()
+        ),
+        start_time,
+    );
+
+    // Stream names are known now; fail before any recorder writes a chunk
+    for target in &targets {
+        for path in std::iter::once(&store_path).chain(mirror_path.as_ref()) {
+            if let Some(warning) = check_stream_paths(path, &target.stream_name)? {
+                log_with_time(&format!("Warning: {}", warning), start_time);
+            }
+        }
+    }
+
+    // Ctrl+C / SIGTERM broadcast QUIT so every recorder flushes and finalizes its stream
+    let shutdown = Arc::new(AtomicBool::new(false));
+    install_shutdown_handler(shutdown.clone())?;
+
+    #[cfg(feature = "async-core")]
+    if args.async_core {
+        let stores = std::iter::once(&store_path).chain(mirror_path.as_ref()).cloned().collect();
+        return run_async_core(&args, &targets, stores, shutdown, start_time);
+    }
+
+    // One status document for all streams, filled from the recorders' STATUS PROGRESS lines
+    let stream_status = Arc::new(Mutex::new(
+        targets
+            .iter()
+            .map(|target| StreamStatus::new(&target.stream_name))
+            .collect::<Vec<_>>(),
+    ));
+    if let Some(port) = args.status_port {
+        let shared = stream_status.clone();
+        let address = serve_status(port, "lsl-multi-recorder", move || {
+            shared.lock().map(|streams| streams.clone()).unwrap_or_default()
+        })?;
+        log_with_time(&format!("Status endpoint: http://{}/status", address), start_time);
+    }
+
+    log_with_time("Spawning recorder processes...", start_time);
+
+    // Create channel for receiving events from recorder output threads
+    let (event_sender, event_receiver) = mpsc::channel();
+
+    let mut recorders: Vec<RecorderProcess> = Vec::new();
+    let mut output_threads: Vec<thread::JoinHandle<()>> = Vec::new();
+
+    for target in &targets {
+        let stream_name = target.stream_name.clone();
+
+        log_with_time(
+            &format!(
+                "\tSpawning recorder for {} (stream_name='{}')",
+                target.resolve_args.join("="),
+                stream_name
+            ),
+            start_time,
+        );
+
+        let mut recorder = spawn_recorder(target, &args, recorder_command(&args)?)?;
+
+        // Spawn output readers for this recorder
+        let stdout = recorder
+            .child
+            .stdout
+            .take()
+            .context("Failed to get stdout")?;
+        let stderr = recorder
+            .child
+            .stderr
+            .take()
+            .context("Failed to get stderr")?;
+
+        let label_out = format!("{}-OUT", stream_name);
+        let label_err = format!("{}-ERR", stream_name);
+
+        output_threads.push(spawn_output_reader(
+            BufReader::new(stdout),
+            label_out.clone(),
+            stream_name.clone(),
+            start_time,
+            event_sender.clone(),
+            stream_status.clone(),
+            args.status_interval > 0.0,
+        ));
+        output_threads.push(spawn_output_reader(
+            BufReader::new(stderr),
+            label_err.clone(),
+            stream_name.clone(),
+            start_time,
+            event_sender.clone(),
+            stream_status.clone(),
+            args.status_interval > 0.0,
+        ));
+
+        recorders.push(recorder);
+    }
+
+    log_with_time(
+        &format!("All {} recorders spawned successfully", recorders.len()),
+        start_time,
+    );
+    println!();
+    log_with_time("Interactive mode active. Available commands:", start_time);
+    log_with_time("\tSTART - Begin recording on all streams", start_time);
+    log_with_time("\tSTOP - Stop recording on all streams", start_time);
+    log_with_time(
+        "\tSTOP_AFTER <seconds> - Stop all after duration",
+        start_time,
+    );
+    log_with_time("\tPAUSE / RESUME - Pause and resume all streams (same run)", start_time);
+    log_with_time("\tQUIT - Terminate all recorders and exit", start_time);
+    log_with_time("\tMETADATA <json> - Update subject/session_id/notes in the manifest", start_time);
+    log_with_time("\tMARK <label> - Annotate the current moment in the session", start_time);
+    if let Some(duration) = args.duration {
+        log_with_time(
+            &format!("\tAuto-stop enabled: {}s after all regular streams ready", duration),
+            start_time,
+        );
+    }
+    println!();
+
+    // Spawn thread to read stdin commands
+    let (cmd_sender, cmd_receiver) = mpsc::channel();
+
+    // Remote commands join the stdin queue; metadata updates and annotations are written right away
+    let stores: Vec<PathBuf> = std::iter::once(&store_path).chain(mirror_path.as_ref()).cloned().collect();
+    if let Some(port) = args.control_port {
+        let remote_sender = cmd_sender.clone();
+        let remote_stores = stores.clone();
+        let address = serve_control(port, move |command| match command {
+            RecorderCommand::Metadata(update) => apply_metadata_update(&remote_stores, update),
+            RecorderCommand::Mark(label) => apply_annotation(&remote_stores, label, lsl::local_clock()).map(|_| ()),
+            _ => remote_sender
+                .send(command.to_line())
+                .map_err(|_| anyhow::anyhow!("the session is shutting down")),
+        })?;
+        log_with_time(&format!("Control server: {}", address), start_time);
+    }
+
+    thread::spawn(move || {
+        let stdin = std::io::stdin();
+        for line in stdin.lock().lines().map_while(Result::ok) {
+            if cmd_sender.send(line).is_err() {
+                break; // Main thread closed
+            }
+        }
+    });
+
+    if args.stop_all_on_failure {
+        let required: Vec<&str> = recorders
+            .iter()
+            .filter(|r| r.required)
+            .map(|r| r.stream_name.as_str())
+            .collect();
+        log_with_time(
+            &format!(
+                "Failure policy: stop all if {} fails for more than {:.1}s",
+                required.join(", "),
+                args.failure_grace
+            ),
+            start_time,
+        );
+    }
+    let failure_grace = std::time::Duration::from_secs_f64(args.failure_grace.max(0.0));
+
+    // Main event loop: handle both commands and recorder events
+    let mut stop_after_pending = args.duration;
+    let mut recording_started = false;
+    // Streams are being recorded (between START and STOP or the auto-stop timer)
+    let mut recording_active = false;
+    // Between PAUSE and RESUME (recording_active is cleared meanwhile)
+    let mut paused = false;
+    let mut session_failure: Option<SessionFailure> = None;
+
+    loop {
+        if shutdown.load(Ordering::SeqCst) {
+            log_with_time("Shutdown signal received, broadcasting QUIT to all recorders...", start_time);
+            // Recorders in the same process group may already be exiting on the same
+            // signal, so a closed stdin is expected and must not skip the others
+            for recorder in recorders.iter_mut() {
+                if writeln!(recorder.stdin, "QUIT").and_then(|_| recorder.stdin.flush()).is_err() {
+                    log_with_time(
+                        &format!("\tRecorder '{}' already closed its input", recorder.stream_name),
+                        start_time,
+                    );
+                }
+            }
+            break;
+        }
+
+        // Process recorder events
+        while let Ok(event) = event_receiver.try_recv() {
+            match event {
+                RecorderEvent::FirstSample { stream_name, is_regular } => {
+                    // Update recorder state
+                    if let Some(recorder) = recorders.iter_mut().find(|r| r.stream_name == stream_name) {
+                        recorder.is_regular = Some(is_regular);
+                        recorder.first_sample_received = true;
+                    }
+
+                    // Check if all regular streams are ready
+                    if stop_after_pending.is_some() && recording_started {
+                        let all_regular_ready = recorders.iter()
+                            .filter(|r| r.is_regular == Some(true))
+                            .all(|r| r.first_sample_received);
+
+                        if all_regular_ready {
+                            let duration = stop_after_pending.unwrap();
+                            log_with_time(
+                                &format!("All regular streams ready! Sending STOP_AFTER {}", duration),
+                                start_time,
+                            );
+                            broadcast_command(&mut recorders, &format!("STOP_AFTER {}", duration))?;
+                            stop_after_pending = None; // Only send once
+                        }
+                    }
+                }
+                RecorderEvent::Started => recording_active = true,
+                RecorderEvent::Stopped => {
+                    // Stream auto-stopped; silence from now on is expected
+                    recording_active = false;
+                    paused = false;
+                    for recorder in recorders.iter_mut() {
+                        recorder.lost_since = None;
+                    }
+                }
+                RecorderEvent::ScheduleComplete { stream_name } => {
+                    if let Some(recorder) = recorders.iter_mut().find(|r| r.stream_name == stream_name) {
+                        recorder.schedule_complete = true;
+                    }
+                }
+                RecorderEvent::StreamLost { stream_name } => {
+                    if let Some(recorder) = recorders.iter_mut().find(|r| r.stream_name == stream_name) {
+                        recorder.lost_since.get_or_insert_with(Instant::now);
+                    }
+                }
+                RecorderEvent::StreamRecovered { stream_name } => {
+                    if let Some(recorder) = recorders.iter_mut().find(|r| r.stream_name == stream_name) {
+                        recorder.lost_since = None;
+                    }
+                }
+            }
+        }
+
+        // Recorders quit on their own after the last block
+        if args.blocks.is_some() && recorders.iter().all(|r| r.schedule_complete) {
+            log_with_time("All recorders finished their block schedule", start_time);
+            break;
+        }
+
+        if args.stop_all_on_failure
+            && recording_active
+            && let Some(failure) = find_failure(&mut recorders, failure_grace)
+        {
+            log_with_time(
+                &format!(
+                    "Required stream '{}' failed ({}), stopping all recorders...",
+                    failure.stream_name, failure.reason
+                ),
+                start_time,
+            );
+            // The failed recorder may already be gone; keep going for the others
+            for recorder in recorders.iter_mut() {
+                let stopped = writeln!(recorder.stdin, "STOP")
+                    .and_then(|_| writeln!(recorder.stdin, "QUIT"))
+                    .and_then(|_| recorder.stdin.flush());
+                if stopped.is_err() {
+                    log_with_time(
+                        &format!("\tRecorder '{}' already closed its input", recorder.stream_name),
+                        start_time,
+                    );
+                }
+            }
+            session_failure = Some(failure);
+            break;
+        }
+
+        // Process stdin and control-port commands (non-blocking)
+        if let Ok(line) = cmd_receiver.try_recv()
+            && !line.trim().is_empty()
+        {
+            match RecorderCommand::parse(&line) {
+                Ok(RecorderCommand::Start) => {
+                    log_with_time("Broadcasting START to all recorders...", start_time);
+                    broadcast_command(&mut recorders, "START")?;
+                    log_with_time("\tSTART command sent to all streams", start_time);
+                    recording_started = true;
+                    recording_active = true;
+                    paused = false;
+
+                    // If duration is set and there are NO regular streams (all irregular),
+                    // send STOP_AFTER immediately
+                    if let Some(duration) = stop_after_pending {
+                        let has_regular_streams = recorders.iter()
+                            .any(|r| r.is_regular == Some(true));
+
+                        if !has_regular_streams {
+                            // Wait a bit for stream types to be detected
+                            thread::sleep(std::time::Duration::from_millis(500));
+
+                            // Re-check after delay
+                            let still_no_regular = recorders.iter()
+                                .all(|r| r.is_regular != Some(true));
+
+                            if still_no_regular {
+                                log_with_time(
+                                    &format!("No regular streams detected, sending STOP_AFTER {} immediately", duration),
+                                    start_time,
+                                );
+                                broadcast_command(&mut recorders, &format!("STOP_AFTER {}", duration))?;
+                                stop_after_pending = None;
+                            }
+                        }
+                    }
+                }
+                Ok(RecorderCommand::Stop) => {
+                    log_with_time("Broadcasting STOP to all recorders...", start_time);
+                    broadcast_command(&mut recorders, "STOP")?;
+                    log_with_time("\tSTOP command sent to all streams", start_time);
+                    recording_active = false;
+                    paused = false;
+                    for recorder in recorders.iter_mut() {
+                        recorder.lost_since = None;
+                    }
+                }
+                Ok(RecorderCommand::Pause) if !recording_active => {
+                    log_with_time("ERROR: PAUSE needs a running recording", start_time);
+                }
+                Ok(RecorderCommand::Pause) => {
+                    log_with_time("Broadcasting PAUSE to all recorders...", start_time);
+                    broadcast_command(&mut recorders, "PAUSE")?;
+                    log_with_time("\tPAUSE command sent to all streams", start_time);
+                    recording_active = false;
+                    paused = true;
+                    for recorder in recorders.iter_mut() {
+                        recorder.lost_since = None;
+                    }
+                }
+                Ok(RecorderCommand::Resume) if !paused => {
+                    log_with_time("ERROR: RESUME needs a paused recording", start_time);
+                }
+                Ok(RecorderCommand::Resume) => {
+                    log_with_time("Broadcasting RESUME to all recorders...", start_time);
+                    broadcast_command(&mut recorders, "RESUME")?;
+                    log_with_time("\tRESUME command sent to all streams", start_time);
+                    recording_active = true;
+                    paused = false;
+                }
+                Ok(RecorderCommand::StopAfter(secs)) => {
+                    log_with_time(
+                        &format!("Will stop all recorders after {} seconds (when regular streams ready)", secs),
+                        start_time,
+                    );
+                    stop_after_pending = Some(secs);
+                }
+                Ok(RecorderCommand::Metadata(update)) => match apply_metadata_update(&stores, &update) {
+                    Ok(()) => log_with_time("Session manifest updated", start_time),
+                    Err(e) => log_with_time(&format!("ERROR: {:#}", e), start_time),
+                },
+                Ok(RecorderCommand::Mark(label)) => match apply_annotation(&stores, &label, lsl::local_clock()) {
+                    Ok(annotation) => log_with_time(
+                        &format!("Annotation '{}' at {:.6}", annotation.label, annotation.time),
+                        start_time,
+                    ),
+                    Err(e) => log_with_time(&format!("ERROR: {:#}", e), start_time),
+                },
+                Ok(RecorderCommand::Quit) => {
+                    log_with_time("Broadcasting QUIT to all recorders...", start_time);
+                    broadcast_command(&mut recorders, "QUIT")?;
+                    log_with_time("\tQUIT command sent to all streams", start_time);
+                    break;
+                }
+                Err(e) => log_with_time(&format!("ERROR: {:#}", e), start_time),
+            }
+        }
+
+        // Small sleep to avoid busy loop
+        thread::sleep(std::time::Duration::from_millis(10));
+    }
+
+    // Wait for all recorder processes to finish
+    log_with_time("Waiting for all recorders to finish...", start_time);
+    for recorder in &mut recorders {
+        let status = recorder.child.wait().context(format!(
+            "Failed to wait for recorder {}",
+            recorder.source_id
+        ))?;
+        log_with_time(
+            &format!(
+                "\tRecorder '{}' finished (status: {})",
+                recorder.stream_name, status
+            ),
+            start_time,
+        );
+    }
+
+    // A rotated session failed in its last part
+    if let Some(ref failure) = session_failure {
+        for path in std::iter::once(&store_path).chain(mirror_path.as_ref()) {
+            let path = &current_part(path);
+            if let Err(e) = mark_session_incomplete(path, &failure.stream_name, &failure.reason) {
+                log_with_time(
+                    &format!("Warning: could not mark {} incomplete: {:#}", path.display(), e),
+                    start_time,
+                );
+            }
+        }
+    }
+
+    // The recorders set --chmod/--chgrp on their streams; this covers the root and meta metadata
+    for path in std::iter::once(&store_path).chain(mirror_path.as_ref()).flat_map(|p| session_parts(p)) {
+        if let Err(e) = apply_permissions(&path, &permissions) {
+            log_with_time(&format!("Warning: could not set permissions on {}: {:#}", path.display(), e), start_time);
+        }
+    }
+
+    if let Some(failure) = session_failure {
+        // The recorders have uploaded their streams; the root attribute goes up here
+        if let Some(ref url) = remote_url {
+            let last_part = current_part(&store_path);
+            let url = part_name(url, read_part_info(&last_part).map_or(1, |info| info.part));
+            if let Err(e) = RemoteUploader::start(&url, &last_part, None).and_then(|u| u.sync()) {
+                log_with_time(&format!("Warning: could not mark {} incomplete: {:#}", url, e), start_time);
+            }
+        }
+        println!("STATUS SESSION_INCOMPLETE");
+        anyhow::bail!(
+            "Session stopped early: stream '{}' {}",
+            failure.stream_name,
+            failure.reason
+        );
+    }
+
+    log_with_time("All recordings completed successfully", start_time);
+    println!();
+
+    // All streams are now saved to a single Zarr file, or one per part of a rotated session
+    let parts = session_parts(&store_path);
+    if parts.len() > 1 {
+        log_with_time(&format!("Generated {} Zarr stores (session parts):", parts.len()), start_time);
+        for part in &parts {
+            log_with_time(&format!("\t{}", part.display()), start_time);
+        }
+    } else {
+        let zarr_filename = remote_url.unwrap_or_else(|| store_path.display().to_string());
+        log_with_time(&format!("Generated Zarr store: {}", zarr_filename), start_time);
+    }
+    log_with_time("Recorded streams:", start_time);
+
+    for recorder in &recorders {
+        log_with_time(&format!("\t/{}/", recorder.stream_name), start_time);
+    }
+
+    Ok(())
+}
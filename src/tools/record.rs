@@ -0,0 +1,527 @@
+//! LSL Recorder - Single-stream Lab Streaming Layer recorder to Zarr format
+//!
+//! This tool records a single LSL stream to disk in Zarr format with support for
+//! interactive control and metadata annotation.
+//!
+//! # Features
+//!
+//! - Records LSL streams to Zarr hierarchical format
+//! - Interactive mode with START/STOP/QUIT commands; PAUSE/RESUME interrupt a run and
+//!   store the paused intervals in the stream metadata; `MARK <label>` annotates the
+//!   current moment in the store's `annotations`
+//! - Direct mode with auto-start recording
+//! - Configurable flush intervals and buffer sizes
+//! - Configurable chunk size and compression (Blosc LZ4, Blosc Zstd, none)
+//! - Memory monitoring and adaptive buffer sizing; `--memory-monitor` reports RSS,
+//!   virtual memory and buffer bytes as `STATUS MEMORY` lines and warns on growth
+//! - Sample rate guard against streams far exceeding their nominal rate
+//! - Graceful Ctrl+C/SIGTERM handling (final flush and metadata finalization)
+//! - Subject, session, and notes metadata support
+//! - `{date}`/`{time}`/`{subject}`/`{session_id}` placeholders in the output path
+//! - Refuses to write into a store from a different subject/session (`--append-session` overrides)
+//! - `--auto-subject` assigns the next free subject ID (`--subject-pattern`, default `P###`)
+//! - Checks that the store can be written before resolving the stream; `--chmod`/`--chgrp`
+//!   set the mode and group of the store's files for shared workstations
+//! - `--status-port` serves samples, rate, buffer fill, last timestamp, dropped-sample
+//!   estimate and recording state as JSON (`GET /status`) for remote monitoring
+//! - `--control-port` accepts the stdin commands (and `METADATA` updates) over TCP or
+//!   WebSocket as text or JSON, e.g. from the stimulus PC
+//! - Redundant recording to a second store with `--mirror`; either store may fail
+//!   without stopping the recording, and both are compared when recording ends
+//! - `--start-at` starts recording at a given local time; `--blocks` with
+//!   `--block-duration`/`--inter-block` records repeated blocks and stores their
+//!   boundaries in the stream's `blocks` attribute
+//! - `--preflight` checks the setup before a session instead of recording: the stream
+//!   resolves and delivers close to its nominal rate, the store is writable and the
+//!   disk holds the session (estimated bytes per hour); GO or NO-GO (exit code 1)
+//!
+//! # Usage
+//!
+//! ```bash
+//! # Interactive mode (default)
+//! lsl-recorder --source-id "EMG_1234" --output experiment --subject P001
+//! # Then use commands: START, STOP, STOP_AFTER <seconds>, PAUSE, RESUME, MARK <label>, QUIT
+//!
+//! # Green light before the subject arrives: resolve, pull 5 s, check the output
+//! lsl-recorder --source-id "EMG_1234" --output experiment --duration 3600 --preflight
+//!
+//! # Direct mode with auto-start
+//! lsl-recorder --source-id "EMG_1234" --output experiment --auto-start
+//!
+//! # Devices without a stable source ID: resolve by name, type or predicate
+//! lsl-recorder --by-name "EEG" --output experiment
+//! lsl-recorder --by-type "Markers" --output experiment
+//! lsl-recorder --predicate "name='EEG' and hostname='lab-pc'" --stream-name EEG --output experiment
+//!
+//! # Date/time placeholders in the output path (experiment_2025-01-31_143005.zarr)
+//! lsl-recorder --source-id "EMG_1234" --output "experiment_{date}_{time}" --subject P001
+//!
+//! # Next free subject ID among the stores in data/ (data/P004_2025-01-31.zarr)
+//! lsl-recorder --source-id "EMG_1234" --output "data/{subject}_{date}" --auto-subject
+//!
+//! # With full metadata
+//! lsl-recorder --source-id "EEG_5678" \
+//!   --stream-name "EEG" \
+//!   --output experiment \
+//!   --subject P001 \
+//!   --session-id session_001 \
+//!   --notes "Baseline recording"
+//!
+//! # Fewer, larger chunks for a 2 kHz stream, zstd for archival
+//! lsl-recorder --source-id "EMG_1234" --output experiment \
+//!   --chunk-samples 2000 --compressor zstd --compression-level 7
+//!
+//! # 10 kHz, 256 channels: 1000-sample chunks packed into 60 s shard files
+//! lsl-recorder --source-id "HDEMG_1" --output experiment \
+//!   --chunk-samples 1000 --shard-samples 600000
+//!
+//! # Write a second copy to another disk
+//! lsl-recorder --source-id "EEG_5678" --output experiment --mirror /mnt/backup/experiment
+//!
+//! # Store readable and writable by the lab group on a shared workstation
+//! lsl-recorder --source-id "EEG_5678" --output /data/experiment --chmod 660 --chgrp lab
+//!
+//! # Mirror ~50 Hz of the signal to a browser dashboard over WebSocket
+//! lsl-recorder --source-id "EMG_1234" --output experiment --tap ws://0.0.0.0:9001
+//!
+//! # Report memory use every 30 s, warn if RSS grows by more than 5 MB/min
+//! lsl-recorder --source-id "EEG_5678" --output experiment \
+//!   --memory-monitor --memory-interval 30 --memory-growth-limit 5
+//!
+//! # Let the experiment control PC poll http://<recorder-host>:8080/status
+//! lsl-recorder --source-id "EMG_1234" --output experiment --status-port 8080
+//!
+//! # Start and stop from the stimulus PC: echo '{"command": "start"}' | nc <recorder-host> 7000
+//! lsl-recorder --source-id "EMG_1234" --output experiment --interactive --control-port 7000
+//!
+//! # Ten 60 s blocks with 30 s pauses, the first one at 14:30
+//! lsl-recorder --source-id "EEG_5678" --output experiment \
+//!   --start-at 14:30 --blocks 10 --block-duration 60 --inter-block 30
+//!
+//! # Configure flushing behavior
+//! lsl-recorder --source-id "1234" --output experiment \
+//!   --flush-interval 2.0 \
+//!   --flush-buffer-size 100
+//! ```
+//!
+//! # Output Format
+//!
+//! Creates Zarr file structure:
+//! ```text
+//! experiment.zarr/
+//! ├── <stream_name>/
+//! │   ├── data        [N × C] float32
+//! │   ├── time        [N] float64
+//! │   └── zarr.json   (metadata)
+//! └── zarr.json       (root metadata)
+//! ```
+//!
+//! # Interactive Commands
+//!
+//! - `START` - Begin recording
+//! - `STOP` - Stop recording
+//! - `STOP_AFTER <seconds>` - Stop after specified duration
+//! - `PAUSE` - Stop storing samples until `RESUME`; the pause is added to the `pauses`
+//!   attribute as `[pause_start, pause_end]` (LSL clock) so the run stays one run
+//! - `RESUME` - Continue a paused recording
+//! - `MARK <label>` - Append the label and the current LSL clock time to the
+//!   `annotations` of the `meta` group (e.g. `MARK electrode adjusted`)
+//! - `QUIT` - Exit the program
+//!
+//! Ctrl+C or SIGTERM behave like `QUIT`: buffered samples are flushed and the
+//! stream metadata is finalized. A second Ctrl+C exits immediately.
+
+use anyhow::Result;
+use chrono::{DateTime, Local};
+use std::io::Write;
+use std::path::Path;
+use std::sync::{
+    atomic::{AtomicBool, Ordering},
+    mpsc, Arc,
+};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::cli::Args;
+use crate::commands::{handle_commands, install_shutdown_handler, serve_control, Command, RecorderControls};
+use crate::lsl::{preflight_stream, record_lsl_stream, RecordingParams, ZarrConfig};
+use crate::permissions::check_writable;
+use crate::preflight::{PreflightCriteria, PreflightReport};
+use crate::rotation::check_no_earlier_parts;
+use crate::schedule::{parse_start_at, wait_until, BlockEvent, BlockSchedule};
+use crate::subject::auto_subject;
+use crate::zarr::check_session_collision;
+use crate::zarr::paths::check_stream_paths;
+use crate::zarr::remote::{is_remote, resolve_output};
+
+/// Run `lsl-recorder` with parsed arguments
+pub fn run(mut args: Args) -> Result<()> {
+
+    if !args.quiet {
+        crate::display_license_notice("lsl-recorder");
+        tracing_subscriber::fmt::init();
+    }
+
+    // Assign the subject before the output path is expanded
+    if args.auto_subject {
+        let subject = auto_subject(&args.output, &args.subject_pattern)?;
+        if !args.quiet {
+            println!("Assigned subject: {}", subject);
+        }
+        args.subject = Some(subject);
+    }
+
+    // A scheduled recording waits for its start time or first block
+    let start_at = args
+        .start_at
+        .as_deref()
+        .map(|value| parse_start_at(value, &Local::now()))
+        .transpose()?;
+    let schedule = args.block_schedule()?;
+    let scheduled = start_at.is_some() || schedule.is_some();
+
+    // Determine auto-start behavior
+    let auto_start = !scheduled && args.auto_start.unwrap_or(!args.interactive);
+
+    // Reject an invalid chunk/shard layout before resolving the stream
+    args.storage_options().validate()?;
+
+    let recording = Arc::new(AtomicBool::new(auto_start));
+    let quit = Arc::new(AtomicBool::new(false));
+    let paused = Arc::new(AtomicBool::new(false));
+    let first_sample_pulled = Arc::new(AtomicBool::new(false));
+    let is_irregular_stream = Arc::new(AtomicBool::new(false));
+
+    // Ctrl+C / SIGTERM stop the recording loop, which flushes and finalizes before exiting
+    install_shutdown_handler(quit.clone())?;
+
+    // Prepare Zarr configuration
+    let zarr_tuple = args.zarr_config();
+    let (store_path, remote_url) = resolve_output(&zarr_tuple.0, args.spool_dir.as_deref());
+    if args.mirror.as_deref().is_some_and(is_remote) {
+        anyhow::bail!("--mirror must be a local path");
+    }
+    let zarr_config = Some(ZarrConfig {
+        store_path,
+        stream_name: zarr_tuple.1,
+        subject: zarr_tuple.2,
+        session_id: zarr_tuple.3,
+        notes: zarr_tuple.4,
+        storage: args.storage_options(),
+        mirror_path: args.mirror_path(),
+        remote_url,
+        permissions: args.store_permissions()?,
+    });
+
+    if args.preflight
+        && let Some(ref config) = zarr_config
+    {
+        return preflight(&args, config);
+    }
+
+    // Refuse to mix subjects/sessions in one store unless explicitly requested
+    if !args.append_session
+        && let Some(ref config) = zarr_config
+    {
+        check_session_collision(&config.store_path, config.subject.as_deref(), config.session_id.as_deref())?;
+        if let Some(ref mirror_path) = config.mirror_path {
+            check_session_collision(mirror_path, config.subject.as_deref(), config.session_id.as_deref())?;
+        }
+        if args.rotation_options().is_enabled() {
+            for path in std::iter::once(&config.store_path).chain(config.mirror_path.as_ref()) {
+                check_no_earlier_parts(path)?;
+            }
+        }
+    }
+
+    // Fail before resolving the stream if the store cannot be written
+    if let Some(ref config) = zarr_config {
+        for path in std::iter::once(&config.store_path).chain(config.mirror_path.as_ref()) {
+            check_writable(path)?;
+            if let Some(warning) = check_stream_paths(path, &config.stream_name)? {
+                eprintln!("Warning: {}", warning);
+            }
+        }
+    }
+
+    // Prepare recording configuration
+    let recording_config = args.recording_config();
+
+    // Prepare stream resolution configuration
+    let resolution_config = args.resolution_config();
+
+    // Commands from stdin and the control port act on the same flags
+    let controls = RecorderControls {
+        recording: recording.clone(),
+        quit: quit.clone(),
+        paused: paused.clone(),
+        first_sample_pulled: first_sample_pulled.clone(),
+        is_irregular_stream: is_irregular_stream.clone(),
+        stores: zarr_config
+            .iter()
+            .flat_map(|config| std::iter::once(config.store_path.clone()).chain(config.mirror_path.clone()))
+            .collect(),
+        clock: lsl::local_clock,
+    };
+    if let Some(port) = args.control_port {
+        let server_controls = controls.clone();
+        let address = serve_control(port, move |command| server_controls.execute(command))?;
+        if !args.quiet {
+            println!("Control server:\t{}", address);
+        }
+    }
+
+    let block_events = if scheduled {
+        let (sender, receiver) = mpsc::channel();
+        spawn_scheduler(start_at, schedule, controls.clone(), sender, args.quiet);
+        Some(receiver)
+    } else {
+        None
+    };
+
+    if args.interactive {
+        // Interactive mode: spawn threads for command handling and recording
+        let recording_clone = recording.clone();
+        let quit_clone = quit.clone();
+        let paused_clone = paused.clone();
+        let first_sample_clone = first_sample_pulled.clone();
+        let is_irregular_clone = is_irregular_stream.clone();
+        let source_id = args.source_id.clone();
+
+        // Spawn LSL recording thread
+        let recording_thread = {
+            let recording = recording_clone;
+            let quit = quit_clone;
+            let paused = paused_clone;
+            let first_sample = first_sample_clone;
+            let is_irregular = is_irregular_clone;
+            let zarr_config_clone = zarr_config.clone();
+            let recording_config_clone = recording_config.clone();
+            let resolution_config_clone = resolution_config.clone();
+            let quiet = args.quiet;
+
+            thread::spawn(move || {
+                let args_clone = args.clone();
+                let params = RecordingParams {
+                    source_id: &source_id,
+                    recording,
+                    quit,
+                    paused,
+                    first_sample_pulled: first_sample,
+                    is_irregular_stream: is_irregular,
+                    quiet,
+                    zarr_config: zarr_config_clone,
+                    recording_config: recording_config_clone,
+                    resolution_config: resolution_config_clone,
+                    recorder_args: &args_clone,
+                    block_events,
+                    observer: None,
+                };
+
+                if let Err(e) = record_lsl_stream(params) {
+                    eprintln!("Recording error: {}", e);
+                }
+            })
+        };
+
+        // Handle commands on a separate thread so a shutdown signal does not wait on stdin
+        thread::spawn(move || {
+            if let Err(e) = handle_commands(controls) {
+                eprintln!("Command handling error: {}", e);
+            }
+        });
+
+        // Wait for recording thread to finish (QUIT or shutdown signal)
+        recording_thread.join().unwrap();
+    } else {
+        // Direct recording mode
+        if !args.quiet {
+            println!(
+                "Starting direct recording for stream: {}",
+                args.resolution_strategy()
+            );
+        }
+
+        // Set up duration timer (regardless of quiet mode)
+        if let Some(duration) = args.duration {
+            if !args.quiet {
+                println!("Recording will stop after {} seconds (timer starts after first sample)", duration);
+            }
+            let recording_clone = recording.clone();
+            let quit_clone = quit.clone();
+            let first_sample_clone = first_sample_pulled.clone();
+            thread::spawn(move || {
+                // Wait for first sample to be pulled
+                while !first_sample_clone.load(Ordering::SeqCst) {
+                    thread::sleep(Duration::from_millis(10));
+                }
+                thread::sleep(Duration::from_secs(duration));
+                recording_clone.store(false, Ordering::SeqCst);
+                quit_clone.store(true, Ordering::SeqCst);
+            });
+        }
+
+        let params = RecordingParams {
+            source_id: &args.source_id,
+            recording,
+            quit,
+            paused,
+            first_sample_pulled,
+            is_irregular_stream,
+            quiet: args.quiet,
+            zarr_config,
+            recording_config,
+            resolution_config,
+            recorder_args: &args,
+            block_events,
+            observer: None,
+        };
+
+        record_lsl_stream(params)?;
+    }
+
+    Ok(())
+}
+
+/// `--preflight`: check the stream and the output instead of recording
+///
+/// Prints the findings and a GO/NO-GO verdict; exits with 1 on NO-GO.
+fn preflight(args: &Args, config: &ZarrConfig) -> Result<()> {
+    let resolution_config = args.resolution_config();
+    let mut report = match preflight_stream(&resolution_config, args.preflight_seconds.max(0.1), args.quiet) {
+        Ok(report) => report,
+        Err(e) => PreflightReport {
+            stream: args.resolution_strategy().to_string(),
+            stream_error: Some(format!("{:#}", e)),
+            ..Default::default()
+        },
+    };
+
+    // The same checks as before a recording, collected instead of failing
+    report.output = config.store_path.clone();
+    for path in std::iter::once(&config.store_path).chain(config.mirror_path.as_ref()) {
+        if let Err(e) = check_writable(path) {
+            report.output_errors.push(format!("{:#}", e));
+        }
+        if !args.append_session
+            && let Err(e) = check_session_collision(path, config.subject.as_deref(), config.session_id.as_deref())
+        {
+            report.output_errors.push(format!("{:#}", e));
+        }
+    }
+    let dir = config
+        .store_path
+        .ancestors()
+        .find(|path| path.is_dir())
+        .unwrap_or_else(|| Path::new("."));
+    report.free_bytes = fs2::available_space(dir).ok();
+    report.planned_seconds = args.duration.map(|seconds| seconds as f64);
+
+    println!("PREFLIGHT ({})", args.resolution_strategy());
+    if report.stream_error.is_none() {
+        println!(
+            "\tStream:\t\t{} ({} x {}, {} Hz nominal)",
+            report.stream, report.channels, report.channel_format, report.nominal_rate
+        );
+        let rate = report
+            .measured_rate()
+            .map(|rate| format!(" ({:.1} Hz)", rate))
+            .unwrap_or_default();
+        println!("\tPulled:\t\t{} samples in {:.1}s{}", report.samples, report.seconds, rate);
+    }
+    println!(
+        "\tOutput:\t\t{} ({})",
+        report.output.display(),
+        if report.output_errors.is_empty() { "writable" } else { "NOT USABLE" }
+    );
+    match report.free_bytes {
+        Some(bytes) => println!("\tFree space:\t{:.1} GiB", bytes as f64 / (1024.0 * 1024.0 * 1024.0)),
+        None => println!("\tFree space:\tunknown"),
+    }
+    if report.stream_error.is_none()
+        && let Some(per_hour) = report.bytes_per_hour()
+    {
+        let hours = report
+            .hours_of_space()
+            .map(|hours| format!(", room for about {:.0} hours", hours))
+            .unwrap_or_default();
+        println!("\tData rate:\t{:.1} MiB/hour before compression{}", per_hour / (1024.0 * 1024.0), hours);
+    }
+    println!();
+
+    let verdict = report.evaluate(&PreflightCriteria::default());
+    for problem in &verdict.problems {
+        println!("\tPROBLEM: {}", problem);
+    }
+    for warning in &verdict.warnings {
+        println!("\tWARNING: {}", warning);
+    }
+    if verdict.go() {
+        println!("VERDICT: GO");
+        Ok(())
+    } else {
+        println!("VERDICT: NO-GO ({} problem(s))", verdict.problems.len());
+        crate::usage::exit(1);
+    }
+}
+
+/// Wait for `--start-at`, then start recording or run the `--blocks` schedule
+///
+/// The schedule ends by quitting the recorder, like `--duration`.
+fn spawn_scheduler(
+    start_at: Option<DateTime<Local>>,
+    schedule: Option<BlockSchedule>,
+    controls: RecorderControls,
+    events: mpsc::Sender<BlockEvent>,
+    quiet: bool,
+) {
+    thread::spawn(move || {
+        if let Some(at) = start_at {
+            if !quiet {
+                println!("Recording starts at {}", at.format("%Y-%m-%d %H:%M:%S"));
+            }
+            let wait = (at - Local::now()).to_std().unwrap_or_default();
+            if !wait_until(Instant::now() + wait, &controls.quit) {
+                return;
+            }
+        }
+
+        let Some(schedule) = schedule else {
+            controls.execute(&Command::Start).ok();
+            return;
+        };
+        if !quiet {
+            println!(
+                "Recording {} blocks of {:.1}s with {:.1}s pauses ({:.1}s in total)",
+                schedule.blocks,
+                schedule.block_duration.as_secs_f64(),
+                schedule.inter_block.as_secs_f64(),
+                schedule.total_duration().as_secs_f64()
+            );
+        }
+
+        let first_block = Instant::now();
+        for index in 1..=schedule.blocks {
+            let start = first_block + schedule.block_start(index);
+            if !wait_until(start, &controls.quit) {
+                return;
+            }
+            controls.recording.store(true, Ordering::SeqCst);
+            events.send(BlockEvent::Started { index, lsl_clock: lsl::local_clock() }).ok();
+            println!("STATUS BLOCK_STARTED ({}/{})", index, schedule.blocks);
+            std::io::stdout().flush().ok();
+
+            if !wait_until(start + schedule.block_duration, &controls.quit) {
+                return;
+            }
+            controls.recording.store(false, Ordering::SeqCst);
+            events.send(BlockEvent::Ended { lsl_clock: lsl::local_clock() }).ok();
+            println!("STATUS BLOCK_ENDED ({}/{})", index, schedule.blocks);
+            std::io::stdout().flush().ok();
+        }
+
+        println!("STATUS SCHEDULE_COMPLETE");
+        std::io::stdout().flush().ok();
+        controls.quit.store(true, Ordering::SeqCst);
+    });
+}
@@ -0,0 +1,1129 @@
+//! LSL Replay - Stream recorded LSL data from Zarr files
+//!
+//! This tool reads recorded LSL streams from Zarr files and replays them
+//! as live LSL streams, with continuous looping enabled by default.
+//!
+//! # Features
+//!
+//! - Replay recorded LSL streams from Zarr files
+//! - Continuous looping enabled by default (use --no-loop to disable)
+//! - Original timing preservation or speed adjustment
+//! - Support for all data formats (Float32, Float64, Int32, Int16, Int8, String)
+//! - Automatic stream metadata reconstruction
+//! - List available streams in a Zarr file
+//! - Replay several streams (or a whole session) with their recorded offsets
+//! - Batch mode for regression testing of online-analysis algorithms
+//! - Block reads prefetched on a background thread, fast enough for kHz streams
+//! - Replay a portion of a recording by time or sample index
+//! - Chunked pushes with per-sample timestamps for high sample rates
+//!
+//! # Usage
+//!
+//! ```bash
+//! # List available streams in a Zarr file
+//! lsl-replay recording.zarr --list
+//!
+//! # Replay a specific stream (loops by default)
+//! lsl-replay recording.zarr --stream MUOVI
+//!
+//! # Replay once (no looping)
+//! lsl-replay recording.zarr --stream MUOVI --no-loop
+//!
+//! # Replay at 2x speed (with looping)
+//! lsl-replay recording.zarr --stream VHI_Control --speed 2.0
+//!
+//! # Replay at half speed
+//! lsl-replay recording.zarr --stream VHI_Predict --speed 0.5
+//!
+//! # Replay a whole session, keeping the offsets between streams
+//! lsl-replay recording.zarr --all
+//!
+//! # Replay two streams together
+//! lsl-replay recording.zarr --stream MUOVI --stream Markers
+//!
+//! # Custom output stream name
+//! lsl-replay recording.zarr --stream MUOVI --output-name "ReplayedMUOVI"
+//!
+//! # Batch replay at 20x speed
+//! lsl-replay recording.zarr --stream MUOVI --batch --speed 20
+//!
+//! # Batch replay as fast as the consumer keeps up
+//! lsl-replay recording.zarr --stream MUOVI --batch --no-realtime
+//!
+//! # Loop ten seconds of a session around an artifact
+//! lsl-replay recording.zarr --all --start-time 3605 --end-time 3615
+//!
+//! # Push 10 ms chunks with their own timestamps instead of one sample at a time
+//! lsl-replay recording.zarr --stream MUOVI --chunk-ms 10
+//!
+//! # Replay samples 20000 to 29999 once
+//! lsl-replay recording.zarr --stream MUOVI --start-sample 20000 --end-sample 30000 --no-loop
+//! ```
+//!
+//! # Timing and Synchronization
+//!
+//! - Preserves original inter-sample timing from recorded timestamps
+//! - Speed factor adjusts playback rate (1.0 = real-time, 2.0 = 2x faster)
+//! - Loops seamlessly without timestamp discontinuities
+//! - Supports both regular and irregular streams
+//!
+//! # Portions of a Recording
+//!
+//! `--start-time`/`--end-time` select the samples between two times in seconds,
+//! both inclusive. When lsl-sync has written `aligned_time` for the replayed
+//! streams, times are read on that axis (0 at the common start of the synchronized
+//! streams), so they match the times shown by the analysis of the synced session;
+//! otherwise they count from the first sample of the stream (or of the session).
+//! `--start-sample`/`--end-sample` (exclusive) select samples of a single stream by
+//! index. The replay starts, loops and is stamped as if the recording began at the
+//! first selected sample; batch replays publish its index as `start_sample`.
+//!
+//! # Batch Mode
+//!
+//! `--batch` replays the recording once for regression tests of online-analysis
+//! algorithms. The replay waits for a consumer (`--consumer-timeout`), stamps every
+//! sample with its recorded offset from the first sample (unscaled by `--speed`),
+//! and publishes the offset between pushed and recorded timestamps in a `<replay>`
+//! element of the stream description, so results can be matched to the recording.
+//! `--speed` sets the pace; `--no-realtime` drops pacing and pushes samples as fast
+//! as they can be read. The outlet buffers the whole recording, so a slower
+//! consumer falls behind instead of losing samples.
+//!
+//! # Chunked Pushes
+//!
+//! By default every sample is pushed on its own when it is due: one sleep and one
+//! outlet call per sample, with live replays stamped by liblsl at push time. With
+//! `--chunk-ms` the samples due within that many milliseconds are pushed together
+//! with `push_chunk_stamped_ex` once the last of them is due, each with an explicit
+//! timestamp: its scheduled time on the local LSL clock in live replays, its
+//! recorded offset in batch and session replays. Timing then follows the recording
+//! exactly instead of the sleep accuracy, and CPU use drops with the number of
+//! outlet calls, at the cost of up to `--chunk-ms` of added latency.
+//!
+//! # Reading
+//!
+//! A background thread reads `--block-samples` samples at a time from the store
+//! into a bounded queue of `--prefetch` blocks; the push loop takes samples from
+//! memory and only waits for the store if the queue runs empty. When looping, the
+//! thread reads the next pass ahead as well.
+//!
+//! # Back-pressure
+//!
+//! Every push is checked against its schedule. Failed pushes, and samples pushed
+//! more than `--max-lag` seconds after they were due, print a warning with the
+//! delivered rate; the replay ends with the outlet statistics (pushed, failed and
+//! late samples, largest lag and delivered rate), so a replay that could not keep
+//! up with real time is never mistaken for one that did.
+
+use anyhow::{Context, Result};
+use clap::Parser;
+use lsl::{ChannelFormat, ExPushable, Pushable, StreamInfo, StreamOutlet};
+use crate::backpressure::{PushMonitor, PushStats, DEFAULT_MAX_LAG};
+use crate::export::ExportStream;
+use crate::replay::{
+    batch_buffer, block_ranges, chunk_ranges, ReplayClock, ReplayWindow, SessionSpan, DEFAULT_BLOCK_SAMPLES,
+    DEFAULT_PREFETCH_BLOCKS,
+};
+use crate::zarr::{get_stream_info, is_store_group};
+use std::ops::Range;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{sync_channel, Receiver};
+use std::sync::{Arc, Barrier, OnceLock};
+use std::thread;
+use std::time::{Duration, Instant};
+use zarrs::array::{Array, ElementOwned};
+use zarrs::array_subset::ArraySubset;
+use zarrs::filesystem::FilesystemStore;
+
+#[derive(Parser)]
+#[command(name = "lsl-replay")]
+#[command(about = "Replay recorded LSL streams from Zarr files")]
+#[command(version)]
+pub struct Args {
+    /// Path to Zarr file to replay
+    file_path: String,
+
+    /// Stream name to replay (repeat to replay several streams together)
+    #[arg(short, long)]
+    stream: Vec<String>,
+
+    /// Replay every stream of the store together, keeping their recorded offsets
+    #[arg(long, conflicts_with = "stream")]
+    all: bool,
+
+    /// List available streams in the Zarr file
+    #[arg(short, long)]
+    list: bool,
+
+    /// Loop continuously (default: true, use --no-loop to disable)
+    #[arg(short = 'L', long, default_value = "true", action = clap::ArgAction::Set)]
+    r#loop: bool,
+
+    /// Playback speed multiplier (1.0 = real-time, 2.0 = 2x speed, 0.5 = half speed)
+    #[arg(long, default_value = "1.0")]
+    speed: f64,
+
+    /// Start of the replayed portion in seconds (on aligned_time if present, else from the first sample)
+    #[arg(long, value_name = "SECONDS", conflicts_with = "start_sample")]
+    start_time: Option<f64>,
+
+    /// End of the replayed portion in seconds (inclusive)
+    #[arg(long, value_name = "SECONDS", conflicts_with = "end_sample")]
+    end_time: Option<f64>,
+
+    /// First sample to replay
+    #[arg(long, value_name = "INDEX")]
+    start_sample: Option<usize>,
+
+    /// End of the samples to replay (exclusive)
+    #[arg(long, value_name = "INDEX")]
+    end_sample: Option<usize>,
+
+    /// Custom output stream name (defaults to original stream name)
+    #[arg(short, long)]
+    output_name: Option<String>,
+
+    /// Replay the recording once for regression testing, keeping recorded timestamp offsets
+    #[arg(long)]
+    batch: bool,
+
+    /// Push samples as fast as possible instead of at --speed (batch mode)
+    #[arg(long, requires = "batch")]
+    no_realtime: bool,
+
+    /// Push the samples due within this many milliseconds as one chunk with per-sample timestamps
+    #[arg(long, value_name = "MS")]
+    chunk_ms: Option<f64>,
+
+    /// Samples read from the store per block
+    #[arg(long, default_value_t = DEFAULT_BLOCK_SAMPLES, value_name = "SAMPLES")]
+    block_samples: usize,
+
+    /// Blocks read ahead of the outlet on a background thread
+    #[arg(long, default_value_t = DEFAULT_PREFETCH_BLOCKS, value_name = "BLOCKS")]
+    prefetch: usize,
+
+    /// Warn when samples are pushed more than this many seconds behind schedule
+    #[arg(long, default_value_t = DEFAULT_MAX_LAG, value_name = "SECONDS")]
+    max_lag: f64,
+
+    /// Seconds to wait for a consumer before a batch replay starts
+    #[arg(long, default_value = "30.0")]
+    consumer_timeout: f64,
+
+    /// Verbose output
+    #[arg(short, long)]
+    verbose: bool,
+}
+
+impl Args {
+    /// Portion of the recording selected on the command line
+    fn window(&self) -> ReplayWindow {
+        ReplayWindow {
+            start_time: self.start_time,
+            end_time: self.end_time,
+            start_sample: self.start_sample,
+            end_sample: self.end_sample,
+        }
+    }
+
+    /// Seconds of recording pushed per chunk with --chunk-ms
+    fn chunk_span(&self) -> Option<f64> {
+        self.chunk_ms.map(|ms| ms / 1000.0 * self.speed)
+    }
+}
+
+/// Run `lsl-replay` with parsed arguments
+pub fn run(args: Args) -> Result<()> {
+
+    crate::display_license_notice("lsl-replay");
+
+    let store = Arc::new(FilesystemStore::new(&args.file_path)?);
+
+    // List mode
+    if args.list {
+        list_streams(&args.file_path)?;
+        return Ok(());
+    }
+
+    if args.block_samples == 0 {
+        anyhow::bail!("--block-samples must be at least 1");
+    }
+    args.window().validate()?;
+    if let Some(ms) = args.chunk_ms
+        && !(ms.is_finite() && ms > 0.0)
+    {
+        anyhow::bail!("--chunk-ms must be a positive number of milliseconds");
+    }
+
+    // Replay mode - require stream name(s)
+    let stream_names = if args.all {
+        store_stream_names(&args.file_path)?
+    } else {
+        args.stream.clone()
+    };
+    if stream_names.is_empty() {
+        anyhow::bail!("Stream name required (use --stream, --all, or --list to see available streams)");
+    }
+
+    // Verify streams exist
+    let streams_path = PathBuf::from(&args.file_path);
+    for stream_name in &stream_names {
+        if !streams_path.join(stream_name).exists() {
+            anyhow::bail!(
+                "Stream '{}' not found in Zarr file. Use --list to see available streams.",
+                stream_name
+            );
+        }
+    }
+    if stream_names.len() > 1 {
+        return replay_session(&store, &stream_names, &args);
+    }
+    let stream_name = &stream_names[0];
+
+    println!("╔════════════════════════════════════════════════════════════════╗");
+    println!("║              LSL Stream Replay                                 ║");
+    println!("╚════════════════════════════════════════════════════════════════╝");
+    println!();
+
+    // Read stream metadata
+    let stream_path = format!("/{}", stream_name);
+    let stream_info = get_stream_info(&store, stream_name)?;
+
+    // Extract stream parameters
+    let source_id = stream_info.source_id.as_deref().unwrap_or("replayed_stream");
+    let stream_type = stream_info.stream_type.as_deref().unwrap_or("Unknown");
+    let channel_count = stream_info.channel_count.context("Missing channel_count")? as u32;
+    let nominal_srate = stream_info.srate();
+    let channel_format_str = stream_info.channel_format.as_deref().context("Missing channel_format")?;
+
+    let channel_format = parse_channel_format(channel_format_str)?;
+
+    // Use custom output name if provided
+    let output_stream_name = args.output_name.as_deref().unwrap_or(stream_name);
+
+    println!("Source file:\t{}", args.file_path);
+    println!("Stream name:\t{}", stream_name);
+    println!("Output name:\t{}", output_stream_name);
+    println!("Stream type:\t{}", stream_type);
+    println!("Source ID:\t{}", source_id);
+    println!("Channels:\t{}", channel_count);
+    println!("Sample rate:\t{} Hz", nominal_srate);
+    println!("Format:\t\t{:?}", channel_format);
+    if args.batch && args.no_realtime {
+        println!("Mode:\t\tBatch (no real-time pacing)");
+    } else if args.batch {
+        println!("Mode:\t\tBatch");
+        println!("Speed:\t\t{}x", args.speed);
+    } else {
+        println!("Speed:\t\t{}x", args.speed);
+        println!("Looping:\t{}", if args.r#loop { "Yes" } else { "No" });
+    }
+    if let Some(ms) = args.chunk_ms {
+        println!("Chunks:\t\t{} ms", ms);
+    }
+    println!();
+
+    if args.speed.is_nan() || args.speed <= 0.0 {
+        anyhow::bail!("--speed must be positive");
+    }
+
+    // Read time array
+    let time_array_path = format!("{}/time", stream_path);
+    let time_array = Array::<FilesystemStore>::open(store.clone(), &time_array_path)
+        .context("Failed to open time array")?;
+
+    let num_samples = time_array.shape()[0] as usize;
+
+    if num_samples == 0 {
+        anyhow::bail!("No samples found in stream");
+    }
+
+    let window = args.window();
+    let axis = window_axis(&store, &[stream_path.as_str()], read_timestamp(&time_array, 0)?);
+    let range = window_range(&store, stream_name, num_samples, &window, axis)?;
+    if range.is_empty() {
+        anyhow::bail!("No samples in the selected portion of '{}'", stream_name);
+    }
+    if !window.is_full() {
+        println!(
+            "Portion:\tsamples {}..{} of {}{}",
+            range.start,
+            range.end,
+            num_samples,
+            axis.describe(&window)
+        );
+        println!();
+    }
+
+    // Create LSL outlet
+    let mut info = StreamInfo::new(
+        output_stream_name,
+        stream_type,
+        channel_count,
+        nominal_srate,
+        channel_format,
+        source_id,
+    )?;
+
+    if args.batch {
+        let first_timestamp = read_timestamp(&time_array, range.start)?;
+        let last_timestamp = read_timestamp(&time_array, range.end - 1)?;
+        let clock = ReplayClock {
+            first_timestamp,
+            origin: lsl::local_clock(),
+            speed: args.speed,
+            realtime: !args.no_realtime,
+        };
+
+        let mut replay_element = info.desc().append_child("replay");
+        replay_element.append_child_value("source_file", &args.file_path);
+        replay_element.append_child_value("source_stream", stream_name);
+        replay_element.append_child_value("start_sample", &range.start.to_string());
+        replay_element.append_child_value("first_timestamp", &first_timestamp.to_string());
+        replay_element.append_child_value("timestamp_offset", &clock.timestamp_offset().to_string());
+        replay_element.append_child_value("speed", &args.speed.to_string());
+        replay_element.append_child_value("realtime", &clock.realtime.to_string());
+
+        let buffer = batch_buffer(range.len(), last_timestamp - first_timestamp, nominal_srate);
+        let outlet = StreamOutlet::new(&info, 0, buffer)?;
+
+        println!("Waiting up to {:.0} s for a consumer...", args.consumer_timeout);
+        if !outlet.wait_for_consumers(args.consumer_timeout) {
+            anyhow::bail!("No consumer connected to '{}' within {} s", output_stream_name, args.consumer_timeout);
+        }
+        println!("Consumer connected, replaying {} samples ({:.1} s recorded)...", range.len(), last_timestamp - first_timestamp);
+
+        let nominal_rate = if clock.realtime { nominal_srate * args.speed } else { 0.0 };
+        let mut monitor = PushMonitor::new(nominal_rate, args.max_lag);
+        let start = Instant::now();
+        let source = SampleSource::new(&store, &stream_path, range, &args);
+        let sent = push_recorded(&source, channel_format, &outlet, &clock, start, args.chunk_span(), &mut monitor)?;
+        let elapsed = start.elapsed().as_secs_f64();
+
+        drain_outlet(&outlet);
+
+        println!();
+        println!(
+            "Batch replay completed: {} samples, {:.1} s recorded in {:.1} s ({:.1}x)",
+            sent,
+            last_timestamp - first_timestamp,
+            elapsed,
+            if elapsed > 0.0 { (last_timestamp - first_timestamp) / elapsed } else { 0.0 }
+        );
+        println!("Outlet: {}", monitor.stats().summary());
+        return Ok(());
+    }
+
+    let outlet = StreamOutlet::new(&info, 0, 360)?;
+
+    println!("Starting replay of {} samples...", range.len());
+    if args.r#loop {
+        println!("Press Ctrl+C to stop");
+    }
+    println!();
+
+    // Replay loop
+    let first_timestamp = read_timestamp(&time_array, range.start)?;
+    let source = SampleSource::new(&store, &stream_path, range, &args);
+    let mut monitor = PushMonitor::new(nominal_srate * args.speed, args.max_lag);
+    match channel_format {
+        ChannelFormat::Float32 => replay_blocks::<f32>(&source, first_timestamp, &outlet, &args, &mut monitor),
+        ChannelFormat::Double64 => replay_blocks::<f64>(&source, first_timestamp, &outlet, &args, &mut monitor),
+        ChannelFormat::Int32 => replay_blocks::<i32>(&source, first_timestamp, &outlet, &args, &mut monitor),
+        ChannelFormat::Int16 => replay_blocks::<i16>(&source, first_timestamp, &outlet, &args, &mut monitor),
+        ChannelFormat::Int8 => replay_blocks::<i8>(&source, first_timestamp, &outlet, &args, &mut monitor),
+        ChannelFormat::String => replay_blocks::<String>(&source, first_timestamp, &outlet, &args, &mut monitor),
+        _ => anyhow::bail!("Unsupported channel format: {:?}", channel_format),
+    }
+}
+
+/// A stream of a multi-stream replay
+struct SessionStream {
+    name: String,
+    path: String,
+    info: crate::zarr::StreamInfoAttribute,
+    channel_format: ChannelFormat,
+    /// Samples replayed (the whole stream, or the selected portion)
+    range: Range<usize>,
+    first_timestamp: f64,
+    last_timestamp: f64,
+}
+
+/// Replay several streams together, one outlet each, keeping their recorded offsets
+///
+/// Every stream runs on its own thread against a clock shared through
+/// [`SessionSpan`]: all outlets are created (and in batch mode connected) before the
+/// first sample is pushed, so a stream that started 2 s after another in the
+/// recording starts 2 s (divided by `--speed`) after it in the replay. Pushed
+/// timestamps keep the recorded offsets unscaled, as in batch mode.
+fn replay_session(store: &Arc<FilesystemStore>, stream_names: &[String], args: &Args) -> Result<()> {
+    if args.output_name.is_some() {
+        anyhow::bail!("--output-name only applies to a single stream");
+    }
+    if args.speed.is_nan() || args.speed <= 0.0 {
+        anyhow::bail!("--speed must be positive");
+    }
+    let window = args.window();
+    if window.has_samples() {
+        anyhow::bail!("--start-sample/--end-sample select samples of a single stream; use --start-time/--end-time");
+    }
+
+    println!("╔════════════════════════════════════════════════════════════════╗");
+    println!("║              LSL Session Replay                                ║");
+    println!("╚════════════════════════════════════════════════════════════════╝");
+    println!();
+
+    let mut streams = Vec::new();
+    for name in stream_names {
+        let path = format!("/{}", name);
+        let info = get_stream_info(store, name)?;
+        let channel_format = parse_channel_format(info.channel_format.as_deref().context("Missing channel_format")?)?;
+        let time_array = Array::<FilesystemStore>::open(store.clone(), &format!("{}/time", path))
+            .with_context(|| format!("Failed to open time array of '{}'", name))?;
+        let num_samples = time_array.shape()[0] as usize;
+        if num_samples == 0 {
+            println!("Skipping '{}': no samples", name);
+            continue;
+        }
+        streams.push(SessionStream {
+            name: name.clone(),
+            path,
+            first_timestamp: read_timestamp(&time_array, 0)?,
+            last_timestamp: read_timestamp(&time_array, num_samples - 1)?,
+            info,
+            channel_format,
+            range: 0..num_samples,
+        });
+    }
+
+    // Without aligned_time the window counts from the first sample of the session
+    let paths: Vec<&str> = streams.iter().map(|s| s.path.as_str()).collect();
+    let recorded_start = streams.iter().map(|s| s.first_timestamp).fold(f64::INFINITY, f64::min);
+    let axis = window_axis(store, &paths, recorded_start);
+    if !window.is_full() {
+        let mut selected = Vec::new();
+        for mut stream in streams {
+            let range = window_range(store, &stream.name, stream.range.end, &window, axis)?;
+            if range.is_empty() {
+                println!("Skipping '{}': no samples in the selected portion", stream.name);
+                continue;
+            }
+            let time_array = Array::<FilesystemStore>::open(store.clone(), &format!("{}/time", stream.path))?;
+            stream.first_timestamp = read_timestamp(&time_array, range.start)?;
+            stream.last_timestamp = read_timestamp(&time_array, range.end - 1)?;
+            stream.range = range;
+            selected.push(stream);
+        }
+        streams = selected;
+    }
+    let mut session = SessionSpan::new(streams.iter().map(|s| (s.first_timestamp, s.last_timestamp)))
+        .context("No samples found in any stream")?;
+    // One sample period of the slowest regular stream separates loop passes
+    session.gap = streams
+        .iter()
+        .map(|s| s.info.srate())
+        .filter(|&srate| srate > 0.0)
+        .map(|srate| 1.0 / srate)
+        .fold(0.0, f64::max);
+
+    println!("Source file:\t{}", args.file_path);
+    println!("Duration:\t{:.3} s", session.duration());
+    if !window.is_full() {
+        println!("Portion:\t{}", axis.describe(&window).trim_start_matches(", "));
+    }
+    if args.batch && args.no_realtime {
+        println!("Mode:\t\tBatch (no real-time pacing)");
+    } else if args.batch {
+        println!("Mode:\t\tBatch");
+        println!("Speed:\t\t{}x", args.speed);
+    } else {
+        println!("Speed:\t\t{}x", args.speed);
+        println!("Looping:\t{}", if args.r#loop { "Yes" } else { "No" });
+    }
+    println!();
+    println!("{:<24} {:>10} {:>10} {:>10}  {}", "Stream", "Offset [s]", "Samples", "Rate [Hz]", "Format");
+    for stream in &streams {
+        println!(
+            "{:<24} {:>10.3} {:>10} {:>10}  {:?}",
+            stream.name,
+            stream.first_timestamp - session.start,
+            stream.range.len(),
+            stream.info.srate(),
+            stream.channel_format
+        );
+    }
+    println!();
+
+    // Batch outlets publish their timestamp offset, so the origin is fixed up front
+    let batch_origin = lsl::local_clock();
+    let ready = Barrier::new(streams.len() + 1);
+    let go = Barrier::new(streams.len() + 1);
+    let failed = AtomicBool::new(false);
+    let start: OnceLock<(Instant, f64)> = OnceLock::new();
+
+    let results: Vec<Result<PushStats>> = thread::scope(|scope| {
+        let handles: Vec<_> = streams
+            .iter()
+            .map(|stream| {
+                let (ready, go, failed, start) = (&ready, &go, &failed, &start);
+                scope.spawn(move || {
+                    let outlet = session_outlet(stream, &session, batch_origin, args);
+                    if outlet.is_err() {
+                        failed.store(true, Ordering::SeqCst);
+                    }
+                    ready.wait();
+                    go.wait();
+                    let outlet = outlet?;
+                    let Some(&(start, origin)) = start.get() else {
+                        return Ok(PushStats::default());
+                    };
+
+                    let nominal_rate = if args.no_realtime { 0.0 } else { stream.info.srate() * args.speed };
+                    let mut monitor = PushMonitor::new(nominal_rate, args.max_lag);
+                    for pass in 0.. {
+                        if args.verbose {
+                            println!("{}: starting loop iteration {}", stream.name, pass + 1);
+                        }
+                        let clock = session.clock(pass, origin, args.speed, !args.no_realtime);
+                        let source = SampleSource::new(store, &stream.path, stream.range.clone(), args);
+                        push_recorded(
+                            &source,
+                            stream.channel_format,
+                            &outlet,
+                            &clock,
+                            start,
+                            args.chunk_span(),
+                            &mut monitor,
+                        )?;
+                        if args.batch || !args.r#loop {
+                            break;
+                        }
+                    }
+                    if args.batch {
+                        drain_outlet(&outlet);
+                    }
+                    Ok(monitor.stats().clone())
+                })
+            })
+            .collect();
+
+        ready.wait();
+        if !failed.load(Ordering::SeqCst) {
+            let origin = if args.batch { batch_origin } else { lsl::local_clock() };
+            let _ = start.set((Instant::now(), origin));
+            println!("Replaying {} streams...", streams.len());
+            if args.r#loop && !args.batch {
+                println!("Press Ctrl+C to stop");
+            }
+        }
+        go.wait();
+
+        handles
+            .into_iter()
+            .map(|handle| handle.join().unwrap_or_else(|_| Err(anyhow::anyhow!("Replay thread panicked"))))
+            .collect()
+    });
+    let elapsed = start.get().map(|(start, _)| start.elapsed().as_secs_f64());
+
+    println!();
+    let mut errors = Vec::new();
+    for (stream, result) in streams.iter().zip(results) {
+        match result {
+            Ok(stats) => println!("{:<24} {}", stream.name, stats.summary()),
+            Err(e) => {
+                println!("{:<24} failed: {:#}", stream.name, e);
+                errors.push(stream.name.as_str());
+            }
+        }
+    }
+    if !errors.is_empty() {
+        anyhow::bail!("Replay failed for: {}", errors.join(", "));
+    }
+    if let Some(elapsed) = elapsed {
+        println!(
+            "Session replay completed: {:.1} s recorded in {:.1} s",
+            session.duration(),
+            elapsed
+        );
+    }
+    Ok(())
+}
+
+/// Outlet of one stream of a multi-stream replay
+///
+/// In batch mode the outlet buffers the whole stream, describes the replay in a
+/// `<replay>` element and waits for a consumer.
+fn session_outlet(stream: &SessionStream, session: &SessionSpan, origin: f64, args: &Args) -> Result<StreamOutlet> {
+    let mut info = StreamInfo::new(
+        &stream.name,
+        stream.info.stream_type.as_deref().unwrap_or("Unknown"),
+        stream.info.channel_count.context("Missing channel_count")? as u32,
+        stream.info.srate(),
+        stream.channel_format,
+        stream.info.source_id.as_deref().unwrap_or("replayed_stream"),
+    )?;
+    if !args.batch {
+        return Ok(StreamOutlet::new(&info, 0, 360)?);
+    }
+
+    let clock = session.clock(0, origin, args.speed, !args.no_realtime);
+    let mut replay_element = info.desc().append_child("replay");
+    replay_element.append_child_value("source_file", &args.file_path);
+    replay_element.append_child_value("source_stream", &stream.name);
+    replay_element.append_child_value("start_sample", &stream.range.start.to_string());
+    replay_element.append_child_value("first_timestamp", &stream.first_timestamp.to_string());
+    replay_element.append_child_value("session_start", &session.start.to_string());
+    replay_element.append_child_value("timestamp_offset", &clock.timestamp_offset().to_string());
+    replay_element.append_child_value("speed", &args.speed.to_string());
+    replay_element.append_child_value("realtime", &clock.realtime.to_string());
+
+    let buffer = batch_buffer(
+        stream.range.len(),
+        stream.last_timestamp - stream.first_timestamp,
+        stream.info.srate(),
+    );
+    let outlet = StreamOutlet::new(&info, 0, buffer)?;
+    println!("{}: waiting up to {:.0} s for a consumer...", stream.name, args.consumer_timeout);
+    if !outlet.wait_for_consumers(args.consumer_timeout) {
+        anyhow::bail!("No consumer connected to '{}' within {} s", stream.name, args.consumer_timeout);
+    }
+    Ok(outlet)
+}
+
+/// How long a finished batch replay keeps its outlet open for the consumer to catch up
+const BATCH_DRAIN: Duration = Duration::from_secs(10);
+
+/// Keep a finished outlet open until its consumers caught up (at most [`BATCH_DRAIN`])
+///
+/// Samples still buffered in the outlet are lost when it closes.
+fn drain_outlet(outlet: &StreamOutlet) {
+    let drain_deadline = Instant::now() + BATCH_DRAIN;
+    while outlet.have_consumers() && Instant::now() < drain_deadline {
+        thread::sleep(Duration::from_millis(100));
+    }
+}
+
+/// Where a replay reads the samples of a stream from
+struct SampleSource<'a> {
+    store: &'a Arc<FilesystemStore>,
+    stream_path: &'a str,
+    /// Samples to read
+    range: Range<usize>,
+    /// Samples per block read from the store
+    block_samples: usize,
+    /// Blocks read ahead of the outlet
+    prefetch: usize,
+}
+
+impl<'a> SampleSource<'a> {
+    fn new(store: &'a Arc<FilesystemStore>, stream_path: &'a str, range: Range<usize>, args: &Args) -> Self {
+        Self {
+            store,
+            stream_path,
+            range,
+            block_samples: args.block_samples,
+            prefetch: args.prefetch,
+        }
+    }
+}
+
+/// Push all samples of a stream once, with the timestamps and pacing of `clock`
+///
+/// `start` is the moment the replay started; `chunk_span` the seconds of recording
+/// pushed per chunk (one sample at a time without). Returns the samples read.
+fn push_recorded(
+    source: &SampleSource,
+    channel_format: ChannelFormat,
+    outlet: &StreamOutlet,
+    clock: &ReplayClock,
+    start: Instant,
+    chunk_span: Option<f64>,
+    monitor: &mut PushMonitor,
+) -> Result<usize> {
+    match channel_format {
+        ChannelFormat::Float32 => push_blocks::<f32>(source, outlet, clock, start, chunk_span, monitor),
+        ChannelFormat::Double64 => push_blocks::<f64>(source, outlet, clock, start, chunk_span, monitor),
+        ChannelFormat::Int32 => push_blocks::<i32>(source, outlet, clock, start, chunk_span, monitor),
+        ChannelFormat::Int16 => push_blocks::<i16>(source, outlet, clock, start, chunk_span, monitor),
+        ChannelFormat::Int8 => push_blocks::<i8>(source, outlet, clock, start, chunk_span, monitor),
+        ChannelFormat::String => push_blocks::<String>(source, outlet, clock, start, chunk_span, monitor),
+        _ => anyhow::bail!("Unsupported channel format: {:?}", channel_format),
+    }
+}
+
+/// Read one timestamp from the time array
+fn read_timestamp(time_array: &Array<FilesystemStore>, index: usize) -> Result<f64> {
+    let subset = ArraySubset::new_with_start_shape(vec![index as u64], vec![1])?;
+    let values = time_array
+        .retrieve_array_subset_ndarray::<f64>(&subset)
+        .with_context(|| format!("Failed to read timestamp {}", index))?;
+    Ok(values[[0]])
+}
+
+/// Time axis of `--start-time` and `--end-time`
+#[derive(Debug, Clone, Copy)]
+enum WindowAxis {
+    /// lsl-sync's `aligned_time`, shared by the synchronized streams
+    Aligned,
+    /// Recorded timestamps, counted from this timestamp
+    Recorded(f64),
+}
+
+impl WindowAxis {
+    /// Suffix describing the time bounds of a window, empty without them
+    fn describe(&self, window: &ReplayWindow) -> String {
+        if window.start_time.is_none() && window.end_time.is_none() {
+            return String::new();
+        }
+        let bound = |time: Option<f64>| time.map_or("-".to_string(), |t| format!("{:.3}", t));
+        let axis = match self {
+            WindowAxis::Aligned => "aligned_time",
+            WindowAxis::Recorded(_) => "from the first sample",
+        };
+        format!(", {} to {} s {}", bound(window.start_time), bound(window.end_time), axis)
+    }
+}
+
+/// `aligned_time` if every stream has it, otherwise recorded time from `recorded_start`
+fn window_axis(store: &Arc<FilesystemStore>, stream_paths: &[&str], recorded_start: f64) -> WindowAxis {
+    let aligned = stream_paths
+        .iter()
+        .all(|path| Array::<FilesystemStore>::open(store.clone(), &format!("{}/aligned_time", path)).is_ok());
+    if aligned {
+        WindowAxis::Aligned
+    } else {
+        WindowAxis::Recorded(recorded_start)
+    }
+}
+
+/// Samples of stream `name` inside the selected portion of the recording
+///
+/// The time bounds are located through the stream's time index, as lsl-export
+/// locates them.
+fn window_range(
+    store: &Arc<FilesystemStore>,
+    name: &str,
+    num_samples: usize,
+    window: &ReplayWindow,
+    axis: WindowAxis,
+) -> Result<Range<usize>> {
+    if window.start_time.is_none() && window.end_time.is_none() {
+        return window.sample_range(num_samples, |_| Ok(0));
+    }
+    let (aligned_time, origin) = match axis {
+        WindowAxis::Aligned => (true, 0.0),
+        WindowAxis::Recorded(start) => (false, start),
+    };
+    let stream = ExportStream::open(store, name, aligned_time)?;
+    let num_samples = num_samples.min(stream.sample_count as usize);
+    window.sample_range(num_samples, |time| Ok(stream.sample_index(time + origin)? as usize))
+}
+
+/// Hold back a batch sample until it is due
+fn wait_until_due(clock: &ReplayClock, start: Instant, recorded: f64) {
+    let Some(due) = clock.due(recorded) else {
+        return;
+    };
+    let target = start + due;
+    let now = Instant::now();
+    if target <= now {
+        return;
+    }
+    if target - now > Duration::from_micros(100) {
+        thread::sleep(target - now);
+    } else {
+        while Instant::now() < target {
+            std::hint::spin_loop();
+        }
+    }
+}
+
+/// Record a push of `samples` samples in the monitor and show its warnings
+fn track_push(
+    monitor: &mut PushMonitor,
+    stream_path: &str,
+    result: std::result::Result<(), lsl::Error>,
+    samples: u64,
+    lag: f64,
+    start: Instant,
+) -> Result<()> {
+    let stream_name = stream_path.trim_start_matches('/');
+    if let Some(warning) = monitor
+        .record(result, samples, lag, start.elapsed().as_secs_f64())
+        .with_context(|| format!("Outlet '{}' stopped accepting samples", stream_name))?
+    {
+        eprintln!("Warning: {}: {}", stream_name, warning);
+    }
+    Ok(())
+}
+
+/// Samples of a stream read ahead of the push loop
+struct Block<T> {
+    /// Loop pass the block belongs to (0 for the first)
+    pass: u64,
+    timestamps: Vec<f64>,
+    samples: Vec<Vec<T>>,
+}
+
+/// Read a stream block by block on a background thread
+///
+/// The bounded channel is the ring buffer between the store and the outlet: the
+/// thread stays up to `prefetch` blocks ahead and waits while the push loop works
+/// from memory. With `looping` it starts over after the last block, so loop passes
+/// follow each other without waiting for the store. The thread stops after a read
+/// error (which it passes on) or when the receiver is dropped.
+fn prefetch_blocks<T: ElementOwned + Clone + Send + 'static>(
+    source: &SampleSource,
+    looping: bool,
+) -> Result<Receiver<Result<Block<T>>>> {
+    // Marker streams store a 1D "events" array, older stores and numeric streams a 2D "data" array
+    let events_array_path = format!("{}/events", source.stream_path);
+    let (array_path, is_events) = if Array::<FilesystemStore>::open(source.store.clone(), &events_array_path).is_ok() {
+        (events_array_path, true)
+    } else {
+        (format!("{}/data", source.stream_path), false)
+    };
+    let data_array =
+        Array::<FilesystemStore>::open(source.store.clone(), &array_path).context("Failed to open data array")?;
+    let num_channels = if is_events { 1 } else { data_array.shape()[0] as usize };
+    let time_array = Array::<FilesystemStore>::open(source.store.clone(), &format!("{}/time", source.stream_path))
+        .context("Failed to open time array")?;
+
+    let (sender, receiver) = sync_channel(source.prefetch.max(1));
+    let (range, block_samples) = (source.range.clone(), source.block_samples);
+    thread::spawn(move || {
+        for pass in 0u64.. {
+            for (offset, len) in block_ranges(range.len(), block_samples) {
+                let block_start = range.start + offset;
+                let block = read_block(&data_array, &time_array, is_events, num_channels, block_start, len)
+                    .map(|(timestamps, samples)| Block { pass, timestamps, samples });
+                let failed = block.is_err();
+                if sender.send(block).is_err() || failed {
+                    return;
+                }
+            }
+            if !looping {
+                return;
+            }
+        }
+    });
+    Ok(receiver)
+}
+
+/// Timestamps and samples `block_start..block_start + len` of a stream
+fn read_block<T: ElementOwned + Clone>(
+    data_array: &Array<FilesystemStore>,
+    time_array: &Array<FilesystemStore>,
+    is_events: bool,
+    num_channels: usize,
+    block_start: usize,
+    len: usize,
+) -> Result<(Vec<f64>, Vec<Vec<T>>)> {
+    let time_subset = ArraySubset::new_with_start_shape(vec![block_start as u64], vec![len as u64])?;
+    let timestamps = time_array
+        .retrieve_array_subset_ndarray::<f64>(&time_subset)
+        .context("Failed to read timestamps")?;
+    let samples = if is_events {
+        let events = data_array
+            .retrieve_array_subset_ndarray::<T>(&time_subset)
+            .with_context(|| format!("Failed to read events from {}", block_start))?;
+        events.iter().map(|event| vec![event.clone()]).collect()
+    } else {
+        let data_subset =
+            ArraySubset::new_with_start_shape(vec![0, block_start as u64], vec![num_channels as u64, len as u64])?;
+        let block = data_array
+            .retrieve_array_subset_ndarray::<T>(&data_subset)
+            .with_context(|| format!("Failed to read samples from {}", block_start))?;
+        (0..len).map(|i| (0..num_channels).map(|ch| block[[ch, i]].clone()).collect()).collect()
+    };
+    Ok((timestamps.iter().copied().collect(), samples))
+}
+
+/// Push the prefetched blocks of one pass with the recorded timestamps (batch and session replays)
+fn push_blocks<T: ElementOwned + Clone + Send + 'static>(
+    source: &SampleSource,
+    outlet: &StreamOutlet,
+    clock: &ReplayClock,
+    start: Instant,
+    chunk_span: Option<f64>,
+    monitor: &mut PushMonitor,
+) -> Result<usize>
+where
+    StreamOutlet: ExPushable<Vec<T>>,
+{
+    let mut read = 0;
+    for block in prefetch_blocks::<T>(source, false)? {
+        let block = block?;
+        let len = block.samples.len();
+        if let Some(span) = chunk_span {
+            for_each_chunk(block, span, clock, start, |chunk, recorded, lag| {
+                let stamps: Vec<f64> = recorded.iter().map(|&t| clock.stamp(t)).collect();
+                let result = outlet.push_chunk_stamped_ex(&chunk, &stamps, true);
+                track_push(monitor, source.stream_path, result, chunk.len() as u64, lag, start)
+            })?;
+        } else {
+            for (i, (&recorded, sample)) in block.timestamps.iter().zip(&block.samples).enumerate() {
+                wait_until_due(clock, start, recorded);
+                let lag = clock.lag(recorded, start.elapsed());
+                let result = outlet.push_sample_ex(sample, clock.stamp(recorded), i + 1 == len);
+                track_push(monitor, source.stream_path, result, 1, lag, start)?;
+            }
+        }
+        read += len;
+    }
+    Ok(read)
+}
+
+/// Split a block into chunks of `span` seconds of recording and hand each to `push` once its last sample is due
+///
+/// `push` gets the samples, their recorded timestamps and the lag of the last one.
+fn for_each_chunk<T>(
+    block: Block<T>,
+    span: f64,
+    clock: &ReplayClock,
+    start: Instant,
+    mut push: impl FnMut(Vec<Vec<T>>, &[f64], f64) -> Result<()>,
+) -> Result<()> {
+    let mut samples = block.samples.into_iter();
+    for range in chunk_ranges(&block.timestamps, span) {
+        let recorded = &block.timestamps[range.clone()];
+        let last = recorded[recorded.len() - 1];
+        wait_until_due(clock, start, last);
+        let lag = clock.lag(last, start.elapsed());
+        push(samples.by_ref().take(range.len()).collect(), recorded, lag)?;
+    }
+    Ok(())
+}
+
+/// Live replay: push the prefetched blocks at the recorded pace, looping unless --no-loop
+///
+/// Samples are stamped with the LSL clock when pushed. Each loop pass is paced from
+/// its own start, so the passes follow each other without a pause.
+fn replay_blocks<T: ElementOwned + Clone + Send + 'static>(
+    source: &SampleSource,
+    first_timestamp: f64,
+    outlet: &StreamOutlet,
+    args: &Args,
+    monitor: &mut PushMonitor,
+) -> Result<()>
+where
+    StreamOutlet: ExPushable<Vec<T>>,
+{
+    // The origin (LSL clock at the start of a pass) only stamps chunks
+    let mut clock = ReplayClock {
+        first_timestamp,
+        origin: 0.0,
+        speed: args.speed,
+        realtime: true,
+    };
+    let start_time = Instant::now();
+    let mut loop_start = start_time;
+    let mut loop_count = 0;
+
+    for block in prefetch_blocks::<T>(source, args.r#loop)? {
+        let block = block?;
+        if block.pass >= loop_count {
+            if args.verbose && loop_count > 0 {
+                print_loop(loop_count, loop_start, start_time, source.range.len(), monitor);
+            }
+            loop_count = block.pass + 1;
+            if args.verbose {
+                println!("Starting loop iteration {}", loop_count);
+            }
+            loop_start = Instant::now();
+            clock.origin = lsl::local_clock();
+        }
+
+        if let Some(span) = args.chunk_span() {
+            for_each_chunk(block, span, &clock, loop_start, |chunk, recorded, lag| {
+                let stamps: Vec<f64> = recorded.iter().map(|&t| clock.due_stamp(t)).collect();
+                let result = outlet.push_chunk_stamped_ex(&chunk, &stamps, true);
+                track_push(monitor, source.stream_path, result, chunk.len() as u64, lag, start_time)
+            })?;
+            continue;
+        }
+        for (&recorded, sample) in block.timestamps.iter().zip(&block.samples) {
+            wait_until_due(&clock, loop_start, recorded);
+            let lag = clock.lag(recorded, loop_start.elapsed());
+            track_push(monitor, source.stream_path, outlet.push_sample(sample), 1, lag, start_time)?;
+        }
+    }
+    if args.verbose {
+        print_loop(loop_count, loop_start, start_time, source.range.len(), monitor);
+    }
+
+    println!();
+    println!("Replay completed: {} loop(s), {} total samples sent", loop_count, monitor.stats().pushed);
+    println!("Outlet: {}", monitor.stats().summary());
+
+    Ok(())
+}
+
+/// Verbose report at the end of a loop pass
+fn print_loop(loop_count: u64, loop_start: Instant, start_time: Instant, num_samples: usize, monitor: &PushMonitor) {
+    println!(
+        "Loop {} completed in {:.3}s (total: {:.1}s, {} samples sent)",
+        loop_count,
+        loop_start.elapsed().as_secs_f64(),
+        start_time.elapsed().as_secs_f64(),
+        loop_count * num_samples as u64
+    );
+    println!("Outlet: {}", monitor.stats().summary());
+}
+
+fn list_streams(file_path: &str) -> Result<()> {
+    println!("╔════════════════════════════════════════════════════════════════╗");
+    println!("║              Available Streams                                 ║");
+    println!("╚════════════════════════════════════════════════════════════════╝");
+    println!();
+    println!("File: {}", file_path);
+    println!();
+
+    let stream_names = store_stream_names(file_path)?;
+
+    if stream_names.is_empty() {
+        println!("No streams found in Zarr file.");
+    } else {
+        println!("Streams:");
+        for name in &stream_names {
+            println!("\t- {}", name);
+        }
+        println!();
+        println!("Use --stream <name> to replay a specific stream, or --all to replay them together");
+    }
+
+    Ok(())
+}
+
+/// Sorted names of the streams in a store
+fn store_stream_names(file_path: &str) -> Result<Vec<String>> {
+    let streams_path = PathBuf::from(file_path);
+    if !streams_path.exists() || !streams_path.is_dir() {
+        anyhow::bail!("Zarr file not found: {}", file_path);
+    }
+
+    let mut stream_names = Vec::new();
+    for entry in std::fs::read_dir(&streams_path)? {
+        let entry = entry?;
+        let name = entry.file_name().to_string_lossy().to_string();
+        if entry.file_type()?.is_dir() && !is_store_group(&name) {
+            stream_names.push(name);
+        }
+    }
+    stream_names.sort();
+    Ok(stream_names)
+}
+
+fn parse_channel_format(format_str: &str) -> Result<ChannelFormat> {
+    match format_str {
+        "Float32" => Ok(ChannelFormat::Float32),
+        "Float64" => Ok(ChannelFormat::Double64),
+        "Int32" => Ok(ChannelFormat::Int32),
+        "Int16" => Ok(ChannelFormat::Int16),
+        "Int8" => Ok(ChannelFormat::Int8),
+        "String" => Ok(ChannelFormat::String),
+        _ => anyhow::bail!("Unknown channel format: {}", format_str),
+    }
+}
@@ -0,0 +1,1158 @@
+//! LSL Sync - Post-processing timestamp synchronization tool
+//!
+//! This tool aligns timestamps across multiple streams in a Zarr recording,
+//! creating synchronized time arrays for multi-stream analysis.
+//!
+//! # Features
+//!
+//! - Align timestamps across multiple streams
+//! - Multiple alignment modes (common-start, first-stream, last-stream, absolute-zero, drift-correct)
+//! - Linear or piecewise-linear clock-drift correction against a reference stream
+//! - Optional trimming to remove data outside common time window
+//! - Non-destructive: preserves original raw timestamps
+//! - Writes aligned timestamps to `/<name>/aligned_time`
+//! - Stores alignment metadata in Zarr attributes
+//! - Supports any number of streams in a Zarr file
+//! - Optionally materializes the trimmed, aligned samples and retires the raw arrays
+//! - Optionally resamples all regular streams onto a common time grid
+//! - `--clean` removes the outputs of earlier runs, restoring the recorded state
+//!
+//! # Usage
+//!
+//! ```bash
+//! # Synchronize with default mode (common-start)
+//! lsl-sync experiment.zarr
+//!
+//! # Synchronize with trimming (removes data outside common window)
+//! lsl-sync experiment.zarr --trim-both
+//!
+//! # Use different alignment mode
+//! lsl-sync experiment.zarr --mode first-stream
+//! lsl-sync experiment.zarr --mode last-stream
+//! lsl-sync experiment.zarr --mode absolute-zero
+//!
+//! # Correct clock drift against the EEG stream (4 pieces over the session)
+//! lsl-sync experiment.zarr --mode drift-correct --reference EEG --drift-segments 4 --trim-both
+//!
+//! # Trim only start or end
+//! lsl-sync experiment.zarr --trim-start
+//! lsl-sync experiment.zarr --trim-end
+//!
+//! # Only process specific streams (auto-skips invalid streams)
+//! lsl-sync experiment.zarr --stream VHI_Control --stream VHI_Predict
+//!
+//! # Write the trimmed samples with aligned timestamps to /<name>/synced
+//! lsl-sync experiment.zarr --trim-both --materialize
+//!
+//! # ... and replace the raw arrays by them once verified, archiving the raw arrays for 30 days
+//! lsl-sync experiment.zarr --trim-both --materialize --raw-retention archive --archive-dir raw_archive --retain-days 30
+//!
+//! # Resample every regular stream to 250 Hz (anti-aliased), or onto the EEG samples
+//! lsl-sync experiment.zarr --trim-both --resample-to 250
+//! lsl-sync experiment.zarr --trim-both --resample-to-stream EEG
+//!
+//! # Delete expired archives
+//! lsl-sync --purge-expired --archive-dir raw_archive
+//!
+//! # Remove all sync outputs before re-running with other parameters
+//! lsl-sync experiment.zarr --clean
+//! ```
+//!
+//! # Alignment Modes
+//!
+//! - `common-start` (recommended): Align to latest start time where ALL streams have data
+//! - `first-stream`: Align to earliest stream start (may have gaps)
+//! - `last-stream`: Align to latest stream start
+//! - `absolute-zero`: Align to t=0
+//! - `drift-correct`: Like `common-start`, after mapping every regular stream onto the
+//!   clock of a reference stream (`--reference`, default: first regular stream by
+//!   name) with a linear fit of their timestamp difference, or `--drift-segments`
+//!   piecewise-linear fits (see [`crate::drift::DriftModel`]). Offset-only
+//!   alignment leaves several milliseconds of drift in hour-long sessions. Irregular
+//!   streams have no sample clock and are only offset.
+//!
+//! # Output
+//!
+//! For each stream:
+//! - Creates `/<name>/aligned_time` array with synchronized timestamps
+//! - Stores metadata in `/<name>/zarr.json`:
+//!   - `alignment_offset`: Time offset applied
+//!   - `trim_start_index`: Start index if trimmed
+//!   - `trim_end_index`: End index if trimmed
+//!   - `original_sample_count`: Samples before trimming
+//!   - `aligned_sample_count`: Samples after trimming
+//!   - `excluded_ranges`: `[start, end)` sample ranges inside the trim range that
+//!     fall into a pause (`--exclude-pauses`)
+//!   - `drift_correction`: Reference stream and fit parameters (`drift-correct` mode)
+//!
+//! With `--exclude-pauses`, the `PAUSE`/`RESUME` intervals of every synchronized
+//! stream (`pauses` attribute, see [`crate::pauses`]) are left out: a pause at the
+//! start or end moves the trim indices past it, and the samples of any other stream
+//! inside a pause are listed in `excluded_ranges` and not counted as trimmed samples.
+//! A materialized copy is one contiguous range, so the two options do not combine.
+//!
+//! With `--materialize`, `/<name>/synced/{data|events,time,time_index}` hold the
+//! samples within the trim indices with aligned timestamps. With `--raw-retention drop` or
+//! `--raw-retention archive` the synced copy is verified against the raw arrays and then
+//! replaces them; the `raw_retention` attribute records the provenance (see
+//! [`crate::zarr::materialize`]). Streams whose raw arrays were
+//! retired are skipped on later runs.
+//!
+//! With `--resample-to <Hz>` or `--resample-to-stream <name>`, every regular stream is
+//! also interpolated onto a grid covering the common window, written to
+//! `/<name>/resampled_data` (float64, NaN where the stream has no data) and
+//! `/<name>/resampled_time` with a `resampling` attribute (see
+//! [`crate::resample`]).
+//!
+//! `--clean` deletes `aligned_time`, `synced`, `resampled_data` and `resampled_time`
+//! and the attributes above from every stream (or the `--stream` selection) and
+//! exits. A new run only overwrites what it produces itself, so clean before
+//! re-running with other options. Streams whose raw arrays were retired are kept
+//! (see [`crate::zarr::clean`]).
+//!
+//! # Workflow
+//!
+//! ```bash
+//! # 1. Record multiple streams
+//! lsl-multi-recorder --source-ids "id1" "id2" --output experiment
+//!
+//! # 2. Synchronize timestamps
+//! lsl-sync experiment.zarr --mode common-start --trim-both
+//!
+//! # 3. Inspect results
+//! lsl-inspect experiment.zarr --verbose
+//!
+//! # 4. Validate synchronization
+//! lsl-validate experiment.zarr
+//! ```
+
+use anyhow::Result;
+use clap::Parser;
+use crate::drift::{fit_drift_model, DriftModel};
+use crate::pauses::{exclude_pauses, merge_pauses, read_pauses, EXCLUDED_RANGES_ATTRIBUTE};
+use crate::resample::{resample_stream, uniform_grid, GridSource};
+use crate::zarr::cache::{StoreCache, StreamExtent, CACHED_HEAD_SAMPLES};
+use crate::zarr::clean::clean_store;
+use crate::zarr::materialize::{
+    materialize_stream, purge_expired_archives, retire_raw_arrays, RawRetention, RAW_RETENTION_ATTRIBUTE,
+};
+use crate::zarr::{is_store_group, read_group_attributes, stream_sample_count, N_SAMPLES_ATTRIBUTE};
+use ndarray::{Array1, Ix1};
+use serde_json::json;
+use std::collections::HashMap;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use zarrs::array::{Array, ArrayBuilder, DataType, FillValue};
+use zarrs::array::codec::{BloscCodec, BloscCompressionLevel, BloscCompressor, BloscShuffleMode};
+use zarrs::array_subset::ArraySubset;
+use zarrs::filesystem::FilesystemStore;
+
+#[derive(Parser)]
+#[command(name = "lsl-sync")]
+#[command(about = "Synchronize timestamps across streams in a Zarr recording")]
+#[command(version)]
+pub struct Args {
+    /// Path to Zarr file to synchronize
+    #[arg(default_value = "experiment.zarr")]
+    zarr_file: PathBuf,
+
+    /// Alignment mode
+    #[arg(long, default_value = "common-start")]
+    #[arg(value_parser = ["common-start", "first-stream", "last-stream", "absolute-zero", "drift-correct"])]
+    mode: String,
+
+    /// Reference stream for drift correction (defaults to the first regular stream by name)
+    #[arg(long)]
+    reference: Option<String>,
+
+    /// Number of piecewise-linear segments of the drift fit (1 = linear)
+    #[arg(long, default_value = "1")]
+    drift_segments: usize,
+
+    /// Trim data before common start
+    #[arg(long)]
+    trim_start: bool,
+
+    /// Trim data after common end
+    #[arg(long)]
+    trim_end: bool,
+
+    /// Trim both start and end (shorthand for --trim-start --trim-end)
+    #[arg(long)]
+    trim_both: bool,
+
+    /// Leave samples recorded during a PAUSE/RESUME pause of any stream out of the trim range
+    #[arg(long, conflicts_with = "materialize")]
+    exclude_pauses: bool,
+
+    /// Verbose output (show detailed stream information)
+    #[arg(short, long)]
+    verbose: bool,
+
+    /// Only process specific streams (can be specified multiple times)
+    #[arg(long)]
+    stream: Vec<String>,
+
+    /// Also write the trimmed samples with aligned timestamps to /<stream>/synced
+    #[arg(long)]
+    materialize: bool,
+
+    /// What to do with the raw arrays once the materialized copy is verified
+    #[arg(long, value_name = "ACTION", default_value_t = RawRetention::Keep)]
+    raw_retention: RawRetention,
+
+    /// Directory receiving archived raw arrays (<dir>/<store>/<stream>/)
+    #[arg(long, required_if_eq("raw_retention", "archive"))]
+    archive_dir: Option<PathBuf>,
+
+    /// Days after which archived raw arrays may be purged (kept indefinitely if not set)
+    #[arg(long)]
+    retain_days: Option<u32>,
+
+    /// Resample all regular streams onto a uniform grid of this rate (Hz)
+    #[arg(long, conflicts_with = "resample_to_stream")]
+    resample_to: Option<f64>,
+
+    /// Resample all regular streams onto the aligned timestamps of this stream
+    #[arg(long)]
+    resample_to_stream: Option<String>,
+
+    /// Delete archives in --archive-dir whose retention period has expired, then exit
+    #[arg(long, requires = "archive_dir")]
+    purge_expired: bool,
+
+    /// Remove aligned_time, synced and resampled arrays and the alignment attributes, then exit
+    #[arg(long, conflicts_with_all = ["materialize", "resample_to", "resample_to_stream", "purge_expired"])]
+    clean: bool,
+
+    /// Do not store the stream extents read here in the derived-value cache
+    #[arg(long)]
+    no_cache: bool,
+}
+
+#[derive(Debug)]
+struct StreamData {
+    name: String,
+    timestamps: Vec<f64>,
+    sample_count: usize,
+    nominal_srate: f64,  // 0.0 for irregular streams
+    is_irregular: bool,  // true if nominal_srate == 0.0
+}
+
+#[derive(Debug, PartialEq)]
+enum ValidationResult {
+    Valid,
+    InvalidTimestamps(String),  // Reason for invalidity
+    InsufficientSamples(String),
+}
+
+/// Validate stream data for synchronization
+fn validate_stream(stream: &StreamData) -> ValidationResult {
+    // Check for empty stream
+    if stream.sample_count == 0 {
+        return ValidationResult::InsufficientSamples(
+            "No samples recorded".to_string()
+        );
+    }
+
+    // Get first and last timestamps
+    let first_ts = stream.timestamps.first().copied().unwrap_or(0.0);
+    let last_ts = stream.timestamps.last().copied().unwrap_or(0.0);
+
+    // Check for invalid timestamps (suspiciously low values indicating uninitialized data)
+    // LSL timestamps are typically large values (seconds since system boot)
+    if first_ts < 1.0 {
+        return ValidationResult::InvalidTimestamps(
+            format!("First timestamp too low: {:.6}s (likely uninitialized data)", first_ts)
+        );
+    }
+
+    // Check for duplicate timestamps (all same value = likely bogus)
+    // Only flag if multiple samples AND all timestamps are identical
+    if stream.sample_count > 1 && (last_ts - first_ts).abs() < 0.001 {
+        return ValidationResult::InvalidTimestamps(
+            format!("All timestamps identical: {:.6}s (likely bogus data)", first_ts)
+        );
+    }
+
+    ValidationResult::Valid
+}
+
+/// Run `lsl-sync` with parsed arguments
+pub fn run(args: Args) -> Result<()> {
+
+    crate::display_license_notice("lsl-sync");
+
+    if args.purge_expired
+        && let Some(ref archive_dir) = args.archive_dir
+    {
+        let purged = purge_expired_archives(archive_dir)?;
+        println!("Purged {} expired archive(s) from {}", purged.len(), archive_dir.display());
+        for path in &purged {
+            println!("\t- {}", path.display());
+        }
+        return Ok(());
+    }
+
+    if args.clean {
+        return clean(&args);
+    }
+
+    if args.raw_retention != RawRetention::Keep && !args.materialize {
+        anyhow::bail!("--raw-retention {} needs --materialize", args.raw_retention);
+    }
+    if args.resample_to.is_some_and(|rate| rate.is_nan() || rate <= 0.0) {
+        anyhow::bail!("--resample-to must be a positive rate");
+    }
+    // Materialized timestamps are the raw ones minus an origin, which cannot carry a drift correction
+    if args.materialize && args.mode == "drift-correct" {
+        anyhow::bail!("--materialize is not supported with --mode drift-correct");
+    }
+
+    let trim_start = args.trim_start || args.trim_both;
+    let trim_end = args.trim_end || args.trim_both;
+
+    println!("╔════════════════════════════════════════════════════════════════╗");
+    println!("║              LSL Synchronization Tool                          ║");
+    println!("╚════════════════════════════════════════════════════════════════╝");
+    println!();
+    println!("Zarr file: {}", args.zarr_file.display());
+    println!("Mode: {}", args.mode);
+    println!("Trim: start={}, end={}", trim_start, trim_end);
+    if args.exclude_pauses {
+        println!("Exclude pauses: yes");
+    }
+    if args.materialize {
+        println!("Materialize: yes (raw arrays: {})", args.raw_retention);
+    }
+    println!();
+
+    let store = Arc::new(FilesystemStore::new(&args.zarr_file)?);
+    let mut cache = if args.no_cache {
+        StoreCache::disabled(&args.zarr_file)
+    } else {
+        StoreCache::open(&args.zarr_file)
+    };
+
+    // Read all streams
+    println!("Reading streams...");
+    let all_streams = read_streams(&store, &args.zarr_file, &mut cache)?;
+    cache.save_or_warn();
+
+    if all_streams.is_empty() {
+        println!("WARNING: No streams found in Zarr file");
+        return Ok(());
+    }
+
+    let regular_count = all_streams.iter().filter(|s| !s.is_irregular).count();
+    let irregular_count = all_streams.len() - regular_count;
+    println!("\tFound {} stream(s): {} regular, {} irregular",
+             all_streams.len(), regular_count, irregular_count);
+    for stream in &all_streams {
+        let stream_type = if stream.is_irregular { "irregular" } else { "regular" };
+        if args.verbose {
+            let first_ts = stream.timestamps.first().unwrap_or(&0.0);
+            let last_ts = stream.timestamps.last().unwrap_or(&0.0);
+            let duration = last_ts - first_ts;
+            println!("\t- {} ({}): {} samples, {:.3} Hz, t=[{:.6}, {:.6}] ({:.3}s)",
+                     stream.name, stream_type, stream.sample_count,
+                     stream.nominal_srate, first_ts, last_ts, duration);
+        } else {
+            println!("\t- {} ({}): {} samples", stream.name, stream_type, stream.sample_count);
+        }
+    }
+    println!();
+
+    // Filter streams based on --stream flag and validation
+    println!("Validating streams...");
+    let mut streams = Vec::new();
+    let mut skipped_streams = Vec::new();
+
+    for stream in all_streams {
+        // Check if stream is in the user-specified list (if provided)
+        let user_selected = args.stream.is_empty() || args.stream.contains(&stream.name);
+
+        if !user_selected {
+            skipped_streams.push((stream.name.clone(), "Not in --stream list".to_string()));
+            continue;
+        }
+
+        // Validate stream data
+        let validation = validate_stream(&stream);
+        match validation {
+            ValidationResult::Valid => {
+                streams.push(stream);
+            }
+            ValidationResult::InvalidTimestamps(reason) => {
+                skipped_streams.push((stream.name.clone(), reason));
+            }
+            ValidationResult::InsufficientSamples(reason) => {
+                skipped_streams.push((stream.name.clone(), reason));
+            }
+        }
+    }
+
+    // Report skipped streams
+    if !skipped_streams.is_empty() {
+        println!("\tSkipped {} stream(s):", skipped_streams.len());
+        for (name, reason) in &skipped_streams {
+            println!("\t- {}: {}", name, reason);
+        }
+        println!();
+    }
+
+    // Check if we have any valid streams left
+    if streams.is_empty() {
+        println!("ERROR: No valid streams to synchronize after validation");
+        println!("Hint: Use --stream to manually select specific streams");
+        return Ok(());
+    }
+
+    let valid_regular_count = streams.iter().filter(|s| !s.is_irregular).count();
+    let valid_irregular_count = streams.len() - valid_regular_count;
+    println!("\tProcessing {} valid stream(s): {} regular, {} irregular",
+             streams.len(), valid_regular_count, valid_irregular_count);
+    println!();
+
+    let drift_corrections = if args.mode == "drift-correct" {
+        println!("Fitting clock drift...");
+        let corrections = correct_drift(&mut streams, args.reference.as_deref(), args.drift_segments)?;
+        println!();
+        corrections
+    } else {
+        HashMap::new()
+    };
+
+    // Calculate alignment offsets
+    println!("Calculating alignment...");
+    let (reference_time, alignment_offsets) = calculate_alignment(&streams, &args.mode)?;
+
+    if args.verbose {
+        println!("\tReference time: {:.6} s (from {} streams)",
+                 reference_time,
+                 if regular_count > 0 { "regular" } else { "all" });
+    } else {
+        println!("\tReference time: {:.6} s", reference_time);
+    }
+    for (name, offset) in &alignment_offsets {
+        // Display relative timing (when stream started relative to reference)
+        // Positive: started BEFORE reference (earlier)
+        // Negative: started AFTER reference (later)
+        // Note: offset itself is what to ADD for alignment (reference - first_ts)
+        let relative_ms = -offset * 1000.0; // Flip sign for intuitive display
+        let sign = if relative_ms >= 0.0 { "+" } else { "" };
+        if args.verbose {
+            // Find the stream to show aligned time range
+            if let Some(stream) = streams.iter().find(|s| s.name == *name) {
+                let first_aligned = stream.timestamps.first().unwrap_or(&0.0) + offset;
+                let last_aligned = stream.timestamps.last().unwrap_or(&0.0) + offset;
+                println!("\t- {}: {}{}ms relative to ref -> t=[{:.6}, {:.6}] aligned",
+                         name, sign, relative_ms as i32, first_aligned, last_aligned);
+            }
+        } else {
+            println!("\t- {}: {}{}ms relative to reference", name, sign, relative_ms as i32);
+        }
+    }
+    println!();
+
+    // Calculate common time window (based on regular streams only)
+    let (common_start, common_end) = calculate_common_window(&streams, &alignment_offsets);
+    let duration = common_end - common_start;
+    println!("Common window (absolute): {:.6} s -> {:.6} s (duration: {:.3} s)",
+             common_start, common_end, duration);
+    println!("Common window (relative): 0.000000 s -> {:.6} s (after alignment)", duration);
+    println!();
+
+    // Check and warn about irregular streams with events outside common window
+    check_irregular_stream_coverage(&streams, &alignment_offsets, common_start, common_end, trim_start, trim_end);
+
+    // Pauses of any stream apply to all of them
+    let pauses = if args.exclude_pauses {
+        let mut pauses = Vec::new();
+        for stream in &streams {
+            pauses.extend(read_pauses(&read_group_attributes(&store, &stream.name)?));
+        }
+        let pauses = merge_pauses(pauses);
+        let paused: f64 = pauses.iter().map(|(start, end)| end - start).sum();
+        println!("Excluding {} pause(s) ({:.3} s)", pauses.len(), paused);
+        println!();
+        pauses
+    } else {
+        Vec::new()
+    };
+
+    // Write aligned timestamps and sync metadata
+    println!("Writing synchronized data...");
+    let mut trim_ranges = Vec::new();
+    for stream in &streams {
+        let range = write_aligned_timestamps(AlignmentParams {
+            store: &store,
+            stream_name: &stream.name,
+            timestamps: &stream.timestamps,
+            offset: alignment_offsets.get(&stream.name).copied().unwrap_or(0.0),
+            common_start,
+            common_end,
+            trim_start,
+            trim_end,
+            pauses: &pauses,
+            drift_correction: drift_corrections.get(&stream.name),
+        })?;
+        trim_ranges.push((stream.name.as_str(), range));
+        println!("\tDone: {}", stream.name);
+    }
+    println!();
+
+    if args.resample_to.is_some() || args.resample_to_stream.is_some() {
+        resample_streams(&args, &streams, common_start, common_end)?;
+    }
+
+    if args.materialize {
+        println!("Materializing synchronized streams...");
+        for (name, range) in &trim_ranges {
+            let samples = materialize_stream(&args.zarr_file, name, range.clone(), common_start)?;
+            println!("\t{}: {} samples -> /{}/synced", name, samples, name);
+        }
+        println!();
+
+        if args.raw_retention != RawRetention::Keep {
+            println!("Verifying and retiring raw arrays ({})...", args.raw_retention);
+            for (name, range) in &trim_ranges {
+                let archive = retire_raw_arrays(
+                    &args.zarr_file,
+                    name,
+                    range.clone(),
+                    common_start,
+                    args.raw_retention,
+                    args.archive_dir.as_deref(),
+                    args.retain_days,
+                )?;
+                match archive {
+                    Some(path) => println!("\t{}: verified, raw arrays archived to {}", name, path.display()),
+                    None => println!("\t{}: verified, raw arrays dropped", name),
+                }
+            }
+            println!();
+        }
+    }
+
+    println!("Synchronization complete!");
+    println!();
+    println!("Aligned timestamps written to:");
+    println!("\t/<stream>/aligned_time");
+    println!();
+    println!("Alignment metadata written to:");
+    println!("\t/<stream>/zarr.json (attributes)");
+    println!();
+    println!("Use lsl-inspect to view results:");
+    println!("\tlsl-inspect {} --verbose", args.zarr_file.display());
+
+    Ok(())
+}
+
+/// Timestamps of a stream recorded before the `n_samples` attribute existed
+///
+/// The metadata of such stores may lag the chunks on disk, so everything in the
+/// chunk directory is read and trailing fill values (0.0) are taken as unwritten.
+fn read_unlabelled_timestamps(
+    zarr_path: &Path,
+    stream_name: &str,
+    time_array: &Array<FilesystemStore>,
+) -> Result<Vec<f64>> {
+    let chunk_shape = time_array
+        .chunk_grid()
+        .chunk_shape(&[0])?
+        .ok_or_else(|| anyhow::anyhow!("Failed to get chunk shape for {}", stream_name))?;
+    let chunk_size = chunk_shape[0].get() as usize;
+
+    // Find highest chunk by checking chunk directory
+    let time_chunk_dir = zarr_path.join(format!("{}/time/c", stream_name));
+    let mut max_chunk = 0;
+    if time_chunk_dir.exists() {
+        for entry in std::fs::read_dir(&time_chunk_dir)?.flatten() {
+            if let Ok(chunk_idx) = entry.file_name().to_string_lossy().parse::<usize>() {
+                max_chunk = max_chunk.max(chunk_idx);
+            }
+        }
+    }
+
+    let estimated_samples = (max_chunk + 1) * chunk_size;
+    let subset = ArraySubset::new_with_start_shape(vec![0], vec![estimated_samples as u64])?;
+    let mut timestamps: Vec<f64> = time_array.retrieve_array_subset_ndarray::<f64>(&subset)?.into_iter().collect();
+    let sample_count = timestamps.iter().rposition(|&ts| ts != 0.0).map_or(0, |i| i + 1);
+    timestamps.truncate(sample_count);
+    Ok(timestamps)
+}
+
+fn read_streams(store: &Arc<FilesystemStore>, zarr_path: &Path, cache: &mut StoreCache) -> Result<Vec<StreamData>> {
+    if !zarr_path.exists() {
+        return Ok(Vec::new());
+    }
+
+    let mut streams = Vec::new();
+    for entry in std::fs::read_dir(zarr_path)? {
+        let entry = entry?;
+        if !entry.file_type()?.is_dir() {
+            continue;
+        }
+
+        let stream_name = entry.file_name().to_string_lossy().to_string();
+        if is_store_group(&stream_name) {
+            continue;
+        }
+
+        // Raw arrays already replaced by a materialized, aligned copy
+        let attrs = read_group_attributes(store, &stream_name).unwrap_or_else(|_| json!({}));
+        if attrs.get(RAW_RETENTION_ATTRIBUTE).is_some() {
+            println!("\tWARNING: Skipping {} (raw arrays retired, already synchronized)", stream_name);
+            continue;
+        }
+
+        // Read time array
+        let time_path = format!("/{}/time", stream_name);
+        let time_array = Array::<FilesystemStore>::open(store.clone(), &time_path)?;
+
+        let timestamps = if attrs.get(N_SAMPLES_ATTRIBUTE).is_some() {
+            let samples = stream_sample_count(&attrs, time_array.shape()[0]);
+            if samples > 0 {
+                let subset = ArraySubset::new_with_start_shape(vec![0], vec![samples])?;
+                time_array.retrieve_array_subset_ndarray::<f64>(&subset)?.into_iter().collect()
+            } else {
+                Vec::new()
+            }
+        } else {
+            read_unlabelled_timestamps(zarr_path, &stream_name, &time_array)?
+        };
+        let sample_count = timestamps.len();
+
+        if sample_count == 0 {
+            println!("\tWARNING: Skipping {} (no samples)", stream_name);
+            continue;
+        }
+
+        // lsl-validate reuses the extent; older stores whose metadata lags the chunks are left out
+        if sample_count as u64 == time_array.shape()[0]
+            && let Some(entry) = cache.stream_mut(&stream_name)
+        {
+            entry.extent = Some(StreamExtent {
+                samples: sample_count as u64,
+                first: timestamps[0],
+                last: timestamps[sample_count - 1],
+            });
+            entry.head = Some(timestamps.iter().take(CACHED_HEAD_SAMPLES).copied().collect());
+        }
+
+        // Read nominal_srate from stream metadata
+        let stream_group_path = format!("/{}", stream_name);
+        let stream_group = zarrs::group::Group::open(store.clone(), &stream_group_path)?;
+
+        // Try to read from stream_info.nominal_srate first (nested), then fallback to top-level
+        let nominal_srate = stream_group
+            .attributes()
+            .get("stream_info")
+            .and_then(|v| v.get("nominal_srate"))
+            .and_then(|v| v.as_f64())
+            .or_else(|| {
+                stream_group
+                    .attributes()
+                    .get("nominal_srate")
+                    .and_then(|v| v.as_f64())
+            })
+            .unwrap_or(0.0);
+
+        let is_irregular = nominal_srate == 0.0;
+
+        streams.push(StreamData {
+            name: stream_name,
+            timestamps,
+            sample_count,
+            nominal_srate,
+            is_irregular,
+        });
+    }
+
+    Ok(streams)
+}
+
+fn calculate_alignment(streams: &[StreamData], mode: &str) -> Result<(f64, HashMap<String, f64>)> {
+    let mut alignment_offsets = HashMap::new();
+
+    if streams.is_empty() {
+        return Ok((0.0, alignment_offsets));
+    }
+
+    // Only use regular streams for alignment calculation
+    // Irregular streams (events, markers) should not constrain the time window
+    let regular_streams: Vec<_> = streams.iter().filter(|s| !s.is_irregular).collect();
+
+    if regular_streams.is_empty() {
+        println!("\tWARNING: No regular streams found - using all streams for alignment");
+        // Fallback: use all streams if no regular streams exist
+        let reference_time = match mode {
+            "first-stream" => streams.iter().filter_map(|s| s.timestamps.first()).fold(f64::INFINITY, |acc, &x| acc.min(x)),
+            "last-stream" | "common-start" | "drift-correct" => streams.iter().filter_map(|s| s.timestamps.first()).fold(f64::NEG_INFINITY, |acc, &x| acc.max(x)),
+            "absolute-zero" => 0.0,
+            _ => anyhow::bail!("Unknown alignment mode: {}", mode),
+        };
+        for stream in streams {
+            if let Some(&first_timestamp) = stream.timestamps.first() {
+                alignment_offsets.insert(stream.name.clone(), reference_time - first_timestamp);
+            }
+        }
+        return Ok((reference_time, alignment_offsets));
+    }
+
+    let reference_time = match mode {
+        "first-stream" => {
+            // Earliest start time among REGULAR streams only
+            regular_streams
+                .iter()
+                .filter_map(|s| s.timestamps.first())
+                .fold(f64::INFINITY, |acc, &x| acc.min(x))
+        }
+        "last-stream" => {
+            // Latest start time among REGULAR streams only
+            regular_streams
+                .iter()
+                .filter_map(|s| s.timestamps.first())
+                .fold(f64::NEG_INFINITY, |acc, &x| acc.max(x))
+        }
+        "absolute-zero" => 0.0,
+        "common-start" | "drift-correct" => {
+            // Latest start time (where ALL REGULAR streams have data) becomes t=0
+            // Irregular streams do NOT constrain this
+            regular_streams
+                .iter()
+                .filter_map(|s| s.timestamps.first())
+                .fold(f64::NEG_INFINITY, |acc, &x| acc.max(x))
+        }
+        _ => anyhow::bail!("Unknown alignment mode: {}", mode),
+    };
+
+    // Calculate offset for ALL streams (both regular and irregular)
+    // Irregular streams get the same offset but won't be trimmed aggressively
+    for stream in streams {
+        if let Some(&first_timestamp) = stream.timestamps.first() {
+            let offset = reference_time - first_timestamp;
+            alignment_offsets.insert(stream.name.clone(), offset);
+        }
+    }
+
+    Ok((reference_time, alignment_offsets))
+}
+
+/// Drift correction attribute of a stream mapped onto the reference clock
+fn drift_attribute(reference: &str, model: &DriftModel) -> serde_json::Value {
+    let segments: Vec<serde_json::Value> = model
+        .segments
+        .iter()
+        .map(|segment| {
+            json!({
+                "start": segment.start,
+                "end": segment.end,
+                "drift_ppm": segment.fit.slope * 1e6,
+                "intercept": segment.fit.intercept,
+                "r_squared": segment.fit.r_squared,
+                "points": segment.fit.points,
+            })
+        })
+        .collect();
+    json!({
+        "reference": reference,
+        "role": "corrected",
+        "drift_ppm": model.overall.ppm(),
+        "offset": model.overall.offset(),
+        "total_drift": model.overall.total_drift(),
+        "r_squared": model.overall.fit.r_squared,
+        "points": model.overall.fit.points,
+        "span": model.overall.span,
+        "segments": segments,
+        "knots": model.knots.iter().map(|&(x, d)| json!([x, d])).collect::<Vec<_>>(),
+    })
+}
+
+/// Map the timestamps of every regular stream onto the reference stream's clock
+///
+/// Returns the `drift_correction` attribute per stream.
+fn correct_drift(
+    streams: &mut [StreamData],
+    reference: Option<&str>,
+    segments: usize,
+) -> Result<HashMap<String, serde_json::Value>> {
+    let reference_index = match reference {
+        Some(name) => streams
+            .iter()
+            .position(|s| s.name == name)
+            .ok_or_else(|| anyhow::anyhow!("Reference stream '{}' not found among the valid streams", name))?,
+        None => streams
+            .iter()
+            .enumerate()
+            .filter(|(_, s)| !s.is_irregular)
+            .min_by(|a, b| a.1.name.cmp(&b.1.name))
+            .map(|(i, _)| i)
+            .ok_or_else(|| anyhow::anyhow!("drift-correct needs at least one regular stream"))?,
+    };
+    if streams[reference_index].is_irregular {
+        anyhow::bail!("Reference stream '{}' is irregular", streams[reference_index].name);
+    }
+    let reference_name = streams[reference_index].name.clone();
+    let reference_timestamps = streams[reference_index].timestamps.clone();
+    let reference_srate = streams[reference_index].nominal_srate;
+    println!("	Reference: {}", reference_name);
+
+    let mut corrections = HashMap::new();
+    corrections.insert(reference_name.clone(), json!({ "reference": reference_name, "role": "reference" }));
+    for stream in streams.iter_mut() {
+        if stream.name == reference_name {
+            continue;
+        }
+        if stream.is_irregular {
+            println!("	- {}: irregular, offset only", stream.name);
+            continue;
+        }
+        let Some(model) = fit_drift_model(
+            &reference_timestamps,
+            reference_srate,
+            &stream.timestamps,
+            stream.nominal_srate,
+            segments,
+        ) else {
+            println!("	WARNING: {}: too little overlap with {} to fit drift, offset only", stream.name, reference_name);
+            continue;
+        };
+
+        println!(
+            "	- {}: {:+.2} ppm, {:+.3} ms over {:.1} s (R² {:.4}, {} segment(s))",
+            stream.name,
+            model.overall.ppm(),
+            model.overall.total_drift() * 1000.0,
+            model.overall.span,
+            model.overall.fit.r_squared,
+            model.segments.len()
+        );
+        for timestamp in stream.timestamps.iter_mut() {
+            *timestamp = model.correct(*timestamp);
+        }
+        corrections.insert(stream.name.clone(), drift_attribute(&reference_name, &model));
+    }
+    Ok(corrections)
+}
+
+fn calculate_common_window(streams: &[StreamData], alignment_offsets: &HashMap<String, f64>) -> (f64, f64) {
+    if streams.is_empty() {
+        return (0.0, 0.0);
+    }
+
+    // Only use regular streams to calculate common window
+    // Irregular streams should not constrain the time window
+    let regular_streams: Vec<_> = streams.iter().filter(|s| !s.is_irregular).collect();
+
+    if regular_streams.is_empty() {
+        // Fallback: if no regular streams, use all streams
+        let mut common_start = f64::NEG_INFINITY;
+        let mut common_end = f64::INFINITY;
+        for stream in streams {
+            if let Some(&offset) = alignment_offsets.get(&stream.name)
+                && let (Some(&first_ts), Some(&last_ts)) = (stream.timestamps.first(), stream.timestamps.last()) {
+                    common_start = common_start.max(first_ts + offset);
+                    common_end = common_end.min(last_ts + offset);
+                }
+        }
+        return (common_start, common_end.max(common_start));
+    }
+
+    let mut common_start = f64::NEG_INFINITY;
+    let mut common_end = f64::INFINITY;
+
+    // Calculate window based on REGULAR streams only
+    for stream in regular_streams {
+        if let Some(&offset) = alignment_offsets.get(&stream.name)
+            && let (Some(&first_ts), Some(&last_ts)) = (stream.timestamps.first(), stream.timestamps.last()) {
+                let aligned_start = first_ts + offset;
+                let aligned_end = last_ts + offset;
+
+                common_start = common_start.max(aligned_start); // Latest start
+                common_end = common_end.min(aligned_end); // Earliest end
+            }
+    }
+
+    // Ensure common_end is not before common_start
+    if common_end < common_start {
+        common_end = common_start;
+    }
+
+    (common_start, common_end)
+}
+
+fn check_irregular_stream_coverage(
+    streams: &[StreamData],
+    alignment_offsets: &HashMap<String, f64>,
+    common_start: f64,
+    common_end: f64,
+    trim_start: bool,
+    trim_end: bool,
+) {
+    let irregular_streams: Vec<_> = streams.iter().filter(|s| s.is_irregular).collect();
+
+    if irregular_streams.is_empty() {
+        return;
+    }
+
+    let mut warnings = Vec::new();
+
+    for stream in irregular_streams {
+        if let Some(&offset) = alignment_offsets.get(&stream.name) {
+            // Count events outside the common window
+            let mut events_before = 0;
+            let mut events_after = 0;
+            let mut events_inside = 0;
+
+            for &ts in &stream.timestamps {
+                let aligned_ts = ts + offset;
+                if aligned_ts < common_start {
+                    events_before += 1;
+                } else if aligned_ts > common_end {
+                    events_after += 1;
+                } else {
+                    events_inside += 1;
+                }
+            }
+
+            // Warn if trimming is enabled and events would be lost
+            if trim_start && events_before > 0 {
+                warnings.push(format!(
+                    "\t- {}: {} event(s) before common window (will be trimmed)",
+                    stream.name, events_before
+                ));
+            }
+            if trim_end && events_after > 0 {
+                warnings.push(format!(
+                    "\t- {}: {} event(s) after common window (will be trimmed)",
+                    stream.name, events_after
+                ));
+            }
+
+            // Info about event distribution
+            if events_before > 0 || events_after > 0 {
+                let total = stream.timestamps.len();
+                warnings.push(format!(
+                    "\t- {}: {}/{} events inside window, {} before, {} after",
+                    stream.name, events_inside, total, events_before, events_after
+                ));
+            }
+        }
+    }
+
+    if !warnings.is_empty() {
+        println!("Irregular stream event coverage:");
+        for warning in warnings {
+            println!("{}", warning);
+        }
+        println!();
+    }
+}
+
+/// Remove the outputs of earlier runs (--clean)
+fn clean(args: &Args) -> Result<()> {
+    if !args.zarr_file.exists() {
+        anyhow::bail!("{} does not exist", args.zarr_file.display());
+    }
+    println!("Removing synchronization outputs from {}...", args.zarr_file.display());
+    let cleaned = clean_store(&args.zarr_file, &args.stream)?;
+    for stream in &cleaned {
+        if let Some(reason) = &stream.skipped {
+            println!("	{}: skipped ({})", stream.stream, reason);
+        } else if stream.changed() {
+            let removed: Vec<&str> = stream
+                .removed_arrays
+                .iter()
+                .chain(&stream.removed_attributes)
+                .map(String::as_str)
+                .collect();
+            println!("	{}: removed {}", stream.stream, removed.join(", "));
+        } else {
+            println!("	{}: nothing to remove", stream.stream);
+        }
+    }
+    let changed = cleaned.iter().filter(|s| s.changed()).count();
+    println!("Cleaned {} of {} stream(s)", changed, cleaned.len());
+    Ok(())
+}
+
+/// Interpolate every regular stream onto the grid selected by --resample-to(-stream)
+fn resample_streams(args: &Args, streams: &[StreamData], common_start: f64, common_end: f64) -> Result<()> {
+    let duration = common_end - common_start;
+    // Same time axis as aligned_time
+    let aligned = |stream: &StreamData| -> Vec<f64> { stream.timestamps.iter().map(|&t| t - common_start).collect() };
+
+    let (grid, source) = match (&args.resample_to_stream, args.resample_to) {
+        (Some(name), _) => {
+            let stream = streams
+                .iter()
+                .find(|s| &s.name == name)
+                .ok_or_else(|| anyhow::anyhow!("Resampling stream '{}' not found among the valid streams", name))?;
+            if stream.is_irregular {
+                anyhow::bail!("Resampling stream '{}' is irregular", name);
+            }
+            let grid: Vec<f64> = aligned(stream).into_iter().filter(|t| (0.0..=duration).contains(t)).collect();
+            (grid, GridSource::Stream(name.clone(), stream.nominal_srate))
+        }
+        (None, Some(rate)) => (uniform_grid(duration, rate), GridSource::Rate(rate)),
+        (None, None) => return Ok(()),
+    };
+
+    println!(
+        "Resampling onto {} grid points at {} Hz ({:.3} s)...",
+        grid.len(),
+        source.rate(),
+        duration
+    );
+    for stream in streams.iter().filter(|s| !s.is_irregular) {
+        let covered = resample_stream(&args.zarr_file, &stream.name, &aligned(stream), &grid, &source)?;
+        println!("	{}: {}/{} points -> /{}/resampled_data", stream.name, covered, grid.len(), stream.name);
+    }
+    for stream in streams.iter().filter(|s| s.is_irregular) {
+        println!("	{}: irregular, not resampled", stream.name);
+    }
+    println!();
+    Ok(())
+}
+
+struct AlignmentParams<'a> {
+    store: &'a Arc<FilesystemStore>,
+    stream_name: &'a str,
+    timestamps: &'a [f64],
+    offset: f64,
+    common_start: f64,
+    common_end: f64,
+    trim_start: bool,
+    trim_end: bool,
+    /// Merged pauses to leave out (`--exclude-pauses`)
+    pauses: &'a [(f64, f64)],
+    drift_correction: Option<&'a serde_json::Value>,
+}
+
+/// Write `aligned_time` and the alignment attributes; returns the trim range
+fn write_aligned_timestamps(params: AlignmentParams) -> Result<Range<u64>> {
+    let AlignmentParams {
+        store,
+        stream_name,
+        timestamps,
+        offset,
+        common_start,
+        common_end,
+        trim_start,
+        trim_end,
+        pauses,
+        drift_correction,
+    } = params;
+    // Shift timestamps to make common_start = t=0
+    // Streams that started before common_start will have negative timestamps
+    let aligned_timestamps: Vec<f64> = timestamps
+        .iter()
+        .map(|&t| t - common_start)
+        .collect();
+
+    // Determine trim indices (common_start is now at t=0, common_end is relative to t=0)
+    let relative_common_end = common_end - common_start;
+    let (trim_start_idx, trim_end_idx) = if trim_start || trim_end {
+        let start_idx = if trim_start {
+            aligned_timestamps
+                .iter()
+                .position(|&t| t >= 0.0)  // common_start is now at t=0
+                .unwrap_or(0)
+        } else {
+            0
+        };
+
+        let end_idx = if trim_end {
+            aligned_timestamps
+                .iter()
+                .rposition(|&t| t <= relative_common_end)
+                .map(|i| i + 1)
+                .unwrap_or(aligned_timestamps.len())
+        } else {
+            aligned_timestamps.len()
+        };
+
+        (start_idx, end_idx)
+    } else {
+        (0, aligned_timestamps.len())
+    };
+
+    // Pauses at the edges move the trim indices, the others are listed separately
+    let (trim_range, excluded) = exclude_pauses(timestamps, pauses, trim_start_idx..trim_end_idx);
+    let (trim_start_idx, trim_end_idx) = (trim_range.start, trim_range.end);
+    let excluded_count: usize = excluded.iter().map(|r| r.len()).sum();
+
+    // Write ALL aligned timestamps (no trimming - Python will use indices)
+    let final_timestamps = &aligned_timestamps;
+
+    // Write to /<stream>/aligned_time (right next to the raw time array)
+    let stream_path = format!("/{}", stream_name);
+    let aligned_time_path = format!("{}/aligned_time", stream_path);
+
+    // Create Blosc codec with BitShuffle for optimal float64 compression
+    let compression_level = BloscCompressionLevel::try_from(5u8)
+        .map_err(|e| anyhow::anyhow!("Invalid compression level: {}", e))?;
+    let blosc_codec = Arc::new(BloscCodec::new(
+        BloscCompressor::LZ4,
+        compression_level,
+        None,  // blocksize (auto-detect)
+        BloscShuffleMode::BitShuffle,  // BitShuffle for float64 timestamps
+        Some(8),  // typesize: 8 bytes for float64
+    )?);
+
+    let array = ArrayBuilder::new(
+        vec![final_timestamps.len() as u64],
+        vec![100],
+        DataType::Float64,
+        FillValue::from(0.0f64),
+    )
+    .bytes_to_bytes_codecs(vec![blosc_codec])
+    .build(store.clone(), &aligned_time_path)?;
+
+    array.store_metadata()?;
+
+    // Write data
+    let data_array = Array1::from(final_timestamps.to_vec());
+    array.store_array_subset_ndarray::<f64, Ix1>(&[0], data_array)?;
+
+    // Write alignment metadata as attributes to the stream group
+    let stream_group_path = format!("/{}", stream_name);
+    let mut stream_group = zarrs::group::Group::open(store.clone(), &stream_group_path)?;
+
+    // Add alignment metadata (trim indices for Python to use, but no actual trimming)
+    let mut attrs = serde_json::Map::new();
+    attrs.insert("alignment_offset".to_string(), json!(offset));
+    attrs.insert("trim_start_index".to_string(), json!(trim_start_idx));
+    attrs.insert("trim_end_index".to_string(), json!(trim_end_idx));
+    attrs.insert("original_sample_count".to_string(), json!(timestamps.len()));
+    // Note: Arrays are NOT trimmed - Python should use trim indices
+    attrs.insert("trimmed_sample_count".to_string(), json!(trim_end_idx - trim_start_idx - excluded_count));
+
+    stream_group.attributes_mut().extend(attrs);
+    if excluded.is_empty() {
+        stream_group.attributes_mut().remove(EXCLUDED_RANGES_ATTRIBUTE);
+    } else {
+        let ranges: Vec<[usize; 2]> = excluded.iter().map(|r| [r.start, r.end]).collect();
+        stream_group
+            .attributes_mut()
+            .insert(EXCLUDED_RANGES_ATTRIBUTE.to_string(), json!(ranges));
+    }
+    // A correction from an earlier drift-correct run no longer applies
+    match drift_correction {
+        Some(correction) => {
+            stream_group
+                .attributes_mut()
+                .insert("drift_correction".to_string(), correction.clone());
+        }
+        None => {
+            stream_group.attributes_mut().remove("drift_correction");
+        }
+    }
+    stream_group.store_metadata()?;
+
+    Ok(trim_start_idx as u64..trim_end_idx as u64)
+}
+
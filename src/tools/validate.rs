@@ -0,0 +1,1204 @@
+//! LSL Validate - Synchronization quality analyzer for Zarr recordings
+//!
+//! This tool analyzes Zarr files to validate timing accuracy, synchronization quality,
+//! and LSL timestamp consistency across multiple streams.
+//!
+//! # Features
+//!
+//! - Analyze timing accuracy and drift for each stream
+//! - Validate LSL timestamp consistency
+//! - Check synchronization quality across multiple streams
+//! - Detect timing gaps and dropouts (intervals over `--gap-factor` nominal periods)
+//! - Set apart gaps caused by clock jumps the recorder noticed (suspend/resume,
+//!   NTP steps; `clock_jumps` attribute) instead of counting them as dropouts
+//! - Fit the clock drift between every pair of regular streams over the whole recording
+//! - Report sample rate accuracy
+//! - Calculate inter-stream timing offsets
+//! - Identify alignment quality metrics
+//! - Study mode (`--study-dir`): compare channel counts, rates and formats of
+//!   identically named streams across all sessions of a study and flag outliers
+//!
+//! # Usage
+//!
+//! ```bash
+//! # Validate default file (experiment.zarr)
+//! lsl-validate
+//!
+//! # Validate specific file
+//! lsl-validate recording.zarr
+//!
+//! # Typical workflow after synchronization
+//! lsl-sync experiment.zarr --mode common-start --trim-both
+//! lsl-validate experiment.zarr
+//!
+//! # Flag intervals over 1.5 nominal periods and list every gap
+//! lsl-validate experiment.zarr --gap-factor 1.5 --verbose
+//!
+//! # Warn when any two streams drift apart by more than 20 ppm
+//! lsl-validate experiment.zarr --drift-budget-ppm 20
+//!
+//! # Gate an acquisition pipeline on a 50 ms threshold with a JSON report
+//! lsl-validate experiment.zarr --threshold-ms 50 --format json > validation.json
+//!
+//! # Validate a store served over HTTP (needs the `http` feature)
+//! lsl-validate https://data.lab.org/sessions/P001.zarr
+//!
+//! # Find sessions whose montage or rate differs from the rest of the study
+//! lsl-validate --study-dir data/
+//!
+//! # Only compare channel counts
+//! lsl-validate --study-dir data/ --study-check channels
+//! ```
+//!
+//! Stores given as `http://` or `https://` URLs are validated from a local cache
+//! (`--cache-dir`) holding their metadata and timestamps only (see
+//! [`crate::zarr::http`]).
+//!
+//! Extents, gap reports and drift fits are kept in the derived-value cache (see
+//! [`crate::zarr::cache`]), so validating an unchanged store again only reads the
+//! time arrays of streams whose values are missing; `--no-cache` recomputes them.
+//!
+//! # Exit Codes
+//!
+//! - `0` - all streams are synchronized within `--threshold-ms`
+//! - `1` - synchronization failed (or an unexpected error occurred)
+//! - `2` - no stream could be loaded
+//!
+//! In study mode, `1` means outliers were found and `2` that the directory holds no stores.
+//!
+//! With `--format json` or `--format yaml`, only the report is written to stdout.
+//!
+//! # Output Metrics
+//!
+//! For each stream:
+//! - Nominal vs. actual sample rate
+//! - Timing drift and jitter
+//! - Timestamp range and duration
+//! - Sample count and missing data
+//! - Gap count, total lost time and largest gap (each gap's timestamps with `--verbose`)
+//!
+//! For multi-stream recordings:
+//! - Inter-stream time offsets
+//! - Clock drift per stream pair: slope (ppm), intercept (offset) and R² of a linear
+//!   fit of the timestamp difference over the recording, with a warning when the
+//!   drift exceeds `--drift-budget-ppm` (warnings do not change the exit code)
+//! - Synchronization quality score
+//! - Common time window overlap
+//! - Alignment accuracy
+
+use anyhow::Result;
+use clap::Parser;
+use crate::clock_jumps::{read_clock_jumps, split_gaps, ClockJump};
+use crate::drift::{fit_drift, DriftFit, DEFAULT_DRIFT_BUDGET_PPM};
+use crate::gaps::{find_gaps, Gap, GapReport, DEFAULT_GAP_FACTOR};
+use crate::study::{check_study, StudyCheck, StudyReport, ALL_STUDY_CHECKS};
+use crate::zarr::cache::{stream_key, CachedDrift, StoreCache, StreamExtent, StreamFingerprint, CACHED_HEAD_SAMPLES};
+use crate::zarr::http::{fetch_http_store, is_http, HttpFetchOptions};
+use crate::zarr::{
+    is_store_group, read_group_attributes, sample_array_path, stream_sample_count, RecorderConfig, StreamInfoAttribute,
+};
+use serde_json::{json, Value};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use zarrs::array::Array;
+use zarrs::array_subset::ArraySubset;
+use zarrs::filesystem::FilesystemStore;
+
+#[derive(Parser)]
+#[command(name = "lsl-validate")]
+#[command(about = "Validate synchronization quality of Zarr recordings")]
+#[command(version)]
+pub struct Args {
+    /// Zarr stores to validate
+    #[arg(default_values_t = ["experiment_EMG.zarr".to_string(), "experiment_EEG.zarr".to_string()])]
+    stores: Vec<String>,
+
+    /// Output format of the report
+    #[arg(long, value_enum, default_value_t = ReportFormat::Text)]
+    format: ReportFormat,
+
+    /// Largest start/end/drift difference (ms) still considered synchronized
+    #[arg(long, default_value = "200.0")]
+    threshold_ms: f64,
+
+    /// Report intervals longer than this many nominal sample periods as gaps
+    #[arg(long, default_value_t = DEFAULT_GAP_FACTOR)]
+    gap_factor: f64,
+
+    /// Warn when the clock drift between two streams exceeds this many ppm
+    #[arg(long, default_value_t = DEFAULT_DRIFT_BUDGET_PPM)]
+    drift_budget_ppm: f64,
+
+    /// List the timestamps of every gap
+    #[arg(short, long)]
+    verbose: bool,
+
+    /// Cache directory for stores read over HTTP (default: <tmp>/lsl-http-cache)
+    #[arg(long)]
+    cache_dir: Option<PathBuf>,
+
+    /// Recompute extents, gaps and drift instead of reusing those of earlier runs
+    #[arg(long)]
+    no_cache: bool,
+
+    /// Compare identically named streams across all stores below this directory instead
+    #[arg(long, conflicts_with = "stores")]
+    study_dir: Option<PathBuf>,
+
+    /// Study mode: property to compare (can be repeated; all if omitted)
+    #[arg(long = "study-check", value_enum, requires = "study_dir")]
+    study_checks: Vec<StudyCheck>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+enum ReportFormat {
+    Text,
+    Json,
+    Yaml,
+}
+
+/// Exit code when synchronization fails
+const EXIT_NOT_SYNCHRONIZED: i32 = 1;
+/// Exit code when no stream could be loaded
+const EXIT_NO_DATA: i32 = 2;
+/// Exit code when a study has outlier sessions
+const EXIT_STUDY_OUTLIERS: i32 = 1;
+
+#[derive(Debug, Clone)]
+struct StreamData {
+    name: String,
+    store_path: String,
+    /// Local store directory (the HTTP cache for remote stores)
+    local_path: PathBuf,
+    /// Time array contents the cached values belong to
+    fingerprint: Option<StreamFingerprint>,
+    /// Full time array; only read when a value is not cached
+    timestamps: Vec<f64>,
+    /// First timestamps, for comparing the starts of the streams
+    head: Vec<f64>,
+    data_shape: (usize, usize), // (channels, samples)
+    stream_info: StreamInfoAttribute,
+    recorder_config: Option<RecorderConfig>,
+    start_time: f64,
+    end_time: f64,
+    duration: f64,
+    sample_count: usize,
+    nominal_sample_rate: f64,
+    actual_sample_rate: f64,
+    channel_count: usize,
+    channel_format: String,
+    /// Gaps not explained by a clock jump
+    gaps: GapReport,
+    /// Clock jumps the recorder noticed (`clock_jumps` attribute)
+    clock_jumps: Vec<ClockJump>,
+    /// Gaps that coincide with one of the clock jumps
+    clock_jump_gaps: Vec<Gap>,
+}
+
+impl StreamData {
+    fn new(name: String, store_path: String, local_path: PathBuf) -> Self {
+        Self {
+            name,
+            store_path,
+            local_path,
+            fingerprint: None,
+            timestamps: Vec::new(),
+            head: Vec::new(),
+            data_shape: (0, 0),
+            stream_info: StreamInfoAttribute::default(),
+            recorder_config: None,
+            start_time: 0.0,
+            end_time: 0.0,
+            duration: 0.0,
+            sample_count: 0,
+            nominal_sample_rate: 0.0,
+            actual_sample_rate: 0.0,
+            channel_count: 0,
+            channel_format: String::new(),
+            gaps: GapReport::default(),
+            clock_jumps: Vec::new(),
+            clock_jump_gaps: Vec::new(),
+        }
+    }
+}
+
+/// Clock drift of `stream` relative to `reference`
+#[derive(Debug, Clone)]
+struct PairDrift {
+    reference: String,
+    stream: String,
+    drift: DriftFit,
+}
+
+#[derive(Debug)]
+struct SyncAnalysis {
+    streams: Vec<StreamData>,
+    start_time_diff: f64,     // Max - Min start times
+    end_time_diff: f64,       // Max - Min end times
+    duration_diff: f64,       // Max - Min durations
+    max_timestamp_drift: f64, // Maximum drift between streams
+    is_synchronized: bool,
+    sync_threshold: f64, // Threshold for considering streams synchronized
+    drift: Vec<PairDrift>,
+    drift_budget_ppm: f64,
+}
+
+fn load_zarr_stream_data(store_path: &str, cache: &mut StoreCache) -> Result<Vec<StreamData>> {
+    let path = Path::new(store_path);
+    if !path.exists() || !path.is_dir() {
+        return Err(anyhow::anyhow!("Store not found or not a directory: {}", store_path));
+    }
+
+    let store = Arc::new(FilesystemStore::new(store_path)?);
+    let mut streams = Vec::new();
+
+    for entry in std::fs::read_dir(path)? {
+        let entry = entry?;
+        if !entry.file_type()?.is_dir() {
+            continue;
+        }
+
+        let stream_name = entry.file_name().to_string_lossy().to_string();
+        if is_store_group(&stream_name) {
+            continue;
+        }
+        let mut stream_data = StreamData::new(stream_name.clone(), store_path.to_string(), path.to_path_buf());
+        stream_data.fingerprint = cache.fingerprint(&stream_name);
+
+        let stream_path = format!("/{}", stream_name);
+
+        // Extent from an earlier run, else from the full time array
+        let cached = cache.stream(&stream_name).and_then(|c| Some((c.extent?, c.head.clone()?)));
+        if let Some((extent, head)) = cached {
+            stream_data.head = head;
+            set_extent(&mut stream_data, extent.samples as usize, extent.first, extent.last);
+        } else if let Some(timestamps) = read_timestamps(&store, &stream_name)? {
+            stream_data.head = timestamps.iter().take(CACHED_HEAD_SAMPLES).copied().collect();
+            if let (Some(&first), Some(&last)) = (timestamps.first(), timestamps.last()) {
+                set_extent(&mut stream_data, timestamps.len(), first, last);
+                if let Some(entry) = cache.stream_mut(&stream_name) {
+                    entry.extent = Some(StreamExtent { samples: timestamps.len() as u64, first, last });
+                    entry.head = Some(stream_data.head.clone());
+                }
+            }
+            stream_data.timestamps = timestamps;
+        }
+
+        // Load data shape (1-D events arrays hold a single marker channel)
+        let data_array_path = sample_array_path(&store, &stream_name);
+        if let Ok(data_array) = Array::<FilesystemStore>::open(store.clone(), &data_array_path) {
+            let shape = data_array.shape();
+            stream_data.data_shape = match shape {
+                [samples] => (1, *samples as usize),
+                _ => (shape[0] as usize, shape[1] as usize), // (channels, samples)
+            };
+            stream_data.channel_count = stream_data.data_shape.0;
+        }
+
+        // Load attributes from stream group (Zarr v3 format)
+        if let Ok(attrs) = read_group_attributes(&store, &stream_path) {
+            if let Some(stream_info) = StreamInfoAttribute::from_attributes(&attrs) {
+                stream_data.nominal_sample_rate = stream_info.srate();
+                if let Some(ref channel_format) = stream_info.channel_format {
+                    stream_data.channel_format = channel_format.clone();
+                }
+                stream_data.stream_info = stream_info;
+            }
+            stream_data.recorder_config = RecorderConfig::from_attributes(&attrs);
+            stream_data.clock_jumps = read_clock_jumps(&attrs);
+        }
+
+        streams.push(stream_data);
+    }
+
+    Ok(streams)
+}
+
+/// All recorded timestamps of a stream (`None` if it has no time array)
+fn read_timestamps(store: &Arc<FilesystemStore>, stream_name: &str) -> Result<Option<Vec<f64>>> {
+    let Ok(time_array) = Array::<FilesystemStore>::open(store.clone(), &format!("/{}/time", stream_name)) else {
+        return Ok(None);
+    };
+    let attrs = read_group_attributes(store, stream_name).unwrap_or_default();
+    let samples = stream_sample_count(&attrs, time_array.shape()[0]);
+    if samples == 0 {
+        return Ok(Some(Vec::new()));
+    }
+    #[allow(clippy::single_range_in_vec_init)]
+    let time_subset = ArraySubset::new_with_ranges(&[0..samples]);
+    let timestamps = time_array.retrieve_array_subset_ndarray::<f64>(&time_subset)?;
+    Ok(Some(timestamps.into_raw_vec_and_offset().0))
+}
+
+/// Read the full time array of a stream whose extent came from the cache
+fn load_timestamps(stream: &mut StreamData) -> Result<()> {
+    if stream.timestamps.len() == stream.sample_count {
+        return Ok(());
+    }
+    let store = Arc::new(FilesystemStore::new(&stream.local_path)?);
+    stream.timestamps = read_timestamps(&store, &stream.name)?.unwrap_or_default();
+    Ok(())
+}
+
+fn set_extent(stream: &mut StreamData, samples: usize, first: f64, last: f64) {
+    stream.sample_count = samples;
+    stream.start_time = first;
+    stream.end_time = last;
+    stream.duration = last - first;
+    if samples > 1 {
+        stream.actual_sample_rate = (samples - 1) as f64 / stream.duration;
+    }
+}
+
+/// Gap report of every stream, from the cache where the stream was checked before
+///
+/// Gaps that coincide with a recorded clock jump are set apart from the dropouts.
+fn analyze_gaps(streams: &mut [StreamData], caches: &mut BTreeMap<PathBuf, StoreCache>, factor: f64) -> Result<()> {
+    for stream in streams.iter_mut() {
+        // Irregular streams never gap
+        if stream.nominal_sample_rate <= 0.0 {
+            continue;
+        }
+        let cache = caches.get_mut(&stream.local_path);
+        let cached = cache
+            .as_ref()
+            .and_then(|c| c.stream(&stream.name))
+            .and_then(|c| c.gaps(factor, stream.nominal_sample_rate))
+            .cloned();
+        let report = match cached {
+            Some(report) => report,
+            None => {
+                load_timestamps(stream)?;
+                let report = find_gaps(&stream.timestamps, stream.nominal_sample_rate, factor);
+                if let Some(entry) = cache.and_then(|c| c.stream_mut(&stream.name)) {
+                    entry.set_gaps(factor, stream.nominal_sample_rate, report.clone());
+                }
+                report
+            }
+        };
+        (stream.gaps, stream.clock_jump_gaps) = split_gaps(&report, &stream.clock_jumps);
+    }
+    Ok(())
+}
+
+/// Drift fit of every pair of regular streams (irregular streams have no sample clock)
+///
+/// Pairs fitted by an earlier run on the same timestamps are taken from the cache.
+fn analyze_drift(streams: &mut [StreamData], caches: &mut BTreeMap<PathBuf, StoreCache>) -> Result<Vec<PairDrift>> {
+    let mut pairs = Vec::new();
+    for i in 0..streams.len() {
+        for j in i + 1..streams.len() {
+            let (reference, other) = (&streams[i], &streams[j]);
+            let (reference_srate, other_srate) = (reference.nominal_sample_rate, other.nominal_sample_rate);
+            if reference_srate <= 0.0 || other_srate <= 0.0 {
+                continue;
+            }
+            let other_key = stream_key(&other.local_path, &other.name);
+            let other_fingerprint = other.fingerprint;
+            let cached = other_fingerprint.and_then(|fingerprint| {
+                caches
+                    .get(&reference.local_path)?
+                    .stream(&reference.name)?
+                    .drift(&other_key, fingerprint, reference_srate, other_srate)
+            });
+
+            let fit = match cached {
+                Some(fit) => fit,
+                None => {
+                    load_timestamps(&mut streams[i])?;
+                    load_timestamps(&mut streams[j])?;
+                    let (reference, other) = (&streams[i], &streams[j]);
+                    let fit = fit_drift(&reference.timestamps, reference_srate, &other.timestamps, other_srate);
+                    if let Some(other_fingerprint) = other_fingerprint
+                        && let Some(entry) = caches
+                            .get_mut(&reference.local_path)
+                            .and_then(|c| c.stream_mut(&reference.name))
+                    {
+                        entry.set_drift(CachedDrift {
+                            other: other_key,
+                            other_fingerprint,
+                            reference_srate,
+                            other_srate,
+                            fit,
+                        });
+                    }
+                    fit
+                }
+            };
+            if let Some(drift) = fit {
+                pairs.push(PairDrift {
+                    reference: streams[i].name.clone(),
+                    stream: streams[j].name.clone(),
+                    drift,
+                });
+            }
+        }
+    }
+    Ok(pairs)
+}
+
+fn analyze_synchronization(
+    streams: &[StreamData],
+    drift: Vec<PairDrift>,
+    sync_threshold: f64,
+    drift_budget_ppm: f64,
+) -> SyncAnalysis {
+    if streams.is_empty() {
+        return SyncAnalysis {
+            streams: streams.to_vec(),
+            start_time_diff: 0.0,
+            end_time_diff: 0.0,
+            duration_diff: 0.0,
+            max_timestamp_drift: 0.0,
+            is_synchronized: false,
+            sync_threshold,
+            drift,
+            drift_budget_ppm,
+        };
+    }
+
+    // Calculate timing differences
+    let start_times: Vec<f64> = streams.iter().map(|s| s.start_time).collect();
+    let end_times: Vec<f64> = streams.iter().map(|s| s.end_time).collect();
+    let durations: Vec<f64> = streams.iter().map(|s| s.duration).collect();
+
+    let start_time_diff = start_times.iter().fold(f64::NAN, |a, &b| a.max(b))
+        - start_times.iter().fold(f64::INFINITY, |a, &b| a.min(b));
+    let end_time_diff = end_times.iter().fold(f64::NAN, |a, &b| a.max(b))
+        - end_times.iter().fold(f64::INFINITY, |a, &b| a.min(b));
+    let duration_diff = durations.iter().fold(f64::NAN, |a, &b| a.max(b))
+        - durations.iter().fold(f64::INFINITY, |a, &b| a.min(b));
+
+    // Calculate maximum timestamp drift between streams
+    let mut max_drift: f64 = 0.0;
+    if streams.len() > 1 {
+        let min_length = streams
+            .iter()
+            .map(|s| s.head.len())
+            .min()
+            .unwrap_or(0);
+        for i in 0..min_length.min(CACHED_HEAD_SAMPLES) {
+            // Check first 100 samples for drift
+            let mut sample_times = Vec::new();
+            for stream in streams {
+                if i < stream.head.len() {
+                    sample_times.push(stream.head[i]);
+                }
+            }
+            if sample_times.len() > 1 {
+                let max_time = sample_times.iter().fold(f64::NAN, |a, &b| a.max(b));
+                let min_time = sample_times.iter().fold(f64::INFINITY, |a, &b| a.min(b));
+                max_drift = max_drift.max(max_time - min_time);
+            }
+        }
+    }
+
+    let is_synchronized = start_time_diff < sync_threshold
+        && end_time_diff < sync_threshold
+        && max_drift < sync_threshold;
+
+    SyncAnalysis {
+        streams: streams.to_vec(),
+        start_time_diff,
+        end_time_diff,
+        duration_diff,
+        max_timestamp_drift: max_drift,
+        is_synchronized,
+        sync_threshold,
+        drift,
+        drift_budget_ppm,
+    }
+}
+
+/// Why the streams count as not synchronized (empty if they are)
+fn failure_reasons(analysis: &SyncAnalysis) -> Vec<String> {
+    let threshold_ms = analysis.sync_threshold * 1000.0;
+    let mut reasons = Vec::new();
+    if analysis.start_time_diff >= analysis.sync_threshold {
+        reasons.push(format!(
+            "Start time difference ({:.1}ms) exceeds {:.0}ms threshold",
+            analysis.start_time_diff * 1000.0,
+            threshold_ms
+        ));
+    }
+    if analysis.end_time_diff >= analysis.sync_threshold {
+        reasons.push(format!(
+            "End time difference ({:.1}ms) exceeds {:.0}ms threshold",
+            analysis.end_time_diff * 1000.0,
+            threshold_ms
+        ));
+    }
+    if analysis.max_timestamp_drift >= analysis.sync_threshold {
+        reasons.push(format!(
+            "Timestamp drift ({:.1}ms) exceeds {:.0}ms threshold",
+            analysis.max_timestamp_drift * 1000.0,
+            threshold_ms
+        ));
+    }
+    reasons
+}
+
+/// Stream pairs whose clock drift exceeds the budget
+fn drift_warnings(analysis: &SyncAnalysis) -> Vec<String> {
+    analysis
+        .drift
+        .iter()
+        .filter(|pair| pair.drift.ppm().abs() > analysis.drift_budget_ppm)
+        .map(|pair| {
+            format!(
+                "Clock drift of {} vs {} ({:+.1} ppm) exceeds {:.1} ppm budget",
+                pair.stream,
+                pair.reference,
+                pair.drift.ppm(),
+                analysis.drift_budget_ppm
+            )
+        })
+        .collect()
+}
+
+fn rate_accuracy(stream: &StreamData) -> f64 {
+    if stream.nominal_sample_rate > 0.0 {
+        (stream.actual_sample_rate / stream.nominal_sample_rate) * 100.0
+    } else {
+        0.0
+    }
+}
+
+fn print_stream_info(stream: &StreamData, verbose: bool) {
+    println!("Stream: {}", stream.name);
+    println!("\tStore:\t\t{}", stream.store_path);
+    println!(
+        "\tData shape:\t{:?} (channels × samples)",
+        stream.data_shape
+    );
+    println!("\tChannels:\t{}", stream.channel_count);
+    println!("\tSample count:\t{}", stream.sample_count);
+    println!("\tDuration:\t{:.3} seconds", stream.duration);
+    println!("\tNominal rate:\t{:.1} Hz", stream.nominal_sample_rate);
+    println!("\tActual rate:\t{:.1} Hz", stream.actual_sample_rate);
+
+    println!("\tRate accuracy:\t{:.2}%", rate_accuracy(stream));
+    println!("\tChannel format:\t{}", stream.channel_format);
+    print_gaps(stream, verbose);
+    print_clock_jumps(stream);
+
+    // Timing information
+    println!("\tStart time:\t{:.6}", stream.start_time);
+    println!("\tEnd time:\t{:.6}", stream.end_time);
+
+    // Extract some key metadata if available
+    if let Some(ref source_id) = stream.stream_info.source_id {
+        println!("\tSource ID:\t{}", source_id);
+    }
+    if let Some(ref hostname) = stream.stream_info.hostname {
+        println!("\tHostname:\t{}", hostname);
+    }
+    if let Some(version) = stream.recorder_config.as_ref().and_then(|c| c.recorder_version.as_deref()) {
+        println!("\tRecorder:\tv{}", version);
+    }
+
+    println!();
+}
+
+fn print_gaps(stream: &StreamData, verbose: bool) {
+    if stream.nominal_sample_rate <= 0.0 {
+        println!("\tGaps:\t\tnot checked (irregular stream)");
+        return;
+    }
+    let Some(largest) = stream.gaps.largest else {
+        println!("\tGaps:\t\tnone");
+        return;
+    };
+    println!(
+        "\tGaps:\t\t{} (lost {:.1} ms, largest {:.1} ms at {:.6})",
+        stream.gaps.count(),
+        stream.gaps.lost_time * 1000.0,
+        largest.interval() * 1000.0,
+        largest.start
+    );
+    if verbose {
+        for gap in &stream.gaps.gaps {
+            println!(
+                "\t\t{:.6} → {:.6}\t{:.1} ms (sample {})",
+                gap.start,
+                gap.end,
+                gap.interval() * 1000.0,
+                gap.index
+            );
+        }
+    }
+}
+
+/// Clock jumps, and the gaps set apart because of them
+fn print_clock_jumps(stream: &StreamData) {
+    if stream.clock_jumps.is_empty() {
+        return;
+    }
+    println!("\tClock jumps:\t{}", stream.clock_jumps.len());
+    for jump in &stream.clock_jumps {
+        println!("\t\t{}", jump);
+    }
+    for gap in &stream.clock_jump_gaps {
+        println!(
+            "\t\tgap {:.6} → {:.6}\t{:.1} ms caused by a clock jump, not counted as lost",
+            gap.start,
+            gap.end,
+            gap.interval() * 1000.0
+        );
+    }
+}
+
+fn gap_json(gap: &Gap) -> Value {
+    json!({
+        "index": gap.index,
+        "start": gap.start,
+        "end": gap.end,
+        "interval_ms": gap.interval() * 1000.0,
+        "lost_ms": gap.lost * 1000.0,
+    })
+}
+
+fn print_sync_analysis(analysis: &SyncAnalysis) {
+    println!("SYNCHRONIZATION ANALYSIS");
+    println!("========================");
+
+    if analysis.is_synchronized {
+        println!("Status:\t\t\tSYNCHRONIZED");
+    } else {
+        println!("Status:\t\t\tNOT SYNCHRONIZED");
+    }
+
+    println!(
+        "Sync threshold:\t\t{:.1} ms",
+        analysis.sync_threshold * 1000.0
+    );
+    println!();
+
+    println!("TIMING ANALYSIS:");
+    println!(
+        "\tStart time difference:\t{:.1} ms",
+        analysis.start_time_diff * 1000.0
+    );
+    println!(
+        "\tEnd time difference:\t{:.1} ms",
+        analysis.end_time_diff * 1000.0
+    );
+    println!(
+        "\tDuration difference:\t{:.1} ms",
+        analysis.duration_diff * 1000.0
+    );
+    println!(
+        "\tMax timestamp drift:\t{:.1} ms",
+        analysis.max_timestamp_drift * 1000.0
+    );
+
+    if !analysis.drift.is_empty() {
+        println!();
+        println!("CLOCK DRIFT (linear fit over full recording):");
+        for pair in &analysis.drift {
+            println!(
+                "\t{} vs {}:\t{:+.2} ppm\t(offset {:.3} ms, {:+.3} ms over {:.1} s, R² {:.4})",
+                pair.stream,
+                pair.reference,
+                pair.drift.ppm(),
+                pair.drift.offset() * 1000.0,
+                pair.drift.total_drift() * 1000.0,
+                pair.drift.span,
+                pair.drift.fit.r_squared
+            );
+        }
+        for warning in drift_warnings(analysis) {
+            println!("\tWARNING: {}", warning);
+        }
+        println!();
+    }
+
+    // Show why synchronization failed/passed
+    if analysis.is_synchronized {
+        println!(
+            "\tAll timing differences are within {:.0}ms threshold",
+            analysis.sync_threshold * 1000.0
+        );
+    } else {
+        println!("\tSynchronization failed because:");
+        for reason in failure_reasons(analysis) {
+            println!("\t\t• {}", reason);
+        }
+    }
+    println!();
+
+    // Detailed timing information
+    if !analysis.streams.is_empty() {
+        println!("DETAILED TIMING:");
+        let min_start = analysis
+            .streams
+            .iter()
+            .map(|s| s.start_time)
+            .fold(f64::INFINITY, |a, b| a.min(b));
+
+        // Show precise start and end times for each stream
+        for stream in &analysis.streams {
+            let start_offset_ms = (stream.start_time - min_start) * 1000.0;
+            println!("\t{}:", stream.name);
+            println!("\t\tStart offset:\t+{:.1} ms", start_offset_ms);
+            println!("\t\tDuration:\t{:.3} seconds", stream.duration);
+            println!("\t\tEnd time:\t{:.6} s", stream.end_time);
+        }
+        println!();
+
+        // Simple timeline (note: limited resolution)
+        println!("RECORDING TIMELINE (second-level resolution):");
+        let max_end = analysis
+            .streams
+            .iter()
+            .map(|s| s.end_time)
+            .fold(f64::NAN, |a, b| a.max(b));
+        let total_duration = max_end - min_start;
+        let max_name_len = analysis
+            .streams
+            .iter()
+            .map(|s| s.name.len())
+            .max()
+            .unwrap_or(10);
+        let name_width = max_name_len.max(10);
+
+        for stream in &analysis.streams {
+            let start_offset = ((stream.start_time - min_start) / total_duration * 50.0) as usize;
+            let duration_bars = ((stream.duration / total_duration * 50.0) as usize).max(1);
+
+            let mut timeline = [' '; 50];
+            for item in timeline
+                .iter_mut()
+                .skip(start_offset)
+                .take(((start_offset + duration_bars).min(50)) - start_offset)
+            {
+                *item = '█';
+            }
+            let timeline_str: String = timeline.iter().collect();
+            println!(
+                "\t{:width$}: |{}|",
+                stream.name,
+                timeline_str,
+                width = name_width
+            );
+        }
+
+        // Timeline scale
+        let scale_padding = format!("\t{}", " ".repeat(name_width + 3));
+        let end_time_str = format!("{:.1}s", total_duration);
+        let start_str = "0.0";
+        let total_scale_width = 50;
+        let padding_needed = total_scale_width - start_str.len() - end_time_str.len();
+        let middle_padding = " ".repeat(padding_needed);
+        println!(
+            "{}|{}{}{}|",
+            scale_padding, start_str, middle_padding, end_time_str
+        );
+
+        // Resolution note
+        let resolution_ms = total_duration * 1000.0 / 50.0;
+        println!(
+            "\tNote: Timeline resolution ~{:.0}ms per character",
+            resolution_ms
+        );
+        println!("\t      Millisecond differences may not be visible above");
+        println!();
+    }
+}
+
+fn print_summary(analysis: &SyncAnalysis) {
+    println!("SUMMARY");
+    println!("=======");
+    println!("Total streams analyzed:\t{}", analysis.streams.len());
+
+    if !analysis.streams.is_empty() {
+        let total_samples: usize = analysis.streams.iter().map(|s| s.sample_count).sum();
+        let total_gaps: usize = analysis.streams.iter().map(|s| s.gaps.count()).sum();
+        let avg_duration = analysis.streams.iter().map(|s| s.duration).sum::<f64>()
+            / analysis.streams.len() as f64;
+
+        println!("Total samples:\t\t{}", total_samples);
+        println!("Average duration:\t{:.3} seconds", avg_duration);
+        println!("Gaps detected:\t\t{}", total_gaps);
+        println!(
+            "Clock jumps:\t\t{}",
+            analysis.streams.iter().map(|s| s.clock_jumps.len()).sum::<usize>()
+        );
+        println!("Drift warnings:\t\t{}", drift_warnings(analysis).len());
+
+        if analysis.is_synchronized {
+            println!("All streams appear to be properly synchronized");
+            println!("The recordings should be suitable for multi-stream analysis");
+        } else {
+            println!("Synchronization issues detected!");
+            println!("Small timing differences (<{:.0}ms) are common due to:", analysis.sync_threshold * 1000.0);
+            println!("\t• System thread scheduling variations");
+            println!("\t• Network timing in LSL data transmission");
+            println!("\t• Recording start/stop coordination delays");
+            println!();
+            println!("These differences may not affect most analyses, but consider:");
+            println!("\t• For event-related analysis: Use LSL timestamps for alignment");
+            println!("\t• For spectral analysis: Usually not problematic");
+            println!("\t• For cross-correlation: May need timestamp-based alignment");
+        }
+
+        // Add context about sync thresholds
+        println!();
+        println!("SYNCHRONIZATION CONTEXT:");
+        println!(
+            "\tThreshold used: {:.0}ms",
+            analysis.sync_threshold * 1000.0
+        );
+        println!("\tThis accounts for normal system timing variations");
+        println!("\tStreams recorded simultaneously can still have small offsets")
+    }
+
+    println!();
+    println!("Run 'cargo run --example multi_recorder' to generate test stores");
+}
+
+/// Machine-readable report of a validation run
+fn build_report(analysis: &SyncAnalysis, stores: &[Value], verbose: bool) -> Value {
+    let streams: Vec<Value> = analysis
+        .streams
+        .iter()
+        .map(|stream| {
+            let mut gaps = json!({
+                "checked": stream.nominal_sample_rate > 0.0,
+                "count": stream.gaps.count(),
+                "lost_time_ms": stream.gaps.lost_time * 1000.0,
+                "largest": stream.gaps.largest.as_ref().map(gap_json),
+            });
+            if verbose {
+                gaps["gaps"] = stream.gaps.gaps.iter().map(gap_json).collect();
+            }
+            json!({
+                "name": stream.name,
+                "store": stream.store_path,
+                "source_id": stream.stream_info.source_id,
+                "hostname": stream.stream_info.hostname,
+                "channel_format": stream.channel_format,
+                "channels": stream.channel_count,
+                "samples": stream.sample_count,
+                "duration": stream.duration,
+                "start_time": stream.start_time,
+                "end_time": stream.end_time,
+                "nominal_rate": stream.nominal_sample_rate,
+                "actual_rate": stream.actual_sample_rate,
+                "rate_accuracy_percent": rate_accuracy(stream),
+                "gaps": gaps,
+                "clock_jumps": {
+                    "count": stream.clock_jumps.len(),
+                    "jumps": stream.clock_jumps,
+                    "gaps": stream.clock_jump_gaps.iter().map(gap_json).collect::<Vec<_>>(),
+                },
+            })
+        })
+        .collect();
+
+    let drift: Vec<Value> = analysis
+        .drift
+        .iter()
+        .map(|pair| {
+            json!({
+                "reference": pair.reference,
+                "stream": pair.stream,
+                "drift_ppm": pair.drift.ppm(),
+                "offset_ms": pair.drift.offset() * 1000.0,
+                "total_drift_ms": pair.drift.total_drift() * 1000.0,
+                "span_s": pair.drift.span,
+                "r_squared": pair.drift.fit.r_squared,
+                "points": pair.drift.fit.points,
+                "within_budget": pair.drift.ppm().abs() <= analysis.drift_budget_ppm,
+            })
+        })
+        .collect();
+
+    let status = if analysis.streams.is_empty() {
+        "no_data"
+    } else if analysis.is_synchronized {
+        "synchronized"
+    } else {
+        "not_synchronized"
+    };
+    json!({
+        "status": status,
+        "synchronized": analysis.is_synchronized,
+        "threshold_ms": analysis.sync_threshold * 1000.0,
+        "start_time_diff_ms": analysis.start_time_diff * 1000.0,
+        "end_time_diff_ms": analysis.end_time_diff * 1000.0,
+        "duration_diff_ms": analysis.duration_diff * 1000.0,
+        "max_timestamp_drift_ms": analysis.max_timestamp_drift * 1000.0,
+        "failures": failure_reasons(analysis),
+        "drift_budget_ppm": analysis.drift_budget_ppm,
+        "clock_drift": drift,
+        "drift_warnings": drift_warnings(analysis),
+        "stores": stores,
+        "streams": streams,
+    })
+}
+
+/// Render a JSON value as block-style YAML
+fn to_yaml(value: &Value, indent: usize, out: &mut String) {
+    let pad = "  ".repeat(indent);
+    match value {
+        Value::Object(map) if !map.is_empty() => {
+            for (key, item) in map {
+                let plain = key.starts_with(|c: char| c.is_ascii_alphabetic())
+                    && key.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+                if plain {
+                    out.push_str(&format!("{}{}:", pad, key));
+                } else {
+                    out.push_str(&format!("{}{}:", pad, Value::String(key.clone())));
+                }
+                yaml_item(item, indent, out);
+            }
+        }
+        Value::Array(items) if !items.is_empty() => {
+            for item in items {
+                out.push_str(&format!("{}-", pad));
+                yaml_item(item, indent, out);
+            }
+        }
+        // Scalars (and empty collections) are valid YAML in JSON notation
+        other => out.push_str(&format!("{}{}\n", pad, other)),
+    }
+}
+
+fn yaml_item(item: &Value, indent: usize, out: &mut String) {
+    match item {
+        Value::Object(map) if !map.is_empty() => {
+            out.push('\n');
+            to_yaml(item, indent + 1, out);
+        }
+        Value::Array(items) if !items.is_empty() => {
+            out.push('\n');
+            to_yaml(item, indent + 1, out);
+        }
+        // Non-finite floats serialize as null
+        scalar => out.push_str(&format!(" {}\n", scalar)),
+    }
+}
+
+/// Load a local store, or a store served over HTTP through its cache
+fn load_store(store_path: &str, args: &Args) -> Result<(Vec<StreamData>, StoreCache)> {
+    let open_cache = |local: &Path| {
+        if args.no_cache {
+            StoreCache::disabled(local)
+        } else {
+            StoreCache::open(local)
+        }
+    };
+    if !is_http(store_path) {
+        let mut cache = open_cache(Path::new(store_path));
+        return Ok((load_zarr_stream_data(store_path, &mut cache)?, cache));
+    }
+    let options = HttpFetchOptions {
+        cache_dir: args.cache_dir.clone(),
+        ..Default::default()
+    };
+    let local = fetch_http_store(store_path, &options)?;
+    let mut cache = open_cache(&local);
+    let mut streams = load_zarr_stream_data(&local.display().to_string(), &mut cache)?;
+    for stream in &mut streams {
+        stream.store_path = store_path.to_string();
+    }
+    Ok((streams, cache))
+}
+
+/// Machine-readable report of a study check
+fn build_study_report(study_dir: &Path, report: &StudyReport) -> Value {
+    let streams: Vec<Value> = report
+        .streams
+        .iter()
+        .map(|stream| {
+            let expected: serde_json::Map<String, Value> = stream
+                .expected
+                .iter()
+                .map(|(check, value)| (check.name().to_string(), json!(value)))
+                .collect();
+            json!({"name": stream.name, "sessions": stream.sessions, "expected": expected})
+        })
+        .collect();
+    let status = if report.sessions.is_empty() {
+        "no_data"
+    } else if report.is_consistent() {
+        "consistent"
+    } else {
+        "outliers"
+    };
+    json!({
+        "status": status,
+        "study_dir": study_dir,
+        "sessions": report.sessions,
+        "streams": streams,
+        "outliers": report.outliers,
+    })
+}
+
+/// Print the result of a study check
+fn print_study_report(report: &StudyReport) {
+    println!("STUDY STREAMS");
+    println!("=============");
+    for stream in &report.streams {
+        let expected: Vec<String> = stream
+            .expected
+            .iter()
+            .map(|(check, value)| format!("{} {}", check.name(), value))
+            .collect();
+        println!("{}:\t{} session(s)\t{}", stream.name, stream.sessions, expected.join(", "));
+    }
+    println!();
+
+    println!("OUTLIERS");
+    println!("========");
+    for outlier in &report.outliers {
+        println!(
+            "{} {}:\t{} in {} ({} in {} of {} sessions)",
+            outlier.stream,
+            outlier.check.name(),
+            outlier.value,
+            outlier.session.display(),
+            outlier.expected,
+            outlier.agreeing,
+            outlier.sessions
+        );
+    }
+    if report.is_consistent() {
+        println!("None: all sessions agree");
+    }
+    println!();
+}
+
+/// Run the study check of `--study-dir`
+fn run_study(args: &Args, study_dir: &Path) -> Result<()> {
+    let checks = if args.study_checks.is_empty() {
+        ALL_STUDY_CHECKS.to_vec()
+    } else {
+        args.study_checks.clone()
+    };
+    let report = check_study(study_dir, &checks)?;
+    let exit_code = if report.sessions.is_empty() {
+        EXIT_NO_DATA
+    } else if report.is_consistent() {
+        0
+    } else {
+        EXIT_STUDY_OUTLIERS
+    };
+
+    match args.format {
+        ReportFormat::Json => {
+            println!("{}", serde_json::to_string_pretty(&build_study_report(study_dir, &report))?);
+        }
+        ReportFormat::Yaml => {
+            let mut yaml = String::new();
+            to_yaml(&build_study_report(study_dir, &report), 0, &mut yaml);
+            print!("{}", yaml);
+        }
+        ReportFormat::Text => {
+            crate::display_license_notice("lsl-validate");
+
+            println!("LSL Study Consistency Validator");
+            println!("===============================");
+            println!();
+            println!("Study:\t{} ({} session(s))", study_dir.display(), report.sessions.len());
+            println!();
+            if report.sessions.is_empty() {
+                println!("No Zarr stores found below {}", study_dir.display());
+            } else {
+                print_study_report(&report);
+                println!("{} outlier(s) in {} stream(s)", report.outliers.len(), report.streams.len());
+            }
+        }
+    }
+
+    crate::usage::exit(exit_code);
+}
+
+/// Run `lsl-validate` with parsed arguments
+pub fn run(args: Args) -> Result<()> {
+    if let Some(ref study_dir) = args.study_dir {
+        return run_study(&args, study_dir);
+    }
+    let text = args.format == ReportFormat::Text;
+
+    if text {
+        crate::display_license_notice("lsl-validate");
+
+        println!("LSL Multi-Stream Synchronization Validator");
+        println!("==========================================");
+        println!();
+    }
+
+    let mut all_streams = Vec::new();
+    let mut stores = Vec::new();
+    let mut caches = BTreeMap::new();
+
+    // Load data from all available stores
+    for store_path in &args.stores {
+        match load_store(store_path, &args) {
+            Ok((mut streams, cache)) => {
+                if let Some(stream) = streams.first() {
+                    caches.insert(stream.local_path.clone(), cache);
+                }
+                if text {
+                    println!("Loaded {} stream(s) from {}", streams.len(), store_path);
+                }
+                stores.push(json!({"path": store_path, "streams": streams.len()}));
+                all_streams.append(&mut streams);
+            }
+            Err(e) => {
+                if text {
+                    println!("Could not load {}: {}", store_path, e);
+                }
+                stores.push(json!({"path": store_path, "error": e.to_string()}));
+            }
+        }
+    }
+
+    analyze_gaps(&mut all_streams, &mut caches, args.gap_factor)?;
+    let drift = analyze_drift(&mut all_streams, &mut caches)?;
+    for cache in caches.values() {
+        cache.save_or_warn();
+    }
+
+    let analysis = analyze_synchronization(&all_streams, drift, args.threshold_ms / 1000.0, args.drift_budget_ppm);
+    let exit_code = if all_streams.is_empty() {
+        EXIT_NO_DATA
+    } else if analysis.is_synchronized {
+        0
+    } else {
+        EXIT_NOT_SYNCHRONIZED
+    };
+
+    match args.format {
+        ReportFormat::Json => {
+            println!("{}", serde_json::to_string_pretty(&build_report(&analysis, &stores, args.verbose))?);
+        }
+        ReportFormat::Yaml => {
+            let mut yaml = String::new();
+            to_yaml(&build_report(&analysis, &stores, args.verbose), 0, &mut yaml);
+            print!("{}", yaml);
+        }
+        ReportFormat::Text if all_streams.is_empty() => {
+            println!("No valid Zarr stores found!");
+            println!("Make sure to run 'cargo run --example multi_recorder' first");
+        }
+        ReportFormat::Text => {
+            println!();
+
+            // Display individual stream information
+            println!("STREAM INFORMATION");
+            println!("==================");
+            for stream in &all_streams {
+                print_stream_info(stream, args.verbose);
+            }
+
+            print_sync_analysis(&analysis);
+            print_summary(&analysis);
+        }
+    }
+
+    crate::usage::exit(exit_code);
+}
@@ -4,8 +4,15 @@ use std::env;
 use std::path::PathBuf;
 
 use super::file_browser::FileBrowserState;
+use super::native_dialog;
 use super::tab::TabState;
 use super::tool_config;
+use super::wizard::{WizardState, WizardStep};
+
+use lsl_recording_toolbox::config::ProjectConfig;
+use lsl_recording_toolbox::tui_presets::Presets;
+use lsl_recording_toolbox::tui_settings::{graphical_session, TuiSettings};
+use lsl_recording_toolbox::usage::usage_dir;
 
 /// Category for grouping tools in the menu.
 #[derive(Clone, Copy, PartialEq, Eq, Debug)]
@@ -35,6 +42,8 @@ pub struct ToolMetadata {
     pub name: &'static str,
     /// Binary name (used for execution)
     pub binary: &'static str,
+    /// `lsl-toolbox` subcommand running the tool from this executable, if it is built in
+    pub subcommand: Option<&'static str>,
     /// Short description of what the tool does
     pub description: &'static str,
     /// Category for menu grouping
@@ -47,50 +56,161 @@ pub const TOOLS: &[ToolMetadata] = &[
     ToolMetadata {
         name: "LSL Recorder",
         binary: "lsl-recorder",
+        subcommand: Some("record"),
         description: "Record a single LSL stream to Zarr format",
         category: ToolCategory::Recording,
     },
     ToolMetadata {
         name: "LSL Multi-Recorder",
         binary: "lsl-multi-recorder",
+        subcommand: Some("multi"),
         description: "Record multiple LSL streams simultaneously",
         category: ToolCategory::Recording,
     },
+    ToolMetadata {
+        name: "LSL Discover",
+        binary: "lsl-discover",
+        subcommand: None,
+        description: "List LSL streams visible on the network",
+        category: ToolCategory::Recording,
+    },
+    ToolMetadata {
+        name: "LSL Spotcheck",
+        binary: "lsl-spotcheck",
+        subcommand: None,
+        description: "Pre-session test recording with go/no-go verdict",
+        category: ToolCategory::Recording,
+    },
     // Analysis
     ToolMetadata {
         name: "LSL Inspect",
         binary: "lsl-inspect",
+        subcommand: Some("inspect"),
         description: "Inspect Zarr recording contents and metadata",
         category: ToolCategory::Analysis,
     },
     ToolMetadata {
         name: "LSL Validate",
         binary: "lsl-validate",
+        subcommand: Some("validate"),
         description: "Validate recording synchronization quality",
         category: ToolCategory::Analysis,
     },
+    ToolMetadata {
+        name: "LSL Query",
+        binary: "lsl-query",
+        subcommand: None,
+        description: "Search recordings by metadata expressions",
+        category: ToolCategory::Analysis,
+    },
+    ToolMetadata {
+        name: "LSL Index",
+        binary: "lsl-index",
+        subcommand: None,
+        description: "Build a SQLite index of recording sessions",
+        category: ToolCategory::Analysis,
+    },
+    // Runs inside the TUI instead of as a process (see EXPLORER_TOOL_INDEX)
+    ToolMetadata {
+        name: "Store Explorer",
+        binary: "store-explorer",
+        subcommand: None,
+        description: "Browse a store's groups, attributes and data",
+        category: ToolCategory::Analysis,
+    },
     // Post-Processing
     ToolMetadata {
         name: "LSL Sync",
         binary: "lsl-sync",
+        subcommand: Some("sync"),
         description: "Synchronize timestamps across streams",
         category: ToolCategory::PostProcessing,
     },
+    ToolMetadata {
+        name: "LSL Repair",
+        binary: "lsl-repair",
+        subcommand: None,
+        description: "Recover recordings interrupted by a crash",
+        category: ToolCategory::PostProcessing,
+    },
+    ToolMetadata {
+        name: "LSL Export",
+        binary: "lsl-export",
+        subcommand: None,
+        description: "Convert recordings to XDF",
+        category: ToolCategory::PostProcessing,
+    },
+    ToolMetadata {
+        name: "LSL Import",
+        binary: "lsl-import",
+        subcommand: None,
+        description: "Import XDF files into the Zarr layout",
+        category: ToolCategory::PostProcessing,
+    },
+    ToolMetadata {
+        name: "LSL Bad Channels",
+        binary: "lsl-badchannels",
+        subcommand: None,
+        description: "Mark or detect bad channels",
+        category: ToolCategory::PostProcessing,
+    },
+    ToolMetadata {
+        name: "LSL Markers",
+        binary: "lsl-markers",
+        subcommand: None,
+        description: "Normalize marker labels with a mapping table",
+        category: ToolCategory::PostProcessing,
+    },
     // Development
     ToolMetadata {
         name: "LSL Replay",
         binary: "lsl-replay",
+        subcommand: Some("replay"),
         description: "Replay recorded LSL streams",
         category: ToolCategory::Development,
     },
+    ToolMetadata {
+        name: "LSL Loopback",
+        binary: "lsl-loopback",
+        subcommand: None,
+        description: "Verify the replay/record loop against the original",
+        category: ToolCategory::Development,
+    },
     ToolMetadata {
         name: "LSL Dummy Stream",
         binary: "lsl-dummy-stream",
+        subcommand: Some("dummy"),
         description: "Generate test LSL streams for development",
         category: ToolCategory::Development,
     },
+    ToolMetadata {
+        name: "LSL Calibrate",
+        binary: "lsl-calibrate",
+        subcommand: None,
+        description: "Measure latency and timestamp bias with test pulses",
+        category: ToolCategory::Development,
+    },
+    ToolMetadata {
+        name: "LSL Demo",
+        binary: "lsl-demo",
+        subcommand: None,
+        description: "Generate an example store to try the tools without hardware",
+        category: ToolCategory::Development,
+    },
 ];
 
+/// Index of the LSL Demo tool in [`TOOLS`], preselected on first run
+pub const DEMO_TOOL_INDEX: usize = 19;
+
+/// Index of the Store Explorer in [`TOOLS`], opened in its tab instead of spawned
+pub const EXPLORER_TOOL_INDEX: usize = 8;
+
+/// Index of the tool with this binary name in [`TOOLS`]
+pub fn tool_index(binary: &str) -> Option<usize> {
+    TOOLS.iter().position(|tool| tool.binary == binary)
+}
+
+
 /// State for close confirmation dialog.
 pub struct CloseConfirmation {
     /// Index of tab being closed
@@ -107,6 +227,16 @@ pub struct RenameState {
     pub cursor: usize,
 }
 
+/// State of the preset dialog of the active tab's form.
+pub struct PresetDialog {
+    /// Presets of the form's tool
+    pub presets: Presets,
+    /// Selected preset in the picker
+    pub selected: usize,
+    /// Name typed when saving the form as a preset (`None` while picking)
+    pub name_input: Option<String>,
+}
+
 /// Main application state with multi-tab support.
 pub struct App {
     /// Currently selected tool index in the menu
@@ -121,10 +251,18 @@ pub struct App {
     pub file_browser: Option<FileBrowserState>,
     /// Rename dialog state
     pub rename_state: Option<RenameState>,
+    /// Preset picker or save dialog state
+    pub preset_dialog: Option<PresetDialog>,
     /// User preference: don't ask before closing tabs with running processes
     pub skip_close_confirmation: bool,
+    /// Preferences kept across sessions (file dialog mode)
+    pub settings: TuiSettings,
+    /// Defaults from the project's `lsl-toolbox.toml`, filled into new forms
+    pub project_config: ProjectConfig,
     /// Whether the application should quit
     pub should_quit: bool,
+    /// No Zarr store in the working directory: the menu suggests generating demo data
+    pub first_run: bool,
     /// Next tab ID (for unique identification)
     next_tab_id: usize,
 }
@@ -132,19 +270,37 @@ pub struct App {
 impl App {
     /// Create a new application instance.
     pub fn new() -> Self {
+        let first_run = !has_zarr_stores(&env::current_dir().unwrap_or_default());
         Self {
-            selected_index: 0,
+            selected_index: if first_run { DEMO_TOOL_INDEX } else { 0 },
             tabs: Vec::new(),
             active_tab_index: None,
             close_confirmation: None,
             file_browser: None,
             rename_state: None,
+            preset_dialog: None,
             skip_close_confirmation: false,
+            settings: TuiSettings::load(&usage_dir()),
+            // lsl-toolbox has already refused to start with an invalid file
+            project_config: ProjectConfig::discover().unwrap_or_default(),
             should_quit: false,
+            first_run,
             next_tab_id: 0,
         }
     }
 
+    /// Whether Space on a path field shows a native dialog instead of the terminal browser.
+    pub fn uses_native_dialogs(&self) -> bool {
+        native_dialog::AVAILABLE && self.settings.file_dialog.uses_native(graphical_session())
+    }
+
+    /// Switch between the terminal browser and native dialogs, and remember the choice.
+    pub fn toggle_file_dialog_mode(&mut self) {
+        self.settings.file_dialog = self.settings.file_dialog.toggled();
+        // A settings file that can't be written only means the choice isn't remembered
+        let _ = self.settings.save(&usage_dir());
+    }
+
     /// Check if file browser is open.
     pub fn has_file_browser(&self) -> bool {
         self.file_browser.is_some()
@@ -165,6 +321,110 @@ impl App {
         self.file_browser.as_mut()
     }
 
+    /// Check if the preset dialog is open.
+    pub fn has_preset_dialog(&self) -> bool {
+        self.preset_dialog.is_some()
+    }
+
+    /// Open the preset picker (`saving = false`) or the save dialog for the active tab's form.
+    pub fn open_preset_dialog(&mut self, saving: bool) {
+        let Some(tab) = self.active_tab() else { return };
+        if tab.form_state.is_none() {
+            return;
+        }
+        self.preset_dialog = Some(PresetDialog {
+            presets: Presets::load(&usage_dir(), TOOLS[tab.tool_index].binary),
+            selected: 0,
+            name_input: saving.then(String::new),
+        });
+    }
+
+    /// Close the preset dialog.
+    pub fn close_preset_dialog(&mut self) {
+        self.preset_dialog = None;
+    }
+
+    /// Move the picker selection by `delta` presets.
+    pub fn preset_select(&mut self, delta: isize) {
+        if let Some(ref mut dialog) = self.preset_dialog {
+            let last = dialog.presets.names().len().saturating_sub(1);
+            dialog.selected = dialog.selected.saturating_add_signed(delta).min(last);
+        }
+    }
+
+    /// Fill the active tab's form from the selected preset and close the picker.
+    pub fn load_selected_preset(&mut self) {
+        let Some(dialog) = self.preset_dialog.take() else { return };
+        let Some(name) = dialog.presets.names().get(dialog.selected).map(|name| name.to_string()) else {
+            return;
+        };
+        if let Some(values) = dialog.presets.get(&name)
+            && let Some(tab) = self.active_tab_mut()
+            && let Some(ref mut form) = tab.form_state
+        {
+            form.apply_values(values);
+            form.preset = Some(name);
+        }
+    }
+
+    /// Delete the selected preset from disk.
+    pub fn delete_selected_preset(&mut self) {
+        let Some(ref mut dialog) = self.preset_dialog else { return };
+        let Some(name) = dialog.presets.names().get(dialog.selected).map(|name| name.to_string()) else {
+            return;
+        };
+        dialog.presets.remove(&name);
+        let result = dialog.presets.save(&usage_dir());
+        dialog.selected = dialog.selected.min(dialog.presets.names().len().saturating_sub(1));
+        self.report_preset_error(result);
+    }
+
+    /// Save the active tab's form under the typed name and close the dialog.
+    pub fn save_preset(&mut self) {
+        let Some(mut dialog) = self.preset_dialog.take() else { return };
+        let Some(name) = dialog.name_input.take() else { return };
+        let Some(form) = self.active_tab().and_then(|tab| tab.form_state.as_ref()) else { return };
+        let values = form.values();
+        let result = dialog.presets.insert(&name, values).and_then(|()| dialog.presets.save(&usage_dir()));
+        let saved = result.is_ok();
+        self.report_preset_error(result);
+        if saved
+            && let Some(tab) = self.active_tab_mut()
+            && let Some(ref mut form) = tab.form_state
+        {
+            form.preset = Some(name.trim().to_string());
+        }
+    }
+
+    /// Type into the preset name.
+    pub fn preset_name_insert(&mut self, c: char) {
+        if let Some(ref mut dialog) = self.preset_dialog
+            && let Some(ref mut name) = dialog.name_input
+            && name.len() < 64
+        {
+            name.push(c);
+        }
+    }
+
+    /// Delete the last character of the preset name.
+    pub fn preset_name_backspace(&mut self) {
+        if let Some(ref mut dialog) = self.preset_dialog
+            && let Some(ref mut name) = dialog.name_input
+        {
+            name.pop();
+        }
+    }
+
+    /// Show a failed preset save in the active tab's form.
+    fn report_preset_error(&mut self, result: anyhow::Result<()>) {
+        if let Err(e) = result
+            && let Some(tab) = self.active_tab_mut()
+            && let Some(ref mut form) = tab.form_state
+        {
+            form.error_message = Some(format!("{:#}", e));
+        }
+    }
+
     /// Check if rename dialog is open.
     pub fn is_renaming(&self) -> bool {
         self.rename_state.is_some()
@@ -294,7 +554,8 @@ impl App {
     pub fn create_tab_from_menu(&mut self) {
         let tool = self.selected_tool();
         let tool_index = self.selected_index;
-        let form = tool_config::create_config_form(tool_index);
+        let mut form = tool_config::create_config_form(tool_index);
+        form.apply_values(&self.project_config.form_values(tool.binary));
 
         let tab = TabState::new(self.next_tab_id, tool_index, tool.name, form);
         self.next_tab_id += 1;
@@ -303,6 +564,58 @@ impl App {
         self.active_tab_index = Some(self.tabs.len() - 1);
     }
 
+    /// Create a new tab for a tool with its store path field set to `store_path`, and switch to it.
+    pub fn create_tab_for_store(&mut self, tool_index: usize, store_path: &str) {
+        let tool = &TOOLS[tool_index];
+        let mut form = tool_config::create_config_form(tool_index);
+        form.apply_values(&self.project_config.form_values(tool.binary));
+        if let Some(field) = form
+            .fields
+            .iter_mut()
+            .find(|field| matches!(field.name.as_str(), "file_path" | "zarr_file"))
+        {
+            field.value = store_path.to_string();
+            field.cursor_pos = field.value.len();
+        }
+
+        let tab = TabState::new(self.next_tab_id, tool_index, tool.name, form);
+        self.next_tab_id += 1;
+
+        self.tabs.push(tab);
+        self.active_tab_index = Some(self.tabs.len() - 1);
+    }
+
+    /// Start the experiment wizard with a Multi-Recorder tab.
+    pub fn start_wizard(&mut self) {
+        let Some(index) = tool_index(WizardStep::Record.binary()) else { return };
+        // The recorder form has no store field; its output path is set as usual
+        self.create_tab_for_store(index, "");
+        self.begin_wizard_step(WizardState::new(WizardStep::Record, None));
+    }
+
+    /// Open the wizard step after the one in the active tab, on the store it worked on.
+    pub fn continue_wizard(&mut self) {
+        let Some((step, store_path)) = self
+            .active_tab()
+            .and_then(|tab| tab.wizard.as_ref())
+            .and_then(|wizard| wizard.next_step())
+            .map(|(step, store)| (step, store.to_string()))
+        else {
+            return;
+        };
+        let Some(index) = tool_index(step.binary()) else { return };
+        self.create_tab_for_store(index, &store_path);
+        self.begin_wizard_step(WizardState::new(step, Some(store_path)));
+    }
+
+    /// Mark the newly created active tab as a step of the wizard.
+    fn begin_wizard_step(&mut self, wizard: WizardState) {
+        if let Some(tab) = self.active_tab_mut() {
+            tab.title = wizard.step.title().to_string();
+            tab.wizard = Some(wizard);
+        }
+    }
+
     /// Switch to next tab or menu (Tab key).
     /// Cycles: Menu → Tab1 → Tab2 → ... → TabN → Menu
     pub fn next_tab(&mut self) {
@@ -403,7 +716,7 @@ impl Default for App {
     }
 }
 
-/// Find the binary path for a tool.
+/// Find the binary path for a tool that is not built into `lsl-toolbox`.
 /// Checks next to current executable, target/release/, target/debug/, and PATH.
 pub fn find_binary(binary_name: &str) -> PathBuf {
     // Get the directory of the current executable
@@ -431,3 +744,14 @@ pub fn find_binary(binary_name: &str) -> PathBuf {
     // Assume it's in PATH
     PathBuf::from(binary_name)
 }
+
+/// Whether `dir` contains a Zarr store (`*.zarr`)
+fn has_zarr_stores(dir: &std::path::Path) -> bool {
+    std::fs::read_dir(dir)
+        .map(|entries| {
+            entries
+                .flatten()
+                .any(|entry| entry.path().extension().is_some_and(|ext| ext == "zarr"))
+        })
+        .unwrap_or(false)
+}
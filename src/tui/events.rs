@@ -145,3 +145,13 @@ pub fn is_space(key: &KeyEvent) -> bool {
 pub fn is_ctrl_r(key: &KeyEvent) -> bool {
     key.code == KeyCode::Char('r') && key.modifiers.contains(KeyModifiers::CONTROL)
 }
+
+/// Check if a key event is Ctrl+S (save preset).
+pub fn is_ctrl_s(key: &KeyEvent) -> bool {
+    key.code == KeyCode::Char('s') && key.modifiers.contains(KeyModifiers::CONTROL)
+}
+
+/// Check if a key event is Ctrl+P (preset picker).
+pub fn is_ctrl_p(key: &KeyEvent) -> bool {
+    key.code == KeyCode::Char('p') && key.modifiers.contains(KeyModifiers::CONTROL)
+}
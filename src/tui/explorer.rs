@@ -0,0 +1,162 @@
+//! Store Explorer state: a keyboard-navigable tree of a Zarr store.
+
+use std::path::PathBuf;
+
+use lsl_recording_toolbox::explore::{
+    list_children, node_details, preview_array, NodeKind, StoreNode, PREVIEW_CHANNELS, PREVIEW_SAMPLES,
+};
+
+/// One visible row of the tree.
+#[derive(Clone, Debug)]
+pub struct TreeRow {
+    /// Group or array shown in this row
+    pub node: StoreNode,
+    /// Nesting level (0 = directly below the store root)
+    pub depth: usize,
+    /// Whether the children of a group are listed below it
+    pub expanded: bool,
+}
+
+/// State of an open Store Explorer tab.
+pub struct ExplorerState {
+    /// Store being explored
+    pub store_path: PathBuf,
+    /// Visible rows, in tree order
+    pub rows: Vec<TreeRow>,
+    /// Currently selected row
+    pub selected_index: usize,
+    /// Metadata, attributes and preview of the selected node
+    pub details: Vec<String>,
+    /// Scroll offset of the details pane
+    pub details_scroll: usize,
+    /// Error message if the store couldn't be read
+    pub error: Option<String>,
+}
+
+impl ExplorerState {
+    /// Open a store and list its top-level groups and arrays.
+    pub fn new(store_path: &str) -> Self {
+        let mut explorer = Self {
+            store_path: PathBuf::from(store_path),
+            rows: Vec::new(),
+            selected_index: 0,
+            details: Vec::new(),
+            details_scroll: 0,
+            error: None,
+        };
+        explorer.refresh();
+        explorer
+    }
+
+    /// Re-read the store, collapsing the tree.
+    pub fn refresh(&mut self) {
+        self.error = None;
+        self.rows = match list_children(&self.store_path, "/") {
+            Ok(children) => children
+                .into_iter()
+                .map(|node| TreeRow { node, depth: 0, expanded: false })
+                .collect(),
+            Err(e) => {
+                self.error = Some(format!("Cannot open store: {:#}", e));
+                Vec::new()
+            }
+        };
+        self.selected_index = 0;
+        self.load_details();
+    }
+
+    /// Get the currently selected row.
+    pub fn selected_row(&self) -> Option<&TreeRow> {
+        self.rows.get(self.selected_index)
+    }
+
+    /// Move selection up.
+    pub fn select_previous(&mut self) {
+        if self.selected_index > 0 {
+            self.selected_index -= 1;
+            self.load_details();
+        }
+    }
+
+    /// Move selection down.
+    pub fn select_next(&mut self) {
+        if self.selected_index + 1 < self.rows.len() {
+            self.selected_index += 1;
+            self.load_details();
+        }
+    }
+
+    /// Expand the selected group (Right/Enter); on an expanded group, move to its first child.
+    pub fn expand_selected(&mut self) {
+        let Some(row) = self.rows.get(self.selected_index) else { return };
+        if row.node.kind != NodeKind::Group {
+            return;
+        }
+        if row.expanded {
+            if self.rows.get(self.selected_index + 1).is_some_and(|next| next.depth > row.depth) {
+                self.select_next();
+            }
+            return;
+        }
+        let depth = row.depth + 1;
+        match list_children(&self.store_path, &row.node.path) {
+            Ok(children) => {
+                let children: Vec<TreeRow> = children
+                    .into_iter()
+                    .map(|node| TreeRow { node, depth, expanded: false })
+                    .collect();
+                let at = self.selected_index + 1;
+                self.rows.splice(at..at, children);
+                self.rows[self.selected_index].expanded = true;
+            }
+            Err(e) => self.details = vec![format!("Cannot list group: {:#}", e)],
+        }
+    }
+
+    /// Collapse the selected group (Left); on a collapsed node, move to its parent.
+    pub fn collapse_selected(&mut self) {
+        let Some(row) = self.rows.get(self.selected_index) else { return };
+        let depth = row.depth;
+        if row.expanded {
+            let end = self.rows[self.selected_index + 1..]
+                .iter()
+                .position(|r| r.depth <= depth)
+                .map_or(self.rows.len(), |offset| self.selected_index + 1 + offset);
+            self.rows.drain(self.selected_index + 1..end);
+            self.rows[self.selected_index].expanded = false;
+        } else if depth > 0
+            && let Some(parent) = self.rows[..self.selected_index].iter().rposition(|r| r.depth < depth)
+        {
+            self.selected_index = parent;
+            self.load_details();
+        }
+    }
+
+    /// Scroll the details pane.
+    pub fn scroll_details(&mut self, amount: isize) {
+        let max_scroll = self.details.len().saturating_sub(1);
+        self.details_scroll = self.details_scroll.saturating_add_signed(amount).min(max_scroll);
+    }
+
+    /// Read the metadata of the selected node, with a data preview for arrays.
+    fn load_details(&mut self) {
+        self.details_scroll = 0;
+        let Some(row) = self.rows.get(self.selected_index) else {
+            self.details.clear();
+            return;
+        };
+        let path = row.node.path.clone();
+        self.details = match node_details(&self.store_path, &path) {
+            Ok(details) => details.lines(),
+            Err(e) => vec![format!("Cannot read metadata: {:#}", e)],
+        };
+        if row.node.kind == NodeKind::Array {
+            self.details.push(String::new());
+            self.details.push("Preview:".to_string());
+            match preview_array(&self.store_path, &path, PREVIEW_SAMPLES, PREVIEW_CHANNELS) {
+                Ok(lines) => self.details.extend(lines.into_iter().map(|line| format!("  {}", line))),
+                Err(e) => self.details.push(format!("  {:#}", e)),
+            }
+        }
+    }
+}
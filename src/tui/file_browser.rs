@@ -14,6 +14,25 @@ pub struct BrowserEntry {
     pub is_dir: bool,
 }
 
+/// Directory to start browsing in for a field value: the value itself if it is a
+/// directory, else its parent, else the working directory.
+pub fn start_dir(start_path: &str) -> PathBuf {
+    let cwd = std::env::current_dir().unwrap_or_else(|_| PathBuf::from("."));
+    if start_path.is_empty() {
+        return cwd;
+    }
+    let p = PathBuf::from(start_path);
+    if p.is_dir() {
+        p
+    } else {
+        // Try parent directory, but fallback to cwd if parent is empty or doesn't exist
+        p.parent()
+            .filter(|parent| !parent.as_os_str().is_empty() && parent.is_dir())
+            .map(|p| p.to_path_buf())
+            .unwrap_or(cwd)
+    }
+}
+
 /// State for the file browser modal.
 pub struct FileBrowserState {
     /// Current directory being browsed
@@ -35,25 +54,8 @@ pub struct FileBrowserState {
 impl FileBrowserState {
     /// Create a new file browser starting at the given path.
     pub fn new(start_path: &str, select_dir: bool, field_index: usize) -> Self {
-        let cwd = std::env::current_dir().unwrap_or_else(|_| PathBuf::from("."));
-
-        let start = if start_path.is_empty() {
-            cwd
-        } else {
-            let p = PathBuf::from(start_path);
-            if p.is_dir() {
-                p
-            } else {
-                // Try parent directory, but fallback to cwd if parent is empty or doesn't exist
-                p.parent()
-                    .filter(|parent| !parent.as_os_str().is_empty() && parent.is_dir())
-                    .map(|p| p.to_path_buf())
-                    .unwrap_or(cwd)
-            }
-        };
-
         let mut browser = Self {
-            current_dir: start,
+            current_dir: start_dir(start_path),
             entries: Vec::new(),
             selected_index: 0,
             select_dir,
@@ -1,5 +1,7 @@
 //! Form field and state management for tool configuration.
 
+use std::collections::BTreeMap;
+
 /// Type of form field, determining input behavior and rendering.
 #[derive(Clone, Debug, PartialEq)]
 pub enum FieldType {
@@ -272,6 +274,8 @@ pub struct FormState {
     pub error_message: Option<String>,
     /// Scroll offset for long forms
     pub scroll_offset: usize,
+    /// Preset last loaded into or saved from the form
+    pub preset: Option<String>,
 }
 
 #[allow(dead_code)]
@@ -284,6 +288,7 @@ impl FormState {
             tool_name: tool_name.to_string(),
             error_message: None,
             scroll_offset: 0,
+            preset: None,
         }
     }
 
@@ -450,6 +455,29 @@ impl FormState {
             .map(|f| f.value.as_str())
     }
 
+    /// Values of all fields by name, for saving as a preset.
+    pub fn values(&self) -> BTreeMap<String, String> {
+        self.fields
+            .iter()
+            .map(|f| (f.name.clone(), f.value.clone()))
+            .collect()
+    }
+
+    /// Set fields from a preset. Names the form doesn't have and options a select
+    /// field doesn't offer are skipped, so presets survive changes to the forms.
+    pub fn apply_values(&mut self, values: &BTreeMap<String, String>) {
+        for field in &mut self.fields {
+            let Some(value) = values.get(&field.name) else { continue };
+            if let FieldType::Select(ref options) = field.field_type {
+                let Some(idx) = options.iter().position(|option| option == value) else { continue };
+                field.select_idx = idx;
+            }
+            field.value = value.clone();
+            field.cursor_pos = field.value.len();
+        }
+        self.error_message = None;
+    }
+
     /// Validate all required fields.
     pub fn validate(&mut self) -> Result<(), String> {
         let missing: Vec<&str> = self
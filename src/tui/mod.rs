@@ -5,17 +5,21 @@
 //! running in separate tabs.
 
 pub mod app;
-pub mod events;
+pub use lsl_recording_toolbox::tui::events;
+pub mod explorer;
 pub mod file_browser;
 pub mod form;
+pub mod native_dialog;
 pub mod process;
 pub mod tab;
 pub mod tool_config;
 pub mod ui;
 pub mod ui_dialog;
+pub mod ui_explorer;
 pub mod ui_file_browser;
 pub mod ui_form;
 pub mod ui_helpers;
 pub mod ui_tabs;
+pub mod wizard;
 
 pub use app::App;
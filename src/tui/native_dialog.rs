@@ -0,0 +1,24 @@
+//! Native open dialogs for path fields (`native-dialogs` feature).
+
+use std::path::PathBuf;
+
+/// Whether this build can show native dialogs.
+pub const AVAILABLE: bool = cfg!(feature = "native-dialogs");
+
+/// Show the desktop's open dialog for a path field, starting next to its current value.
+/// Returns `None` when the dialog is cancelled.
+#[cfg(feature = "native-dialogs")]
+pub fn pick_path(current_value: &str, select_dir: bool) -> Option<PathBuf> {
+    let dialog = rfd::FileDialog::new().set_directory(super::file_browser::start_dir(current_value));
+    if select_dir {
+        dialog.pick_folder()
+    } else {
+        dialog.pick_file()
+    }
+}
+
+/// Without the `native-dialogs` feature there is no dialog to show.
+#[cfg(not(feature = "native-dialogs"))]
+pub fn pick_path(_current_value: &str, _select_dir: bool) -> Option<PathBuf> {
+    None
+}
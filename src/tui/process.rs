@@ -1,11 +1,13 @@
 //! Process management for running tools.
 
 use std::io::{BufRead, BufReader, Write};
+use std::path::PathBuf;
 use std::process::{Child, ChildStdin, Command, Stdio};
 use std::sync::mpsc::{self, Receiver};
 use std::thread;
 
 use anyhow::Result;
+use lsl_recording_toolbox::tools;
 
 use super::app::{find_binary, ToolMetadata};
 
@@ -32,10 +34,15 @@ impl ProcessManager {
     /// Spawn a tool as a child process with the given arguments.
     /// Terminal size (columns, lines) is passed via environment variables.
     pub fn spawn(tool: &ToolMetadata, args: &[&str], terminal_size: (u16, u16)) -> Result<Self> {
-        let binary_path = find_binary(tool.binary);
+        // Built-in tools run as `<this executable> <subcommand>`, others as separate executables
+        let mut command = match tool.subcommand {
+            Some(subcommand) => tools::self_command(subcommand)?,
+            None => Command::new(find_binary(tool.binary)),
+        };
+        let binary_path = PathBuf::from(command.get_program());
         let (cols, rows) = terminal_size;
 
-        let mut child = Command::new(&binary_path)
+        let mut child = command
             .args(args)
             .env("COLUMNS", cols.to_string())
             .env("LINES", rows.to_string())
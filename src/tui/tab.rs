@@ -3,8 +3,16 @@
 //! Each tab encapsulates the complete state for one tool instance:
 //! form configuration, process management, and output display.
 
+use std::collections::BTreeMap;
+use std::time::Instant;
+
+use lsl_recording_toolbox::memory::MemoryStatus;
+use lsl_recording_toolbox::status::StreamStatus;
+
+use super::explorer::ExplorerState;
 use super::form::FormState;
 use super::process::ProcessManager;
+use super::wizard::WizardState;
 
 /// Mode for an individual tab.
 #[derive(Clone, PartialEq)]
@@ -15,6 +23,33 @@ pub enum TabMode {
     Running,
     /// Tool has completed execution
     Completed,
+    /// Browsing a store in the Store Explorer (no process)
+    Explore,
+}
+
+/// Latest memory report of one recorder, for the memory panel.
+#[derive(Clone, Default)]
+pub struct StreamMemory {
+    /// Last `STATUS MEMORY` report
+    pub status: MemoryStatus,
+    /// Whether the recorder reported `STATUS MEMORY_GROWTH` (cleared by `MEMORY_STABLE`)
+    pub growing: bool,
+}
+
+/// Latest progress report of one recorder, for the dashboard.
+#[derive(Clone)]
+pub struct StreamProgress {
+    /// Last `STATUS PROGRESS` report
+    pub status: StreamStatus,
+    /// When the report arrived, to age the last flush between reports
+    pub received: Instant,
+}
+
+impl StreamProgress {
+    /// Seconds since the last flush, as of now.
+    pub fn since_flush(&self) -> Option<f64> {
+        self.status.since_flush_s.map(|age| age + self.received.elapsed().as_secs_f64())
+    }
 }
 
 /// State of a single tab - encapsulates everything needed for one tool instance.
@@ -34,8 +69,16 @@ pub struct TabState {
     pub form_state: Option<FormState>,
     /// Process manager if running
     pub process_manager: Option<ProcessManager>,
+    /// Store Explorer state if in Explore mode
+    pub explorer: Option<ExplorerState>,
     /// Output buffer from process
     pub output_lines: Vec<String>,
+    /// Memory reports per recorder label (`--memory-monitor`)
+    pub memory: BTreeMap<String, StreamMemory>,
+    /// Progress reports per recorder label (`--status-interval`)
+    pub progress: BTreeMap<String, StreamProgress>,
+    /// Step of the experiment wizard this tab runs, if it is part of one
+    pub wizard: Option<WizardState>,
     /// Scroll offset for output viewing
     pub scroll_offset: usize,
     /// Cached visible height for scroll calculations (updated on resize)
@@ -59,7 +102,11 @@ impl TabState {
             command: None,
             form_state: Some(form),
             process_manager: None,
+            explorer: None,
             output_lines: Vec::new(),
+            memory: BTreeMap::new(),
+            progress: BTreeMap::new(),
+            wizard: None,
             scroll_offset: 0,
             cached_visible_height: 20, // Default, will be updated on first render
             auto_scroll_enabled: true,
@@ -72,15 +119,32 @@ impl TabState {
     pub fn start_running(&mut self, process_manager: ProcessManager, command: String) {
         self.mode = TabMode::Running;
         self.command = Some(command);
+        // Later wizard steps work on the store they were run with
+        if let Some(ref mut wizard) = self.wizard
+            && let Some(ref form) = self.form_state
+            && let Some(path) = form.get_value("zarr_file").or_else(|| form.get_value("file_path"))
+        {
+            wizard.store_path = Some(path.trim().to_string());
+        }
         self.form_state = None;
         self.process_manager = Some(process_manager);
         self.output_lines.clear();
+        self.memory.clear();
+        self.progress.clear();
         self.scroll_offset = 0;
         self.auto_scroll_enabled = true;
         self.input_buffer.clear();
         self.input_cursor = 0;
     }
 
+    /// Open the Store Explorer on the store chosen in the form.
+    pub fn start_exploring(&mut self, explorer: ExplorerState) {
+        self.mode = TabMode::Explore;
+        self.command = None;
+        self.form_state = None;
+        self.explorer = Some(explorer);
+    }
+
     /// Mark the tool as completed with optional exit code.
     pub fn complete(&mut self, exit_code: Option<i32>) {
         self.mode = TabMode::Completed;
@@ -91,6 +155,14 @@ impl TabState {
         } else {
             self.output_lines.push("\n[Process terminated]".to_string());
         }
+        if let Some(ref wizard) = self.wizard {
+            let note = match wizard.next_step() {
+                Some((next, store)) => format!("[Wizard: press N to run {} on {}]", next.binary(), store),
+                None if wizard.step.next().is_none() => "[Wizard: done]".to_string(),
+                None => "[Wizard: no store was written, nothing to continue with]".to_string(),
+            };
+            self.output_lines.push(note);
+        }
     }
 
     /// Add output line from the running process.
@@ -102,7 +174,50 @@ impl TabState {
             self.output_lines.drain(0..TRIM_AMOUNT);
             self.scroll_offset = self.scroll_offset.saturating_sub(TRIM_AMOUNT);
         }
-        self.output_lines.push(sanitize_output(&line));
+        let line = sanitize_output(&line);
+        // Progress reports go to the dashboard instead of the log
+        if self.update_progress(&line) {
+            return;
+        }
+        self.update_memory(&line);
+        if let Some(ref mut wizard) = self.wizard {
+            wizard.update_store(&line);
+        }
+        self.output_lines.push(line);
+    }
+
+    /// Recorder that printed a status line: lsl-multi-recorder prefixes each line with
+    /// `[<label>]`; single recorders use the tab title.
+    fn status_label(&self, line: &str, status_at: usize) -> String {
+        line[..status_at]
+            .rsplit('[')
+            .filter_map(|part| part.split_once(']').map(|(label, _)| label))
+            .find(|label| !label.starts_with('+'))
+            .unwrap_or(&self.title)
+            .to_string()
+    }
+
+    /// Track `STATUS PROGRESS` lines per recorder; returns whether the line was one.
+    fn update_progress(&mut self, line: &str) -> bool {
+        let Some(status_at) = line.find("STATUS PROGRESS ") else { return false };
+        let label = self.status_label(line, status_at);
+        let Some(status) = StreamStatus::parse_progress(&label, line) else { return false };
+        self.progress.insert(label, StreamProgress { status, received: Instant::now() });
+        true
+    }
+
+    /// Track `STATUS MEMORY*` lines per recorder.
+    fn update_memory(&mut self, line: &str) {
+        let Some(status_at) = line.find("STATUS MEMORY") else { return };
+        let label = self.status_label(line, status_at);
+        let entry = self.memory.entry(label).or_default();
+        if let Some(status) = MemoryStatus::parse(line) {
+            entry.status = status;
+        } else if line[status_at..].starts_with("STATUS MEMORY_GROWTH") {
+            entry.growing = true;
+        } else if line[status_at..].starts_with("STATUS MEMORY_STABLE") {
+            entry.growing = false;
+        }
     }
 
     /// Check if this tab has a running process.
@@ -7,11 +7,24 @@ pub fn create_config_form(tool_idx: usize) -> FormState {
     match tool_idx {
         0 => create_recorder_form(),
         1 => create_multi_recorder_form(),
-        2 => create_inspect_form(),
-        3 => create_validate_form(),
-        4 => create_sync_form(),
-        5 => create_replay_form(),
-        6 => create_dummy_stream_form(),
+        2 => create_discover_form(),
+        3 => create_spotcheck_form(),
+        4 => create_inspect_form(),
+        5 => create_validate_form(),
+        6 => create_query_form(),
+        7 => create_index_form(),
+        8 => create_explorer_form(),
+        9 => create_sync_form(),
+        10 => create_repair_form(),
+        11 => create_export_form(),
+        12 => create_import_form(),
+        13 => create_badchannels_form(),
+        14 => create_markers_form(),
+        15 => create_replay_form(),
+        16 => create_loopback_form(),
+        17 => create_dummy_stream_form(),
+        18 => create_calibrate_form(),
+        19 => create_demo_form(),
         _ => create_recorder_form(), // fallback
     }
 }
@@ -20,6 +33,7 @@ pub fn create_config_form(tool_idx: usize) -> FormState {
 pub fn form_to_args(form: &FormState) -> Vec<String> {
     let mut args = Vec::new();
     let mut positional_arg: Option<String> = None;
+    let mut subcommand: Option<String> = None;
 
     for field in &form.fields {
         let value = field.value.trim();
@@ -31,7 +45,13 @@ pub fn form_to_args(form: &FormState) -> Vec<String> {
         match field.name.as_str() {
             // Boolean flags - only add if "true" or similar
             "interactive" | "quiet" | "verbose" | "immediate_flush" | "memory_monitor" |
-            "list" | "trim_start" | "trim_end" | "trim_both" => {
+            "list" | "trim_start" | "trim_end" | "trim_both" | "allow_rate_overrun" |
+            "json" | "watch" | "record_all" | "rebuild" | "utc" |
+            "append_session" | "dry_run" | "units" | "aligned_time" | "stop_all_on_failure" |
+            "no_clock_sync" | "detect" | "include_bad_channels" | "monitor" | "materialize" |
+            "batch" | "no_realtime" | "clean" | "auto_subject" | "exclude_pauses" | "quality" |
+            "join_session" | "force" | "config" | "poisson" | "all" | "sample_flags" | "timeline" |
+            "preflight" | "strict" => {
                 if is_truthy(value) {
                     args.push(format!("--{}", field.name.replace('_', "-")));
                 }
@@ -66,6 +86,15 @@ pub fn form_to_args(form: &FormState) -> Vec<String> {
                     }
                 }
             }
+            "required_streams" => {
+                args.push("--required-streams".to_string());
+                for v in value.split(',') {
+                    let v = v.trim();
+                    if !v.is_empty() {
+                        args.push(v.to_string());
+                    }
+                }
+            }
             // lsl-sync --stream (can be repeated)
             "streams" => {
                 for v in value.split(',') {
@@ -76,8 +105,42 @@ pub fn form_to_args(form: &FormState) -> Vec<String> {
                     }
                 }
             }
-            // Positional argument (file path)
-            "file_path" | "zarr_file" => {
+            // lsl-recorder/lsl-multi-recorder --rule (can be repeated)
+            "rules" => {
+                for v in value.split(',') {
+                    let v = v.trim();
+                    if !v.is_empty() {
+                        args.push("--rule".to_string());
+                        args.push(v.to_string());
+                    }
+                }
+            }
+            // lsl-multi-recorder --plausible-range (can be repeated; lsl-recorder takes one)
+            "plausible_range" => {
+                for v in value.split(',') {
+                    let v = v.trim();
+                    if !v.is_empty() {
+                        args.push("--plausible-range".to_string());
+                        args.push(v.to_string());
+                    }
+                }
+            }
+            // lsl-recorder/lsl-multi-recorder --capture-on (can be repeated)
+            "capture_on" => {
+                for v in value.split(',') {
+                    let v = v.trim();
+                    if !v.is_empty() {
+                        args.push("--capture-on".to_string());
+                        args.push(v.to_string());
+                    }
+                }
+            }
+            // Subcommand (lsl-demo generate), goes before everything else
+            "command" => {
+                subcommand = Some(value.to_string());
+            }
+            // Positional argument (file path or query expression)
+            "file_path" | "zarr_file" | "expression" | "data_root" => {
                 positional_arg = Some(value.to_string());
             }
             // Regular named arguments
@@ -92,6 +155,9 @@ pub fn form_to_args(form: &FormState) -> Vec<String> {
     if let Some(pos) = positional_arg {
         args.insert(0, pos);
     }
+    if let Some(command) = subcommand {
+        args.insert(0, command);
+    }
 
     args
 }
@@ -123,45 +189,161 @@ fn create_recorder_form() -> FormState {
         // Required
         FormField::required("source_id", "Source ID *", "1234", "LSL stream source ID"),
         FormField::dir_path("output", "Output Path *", "recording", true, "Type or Space to browse"),
+        // Alternative resolution (leave empty to use Source ID)
+        FormField::optional("by_name", "By Name", "", "Resolve by stream name instead"),
+        FormField::optional("by_type", "By Type", "", "Resolve first stream of this type"),
+        FormField::optional("predicate", "Predicate", "", "LSL XPath predicate (needs Stream Name)"),
         // Metadata
         FormField::optional("stream_name", "Stream Name", "", "Name in Zarr (defaults to source ID)"),
+        FormField::select_field("name_by", "Group Named By", &["name", "uid"], 0),
         FormField::optional("subject", "Subject", "", "Subject identifier"),
+        FormField::optional("subject_pattern", "Subject Pattern", "", "For Auto Subject, '#' per digit (empty = P###)"),
         FormField::optional("session_id", "Session ID", "", "Session identifier"),
         FormField::optional("notes", "Notes", "", "Recording notes"),
         // Recording options
         FormField::float_field("duration", "Duration (s)", 0.0, false, "Max recording duration (0=unlimited)"),
+        FormField::optional("start_at", "Start At", "", "Local time to start recording, e.g. 14:30:00"),
+        FormField::optional("blocks", "Blocks", "", "Number of blocks (empty = no block schedule)"),
+        FormField::optional("block_duration", "Block Duration (s)", "", "Length of each block"),
+        FormField::optional("inter_block", "Inter-Block (s)", "", "Pause between blocks"),
         FormField::float_field("resolve_timeout", "Resolve Timeout", 5.0, false, "Stream resolution timeout (s)"),
         // Buffering
         FormField::float_field("flush_interval", "Flush Interval", 1.0, false, "Flush interval (seconds)"),
         FormField::int_field("flush_buffer_size", "Flush Buffer Size", 50, false, "Samples before flush"),
         FormField::int_field("buffer_size", "Stream Buffer", 1000, false, "LSL buffer size"),
+        // Storage
+        FormField::int_field("chunk_samples", "Chunk Samples", 100, false, "Samples per chunk"),
+        FormField::optional("shard_samples", "Shard Samples", "", "Multiple of chunk samples (empty = no sharding)"),
+        FormField::optional("mirror", "Mirror Output", "", "Second store written in parallel, e.g. on another disk"),
+        FormField::optional("chmod", "File Mode", "", "Octal mode for the store's files, e.g. 660"),
+        FormField::optional("chgrp", "Group", "", "Group owning the store (name or GID)"),
+        FormField::select_field("compressor", "Compressor", &["lz4", "zstd", "none"], 0),
+        FormField::int_field("compression_level", "Compression Level", 5, false, "Blosc level (0-9)"),
+        // Rate guard
+        FormField::float_field("max_rate_factor", "Max Rate Factor", 4.0, false, "Abort above this multiple of nominal rate (0=off)"),
+        // Live tap
+        FormField::optional("tap", "Live Tap", "", "WebSocket address, e.g. ws://0.0.0.0:9001"),
+        FormField::select_field("tap_format", "Tap Format", &["json", "binary"], 0),
+        FormField::float_field("tap_rate", "Tap Rate (Hz)", 50.0, false, "Samples per second sent to tap clients"),
+        // LSL monitor outlet
+        FormField::bool_field("monitor", "Monitor Outlet", false),
+        FormField::float_field("monitor_rate", "Monitor Rate (Hz)", 50.0, false, "Rate of the <stream>_monitor outlet"),
+        FormField::optional("monitor_channels", "Monitor Channels", "", "Comma-separated labels or indices (empty = all)"),
         // Flags
         FormField::bool_field("interactive", "Interactive", false),
+        FormField::bool_field("preflight", "Preflight Only", false),
         FormField::bool_field("quiet", "Quiet Mode", false),
         FormField::bool_field("immediate_flush", "Immediate Flush", false),
         FormField::bool_field("memory_monitor", "Memory Monitor", false),
+        FormField::float_field("memory_interval", "Memory Interval (s)", 10.0, false, "Seconds between memory reports"),
+        FormField::float_field("memory_growth_limit", "Memory Growth Limit", 10.0, false, "MB/min of RSS growth before warning (0 = off)"),
+        FormField::float_field("info_refresh", "Info Refresh (s)", 0.0, false, "Re-fetch stream metadata this often (0 = off)"),
+        FormField::float_field("reconnect_after", "Reconnect After (s)", 5.0, false, "Re-resolve a lost stream after this long (0 = off)"),
+        FormField::float_field("chunk_pull_rate", "Chunk Pull Rate (Hz)", 1000.0, false, "Pull streams this fast or faster in chunks (0 = off)"),
+        FormField::optional("status_port", "Status Port", "", "Serve JSON status (GET /status) on this port"),
+        FormField::float_field("status_interval", "Status Interval (s)", 1.0, false, "Progress reports for the dashboard (0 = off)"),
+        FormField::optional("control_port", "Control Port", "", "Accept START/STOP/QUIT over TCP or WebSocket"),
+        FormField::bool_field("allow_rate_overrun", "Allow Rate Overrun", false),
+        FormField::bool_field("append_session", "Append Session", false),
+        FormField::bool_field("auto_subject", "Auto Subject", false),
+        // Signal quality
+        FormField::bool_field("quality", "Quality Checks", false),
+        FormField::float_field("flatline_seconds", "Flatline After (s)", 2.0, false, "Seconds without change reported as flatline"),
+        FormField::optional("clip_level", "Clip Level", "", "Absolute clipping value (empty = integer format range)"),
+        FormField::optional("rules", "Rules", "", "Comma-separated, e.g. rms < 5 for 30s then pause"),
+        FormField::optional("plausible_range", "Plausible Range", "", "e.g. -10..10 mV or ±500 (empty = no check)"),
+        // Sample flags
+        FormField::bool_field("sample_flags", "Sample Flags", false),
+        FormField::optional("flag_channel", "Flag Channel", "", "Channel whose non-zero values flag samples (empty = none)"),
+        FormField::optional("capture_on", "Capture On", "", "Comma-separated markers that take a capture (marker streams)"),
+        FormField::optional("capture_command", "Capture Command", "", "Run per capture, {file} = path without extension"),
     ])
 }
 
 fn create_multi_recorder_form() -> FormState {
     FormState::new("LSL Multi-Recorder", vec![
         // Required
-        FormField::required("source_ids", "Source IDs *", "", "Comma-separated source IDs"),
+        FormField::optional("source_ids", "Source IDs", "", "Comma-separated source IDs (or enable Record All)"),
         FormField::dir_path("output", "Output Path *", "recording", true, "Type or Space to browse"),
         // Metadata
         FormField::optional("stream_names", "Stream Names", "", "Comma-separated names (optional)"),
+        FormField::select_field("name_by", "Group Named By", &["name", "uid"], 0),
         FormField::optional("subject", "Subject", "", "Subject identifier"),
+        FormField::optional("subject_pattern", "Subject Pattern", "", "For Auto Subject, '#' per digit (empty = P###)"),
         FormField::optional("session_id", "Session ID", "", "Session identifier"),
         FormField::optional("notes", "Notes", "", "Recording notes"),
         // Recording options
         FormField::float_field("duration", "Duration (s)", 0.0, false, "Max recording duration (0=unlimited)"),
+        FormField::optional("start_at", "Start At", "", "Local time to start recording, e.g. 14:30:00"),
+        FormField::optional("blocks", "Blocks", "", "Number of blocks (empty = no block schedule)"),
+        FormField::optional("block_duration", "Block Duration (s)", "", "Length of each block"),
+        FormField::optional("inter_block", "Inter-Block (s)", "", "Pause between blocks"),
         FormField::float_field("resolve_timeout", "Resolve Timeout", 5.0, false, "Stream resolution timeout (s)"),
         // Buffering
         FormField::float_field("flush_interval", "Flush Interval", 1.0, false, "Flush interval (seconds)"),
         FormField::int_field("flush_buffer_size", "Flush Buffer Size", 50, false, "Samples before flush"),
+        // Storage
+        FormField::int_field("chunk_samples", "Chunk Samples", 100, false, "Samples per chunk"),
+        FormField::optional("shard_samples", "Shard Samples", "", "Multiple of chunk samples (empty = no sharding)"),
+        FormField::optional("mirror", "Mirror Output", "", "Second store written in parallel, e.g. on another disk"),
+        FormField::optional("chmod", "File Mode", "", "Octal mode for the store's files, e.g. 660"),
+        FormField::optional("chgrp", "Group", "", "Group owning the store (name or GID)"),
+        FormField::select_field("compressor", "Compressor", &["lz4", "zstd", "none"], 0),
+        FormField::int_field("compression_level", "Compression Level", 5, false, "Blosc level (0-9)"),
         // Flags
+        FormField::bool_field("record_all", "Record All", false),
         FormField::bool_field("quiet", "Quiet Mode", false),
         FormField::bool_field("immediate_flush", "Immediate Flush", false),
+        FormField::bool_field("append_session", "Append Session", false),
+        FormField::bool_field("join_session", "Join Session", false),
+        FormField::bool_field("auto_subject", "Auto Subject", false),
+        // Memory
+        FormField::bool_field("memory_monitor", "Memory Monitor", false),
+        FormField::float_field("memory_interval", "Memory Interval (s)", 10.0, false, "Seconds between memory reports"),
+        FormField::float_field("memory_growth_limit", "Memory Growth Limit", 10.0, false, "MB/min of RSS growth before warning (0 = off)"),
+        FormField::float_field("info_refresh", "Info Refresh (s)", 0.0, false, "Re-fetch stream metadata this often (0 = off)"),
+        FormField::optional("status_port", "Status Port", "", "Serve JSON status (GET /status) on this port"),
+        FormField::float_field("status_interval", "Status Interval (s)", 1.0, false, "Progress reports for the dashboard (0 = off)"),
+        FormField::optional("control_port", "Control Port", "", "Accept START/STOP/QUIT over TCP or WebSocket"),
+        // Signal quality
+        FormField::bool_field("quality", "Quality Checks", false),
+        FormField::float_field("flatline_seconds", "Flatline After (s)", 2.0, false, "Seconds without change reported as flatline"),
+        FormField::optional("clip_level", "Clip Level", "", "Absolute clipping value (empty = integer format range)"),
+        FormField::optional("rules", "Rules", "", "Comma-separated, STREAM: prefix for one stream"),
+        FormField::optional("plausible_range", "Plausible Ranges", "", "Comma-separated, e.g. EMG: ±10 mV (STREAM: prefix for one stream)"),
+        FormField::bool_field("sample_flags", "Sample Flags", false),
+        FormField::optional("capture_stream", "Capture Stream", "", "Marker stream whose markers take captures"),
+        FormField::optional("capture_on", "Capture On", "", "Comma-separated markers that take a capture"),
+        FormField::optional("capture_command", "Capture Command", "", "Run per capture, {file} = path without extension"),
+        // Failure policy
+        FormField::bool_field("stop_all_on_failure", "Stop All on Failure", false),
+        FormField::float_field("failure_grace", "Failure Grace (s)", 10.0, false, "Time a lost stream may take to reconnect"),
+        FormField::float_field("reconnect_after", "Reconnect After (s)", 5.0, false, "Re-resolve a lost stream after this long (0 = off)"),
+        FormField::float_field("chunk_pull_rate", "Chunk Pull Rate (Hz)", 1000.0, false, "Pull streams this fast or faster in chunks (0 = off)"),
+        FormField::optional("required_streams", "Required Streams", "", "Comma-separated (empty = all streams)"),
+    ])
+}
+
+fn create_discover_form() -> FormState {
+    FormState::new("LSL Discover", vec![
+        FormField::float_field("timeout", "Timeout", 2.0, false, "Seconds to wait for responses"),
+        FormField::float_field("interval", "Refresh Interval", 5.0, false, "Seconds between refreshes (watch mode)"),
+        FormField::bool_field("watch", "Watch", false),
+        FormField::bool_field("json", "JSON Output", false),
+    ])
+}
+
+fn create_spotcheck_form() -> FormState {
+    FormState::new("LSL Spotcheck", vec![
+        FormField::optional("source_ids", "Source IDs", "", "Comma-separated source IDs (or enable Record All)"),
+        FormField::optional("stream_names", "Stream Names", "", "Comma-separated names (optional)"),
+        FormField::bool_field("record_all", "Record All", false),
+        FormField::int_field("duration", "Duration (s)", 30, false, "Length of the test recording"),
+        FormField::dir_path("output", "Output Path", "spotcheck_{date}_{time}", false, "Test store (without .zarr)"),
+        FormField::float_field("threshold_ms", "Threshold (ms)", 200.0, false, "Largest difference still considered synchronized"),
+        FormField::float_field("max_rate_error_percent", "Max Rate Error (%)", 5.0, false, "Allowed deviation from the nominal rate"),
+        FormField::float_field("max_lost_percent", "Max Lost (%)", 1.0, false, "Allowed share of the recording lost to gaps"),
+        FormField::bool_field("verbose", "Show Recorder Output", false),
     ])
 }
 
@@ -170,23 +352,121 @@ fn create_inspect_form() -> FormState {
         FormField::file_path("file_path", "Zarr File *", "", true, "Type or Space to browse"),
         FormField::optional("stream", "Stream Filter", "", "Filter to specific stream"),
         FormField::bool_field("verbose", "Verbose", false),
+        FormField::bool_field("utc", "Times in UTC", false),
+        FormField::bool_field("units", "Unit Ranges", false),
+        FormField::bool_field("config", "Recorder Config", false),
+        FormField::bool_field("timeline", "Time Axis Summary", false),
+        FormField::bool_field("json", "JSON Output", false),
     ])
 }
 
 fn create_validate_form() -> FormState {
     FormState::new("LSL Validate", vec![
         FormField::file_path("file_path", "Zarr File *", "", true, "Type or Space to browse"),
+        FormField::float_field("threshold_ms", "Threshold (ms)", 200.0, false, "Largest difference still considered synchronized"),
+        FormField::select_field("format", "Report Format", &["text", "json", "yaml"], 0),
+        FormField::float_field("gap_factor", "Gap Factor", 2.0, false, "Intervals over this many nominal periods are gaps"),
+        FormField::float_field("drift_budget_ppm", "Drift Budget (ppm)", 50.0, false, "Warn above this clock drift between streams"),
+        FormField::bool_field("verbose", "List Gaps", false),
+    ])
+}
+
+fn create_query_form() -> FormState {
+    FormState::new("LSL Query", vec![
+        FormField::optional("expression", "Expression", "", "e.g. subject == \"P00*\" && duration > 600"),
+        FormField::dir_path("root", "Data Root *", ".", true, "Type or Space to browse"),
+        FormField::optional("fields", "Fields", "path,subject,session_id,duration,stream_names", "Comma-separated output fields"),
+        FormField::bool_field("json", "JSON Output", false),
+    ])
+}
+
+fn create_explorer_form() -> FormState {
+    FormState::new("Store Explorer", vec![
+        FormField::dir_path("file_path", "Zarr Store *", "", true, "Type or Space to browse"),
+    ])
+}
+
+fn create_repair_form() -> FormState {
+    FormState::new("LSL Repair", vec![
+        FormField::file_path("file_path", "Zarr File *", "", true, "Type or Space to browse"),
+        FormField::bool_field("dry_run", "Dry Run", true),
+    ])
+}
+
+fn create_export_form() -> FormState {
+    FormState::new("LSL Export", vec![
+        FormField::file_path("file_path", "Zarr File *", "", true, "Type or Space to browse"),
+        FormField::select_field("format", "Format", &["xdf", "edf", "bdf", "csv", "parquet", "ndjson"], 0),
+        FormField::optional("output", "Output", "", "File, or directory for csv/parquet/ndjson"),
+        FormField::optional("streams", "Stream Filter", "", "Comma-separated streams to export"),
+        FormField::bool_field("aligned_time", "Aligned Time", false),
+        FormField::bool_field("include_bad_channels", "Include Bad Channels", false),
+        FormField::bool_field("strict", "Strict (fail on damaged chunks)", false),
+        FormField::float_field("resample_rate", "Resample Rate (Hz)", 100.0, false, "EDF/BDF: rate for irregular streams"),
+        FormField::optional("split_duration", "Split Duration (s)", "", "EDF/BDF: max seconds per file"),
+        FormField::optional("start", "Start (s)", "", "Tables: seconds from recording start"),
+        FormField::optional("end", "End (s)", "", "Tables: seconds from recording start"),
+    ])
+}
+
+fn create_badchannels_form() -> FormState {
+    FormState::new("LSL Bad Channels", vec![
+        FormField::file_path("file_path", "Zarr File *", "", true, "Type or Space to browse"),
+        FormField::optional("streams", "Stream Filter", "", "Comma-separated streams"),
+        FormField::bool_field("detect", "Detect", false),
+        FormField::optional("mark", "Mark Channel", "", "Label or index (needs one stream)"),
+        FormField::optional("reason", "Reason", "", "Reason stored with the mark"),
+        FormField::bool_field("dry_run", "Dry Run", false),
+    ])
+}
+
+fn create_markers_form() -> FormState {
+    FormState::new("LSL Markers", vec![
+        FormField::file_path("file_path", "Zarr File *", "", true, "Type or Space to browse"),
+        FormField::file_path("table", "Table", "", false, "TOML mapping; lists labels if empty"),
+        FormField::optional("streams", "Stream Filter", "", "Comma-separated marker streams"),
+        FormField::bool_field("dry_run", "Dry Run", false),
+    ])
+}
+
+fn create_import_form() -> FormState {
+    FormState::new("LSL Import", vec![
+        FormField::file_path("file_path", "XDF File *", "", true, "Type or Space to browse"),
+        FormField::optional("output", "Output Store", "", "Defaults to <file>.zarr"),
+        FormField::optional("streams", "Stream Filter", "", "Comma-separated streams to import"),
+        FormField::optional("subject", "Subject", "", "Subject identifier"),
+        FormField::optional("session_id", "Session ID", "", "Session identifier"),
+        FormField::bool_field("no_clock_sync", "No Clock Sync", false),
+    ])
+}
+
+fn create_index_form() -> FormState {
+    FormState::new("LSL Index", vec![
+        FormField::dir_path("data_root", "Data Root *", ".", true, "Type or Space to browse"),
+        FormField::optional("db", "Database", "", "Defaults to <root>/lsl-index.sqlite"),
+        FormField::bool_field("rebuild", "Rebuild", false),
+        FormField::bool_field("quiet", "Quiet Mode", false),
     ])
 }
 
 fn create_sync_form() -> FormState {
     FormState::new("LSL Sync", vec![
         FormField::file_path("zarr_file", "Zarr File *", "", true, "Type or Space to browse"),
-        FormField::select_field("mode", "Sync Mode", &["common-start", "first-stream", "last-stream", "absolute-zero"], 0),
+        FormField::select_field("mode", "Sync Mode", &["common-start", "first-stream", "last-stream", "absolute-zero", "drift-correct"], 0),
+        FormField::optional("reference", "Reference Stream", "", "Drift-correct reference (first regular if empty)"),
+        FormField::int_field("drift_segments", "Drift Segments", 1, false, "Piecewise-linear drift segments"),
         FormField::optional("streams", "Stream Filter", "", "Comma-separated streams to process"),
         FormField::bool_field("trim_start", "Trim Start", false),
         FormField::bool_field("trim_end", "Trim End", false),
         FormField::bool_field("trim_both", "Trim Both", false),
+        FormField::bool_field("exclude_pauses", "Exclude Pauses", false),
+        FormField::bool_field("materialize", "Materialize", false),
+        FormField::select_field("raw_retention", "Raw Arrays", &["keep", "drop", "archive"], 0),
+        FormField::optional("archive_dir", "Archive Dir", "", "Where archived raw arrays go"),
+        FormField::optional("retain_days", "Retain Days", "", "Days before archives may be purged"),
+        FormField::optional("resample_to", "Resample To (Hz)", "", "Common grid rate for regular streams"),
+        FormField::optional("resample_to_stream", "Resample To Stream", "", "Use this stream's samples as the grid"),
+        FormField::bool_field("clean", "Clean Sync Outputs", false),
         FormField::bool_field("verbose", "Verbose", false),
     ])
 }
@@ -194,29 +474,86 @@ fn create_sync_form() -> FormState {
 fn create_replay_form() -> FormState {
     FormState::new("LSL Replay", vec![
         FormField::file_path("file_path", "Zarr File *", "", true, "Type or Space to browse"),
-        FormField::required("stream", "Stream Name *", "", "Stream to replay"),
+        FormField::optional("stream", "Stream Name", "", "Stream to replay (empty with Replay All Streams)"),
+        FormField::bool_field("all", "Replay All Streams", false),
         FormField::optional("output_name", "Output Name", "", "Custom output stream name"),
         FormField::float_field("speed", "Speed", 1.0, false, "Playback speed (1.0 = real-time)"),
+        FormField::optional("start_time", "Start Time (s)", "", "Replay from this time (aligned_time if synced)"),
+        FormField::optional("end_time", "End Time (s)", "", "Replay up to this time"),
+        FormField::optional("max_lag", "Max Lag (s)", "", "Warn when samples are pushed this late (default 0.1)"),
+        FormField::optional("chunk_ms", "Chunk (ms)", "", "Push samples in chunks with their own timestamps"),
         FormField::bool_field("loop", "Loop", true),
+        FormField::bool_field("batch", "Batch Mode", false),
+        FormField::bool_field("no_realtime", "No Real-Time Pacing", false),
         FormField::bool_field("list", "List Streams", false),
         FormField::bool_field("verbose", "Verbose", false),
     ])
 }
 
+fn create_loopback_form() -> FormState {
+    FormState::new("LSL Loopback", vec![
+        FormField::file_path("file_path", "Zarr File *", "", true, "Type or Space to browse"),
+        FormField::required("stream", "Stream Name *", "", "Numeric stream to replay and re-record"),
+        FormField::int_field("duration", "Duration (s)", 20, false, "Length of the re-recording"),
+        FormField::dir_path("output", "Output Path", "loopback_{date}_{time}", false, "Re-recorded store (without .zarr)"),
+        FormField::float_field("max_jitter_ms", "Max Jitter (ms)", 5.0, false, "Largest timing residual for a pass"),
+        FormField::bool_field("json", "JSON Output", false),
+        FormField::bool_field("verbose", "Show Tool Output", false),
+    ])
+}
+
 fn create_dummy_stream_form() -> FormState {
     FormState::new("LSL Dummy Stream", vec![
         // Stream identity
         FormField::optional("name", "Stream Name", "TestStream", "Name of the stream"),
-        FormField::optional("stream_type", "Stream Type", "EMG", "Type (EMG, EEG, etc.)"),
+        FormField::optional("stream_type", "Stream Type", "", "Type (empty = EMG or the simulated signal)"),
         FormField::optional("source_id", "Source ID", "TEST_1234", "Unique source identifier"),
         // Signal parameters
         FormField::int_field("channels", "Channels", 100, false, "Number of channels"),
         FormField::int_field("sample_rate", "Sample Rate", 10000, false, "Sampling rate (Hz)"),
         FormField::int_field("chunk_size", "Chunk Size", 18, false, "Samples per chunk"),
         FormField::optional("freq_range", "Freq Range", "1,10", "Frequency range (min,max)"),
-        FormField::select_field("data_type", "Data Type", &["float32", "int16"], 0),
+        FormField::select_field("data_type", "Data Type", &["float32", "int16", "string"], 0),
+        FormField::optional("waveform", "Waveform", "", "sine, chirp, white-noise, pink-noise (empty = modulated sine)"),
+        FormField::optional("frequency", "Frequency", "", "Same sine frequency on every channel (Hz)"),
+        FormField::optional("simulate", "Simulate", "", "emg, eeg or ecg (empty = waveform)"),
+        FormField::optional("trigger_frequency", "Trigger Frequency", "", "Square-wave trigger on the last channel (Hz)"),
+        // Fault injection
+        FormField::optional("jitter_ms", "Jitter (ms)", "", "Timestamp jitter standard deviation"),
+        FormField::optional("dropout_prob", "Dropout Probability", "", "Probability that a chunk is lost"),
+        FormField::optional("drift_ppm", "Drift (ppm)", "", "Clock drift of the timestamps"),
+        FormField::optional("burst_gap", "Burst Gap (s)", "", "Stall length, every Burst Interval"),
+        FormField::optional("burst_interval", "Burst Interval (s)", "", "Seconds between stalls (default 10)"),
+        FormField::optional("calibration_interval", "Calibration Interval", "", "Emit calibration pulses every N seconds"),
+        // Marker events (Data Type string)
+        FormField::optional("events", "Events", "", "Event names, comma-separated (default trial_start,stimulus,response)"),
+        FormField::optional("event_interval", "Event Interval (s)", "", "Seconds between events (default 1)"),
+        FormField::optional("max_lag", "Max Lag (s)", "", "Warn when chunks are pushed this late (default 0.1)"),
+        FormField::bool_field("poisson", "Poisson Timing", false),
         // Flags
         FormField::bool_field("noise", "Noise Mode", false),
         FormField::bool_field("verbose", "Verbose", false),
     ])
 }
+
+fn create_calibrate_form() -> FormState {
+    FormState::new("LSL Calibrate", vec![
+        FormField::optional("source_id", "Source ID", "", "Calibration-capable source (empty = dummy stream)"),
+        FormField::int_field("pulses", "Pulses", 10, false, "Number of pulses to measure"),
+        FormField::float_field("interval", "Interval (s)", 1.0, false, "Seconds between pulses"),
+        FormField::float_field("threshold", "Threshold", 0.5, false, "Pulse detection level"),
+        FormField::file_path("store", "Zarr File", "", false, "Store the result here (optional)"),
+        FormField::optional("stream", "Stream", "", "Stream group for the result (root if empty)"),
+        FormField::bool_field("json", "JSON Output", false),
+    ])
+}
+
+fn create_demo_form() -> FormState {
+    FormState::new("LSL Demo", vec![
+        FormField::select_field("command", "Command", &["generate"], 0),
+        FormField::dir_path("file_path", "Output Store", "demo.zarr", false, "Example store to write"),
+        FormField::float_field("duration", "Duration (s)", 30.0, false, "Length of the session"),
+        FormField::int_field("seed", "Seed", 42, false, "Same seed, same data"),
+        FormField::bool_field("force", "Replace Existing", false),
+    ])
+}
@@ -4,17 +4,25 @@ use ratatui::{
     layout::{Constraint, Direction, Layout, Margin, Rect},
     style::{Color, Modifier, Style},
     text::{Line, Span},
-    widgets::{Block, Borders, List, ListItem, Paragraph, Scrollbar, ScrollbarOrientation, ScrollbarState, Wrap},
+    widgets::{
+        Block, Borders, LineGauge, List, ListItem, Paragraph, Scrollbar, ScrollbarOrientation, ScrollbarState, Wrap,
+    },
     Frame,
 };
 
+use lsl_recording_toolbox::status::RecordingState;
+use lsl_recording_toolbox::tui_settings::FileDialogMode;
+
 use super::app::{App, ToolCategory, TOOLS};
+use super::native_dialog;
 use super::tab::{TabMode, TabState};
 use super::ui_dialog;
+use super::ui_explorer;
 use super::ui_file_browser;
 use super::ui_form;
-use super::ui_helpers::{calculate_command_height, help_item, help_item_dual, render_tab_item};
+use super::ui_helpers::{calculate_command_height, help_item, help_item_dual, help_item_primary, render_tab_item};
 use super::ui_tabs;
+use super::wizard::WizardStep;
 
 /// Render the entire UI based on application state.
 pub fn render(frame: &mut Frame, app: &App) {
@@ -24,9 +32,11 @@ pub fn render(frame: &mut Frame, app: &App) {
         render_tab_view(frame, app);
     }
 
-    // Render dialog overlays (priority: file browser > rename > close confirmation)
+    // Render dialog overlays (priority: file browser > presets > rename > close confirmation)
     if let Some(ref browser) = app.file_browser {
         ui_file_browser::render_file_browser(frame, browser);
+    } else if app.has_preset_dialog() {
+        ui_dialog::render_preset_dialog(frame, app);
     } else if app.is_renaming() {
         ui_dialog::render_rename_dialog(frame, app);
     } else if app.has_confirmation_dialog() {
@@ -70,7 +80,14 @@ fn render_menu(frame: &mut Frame, app: &App) {
             Style::default().fg(Color::Cyan).add_modifier(Modifier::BOLD),
         )),
         Line::from(""),
-        Line::from(Span::styled("Select a tool to run:", Style::default().fg(Color::White))),
+        if app.first_run {
+            Line::from(Span::styled(
+                "No recordings here yet: run LSL Demo to generate demo.zarr and try the tools on it",
+                Style::default().fg(Color::Yellow),
+            ))
+        } else {
+            Line::from(Span::styled("Select a tool to run:", Style::default().fg(Color::White)))
+        },
     ])
     .block(
         Block::default()
@@ -90,7 +107,8 @@ fn render_menu(frame: &mut Frame, app: &App) {
     frame.render_widget(list, chunks[2]);
 
     // Help text
-    let help_spans = build_menu_help_spans(has_tabs);
+    let file_dialog = native_dialog::AVAILABLE.then_some(app.settings.file_dialog);
+    let help_spans = build_menu_help_spans(has_tabs, file_dialog);
     let help = Paragraph::new(Line::from(help_spans));
     frame.render_widget(help, chunks[4]);
 }
@@ -135,8 +153,8 @@ fn build_tool_list_items(app: &App) -> Vec<ListItem<'static>> {
     items
 }
 
-/// Build help spans for the menu view.
-fn build_menu_help_spans(has_tabs: bool) -> Vec<Span<'static>> {
+/// Build help spans for the menu view; `file_dialog` is the switchable dialog mode, if any.
+fn build_menu_help_spans(has_tabs: bool, file_dialog: Option<FileDialogMode>) -> Vec<Span<'static>> {
     let mut spans = vec![Span::styled(" ", Style::default())];
     spans.extend(help_item("Up/Dn", "Navigate "));
 
@@ -147,6 +165,12 @@ fn build_menu_help_spans(has_tabs: bool) -> Vec<Span<'static>> {
         spans.extend(help_item("Enter", "Run "));
     }
 
+    spans.extend(help_item("W", "Wizard "));
+
+    if let Some(mode) = file_dialog {
+        spans.extend(help_item("D", &format!("Dialogs: {} ", mode)));
+    }
+
     spans.extend(help_item("Esc", "Quit"));
     spans
 }
@@ -202,6 +226,11 @@ fn render_tab_view(frame: &mut Frame, app: &App) {
             TabMode::Running | TabMode::Completed => {
                 render_output_for_tab(frame, chunks[2], tab);
             }
+            TabMode::Explore => {
+                if let Some(ref explorer) = tab.explorer {
+                    ui_explorer::render_explorer(frame, chunks[2], explorer);
+                }
+            }
         }
     }
 }
@@ -211,7 +240,7 @@ fn render_output_for_tab(frame: &mut Frame, area: Rect, tab: &TabState) {
     let (status_text, status_color) = match tab.mode {
         TabMode::Running => ("Running", Color::Green),
         TabMode::Completed => ("Completed", Color::Yellow),
-        TabMode::Configure => ("Configure", Color::Cyan),
+        TabMode::Configure | TabMode::Explore => ("Configure", Color::Cyan),
     };
 
     let display_text = tab.command.as_deref().unwrap_or(&tab.title);
@@ -219,21 +248,28 @@ fn render_output_for_tab(frame: &mut Frame, area: Rect, tab: &TabState) {
     let (_, cmd_height) = calculate_command_height(cmd_with_prompt.len(), area.width);
 
     let is_running = tab.mode == TabMode::Running;
+    // Dashboard and memory panels only once a recorder reports (--status-interval, --memory-monitor)
+    let dashboard_height = if tab.progress.is_empty() { 0 } else { tab.progress.len() as u16 + 2 };
+    let memory_height = if tab.memory.is_empty() { 0 } else { tab.memory.len() as u16 + 2 };
     let constraints: Vec<Constraint> = if is_running {
         vec![
-            Constraint::Length(cmd_height), // Command
-            Constraint::Length(1),          // Spacer
-            Constraint::Min(0),             // Output
-            Constraint::Length(1),          // Spacer
-            Constraint::Length(3),          // Input field
-            Constraint::Length(2),          // Help text
+            Constraint::Length(cmd_height),       // Command
+            Constraint::Length(1),                // Spacer
+            Constraint::Length(dashboard_height), // Dashboard
+            Constraint::Length(memory_height),    // Memory
+            Constraint::Min(0),                   // Output
+            Constraint::Length(1),                // Spacer
+            Constraint::Length(3),                // Input field
+            Constraint::Length(2),                // Help text
         ]
     } else {
         vec![
-            Constraint::Length(cmd_height), // Command
-            Constraint::Length(1),          // Spacer
-            Constraint::Min(0),             // Output
-            Constraint::Length(2),          // Help text
+            Constraint::Length(cmd_height),       // Command
+            Constraint::Length(1),                // Spacer
+            Constraint::Length(dashboard_height), // Dashboard
+            Constraint::Length(memory_height),    // Memory
+            Constraint::Min(0),                   // Output
+            Constraint::Length(2),                // Help text
         ]
     };
     let chunks = Layout::default()
@@ -253,21 +289,143 @@ fn render_output_for_tab(frame: &mut Frame, area: Rect, tab: &TabState) {
         );
     frame.render_widget(cmd_box, chunks[0]);
 
+    if dashboard_height > 0 {
+        render_dashboard(frame, chunks[2], tab);
+    }
+    if memory_height > 0 {
+        render_memory_panel(frame, chunks[3], tab);
+    }
+
     // Output area
-    render_output_area(frame, chunks[2], tab);
+    render_output_area(frame, chunks[4], tab);
 
     // Input field (running mode only)
     if is_running {
-        render_input_field(frame, chunks[4], tab);
+        render_input_field(frame, chunks[6], tab);
     }
 
     // Help text
-    let help_chunk_idx = if is_running { 5 } else { 3 };
-    let help_spans = build_output_help_spans(is_running);
+    let help_chunk_idx = if is_running { 7 } else { 5 };
+    let wizard_next = match tab.mode {
+        TabMode::Completed => tab.wizard.as_ref().and_then(|wizard| wizard.next_step()).map(|(step, _)| step),
+        _ => None,
+    };
+    let help_spans = build_output_help_spans(is_running, wizard_next);
     let help = Paragraph::new(Line::from(help_spans));
     frame.render_widget(help, chunks[help_chunk_idx]);
 }
 
+/// Render the latest progress report of each recorder: figures on the left, buffer fill gauge on the right.
+fn render_dashboard(frame: &mut Frame, area: Rect, tab: &TabState) {
+    const GAUGE_WIDTH: u16 = 26;
+
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .title(" Dashboard ")
+        .border_style(Style::default().fg(Color::Cyan));
+    let inner = block.inner(area);
+    frame.render_widget(block, area);
+
+    let rows = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints(tab.progress.iter().map(|_| Constraint::Length(1)))
+        .split(inner);
+    for ((label, progress), &row) in tab.progress.iter().zip(rows.iter()) {
+        let status = &progress.status;
+        let columns = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([Constraint::Min(0), Constraint::Length(GAUGE_WIDTH)])
+            .split(row);
+
+        let state_color = match status.state {
+            RecordingState::Recording => Color::Green,
+            RecordingState::Paused | RecordingState::Stopped => Color::Yellow,
+            RecordingState::Lost => Color::Red,
+            _ => Color::Gray,
+        };
+        let rate = if status.nominal_srate > 0.0 {
+            format!("{:>9.1} / {} Hz", status.rate_hz, status.nominal_srate)
+        } else {
+            format!("{:>9.1} Hz", status.rate_hz)
+        };
+        let flush = progress
+            .since_flush()
+            .map_or_else(|| "-".to_string(), |age| format!("{:.1} s ago", age));
+        let spans = vec![
+            Span::styled(format!(" {:<20} ", label), Style::default().fg(Color::White)),
+            Span::styled(format!("{:<10}", status.state.as_str()), Style::default().fg(state_color)),
+            Span::styled(rate, Style::default().fg(Color::White)),
+            Span::styled(format!("  {:>11} samples", status.samples_recorded), Style::default().fg(Color::White)),
+            Span::styled("  elapsed ", Style::default().fg(Color::DarkGray)),
+            Span::styled(format_elapsed(status.elapsed_s), Style::default().fg(Color::White)),
+            Span::styled("  flushed ", Style::default().fg(Color::DarkGray)),
+            Span::styled(flush, Style::default().fg(Color::White)),
+        ];
+        frame.render_widget(Paragraph::new(Line::from(spans)), columns[0]);
+
+        let fill = status.buffer_fill.clamp(0.0, 1.0);
+        let fill_color = if fill > 0.9 {
+            Color::Red
+        } else if fill > 0.7 {
+            Color::Yellow
+        } else {
+            Color::Green
+        };
+        let gauge = LineGauge::default()
+            .ratio(fill)
+            .label(format!("buffer {:>3.0}% ", fill * 100.0))
+            .filled_style(Style::default().fg(fill_color))
+            .unfilled_style(Style::default().fg(Color::DarkGray));
+        frame.render_widget(gauge, columns[1]);
+    }
+}
+
+/// Recording time as `HH:MM:SS`.
+fn format_elapsed(seconds: f64) -> String {
+    let total = seconds.max(0.0) as u64;
+    format!("{:02}:{:02}:{:02}", total / 3600, (total / 60) % 60, total % 60)
+}
+
+/// Render the latest memory report of each recorder, red while its memory keeps growing.
+fn render_memory_panel(frame: &mut Frame, area: Rect, tab: &TabState) {
+    let mb = |value: Option<f64>| value.map_or_else(|| "-".to_string(), |v| format!("{:.1} MB", v));
+    let lines: Vec<Line> = tab
+        .memory
+        .iter()
+        .map(|(label, memory)| {
+            let status = &memory.status;
+            let growth = status
+                .growth_mb_per_min
+                .map_or_else(|| "-".to_string(), |g| format!("{:+.2} MB/min", g));
+            let text = format!(
+                " {:<20} RSS {:>10}  virtual {:>10}  buffer {:>8.2} MB  {:>9.1} Hz  growth {}",
+                label,
+                mb(status.rss_mb),
+                mb(status.virtual_mb),
+                status.buffer_mb,
+                status.rate_hz,
+                growth
+            );
+            let color = if memory.growing { Color::Red } else { Color::White };
+            Line::from(Span::styled(text, Style::default().fg(color)))
+        })
+        .collect();
+
+    let growing = tab.memory.values().any(|memory| memory.growing);
+    let (title, border_color) = if growing {
+        (" Memory - growing ", Color::Red)
+    } else {
+        (" Memory ", Color::Cyan)
+    };
+    let panel = Paragraph::new(lines).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .title(title)
+            .border_style(Style::default().fg(border_color)),
+    );
+    frame.render_widget(panel, area);
+}
+
 /// Render the output area with scrolling.
 fn render_output_area(frame: &mut Frame, area: Rect, tab: &TabState) {
     let visible_height = area.height.saturating_sub(2) as usize;
@@ -326,8 +484,8 @@ fn render_input_field(frame: &mut Frame, area: Rect, tab: &TabState) {
     frame.render_widget(input, area);
 }
 
-/// Build help spans for the output view.
-fn build_output_help_spans(is_running: bool) -> Vec<Span<'static>> {
+/// Build help spans for the output view; `wizard_next` is the wizard step to offer, if any.
+fn build_output_help_spans(is_running: bool, wizard_next: Option<WizardStep>) -> Vec<Span<'static>> {
     let mut spans = vec![Span::styled(" ", Style::default())];
 
     if is_running {
@@ -336,6 +494,9 @@ fn build_output_help_spans(is_running: bool) -> Vec<Span<'static>> {
         spans.extend(help_item_dual("Ctrl+C", "Esc", "Stop"));
     } else {
         spans.extend(help_item("Up/Dn", "Scroll "));
+        if let Some(step) = wizard_next {
+            spans.extend(help_item_primary("N", &format!("Next: {} ", step.binary())));
+        }
         spans.extend(help_item_dual("Enter", "Esc", "Close Tab"));
     }
 
@@ -157,3 +157,103 @@ pub fn render_rename_dialog(frame: &mut Frame, app: &App) {
 
     frame.render_widget(dialog, dialog_area);
 }
+
+/// Render the preset picker, or the name prompt when saving a preset, as a centered modal.
+pub fn render_preset_dialog(frame: &mut Frame, app: &App) {
+    let Some(ref dialog) = app.preset_dialog else {
+        return;
+    };
+
+    let area = frame.area();
+    let names = dialog.presets.names();
+
+    // Calculate centered dialog position
+    let dialog_width = 50u16;
+    let dialog_height = match dialog.name_input {
+        Some(_) => 5u16,
+        None => names.len().clamp(1, 12) as u16 + 4,
+    };
+    let x = area.width.saturating_sub(dialog_width) / 2;
+    let y = area.height.saturating_sub(dialog_height) / 2;
+
+    let dialog_area = Rect {
+        x,
+        y,
+        width: dialog_width.min(area.width),
+        height: dialog_height.min(area.height),
+    };
+
+    // Clear the dialog area
+    frame.render_widget(Clear, dialog_area);
+
+    let key = |key: &str, color: Color, action: &str| {
+        vec![
+            Span::styled("[", Style::default().fg(Color::DarkGray)),
+            Span::styled(key.to_string(), Style::default().fg(color)),
+            Span::styled(format!("] {}  ", action), Style::default().fg(Color::DarkGray)),
+        ]
+    };
+
+    let (title, lines) = match dialog.name_input {
+        Some(ref name) => {
+            let mut help = vec![Span::styled(" ", Style::default())];
+            help.extend(key("Enter", Color::Green, "Save"));
+            help.extend(key("Esc", Color::Red, "Cancel"));
+            let lines = vec![
+                Line::from(""),
+                Line::from(vec![
+                    Span::styled(" Name [", Style::default().fg(Color::DarkGray)),
+                    Span::styled(format!("{}|", name), Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD)),
+                    Span::styled("]", Style::default().fg(Color::DarkGray)),
+                ]),
+                Line::from(""),
+                Line::from(help),
+            ];
+            (" Save Preset ", lines)
+        }
+        None => {
+            let mut lines = Vec::new();
+            if names.is_empty() {
+                lines.push(Line::from(Span::styled(
+                    " No presets yet: Ctrl+S in the form saves one",
+                    Style::default().fg(Color::DarkGray),
+                )));
+            }
+            // Keep the selection in view
+            let skip = dialog.selected.saturating_sub(11);
+            for (i, name) in names.iter().enumerate().skip(skip).take(12) {
+                let style = if i == dialog.selected {
+                    Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD)
+                } else {
+                    Style::default().fg(Color::White)
+                };
+                let prefix = if i == dialog.selected { " > " } else { "   " };
+                lines.push(Line::from(Span::styled(format!("{}{}", prefix, name), style)));
+            }
+            let mut help = vec![Span::styled(" ", Style::default())];
+            help.extend(key("Enter", Color::Green, "Load"));
+            help.extend(key("Del", Color::Red, "Delete"));
+            help.extend(key("Esc", Color::Cyan, "Close"));
+            lines.push(Line::from(""));
+            lines.push(Line::from(help));
+            (" Presets ", lines)
+        }
+    };
+
+    let dialog = Paragraph::new(lines)
+        .style(Style::default().bg(Color::Black))
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title(title)
+                .title_style(
+                    Style::default()
+                        .fg(Color::Cyan)
+                        .add_modifier(Modifier::BOLD),
+                )
+                .border_style(Style::default().fg(Color::Cyan))
+                .style(Style::default().bg(Color::Black)),
+        );
+
+    frame.render_widget(dialog, dialog_area);
+}
@@ -0,0 +1,119 @@
+//! Store Explorer UI rendering.
+
+use ratatui::{
+    layout::{Constraint, Direction, Layout, Rect},
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, List, ListItem, Paragraph},
+    Frame,
+};
+
+use lsl_recording_toolbox::explore::NodeKind;
+
+use super::explorer::ExplorerState;
+use super::ui_helpers::{help_item, help_item_dual};
+
+/// Render an explorer tab: store path, tree and details side by side, help text.
+pub fn render_explorer(frame: &mut Frame, area: Rect, explorer: &ExplorerState) {
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Length(3), // Store path
+            Constraint::Min(3),    // Tree and details
+            Constraint::Length(2), // Help text
+        ])
+        .split(area);
+
+    let path = Paragraph::new(explorer.store_path.to_string_lossy().to_string())
+        .style(Style::default().fg(Color::White))
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title(" Store Explorer ")
+                .title_style(Style::default().fg(Color::Cyan).add_modifier(Modifier::BOLD))
+                .border_style(Style::default().fg(Color::Cyan)),
+        );
+    frame.render_widget(path, chunks[0]);
+
+    let panes = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Percentage(35), Constraint::Percentage(65)])
+        .split(chunks[1]);
+    render_tree(frame, panes[0], explorer);
+    render_details(frame, panes[1], explorer);
+
+    let mut help_spans = vec![Span::styled(" ", Style::default())];
+    help_spans.extend(help_item("Up/Dn", "Navigate "));
+    help_spans.extend(help_item_dual("Left", "Right", "Collapse/Expand "));
+    help_spans.extend(help_item_dual("PgUp", "PgDn", "Scroll Details "));
+    help_spans.extend(help_item("S", "Sync "));
+    help_spans.extend(help_item("V", "Validate "));
+    help_spans.extend(help_item("R", "Reload "));
+    help_spans.extend(help_item("Esc", "Close Tab"));
+    frame.render_widget(Paragraph::new(Line::from(help_spans)), chunks[2]);
+}
+
+/// Render the tree of groups and arrays, keeping the selected row in view.
+fn render_tree(frame: &mut Frame, area: Rect, explorer: &ExplorerState) {
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .title(" Tree ")
+        .border_style(Style::default().fg(Color::White));
+
+    if let Some(ref error) = explorer.error {
+        let error_widget = Paragraph::new(error.as_str())
+            .style(Style::default().fg(Color::Red))
+            .block(block);
+        frame.render_widget(error_widget, area);
+        return;
+    }
+
+    let visible_height = area.height.saturating_sub(2) as usize;
+    let scroll_offset = (explorer.selected_index + 1).saturating_sub(visible_height);
+    let items: Vec<ListItem> = explorer
+        .rows
+        .iter()
+        .enumerate()
+        .skip(scroll_offset)
+        .take(visible_height)
+        .map(|(i, row)| {
+            let is_selected = i == explorer.selected_index;
+            let (marker, name, color) = match row.node.kind {
+                NodeKind::Group => (if row.expanded { "v " } else { "> " }, format!("{}/", row.node.name), Color::Cyan),
+                NodeKind::Array => ("  ", row.node.name.clone(), Color::White),
+            };
+            let style = if is_selected {
+                Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD)
+            } else {
+                Style::default().fg(color)
+            };
+            ListItem::new(Line::from(vec![
+                Span::raw("  ".repeat(row.depth)),
+                Span::styled(marker, Style::default().fg(Color::DarkGray)),
+                Span::styled(name, style),
+            ]))
+        })
+        .collect();
+    frame.render_widget(List::new(items).block(block), area);
+}
+
+/// Render metadata, attributes and data preview of the selected node.
+fn render_details(frame: &mut Frame, area: Rect, explorer: &ExplorerState) {
+    let title = explorer
+        .selected_row()
+        .map(|row| format!(" {} ", row.node.path))
+        .unwrap_or_else(|| " Details ".to_string());
+    let lines: Vec<Line> = explorer
+        .details
+        .iter()
+        .skip(explorer.details_scroll)
+        .map(|line| Line::from(line.as_str()))
+        .collect();
+    let details = Paragraph::new(lines).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .title(title)
+            .border_style(Style::default().fg(Color::White)),
+    );
+    frame.render_widget(details, area);
+}
@@ -31,13 +31,17 @@ pub fn render_configure_form_for_tab(frame: &mut Frame, area: Rect, form: &FormS
         .split(area);
 
     // Title
-    let title = Paragraph::new(Line::from(vec![
+    let mut title_spans = vec![
         Span::styled("Configure: ", Style::default().fg(Color::White)),
         Span::styled(
             &form.tool_name,
             Style::default().fg(Color::Cyan).add_modifier(Modifier::BOLD),
         ),
-    ]))
+    ];
+    if let Some(ref preset) = form.preset {
+        title_spans.push(Span::styled(format!("  preset: {}", preset), Style::default().fg(Color::Yellow)));
+    }
+    let title = Paragraph::new(Line::from(title_spans))
     .block(
         Block::default()
             .borders(Borders::ALL)
@@ -86,6 +90,8 @@ fn render_bottom_area(frame: &mut Frame, form: &FormState, area: Rect, cmd_with_
         let mut spans = vec![Span::styled(" ", Style::default())];
         spans.extend(help_item_primary("Ctrl+Enter", "Run "));
         spans.extend(help_item("Up/Dn", "Navigate "));
+        spans.extend(help_item("Ctrl+P", "Presets "));
+        spans.extend(help_item("Ctrl+S", "Save Preset "));
         spans.extend(help_item("Esc", "Close"));
         Paragraph::new(Line::from(spans))
     };
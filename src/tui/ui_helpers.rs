@@ -35,6 +35,7 @@ pub fn tab_status_indicator(mode: TabMode) -> (&'static str, Color) {
         TabMode::Configure => ("[*]", Color::Cyan),
         TabMode::Running => ("[>]", Color::Green),
         TabMode::Completed => ("[x]", Color::Yellow),
+        TabMode::Explore => ("[#]", Color::Magenta),
     }
 }
 
@@ -0,0 +1,79 @@
+//! Experiment wizard: record, then sync and validate the same store.
+//!
+//! A wizard run is a chain of tabs. Each carries a [`WizardState`] with its step and,
+//! once known, the store it works on: lsl-multi-recorder announces the store it
+//! records to with `STATUS STORE <path>`, and the sync and validate steps use the path
+//! in their form when they are run. When a step's tab completes, the next step opens
+//! in a new tab with that path already filled in.
+
+use std::path::Path;
+
+/// Steps of the wizard, in order.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum WizardStep {
+    Record,
+    Sync,
+    Validate,
+}
+
+impl WizardStep {
+    /// Binary of the tool that runs this step.
+    pub fn binary(self) -> &'static str {
+        match self {
+            WizardStep::Record => "lsl-multi-recorder",
+            WizardStep::Sync => "lsl-sync",
+            WizardStep::Validate => "lsl-validate",
+        }
+    }
+
+    /// Tab title for this step.
+    pub fn title(self) -> &'static str {
+        match self {
+            WizardStep::Record => "Wizard: Record",
+            WizardStep::Sync => "Wizard: Sync",
+            WizardStep::Validate => "Wizard: Validate",
+        }
+    }
+
+    /// The step after this one, if any.
+    pub fn next(self) -> Option<WizardStep> {
+        match self {
+            WizardStep::Record => Some(WizardStep::Sync),
+            WizardStep::Sync => Some(WizardStep::Validate),
+            WizardStep::Validate => None,
+        }
+    }
+}
+
+/// Wizard progress of one tab.
+#[derive(Clone, Debug)]
+pub struct WizardState {
+    /// Step this tab runs
+    pub step: WizardStep,
+    /// Store the step works on, once known
+    pub store_path: Option<String>,
+}
+
+impl WizardState {
+    /// State of a tab running `step`, on `store_path` if it is already known.
+    pub fn new(step: WizardStep, store_path: Option<String>) -> Self {
+        Self { step, store_path }
+    }
+
+    /// Remember the store announced by a `STATUS STORE <path>` line.
+    pub fn update_store(&mut self, line: &str) {
+        let Some((_, path)) = line.split_once("STATUS STORE ") else { return };
+        let path = path.trim();
+        if !path.is_empty() {
+            self.store_path = Some(path.to_string());
+        }
+    }
+
+    /// The step to offer once this one has completed, with the store to run it on.
+    /// Nothing is offered at the last step or if no store was written.
+    pub fn next_step(&self) -> Option<(WizardStep, &str)> {
+        let next = self.step.next()?;
+        let store = self.store_path.as_deref().filter(|path| Path::new(path).exists())?;
+        Some((next, store))
+    }
+}
@@ -0,0 +1,91 @@
+//! Named presets of TUI tool forms (`lsl-toolbox`)
+//!
+//! A preset keeps the values of a tool's form fields under a name, so a session's
+//! setup (e.g. eight source IDs, subject pattern and storage options) is loaded with
+//! one key instead of being typed again. Presets live in the `presets` directory of
+//! the toolbox config directory (see [`usage_dir`](crate::usage::usage_dir)), one
+//! JSON file per tool named after its binary (`presets/lsl-multi-recorder.json`):
+//!
+//! ```json
+//! {
+//!   "lab-a": {"source_ids": "EMG_1234,EEG_5678", "output": "data/{subject}/session"}
+//! }
+//! ```
+//!
+//! As with the TUI settings, a missing or unreadable file means no presets.
+
+use anyhow::{Context, Result};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+/// Directory of the preset files in the config directory
+pub const PRESETS_DIR: &str = "presets";
+
+/// Field values of a form by field name
+pub type PresetValues = BTreeMap<String, String>;
+
+/// The presets of one tool
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Presets {
+    /// Binary name of the tool
+    pub tool: String,
+    presets: BTreeMap<String, PresetValues>,
+}
+
+impl Presets {
+    /// Presets of `tool` stored under `dir`, or none if its file is missing or invalid
+    pub fn load(dir: &Path, tool: &str) -> Self {
+        let presets = std::fs::read(preset_file(dir, tool))
+            .ok()
+            .and_then(|bytes| serde_json::from_slice(&bytes).ok())
+            .unwrap_or_default();
+        Self {
+            tool: tool.to_string(),
+            presets,
+        }
+    }
+
+    /// Write the presets to the tool's file under `dir`
+    pub fn save(&self, dir: &Path) -> Result<()> {
+        let path = preset_file(dir, &self.tool);
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent).with_context(|| format!("Failed to create {}", parent.display()))?;
+        }
+        std::fs::write(&path, serde_json::to_string_pretty(&self.presets)?)
+            .with_context(|| format!("Failed to write {}", path.display()))
+    }
+
+    /// Preset names, sorted
+    pub fn names(&self) -> Vec<&str> {
+        self.presets.keys().map(String::as_str).collect()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.presets.is_empty()
+    }
+
+    /// Field values of a preset
+    pub fn get(&self, name: &str) -> Option<&PresetValues> {
+        self.presets.get(name)
+    }
+
+    /// Store `values` as preset `name` (trimmed), replacing a preset of that name
+    pub fn insert(&mut self, name: &str, values: PresetValues) -> Result<()> {
+        let name = name.trim();
+        if name.is_empty() {
+            anyhow::bail!("Preset name cannot be empty");
+        }
+        self.presets.insert(name.to_string(), values);
+        Ok(())
+    }
+
+    /// Remove a preset; returns whether it existed
+    pub fn remove(&mut self, name: &str) -> bool {
+        self.presets.remove(name).is_some()
+    }
+}
+
+/// Preset file of `tool` under the config directory `dir`
+pub fn preset_file(dir: &Path, tool: &str) -> PathBuf {
+    dir.join(PRESETS_DIR).join(format!("{}.json", tool))
+}
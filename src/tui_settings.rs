@@ -0,0 +1,87 @@
+//! Persistent preferences of the TUI launcher (`lsl-toolbox`)
+//!
+//! Settings live in `tui-settings.json` in the toolbox config directory (see
+//! [`usage_dir`](crate::usage::usage_dir)). A missing or unreadable file means the
+//! defaults, so a broken settings file never keeps the TUI from starting, and
+//! unknown keys are ignored so older versions can read newer files.
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+/// Settings file in the config directory
+pub const SETTINGS_FILE: &str = "tui-settings.json";
+
+/// How path fields are browsed (Space on a path field)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FileDialogMode {
+    /// Built-in terminal file browser
+    #[default]
+    Terminal,
+    /// Native open dialogs of the desktop when in a graphical session
+    /// (needs the `native-dialogs` feature), the terminal browser otherwise
+    Native,
+}
+
+impl FileDialogMode {
+    /// The other mode
+    pub fn toggled(self) -> Self {
+        match self {
+            FileDialogMode::Terminal => FileDialogMode::Native,
+            FileDialogMode::Native => FileDialogMode::Terminal,
+        }
+    }
+
+    /// Whether a native dialog is shown, given whether a graphical session is available
+    pub fn uses_native(self, graphical: bool) -> bool {
+        self == FileDialogMode::Native && graphical
+    }
+}
+
+impl std::fmt::Display for FileDialogMode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            FileDialogMode::Terminal => write!(f, "terminal"),
+            FileDialogMode::Native => write!(f, "native"),
+        }
+    }
+}
+
+/// Preferences kept across TUI sessions
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TuiSettings {
+    pub file_dialog: FileDialogMode,
+}
+
+impl TuiSettings {
+    /// Settings from [`SETTINGS_FILE`] in `dir`, or the defaults if it is missing or invalid
+    pub fn load(dir: &Path) -> Self {
+        std::fs::read(dir.join(SETTINGS_FILE))
+            .ok()
+            .and_then(|bytes| serde_json::from_slice(&bytes).ok())
+            .unwrap_or_default()
+    }
+
+    /// Write the settings to [`SETTINGS_FILE`] in `dir`
+    pub fn save(&self, dir: &Path) -> Result<()> {
+        std::fs::create_dir_all(dir).with_context(|| format!("Failed to create {}", dir.display()))?;
+        let path = dir.join(SETTINGS_FILE);
+        std::fs::write(&path, serde_json::to_string_pretty(self)?)
+            .with_context(|| format!("Failed to write {}", path.display()))
+    }
+}
+
+/// Whether the TUI runs in a desktop session that can show native dialogs
+///
+/// On Linux and BSD this needs an X11 or Wayland display; on Windows and macOS
+/// every local session qualifies, but SSH logins do not.
+pub fn graphical_session() -> bool {
+    let set = |name: &str| std::env::var_os(name).is_some_and(|value| !value.is_empty());
+    if cfg!(any(target_os = "windows", target_os = "macos")) {
+        !set("SSH_CONNECTION")
+    } else {
+        set("DISPLAY") || set("WAYLAND_DISPLAY")
+    }
+}
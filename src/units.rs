@@ -0,0 +1,67 @@
+//! Channel unit handling for scaling checks
+//!
+//! LSL streams declare channel units as free text (`microvolts`, `uV`, `mV`, ...).
+//! These helpers normalize voltage units and judge whether an observed signal range
+//! is plausible for the declared unit, which catches the classic scaling bug of
+//! sending volts while declaring microvolts (or the other way round).
+
+/// Plausible peak amplitude of electrophysiological signals (EEG/EMG/ECG) in volts:
+/// from 0.1 µV up to 100 mV
+const BIOPOTENTIAL_PEAK_RANGE_V: (f64, f64) = (1e-7, 0.1);
+
+/// Voltage units with their factor to volts, largest first
+const VOLTAGE_UNITS: [(&str, f64); 4] = [("V", 1.0), ("mV", 1e-3), ("µV", 1e-6), ("nV", 1e-9)];
+
+/// Factor converting a value in `unit` to volts, if `unit` is a voltage unit
+pub fn volt_scale(unit: &str) -> Option<f64> {
+    match unit.trim().to_lowercase().as_str() {
+        "v" | "volt" | "volts" => Some(1.0),
+        "mv" | "millivolt" | "millivolts" => Some(1e-3),
+        "uv" | "µv" | "μv" | "microvolt" | "microvolts" => Some(1e-6),
+        "nv" | "nanovolt" | "nanovolts" => Some(1e-9),
+        _ => None,
+    }
+}
+
+/// Canonical short symbol of a voltage unit (`microvolts` -> `µV`), or the unit unchanged
+pub fn unit_symbol(unit: &str) -> String {
+    match volt_scale(unit) {
+        Some(scale) => VOLTAGE_UNITS
+            .iter()
+            .find(|(_, s)| *s == scale)
+            .map(|(symbol, _)| symbol.to_string())
+            .unwrap_or_else(|| unit.to_string()),
+        None => unit.to_string(),
+    }
+}
+
+/// Explain why a channel's value range looks wrong for its declared unit
+///
+/// Returns `None` when the unit is not a voltage, the range is plausible, or the
+/// channel is flat (flat channels are reported separately).
+pub fn range_warning(unit: &str, min: f64, max: f64) -> Option<String> {
+    let scale = volt_scale(unit)?;
+    let peak = min.abs().max(max.abs());
+    if peak == 0.0 || !peak.is_finite() {
+        return None;
+    }
+
+    let (low, high) = BIOPOTENTIAL_PEAK_RANGE_V;
+    let peak_volts = peak * scale;
+    if (low..=high).contains(&peak_volts) {
+        return None;
+    }
+
+    let declared = unit_symbol(unit);
+    let suggestion = VOLTAGE_UNITS
+        .iter()
+        .find(|(_, s)| (low..=high).contains(&(peak * s)))
+        .map(|(symbol, _)| format!(" (values look like {})", symbol))
+        .unwrap_or_default();
+    let direction = if peak_volts < low { "too small" } else { "too large" };
+
+    Some(format!(
+        "peak {:.3e} {} is {} for a biopotential{}",
+        peak, declared, direction, suggestion
+    ))
+}
@@ -0,0 +1,346 @@
+//! Local usage log of the tools (`lsl-toolbox stats`)
+//!
+//! Whoever maintains the toolbox for a lab wants to know which tools are run, for
+//! how long, and how they fail. When the log is enabled, every tool run appends one
+//! line to `usage.jsonl` in [`usage_dir`]: tool name, version, start time,
+//! duration, the names of the long options given, and success or the category of
+//! the error ([`error_category`]). Option values, positional arguments (paths,
+//! stream names), error messages and anything else that could identify data or
+//! subjects are never written, and nothing is sent over the network: the log is a
+//! local file, summarized by `lsl-toolbox stats`.
+//!
+//! The log is opt-in: `lsl-toolbox stats --enable` creates a marker file next to
+//! it, `--disable` removes it, and `LSL_USAGE_LOG=1` (or `0`) overrides the marker.
+//! A log that cannot be written never affects the tool.
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::Instant;
+
+/// Log file in the usage directory, one JSON [`UsageEntry`] per line
+pub const USAGE_LOG_FILE: &str = "usage.jsonl";
+
+/// Marker file in the usage directory that enables the log
+pub const ENABLED_MARKER: &str = "usage-log-enabled";
+
+/// One run of a tool
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct UsageEntry {
+    pub tool: String,
+    pub version: String,
+    /// Start of the run (RFC 3339, UTC)
+    pub started_at: String,
+    /// Seconds from start to exit
+    pub duration: f64,
+    /// Long options given, without their values (`--speed`, `--stream`)
+    #[serde(default)]
+    pub options: Vec<String>,
+    pub success: bool,
+    /// Category of the error (see [`error_category`]), or `exit <code>`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// Runs of one tool in the log
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct ToolUsage {
+    pub tool: String,
+    pub runs: u64,
+    pub failures: u64,
+    /// Seconds spent in all runs
+    pub total_duration: f64,
+    /// Longest run in seconds
+    pub longest: f64,
+    /// Start of the latest run
+    pub last_run: String,
+    /// Failed runs per error category
+    pub errors: BTreeMap<String, u64>,
+    /// Runs per option given
+    pub options: BTreeMap<String, u64>,
+}
+
+impl ToolUsage {
+    /// Mean run time in seconds
+    pub fn mean_duration(&self) -> f64 {
+        if self.runs > 0 {
+            self.total_duration / self.runs as f64
+        } else {
+            0.0
+        }
+    }
+}
+
+/// Directory of the usage log: `$LSL_CONFIG_DIR`, else the user config directory
+///
+/// `$XDG_CONFIG_HOME/lsl-recording-toolbox`, `~/.config/lsl-recording-toolbox`, or
+/// `%APPDATA%\lsl-recording-toolbox` on Windows.
+pub fn usage_dir() -> PathBuf {
+    if let Some(dir) = std::env::var_os("LSL_CONFIG_DIR") {
+        return PathBuf::from(dir);
+    }
+    std::env::var_os("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))
+        .or_else(|| std::env::var_os("APPDATA").map(PathBuf::from))
+        .unwrap_or_else(std::env::temp_dir)
+        .join("lsl-recording-toolbox")
+}
+
+/// Whether runs are logged: `LSL_USAGE_LOG` if set, else the marker in `dir`
+pub fn is_enabled(dir: &Path) -> bool {
+    match std::env::var("LSL_USAGE_LOG") {
+        Ok(value) if !value.is_empty() => !matches!(value.to_ascii_lowercase().as_str(), "0" | "false" | "off" | "no"),
+        _ => dir.join(ENABLED_MARKER).exists(),
+    }
+}
+
+/// Turn the log in `dir` on or off (the log itself is kept)
+pub fn set_enabled(dir: &Path, enabled: bool) -> Result<()> {
+    let marker = dir.join(ENABLED_MARKER);
+    if enabled {
+        std::fs::create_dir_all(dir).with_context(|| format!("Failed to create {}", dir.display()))?;
+        std::fs::write(&marker, b"").with_context(|| format!("Failed to write {}", marker.display()))?;
+    } else if marker.exists() {
+        std::fs::remove_file(&marker).with_context(|| format!("Failed to remove {}", marker.display()))?;
+    }
+    Ok(())
+}
+
+/// Names of the long options in a command line, sorted and without values
+///
+/// Arguments after a bare `--` are positional and skipped.
+pub fn option_names(args: impl IntoIterator<Item = String>) -> Vec<String> {
+    let mut names: Vec<String> = args
+        .into_iter()
+        .take_while(|arg| arg != "--")
+        .filter(|arg| arg.len() > 2 && arg.starts_with("--"))
+        .map(|arg| arg.split('=').next().unwrap_or_default().to_string())
+        .collect();
+    names.sort();
+    names.dedup();
+    names
+}
+
+/// Category of an error: the kind of failure, never its message
+///
+/// `io` (file system), `store` (Zarr arrays and storage), `json`, `sqlite`, `lsl`,
+/// or `other` for the tools' own checks (bad arguments, missing streams, ...).
+pub fn error_category(error: &anyhow::Error) -> &'static str {
+    for cause in error.chain() {
+        if cause.is::<std::io::Error>() {
+            return "io";
+        }
+        if cause.is::<zarrs::array::ArrayError>() || cause.is::<zarrs::storage::StorageError>() {
+            return "store";
+        }
+        if cause.is::<serde_json::Error>() {
+            return "json";
+        }
+        if cause.is::<rusqlite::Error>() {
+            return "sqlite";
+        }
+        #[cfg(feature = "lsl")]
+        if cause.is::<lsl::Error>() {
+            return "lsl";
+        }
+    }
+    "other"
+}
+
+/// The run in progress, finished by [`track`] or [`exit`]
+struct CurrentRun {
+    tool: String,
+    started: Instant,
+    started_at: DateTime<Utc>,
+}
+
+static CURRENT_RUN: Mutex<Option<CurrentRun>> = Mutex::new(None);
+
+/// Run a tool and log the run if the usage log is enabled
+pub fn track(tool: &str, run: impl FnOnce() -> Result<()>) -> Result<()> {
+    if let Ok(mut current) = CURRENT_RUN.lock() {
+        *current = Some(CurrentRun {
+            tool: tool.to_string(),
+            started: Instant::now(),
+            started_at: Utc::now(),
+        });
+    }
+    let result = run();
+    match result {
+        Ok(()) => finish_run(true, None),
+        Err(ref e) => finish_run(false, Some(error_category(e).to_string())),
+    }
+    result
+}
+
+/// Log the run in progress and exit with `code`
+///
+/// For tools whose exit code carries a result (`lsl-validate`, `lsl-spotcheck`).
+pub fn exit(code: i32) -> ! {
+    finish_run(code == 0, (code != 0).then(|| format!("exit {}", code)));
+    std::process::exit(code)
+}
+
+fn finish_run(success: bool, error: Option<String>) {
+    let Some(run) = CURRENT_RUN.lock().ok().and_then(|mut current| current.take()) else {
+        return;
+    };
+    let dir = usage_dir();
+    if !is_enabled(&dir) {
+        return;
+    }
+    let entry = UsageEntry {
+        tool: run.tool,
+        version: env!("CARGO_PKG_VERSION").to_string(),
+        started_at: run.started_at.to_rfc3339_opts(chrono::SecondsFormat::Secs, true),
+        duration: run.started.elapsed().as_secs_f64(),
+        options: option_names(std::env::args().skip(1)),
+        success,
+        error,
+    };
+    if let Err(e) = append_entry(&dir.join(USAGE_LOG_FILE), &entry) {
+        tracing::debug!("Could not write the usage log: {}", e);
+    }
+}
+
+/// Append one entry to a usage log
+pub fn append_entry(log_path: &Path, entry: &UsageEntry) -> Result<()> {
+    if let Some(parent) = log_path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    let mut line = serde_json::to_string(entry)?;
+    line.push('\n');
+    OpenOptions::new().create(true).append(true).open(log_path)?.write_all(line.as_bytes())?;
+    Ok(())
+}
+
+/// Entries of a usage log; lines that do not parse are skipped
+pub fn read_usage_log(log_path: &Path) -> Result<Vec<UsageEntry>> {
+    if !log_path.exists() {
+        return Ok(Vec::new());
+    }
+    let content =
+        std::fs::read_to_string(log_path).with_context(|| format!("Failed to read {}", log_path.display()))?;
+    Ok(content.lines().filter_map(|line| serde_json::from_str(line).ok()).collect())
+}
+
+/// Usage per tool, most-run tools first
+pub fn summarize(entries: &[UsageEntry]) -> Vec<ToolUsage> {
+    let mut tools: BTreeMap<&str, ToolUsage> = BTreeMap::new();
+    for entry in entries {
+        let usage = tools.entry(entry.tool.as_str()).or_insert_with(|| ToolUsage {
+            tool: entry.tool.clone(),
+            ..ToolUsage::default()
+        });
+        usage.runs += 1;
+        usage.total_duration += entry.duration;
+        usage.longest = usage.longest.max(entry.duration);
+        if entry.started_at > usage.last_run {
+            usage.last_run = entry.started_at.clone();
+        }
+        if !entry.success {
+            usage.failures += 1;
+            let category = entry.error.clone().unwrap_or_else(|| "other".to_string());
+            *usage.errors.entry(category).or_default() += 1;
+        }
+        for option in &entry.options {
+            *usage.options.entry(option.clone()).or_default() += 1;
+        }
+    }
+    let mut tools: Vec<ToolUsage> = tools.into_values().collect();
+    tools.sort_by(|a, b| b.runs.cmp(&a.runs).then_with(|| a.tool.cmp(&b.tool)));
+    tools
+}
+
+/// Options of `lsl-toolbox stats`
+#[derive(Debug, Clone, Default)]
+pub struct StatsOptions {
+    pub enable: bool,
+    pub disable: bool,
+    /// Delete the log
+    pub clear: bool,
+    /// Print the summary as JSON
+    pub json: bool,
+}
+
+/// `lsl-toolbox stats`: change the log settings or summarize the log
+pub fn run_stats(options: &StatsOptions) -> Result<()> {
+    let dir = usage_dir();
+    let log_path = dir.join(USAGE_LOG_FILE);
+    if options.enable || options.disable {
+        set_enabled(&dir, options.enable)?;
+        println!(
+            "Usage log {} ({})",
+            if options.enable { "enabled" } else { "disabled" },
+            log_path.display()
+        );
+    }
+    if options.clear {
+        if log_path.exists() {
+            std::fs::remove_file(&log_path).with_context(|| format!("Failed to remove {}", log_path.display()))?;
+        }
+        println!("Usage log cleared");
+    }
+    if options.enable || options.disable || options.clear {
+        return Ok(());
+    }
+
+    let entries = read_usage_log(&log_path)?;
+    let tools = summarize(&entries);
+    if options.json {
+        println!("{}", serde_json::to_string_pretty(&tools)?);
+        return Ok(());
+    }
+
+    println!("Usage log:\t{}", log_path.display());
+    println!("Logging:\t{}", if is_enabled(&dir) { "enabled" } else { "disabled (lsl-toolbox stats --enable)" });
+    if let Some(first) = entries.iter().map(|e| e.started_at.as_str()).min() {
+        println!("Since:\t\t{}", first);
+    }
+    println!();
+    if tools.is_empty() {
+        println!("No runs logged");
+        return Ok(());
+    }
+
+    println!(
+        "{:<22} {:>6} {:>7} {:>12} {:>10} {:>10}  {}",
+        "Tool", "Runs", "Failed", "Total [s]", "Mean [s]", "Max [s]", "Last run"
+    );
+    for tool in &tools {
+        println!(
+            "{:<22} {:>6} {:>7} {:>12.1} {:>10.1} {:>10.1}  {}",
+            tool.tool,
+            tool.runs,
+            tool.failures,
+            tool.total_duration,
+            tool.mean_duration(),
+            tool.longest,
+            tool.last_run
+        );
+    }
+    for tool in &tools {
+        if tool.errors.is_empty() && tool.options.is_empty() {
+            continue;
+        }
+        println!();
+        println!("{}:", tool.tool);
+        if !tool.errors.is_empty() {
+            let errors: Vec<String> = tool.errors.iter().map(|(category, n)| format!("{} {}", category, n)).collect();
+            println!("\tErrors:\t\t{}", errors.join(", "));
+        }
+        if !tool.options.is_empty() {
+            let mut options: Vec<(&String, &u64)> = tool.options.iter().collect();
+            options.sort_by(|a, b| b.1.cmp(a.1).then_with(|| a.0.cmp(b.0)));
+            let options: Vec<String> = options.iter().map(|(option, n)| format!("{} {}", option, n)).collect();
+            println!("\tOptions:\t{}", options.join(", "));
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,177 @@
+//! Typed access to the attributes of a stream group
+//!
+//! The recorder, lsl-import and lsl-demo write `stream_info` and `recorder_config`
+//! when a stream is created, and lsl-sync adds the alignment attributes
+//! (`alignment_offset`, `trim_start_index`, ...). The structs here describe those
+//! attributes so tools read them through one definition instead of digging through
+//! `serde_json::Value`s, and a renamed or retyped field fails to compile instead of
+//! silently reading as missing.
+//!
+//! Every field of the stored schema that older stores may lack is optional; unknown
+//! fields are ignored, so new attributes never break old readers.
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::sync::Arc;
+use zarrs::filesystem::FilesystemStore;
+
+use super::read_group_attributes;
+
+/// The `stream_info` attribute: the LSL stream header at recording time
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct StreamInfoAttribute {
+    /// Stream name (only stored by lsl-import; the group name is the recorded name)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(default, rename = "type")]
+    pub stream_type: Option<String>,
+    #[serde(default)]
+    pub source_id: Option<String>,
+    #[serde(default)]
+    pub hostname: Option<String>,
+    #[serde(default)]
+    pub channel_count: Option<u64>,
+    /// Nominal rate in Hz (0 for irregular streams)
+    #[serde(default)]
+    pub nominal_srate: Option<f64>,
+    /// `Float32`, `Double64`, `Int32`, `Int16`, `Int8` or `String`
+    #[serde(default)]
+    pub channel_format: Option<String>,
+    #[serde(default)]
+    pub created_at: Option<f64>,
+    #[serde(default)]
+    pub uid: Option<String>,
+    #[serde(default)]
+    pub session_id: Option<String>,
+    #[serde(default)]
+    pub version: Option<f64>,
+    /// The `<desc>` element of the header as JSON
+    #[serde(default)]
+    pub description: Value,
+    /// Per-channel metadata (label, unit, type) from `<desc><channels>`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub channels: Vec<Value>,
+}
+
+impl StreamInfoAttribute {
+    /// The `stream_info` of a stream group's attributes
+    pub fn from_attributes(attributes: &Value) -> Option<Self> {
+        serde_json::from_value(attributes.get("stream_info")?.clone()).ok()
+    }
+
+    /// Nominal rate in Hz, 0 if unknown or irregular
+    pub fn srate(&self) -> f64 {
+        self.nominal_srate.unwrap_or(0.0)
+    }
+}
+
+/// The `recorder_config` attribute: how a stream was recorded, imported or generated
+///
+/// Recordings store every `lsl-recorder` option; imported and generated streams store
+/// a few fields plus their origin. Options without a field here are kept in `other`.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct RecorderConfig {
+    #[serde(default)]
+    pub recorder_version: Option<String>,
+    #[serde(default)]
+    pub recorded_at: Option<String>,
+    #[serde(default)]
+    pub subject: Option<String>,
+    #[serde(default)]
+    pub session_id: Option<String>,
+    #[serde(default)]
+    pub notes: Option<String>,
+    #[serde(default)]
+    pub resolution_strategy: Option<String>,
+    /// Requested recording duration in seconds
+    #[serde(default)]
+    pub duration: Option<u64>,
+    #[serde(default)]
+    pub chunk_samples: Option<u64>,
+    #[serde(default)]
+    pub compressor: Option<String>,
+    #[serde(default)]
+    pub compression_level: Option<u64>,
+    #[serde(default)]
+    pub shard_samples: Option<u64>,
+    /// Source file of a stream written by lsl-import
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub imported_from: Option<String>,
+    /// Tool that generated a synthetic stream (`lsl-demo`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub generated_by: Option<String>,
+    #[serde(flatten)]
+    pub other: serde_json::Map<String, Value>,
+}
+
+impl RecorderConfig {
+    /// The `recorder_config` of a stream group's attributes
+    pub fn from_attributes(attributes: &Value) -> Option<Self> {
+        serde_json::from_value(attributes.get("recorder_config")?.clone()).ok()
+    }
+}
+
+/// Alignment attributes written by lsl-sync
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Alignment {
+    /// Seconds added to the stream's timestamps for `aligned_time`
+    pub alignment_offset: f64,
+    /// First sample inside the common window
+    pub trim_start_index: u64,
+    /// End (exclusive) of the samples inside the common window
+    pub trim_end_index: u64,
+    pub original_sample_count: u64,
+    /// Samples inside the window, without excluded (paused) ones
+    pub trimmed_sample_count: u64,
+    /// `[start, end)` sample ranges left out with `--exclude-pauses`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub excluded_ranges: Vec<[u64; 2]>,
+    /// Fit of `--mode drift-correct` that mapped the stream onto the reference clock
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub drift_correction: Option<Value>,
+}
+
+impl Alignment {
+    /// The alignment of a stream group's attributes (`None` before lsl-sync ran)
+    pub fn from_attributes(attributes: &Value) -> Option<Self> {
+        attributes.get("alignment_offset")?;
+        serde_json::from_value(attributes.clone()).ok()
+    }
+}
+
+/// Attributes of a stream group, with a readable error for missing streams
+fn stream_attributes(store: &Arc<FilesystemStore>, stream_name: &str) -> Result<Value> {
+    read_group_attributes(store, &format!("/{}", stream_name))
+        .with_context(|| format!("Failed to read the metadata of stream '{}'", stream_name))
+}
+
+/// `stream_info` of a stream; an error if the stream has none
+pub fn get_stream_info(store: &Arc<FilesystemStore>, stream_name: &str) -> Result<StreamInfoAttribute> {
+    let attributes = stream_attributes(store, stream_name)?;
+    let info = attributes
+        .get("stream_info")
+        .with_context(|| format!("No stream_info in the metadata of stream '{}'", stream_name))?;
+    serde_json::from_value(info.clone()).with_context(|| format!("Invalid stream_info in stream '{}'", stream_name))
+}
+
+/// `recorder_config` of a stream (`None` if the stream has none)
+pub fn get_recorder_config(store: &Arc<FilesystemStore>, stream_name: &str) -> Result<Option<RecorderConfig>> {
+    let attributes = stream_attributes(store, stream_name)?;
+    attributes
+        .get("recorder_config")
+        .map(|config| serde_json::from_value(config.clone()))
+        .transpose()
+        .with_context(|| format!("Invalid recorder_config in stream '{}'", stream_name))
+}
+
+/// Alignment of a stream (`None` if lsl-sync has not aligned it)
+pub fn get_alignment(store: &Arc<FilesystemStore>, stream_name: &str) -> Result<Option<Alignment>> {
+    let attributes = stream_attributes(store, stream_name)?;
+    if attributes.get("alignment_offset").is_none() {
+        return Ok(None);
+    }
+    serde_json::from_value(attributes)
+        .map(Some)
+        .with_context(|| format!("Invalid alignment attributes in stream '{}'", stream_name))
+}
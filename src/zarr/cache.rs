@@ -0,0 +1,348 @@
+//! On-disk cache of values derived from a store's time arrays
+//!
+//! Inspecting, validating and synchronizing a session each start by reading every
+//! stream's time array (and `lsl-inspect --units` its samples), which for long
+//! high-rate recordings means gigabytes scanned again for the same extents, gap
+//! reports, drift fits, channel ranges and time-axis summaries. Tools keep those
+//! results in a small JSON file per store and reuse them on the next run.
+//!
+//! Cache files live outside the store, so read-only and shared stores work and the
+//! store's own modification times are left alone: `$LSL_CACHE_DIR`, else
+//! `$XDG_CACHE_HOME/lsl-recording-toolbox`, else `~/.cache/lsl-recording-toolbox`,
+//! with the absolute store path below `stores/` (`/data/P001.zarr` becomes
+//! `stores/data/P001.zarr.json`).
+//!
+//! Every entry is keyed by its stream's [`StreamFingerprint`], the modification times
+//! of the `zarr.json` files and chunk directories of its time and sample arrays.
+//! Appending samples, repairing or re-importing a stream changes them and the
+//! stream's entry is discarded; attribute updates such as `lsl-sync` writing its
+//! alignment leave the arrays, and so the entry, untouched. Fingerprints are taken when the cache is opened, so a
+//! stream that changes while a tool reads it is stored under the older fingerprint
+//! and recomputed next time. Tools accept `--no-cache` to bypass the cache.
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Component, Path, PathBuf};
+use std::time::UNIX_EPOCH;
+
+use crate::drift::DriftFit;
+use crate::gaps::GapReport;
+use crate::time_axis::TimeAxisSummary;
+use crate::zarr::is_store_group;
+
+/// Format version of cache files; files of another version are ignored
+const CACHE_VERSION: u32 = 1;
+
+/// Timestamps kept per stream for comparing the start of streams
+pub const CACHED_HEAD_SAMPLES: usize = 100;
+
+/// Default cache directory (see the module documentation)
+pub fn default_cache_dir() -> PathBuf {
+    if let Some(dir) = std::env::var_os("LSL_CACHE_DIR") {
+        return PathBuf::from(dir);
+    }
+    std::env::var_os("XDG_CACHE_HOME")
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".cache")))
+        .or_else(|| std::env::var_os("LOCALAPPDATA").map(PathBuf::from))
+        .unwrap_or_else(std::env::temp_dir)
+        .join("lsl-recording-toolbox")
+}
+
+/// Cache file of a store below `cache_dir`
+pub fn cache_file_path(store_path: &Path, cache_dir: &Path) -> PathBuf {
+    let absolute = std::fs::canonicalize(store_path).unwrap_or_else(|_| store_path.to_path_buf());
+    let mut path = cache_dir.join("stores");
+    for component in absolute.components() {
+        match component {
+            Component::Prefix(prefix) => path.push(prefix.as_os_str().to_string_lossy().replace(':', "")),
+            Component::Normal(part) => path.push(part),
+            _ => {}
+        }
+    }
+    let mut file = path.into_os_string();
+    file.push(".json");
+    PathBuf::from(file)
+}
+
+/// Modification times identifying the current contents of a stream's arrays
+///
+/// Times are nanoseconds since the Unix epoch; chunk directories are absent before
+/// the first chunk is written.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StreamFingerprint {
+    /// `time/zarr.json`
+    pub time_metadata: u64,
+    /// `time/c`
+    pub time_chunks: Option<u64>,
+    /// `zarr.json` of the sample array (`data`, or `events` for marker streams)
+    pub samples_metadata: Option<u64>,
+    /// Chunk directory of the sample array
+    pub samples_chunks: Option<u64>,
+}
+
+impl StreamFingerprint {
+    /// Fingerprint of a stream, `None` if it has no time array
+    pub fn of(store_path: &Path, stream_name: &str) -> Option<Self> {
+        let stream = store_path.join(stream_name);
+        let samples = ["data", "events"]
+            .iter()
+            .map(|name| stream.join(name))
+            .find(|path| path.join("zarr.json").exists())
+            .unwrap_or_else(|| stream.join("data"));
+        Some(Self {
+            time_metadata: modified_nanos(&stream.join("time").join("zarr.json"))?,
+            time_chunks: modified_nanos(&stream.join("time").join("c")),
+            samples_metadata: modified_nanos(&samples.join("zarr.json")),
+            samples_chunks: modified_nanos(&samples.join("c")),
+        })
+    }
+}
+
+fn modified_nanos(path: &Path) -> Option<u64> {
+    let modified = std::fs::metadata(path).ok()?.modified().ok()?;
+    Some(modified.duration_since(UNIX_EPOCH).unwrap_or_default().as_nanos() as u64)
+}
+
+/// Sample count and first/last timestamp of a stream
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct StreamExtent {
+    pub samples: u64,
+    pub first: f64,
+    pub last: f64,
+}
+
+/// Gap report of a stream for one gap factor and nominal rate
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CachedGaps {
+    pub factor: f64,
+    pub nominal_srate: f64,
+    pub report: GapReport,
+}
+
+/// Drift fit of a stream (the reference) against another stream
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CachedDrift {
+    /// Other stream as `<store path>#<stream name>`
+    pub other: String,
+    pub other_fingerprint: StreamFingerprint,
+    pub reference_srate: f64,
+    pub other_srate: f64,
+    /// `None` if the streams could not be fitted
+    pub fit: Option<DriftFit>,
+}
+
+/// Cached values of one stream
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CachedStream {
+    pub fingerprint: StreamFingerprint,
+    #[serde(default)]
+    pub extent: Option<StreamExtent>,
+    /// First [`CACHED_HEAD_SAMPLES`] timestamps
+    #[serde(default)]
+    pub head: Option<Vec<f64>>,
+    #[serde(default)]
+    pub gaps: Vec<CachedGaps>,
+    #[serde(default)]
+    pub drift: Vec<CachedDrift>,
+    /// Per-channel `[min, max]` of the sample array, `None` for channels without samples
+    #[serde(default)]
+    pub channel_ranges: Option<Vec<Option<(f64, f64)>>>,
+    /// Time-axis summary (`lsl-inspect --timeline`)
+    #[serde(default)]
+    pub time_axis: Option<TimeAxisSummary>,
+}
+
+impl CachedStream {
+    fn new(fingerprint: StreamFingerprint) -> Self {
+        Self {
+            fingerprint,
+            extent: None,
+            head: None,
+            gaps: Vec::new(),
+            drift: Vec::new(),
+            channel_ranges: None,
+            time_axis: None,
+        }
+    }
+
+    /// Gap report for this factor and nominal rate
+    pub fn gaps(&self, factor: f64, nominal_srate: f64) -> Option<&GapReport> {
+        self.gaps
+            .iter()
+            .find(|g| g.factor == factor && g.nominal_srate == nominal_srate)
+            .map(|g| &g.report)
+    }
+
+    /// Time-axis summary for this nominal rate and number of bins
+    pub fn time_axis(&self, nominal_srate: f64, bins: usize) -> Option<&TimeAxisSummary> {
+        self.time_axis
+            .as_ref()
+            .filter(|summary| summary.nominal_srate == nominal_srate.max(0.0) && summary.bins.len() == bins)
+    }
+
+    pub fn set_gaps(&mut self, factor: f64, nominal_srate: f64, report: GapReport) {
+        self.gaps.retain(|g| g.factor != factor || g.nominal_srate != nominal_srate);
+        self.gaps.push(CachedGaps { factor, nominal_srate, report });
+    }
+
+    /// Drift fit against `other`, if it was computed for its current contents and these rates
+    pub fn drift(
+        &self,
+        other: &str,
+        other_fingerprint: StreamFingerprint,
+        reference_srate: f64,
+        other_srate: f64,
+    ) -> Option<Option<DriftFit>> {
+        self.drift
+            .iter()
+            .find(|d| {
+                d.other == other
+                    && d.other_fingerprint == other_fingerprint
+                    && d.reference_srate == reference_srate
+                    && d.other_srate == other_srate
+            })
+            .map(|d| d.fit)
+    }
+
+    pub fn set_drift(&mut self, entry: CachedDrift) {
+        self.drift.retain(|d| d.other != entry.other);
+        self.drift.push(entry);
+    }
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct CacheFile {
+    version: u32,
+    store: PathBuf,
+    streams: BTreeMap<String, CachedStream>,
+}
+
+/// Derived values of one store, valid for its streams' current fingerprints
+#[derive(Debug)]
+pub struct StoreCache {
+    store: PathBuf,
+    /// `None` when caching is disabled
+    file: Option<PathBuf>,
+    fingerprints: BTreeMap<String, StreamFingerprint>,
+    streams: BTreeMap<String, CachedStream>,
+    changed: bool,
+}
+
+impl StoreCache {
+    /// Cache of a store in the default cache directory
+    pub fn open(store_path: &Path) -> Self {
+        Self::open_in(store_path, &default_cache_dir())
+    }
+
+    /// Cache of a store in `cache_dir`; entries of changed streams are dropped
+    ///
+    /// A missing or unreadable cache file gives an empty cache.
+    pub fn open_in(store_path: &Path, cache_dir: &Path) -> Self {
+        let file = cache_file_path(store_path, cache_dir);
+        let fingerprints = fingerprint_streams(store_path);
+        let saved: CacheFile = std::fs::read(&file)
+            .ok()
+            .and_then(|bytes| serde_json::from_slice(&bytes).ok())
+            .filter(|saved: &CacheFile| saved.version == CACHE_VERSION)
+            .unwrap_or_default();
+        let streams = saved
+            .streams
+            .into_iter()
+            .filter(|(name, entry)| fingerprints.get(name) == Some(&entry.fingerprint))
+            .collect();
+        Self {
+            store: store_path.to_path_buf(),
+            file: Some(file),
+            fingerprints,
+            streams,
+            changed: false,
+        }
+    }
+
+    /// A cache that never holds values and never writes (`--no-cache`)
+    pub fn disabled(store_path: &Path) -> Self {
+        Self {
+            store: store_path.to_path_buf(),
+            file: None,
+            fingerprints: fingerprint_streams(store_path),
+            streams: BTreeMap::new(),
+            changed: false,
+        }
+    }
+
+    /// Fingerprint of a stream when the cache was opened
+    pub fn fingerprint(&self, stream_name: &str) -> Option<StreamFingerprint> {
+        self.fingerprints.get(stream_name).copied()
+    }
+
+    /// Cached values of a stream, if any
+    pub fn stream(&self, stream_name: &str) -> Option<&CachedStream> {
+        self.streams.get(stream_name)
+    }
+
+    /// Values of a stream to fill in; `None` for streams without a time array
+    pub fn stream_mut(&mut self, stream_name: &str) -> Option<&mut CachedStream> {
+        self.file.as_ref()?;
+        let fingerprint = self.fingerprint(stream_name)?;
+        self.changed = true;
+        Some(
+            self.streams
+                .entry(stream_name.to_string())
+                .or_insert_with(|| CachedStream::new(fingerprint)),
+        )
+    }
+
+    /// Write the cache file if values were added
+    pub fn save(&self) -> Result<()> {
+        let Some(ref file) = self.file else {
+            return Ok(());
+        };
+        if !self.changed {
+            return Ok(());
+        }
+        let contents = CacheFile {
+            version: CACHE_VERSION,
+            store: self.store.clone(),
+            streams: self.streams.clone(),
+        };
+        if let Some(parent) = file.parent() {
+            std::fs::create_dir_all(parent)
+                .with_context(|| format!("Cannot create cache directory {}", parent.display()))?;
+        }
+        // Written next to the target and renamed, so concurrent readers never see half a file
+        let temporary = file.with_extension(format!("json.{}", std::process::id()));
+        std::fs::write(&temporary, serde_json::to_vec(&contents)?)
+            .with_context(|| format!("Cannot write cache file {}", temporary.display()))?;
+        std::fs::rename(&temporary, file)?;
+        Ok(())
+    }
+
+    /// Save, reporting a failure as a warning; a cache that cannot be written only costs time
+    pub fn save_or_warn(&self) {
+        if let Err(e) = self.save() {
+            eprintln!("Warning: {:#}", e);
+        }
+    }
+}
+
+/// Fingerprints of all streams of a store
+fn fingerprint_streams(store_path: &Path) -> BTreeMap<String, StreamFingerprint> {
+    let Ok(entries) = std::fs::read_dir(store_path) else {
+        return BTreeMap::new();
+    };
+    entries
+        .flatten()
+        .filter(|entry| entry.file_type().is_ok_and(|t| t.is_dir()))
+        .map(|entry| entry.file_name().to_string_lossy().to_string())
+        .filter(|name| !is_store_group(name))
+        .filter_map(|name| Some((name.clone(), StreamFingerprint::of(store_path, &name)?)))
+        .collect()
+}
+
+/// Stream identity used as key of cached drift fits
+pub fn stream_key(store_path: &Path, stream_name: &str) -> String {
+    let absolute = std::fs::canonicalize(store_path).unwrap_or_else(|_| store_path.to_path_buf());
+    format!("{}#{}", absolute.display(), stream_name)
+}
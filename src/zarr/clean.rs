@@ -0,0 +1,102 @@
+//! Removal of `lsl-sync` outputs (`lsl-sync --clean`)
+//!
+//! Synchronizing writes arrays next to the raw ones (`aligned_time`, the
+//! materialized `synced` group, `resampled_data`/`resampled_time`) and alignment
+//! attributes to every stream group. Re-running with other parameters only
+//! overwrites what the new run produces, so outputs of an earlier run (a `synced`
+//! copy, a resampling, trim indices of a stream no longer selected) would stay
+//! behind. [`clean_store`] removes all of them, returning the store to the state
+//! the recorder left it in.
+//!
+//! Streams whose raw arrays were retired (`raw_retention` attribute) hold only the
+//! synchronized copy and cannot be restored; they are left untouched.
+
+use anyhow::Result;
+use std::path::Path;
+use std::sync::Arc;
+use zarrs::filesystem::FilesystemStore;
+
+use super::materialize::{RAW_RETENTION_ATTRIBUTE, SYNCED_GROUP};
+use super::{is_store_group, read_group_attributes};
+use crate::pauses::EXCLUDED_RANGES_ATTRIBUTE;
+use crate::resample::{RESAMPLED_DATA, RESAMPLED_TIME, RESAMPLING_ATTRIBUTE};
+
+/// Arrays and groups written by `lsl-sync` inside a stream group
+pub const SYNC_OUTPUTS: [&str; 4] = ["aligned_time", SYNCED_GROUP, RESAMPLED_DATA, RESAMPLED_TIME];
+
+/// Stream group attributes written by `lsl-sync`
+pub const SYNC_OUTPUT_ATTRIBUTES: [&str; 8] = [
+    "alignment_offset",
+    "trim_start_index",
+    "trim_end_index",
+    "original_sample_count",
+    "trimmed_sample_count",
+    EXCLUDED_RANGES_ATTRIBUTE,
+    "drift_correction",
+    RESAMPLING_ATTRIBUTE,
+];
+
+/// What was removed from one stream
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CleanedStream {
+    pub stream: String,
+    pub removed_arrays: Vec<String>,
+    pub removed_attributes: Vec<String>,
+    /// Why the stream was left untouched
+    pub skipped: Option<String>,
+}
+
+impl CleanedStream {
+    /// Whether anything was removed
+    pub fn changed(&self) -> bool {
+        !self.removed_arrays.is_empty() || !self.removed_attributes.is_empty()
+    }
+}
+
+/// Remove the `lsl-sync` outputs of one stream
+pub fn clean_stream(store_path: &Path, stream_name: &str) -> Result<CleanedStream> {
+    let store = Arc::new(FilesystemStore::new(store_path)?);
+    let mut cleaned = CleanedStream {
+        stream: stream_name.to_string(),
+        ..Default::default()
+    };
+
+    let attributes = read_group_attributes(&store, stream_name)?;
+    if attributes.get(RAW_RETENTION_ATTRIBUTE).is_some() {
+        cleaned.skipped = Some("raw arrays retired, only the synchronized copy is left".to_string());
+        return Ok(cleaned);
+    }
+
+    for name in SYNC_OUTPUTS {
+        let path = store_path.join(stream_name).join(name);
+        if path.exists() {
+            std::fs::remove_dir_all(&path)?;
+            cleaned.removed_arrays.push(name.to_string());
+        }
+    }
+
+    if SYNC_OUTPUT_ATTRIBUTES.iter().any(|name| attributes.get(name).is_some()) {
+        let mut group = zarrs::group::Group::open(store.clone(), &format!("/{}", stream_name))?;
+        for name in SYNC_OUTPUT_ATTRIBUTES {
+            if group.attributes_mut().remove(name).is_some() {
+                cleaned.removed_attributes.push(name.to_string());
+            }
+        }
+        group.store_metadata()?;
+    }
+    Ok(cleaned)
+}
+
+/// Remove the `lsl-sync` outputs of every stream, or only of `streams` if not empty
+pub fn clean_store(store_path: &Path, streams: &[String]) -> Result<Vec<CleanedStream>> {
+    let mut names: Vec<String> = std::fs::read_dir(store_path)?
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.file_type().map(|t| t.is_dir()).unwrap_or(false))
+        .map(|entry| entry.file_name().to_string_lossy().to_string())
+        .filter(|name| !is_store_group(name) && store_path.join(name).join("zarr.json").is_file())
+        .filter(|name| streams.is_empty() || streams.contains(name))
+        .collect();
+    names.sort();
+
+    names.iter().map(|name| clean_stream(store_path, name)).collect()
+}
@@ -0,0 +1,285 @@
+//! Read-only access to stores served over HTTP(S)
+//!
+//! `lsl-inspect https://data.lab.org/sessions/P001.zarr` and `lsl-validate` accept
+//! store URLs. Like recording to object storage (see [`super::remote`]), the tools
+//! keep working on a local directory: [`fetch_http_store`] copies the metadata of
+//! the store, its streams and their arrays into a cache directory, together with the
+//! chunks of the timestamp arrays (`time`, `time_index`, `aligned_time`). Sample
+//! chunks are only fetched when asked for (`lsl-inspect --units`), so a quick check
+//! of a long recording transfers a small fraction of it.
+//!
+//! Plain HTTP has no directory listing. Streams are taken from the server's
+//! directory index page (as served by nginx `autoindex`, Apache or
+//! `python -m http.server`), else from the store's session manifest and device
+//! table, or are named explicitly (`lsl-inspect --stream`). Chunk keys are computed
+//! from each array's shape and chunk grid; chunks the server does not have are left
+//! to the fill value.
+//!
+//! Fetching needs the `http` cargo feature.
+
+use std::path::{Path, PathBuf};
+
+use super::remote::spool_path;
+
+/// URL schemes of stores read over HTTP
+const HTTP_SCHEMES: [&str; 2] = ["http://", "https://"];
+
+/// Arrays of a stream group copied into the cache
+pub const STREAM_ARRAYS: [&str; 6] = ["time", "time_index", "aligned_time", "flags", "data", "events"];
+
+/// Arrays whose chunks hold samples rather than timestamps
+const SAMPLE_ARRAYS: [&str; 2] = ["data", "events"];
+
+/// Whether a store path is an HTTP(S) URL
+pub fn is_http(path: &str) -> bool {
+    HTTP_SCHEMES.iter().any(|scheme| path.starts_with(scheme))
+}
+
+/// Local cache directory of a store URL
+///
+/// `https://data.lab.org/P001.zarr` becomes `<cache_dir>/https/data.lab.org/P001.zarr`,
+/// with `<tmp>/lsl-http-cache` as default `cache_dir`.
+pub fn cache_path(url: &str, cache_dir: Option<&Path>) -> PathBuf {
+    let base = cache_dir
+        .map(Path::to_path_buf)
+        .unwrap_or_else(|| std::env::temp_dir().join("lsl-http-cache"));
+    spool_path(url.trim_end_matches('/'), Some(&base))
+}
+
+/// Keys of all chunks (or shards) of an array, from its zarr.json metadata
+///
+/// Supports the `default` (`c/0/1`) and `v2` (`0.1`) chunk key encodings.
+pub fn chunk_keys(metadata: &serde_json::Value) -> Vec<String> {
+    let dims = |pointer: &str| -> Vec<u64> {
+        metadata
+            .pointer(pointer)
+            .and_then(|v| v.as_array())
+            .map(|values| values.iter().filter_map(|v| v.as_u64()).collect())
+            .unwrap_or_default()
+    };
+    let shape = dims("/shape");
+    let chunk_shape = dims("/chunk_grid/configuration/chunk_shape");
+    if shape.len() != chunk_shape.len() || chunk_shape.contains(&0) {
+        return Vec::new();
+    }
+    let counts: Vec<u64> = shape.iter().zip(&chunk_shape).map(|(s, c)| s.div_ceil(*c)).collect();
+    if counts.contains(&0) {
+        return Vec::new();
+    }
+
+    let encoding = metadata
+        .pointer("/chunk_key_encoding/name")
+        .and_then(|v| v.as_str())
+        .unwrap_or("default");
+    let v2 = encoding == "v2";
+    let separator = metadata
+        .pointer("/chunk_key_encoding/configuration/separator")
+        .and_then(|v| v.as_str())
+        .unwrap_or(if v2 { "." } else { "/" });
+
+    let mut keys = Vec::new();
+    let mut index = vec![0u64; counts.len()];
+    loop {
+        let parts: Vec<String> = index.iter().map(u64::to_string).collect();
+        keys.push(match (v2, parts.is_empty()) {
+            (true, true) => "0".to_string(),
+            (true, false) => parts.join(separator),
+            (false, true) => "c".to_string(),
+            (false, false) => format!("c{}{}", separator, parts.join(separator)),
+        });
+        // Advance the last dimension fastest
+        let Some(dim) = (0..index.len()).rev().find(|&d| index[d] + 1 < counts[d]) else {
+            break;
+        };
+        index[dim] += 1;
+        for later in &mut index[dim + 1..] {
+            *later = 0;
+        }
+    }
+    keys
+}
+
+/// Subdirectory names linked from an HTML directory index page
+pub fn index_links(html: &str) -> Vec<String> {
+    let mut names: Vec<String> = html
+        .split("href=")
+        .skip(1)
+        .filter_map(|rest| {
+            let quote = rest.chars().next().filter(|c| *c == '"' || *c == '\'')?;
+            let target = rest[1..].split(quote).next()?;
+            let name = target.strip_prefix("./").unwrap_or(target).strip_suffix('/')?;
+            let name = name.replace("%20", " ");
+            let valid = !name.is_empty()
+                && !name.starts_with(['.', '?', '#'])
+                && !name.contains(['/', ':']);
+            valid.then_some(name)
+        })
+        .collect();
+    names.sort();
+    names.dedup();
+    names
+}
+
+/// What [`fetch_http_store`] copies
+#[derive(Debug, Clone, Default)]
+pub struct HttpFetchOptions {
+    /// Cache root (default `<tmp>/lsl-http-cache`)
+    pub cache_dir: Option<PathBuf>,
+    /// Streams to fetch; all discovered streams if empty
+    pub streams: Vec<String>,
+    /// Also fetch the sample chunks of `data`/`events`
+    pub sample_data: bool,
+}
+
+#[cfg(feature = "http")]
+pub use fetch::fetch_http_store;
+
+#[cfg(feature = "http")]
+mod fetch {
+    use anyhow::{Context, Result};
+    use std::io::Read;
+    use std::path::{Path, PathBuf};
+    use std::time::Duration;
+
+    use super::{cache_path, chunk_keys, index_links, HttpFetchOptions, SAMPLE_ARRAYS, STREAM_ARRAYS};
+    use crate::zarr::{is_store_group, META_GROUP};
+
+    /// Copy a store served over HTTP(S) into the local cache and return the cache path
+    ///
+    /// The cached copy of the URL is replaced on every call.
+    pub fn fetch_http_store(url: &str, options: &HttpFetchOptions) -> Result<PathBuf> {
+        let url = url.trim_end_matches('/');
+        let local = cache_path(url, options.cache_dir.as_deref());
+        if local.exists() {
+            std::fs::remove_dir_all(&local)
+                .with_context(|| format!("Failed to clear the cache at {}", local.display()))?;
+        }
+        let agent = ureq::AgentBuilder::new().timeout(Duration::from_secs(30)).build();
+        let fetcher = Fetcher { agent, url, local: &local };
+
+        if !fetcher.copy("zarr.json")? {
+            anyhow::bail!("No Zarr store at {} (zarr.json not found)", url);
+        }
+        fetcher.copy(&format!("{}/zarr.json", META_GROUP))?;
+
+        let explicit = !options.streams.is_empty();
+        let streams = if explicit { options.streams.clone() } else { fetcher.discover_streams()? };
+        if streams.is_empty() {
+            anyhow::bail!(
+                "Cannot list the streams of {}: the server has no directory index and the store no manifest or device table; name them with --stream",
+                url
+            );
+        }
+
+        for stream in &streams {
+            if !fetcher.copy(&format!("{}/zarr.json", stream))? {
+                if explicit {
+                    eprintln!("Warning: stream '{}' not found at {}", stream, url);
+                }
+                continue;
+            }
+            for array in STREAM_ARRAYS {
+                let metadata_key = format!("{}/{}/zarr.json", stream, array);
+                let Some(metadata) = fetcher.get(&metadata_key)? else {
+                    continue;
+                };
+                fetcher.store(&metadata_key, &metadata)?;
+                if SAMPLE_ARRAYS.contains(&array) && !options.sample_data {
+                    continue;
+                }
+                let metadata: serde_json::Value = serde_json::from_slice(&metadata)
+                    .with_context(|| format!("Invalid metadata at {}/{}", url, metadata_key))?;
+                for key in chunk_keys(&metadata) {
+                    fetcher.copy(&format!("{}/{}/{}", stream, array, key))?;
+                }
+            }
+        }
+        Ok(local)
+    }
+
+    struct Fetcher<'a> {
+        agent: ureq::Agent,
+        url: &'a str,
+        local: &'a Path,
+    }
+
+    impl Fetcher<'_> {
+        /// Body of `<url>/<key>`, `None` if the server does not have it
+        fn get(&self, key: &str) -> Result<Option<Vec<u8>>> {
+            let url = format!("{}/{}", self.url, key);
+            match self.agent.get(&url).call() {
+                Ok(response) => {
+                    let mut bytes = Vec::new();
+                    response.into_reader().read_to_end(&mut bytes)?;
+                    Ok(Some(bytes))
+                }
+                Err(ureq::Error::Status(404 | 403, _)) => Ok(None),
+                Err(e) => Err(anyhow::anyhow!("GET {} failed: {}", url, e)),
+            }
+        }
+
+        fn store(&self, key: &str, bytes: &[u8]) -> Result<()> {
+            let path = key.split('/').fold(self.local.to_path_buf(), |path, part| path.join(part));
+            if let Some(parent) = path.parent() {
+                std::fs::create_dir_all(parent)?;
+            }
+            std::fs::write(&path, bytes)?;
+            Ok(())
+        }
+
+        /// Copy `<url>/<key>` into the cache; false if the server does not have it
+        fn copy(&self, key: &str) -> Result<bool> {
+            match self.get(key)? {
+                Some(bytes) => {
+                    self.store(key, &bytes)?;
+                    Ok(true)
+                }
+                None => Ok(false),
+            }
+        }
+
+        /// Stream groups from the directory index, else from the session manifest and device table
+        fn discover_streams(&self) -> Result<Vec<String>> {
+            let listed: Vec<String> = self
+                .get("")?
+                .map(|html| index_links(&String::from_utf8_lossy(&html)))
+                .unwrap_or_default()
+                .into_iter()
+                .filter(|name| !is_store_group(name))
+                .collect();
+            if !listed.is_empty() {
+                return Ok(listed);
+            }
+
+            let meta_path = self.local.join(META_GROUP).join("zarr.json");
+            let Ok(meta) = std::fs::read(&meta_path) else {
+                return Ok(Vec::new());
+            };
+            let meta: serde_json::Value = serde_json::from_slice(&meta)?;
+            let manifest_streams = meta
+                .pointer("/attributes/manifest/streams")
+                .and_then(|v| v.as_array())
+                .into_iter()
+                .flatten()
+                .filter_map(|stream| stream.get("name").and_then(|n| n.as_str()));
+            let device_streams = meta
+                .pointer("/attributes/devices")
+                .and_then(|v| v.as_array())
+                .into_iter()
+                .flatten()
+                .filter_map(|device| device.get("streams").and_then(|s| s.as_array()))
+                .flatten()
+                .filter_map(|s| s.as_str());
+            let mut streams: Vec<String> = manifest_streams.chain(device_streams).map(String::from).collect();
+            streams.sort();
+            streams.dedup();
+            Ok(streams)
+        }
+    }
+}
+
+/// Placeholder used when the crate is built without the `http` feature
+#[cfg(not(feature = "http"))]
+pub fn fetch_http_store(url: &str, _options: &HttpFetchOptions) -> anyhow::Result<PathBuf> {
+    anyhow::bail!("Reading {} requires the `http` feature", url)
+}
@@ -0,0 +1,388 @@
+//! Stream array layout shared by the recorder and lsl-import
+//!
+//! Creates the groups and arrays of a stream (`data` or `events`, `time`, and
+//! optionally `flags`) with the configured chunking and compression, and parses
+//! LSL stream header XML into the `stream_info` attribute. Channel formats are named as stored in
+//! `stream_info.channel_format` (`Float32`, `Double64`, `Int32`, `Int16`, `Int8`,
+//! `String`).
+
+use anyhow::Result;
+use std::num::NonZeroU64;
+use std::sync::Arc;
+use zarrs::array::codec::array_to_bytes::sharding::{ShardingCodec, ShardingCodecBuilder};
+use zarrs::array::codec::{
+    BloscCodec, BloscCompressionLevel, BloscCompressor, BloscShuffleMode, BytesToBytesCodecTraits,
+};
+use zarrs::array::{Array, ArrayBuilder, DataType, FillValue};
+use zarrs::filesystem::FilesystemStore;
+use zarrs::group::GroupBuilder;
+
+use super::{group_exists, Compressor, StorageOptions};
+
+/// Create a Zarr group if it doesn't exist
+pub(crate) fn create_group_if_not_exists(store: &Arc<FilesystemStore>, path: &str) -> Result<()> {
+    if !group_exists(store, path)? {
+        let group = GroupBuilder::new().build(store.clone(), path)?;
+        group.store_metadata()?;
+    }
+    Ok(())
+}
+
+/// Parse `<desc><channels><channel>...</channel></channels></desc>` into one object per channel
+pub(crate) fn parse_channels_to_json(xml: &str) -> Vec<serde_json::Value> {
+    use quick_xml::events::Event;
+    use quick_xml::Reader;
+
+    let mut reader = Reader::from_str(xml);
+    reader.config_mut().trim_text(true);
+
+    let mut path: Vec<String> = Vec::new();
+    let mut channels = Vec::new();
+    let mut current: Option<serde_json::Map<String, serde_json::Value>> = None;
+    let mut text = String::new();
+
+    loop {
+        match reader.read_event() {
+            Ok(Event::Start(e)) => {
+                let tag = String::from_utf8_lossy(e.name().as_ref()).to_string();
+                if tag == "channel" && path.ends_with(&["desc".to_string(), "channels".to_string()]) {
+                    current = Some(serde_json::Map::new());
+                }
+                path.push(tag);
+                text.clear();
+            }
+            Ok(Event::Text(e)) => {
+                if let Ok(t) = e.unescape() {
+                    text.push_str(&t);
+                }
+            }
+            Ok(Event::End(_)) => {
+                let tag = path.pop().unwrap_or_default();
+                if tag == "channel" && current.is_some() && path.last().map(String::as_str) == Some("channels") {
+                    channels.push(serde_json::Value::Object(current.take().unwrap_or_default()));
+                } else if let Some(ref mut channel) = current
+                    && path.last().map(String::as_str) == Some("channel")
+                {
+                    channel.insert(tag, serde_json::Value::String(std::mem::take(&mut text)));
+                }
+            }
+            Ok(Event::Eof) => break,
+            Err(e) => {
+                tracing::warn!("Error parsing channel metadata: {}", e);
+                break;
+            }
+            _ => {}
+        }
+    }
+
+    channels
+}
+
+/// Parse the <desc> element from LSL XML to JSON using quick-xml
+pub(crate) fn parse_desc_to_json(xml: &str) -> serde_json::Value {
+    use quick_xml::events::Event;
+    use quick_xml::Reader;
+
+    let mut reader = Reader::from_str(xml);
+    reader.config_mut().trim_text(true);
+
+    let mut in_desc = false;
+    let mut depth = 0;
+    let mut desc_xml = Vec::new();
+
+    loop {
+        match reader.read_event() {
+            Ok(Event::Start(e)) if e.name().as_ref() == b"desc" => {
+                in_desc = true;
+                depth = 1;
+            }
+            Ok(Event::Start(e)) if in_desc => {
+                depth += 1;
+                desc_xml.extend_from_slice(b"<");
+                desc_xml.extend_from_slice(e.name().as_ref());
+                desc_xml.extend_from_slice(b">");
+            }
+            Ok(Event::End(e)) if in_desc => {
+                depth -= 1;
+                if depth == 0 {
+                    // Finished reading desc element
+                    let desc_content = String::from_utf8_lossy(&desc_xml).to_string();
+                    return parse_xml_to_json(&desc_content);
+                }
+                desc_xml.extend_from_slice(b"</");
+                desc_xml.extend_from_slice(e.name().as_ref());
+                desc_xml.extend_from_slice(b">");
+            }
+            Ok(Event::Text(e)) if in_desc => {
+                desc_xml.extend_from_slice(&e);
+            }
+            Ok(Event::Empty(e)) if e.name().as_ref() == b"desc" => {
+                // Empty desc element
+                return serde_json::Value::Object(serde_json::Map::new());
+            }
+            Ok(Event::Eof) => break,
+            Err(e) => {
+                tracing::warn!("Error parsing LSL XML: {}", e);
+                break;
+            }
+            _ => {}
+        }
+    }
+
+    serde_json::Value::Object(serde_json::Map::new())
+}
+
+/// Parse XML string to JSON recursively using quick-xml
+fn parse_xml_to_json(xml: &str) -> serde_json::Value {
+    use quick_xml::events::Event;
+    use quick_xml::Reader;
+
+    let mut result = serde_json::Map::new();
+    let mut reader = Reader::from_str(xml);
+    reader.config_mut().trim_text(true);
+
+    let mut current_tag = String::new();
+    let mut current_text = String::new();
+
+    loop {
+        match reader.read_event() {
+            Ok(Event::Start(e)) => {
+                current_tag = String::from_utf8_lossy(e.name().as_ref()).to_string();
+                current_text.clear();
+            }
+            Ok(Event::Text(e)) => {
+                if let Ok(text) = e.unescape() {
+                    current_text.push_str(&text);
+                }
+            }
+            Ok(Event::End(_)) => {
+                if !current_tag.is_empty() {
+                    result.insert(current_tag.clone(), serde_json::Value::String(current_text.clone()));
+                    current_tag.clear();
+                    current_text.clear();
+                }
+            }
+            Ok(Event::Empty(e)) => {
+                let tag = String::from_utf8_lossy(e.name().as_ref()).to_string();
+                result.insert(tag, serde_json::Value::String(String::new()));
+            }
+            Ok(Event::Eof) => break,
+            Err(e) => {
+                tracing::warn!("Error parsing XML element: {}", e);
+                break;
+            }
+            _ => {}
+        }
+    }
+
+    serde_json::Value::Object(result)
+}
+
+/// Get dtype for Zarr array based on the channel format name
+pub(crate) fn zarr_dtype(channel_format: &str) -> Result<DataType> {
+    match channel_format {
+        "Float32" => Ok(DataType::Float32),
+        "Double64" => Ok(DataType::Float64),
+        "Int32" => Ok(DataType::Int32),
+        "Int16" => Ok(DataType::Int16),
+        "Int8" => Ok(DataType::Int8),
+        "String" => Ok(DataType::String),
+        _ => Err(anyhow::anyhow!(
+            "Unsupported channel format for Zarr: {}",
+            channel_format
+        )),
+    }
+}
+
+/// Get typesize for Blosc compression based on the channel format name
+fn blosc_typesize(channel_format: &str) -> Option<usize> {
+    match channel_format {
+        "Float32" => Some(4),  // 4 bytes
+        "Double64" => Some(8),  // 8 bytes
+        "Int32" => Some(4),  // 4 bytes
+        "Int16" => Some(2),  // 2 bytes
+        "Int8" => Some(1),   // 1 byte
+        _ => None,  // String or unsupported
+    }
+}
+
+/// Build the bytes-to-bytes codec chain for the configured compressor
+fn bytes_to_bytes_codecs(
+    storage: &StorageOptions,
+    shuffle_mode: BloscShuffleMode,
+    typesize: Option<usize>,
+) -> Result<Vec<Arc<dyn BytesToBytesCodecTraits>>> {
+    let compressor = match storage.compressor {
+        Compressor::Lz4 => BloscCompressor::LZ4,
+        Compressor::Zstd => BloscCompressor::Zstd,
+        Compressor::None => return Ok(Vec::new()),
+    };
+    let compression_level = BloscCompressionLevel::try_from(storage.compression_level)
+        .map_err(|e| anyhow::anyhow!("Invalid compression level: {}", e))?;
+    let blosc_codec = BloscCodec::new(
+        compressor,
+        compression_level,
+        None,  // blocksize (auto-detect)
+        shuffle_mode,
+        typesize,  // typesize required for shuffling
+    )?;
+    Ok(vec![Arc::new(blosc_codec)])
+}
+
+/// Sharding codec wrapping `codecs` around inner chunks of `inner_chunk_shape`
+fn sharding_codec(
+    inner_chunk_shape: &[u64],
+    codecs: Vec<Arc<dyn BytesToBytesCodecTraits>>,
+) -> Result<Arc<ShardingCodec>> {
+    let inner_chunk_shape = inner_chunk_shape
+        .iter()
+        .map(|&n| NonZeroU64::new(n).ok_or_else(|| anyhow::anyhow!("Chunk dimensions must be non-zero")))
+        .collect::<Result<Vec<_>>>()?;
+    Ok(Arc::new(
+        ShardingCodecBuilder::new(inner_chunk_shape.into())
+            .bytes_to_bytes_codecs(codecs)
+            .build(),
+    ))
+}
+
+/// Create and store an empty sample array at `path`
+///
+/// `events` selects the 1-D marker layout (one string per sample); otherwise the
+/// array is `[channels, samples]`.
+pub(crate) fn create_sample_array(
+    store: &Arc<FilesystemStore>,
+    path: &str,
+    channel_format: &str,
+    channels: usize,
+    events: bool,
+    storage: &StorageOptions,
+) -> Result<Array<FilesystemStore>> {
+    let dtype = zarr_dtype(channel_format)?;
+    // Outer chunk along the sample axis: one shard when sharding, one chunk otherwise
+    let outer_chunk_samples = storage.shard_samples.unwrap_or(storage.chunk_samples);
+
+    // Select shuffle mode based on data type for optimal compression
+    // BitShuffle: best for floating-point (EMG/EEG signals)
+    // Shuffle: best for integers
+    let shuffle_mode = match channel_format {
+        "Float32" | "Double64" => BloscShuffleMode::BitShuffle,
+        "Int32" | "Int16" | "Int8" => BloscShuffleMode::Shuffle,
+        _ => BloscShuffleMode::NoShuffle, // String (not compressed anyway)
+    };
+
+    // Get typesize for Blosc (required when shuffling is enabled)
+    let typesize = blosc_typesize(channel_format);
+
+    let codecs = bytes_to_bytes_codecs(storage, shuffle_mode, typesize)?;
+
+    // Select appropriate fill value and build array based on data type
+    let array = if events {
+        // Marker events: one string per sample
+        ArrayBuilder::new(
+            vec![0], // samples dimension is unlimited
+            vec![storage.chunk_samples],
+            dtype,
+            FillValue::from(""),
+        )
+        .dimension_names(Some(vec![Some("samples".to_string())]))
+        .build(store.clone(), path)?
+    } else if channel_format == "String" {
+        // String arrays: no compression or sharding, empty string fill value
+        ArrayBuilder::new(
+            vec![channels as u64, 0], // [channels, samples] - samples dimension is unlimited
+            vec![channels as u64, storage.chunk_samples], // chunk size: [channels, chunk_samples]
+            dtype,
+            FillValue::from(""),
+        )
+        .dimension_names(Some(vec![
+            Some("channels".to_string()),
+            Some("samples".to_string()),
+        ]))
+        .build(store.clone(), path)?
+    } else {
+        // Numeric arrays: with Blosc compression
+        let mut builder = ArrayBuilder::new(
+            vec![channels as u64, 0], // [channels, samples] - samples dimension is unlimited
+            vec![channels as u64, outer_chunk_samples], // [channels, shard or chunk samples]
+            dtype,
+            FillValue::from(0.0f32),
+        );
+        builder.dimension_names(Some(vec![
+            Some("channels".to_string()),
+            Some("samples".to_string()),
+        ]));
+        if storage.shard_samples.is_some() {
+            builder.array_to_bytes_codec(sharding_codec(&[channels as u64, storage.chunk_samples], codecs)?);
+        } else {
+            builder.bytes_to_bytes_codecs(codecs);
+        }
+        builder.build(store.clone(), path)?
+    };
+
+    array.store_metadata()?;
+    Ok(array)
+}
+
+/// Create and store an empty time array at `path`
+pub(crate) fn create_time_array(
+    store: &Arc<FilesystemStore>,
+    path: &str,
+    storage: &StorageOptions,
+) -> Result<Array<FilesystemStore>> {
+    let outer_chunk_samples = storage.shard_samples.unwrap_or(storage.chunk_samples);
+
+    // BitShuffle for optimal float64 timestamp compression
+    let codecs = bytes_to_bytes_codecs(storage, BloscShuffleMode::BitShuffle, Some(8))?;
+
+    let mut builder = ArrayBuilder::new(
+        vec![0], // unlimited dimension
+        vec![outer_chunk_samples], // shard or chunk samples
+        DataType::Float64,
+        FillValue::from(0.0f64),
+    );
+    builder.dimension_names(Some(vec![Some("samples".to_string())]));
+    if storage.shard_samples.is_some() {
+        builder.array_to_bytes_codec(sharding_codec(&[storage.chunk_samples], codecs)?);
+    } else {
+        builder.bytes_to_bytes_codecs(codecs);
+    }
+    let array = builder.build(store.clone(), path)?;
+
+    array.store_metadata()?;
+
+    // Note: Array-level attributes are not set via API in zarr-rs
+    // Time array description is self-evident from the array name
+
+    Ok(array)
+}
+
+/// Create the per-sample `flags` array of a stream (`--sample-flags`), chunked like its time array
+#[cfg(feature = "lsl")]
+pub(crate) fn create_flags_array(
+    store: &Arc<FilesystemStore>,
+    path: &str,
+    storage: &StorageOptions,
+) -> Result<Array<FilesystemStore>> {
+    let outer_chunk_samples = storage.shard_samples.unwrap_or(storage.chunk_samples);
+    let codecs = bytes_to_bytes_codecs(storage, BloscShuffleMode::NoShuffle, Some(1))?;
+
+    let mut attributes = serde_json::Map::new();
+    attributes.insert("bits".to_string(), crate::flags::bits_attribute());
+    let mut builder = ArrayBuilder::new(
+        vec![0], // unlimited dimension
+        vec![outer_chunk_samples],
+        DataType::UInt8,
+        FillValue::from(0u8),
+    );
+    builder
+        .dimension_names(Some(vec![Some("samples".to_string())]))
+        .attributes(attributes);
+    if storage.shard_samples.is_some() {
+        builder.array_to_bytes_codec(sharding_codec(&[storage.chunk_samples], codecs)?);
+    } else {
+        builder.bytes_to_bytes_codecs(codecs);
+    }
+    let array = builder.build(store.clone(), path)?;
+    array.store_metadata()?;
+    Ok(array)
+}
@@ -0,0 +1,407 @@
+//! Materialized synchronized streams and retirement of the raw arrays
+//!
+//! `lsl-sync` only records trim indices and writes `aligned_time` next to the raw
+//! arrays. With `--materialize` it also writes the trimmed samples with their aligned
+//! timestamps to a `synced` subgroup of each stream (`synced/data` or `synced/events`,
+//! `synced/time`, `synced/time_index`), which analysis code can read without applying
+//! the indices itself.
+//!
+//! Keeping both copies doubles the storage. With a [`RawRetention`] other than `Keep`,
+//! the synced copy is read back and compared sample by sample with the raw arrays;
+//! only if it matches are the raw `data`/`events`, `time`, `time_index` and
+//! `aligned_time` arrays deleted or moved to an archive directory, and the synced
+//! arrays take their place. Every tool keeps working on the stream, whose `time` now
+//! holds aligned timestamps. The stream's `raw_retention` attribute records what was
+//! done: the time origin subtracted from the LSL timestamps, the trimmed sample range,
+//! the original sample count and timestamp range, and the archive location.
+//!
+//! Archives carry an `archive.json` manifest; with a retention period its
+//! `expires_at` lets [`purge_expired_archives`] remove them once they are no longer
+//! needed.
+
+use anyhow::{Context, Result};
+use clap::ValueEnum;
+use ndarray::{Array1, Array2, Ix1, Ix2};
+use serde_json::json;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use zarrs::array::Array;
+use zarrs::filesystem::FilesystemStore;
+use zarrs::group::GroupBuilder;
+
+use super::layout::{create_sample_array, create_time_array};
+use super::time_index::{open_or_create_index_array, TimeIndex};
+use super::{
+    read_group_attributes, sample_array_path, Compressor, RecorderConfig, StorageOptions, N_SAMPLES_ATTRIBUTE,
+};
+use crate::export::{ExportStream, SampleBlock, EXPORT_BLOCK_SAMPLES};
+
+/// Subgroup of a stream holding its materialized synchronized arrays
+pub const SYNCED_GROUP: &str = "synced";
+
+/// Stream group attribute describing retired raw arrays
+pub const RAW_RETENTION_ATTRIBUTE: &str = "raw_retention";
+
+/// Manifest file written into every archive directory
+pub const ARCHIVE_MANIFEST: &str = "archive.json";
+
+/// Arrays replaced by the synced copy
+const RAW_ARRAYS: [&str; 5] = ["data", "events", "time", "time_index", "aligned_time"];
+
+/// What happens to the raw arrays once the synced copy is verified
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum RawRetention {
+    /// Keep the raw arrays next to the synced copy
+    Keep,
+    /// Delete the raw arrays
+    Drop,
+    /// Move the raw arrays to an archive directory
+    Archive,
+}
+
+impl std::fmt::Display for RawRetention {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RawRetention::Keep => write!(f, "keep"),
+            RawRetention::Drop => write!(f, "drop"),
+            RawRetention::Archive => write!(f, "archive"),
+        }
+    }
+}
+
+/// Storage layout recorded in a stream's `recorder_config` (defaults for missing fields)
+pub fn storage_from_attributes(attributes: &serde_json::Value) -> StorageOptions {
+    let config = RecorderConfig::from_attributes(attributes).unwrap_or_default();
+    let defaults = StorageOptions::default();
+    StorageOptions {
+        chunk_samples: config.chunk_samples.unwrap_or(defaults.chunk_samples),
+        compressor: config
+            .compressor
+            .and_then(|s| Compressor::from_str(&s, true).ok())
+            .unwrap_or(defaults.compressor),
+        compression_level: config
+            .compression_level
+            .map(|level| level.min(9) as u8)
+            .unwrap_or(defaults.compression_level),
+        shard_samples: config.shard_samples,
+    }
+}
+
+/// Store `count` samples in sample-major order into a sample array at `start`
+fn store_samples(array: &Array<FilesystemStore>, start: u64, count: usize, channels: usize, values: SampleBlock) -> Result<()> {
+    macro_rules! store_block {
+        ($values:expr, $ty:ty) => {{
+            // Sample-major -> [channels, samples]
+            let block = Array2::from_shape_vec((count, channels), $values)?
+                .reversed_axes()
+                .as_standard_layout()
+                .into_owned();
+            array.store_array_subset_ndarray::<$ty, Ix2>(&[0, start], block)?;
+        }};
+    }
+
+    match values {
+        SampleBlock::String(v) if array.shape().len() == 1 => {
+            array.store_array_subset_ndarray::<String, Ix1>(&[start], Array1::from_vec(v))?;
+        }
+        SampleBlock::Float32(v) => store_block!(v, f32),
+        SampleBlock::Float64(v) => store_block!(v, f64),
+        SampleBlock::Int32(v) => store_block!(v, i32),
+        SampleBlock::Int16(v) => store_block!(v, i16),
+        SampleBlock::Int8(v) => store_block!(v, i8),
+        SampleBlock::String(v) => store_block!(v, String),
+    }
+    Ok(())
+}
+
+/// Write samples `range` of a stream, with `origin` subtracted from the timestamps,
+/// to its `synced` subgroup (replacing an earlier one)
+///
+/// The arrays get the chunk layout and compression the stream was recorded with.
+/// Returns the number of samples written.
+pub fn materialize_stream(store_path: &Path, stream_name: &str, range: Range<u64>, origin: f64) -> Result<u64> {
+    let store = Arc::new(FilesystemStore::new(store_path)?);
+    let source = ExportStream::open(&store, stream_name, false)?;
+    let range = range.start.min(source.sample_count)..range.end.min(source.sample_count);
+    let storage = storage_from_attributes(&source.attributes);
+    storage.validate()?;
+
+    let synced_dir = store_path.join(stream_name).join(SYNCED_GROUP);
+    if synced_dir.exists() {
+        std::fs::remove_dir_all(&synced_dir)?;
+    }
+    // The synced group carries the stream info so it can be read like a stream
+    let base = format!("{}/{}", stream_name, SYNCED_GROUP);
+    let mut attributes = serde_json::Map::new();
+    attributes.insert("stream_info".to_string(), source.stream_info.clone());
+    attributes.insert(N_SAMPLES_ATTRIBUTE.to_string(), json!(range.end - range.start));
+    GroupBuilder::new()
+        .attributes(attributes)
+        .build(store.clone(), &format!("/{}", base))?
+        .store_metadata()?;
+
+    let events = sample_array_path(&store, stream_name).ends_with("/events");
+    let data_name = if events { "events" } else { "data" };
+    let mut data = create_sample_array(
+        &store,
+        &format!("/{}/{}", base, data_name),
+        &source.channel_format,
+        source.channel_count,
+        events,
+        &storage,
+    )?;
+    let mut time = create_time_array(&store, &format!("/{}/time", base), &storage)?;
+    let outer_chunk_samples = storage.shard_samples.unwrap_or(storage.chunk_samples);
+    let mut index_array = open_or_create_index_array(&store, &base, outer_chunk_samples)?;
+    let mut index = TimeIndex::new(outer_chunk_samples);
+
+    let total = range.end - range.start;
+    if events {
+        data.set_shape(vec![total])?;
+    } else {
+        data.set_shape(vec![source.channel_count as u64, total])?;
+    }
+    time.set_shape(vec![total])?;
+
+    // Blocks aligned to the outer chunks of the new arrays, so each chunk is written once
+    let block_samples = EXPORT_BLOCK_SAMPLES.div_ceil(outer_chunk_samples) * outer_chunk_samples;
+    let mut written = 0;
+    while written < total {
+        let len = block_samples.min(total - written);
+        let (timestamps, values) = source.read_block(range.start + written, len)?;
+        let aligned: Vec<f64> = timestamps.iter().map(|&t| t - origin).collect();
+        let first_row = index.append(aligned.iter().copied());
+        time.store_array_subset_ndarray::<f64, Ix1>(&[written], Array1::from_vec(aligned))?;
+        store_samples(&data, written, len as usize, source.channel_count, values)?;
+        index.store_rows(&mut index_array, first_row)?;
+        written += len;
+    }
+
+    data.store_metadata()?;
+    time.store_metadata()?;
+    index_array.store_metadata()?;
+    Ok(total)
+}
+
+/// Whether two blocks hold identical samples (NaNs compare equal to themselves)
+fn same_values(a: &SampleBlock, b: &SampleBlock) -> bool {
+    match (a, b) {
+        (SampleBlock::Float32(a), SampleBlock::Float32(b)) => {
+            a.iter().map(|x| x.to_bits()).eq(b.iter().map(|x| x.to_bits()))
+        }
+        (SampleBlock::Float64(a), SampleBlock::Float64(b)) => {
+            a.iter().map(|x| x.to_bits()).eq(b.iter().map(|x| x.to_bits()))
+        }
+        _ => a == b,
+    }
+}
+
+/// Read the synced copy back and compare it with samples `range` of the raw arrays
+pub fn verify_materialized(store_path: &Path, stream_name: &str, range: Range<u64>, origin: f64) -> Result<()> {
+    let store = Arc::new(FilesystemStore::new(store_path)?);
+    let raw = ExportStream::open(&store, stream_name, false)?;
+    let synced = ExportStream::open(&store, &format!("{}/{}", stream_name, SYNCED_GROUP), false)
+        .context("Synced copy is missing")?;
+
+    let expected = range.end.min(raw.sample_count) - range.start.min(raw.sample_count);
+    if synced.sample_count != expected {
+        anyhow::bail!("synced copy has {} samples, expected {}", synced.sample_count, expected);
+    }
+
+    let mut checked = 0;
+    while checked < expected {
+        let len = EXPORT_BLOCK_SAMPLES.min(expected - checked);
+        let (raw_times, raw_values) = raw.read_block(range.start + checked, len)?;
+        let (synced_times, synced_values) = synced.read_block(checked, len)?;
+        if !same_values(&raw_values, &synced_values) {
+            anyhow::bail!("sample values differ in samples {}..{}", checked, checked + len);
+        }
+        if let Some(i) = raw_times
+            .iter()
+            .zip(&synced_times)
+            .position(|(&raw, &synced)| raw - origin != synced)
+        {
+            anyhow::bail!("timestamp of sample {} differs", checked + i as u64);
+        }
+        checked += len;
+    }
+    Ok(())
+}
+
+/// Move a directory, copying when a rename is not possible (e.g. across file systems)
+fn move_dir(from: &Path, to: &Path) -> Result<()> {
+    if std::fs::rename(from, to).is_ok() {
+        return Ok(());
+    }
+    copy_dir(from, to)?;
+    std::fs::remove_dir_all(from)?;
+    Ok(())
+}
+
+fn copy_dir(from: &Path, to: &Path) -> Result<()> {
+    std::fs::create_dir_all(to)?;
+    for entry in std::fs::read_dir(from)? {
+        let entry = entry?;
+        let target = to.join(entry.file_name());
+        if entry.file_type()?.is_dir() {
+            copy_dir(&entry.path(), &target)?;
+        } else {
+            std::fs::copy(entry.path(), &target)?;
+        }
+    }
+    Ok(())
+}
+
+/// Replace a stream's raw arrays by its verified synced copy
+///
+/// `range` and `origin` are those passed to [`materialize_stream`]. With
+/// [`RawRetention::Archive`] the raw arrays are moved to
+/// `<archive_dir>/<store name>/<stream>/`, with a manifest that expires after
+/// `retain_days` if given. Returns the archive directory, if any.
+pub fn retire_raw_arrays(
+    store_path: &Path,
+    stream_name: &str,
+    range: Range<u64>,
+    origin: f64,
+    retention: RawRetention,
+    archive_dir: Option<&Path>,
+    retain_days: Option<u32>,
+) -> Result<Option<PathBuf>> {
+    if retention == RawRetention::Keep {
+        return Ok(None);
+    }
+    verify_materialized(store_path, stream_name, range.clone(), origin)
+        .with_context(|| format!("Verification of the synced copy of '{}' failed; raw arrays kept", stream_name))?;
+
+    let store = Arc::new(FilesystemStore::new(store_path)?);
+    let raw = ExportStream::open(&store, stream_name, false)?;
+    let raw_range = raw.timestamp_range()?;
+    let original_samples = raw.sample_count;
+    drop(raw);
+
+    let stream_dir = store_path.join(stream_name);
+    let now = chrono::Utc::now();
+    let expires_at = retain_days.map(|days| (now + chrono::Duration::days(days as i64)).to_rfc3339());
+
+    let archive = match retention {
+        RawRetention::Archive => {
+            let archive_dir = archive_dir.context("Archiving raw arrays needs an archive directory")?;
+            let store_name = store_path
+                .file_name()
+                .map(|n| n.to_string_lossy().to_string())
+                .unwrap_or_else(|| "store".to_string());
+            let target = archive_dir.join(store_name).join(stream_name);
+            if target.exists() {
+                anyhow::bail!("Archive {} already exists", target.display());
+            }
+            std::fs::create_dir_all(&target)?;
+            Some(target)
+        }
+        _ => None,
+    };
+
+    for name in RAW_ARRAYS {
+        let path = stream_dir.join(name);
+        if !path.exists() {
+            continue;
+        }
+        match archive {
+            Some(ref target) => move_dir(&path, &target.join(name))?,
+            None => std::fs::remove_dir_all(&path)?,
+        }
+    }
+
+    // The synced arrays take the place of the raw ones
+    let synced_dir = stream_dir.join(SYNCED_GROUP);
+    for entry in std::fs::read_dir(&synced_dir)? {
+        let entry = entry?;
+        if entry.file_type()?.is_dir() {
+            std::fs::rename(entry.path(), stream_dir.join(entry.file_name()))?;
+        }
+    }
+    std::fs::remove_dir_all(&synced_dir)?;
+
+    let provenance = json!({
+        "action": retention.to_string(),
+        "retired_at": now.to_rfc3339(),
+        "verified": true,
+        "time_origin": origin,
+        "trim_start_index": range.start,
+        "trim_end_index": range.end,
+        "original_sample_count": original_samples,
+        "original_first_timestamp": raw_range.map(|r| r.0),
+        "original_last_timestamp": raw_range.map(|r| r.1),
+        "archive_path": archive.as_ref().map(|p| p.display().to_string()),
+        "expires_at": expires_at,
+    });
+
+    if let Some(ref target) = archive {
+        let manifest = json!({
+            "store": store_path.display().to_string(),
+            "stream": stream_name,
+            "archived_at": now.to_rfc3339(),
+            "expires_at": expires_at,
+            "arrays": RAW_ARRAYS.iter().filter(|name| target.join(name).exists()).collect::<Vec<_>>(),
+        });
+        std::fs::write(target.join(ARCHIVE_MANIFEST), serde_json::to_string_pretty(&manifest)?)?;
+    }
+
+    // Timestamps in the stream are now relative to the origin; keep the anchors consistent
+    let attributes = read_group_attributes(&store, stream_name)?;
+    let mut group = zarrs::group::Group::open(store.clone(), &format!("/{}", stream_name))?;
+    let group_attributes = group.attributes_mut();
+    for key in ["start_time", "end_time"] {
+        if let Some(mut anchor) = attributes.get(key).cloned()
+            && let Some(clock) = anchor.get("lsl_clock").and_then(|v| v.as_f64())
+        {
+            anchor["lsl_clock"] = json!(clock - origin);
+            group_attributes.insert(key.to_string(), anchor);
+        }
+    }
+    for key in ["first_timestamp", "last_timestamp"] {
+        if let Some(timestamp) = attributes.get(key).and_then(|v| v.as_f64()) {
+            group_attributes.insert(key.to_string(), json!(timestamp - origin));
+        }
+    }
+    let samples = range.end.min(original_samples) - range.start.min(original_samples);
+    group_attributes.insert("trim_start_index".to_string(), json!(0));
+    group_attributes.insert("trim_end_index".to_string(), json!(samples));
+    group_attributes.insert("trimmed_sample_count".to_string(), json!(samples));
+    group_attributes.insert(N_SAMPLES_ATTRIBUTE.to_string(), json!(samples));
+    group_attributes.insert(RAW_RETENTION_ATTRIBUTE.to_string(), provenance);
+    group.store_metadata()?;
+
+    Ok(archive)
+}
+
+/// Delete archives below `archive_dir` whose manifest has expired; returns their paths
+pub fn purge_expired_archives(archive_dir: &Path) -> Result<Vec<PathBuf>> {
+    let mut purged = Vec::new();
+    let Ok(stores) = std::fs::read_dir(archive_dir) else {
+        return Ok(purged);
+    };
+    let now = chrono::Utc::now();
+    for store in stores.filter_map(|e| e.ok()) {
+        let Ok(streams) = std::fs::read_dir(store.path()) else {
+            continue;
+        };
+        for stream in streams.filter_map(|e| e.ok()) {
+            let path = stream.path();
+            let Ok(manifest) = std::fs::read_to_string(path.join(ARCHIVE_MANIFEST)) else {
+                continue;
+            };
+            let manifest: serde_json::Value = serde_json::from_str(&manifest)?;
+            let expired = manifest
+                .get("expires_at")
+                .and_then(|v| v.as_str())
+                .and_then(|s| chrono::DateTime::parse_from_rfc3339(s).ok())
+                .is_some_and(|expires| expires < now);
+            if expired {
+                std::fs::remove_dir_all(&path)?;
+                purged.push(path);
+            }
+        }
+    }
+    Ok(purged)
+}
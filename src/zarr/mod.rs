@@ -1,35 +1,181 @@
+pub mod attributes;
+pub mod clean;
+pub mod cache;
+pub mod http;
+pub(crate) mod layout;
+pub mod materialize;
+pub mod paths;
+pub mod recovery;
+pub mod remote;
+pub mod repair;
+pub mod time_index;
+#[cfg(feature = "lsl")]
+mod setup;
+#[cfg(feature = "lsl")]
 pub mod writer;
 
+pub use attributes::{get_alignment, get_recorder_config, get_stream_info, Alignment, RecorderConfig, StreamInfoAttribute};
+#[cfg(feature = "lsl")]
+pub use setup::{serialize_stream_info, setup_flags_array, setup_stream_arrays};
+
 use anyhow::Result;
 use fs2::FileExt;
 use serde_json::json;
 use std::fs::OpenOptions;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
 use std::time::Duration;
-use zarrs::array::{Array, ArrayBuilder, DataType, FillValue};
-use zarrs::array::codec::{BloscCodec, BloscCompressionLevel, BloscCompressor, BloscShuffleMode};
 use zarrs::filesystem::FilesystemStore;
 use zarrs::group::GroupBuilder;
 use zarrs::storage::{StoreKey, ReadableStorageTraits};
 
+use crate::manifest::write_manifest;
+
+/// Group holding store-level metadata such as the device table; never a stream
+pub const META_GROUP: &str = "meta";
+
+/// Group holding marker-triggered captures and their files; never a stream
+pub const ANNEX_GROUP: &str = "annex";
+
+/// Stream group attribute with the number of samples written, updated on every flush
+pub const N_SAMPLES_ATTRIBUTE: &str = "n_samples";
+
+/// Whether a top-level group of a store holds store-level data rather than a stream
+pub fn is_store_group(name: &str) -> bool {
+    name == META_GROUP || name == ANNEX_GROUP
+}
+
+/// Compression applied to newly created stream arrays
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum Compressor {
+    /// Blosc LZ4: fast, moderate ratio (default)
+    Lz4,
+    /// Blosc Zstandard: slower, better ratio for archival
+    Zstd,
+    /// No compression
+    None,
+}
+
+impl std::fmt::Display for Compressor {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Compressor::Lz4 => write!(f, "lz4"),
+            Compressor::Zstd => write!(f, "zstd"),
+            Compressor::None => write!(f, "none"),
+        }
+    }
+}
+
+/// What the Zarr group of a recorded stream is named after
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum GroupNaming {
+    /// `--stream-name`, or the source ID, name, type or predicate the stream was resolved by (default)
+    Name,
+    /// The LSL UID of the stream outlet, unique even for identical devices
+    Uid,
+}
+
+impl GroupNaming {
+    /// Group name of a stream named `name` whose outlet has the LSL UID `uid`
+    ///
+    /// UIDs are UUIDs; any character that is not filesystem-safe is replaced by `_`.
+    pub fn group_name(self, name: &str, uid: &str) -> Result<String> {
+        match self {
+            GroupNaming::Name => Ok(name.to_string()),
+            GroupNaming::Uid => {
+                let uid: String = uid
+                    .trim()
+                    .chars()
+                    .map(|c| if c.is_alphanumeric() || c == '-' || c == '_' { c } else { '_' })
+                    .collect();
+                if uid.is_empty() {
+                    anyhow::bail!("Stream '{}' has no UID to name its group after", name);
+                }
+                Ok(uid)
+            }
+        }
+    }
+}
+
+impl std::fmt::Display for GroupNaming {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            GroupNaming::Name => write!(f, "name"),
+            GroupNaming::Uid => write!(f, "uid"),
+        }
+    }
+}
+
+/// Chunk layout and compression for newly created stream arrays
+///
+/// Existing arrays keep the layout they were created with.
+#[derive(Debug, Clone)]
+pub struct StorageOptions {
+    /// Samples per chunk along the time axis
+    pub chunk_samples: u64,
+    pub compressor: Compressor,
+    /// Blosc compression level (0-9)
+    pub compression_level: u8,
+    /// Samples per shard along the time axis; `None` stores one file per chunk
+    ///
+    /// Must be a multiple of `chunk_samples`. Each shard becomes a single file
+    /// holding `shard_samples / chunk_samples` independently compressed chunks.
+    pub shard_samples: Option<u64>,
+}
+
+impl StorageOptions {
+    /// Check that the shard size is a whole number of chunks
+    pub fn validate(&self) -> Result<()> {
+        if self.chunk_samples == 0 {
+            anyhow::bail!("Chunk size must be at least 1 sample");
+        }
+        if let Some(shard) = self.shard_samples
+            && (shard == 0 || !shard.is_multiple_of(self.chunk_samples))
+        {
+            anyhow::bail!(
+                "Shard size ({} samples) must be a multiple of the chunk size ({} samples)",
+                shard,
+                self.chunk_samples
+            );
+        }
+        Ok(())
+    }
+}
+
+impl Default for StorageOptions {
+    fn default() -> Self {
+        Self {
+            chunk_samples: 100,
+            compressor: Compressor::Lz4,
+            compression_level: 5,
+            shard_samples: None,
+        }
+    }
+}
+
 /// Initialize or open Zarr store with base structure, handling concurrent access
+///
+/// Subject, session ID and notes go to the session manifest in the `meta` group
+/// (see [`crate::manifest`]).
 pub fn open_or_create_zarr_store(
     store_path: &Path,
-    _subject: Option<&str>,
-    _session_id: Option<&str>,
-    _notes: Option<&str>,
+    subject: Option<&str>,
+    session_id: Option<&str>,
+    notes: Option<&str>,
 ) -> Result<Arc<FilesystemStore>> {
     println!("Writing to Zarr store: {:?}", store_path);
 
+    // Chunk paths may exceed MAX_PATH on Windows (see paths::long_path)
+    let fs_path = paths::long_path(store_path);
+
     // Create the store directory if it doesn't exist
-    std::fs::create_dir_all(store_path)?;
+    std::fs::create_dir_all(&fs_path)?;
 
     // Create filesystem store
-    let store = Arc::new(FilesystemStore::new(store_path)?);
+    let store = Arc::new(FilesystemStore::new(&fs_path)?);
 
     // Use file locking to coordinate concurrent access during initialization
-    let lock_path = store_path.join(".zarr_init.lock");
+    let lock_path = fs_path.join(".zarr_init.lock");
     let lock_file = OpenOptions::new()
         .create(true)
         .write(true)
@@ -45,6 +191,7 @@ pub fn open_or_create_zarr_store(
         match initialize_store_structure(&store) {
             Ok(_) => {
                 lock_file.unlock()?;
+                write_manifest(store_path, subject, session_id, notes)?;
                 return Ok(store);
             }
             Err(e) => {
@@ -99,309 +246,165 @@ fn group_exists(store: &Arc<FilesystemStore>, path: &str) -> Result<bool> {
     }
 }
 
-/// Create a Zarr group if it doesn't exist
-fn create_group_if_not_exists(store: &Arc<FilesystemStore>, path: &str) -> Result<()> {
-    if !group_exists(store, path)? {
-        let group = GroupBuilder::new().build(store.clone(), path)?;
-        group.store_metadata()?;
-    }
-    Ok(())
-}
-
-
-/// Serialize LSL StreamInfo to JSON value
-fn serialize_stream_info(info: &mut lsl::StreamInfo) -> Result<serde_json::Value> {
-    // Get full XML representation and extract just the <desc> element
-    let full_xml = info.to_xml()
-        .map_err(|e| anyhow::anyhow!("Failed to serialize stream info XML: {}", e))?;
-
-    // Parse <desc>...</desc> content to JSON to avoid duplicating basic stream info
-    let description_json = parse_desc_to_json(&full_xml);
-
-    let stream_info_json = json!({
-        "type": info.stream_type(),
-        "source_id": info.source_id(),
-        "hostname": info.hostname(),
-        "channel_count": info.channel_count(),
-        "nominal_srate": info.nominal_srate(),
-        "channel_format": format!("{:?}", info.channel_format()),
-        "created_at": info.created_at(),
-        "uid": info.uid(),
-        "session_id": info.session_id(),
-        "version": info.version(),
-        "description": description_json
-    });
-
-    Ok(stream_info_json)
-}
-
-/// Parse the <desc> element from LSL XML to JSON using quick-xml
-fn parse_desc_to_json(xml: &str) -> serde_json::Value {
-    use quick_xml::events::Event;
-    use quick_xml::Reader;
-
-    let mut reader = Reader::from_str(xml);
-    reader.config_mut().trim_text(true);
-
-    let mut in_desc = false;
-    let mut depth = 0;
-    let mut desc_xml = Vec::new();
+/// Check if a Zarr array exists (Zarr v3 uses zarr.json with node_type)
+pub(crate) fn array_exists(store: &Arc<FilesystemStore>, path: &str) -> Result<bool> {
+    let trimmed_path = path.trim_end_matches('/').trim_start_matches('/');
+    let metadata_path = format!("{}/zarr.json", trimmed_path);
+    let metadata_key = StoreKey::new(&metadata_path)?;
 
-    loop {
-        match reader.read_event() {
-            Ok(Event::Start(e)) if e.name().as_ref() == b"desc" => {
-                in_desc = true;
-                depth = 1;
-            }
-            Ok(Event::Start(e)) if in_desc => {
-                depth += 1;
-                desc_xml.extend_from_slice(b"<");
-                desc_xml.extend_from_slice(e.name().as_ref());
-                desc_xml.extend_from_slice(b">");
-            }
-            Ok(Event::End(e)) if in_desc => {
-                depth -= 1;
-                if depth == 0 {
-                    // Finished reading desc element
-                    let desc_content = String::from_utf8_lossy(&desc_xml).to_string();
-                    return parse_xml_to_json(&desc_content);
-                }
-                desc_xml.extend_from_slice(b"</");
-                desc_xml.extend_from_slice(e.name().as_ref());
-                desc_xml.extend_from_slice(b">");
-            }
-            Ok(Event::Text(e)) if in_desc => {
-                desc_xml.extend_from_slice(&e);
-            }
-            Ok(Event::Empty(e)) if e.name().as_ref() == b"desc" => {
-                // Empty desc element
-                return serde_json::Value::Object(serde_json::Map::new());
-            }
-            Ok(Event::Eof) => break,
-            Err(e) => {
-                tracing::warn!("Error parsing LSL XML: {}", e);
-                break;
-            }
-            _ => {}
+    match store.get(&metadata_key) {
+        Ok(Some(data)) => {
+            // Parse JSON and check node_type
+            let json: serde_json::Value = serde_json::from_slice(&data)?;
+            Ok(json.get("node_type").and_then(|v| v.as_str()) == Some("array"))
         }
+        _ => Ok(false),
     }
-
-    serde_json::Value::Object(serde_json::Map::new())
 }
 
-/// Parse XML string to JSON recursively using quick-xml
-fn parse_xml_to_json(xml: &str) -> serde_json::Value {
-    use quick_xml::events::Event;
-    use quick_xml::Reader;
-
-    let mut result = serde_json::Map::new();
-    let mut reader = Reader::from_str(xml);
-    reader.config_mut().trim_text(true);
-
-    let mut current_tag = String::new();
-    let mut current_text = String::new();
-
-    loop {
-        match reader.read_event() {
-            Ok(Event::Start(e)) => {
-                current_tag = String::from_utf8_lossy(e.name().as_ref()).to_string();
-                current_text.clear();
-            }
-            Ok(Event::Text(e)) => {
-                if let Ok(text) = e.unescape() {
-                    current_text.push_str(&text);
-                }
-            }
-            Ok(Event::End(_)) => {
-                if !current_tag.is_empty() {
-                    result.insert(current_tag.clone(), serde_json::Value::String(current_text.clone()));
-                    current_tag.clear();
-                    current_text.clear();
-                }
-            }
-            Ok(Event::Empty(e)) => {
-                let tag = String::from_utf8_lossy(e.name().as_ref()).to_string();
-                result.insert(tag, serde_json::Value::String(String::new()));
-            }
-            Ok(Event::Eof) => break,
-            Err(e) => {
-                tracing::warn!("Error parsing XML element: {}", e);
-                break;
-            }
-            _ => {}
-        }
+/// Path of the array holding a stream's samples
+///
+/// Single-channel string (marker) streams are stored in a 1-D `events` array;
+/// everything else in the 2-D `data` array (`[channels, samples]`).
+pub fn sample_array_path(store: &Arc<FilesystemStore>, stream_name: &str) -> String {
+    let stream_path = format!("/{}", stream_name.trim_start_matches('/'));
+    let events_path = format!("{}/events", stream_path);
+    if array_exists(store, &events_path).unwrap_or(false) {
+        events_path
+    } else {
+        format!("{}/data", stream_path)
     }
-
-    serde_json::Value::Object(result)
-}
-
-/// Parse recorder config JSON string to serde_json::Value
-fn parse_recorder_config(recorder_config_json: &str) -> Result<serde_json::Value> {
-    let config: serde_json::Value = serde_json::from_str(recorder_config_json)?;
-    Ok(config)
 }
 
-/// Get dtype for Zarr array based on LSL channel format
-fn get_zarr_dtype(channel_format: lsl::ChannelFormat) -> Result<DataType> {
-    match channel_format {
-        lsl::ChannelFormat::Float32 => Ok(DataType::Float32),
-        lsl::ChannelFormat::Double64 => Ok(DataType::Float64),
-        lsl::ChannelFormat::Int32 => Ok(DataType::Int32),
-        lsl::ChannelFormat::Int16 => Ok(DataType::Int16),
-        lsl::ChannelFormat::Int8 => Ok(DataType::Int8),
-        lsl::ChannelFormat::String => Ok(DataType::String),
-        _ => Err(anyhow::anyhow!(
-            "Unsupported channel format for Zarr: {:?}",
-            channel_format
-        )),
-    }
+/// Samples of a stream whose time array holds `length` entries, given its group attributes
+///
+/// The `n_samples` attribute counts what the recorder actually wrote, so the count
+/// never includes chunk padding. Streams without it (older recorders, imports)
+/// count the whole time array.
+pub fn stream_sample_count(attributes: &serde_json::Value, length: u64) -> u64 {
+    attributes
+        .get(N_SAMPLES_ATTRIBUTE)
+        .and_then(|v| v.as_u64())
+        .map_or(length, |samples| samples.min(length))
 }
 
-/// Get typesize for Blosc compression based on LSL channel format
-fn get_blosc_typesize(channel_format: lsl::ChannelFormat) -> Option<usize> {
-    match channel_format {
-        lsl::ChannelFormat::Float32 => Some(4),  // 4 bytes
-        lsl::ChannelFormat::Double64 => Some(8),  // 8 bytes
-        lsl::ChannelFormat::Int32 => Some(4),  // 4 bytes
-        lsl::ChannelFormat::Int16 => Some(2),  // 2 bytes
-        lsl::ChannelFormat::Int8 => Some(1),   // 1 byte
-        _ => None,  // String or unsupported
-    }
+/// Expand `{date}`, `{time}`, `{subject}` and `{session_id}` placeholders in an output path
+///
+/// Date and time use the local timezone (`2025-01-31`, `143005`). Missing subject or
+/// session values expand to `unknown`.
+pub fn expand_output_template(
+    output: &Path,
+    subject: Option<&str>,
+    session_id: Option<&str>,
+) -> PathBuf {
+    let now = chrono::Local::now();
+    let expanded = output
+        .to_string_lossy()
+        .replace("{date}", &now.format("%Y-%m-%d").to_string())
+        .replace("{time}", &now.format("%H%M%S").to_string())
+        .replace("{subject}", subject.unwrap_or("unknown"))
+        .replace("{session_id}", session_id.unwrap_or("unknown"));
+    PathBuf::from(expanded)
 }
 
-/// Setup stream arrays (data and time) in the Zarr store
-pub fn setup_stream_arrays(
-    store: &Arc<FilesystemStore>,
-    stream_name: &str,
-    info: &mut lsl::StreamInfo,
-    channel_format: lsl::ChannelFormat,
-    recorder_config_json: &str,
-    time_correction: f64,
-    first_timestamp: Option<f64>,
-) -> Result<(Array<FilesystemStore>, Array<FilesystemStore>)> {
-    // Create stream group (use absolute path with /)
-    let stream_path = format!("/{}", stream_name);
-    create_group_if_not_exists(store, &stream_path)?;
-
-    // Prepare sync metadata (will be added to stream group attributes)
-    let mut sync_attrs = serde_json::Map::new();
-    sync_attrs.insert("lsl_clock_offset".to_string(), json!(time_correction));
-    sync_attrs.insert("recorded_at".to_string(), json!(chrono::Utc::now().to_rfc3339()));
-    if let Some(first_ts) = first_timestamp {
-        sync_attrs.insert("first_timestamp".to_string(), json!(first_ts));
+/// Refuse to write into an existing store that belongs to a different subject or session
+///
+/// Compares `subject` and `session_id` against the `recorder_config` of every stream
+/// already in the store. Missing stores and stores without streams always pass.
+pub fn check_session_collision(
+    store_path: &Path,
+    subject: Option<&str>,
+    session_id: Option<&str>,
+) -> Result<()> {
+    if !store_path.join("zarr.json").exists() {
+        return Ok(());
     }
 
-    // Create or get data array (use absolute path with /)
-    let data_path = format!("{}/data", stream_path);
-    let data_array = if array_exists(store, &data_path)? {
-        Array::open(store.clone(), &data_path)?
-    } else {
-        let channels = info.channel_count() as usize;
-        let dtype = get_zarr_dtype(channel_format)?;
-
-        // Select shuffle mode based on data type for optimal compression
-        // BitShuffle: best for floating-point (EMG/EEG signals)
-        // Shuffle: best for integers
-        let shuffle_mode = match channel_format {
-            lsl::ChannelFormat::Float32 | lsl::ChannelFormat::Double64 => BloscShuffleMode::BitShuffle,
-            lsl::ChannelFormat::Int32 | lsl::ChannelFormat::Int16 | lsl::ChannelFormat::Int8 => BloscShuffleMode::Shuffle,
-            _ => BloscShuffleMode::NoShuffle, // String (not compressed anyway)
+    let store = Arc::new(FilesystemStore::new(store_path)?);
+    for entry in std::fs::read_dir(store_path)? {
+        let entry = entry?;
+        if !entry.file_type()?.is_dir() {
+            continue;
+        }
+        let stream_name = entry.file_name().to_string_lossy().to_string();
+        let Ok(attrs) = read_group_attributes(&store, &stream_name) else {
+            continue;
         };
-
-        // Get typesize for Blosc (required when shuffling is enabled)
-        let typesize = get_blosc_typesize(channel_format);
-
-        // Create Blosc codec with LZ4 compression (not used for String type)
-        let compression_level = BloscCompressionLevel::try_from(5u8)
-            .map_err(|e| anyhow::anyhow!("Invalid compression level: {}", e))?;
-        let blosc_codec = Arc::new(BloscCodec::new(
-            BloscCompressor::LZ4,
-            compression_level,
-            None,  // blocksize (auto-detect)
-            shuffle_mode,
-            typesize,  // typesize required for shuffling
-        )?);
-
-        // Select appropriate fill value and build array based on data type
-        let array = if matches!(channel_format, lsl::ChannelFormat::String) {
-            // String arrays: no compression, empty string fill value
-            ArrayBuilder::new(
-                vec![channels as u64, 0], // [channels, samples] - samples dimension is unlimited
-                vec![channels as u64, 100], // chunk size: [channels, 100 samples]
-                dtype,
-                FillValue::from(""),
-            )
-            .dimension_names(Some(vec![
-                Some("channels".to_string()),
-                Some("samples".to_string()),
-            ]))
-            .build(store.clone(), &data_path)?
-        } else {
-            // Numeric arrays: with Blosc compression
-            ArrayBuilder::new(
-                vec![channels as u64, 0], // [channels, samples] - samples dimension is unlimited
-                vec![channels as u64, 100], // chunk size: [channels, 100 samples]
-                dtype,
-                FillValue::from(0.0f32),
-            )
-            .dimension_names(Some(vec![
-                Some("channels".to_string()),
-                Some("samples".to_string()),
-            ]))
-            .bytes_to_bytes_codecs(vec![blosc_codec])
-            .build(store.clone(), &data_path)?
+        let Some(config) = attrs.get("recorder_config") else {
+            continue;
         };
 
-        array.store_metadata()?;
-
-        // Store metadata in the stream group instead of on the array
-        let mut stream_group = zarrs::group::Group::open(store.clone(), &stream_path)?;
-        let mut stream_attrs = serde_json::Map::new();
-        stream_attrs.insert("stream_info".to_string(), serialize_stream_info(info)?);
-        stream_attrs.insert("recorder_config".to_string(), parse_recorder_config(recorder_config_json)?);
-        // Add sync metadata to stream attributes
-        stream_attrs.extend(sync_attrs);
-        stream_group.attributes_mut().extend(stream_attrs);
-        stream_group.store_metadata()?;
+        let existing_subject = config.get("subject").and_then(|v| v.as_str());
+        let existing_session = config.get("session_id").and_then(|v| v.as_str());
+        if existing_subject != subject || existing_session != session_id {
+            anyhow::bail!(
+                "Store {} already contains stream '{}' from subject {} / session {} \
+                 (this recording: subject {} / session {}). \
+                 Use a different --output (e.g. with {{date}}/{{time}} placeholders) or pass --append-session",
+                store_path.display(),
+                stream_name,
+                existing_subject.unwrap_or("<none>"),
+                existing_session.unwrap_or("<none>"),
+                subject.unwrap_or("<none>"),
+                session_id.unwrap_or("<none>"),
+            );
+        }
+    }
+    Ok(())
+}
 
-        array
-    };
+/// Mark a session as incomplete in the store's root group
+///
+/// Sets the root `session_status` attribute to `{"complete": false, ...}` with the
+/// stream that failed and why, so later analysis can tell an aborted session from
+/// a finished one.
+pub fn mark_session_incomplete(store_path: &Path, failed_stream: &str, reason: &str) -> Result<()> {
+    let store = Arc::new(FilesystemStore::new(store_path)?);
+    let lock_file = OpenOptions::new()
+        .create(true)
+        .write(true)
+        .truncate(false)
+        .open(store_path.join(".zarr_metadata.lock"))?;
+    lock_file.lock_exclusive()?;
+    let result = (|| -> Result<()> {
+        let mut root = zarrs::group::Group::open(store.clone(), "/")?;
+        root.attributes_mut().insert(
+            "session_status".to_string(),
+            json!({
+                "complete": false,
+                "failed_stream": failed_stream,
+                "reason": reason,
+                "stopped_at": chrono::Utc::now().to_rfc3339(),
+            }),
+        );
+        root.store_metadata()?;
+        Ok(())
+    })();
+    lock_file.unlock()?;
+    result
+}
 
-    // Create or get time array
-    let time_path = format!("{}/time", stream_path);
-    let time_array = if array_exists(store, &time_path)? {
-        Array::open(store.clone(), &time_path)?
-    } else {
-        // Create Blosc codec with BitShuffle for optimal float64 timestamp compression
-        let compression_level = BloscCompressionLevel::try_from(5u8)
-            .map_err(|e| anyhow::anyhow!("Invalid compression level: {}", e))?;
-        let blosc_codec = Arc::new(BloscCodec::new(
-            BloscCompressor::LZ4,
-            compression_level,
-            None,  // blocksize (auto-detect)
-            BloscShuffleMode::BitShuffle,  // BitShuffle for float64 timestamps
-            Some(8),  // typesize: 8 bytes for float64
-        )?);
-
-        let array = ArrayBuilder::new(
-            vec![0], // unlimited dimension
-            vec![100], // chunk size: 100 samples
-            DataType::Float64,
-            FillValue::from(0.0f64),
-        )
-        .dimension_names(Some(vec![Some("samples".to_string())]))
-        .bytes_to_bytes_codecs(vec![blosc_codec])
-        .build(store.clone(), &time_path)?;
-
-        array.store_metadata()?;
-
-        // Note: Array-level attributes are not set via API in zarr-rs
-        // Time array description is self-evident from the array name
-
-        array
-    };
+/// Current wall-clock time (UTC and local, RFC3339) paired with an LSL clock reading
+///
+/// The `lsl_clock` value anchors LSL timestamps to wall-clock time:
+/// `wall_time = utc + (timestamp - lsl_clock)`.
+pub fn time_anchor(lsl_clock: f64) -> serde_json::Value {
+    let now = chrono::Utc::now();
+    json!({
+        "utc": now.to_rfc3339_opts(chrono::SecondsFormat::Micros, true),
+        "local": now
+            .with_timezone(&chrono::Local)
+            .to_rfc3339_opts(chrono::SecondsFormat::Micros, false),
+        "lsl_clock": lsl_clock,
+    })
+}
 
-    Ok((data_array, time_array))
+/// Parse the UTC time of a `start_time`/`end_time` anchor written by [`time_anchor`]
+pub fn parse_time_anchor(anchor: &serde_json::Value) -> Option<chrono::DateTime<chrono::Utc>> {
+    let text = anchor.get("utc").and_then(|v| v.as_str())?;
+    chrono::DateTime::parse_from_rfc3339(text)
+        .ok()
+        .map(|t| t.with_timezone(&chrono::Utc))
 }
 
 /// Read attributes from a group's zarr.json file (Zarr v3 format)
@@ -423,20 +426,3 @@ pub fn read_group_attributes(store: &Arc<FilesystemStore>, path: &str) -> Result
         .cloned()
         .unwrap_or_else(|| json!({})))
 }
-
-/// Check if a Zarr array exists (Zarr v3 uses zarr.json with node_type)
-fn array_exists(store: &Arc<FilesystemStore>, path: &str) -> Result<bool> {
-    let trimmed_path = path.trim_end_matches('/').trim_start_matches('/');
-    let metadata_path = format!("{}/zarr.json", trimmed_path);
-    let metadata_key = StoreKey::new(&metadata_path)?;
-
-    match store.get(&metadata_key) {
-        Ok(Some(data)) => {
-            // Parse JSON and check node_type
-            let json: serde_json::Value = serde_json::from_slice(&data)?;
-            Ok(json.get("node_type").and_then(|v| v.as_str()) == Some("array"))
-        }
-        _ => Ok(false),
-    }
-}
-
@@ -0,0 +1,131 @@
+//! Path length limits of store files
+//!
+//! A chunk file lives at `<store>/<stream>/<array>/c/<channel chunk>/<chunk index>`.
+//! Windows limits paths to `MAX_PATH` (260 UTF-16 units including the terminating
+//! NUL) unless they are given in the verbatim `\\?\` form, and NTFS, ext4 and APFS
+//! limit every file and directory name to 255 units. A deep output directory plus
+//! a long or nested (`Lab/EMG/Left`) stream name passes setup and then fails at the
+//! first flush whose chunk path crosses the limit, with an error that does not name
+//! the path.
+//!
+//! [`check_stream_paths`] runs before a recording or import starts: names longer
+//! than [`MAX_NAME_LEN`] and paths longer than [`MAX_VERBATIM_PATH`] are errors.
+//! Paths longer than [`WINDOWS_MAX_PATH`] only warn: stores are opened through
+//! [`long_path`], which gives the `\\?\` form on Windows (and the path unchanged
+//! elsewhere), so the toolbox itself is not bound by `MAX_PATH`, but other software
+//! opening the store on Windows may be.
+
+use anyhow::Result;
+use std::path::{Component, Path, PathBuf};
+
+/// Longest path Windows software accepts without the `\\?\` prefix (`MAX_PATH` without the NUL)
+pub const WINDOWS_MAX_PATH: usize = 259;
+
+/// Longest path in the `\\?\` form
+pub const MAX_VERBATIM_PATH: usize = 32_767;
+
+/// Longest file or directory name
+pub const MAX_NAME_LEN: usize = 255;
+
+/// Digits reserved for chunk indices: 10^10 chunks are 30 years of 100-sample chunks at 1 kHz
+pub const CHUNK_INDEX_DIGITS: usize = 10;
+
+/// Longest chunk key below a stream group, `synced/time_index/c/<index>/0`, without the index
+const LONGEST_CHUNK_KEY: &str = "synced/time_index/c//0";
+
+/// Length of the longest chunk path of `stream_name` in `store_path`, in UTF-16 units
+///
+/// Counts the absolute path as Windows sees it, without the `\\?\` prefix.
+pub fn longest_chunk_path(store_path: &Path, stream_name: &str) -> usize {
+    let store = std::path::absolute(store_path).unwrap_or_else(|_| store_path.to_path_buf());
+    utf16_len(&store.to_string_lossy())
+        + 1
+        + utf16_len(stream_name)
+        + 1
+        + LONGEST_CHUNK_KEY.len()
+        + CHUNK_INDEX_DIGITS
+}
+
+/// Check that the files of a stream fit the path length limits before writing it
+///
+/// Fails for names or paths no file system accepts; returns a warning when the
+/// chunk paths exceed [`WINDOWS_MAX_PATH`].
+pub fn check_stream_paths(store_path: &Path, stream_name: &str) -> Result<Option<String>> {
+    let store = std::path::absolute(store_path).unwrap_or_else(|_| store_path.to_path_buf());
+    let store_names = store.components().filter_map(|component| match component {
+        Component::Normal(name) => Some(name.to_string_lossy().into_owned()),
+        _ => None,
+    });
+    for name in store_names.chain(stream_name.split('/').map(String::from)) {
+        // Bytes on Linux and macOS, UTF-16 units on Windows; bytes are never fewer
+        if name.len() > MAX_NAME_LEN {
+            anyhow::bail!(
+                "'{}' is {} bytes long; file and directory names are limited to {} (shorten the stream name or output path)",
+                name,
+                name.len(),
+                MAX_NAME_LEN
+            );
+        }
+    }
+
+    let longest = longest_chunk_path(store_path, stream_name);
+    if longest > MAX_VERBATIM_PATH {
+        anyhow::bail!(
+            "Chunk files of stream '{}' in {} would need {} characters, more than the {} any file system accepts",
+            stream_name,
+            store_path.display(),
+            longest,
+            MAX_VERBATIM_PATH
+        );
+    }
+    Ok((longest > WINDOWS_MAX_PATH).then(|| {
+        format!(
+            "chunk files of stream '{}' in {} reach {} characters, over the {} that Windows software without long \
+             path support can open; use a shorter output directory or stream name",
+            stream_name,
+            store_path.display(),
+            longest,
+            WINDOWS_MAX_PATH
+        )
+    }))
+}
+
+/// Path to open a store at: the `\\?\` form on Windows, lifting `MAX_PATH`
+#[cfg(windows)]
+pub fn long_path(path: &Path) -> PathBuf {
+    match std::path::absolute(path) {
+        Ok(absolute) => PathBuf::from(verbatim_path(&absolute.to_string_lossy())),
+        Err(_) => path.to_path_buf(),
+    }
+}
+
+/// Path to open a store at: the path itself outside Windows
+#[cfg(not(windows))]
+pub fn long_path(path: &Path) -> PathBuf {
+    path.to_path_buf()
+}
+
+/// `\\?\` form of an absolute Windows path
+///
+/// `C:\data` becomes `\\?\C:\data` and `\\server\share` becomes
+/// `\\?\UNC\server\share`. Verbatim paths reach the file system as they are, so
+/// `/` is replaced by `\`. Relative and already verbatim paths are returned
+/// unchanged.
+pub fn verbatim_path(path: &str) -> String {
+    if path.starts_with(r"\\?\") || path.starts_with(r"\\.\") {
+        return path.to_string();
+    }
+    let backslashed = path.replace('/', "\\");
+    if let Some(share) = backslashed.strip_prefix(r"\\") {
+        return format!(r"\\?\UNC\{}", share);
+    }
+    let bytes = backslashed.as_bytes();
+    if bytes.len() >= 3 && bytes[0].is_ascii_alphabetic() && bytes[1] == b':' && bytes[2] == b'\\' {
+        return format!(r"\\?\{}", backslashed);
+    }
+    path.to_string()
+}
+
+fn utf16_len(text: &str) -> usize {
+    text.encode_utf16().count()
+}
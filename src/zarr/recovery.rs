@@ -0,0 +1,121 @@
+//! Recovery markers for recordings whose final flush or finalization failed
+//!
+//! When the last flush or `finalize_recording_metadata` fails at the end of a
+//! recording (disk full, store moved or unmounted), the recorder still knows what it
+//! recorded. It writes that summary as a small JSON file outside the store, named
+//! `<store>.<stream>.recovery.json` and placed next to the store, or in the current
+//! directory or `<tmp>/lsl-recovery` if that fails. `lsl-repair` picks the marker up
+//! and restores the summary the recorder could not write (connection history, event
+//! counts, end time), then deletes it.
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use crate::connection::ConnectionStats;
+
+/// File name suffix of recovery markers
+pub const RECOVERY_SUFFIX: &str = ".recovery.json";
+
+/// Summary of a recording that could not be finalized
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RecoveryMarker {
+    /// Store the recording was written to
+    pub store: PathBuf,
+    pub stream: String,
+    /// Step that failed: `flush` or `finalize`
+    pub failed_step: String,
+    pub error: String,
+    /// Samples pulled from the stream while recording
+    pub samples_recorded: u64,
+    /// Samples written to the store before the failure
+    pub samples_written: u64,
+    /// Samples still buffered in memory, lost with the failed flush
+    pub unflushed_samples: u64,
+    pub first_timestamp: Option<f64>,
+    pub last_timestamp: Option<f64>,
+    /// `end_time` anchor the recorder would have stored
+    pub end_time: Option<Value>,
+    /// Occurrences per marker value (event streams only)
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub event_counts: BTreeMap<String, u64>,
+    pub connection: Option<ConnectionStats>,
+    pub written_at: String,
+    pub recorder_version: String,
+}
+
+/// Marker file name for a stream of a store, e.g. `experiment.zarr.EEG.recovery.json`
+pub fn marker_file_name(store_path: &Path, stream: &str) -> String {
+    let store_name = store_path.file_name().map(|n| n.to_string_lossy()).unwrap_or_default();
+    format!("{}.{}{}", store_name, stream, RECOVERY_SUFFIX)
+}
+
+/// Directories tried for a store's markers, in order: next to the store, the
+/// current directory, and `<tmp>/lsl-recovery`
+pub fn marker_dirs(store_path: &Path) -> Vec<PathBuf> {
+    let mut dirs = Vec::new();
+    if let Some(parent) = store_path.parent() {
+        dirs.push(if parent.as_os_str().is_empty() { PathBuf::from(".") } else { parent.to_path_buf() });
+    }
+    if let Ok(current) = std::env::current_dir() {
+        dirs.push(current);
+    }
+    dirs.push(std::env::temp_dir().join("lsl-recovery"));
+    dirs
+}
+
+/// Write a marker to the first directory that accepts it; returns its path
+pub fn write_recovery_marker(marker: &RecoveryMarker) -> Result<PathBuf> {
+    let contents = serde_json::to_string_pretty(marker)?;
+    let file_name = marker_file_name(&marker.store, &marker.stream);
+    let mut last_error = None;
+    for dir in marker_dirs(&marker.store) {
+        let path = dir.join(&file_name);
+        match std::fs::create_dir_all(&dir).and_then(|_| std::fs::write(&path, &contents)) {
+            Ok(()) => return Ok(path),
+            Err(e) => last_error = Some(anyhow::anyhow!("{}: {}", path.display(), e)),
+        }
+    }
+    Err(last_error
+        .unwrap_or_else(|| anyhow::anyhow!("no directory to write to"))
+        .context("Failed to write the recovery marker"))
+}
+
+/// Read a recovery marker file
+pub fn read_recovery_marker(path: &Path) -> Result<RecoveryMarker> {
+    let contents = std::fs::read(path).with_context(|| format!("Failed to read {}", path.display()))?;
+    serde_json::from_slice(&contents).with_context(|| format!("{} is not a recovery marker", path.display()))
+}
+
+/// Markers left for the streams of a store in any of its [`marker_dirs`]
+///
+/// The store may have been moved since, so markers are matched by store file name.
+pub fn find_recovery_markers(store_path: &Path) -> Vec<(PathBuf, RecoveryMarker)> {
+    let Some(store_name) = store_path.file_name().map(|n| format!("{}.", n.to_string_lossy())) else {
+        return Vec::new();
+    };
+    let mut markers: Vec<(PathBuf, RecoveryMarker)> = Vec::new();
+    for dir in marker_dirs(store_path) {
+        let Ok(entries) = std::fs::read_dir(&dir) else {
+            continue;
+        };
+        for path in entries.filter_map(|e| e.ok()).map(|e| e.path()) {
+            let name = path.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+            if !name.starts_with(&store_name) || !name.ends_with(RECOVERY_SUFFIX) {
+                continue;
+            }
+            // The same directory can be reached twice (e.g. the store is in the current directory)
+            let canonical = path.canonicalize().unwrap_or_else(|_| path.clone());
+            if markers.iter().any(|(p, _)| p.canonicalize().unwrap_or_else(|_| p.clone()) == canonical) {
+                continue;
+            }
+            if let Ok(marker) = read_recovery_marker(&path) {
+                markers.push((path, marker));
+            }
+        }
+    }
+    markers.sort_by(|a, b| a.0.cmp(&b.0));
+    markers
+}
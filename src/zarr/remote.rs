@@ -0,0 +1,313 @@
+//! Recording to object storage (S3, GCS)
+//!
+//! An `--output` of the form `s3://bucket/path` or `gs://bucket/path` records into a
+//! local spool directory with the regular filesystem store, and a [`RemoteUploader`]
+//! copies every file that changed to the bucket after each flush. Chunks are written
+//! once and stay small, so most uploads are single requests; files above
+//! [`MULTIPART_THRESHOLD`] (large shards) go up as multi-part uploads.
+//!
+//! Keeping the Zarr writing local means file locks, crash repair and the mirror check
+//! work unchanged, and a network outage only delays the upload: the next flush retries
+//! every file that is not yet in the bucket. Credentials come from the usual
+//! environment variables (`AWS_ACCESS_KEY_ID`, `AWS_REGION`, `GOOGLE_SERVICE_ACCOUNT`, ...).
+//!
+//! Uploading needs the `object-store` cargo feature.
+
+use std::path::{Path, PathBuf};
+
+/// URL schemes recognised as object storage
+const REMOTE_SCHEMES: [&str; 2] = ["s3://", "gs://"];
+
+/// Files at least this large are uploaded in parts
+pub const MULTIPART_THRESHOLD: u64 = 16 * 1024 * 1024;
+
+/// Part size of multi-part uploads
+pub const MULTIPART_PART_SIZE: usize = 8 * 1024 * 1024;
+
+/// Whether an output path is an object storage URL
+pub fn is_remote(path: &Path) -> bool {
+    let path = path.to_string_lossy();
+    REMOTE_SCHEMES.iter().any(|scheme| path.starts_with(scheme))
+}
+
+/// Local directory that holds the files of a remote store while recording
+///
+/// `s3://bucket/exp.zarr` becomes `<spool_dir>/s3/bucket/exp.zarr`, with the system
+/// temporary directory as default `spool_dir`. Recorders writing the same URL share
+/// the spool directory, like they share a local store.
+pub fn spool_path(url: &str, spool_dir: Option<&Path>) -> PathBuf {
+    let base = spool_dir
+        .map(Path::to_path_buf)
+        .unwrap_or_else(|| std::env::temp_dir().join("lsl-spool"));
+    let (scheme, rest) = url.split_once("://").unwrap_or(("remote", url));
+    rest.split('/')
+        .filter(|part| !part.is_empty() && *part != "." && *part != "..")
+        .fold(base.join(scheme), |path, part| path.join(part))
+}
+
+/// Local store path and remote URL for an output path
+///
+/// Local paths are returned unchanged with no URL.
+pub fn resolve_output(path: &Path, spool_dir: Option<&Path>) -> (PathBuf, Option<String>) {
+    if is_remote(path) {
+        let url = path.to_string_lossy().trim_end_matches('/').to_string();
+        (spool_path(&url, spool_dir), Some(url))
+    } else {
+        (path.to_path_buf(), None)
+    }
+}
+
+/// Files uploaded by a [`RemoteUploader`]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct UploadStats {
+    pub files: u64,
+    pub bytes: u64,
+    /// Uploads that used multi-part requests
+    pub multipart: u64,
+}
+
+#[cfg(feature = "object-store")]
+pub use uploader::RemoteUploader;
+
+#[cfg(feature = "object-store")]
+mod uploader {
+    use anyhow::{Context, Result};
+    use object_store::aws::AmazonS3Builder;
+    use object_store::gcp::GoogleCloudStorageBuilder;
+    use object_store::path::Path as ObjectPath;
+    use object_store::{ObjectStore, PutPayload, WriteMultipart};
+    use std::collections::HashMap;
+    use std::io::Read;
+    use std::path::{Path, PathBuf};
+    use std::sync::mpsc::{channel, Receiver, Sender};
+    use std::thread::JoinHandle;
+    use std::time::SystemTime;
+
+    use super::{UploadStats, MULTIPART_PART_SIZE, MULTIPART_THRESHOLD};
+
+    enum Request {
+        /// Upload changed files, replying with the outcome if a sender is given
+        Sync(Option<Sender<Result<UploadStats, String>>>),
+        Finish,
+    }
+
+    /// Background uploader from a local store directory to object storage
+    ///
+    /// Only the root and `meta` groups and the files of one stream group are uploaded, so
+    /// several recorders can share a store without uploading each other's files.
+    pub struct RemoteUploader {
+        url: String,
+        requests: Sender<Request>,
+        thread: Option<JoinHandle<()>>,
+    }
+
+    impl RemoteUploader {
+        /// Start uploading `local_root` (and within it the group `stream`, if given) to `url`
+        pub fn start(url: &str, local_root: &Path, stream: Option<&str>) -> Result<Self> {
+            let (scheme, rest) = url.split_once("://").unwrap_or_default();
+            let (bucket, prefix) = rest.split_once('/').unwrap_or((rest, ""));
+            let store: Box<dyn ObjectStore> = match scheme {
+                "s3" => Box::new(AmazonS3Builder::from_env().with_bucket_name(bucket).build()?),
+                "gs" => Box::new(GoogleCloudStorageBuilder::from_env().with_bucket_name(bucket).build()?),
+                other => anyhow::bail!("Unsupported object storage scheme '{}'", other),
+            };
+            let runtime = tokio::runtime::Builder::new_current_thread()
+                .enable_all()
+                .build()
+                .context("Failed to start the upload runtime")?;
+
+            let mut worker = Worker {
+                store,
+                runtime,
+                prefix: ObjectPath::from(prefix),
+                local_root: local_root.to_path_buf(),
+                stream: stream.map(String::from),
+                uploaded: HashMap::new(),
+                stats: UploadStats::default(),
+            };
+            let (requests, receiver) = channel();
+            let thread = std::thread::Builder::new()
+                .name("remote-upload".to_string())
+                .spawn(move || worker.run(receiver))?;
+
+            Ok(Self {
+                url: url.to_string(),
+                requests,
+                thread: Some(thread),
+            })
+        }
+
+        /// Remote URL of the store
+        pub fn url(&self) -> &str {
+            &self.url
+        }
+
+        /// Upload changed files in the background (coalesced with pending requests)
+        pub fn request_sync(&self) {
+            self.requests.send(Request::Sync(None)).ok();
+        }
+
+        /// Upload all changed files and wait until they are in the bucket
+        pub fn sync(&self) -> Result<UploadStats> {
+            let (reply, outcome) = channel();
+            self.requests
+                .send(Request::Sync(Some(reply)))
+                .map_err(|_| anyhow::anyhow!("Upload thread for {} has stopped", self.url))?;
+            outcome
+                .recv()
+                .map_err(|_| anyhow::anyhow!("Upload thread for {} has stopped", self.url))?
+                .map_err(|e| anyhow::anyhow!("Upload to {} failed: {}", self.url, e))
+        }
+    }
+
+    impl Drop for RemoteUploader {
+        fn drop(&mut self) {
+            self.requests.send(Request::Finish).ok();
+            if let Some(thread) = self.thread.take() {
+                thread.join().ok();
+            }
+        }
+    }
+
+    struct Worker {
+        store: Box<dyn ObjectStore>,
+        runtime: tokio::runtime::Runtime,
+        prefix: ObjectPath,
+        local_root: PathBuf,
+        stream: Option<String>,
+        // Size and modification time of each file as last uploaded
+        uploaded: HashMap<PathBuf, (u64, SystemTime)>,
+        stats: UploadStats,
+    }
+
+    impl Worker {
+        fn run(&mut self, receiver: Receiver<Request>) {
+            let mut warned = false;
+            while let Ok(request) = receiver.recv() {
+                // Requests that queued up during the last upload are served by one pass
+                let mut replies = Vec::new();
+                let mut finish = false;
+                for request in std::iter::once(request).chain(receiver.try_iter()) {
+                    match request {
+                        Request::Sync(Some(reply)) => replies.push(reply),
+                        Request::Sync(None) => {}
+                        Request::Finish => finish = true,
+                    }
+                }
+
+                let outcome = self.sync().map_err(|e| format!("{:#}", e));
+                match outcome {
+                    Err(ref e) if !warned && replies.is_empty() => {
+                        eprintln!("Warning: upload failed, retrying at the next flush: {}", e);
+                        warned = true;
+                    }
+                    Ok(_) => warned = false,
+                    _ => {}
+                }
+                for reply in replies {
+                    reply.send(outcome.clone()).ok();
+                }
+                if finish {
+                    break;
+                }
+            }
+        }
+
+        /// Upload every file that changed since its last upload
+        fn sync(&mut self) -> Result<UploadStats> {
+            let mut files = vec![self.local_root.join("zarr.json")];
+            collect_files(&self.local_root.join(crate::zarr::META_GROUP), &mut files)?;
+            if let Some(ref stream) = self.stream {
+                collect_files(&self.local_root.join(stream), &mut files)?;
+            }
+
+            for file in files {
+                let Ok(metadata) = std::fs::metadata(&file) else {
+                    continue;
+                };
+                let version = (metadata.len(), metadata.modified()?);
+                if self.uploaded.get(&file) == Some(&version) {
+                    continue;
+                }
+                let relative = file.strip_prefix(&self.local_root)?;
+                let key = relative
+                    .components()
+                    .fold(self.prefix.clone(), |key, part| key.child(part.as_os_str().to_string_lossy().as_ref()));
+                self.upload(&file, &key, metadata.len())
+                    .with_context(|| format!("Failed to upload {}", relative.display()))?;
+                self.uploaded.insert(file, version);
+            }
+            Ok(self.stats)
+        }
+
+        fn upload(&mut self, file: &Path, key: &ObjectPath, len: u64) -> Result<()> {
+            if len < MULTIPART_THRESHOLD {
+                let bytes = std::fs::read(file)?;
+                self.runtime.block_on(self.store.put(key, PutPayload::from(bytes)))?;
+            } else {
+                let mut reader = std::fs::File::open(file)?;
+                self.runtime.block_on(async {
+                    let upload = self.store.put_multipart(key).await?;
+                    let mut writer = WriteMultipart::new_with_chunk_size(upload, MULTIPART_PART_SIZE);
+                    let mut buffer = vec![0u8; MULTIPART_PART_SIZE];
+                    loop {
+                        let read = reader.read(&mut buffer)?;
+                        if read == 0 {
+                            break;
+                        }
+                        // Keep at most a few parts in flight
+                        writer.wait_for_capacity(4).await?;
+                        writer.write(&buffer[..read]);
+                    }
+                    writer.finish().await?;
+                    anyhow::Ok(())
+                })?;
+                self.stats.multipart += 1;
+            }
+            self.stats.files += 1;
+            self.stats.bytes += len;
+            Ok(())
+        }
+    }
+
+    /// All files below `dir`, skipping hidden files such as lock files
+    fn collect_files(dir: &Path, files: &mut Vec<PathBuf>) -> Result<()> {
+        let Ok(entries) = std::fs::read_dir(dir) else {
+            return Ok(());
+        };
+        for entry in entries {
+            let entry = entry?;
+            if entry.file_name().to_string_lossy().starts_with('.') {
+                continue;
+            }
+            let path = entry.path();
+            if entry.file_type()?.is_dir() {
+                collect_files(&path, files)?;
+            } else {
+                files.push(path);
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Placeholder used when the crate is built without the `object-store` feature
+#[cfg(not(feature = "object-store"))]
+pub struct RemoteUploader;
+
+#[cfg(not(feature = "object-store"))]
+impl RemoteUploader {
+    pub fn start(url: &str, _local_root: &Path, _stream: Option<&str>) -> anyhow::Result<Self> {
+        anyhow::bail!("Recording to {} requires the `object-store` feature", url)
+    }
+
+    pub fn url(&self) -> &str {
+        ""
+    }
+
+    pub fn request_sync(&self) {}
+
+    pub fn sync(&self) -> anyhow::Result<UploadStats> {
+        Ok(UploadStats::default())
+    }
+}
@@ -0,0 +1,294 @@
+//! Recovery of recordings interrupted by a crash or power loss
+//!
+//! The recorder writes chunk data before updating array metadata, and only writes
+//! `first_timestamp`/`last_timestamp` when a recording ends cleanly. An interruption can
+//! therefore leave a stream with:
+//!
+//! - chunks on disk beyond the recorded array shape (metadata never updated)
+//! - trailing fill values (`0.0` timestamps) where chunks were never written
+//! - data and time arrays of different lengths
+//! - no finalization metadata
+//!
+//! [`repair_store`] truncates each stream to its last valid timestamp, restores the
+//! finalization attributes and marks the stream with `recovered: true`. A
+//! [recovery marker](super::recovery) left by a recorder whose finalization failed
+//! adds what cannot be read back from the arrays: connection history, event counts
+//! and end time.
+
+use anyhow::Result;
+use serde_json::json;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use zarrs::array::Array;
+use zarrs::array_subset::ArraySubset;
+use zarrs::filesystem::FilesystemStore;
+
+use super::recovery::{find_recovery_markers, read_recovery_marker, RecoveryMarker};
+use super::time_index::{open_or_create_index_array, TimeIndex};
+use crate::connection::CONNECTION_ATTRIBUTE;
+use super::{read_group_attributes, sample_array_path, N_SAMPLES_ATTRIBUTE};
+
+/// Outcome of checking (and possibly repairing) one stream
+#[derive(Debug, Clone)]
+pub struct StreamRepair {
+    pub stream: String,
+    /// Samples according to the time array metadata before repair
+    pub samples_before: u64,
+    /// Samples with a valid timestamp present in both data and time arrays
+    pub samples_after: u64,
+    /// `last_timestamp` was missing (recording never finalized)
+    pub was_unfinalized: bool,
+    /// An `aligned_time` array exists and no longer matches the repaired length
+    pub stale_alignment: bool,
+    /// Recovery marker left for the stream by a failed finalization
+    pub marker: Option<PathBuf>,
+    /// Samples the recorder pulled according to the marker
+    pub samples_recorded: Option<u64>,
+}
+
+impl StreamRepair {
+    /// Whether the stream needed any change
+    pub fn needs_repair(&self) -> bool {
+        self.samples_before != self.samples_after || self.was_unfinalized || self.marker.is_some()
+    }
+}
+
+/// Read `shape` and the chunk length along the sample axis from an array's zarr.json
+pub(super) fn array_layout(array_dir: &Path, sample_axis: usize) -> Option<(Vec<u64>, u64)> {
+    let metadata: serde_json::Value =
+        serde_json::from_slice(&std::fs::read(array_dir.join("zarr.json")).ok()?).ok()?;
+    let shape = metadata
+        .get("shape")?
+        .as_array()?
+        .iter()
+        .filter_map(|v| v.as_u64())
+        .collect::<Vec<_>>();
+    let chunk_len = metadata
+        .pointer("/chunk_grid/configuration/chunk_shape")?
+        .as_array()?
+        .get(sample_axis)?
+        .as_u64()?;
+    Some((shape, chunk_len))
+}
+
+/// Highest chunk index along the sample axis that exists on disk
+///
+/// Chunk keys use the default `c/` encoding: `c/<k>` for 1-D and `c/<i>/<k>` for
+/// 2-D arrays, so the sample index is always the last path component.
+fn max_chunk_on_disk(dir: &Path) -> Option<u64> {
+    let mut max: Option<u64> = None;
+    for entry in std::fs::read_dir(dir).ok()?.filter_map(|e| e.ok()) {
+        let path = entry.path();
+        let index = if path.is_dir() {
+            max_chunk_on_disk(&path)
+        } else {
+            path.file_name().and_then(|n| n.to_str()).and_then(|n| n.parse::<u64>().ok())
+        };
+        max = max.max(index);
+    }
+    max
+}
+
+/// Number of samples an array could hold given the chunks present on disk
+fn extent_on_disk(array_dir: &Path, sample_axis: usize) -> Option<u64> {
+    let (shape, chunk_len) = array_layout(array_dir, sample_axis)?;
+    let on_disk = max_chunk_on_disk(&array_dir.join("c")).map(|k| (k + 1) * chunk_len).unwrap_or(0);
+    Some(shape.get(sample_axis).copied().unwrap_or(0).max(on_disk))
+}
+
+/// Check one stream and, unless `dry_run`, truncate and finalize it
+fn repair_stream(
+    store: &Arc<FilesystemStore>,
+    store_path: &Path,
+    stream_name: &str,
+    marker: Option<&(PathBuf, RecoveryMarker)>,
+    dry_run: bool,
+) -> Result<StreamRepair> {
+    let stream_dir = store_path.join(stream_name);
+    let time_path = format!("/{}/time", stream_name);
+    let data_path = sample_array_path(store, stream_name);
+
+    let mut time_array = Array::<FilesystemStore>::open(store.clone(), &time_path)?;
+    let samples_before = time_array.shape()[0];
+
+    // Look at everything that reached the disk, not just what the metadata admits to
+    let time_extent = extent_on_disk(&stream_dir.join("time"), 0).unwrap_or(samples_before);
+    time_array.set_shape(vec![time_extent])?;
+
+    let timestamps = if time_extent > 0 {
+        let subset = ArraySubset::new_with_start_shape(vec![0], vec![time_extent])?;
+        time_array
+            .retrieve_array_subset_ndarray::<f64>(&subset)?
+            .into_iter()
+            .collect::<Vec<_>>()
+    } else {
+        Vec::new()
+    };
+
+    // Trailing fill values (0.0) mark samples that were never written
+    let valid_time = timestamps
+        .iter()
+        .rposition(|&ts| ts != 0.0 && ts.is_finite())
+        .map(|i| i as u64 + 1)
+        .unwrap_or(0);
+
+    // Samples are the last axis: [channels, samples] for data, [samples] for events
+    let mut data_array = Array::<FilesystemStore>::open(store.clone(), &data_path)?;
+    let data_shape = data_array.shape().to_vec();
+    let sample_axis = data_shape.len() - 1;
+    let data_dir = stream_dir.join(data_path.rsplit('/').next().unwrap_or("data"));
+    let data_extent = extent_on_disk(&data_dir, sample_axis).unwrap_or(data_shape[sample_axis]);
+    let samples_after = valid_time.min(data_extent);
+
+    let attrs = read_group_attributes(store, stream_name)?;
+    let stored_last = attrs.get("last_timestamp").and_then(|v| v.as_f64());
+    let was_unfinalized = stored_last.is_none();
+    let stale_alignment = stream_dir.join("aligned_time").join("zarr.json").exists()
+        && array_layout(&stream_dir.join("aligned_time"), 0)
+            .map(|(shape, _)| shape.first().copied() != Some(samples_after))
+            .unwrap_or(true);
+
+    let report = StreamRepair {
+        stream: stream_name.to_string(),
+        samples_before,
+        samples_after,
+        was_unfinalized,
+        stale_alignment,
+        marker: marker.map(|(path, _)| path.clone()),
+        samples_recorded: marker.map(|(_, marker)| marker.samples_recorded),
+    };
+
+    if dry_run || !report.needs_repair() {
+        return Ok(report);
+    }
+
+    let mut repaired_shape = data_shape;
+    repaired_shape[sample_axis] = samples_after;
+    data_array.set_shape(repaired_shape)?;
+    data_array.store_metadata()?;
+    time_array.set_shape(vec![samples_after])?;
+    time_array.store_metadata()?;
+
+    // Rebuild the chunk timestamp index for the truncated time array
+    if let Some((_, chunk_samples)) = array_layout(&stream_dir.join("time"), 0) {
+        let chunk_samples = TimeIndex::load(store, stream_name)
+            .ok()
+            .flatten()
+            .map_or(chunk_samples, |index| index.chunk_samples);
+        let index = TimeIndex::build(&timestamps[..samples_after as usize], chunk_samples);
+        let mut index_array = open_or_create_index_array(store, stream_name, chunk_samples)?;
+        index.store_rows(&mut index_array, 0)?;
+        index_array.store_metadata()?;
+    }
+
+    let mut stream_group = zarrs::group::Group::open(store.clone(), &format!("/{}", stream_name))?;
+    let group_attrs = stream_group.attributes_mut();
+    if samples_after > 0 {
+        group_attrs.insert("first_timestamp".to_string(), json!(timestamps[0]));
+        group_attrs.insert(
+            "last_timestamp".to_string(),
+            json!(timestamps[samples_after as usize - 1]),
+        );
+    }
+    if sample_axis == 0 {
+        group_attrs.insert("event_count".to_string(), json!(samples_after));
+    }
+    group_attrs.insert(N_SAMPLES_ATTRIBUTE.to_string(), json!(samples_after));
+    group_attrs.insert("recovered".to_string(), json!(true));
+    let mut recovery = json!({
+        "samples_before": samples_before,
+        "samples_after": samples_after,
+        "was_unfinalized": was_unfinalized,
+        "repaired_at": chrono::Utc::now().to_rfc3339(),
+        "repair_version": env!("CARGO_PKG_VERSION"),
+    });
+    if let Some((path, marker)) = marker {
+        apply_marker(group_attrs, marker, stored_last, sample_axis == 0);
+        recovery["marker"] = json!({
+            "path": path.display().to_string(),
+            "failed_step": marker.failed_step,
+            "error": marker.error,
+            "samples_recorded": marker.samples_recorded,
+            "samples_written": marker.samples_written,
+            "unflushed_samples": marker.unflushed_samples,
+            "written_at": marker.written_at,
+        });
+    }
+    group_attrs.insert("recovery".to_string(), recovery);
+    stream_group.store_metadata()?;
+
+    // Its contents now live in the `recovery` attribute
+    if let Some((path, _)) = marker {
+        std::fs::remove_file(path)?;
+    }
+
+    Ok(report)
+}
+
+/// Restore the summary a recorder could not write from its recovery marker
+///
+/// Skipped if the recorder got as far as storing the marker's `last_timestamp`
+/// before the repair (e.g. only the upload failed), so nothing is counted twice.
+fn apply_marker(
+    attrs: &mut serde_json::Map<String, serde_json::Value>,
+    marker: &RecoveryMarker,
+    stored_last: Option<f64>,
+    events: bool,
+) {
+    if stored_last.is_some() && stored_last >= marker.last_timestamp {
+        return;
+    }
+    if let Some(ref end_time) = marker.end_time {
+        attrs.insert("end_time".to_string(), end_time.clone());
+    }
+    if let Some(ref connection) = marker.connection
+        && let Ok(value) = serde_json::to_value(connection)
+    {
+        attrs.insert(CONNECTION_ATTRIBUTE.to_string(), value);
+    }
+    if events {
+        // Add this recording's counts to those of earlier recordings appended to the stream
+        let mut event_counts = marker.event_counts.clone();
+        if let Some(existing) = attrs.get("event_counts").and_then(|v| v.as_object()) {
+            for (event, count) in existing {
+                *event_counts.entry(event.clone()).or_insert(0) += count.as_u64().unwrap_or(0);
+            }
+        }
+        attrs.insert("event_counts".to_string(), json!(event_counts));
+    }
+}
+
+/// Check every stream in a store and repair the ones left behind by an interruption
+///
+/// Recovery markers are looked up next to the store, in the current directory and in
+/// the temp directory; `markers` adds marker files kept elsewhere. With `dry_run`
+/// nothing is written; the returned reports describe what would change.
+pub fn repair_store(store_path: &Path, markers: &[PathBuf], dry_run: bool) -> Result<Vec<StreamRepair>> {
+    if !store_path.join("zarr.json").exists() {
+        anyhow::bail!("Not a Zarr store: {}", store_path.display());
+    }
+    let mut found = find_recovery_markers(store_path);
+    for path in markers {
+        found.push((path.clone(), read_recovery_marker(path)?));
+    }
+
+    let store = Arc::new(FilesystemStore::new(store_path)?);
+    let mut stream_names: Vec<String> = std::fs::read_dir(store_path)?
+        .filter_map(|e| e.ok())
+        .filter(|e| e.path().join("time").join("zarr.json").exists())
+        .map(|e| e.file_name().to_string_lossy().to_string())
+        .collect();
+    stream_names.sort();
+
+    stream_names
+        .iter()
+        .map(|name| {
+            // The newest marker wins if a stream failed to finalize more than once
+            let marker = found
+                .iter()
+                .filter(|(_, marker)| marker.stream == *name)
+                .max_by(|a, b| a.1.written_at.cmp(&b.1.written_at));
+            repair_stream(&store, store_path, name, marker, dry_run)
+        })
+        .collect()
+}
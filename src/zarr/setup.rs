@@ -0,0 +1,144 @@
+//! Stream group and array creation for recordings
+//!
+//! Maps LSL stream metadata and channel formats onto Zarr groups, attributes and
+//! Blosc-compressed (optionally sharded) arrays. Only built with the `lsl` feature;
+//! the array layout itself lives in [`super::layout`].
+
+use anyhow::Result;
+use serde_json::json;
+use std::sync::Arc;
+use zarrs::array::Array;
+use zarrs::filesystem::FilesystemStore;
+
+use super::layout::{
+    create_flags_array, create_group_if_not_exists, create_sample_array, create_time_array, parse_channels_to_json,
+    parse_desc_to_json,
+};
+use super::{array_exists, StorageOptions};
+
+/// Serialize LSL StreamInfo to JSON value
+pub fn serialize_stream_info(info: &mut lsl::StreamInfo) -> Result<serde_json::Value> {
+    // Get full XML representation and extract just the <desc> element
+    let full_xml = info.to_xml()
+        .map_err(|e| anyhow::anyhow!("Failed to serialize stream info XML: {}", e))?;
+
+    // Parse <desc>...</desc> content to JSON to avoid duplicating basic stream info
+    let description_json = parse_desc_to_json(&full_xml);
+
+    let mut stream_info_json = json!({
+        "name": info.stream_name(),
+        "type": info.stream_type(),
+        "source_id": info.source_id(),
+        "hostname": info.hostname(),
+        "channel_count": info.channel_count(),
+        "nominal_srate": info.nominal_srate(),
+        "channel_format": format!("{:?}", info.channel_format()),
+        "created_at": info.created_at(),
+        "uid": info.uid(),
+        "session_id": info.session_id(),
+        "version": info.version(),
+        "description": description_json
+    });
+
+    // Per-channel metadata (label, unit, type) is flattened away in "description"
+    let channels = parse_channels_to_json(&full_xml);
+    if !channels.is_empty() {
+        stream_info_json["channels"] = serde_json::Value::Array(channels);
+    }
+
+    Ok(stream_info_json)
+}
+
+/// Parse recorder config JSON string to serde_json::Value
+fn parse_recorder_config(recorder_config_json: &str) -> Result<serde_json::Value> {
+    let config: serde_json::Value = serde_json::from_str(recorder_config_json)?;
+    Ok(config)
+}
+
+/// Setup stream arrays (data or events, and time) in the Zarr store
+///
+/// Returns the sample array (`data`, or `events` for single-channel marker streams)
+/// and the time array.
+pub fn setup_stream_arrays(
+    store: &Arc<FilesystemStore>,
+    stream_name: &str,
+    info: &mut lsl::StreamInfo,
+    recorder_config_json: &str,
+    time_correction: f64,
+    first_timestamp: Option<f64>,
+    storage: &StorageOptions,
+) -> Result<(Array<FilesystemStore>, Array<FilesystemStore>)> {
+    let channel_format = info.channel_format();
+    let channel_format_name = format!("{:?}", channel_format);
+    storage.validate()?;
+    if super::is_store_group(stream_name) {
+        anyhow::bail!("'{}' is reserved for store metadata; choose another stream name", stream_name);
+    }
+
+    // Create stream group (use absolute path with /)
+    let stream_path = format!("/{}", stream_name);
+    create_group_if_not_exists(store, &stream_path)?;
+
+    // Prepare sync metadata (will be added to stream group attributes)
+    let mut sync_attrs = serde_json::Map::new();
+    sync_attrs.insert("lsl_clock_offset".to_string(), json!(time_correction));
+    if let Some(first_ts) = first_timestamp {
+        sync_attrs.insert("first_timestamp".to_string(), json!(first_ts));
+    }
+
+    // Single-channel string streams are markers and get a 1-D events array, as in XDF.
+    // A 2-D data array left by an older recording of the same stream is appended to as is.
+    let legacy_data_path = format!("{}/data", stream_path);
+    let events = matches!(channel_format, lsl::ChannelFormat::String)
+        && info.channel_count() == 1
+        && !array_exists(store, &legacy_data_path)?;
+
+    // Create or get data array (use absolute path with /)
+    let data_path = if events {
+        format!("{}/events", stream_path)
+    } else {
+        legacy_data_path
+    };
+    let data_array = if array_exists(store, &data_path)? {
+        Array::open(store.clone(), &data_path)?
+    } else {
+        let channels = info.channel_count() as usize;
+        let array = create_sample_array(store, &data_path, &channel_format_name, channels, events, storage)?;
+
+        // Store metadata in the stream group instead of on the array
+        let mut stream_group = zarrs::group::Group::open(store.clone(), &stream_path)?;
+        let mut stream_attrs = serde_json::Map::new();
+        stream_attrs.insert("stream_info".to_string(), serialize_stream_info(info)?);
+        stream_attrs.insert("recorder_config".to_string(), parse_recorder_config(recorder_config_json)?);
+        // Add sync metadata to stream attributes
+        stream_attrs.extend(sync_attrs);
+        stream_group.attributes_mut().extend(stream_attrs);
+        stream_group.store_metadata()?;
+
+        array
+    };
+
+    // Create or get time array
+    let time_path = format!("{}/time", stream_path);
+    let time_array = if array_exists(store, &time_path)? {
+        Array::open(store.clone(), &time_path)?
+    } else {
+        create_time_array(store, &time_path, storage)?
+    };
+
+    Ok((data_array, time_array))
+}
+
+/// Open or create the per-sample `flags` array of a stream set up by [`setup_stream_arrays`]
+pub fn setup_flags_array(
+    store: &Arc<FilesystemStore>,
+    stream_name: &str,
+    storage: &StorageOptions,
+) -> Result<Array<FilesystemStore>> {
+    let flags_path = crate::flags::flags_array_path(stream_name);
+    if array_exists(store, &flags_path)? {
+        Ok(Array::open(store.clone(), &flags_path)?)
+    } else {
+        create_flags_array(store, &flags_path, storage)
+    }
+}
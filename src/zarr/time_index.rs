@@ -0,0 +1,174 @@
+//! Per-chunk timestamp index for time-based slicing
+//!
+//! Each stream has a small `time_index` array next to its `time` array, of shape
+//! `[chunks, 2]`: the first and last timestamp of every chunk of the time array.
+//! The recorder updates the rows it touched at every flush, so a reader looking for
+//! a time window can go straight to the chunks that hold it instead of scanning the
+//! whole time array.
+//!
+//! The index granularity is stored as the array's `chunk_samples` attribute, and the
+//! number of samples it covers as `samples`. Samples beyond that (a recording
+//! interrupted before its last index update) are not indexed; readers search them
+//! directly. Lookups assume timestamps increase monotonically, as recorded by LSL.
+
+use anyhow::{Context, Result};
+use ndarray::{Array2, Ix2};
+use serde_json::json;
+use std::ops::Range;
+use std::sync::Arc;
+use zarrs::array::{Array, ArrayBuilder, DataType, FillValue};
+use zarrs::array_subset::ArraySubset;
+use zarrs::filesystem::FilesystemStore;
+
+use super::array_exists;
+
+/// Rows per chunk of the index array itself
+const INDEX_ROWS_PER_CHUNK: u64 = 4096;
+
+/// First and last timestamp of each chunk of a stream's time array
+#[derive(Debug, Clone, PartialEq)]
+pub struct TimeIndex {
+    /// Samples per indexed chunk
+    pub chunk_samples: u64,
+    /// `[first, last]` timestamp per chunk
+    pub entries: Vec<[f64; 2]>,
+    /// Samples covered by the index
+    pub samples: u64,
+}
+
+impl TimeIndex {
+    /// Empty index with the given granularity
+    pub fn new(chunk_samples: u64) -> Self {
+        Self {
+            chunk_samples: chunk_samples.max(1),
+            entries: Vec::new(),
+            samples: 0,
+        }
+    }
+
+    /// Index of a complete time array
+    pub fn build(timestamps: &[f64], chunk_samples: u64) -> Self {
+        let mut index = Self::new(chunk_samples);
+        index.append(timestamps.iter().copied());
+        index
+    }
+
+    /// Extend the index by `timestamps` following the samples already covered
+    ///
+    /// Returns the first row that changed.
+    pub fn append(&mut self, timestamps: impl IntoIterator<Item = f64>) -> usize {
+        let first_row = (self.samples / self.chunk_samples) as usize;
+        for timestamp in timestamps {
+            let row = (self.samples / self.chunk_samples) as usize;
+            match self.entries.get_mut(row) {
+                Some(entry) => entry[1] = timestamp,
+                None => self.entries.push([timestamp, timestamp]),
+            }
+            self.samples += 1;
+        }
+        first_row
+    }
+
+    /// Samples that can hold the first sample with a timestamp at or after `time`
+    ///
+    /// This is one chunk when `time` falls within the indexed samples, otherwise
+    /// the unindexed tail up to `sample_count`.
+    pub fn locate(&self, time: f64, sample_count: u64) -> Range<u64> {
+        let covered = self.samples.min(sample_count);
+        let row = self.entries.partition_point(|entry| entry[1] < time) as u64;
+        let start = (row * self.chunk_samples).min(covered);
+        if start < covered {
+            start..((row + 1) * self.chunk_samples).min(covered)
+        } else {
+            covered..sample_count
+        }
+    }
+
+    /// Samples that can hold timestamps in `[start, end)`
+    pub fn sample_range(&self, start: f64, end: f64, sample_count: u64) -> Range<u64> {
+        let first = self.locate(start, sample_count).start;
+        let last = self.locate(end, sample_count).end;
+        first..last.max(first)
+    }
+
+    /// Load the index of a stream, or `None` if the stream has none
+    pub fn load(store: &Arc<FilesystemStore>, stream_name: &str) -> Result<Option<Self>> {
+        let path = index_array_path(stream_name);
+        if !array_exists(store, &path)? {
+            return Ok(None);
+        }
+        let array = Array::<FilesystemStore>::open(store.clone(), &path)?;
+        let attribute = |key: &str| array.attributes().get(key).and_then(|v| v.as_u64());
+        let chunk_samples = attribute("chunk_samples")
+            .with_context(|| format!("time_index of stream '{}' has no chunk_samples", stream_name))?;
+
+        let rows = array.shape()[0];
+        let entries = if rows > 0 {
+            let subset = ArraySubset::new_with_start_shape(vec![0, 0], vec![rows, 2])?;
+            array
+                .retrieve_array_subset_ndarray::<f64>(&subset)?
+                .into_dimensionality::<Ix2>()?
+                .rows()
+                .into_iter()
+                .map(|row| [row[0], row[1]])
+                .collect()
+        } else {
+            Vec::new()
+        };
+        let samples = attribute("samples").unwrap_or(rows * chunk_samples);
+
+        Ok(Some(Self {
+            chunk_samples,
+            entries,
+            samples,
+        }))
+    }
+
+    /// Write rows from `first_row` on, with the array shape and attributes
+    pub fn store_rows(&self, array: &mut Array<FilesystemStore>, first_row: usize) -> Result<()> {
+        let rows = self.entries.len();
+        array.set_shape(vec![rows as u64, 2])?;
+        if first_row < rows {
+            let values = self.entries[first_row..].iter().flatten().copied().collect();
+            let block = Array2::from_shape_vec((rows - first_row, 2), values)?;
+            array.store_array_subset_ndarray::<f64, Ix2>(&[first_row as u64, 0], block)?;
+        }
+        let attributes = array.attributes_mut();
+        attributes.insert("chunk_samples".to_string(), json!(self.chunk_samples));
+        attributes.insert("samples".to_string(), json!(self.samples));
+        Ok(())
+    }
+}
+
+/// Path of a stream's index array
+pub fn index_array_path(stream_name: &str) -> String {
+    format!("/{}/time_index", stream_name.trim_start_matches('/'))
+}
+
+/// Open a stream's index array, creating it if needed
+pub fn open_or_create_index_array(
+    store: &Arc<FilesystemStore>,
+    stream_name: &str,
+    chunk_samples: u64,
+) -> Result<Array<FilesystemStore>> {
+    let path = index_array_path(stream_name);
+    if array_exists(store, &path)? {
+        return Ok(Array::open(store.clone(), &path)?);
+    }
+
+    let mut attributes = serde_json::Map::new();
+    attributes.insert("chunk_samples".to_string(), json!(chunk_samples));
+    attributes.insert("samples".to_string(), json!(0));
+    let mut builder = ArrayBuilder::new(
+        vec![0, 2], // [chunks, first/last] - chunks dimension is unlimited
+        vec![INDEX_ROWS_PER_CHUNK, 2],
+        DataType::Float64,
+        FillValue::from(0.0f64),
+    );
+    builder
+        .dimension_names(Some(vec![Some("chunks".to_string()), Some("bounds".to_string())]))
+        .attributes(attributes);
+    let array = builder.build(store.clone(), &path)?;
+    array.store_metadata()?;
+    Ok(array)
+}
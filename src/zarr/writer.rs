@@ -1,27 +1,540 @@
-use anyhow::Result;
+use anyhow::{Context, Result};
 use fs2::FileExt;
 use ndarray::{Array1, Array2, Ix1, Ix2};
+use std::collections::BTreeMap;
 use std::fs::File;
+use std::io::Write;
 use std::path::PathBuf;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::mpsc::{sync_channel, Receiver, SyncSender, TrySendError};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::thread::JoinHandle;
 use std::time::{Duration, Instant};
 use zarrs::array::Array;
+use zarrs::array_subset::ArraySubset;
 use zarrs::filesystem::FilesystemStore;
 
+use super::remote::RemoteUploader;
+use super::time_index::{open_or_create_index_array, TimeIndex};
+use super::N_SAMPLES_ATTRIBUTE;
+use crate::badchannels::{BadChannel, BadChannelMask, ChannelStatistics, DetectionOptions, BAD_CHANNELS_ATTRIBUTE};
+use crate::clock_jumps::{append_clock_jump, ClockJump};
+use crate::connection::{ConnectionStats, CONNECTION_ATTRIBUTE};
+use crate::dropped::{DropEstimate, DROPPED_SAMPLES_ATTRIBUTE};
+use crate::export::channel_label;
+use crate::flags::add_flag_counts;
+use crate::info_history::{append_snapshot, InfoSnapshot};
+use crate::pauses::append_pause;
+use crate::plausibility::PLAUSIBILITY_ATTRIBUTE;
+use crate::quality::QUALITY_ATTRIBUTE;
+use crate::rotation::{Continuation, CONTINUATION_ATTRIBUTE};
+use crate::rules::RULES_ATTRIBUTE;
+use crate::schedule::{append_block, Block};
+
+/// Timestamps compared per read when checking a mirror against the primary store
+const MIRROR_CHECK_BLOCK: u64 = 100_000;
+
+/// Flushes that may wait for the writer thread before the recording loop holds them back
+pub const WRITE_QUEUE_CAPACITY: usize = 8;
+
 /// Configuration for creating a ZarrWriter
 pub struct ZarrWriterConfig {
     pub data_array: Array<FilesystemStore>,
     pub time_array: Array<FilesystemStore>,
     pub buffer_size: usize,
+    /// Samples per shard when the arrays use the sharding codec
+    pub shard_samples: Option<usize>,
     pub channel_format: lsl::ChannelFormat,
     pub flush_interval: Duration,
     pub store_path: PathBuf,
     pub store: std::sync::Arc<FilesystemStore>,
     pub stream_name: String,
+    /// Secondary store that receives every flush as well (`--mirror`)
+    pub mirror: Option<MirrorConfig>,
+    /// Track channel statistics and store detected bad channels when the recording ends
+    pub detect_bad_channels: bool,
+    /// Uploads the primary store to object storage after every flush (`--output s3://...`)
+    pub remote: Option<RemoteUploader>,
+    /// Per-sample validity flags (`--sample-flags`, see [`crate::flags`])
+    pub flags_array: Option<Array<FilesystemStore>>,
+}
+
+/// Arrays of a mirror store, set up like the primary arrays
+pub struct MirrorConfig {
+    pub data_array: Array<FilesystemStore>,
+    pub time_array: Array<FilesystemStore>,
+    pub flags_array: Option<Array<FilesystemStore>>,
+    pub store_path: PathBuf,
+    pub store: std::sync::Arc<FilesystemStore>,
+}
+
+/// Samples of one flush, laid out for the sample array
+#[derive(Clone)]
+enum SampleBatch {
+    Float32(Array2<f32>),
+    Float64(Array2<f64>),
+    Int32(Array2<i32>),
+    Int16(Array2<i16>),
+    Int8(Array2<i8>),
+    String(Array2<String>),
+    Events(Array1<String>),
+}
+
+/// A store the writer persists samples to: the primary store or its mirror
+struct StoreTarget {
+    data_array: Array<FilesystemStore>,
+    time_array: Array<FilesystemStore>,
+    // First/last timestamp per time chunk, updated with every write
+    index_array: Array<FilesystemStore>,
+    time_index: TimeIndex,
+    // Per-sample flags, when recording with --sample-flags
+    flags_array: Option<Array<FilesystemStore>>,
+    // File lock for coordinating metadata writes across concurrent processes
+    metadata_lock: File,
+    store: std::sync::Arc<FilesystemStore>,
+    store_path: PathBuf,
+    // Group whose `n_samples` attribute follows the length
+    stream_path: String,
+    // Samples in the arrays (an appended mirror may hold fewer than the primary)
+    length: usize,
+    // Samples written by this writer, compared by the final consistency check
+    written: usize,
+    // First write error; a failed store is not written again
+    error: Option<String>,
+}
+
+impl StoreTarget {
+    fn new(
+        data_array: Array<FilesystemStore>,
+        time_array: Array<FilesystemStore>,
+        flags_array: Option<Array<FilesystemStore>>,
+        store_path: PathBuf,
+        store: std::sync::Arc<FilesystemStore>,
+        stream_name: &str,
+    ) -> Result<Self> {
+        let lock_path = store_path.join(".zarr_metadata.lock");
+        let metadata_lock = std::fs::OpenOptions::new()
+            .create(true)
+            .write(true)
+            .truncate(false)
+            .open(lock_path)?;
+        // Last dimension is samples: [channels, samples] for data, [samples] for events
+        let length = data_array.shape().last().copied().unwrap_or(0) as usize;
+
+        // Index at the granularity of the time array's chunks (shards when sharded)
+        let (_, chunk_samples) = super::repair::array_layout(&store_path.join(stream_name).join("time"), 0)
+            .with_context(|| format!("Could not read the chunk layout of {}/time", stream_name))?;
+        let mut index_array = open_or_create_index_array(&store, stream_name, chunk_samples)?;
+        let time_index = match TimeIndex::load(&store, stream_name)? {
+            Some(index) if index.samples == length as u64 => index,
+            // Appending to a stream whose index is missing or behind: index what is there
+            _ => {
+                let existing = if length > 0 {
+                    let subset = ArraySubset::new_with_start_shape(vec![0], vec![length as u64])?;
+                    time_array.retrieve_array_subset_ndarray::<f64>(&subset)?.into_iter().collect()
+                } else {
+                    Vec::new()
+                };
+                let index = TimeIndex::build(&existing, chunk_samples);
+                index.store_rows(&mut index_array, 0)?;
+                index_array.store_metadata()?;
+                index
+            }
+        };
+
+        Ok(Self {
+            data_array,
+            time_array,
+            index_array,
+            time_index,
+            flags_array,
+            metadata_lock,
+            store,
+            store_path,
+            stream_path: format!("/{}", stream_name),
+            length,
+            written: 0,
+            error: None,
+        })
+    }
+
+    fn is_healthy(&self) -> bool {
+        self.error.is_none()
+    }
+
+    /// Append a batch of samples (and their flags), then persist the array metadata
+    fn write(
+        &mut self,
+        num_channels: usize,
+        batch: SampleBatch,
+        time: Array1<f64>,
+        flags: Option<Array1<u8>>,
+    ) -> Result<()> {
+        let num_samples = time.len();
+        let new_length = self.length + num_samples;
+
+        // Resize arrays to accommodate new samples (zarrs does NOT auto-expand)
+        // Set shape but defer metadata write until after data is written
+        let new_data_shape = match batch {
+            SampleBatch::Events(_) => vec![new_length as u64],
+            _ => vec![num_channels as u64, new_length as u64],
+        };
+        self.data_array.set_shape(new_data_shape)?;
+        self.time_array.set_shape(vec![new_length as u64])?;
+
+        // Define start indices for writing
+        let start_indices = &[0u64, self.length as u64];
+        match batch {
+            SampleBatch::Float32(data) => self.data_array.store_array_subset_ndarray::<f32, Ix2>(start_indices, data)?,
+            SampleBatch::Float64(data) => self.data_array.store_array_subset_ndarray::<f64, Ix2>(start_indices, data)?,
+            SampleBatch::Int32(data) => self.data_array.store_array_subset_ndarray::<i32, Ix2>(start_indices, data)?,
+            SampleBatch::Int16(data) => self.data_array.store_array_subset_ndarray::<i16, Ix2>(start_indices, data)?,
+            SampleBatch::Int8(data) => self.data_array.store_array_subset_ndarray::<i8, Ix2>(start_indices, data)?,
+            SampleBatch::String(data) => self.data_array.store_array_subset_ndarray::<String, Ix2>(start_indices, data)?,
+            SampleBatch::Events(data) => self
+                .data_array
+                .store_array_subset_ndarray::<String, Ix1>(&[self.length as u64], data)?,
+        }
+
+        // Write time data starting at the current length
+        let first_index_row = self.time_index.append(time.iter().copied());
+        let time_start_indices = &[self.length as u64];
+        self.time_array.store_array_subset_ndarray::<f64, Ix1>(time_start_indices, time)?;
+        self.time_index.store_rows(&mut self.index_array, first_index_row)?;
+        if let (Some(flags_array), Some(flags)) = (self.flags_array.as_mut(), flags) {
+            // Samples of an earlier recording without flags read as the fill value 0
+            flags_array.set_shape(vec![new_length as u64])?;
+            flags_array.store_array_subset_ndarray::<u8, Ix1>(time_start_indices, flags)?;
+        }
+
+        self.length = new_length;
+        self.written += num_samples;
+
+        // Persist metadata AFTER writing data with exclusive lock to prevent race conditions
+        self.metadata_lock.lock_exclusive()?;
+        let metadata_result = (|| -> Result<()> {
+            self.data_array.store_metadata()?;
+            self.time_array.store_metadata()?;
+            self.index_array.store_metadata()?;
+            if let Some(ref flags_array) = self.flags_array {
+                flags_array.store_metadata()?;
+            }
+            // Last, so the count never exceeds what the arrays admit to
+            let mut stream_group = zarrs::group::Group::open(self.store.clone(), &self.stream_path)?;
+            stream_group
+                .attributes_mut()
+                .insert(N_SAMPLES_ATTRIBUTE.to_string(), serde_json::json!(new_length));
+            stream_group.store_metadata()?;
+            Ok(())
+        })();
+        self.metadata_lock.unlock()?;
+        metadata_result
+    }
+
+    /// Timestamps written by this writer, read back from disk
+    fn read_written_times(&self, stream_name: &str, start: u64, len: u64) -> Result<Vec<f64>> {
+        let time_array = Array::<FilesystemStore>::open(self.store.clone(), &format!("/{}/time", stream_name))?;
+        let offset = (self.length - self.written) as u64;
+        let subset = ArraySubset::new_with_start_shape(vec![offset + start], vec![len])?;
+        Ok(time_array
+            .retrieve_array_subset_ndarray::<f64>(&subset)?
+            .into_iter()
+            .collect())
+    }
+}
+
+/// The primary store and its mirror, shared by the recording loop and the writer thread
+struct Stores {
+    primary: StoreTarget,
+    mirror: Option<StoreTarget>,
+}
+
+impl Stores {
+    /// Stores still being written
+    fn healthy(&self) -> impl Iterator<Item = &StoreTarget> {
+        std::iter::once(&self.primary)
+            .chain(self.mirror.as_ref())
+            .filter(|target| target.is_healthy())
+    }
+
+    /// Samples written to the store that got furthest (primary or mirror)
+    fn samples_written(&self) -> usize {
+        std::iter::once(&self.primary)
+            .chain(self.mirror.as_ref())
+            .map(|target| target.written)
+            .max()
+            .unwrap_or(0)
+    }
+
+    /// Change the stream group's attributes in every healthy store, under the metadata lock
+    fn update_stream_attributes(&self, what: &str, update: &AttributeUpdate) -> Result<()> {
+        for target in self.healthy() {
+            target.metadata_lock.lock_exclusive()?;
+            let result = (|| -> Result<()> {
+                let mut stream_group = zarrs::group::Group::open(target.store.clone(), &target.stream_path)?;
+                update(stream_group.attributes_mut())?;
+                stream_group.store_metadata()?;
+                Ok(())
+            })();
+            target.metadata_lock.unlock()?;
+            result.with_context(|| format!("Failed to store the {} in {}", what, target.store_path.display()))?;
+        }
+        Ok(())
+    }
+
+    /// Write a batch to the primary store and, in parallel, to the mirror
+    ///
+    /// A store that fails is dropped and recording continues on the other one; the
+    /// error is returned only when no store is left.
+    fn write_batch(
+        &mut self,
+        num_channels: usize,
+        batch: SampleBatch,
+        time: Array1<f64>,
+        flags: Option<Array1<u8>>,
+    ) -> Result<()> {
+        let primary = &mut self.primary;
+        let mirror = self.mirror.as_mut().filter(|m| m.is_healthy());
+
+        let (primary_result, mirror_result) = std::thread::scope(|scope| {
+            let mirror_handle = mirror.map(|target| {
+                let (batch, time, flags) = (batch.clone(), time.clone(), flags.clone());
+                scope.spawn(move || target.write(num_channels, batch, time, flags))
+            });
+            let primary_result = primary
+                .is_healthy()
+                .then(|| primary.write(num_channels, batch, time, flags));
+            let mirror_result = mirror_handle.map(|handle| {
+                handle
+                    .join()
+                    .unwrap_or_else(|_| Err(anyhow::anyhow!("mirror writer thread panicked")))
+            });
+            (primary_result, mirror_result)
+        });
+
+        if let (Some(Err(e)), Some(mirror)) = (mirror_result, self.mirror.as_mut()) {
+            eprintln!("Warning: mirror store {} failed, recording continues without it: {:#}", mirror.store_path.display(), e);
+            println!("STATUS MIRROR_FAILED");
+            std::io::stdout().flush().ok();
+            mirror.error = Some(format!("{:#}", e));
+        }
+
+        if let Some(Err(e)) = primary_result {
+            let mirror = self.mirror.as_ref().filter(|m| m.is_healthy());
+            let Some(mirror) = mirror else {
+                return Err(e);
+            };
+            eprintln!(
+                "Warning: primary store {} failed, recording continues on mirror {}: {:#}",
+                self.primary.store_path.display(),
+                mirror.store_path.display(),
+                e
+            );
+            println!("STATUS PRIMARY_FAILED");
+            std::io::stdout().flush().ok();
+            self.primary.error = Some(format!("{:#}", e));
+        }
+
+        // The primary failed earlier and the mirror has failed now
+        if !self.primary.is_healthy() && !self.mirror.as_ref().is_some_and(|m| m.is_healthy()) {
+            anyhow::bail!(
+                "All stores failed (primary: {})",
+                self.primary.error.as_deref().unwrap_or_default()
+            );
+        }
+
+        Ok(())
+    }
+
+    /// Compare what this recording wrote to the primary and the mirror store
+    ///
+    /// Both stores must hold the same number of new samples with identical timestamps
+    /// on disk.
+    fn check_mirror(&self, mirror: &StoreTarget, stream_name: &str) -> Result<(), String> {
+        if let Some(ref error) = self.primary.error {
+            return Err(format!("primary store failed: {}", error));
+        }
+        if let Some(ref error) = mirror.error {
+            return Err(format!("mirror store failed: {}", error));
+        }
+        if self.primary.written != mirror.written {
+            return Err(format!(
+                "sample counts differ (primary {}, mirror {})",
+                self.primary.written, mirror.written
+            ));
+        }
+
+        let mut start = 0;
+        while start < self.primary.written as u64 {
+            let len = MIRROR_CHECK_BLOCK.min(self.primary.written as u64 - start);
+            let read = |target: &StoreTarget| {
+                target
+                    .read_written_times(stream_name, start, len)
+                    .map_err(|e| format!("could not read back {}: {:#}", target.store_path.display(), e))
+            };
+            let (primary_times, mirror_times) = (read(&self.primary)?, read(mirror)?);
+            if let Some(i) = primary_times
+                .iter()
+                .zip(&mirror_times)
+                .position(|(a, b)| a.to_bits() != b.to_bits())
+            {
+                return Err(format!("timestamps differ at sample {} of this recording", start + i as u64));
+            }
+            start += len;
+        }
+        Ok(())
+    }
+}
+
+/// A flush handed to the writer thread
+struct WriteJob {
+    num_channels: usize,
+    batch: SampleBatch,
+    time: Array1<f64>,
+    flags: Option<Array1<u8>>,
+}
+
+/// A change to the stream group's attributes, applied to every store still being written
+type AttributeUpdate = Box<dyn Fn(&mut serde_json::Map<String, serde_json::Value>) -> serde_json::Result<()> + Send>;
+
+enum WriterRequest {
+    Write(WriteJob),
+    /// Attribute update, with what it stores for the warning when it fails
+    Attributes(&'static str, AttributeUpdate),
+    /// Answered once every earlier write is done
+    Sync(std::sync::mpsc::Sender<()>),
+}
+
+/// Figures of the writer thread
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct WriterStats {
+    /// Flushes waiting for or being written by the writer thread
+    pub queue_depth: usize,
+    pub queue_capacity: usize,
+    /// Highest queue depth so far
+    pub max_queue_depth: usize,
+    /// Flushes written so far
+    pub writes: u64,
+    /// Time the writer thread took per flush (ms)
+    pub mean_write_ms: f64,
+    pub max_write_ms: f64,
+    /// Time the recording loop waited for room in the full queue (s)
+    pub blocked_s: f64,
+}
+
+/// State shared by the recording loop and the writer thread
+struct WriterShared {
+    // Held by the writer thread while it writes; the recording loop only takes it once the queue is drained
+    stores: Mutex<Stores>,
+    // Stores::samples_written after the last write
+    samples_written: AtomicUsize,
+    // First error that left no store to write to
+    failure: Mutex<Option<String>>,
+    queue_depth: AtomicUsize,
+    max_queue_depth: AtomicUsize,
+    writes: AtomicU64,
+    write_us: AtomicU64,
+    max_write_us: AtomicU64,
+    blocked_us: AtomicU64,
+}
+
+impl WriterShared {
+    fn stores(&self) -> Result<MutexGuard<'_, Stores>> {
+        self.stores.lock().map_err(|_| anyhow::anyhow!("Zarr writer thread panicked"))
+    }
+
+    fn failure(&self) -> Option<String> {
+        self.failure.lock().ok().and_then(|failure| failure.clone())
+    }
+}
+
+/// Write the queued flushes until the writer is dropped
+///
+/// Runs on its own thread, so a slow disk holds up the queue instead of the sample
+/// pulls. After a failure that leaves no store, queued flushes are discarded and the
+/// recording loop returns the error on its next flush.
+fn run_writer(
+    shared: Arc<WriterShared>,
+    requests: Receiver<WriterRequest>,
+    remote: Option<Arc<RemoteUploader>>,
+    mut total: usize,
+) {
+    let mut slow_flush_warnings = 0u32;
+    for request in requests {
+        let job = match request {
+            WriterRequest::Write(job) => job,
+            WriterRequest::Attributes(what, update) => {
+                if let Err(e) = shared.stores().and_then(|stores| stores.update_stream_attributes(what, &update)) {
+                    eprintln!("Warning: {:#}", e);
+                }
+                continue;
+            }
+            WriterRequest::Sync(reply) => {
+                reply.send(()).ok();
+                continue;
+            }
+        };
+        let num_samples = job.time.len();
+        let started = Instant::now();
+        let result = if shared.failure().is_some() {
+            Ok(())
+        } else {
+            match shared.stores.lock() {
+                Ok(mut stores) => {
+                    let result = stores.write_batch(job.num_channels, job.batch, job.time, job.flags);
+                    shared.samples_written.store(stores.samples_written(), Ordering::SeqCst);
+                    result
+                }
+                Err(_) => Err(anyhow::anyhow!("Zarr writer thread panicked")),
+            }
+        };
+        shared.queue_depth.fetch_sub(1, Ordering::SeqCst);
+        if let Err(e) = result {
+            if let Ok(mut failure) = shared.failure.lock() {
+                failure.get_or_insert(format!("{:#}", e));
+            }
+            continue;
+        }
+        if let Some(ref remote) = remote {
+            remote.request_sync();
+        }
+
+        // Monitor write performance
+        let write_duration = started.elapsed();
+        let micros = write_duration.as_micros() as u64;
+        shared.writes.fetch_add(1, Ordering::SeqCst);
+        shared.write_us.fetch_add(micros, Ordering::SeqCst);
+        shared.max_write_us.fetch_max(micros, Ordering::SeqCst);
+        total += num_samples;
+
+        // Warn about slow writes; the queue absorbs them, but a disk that stays slow fills it
+        if write_duration > Duration::from_millis(100) {
+            slow_flush_warnings += 1;
+            if slow_flush_warnings <= 5 {
+                // Only warn first 5 times
+                println!(
+                    "Warning: Slow Zarr flush detected:\t{:.1}ms for {} samples (warning {}/5)",
+                    write_duration.as_millis(),
+                    num_samples,
+                    slow_flush_warnings
+                );
+            }
+        }
+
+        if slow_flush_warnings <= 5 {
+            println!(
+                "Zarr: Wrote {} samples (total: {} samples, {:.1}ms flush)",
+                num_samples,
+                total,
+                write_duration.as_millis()
+            );
+        }
+    }
 }
 
-/// Enum to handle different LSL data types
-#[derive(Debug, Clone)]
-pub enum SampleData {
+/// Values of the buffered samples, sample after sample as inlets deliver them
+#[derive(Debug)]
+enum BufferedValues {
     Float32(Vec<f32>),
     Float64(Vec<f64>),
     Int32(Vec<i32>),
@@ -30,291 +543,448 @@ pub enum SampleData {
     String(Vec<String>),
 }
 
-impl SampleData {
-    pub fn len(&self) -> usize {
+impl BufferedValues {
+    fn new(channel_format: lsl::ChannelFormat) -> Result<Self> {
+        Ok(match channel_format {
+            lsl::ChannelFormat::Float32 => BufferedValues::Float32(Vec::new()),
+            lsl::ChannelFormat::Double64 => BufferedValues::Float64(Vec::new()),
+            lsl::ChannelFormat::Int32 => BufferedValues::Int32(Vec::new()),
+            lsl::ChannelFormat::Int16 => BufferedValues::Int16(Vec::new()),
+            lsl::ChannelFormat::Int8 => BufferedValues::Int8(Vec::new()),
+            lsl::ChannelFormat::String => BufferedValues::String(Vec::new()),
+            format => return Err(anyhow::anyhow!("Unsupported channel format for Zarr: {:?}", format)),
+        })
+    }
+
+    /// Drop the first `len` values
+    fn drain_front(&mut self, len: usize) {
         match self {
-            SampleData::Float32(v) => v.len(),
-            SampleData::Float64(v) => v.len(),
-            SampleData::Int32(v) => v.len(),
-            SampleData::Int16(v) => v.len(),
-            SampleData::Int8(v) => v.len(),
-            SampleData::String(v) => v.len(),
+            BufferedValues::Float32(v) => drop(v.drain(..len)),
+            BufferedValues::Float64(v) => drop(v.drain(..len)),
+            BufferedValues::Int32(v) => drop(v.drain(..len)),
+            BufferedValues::Int16(v) => drop(v.drain(..len)),
+            BufferedValues::Int8(v) => drop(v.drain(..len)),
+            BufferedValues::String(v) => drop(v.drain(..len)),
         }
     }
 
-    #[allow(dead_code)]
-    pub fn is_empty(&self) -> bool {
+    /// Bytes held by the values (string contents included)
+    fn heap_bytes(&self) -> usize {
         match self {
-            SampleData::Float32(v) => v.is_empty(),
-            SampleData::Float64(v) => v.is_empty(),
-            SampleData::Int32(v) => v.is_empty(),
-            SampleData::Int16(v) => v.is_empty(),
-            SampleData::Int8(v) => v.is_empty(),
-            SampleData::String(v) => v.is_empty(),
+            BufferedValues::Float32(v) => v.capacity() * size_of::<f32>(),
+            BufferedValues::Float64(v) => v.capacity() * size_of::<f64>(),
+            BufferedValues::Int32(v) => v.capacity() * size_of::<i32>(),
+            BufferedValues::Int16(v) => v.capacity() * size_of::<i16>(),
+            BufferedValues::Int8(v) => v.capacity() * size_of::<i8>(),
+            BufferedValues::String(v) => {
+                v.capacity() * size_of::<String>() + v.iter().map(|s| s.capacity()).sum::<usize>()
+            }
         }
     }
 }
 
 /// Structure to manage Zarr writing with buffering
+///
+/// Samples are buffered on the recording thread; each flush lays them out and hands
+/// them to a writer thread over a queue of [`WRITE_QUEUE_CAPACITY`] flushes. While
+/// the queue is full the samples stay buffered, up to the buffer limit, and only
+/// then does a flush wait for the disk.
 pub struct ZarrWriter {
-    data_array: Array<FilesystemStore>,
-    time_array: Array<FilesystemStore>,
-    sample_buffer: Vec<SampleData>,
+    shared: Arc<WriterShared>,
+    requests: Option<SyncSender<WriterRequest>>,
+    writer_thread: Option<JoinHandle<()>>,
+    values: BufferedValues,
+    // Values per buffered sample, taken from the samples added
+    channels: usize,
     time_buffer: Vec<f64>,
+    // Flags of the buffered samples (`None` without --sample-flags)
+    flag_buffer: Option<Vec<u8>>,
     buffer_size: usize,
     max_buffer_size: usize, // Maximum allowed buffer size to prevent memory bloat
+    // Write whole shards only, so each shard is encoded once instead of rewritten per flush
+    shard_samples: Option<usize>,
     current_length: usize,
     channel_format: lsl::ChannelFormat,
+    // Marker stream stored in a 1-D events array, with occurrences per marker value
+    events: bool,
+    event_counts: BTreeMap<String, u64>,
     last_flush_time: Instant,
     flush_interval: Duration,
     // Pre-allocated buffer to avoid allocations during flush
     temp_data_buffer: Vec<f64>, // Use f64 as largest type, cast as needed
-    // Backpressure monitoring
-    slow_flush_warnings: u32,
-    last_flush_duration: Duration,
-    // File lock for coordinating metadata writes across concurrent processes
-    metadata_lock: File,
-    // Store reference and stream name for metadata updates
-    store: std::sync::Arc<FilesystemStore>,
+    // Whether the recording loop already waited for the full queue (warned once)
+    warned_queue_full: bool,
     stream_name: String,
+    // Statistics of all written samples for bad-channel detection (numeric streams only)
+    detect_bad_channels: bool,
+    channel_statistics: Option<ChannelStatistics>,
+    remote: Option<Arc<RemoteUploader>>,
 }
 
 impl ZarrWriter {
     pub fn new(config: ZarrWriterConfig) -> Result<Self> {
         // Set max buffer size to 10x normal buffer size to prevent memory bloat
-        let max_buffer_size = (config.buffer_size * 10).max(1000);
-        let current_length = config.data_array.shape()[1] as usize; // Second dimension is samples
+        // and always leave room for two full shards
+        let max_buffer_size = (config.buffer_size * 10)
+            .max(1000)
+            .max(config.shard_samples.unwrap_or(0) * 2);
+        let events = config.data_array.shape().len() == 1;
+        let sample_flags = config.flags_array.is_some();
 
-        // Create metadata lock file for coordinating concurrent writes
-        let lock_path = config.store_path.join(".zarr_metadata.lock");
-        let metadata_lock = std::fs::OpenOptions::new()
-            .create(true)
-            .write(true)
-            .truncate(false)
-            .open(lock_path)?;
+        let primary = StoreTarget::new(
+            config.data_array,
+            config.time_array,
+            config.flags_array,
+            config.store_path,
+            config.store,
+            &config.stream_name,
+        )?;
+        let mirror = config
+            .mirror
+            .map(|m| {
+                StoreTarget::new(m.data_array, m.time_array, m.flags_array, m.store_path, m.store, &config.stream_name)
+            })
+            .transpose()?;
+
+        let current_length = primary.length;
+        let shared = Arc::new(WriterShared {
+            stores: Mutex::new(Stores { primary, mirror }),
+            samples_written: AtomicUsize::new(0),
+            failure: Mutex::new(None),
+            queue_depth: AtomicUsize::new(0),
+            max_queue_depth: AtomicUsize::new(0),
+            writes: AtomicU64::new(0),
+            write_us: AtomicU64::new(0),
+            max_write_us: AtomicU64::new(0),
+            blocked_us: AtomicU64::new(0),
+        });
+        let remote = config.remote.map(Arc::new);
+        let (requests, queue) = sync_channel(WRITE_QUEUE_CAPACITY);
+        let writer_thread = {
+            let (shared, remote) = (shared.clone(), remote.clone());
+            std::thread::Builder::new()
+                .name(format!("zarr-writer-{}", config.stream_name))
+                .spawn(move || run_writer(shared, queue, remote, current_length))?
+        };
 
         Ok(Self {
-            data_array: config.data_array,
-            time_array: config.time_array,
-            sample_buffer: Vec::new(),
+            current_length,
+            shared,
+            requests: Some(requests),
+            writer_thread: Some(writer_thread),
+            values: BufferedValues::new(config.channel_format)?,
+            channels: 0,
             time_buffer: Vec::new(),
+            flag_buffer: sample_flags.then(Vec::new),
             buffer_size: config.buffer_size,
             max_buffer_size,
-            current_length,
+            shard_samples: config.shard_samples,
             channel_format: config.channel_format,
+            events,
+            event_counts: BTreeMap::new(),
             last_flush_time: Instant::now(),
             flush_interval: config.flush_interval,
             temp_data_buffer: Vec::new(),
-            slow_flush_warnings: 0,
-            last_flush_duration: Duration::from_millis(0),
-            metadata_lock,
-            store: config.store,
+            warned_queue_full: false,
             stream_name: config.stream_name,
+            detect_bad_channels: config.detect_bad_channels,
+            channel_statistics: None,
+            remote,
         })
     }
 
     /// Add sample by reference to avoid cloning - more efficient for hot path
     pub fn add_sample_slice_f32(&mut self, data: &[f32], timestamp: f64) {
-        self.sample_buffer.push(SampleData::Float32(data.to_vec()));
-        self.time_buffer.push(timestamp);
+        self.add_sample_block_f32(data, &[timestamp]);
     }
 
     pub fn add_sample_slice_f64(&mut self, data: &[f64], timestamp: f64) {
-        self.sample_buffer.push(SampleData::Float64(data.to_vec()));
-        self.time_buffer.push(timestamp);
+        self.add_sample_block_f64(data, &[timestamp]);
     }
 
     pub fn add_sample_slice_i32(&mut self, data: &[i32], timestamp: f64) {
-        self.sample_buffer.push(SampleData::Int32(data.to_vec()));
-        self.time_buffer.push(timestamp);
+        self.add_sample_block_i32(data, &[timestamp]);
     }
 
     pub fn add_sample_slice_i16(&mut self, data: &[i16], timestamp: f64) {
-        self.sample_buffer.push(SampleData::Int16(data.to_vec()));
-        self.time_buffer.push(timestamp);
+        self.add_sample_block_i16(data, &[timestamp]);
     }
 
     pub fn add_sample_slice_i8(&mut self, data: &[i8], timestamp: f64) {
-        self.sample_buffer.push(SampleData::Int8(data.to_vec()));
-        self.time_buffer.push(timestamp);
+        self.add_sample_block_i8(data, &[timestamp]);
     }
 
     pub fn add_sample_slice_string(&mut self, data: &[String], timestamp: f64) {
-        self.sample_buffer.push(SampleData::String(data.to_vec()));
-        self.time_buffer.push(timestamp);
+        if let BufferedValues::String(ref mut values) = self.values {
+            values.extend_from_slice(data);
+            self.channels = data.len();
+            self.push_timestamps(&[timestamp]);
+        }
     }
 
-    pub fn flush(&mut self) -> Result<()> {
-        if self.sample_buffer.is_empty() {
-            return Ok(());
+    /// Add a block of samples laid out sample after sample in `data`, as an inlet chunk is
+    pub fn add_sample_block_f32(&mut self, data: &[f32], timestamps: &[f64]) {
+        if !timestamps.is_empty()
+            && let BufferedValues::Float32(ref mut values) = self.values
+        {
+            values.extend_from_slice(data);
+            self.push_block(data.len(), timestamps);
         }
+    }
 
-        let flush_start = Instant::now();
+    pub fn add_sample_block_f64(&mut self, data: &[f64], timestamps: &[f64]) {
+        if !timestamps.is_empty()
+            && let BufferedValues::Float64(ref mut values) = self.values
+        {
+            values.extend_from_slice(data);
+            self.push_block(data.len(), timestamps);
+        }
+    }
 
-        let num_samples = self.sample_buffer.len();
-        let num_channels = self.sample_buffer[0].len();
-        let new_length = self.current_length + num_samples;
+    pub fn add_sample_block_i32(&mut self, data: &[i32], timestamps: &[f64]) {
+        if !timestamps.is_empty()
+            && let BufferedValues::Int32(ref mut values) = self.values
+        {
+            values.extend_from_slice(data);
+            self.push_block(data.len(), timestamps);
+        }
+    }
 
-        // Resize arrays to accommodate new samples (zarrs does NOT auto-expand)
-        // Set shape but defer metadata write until after data is written
-        let new_data_shape = vec![num_channels as u64, new_length as u64];
-        self.data_array.set_shape(new_data_shape)?;
+    pub fn add_sample_block_i16(&mut self, data: &[i16], timestamps: &[f64]) {
+        if !timestamps.is_empty()
+            && let BufferedValues::Int16(ref mut values) = self.values
+        {
+            values.extend_from_slice(data);
+            self.push_block(data.len(), timestamps);
+        }
+    }
+
+    pub fn add_sample_block_i8(&mut self, data: &[i8], timestamps: &[f64]) {
+        if !timestamps.is_empty()
+            && let BufferedValues::Int8(ref mut values) = self.values
+        {
+            values.extend_from_slice(data);
+            self.push_block(data.len(), timestamps);
+        }
+    }
+
+    /// Count a block of `len` values just appended to the buffer
+    fn push_block(&mut self, len: usize, timestamps: &[f64]) {
+        self.channels = len / timestamps.len();
+        self.push_timestamps(timestamps);
+    }
+
+    fn push_timestamps(&mut self, timestamps: &[f64]) {
+        self.time_buffer.extend_from_slice(timestamps);
+        if let Some(ref mut flags) = self.flag_buffer {
+            flags.resize(self.time_buffer.len(), 0);
+        }
+    }
+
+    /// Set flag bits (see [`crate::flags`]) on the sample added last
+    ///
+    /// Does nothing when the writer records no flags.
+    pub fn flag_last_sample(&mut self, flags: u8) {
+        if let Some(last) = self.flag_buffer.as_mut().and_then(|buffer| buffer.last_mut()) {
+            *last |= flags;
+        }
+    }
+
+    /// Hand buffered samples to the writer thread
+    ///
+    /// With sharding enabled only complete shards are written; the remainder stays
+    /// buffered until the shard fills up or [`flush_all`](Self::flush_all) is called.
+    /// Fails once an earlier write left no store to write to.
+    pub fn flush(&mut self) -> Result<()> {
+        let num_samples = match self.shard_samples {
+            Some(shard) => {
+                let end = self.current_length + self.time_buffer.len();
+                (end / shard * shard).saturating_sub(self.current_length)
+            }
+            None => self.time_buffer.len(),
+        };
+        self.write_samples(num_samples)
+    }
+
+    /// Write all buffered samples, including a partially filled shard, and wait until they are on disk
+    pub fn flush_all(&mut self) -> Result<()> {
+        self.write_samples(self.time_buffer.len())?;
+        self.wait_for_writes()
+    }
 
-        let new_time_shape = vec![new_length as u64];
-        self.time_array.set_shape(new_time_shape)?;
+    /// Wait until the writer thread has written every queued flush
+    pub fn wait_for_writes(&self) -> Result<()> {
+        if let Some(ref requests) = self.requests {
+            let (reply, done) = std::sync::mpsc::channel();
+            if requests.send(WriterRequest::Sync(reply)).is_ok() {
+                done.recv().ok();
+            }
+        }
+        self.check_failure()
+    }
+
+    fn check_failure(&self) -> Result<()> {
+        match self.shared.failure() {
+            Some(failure) => Err(anyhow::anyhow!(failure)),
+            None => Ok(()),
+        }
+    }
+
+    /// Lay out the first `num_samples` buffered samples and queue them for the writer thread
+    fn write_samples(&mut self, num_samples: usize) -> Result<()> {
+        self.check_failure()?;
+        if num_samples == 0 {
+            return Ok(());
+        }
+
+        let num_channels = self.channels;
 
         // Prepare time as 1D array - move data to avoid clone
-        let time_array = Array1::from_vec(std::mem::take(&mut self.time_buffer));
+        let time_array = Array1::from_vec(self.time_buffer.drain(..num_samples).collect());
+        let flags = self
+            .flag_buffer
+            .as_mut()
+            .map(|buffer| Array1::from_vec(buffer.drain(..num_samples).collect()));
 
-        // Write data based on channel format using array subset
-        macro_rules! write_samples {
-            ($type:ty, $variant:ident) => {{
+        // Lay out data based on channel format
+        macro_rules! batch_samples {
+            ($values:ident, $type:ty, $variant:ident) => {{
                 // Prepare flattened data buffer
                 self.temp_data_buffer.clear();
                 self.temp_data_buffer.reserve(num_channels * num_samples);
 
-                // Fill buffer in column-major order (channel-first layout for Zarr)
+                // Buffered sample after sample; fill in column-major order (channel-first layout for Zarr)
                 for channel in 0..num_channels {
-                    for i in 0..num_samples {
-                        if let SampleData::$variant(values) = &self.sample_buffer[i] {
-                            self.temp_data_buffer.push(values[channel] as f64);
-                        }
-                    }
+                    self.temp_data_buffer
+                        .extend((0..num_samples).map(|i| $values[i * num_channels + channel] as f64));
+                }
+                if self.detect_bad_channels {
+                    self.channel_statistics
+                        .get_or_insert_with(|| ChannelStatistics::new(num_channels))
+                        .push_channel_major(&self.temp_data_buffer, num_samples);
                 }
 
                 // Cast to target type and create array
                 let typed_data: Vec<$type> =
                     self.temp_data_buffer.iter().map(|&x| x as $type).collect();
-                let data_array =
-                    Array2::<$type>::from_shape_vec((num_channels, num_samples), typed_data)?;
-
-                // Define start indices for writing
-                let start_indices = &[0u64, self.current_length as u64];
-
-                // Write to Zarr array
-                self.data_array.store_array_subset_ndarray::<$type, Ix2>(start_indices, data_array)?;
+                SampleBatch::$variant(Array2::<$type>::from_shape_vec((num_channels, num_samples), typed_data)?)
             }};
         }
 
-        match self.channel_format {
-            lsl::ChannelFormat::Float32 => write_samples!(f32, Float32),
-            lsl::ChannelFormat::Double64 => write_samples!(f64, Float64),
-            lsl::ChannelFormat::Int32 => write_samples!(i32, Int32),
-            lsl::ChannelFormat::Int16 => write_samples!(i16, Int16),
-            lsl::ChannelFormat::Int8 => write_samples!(i8, Int8),
-            lsl::ChannelFormat::String => {
+        let batch = match &self.values {
+            BufferedValues::Float32(values) => batch_samples!(values, f32, Float32),
+            BufferedValues::Float64(values) => batch_samples!(values, f64, Float64),
+            BufferedValues::Int32(values) => batch_samples!(values, i32, Int32),
+            BufferedValues::Int16(values) => batch_samples!(values, i16, Int16),
+            BufferedValues::Int8(values) => batch_samples!(values, i8, Int8),
+            BufferedValues::String(values) if self.events => {
+                // Marker stream: one event string per sample in a 1D array
+                let mut event_data = Vec::with_capacity(num_samples);
+                for i in 0..num_samples {
+                    let event = values.get(i * num_channels).cloned().unwrap_or_default();
+                    *self.event_counts.entry(event.clone()).or_insert(0) += 1;
+                    event_data.push(event);
+                }
+                SampleBatch::Events(Array1::from_vec(event_data))
+            }
+            BufferedValues::String(values) => {
                 // For string format (event markers), use 2D array to match other formats
                 // Shape: [channels, samples]
-                self.temp_data_buffer.clear();
-
                 // Collect strings in column-major order (channel-first layout)
                 let mut string_data = Vec::with_capacity(num_channels * num_samples);
                 for channel in 0..num_channels {
-                    for i in 0..num_samples {
-                        if let SampleData::String(values) = &self.sample_buffer[i] {
-                            string_data.push(values[channel].clone());
-                        }
-                    }
+                    string_data.extend((0..num_samples).map(|i| values[i * num_channels + channel].clone()));
                 }
-
-                // Create 2D string array
-                let data_array = Array2::<String>::from_shape_vec(
-                    (num_channels, num_samples),
-                    string_data
-                )?;
-
-                // Define start indices for writing
-                let start_indices = &[0u64, self.current_length as u64];
-
-                // Write to Zarr array
-                self.data_array.store_array_subset_ndarray::<String, Ix2>(start_indices, data_array)?;
-            }
-            _ => {
-                return Err(anyhow::anyhow!(
-                    "Unsupported channel format for Zarr: {:?}",
-                    self.channel_format
-                ));
+                SampleBatch::String(Array2::<String>::from_shape_vec((num_channels, num_samples), string_data)?)
             }
-        }
-
-        // Write time data starting at current_length
-        let time_start_indices = &[self.current_length as u64];
-        self.time_array.store_array_subset_ndarray::<f64, Ix1>(time_start_indices, time_array)?;
+        };
 
-        self.current_length = new_length;
-        self.sample_buffer.clear();
-        self.time_buffer.clear();
+        self.queue_write(WriteJob {
+            num_channels,
+            batch,
+            time: time_array,
+            flags,
+        })?;
 
-        // Monitor flush performance and detect backpressure
-        let flush_duration = flush_start.elapsed();
-        self.last_flush_duration = flush_duration;
+        self.current_length += num_samples;
+        self.values.drain_front(num_samples * num_channels);
         self.last_flush_time = Instant::now();
+        Ok(())
+    }
 
-        // Warn about slow flushes that might indicate backpressure
-        if flush_duration > Duration::from_millis(100) {
-            self.slow_flush_warnings += 1;
-            if self.slow_flush_warnings <= 5 {
-                // Only warn first 5 times
-                println!(
-                    "Warning: Slow Zarr flush detected:\t{:.1}ms for {} samples (warning {}/5)",
-                    flush_duration.as_millis(),
-                    num_samples,
-                    self.slow_flush_warnings
-                );
+    /// Hand a flush to the writer thread, waiting for room if the queue is full
+    fn queue_write(&mut self, job: WriteJob) -> Result<()> {
+        let requests = self.requests.as_ref().context("Zarr writer thread has stopped")?;
+        let depth = self.shared.queue_depth.fetch_add(1, Ordering::SeqCst) + 1;
+        self.shared.max_queue_depth.fetch_max(depth, Ordering::SeqCst);
+        let sent = match requests.try_send(WriterRequest::Write(job)) {
+            Ok(()) => Ok(()),
+            Err(TrySendError::Full(request)) => {
+                if !self.warned_queue_full {
+                    self.warned_queue_full = true;
+                    eprintln!(
+                        "Warning: Zarr write queue of {} is full, recording waits for the disk",
+                        self.stream_name
+                    );
+                }
+                let blocked = Instant::now();
+                let sent = requests.send(request).map_err(|_| ());
+                self.shared
+                    .blocked_us
+                    .fetch_add(blocked.elapsed().as_micros() as u64, Ordering::SeqCst);
+                sent
             }
-        }
+            Err(TrySendError::Disconnected(_)) => Err(()),
+        };
+        sent.map_err(|()| {
+            self.shared.queue_depth.fetch_sub(1, Ordering::SeqCst);
+            anyhow::anyhow!("Zarr writer thread of {} has stopped", self.stream_name)
+        })
+    }
 
-        if self.slow_flush_warnings <= 5 {
-            println!(
-                "Zarr: Wrote {} samples (total: {} samples, {:.1}ms flush)",
-                num_samples,
-                self.current_length,
-                flush_duration.as_millis()
-            );
+    /// Queue depth and write latency of the writer thread
+    pub fn writer_stats(&self) -> WriterStats {
+        let writes = self.shared.writes.load(Ordering::SeqCst);
+        let write_us = self.shared.write_us.load(Ordering::SeqCst);
+        WriterStats {
+            queue_depth: self.shared.queue_depth.load(Ordering::SeqCst),
+            queue_capacity: WRITE_QUEUE_CAPACITY,
+            max_queue_depth: self.shared.max_queue_depth.load(Ordering::SeqCst),
+            writes,
+            mean_write_ms: if writes == 0 { 0.0 } else { write_us as f64 / writes as f64 / 1000.0 },
+            max_write_ms: self.shared.max_write_us.load(Ordering::SeqCst) as f64 / 1000.0,
+            blocked_s: self.shared.blocked_us.load(Ordering::SeqCst) as f64 / 1e6,
         }
-
-        // Persist metadata AFTER writing data with exclusive lock to prevent race conditions
-        self.metadata_lock.lock_exclusive()?;
-        let metadata_result = (|| -> Result<()> {
-            self.data_array.store_metadata()?;
-            self.time_array.store_metadata()?;
-            Ok(())
-        })();
-        self.metadata_lock.unlock()?;
-        metadata_result?;
-
-        Ok(())
     }
 
     pub fn needs_flush(&self) -> bool {
         // Force flush if approaching memory limit (emergency flush)
-        if self.sample_buffer.len() >= self.max_buffer_size {
+        if self.time_buffer.len() >= self.max_buffer_size {
             return true;
         }
 
-        // Check buffer size threshold
-        if self.sample_buffer.len() >= self.buffer_size {
-            return true;
+        // The disk is behind: keep buffering rather than wait for room in the queue
+        if self.shared.queue_depth.load(Ordering::SeqCst) >= WRITE_QUEUE_CAPACITY {
+            return false;
         }
 
-        // Check time-based threshold (only if we have samples to flush)
-        if !self.sample_buffer.is_empty() && self.last_flush_time.elapsed() >= self.flush_interval {
-            return true;
+        // Sharded arrays are only written once a shard is complete
+        if let Some(shard) = self.shard_samples {
+            return self.current_length % shard + self.time_buffer.len() >= shard;
         }
 
-        // Force flush if we're accumulating samples faster than we can write (backpressure)
-        if self.sample_buffer.len() > self.buffer_size / 2
-            && self.last_flush_duration > Duration::from_millis(50)
-        {
+        // Check buffer size threshold
+        if self.time_buffer.len() >= self.buffer_size {
             return true;
         }
 
-        false
+        // Check time-based threshold (only if we have samples to flush)
+        !self.time_buffer.is_empty() && self.last_flush_time.elapsed() >= self.flush_interval
     }
 
     /// Get current buffer sample count for monitoring
     pub fn buffer_sample_count(&self) -> usize {
-        self.sample_buffer.len()
+        self.time_buffer.len()
     }
 
     /// Get buffer capacity for monitoring
@@ -322,18 +992,229 @@ impl ZarrWriter {
         self.max_buffer_size
     }
 
+    /// Samples written by this writer to the store that got furthest (primary or mirror)
+    pub fn samples_written(&self) -> usize {
+        self.shared.samples_written.load(Ordering::SeqCst)
+    }
+
+    /// Occurrences per marker value written by this writer (event streams only)
+    pub fn event_counts(&self) -> &BTreeMap<String, u64> {
+        &self.event_counts
+    }
+
+    /// Memory held by buffered samples, their timestamps and the flush scratch buffer
+    pub fn buffer_bytes(&self) -> usize {
+        self.values.heap_bytes()
+            + self.time_buffer.capacity() * size_of::<f64>()
+            + self.flag_buffer.as_ref().map_or(0, |flags| flags.capacity())
+            + self.temp_data_buffer.capacity() * size_of::<f64>()
+    }
+
+    /// Append a re-fetched stream info to `stream_info_history` in every store still being written
+    pub fn record_stream_info_snapshot(&self, snapshot: &InfoSnapshot) -> Result<()> {
+        let snapshot = snapshot.clone();
+        self.update_stream_attributes("stream info snapshot", move |attributes| append_snapshot(attributes, &snapshot))
+    }
+
+    /// Append a finished `--blocks` block to the `blocks` attribute in every store still being written
+    pub fn record_block(&self, block: &Block) -> Result<()> {
+        let block = block.clone();
+        self.update_stream_attributes("block boundaries", move |attributes| append_block(attributes, &block))
+    }
+
+    /// Store the `start_time` anchor, taken when the first sample is recorded
+    ///
+    /// A stream appended to keeps the anchor of its first recording, like `end_time`
+    /// follows the last one.
+    pub fn record_start_time(&self) -> Result<()> {
+        let anchor = super::time_anchor(lsl::local_clock());
+        self.update_stream_attributes("start time", move |attributes| {
+            attributes
+                .entry("start_time".to_string())
+                .or_insert_with(|| anchor.clone());
+            Ok(())
+        })
+    }
+
+    /// Append a `[pause_start, pause_end]` interval to the `pauses` attribute in every store still being written
+    pub fn record_pause(&self, pause: (f64, f64)) -> Result<()> {
+        self.update_stream_attributes("pause", move |attributes| {
+            append_pause(attributes, pause);
+            Ok(())
+        })
+    }
+
+    /// Append a detected clock jump to the `clock_jumps` attribute in every store still being written
+    pub fn record_clock_jump(&self, jump: &ClockJump) -> Result<()> {
+        let jump = *jump;
+        self.update_stream_attributes("clock jump", move |attributes| append_clock_jump(attributes, &jump))
+    }
+
+    /// Link the stream to its samples in the previous part of a rotated recording
+    pub fn record_continuation(&self, continuation: &Continuation) -> Result<()> {
+        let continuation = serde_json::to_value(continuation)?;
+        self.update_stream_attributes("part continuation", move |attributes| {
+            attributes.insert(CONTINUATION_ATTRIBUTE.to_string(), continuation.clone());
+            Ok(())
+        })
+    }
+
+    /// Store the `--quality` summary as the `quality` attribute in every store still being written
+    pub fn record_quality(&self, summary: &serde_json::Value) -> Result<()> {
+        let summary = summary.clone();
+        self.update_stream_attributes("quality summary", move |attributes| {
+            attributes.insert(QUALITY_ATTRIBUTE.to_string(), summary.clone());
+            Ok(())
+        })
+    }
+
+    /// Store the `--rule` triggers as the `rules` attribute in every store still being written
+    pub fn record_rules(&self, summary: &serde_json::Value) -> Result<()> {
+        let summary = summary.clone();
+        self.update_stream_attributes("rule triggers", move |attributes| {
+            attributes.insert(RULES_ATTRIBUTE.to_string(), summary.clone());
+            Ok(())
+        })
+    }
+
+    /// Store the `--plausible-range` counts as the `plausibility` attribute in every store still being written
+    pub fn record_plausibility(&self, summary: &serde_json::Value) -> Result<()> {
+        let summary = summary.clone();
+        self.update_stream_attributes("plausibility counts", move |attributes| {
+            attributes.insert(PLAUSIBILITY_ATTRIBUTE.to_string(), summary.clone());
+            Ok(())
+        })
+    }
+
+    /// Store the dropped-sample estimate as the `dropped_samples` attribute in every store still being written
+    pub fn record_dropped_samples(&self, estimate: &DropEstimate) -> Result<()> {
+        let estimate = serde_json::to_value(estimate)?;
+        self.update_stream_attributes("dropped-sample estimate", move |attributes| {
+            attributes.insert(DROPPED_SAMPLES_ATTRIBUTE.to_string(), estimate.clone());
+            Ok(())
+        })
+    }
+
+    /// Add the flagged sample counts of `--sample-flags` to the `sample_flags` attribute in every store still being written
+    pub fn record_flag_counts(&self, flagged: u64, counts: &BTreeMap<&str, u64>) -> Result<()> {
+        let counts: BTreeMap<String, u64> = counts.iter().map(|(flag, count)| (flag.to_string(), *count)).collect();
+        self.update_stream_attributes("sample flag counts", move |attributes| {
+            let counts: BTreeMap<&str, u64> = counts.iter().map(|(flag, count)| (flag.as_str(), *count)).collect();
+            add_flag_counts(attributes, flagged, &counts);
+            Ok(())
+        })
+    }
+
+    /// Queue a change to the stream group's attributes for the writer thread
+    ///
+    /// The writer thread applies it after the flushes queued before it, in every
+    /// healthy store under the metadata lock, and warns when it fails. Waits only
+    /// while the write queue is full.
+    fn update_stream_attributes(
+        &self,
+        what: &'static str,
+        update: impl Fn(&mut serde_json::Map<String, serde_json::Value>) -> serde_json::Result<()> + Send + 'static,
+    ) -> Result<()> {
+        let requests = self.requests.as_ref().context("Zarr writer thread has stopped")?;
+        requests
+            .send(WriterRequest::Attributes(what, Box::new(update)))
+            .map_err(|_| anyhow::anyhow!("Zarr writer thread of {} has stopped", self.stream_name))
+    }
+
     /// Finalize recording metadata with first and last timestamps
+    ///
+    /// Waits for the queued writes first. With a mirror, both stores are compared and
+    /// the outcome is stored as the stream's `mirror` attribute in each store.
     pub fn finalize_recording_metadata(
         &mut self,
         first_timestamp: Option<f64>,
         last_timestamp: Option<f64>,
+        connection: Option<&ConnectionStats>,
+    ) -> Result<()> {
+        // Everything queued must be on disk before the stores are compared and finalized
+        self.wait_for_writes()?;
+        let stores = self.shared.stores()?;
+        let mirror_status = stores.mirror.as_ref().map(|mirror| {
+            let check = stores.check_mirror(mirror, &self.stream_name);
+            match check {
+                Ok(()) => println!("Mirror: consistent ({} samples)", mirror.written),
+                Err(ref e) => {
+                    eprintln!("Warning: mirror check failed: {}", e);
+                    println!("STATUS MIRROR_INCONSISTENT");
+                    std::io::stdout().flush().ok();
+                }
+            }
+            serde_json::json!({
+                "primary": stores.primary.store_path.display().to_string(),
+                "mirror": mirror.store_path.display().to_string(),
+                "status": if check.is_ok() { "consistent" } else { "failed" },
+                "detail": check.err(),
+                "samples": mirror.written,
+            })
+        });
+
+        let bad_channels = self.channel_statistics.as_ref().map(|statistics| {
+            let detected = statistics.detect(&DetectionOptions::default());
+            for (index, reason) in &detected {
+                println!("Bad channel detected:\t{} ({})", index, reason);
+            }
+            detected
+        });
+
+        // Finalize every store still being written; fail only if none could be finalized
+        let mut finalized = false;
+        let mut last_error = None;
+        for target in stores.healthy() {
+            match self.finalize_target(
+                target,
+                first_timestamp,
+                last_timestamp,
+                mirror_status.as_ref(),
+                bad_channels.as_deref(),
+                connection,
+            ) {
+                Ok(()) => finalized = true,
+                Err(e) => {
+                    eprintln!("Warning: could not finalize {}: {:#}", target.store_path.display(), e);
+                    last_error = Some(e);
+                }
+            }
+        }
+
+        if let Some(ref remote) = self.remote {
+            // The recording is complete only once the final metadata is in the bucket
+            let stats = remote.sync()?;
+            println!(
+                "Uploaded to {}:\t{} files, {:.1} MB ({} multi-part)",
+                remote.url(),
+                stats.files,
+                stats.bytes as f64 / 1e6,
+                stats.multipart
+            );
+        }
+
+        match last_error {
+            Some(e) if !finalized => Err(e),
+            _ => Ok(()),
+        }
+    }
+
+    /// Write the final stream attributes to one store
+    fn finalize_target(
+        &self,
+        target: &StoreTarget,
+        first_timestamp: Option<f64>,
+        last_timestamp: Option<f64>,
+        mirror_status: Option<&serde_json::Value>,
+        bad_channels: Option<&[(usize, String)]>,
+        connection: Option<&ConnectionStats>,
     ) -> Result<()> {
         // Open the stream group to update its attributes
         let stream_path = format!("/{}", self.stream_name);
-        let mut stream_group = zarrs::group::Group::open(self.store.clone(), &stream_path)?;
+        let mut stream_group = zarrs::group::Group::open(target.store.clone(), &stream_path)?;
 
         // Acquire exclusive lock for metadata write
-        self.metadata_lock.lock_exclusive()?;
+        target.metadata_lock.lock_exclusive()?;
 
         // Add final recording metadata
         if let Some(first_ts) = first_timestamp {
@@ -350,15 +1231,79 @@ impl ZarrWriter {
             );
         }
 
+        stream_group.attributes_mut().insert(
+            "end_time".to_string(),
+            super::time_anchor(lsl::local_clock())
+        );
+
+        if self.events {
+            // Add this recording's counts to those of earlier recordings appended to the stream
+            let mut event_counts = self.event_counts.clone();
+            if let Some(existing) = stream_group.attributes().get("event_counts").and_then(|v| v.as_object()) {
+                for (event, count) in existing {
+                    *event_counts.entry(event.clone()).or_insert(0) += count.as_u64().unwrap_or(0);
+                }
+            }
+            stream_group.attributes_mut().insert(
+                "event_count".to_string(),
+                serde_json::json!(target.length)
+            );
+            stream_group.attributes_mut().insert(
+                "event_counts".to_string(),
+                serde_json::json!(event_counts)
+            );
+        }
+
+        if let Some(status) = mirror_status {
+            stream_group.attributes_mut().insert("mirror".to_string(), status.clone());
+        }
+
+        if let Some(stats) = connection {
+            stream_group
+                .attributes_mut()
+                .insert(CONNECTION_ATTRIBUTE.to_string(), serde_json::to_value(stats)?);
+        }
+
+        if let Some(detected) = bad_channels {
+            // Replace the marks of an earlier recording appended to the stream, keep the others
+            let attributes = serde_json::Value::Object(stream_group.attributes().clone());
+            let channel_count = target.data_array.shape().first().copied().unwrap_or(0) as usize;
+            let mut mask = BadChannelMask::from_attributes(&attributes, channel_count);
+            mask.clear_source("online");
+            for (index, reason) in detected {
+                if !mask.is_bad(*index) {
+                    mask.mark(BadChannel {
+                        index: *index,
+                        label: channel_label(attributes.get("stream_info").unwrap_or(&serde_json::Value::Null), *index),
+                        reason: reason.clone(),
+                        source: "online".to_string(),
+                    });
+                }
+            }
+            stream_group
+                .attributes_mut()
+                .insert(BAD_CHANNELS_ATTRIBUTE.to_string(), mask.to_json());
+        }
+
         // Note: requested_duration is already stored in recorder_config.duration
 
         // Store metadata to disk
         let result = stream_group.store_metadata();
 
         // Release lock
-        self.metadata_lock.unlock()?;
+        target.metadata_lock.unlock()?;
 
         result?;
         Ok(())
     }
 }
+
+impl Drop for ZarrWriter {
+    fn drop(&mut self) {
+        // Closing the queue ends the writer thread once it has written what is queued
+        self.requests.take();
+        if let Some(thread) = self.writer_thread.take() {
+            thread.join().ok();
+        }
+    }
+}
@@ -0,0 +1,105 @@
+#![cfg(feature = "async-core")]
+
+use anyhow::Result;
+use lsl_recording_toolbox::async_core::{poll_interval, serve_control_async, SessionControl, SessionState};
+use lsl_recording_toolbox::commands::Command;
+use serde_json::{json, Value};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::net::TcpStream;
+
+fn clock() -> f64 {
+    100.0
+}
+
+#[test]
+fn test_poll_interval() {
+    // 2.5 sample periods, kept between 5 ms and 100 ms
+    assert_eq!(poll_interval(100.0), Duration::from_millis(25));
+    assert_eq!(poll_interval(2000.0), Duration::from_millis(5));
+    assert_eq!(poll_interval(1.0), Duration::from_millis(100));
+    assert_eq!(poll_interval(0.0), Duration::from_millis(50));
+}
+
+#[tokio::test]
+async fn test_session_state_transitions() -> Result<()> {
+    let control = SessionControl::new(1, Vec::new(), clock);
+    let mut state = control.subscribe();
+    assert_eq!(control.state(), SessionState::default());
+
+    // Nothing to pause or resume yet
+    assert!(control.execute(&Command::Pause).is_err());
+    assert!(control.execute(&Command::Resume).is_err());
+
+    control.execute(&Command::Start)?;
+    assert!(state.has_changed()?);
+    assert!(state.borrow_and_update().recording);
+
+    control.execute(&Command::Pause)?;
+    assert_eq!(
+        control.state(),
+        SessionState {
+            recording: false,
+            paused: true,
+            quit: false
+        }
+    );
+    control.execute(&Command::Resume)?;
+    assert!(control.state().recording && !control.state().paused);
+    assert!(control.execute(&Command::Resume).is_err());
+
+    control.execute(&Command::Stop)?;
+    control.execute(&Command::Quit)?;
+    assert_eq!(
+        control.state(),
+        SessionState {
+            recording: false,
+            paused: false,
+            quit: true
+        }
+    );
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_stop_after_waits_for_streams() -> Result<()> {
+    let control = SessionControl::new(2, Vec::new(), clock);
+    control.execute(&Command::Start)?;
+    control.execute(&Command::StopAfter(1))?;
+
+    // The countdown has not started while a stream is not ready
+    control.stream_ready();
+    tokio::time::sleep(Duration::from_millis(1500)).await;
+    assert!(control.state().recording);
+
+    control.stream_ready();
+    tokio::time::sleep(Duration::from_millis(1500)).await;
+    assert!(!control.state().recording);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_serve_control_async() -> Result<()> {
+    let received = Arc::new(Mutex::new(Vec::new()));
+    let sink = received.clone();
+    let address = serve_control_async(0, move |command| {
+        sink.lock().unwrap().push(command.clone());
+        Ok(())
+    })
+    .await?;
+
+    let stream = TcpStream::connect(("127.0.0.1", address.port())).await?;
+    let (reader, mut writer) = stream.into_split();
+    let mut replies = BufReader::new(reader).lines();
+    writer.write_all(b"{\"command\": \"start\", \"id\": 1}\n").await?;
+    let reply: Value = serde_json::from_str(&replies.next_line().await?.unwrap())?;
+    assert_eq!(reply, json!({"ok": true, "command": "start", "id": 1}));
+
+    writer.write_all(b"STOP_AFTER 2\n").await?;
+    let reply: Value = serde_json::from_str(&replies.next_line().await?.unwrap())?;
+    assert_eq!(reply["command"], "stop_after");
+
+    assert_eq!(*received.lock().unwrap(), vec![Command::Start, Command::StopAfter(2)]);
+    Ok(())
+}
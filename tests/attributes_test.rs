@@ -0,0 +1,85 @@
+use anyhow::Result;
+use lsl_recording_toolbox::demo::{generate_demo_store, DemoOptions};
+use lsl_recording_toolbox::zarr::{
+    get_alignment, get_recorder_config, get_stream_info, read_group_attributes, stream_sample_count, Alignment,
+    StreamInfoAttribute,
+};
+use serde_json::json;
+use std::sync::Arc;
+use zarrs::filesystem::FilesystemStore;
+
+#[test]
+fn test_typed_stream_attributes() -> Result<()> {
+    let dir = std::env::temp_dir().join(format!("lsl_attributes_{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    let store_path = dir.join("demo.zarr");
+    let options = DemoOptions {
+        duration: 6.0,
+        ..DemoOptions::default()
+    };
+    generate_demo_store(&store_path, &options)?;
+    let store = Arc::new(FilesystemStore::new(&store_path)?);
+
+    let info = get_stream_info(&store, "EEG")?;
+    assert_eq!(info.source_id.as_deref(), Some("demo_eeg"));
+    assert_eq!((info.channel_count, info.srate()), (Some(8), 250.0));
+    assert_eq!(info.channel_format.as_deref(), Some("Float32"));
+    assert_eq!(info.channels.len(), 8);
+    // The struct covers the whole stored schema
+    let stored = read_group_attributes(&store, "/EEG")?;
+    assert_eq!(serde_json::to_value(&info)?, stored["stream_info"]);
+
+    let config = get_recorder_config(&store, "EEG")?.expect("demo streams have a recorder_config");
+    assert_eq!(config.subject.as_deref(), Some("demo01"));
+    assert_eq!(config.generated_by.as_deref(), Some("lsl-demo"));
+    assert_eq!(config.chunk_samples, Some(100));
+    assert_eq!(config.other["seed"], 42);
+
+    let alignment = get_alignment(&store, "EMG")?.expect("lsl-demo synchronizes its streams");
+    assert_eq!(alignment.original_sample_count, 6000);
+    assert!(alignment.trim_start_index < alignment.trim_end_index);
+    assert!(alignment.trim_end_index <= alignment.original_sample_count);
+    assert_eq!(alignment.trimmed_sample_count, alignment.trim_end_index - alignment.trim_start_index);
+
+    assert!(get_stream_info(&store, "Missing").is_err());
+
+    std::fs::remove_dir_all(&dir)?;
+    Ok(())
+}
+
+#[test]
+fn test_attributes_from_json() {
+    // Before lsl-sync there is no alignment
+    let recorded = json!({"stream_info": {"type": "EMG", "nominal_srate": 2000.0}, "first_timestamp": 10.0});
+    assert_eq!(Alignment::from_attributes(&recorded), None);
+
+    // Older stores lack most fields; unknown fields are ignored
+    let info = StreamInfoAttribute::from_attributes(&recorded).unwrap();
+    assert_eq!(info.stream_type.as_deref(), Some("EMG"));
+    assert_eq!(info.channel_count, None);
+    assert_eq!(info.srate(), 2000.0);
+
+    let synced = json!({
+        "alignment_offset": -0.25,
+        "trim_start_index": 10,
+        "trim_end_index": 990,
+        "original_sample_count": 1000,
+        "trimmed_sample_count": 900,
+        "excluded_ranges": [[400, 480]],
+        "lsl_clock_offset": 0.001,
+    });
+    let alignment = Alignment::from_attributes(&synced).unwrap();
+    assert_eq!(alignment.alignment_offset, -0.25);
+    assert_eq!(alignment.excluded_ranges, [[400, 480]]);
+    assert_eq!(alignment.drift_correction, None);
+}
+
+#[test]
+fn test_stream_sample_count() {
+    // Timestamps of 0.0 are real samples once counted by the recorder
+    assert_eq!(stream_sample_count(&json!({"n_samples": 1500}), 2000), 1500);
+    // Never beyond the arrays, e.g. after an interrupted flush
+    assert_eq!(stream_sample_count(&json!({"n_samples": 2500}), 2000), 2000);
+    // Stores without the attribute count the whole time array
+    assert_eq!(stream_sample_count(&json!({}), 2000), 2000);
+}
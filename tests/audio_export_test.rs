@@ -0,0 +1,109 @@
+use anyhow::Result;
+use lsl_recording_toolbox::demo::{generate_demo_store, DemoOptions};
+use lsl_recording_toolbox::export::audio::{
+    default_audio_output_path, export_audio, parse_channel_spec, pcm_sample, wav_header, AudioOptions, ChannelLevels,
+    Normalization,
+};
+use lsl_recording_toolbox::export::ExportOptions;
+use std::path::{Path, PathBuf};
+
+#[test]
+fn test_channel_spec_and_output_path() {
+    assert_eq!(parse_channel_spec("EMG:3").unwrap(), ("EMG", "3"));
+    // Stream names may contain colons; the channel follows the last one
+    assert_eq!(parse_channel_spec("lab:EMG:C3").unwrap(), ("lab:EMG", "C3"));
+    for invalid in ["EMG", "EMG:", ":3"] {
+        assert!(parse_channel_spec(invalid).is_err(), "{}", invalid);
+    }
+    assert_eq!(
+        default_audio_output_path(Path::new("data/experiment.zarr/"), "EEG:C3 ref"),
+        PathBuf::from("data/experiment_EEG_C3_ref.wav")
+    );
+}
+
+#[test]
+fn test_levels_and_pcm() {
+    let levels = ChannelLevels::from_values(&[10.0, 12.0, 8.0, 10.0, f64::NAN]);
+    assert_eq!(levels.mean, 10.0);
+    assert_eq!(levels.peak, 2.0);
+    assert!((levels.rms - 2.0f64.sqrt()).abs() < 1e-12);
+    assert!((levels.gain(Normalization::Peak) - 0.495).abs() < 1e-12);
+    assert!((levels.gain(Normalization::Rms) - 0.1 / 2.0f64.sqrt()).abs() < 1e-12);
+    // A flat channel stays silent
+    assert_eq!(ChannelLevels::from_values(&[3.0; 4]).gain(Normalization::Peak), 0.0);
+
+    assert_eq!(pcm_sample(1.0), (i16::MAX, false));
+    assert_eq!(pcm_sample(-0.5), (-16384, false));
+    assert_eq!(pcm_sample(1.5), (i16::MAX, true));
+    assert_eq!(pcm_sample(f64::NAN), (0, false));
+
+    let header = wav_header(8000, 100);
+    assert_eq!(&header[0..4], b"RIFF");
+    assert_eq!(u32::from_le_bytes(header[4..8].try_into().unwrap()), 236);
+    assert_eq!(u32::from_le_bytes(header[24..28].try_into().unwrap()), 8000);
+    assert_eq!(u32::from_le_bytes(header[40..44].try_into().unwrap()), 200);
+}
+
+#[test]
+fn test_export_audio() -> Result<()> {
+    let dir = std::env::temp_dir().join(format!("lsl_audio_export_{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    let store_path = dir.join("demo.zarr");
+    let options = DemoOptions {
+        duration: 6.0,
+        ..DemoOptions::default()
+    };
+    generate_demo_store(&store_path, &options)?;
+
+    let output = dir.join("emg.wav");
+    let audio = AudioOptions {
+        channel: "EMG:EMG3".to_string(),
+        rate: 8000,
+        ..AudioOptions::default()
+    };
+    let summary = export_audio(&store_path, &output, &ExportOptions::default(), &audio)?;
+    assert_eq!(summary.channel, "EMG3");
+    // One audio sample every 1/8000 s of recording, both ends included
+    let expected = summary.recorded_duration * 8000.0;
+    assert!((summary.frames as f64 - expected - 0.5).abs() <= 0.5, "{} frames", summary.frames);
+    assert!(summary.recorded_duration > 5.5);
+    assert_eq!((summary.clipped, summary.silent), (0, 0));
+
+    let bytes = std::fs::read(&output)?;
+    assert_eq!(bytes.len() as u64, 44 + 2 * summary.frames);
+    assert_eq!(&bytes[36..40], b"data");
+    // Peak normalization: the loudest sample comes close to full scale
+    let loudest = bytes[44..]
+        .chunks(2)
+        .map(|pcm| i16::from_le_bytes([pcm[0], pcm[1]]).unsigned_abs())
+        .max()
+        .unwrap();
+    assert!(loudest > i16::MAX as u16 / 2 && loudest <= 32_440, "loudest sample {}", loudest);
+
+    // Time compression halves the audio; the channel index works as well
+    let fast = AudioOptions {
+        channel: "EMG:2".to_string(),
+        rate: 8000,
+        speed: 2.0,
+        ..AudioOptions::default()
+    };
+    let summary = export_audio(&store_path, &dir.join("fast.wav"), &ExportOptions::default(), &fast)?;
+    assert!((summary.duration - summary.recorded_duration / 2.0).abs() < 0.001);
+
+    // Flat channel: silent file; markers and unknown channels are errors
+    let flat = AudioOptions {
+        channel: "EEG:Oz".to_string(),
+        ..AudioOptions::default()
+    };
+    assert_eq!(export_audio(&store_path, &dir.join("oz.wav"), &ExportOptions::default(), &flat)?.gain, 0.0);
+    for channel in ["Markers:0", "EMG:EMG9", "Missing:0"] {
+        let audio = AudioOptions {
+            channel: channel.to_string(),
+            ..AudioOptions::default()
+        };
+        assert!(export_audio(&store_path, &dir.join("x.wav"), &ExportOptions::default(), &audio).is_err(), "{}", channel);
+    }
+
+    std::fs::remove_dir_all(&dir)?;
+    Ok(())
+}
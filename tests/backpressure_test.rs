@@ -0,0 +1,53 @@
+use lsl_recording_toolbox::backpressure::{PushMonitor, MAX_CONSECUTIVE_FAILURES};
+use lsl_recording_toolbox::replay::ReplayClock;
+use std::time::Duration;
+
+#[test]
+fn test_late_pushes_warn_at_most_every_interval() -> anyhow::Result<()> {
+    let mut monitor = PushMonitor::new(1000.0, 0.1);
+    assert_eq!(monitor.record(Ok::<(), String>(()), 500, 0.01, 0.5)?, None);
+
+    // Falling behind: the first late push warns, the next ones within 5 s do not
+    let warning = monitor.record(Ok::<(), String>(()), 250, 0.3, 1.0)?.expect("late push warns");
+    assert!(warning.contains("0.300 s late"), "{}", warning);
+    assert_eq!(monitor.record(Ok::<(), String>(()), 250, 0.5, 2.0)?, None);
+    assert!(monitor.record(Ok::<(), String>(()), 0, 0.6, 6.5)?.is_some());
+
+    let stats = monitor.stats();
+    assert_eq!((stats.pushed, stats.late, stats.failed), (1000, 500, 0));
+    assert_eq!(stats.max_lag, 0.6);
+    assert!((stats.delivered_rate() - 1000.0 / 6.5).abs() < 1e-9);
+    assert!(stats.degraded());
+    assert!(stats.summary().contains("of 1000.0 Hz"));
+    Ok(())
+}
+
+#[test]
+fn test_failed_pushes() -> anyhow::Result<()> {
+    let mut monitor = PushMonitor::new(0.0, 0.1);
+    let warning = monitor.record(Err("buffer full"), 1, 0.0, 0.0)?.expect("failure warns");
+    assert!(warning.contains("push failed: buffer full"), "{}", warning);
+    // A successful push resets the run of failures
+    monitor.record(Ok::<(), &str>(()), 1, 0.0, 0.1)?;
+    for i in 1..MAX_CONSECUTIVE_FAILURES {
+        monitor.record(Err("buffer full"), 1, 0.0, i as f64)?;
+    }
+    assert!(monitor.record(Err("buffer full"), 1, 0.0, 200.0).is_err());
+    assert_eq!(monitor.stats().failed, MAX_CONSECUTIVE_FAILURES + 1);
+    assert_eq!(monitor.stats().pushed, 1);
+    Ok(())
+}
+
+#[test]
+fn test_replay_clock_lag() {
+    let clock = ReplayClock {
+        first_timestamp: 100.0,
+        origin: 0.0,
+        speed: 2.0,
+        realtime: true,
+    };
+    // Due 1 s after the start at 2x speed
+    assert_eq!(clock.lag(102.0, Duration::from_millis(1250)), 0.25);
+    assert_eq!(clock.lag(102.0, Duration::from_millis(500)), 0.0);
+    assert_eq!(ReplayClock { realtime: false, ..clock }.lag(102.0, Duration::from_secs(9)), 0.0);
+}
@@ -0,0 +1,63 @@
+use serde_json::json;
+
+use lsl_recording_toolbox::badchannels::{BadChannel, BadChannelMask, ChannelStatistics, DetectionOptions};
+
+fn bad(index: usize, source: &str) -> BadChannel {
+    BadChannel {
+        index,
+        label: format!("ch{}", index),
+        reason: "test".to_string(),
+        source: source.to_string(),
+    }
+}
+
+#[test]
+fn test_mask_round_trip() {
+    let mut mask = BadChannelMask::new(4);
+    mask.mark(bad(2, "manual"));
+    mask.mark(bad(0, "detected"));
+    // Marking again replaces the earlier mark
+    mask.mark(bad(2, "detected"));
+    assert_eq!(mask.mask(), vec![true, false, true, false]);
+    assert_eq!(mask.good_channels(), vec![1, 3]);
+
+    let attributes = json!({ "bad_channels": mask.to_json() });
+    let loaded = BadChannelMask::from_attributes(&attributes, 4);
+    assert_eq!(loaded, mask);
+
+    let mut loaded = loaded;
+    loaded.clear_source("detected");
+    assert!(loaded.channels.is_empty());
+    assert!(!loaded.clear(1));
+}
+
+#[test]
+fn test_missing_attribute() {
+    let mask = BadChannelMask::from_attributes(&json!({}), 3);
+    assert_eq!(mask.mask(), vec![false; 3]);
+}
+
+#[test]
+fn test_detect() {
+    // ch0 normal, ch1 flat, ch2 noisy, ch3 NaN, ch4 normal
+    let mut statistics = ChannelStatistics::new(5);
+    for i in 0..1000 {
+        let wave = (i as f64 * 0.1).sin();
+        statistics.push_interleaved(&[wave, 5.0, wave * 100.0, f64::NAN, wave * 1.5]);
+    }
+    let detected = statistics.detect(&DetectionOptions::default());
+    let indices: Vec<usize> = detected.iter().map(|(i, _)| *i).collect();
+    assert_eq!(indices, vec![1, 2, 3]);
+    assert_eq!(detected[0].1, "flat");
+}
+
+#[test]
+fn test_channel_major_matches_interleaved() {
+    let mut interleaved = ChannelStatistics::new(2);
+    interleaved.push_interleaved(&[1.0, 0.0, 2.0, 0.0, 3.0, 0.0]);
+    let mut channel_major = ChannelStatistics::new(2);
+    channel_major.push_channel_major(&[1.0, 2.0, 3.0, 0.0, 0.0, 0.0], 3);
+    let options = DetectionOptions::default();
+    assert_eq!(interleaved.detect(&options), channel_major.detect(&options));
+    assert_eq!(interleaved.detect(&options), vec![(1, "flat".to_string())]);
+}
@@ -0,0 +1,31 @@
+use lsl_recording_toolbox::calibration::{match_onsets, EdgeDetector, OffsetStats};
+
+#[test]
+fn test_edge_detector_finds_pulse_onsets() {
+    let signal = [1.0, 1.0, 0.0, 0.0, 1.0, 1.0, 0.0, 1.0];
+    let mut detector = EdgeDetector::new(0.5);
+    let onsets: Vec<usize> = signal
+        .iter()
+        .enumerate()
+        .filter(|(_, v)| detector.is_onset(**v))
+        .map(|(i, _)| i)
+        .collect();
+
+    // A stream starting mid-pulse does not count as an onset
+    assert_eq!(onsets, vec![4, 7]);
+}
+
+#[test]
+fn test_match_onsets_and_stats() {
+    let truth = [10.0, 11.0, 12.0, 13.0];
+    // Pulse at 12.0 was missed; 20.0 is an unrelated crossing
+    let observed = [10.004, 11.006, 13.005, 20.0];
+    let offsets = match_onsets(&truth, &observed, 0.5);
+    assert_eq!(offsets.len(), 3);
+
+    let stats = OffsetStats::from_offsets(&offsets).unwrap();
+    assert!((stats.mean - 0.005).abs() < 1e-9);
+    assert!((stats.min - 0.004).abs() < 1e-9);
+    assert!((stats.max - 0.006).abs() < 1e-9);
+    assert!(OffsetStats::from_offsets(&[]).is_none());
+}
@@ -0,0 +1,83 @@
+use anyhow::Result;
+use lsl_recording_toolbox::captures::{read_captures, CaptureTrigger, ANNEX_GROUP};
+use lsl_recording_toolbox::zarr::is_store_group;
+use std::path::PathBuf;
+use std::sync::Arc;
+use zarrs::filesystem::FilesystemStore;
+use zarrs::group::GroupBuilder;
+
+fn empty_store(name: &str) -> Result<PathBuf> {
+    let path = std::env::temp_dir().join(format!("lsl_captures_{}_{}.zarr", name, std::process::id()));
+    let _ = std::fs::remove_dir_all(&path);
+    std::fs::create_dir_all(&path)?;
+    let store = Arc::new(FilesystemStore::new(&path)?);
+    GroupBuilder::new().build(store, "/")?.store_metadata()?;
+    Ok(path)
+}
+
+fn markers(values: &[&str]) -> Vec<String> {
+    values.iter().map(|v| v.to_string()).collect()
+}
+
+#[test]
+fn test_captures_store_time_anchors() -> Result<()> {
+    let path = empty_store("anchors")?;
+    let mut trigger = CaptureTrigger::new(markers(&["stimulus"]), None, "Markers".to_string(), &path);
+
+    assert!(trigger.sample(&path, &markers(&["response"]), 10.0, 10.001, 1_700_000_000.0).is_none());
+    let capture = trigger
+        .sample(&path, &markers(&[" stimulus "]), 11.0, 11.002, 1_700_000_001.0)
+        .expect("stimulus takes a capture");
+    assert_eq!((capture.index, capture.marker.as_str()), (1, "stimulus"));
+    trigger.sample(&path, &markers(&["stimulus"]), 12.0, 12.001, 1_700_000_002.0);
+    trigger.finish();
+
+    let captures = read_captures(&path);
+    assert_eq!(captures.len(), 2);
+    assert_eq!(captures[0].stream, "Markers");
+    assert_eq!((captures[0].marker_time, captures[0].lsl_time), (11.0, 11.002));
+    assert_eq!(captures[1].wall_clock, 1_700_000_002.0);
+    assert!(captures[0].duration_s.is_none() && captures[0].files.is_empty());
+
+    // A new recorder into the same store numbers on
+    let mut trigger = CaptureTrigger::new(markers(&["stimulus"]), None, "Markers".to_string(), &path);
+    assert_eq!(trigger.sample(&path, &markers(&["stimulus"]), 13.0, 13.0, 0.0).map(|c| c.index), Some(3));
+
+    std::fs::remove_dir_all(&path)?;
+    Ok(())
+}
+
+#[cfg(unix)]
+#[test]
+fn test_capture_command_output_is_listed() -> Result<()> {
+    let path = empty_store("command")?;
+    let command = "echo $LSL_CAPTURE_MARKER > {file}.txt".to_string();
+    let mut trigger = CaptureTrigger::new(markers(&["photo"]), Some(command), "Markers".to_string(), &path);
+    trigger.sample(&path, &markers(&["photo"]), 5.0, 5.0, 0.0);
+    trigger.finish();
+
+    let captures = read_captures(&path);
+    assert_eq!(captures.len(), 1);
+    assert_eq!(captures[0].exit_code, Some(0));
+    assert_eq!(captures[0].files, vec!["capture_0001.txt".to_string()]);
+    let written = std::fs::read_to_string(path.join(ANNEX_GROUP).join("capture_0001.txt"))?;
+    assert_eq!(written.trim(), "photo");
+
+    // A failing command is stored with its error
+    let mut trigger = CaptureTrigger::new(markers(&["photo"]), Some("exit 3".to_string()), "Markers".to_string(), &path);
+    trigger.sample(&path, &markers(&["photo"]), 6.0, 6.0, 0.0);
+    trigger.finish();
+    let failed = &read_captures(&path)[1];
+    assert_eq!(failed.exit_code, Some(3));
+    assert!(failed.error.is_some());
+
+    std::fs::remove_dir_all(&path)?;
+    Ok(())
+}
+
+#[test]
+fn test_annex_is_not_a_stream() {
+    assert!(is_store_group("annex"));
+    assert!(is_store_group("meta"));
+    assert!(!is_store_group("EEG"));
+}
@@ -0,0 +1,90 @@
+use lsl_recording_toolbox::chunked::{for_each_kept_run, pulls_in_chunks, ChunkQueue, DEFAULT_CHUNK_PULL_RATE};
+
+#[test]
+fn test_pulls_in_chunks() {
+    assert!(pulls_in_chunks(4000.0, DEFAULT_CHUNK_PULL_RATE));
+    assert!(pulls_in_chunks(1000.0, 1000.0));
+    assert!(!pulls_in_chunks(500.0, DEFAULT_CHUNK_PULL_RATE));
+    // Irregular streams and a disabled threshold
+    assert!(!pulls_in_chunks(0.0, DEFAULT_CHUNK_PULL_RATE));
+    assert!(!pulls_in_chunks(4000.0, 0.0));
+}
+
+/// Refill `queue` with a flat chunk, as `pull_chunk_buf` would
+fn refill(queue: &mut ChunkQueue<f32>, data: &[f32], timestamps: &[f64]) {
+    queue
+        .refill(|buf, ts| {
+            buf.extend_from_slice(data);
+            ts.extend_from_slice(timestamps);
+            Ok::<_, ()>(())
+        })
+        .unwrap();
+}
+
+#[test]
+fn test_chunk_queue_hands_out_samples_in_order() {
+    let mut queue = ChunkQueue::new();
+    let mut buf = vec![0.0f32; 2];
+    assert_eq!(queue.pop_into(&mut buf), None);
+
+    refill(&mut queue, &[1.0, 2.0, 3.0, 4.0, 5.0, 6.0], &[10.0, 0.0, 10.5]);
+    // Samples without a timestamp are skipped
+    assert_eq!(queue.len(), 2);
+    assert_eq!(queue.pop_into(&mut buf), Some(10.0));
+    assert_eq!(buf, vec![1.0, 2.0]);
+    assert_eq!(queue.pop_into(&mut buf), Some(10.5));
+    assert_eq!(buf, vec![5.0, 6.0]);
+    assert!(queue.is_empty());
+
+    // The buffers are reused for the next chunk
+    refill(&mut queue, &[7.0, 8.0], &[11.0]);
+    assert_eq!(queue.pop_into(&mut buf), Some(11.0));
+    assert_eq!(buf, vec![7.0, 8.0]);
+    assert_eq!(queue.pop_into(&mut buf), None);
+
+    // Empty and failed pulls do not count
+    refill(&mut queue, &[], &[]);
+    assert!(queue.refill(|_, _| Err("inlet lost")).is_err());
+    assert!(queue.is_empty());
+    assert_eq!(queue.chunk_stats(), (2, 1.5, 2));
+}
+
+#[test]
+fn test_chunk_queue_hands_unwritten_samples_to_the_writer_once() {
+    let mut queue = ChunkQueue::new();
+    let mut buf = vec![0.0f32; 2];
+    refill(&mut queue, &[1.0, 2.0, 3.0, 4.0, 5.0, 6.0], &[10.0, 10.25, 10.5]);
+    assert!(!queue.last_written());
+
+    // The first sample is handed out before the rest of the chunk goes to the writer
+    assert_eq!(queue.pop_into(&mut buf), Some(10.0));
+    assert!(!queue.last_written());
+    let (data, timestamps) = queue.take_unwritten();
+    assert_eq!((data, timestamps), (&[3.0, 4.0, 5.0, 6.0][..], &[10.25, 10.5][..]));
+    assert_eq!(queue.take_unwritten(), (&[][..], &[][..]));
+
+    // The remaining samples are still handed out, already written
+    assert_eq!(queue.pop_into(&mut buf), Some(10.25));
+    assert!(queue.last_written());
+    assert_eq!(queue.pop_into(&mut buf), Some(10.5));
+    assert!(queue.last_written());
+
+    // A new chunk starts unwritten
+    refill(&mut queue, &[7.0, 8.0], &[11.0]);
+    assert!(!queue.last_written());
+    assert_eq!(queue.take_unwritten(), (&[7.0, 8.0][..], &[11.0][..]));
+}
+
+#[test]
+fn test_for_each_kept_run() {
+    let runs = |timestamps: &[f64]| {
+        let mut runs = Vec::new();
+        for_each_kept_run(timestamps, |ts| !(2.0..3.0).contains(&ts), |run| runs.push(run));
+        runs
+    };
+    assert_eq!(runs(&[1.0, 1.5, 1.75]), vec![0..3]);
+    // Unstamped samples and samples `keep` rejects split the runs
+    assert_eq!(runs(&[1.0, 0.0, 1.5, 2.0, 2.5, 3.0, 3.5]), vec![0..1, 2..3, 5..7]);
+    assert_eq!(runs(&[0.0, 2.0]), vec![]);
+    assert_eq!(runs(&[]), vec![]);
+}
@@ -0,0 +1,78 @@
+use anyhow::Result;
+use lsl_recording_toolbox::zarr::clean::{clean_store, SYNC_OUTPUTS};
+use serde_json::{json, Value};
+use std::path::Path;
+
+/// Write a group's zarr.json with `attributes`
+fn write_group(path: &Path, attributes: Value) -> Result<()> {
+    std::fs::create_dir_all(path)?;
+    let meta = json!({ "zarr_format": 3, "node_type": "group", "attributes": attributes });
+    std::fs::write(path.join("zarr.json"), meta.to_string())?;
+    Ok(())
+}
+
+fn attributes(path: &Path) -> Result<Value> {
+    let meta: Value = serde_json::from_slice(&std::fs::read(path.join("zarr.json"))?)?;
+    Ok(meta["attributes"].clone())
+}
+
+/// A synchronized stream (EMG), a stream never synchronized (Markers) and one with retired raw arrays (EEG)
+fn write_store(path: &Path) -> Result<()> {
+    let _ = std::fs::remove_dir_all(path);
+    write_group(path, json!({}))?;
+    write_group(
+        &path.join("EMG"),
+        json!({
+            "stream_info": { "nominal_srate": 10.0 },
+            "first_timestamp": 100.0,
+            "alignment_offset": 0.0,
+            "trim_start_index": 2,
+            "trim_end_index": 8,
+            "original_sample_count": 10,
+            "trimmed_sample_count": 6,
+            "drift_correction": { "reference": "EEG" },
+            "resampling": { "grid": { "rate": 5.0 } },
+        }),
+    )?;
+    for name in ["data", "time"].iter().chain(SYNC_OUTPUTS.iter()) {
+        write_group(&path.join("EMG").join(name), json!({}))?;
+    }
+    write_group(&path.join("Markers"), json!({ "stream_info": { "nominal_srate": 0.0 } }))?;
+    write_group(&path.join("EEG"), json!({ "raw_retention": { "action": "drop" }, "trim_start_index": 0 }))?;
+    write_group(&path.join("EEG").join("aligned_time"), json!({}))?;
+    Ok(())
+}
+
+#[test]
+fn test_clean_store_restores_recorded_state() -> Result<()> {
+    let store = std::env::temp_dir().join(format!("lsl_clean_{}.zarr", std::process::id()));
+    write_store(&store)?;
+
+    let cleaned = clean_store(&store, &[])?;
+    let names: Vec<&str> = cleaned.iter().map(|s| s.stream.as_str()).collect();
+    assert_eq!(names, ["EEG", "EMG", "Markers"]);
+
+    // Retired raw arrays cannot be restored
+    assert!(cleaned[0].skipped.is_some() && !cleaned[0].changed());
+    assert!(store.join("EEG/aligned_time").exists());
+    assert_eq!(attributes(&store.join("EEG"))?["trim_start_index"], 0);
+
+    assert_eq!(cleaned[1].removed_arrays.len(), SYNC_OUTPUTS.len());
+    assert_eq!(cleaned[1].removed_attributes.len(), 7);
+    for name in SYNC_OUTPUTS {
+        assert!(!store.join("EMG").join(name).exists());
+    }
+    assert!(store.join("EMG/data").exists() && store.join("EMG/time").exists());
+    let emg = attributes(&store.join("EMG"))?;
+    assert_eq!(emg.as_object().unwrap().len(), 2);
+    assert_eq!(emg["first_timestamp"], 100.0);
+
+    assert!(!cleaned[2].changed());
+
+    // Nothing left on a second run; --stream selects streams
+    assert!(clean_store(&store, &[])?.iter().all(|s| !s.changed()));
+    assert_eq!(clean_store(&store, &["Markers".to_string()])?.len(), 1);
+
+    std::fs::remove_dir_all(&store)?;
+    Ok(())
+}
@@ -0,0 +1,68 @@
+use lsl_recording_toolbox::clock_jumps::{
+    append_clock_jump, read_clock_jumps, split_gaps, ClockJumpDetector, ClockJumpKind,
+};
+use lsl_recording_toolbox::gaps::find_gaps;
+
+#[test]
+fn test_wall_clock_jump() {
+    let mut detector = ClockJumpDetector::new(1.0);
+    assert!(detector.check_wall_clock(100.0, 1_700_000_000.0).is_none());
+    // Both clocks advance together
+    assert!(detector.check_wall_clock(101.0, 1_700_000_001.0).is_none());
+    // Suspended for a minute: the LSL clock stood still
+    let jump = detector.check_wall_clock(101.6, 1_700_000_061.6).unwrap();
+    assert_eq!(jump.kind, ClockJumpKind::WallClock);
+    assert_eq!((jump.before, jump.after), (101.0, 101.6));
+    assert!((jump.jump - 60.0).abs() < 1e-5);
+    assert!(jump.to_string().starts_with("wall clock jumped +60.00s at LSL 101.600"));
+    // An NTP step backwards
+    let jump = detector.check_wall_clock(102.6, 1_700_000_059.6).unwrap();
+    assert!((jump.jump + 3.0).abs() < 1e-5);
+
+    let mut disabled = ClockJumpDetector::new(0.0);
+    disabled.check_wall_clock(100.0, 0.0);
+    assert!(disabled.check_wall_clock(101.0, 1000.0).is_none());
+}
+
+#[test]
+fn test_timestamp_jump_and_gap_split() {
+    // 10 Hz stream arriving 50 ms after its timestamps; the clock jumps 5 s at sample 20
+    let mut detector = ClockJumpDetector::new(1.0);
+    let mut timestamps = Vec::new();
+    let mut jumps = Vec::new();
+    for i in 0..40 {
+        let arrival = 10.0 + i as f64 * 0.1 + 0.05;
+        let ts = 10.0 + i as f64 * 0.1 + if i >= 20 { 5.0 } else { 0.0 };
+        timestamps.push(ts);
+        jumps.extend(detector.check_sample(ts, arrival));
+    }
+    assert_eq!(jumps.len(), 1);
+    assert_eq!(jumps[0].kind, ClockJumpKind::Timestamps);
+    assert_eq!((jumps[0].before, jumps[0].after), (timestamps[19], timestamps[20]));
+
+    // Samples delivered late from a buffer are no jump
+    let mut buffered = ClockJumpDetector::new(1.0);
+    for i in 0..40 {
+        let arrival = if i < 20 { i as f64 * 0.1 } else { 10.0 };
+        assert!(buffered.check_sample(i as f64 * 0.1, arrival).is_none());
+    }
+
+    // Stored and read back, the jump explains the gap but not a real dropout
+    let mut attributes = serde_json::Map::new();
+    append_clock_jump(&mut attributes, &jumps[0]).unwrap();
+    let stored = read_clock_jumps(&serde_json::Value::Object(attributes));
+    assert_eq!(stored.len(), 1);
+    assert_eq!(stored[0].kind, ClockJumpKind::Timestamps);
+    assert!((stored[0].jump - 5.0).abs() < 1e-9);
+
+    timestamps.truncate(30);
+    timestamps.extend((0..10).map(|i| 20.0 + i as f64 * 0.1));
+    let report = find_gaps(&timestamps, 10.0, 2.0);
+    assert_eq!(report.count(), 2);
+    let (remaining, explained) = split_gaps(&report, &stored);
+    assert_eq!(explained.len(), 1);
+    assert_eq!(explained[0].index, 20);
+    assert_eq!(remaining.count(), 1);
+    assert_eq!(remaining.largest.map(|gap| gap.index), Some(30));
+    assert!((remaining.lost_time - (20.0 - timestamps[29] - 0.1)).abs() < 1e-9);
+}
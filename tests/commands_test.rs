@@ -0,0 +1,219 @@
+use anyhow::Result;
+use lsl_recording_toolbox::annotations::{read_annotations, Annotation};
+use lsl_recording_toolbox::commands::{
+    apply_annotation, apply_metadata_update, handle_request, serve_control, Command, MetadataUpdate, RecorderControls,
+};
+use lsl_recording_toolbox::manifest::read_manifest;
+use lsl_recording_toolbox::zarr::open_or_create_zarr_store;
+use serde_json::{json, Value};
+use std::io::{BufRead, BufReader, Write};
+use std::net::TcpStream;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+
+#[test]
+fn test_parse_text_commands() {
+    assert_eq!(Command::parse("START").unwrap(), Command::Start);
+    assert_eq!(Command::parse("  stop \n").unwrap(), Command::Stop);
+    assert_eq!(Command::parse("STOP_AFTER 30").unwrap(), Command::StopAfter(30));
+    assert_eq!(Command::parse("quit").unwrap(), Command::Quit);
+    assert_eq!(Command::parse("PAUSE").unwrap(), Command::Pause);
+    assert_eq!(Command::parse("resume").unwrap(), Command::Resume);
+    assert_eq!(
+        Command::parse("MARK  subject sneezed ").unwrap(),
+        Command::Mark("subject sneezed".to_string())
+    );
+    assert_eq!(
+        Command::parse(r#"METADATA {"notes": "block 2"}"#).unwrap(),
+        Command::Metadata(MetadataUpdate {
+            notes: Some("block 2".to_string()),
+            ..Default::default()
+        })
+    );
+
+    assert_eq!(Command::parse("STOP_AFTER soon").unwrap_err().to_string(), "bad STOP_AFTER arg");
+    assert_eq!(Command::parse("REWIND").unwrap_err().to_string(), "unknown command: REWIND");
+    assert!(Command::parse("PAUSE 10").is_err());
+    assert!(Command::parse("START now").is_err());
+    assert_eq!(Command::parse("mark").unwrap_err().to_string(), "MARK needs a label");
+    assert!(Command::parse(r#"METADATA {"mood": "good"}"#).is_err());
+
+    // Text form round trip, as forwarded to recorders
+    for command in [
+        Command::Start,
+        Command::StopAfter(5),
+        Command::Pause,
+        Command::Resume,
+        Command::Mark("electrode adjusted".to_string()),
+        Command::Metadata(MetadataUpdate {
+            subject: Some("P002".to_string()),
+            ..Default::default()
+        }),
+    ] {
+        assert_eq!(Command::parse(&command.to_line()).unwrap(), command);
+    }
+}
+
+#[test]
+fn test_parse_json_commands() {
+    assert_eq!(Command::from_json(&json!({"command": "start"})).unwrap(), Command::Start);
+    assert_eq!(
+        Command::from_json(&json!({"command": "STOP_AFTER", "seconds": 60})).unwrap(),
+        Command::StopAfter(60)
+    );
+    assert_eq!(
+        Command::from_json(&json!({"command": "metadata", "session_id": "S2", "id": 3})).unwrap(),
+        Command::Metadata(MetadataUpdate {
+            session_id: Some("S2".to_string()),
+            ..Default::default()
+        })
+    );
+    assert_eq!(Command::from_json(&json!({"command": "pause"})).unwrap(), Command::Pause);
+    assert_eq!(Command::from_json(&json!({"command": "Resume"})).unwrap(), Command::Resume);
+    assert_eq!(
+        Command::from_json(&json!({"command": "mark", "label": "blink"})).unwrap(),
+        Command::Mark("blink".to_string())
+    );
+    assert!(Command::from_json(&json!({"command": "mark", "label": " "})).is_err());
+    assert!(Command::from_json(&json!({"command": "stop_after"})).is_err());
+    assert!(Command::from_json(&json!({"seconds": 60})).is_err());
+    assert!(Command::from_json(&json!({"command": "rewind"})).is_err());
+}
+
+#[test]
+fn test_handle_request_replies() {
+    let received = Mutex::new(Vec::new());
+    let handler = |command: &Command| -> Result<()> {
+        if *command == Command::Quit {
+            anyhow::bail!("not now");
+        }
+        received.lock().unwrap().push(command.clone());
+        Ok(())
+    };
+    let reply = |request: &str| -> Value { serde_json::from_str(&handle_request(request, &handler).unwrap()).unwrap() };
+
+    assert_eq!(reply("START"), json!({"ok": true, "command": "start"}));
+    assert_eq!(
+        reply(r#"{"command": "stop_after", "seconds": 10, "id": "abc"}"#),
+        json!({"ok": true, "command": "stop_after", "id": "abc"})
+    );
+    assert_eq!(reply(r#"{"command": "quit", "id": 7}"#), json!({"ok": false, "error": "not now", "id": 7}));
+    assert_eq!(reply("{not json")["ok"], false);
+    assert_eq!(reply("FLY")["error"], "unknown command: FLY");
+    assert!(handle_request("   ", &handler).is_none());
+
+    assert_eq!(*received.lock().unwrap(), vec![Command::Start, Command::StopAfter(10)]);
+}
+
+#[test]
+fn test_metadata_update_writes_manifest() -> Result<()> {
+    let store = std::env::temp_dir().join(format!("lsl_commands_{}.zarr", std::process::id()));
+    let _ = std::fs::remove_dir_all(&store);
+    let update = MetadataUpdate {
+        notes: Some("eyes closed".to_string()),
+        ..Default::default()
+    };
+
+    // Not connected yet
+    assert!(apply_metadata_update(std::slice::from_ref(&store), &update).is_err());
+
+    open_or_create_zarr_store(&store, Some("P001"), Some("session_001"), Some("pilot"))?;
+    apply_metadata_update(std::slice::from_ref(&store), &update)?;
+    let manifest = read_manifest(&store).unwrap();
+    assert_eq!(manifest.subject.as_deref(), Some("P001"));
+    assert_eq!(manifest.notes.as_deref(), Some("eyes closed"));
+
+    assert!(apply_metadata_update(std::slice::from_ref(&store), &MetadataUpdate::default()).is_err());
+
+    std::fs::remove_dir_all(&store)?;
+    Ok(())
+}
+
+#[test]
+fn test_control_server_over_tcp() -> Result<()> {
+    let received = Arc::new(Mutex::new(Vec::new()));
+    let sink = received.clone();
+    let address = serve_control(0, move |command| {
+        sink.lock().unwrap().push(command.clone());
+        Ok(())
+    })?;
+
+    let mut stream = TcpStream::connect(("127.0.0.1", address.port()))?;
+    let mut replies = BufReader::new(stream.try_clone()?).lines();
+    writeln!(stream, r#"{{"command": "start", "id": 1}}"#)?;
+    let reply: Value = serde_json::from_str(&replies.next().unwrap()?)?;
+    assert_eq!(reply, json!({"ok": true, "command": "start", "id": 1}));
+
+    writeln!(stream, "STOP_AFTER 2")?;
+    let reply: Value = serde_json::from_str(&replies.next().unwrap()?)?;
+    assert_eq!(reply["command"], "stop_after");
+
+    assert_eq!(*received.lock().unwrap(), vec![Command::Start, Command::StopAfter(2)]);
+    Ok(())
+}
+
+#[test]
+fn test_pause_and_resume() -> Result<()> {
+    let controls = RecorderControls {
+        recording: Arc::new(AtomicBool::new(false)),
+        quit: Arc::new(AtomicBool::new(false)),
+        paused: Arc::new(AtomicBool::new(false)),
+        first_sample_pulled: Arc::new(AtomicBool::new(false)),
+        is_irregular_stream: Arc::new(AtomicBool::new(false)),
+        stores: Vec::new(),
+        clock: || 0.0,
+    };
+    let state = || (controls.recording.load(Ordering::SeqCst), controls.paused.load(Ordering::SeqCst));
+
+    assert!(controls.execute(&Command::Pause).is_err());
+    assert!(controls.execute(&Command::Resume).is_err());
+
+    controls.execute(&Command::Start)?;
+    controls.execute(&Command::Pause)?;
+    assert_eq!(state(), (false, true));
+    assert!(controls.execute(&Command::Pause).is_err());
+    controls.execute(&Command::Resume)?;
+    assert_eq!(state(), (true, false));
+
+    // STOP ends the run, pause included
+    controls.execute(&Command::Pause)?;
+    controls.execute(&Command::Stop)?;
+    assert_eq!(state(), (false, false));
+    assert!(controls.execute(&Command::Resume).is_err());
+    Ok(())
+}
+
+#[test]
+fn test_mark_writes_annotations() -> Result<()> {
+    let store = std::env::temp_dir().join(format!("lsl_commands_mark_{}.zarr", std::process::id()));
+    let _ = std::fs::remove_dir_all(&store);
+
+    // Not connected yet
+    assert!(apply_annotation(std::slice::from_ref(&store), "too early", 1.0).is_err());
+
+    open_or_create_zarr_store(&store, Some("P001"), Some("session_001"), None)?;
+    apply_annotation(std::slice::from_ref(&store), "subject sneezed", 1234.5)?;
+    let controls = RecorderControls {
+        recording: Arc::new(AtomicBool::new(true)),
+        quit: Arc::new(AtomicBool::new(false)),
+        paused: Arc::new(AtomicBool::new(false)),
+        first_sample_pulled: Arc::new(AtomicBool::new(true)),
+        is_irregular_stream: Arc::new(AtomicBool::new(false)),
+        stores: vec![store.clone()],
+        clock: || 1240.25,
+    };
+    controls.execute(&Command::Mark("electrode adjusted".to_string()))?;
+
+    assert_eq!(
+        read_annotations(&store),
+        vec![
+            Annotation { label: "subject sneezed".to_string(), time: 1234.5 },
+            Annotation { label: "electrode adjusted".to_string(), time: 1240.25 },
+        ]
+    );
+    // The session manifest is left alone
+    assert_eq!(read_manifest(&store).unwrap().subject.as_deref(), Some("P001"));
+
+    std::fs::remove_dir_all(&store)?;
+    Ok(())
+}
@@ -0,0 +1,82 @@
+//! Store fixtures shared by the integration tests
+//!
+//! Arrays are written in chunks of 4 samples so that every fixture spans several chunks.
+#![allow(dead_code)]
+
+use anyhow::Result;
+use ndarray::{Array1, Array2, Ix1, Ix2};
+use std::path::Path;
+use std::sync::Arc;
+use zarrs::array::{Array, ArrayBuilder, DataType, FillValue};
+use zarrs::filesystem::FilesystemStore;
+use zarrs::group::GroupBuilder;
+
+/// Samples per chunk of the fixture arrays
+pub const CHUNK_SAMPLES: u64 = 4;
+
+/// Replace whatever is at `path` with an empty store holding only the root group
+pub fn create_store(path: &Path) -> Result<Arc<FilesystemStore>> {
+    let _ = std::fs::remove_dir_all(path);
+    std::fs::create_dir_all(path)?;
+    let store = Arc::new(FilesystemStore::new(path)?);
+    GroupBuilder::new().build(store.clone(), "/")?.store_metadata()?;
+    Ok(store)
+}
+
+/// Create the group of stream `name` with `attributes` (a JSON object)
+pub fn write_stream_group(store: &Arc<FilesystemStore>, name: &str, attributes: serde_json::Value) -> Result<()> {
+    GroupBuilder::new()
+        .attributes(attributes.as_object().cloned().unwrap_or_default())
+        .build(store.clone(), &format!("/{}", name))?
+        .store_metadata()?;
+    Ok(())
+}
+
+/// Write `values` (channels × samples) as the Float32 data array of stream `name`
+pub fn write_data(store: &Arc<FilesystemStore>, name: &str, values: Array2<f32>) -> Result<()> {
+    let shape = vec![values.nrows() as u64, values.ncols() as u64];
+    let chunks = vec![values.nrows() as u64, CHUNK_SAMPLES];
+    let data = ArrayBuilder::new(shape, chunks, DataType::Float32, FillValue::from(0.0f32))
+        .build(store.clone(), &format!("/{}/data", name))?;
+    data.store_metadata()?;
+    if !values.is_empty() {
+        data.store_array_subset_ndarray::<f32, Ix2>(&[0, 0], values)?;
+    }
+    Ok(())
+}
+
+/// Write `timestamps` as the time array of stream `name`
+pub fn write_time(store: &Arc<FilesystemStore>, name: &str, timestamps: Array1<f64>) -> Result<Array<FilesystemStore>> {
+    let shape = vec![timestamps.len() as u64];
+    let time = ArrayBuilder::new(shape, vec![CHUNK_SAMPLES], DataType::Float64, FillValue::from(0.0f64))
+        .build(store.clone(), &format!("/{}/time", name))?;
+    time.store_metadata()?;
+    if !timestamps.is_empty() {
+        time.store_array_subset_ndarray::<f64, Ix1>(&[0], timestamps)?;
+    }
+    Ok(time)
+}
+
+/// Stream `name` with its group, data and time arrays
+pub fn write_stream(
+    store: &Arc<FilesystemStore>,
+    name: &str,
+    attributes: serde_json::Value,
+    values: Array2<f32>,
+    timestamps: Array1<f64>,
+) -> Result<()> {
+    write_stream_group(store, name, attributes)?;
+    write_data(store, name, values)?;
+    write_time(store, name, timestamps)?;
+    Ok(())
+}
+
+/// `channels` × `samples` values `channel * 10 + sample`
+pub fn ramp(channels: usize, samples: usize) -> Array2<f32> {
+    Array2::from_shape_fn((channels, samples), |(c, s)| (c * 10 + s) as f32)
+}
+
+/// `samples` timestamps from `first`, `period` seconds apart
+pub fn regular_timestamps(samples: usize, first: f64, period: f64) -> Array1<f64> {
+    Array1::from_shape_fn(samples, |s| first + s as f64 * period)
+}
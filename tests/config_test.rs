@@ -0,0 +1,124 @@
+use anyhow::Result;
+use clap::{Arg, ArgAction, Command};
+use lsl_recording_toolbox::config::{find_config, ProjectConfig, CONFIG_FILE};
+
+const RIG: &str = r#"
+output = "data/{subject}/session_{date}"
+subject_pattern = "sub-##"
+flush_interval = 2.5
+compressor = "zstd"
+
+[[streams]]
+source_id = "EMG_1234"
+name = "EMG"
+plausible_range = "±10 mV"
+
+[[streams]]
+source_id = "EEG_5678"
+name = "EEG"
+
+[tools.lsl-validate]
+threshold-ms = 50
+verbose = true
+
+[tools.lsl-multi-recorder]
+compressor = "none"
+"#;
+
+/// The options of a multi-recorder that matter here
+fn multi_recorder() -> Command {
+    Command::new("lsl-multi-recorder")
+        .arg(
+            Arg::new("source_ids")
+                .long("source-ids")
+                .num_args(1..)
+                .required_unless_present("record_all"),
+        )
+        .arg(Arg::new("stream_names").long("stream-names").num_args(1..))
+        .arg(Arg::new("record_all").long("record-all").action(ArgAction::SetTrue))
+        .arg(Arg::new("output").long("output").default_value("experiment"))
+        .arg(Arg::new("compressor").long("compressor").default_value("lz4"))
+}
+
+fn values(matches: &clap::ArgMatches, id: &str) -> Vec<String> {
+    matches.get_many::<String>(id).into_iter().flatten().cloned().collect()
+}
+
+#[test]
+fn test_project_config_defaults() -> Result<()> {
+    let config = ProjectConfig::from_toml(RIG)?;
+    assert_eq!(config.streams.len(), 2);
+
+    let recorder = config.defaults("lsl-recorder");
+    assert_eq!(recorder["output"], ["data/{subject}/session_{date}"]);
+    assert_eq!(recorder["flush_interval"], ["2.5"]);
+    // The stream list is only for the multi-stream tools
+    assert!(!recorder.contains_key("source_ids"));
+
+    let multi = config.defaults("lsl-multi-recorder");
+    assert_eq!(multi["source_ids"], ["EMG_1234", "EEG_5678"]);
+    assert_eq!(multi["stream_names"], ["EMG", "EEG"]);
+    // Plausibility ranges go to the recorder of their stream
+    assert_eq!(multi["plausible_range"], ["EMG: ±10 mV"]);
+    assert!(!recorder.contains_key("plausible_range"));
+    // Tool settings take precedence over shared ones
+    assert_eq!(multi["compressor"], ["none"]);
+
+    // Shared settings are for the recorders only
+    let validate = config.defaults("lsl-validate");
+    assert!(!validate.contains_key("output"));
+    assert_eq!(validate["threshold_ms"], ["50"]);
+    assert_eq!(validate["verbose"], ["true"]);
+    assert_eq!(config.form_values("lsl-spotcheck")["source_ids"], "EMG_1234,EEG_5678");
+
+    assert!(ProjectConfig::from_toml("outptu = \"x\"\n").is_err());
+    // Either every stream is named or none
+    let half_named = "[[streams]]\nsource_id = \"a\"\nname = \"A\"\n[[streams]]\nsource_id = \"b\"\n";
+    assert!(ProjectConfig::from_toml(half_named).is_err());
+    let bad_range = "[[streams]]\nsource_id = \"a\"\nplausible_range = \"10..-10\"\n";
+    assert!(ProjectConfig::from_toml(bad_range).is_err());
+    Ok(())
+}
+
+#[test]
+fn test_project_config_apply() -> Result<()> {
+    let config = ProjectConfig::from_toml(RIG)?;
+    let command = config.apply("lsl-multi-recorder", multi_recorder())?;
+
+    // The configured stream list stands in for the required --source-ids
+    let matches = command.clone().try_get_matches_from(["lsl-multi-recorder"])?;
+    assert_eq!(values(&matches, "source_ids"), ["EMG_1234", "EEG_5678"]);
+    assert_eq!(values(&matches, "output"), ["data/{subject}/session_{date}"]);
+    assert_eq!(values(&matches, "compressor"), ["none"]);
+
+    // Command-line options win, and configured streams do not conflict with --record-all
+    let matches = command
+        .clone()
+        .try_get_matches_from(["lsl-multi-recorder", "--output", "pilot", "--record-all"])?;
+    assert_eq!(values(&matches, "output"), ["pilot"]);
+    assert!(matches.get_flag("record_all"));
+
+    // Options a tool does not have are errors in its own table only
+    let command = Command::new("lsl-validate").arg(Arg::new("threshold_ms").long("threshold-ms"));
+    assert!(config.apply("lsl-validate", command.clone()).is_err());
+    assert!(ProjectConfig::default().apply("lsl-validate", command).is_ok());
+    Ok(())
+}
+
+#[test]
+fn test_find_config() -> Result<()> {
+    let dir = std::env::temp_dir().join(format!("lsl_project_config_{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    let nested = dir.join("data").join("P001");
+    std::fs::create_dir_all(&nested)?;
+    assert_eq!(find_config(&nested), None);
+
+    std::fs::write(dir.join(CONFIG_FILE), RIG)?;
+    assert_eq!(find_config(&nested), Some(dir.join(CONFIG_FILE)));
+    let config = ProjectConfig::load(&dir.join(CONFIG_FILE))?;
+    assert_eq!(config.path, Some(dir.join(CONFIG_FILE)));
+    assert_eq!(config.subject_pattern.as_deref(), Some("sub-##"));
+
+    std::fs::remove_dir_all(&dir)?;
+    Ok(())
+}
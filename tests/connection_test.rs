@@ -0,0 +1,127 @@
+mod common;
+
+use anyhow::Result;
+use lsl_recording_toolbox::connection::{ConnectionStats, ConnectionTracker, OutageInterval, CONNECTION_ATTRIBUTE};
+use lsl_recording_toolbox::session::summarize_store;
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+/// Create a minimal store with one stream carrying `connection` statistics
+fn write_store(path: &Path, connection: &ConnectionStats) -> Result<()> {
+    let store = common::create_store(path)?;
+    common::write_stream_group(&store, "EMG", serde_json::json!({ CONNECTION_ATTRIBUTE: connection }))
+}
+
+#[test]
+fn test_connection_tracker() {
+    let start = Instant::now();
+    let mut tracker = ConnectionTracker::new();
+    tracker.resolution_attempt(Duration::ZERO);
+    tracker.resolution_attempt(Duration::from_millis(112));
+    tracker.resolution_attempt(Duration::from_millis(105));
+
+    // Outage from the last sample at 10 s until samples return at 13.5 s
+    tracker.stream_lost(start + Duration::from_secs(10));
+    // Repeated reports keep the first start
+    tracker.stream_lost(start + Duration::from_secs(12));
+    tracker.stream_recovered(start + Duration::from_millis(13_500));
+    // Recovery without outage is ignored
+    tracker.stream_recovered(start + Duration::from_secs(14));
+
+    // Still lost when recording ends
+    tracker.stream_lost(start + Duration::from_secs(20));
+    assert!(tracker.is_lost());
+    let stats = tracker.finish(start + Duration::from_secs(25));
+
+    assert_eq!(stats.resolution_attempts, 3);
+    assert_eq!(stats.retry_delays_ms, vec![112, 105]);
+    assert_eq!(stats.total_retry_delay_ms(), 217);
+    assert_eq!(stats.reconnections, 1);
+    assert_eq!(stats.outages, 2);
+    assert!((stats.total_downtime_s - 8.5).abs() < 1e-9);
+    assert!((stats.longest_outage_s - 5.0).abs() < 1e-9);
+}
+
+#[test]
+fn test_connection_stats_in_session_summary() -> Result<()> {
+    let store = std::env::temp_dir().join(format!("lsl_connection_{}.zarr", std::process::id()));
+    let stats = ConnectionStats {
+        resolution_attempts: 2,
+        retry_delays_ms: vec![110],
+        reconnections: 1,
+        outages: 1,
+        total_downtime_s: 3.25,
+        longest_outage_s: 3.25,
+        outage_intervals: vec![OutageInterval {
+            start: Some(41.0),
+            end: Some(44.25),
+        }],
+        reconnect_attempts: 0,
+    };
+    write_store(&store, &stats)?;
+
+    let summary = summarize_store(&store)?;
+    assert_eq!(summary.streams[0].connection, Some(stats));
+    let json = serde_json::to_value(&summary)?;
+    assert_eq!(json["streams"][0]["connection"]["total_downtime_s"], 3.25);
+
+    // Stores recorded before the statistics existed
+    assert_eq!(ConnectionStats::from_attributes(&serde_json::json!({})), None);
+
+    std::fs::remove_dir_all(&store)?;
+    Ok(())
+}
+
+#[test]
+fn test_outage_intervals_and_reconnect_attempts() {
+    let start = Instant::now();
+    let mut tracker = ConnectionTracker::new();
+    tracker.resolution_attempt(Duration::ZERO);
+    assert_eq!(tracker.lost_for(start), None);
+
+    // Last sample at LSL 100.5, silent for 12 s, resolved anew twice meanwhile
+    tracker.stream_lost_after(start, Some(100.5));
+    assert_eq!(tracker.lost_for(start + Duration::from_secs(6)), Some(Duration::from_secs(6)));
+    tracker.reconnect_attempt();
+    tracker.reconnect_attempt();
+    tracker.stream_recovered_at(start + Duration::from_secs(12), Some(112.5));
+
+    // Lost again when recording ends
+    tracker.stream_lost_after(start + Duration::from_secs(20), Some(120.5));
+    let stats = tracker.finish(start + Duration::from_secs(23));
+
+    assert_eq!(stats.reconnect_attempts, 2);
+    assert_eq!(stats.outages, 2);
+    assert_eq!(
+        stats.outage_intervals,
+        vec![
+            OutageInterval {
+                start: Some(100.5),
+                end: Some(112.5)
+            },
+            OutageInterval {
+                start: Some(120.5),
+                end: None
+            },
+        ]
+    );
+    assert!((stats.total_downtime_s - 15.0).abs() < 1e-9);
+}
+
+#[test]
+fn test_connection_stats_without_outage_intervals() {
+    // Stores recorded before the intervals were kept
+    let attributes = serde_json::json!({
+        CONNECTION_ATTRIBUTE: {
+            "resolution_attempts": 1,
+            "retry_delays_ms": [],
+            "reconnections": 1,
+            "outages": 1,
+            "total_downtime_s": 2.5,
+            "longest_outage_s": 2.5
+        }
+    });
+    let stats = ConnectionStats::from_attributes(&attributes).expect("connection attribute");
+    assert!(stats.outage_intervals.is_empty());
+    assert_eq!(stats.reconnect_attempts, 0);
+}
@@ -0,0 +1,69 @@
+mod common;
+
+use anyhow::Result;
+use lsl_recording_toolbox::export::table::{export_tables, TableOptions};
+use lsl_recording_toolbox::export::{ExportFormat, ExportOptions, ExportStream, SampleBlock};
+use serde_json::json;
+use std::path::Path;
+use std::sync::Arc;
+use zarrs::filesystem::FilesystemStore;
+
+/// EMG: 2 channels, 16 samples in chunks of 4 with value `channel * 10 + sample`;
+/// the data chunk of samples 4-7 is truncated and the time chunk of samples 8-11 deleted
+fn write_damaged_store(path: &Path) -> Result<Arc<FilesystemStore>> {
+    let store = common::create_store(path)?;
+    let info = json!({ "channel_format": "Float32", "channel_count": 2, "nominal_srate": 4.0 });
+    let attributes = json!({ "stream_info": info });
+    common::write_stream(&store, "EMG", attributes, common::ramp(2, 16), common::regular_timestamps(16, 10.0, 0.25))?;
+
+    std::fs::write(path.join("EMG/data/c/0/1"), [1u8, 2, 3])?;
+    std::fs::remove_file(path.join("EMG/time/c/2"))?;
+    Ok(store)
+}
+
+#[test]
+fn test_damaged_chunks_are_substituted() -> Result<()> {
+    let path = std::env::temp_dir().join(format!("lsl_damaged_{}.zarr", std::process::id()));
+    let store = write_damaged_store(&path)?;
+
+    // By default a damaged chunk fails the read
+    let strict = ExportStream::open(&store, "EMG", false)?;
+    assert!(strict.read_block(0, 16).is_err());
+
+    let stream = ExportStream::open(&store, "EMG", false)?.tolerate_damage(true);
+    let (timestamps, values) = stream.read_block(0, 16)?;
+    assert_eq!(timestamps[3], 10.75);
+    assert!(timestamps[8..12].iter().all(|t| t.is_nan()));
+    assert_eq!(timestamps[12], 13.0);
+    let SampleBlock::Float32(values) = values else {
+        panic!("Float32 stream read as {:?}", values);
+    };
+    assert_eq!(&values[6..8], &[3.0, 13.0]);
+    assert_eq!(&values[8..16], &[0.0; 8]);
+    assert_eq!(&values[16..18], &[8.0, 18.0]);
+
+    let damaged = stream.damaged_ranges();
+    assert_eq!(damaged.len(), 2);
+    assert_eq!((damaged[0].array.as_str(), damaged[0].start, damaged[0].end), ("time", 8, 12));
+    assert_eq!(damaged[0].reason, "chunk missing");
+    assert_eq!((damaged[1].array.as_str(), damaged[1].start, damaged[1].end), ("data", 4, 8));
+
+    // Reading the same chunks again reports them once
+    stream.read_block(4, 8)?;
+    assert_eq!(stream.damaged_ranges().len(), 2);
+
+    // The export carries on past the damage
+    let output = path.with_extension("csv");
+    let options = ExportOptions {
+        tolerate_damage: true,
+        ..ExportOptions::default()
+    };
+    let tables = export_tables(&path, &output, ExportFormat::Csv, &options, &TableOptions::default())?;
+    assert_eq!(tables[0].rows, 16);
+    let strict = ExportOptions::default();
+    assert!(export_tables(&path, &output, ExportFormat::Csv, &strict, &TableOptions::default()).is_err());
+
+    std::fs::remove_dir_all(&path)?;
+    std::fs::remove_dir_all(&output)?;
+    Ok(())
+}
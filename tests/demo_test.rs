@@ -0,0 +1,97 @@
+use anyhow::Result;
+use lsl_recording_toolbox::annotations::read_annotations;
+use lsl_recording_toolbox::demo::{generate_demo_store, DemoOptions, DEMO_START};
+use lsl_recording_toolbox::devices::read_devices;
+use lsl_recording_toolbox::export::{list_streams, ExportStream};
+use lsl_recording_toolbox::manifest::read_manifest;
+use lsl_recording_toolbox::quality::QUALITY_ATTRIBUTE;
+use std::path::Path;
+use std::sync::Arc;
+use zarrs::filesystem::FilesystemStore;
+
+fn options(duration: f64) -> DemoOptions {
+    DemoOptions {
+        duration,
+        ..DemoOptions::default()
+    }
+}
+
+/// Timestamps and numeric values of a whole stream
+fn read_stream(store_path: &Path, name: &str) -> Result<(Vec<f64>, Option<Vec<f64>>)> {
+    let store = Arc::new(FilesystemStore::new(store_path)?);
+    let stream = ExportStream::open(&store, name, false)?;
+    let (timestamps, values) = stream.read_block(0, stream.sample_count)?;
+    Ok((timestamps, values.to_f64()))
+}
+
+#[test]
+fn test_demo_store_contents() -> Result<()> {
+    let dir = std::env::temp_dir().join(format!("lsl_demo_contents_{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    let store_path = dir.join("demo.zarr");
+
+    let streams = generate_demo_store(&store_path, &options(12.0))?;
+    let names: Vec<&str> = streams.iter().map(|s| s.name.as_str()).collect();
+    assert_eq!(names, ["EMG", "EEG", "ECG", "Markers"]);
+    assert_eq!(list_streams(&store_path)?, ["ECG", "EEG", "EMG", "Markers"]);
+    assert_eq!((streams[0].channels, streams[0].samples), (4, 12_000));
+    // Trials start every 3 s from 1 s on, three events each
+    assert_eq!(streams[3].samples, 9);
+
+    let store = Arc::new(FilesystemStore::new(&store_path)?);
+    for name in ["EMG", "EEG", "ECG", "Markers"] {
+        let stream = ExportStream::open(&store, name, true)?;
+        assert!(stream.has_aligned_time(), "{} has no aligned_time", name);
+        let first = stream.attributes["first_timestamp"].as_f64().unwrap();
+        assert!((first - DEMO_START).abs() < 1.5, "{}: {}", name, first);
+    }
+
+    // QC results: the dead Oz electrode is flat
+    let eeg = ExportStream::open(&store, "EEG", false)?;
+    assert_eq!(eeg.bad_channels.channels.len(), 1);
+    assert_eq!(eeg.bad_channels.channels[0].label, "Oz");
+    let flatline = eeg.attributes[QUALITY_ATTRIBUTE]["channels"][7]["flatline_s"].as_f64().unwrap();
+    assert!(flatline > 11.9, "Oz flat for {} s", flatline);
+    assert!(ExportStream::open(&store, "EMG", false)?.bad_channels.channels.is_empty());
+
+    let markers = ExportStream::open(&store, "Markers", false)?;
+    assert_eq!(markers.attributes["event_counts"]["stimulus"], 3);
+
+    assert_eq!(read_annotations(&store_path).len(), 3);
+    assert_eq!(read_devices(&store_path).unwrap().len(), 2);
+    let manifest = read_manifest(&store_path).unwrap();
+    assert_eq!(manifest.subject.as_deref(), Some("demo01"));
+    assert_eq!(manifest.streams.len(), 4);
+
+    // An existing store is only replaced on request
+    assert!(generate_demo_store(&store_path, &options(12.0)).is_err());
+    let overwrite = DemoOptions {
+        overwrite: true,
+        ..options(6.0)
+    };
+    assert_eq!(generate_demo_store(&store_path, &overwrite)?[0].samples, 6_000);
+
+    std::fs::remove_dir_all(&dir)?;
+    Ok(())
+}
+
+#[test]
+fn test_demo_store_is_deterministic() -> Result<()> {
+    let dir = std::env::temp_dir().join(format!("lsl_demo_deterministic_{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+
+    let first = dir.join("first.zarr");
+    let second = dir.join("second.zarr");
+    let other_seed = dir.join("other.zarr");
+    generate_demo_store(&first, &options(6.0))?;
+    generate_demo_store(&second, &options(6.0))?;
+    generate_demo_store(&other_seed, &DemoOptions { seed: 7, ..options(6.0) })?;
+
+    for name in ["EMG", "EEG", "ECG", "Markers"] {
+        assert_eq!(read_stream(&first, name)?, read_stream(&second, name)?, "{}", name);
+    }
+    assert_ne!(read_stream(&first, "EEG")?.1, read_stream(&other_seed, "EEG")?.1);
+
+    std::fs::remove_dir_all(&dir)?;
+    Ok(())
+}
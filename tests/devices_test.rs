@@ -0,0 +1,34 @@
+use lsl_recording_toolbox::devices::{devices_from_streams, Device};
+use serde_json::json;
+
+fn stream_info(description: serde_json::Value) -> serde_json::Value {
+    json!({"type": "EEG", "description": description})
+}
+
+#[test]
+fn test_device_from_stream_info() {
+    let info = stream_info(json!({"manufacturer": "BrainProducts", "model": " actiCHamp ", "serial": "17010768"}));
+    let device = Device::from_stream_info(&info).unwrap();
+    assert_eq!(device.manufacturer.as_deref(), Some("BrainProducts"));
+    assert_eq!(device.model.as_deref(), Some("actiCHamp"));
+    assert_eq!(device.serial_number.as_deref(), Some("17010768"));
+
+    assert!(Device::from_stream_info(&stream_info(json!({"manufacturer": "", "unit": "uV"}))).is_none());
+    assert!(Device::from_stream_info(&json!({})).is_none());
+}
+
+#[test]
+fn test_devices_deduplicated_across_streams() {
+    let amplifier = json!({"manufacturer": "TMSi", "model": "SAGA", "serial_number": "1001"});
+    let streams = vec![
+        ("EMG".to_string(), stream_info(amplifier.clone())),
+        ("Markers".to_string(), stream_info(json!({}))),
+        ("EMG_aux".to_string(), stream_info(amplifier)),
+        ("EMG2".to_string(), stream_info(json!({"manufacturer": "TMSi", "model": "SAGA", "serial_number": "1002"}))),
+    ];
+    let devices = devices_from_streams(&streams);
+    assert_eq!(devices.len(), 2);
+    assert_eq!(devices[0].streams, vec!["EMG", "EMG_aux"]);
+    assert_eq!(devices[1].streams, vec!["EMG2"]);
+    assert_eq!(Device::from_json(&devices[0].to_json()), devices[0]);
+}
@@ -0,0 +1,30 @@
+#![cfg(feature = "lsl")]
+
+use lsl_recording_toolbox::discovery::{sort_streams, DiscoveredStream};
+
+fn stream(name: &str, source_id: &str, uid: &str) -> DiscoveredStream {
+    DiscoveredStream {
+        name: name.to_string(),
+        stream_type: "EMG".to_string(),
+        source_id: source_id.to_string(),
+        channel_count: 8,
+        nominal_srate: 2000.0,
+        channel_format: "Float32".to_string(),
+        hostname: "lab-pc".to_string(),
+        uid: uid.to_string(),
+    }
+}
+
+#[test]
+fn test_sort_streams_drops_repeated_answers() {
+    // A restarted app next to its old outlet: same name and source_id, answers interleaved
+    let mut streams = vec![
+        stream("EMG", "amp1", "uid-old"),
+        stream("EMG", "amp1", "uid-new"),
+        stream("EEG", "amp2", "uid-eeg"),
+        stream("EMG", "amp1", "uid-old"),
+    ];
+    sort_streams(&mut streams);
+    let uids: Vec<&str> = streams.iter().map(|s| s.uid.as_str()).collect();
+    assert_eq!(uids, vec!["uid-eeg", "uid-new", "uid-old"]);
+}
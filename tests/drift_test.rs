@@ -0,0 +1,80 @@
+use lsl_recording_toolbox::drift::{fit_drift, fit_drift_model, linear_fit};
+
+#[test]
+fn test_linear_fit() {
+    let x = [0.0, 1.0, 2.0, 3.0];
+    let y = [1.0, 3.0, 5.0, 7.0];
+    let fit = linear_fit(&x, &y).unwrap();
+    assert!((fit.slope - 2.0).abs() < 1e-12);
+    assert!((fit.intercept - 1.0).abs() < 1e-12);
+    assert!((fit.r_squared - 1.0).abs() < 1e-12);
+    assert_eq!(fit.points, 4);
+
+    assert!(linear_fit(&[1.0], &[1.0]).is_none());
+    assert!(linear_fit(&[1.0, 1.0], &[1.0, 2.0]).is_none());
+}
+
+#[test]
+fn test_fit_drift_between_rates() {
+    // 100 s at 1000 Hz against 500 Hz whose samples fall 20 ppm later, offset by 5 ms
+    let reference: Vec<f64> = (0..100_000).map(|i| 1000.0 + i as f64 / 1000.0).collect();
+    let other: Vec<f64> = (0..50_000)
+        .map(|j| 1000.005 + (j as f64 / 500.0) * (1.0 + 20e-6))
+        .collect();
+
+    let drift = fit_drift(&reference, 1000.0, &other, 500.0).unwrap();
+    assert!((drift.ppm() - 20.0).abs() < 0.1);
+    assert!((drift.offset() - 0.005).abs() < 1e-6);
+    assert!(drift.fit.r_squared > 0.99);
+    assert!(drift.fit.points <= 10_000);
+    assert!((drift.total_drift() - 0.002).abs() < 1e-4);
+
+    // Irregular streams have no sample clock
+    assert!(fit_drift(&reference, 1000.0, &other, 0.0).is_none());
+}
+
+#[test]
+fn test_drift_model_corrects_linear_drift() {
+    // 1000 s at 100 Hz against 100 Hz running 30 ppm slow, starting 2 ms later
+    let reference: Vec<f64> = (0..100_000).map(|i| 500.0 + i as f64 / 100.0).collect();
+    let other: Vec<f64> = (0..100_000).map(|i| 500.002 + i as f64 / 100.0 * (1.0 + 30e-6)).collect();
+
+    let model = fit_drift_model(&reference, 100.0, &other, 100.0, 1).unwrap();
+    assert_eq!(model.segments.len(), 1);
+    assert_eq!(model.knots.len(), 2);
+    assert!((model.overall.ppm() - 30.0).abs() < 0.01);
+
+    // Drift is removed, the 2 ms start offset is kept
+    for i in [0, 50_000, 99_999] {
+        assert!((model.correct(other[i]) - (reference[i] + 0.002)).abs() < 1e-7);
+    }
+}
+
+#[test]
+fn test_drift_model_follows_changing_drift() {
+    // Drift of 20 ppm for 500 s, then -20 ppm
+    let reference: Vec<f64> = (0..100_000).map(|i| 500.0 + i as f64 / 100.0).collect();
+    let other: Vec<f64> = reference
+        .iter()
+        .map(|&t| {
+            let x = t - 500.0;
+            t + if x < 500.0 { 20e-6 * x } else { 20e-6 * 500.0 - 20e-6 * (x - 500.0) }
+        })
+        .collect();
+
+    let linear = fit_drift_model(&reference, 100.0, &other, 100.0, 1).unwrap();
+    let piecewise = fit_drift_model(&reference, 100.0, &other, 100.0, 2).unwrap();
+    assert_eq!(piecewise.segments.len(), 2);
+    assert!((piecewise.segments[0].fit.slope * 1e6 - 20.0).abs() < 0.01);
+    assert!((piecewise.segments[1].fit.slope * 1e6 + 20.0).abs() < 0.01);
+
+    let error = |model: &lsl_recording_toolbox::drift::DriftModel| {
+        reference
+            .iter()
+            .zip(&other)
+            .map(|(&r, &o)| (model.correct(o) - r).abs())
+            .fold(0.0, f64::max)
+    };
+    assert!(error(&piecewise) < 1e-5);
+    assert!(error(&linear) > 1e-3);
+}
@@ -0,0 +1,64 @@
+use lsl_recording_toolbox::dropped::{DropEstimate, DropEstimator, DROPPED_SAMPLES_ATTRIBUTE, DROP_REPORT_INTERVAL};
+
+#[test]
+fn test_irregular_streams_have_no_estimate() {
+    assert!(DropEstimator::new(0.0).is_none());
+}
+
+#[test]
+fn test_drop_estimate_and_reports() {
+    // 100 Hz, every 20th sample missing (5% loss)
+    let mut drops = DropEstimator::new(100.0).unwrap();
+    let mut reports = Vec::new();
+    for i in 0..2000 {
+        if i % 20 == 19 {
+            continue;
+        }
+        if let Some(estimate) = drops.sample(100.0 + i as f64 / 100.0) {
+            reports.push(estimate);
+        }
+    }
+    // Reported after the first interval; the second one is not complete
+    assert_eq!(DROP_REPORT_INTERVAL, 10.0);
+    assert_eq!(reports.len(), 1);
+    assert_eq!((reports[0].expected, reports[0].dropped), (1001, 50));
+
+    let estimate = drops.estimate();
+    assert_eq!(estimate.expected, 1999);
+    assert_eq!(estimate.received, 1900);
+    assert_eq!(estimate.dropped, 99);
+    assert!((estimate.percent() - 4.95).abs() < 0.01);
+    assert!(estimate.is_significant());
+}
+
+#[test]
+fn test_stops_are_not_counted_as_loss() {
+    let mut drops = DropEstimator::new(10.0).unwrap();
+    for i in 0..50 {
+        drops.sample(i as f64 / 10.0);
+    }
+    // Stopped for a minute, then 50 more samples
+    drops.interrupt();
+    for i in 0..50 {
+        assert_eq!(drops.sample(65.0 + i as f64 / 10.0), None);
+    }
+    let estimate = drops.estimate();
+    assert_eq!(estimate.expected, 100);
+    assert_eq!(estimate.dropped, 0);
+    assert!((estimate.duration_s - 9.8).abs() < 1e-9);
+    assert!(!estimate.is_significant());
+}
+
+#[test]
+fn test_drop_estimate_attribute() {
+    let estimate = DropEstimate {
+        nominal_srate: 2000.0,
+        duration_s: 52.0,
+        expected: 104_001,
+        received: 103_478,
+        dropped: 523,
+    };
+    let attributes = serde_json::json!({ DROPPED_SAMPLES_ATTRIBUTE: estimate });
+    assert_eq!(DropEstimate::from_attributes(&attributes), Some(estimate));
+    assert_eq!(DropEstimate::from_attributes(&serde_json::json!({})), None);
+}
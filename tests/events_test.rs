@@ -0,0 +1,63 @@
+use lsl_recording_toolbox::events::{EventGenerator, EventOptions};
+
+fn names(list: &[&str]) -> Vec<String> {
+    list.iter().map(|name| name.to_string()).collect()
+}
+
+#[test]
+fn test_regular_events_cycle_through_names() -> anyhow::Result<()> {
+    let options = EventOptions {
+        names: names(&["go", "stop"]),
+        interval: 0.5,
+        poisson: false,
+    };
+    let events: Vec<_> = EventGenerator::new(options, 1)?.take(5).collect();
+
+    let times: Vec<f64> = events.iter().map(|e| e.time).collect();
+    assert_eq!(times, [0.5, 1.0, 1.5, 2.0, 2.5]);
+    let labels: Vec<&str> = events.iter().map(|e| e.name.as_str()).collect();
+    assert_eq!(labels, ["go", "stop", "go", "stop", "go"]);
+    Ok(())
+}
+
+#[test]
+fn test_poisson_events() -> anyhow::Result<()> {
+    let options = EventOptions {
+        interval: 2.0,
+        poisson: true,
+        ..EventOptions::default()
+    };
+    let events: Vec<_> = EventGenerator::new(options.clone(), 7)?.take(5000).collect();
+
+    let waits: Vec<f64> = std::iter::once(events[0].time)
+        .chain(events.windows(2).map(|pair| pair[1].time - pair[0].time))
+        .collect();
+    assert!(waits.iter().all(|&wait| wait >= 0.0));
+    // Exponential waits: mean and standard deviation both equal the interval
+    let mean = waits.iter().sum::<f64>() / waits.len() as f64;
+    let std = (waits.iter().map(|w| (w - mean).powi(2)).sum::<f64>() / waits.len() as f64).sqrt();
+    assert!((mean - 2.0).abs() < 0.15, "mean wait {}", mean);
+    assert!((std - 2.0).abs() < 0.2, "wait std {}", std);
+    assert_eq!(events[3].name, "trial_start");
+
+    // The seed fixes the schedule
+    let again: Vec<_> = EventGenerator::new(options.clone(), 7)?.take(5000).collect();
+    assert_eq!(events, again);
+    let other: Vec<_> = EventGenerator::new(options, 8)?.take(5).collect();
+    assert_ne!(events[..5], other[..]);
+    Ok(())
+}
+
+#[test]
+fn test_invalid_event_options() {
+    let no_names = EventOptions {
+        names: Vec::new(),
+        ..EventOptions::default()
+    };
+    assert!(EventGenerator::new(no_names, 1).is_err());
+    let zero_interval = EventOptions {
+        interval: 0.0,
+        ..EventOptions::default()
+    };
+    assert!(EventGenerator::new(zero_interval, 1).is_err());
+}
@@ -0,0 +1,80 @@
+mod common;
+
+use anyhow::Result;
+use lsl_recording_toolbox::explore::{list_children, node_details, preview_array, NodeKind};
+use ndarray::Array2;
+use serde_json::json;
+use std::path::Path;
+
+/// Create a store with a `meta` group and a 2-channel stream of 12 samples with value `channel * 100 + sample`
+fn write_store(path: &Path) -> Result<()> {
+    let store = common::create_store(path)?;
+    common::write_stream_group(&store, "meta", json!({}))?;
+    let attributes = json!({ "stream_info": { "channel_format": "Float32", "nominal_srate": 10.0 } });
+    let values = Array2::from_shape_fn((2, 12), |(c, s)| (c * 100 + s) as f32);
+    common::write_stream(&store, "EMG", attributes, values, common::regular_timestamps(12, 100.0, 0.1))
+}
+
+#[test]
+fn test_store_tree_and_details() -> Result<()> {
+    let dir = std::env::temp_dir().join(format!("lsl_explore_tree_{}", std::process::id()));
+    let store_path = dir.join("session.zarr");
+    write_store(&store_path)?;
+
+    let top: Vec<(String, NodeKind)> = list_children(&store_path, "/")?
+        .into_iter()
+        .map(|node| (node.path, node.kind))
+        .collect();
+    assert_eq!(top, [("/EMG".to_string(), NodeKind::Group), ("/meta".to_string(), NodeKind::Group)]);
+
+    // Chunk directories have no zarr.json and are not listed
+    let stream: Vec<String> = list_children(&store_path, "/EMG")?.into_iter().map(|node| node.name).collect();
+    assert_eq!(stream, ["data", "time"]);
+    assert!(list_children(&store_path, "/EMG/data")?.is_empty());
+
+    let group = node_details(&store_path, "/EMG")?;
+    assert_eq!(group.kind, NodeKind::Group);
+    assert_eq!(group.attributes["stream_info"]["nominal_srate"], 10.0);
+    let lines = group.lines();
+    assert_eq!(lines[..3], ["Group", "Attributes:", "  {"]);
+
+    let data = node_details(&store_path, "/EMG/data")?;
+    assert_eq!(data.shape, Some(vec![2, 12]));
+    assert_eq!(data.data_type.as_deref(), Some("float32"));
+    assert_eq!(data.chunk_shape, Some(vec![2, 4]));
+    assert!(data.lines().contains(&"Attributes: none".to_string()));
+
+    std::fs::remove_dir_all(&dir)?;
+    Ok(())
+}
+
+#[test]
+fn test_array_preview() -> Result<()> {
+    let dir = std::env::temp_dir().join(format!("lsl_explore_preview_{}", std::process::id()));
+    let store_path = dir.join("session.zarr");
+    write_store(&store_path)?;
+
+    // Samples are rows, channels columns
+    let preview = preview_array(&store_path, "/EMG/data", 3, 1)?;
+    assert_eq!(
+        preview,
+        [
+            "  sample       ch0",
+            "       0  0.000000",
+            "       1  1.000000",
+            "       2  2.000000",
+            "... first 3 of 12 samples, 1 of 2 channels",
+        ]
+    );
+    let both = preview_array(&store_path, "/EMG/data", 2, 8)?;
+    assert_eq!(both[2], "       1    1.000000  101.000000");
+    assert_eq!(both.last().unwrap(), "... first 2 of 12 samples, 2 of 2 channels");
+
+    let time = preview_array(&store_path, "EMG/time", 20, 8)?;
+    assert_eq!(time.len(), 13);
+    assert_eq!(time[0], "  sample       value");
+    assert_eq!(time[12], "      11  101.100000");
+
+    std::fs::remove_dir_all(&dir)?;
+    Ok(())
+}
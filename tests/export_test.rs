@@ -0,0 +1,41 @@
+use std::path::{Path, PathBuf};
+
+use lsl_recording_toolbox::export::xdf::write_xdf;
+use lsl_recording_toolbox::export::{default_output_path, ExportFormat};
+
+#[test]
+fn test_default_output_path() {
+    assert_eq!(
+        default_output_path(Path::new("data/experiment.zarr"), ExportFormat::Xdf),
+        PathBuf::from("data/experiment.xdf")
+    );
+    // Trailing separators do not leave the extension on an empty component
+    assert_eq!(
+        default_output_path(Path::new("experiment.zarr/"), ExportFormat::Xdf),
+        PathBuf::from("experiment.xdf")
+    );
+    assert_eq!(
+        default_output_path(Path::new("experiment.zarr"), ExportFormat::Bdf),
+        PathBuf::from("experiment.bdf")
+    );
+    // Per-stream formats write into a directory
+    assert_eq!(
+        default_output_path(Path::new("data/experiment.zarr"), ExportFormat::Csv),
+        PathBuf::from("data/experiment_csv")
+    );
+}
+
+#[test]
+fn test_xdf_file_header() {
+    let mut out = Vec::new();
+    let summaries = write_xdf(&mut out, &[]).unwrap();
+    assert!(summaries.is_empty());
+
+    assert_eq!(&out[..4], b"XDF:");
+    // 1-byte length prefix, then the FileHeader tag
+    let length = out[5] as usize;
+    assert_eq!(out[4], 1);
+    assert_eq!(u16::from_le_bytes([out[6], out[7]]), 1);
+    assert_eq!(out.len(), 6 + length);
+    assert!(String::from_utf8_lossy(&out[8..]).contains("<version>1.0</version>"));
+}
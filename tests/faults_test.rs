@@ -0,0 +1,84 @@
+use lsl_recording_toolbox::drift::linear_fit;
+use lsl_recording_toolbox::faults::{FaultCounts, FaultInjector, FaultOptions};
+use lsl_recording_toolbox::gaps::{find_gaps, DEFAULT_GAP_FACTOR};
+
+const RATE: f64 = 100.0;
+const CHUNK: usize = 10;
+
+/// Timestamps of the kept samples of `seconds` of a 100 Hz stream, starting at 1000 s
+fn stamps(options: FaultOptions, seconds: u64) -> (Vec<f64>, FaultCounts) {
+    let mut injector = FaultInjector::new(options, RATE, 3).unwrap();
+    let chunks = seconds * RATE as u64 / CHUNK as u64;
+    let timestamps = (0..chunks)
+        .flat_map(|chunk| injector.stamp_chunk(chunk * CHUNK as u64, CHUNK, 1000.0))
+        .flatten()
+        .collect();
+    (timestamps, injector.counts())
+}
+
+#[test]
+fn test_no_faults() {
+    assert!(!FaultOptions::default().any());
+    let (timestamps, counts) = stamps(FaultOptions::default(), 10);
+    assert_eq!(timestamps.len(), 1000);
+    assert_eq!(timestamps[250], 1002.5);
+    assert_eq!(counts, FaultCounts::default());
+}
+
+#[test]
+fn test_jitter_and_drift() {
+    let options = FaultOptions {
+        jitter_ms: 1.0,
+        drift_ppm: 200.0,
+        ..FaultOptions::default()
+    };
+    let (timestamps, _) = stamps(options, 60);
+    let nominal: Vec<f64> = (0..timestamps.len()).map(|i| i as f64 / RATE).collect();
+    let fit = linear_fit(&nominal, &timestamps).unwrap();
+    assert!(((fit.slope - 1.0) * 1e6 - 200.0).abs() < 5.0, "slope {}", fit.slope);
+
+    let residuals: Vec<f64> = nominal.iter().zip(&timestamps).map(|(x, y)| y - fit.at(*x)).collect();
+    let std = (residuals.iter().map(|r| r * r).sum::<f64>() / residuals.len() as f64).sqrt();
+    assert!((std - 0.001).abs() < 0.0001, "jitter {} s", std);
+}
+
+#[test]
+fn test_dropouts_and_burst_gaps() {
+    let dropouts = FaultOptions {
+        dropout_prob: 0.1,
+        ..FaultOptions::default()
+    };
+    let (timestamps, counts) = stamps(dropouts, 100);
+    assert!((50..150).contains(&counts.dropped_chunks), "{} chunks", counts.dropped_chunks);
+    assert_eq!(timestamps.len() as u64, 10_000 - counts.dropped_samples);
+    // Adjacent lost chunks merge into one gap; the lost time is what is missing between the ends
+    let gaps = find_gaps(&timestamps, RATE, DEFAULT_GAP_FACTOR);
+    assert!(gaps.count() > 0 && gaps.count() as u64 <= counts.dropped_chunks);
+    let span = timestamps[timestamps.len() - 1] - timestamps[0];
+    let missing = span * RATE + 1.0 - timestamps.len() as f64;
+    assert!((gaps.lost_time - missing / RATE).abs() < 1e-6);
+
+    // 2 s stalls at 10, 20 and 30 s
+    let bursts = FaultOptions {
+        burst_gap: Some(2.0),
+        burst_interval: 10.0,
+        ..FaultOptions::default()
+    };
+    let (timestamps, counts) = stamps(bursts, 40);
+    assert_eq!(counts.gap_samples, 600);
+    let gaps = find_gaps(&timestamps, RATE, DEFAULT_GAP_FACTOR);
+    assert_eq!(gaps.count(), 3);
+    assert!((gaps.gaps[0].start - 1009.99).abs() < 1e-9 && (gaps.gaps[0].end - 1012.0).abs() < 1e-9);
+
+    let too_long = FaultOptions {
+        burst_gap: Some(10.0),
+        burst_interval: 10.0,
+        ..FaultOptions::default()
+    };
+    assert!(FaultInjector::new(too_long, RATE, 0).is_err());
+    let invalid = FaultOptions {
+        dropout_prob: 1.5,
+        ..FaultOptions::default()
+    };
+    assert!(FaultInjector::new(invalid, RATE, 0).is_err());
+}
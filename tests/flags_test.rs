@@ -0,0 +1,90 @@
+use anyhow::Result;
+use lsl_recording_toolbox::flags::{
+    add_flag_counts, bits_attribute, flag_names, flags_array_path, read_flag_counts, read_sample_flags, valid_mask,
+    FlagTracker, BUFFER_OVERFLOW, CLOCK_JUMP, DEVICE, RECONNECTED,
+};
+use ndarray::{Array1, Ix1};
+use serde_json::{json, Map, Value};
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use zarrs::array::{ArrayBuilder, DataType, FillValue};
+use zarrs::filesystem::FilesystemStore;
+
+#[test]
+fn test_flag_tracker() {
+    // Regular stream with a 300 s inlet buffer, channel 2 as status channel
+    let mut tracker = FlagTracker::new(Some(2), Some(300.0));
+    assert_eq!(tracker.sample(100.0, 100.01, Some(0.0)), 0);
+    assert_eq!(tracker.sample(100.1, 100.11, Some(1.0)), DEVICE);
+
+    // Reconnected at 200 s: flagged for one second of arrivals
+    tracker.stream_recovered(200.0);
+    assert_eq!(tracker.sample(199.5, 200.2, Some(0.0)), RECONNECTED);
+    assert_eq!(tracker.sample(199.9, 201.0, Some(0.0)), RECONNECTED);
+    assert_eq!(tracker.sample(200.5, 201.5, Some(0.0)), 0);
+
+    // A clock jump flags the next sample only
+    tracker.clock_jump();
+    assert_eq!(tracker.sample(210.0, 210.0, None), CLOCK_JUMP);
+    assert_eq!(tracker.sample(210.1, 210.1, None), 0);
+
+    // More than 90% of the buffer behind
+    assert_eq!(tracker.sample(300.0, 571.0, Some(2.0)), BUFFER_OVERFLOW | DEVICE);
+    assert_eq!(tracker.sample(300.0, 569.0, None), 0);
+
+    assert_eq!(tracker.flagged(), 5);
+    let counts: Vec<(&str, u64)> = tracker.counts().iter().map(|(name, n)| (*name, *n)).collect();
+    assert_eq!(counts, [("buffer_overflow", 1), ("clock_jump", 1), ("device", 2), ("reconnected", 2)]);
+
+    // Irregular streams have no buffer limit in seconds
+    let mut irregular = FlagTracker::new(None, None);
+    assert_eq!(irregular.sample(0.0, 10_000.0, Some(1.0)), 0);
+}
+
+#[test]
+fn test_flag_names_and_mask() {
+    assert_eq!(flag_names(RECONNECTED | DEVICE), ["reconnected", "device"]);
+    assert!(flag_names(0).is_empty());
+    assert_eq!(bits_attribute()["2"], "buffer_overflow");
+
+    let flags = [0, RECONNECTED, DEVICE, RECONNECTED | CLOCK_JUMP];
+    assert_eq!(valid_mask(&flags, DEVICE | CLOCK_JUMP), [true, true, false, false]);
+    assert_eq!(valid_mask(&flags, 0xff), [true, false, false, false]);
+}
+
+#[test]
+fn test_flag_counts_attribute() {
+    let mut attributes = Map::new();
+    assert_eq!(read_flag_counts(&Value::Object(attributes.clone())), None);
+
+    let first = BTreeMap::from([("reconnected", 40), ("device", 2)]);
+    add_flag_counts(&mut attributes, 41, &first);
+    // An appended recording adds to the counts
+    add_flag_counts(&mut attributes, 1, &BTreeMap::from([("device", 1)]));
+    assert_eq!(attributes["sample_flags"], json!({"flagged": 42, "counts": {"device": 3, "reconnected": 40}}));
+
+    let (flagged, counts) = read_flag_counts(&Value::Object(attributes)).unwrap();
+    assert_eq!((flagged, counts["device"]), (42, 3));
+}
+
+#[test]
+fn test_read_sample_flags() -> Result<()> {
+    let dir = std::env::temp_dir().join(format!("lsl_flags_{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir)?;
+    let store = Arc::new(FilesystemStore::new(&dir)?);
+    assert_eq!(read_sample_flags(&store, "EEG", 0, 4)?, None);
+
+    let array = ArrayBuilder::new(vec![5], vec![2], DataType::UInt8, FillValue::from(0u8))
+        .build(store.clone(), &flags_array_path("EEG"))?;
+    array.store_metadata()?;
+    array.store_array_subset_ndarray::<u8, Ix1>(&[0], Array1::from_vec(vec![0, RECONNECTED, 0, DEVICE, 0]))?;
+
+    assert_eq!(read_sample_flags(&store, "EEG", 1, 3)?, Some(vec![RECONNECTED, 0, DEVICE]));
+    // Samples past the array (recorded without flags) read as 0
+    assert_eq!(read_sample_flags(&store, "EEG", 3, 4)?, Some(vec![DEVICE, 0, 0, 0]));
+    assert_eq!(read_sample_flags(&store, "EEG", 7, 2)?, Some(vec![0, 0]));
+
+    std::fs::remove_dir_all(&dir)?;
+    Ok(())
+}
@@ -0,0 +1,26 @@
+use lsl_recording_toolbox::gaps::find_gaps;
+
+#[test]
+fn test_find_gaps() {
+    // 100 Hz with one 50 ms dropout and one 30 ms dropout
+    let mut timestamps: Vec<f64> = (0..10).map(|i| i as f64 * 0.01).collect();
+    timestamps.extend((0..10).map(|i| 0.14 + i as f64 * 0.01));
+    timestamps.extend((0..10).map(|i| 0.26 + i as f64 * 0.01));
+
+    let report = find_gaps(&timestamps, 100.0, 2.0);
+    assert_eq!(report.count(), 2);
+    assert_eq!(report.gaps[0].index, 10);
+    assert!((report.gaps[0].interval() - 0.05).abs() < 1e-9);
+    assert!((report.lost_time - (0.04 + 0.02)).abs() < 1e-9);
+    assert_eq!(report.largest, Some(report.gaps[0]));
+
+    // A higher factor ignores the shorter dropout
+    assert_eq!(find_gaps(&timestamps, 100.0, 4.0).count(), 1);
+}
+
+#[test]
+fn test_find_gaps_irregular_stream() {
+    let timestamps = [0.0, 0.1, 5.0, 5.2];
+    assert_eq!(find_gaps(&timestamps, 0.0, 2.0).count(), 0);
+    assert_eq!(find_gaps(&[], 100.0, 2.0).count(), 0);
+}
@@ -0,0 +1,154 @@
+use lsl_recording_toolbox::zarr::http::{cache_path, chunk_keys, index_links, is_http};
+use serde_json::json;
+use std::path::{Path, PathBuf};
+
+#[test]
+fn test_http_urls_and_cache_path() {
+    assert!(is_http("https://data.lab.org/P001.zarr"));
+    assert!(is_http("http://localhost:8000/P001.zarr"));
+    assert!(!is_http("s3://bucket/P001.zarr"));
+    assert!(!is_http("data/P001.zarr"));
+
+    assert_eq!(
+        cache_path("https://data.lab.org/sessions/P001.zarr/", Some(Path::new("/cache"))),
+        PathBuf::from("/cache/https/data.lab.org/sessions/P001.zarr")
+    );
+    assert_eq!(
+        cache_path("http://localhost:8000/../P001.zarr", Some(Path::new("/cache"))),
+        PathBuf::from("/cache/http/localhost:8000/P001.zarr")
+    );
+}
+
+#[test]
+fn test_chunk_keys() {
+    let data = json!({
+        "shape": [8, 250],
+        "chunk_grid": { "name": "regular", "configuration": { "chunk_shape": [8, 100] } },
+        "chunk_key_encoding": { "name": "default", "configuration": { "separator": "/" } },
+    });
+    assert_eq!(chunk_keys(&data), ["c/0/0", "c/0/1", "c/0/2"]);
+
+    let grid = json!({
+        "shape": [3, 2],
+        "chunk_grid": { "configuration": { "chunk_shape": [2, 1] } },
+        "chunk_key_encoding": { "name": "v2" },
+    });
+    assert_eq!(chunk_keys(&grid), ["0.0", "0.1", "1.0", "1.1"]);
+
+    // Empty arrays have no chunks
+    let empty = json!({ "shape": [0], "chunk_grid": { "configuration": { "chunk_shape": [100] } } });
+    assert!(chunk_keys(&empty).is_empty());
+}
+
+#[test]
+fn test_index_links() {
+    // Index pages as served by python -m http.server and nginx autoindex
+    let html = r#"<ul>
+        <li><a href="EEG/">EEG/</a></li>
+        <li><a href="Markers%20Task/">Markers Task/</a></li>
+        <li><a href="zarr.json">zarr.json</a></li>
+        <li><a href="../">../</a></li>
+        <li><a href='./EMG/'>EMG/</a></li>
+        <li><a href="?C=N;O=D">Name</a></li>
+        <li><a href="https://example.org/other/">elsewhere</a></li>
+        <li><a href="EEG/">EEG/</a></li>
+    </ul>"#;
+    assert_eq!(index_links(html), ["EEG", "EMG", "Markers Task"]);
+}
+
+#[cfg(feature = "http")]
+#[test]
+fn test_fetch_http_store() -> anyhow::Result<()> {
+    use lsl_recording_toolbox::zarr::http::{fetch_http_store, HttpFetchOptions};
+    use std::io::{BufRead, BufReader, Write};
+    use std::net::TcpListener;
+
+    let dir = std::env::temp_dir().join(format!("lsl_http_{}", std::process::id()));
+    let served = dir.join("served");
+    let _ = std::fs::remove_dir_all(&dir);
+    let array = |shape: u64| {
+        json!({
+            "zarr_format": 3, "node_type": "array", "shape": [shape],
+            "chunk_grid": { "name": "regular", "configuration": { "chunk_shape": [4] } },
+            "chunk_key_encoding": { "name": "default", "configuration": { "separator": "/" } },
+        })
+        .to_string()
+    };
+    for (path, contents) in [
+        ("P001.zarr/zarr.json", r#"{"zarr_format": 3, "node_type": "group"}"#.to_string()),
+        ("P001.zarr/EMG/zarr.json", r#"{"zarr_format": 3, "node_type": "group"}"#.to_string()),
+        ("P001.zarr/EMG/time/zarr.json", array(6)),
+        ("P001.zarr/EMG/time/c/0", "t0".to_string()),
+        ("P001.zarr/EMG/time/c/1", "t1".to_string()),
+        ("P001.zarr/EMG/events/zarr.json", array(6)),
+        ("P001.zarr/EMG/events/c/0", "e0".to_string()),
+    ] {
+        let file = served.join(path);
+        std::fs::create_dir_all(file.parent().unwrap())?;
+        std::fs::write(file, contents)?;
+    }
+
+    // Static file server with directory index pages
+    let listener = TcpListener::bind("127.0.0.1:0")?;
+    let port = listener.local_addr()?.port();
+    let root = served.clone();
+    std::thread::spawn(move || {
+        for mut connection in listener.incoming().flatten() {
+            // Read the whole request head before answering
+            let mut reader = BufReader::new(&connection);
+            let mut request = String::new();
+            reader.read_line(&mut request).ok();
+            let mut header = String::new();
+            while reader.read_line(&mut header).is_ok_and(|n| n > 2) {
+                header.clear();
+            }
+            let target = request.split_whitespace().nth(1).unwrap_or("/").to_string();
+            let path = root.join(target.trim_start_matches('/'));
+            let body = if target.ends_with('/') && path.is_dir() {
+                let links: String = std::fs::read_dir(&path)
+                    .unwrap()
+                    .flatten()
+                    .filter(|e| e.path().is_dir())
+                    .map(|e| format!("<a href=\"{}/\">x</a>", e.file_name().to_string_lossy()))
+                    .collect();
+                Some(links.into_bytes())
+            } else {
+                std::fs::read(&path).ok()
+            };
+            let response = match body {
+                Some(body) => {
+                    let mut response =
+                        format!("HTTP/1.1 200 OK\r\nContent-Length: {}\r\nConnection: close\r\n\r\n", body.len()).into_bytes();
+                    response.extend(body);
+                    response
+                }
+                None => b"HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\nConnection: close\r\n\r\n".to_vec(),
+            };
+            connection.write_all(&response).ok();
+        }
+    });
+
+    let url = format!("http://127.0.0.1:{}/P001.zarr", port);
+    let options = HttpFetchOptions {
+        cache_dir: Some(dir.join("cache")),
+        ..Default::default()
+    };
+    let local = fetch_http_store(&url, &options)?;
+    assert_eq!(local, dir.join("cache").join("http").join(format!("127.0.0.1:{}", port)).join("P001.zarr"));
+    assert_eq!(std::fs::read_to_string(local.join("EMG/time/c/1"))?, "t1");
+    // Sample chunks only on request
+    assert!(local.join("EMG/events/zarr.json").exists());
+    assert!(!local.join("EMG/events/c/0").exists());
+
+    let options = HttpFetchOptions {
+        sample_data: true,
+        streams: vec!["EMG".to_string()],
+        ..options
+    };
+    let local = fetch_http_store(&url, &options)?;
+    assert_eq!(std::fs::read_to_string(local.join("EMG/events/c/0"))?, "e0");
+
+    assert!(fetch_http_store(&format!("http://127.0.0.1:{}/missing.zarr", port), &options).is_err());
+    std::fs::remove_dir_all(&dir)?;
+    Ok(())
+}
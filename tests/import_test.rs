@@ -0,0 +1,22 @@
+use lsl_recording_toolbox::import::group_name;
+
+#[test]
+fn test_group_name() {
+    assert_eq!(group_name("EEG", &[]), "EEG");
+    // Path separators and spaces cannot appear in a group name
+    assert_eq!(group_name("BioSemi/EEG 64", &[]), "BioSemi_EEG_64");
+    assert_eq!(group_name("", &[]), "stream");
+}
+
+#[test]
+fn test_group_name_duplicates() {
+    let used = vec!["Markers".to_string(), "Markers_2".to_string()];
+    assert_eq!(group_name("Markers", &used), "Markers_3");
+    assert_eq!(group_name("EEG", &used), "EEG");
+}
+
+#[test]
+fn test_group_name_reserved() {
+    // The store-level metadata group cannot be a stream
+    assert_eq!(group_name("meta", &[]), "meta_2");
+}
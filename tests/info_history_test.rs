@@ -0,0 +1,74 @@
+use lsl_recording_toolbox::info_history::{
+    append_snapshot, changed_paths, read_history, InfoHistory, STREAM_INFO_HISTORY_ATTRIBUTE,
+};
+use serde_json::{json, Value};
+
+fn eeg_info(impedance_c3: f64) -> Value {
+    json!({
+        "type": "EEG",
+        "source_id": "EEG_001",
+        "channel_count": 2,
+        "nominal_srate": 500.0,
+        "description": {"impedances": {"C3": impedance_c3, "C4": 4.2}, "manufacturer": "Acme"},
+        "channels": [{"label": "C3", "unit": "microvolts"}, {"label": "C4", "unit": "microvolts"}],
+    })
+}
+
+#[test]
+fn test_changed_paths() {
+    assert!(changed_paths(&eeg_info(5.0), &eeg_info(5.0)).is_empty());
+    assert_eq!(changed_paths(&eeg_info(5.0), &eeg_info(12.5)), vec!["description.impedances.C3"]);
+
+    let mut relabeled = eeg_info(5.0);
+    relabeled["channels"][1]["label"] = json!("Cz");
+    relabeled["description"]["gain"] = json!(24);
+    assert_eq!(
+        changed_paths(&eeg_info(5.0), &relabeled),
+        vec!["channels.1.label", "description.gain"]
+    );
+
+    // Different lengths or types are reported at the containing field
+    let mut fewer = eeg_info(5.0);
+    fewer["channels"].as_array_mut().unwrap().pop();
+    assert_eq!(changed_paths(&eeg_info(5.0), &fewer), vec!["channels"]);
+    assert_eq!(changed_paths(&json!(1), &json!("1")), vec!["stream_info"]);
+
+    // Long lists are cut short
+    let old: Value = (0..50).map(|i| (format!("ch{:02}", i), json!(1.0))).collect::<serde_json::Map<_, _>>().into();
+    let new: Value = (0..50).map(|i| (format!("ch{:02}", i), json!(2.0))).collect::<serde_json::Map<_, _>>().into();
+    let paths = changed_paths(&old, &new);
+    assert_eq!(paths.len(), 21);
+    assert_eq!(paths.last().unwrap(), "...");
+}
+
+#[test]
+fn test_history_keeps_only_changes() {
+    let mut history = InfoHistory::new(eeg_info(5.0));
+    assert!(history.update(100.0, eeg_info(5.0)).is_none());
+
+    let snapshot = history.update(110.0, eeg_info(12.5)).unwrap();
+    assert_eq!(snapshot.lsl_clock, 110.0);
+    assert_eq!(snapshot.changed, vec!["description.impedances.C3"]);
+    assert_eq!(snapshot.stream_info, eeg_info(12.5));
+    assert!(snapshot.utc.ends_with('Z'));
+
+    // Compared with the latest version, not the connect-time one
+    assert!(history.update(120.0, eeg_info(12.5)).is_none());
+    assert!(history.update(130.0, eeg_info(5.0)).is_some());
+}
+
+#[test]
+fn test_append_and_read_history() {
+    let mut attributes = serde_json::Map::new();
+    attributes.insert("stream_info".to_string(), eeg_info(5.0));
+    assert!(read_history(&Value::Object(attributes.clone())).is_empty());
+
+    let mut history = InfoHistory::new(eeg_info(5.0));
+    let first = history.update(110.0, eeg_info(12.5)).unwrap();
+    let second = history.update(170.0, eeg_info(30.0)).unwrap();
+    append_snapshot(&mut attributes, &first).unwrap();
+    append_snapshot(&mut attributes, &second).unwrap();
+
+    assert_eq!(attributes[STREAM_INFO_HISTORY_ATTRIBUTE].as_array().unwrap().len(), 2);
+    assert_eq!(read_history(&Value::Object(attributes)), vec![first, second]);
+}
@@ -0,0 +1,65 @@
+use lsl_recording_toolbox::live_view::{LatencyWindow, SampleWindow};
+
+#[test]
+fn test_window_keeps_last_span() {
+    let mut window = SampleWindow::new(2, 1.0);
+    assert!(window.is_empty());
+    assert_eq!(window.effective_rate(), None);
+
+    // 8 Hz for 3 seconds
+    for i in 0..24 {
+        window.push(i as f64 / 8.0, &[i as f64, -(i as f64)]);
+    }
+    assert_eq!(window.total(), 24);
+    assert_eq!(window.len(), 9);
+    assert_eq!(window.latest(), Some(2.875));
+    assert_eq!(window.effective_rate(), Some(8.0));
+    assert_eq!(window.value_range(0), Some((15.0, 23.0)));
+    assert_eq!(window.value_range(1), Some((-23.0, -15.0)));
+    assert_eq!(window.value_range(2), None);
+
+    window.set_span(0.5);
+    assert_eq!(window.len(), 5);
+    assert_eq!(window.total(), 24);
+}
+
+#[test]
+fn test_plot_points_min_max_per_column() {
+    let mut window = SampleWindow::new(1, 1.0);
+    // 100 Hz square-ish signal with a single spike
+    for i in 0..=100 {
+        let value = if i == 37 { 50.0 } else { (i % 2) as f64 };
+        window.push(i as f64 * 0.01, &[value]);
+    }
+
+    let points = window.plot_points(0, 4);
+    assert_eq!(points.len(), 8);
+    assert!(points.iter().all(|&(x, _)| (-1.0..=0.0).contains(&x)));
+    assert_eq!(points[0], (-0.875, 0.0));
+    assert_eq!(points[1], (-0.875, 1.0));
+    // The spike survives the reduction
+    assert_eq!(points[3], (-0.625, 50.0));
+
+    // Non-finite values and empty columns are skipped
+    let mut sparse = SampleWindow::new(1, 1.0);
+    sparse.push(0.0, &[f64::NAN]);
+    sparse.push(0.9, &[2.0]);
+    sparse.push(1.0, &[2.0]);
+    assert_eq!(sparse.plot_points(0, 4), [(-0.125, 2.0)]);
+    assert_eq!(sparse.value_range(0), Some((2.0, 2.0)));
+}
+
+#[test]
+fn test_latency_window() {
+    let mut latency = LatencyWindow::new(1.0);
+    assert_eq!(latency.stats(), None);
+
+    latency.push(10.0, 9.99);
+    latency.push(10.5, 10.47);
+    latency.push(11.2, 11.19);
+    let stats = latency.stats().unwrap();
+    // The first arrival is more than a second old
+    assert!((stats.last - 0.01).abs() < 1e-9);
+    assert!((stats.mean - 0.02).abs() < 1e-9);
+    assert!((stats.max - 0.03).abs() < 1e-9);
+}
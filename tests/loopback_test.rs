@@ -0,0 +1,87 @@
+use lsl_recording_toolbox::loopback::{compare, find_lag, Signal};
+
+/// Deterministic non-periodic test signal
+fn noise(n: usize, seed: u64) -> Vec<f64> {
+    let mut state = seed;
+    (0..n)
+        .map(|_| {
+            state = state.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+            (state >> 33) as f64 / (1u64 << 31) as f64 - 0.5
+        })
+        .collect()
+}
+
+/// Two-channel signal at 1000 Hz: a flat channel and a noise channel
+fn original() -> Signal {
+    let values = noise(20_000, 7);
+    Signal {
+        timestamps: (0..20_000).map(|i| 100.0 + i as f64 / 1000.0).collect(),
+        values: values.iter().flat_map(|&v| [1.0, v]).collect(),
+        channel_count: 2,
+    }
+}
+
+#[test]
+fn test_find_lag() {
+    let signal = noise(5000, 3);
+    let (lag, r) = find_lag(&signal, &signal[1234..3000], 2000).unwrap();
+    assert_eq!(lag, 1234);
+    assert!((r - 1.0).abs() < 1e-9);
+
+    // Outside the searched lags
+    let (lag, _) = find_lag(&signal, &signal[1234..3000], 1000).unwrap();
+    assert_ne!(lag, 1234);
+    // Constant recordings cannot be located
+    assert!(find_lag(&signal, &[0.5; 100], 1000).is_none());
+}
+
+#[test]
+fn test_compare_lossless_loop() {
+    let original = original();
+    // Recording joins at sample 300, 2.5 s later on another clock, pacing 50 ppm slow
+    let lag = 300;
+    let recorded = Signal {
+        timestamps: (lag..10_000)
+            .map(|i| original.timestamps[i] + 2.5 + (original.timestamps[i] - original.timestamps[lag]) * 50e-6)
+            .collect(),
+        values: original.values[lag * 2..10_000 * 2].to_vec(),
+        channel_count: 2,
+    };
+
+    let report = compare(&original, &recorded, 1000).unwrap();
+    assert_eq!(report.channel, 1);
+    assert_eq!(report.lag, lag);
+    assert_eq!(report.compared, 10_000 - lag);
+    assert_eq!(report.identical, report.compared);
+    assert_eq!(report.fidelity_percent(), 100.0);
+    assert!((report.timing.unwrap().intercept - 2.5).abs() < 1e-6);
+    assert!((report.drift_ppm() - 50.0).abs() < 0.1);
+    assert!(report.max_jitter < 1e-6);
+}
+
+#[test]
+fn test_compare_reports_altered_samples() {
+    let original = original();
+    let mut recorded = Signal {
+        timestamps: original.timestamps[..5000].to_vec(),
+        values: original.values[..10_000].to_vec(),
+        channel_count: 2,
+    };
+    recorded.values[2 * 4000 + 1] += 0.25;
+    // Late sample
+    recorded.timestamps[4500] += 0.004;
+
+    let report = compare(&original, &recorded, 100).unwrap();
+    assert_eq!(report.lag, 0);
+    assert_eq!(report.identical, 4999);
+    assert!((report.max_abs_error - 0.25).abs() < 1e-12);
+    assert!((report.max_jitter - 0.004).abs() < 1e-4);
+
+    // Channel counts must match
+    let mono = Signal {
+        timestamps: recorded.timestamps.clone(),
+        values: original.channel(1)[..5000].to_vec(),
+        channel_count: 1,
+    };
+    assert!(compare(&original, &mono, 100).is_none());
+}
@@ -0,0 +1,75 @@
+use anyhow::Result;
+use lsl_recording_toolbox::manifest::{read_manifest, update_manifest_streams, write_manifest, MANIFEST_ATTRIBUTE};
+use lsl_recording_toolbox::zarr::open_or_create_zarr_store;
+use serde_json::json;
+use std::path::Path;
+
+/// Add a stream group with `stream_info`, `recorder_config` and a time array
+fn write_stream(store: &Path, name: &str, source_id: &str) -> Result<()> {
+    let group = store.join(name);
+    std::fs::create_dir_all(group.join("time"))?;
+    let meta = json!({
+        "zarr_format": 3,
+        "node_type": "group",
+        "attributes": {
+            "stream_info": { "source_id": source_id, "type": "EMG", "channel_count": 8, "nominal_srate": 2000.0 },
+            "recorder_config": { "subject": "P001", "chunk_samples": 100, "recorder_version": "1.10.0" },
+        },
+    });
+    std::fs::write(group.join("zarr.json"), meta.to_string())?;
+    std::fs::write(group.join("time").join("zarr.json"), r#"{"zarr_format": 3, "node_type": "array"}"#)?;
+    Ok(())
+}
+
+#[test]
+fn test_manifest_written_with_store() -> Result<()> {
+    let store = std::env::temp_dir().join(format!("lsl_manifest_{}.zarr", std::process::id()));
+    let _ = std::fs::remove_dir_all(&store);
+
+    open_or_create_zarr_store(&store, Some("P001"), Some("session_001"), Some("pilot"))?;
+    let manifest = read_manifest(&store).expect("manifest written on creation");
+    assert_eq!(manifest.subject.as_deref(), Some("P001"));
+    assert_eq!(manifest.session_id.as_deref(), Some("session_001"));
+    assert_eq!(manifest.notes.as_deref(), Some("pilot"));
+    assert_eq!(manifest.toolbox_version.as_deref(), Some(env!("CARGO_PKG_VERSION")));
+    assert_eq!(manifest.host.as_ref().unwrap().os, std::env::consts::OS);
+    assert!(manifest.created_at.is_some());
+    assert!(manifest.streams.is_empty());
+
+    // Streams are picked up as they are added
+    write_stream(&store, "EMG", "EMG_001")?;
+    write_stream(&store, "EMG2", "EMG_002")?;
+    let manifest = update_manifest_streams(&store)?;
+    let names: Vec<&str> = manifest.streams.iter().map(|s| s.name.as_str()).collect();
+    assert_eq!(names, ["EMG", "EMG2"]);
+    assert_eq!(manifest.streams[0].source_id.as_deref(), Some("EMG_001"));
+    assert_eq!(manifest.streams[0].channel_count, Some(8));
+    assert_eq!(manifest.streams[0].recorder_version.as_deref(), Some("1.10.0"));
+    assert_eq!(manifest.streams[0].config["chunk_samples"], 100);
+
+    // A later writer without session details keeps the existing ones
+    let created_at = manifest.created_at.clone();
+    let manifest = write_manifest(&store, None, None, None)?;
+    assert_eq!(manifest.subject.as_deref(), Some("P001"));
+    assert_eq!(manifest.notes.as_deref(), Some("pilot"));
+    assert_eq!(manifest.created_at, created_at);
+    assert_eq!(manifest.streams.len(), 2);
+
+    // Stored next to the device table in the meta group
+    let meta: serde_json::Value = serde_json::from_slice(&std::fs::read(store.join("meta").join("zarr.json"))?)?;
+    assert_eq!(meta["attributes"][MANIFEST_ATTRIBUTE]["streams"][1]["type"], "EMG");
+
+    std::fs::remove_dir_all(&store)?;
+    Ok(())
+}
+
+#[test]
+fn test_store_without_manifest() -> Result<()> {
+    let store = std::env::temp_dir().join(format!("lsl_manifest_old_{}.zarr", std::process::id()));
+    let _ = std::fs::remove_dir_all(&store);
+    std::fs::create_dir_all(&store)?;
+    std::fs::write(store.join("zarr.json"), r#"{"zarr_format": 3, "node_type": "group"}"#)?;
+    assert!(read_manifest(&store).is_none());
+    std::fs::remove_dir_all(&store)?;
+    Ok(())
+}
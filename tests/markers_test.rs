@@ -0,0 +1,83 @@
+use anyhow::Result;
+use lsl_recording_toolbox::demo::{generate_demo_store, DemoOptions};
+use lsl_recording_toolbox::export::{ExportStream, SampleBlock};
+use lsl_recording_toolbox::markers::{normalize_stream, MarkerTable, LABELS_ARRAY, NORMALIZATION_ATTRIBUTE};
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use zarrs::array::Array;
+use zarrs::array_subset::ArraySubset;
+use zarrs::filesystem::FilesystemStore;
+
+#[test]
+fn test_marker_table() -> Result<()> {
+    let table = MarkerTable::from_toml(
+        r#"
+        [labels]
+        stim_on = ["StimOn", "stimulus_onset", 1]
+        response = ["resp"]
+        "#,
+    )?;
+    assert!(!table.ignore_case);
+    assert_eq!(table.normalize("StimOn"), Some("stim_on"));
+    assert_eq!(table.normalize("1"), Some("stim_on"));
+    // Canonical labels match themselves
+    assert_eq!(table.normalize("stim_on"), Some("stim_on"));
+    assert_eq!(table.normalize("resp"), Some("response"));
+    assert_eq!(table.normalize("STIMON"), None);
+    assert_eq!(table.normalize("trial_start"), None);
+
+    let table = MarkerTable::from_toml("ignore_case = true\n[labels]\nstim_on = [\"StimOn\"]\n")?;
+    assert_eq!(table.normalize("STIMON"), Some("stim_on"));
+    assert_eq!(table.normalize("Stim_On"), Some("stim_on"));
+
+    // One alias for two labels, unsupported values and unknown keys are rejected
+    assert!(MarkerTable::from_toml("[labels]\na = [\"x\"]\nb = [\"x\"]\n").is_err());
+    assert!(MarkerTable::from_toml("[labels]\na = [1.5]\n").is_err());
+    assert!(MarkerTable::from_toml("ignore_cases = true\n[labels]\n").is_err());
+    Ok(())
+}
+
+#[test]
+fn test_normalize_stream() -> Result<()> {
+    let dir = std::env::temp_dir().join(format!("lsl_markers_{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    let store_path = dir.join("demo.zarr");
+    let options = DemoOptions {
+        duration: 12.0,
+        ..DemoOptions::default()
+    };
+    generate_demo_store(&store_path, &options)?;
+
+    let table = MarkerTable::from_toml("[labels]\nstim_on = [\"stimulus\"]\ntrial_start = []\n")?;
+    let report = normalize_stream(&store_path, "Markers", &table, Some("markers.toml"))?;
+    assert_eq!((report.events, report.rewritten), (9, 3));
+    assert_eq!(report.unmatched, BTreeMap::from([("response".to_string(), 3)]));
+    assert_eq!(report.counts.get("stim_on"), Some(&3));
+    assert_eq!(report.counts.get("response"), Some(&3));
+
+    // Labels written next to the untouched events
+    let store = Arc::new(FilesystemStore::new(&store_path)?);
+    let labels: Vec<String> = Array::open(store.clone(), &format!("/Markers/{}", LABELS_ARRAY))?
+        .retrieve_array_subset_ndarray::<String>(&ArraySubset::new_with_shape(vec![9]))?
+        .into_iter()
+        .collect();
+    assert_eq!(labels[..3], ["trial_start", "stim_on", "response"]);
+    let stream = ExportStream::open(&store, "Markers", false)?;
+    let (_, events) = stream.read_block(0, 3)?;
+    let SampleBlock::String(events) = events else {
+        panic!("Markers should hold string events");
+    };
+    assert_eq!(events, ["trial_start", "stimulus", "response"]);
+
+    let attribute = &stream.attributes[NORMALIZATION_ATTRIBUTE];
+    assert_eq!(attribute["rewritten"], 3);
+    assert_eq!(attribute["unmatched"]["response"], 3);
+    assert_eq!(attribute["table_source"], "markers.toml");
+    assert_eq!(attribute["table"]["labels"]["stim_on"][0], "stimulus");
+
+    // Numeric streams cannot be normalized
+    assert!(normalize_stream(&store_path, "EEG", &table, None).is_err());
+
+    std::fs::remove_dir_all(&dir)?;
+    Ok(())
+}
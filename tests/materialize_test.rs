@@ -0,0 +1,104 @@
+mod common;
+
+use anyhow::Result;
+use lsl_recording_toolbox::export::ExportStream;
+use lsl_recording_toolbox::zarr::materialize::{
+    materialize_stream, purge_expired_archives, retire_raw_arrays, verify_materialized, RawRetention,
+    ARCHIVE_MANIFEST, RAW_RETENTION_ATTRIBUTE,
+};
+use lsl_recording_toolbox::zarr::{read_group_attributes, N_SAMPLES_ATTRIBUTE};
+use serde_json::json;
+use std::path::Path;
+use std::sync::Arc;
+use zarrs::filesystem::FilesystemStore;
+
+/// Create a store with a 2-channel stream of 10 samples at 10 Hz from t=100 s; sample 5 of channel 1 is NaN
+fn write_store(path: &Path) -> Result<()> {
+    let store = common::create_store(path)?;
+    let attributes = json!({
+        "stream_info": { "channel_format": "Float32", "channel_count": 2, "nominal_srate": 10.0 },
+        "recorder_config": { "chunk_samples": 4 },
+        "first_timestamp": 100.0,
+    });
+    let mut values = common::ramp(2, 10);
+    values[[1, 5]] = f32::NAN;
+    common::write_stream(&store, "EMG", attributes, values, common::regular_timestamps(10, 100.0, 0.1))
+}
+
+#[test]
+fn test_materialize_and_archive_raw_arrays() -> Result<()> {
+    let dir = std::env::temp_dir().join(format!("lsl_materialize_{}", std::process::id()));
+    let store_path = dir.join("session.zarr");
+    let archive_dir = dir.join("archive");
+    write_store(&store_path)?;
+
+    assert_eq!(materialize_stream(&store_path, "EMG", 2..8, 100.0)?, 6);
+    // NaN samples survive the comparison
+    verify_materialized(&store_path, "EMG", 2..8, 100.0)?;
+    assert!(verify_materialized(&store_path, "EMG", 1..7, 100.0).is_err());
+
+    // Keeping the raw arrays is a no-op
+    assert!(retire_raw_arrays(&store_path, "EMG", 2..8, 100.0, RawRetention::Keep, None, None)?.is_none());
+    assert!(store_path.join("EMG/synced").exists());
+
+    let archive = retire_raw_arrays(
+        &store_path,
+        "EMG",
+        2..8,
+        100.0,
+        RawRetention::Archive,
+        Some(&archive_dir),
+        Some(0),
+    )?
+    .unwrap();
+    assert_eq!(archive, archive_dir.join("session.zarr").join("EMG"));
+    assert!(archive.join("data").exists() && archive.join("time").exists());
+    assert!(archive.join(ARCHIVE_MANIFEST).exists());
+    assert!(!store_path.join("EMG/synced").exists());
+
+    // The stream now holds the trimmed samples with aligned timestamps
+    let store = Arc::new(FilesystemStore::new(&store_path)?);
+    let stream = ExportStream::open(&store, "EMG", false)?;
+    assert_eq!(stream.sample_count, 6);
+    let (timestamps, _) = stream.read_block(0, 6)?;
+    assert!((timestamps[0] - 0.2).abs() < 1e-9);
+    assert!(stream.time_index().is_some());
+
+    let attributes = read_group_attributes(&store, "EMG")?;
+    let provenance = &attributes[RAW_RETENTION_ATTRIBUTE];
+    assert_eq!(provenance["action"], "archive");
+    assert_eq!(provenance["original_sample_count"], 10);
+    assert_eq!(provenance["trim_start_index"], 2);
+    assert_eq!(attributes["trimmed_sample_count"], 6);
+    assert_eq!(attributes[N_SAMPLES_ATTRIBUTE], 6);
+    assert!((attributes["first_timestamp"].as_f64().unwrap()).abs() < 1e-9);
+
+    // Retained for zero days: expired right away
+    assert_eq!(purge_expired_archives(&archive_dir)?, vec![archive.clone()]);
+    assert!(!archive.exists());
+
+    std::fs::remove_dir_all(&dir)?;
+    Ok(())
+}
+
+#[test]
+fn test_sample_count_follows_n_samples() -> Result<()> {
+    let dir = std::env::temp_dir().join(format!("lsl_materialize_count_{}", std::process::id()));
+    let store_path = dir.join("session.zarr");
+    write_store(&store_path)?;
+    let store = Arc::new(FilesystemStore::new(&store_path)?);
+
+    // A recorder that flushed 7 samples; the tail of the arrays is never counted
+    let mut group = zarrs::group::Group::open(store.clone(), "/EMG")?;
+    group.attributes_mut().insert(N_SAMPLES_ATTRIBUTE.to_string(), json!(7));
+    group.store_metadata()?;
+    assert_eq!(ExportStream::open(&store, "EMG", false)?.sample_count, 7);
+
+    assert_eq!(materialize_stream(&store_path, "EMG", 0..10, 100.0)?, 7);
+    let synced = ExportStream::open(&store, "EMG/synced", false)?;
+    assert_eq!(synced.sample_count, 7);
+    assert_eq!(read_group_attributes(&store, "EMG/synced")?[N_SAMPLES_ATTRIBUTE], 7);
+
+    std::fs::remove_dir_all(&dir)?;
+    Ok(())
+}
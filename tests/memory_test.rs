@@ -0,0 +1,83 @@
+use lsl_recording_toolbox::memory::{growth_rate, GrowthDetector, MemoryEvent, MemoryStatus, GROWTH_WINDOW};
+
+#[test]
+fn test_status_line_round_trip() {
+    let status = MemoryStatus {
+        rss_mb: Some(52.1),
+        virtual_mb: Some(1204.3),
+        buffer_mb: 0.41,
+        rate_hz: 2000.0,
+        growth_mb_per_min: Some(-0.25),
+    };
+    let line = status.status_line();
+    assert_eq!(
+        line,
+        "STATUS MEMORY rss=52.1MB virtual=1204.3MB buffer=0.41MB rate=2000.0Hz growth=-0.25MB/min"
+    );
+    assert_eq!(MemoryStatus::parse(&line), Some(status));
+
+    // As forwarded by lsl-multi-recorder, before the first growth estimate
+    let parsed = MemoryStatus::parse("[+00:10.002] [EMG_001] STATUS MEMORY buffer=0.10MB rate=500.0Hz").unwrap();
+    assert_eq!(parsed.rss_mb, None);
+    assert_eq!(parsed.rate_hz, 500.0);
+    assert_eq!(parsed.growth_mb_per_min, None);
+
+    assert!(MemoryStatus::parse("STATUS MEMORY_GROWTH (+12.0MB/min)").is_none());
+    assert!(MemoryStatus::parse("STATUS MEMORY buffer=lots").is_none());
+}
+
+#[test]
+fn test_growth_rate() {
+    assert_eq!(growth_rate(&[]), None);
+    assert_eq!(growth_rate(&[(0.0, 50.0)]), None);
+    assert_eq!(growth_rate(&[(5.0, 50.0), (5.0, 60.0)]), None);
+
+    // 1 MB every 10 s = 6 MB/min, despite noise around the line
+    let points: Vec<(f64, f64)> = (0..6)
+        .map(|i| (i as f64 * 10.0, 50.0 + i as f64 + if i % 2 == 0 { 0.1 } else { -0.1 }))
+        .collect();
+    let slope = growth_rate(&points).unwrap();
+    assert!((slope - 6.0).abs() < 0.2, "slope {}", slope);
+}
+
+#[test]
+fn test_growth_detector() {
+    let mut detector = GrowthDetector::new(5.0);
+    let mut t = 0.0;
+    let mut rss = 50.0;
+    let mut step = |detector: &mut GrowthDetector, mb_per_report: f64| {
+        t += 10.0;
+        rss += mb_per_report;
+        detector.update(t, rss)
+    };
+
+    // Steady memory: no events
+    for _ in 0..GROWTH_WINDOW * 2 {
+        let (_, event) = step(&mut detector, 0.0);
+        assert_eq!(event, None);
+    }
+
+    // 2 MB per 10 s report = 12 MB/min: reported once, as soon as the window shows it
+    let events: Vec<MemoryEvent> = (0..GROWTH_WINDOW * 2)
+        .filter_map(|_| step(&mut detector, 2.0).1)
+        .collect();
+    assert_eq!(events.len(), 1);
+    assert!(matches!(events[0], MemoryEvent::Growing(slope) if slope > 5.0));
+    assert!(detector.is_growing());
+    assert!(events[0].status_line().starts_with("STATUS MEMORY_GROWTH (+"));
+
+    // Levelling off clears the state again
+    let events: Vec<MemoryEvent> = (0..GROWTH_WINDOW * 2)
+        .filter_map(|_| step(&mut detector, 0.0).1)
+        .collect();
+    assert!(matches!(events.as_slice(), [MemoryEvent::Stable(_)]));
+    assert!(!detector.is_growing());
+
+    // A limit of 0 only reports the slope
+    let mut detector = GrowthDetector::new(0.0);
+    for i in 0..GROWTH_WINDOW * 2 {
+        let (slope, event) = detector.update(i as f64 * 10.0, 50.0 + i as f64 * 10.0);
+        assert_eq!(event, None);
+        assert_eq!(slope.is_some(), i > 0);
+    }
+}
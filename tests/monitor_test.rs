@@ -0,0 +1,30 @@
+#![cfg(feature = "lsl")]
+
+use lsl_recording_toolbox::monitor::{select_monitor_channels, Decimator, MonitorReduction};
+
+#[test]
+fn test_decimator_pick() {
+    let mut decimator = Decimator::new(3, vec![2, 0], MonitorReduction::Pick);
+    let out: Vec<_> = (0..7)
+        .filter_map(|i| decimator.push(i as f64, &[i as f32, 0.0, -(i as f32)]))
+        .collect();
+    // First sample of every block of three, selected channels in the requested order
+    assert_eq!(out, vec![(0.0, vec![0.0, 0.0]), (3.0, vec![-3.0, 3.0]), (6.0, vec![-6.0, 6.0])]);
+}
+
+#[test]
+fn test_decimator_mean() {
+    let mut decimator = Decimator::new(4, vec![0], MonitorReduction::Mean);
+    let out: Vec<_> = (0..9).filter_map(|i| decimator.push(i as f64, &[i as f64])).collect();
+    // Incomplete trailing block is held back
+    assert_eq!(out, vec![(1.5, vec![1.5]), (5.5, vec![5.5])]);
+}
+
+#[test]
+fn test_select_monitor_channels() {
+    let labels: Vec<String> = ["C3", "Cz", "C4"].iter().map(|s| s.to_string()).collect();
+    assert_eq!(select_monitor_channels(&labels, &[]).unwrap(), vec![0, 1, 2]);
+    let wanted = vec!["C4".to_string(), "0".to_string()];
+    assert_eq!(select_monitor_channels(&labels, &wanted).unwrap(), vec![2, 0]);
+    assert!(select_monitor_channels(&labels, &["Pz".to_string()]).is_err());
+}
@@ -0,0 +1,69 @@
+use lsl_recording_toolbox::observer::{BatchValues, ObserverDispatch, RecordingCallbacks};
+use lsl_recording_toolbox::status::{RecordingState, StreamStatus};
+use std::sync::{Arc, Mutex};
+
+fn status(state: RecordingState, samples: u64) -> StreamStatus {
+    StreamStatus {
+        state,
+        samples_recorded: samples,
+        ..StreamStatus::new("EMG")
+    }
+}
+
+#[test]
+fn test_observer_dispatch() {
+    let events = Arc::new(Mutex::new(Vec::new()));
+    let (batches, flushes, states) = (events.clone(), events.clone(), events.clone());
+    let callbacks = RecordingCallbacks::new()
+        .on_sample_batch(move |batch| {
+            let first = batch.numeric_sample(0).unwrap().to_vec();
+            batches.lock().unwrap().push(format!("batch {} {} {:?}", batch.stream, batch.len(), first));
+        })
+        .on_flush(move |status| flushes.lock().unwrap().push(format!("flush {}", status.samples_recorded)))
+        .on_state_change(move |previous, status| {
+            states.lock().unwrap().push(format!("{:?} -> {:?}", previous, status.state));
+        });
+    let mut dispatch = ObserverDispatch::new(Box::new(callbacks), "EMG", 2, false);
+
+    dispatch.state(&status(RecordingState::Ready, 0));
+    dispatch.state(&status(RecordingState::Recording, 0));
+    // The same state again is no change
+    dispatch.state(&status(RecordingState::Recording, 0));
+    dispatch.offer(1.0, &[1i16, 2]);
+    dispatch.offer(1.5, &[3i16, 4]);
+    dispatch.flushed(&status(RecordingState::Recording, 2));
+    // Pending samples are handed over before the state changes
+    dispatch.offer(2.0, &[5i16, 6]);
+    dispatch.finish(&status(RecordingState::Finished, 3));
+
+    assert_eq!(
+        *events.lock().unwrap(),
+        [
+            "Resolving -> Ready",
+            "Ready -> Recording",
+            "flush 2",
+            "batch EMG 3 [1.0, 2.0]",
+            "Recording -> Finished",
+        ]
+    );
+}
+
+#[test]
+fn test_marker_batches() {
+    let batches = Arc::new(Mutex::new(Vec::new()));
+    let received = batches.clone();
+    let callbacks =
+        RecordingCallbacks::new().on_sample_batch(move |batch| received.lock().unwrap().push(batch.clone()));
+    let mut dispatch = ObserverDispatch::new(Box::new(callbacks), "Markers", 1, true);
+
+    // Numeric samples do not fit a string stream's batch
+    dispatch.offer(0.5, &[1.0f32]);
+    dispatch.offer_marker(1.0, &["trial_start".to_string()]);
+    dispatch.finish(&status(RecordingState::Finished, 1));
+
+    let batches = batches.lock().unwrap();
+    assert_eq!(batches.len(), 1);
+    assert_eq!(batches[0].timestamps, [1.0]);
+    assert_eq!(batches[0].values, BatchValues::Text(vec!["trial_start".to_string()]));
+    assert_eq!(batches[0].numeric_sample(0), None);
+}
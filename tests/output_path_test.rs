@@ -0,0 +1,56 @@
+mod common;
+
+use anyhow::Result;
+use lsl_recording_toolbox::zarr::{check_session_collision, expand_output_template, GroupNaming};
+use std::path::{Path, PathBuf};
+
+/// Create a minimal store with one stream recorded for `subject`/`session_id`
+fn write_store(path: &Path, subject: &str, session_id: &str) -> Result<()> {
+    let store = common::create_store(path)?;
+    let attributes = serde_json::json!({
+        "recorder_config": { "subject": subject, "session_id": session_id }
+    });
+    common::write_stream_group(&store, "EMG", attributes)
+}
+
+#[test]
+fn test_expand_output_template() {
+    let expanded = expand_output_template(Path::new("data/{subject}_{session_id}_{date}"), Some("P001"), None);
+    let text = expanded.to_string_lossy();
+
+    assert!(text.starts_with("data/P001_unknown_"));
+    assert!(!text.contains('{'));
+    assert_eq!(
+        expand_output_template(Path::new("experiment"), None, None),
+        PathBuf::from("experiment")
+    );
+}
+
+#[test]
+fn test_group_naming() -> Result<()> {
+    let uid = "6f3c2a9e-1b7d-4e5f-8a0c-2d9e4b7f1a36";
+    assert_eq!(GroupNaming::Name.group_name("EEG", uid)?, "EEG");
+    assert_eq!(GroupNaming::Uid.group_name("EEG", uid)?, uid);
+    // Two outlets of the same model only differ by UID
+    assert_ne!(
+        GroupNaming::Uid.group_name("EEG", uid)?,
+        GroupNaming::Uid.group_name("EEG", "0a1b2c3d-4e5f-6071-8293-a4b5c6d7e8f9")?
+    );
+    assert_eq!(GroupNaming::Uid.group_name("EEG", " a/b.c ")?, "a_b_c");
+    assert!(GroupNaming::Uid.group_name("EEG", "").is_err());
+    Ok(())
+}
+
+#[test]
+fn test_session_collision_detection() -> Result<()> {
+    let store = std::env::temp_dir().join(format!("lsl_collision_{}.zarr", std::process::id()));
+    write_store(&store, "P001", "session_001")?;
+
+    assert!(check_session_collision(&store, Some("P001"), Some("session_001")).is_ok());
+    assert!(check_session_collision(&store, Some("P002"), Some("session_001")).is_err());
+    assert!(check_session_collision(&store, Some("P001"), None).is_err());
+    assert!(check_session_collision(&store.with_extension("missing"), Some("P002"), None).is_ok());
+
+    std::fs::remove_dir_all(&store)?;
+    Ok(())
+}
@@ -0,0 +1,65 @@
+use anyhow::Result;
+use lsl_recording_toolbox::demo::{generate_demo_store, DemoOptions};
+use lsl_recording_toolbox::zarr::get_stream_info;
+use lsl_recording_toolbox::zarr::paths::{
+    check_stream_paths, long_path, longest_chunk_path, verbatim_path, MAX_NAME_LEN, WINDOWS_MAX_PATH,
+};
+use std::path::Path;
+use std::sync::Arc;
+use zarrs::filesystem::FilesystemStore;
+
+#[test]
+fn test_verbatim_paths() {
+    assert_eq!(verbatim_path(r"C:\data\exp.zarr"), r"\\?\C:\data\exp.zarr");
+    assert_eq!(verbatim_path("D:/data/exp.zarr"), r"\\?\D:\data\exp.zarr");
+    assert_eq!(verbatim_path(r"\\lab-nas\share\exp.zarr"), r"\\?\UNC\lab-nas\share\exp.zarr");
+    // Already verbatim, device and relative paths are left alone
+    assert_eq!(verbatim_path(r"\\?\C:\data"), r"\\?\C:\data");
+    assert_eq!(verbatim_path(r"\\.\pipe\x"), r"\\.\pipe\x");
+    assert_eq!(verbatim_path("data/exp.zarr"), "data/exp.zarr");
+}
+
+#[test]
+fn test_stream_path_limits() -> Result<()> {
+    // "/data/exp.zarr" + "/EMG/" + "synced/time_index/c/<index>/0" with 10 index digits
+    assert_eq!(longest_chunk_path(Path::new("/data/exp.zarr"), "EMG"), 14 + 5 + 22 + 10);
+
+    assert_eq!(check_stream_paths(Path::new("/data/exp.zarr"), "EMG")?, None);
+    assert_eq!(check_stream_paths(Path::new("/data/exp.zarr"), "Lab/EMG/Left")?, None);
+
+    // Over MAX_PATH: a warning, recording still works through long_path
+    let deep = format!("/data/{}/exp.zarr", ["session"; 30].join("/"));
+    assert!(longest_chunk_path(Path::new(&deep), "EMG") > WINDOWS_MAX_PATH);
+    let warning = check_stream_paths(Path::new(&deep), "EMG")?.expect("MAX_PATH warning");
+    assert!(warning.contains("'EMG'"), "{}", warning);
+
+    // Names no file system accepts are errors
+    let long_name = "x".repeat(MAX_NAME_LEN + 1);
+    assert!(check_stream_paths(Path::new("/data/exp.zarr"), &long_name).is_err());
+    assert!(check_stream_paths(Path::new("/data/exp.zarr"), &format!("Lab/{}", long_name)).is_err());
+    assert!(check_stream_paths(&Path::new("/data").join(&long_name), "EMG").is_err());
+    Ok(())
+}
+
+#[test]
+fn test_store_on_long_path() -> Result<()> {
+    let dir = std::env::temp_dir().join(format!("lsl_paths_{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    let mut store_path = dir.clone();
+    for level in 0..10 {
+        store_path.push(format!("{}_{}", "nested_output_directory", level));
+    }
+    store_path.push("demo.zarr");
+    assert!(longest_chunk_path(&store_path, "EMG") > WINDOWS_MAX_PATH);
+
+    let options = DemoOptions {
+        duration: 6.0,
+        ..DemoOptions::default()
+    };
+    generate_demo_store(&store_path, &options)?;
+    let store = Arc::new(FilesystemStore::new(long_path(&store_path))?);
+    assert_eq!(get_stream_info(&store, "EMG")?.channel_count, Some(4));
+
+    std::fs::remove_dir_all(&dir)?;
+    Ok(())
+}
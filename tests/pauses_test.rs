@@ -0,0 +1,68 @@
+use lsl_recording_toolbox::pauses::{
+    append_pause, exclude_pauses, merge_pauses, read_pauses, PauseTracker, PAUSES_ATTRIBUTE,
+};
+use serde_json::{json, Value};
+
+#[test]
+fn test_pause_tracker() {
+    let mut tracker = PauseTracker::default();
+    assert!(tracker.update(false, 1.0).is_none());
+    assert!(!tracker.covers(1.0));
+
+    assert!(tracker.update(true, 10.0).is_none());
+    assert!(tracker.update(true, 11.0).is_none());
+    assert!(tracker.is_paused());
+    assert_eq!(tracker.update(false, 15.0), Some((10.0, 15.0)));
+    assert!(!tracker.is_paused());
+
+    // Samples buffered during the pause arrive after RESUME
+    assert!(tracker.covers(12.5));
+    assert!(!tracker.covers(9.9) && !tracker.covers(15.1));
+
+    // QUIT while paused closes the pause
+    tracker.update(true, 20.0);
+    assert_eq!(tracker.finish(22.0), Some((20.0, 22.0)));
+    assert!(tracker.finish(23.0).is_none());
+}
+
+#[test]
+fn test_append_and_read_pauses() {
+    let mut attributes = serde_json::Map::new();
+    assert!(read_pauses(&Value::Object(attributes.clone())).is_empty());
+
+    append_pause(&mut attributes, (10.0, 15.0));
+    append_pause(&mut attributes, (20.0, 22.5));
+    assert_eq!(attributes[PAUSES_ATTRIBUTE], json!([[10.0, 15.0], [20.0, 22.5]]));
+    assert_eq!(read_pauses(&Value::Object(attributes)), vec![(10.0, 15.0), (20.0, 22.5)]);
+}
+
+#[test]
+fn test_merge_pauses() {
+    // The same pause seen by two recorders, a few milliseconds apart
+    let merged = merge_pauses(vec![(20.0, 22.0), (10.0, 15.0), (10.004, 15.003), (30.0, 29.0)]);
+    assert_eq!(merged, vec![(10.0, 15.003), (20.0, 22.0)]);
+}
+
+#[test]
+fn test_exclude_pauses() {
+    // 10 Hz from t=0 to t=9.9
+    let timestamps: Vec<f64> = (0..100).map(|i| i as f64 * 0.1).collect();
+
+    let (range, excluded) = exclude_pauses(&timestamps, &[(2.05, 3.05)], 0..100);
+    assert_eq!(range, 0..100);
+    assert_eq!(excluded, vec![21..31]);
+
+    // Pauses at the edges move the trim indices
+    let pauses = [(-1.0, 0.45), (3.95, 4.95), (9.45, 11.0)];
+    let (range, excluded) = exclude_pauses(&timestamps, &pauses, 0..100);
+    assert_eq!(range, 5..95);
+    assert_eq!(excluded, vec![40..50]);
+
+    // Only samples inside the trim range count
+    let (range, excluded) = exclude_pauses(&timestamps, &[(2.05, 3.05)], 25..60);
+    assert_eq!(range, 31..60);
+    assert!(excluded.is_empty());
+
+    let (range, _) = exclude_pauses(&timestamps, &[(-1.0, 20.0)], 0..100);
+    assert!(range.is_empty());
+}
@@ -0,0 +1,77 @@
+use anyhow::Result;
+use lsl_recording_toolbox::permissions::{
+    apply_permissions, apply_root_permissions, check_writable, directory_mode, parse_mode, resolve_group,
+    StorePermissions,
+};
+
+#[test]
+fn test_parse_mode() -> Result<()> {
+    assert_eq!(parse_mode("660")?, 0o660);
+    assert_eq!(parse_mode("0640")?, 0o640);
+    assert!(parse_mode("rw-rw----").is_err());
+    assert!(parse_mode("17777").is_err());
+    // The recorder must keep write access to its own files
+    assert!(parse_mode("440").is_err());
+
+    assert_eq!(directory_mode(0o660), 0o770);
+    assert_eq!(directory_mode(0o640), 0o750);
+    assert_eq!(directory_mode(0o600), 0o700);
+    Ok(())
+}
+
+#[test]
+fn test_resolve_group() -> Result<()> {
+    assert_eq!(resolve_group("1234")?, 1234);
+    assert!(resolve_group("no-such-group-lsl").is_err());
+    assert!(StorePermissions::new(None, Some("no-such-group-lsl")).is_err());
+    assert!(StorePermissions::new(None, None)?.is_empty());
+    Ok(())
+}
+
+#[test]
+fn test_check_writable() -> Result<()> {
+    let dir = std::env::temp_dir().join(format!("lsl_writable_{}", std::process::id()));
+    std::fs::create_dir_all(&dir)?;
+    // Stores that do not exist yet are checked in their closest existing parent
+    check_writable(&dir.join("sessions").join("P001.zarr"))?;
+    assert_eq!(std::fs::read_dir(&dir)?.count(), 0);
+    std::fs::remove_dir_all(&dir)?;
+    Ok(())
+}
+
+#[cfg(unix)]
+#[test]
+fn test_apply_permissions() -> Result<()> {
+    use std::os::unix::fs::{MetadataExt, PermissionsExt};
+
+    let store = std::env::temp_dir().join(format!("lsl_permissions_{}.zarr", std::process::id()));
+    let _ = std::fs::remove_dir_all(&store);
+    std::fs::create_dir_all(store.join("EMG").join("time").join("c"))?;
+    std::fs::write(store.join("zarr.json"), "{}")?;
+    std::fs::write(store.join("EMG").join("time").join("c").join("0"), "chunk")?;
+    let mode = |path: &std::path::Path| -> Result<u32> { Ok(std::fs::metadata(path)?.permissions().mode() & 0o7777) };
+
+    // Own primary group: allowed without privileges
+    let gid = std::fs::metadata(&store)?.gid();
+    let permissions = StorePermissions::new(Some("640"), Some(&gid.to_string()))?;
+
+    // The root alone, as for a store the recorder created among entries of others
+    std::fs::set_permissions(store.join("zarr.json"), std::fs::Permissions::from_mode(0o600))?;
+    apply_root_permissions(&store, &permissions)?;
+    assert_eq!(mode(&store)?, 0o2750);
+    assert_eq!(mode(&store.join("zarr.json"))?, 0o600);
+
+    assert_eq!(apply_permissions(&store, &permissions)?, 6);
+    assert_eq!(mode(&store.join("zarr.json"))?, 0o640);
+    assert_eq!(mode(&store.join("EMG/time/c/0"))?, 0o640);
+    // Directories are searchable and pass their group on
+    assert_eq!(mode(&store.join("EMG/time"))?, 0o2750);
+    assert_eq!(std::fs::metadata(store.join("EMG/time/c/0"))?.gid(), gid);
+
+    // Nothing requested, nothing changed; missing paths are skipped
+    assert_eq!(apply_permissions(&store, &StorePermissions::default())?, 0);
+    assert_eq!(apply_permissions(&store.join("missing"), &permissions)?, 0);
+
+    std::fs::remove_dir_all(&store)?;
+    Ok(())
+}
@@ -0,0 +1,64 @@
+use lsl_recording_toolbox::plausibility::{parse_plausible_range, parse_stream_range, PlausibilityCheck};
+
+#[test]
+fn test_parse_plausible_range() {
+    let range = parse_plausible_range("-10..10 mV").unwrap();
+    assert_eq!((range.min, range.max, range.unit.as_deref()), (-10.0, 10.0, Some("mV")));
+    assert_eq!(range.to_string(), "-10..10 mV");
+    assert_eq!(parse_plausible_range(&range.to_string()).unwrap(), range);
+
+    // ±LIMIT is symmetric, and units are normalized
+    let range = parse_plausible_range("±500 microvolts").unwrap();
+    assert_eq!((range.min, range.max, range.unit.as_deref()), (-500.0, 500.0, Some("µV")));
+    assert_eq!(parse_plausible_range("+-2.5").unwrap().max, 2.5);
+
+    let invalid = ["", "10", "10..-10", "1..1", "-1..x", "±10 kg", "-inf..0"];
+    for invalid in invalid {
+        assert!(parse_plausible_range(invalid).is_err(), "{}", invalid);
+    }
+
+    let range = parse_stream_range("EMG: ±10 mV").unwrap();
+    assert_eq!(range.stream.as_deref(), Some("EMG"));
+    assert!(range.applies_to("EMG", "EMG_1234"));
+    assert!(!range.applies_to("EEG", "EEG_5678"));
+    assert_eq!(range.to_string(), "EMG: -10..10 mV");
+    assert!(parse_stream_range("-1..1").unwrap().applies_to("EEG", "EEG_5678"));
+    assert!(parse_stream_range(": -1..1").is_err());
+}
+
+#[test]
+fn test_bounds_follow_channel_units() {
+    let range = parse_plausible_range("±10 mV").unwrap();
+    assert_eq!(range.bounds_in(Some("microvolts")), (-10000.0, 10000.0));
+    assert_eq!(range.bounds_in(Some("V")), (-0.01, 0.01));
+    // Channels without a voltage unit get the bounds as written
+    assert_eq!(range.bounds_in(None), (-10.0, 10.0));
+    assert_eq!(range.bounds_in(Some("a.u.")), (-10.0, 10.0));
+}
+
+#[test]
+fn test_implausible_samples_are_counted() {
+    let range = parse_plausible_range("±10 mV").unwrap();
+    let units = [Some("uV".to_string()), Some("mV".to_string())];
+    let mut check = PlausibilityCheck::new(range, vec!["EMG1".to_string(), "EMG2".to_string()], &units);
+
+    assert!(check.push(1.0, &[500.0f32, 2.0]).is_empty());
+    // 15 mV on the first channel (in µV) and on the second (in mV)
+    let events = check.push(2.0, &[15000.0f32, 15.0]);
+    assert_eq!(events.len(), 2);
+    assert_eq!((events[0].label.as_str(), events[0].value, events[0].max), ("EMG1", 15000.0, 10000.0));
+    assert_eq!(events[1].time, 2.0);
+    // Later violations of the same channel are only counted
+    assert!(check.push(3.0, &[20000.0f32, 0.0]).is_empty());
+    assert!(check.push(4.0, &[f32::NAN, -1.0]).is_empty());
+    assert_eq!(check.implausible_samples(), 2);
+
+    let summary = check.summary();
+    assert_eq!(summary["range"], "-10..10 mV");
+    assert_eq!(summary["samples"], 4);
+    assert_eq!(summary["first_time"], 2.0);
+    assert_eq!(summary["channels"][0]["outside"], 2);
+    assert_eq!(summary["channels"][0]["observed_max"], 20000.0);
+    assert_eq!(summary["channels"][1]["outside"], 1);
+    assert_eq!(summary["channels"][1]["observed_min"], -1.0);
+}
@@ -0,0 +1,78 @@
+use lsl_recording_toolbox::preflight::{PreflightCriteria, PreflightReport};
+use std::path::PathBuf;
+
+const GIB: u64 = 1024 * 1024 * 1024;
+
+/// Five seconds of an 8-channel Float32 stream at 2000 Hz on a disk with `free_gib` free
+fn report(free_gib: u64) -> PreflightReport {
+    PreflightReport {
+        stream: "EMG".to_string(),
+        channels: 8,
+        channel_format: "Float32".to_string(),
+        nominal_rate: 2000.0,
+        samples: 10001,
+        seconds: 5.0,
+        first_timestamp: Some(100.0),
+        last_timestamp: Some(105.0),
+        sample_bytes: 8.0 * 4.0 + 8.0,
+        output: PathBuf::from("experiment.zarr"),
+        free_bytes: Some(free_gib * GIB),
+        ..Default::default()
+    }
+}
+
+#[test]
+fn test_preflight_go() {
+    let report = report(100);
+    assert_eq!(report.measured_rate(), Some(2000.0));
+    // 40 bytes per sample at 2000 Hz
+    assert_eq!(report.bytes_per_hour(), Some(288_000_000.0));
+    let hours = report.hours_of_space().unwrap();
+    assert!((hours - 100.0 * GIB as f64 / 288e6).abs() < 1e-9);
+
+    let verdict = report.evaluate(&PreflightCriteria::default());
+    assert!(verdict.go(), "{:?}", verdict);
+    assert!(verdict.warnings.is_empty());
+}
+
+#[test]
+fn test_preflight_no_go() {
+    let criteria = PreflightCriteria::default();
+
+    // Runs at 90% of its nominal rate
+    let mut slow = report(100);
+    slow.samples = 9001;
+    let verdict = slow.evaluate(&criteria);
+    assert_eq!(verdict.problems.len(), 1);
+    assert!(verdict.problems[0].contains("-10.0%"), "{}", verdict.problems[0]);
+
+    // Silent, unresolved or unwritable
+    let mut silent = report(100);
+    silent.samples = 0;
+    assert!(!silent.evaluate(&criteria).go());
+    let unresolved = PreflightReport {
+        stream_error: Some("No stream found with source_id=EMG_1234 after 3 attempts".to_string()),
+        ..report(100)
+    };
+    assert_eq!(unresolved.evaluate(&criteria).problems.len(), 1);
+    let mut unwritable = report(100);
+    unwritable.output_errors.push("Cannot write to /data".to_string());
+    assert_eq!(unwritable.evaluate(&criteria).problems, ["Cannot write to /data"]);
+
+    // One GiB holds under four hours: too little for a 5 h session, a warning without one
+    let mut full = report(1);
+    assert!(full.evaluate(&criteria).go());
+    assert_eq!(full.evaluate(&criteria).warnings.len(), 0);
+    full.free_bytes = Some(GIB / 2);
+    assert_eq!(full.evaluate(&criteria).warnings.len(), 1);
+    full.free_bytes = Some(GIB);
+    full.planned_seconds = Some(5.0 * 3600.0);
+    assert!(!full.evaluate(&criteria).go());
+
+    // Irregular streams are not held to a rate; their data rate comes from the samples
+    let mut markers = report(100);
+    markers.nominal_rate = 0.0;
+    markers.samples = 6;
+    assert!(markers.evaluate(&criteria).go());
+    assert_eq!(markers.bytes_per_hour(), Some(1.0 * 40.0 * 3600.0));
+}
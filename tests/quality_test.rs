@@ -0,0 +1,74 @@
+use lsl_recording_toolbox::quality::{QualityIssue, QualityMonitor, QualityOptions};
+
+fn labels(names: &[&str]) -> Vec<String> {
+    names.iter().map(|s| s.to_string()).collect()
+}
+
+#[test]
+fn test_flatline_and_rms() {
+    // 100 Hz: 1 s windows of 100 samples, flatline after 200 unchanged samples
+    let mut quality = QualityMonitor::new(labels(&["C3", "C4"]), 100.0, None, &QualityOptions::default());
+    let mut events = Vec::new();
+    for i in 0..250 {
+        // C3 is stuck at 5.0, C4 alternates around 0
+        let c4 = if i % 2 == 0 { 1.0f32 } else { -1.0 };
+        events.extend(quality.push(&[5.0f32, c4]));
+    }
+    assert_eq!(events.len(), 1);
+    assert_eq!((events[0].channel, events[0].issue, events[0].active), (0, QualityIssue::Flatline, true));
+    assert_eq!(events[0].to_string(), "C3: flatline for 2.0s");
+
+    let events = quality.push(&[6.0f32, 1.0]);
+    assert_eq!(events.len(), 1);
+    assert!(!events[0].active);
+    assert_eq!(events[0].to_string(), "C3: flatline ended");
+
+    let summary = quality.summary();
+    assert_eq!(summary["windows"], 2);
+    let c3 = &summary["channels"][0];
+    assert_eq!((c3["label"].as_str(), c3["warnings"].as_u64()), (Some("C3"), Some(1)));
+    assert_eq!(c3["rms_max"], 0.0);
+    assert!((c3["flatline_s"].as_f64().unwrap() - 2.49).abs() < 1e-9);
+    // RMS is taken about the window mean, so the DC offset of C3 does not count
+    let c4 = &summary["channels"][1];
+    assert_eq!((c4["rms_mean"].as_f64(), c4["flatline_s"].as_f64()), (Some(1.0), Some(0.0)));
+    assert_eq!(c4["clipped_fraction"], 0.0);
+}
+
+#[test]
+fn test_clipping() {
+    // Int16 stream: samples at the format limit count as clipped
+    let options = QualityOptions { window: 0.1, ..Default::default() };
+    let mut quality = QualityMonitor::new(labels(&["EMG"]), 1000.0, Some(i16::MAX as f64), &options);
+    let mut events = Vec::new();
+    for i in 0..100i16 {
+        let value = if i < 10 { i16::MAX } else if i % 2 == 0 { 100 } else { -100 };
+        events.extend(quality.push(&[value]));
+    }
+    assert_eq!(events.len(), 1);
+    assert_eq!((events[0].issue, events[0].active), (QualityIssue::Clipping, true));
+    assert_eq!(events[0].to_string(), "EMG: clipping 10.0% of samples");
+
+    // The next clean window ends the warning
+    let events: Vec<_> = (0..100i16).flat_map(|i| quality.push(&[i % 7])).collect();
+    assert_eq!(events.len(), 1);
+    assert_eq!(events[0].to_string(), "EMG: clipping ended");
+    assert_eq!(quality.summary()["channels"][0]["clipped_fraction"], 0.05);
+
+    // Float streams without a level: runs of identical samples at the window extremes
+    let mut quality = QualityMonitor::new(labels(&["ch0"]), 100.0, None, &QualityOptions::default());
+    let mut events = Vec::new();
+    for i in 0..100 {
+        let value = if (40..45).contains(&i) { 3.0f64 } else if i % 2 == 0 { 1.0 } else { -1.0 };
+        events.extend(quality.push(&[value]));
+    }
+    assert_eq!(events.len(), 1);
+    assert_eq!(events[0].to_string(), "ch0: clipping 5.0% of samples");
+
+    // An explicit level overrides the format range
+    let options = QualityOptions { clip_level: Some(50.0), window: 0.1, ..Default::default() };
+    let mut quality = QualityMonitor::new(labels(&["EMG"]), 1000.0, Some(i16::MAX as f64), &options);
+    let events: Vec<_> = (0..100i16).flat_map(|i| quality.push(&[if i % 2 == 0 { 100 } else { -100 }])).collect();
+    assert_eq!(events.len(), 1);
+    assert_eq!(quality.summary()["clip_level"], 50.0);
+}
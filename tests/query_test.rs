@@ -0,0 +1,71 @@
+use lsl_recording_toolbox::query::{glob_match, Query};
+use lsl_recording_toolbox::session::{SessionSummary, StreamSummary};
+use std::path::PathBuf;
+
+/// Build a session with two streams spanning `duration` seconds
+fn session(subject: &str, duration: f64) -> SessionSummary {
+    SessionSummary {
+        path: PathBuf::from("/data/P001_session1.zarr"),
+        subject: Some(subject.to_string()),
+        session_id: Some("session_001".to_string()),
+        streams: vec![
+            StreamSummary {
+                name: "EEG".to_string(),
+                stream_type: Some("EEG".to_string()),
+                samples: 1000,
+                first_timestamp: Some(100.0),
+                last_timestamp: Some(100.0 + duration),
+                ..Default::default()
+            },
+            StreamSummary {
+                name: "Markers".to_string(),
+                stream_type: Some("Markers".to_string()),
+                samples: 10,
+                first_timestamp: Some(101.0),
+                last_timestamp: Some(100.0 + duration / 2.0),
+                ..Default::default()
+            },
+        ],
+        ..Default::default()
+    }
+}
+
+#[test]
+fn test_glob_match() {
+    assert!(glob_match("P00*", "P001"));
+    assert!(glob_match("P00?", "P002"));
+    assert!(glob_match("*pilot*", "first pilot run"));
+    assert!(!glob_match("P00?", "P0010"));
+    assert!(!glob_match("EEG", "EMG"));
+}
+
+#[test]
+fn test_query_combines_fields_and_functions() {
+    let query = Query::parse(r#"subject == "P00*" && duration > 600 && has_stream("EEG")"#).unwrap();
+
+    assert!(query.matches(&session("P001", 900.0)));
+    assert!(!query.matches(&session("P001", 300.0)));
+    assert!(!query.matches(&session("Q001", 900.0)));
+}
+
+#[test]
+fn test_query_negation_and_missing_fields() {
+    let query = Query::parse(r#"!(notes == "*pilot*") && streams >= 2"#).unwrap();
+    assert!(query.matches(&session("P001", 10.0)));
+
+    let query = Query::parse(r#"notes == "*""#).unwrap();
+    assert!(!query.matches(&session("P001", 10.0)));
+}
+
+#[test]
+fn test_empty_query_matches_everything() {
+    assert!(Query::parse("").unwrap().matches(&session("P001", 1.0)));
+}
+
+#[test]
+fn test_invalid_queries_are_rejected() {
+    assert!(Query::parse("unknown_field == 1").is_err());
+    assert!(Query::parse(r#"subject == "P001"#).is_err());
+    assert!(Query::parse("duration >").is_err());
+    assert!(Query::parse("has_nothing(\"x\")").is_err());
+}
@@ -0,0 +1,60 @@
+use lsl_recording_toolbox::rate_guard::{parse_rate_factor, RateGuard};
+
+/// Feed `seconds` of samples at `rate` Hz from t=100 s, stopping at the first error
+fn feed(guard: &mut RateGuard, rate: f64, seconds: f64) -> anyhow::Result<()> {
+    let samples = (rate * seconds) as usize;
+    for i in 0..samples {
+        guard.check(100.0 + i as f64 / rate, true)?;
+    }
+    Ok(())
+}
+
+#[test]
+fn test_parse_rate_factor() {
+    assert_eq!(parse_rate_factor("0").unwrap(), 0.0);
+    assert_eq!(parse_rate_factor("1").unwrap(), 1.0);
+    assert_eq!(parse_rate_factor(" 4.5 ").unwrap(), 4.5);
+    // Below 1 every healthy stream would abort; negative values would silently disable the guard
+    assert!(parse_rate_factor("0.5").is_err());
+    assert!(parse_rate_factor("-2").is_err());
+    assert!(parse_rate_factor("inf").is_err());
+    assert!(parse_rate_factor("fast").is_err());
+}
+
+#[test]
+fn test_rate_measured_per_window() {
+    let mut guard = RateGuard::new(100.0, 4.0, false);
+    // Nothing is measured before a window is complete
+    feed(&mut guard, 100.0, 1.5).unwrap();
+    assert_eq!(guard.measured_rate(), None);
+
+    let mut guard = RateGuard::new(100.0, 4.0, false);
+    feed(&mut guard, 100.0, 5.0).unwrap();
+    let measured = guard.measured_rate().unwrap();
+    assert!((measured - 100.0).abs() < 1.0, "measured {}", measured);
+}
+
+#[test]
+fn test_rate_overrun_aborts() {
+    let mut guard = RateGuard::new(100.0, 4.0, false);
+    let error = feed(&mut guard, 1000.0, 3.0).unwrap_err();
+    assert!(error.to_string().contains("--allow-rate-overrun"));
+    assert!(guard.measured_rate().unwrap() > 400.0);
+}
+
+#[test]
+fn test_rate_overrun_warns_only() {
+    let mut guard = RateGuard::new(100.0, 4.0, true);
+    feed(&mut guard, 1000.0, 5.0).unwrap();
+    assert!(guard.measured_rate().unwrap() > 400.0);
+}
+
+#[test]
+fn test_rate_guard_disabled() {
+    // Factor 0 and irregular streams are never checked
+    let mut off = RateGuard::new(100.0, 0.0, false);
+    feed(&mut off, 1000.0, 5.0).unwrap();
+    let mut irregular = RateGuard::new(0.0, 4.0, false);
+    feed(&mut irregular, 1000.0, 5.0).unwrap();
+    assert_eq!((off.measured_rate(), irregular.measured_rate()), (None, None));
+}
@@ -0,0 +1,117 @@
+mod common;
+
+use anyhow::Result;
+use lsl_recording_toolbox::connection::ConnectionStats;
+use lsl_recording_toolbox::zarr::read_group_attributes;
+use lsl_recording_toolbox::zarr::recovery::{
+    find_recovery_markers, marker_file_name, read_recovery_marker, write_recovery_marker, RecoveryMarker,
+};
+use lsl_recording_toolbox::zarr::repair::repair_store;
+use serde_json::json;
+use std::path::Path;
+use std::sync::Arc;
+use zarrs::filesystem::FilesystemStore;
+
+/// Create a store with a 2-channel stream of 8 samples at 10 Hz from t=100 s
+fn write_store(path: &Path, attributes: serde_json::Value) -> Result<()> {
+    let store = common::create_store(path)?;
+    common::write_stream(&store, "EEG", attributes, common::ramp(2, 8), common::regular_timestamps(8, 100.0, 0.1))
+}
+
+fn marker(store: &Path) -> RecoveryMarker {
+    RecoveryMarker {
+        store: store.to_path_buf(),
+        stream: "EEG".to_string(),
+        failed_step: "finalize".to_string(),
+        error: "No space left on device (os error 28)".to_string(),
+        samples_recorded: 8,
+        samples_written: 8,
+        unflushed_samples: 0,
+        first_timestamp: Some(100.0),
+        last_timestamp: Some(100.7),
+        end_time: Some(json!({ "lsl_clock": 101.0, "utc": "2025-01-31T14:30:01Z" })),
+        event_counts: Default::default(),
+        connection: Some(ConnectionStats { resolution_attempts: 3, retry_delays_ms: vec![50, 60], ..Default::default() }),
+        written_at: "2025-01-31T14:30:01Z".to_string(),
+        recorder_version: "0.0.0".to_string(),
+    }
+}
+
+#[test]
+fn test_marker_written_next_to_store() -> Result<()> {
+    let dir = std::env::temp_dir().join(format!("lsl_recovery_marker_{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    let store = dir.join("session.zarr");
+
+    assert_eq!(marker_file_name(&store, "EEG"), "session.zarr.EEG.recovery.json");
+    // The store directory itself may be gone; its parent is created
+    let path = write_recovery_marker(&marker(&store))?;
+    assert_eq!(path, dir.join("session.zarr.EEG.recovery.json"));
+    assert_eq!(read_recovery_marker(&path)?, marker(&store));
+
+    let found = find_recovery_markers(&store);
+    assert_eq!(found.len(), 1);
+    assert_eq!(found[0].1.stream, "EEG");
+    assert!(find_recovery_markers(&dir.join("other.zarr")).is_empty());
+
+    std::fs::remove_dir_all(&dir)?;
+    Ok(())
+}
+
+#[test]
+fn test_repair_applies_marker() -> Result<()> {
+    let dir = std::env::temp_dir().join(format!("lsl_recovery_repair_{}", std::process::id()));
+    let store = dir.join("session.zarr");
+    write_store(&store, json!({ "stream_info": { "nominal_srate": 10.0 } }))?;
+    let marker_path = write_recovery_marker(&marker(&store))?;
+
+    // A dry run reports the marker but keeps it
+    let reports = repair_store(&store, &[], true)?;
+    assert!(reports[0].needs_repair());
+    assert_eq!(reports[0].samples_recorded, Some(8));
+    assert!(marker_path.exists());
+
+    let reports = repair_store(&store, &[], false)?;
+    assert_eq!(reports[0].marker.as_deref(), Some(marker_path.as_path()));
+    assert!(!marker_path.exists());
+
+    let store_handle = Arc::new(FilesystemStore::new(&store)?);
+    let attrs = read_group_attributes(&store_handle, "EEG")?;
+    assert_eq!(attrs["last_timestamp"], json!(100.0 + 7.0 * 0.1));
+    assert_eq!(attrs["end_time"]["lsl_clock"], 101.0);
+    assert_eq!(attrs["connection"]["resolution_attempts"], 3);
+    assert_eq!(attrs["recovery"]["marker"]["failed_step"], "finalize");
+    assert_eq!(attrs["recovery"]["marker"]["samples_recorded"], 8);
+
+    // Applied once: the stream is consistent again
+    assert!(!repair_store(&store, &[], false)?[0].needs_repair());
+
+    std::fs::remove_dir_all(&dir)?;
+    Ok(())
+}
+
+#[test]
+fn test_marker_of_finalized_stream_adds_nothing() -> Result<()> {
+    let dir = std::env::temp_dir().join(format!("lsl_recovery_finalized_{}", std::process::id()));
+    let store = dir.join("session.zarr");
+    // Finalized locally; only the upload failed afterwards
+    write_store(
+        &store,
+        json!({ "last_timestamp": 100.7, "connection": { "resolution_attempts": 1, "retry_delays_ms": [] } }),
+    )?;
+    let elsewhere = dir.join("copied.json");
+    std::fs::write(&elsewhere, serde_json::to_string(&marker(&store))?)?;
+
+    let reports = repair_store(&store, &[elsewhere.clone()], false)?;
+    assert_eq!(reports[0].marker.as_deref(), Some(elsewhere.as_path()));
+    assert!(!elsewhere.exists());
+
+    let store_handle = Arc::new(FilesystemStore::new(&store)?);
+    let attrs = read_group_attributes(&store_handle, "EEG")?;
+    assert_eq!(attrs["connection"]["resolution_attempts"], 1);
+    assert!(attrs.get("end_time").is_none());
+    assert_eq!(attrs["recovery"]["marker"]["error"], "No space left on device (os error 28)");
+
+    std::fs::remove_dir_all(&dir)?;
+    Ok(())
+}
@@ -0,0 +1,35 @@
+use std::path::{Path, PathBuf};
+
+use lsl_recording_toolbox::zarr::remote::{is_remote, resolve_output, spool_path};
+
+#[test]
+fn test_is_remote() {
+    assert!(is_remote(Path::new("s3://lab-data/experiment.zarr")));
+    assert!(is_remote(Path::new("gs://lab-data/P001.zarr")));
+    assert!(!is_remote(Path::new("experiment.zarr")));
+    assert!(!is_remote(Path::new("/mnt/s3/experiment.zarr")));
+}
+
+#[test]
+fn test_spool_path() {
+    assert_eq!(
+        spool_path("s3://lab-data/2025/experiment.zarr", Some(Path::new("/spool"))),
+        PathBuf::from("/spool/s3/lab-data/2025/experiment.zarr")
+    );
+    // Parent references cannot escape the spool directory
+    assert_eq!(
+        spool_path("gs://bucket/../x.zarr", Some(Path::new("/spool"))),
+        PathBuf::from("/spool/gs/bucket/x.zarr")
+    );
+}
+
+#[test]
+fn test_resolve_output() {
+    let (local, url) = resolve_output(Path::new("experiment.zarr"), None);
+    assert_eq!(local, PathBuf::from("experiment.zarr"));
+    assert_eq!(url, None);
+
+    let (local, url) = resolve_output(Path::new("s3://lab-data/experiment.zarr/"), Some(Path::new("/spool")));
+    assert_eq!(local, PathBuf::from("/spool/s3/lab-data/experiment.zarr"));
+    assert_eq!(url.as_deref(), Some("s3://lab-data/experiment.zarr"));
+}
@@ -0,0 +1,149 @@
+use lsl_recording_toolbox::replay::{
+    batch_buffer, block_ranges, chunk_ranges, ReplayClock, ReplayWindow, SessionSpan,
+};
+use std::time::Duration;
+
+#[test]
+fn test_replay_clock_keeps_recorded_offsets() {
+    let clock = ReplayClock {
+        first_timestamp: 1000.0,
+        origin: 50.0,
+        speed: 20.0,
+        realtime: true,
+    };
+    // Pushed timestamps are not scaled by the speed
+    assert_eq!(clock.stamp(1000.0), 50.0);
+    assert_eq!(clock.stamp(1010.0), 60.0);
+    assert_eq!(clock.stamp(1010.0) + clock.timestamp_offset(), 1010.0);
+
+    // Pacing follows the recorded offsets divided by the speed
+    assert_eq!(clock.due(1010.0), Some(Duration::from_millis(500)));
+    // Out-of-order timestamps are due right away
+    assert_eq!(clock.due(999.0), Some(Duration::ZERO));
+
+    let unpaced = ReplayClock { realtime: false, ..clock };
+    assert_eq!(unpaced.due(1010.0), None);
+    assert_eq!(unpaced.stamp(1010.0), 60.0);
+}
+
+#[test]
+fn test_batch_buffer() {
+    // Regular streams: seconds with 10% headroom
+    assert_eq!(batch_buffer(36_000_000, 3600.0, 10000.0), 3961);
+    // Irregular streams: hundreds of samples
+    assert_eq!(batch_buffer(250, 3600.0, 0.0), 4);
+}
+
+#[test]
+fn test_session_span_keeps_stream_offsets() {
+    // EEG starts 2 s after the markers, EMG ends last
+    let mut session = SessionSpan::new([(102.0, 160.0), (100.0, 150.0), (101.5, 161.0)]).unwrap();
+    assert_eq!((session.start, session.end, session.duration()), (100.0, 161.0, 61.0));
+    session.gap = 1.0;
+
+    let first = session.clock(0, 10.0, 2.0, true);
+    assert_eq!(first.stamp(100.0), 10.0);
+    assert_eq!(first.stamp(102.0), 12.0);
+    assert_eq!(first.due(102.0), Some(Duration::from_secs(1)));
+
+    // The next loop pass starts one gap after the end of the session
+    let second = session.clock(1, 10.0, 2.0, true);
+    assert_eq!(second.stamp(100.0), 72.0);
+    assert_eq!(second.stamp(102.0), 74.0);
+    assert_eq!(second.due(100.0), Some(Duration::from_secs(31)));
+
+    assert_eq!(SessionSpan::new(Vec::new()), None);
+}
+
+#[test]
+fn test_block_ranges() {
+    let blocks: Vec<_> = block_ranges(10_000, 4096).collect();
+    assert_eq!(blocks, [(0, 4096), (4096, 4096), (8192, 1808)]);
+    assert_eq!(block_ranges(3, 4096).collect::<Vec<_>>(), [(0, 3)]);
+    assert_eq!(block_ranges(0, 4096).count(), 0);
+    // A zero block size reads one sample at a time instead of panicking
+    assert_eq!(block_ranges(2, 0).collect::<Vec<_>>(), [(0, 1), (1, 1)]);
+}
+
+#[test]
+fn test_replay_window_sample_range() -> anyhow::Result<()> {
+    // Ten samples, 0.5 s apart: the first at or after a time
+    let first_at = |time: f64| Ok((time / 0.5).ceil().clamp(0.0, 10.0) as usize);
+
+    let full = ReplayWindow::default();
+    assert!(full.is_full());
+    assert_eq!(full.sample_range(10, first_at)?, 0..10);
+
+    // Both time bounds are inclusive
+    let times = ReplayWindow {
+        start_time: Some(1.0),
+        end_time: Some(2.0),
+        ..ReplayWindow::default()
+    };
+    assert_eq!(times.sample_range(10, first_at)?, 2..5);
+
+    let mixed = ReplayWindow {
+        start_sample: Some(3),
+        end_time: Some(2.0),
+        ..ReplayWindow::default()
+    };
+    assert!(mixed.has_samples());
+    assert_eq!(mixed.sample_range(10, first_at)?, 3..5);
+
+    // Bounds past the end are clamped; a window without samples is empty
+    let samples = ReplayWindow {
+        start_sample: Some(8),
+        end_sample: Some(100),
+        ..ReplayWindow::default()
+    };
+    assert_eq!(samples.sample_range(10, first_at)?, 8..10);
+    let late = ReplayWindow {
+        start_time: Some(7.0),
+        ..ReplayWindow::default()
+    };
+    assert!(late.sample_range(10, first_at)?.is_empty());
+    Ok(())
+}
+
+#[test]
+fn test_invalid_replay_window() {
+    let reversed = ReplayWindow {
+        start_time: Some(2.0),
+        end_time: Some(1.0),
+        ..ReplayWindow::default()
+    };
+    assert!(reversed.validate().is_err());
+    let no_samples = ReplayWindow {
+        start_sample: Some(5),
+        end_sample: Some(5),
+        ..ReplayWindow::default()
+    };
+    assert!(no_samples.validate().is_err());
+    let not_a_time = ReplayWindow {
+        end_time: Some(f64::NAN),
+        ..ReplayWindow::default()
+    };
+    assert!(not_a_time.validate().is_err());
+    assert!(ReplayWindow::default().validate().is_ok());
+}
+
+#[test]
+fn test_chunk_ranges() {
+    let timestamps = [0.0, 0.01, 0.02, 0.03, 0.05, 0.2];
+    assert_eq!(chunk_ranges(&timestamps, 0.025), [0..3, 3..5, 5..6]);
+    // Every chunk holds at least one sample
+    assert_eq!(chunk_ranges(&timestamps, 0.0), [0..1, 1..2, 2..3, 3..4, 4..5, 5..6]);
+    // One chunk for everything
+    assert_eq!(chunk_ranges(&timestamps, 1.0).as_slice(), std::slice::from_ref(&(0..6)));
+    assert!(chunk_ranges(&[], 0.01).is_empty());
+
+    // Live chunks are stamped with the scheduled time, which follows the speed
+    let clock = ReplayClock {
+        first_timestamp: 10.0,
+        origin: 100.0,
+        speed: 2.0,
+        realtime: true,
+    };
+    assert_eq!(clock.due_stamp(12.0), 101.0);
+    assert_eq!(clock.stamp(12.0), 102.0);
+}
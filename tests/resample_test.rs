@@ -0,0 +1,137 @@
+mod common;
+
+use anyhow::Result;
+use lsl_recording_toolbox::resample::{
+    anti_alias_kernel, resample_block, resample_stream, uniform_grid, GridSource, MAX_TAPS, RESAMPLING_ATTRIBUTE,
+};
+use lsl_recording_toolbox::zarr::read_group_attributes;
+use ndarray::Ix2;
+use serde_json::json;
+use std::f64::consts::PI;
+use std::path::Path;
+use std::sync::Arc;
+use zarrs::array::Array;
+use zarrs::array_subset::ArraySubset;
+use zarrs::filesystem::FilesystemStore;
+
+/// Create a store with a 2-channel stream of 10 samples at 10 Hz from t=100 s; sample 5 of channel 1 is NaN
+fn write_store(path: &Path) -> Result<()> {
+    let store = common::create_store(path)?;
+    let attributes = json!({
+        "stream_info": { "channel_format": "Float32", "channel_count": 2, "nominal_srate": 10.0 },
+        "recorder_config": { "chunk_samples": 4 },
+    });
+    let mut values = common::ramp(2, 10);
+    values[[1, 5]] = f32::NAN;
+    common::write_stream(&store, "EMG", attributes, values, common::regular_timestamps(10, 100.0, 0.1))
+}
+
+#[test]
+fn test_anti_alias_kernel() {
+    let kernel = anti_alias_kernel(1000.0, 100.0).unwrap();
+    assert_eq!(kernel.len(), 81);
+    assert!((kernel.iter().sum::<f64>() - 1.0).abs() < 1e-12);
+    // Symmetric (linear phase)
+    assert!(kernel.iter().zip(kernel.iter().rev()).all(|(a, b)| (a - b).abs() < 1e-15));
+
+    // Nothing to filter when upsampling
+    assert!(anti_alias_kernel(100.0, 100.0).is_none());
+    assert!(anti_alias_kernel(100.0, 250.0).is_none());
+    assert!(anti_alias_kernel(0.0, 100.0).is_none());
+    // Very large ratios are capped
+    assert_eq!(anti_alias_kernel(1_000_000.0, 1.0).unwrap().len(), MAX_TAPS);
+}
+
+#[test]
+fn test_uniform_grid() {
+    assert_eq!(uniform_grid(1.0, 4.0), vec![0.0, 0.25, 0.5, 0.75, 1.0]);
+    assert_eq!(uniform_grid(0.99, 4.0).len(), 4);
+    assert!(uniform_grid(1.0, 0.0).is_empty());
+}
+
+#[test]
+fn test_resample_block_interpolates_within_coverage() {
+    let timestamps = [0.0, 1.0, 2.0, 3.0];
+    let values = [0.0, 10.0, 1.0, 20.0, 4.0, 30.0, 5.0, 40.0];
+    let out = resample_block(&timestamps, &values, 2, None, None, &[-0.5, 0.5, 2.0, 2.75, 3.5]);
+    assert!(out[0].is_nan() && out[1].is_nan());
+    assert_eq!(&out[2..8], &[0.5, 15.0, 4.0, 30.0, 4.75, 37.5]);
+    assert!(out[8].is_nan() && out[9].is_nan());
+
+    // No interpolation across a gap
+    let timestamps = [0.0, 1.0, 5.0, 6.0];
+    let values = [0.0, 1.0, 5.0, 6.0];
+    let out = resample_block(&timestamps, &values, 1, None, Some(1.5), &[0.5, 3.0, 5.5]);
+    assert_eq!(out[0], 0.5);
+    assert!(out[1].is_nan());
+    assert_eq!(out[2], 5.5);
+}
+
+#[test]
+fn test_resample_block_removes_aliases() {
+    let timestamps: Vec<f64> = (0..2000).map(|i| i as f64 / 1000.0).collect();
+    let tone = |hz: f64| -> Vec<f64> { timestamps.iter().map(|t| (2.0 * PI * hz * t).sin()).collect() };
+    // Grid points away from the clamped edges
+    let grid: Vec<f64> = uniform_grid(1.0, 100.0).iter().map(|t| t + 0.5).collect();
+    let kernel = anti_alias_kernel(1000.0, 100.0).unwrap();
+
+    // 330 Hz would alias to 30 Hz on a 100 Hz grid
+    let aliased = resample_block(&timestamps, &tone(330.0), 1, None, None, &grid);
+    assert!(aliased.iter().any(|v| v.abs() > 0.5));
+    let filtered = resample_block(&timestamps, &tone(330.0), 1, Some(&kernel), None, &grid);
+    assert!(filtered.iter().all(|v| v.abs() < 1e-3));
+
+    // Passband is kept
+    let slow = resample_block(&timestamps, &tone(5.0), 1, Some(&kernel), None, &grid);
+    for (&t, &v) in grid.iter().zip(&slow) {
+        assert!((v - (2.0 * PI * 5.0 * t).sin()).abs() < 0.01);
+    }
+}
+
+#[test]
+fn test_resample_stream() -> Result<()> {
+    let dir = std::env::temp_dir().join(format!("lsl_resample_{}", std::process::id()));
+    let store_path = dir.join("session.zarr");
+    write_store(&store_path)?;
+
+    let aligned: Vec<f64> = (0..10).map(|s| (100.0 + s as f64 * 0.1) - 100.0).collect();
+    let grid = uniform_grid(1.0, 20.0);
+    let covered = resample_stream(&store_path, "EMG", &aligned, &grid, &GridSource::Rate(20.0))?;
+    assert_eq!(covered, 19);
+
+    let store = Arc::new(FilesystemStore::new(&store_path)?);
+    let data = Array::open(store.clone(), "/EMG/resampled_data")?;
+    assert_eq!(data.shape(), &[2, 21]);
+    let values = data
+        .retrieve_array_subset_ndarray::<f64>(&ArraySubset::new_with_shape(vec![2, 21]))?
+        .into_dimensionality::<Ix2>()?;
+    for k in 0..19 {
+        assert!((values[[0, k]] - k as f64 / 2.0).abs() < 1e-9);
+    }
+    // NaN sample 5 of channel 1 spreads to its neighbouring grid points only
+    assert!((values[[1, 8]] - 14.0).abs() < 1e-9);
+    assert!(values[[1, 9]].is_nan() && values[[1, 10]].is_nan() && values[[1, 11]].is_nan());
+    assert!((values[[1, 12]] - 16.0).abs() < 1e-9);
+    // Beyond the last sample
+    assert!(values[[0, 19]].is_nan() && values[[0, 20]].is_nan());
+
+    let time = Array::open(store.clone(), "/EMG/resampled_time")?;
+    let times = time.retrieve_array_subset_ndarray::<f64>(&ArraySubset::new_with_shape(vec![21]))?;
+    assert_eq!(times.iter().copied().collect::<Vec<f64>>(), grid);
+
+    let attributes = read_group_attributes(&store, "EMG")?;
+    let resampling = &attributes[RESAMPLING_ATTRIBUTE];
+    assert_eq!(resampling["grid"]["rate"], json!(20.0));
+    assert_eq!(resampling["covered_points"], json!(19));
+    assert!(resampling["anti_alias"].is_null());
+
+    // Downsampling is filtered; re-running replaces the arrays
+    resample_stream(&store_path, "EMG", &aligned, &uniform_grid(1.0, 5.0), &GridSource::Stream("EEG".to_string(), 5.0))?;
+    let attributes = read_group_attributes(&store, "EMG")?;
+    assert_eq!(attributes[RESAMPLING_ATTRIBUTE]["grid"]["stream"], json!("EEG"));
+    assert_eq!(attributes[RESAMPLING_ATTRIBUTE]["anti_alias"]["taps"], json!(17));
+    assert_eq!(Array::open(store, "/EMG/resampled_data")?.shape(), &[2, 6]);
+
+    std::fs::remove_dir_all(&dir)?;
+    Ok(())
+}
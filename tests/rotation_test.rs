@@ -0,0 +1,110 @@
+use anyhow::Result;
+use lsl_recording_toolbox::rotation::{
+    current_part, link_next, mark_part, parse_duration, parse_size, part_name, part_path, read_part_info,
+    session_base, session_parts, PartTracker, RotationOptions,
+};
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Duration;
+use zarrs::filesystem::FilesystemStore;
+use zarrs::group::GroupBuilder;
+
+fn create_store(path: &Path) -> Result<()> {
+    std::fs::create_dir_all(path)?;
+    let store = Arc::new(FilesystemStore::new(path)?);
+    GroupBuilder::new().build(store, "/")?.store_metadata()?;
+    Ok(())
+}
+
+#[test]
+fn test_parse_duration_and_size() {
+    assert_eq!(parse_duration("30min").unwrap(), Duration::from_secs(1800));
+    assert_eq!(parse_duration("1h30m").unwrap(), Duration::from_secs(5400));
+    assert_eq!(parse_duration("90").unwrap(), Duration::from_secs(90));
+    assert_eq!(parse_duration("1800s").unwrap(), Duration::from_secs(1800));
+    assert!(parse_duration("10 days").is_err());
+    assert!(parse_duration("0s").is_err());
+
+    assert_eq!(parse_size("10GB").unwrap(), 10_000_000_000);
+    assert_eq!(parse_size("1.5GiB").unwrap(), 1_610_612_736);
+    assert_eq!(parse_size("2048").unwrap(), 2048);
+    assert!(parse_size("10 parsecs").is_err());
+}
+
+#[test]
+fn test_part_names() {
+    assert_eq!(part_name("experiment.zarr", 1), "experiment.zarr");
+    assert_eq!(part_name("experiment.zarr", 2), "experiment_part002.zarr");
+    assert_eq!(part_name("s3://lab/P001.zarr/", 12), "s3://lab/P001_part012.zarr");
+    assert_eq!(
+        part_path(Path::new("data/experiment.zarr"), 3),
+        Path::new("data/experiment_part003.zarr")
+    );
+    assert_eq!(
+        session_base(Path::new("/nonexistent/experiment_part003.zarr")),
+        Path::new("/nonexistent/experiment.zarr")
+    );
+}
+
+#[test]
+fn test_part_tracker() {
+    // Parts by duration count from the start of recording
+    let options = RotationOptions { every: Some(Duration::from_secs(10)), size: None };
+    let mut parts = PartTracker::new(options);
+    assert_eq!(parts.due(95.0, || 0), None);
+    parts.start(100.0);
+    parts.start(105.0);
+    assert_eq!(parts.due(109.9, || 0), None);
+    assert_eq!(parts.due(110.0, || 0), Some(2));
+    parts.advance(2);
+    assert_eq!(parts.due(119.0, || 0), None);
+    // A silent stream skips the parts it has no samples for
+    assert_eq!(parts.due(135.0, || 0), Some(4));
+
+    // Parts by size, measured at most every few seconds
+    let options = RotationOptions { every: None, size: Some(1000) };
+    let mut parts = PartTracker::new(options);
+    assert_eq!(parts.due(1.0, || 999), None);
+    assert_eq!(parts.due(2.0, || 5000), None);
+    parts.advance(1);
+    assert_eq!(parts.due(3.0, || 5000), Some(2));
+    parts.advance(2);
+    assert_eq!(parts.due(4.0, || 10), None);
+    assert_eq!(parts.part(), 2);
+}
+
+#[test]
+fn test_session_parts_and_links() -> Result<()> {
+    let dir = std::env::temp_dir().join(format!("lsl_rotation_{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    let base = dir.join("night.zarr");
+    let options = RotationOptions { every: Some(Duration::from_secs(1800)), size: None };
+
+    create_store(&base)?;
+    create_store(&dir.join("night_2.zarr"))?;
+    assert_eq!(session_parts(&base), vec![base.clone()]);
+    assert_eq!(current_part(&base), base);
+
+    mark_part(&base, &base, 1, None, &options)?;
+    let second = part_path(&base, 2);
+    create_store(&second)?;
+    mark_part(&second, &base, 2, Some(&base), &options)?;
+    link_next(&base, &second)?;
+
+    assert_eq!(session_parts(&second), vec![base.clone(), second.clone()]);
+    assert_eq!(current_part(&base), second);
+    let first = read_part_info(&base).unwrap();
+    assert_eq!((first.part, first.next.as_deref()), (1, Some("night_part002.zarr")));
+    let info = read_part_info(&second).unwrap();
+    assert_eq!((info.session.as_str(), info.part), ("night.zarr", 2));
+    assert_eq!((info.previous.as_deref(), info.next.as_deref()), (Some("night.zarr"), None));
+    assert_eq!(info.every_s, Some(1800.0));
+
+    // A later recorder marking the same part keeps the link to the next one
+    link_next(&second, &part_path(&base, 3))?;
+    mark_part(&second, &base, 2, Some(&base), &options)?;
+    assert_eq!(read_part_info(&second).unwrap().next.as_deref(), Some("night_part003.zarr"));
+
+    std::fs::remove_dir_all(&dir)?;
+    Ok(())
+}
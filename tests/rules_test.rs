@@ -0,0 +1,80 @@
+use lsl_recording_toolbox::rules::{parse_rule, parse_stream_rule, Comparison, RuleAction, RuleEngine, RuleEvent};
+use std::time::Duration;
+
+/// Push samples `from..to` of a 10 Hz stream whose channels alternate around 0 with `amplitudes`
+fn push(engine: &mut RuleEngine, from: usize, to: usize, amplitudes: [f32; 2]) -> Vec<(usize, RuleEvent)> {
+    let mut events = Vec::new();
+    for i in from..to {
+        let sign = if i % 2 == 0 { 1.0 } else { -1.0 };
+        let sample = [sign * amplitudes[0], sign * amplitudes[1]];
+        events.extend(engine.push(i as f64 * 0.1, &sample).into_iter().map(|event| (i, event)));
+    }
+    events
+}
+
+#[test]
+fn test_parse_rule() {
+    let rule = parse_rule("rms < 5 for 30s then pause").unwrap();
+    assert_eq!(rule.comparison, Comparison::Below);
+    assert_eq!(rule.threshold, 5.0);
+    assert_eq!(rule.duration, Duration::from_secs(30));
+    assert_eq!(rule.action, RuleAction::Pause);
+    assert_eq!(rule.to_string(), "rms < 5 for 30s then pause");
+
+    // Warning is the default action, spaces around the comparison are optional
+    let rule = parse_rule("RMS>200.5 for 2min").unwrap();
+    assert_eq!((rule.comparison, rule.action), (Comparison::Above, RuleAction::Warn));
+    assert_eq!(rule.duration, Duration::from_secs(120));
+    assert_eq!(parse_rule(&rule.to_string()).unwrap(), rule);
+
+    let invalid = ["", "rms < 5", "mean < 5 for 3s", "rms = 5 for 3s", "rms < x for 3s", "rms < 5 for 3s then stop"];
+    for invalid in invalid {
+        assert!(parse_rule(invalid).is_err(), "{}", invalid);
+    }
+
+    let rule = parse_stream_rule("EEG: rms < 5 for 30s then pause").unwrap();
+    assert_eq!(rule.stream.as_deref(), Some("EEG"));
+    assert!(rule.applies_to("EEG", "EEG_5678"));
+    assert!(!rule.applies_to("EMG", "EMG_1234"));
+    assert!(parse_stream_rule("rms < 5 for 30s").unwrap().applies_to("EMG", "EMG_1234"));
+    assert!(parse_stream_rule(": rms < 5 for 30s").is_err());
+}
+
+#[test]
+fn test_rule_triggers_and_clears() {
+    // 10 Hz: one-second windows of 10 samples
+    let rule = parse_rule("rms < 5 for 3s then pause").unwrap();
+    let mut engine = RuleEngine::new(vec![rule.clone()], 2, 10.0);
+
+    // Both channels on the subject, then only one of them off: the rule needs all channels
+    assert!(push(&mut engine, 0, 20, [10.0, 10.0]).is_empty());
+    assert!(push(&mut engine, 20, 60, [1.0, 10.0]).is_empty());
+
+    // Both off from 6.0 s: the condition has held for 3 s at the end of the fourth window
+    let events = push(&mut engine, 60, 100, [1.0, 1.0]);
+    assert_eq!(events.len(), 1);
+    let (at, ref event) = events[0];
+    assert_eq!(at, 99);
+    assert!(event.triggered);
+    assert_eq!(event.rule, rule);
+    assert!((event.since - 6.0).abs() < 1e-9);
+
+    // Back on the subject: cleared at the end of the first window with signal
+    let events = push(&mut engine, 100, 110, [10.0, 10.0]);
+    assert_eq!(events.len(), 1);
+    assert_eq!(events[0].0, 109);
+    assert!(!events[0].1.triggered);
+
+    // Off again until recording ends
+    assert_eq!(push(&mut engine, 110, 150, [0.0, 0.0]).len(), 1);
+    engine.finish(14.9);
+
+    let summary = engine.summary();
+    assert_eq!(summary[0]["rule"], "rms < 5 for 3s then pause");
+    let triggers = summary[0]["triggers"].as_array().unwrap();
+    assert_eq!(triggers.len(), 2);
+    assert!((triggers[0][0].as_f64().unwrap() - 6.0).abs() < 1e-9);
+    assert!((triggers[0][1].as_f64().unwrap() - 10.0).abs() < 1e-9);
+    assert!((triggers[1][0].as_f64().unwrap() - 11.0).abs() < 1e-9);
+    assert_eq!(triggers[1][1], 14.9);
+}
@@ -0,0 +1,99 @@
+use chrono::{TimeZone, Utc};
+use lsl_recording_toolbox::schedule::{
+    append_block, parse_start_at, read_blocks, wait_until, BlockEvent, BlockSchedule, BlockTracker, BLOCKS_ATTRIBUTE,
+};
+use serde_json::Value;
+use std::sync::atomic::AtomicBool;
+use std::time::{Duration, Instant};
+
+#[test]
+fn test_parse_start_at() {
+    let now = Utc.with_ymd_and_hms(2025, 1, 31, 14, 0, 0).unwrap();
+
+    assert_eq!(parse_start_at("14:30:00", &now).unwrap(), Utc.with_ymd_and_hms(2025, 1, 31, 14, 30, 0).unwrap());
+    assert_eq!(parse_start_at(" 14:30 ", &now).unwrap(), Utc.with_ymd_and_hms(2025, 1, 31, 14, 30, 0).unwrap());
+    // A time of day that has passed means tomorrow
+    assert_eq!(parse_start_at("09:15", &now).unwrap(), Utc.with_ymd_and_hms(2025, 2, 1, 9, 15, 0).unwrap());
+    assert_eq!(parse_start_at("14:00:00", &now).unwrap(), Utc.with_ymd_and_hms(2025, 2, 1, 14, 0, 0).unwrap());
+
+    assert_eq!(
+        parse_start_at("2025-02-03 08:00", &now).unwrap(),
+        Utc.with_ymd_and_hms(2025, 2, 3, 8, 0, 0).unwrap()
+    );
+    assert_eq!(
+        parse_start_at("2025-02-03T08:00:30", &now).unwrap(),
+        Utc.with_ymd_and_hms(2025, 2, 3, 8, 0, 30).unwrap()
+    );
+
+    let past = parse_start_at("2025-01-31 13:59:59", &now).unwrap_err();
+    assert!(past.to_string().contains("in the past"));
+    assert!(parse_start_at("25:00", &now).is_err());
+    assert!(parse_start_at("half past two", &now).is_err());
+}
+
+#[test]
+fn test_block_schedule_offsets() {
+    let schedule = BlockSchedule {
+        blocks: 10,
+        block_duration: Duration::from_secs(60),
+        inter_block: Duration::from_secs(30),
+    };
+    assert_eq!(schedule.block_start(1), Duration::ZERO);
+    assert_eq!(schedule.block_start(2), Duration::from_secs(90));
+    assert_eq!(schedule.block_start(10), Duration::from_secs(810));
+    assert_eq!(schedule.total_duration(), Duration::from_secs(870));
+
+    let single = BlockSchedule { blocks: 1, ..schedule };
+    assert_eq!(single.total_duration(), Duration::from_secs(60));
+}
+
+#[test]
+fn test_block_tracker() {
+    let mut tracker = BlockTracker::default();
+    assert!(tracker.apply(BlockEvent::Ended { lsl_clock: 5.0 }).is_none());
+    assert!(tracker.interrupt(5.0).is_none());
+
+    assert!(tracker.apply(BlockEvent::Started { index: 1, lsl_clock: 100.0 }).is_none());
+    let block = tracker.apply(BlockEvent::Ended { lsl_clock: 160.0 }).unwrap();
+    assert_eq!((block.index, block.start, block.end, block.interrupted), (1, 100.0, 160.0, false));
+    assert!(block.start_utc.ends_with('Z'));
+
+    // QUIT during the second block
+    tracker.apply(BlockEvent::Started { index: 2, lsl_clock: 190.0 });
+    let block = tracker.interrupt(201.5).unwrap();
+    assert_eq!((block.index, block.end, block.interrupted), (2, 201.5, true));
+    assert!(tracker.interrupt(210.0).is_none());
+}
+
+#[test]
+fn test_append_and_read_blocks() {
+    let mut attributes = serde_json::Map::new();
+    assert!(read_blocks(&Value::Object(attributes.clone())).is_empty());
+
+    let mut tracker = BlockTracker::default();
+    tracker.apply(BlockEvent::Started { index: 1, lsl_clock: 100.0 });
+    let first = tracker.apply(BlockEvent::Ended { lsl_clock: 160.0 }).unwrap();
+    tracker.apply(BlockEvent::Started { index: 2, lsl_clock: 190.0 });
+    let second = tracker.interrupt(201.5).unwrap();
+    append_block(&mut attributes, &first).unwrap();
+    append_block(&mut attributes, &second).unwrap();
+
+    let stored = attributes[BLOCKS_ATTRIBUTE].as_array().unwrap();
+    assert_eq!(stored.len(), 2);
+    assert!(stored[0].get("interrupted").is_none());
+    assert_eq!(stored[1]["interrupted"], true);
+    assert_eq!(read_blocks(&Value::Object(attributes)), vec![first, second]);
+}
+
+#[test]
+fn test_wait_until() {
+    let quit = AtomicBool::new(false);
+    let start = Instant::now();
+    assert!(wait_until(start + Duration::from_millis(50), &quit));
+    assert!(start.elapsed() >= Duration::from_millis(50));
+
+    let quit = AtomicBool::new(true);
+    let start = Instant::now();
+    assert!(!wait_until(start + Duration::from_secs(10), &quit));
+    assert!(start.elapsed() < Duration::from_secs(1));
+}
@@ -0,0 +1,71 @@
+use anyhow::Result;
+use lsl_recording_toolbox::session_lock::{read_session_holders, SessionHolder, SessionLock, SESSION_LOCK_FILE};
+
+#[test]
+fn test_second_session_refused_and_joined() -> Result<()> {
+    let dir = std::env::temp_dir().join(format!("lsl_session_lock_{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    let store = dir.join("experiment.zarr");
+
+    let first = SessionLock::acquire(&store, Some("session_001"), false)?;
+    assert_eq!(read_session_holders(&store).len(), 1);
+
+    let refused = SessionLock::acquire(&store, Some("session_002"), false).unwrap_err();
+    assert!(refused.to_string().contains("--join-session"), "{}", refused);
+    assert!(SessionLock::acquire(&store, Some("session_002"), true).is_err());
+
+    let joined = SessionLock::acquire(&store, Some("session_001"), true)?;
+    assert_eq!(joined.others().len(), 1);
+    assert_eq!(read_session_holders(&store).len(), 2);
+    drop(joined);
+    assert_eq!(read_session_holders(&store).len(), 1);
+
+    // The store was never written to, so releasing the last lock removes it again
+    drop(first);
+    assert!(!store.exists());
+
+    std::fs::remove_dir_all(&dir)?;
+    Ok(())
+}
+
+#[test]
+fn test_stale_holder_is_replaced() -> Result<()> {
+    let dir = std::env::temp_dir().join(format!("lsl_session_lock_stale_{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    let store = dir.join("experiment.zarr");
+    std::fs::create_dir_all(&store)?;
+    std::fs::write(store.join("zarr.json"), "{}")?;
+
+    // A crashed run on this host left its registration behind
+    let crashed = SessionHolder {
+        pid: 999_999_999,
+        ..SessionHolder::current(Some("old"))
+    };
+    assert!(!crashed.is_alive());
+    std::fs::write(
+        store.join(SESSION_LOCK_FILE),
+        serde_json::json!({"holders": [crashed]}).to_string(),
+    )?;
+
+    let lock = SessionLock::acquire(&store, None, false)?;
+    assert!(lock.others().is_empty());
+    let holders = read_session_holders(&store);
+    assert_eq!((holders.len(), holders[0].pid), (1, std::process::id()));
+
+    // Holders on other hosts cannot be checked and block the store
+    drop(lock);
+    let remote = SessionHolder {
+        host: Some("other-host".to_string()),
+        ..crashed
+    };
+    assert!(remote.is_alive());
+    std::fs::write(
+        store.join(SESSION_LOCK_FILE),
+        serde_json::json!({"holders": [remote]}).to_string(),
+    )?;
+    assert!(SessionLock::acquire(&store, None, false).is_err());
+    assert!(store.exists());
+
+    std::fs::remove_dir_all(&dir)?;
+    Ok(())
+}
@@ -0,0 +1,37 @@
+mod common;
+
+use anyhow::Result;
+use lsl_recording_toolbox::session::summarize_store;
+use serde_json::json;
+use std::path::Path;
+
+/// Create a store with one stream group carrying `attributes`
+fn write_store(path: &Path, attributes: serde_json::Value) -> Result<()> {
+    let store = common::create_store(path)?;
+    common::write_stream_group(&store, "EMG", attributes)
+}
+
+#[test]
+fn test_recorded_at_follows_start_time_anchor() -> Result<()> {
+    let store = std::env::temp_dir().join(format!("lsl_session_{}.zarr", std::process::id()));
+
+    // Configured at connect time, first sample recorded ten minutes later
+    write_store(
+        &store,
+        json!({
+            "recorder_config": {"recorded_at": "2025-03-01T09:50:00+00:00"},
+            "start_time": {"utc": "2025-03-01T10:00:00.000000Z", "local": "2025-03-01T11:00:00.000000+01:00",
+                           "lsl_clock": 5000.0},
+        }),
+    )?;
+    let summary = summarize_store(&store)?;
+    assert_eq!(summary.recorded_at.as_deref(), Some("2025-03-01T10:00:00+00:00"));
+
+    // Older stores without the anchor keep their RFC3339 string
+    write_store(&store, json!({"recorded_at": "2024-11-05T08:00:00+00:00"}))?;
+    let summary = summarize_store(&store)?;
+    assert_eq!(summary.recorded_at.as_deref(), Some("2024-11-05T08:00:00+00:00"));
+
+    std::fs::remove_dir_all(&store)?;
+    Ok(())
+}
@@ -0,0 +1,110 @@
+use lsl_recording_toolbox::signals::{SignalGenerator, SignalOptions, Simulation, Waveform};
+
+fn options(waveform: Waveform, simulate: Option<Simulation>, channels: usize) -> SignalOptions {
+    SignalOptions {
+        waveform,
+        simulate,
+        frequencies: vec![5.5; channels],
+        chirp_range: (1.0, 50.0),
+        chirp_duration: 2.0,
+        trigger_frequency: None,
+        sample_rate: 1000.0,
+        seed: 7,
+    }
+}
+
+fn generate(options: SignalOptions, samples: usize) -> Vec<Vec<f64>> {
+    let mut generator = SignalGenerator::new(options).unwrap();
+    (0..samples).map(|_| generator.next_sample()).collect()
+}
+
+fn channel(samples: &[Vec<f64>], index: usize) -> Vec<f64> {
+    samples.iter().map(|sample| sample[index]).collect()
+}
+
+fn rising_crossings(values: &[f64]) -> usize {
+    values.windows(2).filter(|w| w[0] < 0.0 && w[1] >= 0.0).count()
+}
+
+/// Correlation of consecutive values: near 0 for white noise, near 1 for low frequencies
+fn lag_one_correlation(values: &[f64]) -> f64 {
+    let mean = values.iter().sum::<f64>() / values.len() as f64;
+    let variance: f64 = values.iter().map(|v| (v - mean).powi(2)).sum();
+    let covariance: f64 = values.windows(2).map(|w| (w[0] - mean) * (w[1] - mean)).sum();
+    covariance / variance
+}
+
+#[test]
+fn test_waveforms() {
+    // 5.5 Hz sine over 2 s
+    let sine = channel(&generate(options(Waveform::Sine, None, 1), 2000), 0);
+    assert_eq!(rising_crossings(&sine), 10);
+    assert!((sine.iter().copied().fold(0.0, f64::max) - 1.0).abs() < 1e-3);
+
+    // A chirp crosses zero faster at the end of its 1-50 Hz sweep
+    let chirp = channel(&generate(options(Waveform::Chirp, None, 1), 2000), 0);
+    assert!(rising_crossings(&chirp[1500..]) > 4 * rising_crossings(&chirp[..500]));
+
+    let white = channel(&generate(options(Waveform::WhiteNoise, None, 1), 20000), 0);
+    let pink = channel(&generate(options(Waveform::PinkNoise, None, 1), 20000), 0);
+    assert!(lag_one_correlation(&white).abs() < 0.05);
+    assert!(lag_one_correlation(&pink) > 0.5);
+
+    // The same seed gives the same noise, channels differ
+    let noise = generate(options(Waveform::WhiteNoise, None, 2), 100);
+    assert_eq!(noise, generate(options(Waveform::WhiteNoise, None, 2), 100));
+    assert_ne!(channel(&noise, 0), channel(&noise, 1));
+}
+
+#[test]
+fn test_trigger_channel() {
+    let mut trigger = options(Waveform::Sine, None, 3);
+    trigger.trigger_frequency = Some(2.0);
+    let samples = generate(trigger, 1000);
+    let last = channel(&samples, 2);
+    assert!(last.iter().all(|v| *v == 0.0 || *v == 1.0));
+    assert_eq!(last.windows(2).filter(|w| w[0] == 0.0 && w[1] == 1.0).count(), 1);
+    assert_eq!(last[..250].iter().sum::<f64>(), 250.0);
+
+    let mut too_fast = options(Waveform::Sine, None, 1);
+    too_fast.trigger_frequency = Some(600.0);
+    assert!(SignalGenerator::new(too_fast).is_err());
+}
+
+#[test]
+fn test_simulations() {
+    for simulation in [Simulation::Emg, Simulation::Eeg, Simulation::Ecg] {
+        let samples = generate(options(Waveform::Sine, Some(simulation), 4), 8000);
+        // Within range without being clipped
+        assert!(samples.iter().flatten().all(|v| v.abs() < 0.95), "{:?}", simulation);
+    }
+
+    // EMG bursts: contraction in the first half of every 4 s, rest in the second
+    let emg = channel(&generate(options(Waveform::Sine, Some(Simulation::Emg), 1), 4000), 0);
+    let rms = |values: &[f64]| (values.iter().map(|v| v * v).sum::<f64>() / values.len() as f64).sqrt();
+    assert!(rms(&emg[500..1500]) > 5.0 * rms(&emg[2500..3500]));
+
+    // ECG: about 72 R peaks per minute on the first lead
+    let ecg = channel(&generate(options(Waveform::Sine, Some(Simulation::Ecg), 1), 10000), 0);
+    let mut beats = 0;
+    let mut armed = true;
+    for value in ecg {
+        if armed && value > 0.5 {
+            beats += 1;
+            armed = false;
+        } else if value < 0.2 {
+            armed = true;
+        }
+    }
+    assert!((11..=13).contains(&beats), "{} beats in 10 s", beats);
+
+    // Low sample rates narrow the bands instead of inverting them
+    for (simulation, sample_rate) in [(Simulation::Emg, 50.0), (Simulation::Emg, 10.0), (Simulation::Eeg, 2.0)] {
+        let mut slow = options(Waveform::Sine, Some(simulation), 1);
+        slow.sample_rate = sample_rate;
+        let samples = generate(slow, 400);
+        let values = channel(&samples, 0);
+        assert!(values.iter().all(|v| v.is_finite() && v.abs() < 0.95), "{:?} at {} Hz", simulation, sample_rate);
+        assert!(values.iter().any(|v| *v != 0.0), "{:?} at {} Hz", simulation, sample_rate);
+    }
+}
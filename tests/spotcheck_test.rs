@@ -0,0 +1,62 @@
+use lsl_recording_toolbox::spotcheck::{evaluate, quicklook_svg, SpotcheckCriteria};
+use serde_json::json;
+
+fn stream(name: &str, accuracy: f64, lost_time_ms: f64) -> serde_json::Value {
+    json!({
+        "name": name,
+        "samples": 30000,
+        "nominal_rate": 1000.0,
+        "actual_rate": accuracy * 10.0,
+        "rate_accuracy_percent": accuracy,
+        "duration": 30.0,
+        "gaps": { "count": if lost_time_ms > 0.0 { 1 } else { 0 }, "lost_time_ms": lost_time_ms }
+    })
+}
+
+#[test]
+fn test_evaluate_go() {
+    let report = json!({
+        "streams": [stream("EMG", 99.9, 0.0), stream("EEG", 100.2, 10.0)],
+        "failures": [],
+        "drift_warnings": ["EMG vs EEG: 80.0 ppm exceeds the 50.0 ppm budget"]
+    });
+    let verdict = evaluate(&report, &["EMG".to_string(), "EEG".to_string()], &SpotcheckCriteria::default());
+    assert!(verdict.go());
+    // Drift over budget only warns
+    assert_eq!(verdict.warnings.len(), 1);
+}
+
+#[test]
+fn test_evaluate_no_go() {
+    let mut empty = stream("Markers", 0.0, 0.0);
+    empty["samples"] = json!(0);
+    let report = json!({
+        "streams": [stream("EMG", 90.0, 0.0), stream("EEG", 100.0, 600.0), empty],
+        "failures": ["Start time difference 350.0 ms exceeds 200.0 ms"]
+    });
+    let verdict = evaluate(&report, &["EMG".to_string(), "ECG".to_string()], &SpotcheckCriteria::default());
+    assert!(!verdict.go());
+    assert_eq!(verdict.problems.len(), 5);
+    assert!(verdict.problems.iter().any(|p| p.contains("'ECG' was not recorded")));
+    assert!(verdict.problems.iter().any(|p| p.contains("'Markers' recorded no samples")));
+    assert!(verdict.problems.iter().any(|p| p.contains("'EMG' runs at 90.0%")));
+    assert!(verdict.problems.iter().any(|p| p.contains("'EEG' lost 2.0%")));
+
+    let verdict = evaluate(&json!({ "streams": [] }), &[], &SpotcheckCriteria::default());
+    assert_eq!(verdict.problems, vec!["No stream was recorded".to_string()]);
+}
+
+#[test]
+fn test_quicklook_svg() {
+    let sine: Vec<f64> = (0..5000).map(|i| (i as f64 * 0.01).sin()).collect();
+    let channels = vec![sine, vec![1.5; 5000], vec![f64::NAN; 5000]];
+    let labels = vec!["C1".to_string(), "C2".to_string(), "C<3>".to_string()];
+    let svg = quicklook_svg("EMG", 5.0, &channels, &labels);
+
+    assert!(svg.starts_with("<svg") && svg.ends_with("</svg>\n"));
+    assert_eq!(svg.matches("<polyline").count(), 1);
+    assert!(svg.contains("flat (1.5)"));
+    assert!(svg.contains("no finite values"));
+    assert!(svg.contains("C&lt;3&gt;"));
+    assert!(svg.contains("5.0 s"));
+}
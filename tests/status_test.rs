@@ -0,0 +1,170 @@
+use anyhow::Result;
+use lsl_recording_toolbox::status::{serve_status, session_state, RecordingState, StatusTracker, StreamStatus};
+use std::io::{Read, Write};
+use std::net::TcpStream;
+
+#[test]
+fn test_progress_line_round_trip() {
+    let status = StreamStatus {
+        stream: "EMG".to_string(),
+        state: RecordingState::Recording,
+        samples_recorded: 598000,
+        rate_hz: 1999.6,
+        nominal_srate: 2000.0,
+        buffer_fill: 0.125,
+        last_timestamp: Some(81234.567891),
+        dropped_samples_estimate: 3,
+        elapsed_s: 312.5,
+        since_flush_s: Some(0.8),
+        write_queue: 0,
+        write_latency_ms: None,
+        write_latency_max_ms: None,
+        implausible_samples: None,
+    };
+    let line = status.progress_line();
+    assert_eq!(
+        line,
+        "STATUS PROGRESS state=recording samples=598000 rate=1999.6Hz nominal=2000Hz buffer=0.125 dropped=3 \
+         elapsed=312.5s last_ts=81234.567891 flushed=0.8s"
+    );
+    let forwarded = format!("[+01:02.003] [EMG-OUT] {}", line);
+    assert_eq!(StreamStatus::parse_progress("EMG", &forwarded), Some(status));
+
+    // Before the first sample
+    let parsed = StreamStatus::parse_progress(
+        "EEG",
+        "STATUS PROGRESS state=ready samples=0 rate=0.0Hz nominal=500Hz buffer=0.000 dropped=0",
+    )
+    .unwrap();
+    assert_eq!(parsed.state, RecordingState::Ready);
+    assert_eq!(parsed.last_timestamp, None);
+    assert_eq!(parsed.elapsed_s, 0.0);
+    assert_eq!(parsed.since_flush_s, None);
+
+    assert!(StreamStatus::parse_progress("EMG", "STATUS PROGRESS state=sleeping").is_none());
+    assert!(StreamStatus::parse_progress("EMG", "STATUS STARTED").is_none());
+}
+
+#[test]
+fn test_progress_line_write_figures() {
+    let mut tracker = StatusTracker::new("EMG", 0.0);
+    tracker.set_write_stats(2, None);
+    // No write figures before the writer thread has written
+    assert!(!tracker.status().progress_line().contains("queue="));
+
+    tracker.set_write_stats(3, Some((4.31, 120.0)));
+    let line = tracker.status().progress_line();
+    assert!(line.ends_with(" queue=3 write=4.3ms write_max=120.0ms"), "{}", line);
+    let parsed = StreamStatus::parse_progress("EMG", &line).unwrap();
+    assert_eq!(parsed.write_queue, 3);
+    assert_eq!(parsed.write_latency_ms, Some(4.3));
+    assert_eq!(parsed.write_latency_max_ms, Some(120.0));
+
+    // The implausible count follows once a plausible range is set
+    tracker.set_implausible_samples(4);
+    let line = tracker.status().progress_line();
+    assert!(line.ends_with(" implausible=4"), "{}", line);
+    assert_eq!(StreamStatus::parse_progress("EMG", &line).unwrap().implausible_samples, Some(4));
+}
+
+#[test]
+fn test_dropped_sample_estimate() {
+    let mut tracker = StatusTracker::new("EMG", 0.0);
+    tracker.set_nominal_srate(100.0);
+    tracker.set_state(RecordingState::Recording);
+    for i in 0..10 {
+        tracker.sample(i as f64 * 0.01);
+    }
+    assert_eq!(tracker.status().dropped_samples_estimate, 0);
+
+    // 5 samples missing between 0.09 and 0.15
+    tracker.sample(0.15);
+    assert_eq!(tracker.status().dropped_samples_estimate, 5);
+
+    // A pause between STOP and START is not a dropout
+    tracker.set_state(RecordingState::Stopped);
+    tracker.set_state(RecordingState::Recording);
+    tracker.sample(10.0);
+    assert_eq!(tracker.status().dropped_samples_estimate, 5);
+    assert_eq!(tracker.status().samples_recorded, 12);
+    assert_eq!(tracker.status().last_timestamp, Some(10.0));
+
+    // Irregular streams have no expected spacing
+    let mut tracker = StatusTracker::new("Markers", 0.0);
+    tracker.set_state(RecordingState::Recording);
+    tracker.sample(1.0);
+    tracker.sample(50.0);
+    assert_eq!(tracker.status().dropped_samples_estimate, 0);
+
+    tracker.finish();
+    assert_eq!(tracker.shared().lock().unwrap().state, RecordingState::Finished);
+}
+
+#[test]
+fn test_elapsed_and_flush_age() {
+    let mut tracker = StatusTracker::new("EMG", 0.0);
+    assert_eq!(tracker.status().elapsed_s, 0.0);
+
+    tracker.set_state(RecordingState::Recording);
+    std::thread::sleep(std::time::Duration::from_millis(30));
+    tracker.flushed();
+    tracker.set_state(RecordingState::Stopped);
+    let recorded = tracker.status().elapsed_s;
+    assert!(recorded >= 0.03);
+    assert!(tracker.status().since_flush_s.is_some_and(|age| age < recorded));
+
+    // Time between STOP and START is not counted
+    std::thread::sleep(std::time::Duration::from_millis(30));
+    tracker.set_state(RecordingState::Recording);
+    assert!(tracker.status().elapsed_s < recorded + 0.02);
+}
+
+#[test]
+fn test_session_state() {
+    let with_states = |states: &[RecordingState]| -> Vec<StreamStatus> {
+        states
+            .iter()
+            .map(|&state| StreamStatus {
+                state,
+                ..StreamStatus::new("S")
+            })
+            .collect()
+    };
+    use RecordingState::*;
+    assert_eq!(session_state(&[]), Resolving);
+    assert_eq!(session_state(&with_states(&[Ready, Recording])), Recording);
+    assert_eq!(session_state(&with_states(&[Lost, Stopped])), Lost);
+    assert_eq!(session_state(&with_states(&[Finished, Finished])), Finished);
+    assert_eq!(session_state(&with_states(&[Finished, Stopped])), Stopped);
+    assert_eq!(session_state(&with_states(&[Resolving, Ready])), Ready);
+}
+
+fn http_get(address: std::net::SocketAddr, path: &str) -> Result<String> {
+    let mut stream = TcpStream::connect(("127.0.0.1", address.port()))?;
+    write!(stream, "GET {} HTTP/1.1\r\nHost: localhost\r\n\r\n", path)?;
+    let mut response = String::new();
+    stream.read_to_string(&mut response)?;
+    Ok(response)
+}
+
+#[test]
+fn test_status_endpoint() -> Result<()> {
+    let tracker = StatusTracker::new("EMG", 0.0);
+    let shared = tracker.shared();
+    let address = serve_status(0, "lsl-recorder", move || vec![shared.lock().unwrap().clone()])?;
+
+    let response = http_get(address, "/status")?;
+    assert!(response.starts_with("HTTP/1.1 200 OK"));
+    assert!(response.contains("Content-Type: application/json"));
+    let body = response.split("\r\n\r\n").nth(1).unwrap();
+    let json: serde_json::Value = serde_json::from_str(body)?;
+    assert_eq!(json["tool"], "lsl-recorder");
+    assert_eq!(json["state"], "resolving");
+    assert_eq!(json["streams"][0]["stream"], "EMG");
+    assert_eq!(json["streams"][0]["samples_recorded"], 0);
+    assert!(json["streams"][0]["last_timestamp"].is_null());
+
+    assert!(http_get(address, "/")?.starts_with("HTTP/1.1 200 OK"));
+    assert!(http_get(address, "/metrics")?.starts_with("HTTP/1.1 404"));
+    Ok(())
+}
@@ -0,0 +1,68 @@
+mod common;
+
+use anyhow::Result;
+use lsl_recording_toolbox::zarr::cache::{cache_file_path, StoreCache, StreamExtent};
+use std::path::Path;
+use std::sync::Arc;
+use zarrs::array::Array;
+use zarrs::filesystem::FilesystemStore;
+use zarrs::group::GroupBuilder;
+
+/// Store with one EEG stream of 8 timestamps
+fn write_store(path: &Path) -> Result<Array<FilesystemStore>> {
+    let store = common::create_store(path)?;
+    common::write_stream_group(&store, "EEG", serde_json::json!({}))?;
+    common::write_time(&store, "EEG", common::regular_timestamps(8, 100.0, 1.0))
+}
+
+#[test]
+fn test_cache_file_path() {
+    assert_eq!(
+        cache_file_path(Path::new("/nonexistent/data/P001.zarr"), Path::new("/cache")),
+        Path::new("/cache/stores/nonexistent/data/P001.zarr.json")
+    );
+}
+
+#[test]
+fn test_store_cache_roundtrip_and_invalidation() -> Result<()> {
+    let dir = std::env::temp_dir().join(format!("lsl_store_cache_{}", std::process::id()));
+    let store_path = dir.join("session.zarr");
+    let cache_dir = dir.join("cache");
+    let time = write_store(&store_path)?;
+
+    let extent = StreamExtent { samples: 8, first: 100.0, last: 107.0 };
+    let mut cache = StoreCache::open_in(&store_path, &cache_dir);
+    assert!(cache.stream("EEG").is_none());
+    // Only streams with a time array get entries
+    assert!(cache.stream_mut("Markers").is_none());
+    cache.stream_mut("EEG").unwrap().extent = Some(extent);
+    cache.save()?;
+    assert!(cache_file_path(&store_path, &cache_dir).exists());
+
+    let cache = StoreCache::open_in(&store_path, &cache_dir);
+    assert_eq!(cache.stream("EEG").and_then(|c| c.extent), Some(extent));
+
+    // New attributes on the stream group leave the arrays, and the entry, untouched
+    let store = Arc::new(FilesystemStore::new(&store_path)?);
+    let mut attributes = serde_json::Map::new();
+    attributes.insert("alignment_offset".to_string(), serde_json::json!(0.5));
+    GroupBuilder::new().attributes(attributes).build(store, "/EEG")?.store_metadata()?;
+    assert!(StoreCache::open_in(&store_path, &cache_dir).stream("EEG").is_some());
+
+    // Rewriting the time array drops the entry
+    std::thread::sleep(std::time::Duration::from_millis(20));
+    time.store_metadata()?;
+    let mut cache = StoreCache::open_in(&store_path, &cache_dir);
+    assert!(cache.stream("EEG").is_none());
+    assert!(cache.stream_mut("EEG").unwrap().extent.is_none());
+
+    // A disabled cache neither reads nor writes
+    let mut cache = StoreCache::disabled(&store_path);
+    assert!(cache.stream_mut("EEG").is_none());
+    std::fs::remove_dir_all(&cache_dir)?;
+    cache.save()?;
+    assert!(!cache_dir.exists());
+
+    std::fs::remove_dir_all(&dir)?;
+    Ok(())
+}
@@ -0,0 +1,81 @@
+use lsl_recording_toolbox::session::{SessionSummary, StreamSummary};
+use lsl_recording_toolbox::study::{check_sessions, StudyCheck, ALL_STUDY_CHECKS};
+use std::path::PathBuf;
+
+fn stream(name: &str, channels: u64, rate: f64, format: &str) -> StreamSummary {
+    StreamSummary {
+        name: name.to_string(),
+        channel_count: Some(channels),
+        nominal_srate: Some(rate),
+        channel_format: Some(format.to_string()),
+        ..Default::default()
+    }
+}
+
+fn session(path: &str, streams: Vec<StreamSummary>) -> SessionSummary {
+    SessionSummary {
+        path: PathBuf::from(path),
+        streams,
+        ..Default::default()
+    }
+}
+
+/// Four weekly sessions; the EEG montage changed in week 3 and week 4 has no markers
+fn study() -> Vec<SessionSummary> {
+    let week = |n: u32, eeg_channels: u64, markers: bool| {
+        let mut streams = vec![stream("EEG", eeg_channels, 500.0, "Float32"), stream("EMG", 8, 2000.0, "Float32")];
+        if markers {
+            streams.push(stream("Markers", 1, 0.0, "String"));
+        }
+        session(&format!("study/week{}.zarr", n), streams)
+    };
+    vec![week(1, 64, true), week(2, 64, true), week(3, 32, true), week(4, 64, false)]
+}
+
+#[test]
+fn test_study_outliers() {
+    let report = check_sessions(&study(), &ALL_STUDY_CHECKS);
+    assert_eq!(report.sessions.len(), 4);
+    assert_eq!(report.streams.iter().map(|s| s.sessions).collect::<Vec<_>>(), vec![4, 4, 3]);
+    assert_eq!(
+        report.streams[0].expected,
+        vec![
+            (StudyCheck::Channels, "64".to_string()),
+            (StudyCheck::Rate, "500".to_string()),
+            (StudyCheck::Format, "Float32".to_string()),
+        ]
+    );
+
+    assert_eq!(report.outliers.len(), 1);
+    let outlier = &report.outliers[0];
+    assert_eq!((outlier.stream.as_str(), outlier.check), ("EEG", StudyCheck::Channels));
+    assert_eq!(outlier.session, PathBuf::from("study/week3.zarr"));
+    assert_eq!((outlier.value.as_str(), outlier.expected.as_str()), ("32", "64"));
+    assert_eq!((outlier.agreeing, outlier.sessions), (3, 4));
+    assert!(!report.is_consistent());
+
+    // Only the checked properties count
+    let report = check_sessions(&study(), &[StudyCheck::Rate, StudyCheck::Format]);
+    assert!(report.is_consistent());
+}
+
+#[test]
+fn test_study_ties_and_missing_values() {
+    // Two sessions each: the session that sorts first sets the expected rate
+    let mut sessions = vec![
+        session("a.zarr", vec![stream("EMG", 8, 1000.0, "Float32")]),
+        session("b.zarr", vec![stream("EMG", 8, 2000.0, "Float32")]),
+        session("c.zarr", vec![stream("EMG", 8, 2000.0, "Float32")]),
+        session("d.zarr", vec![stream("EMG", 8, 1000.0, "Float32")]),
+    ];
+    let report = check_sessions(&sessions, &[StudyCheck::Rate]);
+    let flagged: Vec<_> = report.outliers.iter().map(|o| o.session.display().to_string()).collect();
+    assert_eq!(flagged, ["b.zarr", "c.zarr"]);
+
+    // Streams without the property are left out of the comparison
+    sessions[1].streams[0].nominal_srate = None;
+    sessions[2].streams[0].nominal_srate = None;
+    assert!(check_sessions(&sessions, &[StudyCheck::Rate]).is_consistent());
+
+    assert!(check_sessions(&[], &ALL_STUDY_CHECKS).streams.is_empty());
+}
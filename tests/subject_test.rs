@@ -0,0 +1,68 @@
+mod common;
+
+use anyhow::Result;
+use lsl_recording_toolbox::subject::{auto_subject, next_subject, output_root, SubjectPattern};
+use serde_json::json;
+use std::path::{Path, PathBuf};
+
+/// Create a store whose stream was recorded for `subject`
+fn write_store(path: &Path, subject: &str) -> Result<()> {
+    let store = common::create_store(path)?;
+    common::write_stream_group(&store, "EMG", json!({ "recorder_config": { "subject": subject } }))
+}
+
+#[test]
+fn test_subject_pattern() -> Result<()> {
+    let pattern = SubjectPattern::parse("P###")?;
+    assert_eq!(pattern.format(1), "P001");
+    assert_eq!(pattern.format(1234), "P1234");
+    assert_eq!(pattern.number("P012"), Some(12));
+    assert_eq!(pattern.number("P7"), Some(7));
+    assert_eq!(pattern.number("P"), None);
+    assert_eq!(pattern.number("Pilot"), None);
+    assert_eq!(pattern.number("Q001"), None);
+
+    let pattern = SubjectPattern::parse("sub-##_pilot")?;
+    assert_eq!(pattern.format(3), "sub-03_pilot");
+    assert_eq!(pattern.number("sub-03_pilot"), Some(3));
+    assert_eq!(pattern.number("sub-03"), None);
+
+    assert!(SubjectPattern::parse("P").is_err());
+    assert!(SubjectPattern::parse("P##-##").is_err());
+    assert!(SubjectPattern::parse("data/P###").is_err());
+    Ok(())
+}
+
+#[test]
+fn test_output_root() {
+    assert_eq!(output_root(Path::new("data/{subject}/session_{date}")), PathBuf::from("data"));
+    assert_eq!(output_root(Path::new("data/experiment")), PathBuf::from("data"));
+    assert_eq!(output_root(Path::new("{subject}_{date}")), PathBuf::from("."));
+    assert_eq!(output_root(Path::new("experiment")), PathBuf::from("."));
+}
+
+#[test]
+fn test_next_subject() -> Result<()> {
+    let root = std::env::temp_dir().join(format!("lsl_subjects_{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&root);
+    std::fs::create_dir_all(&root)?;
+    let pattern = SubjectPattern::parse("P###")?;
+    assert_eq!(next_subject(&root, &pattern)?, "P001");
+
+    // Subjects from directory names and from store metadata
+    write_store(&root.join("P001").join("session_2025-01-31.zarr"), "P001")?;
+    write_store(&root.join("pilot_2025-02-01.zarr"), "P004")?;
+    std::fs::create_dir_all(root.join("P002"))?;
+    std::fs::create_dir_all(root.join(".P099"))?;
+    assert_eq!(next_subject(&root, &pattern)?, "P005");
+
+    // Unrelated patterns are counted separately
+    assert_eq!(next_subject(&root, &SubjectPattern::parse("S##")?)?, "S01");
+
+    let output = root.join("{subject}").join("session_{date}");
+    assert_eq!(auto_subject(&output, "P###")?, "P005");
+    assert!(auto_subject(Path::new("s3://bucket/{subject}/session"), "P###").is_err());
+
+    std::fs::remove_dir_all(&root)?;
+    Ok(())
+}
@@ -0,0 +1,78 @@
+mod common;
+
+use anyhow::Result;
+use lsl_recording_toolbox::export::table::{is_broken_pipe, write_table, TableOptions};
+use lsl_recording_toolbox::export::{ExportFormat, ExportOptions};
+use serde_json::json;
+use std::path::Path;
+
+/// EMG (2 channels) and EEG, 8 samples each at 4 Hz from t=10 with value `channel * 10 + sample`
+fn write_store(path: &Path) -> Result<()> {
+    let store = common::create_store(path)?;
+    let emg = json!({
+        "channel_format": "Float32", "channel_count": 2, "nominal_srate": 4.0,
+        "channels": [{ "label": "C3" }, { "label": "C4" }],
+    });
+    let eeg = json!({ "channel_format": "Float32", "channel_count": 1, "nominal_srate": 4.0 });
+    let timestamps = common::regular_timestamps(8, 10.0, 0.25);
+    common::write_stream(&store, "EMG", json!({ "stream_info": emg }), common::ramp(2, 8), timestamps.clone())?;
+    common::write_stream(&store, "EEG", json!({ "stream_info": eeg }), common::ramp(1, 8), timestamps)
+}
+
+#[test]
+fn test_write_table_ndjson_and_csv() -> Result<()> {
+    let store = std::env::temp_dir().join(format!("lsl_table_stream_{}.zarr", std::process::id()));
+    write_store(&store)?;
+    let options = ExportOptions {
+        streams: vec!["EMG".to_string()],
+        ..ExportOptions::default()
+    };
+
+    // Seconds 0.5-1.5 of the recording: samples 2 to 5
+    let table = TableOptions {
+        start: Some(0.5),
+        end: Some(1.5),
+        ..TableOptions::default()
+    };
+    let mut out = Vec::new();
+    let rows = write_table(&store, &mut out, ExportFormat::Ndjson, &options, &table)?;
+    assert_eq!(rows, 4);
+    let lines: Vec<serde_json::Value> = String::from_utf8(out)?
+        .lines()
+        .map(serde_json::from_str)
+        .collect::<Result<_, _>>()?;
+    assert_eq!(lines.len(), 4);
+    assert_eq!(lines[0], json!({ "time": 10.5, "C3": 2.0, "C4": 12.0 }));
+    assert_eq!(lines[3]["time"], 11.25);
+
+    let table = TableOptions {
+        channels: vec!["C4".to_string()],
+        ..TableOptions::default()
+    };
+    let mut out = Vec::new();
+    assert_eq!(write_table(&store, &mut out, ExportFormat::Csv, &options, &table)?, 8);
+    let csv = String::from_utf8(out)?;
+    let mut lines = csv.lines();
+    assert_eq!(lines.next(), Some("time,C4"));
+    assert_eq!(lines.next(), Some("10,10"));
+    assert_eq!(csv.lines().count(), 9);
+
+    // One stream at a time, and only text formats
+    let all = ExportOptions::default();
+    let error = write_table(&store, Vec::new(), ExportFormat::Csv, &all, &table).unwrap_err();
+    assert!(error.to_string().contains("EEG, EMG"));
+    assert!(write_table(&store, Vec::new(), ExportFormat::Parquet, &options, &table).is_err());
+
+    std::fs::remove_dir_all(&store)?;
+    Ok(())
+}
+
+#[test]
+fn test_is_broken_pipe() {
+    let pipe = std::io::Error::from(std::io::ErrorKind::BrokenPipe);
+    assert!(is_broken_pipe(&anyhow::Error::from(pipe).context("writing samples")));
+    assert!(is_broken_pipe(&anyhow::Error::from(csv::Error::from(std::io::Error::from(
+        std::io::ErrorKind::BrokenPipe
+    )))));
+    assert!(!is_broken_pipe(&anyhow::anyhow!("Stream 'EMG' not found")));
+}
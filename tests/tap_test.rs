@@ -0,0 +1,20 @@
+#![cfg(feature = "lsl")]
+
+use lsl_recording_toolbox::tap::{parse_tap_address, tap_decimation};
+
+#[test]
+fn test_parse_tap_address() {
+    assert_eq!(parse_tap_address("ws://0.0.0.0:9001").unwrap(), "0.0.0.0:9001");
+    assert_eq!(parse_tap_address("ws://localhost:9001/emg").unwrap(), "localhost:9001");
+    assert_eq!(parse_tap_address("127.0.0.1:9001").unwrap(), "127.0.0.1:9001");
+    assert!(parse_tap_address("ws://localhost").is_err());
+    assert!(parse_tap_address("wss://localhost:9001").is_err());
+}
+
+#[test]
+fn test_tap_decimation() {
+    assert_eq!(tap_decimation(2000.0, 50.0), 40);
+    assert_eq!(tap_decimation(30.0, 50.0), 1);
+    // Irregular streams are forwarded in full
+    assert_eq!(tap_decimation(0.0, 50.0), 1);
+}
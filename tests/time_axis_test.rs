@@ -0,0 +1,58 @@
+use lsl_recording_toolbox::time_axis::{summarize_time_axis, TimeAxisScan};
+
+#[test]
+fn test_regular_stream_with_gap() {
+    // 100 Hz for 10 s with samples 300..349 missing (0.5 s)
+    let timestamps: Vec<f64> = (0..1000).filter(|i| !(300..350).contains(i)).map(|i| i as f64 / 100.0).collect();
+    let summary = summarize_time_axis(&timestamps, 100.0, 10).unwrap();
+
+    assert_eq!(summary.samples, 950);
+    assert!(summary.monotonic());
+    assert_eq!(summary.gaps, 1);
+    assert!((summary.lost_time - 0.5).abs() < 1e-9);
+    assert!((summary.largest_gap - 0.51).abs() < 1e-9);
+    assert!((summary.effective_rate.unwrap() - 949.0 / 9.99).abs() < 1e-9);
+
+    // The gap starts at the end of bin 2 and empties half of bin 3
+    assert_eq!(summary.bins[3], 50);
+    assert_eq!(summary.sparkline(), "███▅██████");
+    assert_eq!(summary.gap_markers().as_deref(), Some("  ^       "));
+}
+
+#[test]
+fn test_non_increasing_timestamps() {
+    let summary = summarize_time_axis(&[0.0, 0.1, 0.1, 0.2, 0.15, 0.3], 10.0, 3).unwrap();
+    assert!(!summary.monotonic());
+    assert_eq!(summary.non_increasing, 2);
+    assert_eq!(summary.backwards, 1);
+    assert!((summary.largest_backwards - 0.05).abs() < 1e-9);
+    assert_eq!(summary.gaps, 0);
+    assert_eq!(summary.gap_markers(), None);
+}
+
+#[test]
+fn test_blockwise_scan_matches_whole_series() {
+    let timestamps: Vec<f64> = (0..500).map(|i| i as f64 * 0.002 + if i >= 250 { 0.1 } else { 0.0 }).collect();
+    let whole = summarize_time_axis(&timestamps, 500.0, 20).unwrap();
+
+    let mut scan = TimeAxisScan::new(timestamps[0], timestamps[499], 500.0, 20);
+    for block in timestamps.chunks(37) {
+        scan.push(block);
+    }
+    assert_eq!(scan.finish(), whole);
+    assert_eq!(whole.gaps, 1);
+}
+
+#[test]
+fn test_irregular_and_single_sample_streams() {
+    // Irregular streams are scaled to their fullest bin and never gap
+    let summary = summarize_time_axis(&[0.0, 0.1, 0.2, 0.3, 5.0, 10.0], 0.0, 2).unwrap();
+    assert_eq!(summary.bins, [4, 2]);
+    assert_eq!(summary.sparkline(), "█▄");
+    assert_eq!(summary.gaps, 0);
+
+    let single = summarize_time_axis(&[42.0], 100.0, 60).unwrap();
+    assert_eq!((single.samples, single.bins.len()), (1, 1));
+    assert_eq!(single.effective_rate, None);
+    assert!(summarize_time_axis(&[], 100.0, 60).is_none());
+}
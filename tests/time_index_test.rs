@@ -0,0 +1,20 @@
+use lsl_recording_toolbox::zarr::time_index::TimeIndex;
+
+#[test]
+fn test_time_index_append_and_locate() {
+    // 10 samples at 1 Hz in chunks of 4: [0..4), [4..8), [8..10)
+    let timestamps: Vec<f64> = (0..10).map(|i| 100.0 + i as f64).collect();
+    let mut index = TimeIndex::new(4);
+    assert_eq!(index.append(timestamps[..6].iter().copied()), 0);
+    // The second write continues the partially filled chunk
+    assert_eq!(index.append(timestamps[6..].iter().copied()), 1);
+    assert_eq!(index, TimeIndex::build(&timestamps, 4));
+    assert_eq!(index.entries, vec![[100.0, 103.0], [104.0, 107.0], [108.0, 109.0]]);
+
+    assert_eq!(index.locate(99.0, 10), 0..4);
+    assert_eq!(index.locate(105.5, 10), 4..8);
+    assert_eq!(index.locate(109.0, 10), 8..10);
+    // Past the indexed samples: search the unindexed tail
+    assert_eq!(index.locate(200.0, 12), 10..12);
+    assert_eq!(index.sample_range(101.0, 105.0, 10), 0..8);
+}
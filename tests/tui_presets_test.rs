@@ -0,0 +1,45 @@
+use anyhow::Result;
+use lsl_recording_toolbox::tui_presets::{preset_file, PresetValues, Presets};
+
+#[test]
+fn test_presets_round_trip() -> Result<()> {
+    let dir = std::env::temp_dir().join(format!("lsl_tui_presets_{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+
+    // No file: no presets
+    let mut presets = Presets::load(&dir, "lsl-multi-recorder");
+    assert!(presets.is_empty());
+
+    let values = PresetValues::from([
+        ("source_ids".to_string(), "EMG_1234,EEG_5678".to_string()),
+        ("record_all".to_string(), "false".to_string()),
+    ]);
+    presets.insert(" lab-a ", values.clone())?;
+    presets.insert("lab-b", PresetValues::new())?;
+    assert!(presets.insert("  ", PresetValues::new()).is_err());
+    presets.save(&dir)?;
+    assert!(preset_file(&dir, "lsl-multi-recorder").ends_with("presets/lsl-multi-recorder.json"));
+
+    let mut loaded = Presets::load(&dir, "lsl-multi-recorder");
+    assert_eq!(loaded, presets);
+    assert_eq!(loaded.names(), ["lab-a", "lab-b"]);
+    assert_eq!(loaded.get("lab-a"), Some(&values));
+    // Presets are kept per tool
+    assert!(Presets::load(&dir, "lsl-recorder").is_empty());
+
+    // Saving under an existing name replaces the preset
+    loaded.insert("lab-b", values.clone())?;
+    assert!(loaded.remove("lab-a"));
+    assert!(!loaded.remove("lab-a"));
+    loaded.save(&dir)?;
+    let reloaded = Presets::load(&dir, "lsl-multi-recorder");
+    assert_eq!(reloaded.names(), ["lab-b"]);
+    assert_eq!(reloaded.get("lab-b"), Some(&values));
+
+    // A broken file means no presets
+    std::fs::write(preset_file(&dir, "lsl-multi-recorder"), "{not json")?;
+    assert!(Presets::load(&dir, "lsl-multi-recorder").is_empty());
+
+    std::fs::remove_dir_all(&dir)?;
+    Ok(())
+}
@@ -0,0 +1,42 @@
+use anyhow::Result;
+use lsl_recording_toolbox::tui_settings::{FileDialogMode, TuiSettings, SETTINGS_FILE};
+
+#[test]
+fn test_settings_round_trip() -> Result<()> {
+    let dir = std::env::temp_dir().join(format!("lsl_tui_settings_{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+
+    // Missing file: defaults
+    assert_eq!(TuiSettings::load(&dir), TuiSettings::default());
+    assert_eq!(TuiSettings::default().file_dialog, FileDialogMode::Terminal);
+
+    let settings = TuiSettings {
+        file_dialog: FileDialogMode::Native,
+    };
+    settings.save(&dir)?;
+    let text = std::fs::read_to_string(dir.join(SETTINGS_FILE))?;
+    assert!(text.contains("\"file_dialog\": \"native\""));
+    assert_eq!(TuiSettings::load(&dir), settings);
+
+    // Unknown keys are ignored, missing keys default, broken files fall back to defaults
+    std::fs::write(dir.join(SETTINGS_FILE), r#"{"theme": "dark"}"#)?;
+    assert_eq!(TuiSettings::load(&dir), TuiSettings::default());
+    std::fs::write(dir.join(SETTINGS_FILE), "{not json")?;
+    assert_eq!(TuiSettings::load(&dir), TuiSettings::default());
+
+    std::fs::remove_dir_all(&dir)?;
+    Ok(())
+}
+
+#[test]
+fn test_file_dialog_mode() {
+    assert_eq!(FileDialogMode::Terminal.toggled(), FileDialogMode::Native);
+    assert_eq!(FileDialogMode::Native.toggled(), FileDialogMode::Terminal);
+
+    // Native dialogs only in a graphical session
+    assert!(FileDialogMode::Native.uses_native(true));
+    assert!(!FileDialogMode::Native.uses_native(false));
+    assert!(!FileDialogMode::Terminal.uses_native(true));
+
+    assert_eq!(FileDialogMode::Native.to_string(), "native");
+}
@@ -0,0 +1,29 @@
+use lsl_recording_toolbox::units::{range_warning, unit_symbol, volt_scale};
+
+#[test]
+fn test_voltage_unit_normalization() {
+    assert_eq!(volt_scale("microvolts"), Some(1e-6));
+    assert_eq!(volt_scale("uV"), Some(1e-6));
+    assert_eq!(volt_scale("mV"), Some(1e-3));
+    assert_eq!(volt_scale("degrees"), None);
+    assert_eq!(unit_symbol("microvolts"), "µV");
+    assert_eq!(unit_symbol("mmHg"), "mmHg");
+}
+
+#[test]
+fn test_range_warning_detects_scaling_bugs() {
+    // Typical EEG in microvolts is fine
+    assert!(range_warning("microvolts", -80.0, 95.0).is_none());
+
+    // EEG sent in volts but declared as microvolts
+    let warning = range_warning("microvolts", -8e-5, 9.5e-5).unwrap();
+    assert!(warning.contains("too small"));
+    assert!(warning.contains("look like V"));
+
+    // Microvolt-scale values declared as volts
+    assert!(range_warning("V", -80.0, 95.0).unwrap().contains("too large"));
+
+    // Non-voltage units and flat channels are not judged here
+    assert!(range_warning("g", 0.0, 1e6).is_none());
+    assert!(range_warning("uV", 0.0, 0.0).is_none());
+}
@@ -0,0 +1,100 @@
+use anyhow::{Context, Result};
+use lsl_recording_toolbox::usage::{
+    append_entry, error_category, is_enabled, option_names, read_usage_log, set_enabled, summarize, UsageEntry,
+    ENABLED_MARKER, USAGE_LOG_FILE,
+};
+
+fn entry(tool: &str, started_at: &str, duration: f64, options: &[&str], error: Option<&str>) -> UsageEntry {
+    UsageEntry {
+        tool: tool.to_string(),
+        version: "1.10.0".to_string(),
+        started_at: started_at.to_string(),
+        duration,
+        options: options.iter().map(|o| o.to_string()).collect(),
+        success: error.is_none(),
+        error: error.map(String::from),
+    }
+}
+
+#[test]
+fn test_option_names() {
+    let args = [
+        "experiment.zarr",
+        "--speed=2",
+        "--stream",
+        "EMG",
+        "-v",
+        "--stream",
+        "EEG",
+        "--loop",
+        "--",
+        "--not-an-option",
+    ];
+    // Values and positional arguments are dropped
+    assert_eq!(option_names(args.map(String::from)), ["--loop", "--speed", "--stream"]);
+    assert!(option_names(["--".to_string(), "-".to_string()]).is_empty());
+}
+
+#[test]
+fn test_error_category() {
+    let missing = std::fs::read("/nonexistent/usage_test/file").context("Failed to read the file");
+    assert_eq!(error_category(&missing.unwrap_err()), "io");
+    let json = serde_json::from_str::<serde_json::Value>("{").map_err(anyhow::Error::from);
+    assert_eq!(error_category(&json.unwrap_err()), "json");
+    // The tools' own checks carry no typed cause
+    assert_eq!(error_category(&anyhow::anyhow!("Stream 'EMG' not found in /data/P001.zarr")), "other");
+}
+
+#[test]
+fn test_summarize() {
+    let entries = [
+        entry("lsl-replay", "2026-03-02T10:00:00Z", 30.0, &["--speed"], None),
+        entry("lsl-inspect", "2026-03-01T09:00:00Z", 0.5, &[], None),
+        entry("lsl-replay", "2026-03-03T10:00:00Z", 90.0, &["--loop", "--speed"], None),
+        entry("lsl-replay", "2026-03-01T10:00:00Z", 0.2, &[], Some("io")),
+    ];
+    let tools = summarize(&entries);
+    assert_eq!(tools.iter().map(|t| t.tool.as_str()).collect::<Vec<_>>(), ["lsl-replay", "lsl-inspect"]);
+
+    let replay = &tools[0];
+    assert_eq!((replay.runs, replay.failures), (3, 1));
+    assert!((replay.total_duration - 120.2).abs() < 1e-9);
+    assert!((replay.mean_duration() - 120.2 / 3.0).abs() < 1e-9);
+    assert_eq!(replay.longest, 90.0);
+    assert_eq!(replay.last_run, "2026-03-03T10:00:00Z");
+    assert_eq!(replay.errors.get("io"), Some(&1));
+    assert_eq!((replay.options["--speed"], replay.options["--loop"]), (2, 1));
+    assert!(tools[1].errors.is_empty());
+}
+
+#[test]
+fn test_usage_log_file() -> Result<()> {
+    let dir = std::env::temp_dir().join(format!("lsl_usage_{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+
+    if std::env::var_os("LSL_USAGE_LOG").is_none() {
+        assert!(!is_enabled(&dir));
+        set_enabled(&dir, true)?;
+        assert!(is_enabled(&dir));
+        set_enabled(&dir, false)?;
+        assert!(!is_enabled(&dir));
+    }
+    set_enabled(&dir, true)?;
+    assert!(dir.join(ENABLED_MARKER).exists());
+
+    let log_path = dir.join(USAGE_LOG_FILE);
+    assert!(read_usage_log(&log_path)?.is_empty());
+    let first = entry("lsl-sync", "2026-03-01T09:00:00Z", 4.0, &["--mode"], None);
+    let second = entry("lsl-validate", "2026-03-01T09:01:00Z", 1.5, &[], Some("exit 2"));
+    append_entry(&log_path, &first)?;
+    // A torn line from an interrupted write is skipped
+    std::fs::OpenOptions::new()
+        .append(true)
+        .open(&log_path)
+        .and_then(|mut file| std::io::Write::write_all(&mut file, b"{\"tool\": \"lsl-rec\n"))?;
+    append_entry(&log_path, &second)?;
+    assert_eq!(read_usage_log(&log_path)?, [first, second]);
+
+    std::fs::remove_dir_all(&dir)?;
+    Ok(())
+}
@@ -0,0 +1,84 @@
+mod common;
+
+use anyhow::Result;
+use lsl_recording_toolbox::export::wide::{default_wide_output_path, export_wide, grid_points, WideOptions};
+use lsl_recording_toolbox::export::{ExportFormat, ExportOptions};
+use serde_json::json;
+use std::path::{Path, PathBuf};
+
+/// EMG (2 channels, 8 Hz from t=100), EEG (16 Hz from t=100.25) and an irregular Events stream,
+/// 16 samples each with value `channel * 10 + sample`
+fn write_store(path: &Path) -> Result<()> {
+    let store = common::create_store(path)?;
+    let emg = json!({
+        "channel_format": "Float32", "channel_count": 2, "nominal_srate": 8.0,
+        "channels": [{ "label": "C3" }, { "label": "C4" }],
+    });
+    let eeg = json!({ "channel_format": "Float32", "channel_count": 1, "nominal_srate": 16.0 });
+    let events = json!({ "channel_format": "Float32", "channel_count": 1, "nominal_srate": 0.0 });
+    // Irregular streams get the same spacing as EMG
+    let streams = [("EMG", emg, 2, 100.0, 0.125), ("EEG", eeg, 1, 100.25, 0.0625), ("Events", events, 1, 100.0, 0.125)];
+    for (name, info, channels, first, period) in streams {
+        let timestamps = common::regular_timestamps(16, first, period);
+        common::write_stream(&store, name, json!({ "stream_info": info }), common::ramp(channels, 16), timestamps)?;
+    }
+    Ok(())
+}
+
+fn read_csv(path: &Path) -> Result<Vec<Vec<String>>> {
+    let mut reader = csv::ReaderBuilder::new().has_headers(false).from_path(path)?;
+    Ok(reader.records().map(|r| r.map(|r| r.iter().map(String::from).collect())).collect::<Result<_, _>>()?)
+}
+
+#[test]
+fn test_default_wide_output_path() {
+    assert_eq!(
+        default_wide_output_path(Path::new("data/experiment.zarr/"), ExportFormat::Csv),
+        PathBuf::from("data/experiment_wide.csv")
+    );
+    assert_eq!(
+        default_wide_output_path(Path::new("experiment.zarr"), ExportFormat::Parquet),
+        PathBuf::from("experiment_wide.parquet")
+    );
+    assert_eq!(grid_points(100.0, 101.0, 16.0), 17);
+    assert_eq!(grid_points(101.0, 100.0, 16.0), 0);
+}
+
+#[test]
+fn test_wide_csv() -> Result<()> {
+    let dir = std::env::temp_dir().join(format!("lsl_wide_csv_{}", std::process::id()));
+    let store = dir.join("session.zarr");
+    write_store(&store)?;
+    let output = dir.join("session_wide.csv");
+
+    let summary = export_wide(&store, &output, ExportFormat::Csv, &ExportOptions::default(), &WideOptions::default())?;
+    assert_eq!(summary.streams, ["EEG", "EMG"]);
+    assert_eq!(summary.skipped, [("Events".to_string(), "irregular stream".to_string())]);
+    // Highest nominal rate over the common period 100.25..=101.1875
+    assert_eq!(summary.rate, 16.0);
+    assert_eq!((summary.rows, summary.columns), (16, 4));
+
+    let rows = read_csv(&output)?;
+    assert_eq!(rows[0], ["time", "EEG.ch0", "EMG.C3", "EMG.C4"]);
+    assert_eq!(rows.len(), 17);
+    // EEG samples are on the grid, EMG is interpolated between its 8 Hz samples
+    assert_eq!(rows[1], ["100.25", "0", "2", "12"]);
+    assert_eq!(rows[2], ["100.3125", "1", "2.5", "12.5"]);
+    assert_eq!(rows[16], ["101.1875", "15", "9.5", "19.5"]);
+
+    // --start counts from the first sample of any stream
+    let wide = WideOptions { rate: Some(8.0), start: Some(0.5), end: None };
+    let summary = export_wide(&store, &output, ExportFormat::Csv, &ExportOptions::default(), &wide)?;
+    assert_eq!(summary.rows, 6);
+    assert_eq!(read_csv(&output)?[1][0], "100.5");
+
+    // Irregular streams cannot be resampled
+    let options = ExportOptions { streams: vec!["EMG".to_string(), "Events".to_string()], ..Default::default() };
+    let error =
+        export_wide(&store, &output, ExportFormat::Csv, &options, &WideOptions::default()).unwrap_err();
+    assert!(error.to_string().contains("irregular stream"));
+    assert!(export_wide(&store, &output, ExportFormat::Xdf, &ExportOptions::default(), &WideOptions::default()).is_err());
+
+    std::fs::remove_dir_all(&dir)?;
+    Ok(())
+}
@@ -0,0 +1,212 @@
+#![cfg(feature = "lsl")]
+
+mod common;
+
+use anyhow::Result;
+use lsl_recording_toolbox::zarr::{read_group_attributes, N_SAMPLES_ATTRIBUTE};
+use lsl_recording_toolbox::zarr::writer::{MirrorConfig, ZarrWriter, ZarrWriterConfig, WRITE_QUEUE_CAPACITY};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+use zarrs::array::Array;
+use zarrs::array_subset::ArraySubset;
+use zarrs::filesystem::FilesystemStore;
+
+/// Store with a 2-channel EEG stream holding `existing` samples from t=100 s, 0.1 s apart
+fn write_store(path: &Path, existing: usize) -> Result<Arc<FilesystemStore>> {
+    let store = common::create_store(path)?;
+    let timestamps = common::regular_timestamps(existing, 100.0, 0.1);
+    common::write_stream(&store, "EEG", serde_json::json!({}), common::ramp(2, existing), timestamps)?;
+    Ok(store)
+}
+
+fn open_arrays(store: &Arc<FilesystemStore>) -> Result<(Array<FilesystemStore>, Array<FilesystemStore>)> {
+    Ok((Array::open(store.clone(), "/EEG/data")?, Array::open(store.clone(), "/EEG/time")?))
+}
+
+/// Float32 writer for the EEG stream, flushing only when asked to
+fn open_writer(path: &Path, mirror: Option<&Path>) -> Result<ZarrWriter> {
+    let store = Arc::new(FilesystemStore::new(path)?);
+    let (data_array, time_array) = open_arrays(&store)?;
+    let mirror = mirror
+        .map(|mirror_path| -> Result<MirrorConfig> {
+            let store = Arc::new(FilesystemStore::new(mirror_path)?);
+            let (data_array, time_array) = open_arrays(&store)?;
+            Ok(MirrorConfig { data_array, time_array, flags_array: None, store_path: mirror_path.to_path_buf(), store })
+        })
+        .transpose()?;
+    ZarrWriter::new(ZarrWriterConfig {
+        data_array,
+        time_array,
+        buffer_size: 1000,
+        shard_samples: None,
+        channel_format: lsl::ChannelFormat::Float32,
+        flush_interval: Duration::from_secs(3600),
+        store_path: path.to_path_buf(),
+        store,
+        stream_name: "EEG".to_string(),
+        mirror,
+        detect_bad_channels: false,
+        remote: None,
+        flags_array: None,
+    })
+}
+
+/// Add samples `first..first + count` with value `channel * 10 + sample` at t = 200 + sample
+fn add_samples(writer: &mut ZarrWriter, first: usize, count: usize) {
+    for s in first..first + count {
+        writer.add_sample_slice_f32(&[s as f32, (10 + s) as f32], 200.0 + s as f64);
+    }
+}
+
+/// Timestamps of the EEG stream on disk
+fn read_times(path: &Path) -> Result<Vec<f64>> {
+    let store = Arc::new(FilesystemStore::new(path)?);
+    let time = Array::<FilesystemStore>::open(store, "/EEG/time")?;
+    let subset = ArraySubset::new_with_shape(time.shape().to_vec());
+    Ok(time.retrieve_array_subset_ndarray::<f64>(&subset)?.into_iter().collect())
+}
+
+fn dir(name: &str) -> PathBuf {
+    std::env::temp_dir().join(format!("lsl_writer_{}_{}", name, std::process::id()))
+}
+
+#[test]
+fn test_flushes_reach_disk() -> Result<()> {
+    let dir = dir("flush");
+    let path = dir.join("session.zarr");
+    write_store(&path, 0)?;
+    let mut writer = open_writer(&path, None)?;
+
+    // Two flushes queued without waiting, the rest (as one block) written by flush_all
+    add_samples(&mut writer, 0, 4);
+    writer.flush()?;
+    add_samples(&mut writer, 4, 4);
+    writer.flush()?;
+    writer.add_sample_block_f32(&[8.0, 18.0, 9.0, 19.0], &[208.0, 209.0]);
+    assert_eq!(writer.buffer_sample_count(), 2);
+    writer.flush_all()?;
+
+    let stats = writer.writer_stats();
+    assert_eq!((stats.queue_depth, stats.queue_capacity, stats.writes), (0, WRITE_QUEUE_CAPACITY, 3));
+    assert!((1..=WRITE_QUEUE_CAPACITY).contains(&stats.max_queue_depth));
+    assert_eq!(writer.samples_written(), 10);
+
+    let expected: Vec<f64> = (0..10).map(|s| 200.0 + s as f64).collect();
+    assert_eq!(read_times(&path)?, expected);
+    let store = Arc::new(FilesystemStore::new(&path)?);
+    let data = Array::<FilesystemStore>::open(store, "/EEG/data")?;
+    let values = data.retrieve_array_subset_ndarray::<f32>(&ArraySubset::new_with_shape(vec![2, 10]))?;
+    assert!(values.iter().eq(common::ramp(2, 10).iter()));
+
+    drop(writer);
+    std::fs::remove_dir_all(&dir)?;
+    Ok(())
+}
+
+#[test]
+fn test_failed_store_surfaces_on_next_flush() -> Result<()> {
+    let dir = dir("failure");
+    let path = dir.join("session.zarr");
+    write_store(&path, 0)?;
+    let mut writer = open_writer(&path, None)?;
+
+    // Chunks cannot be created below a file, not even by root
+    std::fs::write(path.join("EEG/data/c"), b"")?;
+    add_samples(&mut writer, 0, 4);
+    writer.flush()?;
+    assert!(writer.wait_for_writes().is_err());
+
+    // Later flushes are refused rather than silently dropped
+    add_samples(&mut writer, 4, 4);
+    assert!(writer.flush().is_err());
+    assert!(writer.flush_all().is_err());
+    assert_eq!(writer.writer_stats().writes, 0);
+
+    drop(writer);
+    std::fs::remove_dir_all(&dir)?;
+    Ok(())
+}
+
+#[test]
+fn test_drop_writes_queued_flushes() -> Result<()> {
+    let dir = dir("drop");
+    let path = dir.join("session.zarr");
+    write_store(&path, 0)?;
+    let mut writer = open_writer(&path, None)?;
+
+    for flush in 0..WRITE_QUEUE_CAPACITY {
+        add_samples(&mut writer, flush * 4, 4);
+        writer.flush()?;
+    }
+    drop(writer);
+
+    let samples = WRITE_QUEUE_CAPACITY * 4;
+    let expected: Vec<f64> = (0..samples).map(|s| 200.0 + s as f64).collect();
+    assert_eq!(read_times(&path)?, expected);
+
+    std::fs::remove_dir_all(&dir)?;
+    Ok(())
+}
+
+#[test]
+fn test_n_samples_follows_every_flush() -> Result<()> {
+    let dir = dir("n_samples");
+    let (path, mirror_path) = (dir.join("session.zarr"), dir.join("mirror.zarr"));
+    // Appending to both stores; the mirror was added after the first recording and is shorter
+    write_store(&path, 8)?;
+    write_store(&mirror_path, 4)?;
+    let mut writer = open_writer(&path, Some(&mirror_path))?;
+
+    let n_samples = |path: &Path| -> Result<serde_json::Value> {
+        let store = Arc::new(FilesystemStore::new(path)?);
+        Ok(read_group_attributes(&store, "EEG")?[N_SAMPLES_ATTRIBUTE].clone())
+    };
+    for flush in 1..=3 {
+        add_samples(&mut writer, flush * 3, 3);
+        writer.flush()?;
+        writer.wait_for_writes()?;
+        assert_eq!(n_samples(&path)?, 8 + flush * 3);
+        assert_eq!(n_samples(&mirror_path)?, 4 + flush * 3);
+    }
+    assert_eq!(writer.samples_written(), 9);
+    drop(writer);
+
+    // Earlier samples are kept and the new ones follow them
+    let times = read_times(&mirror_path)?;
+    assert_eq!(times[..4], common::regular_timestamps(4, 100.0, 0.1).to_vec());
+    assert_eq!(times[4..], (3..12).map(|s| 200.0 + s as f64).collect::<Vec<f64>>());
+    assert_eq!(read_times(&path)?.len(), 17);
+
+    std::fs::remove_dir_all(&dir)?;
+    Ok(())
+}
+
+#[test]
+fn test_attribute_updates_follow_queued_flushes() -> Result<()> {
+    let dir = dir("attributes");
+    let (path, mirror_path) = (dir.join("session.zarr"), dir.join("mirror.zarr"));
+    write_store(&path, 0)?;
+    write_store(&mirror_path, 0)?;
+    let mut writer = open_writer(&path, Some(&mirror_path))?;
+
+    add_samples(&mut writer, 0, 4);
+    writer.flush()?;
+    writer.record_start_time()?;
+    writer.record_pause((201.0, 202.0))?;
+    writer.record_pause((203.0, 204.0))?;
+    writer.wait_for_writes()?;
+    assert_eq!(writer.samples_written(), 4);
+
+    for path in [&path, &mirror_path] {
+        let store = Arc::new(FilesystemStore::new(path)?);
+        let attributes = read_group_attributes(&store, "EEG")?;
+        assert!(attributes.get("start_time").is_some());
+        assert_eq!(attributes["pauses"], serde_json::json!([[201.0, 202.0], [203.0, 204.0]]));
+        assert_eq!(attributes[N_SAMPLES_ATTRIBUTE], 4);
+    }
+
+    drop(writer);
+    std::fs::remove_dir_all(&dir)?;
+    Ok(())
+}